@@ -0,0 +1,184 @@
+//! Criterion benchmarks of the request hot paths: token validation, the rate-limit check,
+//! order creation and inventory reservation.
+//!
+//! ```sh
+//! cargo bench --bench hot_paths -- --save-baseline main        # on main
+//! cargo bench --bench hot_paths --features testing -- --baseline main
+//! ```
+//!
+//! The database benchmarks need the `testing` feature and run against in-memory SQLite,
+//! or `TEST_DATABASE_URL`. The rate-limit benchmark needs `BENCH_REDIS_URL` and is
+//! skipped without it.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::collections::HashSet;
+use tokio::runtime::Runtime;
+
+use stateset_api::auth::{generate_token, validate_token, AuthConfig};
+use stateset_api::rate_limiter::RateLimiter;
+
+fn runtime() -> Runtime {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .expect("benchmark runtime starts")
+}
+
+fn auth_config() -> AuthConfig {
+    AuthConfig {
+        secret: "bench-secret".to_string(),
+        issuer: "stateset-api".to_string(),
+        audience: "stateset-api".to_string(),
+        allowed_roles: ["user", "admin"].iter().map(|r| r.to_string()).collect::<HashSet<_>>(),
+        token_expiration: 3600,
+    }
+}
+
+fn bench_token_validation(c: &mut Criterion) {
+    let config = auth_config();
+    let permissions = ["orders:read", "orders:write", "inventory:read", "inventory:write"];
+    let token = generate_token(
+        "bench-user",
+        "user",
+        Some(permissions.iter().map(|p| p.to_string()).collect()),
+        &config,
+    )
+    .expect("token can be signed");
+    let forged = format!("{}x", token);
+
+    let mut group = c.benchmark_group("auth");
+    group.bench_function("validate_token", |b| {
+        b.iter(|| validate_token(black_box(&token), &config).expect("token is valid"))
+    });
+    group.bench_function("reject_bad_signature", |b| {
+        b.iter(|| validate_token(black_box(&forged), &config).expect_err("signature is checked"))
+    });
+    group.finish();
+}
+
+fn bench_rate_limit(c: &mut Criterion) {
+    let Ok(redis_url) = std::env::var("BENCH_REDIS_URL") else {
+        eprintln!("Skipping rate_limit benchmarks: BENCH_REDIS_URL is not set");
+        return;
+    };
+    let rt = runtime();
+    let logger = slog::Logger::root(slog::Discard, slog::o!());
+    let limiter = rt
+        .block_on(RateLimiter::new(&redis_url, "bench", usize::MAX, 60, logger))
+        .expect("Redis is reachable");
+
+    // Keys rotate because the limiter treats concurrent checks of one key as limited
+    let keys: Vec<String> = (0..1024).map(|i| format!("client-{}", i)).collect();
+    let mut next = 0;
+    c.bench_function("rate_limit/is_rate_limited", |b| {
+        b.to_async(&rt).iter(|| {
+            next = (next + 1) % keys.len();
+            let key = &keys[next];
+            let limiter = &limiter;
+            async move { limiter.is_rate_limited(key).await.expect("check succeeds") }
+        })
+    });
+}
+
+#[cfg(feature = "testing")]
+fn bench_database(c: &mut Criterion) {
+    use chrono::Utc;
+    use sea_orm::ConnectionTrait;
+    use stateset_api::commands::inventory::reserve_inventory_command::{
+        ReservationRequest, ReservationStrategy, ReservationType,
+    };
+    use stateset_api::commands::inventory::ReserveInventoryCommand;
+    use stateset_api::commands::orders::create_order_command::OrderItem;
+    use stateset_api::commands::orders::CreateOrderCommand;
+    use stateset_api::commands::Command;
+    use stateset_api::db::dialect;
+    use stateset_api::testing::TestApp;
+    use std::time::{Duration, Instant};
+    use uuid::Uuid;
+
+    const WAREHOUSE: &str = "WH-BENCH";
+
+    let rt = runtime();
+    let app = rt.block_on(TestApp::builder().build()).expect("test database is ready");
+
+    c.bench_function("orders/create", |b| {
+        b.to_async(&rt).iter(|| async {
+            let command = CreateOrderCommand {
+                customer_id: Uuid::new_v4(),
+                items: vec![
+                    OrderItem { product_id: Uuid::new_v4(), quantity: 2 },
+                    OrderItem { product_id: Uuid::new_v4(), quantity: 1 },
+                ],
+                total: None,
+                allow_duplicate: true,
+                placed_by: None,
+            };
+            command
+                .execute(app.db.clone(), app.event_sender.clone())
+                .await
+                .expect("order is created")
+        })
+    });
+
+    // Availability sums a product's active reservations, so each sample starts from a
+    // fresh product to keep samples comparable
+    c.bench_function("inventory/reserve", |b| {
+        b.to_async(&rt).iter_custom(|iters| {
+            let app = &app;
+            async move {
+                let product_id = Uuid::new_v4();
+                app.db
+                    .execute(dialect::statement(
+                        app.db.as_ref(),
+                        "INSERT INTO inventory_levels (id, warehouse_id, product_id, quantity, allocated_quantity, version, last_updated_at) \
+                         VALUES ($1, $2, $3, $4, 0, 1, $5)",
+                        [
+                            Uuid::new_v4().into(),
+                            WAREHOUSE.into(),
+                            product_id.into(),
+                            1_000_000_000i32.into(),
+                            Utc::now().naive_utc().into(),
+                        ],
+                    ))
+                    .await
+                    .expect("inventory level is seeded");
+                let mut elapsed = Duration::ZERO;
+                for _ in 0..iters {
+                    let command = ReserveInventoryCommand {
+                        warehouse_id: WAREHOUSE.to_string(),
+                        reference_id: Uuid::new_v4(),
+                        reference_type: "SALES_ORDER".to_string(),
+                        items: vec![ReservationRequest {
+                            product_id,
+                            quantity: 1,
+                            lot_numbers: None,
+                            location_id: None,
+                            substitutes: None,
+                        }],
+                        reservation_type: ReservationType::SalesOrder,
+                        duration_days: None,
+                        ttl_seconds: Some(900),
+                        priority: None,
+                        notes: None,
+                        reservation_strategy: ReservationStrategy::Strict,
+                    };
+                    let start = Instant::now();
+                    command
+                        .execute(app.db.clone(), app.event_sender.clone())
+                        .await
+                        .expect("inventory is reserved");
+                    elapsed += start.elapsed();
+                }
+                elapsed
+            }
+        })
+    });
+}
+
+#[cfg(not(feature = "testing"))]
+fn bench_database(_: &mut Criterion) {
+    eprintln!("Skipping database benchmarks: build with --features testing");
+}
+
+criterion_group!(benches, bench_token_validation, bench_rate_limit, bench_database);
+criterion_main!(benches);
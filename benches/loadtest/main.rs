@@ -0,0 +1,251 @@
+//! Load test harness: drives realistic mixed traffic against a compiled server and fails
+//! when a profile's error-rate, throughput or latency thresholds are broken, or when p95
+//! latency regressed against a saved baseline.
+//!
+//! ```sh
+//! cargo build --release
+//! stateset-cli migrate && stateset-cli seed
+//! export LOADTEST_TOKEN=$(stateset-cli tokens issue --subject loadtest --role admin)
+//! cargo bench --bench loadtest -- --profile steady --report target/loadtest/main.json   # on main
+//! cargo bench --bench loadtest -- --profile steady --baseline target/loadtest/main.json
+//! ```
+//!
+//! Without `--base-url` the harness starts `target/release/stateset-api` (or
+//! `LOADTEST_BINARY`) on `--port` with the current environment and stops it afterwards.
+//! Profiles live in `benches/loadtest/profiles.json`.
+
+mod report;
+mod traffic;
+
+use clap::Parser;
+use rand::{distributions::WeightedIndex, prelude::Distribution, rngs::StdRng, SeedableRng};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitCode, Stdio};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::time::MissedTickBehavior;
+
+use report::{Outcome, Profile, Report, Samples};
+use traffic::{Scenario, Traffic};
+
+const DEFAULT_BINARY: &str = "target/release/stateset-api";
+
+/// How long a spawned server gets to answer `/health`.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Parser)]
+#[command(name = "loadtest", about = "Mixed-traffic load test with regression thresholds")]
+struct Args {
+    /// Profile name in the profiles file.
+    #[arg(long, default_value = "smoke")]
+    profile: String,
+    #[arg(long, default_value = "benches/loadtest/profiles.json")]
+    profiles: PathBuf,
+    /// Target an already running server instead of starting one.
+    #[arg(long)]
+    base_url: Option<String>,
+    /// Port for the server the harness starts.
+    #[arg(long, default_value_t = 18080)]
+    port: u16,
+    /// Bearer token; defaults to `LOADTEST_TOKEN`.
+    #[arg(long)]
+    token: Option<String>,
+    /// Report of an earlier run to compare p95 latencies with.
+    #[arg(long)]
+    baseline: Option<PathBuf>,
+    #[arg(long, default_value_t = 10.0)]
+    max_regression_pct: f64,
+    /// Where to write this run's report; defaults to `target/loadtest/<profile>.json`.
+    #[arg(long)]
+    report: Option<PathBuf>,
+    /// Passed by `cargo bench`.
+    #[arg(long, hide = true)]
+    bench: bool,
+}
+
+/// Stops the spawned server when the run ends, including on early returns.
+struct Server(Child);
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+fn load_profile(args: &Args) -> Result<Profile, String> {
+    let raw = std::fs::read_to_string(&args.profiles)
+        .map_err(|e| format!("Cannot read {}: {}", args.profiles.display(), e))?;
+    let mut profiles: BTreeMap<String, Profile> =
+        serde_json::from_str(&raw).map_err(|e| format!("Invalid {}: {}", args.profiles.display(), e))?;
+    let profile = profiles
+        .remove(&args.profile)
+        .ok_or_else(|| format!("Unknown profile {}; known: {:?}", args.profile, profiles.keys().collect::<Vec<_>>()))?;
+    if let Some(unknown) = profile.mix.keys().chain(profile.p95_ms.keys()).find(|n| Scenario::from_name(n).is_none()) {
+        return Err(format!("Unknown scenario in profile {}: {}", args.profile, unknown));
+    }
+    if profile.concurrency == 0 || profile.target_rps <= 0.0 || profile.mix.values().all(|w| *w == 0) {
+        return Err(format!("Profile {} needs a concurrency, target_rps and mix", args.profile));
+    }
+    Ok(profile)
+}
+
+async fn start_server(port: u16) -> Result<(Server, String), String> {
+    let binary = std::env::var("LOADTEST_BINARY").unwrap_or_else(|_| DEFAULT_BINARY.to_string());
+    let child = Command::new(&binary)
+        .env("APP_PORT", port.to_string())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Cannot start {}: {} (run cargo build --release first)", binary, e))?;
+    let server = Server(child);
+    let base_url = format!("http://127.0.0.1:{}", port);
+    let client = reqwest::Client::new();
+    let deadline = Instant::now() + STARTUP_TIMEOUT;
+    while Instant::now() < deadline {
+        if client.get(format!("{}/health", base_url)).send().await.is_ok_and(|r| r.status().is_success()) {
+            return Ok((server, base_url));
+        }
+        tokio::time::sleep(Duration::from_millis(250)).await;
+    }
+    Err(format!("{} did not become healthy within {:?}", binary, STARTUP_TIMEOUT))
+}
+
+/// One closed-loop worker sending its share of the target rate until `deadline`.
+async fn worker(
+    id: usize,
+    traffic: Arc<Traffic>,
+    client: reqwest::Client,
+    scenarios: Arc<(Vec<Scenario>, WeightedIndex<u32>)>,
+    interval: Duration,
+    deadline: Instant,
+) -> HashMap<&'static str, Samples> {
+    // Seeded per worker so a profile always sends the same sequence of requests
+    let mut rng = StdRng::seed_from_u64(id as u64);
+    let mut samples: HashMap<&'static str, Samples> = HashMap::new();
+    let mut ticker = tokio::time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    while Instant::now() < deadline {
+        ticker.tick().await;
+        let scenario = scenarios.0[scenarios.1.sample(&mut rng)];
+        let request = traffic.request(&client, scenario, &mut rng);
+        let started = Instant::now();
+        let outcome = match request.send().await {
+            Ok(response) if response.status().is_server_error() => Outcome::Failed,
+            Ok(response) if response.status().is_client_error() => Outcome::Rejected,
+            Ok(response) => {
+                // Latency includes reading the body, as a client would
+                match response.bytes().await {
+                    Ok(_) => Outcome::Ok,
+                    Err(_) => Outcome::Failed,
+                }
+            }
+            Err(_) => Outcome::Failed,
+        };
+        samples.entry(scenario.name()).or_default().record(started.elapsed(), outcome);
+    }
+    samples
+}
+
+async fn run(args: Args) -> Result<bool, String> {
+    let profile = load_profile(&args)?;
+    let token = args
+        .token
+        .clone()
+        .or_else(|| std::env::var("LOADTEST_TOKEN").ok())
+        .ok_or("Pass --token or set LOADTEST_TOKEN")?;
+    let baseline: Option<Report> = match &args.baseline {
+        Some(path) => {
+            let raw = std::fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+            Some(serde_json::from_str(&raw).map_err(|e| format!("Invalid baseline {}: {}", path.display(), e))?)
+        }
+        None => None,
+    };
+
+    let (_server, base_url) = match &args.base_url {
+        Some(url) => (None, url.clone()),
+        None => {
+            let (server, url) = start_server(args.port).await?;
+            (Some(server), url)
+        }
+    };
+
+    let (names, weights): (Vec<Scenario>, Vec<u32>) =
+        profile.mix.iter().filter_map(|(name, weight)| Some((Scenario::from_name(name)?, *weight))).unzip();
+    let index = WeightedIndex::new(&weights).map_err(|e| format!("Invalid mix: {}", e))?;
+    let scenarios = Arc::new((names, index));
+    let traffic = Arc::new(Traffic::new(&base_url, &token));
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .pool_max_idle_per_host(profile.concurrency)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    println!(
+        "Running {} against {}: {} workers, {} rps for {}s",
+        args.profile, base_url, profile.concurrency, profile.target_rps, profile.duration_secs
+    );
+    let interval = Duration::from_secs_f64(profile.concurrency as f64 / profile.target_rps);
+    let started = Instant::now();
+    let deadline = started + Duration::from_secs(profile.duration_secs);
+    let workers: Vec<_> = (0..profile.concurrency)
+        .map(|id| tokio::spawn(worker(id, traffic.clone(), client.clone(), scenarios.clone(), interval, deadline)))
+        .collect();
+    let mut samples: HashMap<&'static str, Samples> = HashMap::new();
+    for handle in workers {
+        for (name, worker_samples) in handle.await.map_err(|e| format!("Worker panicked: {}", e))? {
+            samples.entry(name).or_default().merge(worker_samples);
+        }
+    }
+    let report = Report::new(&args.profile, started.elapsed(), samples);
+
+    println!(
+        "{:<18} {:>8} {:>8} {:>8} {:>9} {:>9} {:>9}",
+        "scenario", "requests", "rejected", "failed", "p50 ms", "p95 ms", "p99 ms"
+    );
+    for (name, s) in &report.scenarios {
+        println!(
+            "{:<18} {:>8} {:>8} {:>8} {:>9.1} {:>9.1} {:>9.1}",
+            name, s.requests, s.rejected, s.failed, s.p50_ms, s.p95_ms, s.p99_ms
+        );
+    }
+    println!(
+        "{} requests, {:.1} rps, {:.2}% errors",
+        report.requests,
+        report.achieved_rps,
+        report.error_rate * 100.0
+    );
+
+    let path = args
+        .report
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("target/loadtest/{}.json", args.profile)));
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    }
+    let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+    println!("Report written to {}", path.display());
+
+    let violations = report.violations(&profile, baseline.as_ref(), args.max_regression_pct);
+    for violation in &violations {
+        eprintln!("FAIL {}", violation);
+    }
+    Ok(violations.is_empty())
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let runtime = tokio::runtime::Runtime::new().expect("runtime starts");
+    match runtime.block_on(run(args)) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::from(2)
+        }
+    }
+}
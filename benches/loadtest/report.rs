@@ -0,0 +1,157 @@
+// loadtest/report.rs
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+/// p95 increases smaller than this are treated as noise when comparing with a baseline.
+const MIN_REGRESSION_MS: f64 = 2.0;
+
+/// Throughput below this share of the target fails the run: the server fell behind.
+const MIN_THROUGHPUT_RATIO: f64 = 0.9;
+
+/// A load profile from `profiles.json`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Profile {
+    pub duration_secs: u64,
+    pub concurrency: usize,
+    pub target_rps: f64,
+    /// Share of requests allowed to fail with a 5xx or a transport error.
+    pub max_error_rate: f64,
+    /// Relative weight of each scenario.
+    pub mix: BTreeMap<String, u32>,
+    /// Upper bound on each scenario's 95th percentile latency.
+    #[serde(default)]
+    pub p95_ms: BTreeMap<String, f64>,
+}
+
+/// How one request ended.
+#[derive(Debug, Clone, Copy)]
+pub enum Outcome {
+    Ok,
+    /// 4xx: counted, but not an error, since a reservation may rightly be refused.
+    Rejected,
+    /// 5xx or no response.
+    Failed,
+}
+
+/// Latencies and outcomes of one scenario, collected by a worker and merged at the end.
+#[derive(Debug, Default)]
+pub struct Samples {
+    latencies_us: Vec<u64>,
+    rejected: u64,
+    failed: u64,
+}
+
+impl Samples {
+    pub fn record(&mut self, latency: Duration, outcome: Outcome) {
+        self.latencies_us.push(latency.as_micros() as u64);
+        match outcome {
+            Outcome::Ok => {}
+            Outcome::Rejected => self.rejected += 1,
+            Outcome::Failed => self.failed += 1,
+        }
+    }
+
+    pub fn merge(&mut self, other: Samples) {
+        self.latencies_us.extend(other.latencies_us);
+        self.rejected += other.rejected;
+        self.failed += other.failed;
+    }
+
+    pub fn summarize(mut self) -> ScenarioReport {
+        self.latencies_us.sort_unstable();
+        let ms = |q: f64| percentile(&self.latencies_us, q) as f64 / 1000.0;
+        ScenarioReport {
+            requests: self.latencies_us.len() as u64,
+            rejected: self.rejected,
+            failed: self.failed,
+            p50_ms: ms(0.50),
+            p95_ms: ms(0.95),
+            p99_ms: ms(0.99),
+            max_ms: ms(1.0),
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted values; 0 when there are none.
+pub fn percentile(sorted: &[u64], q: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (q * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioReport {
+    pub requests: u64,
+    pub rejected: u64,
+    pub failed: u64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Written after every run; pass an earlier one as `--baseline` to catch regressions.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Report {
+    pub profile: String,
+    pub elapsed_secs: f64,
+    pub requests: u64,
+    pub achieved_rps: f64,
+    pub error_rate: f64,
+    pub scenarios: BTreeMap<String, ScenarioReport>,
+}
+
+impl Report {
+    pub fn new(profile: &str, elapsed: Duration, samples: HashMap<&'static str, Samples>) -> Self {
+        let scenarios: BTreeMap<String, ScenarioReport> =
+            samples.into_iter().map(|(name, s)| (name.to_string(), s.summarize())).collect();
+        let requests: u64 = scenarios.values().map(|s| s.requests).sum();
+        let failed: u64 = scenarios.values().map(|s| s.failed).sum();
+        let elapsed_secs = elapsed.as_secs_f64();
+        Self {
+            profile: profile.to_string(),
+            elapsed_secs,
+            requests,
+            achieved_rps: if elapsed_secs > 0.0 { requests as f64 / elapsed_secs } else { 0.0 },
+            error_rate: if requests > 0 { failed as f64 / requests as f64 } else { 0.0 },
+            scenarios,
+        }
+    }
+
+    /// Every threshold the run broke: the profile's error rate, throughput and latency
+    /// limits, and p95 growth beyond `max_regression_pct` over the baseline.
+    pub fn violations(&self, profile: &Profile, baseline: Option<&Report>, max_regression_pct: f64) -> Vec<String> {
+        let mut violations = Vec::new();
+        if self.error_rate > profile.max_error_rate {
+            violations.push(format!(
+                "error rate {:.2}% exceeds {:.2}%",
+                self.error_rate * 100.0,
+                profile.max_error_rate * 100.0
+            ));
+        }
+        if self.achieved_rps < profile.target_rps * MIN_THROUGHPUT_RATIO {
+            violations.push(format!(
+                "throughput {:.1} rps is below the {:.1} rps target",
+                self.achieved_rps, profile.target_rps
+            ));
+        }
+        for (name, scenario) in &self.scenarios {
+            if let Some(limit) = profile.p95_ms.get(name).filter(|limit| scenario.p95_ms > **limit) {
+                violations.push(format!("{}: p95 {:.1} ms exceeds {:.1} ms", name, scenario.p95_ms, limit));
+            }
+            let Some(before) = baseline.and_then(|b| b.scenarios.get(name)) else { continue };
+            let allowed = before.p95_ms * (1.0 + max_regression_pct / 100.0);
+            if scenario.p95_ms > allowed && scenario.p95_ms - before.p95_ms > MIN_REGRESSION_MS {
+                violations.push(format!(
+                    "{}: p95 regressed from {:.1} ms to {:.1} ms (more than {}%)",
+                    name, before.p95_ms, scenario.p95_ms, max_regression_pct
+                ));
+            }
+        }
+        violations
+    }
+}
@@ -0,0 +1,126 @@
+// loadtest/traffic.rs
+
+use rand::{seq::SliceRandom, Rng};
+use reqwest::{Method, RequestBuilder};
+use serde_json::json;
+use uuid::Uuid;
+
+use stateset_api::seed::{self, SeedOptions};
+
+/// One kind of request in the traffic mix; the name is the key used in `profiles.json`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scenario {
+    ListOrders,
+    GetOrder,
+    SearchOrders,
+    ListProducts,
+    CreateOrder,
+    ReserveInventory,
+}
+
+impl Scenario {
+    pub const ALL: [Scenario; 6] = [
+        Scenario::ListOrders,
+        Scenario::GetOrder,
+        Scenario::SearchOrders,
+        Scenario::ListProducts,
+        Scenario::CreateOrder,
+        Scenario::ReserveInventory,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Scenario::ListOrders => "list_orders",
+            Scenario::GetOrder => "get_order",
+            Scenario::SearchOrders => "search_orders",
+            Scenario::ListProducts => "list_products",
+            Scenario::CreateOrder => "create_order",
+            Scenario::ReserveInventory => "reserve_inventory",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Scenario> {
+        Scenario::ALL.into_iter().find(|s| s.name() == name)
+    }
+}
+
+/// Ids and customers the requests draw from. They come from the same generator as
+/// `stateset-cli seed`, so reads hit rows that exist when the database was seeded with
+/// the default options.
+pub struct Traffic {
+    base_url: String,
+    token: String,
+    order_ids: Vec<Uuid>,
+    customers: Vec<(Uuid, String)>,
+    /// Inventory item id and warehouse.
+    stock: Vec<(Uuid, i32)>,
+}
+
+impl Traffic {
+    pub fn new(base_url: &str, token: &str) -> Self {
+        let data = seed::generate(&SeedOptions::default());
+        Self {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token: token.to_string(),
+            order_ids: data.orders.iter().map(|o| o.id).collect(),
+            customers: data.customers.iter().map(|c| (c.id, c.email.clone())).collect(),
+            stock: data
+                .inventory
+                .iter()
+                .filter_map(|i| Uuid::parse_str(&i.id).ok().map(|id| (id, i.warehouse)))
+                .collect(),
+        }
+    }
+
+    /// A request for `scenario` with randomly drawn, realistic parameters.
+    pub fn request<R: Rng>(&self, client: &reqwest::Client, scenario: Scenario, rng: &mut R) -> RequestBuilder {
+        let (method, path, body) = match scenario {
+            Scenario::ListOrders => {
+                (Method::GET, format!("/orders?page={}&per_page=20", rng.gen_range(1..=5)), None)
+            }
+            Scenario::GetOrder => {
+                let id = self.order_ids.choose(rng).copied().unwrap_or_else(Uuid::new_v4);
+                (Method::GET, format!("/orders/{}", id), None)
+            }
+            Scenario::SearchOrders => {
+                let email = self.customers.choose(rng).map(|c| c.1.as_str()).unwrap_or("nobody@example.com");
+                (Method::GET, format!("/orders/search?customer_email={}", urlencode(email)), None)
+            }
+            Scenario::ListProducts => {
+                (Method::GET, format!("/api/v1/products?page={}&per_page=50", rng.gen_range(1..=2)), None)
+            }
+            Scenario::CreateOrder => {
+                let customer_id = self.customers.choose(rng).map(|c| c.0).unwrap_or_else(Uuid::new_v4);
+                let items: Vec<_> = self
+                    .stock
+                    .choose_multiple(rng, rng.gen_range(1..=3))
+                    .map(|(id, _)| json!({ "product_id": id, "quantity": rng.gen_range(1..=3) }))
+                    .collect();
+                let body = json!({ "customer_id": customer_id, "items": items, "allow_duplicate": true });
+                (Method::POST, "/orders".to_string(), Some(body))
+            }
+            Scenario::ReserveInventory => {
+                let (product_id, warehouse) = self.stock.choose(rng).copied().unwrap_or((Uuid::new_v4(), 1));
+                let body = json!({
+                    "warehouse_id": warehouse.to_string(),
+                    "reference_id": Uuid::new_v4(),
+                    "reference_type": "CART",
+                    "items": [{ "product_id": product_id, "quantity": 1 }],
+                    "reservation_type": "CustomerHold",
+                    "ttl_seconds": 900,
+                    "reservation_strategy": "Partial",
+                });
+                (Method::POST, "/api/v1/inventory/reserve".to_string(), Some(body))
+            }
+        };
+        let request = client.request(method, format!("{}{}", self.base_url, path)).bearer_auth(&self.token);
+        match body {
+            Some(body) => request.json(&body),
+            None => request,
+        }
+    }
+}
+
+fn urlencode(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
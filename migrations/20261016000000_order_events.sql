@@ -0,0 +1,15 @@
+-- phase: expand
+-- Append-only stream of domain events per order, written when `order_event_sourcing`
+-- is on. `order_events_sequence` makes each position unique within an order.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS order_events (
+    id UUID PRIMARY KEY,
+    order_id UUID NOT NULL,
+    sequence BIGINT NOT NULL,
+    event_type TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    actor TEXT,
+    created_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,22 @@
+-- phase: expand
+-- Service accounts for machine clients: OAuth client credentials and API keys, stored
+-- only as hashes.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS service_accounts (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    client_id TEXT NOT NULL UNIQUE,
+    client_secret_hash TEXT NOT NULL,
+    api_key_prefix TEXT NOT NULL,
+    api_key_hash TEXT NOT NULL UNIQUE,
+    scopes JSONB NOT NULL,
+    allowed_ips JSONB NOT NULL,
+    is_active BOOLEAN NOT NULL,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    last_used_at TIMESTAMPTZ,
+    revoked_at TIMESTAMPTZ
+);
@@ -0,0 +1,13 @@
+-- phase: expand
+-- Global network denylist managed through the admin API, in CIDR notation.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS network_acl_entries (
+    id SERIAL PRIMARY KEY,
+    cidr TEXT NOT NULL,
+    reason TEXT,
+    is_active BOOLEAN NOT NULL,
+    created_by TEXT,
+    created_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,17 @@
+-- phase: expand
+-- One row per retention policy run, with the rows it archived, exported or purged.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS retention_runs (
+    id UUID PRIMARY KEY,
+    policy TEXT NOT NULL,
+    table_name TEXT NOT NULL,
+    action TEXT NOT NULL,
+    cutoff TIMESTAMPTZ NOT NULL,
+    rows_affected BIGINT NOT NULL,
+    export_key TEXT,
+    error TEXT,
+    started_at TIMESTAMPTZ NOT NULL,
+    finished_at TIMESTAMPTZ NOT NULL
+);
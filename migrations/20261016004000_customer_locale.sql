@@ -0,0 +1,7 @@
+-- phase: expand
+-- Preferred locale of a customer for notifications and documents; unset falls back to
+-- the request locale.
+
+SET lock_timeout = '5s';
+
+ALTER TABLE customers ADD COLUMN IF NOT EXISTS locale TEXT;
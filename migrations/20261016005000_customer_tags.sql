@@ -0,0 +1,17 @@
+-- phase: expand
+-- Tags and computed segments per customer. Segment rows are replaced on every
+-- evaluator run; manual rows only change through the API.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS customer_tags (
+    customer_id INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    source VARCHAR(16) NOT NULL,
+    created_by TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (customer_id, tag)
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_customer_tags_tag ON customer_tags (tag);
@@ -0,0 +1,30 @@
+-- phase: expand
+-- Holds on stock for orders, carts and other references until they are released or
+-- expire; expired holds are released by the reservation expiry sweep.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS inventory_reservations (
+    id UUID PRIMARY KEY,
+    warehouse_id TEXT NOT NULL,
+    product_id UUID NOT NULL,
+    reference_id UUID NOT NULL,
+    reference_type TEXT NOT NULL,
+    quantity INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    reservation_type TEXT NOT NULL,
+    lot_numbers TEXT[],
+    location_id TEXT,
+    priority INTEGER,
+    notes TEXT,
+    expiration_date TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    created_by TEXT,
+    release_date TIMESTAMP,
+    release_reason TEXT
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_inventory_reservations_product_id ON inventory_reservations (product_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_inventory_reservations_reference_id ON inventory_reservations (reference_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_inventory_reservations_expiration_date ON inventory_reservations (expiration_date);
@@ -0,0 +1,25 @@
+-- phase: expand
+-- Checkout sessions, the record of truth behind the Redis cache. `version` guards
+-- concurrent updates.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS checkout_sessions (
+    id UUID PRIMARY KEY,
+    customer_id INTEGER,
+    status VARCHAR(16) NOT NULL,
+    items JSONB NOT NULL,
+    shipping_address JSONB,
+    payment JSONB,
+    currency TEXT NOT NULL,
+    metadata JSONB NOT NULL,
+    order_id UUID,
+    version INTEGER NOT NULL,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    expires_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_checkout_sessions_customer_id ON checkout_sessions (customer_id);
@@ -0,0 +1,29 @@
+-- phase: expand
+-- Catalog listings keyed by SKU, the source of the product feed.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS product_listings (
+    sku TEXT PRIMARY KEY,
+    title TEXT NOT NULL,
+    description TEXT NOT NULL,
+    brand TEXT,
+    gtin TEXT,
+    item_group_id TEXT,
+    color TEXT,
+    size TEXT,
+    hs_code TEXT,
+    country_of_origin TEXT,
+    price NUMERIC(19, 4) NOT NULL,
+    currency TEXT NOT NULL,
+    link TEXT,
+    image_link TEXT,
+    active BOOLEAN NOT NULL,
+    enable_checkout BOOLEAN NOT NULL,
+    metadata JSONB,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_product_listings_updated_at ON product_listings (updated_at);
@@ -0,0 +1,19 @@
+-- phase: expand
+-- Decisions recorded by the autonomous agents, one row per action with its rationale.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS agent_decisions (
+    id UUID PRIMARY KEY,
+    agent TEXT NOT NULL,
+    run_id UUID NOT NULL,
+    subject TEXT NOT NULL,
+    action TEXT NOT NULL,
+    rationale TEXT NOT NULL,
+    details JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_agent_decisions_agent ON agent_decisions (agent);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_agent_decisions_subject ON agent_decisions (subject);
@@ -0,0 +1,7 @@
+-- phase: expand
+-- When the carrier confirmed delivery, compared with `estimated_delivery` for SLA
+-- reporting.
+
+SET lock_timeout = '5s';
+
+ALTER TABLE shipments ADD COLUMN IF NOT EXISTS delivered_at TIMESTAMPTZ;
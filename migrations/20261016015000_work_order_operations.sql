@@ -0,0 +1,42 @@
+-- phase: expand
+-- Routing operations of a work order and the labor and machine time booked against
+-- them, the basis of work order costing.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS work_order_operations (
+    id UUID PRIMARY KEY,
+    work_order_id UUID NOT NULL,
+    sequence INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    work_center TEXT,
+    scheduled_date DATE,
+    machine_id INTEGER,
+    standard_labor_hours DOUBLE PRECISION NOT NULL,
+    standard_machine_hours DOUBLE PRECISION NOT NULL,
+    labor_rate NUMERIC(19, 4) NOT NULL,
+    machine_rate NUMERIC(19, 4) NOT NULL,
+    status TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS work_order_time_entries (
+    id UUID PRIMARY KEY,
+    operation_id UUID NOT NULL REFERENCES work_order_operations (id) ON DELETE CASCADE,
+    work_order_id UUID NOT NULL,
+    kind TEXT NOT NULL,
+    worker TEXT,
+    machine_id INTEGER,
+    started_at TIMESTAMPTZ NOT NULL,
+    ended_at TIMESTAMPTZ,
+    rate NUMERIC(19, 4) NOT NULL,
+    recorded_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_work_order_operations_work_order_id ON work_order_operations (work_order_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_work_order_time_entries_operation_id ON work_order_time_entries (operation_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_work_order_time_entries_work_order_id ON work_order_time_entries (work_order_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_work_order_time_entries_worker ON work_order_time_entries (worker);
@@ -0,0 +1,46 @@
+-- phase: expand
+-- Inspection plans with their sampling rules, and the inspections opened from receipts
+-- and work order completions.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS inspection_plans (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    source TEXT NOT NULL,
+    sku TEXT,
+    sampling TEXT NOT NULL,
+    sample_value INTEGER NOT NULL,
+    acceptance_number INTEGER NOT NULL,
+    active BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS quality_inspections (
+    id UUID PRIMARY KEY,
+    plan_id UUID NOT NULL REFERENCES inspection_plans (id),
+    source TEXT NOT NULL,
+    reference TEXT NOT NULL,
+    sku TEXT NOT NULL,
+    supplier_id UUID,
+    warehouse INTEGER NOT NULL,
+    inventory_item_id TEXT NOT NULL,
+    lot_quantity INTEGER NOT NULL,
+    sample_size INTEGER NOT NULL,
+    status TEXT NOT NULL,
+    disposition TEXT,
+    defects INTEGER,
+    quarantined_quantity INTEGER,
+    quarantine_item_id TEXT,
+    inspector TEXT,
+    notes TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_inspection_plans_source ON inspection_plans (source);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_quality_inspections_reference ON quality_inspections (reference);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_quality_inspections_supplier_id ON quality_inspections (supplier_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_quality_inspections_status ON quality_inspections (status);
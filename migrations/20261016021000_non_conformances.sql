@@ -0,0 +1,44 @@
+-- phase: expand
+-- Non-conformance reports and the corrective and preventive actions raised against them.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS non_conformances (
+    id UUID PRIMARY KEY,
+    source TEXT NOT NULL,
+    source_id TEXT NOT NULL,
+    sku TEXT,
+    supplier_id TEXT,
+    description TEXT NOT NULL,
+    severity TEXT NOT NULL,
+    status TEXT NOT NULL,
+    assignee TEXT,
+    due_date DATE,
+    root_cause TEXT,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    closed_at TIMESTAMPTZ
+);
+
+CREATE TABLE IF NOT EXISTS corrective_actions (
+    id UUID PRIMARY KEY,
+    ncr_id UUID NOT NULL REFERENCES non_conformances (id) ON DELETE CASCADE,
+    kind TEXT NOT NULL,
+    description TEXT NOT NULL,
+    status TEXT NOT NULL,
+    assignee TEXT,
+    due_date DATE NOT NULL,
+    verification_notes TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_non_conformances_source_id ON non_conformances (source_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_non_conformances_sku ON non_conformances (sku);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_non_conformances_supplier_id ON non_conformances (supplier_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_non_conformances_status ON non_conformances (status);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_corrective_actions_ncr_id ON corrective_actions (ncr_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_corrective_actions_status ON corrective_actions (status);
@@ -0,0 +1,22 @@
+-- phase: expand
+-- Goods received per supplier and SKU, the on-time half of supplier scorecards.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS supplier_receipts (
+    id UUID PRIMARY KEY,
+    supplier_id UUID NOT NULL,
+    purchase_order_id UUID,
+    reference TEXT NOT NULL,
+    sku TEXT NOT NULL,
+    ordered_quantity INTEGER NOT NULL,
+    received_quantity INTEGER NOT NULL,
+    promised_date DATE NOT NULL,
+    inspection_id UUID,
+    received_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_supplier_receipts_supplier_id ON supplier_receipts (supplier_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_supplier_receipts_purchase_order_id ON supplier_receipts (purchase_order_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_supplier_receipts_received_at ON supplier_receipts (received_at);
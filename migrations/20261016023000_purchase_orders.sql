@@ -0,0 +1,56 @@
+-- phase: expand
+-- Purchase requisitions and the purchase orders, with their lines, issued from them.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS requisitions (
+    id UUID PRIMARY KEY,
+    requested_by TEXT NOT NULL,
+    supplier_id UUID NOT NULL,
+    currency TEXT NOT NULL,
+    lines JSONB NOT NULL,
+    shipping_address JSONB NOT NULL,
+    total_amount NUMERIC(19, 4) NOT NULL,
+    needed_by DATE NOT NULL,
+    justification TEXT,
+    status TEXT NOT NULL,
+    purchase_order_id UUID,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    submitted_at TIMESTAMPTZ
+);
+
+CREATE TABLE IF NOT EXISTS purchase_orders (
+    id UUID PRIMARY KEY,
+    po_number TEXT NOT NULL UNIQUE,
+    supplier_id UUID NOT NULL,
+    requisition_id UUID,
+    status TEXT NOT NULL,
+    currency TEXT NOT NULL,
+    total_amount NUMERIC(19, 4) NOT NULL,
+    expected_delivery_date DATE NOT NULL,
+    shipping_address JSONB NOT NULL,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS purchase_order_lines (
+    id UUID PRIMARY KEY,
+    purchase_order_id UUID NOT NULL REFERENCES purchase_orders (id),
+    line_number INTEGER NOT NULL,
+    sku TEXT NOT NULL,
+    description TEXT,
+    quantity INTEGER NOT NULL,
+    unit_price NUMERIC(19, 4) NOT NULL,
+    tax_rate NUMERIC(19, 4),
+    line_total NUMERIC(19, 4) NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_requisitions_requested_by ON requisitions (requested_by);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_requisitions_status ON requisitions (status);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_purchase_orders_supplier_id ON purchase_orders (supplier_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_purchase_orders_requisition_id ON purchase_orders (requisition_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_purchase_orders_status ON purchase_orders (status);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_purchase_order_lines_purchase_order_id ON purchase_order_lines (purchase_order_id);
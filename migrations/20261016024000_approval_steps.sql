@@ -0,0 +1,28 @@
+-- phase: expand
+-- Steps of an approval chain per subject, e.g. a requisition, with delegation and
+-- escalation.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS approval_steps (
+    id UUID PRIMARY KEY,
+    subject TEXT NOT NULL,
+    subject_id TEXT NOT NULL,
+    step INTEGER NOT NULL,
+    approver_kind TEXT NOT NULL,
+    approver TEXT NOT NULL,
+    delegated_from TEXT,
+    status TEXT NOT NULL,
+    due_at TIMESTAMPTZ,
+    escalated_at TIMESTAMPTZ,
+    decided_by TEXT,
+    comment TEXT,
+    decided_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_approval_steps_subject ON approval_steps (subject);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_approval_steps_subject_id ON approval_steps (subject_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_approval_steps_approver ON approval_steps (approver);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_approval_steps_status ON approval_steps (status);
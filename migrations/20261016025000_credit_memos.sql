@@ -0,0 +1,40 @@
+-- phase: expand
+-- Customer credit memos, at most one per return, and their applications to invoices
+-- and refunds.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS credit_memos (
+    id UUID PRIMARY KEY,
+    memo_number TEXT NOT NULL UNIQUE,
+    customer_id UUID NOT NULL,
+    source TEXT NOT NULL,
+    return_id UUID UNIQUE,
+    reason TEXT NOT NULL,
+    currency TEXT NOT NULL,
+    amount NUMERIC(19, 4) NOT NULL,
+    remaining NUMERIC(19, 4) NOT NULL,
+    status TEXT NOT NULL,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    exported_at TIMESTAMPTZ
+);
+
+CREATE TABLE IF NOT EXISTS credit_memo_applications (
+    id UUID PRIMARY KEY,
+    credit_memo_id UUID NOT NULL REFERENCES credit_memos (id),
+    kind TEXT NOT NULL,
+    invoice_id TEXT,
+    reference TEXT,
+    amount NUMERIC(19, 4) NOT NULL,
+    applied_by TEXT NOT NULL,
+    applied_at TIMESTAMPTZ NOT NULL,
+    exported_at TIMESTAMPTZ
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_credit_memos_customer_id ON credit_memos (customer_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_credit_memos_status ON credit_memos (status);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_credit_memo_applications_credit_memo_id ON credit_memo_applications (credit_memo_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_credit_memo_applications_invoice_id ON credit_memo_applications (invoice_id);
@@ -0,0 +1,25 @@
+-- phase: expand
+-- Outbox of documents posted to the accounting system, retried with backoff until
+-- they are posted.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS accounting_exports (
+    id UUID PRIMARY KEY,
+    document_kind VARCHAR(16) NOT NULL,
+    document_id TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    entry JSONB NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    attempts INTEGER NOT NULL,
+    next_attempt_at TIMESTAMPTZ,
+    last_error TEXT,
+    external_id TEXT,
+    posted_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_accounting_exports_document_id ON accounting_exports (document_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_accounting_exports_status ON accounting_exports (status);
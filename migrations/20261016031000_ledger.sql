@@ -0,0 +1,30 @@
+-- phase: expand
+-- Double-entry ledger: one transaction per source document, keyed so a document posts
+-- once, and its balanced debit and credit entries.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS ledger_transactions (
+    id UUID PRIMARY KEY,
+    source_key TEXT NOT NULL UNIQUE,
+    kind TEXT NOT NULL,
+    reference TEXT NOT NULL,
+    description TEXT NOT NULL,
+    occurred_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS ledger_entries (
+    id UUID PRIMARY KEY,
+    transaction_id UUID NOT NULL REFERENCES ledger_transactions (id),
+    account VARCHAR(32) NOT NULL,
+    debit NUMERIC(19, 4) NOT NULL,
+    credit NUMERIC(19, 4) NOT NULL,
+    occurred_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_ledger_transactions_kind ON ledger_transactions (kind);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_ledger_entries_transaction_id ON ledger_entries (transaction_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_ledger_entries_account ON ledger_entries (account);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_ledger_entries_occurred_at ON ledger_entries (occurred_at);
@@ -0,0 +1,39 @@
+-- phase: expand
+-- Payment authorizations and the captures made against them. Captures are keyed by
+-- idempotency key so a repeated trigger captures once.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS payment_authorizations (
+    id UUID PRIMARY KEY,
+    order_id UUID NOT NULL,
+    gateway TEXT NOT NULL,
+    authorization_ref TEXT NOT NULL UNIQUE,
+    currency TEXT NOT NULL,
+    authorized_amount NUMERIC(19, 4) NOT NULL,
+    captured_amount NUMERIC(19, 4) NOT NULL,
+    capture_strategy VARCHAR(16) NOT NULL,
+    status VARCHAR(24) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS payment_captures (
+    id UUID PRIMARY KEY,
+    authorization_id UUID NOT NULL REFERENCES payment_authorizations (id),
+    shipment_reference TEXT,
+    amount NUMERIC(19, 4) NOT NULL,
+    final_capture BOOLEAN NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    idempotency_key TEXT NOT NULL UNIQUE,
+    gateway_ref TEXT,
+    error TEXT,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_payment_authorizations_order_id ON payment_authorizations (order_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_payment_authorizations_status ON payment_authorizations (status);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_payment_captures_authorization_id ON payment_captures (authorization_id);
@@ -0,0 +1,44 @@
+-- phase: expand
+-- Recurring subscriptions and the dunning cases opened when a renewal charge fails.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS subscriptions (
+    id UUID PRIMARY KEY,
+    customer_id UUID NOT NULL,
+    user_id INTEGER,
+    plan TEXT NOT NULL,
+    amount NUMERIC(19, 4) NOT NULL,
+    currency TEXT NOT NULL,
+    gateway_customer_ref TEXT NOT NULL,
+    payment_method_ref TEXT NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    current_period_end TIMESTAMPTZ NOT NULL,
+    past_due_since TIMESTAMPTZ,
+    canceled_at TIMESTAMPTZ,
+    cancel_reason TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS dunning_cases (
+    id UUID PRIMARY KEY,
+    subscription_id UUID NOT NULL REFERENCES subscriptions (id),
+    amount NUMERIC(19, 4) NOT NULL,
+    currency TEXT NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    failures INTEGER NOT NULL,
+    next_retry_at TIMESTAMPTZ,
+    last_error TEXT,
+    gateway_ref TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    resolved_at TIMESTAMPTZ
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_subscriptions_customer_id ON subscriptions (customer_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_subscriptions_status ON subscriptions (status);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_dunning_cases_subscription_id ON dunning_cases (subscription_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_dunning_cases_status ON dunning_cases (status);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_dunning_cases_next_retry_at ON dunning_cases (next_retry_at);
@@ -0,0 +1,44 @@
+-- phase: expand
+-- Chargebacks and inquiries received from payment providers, with the history of their
+-- status changes.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS disputes (
+    id UUID PRIMARY KEY,
+    provider TEXT NOT NULL,
+    external_id TEXT NOT NULL UNIQUE,
+    payment_ref TEXT NOT NULL,
+    order_id UUID,
+    authorization_id UUID,
+    amount NUMERIC(19, 4) NOT NULL,
+    currency TEXT NOT NULL,
+    reason TEXT NOT NULL,
+    status VARCHAR(24) NOT NULL,
+    is_inquiry BOOLEAN NOT NULL,
+    evidence_due_by TIMESTAMPTZ,
+    evidence JSONB,
+    evidence_submitted_at TIMESTAMPTZ,
+    deadline_alerted_at TIMESTAMPTZ,
+    closed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS dispute_events (
+    id UUID PRIMARY KEY,
+    dispute_id UUID NOT NULL REFERENCES disputes (id),
+    from_status VARCHAR(24),
+    to_status VARCHAR(24) NOT NULL,
+    source TEXT NOT NULL,
+    note TEXT,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_disputes_payment_ref ON disputes (payment_ref);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_disputes_order_id ON disputes (order_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_disputes_reason ON disputes (reason);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_disputes_status ON disputes (status);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_disputes_evidence_due_by ON disputes (evidence_due_by);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_dispute_events_dispute_id ON dispute_events (dispute_id);
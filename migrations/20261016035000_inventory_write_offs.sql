@@ -0,0 +1,28 @@
+-- phase: expand
+-- Inventory write-offs awaiting approval or posted to the ledger.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS inventory_write_offs (
+    id UUID PRIMARY KEY,
+    inventory_item_id TEXT NOT NULL,
+    sku TEXT NOT NULL,
+    warehouse INTEGER NOT NULL,
+    quantity INTEGER NOT NULL,
+    unit_cost NUMERIC(19, 4) NOT NULL,
+    amount NUMERIC(19, 4) NOT NULL,
+    reason VARCHAR(24) NOT NULL,
+    notes TEXT,
+    status VARCHAR(24) NOT NULL,
+    requested_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    posted_at TIMESTAMPTZ
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_inventory_write_offs_inventory_item_id ON inventory_write_offs (inventory_item_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_inventory_write_offs_sku ON inventory_write_offs (sku);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_inventory_write_offs_warehouse ON inventory_write_offs (warehouse);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_inventory_write_offs_status ON inventory_write_offs (status);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_inventory_write_offs_posted_at ON inventory_write_offs (posted_at);
@@ -0,0 +1,79 @@
+-- phase: expand
+-- Advance shipping notices with their lines, and the backorders and transfer orders
+-- their inbound stock is cross-docked to.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS asns (
+    id UUID PRIMARY KEY,
+    asn_number TEXT NOT NULL UNIQUE,
+    supplier_id UUID NOT NULL,
+    purchase_order_id UUID,
+    warehouse INTEGER NOT NULL,
+    status VARCHAR(24) NOT NULL,
+    expected_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS asn_lines (
+    id UUID PRIMARY KEY,
+    asn_id UUID NOT NULL REFERENCES asns (id),
+    sku TEXT NOT NULL,
+    quantity INTEGER NOT NULL,
+    cross_dock_quantity INTEGER NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS backorders (
+    id UUID PRIMARY KEY,
+    order_id UUID NOT NULL,
+    sku TEXT NOT NULL,
+    warehouse INTEGER NOT NULL,
+    quantity INTEGER NOT NULL,
+    allocated_quantity INTEGER NOT NULL,
+    status VARCHAR(24) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS transfer_orders (
+    id UUID PRIMARY KEY,
+    sku TEXT NOT NULL,
+    from_warehouse INTEGER NOT NULL,
+    to_warehouse INTEGER NOT NULL,
+    quantity INTEGER NOT NULL,
+    allocated_quantity INTEGER NOT NULL,
+    status VARCHAR(24) NOT NULL,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS cross_dock_allocations (
+    id UUID PRIMARY KEY,
+    asn_id UUID NOT NULL,
+    asn_line_id UUID NOT NULL,
+    sku TEXT NOT NULL,
+    quantity INTEGER NOT NULL,
+    target VARCHAR(24) NOT NULL,
+    target_id UUID NOT NULL,
+    order_id UUID,
+    to_warehouse INTEGER,
+    status VARCHAR(24) NOT NULL,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_asns_supplier_id ON asns (supplier_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_asns_purchase_order_id ON asns (purchase_order_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_asns_status ON asns (status);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_asn_lines_asn_id ON asn_lines (asn_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_asn_lines_sku ON asn_lines (sku);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_backorders_order_id ON backorders (order_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_backorders_sku ON backorders (sku);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_backorders_status ON backorders (status);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_transfer_orders_sku ON transfer_orders (sku);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_transfer_orders_status ON transfer_orders (status);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_cross_dock_allocations_asn_id ON cross_dock_allocations (asn_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_cross_dock_allocations_target_id ON cross_dock_allocations (target_id);
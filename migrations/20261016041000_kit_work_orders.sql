@@ -0,0 +1,36 @@
+-- phase: expand
+-- Kitting and de-kitting work orders, with the components and costs captured for each.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS kit_work_orders (
+    id UUID PRIMARY KEY,
+    number TEXT NOT NULL UNIQUE,
+    operation VARCHAR(16) NOT NULL,
+    kit_sku TEXT NOT NULL,
+    warehouse INTEGER NOT NULL,
+    quantity INTEGER NOT NULL,
+    labor_cost_per_kit NUMERIC(19, 4) NOT NULL,
+    material_cost NUMERIC(19, 4),
+    labor_cost NUMERIC(19, 4),
+    kit_unit_cost NUMERIC(19, 4),
+    status VARCHAR(16) NOT NULL,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    started_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ
+);
+
+CREATE TABLE IF NOT EXISTS kit_work_order_components (
+    id UUID PRIMARY KEY,
+    work_order_id UUID NOT NULL REFERENCES kit_work_orders (id),
+    component_sku TEXT NOT NULL,
+    quantity_per_kit INTEGER NOT NULL,
+    unit_cost NUMERIC(19, 4)
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_kit_work_orders_kit_sku ON kit_work_orders (kit_sku);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_kit_work_orders_status ON kit_work_orders (status);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_kit_work_order_components_work_order_id ON kit_work_order_components (work_order_id);
@@ -0,0 +1,66 @@
+-- phase: expand
+-- BOM revisions, the engineering change orders that release them, and the holds placed
+-- on work orders pinned to a superseded revision.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS bom_revisions (
+    id UUID PRIMARY KEY,
+    bom_id INTEGER NOT NULL,
+    revision TEXT NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    effective_from DATE NOT NULL,
+    eco_id UUID,
+    changes JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    superseded_at TIMESTAMPTZ
+);
+
+CREATE TABLE IF NOT EXISTS engineering_change_orders (
+    id UUID PRIMARY KEY,
+    number TEXT NOT NULL UNIQUE,
+    title TEXT NOT NULL,
+    description TEXT,
+    reason TEXT,
+    status VARCHAR(24) NOT NULL,
+    effective_date DATE NOT NULL,
+    requested_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    submitted_at TIMESTAMPTZ,
+    approved_at TIMESTAMPTZ,
+    implemented_at TIMESTAMPTZ
+);
+
+CREATE TABLE IF NOT EXISTS eco_changes (
+    id UUID PRIMARY KEY,
+    eco_id UUID NOT NULL REFERENCES engineering_change_orders (id),
+    bom_id INTEGER NOT NULL,
+    to_revision TEXT NOT NULL,
+    from_revision TEXT,
+    changes JSONB NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS eco_work_order_holds (
+    id UUID PRIMARY KEY,
+    eco_id UUID NOT NULL,
+    work_order_id UUID NOT NULL,
+    bom_id INTEGER NOT NULL,
+    revision TEXT NOT NULL,
+    previous_status TEXT NOT NULL,
+    held_at TIMESTAMPTZ NOT NULL,
+    released_at TIMESTAMPTZ,
+    released_by TEXT
+);
+
+ALTER TABLE work_orders ADD COLUMN IF NOT EXISTS bom_revision TEXT;
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_bom_revisions_bom_id ON bom_revisions (bom_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_bom_revisions_status ON bom_revisions (status);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_engineering_change_orders_status ON engineering_change_orders (status);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_engineering_change_orders_effective_date ON engineering_change_orders (effective_date);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_eco_changes_eco_id ON eco_changes (eco_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_eco_changes_bom_id ON eco_changes (bom_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_eco_work_order_holds_eco_id ON eco_work_order_holds (eco_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_eco_work_order_holds_work_order_id ON eco_work_order_holds (work_order_id);
@@ -0,0 +1,23 @@
+-- phase: expand
+-- Monthly sales forecasts per SKU and channel, with planner overrides.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS sales_forecasts (
+    id UUID PRIMARY KEY,
+    sku TEXT NOT NULL,
+    channel TEXT NOT NULL,
+    month DATE NOT NULL,
+    quantity INTEGER NOT NULL,
+    override_quantity INTEGER,
+    override_reason TEXT,
+    overridden_by TEXT,
+    overridden_at TIMESTAMPTZ,
+    source VARCHAR(16) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_sales_forecasts_sku ON sales_forecasts (sku);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_sales_forecasts_month ON sales_forecasts (month);
@@ -0,0 +1,33 @@
+-- phase: expand
+-- Work centers with their shift calendars and holidays for capacity planning.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS work_centers (
+    id UUID PRIMARY KEY,
+    code TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    efficiency DOUBLE PRECISION NOT NULL,
+    active BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS work_center_shifts (
+    id UUID PRIMARY KEY,
+    work_center_id UUID NOT NULL REFERENCES work_centers (id) ON DELETE CASCADE,
+    weekday SMALLINT NOT NULL,
+    start_time TIME NOT NULL,
+    end_time TIME NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS work_center_holidays (
+    id UUID PRIMARY KEY,
+    work_center_id UUID NOT NULL REFERENCES work_centers (id) ON DELETE CASCADE,
+    date DATE NOT NULL,
+    description TEXT
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_work_center_shifts_work_center_id ON work_center_shifts (work_center_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_work_center_holidays_work_center_id ON work_center_holidays (work_center_id);
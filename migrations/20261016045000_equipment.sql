@@ -0,0 +1,64 @@
+-- phase: expand
+-- Equipment register with preventive maintenance schedules, meter readings and downtime,
+-- and the link from maintenance work orders back to the equipment and schedule.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS equipment (
+    id UUID PRIMARY KEY,
+    asset_tag TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    category TEXT,
+    location TEXT,
+    work_center TEXT,
+    machine_id INTEGER,
+    status VARCHAR(16) NOT NULL,
+    meter_unit TEXT,
+    meter_reading DOUBLE PRECISION,
+    installed_on DATE,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS maintenance_schedules (
+    id UUID PRIMARY KEY,
+    equipment_id UUID NOT NULL REFERENCES equipment (id),
+    name TEXT NOT NULL,
+    instructions TEXT,
+    interval_days INTEGER,
+    meter_interval DOUBLE PRECISION,
+    lead_days INTEGER NOT NULL,
+    estimated_hours DOUBLE PRECISION NOT NULL,
+    next_due_date DATE,
+    next_due_meter DOUBLE PRECISION,
+    active BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS meter_readings (
+    id UUID PRIMARY KEY,
+    equipment_id UUID NOT NULL REFERENCES equipment (id),
+    reading DOUBLE PRECISION NOT NULL,
+    recorded_by TEXT NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS equipment_downtime (
+    id UUID PRIMARY KEY,
+    equipment_id UUID NOT NULL REFERENCES equipment (id),
+    work_order_id UUID,
+    planned BOOLEAN NOT NULL,
+    reason TEXT NOT NULL,
+    started_at TIMESTAMPTZ NOT NULL,
+    ended_at TIMESTAMPTZ,
+    reported_by TEXT NOT NULL
+);
+
+ALTER TABLE work_orders ADD COLUMN IF NOT EXISTS equipment_id UUID;
+ALTER TABLE work_orders ADD COLUMN IF NOT EXISTS maintenance_schedule_id UUID;
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_maintenance_schedules_equipment_id ON maintenance_schedules (equipment_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_meter_readings_equipment_id ON meter_readings (equipment_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_equipment_downtime_equipment_id ON equipment_downtime (equipment_id);
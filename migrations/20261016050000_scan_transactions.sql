@@ -0,0 +1,18 @@
+-- phase: expand
+-- Replay log for handheld scans, keyed by the device's idempotency key.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS scan_transactions (
+    id UUID PRIMARY KEY,
+    idempotency_key TEXT NOT NULL UNIQUE,
+    action TEXT NOT NULL,
+    code TEXT NOT NULL,
+    quantity INTEGER,
+    warehouse INTEGER,
+    reference TEXT,
+    device_id TEXT,
+    actor TEXT NOT NULL,
+    response JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,39 @@
+-- phase: expand
+-- Registered label and document printers and the jobs queued for them to claim.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS printers (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    station TEXT,
+    warehouse INTEGER,
+    language VARCHAR(8) NOT NULL,
+    media TEXT NOT NULL,
+    auto_print_shipments BOOLEAN NOT NULL,
+    active BOOLEAN NOT NULL,
+    last_seen_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS print_jobs (
+    id UUID PRIMARY KEY,
+    printer_id UUID NOT NULL REFERENCES printers (id),
+    status VARCHAR(16) NOT NULL,
+    document_type TEXT NOT NULL,
+    document_id TEXT NOT NULL,
+    content_type TEXT NOT NULL,
+    content BYTEA NOT NULL,
+    copies INTEGER NOT NULL,
+    attempts INTEGER NOT NULL,
+    error TEXT,
+    created_by TEXT NOT NULL,
+    claimed_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_print_jobs_printer_id_status ON print_jobs (printer_id, status);
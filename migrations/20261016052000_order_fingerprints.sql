@@ -0,0 +1,20 @@
+-- phase: expand
+-- Order fingerprints used to flag likely duplicate orders for review.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS order_fingerprints (
+    id UUID PRIMARY KEY,
+    order_id UUID NOT NULL UNIQUE,
+    customer_id UUID NOT NULL,
+    fingerprint TEXT NOT NULL,
+    total NUMERIC(19, 4),
+    duplicate_of UUID,
+    review_status VARCHAR(16),
+    reviewed_by TEXT,
+    reviewed_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_order_fingerprints_customer_id_fingerprint ON order_fingerprints (customer_id, fingerprint);
@@ -0,0 +1,44 @@
+-- phase: expand
+-- Shipping zones and the geocoded addresses resolved into them; each subject keeps one
+-- geocoded address.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS shipping_zones (
+    id UUID PRIMARY KEY,
+    code TEXT NOT NULL UNIQUE,
+    name TEXT NOT NULL,
+    priority INTEGER NOT NULL,
+    countries JSONB NOT NULL,
+    regions JSONB NOT NULL,
+    postal_prefixes JSONB NOT NULL,
+    center_latitude DOUBLE PRECISION,
+    center_longitude DOUBLE PRECISION,
+    radius_km DOUBLE PRECISION,
+    rate_group TEXT,
+    tax_jurisdiction TEXT,
+    transit_days_min INTEGER,
+    transit_days_max INTEGER,
+    active BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS address_geocodes (
+    id UUID PRIMARY KEY,
+    subject_type TEXT NOT NULL,
+    subject_id TEXT NOT NULL,
+    address TEXT NOT NULL,
+    latitude DOUBLE PRECISION,
+    longitude DOUBLE PRECISION,
+    country TEXT NOT NULL,
+    region TEXT,
+    postal_code TEXT,
+    zone_id UUID REFERENCES shipping_zones (id),
+    provider TEXT,
+    geocoded_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE UNIQUE INDEX CONCURRENTLY IF NOT EXISTS idx_address_geocodes_subject ON address_geocodes (subject_type, subject_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_address_geocodes_zone_id ON address_geocodes (zone_id);
@@ -0,0 +1,25 @@
+-- phase: expand
+-- End-of-day carrier manifests and the link from each shipment to the manifest it was
+-- closed out on.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS carrier_manifests (
+    id UUID PRIMARY KEY,
+    carrier TEXT NOT NULL,
+    manifest_date DATE NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    reference TEXT,
+    adapter TEXT NOT NULL,
+    shipment_count INTEGER NOT NULL,
+    document TEXT NOT NULL,
+    error TEXT,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+ALTER TABLE shipments ADD COLUMN IF NOT EXISTS manifest_id UUID;
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_carrier_manifests_carrier_manifest_date ON carrier_manifests (carrier, manifest_date);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_shipments_manifest_id ON shipments (manifest_id);
@@ -0,0 +1,32 @@
+-- phase: expand
+-- Hazmat classification per SKU and the dangerous-goods lines declared on each shipment.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS product_hazmat (
+    sku TEXT PRIMARY KEY,
+    un_number TEXT NOT NULL,
+    proper_shipping_name TEXT NOT NULL,
+    hazard_class TEXT NOT NULL,
+    packing_group VARCHAR(3),
+    limited_quantity BOOLEAN NOT NULL,
+    updated_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS shipment_dangerous_goods (
+    id UUID PRIMARY KEY,
+    shipment_id INTEGER NOT NULL,
+    sku TEXT NOT NULL,
+    quantity INTEGER NOT NULL,
+    un_number TEXT NOT NULL,
+    proper_shipping_name TEXT NOT NULL,
+    hazard_class TEXT NOT NULL,
+    packing_group VARCHAR(3),
+    limited_quantity BOOLEAN NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_shipment_dangerous_goods_shipment_id ON shipment_dangerous_goods (shipment_id);
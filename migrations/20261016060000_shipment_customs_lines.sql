@@ -0,0 +1,21 @@
+-- phase: expand
+-- Customs declaration lines captured for international shipments.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS shipment_customs_lines (
+    id UUID PRIMARY KEY,
+    shipment_id INTEGER NOT NULL,
+    destination_country TEXT NOT NULL,
+    sku TEXT NOT NULL,
+    description TEXT NOT NULL,
+    hs_code TEXT NOT NULL,
+    country_of_origin TEXT NOT NULL,
+    quantity INTEGER NOT NULL,
+    unit_value NUMERIC(19, 4) NOT NULL,
+    currency TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_shipment_customs_lines_shipment_id ON shipment_customs_lines (shipment_id);
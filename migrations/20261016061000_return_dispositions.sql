@@ -0,0 +1,43 @@
+-- phase: expand
+-- Dispositions recorded for returned units and the vendor returns raised from them.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS return_dispositions (
+    id UUID PRIMARY KEY,
+    return_id UUID NOT NULL,
+    rma TEXT NOT NULL,
+    sku TEXT NOT NULL,
+    warehouse INTEGER NOT NULL,
+    quantity INTEGER NOT NULL,
+    disposition VARCHAR(24) NOT NULL,
+    reason TEXT NOT NULL,
+    inventory_item_id TEXT,
+    work_order_id UUID,
+    write_off_id UUID,
+    vendor_return_id UUID,
+    notes TEXT,
+    disposed_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS vendor_returns (
+    id UUID PRIMARY KEY,
+    supplier_id UUID NOT NULL,
+    purchase_order_id UUID,
+    sku TEXT NOT NULL,
+    quantity INTEGER NOT NULL,
+    unit_cost NUMERIC(19, 4) NOT NULL,
+    amount NUMERIC(19, 4) NOT NULL,
+    rma TEXT,
+    reason TEXT NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_return_dispositions_return_id ON return_dispositions (return_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_return_dispositions_sku ON return_dispositions (sku);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_vendor_returns_supplier_id ON vendor_returns (supplier_id);
@@ -0,0 +1,57 @@
+-- phase: expand
+-- Drop-ship supplier sources per SKU, the supplier orders forwarded for customer orders and
+-- the shipments suppliers confirm against them. Adding a column with a constant default is a
+-- metadata-only change on PostgreSQL 11+.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS dropship_sources (
+    sku TEXT PRIMARY KEY,
+    supplier_id UUID NOT NULL,
+    supplier_sku TEXT,
+    unit_cost NUMERIC(19, 4) NOT NULL,
+    currency TEXT NOT NULL,
+    lead_time_days INTEGER NOT NULL,
+    active BOOLEAN NOT NULL,
+    updated_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS dropship_orders (
+    id UUID PRIMARY KEY,
+    order_id UUID NOT NULL,
+    purchase_order_id UUID NOT NULL UNIQUE,
+    supplier_id UUID NOT NULL,
+    customer_name TEXT NOT NULL,
+    customer_email TEXT NOT NULL,
+    ship_to TEXT NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    supplier_reference TEXT,
+    forward_attempts INTEGER NOT NULL,
+    forward_error TEXT,
+    forwarded_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS dropship_shipments (
+    id UUID PRIMARY KEY,
+    dropship_order_id UUID NOT NULL,
+    carrier TEXT NOT NULL,
+    tracking_number TEXT NOT NULL,
+    tracking_url TEXT,
+    shipped_at TIMESTAMPTZ NOT NULL,
+    confirmed_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS dropship BOOLEAN NOT NULL DEFAULT false;
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_dropship_sources_supplier_id ON dropship_sources (supplier_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_dropship_orders_order_id ON dropship_orders (order_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_dropship_orders_supplier_id ON dropship_orders (supplier_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_dropship_orders_status ON dropship_orders (status);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_dropship_shipments_dropship_order_id ON dropship_shipments (dropship_order_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_dropship_shipments_tracking_number ON dropship_shipments (tracking_number);
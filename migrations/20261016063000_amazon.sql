@@ -0,0 +1,38 @@
+-- phase: expand
+-- Amazon seller SKU mappings, imported Amazon orders and the order sync cursor.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS amazon_listings (
+    seller_sku TEXT PRIMARY KEY,
+    asin TEXT NOT NULL,
+    sku TEXT NOT NULL,
+    auto_mapped BOOLEAN NOT NULL,
+    updated_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS amazon_orders (
+    amazon_order_id TEXT PRIMARY KEY,
+    order_id UUID NOT NULL UNIQUE,
+    fulfillment_channel VARCHAR(8) NOT NULL,
+    amazon_status TEXT NOT NULL,
+    purchase_date TIMESTAMPTZ NOT NULL,
+    last_update_date TIMESTAMPTZ NOT NULL,
+    items JSONB NOT NULL,
+    shipment_confirmed_at TIMESTAMPTZ,
+    confirmation_error TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS amazon_sync_cursors (
+    name TEXT PRIMARY KEY,
+    synced_until TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_amazon_listings_asin ON amazon_listings (asin);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_amazon_listings_sku ON amazon_listings (sku);
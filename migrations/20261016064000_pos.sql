@@ -0,0 +1,58 @@
+-- phase: expand
+-- Register shifts, POS sales replayed from registers and the tenders taken against them.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS pos_shifts (
+    id UUID PRIMARY KEY,
+    register_id TEXT NOT NULL,
+    warehouse_id UUID NOT NULL,
+    cashier TEXT NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    opening_float NUMERIC(19, 4) NOT NULL,
+    cash_sales NUMERIC(19, 4) NOT NULL,
+    counted_cash NUMERIC(19, 4),
+    cash_variance NUMERIC(19, 4),
+    sales_count INTEGER NOT NULL,
+    opened_at TIMESTAMPTZ NOT NULL,
+    closed_at TIMESTAMPTZ,
+    closed_by TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS pos_sales (
+    id UUID PRIMARY KEY,
+    register_id TEXT NOT NULL,
+    shift_id UUID NOT NULL,
+    cashier TEXT NOT NULL,
+    sold_at TIMESTAMPTZ NOT NULL,
+    total NUMERIC(19, 4) NOT NULL,
+    currency TEXT NOT NULL,
+    fingerprint TEXT NOT NULL,
+    offline BOOLEAN NOT NULL,
+    replays INTEGER NOT NULL,
+    conflict_fingerprint TEXT,
+    last_conflict_at TIMESTAMPTZ,
+    received_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS pos_payments (
+    id UUID PRIMARY KEY,
+    sale_id UUID NOT NULL,
+    shift_id UUID NOT NULL,
+    tender VARCHAR(16) NOT NULL,
+    amount NUMERIC(19, 4) NOT NULL,
+    tendered NUMERIC(19, 4),
+    change_given NUMERIC(19, 4),
+    reference TEXT,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_pos_shifts_register_id ON pos_shifts (register_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_pos_shifts_status ON pos_shifts (status);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_pos_sales_register_id ON pos_sales (register_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_pos_sales_shift_id ON pos_sales (shift_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_pos_payments_sale_id ON pos_payments (sale_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_pos_payments_shift_id ON pos_payments (shift_id);
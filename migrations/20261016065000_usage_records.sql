@@ -0,0 +1,20 @@
+-- phase: expand
+-- Monthly usage counters per tenant and metric; the metering flush upserts on
+-- (tenant_id, period, metric).
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS usage_records (
+    id UUID PRIMARY KEY,
+    tenant_id TEXT NOT NULL,
+    period TEXT NOT NULL,
+    metric VARCHAR(32) NOT NULL,
+    quantity BIGINT NOT NULL,
+    exported_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    UNIQUE (tenant_id, period, metric)
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_usage_records_period ON usage_records (period);
@@ -0,0 +1,24 @@
+-- phase: expand
+-- Wrapped per-tenant data keys; each tenant has one row per key version. Service
+-- accounts record the tenant they act for, which their tokens carry.
+-- ALTER TABLE service_accounts ADD COLUMN IF NOT EXISTS tenant_id TEXT;
+
+CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS tenant_data_keys (
+    id UUID PRIMARY KEY,
+    tenant_id TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    wrapped_key TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    master_key_id TEXT NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    remaining_values BIGINT,
+    created_at TIMESTAMPTZ NOT NULL,
+    retired_at TIMESTAMPTZ,
+    UNIQUE (tenant_id, version)
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_tenant_data_keys_status ON tenant_data_keys (status);
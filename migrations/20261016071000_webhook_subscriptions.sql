@@ -0,0 +1,20 @@
+-- phase: expand
+-- Entity webhook subscriptions with their payload field selection and template.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS webhook_subscriptions (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    url TEXT NOT NULL,
+    event_types JSONB NOT NULL,
+    secret TEXT NOT NULL,
+    fields JSONB,
+    exclude JSONB NOT NULL,
+    template JSONB,
+    include_pii BOOLEAN NOT NULL,
+    active BOOLEAN NOT NULL,
+    created_by TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
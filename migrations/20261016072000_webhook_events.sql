@@ -0,0 +1,15 @@
+-- phase: expand
+-- Outbox of emitted webhook events, kept so deliveries can be replayed by time range.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS webhook_events (
+    id UUID PRIMARY KEY,
+    event_type TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    occurred_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_webhook_events_event_type ON webhook_events (event_type);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_webhook_events_occurred_at ON webhook_events (occurred_at);
@@ -0,0 +1,19 @@
+-- phase: expand
+-- Inbound payloads that failed validation, held for inspection and replay.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS ingest_quarantine (
+    id UUID PRIMARY KEY,
+    source TEXT NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    reason TEXT NOT NULL,
+    headers JSONB NOT NULL,
+    body TEXT NOT NULL,
+    attempts INTEGER NOT NULL,
+    received_at TIMESTAMPTZ NOT NULL,
+    resolved_at TIMESTAMPTZ
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_ingest_quarantine_source ON ingest_quarantine (source);
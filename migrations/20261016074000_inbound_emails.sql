@@ -0,0 +1,23 @@
+-- phase: expand
+-- Inbound customer emails, deduplicated on Message-ID, and the order or return each was
+-- matched to.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS inbound_emails (
+    id UUID PRIMARY KEY,
+    message_id TEXT NOT NULL UNIQUE,
+    channel TEXT NOT NULL,
+    from_address TEXT NOT NULL,
+    subject TEXT NOT NULL,
+    kind TEXT,
+    status VARCHAR(16) NOT NULL,
+    order_id UUID,
+    return_id UUID,
+    raw TEXT NOT NULL,
+    received_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_inbound_emails_order_id ON inbound_emails (order_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_inbound_emails_return_id ON inbound_emails (return_id);
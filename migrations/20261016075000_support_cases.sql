@@ -0,0 +1,35 @@
+-- phase: expand
+-- Support cases with their SLA deadlines and links to the order, return or shipment.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS support_cases (
+    id UUID PRIMARY KEY,
+    subject TEXT NOT NULL,
+    description TEXT NOT NULL,
+    customer_email TEXT NOT NULL,
+    customer_id UUID,
+    order_id UUID,
+    return_id UUID,
+    shipment_id INTEGER,
+    priority TEXT NOT NULL,
+    status TEXT NOT NULL,
+    assignee TEXT,
+    first_response_due_at TIMESTAMPTZ NOT NULL,
+    resolution_due_at TIMESTAMPTZ NOT NULL,
+    first_responded_at TIMESTAMPTZ,
+    sla_paused_at TIMESTAMPTZ,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    resolved_at TIMESTAMPTZ
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_support_cases_customer_email ON support_cases (customer_email);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_support_cases_customer_id ON support_cases (customer_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_support_cases_order_id ON support_cases (order_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_support_cases_return_id ON support_cases (return_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_support_cases_shipment_id ON support_cases (shipment_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_support_cases_status ON support_cases (status);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_support_cases_assignee ON support_cases (assignee);
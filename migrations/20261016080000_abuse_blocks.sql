@@ -0,0 +1,18 @@
+-- phase: expand
+-- Blocked client fingerprints, networks and JA3 hashes for protected endpoints.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS abuse_blocks (
+    id SERIAL PRIMARY KEY,
+    kind VARCHAR(16) NOT NULL,
+    value TEXT NOT NULL,
+    reason TEXT,
+    expires_at TIMESTAMPTZ,
+    is_active BOOLEAN NOT NULL,
+    created_by TEXT,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_abuse_blocks_is_active ON abuse_blocks (is_active);
@@ -0,0 +1,24 @@
+-- phase: expand
+-- Request counts per route group and flush interval, written by every instance and summed
+-- over a window to evaluate SLOs.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS slo_buckets (
+    id BIGSERIAL PRIMARY KEY,
+    route_group TEXT NOT NULL,
+    bucket_start TIMESTAMPTZ NOT NULL,
+    requests BIGINT NOT NULL,
+    errors BIGINT NOT NULL,
+    le_50ms BIGINT NOT NULL,
+    le_100ms BIGINT NOT NULL,
+    le_250ms BIGINT NOT NULL,
+    le_500ms BIGINT NOT NULL,
+    le_1000ms BIGINT NOT NULL,
+    le_2500ms BIGINT NOT NULL,
+    le_5000ms BIGINT NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_slo_buckets_route_group ON slo_buckets (route_group);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_slo_buckets_bucket_start ON slo_buckets (bucket_start);
@@ -0,0 +1,20 @@
+-- phase: expand
+-- Change data capture consumer offsets and the last row image per table and key, used
+-- to emit before images.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS cdc_offsets (
+    name TEXT PRIMARY KEY,
+    occurred_at TIMESTAMPTZ NOT NULL,
+    event_id UUID NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS cdc_row_images (
+    table_name TEXT NOT NULL,
+    row_key TEXT NOT NULL,
+    image JSONB NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (table_name, row_key)
+);
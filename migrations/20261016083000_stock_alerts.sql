@@ -0,0 +1,28 @@
+-- phase: expand
+-- Low-stock alert subscriptions and per-SKU thresholds.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS stock_alert_subscriptions (
+    id UUID PRIMARY KEY,
+    user_id INTEGER NOT NULL,
+    sku_pattern TEXT NOT NULL,
+    warehouse INTEGER,
+    locale TEXT NOT NULL,
+    created_by TEXT,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS stock_alert_thresholds (
+    id UUID PRIMARY KEY,
+    sku TEXT NOT NULL,
+    warehouse INTEGER,
+    threshold INTEGER NOT NULL,
+    updated_by TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_stock_alert_subscriptions_user_id ON stock_alert_subscriptions (user_id);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_stock_alert_thresholds_sku ON stock_alert_thresholds (sku);
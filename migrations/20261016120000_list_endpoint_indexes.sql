@@ -0,0 +1,17 @@
+-- phase: expand
+-- Indexes for the filter and sort combinations of the order, return, inventory and
+-- shipment list endpoints, as reported missing by GET /api/v1/admin/database/indexes.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_orders_created_date ON orders (created_date DESC);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_orders_order_status_created_date ON orders (order_status, created_date DESC);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_orders_customer_email_created_date ON orders (customer_email, created_date DESC);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_returns_created_date ON returns (created_date DESC);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_returns_status_created_date ON returns (status, created_date DESC);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_returns_customer_id_created_date ON returns (customer_id, created_date DESC);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_returns_order_id_created_date ON returns (order_id, created_date DESC);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_inventory_items_sku_warehouse ON inventory_items (sku, warehouse);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_shipments_tracking_number ON shipments (tracking_number);
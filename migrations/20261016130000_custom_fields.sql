@@ -0,0 +1,31 @@
+-- phase: expand
+-- Integrator metadata on orders, customers and product listings, the per-tenant custom
+-- field definitions it is checked against, and GIN indexes for the metadata filters of
+-- the list endpoints.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS metadata JSONB;
+ALTER TABLE customers ADD COLUMN IF NOT EXISTS metadata JSONB;
+ALTER TABLE product_listings ADD COLUMN IF NOT EXISTS metadata JSONB;
+
+CREATE TABLE IF NOT EXISTS custom_field_definitions (
+    id UUID PRIMARY KEY,
+    tenant_id TEXT NOT NULL,
+    entity VARCHAR(16) NOT NULL,
+    key TEXT NOT NULL,
+    field_type VARCHAR(16) NOT NULL,
+    required BOOLEAN NOT NULL DEFAULT false,
+    allowed_values JSONB,
+    description TEXT,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE UNIQUE INDEX CONCURRENTLY IF NOT EXISTS idx_custom_field_definitions_tenant_entity_key ON custom_field_definitions (tenant_id, entity, key);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_orders_metadata ON orders USING GIN (metadata jsonb_path_ops);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_customers_metadata ON customers USING GIN (metadata jsonb_path_ops);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_product_listings_metadata ON product_listings USING GIN (metadata jsonb_path_ops);
@@ -0,0 +1,36 @@
+-- phase: expand
+-- Recent API-key requests and webhook delivery attempts for the developer console.
+-- Rows are kept only as long as a `retention` policy on these tables allows.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS developer_request_logs (
+    id UUID PRIMARY KEY,
+    api_key_prefix TEXT NOT NULL,
+    method VARCHAR(16) NOT NULL,
+    path TEXT NOT NULL,
+    query TEXT,
+    status INTEGER NOT NULL,
+    latency_ms BIGINT NOT NULL,
+    request_body TEXT NOT NULL,
+    response_body TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS webhook_delivery_logs (
+    id UUID PRIMARY KEY,
+    subscription_id UUID NOT NULL,
+    event_type TEXT NOT NULL,
+    url TEXT NOT NULL,
+    attempt INTEGER NOT NULL,
+    replay BOOLEAN NOT NULL DEFAULT false,
+    status INTEGER,
+    error TEXT,
+    latency_ms BIGINT NOT NULL,
+    response_body TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_developer_request_logs_key_created ON developer_request_logs (api_key_prefix, created_at DESC);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_webhook_delivery_logs_subscription_created ON webhook_delivery_logs (subscription_id, created_at DESC);
@@ -0,0 +1,62 @@
+-- phase: expand
+-- Change tracking for `?updated_since=` sync of orders, product listings, inventory items
+-- and customers: a trigger-stamped `sync_updated_at` on each table and tombstones written
+-- by delete triggers. Existing rows start at the epoch, so a sync from the epoch returns
+-- them all. The constant default is stored in the catalog and does not rewrite tables.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS sync_updated_at TIMESTAMPTZ NOT NULL DEFAULT '1970-01-01 00:00:00+00';
+ALTER TABLE product_listings ADD COLUMN IF NOT EXISTS sync_updated_at TIMESTAMPTZ NOT NULL DEFAULT '1970-01-01 00:00:00+00';
+ALTER TABLE inventory_items ADD COLUMN IF NOT EXISTS sync_updated_at TIMESTAMPTZ NOT NULL DEFAULT '1970-01-01 00:00:00+00';
+ALTER TABLE customers ADD COLUMN IF NOT EXISTS sync_updated_at TIMESTAMPTZ NOT NULL DEFAULT '1970-01-01 00:00:00+00';
+
+CREATE TABLE IF NOT EXISTS sync_tombstones (
+    id BIGSERIAL PRIMARY KEY,
+    entity VARCHAR(16) NOT NULL,
+    record_id TEXT NOT NULL,
+    deleted_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE OR REPLACE FUNCTION sync_touch() RETURNS trigger AS $$
+BEGIN
+    NEW.sync_updated_at := now();
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Arguments: the entity name recorded and the key column of the table
+CREATE OR REPLACE FUNCTION sync_tombstone() RETURNS trigger AS $$
+BEGIN
+    INSERT INTO sync_tombstones (entity, record_id, deleted_at)
+    VALUES (TG_ARGV[0], to_jsonb(OLD) ->> TG_ARGV[1], now());
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE TRIGGER orders_sync_touch BEFORE INSERT OR UPDATE ON orders
+    FOR EACH ROW EXECUTE FUNCTION sync_touch();
+CREATE OR REPLACE TRIGGER orders_sync_tombstone AFTER DELETE ON orders
+    FOR EACH ROW EXECUTE FUNCTION sync_tombstone('order', 'id');
+
+CREATE OR REPLACE TRIGGER product_listings_sync_touch BEFORE INSERT OR UPDATE ON product_listings
+    FOR EACH ROW EXECUTE FUNCTION sync_touch();
+CREATE OR REPLACE TRIGGER product_listings_sync_tombstone AFTER DELETE ON product_listings
+    FOR EACH ROW EXECUTE FUNCTION sync_tombstone('product', 'sku');
+
+CREATE OR REPLACE TRIGGER inventory_items_sync_touch BEFORE INSERT OR UPDATE ON inventory_items
+    FOR EACH ROW EXECUTE FUNCTION sync_touch();
+CREATE OR REPLACE TRIGGER inventory_items_sync_tombstone AFTER DELETE ON inventory_items
+    FOR EACH ROW EXECUTE FUNCTION sync_tombstone('inventory', 'id');
+
+CREATE OR REPLACE TRIGGER customers_sync_touch BEFORE INSERT OR UPDATE ON customers
+    FOR EACH ROW EXECUTE FUNCTION sync_touch();
+CREATE OR REPLACE TRIGGER customers_sync_tombstone AFTER DELETE ON customers
+    FOR EACH ROW EXECUTE FUNCTION sync_tombstone('customer', 'id');
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_orders_sync_updated_at ON orders (sync_updated_at);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_product_listings_sync_updated_at ON product_listings (sync_updated_at);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_inventory_items_sync_updated_at ON inventory_items (sync_updated_at);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_customers_sync_updated_at ON customers (sync_updated_at);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_sync_tombstones_entity_deleted_at ON sync_tombstones (entity, deleted_at);
@@ -0,0 +1,23 @@
+-- phase: expand
+-- Fraud risk on returns, and the serial numbers each order shipped with so a returned
+-- unit can be checked against them.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+ALTER TABLE returns ADD COLUMN IF NOT EXISTS risk_score INTEGER;
+ALTER TABLE returns ADD COLUMN IF NOT EXISTS risk_signals JSONB;
+
+CREATE TABLE IF NOT EXISTS shipped_serials (
+    id UUID PRIMARY KEY,
+    order_id UUID NOT NULL,
+    sku TEXT NOT NULL,
+    serial_number TEXT NOT NULL,
+    recorded_by TEXT NOT NULL,
+    recorded_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE UNIQUE INDEX CONCURRENTLY IF NOT EXISTS idx_shipped_serials_order_serial ON shipped_serials (order_id, serial_number);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_shipped_serials_serial ON shipped_serials (serial_number);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_returns_customer_requested ON returns (customer_email, requested_date);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_returns_risk_score ON returns (risk_score DESC) WHERE risk_score IS NOT NULL;
@@ -0,0 +1,36 @@
+-- phase: expand
+-- Payment vault: opaque tokens for payment methods held by the processor. Only token
+-- hashes and card display details are stored.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS payment_vault_tokens (
+    id UUID PRIMARY KEY,
+    token_hash TEXT NOT NULL,
+    token_prefix TEXT NOT NULL,
+    provider TEXT NOT NULL,
+    payment_method_ref TEXT NOT NULL,
+    customer_ref TEXT,
+    brand TEXT,
+    last4 TEXT,
+    exp_month INTEGER,
+    exp_year INTEGER,
+    usage VARCHAR(16) NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    max_amount NUMERIC(19, 4),
+    currency VARCHAR(3),
+    checkout_session_id UUID,
+    last_used_by TEXT,
+    last_used_at TIMESTAMPTZ,
+    created_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    expires_at TIMESTAMPTZ
+);
+
+CREATE UNIQUE INDEX CONCURRENTLY IF NOT EXISTS idx_payment_vault_tokens_hash ON payment_vault_tokens (token_hash);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_payment_vault_tokens_status ON payment_vault_tokens (status);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_payment_vault_tokens_session ON payment_vault_tokens (checkout_session_id) WHERE checkout_session_id IS NOT NULL;
+
+ALTER TABLE subscriptions ADD COLUMN IF NOT EXISTS vault_token_id UUID;
@@ -0,0 +1,29 @@
+-- phase: expand
+-- Order routing rules: priority-ordered sourcing overrides with conditions on destination,
+-- channel and SKU class, edited through the admin API.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS routing_rules (
+    id UUID PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    priority INTEGER NOT NULL,
+    countries JSONB NOT NULL DEFAULT '[]',
+    regions JSONB NOT NULL DEFAULT '[]',
+    channels JSONB NOT NULL DEFAULT '[]',
+    sku_classes JSONB NOT NULL DEFAULT '[]',
+    strategy JSONB NOT NULL DEFAULT '[]',
+    excluded_warehouses JSONB NOT NULL DEFAULT '[]',
+    ship_from_store BOOLEAN,
+    max_splits INTEGER,
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_by TEXT NOT NULL,
+    updated_by TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE UNIQUE INDEX CONCURRENTLY IF NOT EXISTS idx_routing_rules_name ON routing_rules (name);
+CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_routing_rules_active_priority ON routing_rules (priority) WHERE active;
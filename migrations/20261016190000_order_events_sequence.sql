@@ -0,0 +1,5 @@
+-- phase: expand
+-- One event per position in an order's stream, so concurrent appends cannot interleave.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+CREATE UNIQUE INDEX CONCURRENTLY IF NOT EXISTS idx_order_events_order_id_sequence ON order_events (order_id, sequence);
@@ -0,0 +1,138 @@
+-- phase: expand
+-- First of three steps range-partitioning the high-volume tables by month on their
+-- creation timestamp while they keep serving traffic:
+--
+-- 1. This migration creates `<table>_partitioned` beside each table, with a partition for
+--    rows older than the current month, the current and next three months and a default
+--    partition, and a trigger copying every write on the table into it.
+-- 2. `stateset-cli partitions backfill <table>` copies the existing rows in small batches.
+-- 3. `stateset-cli partitions swap` checks every row was copied and prints the contract
+--    migration swapping each table for its copy. Foreign keys touching the tables are
+--    replaced there by the reference checks created below.
+--
+-- Tables that do not exist are skipped, as are tables already partitioned. Until the swap
+-- ships, column changes to these tables have to be made to their `_partitioned` copy too.
+-- The primary key of a copy is (id, <column>); unique indexes are not copied, as Postgres
+-- cannot enforce them across partitions unless they include the partition column.
+SET lock_timeout = '5s';
+
+-- Mirrors a write on a table into its partitioned copy. An update deletes and re-inserts
+-- the row, which also moves it when its partition column changed.
+CREATE OR REPLACE FUNCTION partition_copy_row() RETURNS TRIGGER AS $$
+BEGIN
+    IF TG_OP IN ('UPDATE', 'DELETE') THEN
+        EXECUTE format('DELETE FROM %I WHERE id = ($1).id', TG_TABLE_NAME || '_partitioned') USING OLD;
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        EXECUTE format('INSERT INTO %I SELECT ($1).* ON CONFLICT DO NOTHING', TG_TABLE_NAME || '_partitioned') USING NEW;
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Stands in for a foreign key on the referencing table, since nothing can declare one on
+-- `id` alone once the key includes the partition column. Locks the referenced row as a
+-- foreign key would. Arguments: referenced table, referenced column, referencing column.
+CREATE OR REPLACE FUNCTION partition_reference_check() RETURNS TRIGGER AS $$
+DECLARE
+    missing BOOLEAN;
+    found INTEGER;
+BEGIN
+    EXECUTE format('SELECT ($1).%I IS NULL', TG_ARGV[2]) INTO missing USING NEW;
+    IF missing THEN
+        RETURN NULL;
+    END IF;
+    EXECUTE format('SELECT 1 FROM %I WHERE %I = ($1).%I FOR KEY SHARE', TG_ARGV[0], TG_ARGV[1], TG_ARGV[2]) USING NEW;
+    GET DIAGNOSTICS found = ROW_COUNT;
+    IF found = 0 THEN
+        RAISE EXCEPTION 'insert or update on table "%" violates reference to "%"', TG_TABLE_NAME, TG_ARGV[0]
+            USING ERRCODE = 'foreign_key_violation';
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- The referenced side of the same check: deleting a referenced row or changing its key
+-- is refused, or cascades to the referencing rows where the replaced foreign key did.
+-- Arguments: referencing table, referencing column, referenced column, `cascade` or
+-- `restrict`.
+CREATE OR REPLACE FUNCTION partition_referenced_check() RETURNS TRIGGER AS $$
+DECLARE
+    unchanged BOOLEAN;
+    referenced BOOLEAN;
+BEGIN
+    IF TG_OP = 'UPDATE' THEN
+        EXECUTE format('SELECT ($1).%I IS NOT DISTINCT FROM ($2).%I', TG_ARGV[2], TG_ARGV[2]) INTO unchanged USING OLD, NEW;
+        IF unchanged THEN
+            RETURN NULL;
+        END IF;
+    END IF;
+    IF TG_OP = 'DELETE' AND TG_ARGV[3] = 'cascade' THEN
+        EXECUTE format('DELETE FROM %I WHERE %I = ($1).%I', TG_ARGV[0], TG_ARGV[1], TG_ARGV[2]) USING OLD;
+        RETURN NULL;
+    END IF;
+    EXECUTE format('SELECT EXISTS (SELECT 1 FROM %I WHERE %I = ($1).%I)', TG_ARGV[0], TG_ARGV[1], TG_ARGV[2])
+        INTO referenced USING OLD;
+    IF referenced THEN
+        RAISE EXCEPTION 'update or delete on table "%" violates reference from "%"', TG_TABLE_NAME, TG_ARGV[0]
+            USING ERRCODE = 'foreign_key_violation';
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION partition_shadow(tbl TEXT, col TEXT, premake INT) RETURNS VOID AS $$
+DECLARE
+    copy TEXT := tbl || '_partitioned';
+    idx RECORD;
+    current_start DATE := date_trunc('month', now() AT TIME ZONE 'UTC')::date;
+    p_from DATE;
+BEGIN
+    IF to_regclass(tbl) IS NULL THEN
+        RAISE NOTICE '% does not exist; not partitioned', tbl;
+        RETURN;
+    END IF;
+    IF EXISTS (SELECT 1 FROM pg_partitioned_table WHERE partrelid = to_regclass(tbl)) THEN
+        RETURN;
+    END IF;
+    EXECUTE format(
+        'CREATE TABLE IF NOT EXISTS %I (LIKE %I INCLUDING DEFAULTS INCLUDING GENERATED INCLUDING STORAGE, PRIMARY KEY (id, %I)) PARTITION BY RANGE (%I)',
+        copy, tbl, col, col
+    );
+    FOR idx IN
+        SELECT i.relname AS name, substring(pg_get_indexdef(x.indexrelid) FROM ' USING .*$') AS definition
+        FROM pg_index x
+        JOIN pg_class i ON i.oid = x.indexrelid
+        WHERE x.indrelid = to_regclass(tbl) AND NOT x.indisunique
+    LOOP
+        EXECUTE format('CREATE INDEX IF NOT EXISTS %I ON %I %s', left(idx.name, 57) || '_part', copy, idx.definition);
+    END LOOP;
+    EXECUTE format(
+        'CREATE TABLE IF NOT EXISTS %I PARTITION OF %I FOR VALUES FROM (MINVALUE) TO (%L)',
+        tbl || '_history', copy, current_start::text || ' 00:00:00+00'
+    );
+    FOR i IN 0..premake LOOP
+        p_from := (current_start + make_interval(months => i))::date;
+        EXECUTE format(
+            'CREATE TABLE IF NOT EXISTS %I PARTITION OF %I FOR VALUES FROM (%L) TO (%L)',
+            tbl || '_p' || to_char(p_from, 'YYYYMM'), copy,
+            p_from::text || ' 00:00:00+00', (p_from + make_interval(months => 1))::date::text || ' 00:00:00+00'
+        );
+    END LOOP;
+    EXECUTE format('CREATE TABLE IF NOT EXISTS %I PARTITION OF %I DEFAULT', tbl || '_default', copy);
+    IF NOT EXISTS (SELECT 1 FROM pg_trigger WHERE tgrelid = to_regclass(tbl) AND tgname = 'partition_copy') THEN
+        EXECUTE format(
+            'CREATE TRIGGER partition_copy AFTER INSERT OR UPDATE OR DELETE ON %I FOR EACH ROW EXECUTE FUNCTION partition_copy_row()',
+            tbl
+        );
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+SELECT partition_shadow('orders', 'created_date', 3);
+SELECT partition_shadow('order_items', 'created_at', 3);
+SELECT partition_shadow('inventory_transactions', 'created_at', 3);
+SELECT partition_shadow('outbox', 'created_at', 3);
+SELECT partition_shadow('audit_logs', 'created_at', 3);
+
+DROP FUNCTION partition_shadow(TEXT, TEXT, INT);
@@ -0,0 +1,20 @@
+-- phase: expand
+-- Backfill progress, with pause requests kept in the row so any instance can pause a
+-- backfill running on another.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS backfill_states (
+    name TEXT PRIMARY KEY,
+    status VARCHAR(16) NOT NULL,
+    cursor TEXT,
+    rows_processed BIGINT NOT NULL DEFAULT 0,
+    job_id UUID,
+    error TEXT,
+    pause_requested BOOLEAN NOT NULL DEFAULT false,
+    started_at TIMESTAMPTZ NOT NULL,
+    finished_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+ALTER TABLE backfill_states ADD COLUMN IF NOT EXISTS pause_requested BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,12 @@
+-- phase: expand
+-- Locations already alerted as low, shared by every instance so a restart or a second
+-- replica does not alert the same drop again.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS stock_alert_states (
+    sku TEXT NOT NULL,
+    warehouse INTEGER NOT NULL,
+    flagged_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (sku, warehouse)
+);
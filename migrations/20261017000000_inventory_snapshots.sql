@@ -0,0 +1,22 @@
+-- phase: expand
+-- Nightly inventory snapshots, one row per SKU, warehouse and day; the unique index is
+-- what re-running a day upserts on.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS inventory_snapshots (
+    id UUID PRIMARY KEY,
+    snapshot_date DATE NOT NULL,
+    taken_at TIMESTAMPTZ NOT NULL,
+    sku TEXT NOT NULL,
+    warehouse INTEGER NOT NULL,
+    on_hand BIGINT NOT NULL DEFAULT 0,
+    reserved BIGINT NOT NULL DEFAULT 0,
+    allocated BIGINT NOT NULL DEFAULT 0,
+    incoming BIGINT NOT NULL DEFAULT 0,
+    unit_cost NUMERIC
+);
+
+CREATE UNIQUE INDEX CONCURRENTLY IF NOT EXISTS idx_inventory_snapshots_day_sku_warehouse
+    ON inventory_snapshots (snapshot_date, sku, warehouse);
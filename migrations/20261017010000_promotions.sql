@@ -0,0 +1,18 @@
+-- phase: expand
+-- Promotions, optionally limited to customer segments.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS promotions (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    discount_percentage DOUBLE PRECISION NOT NULL,
+    start_date TIMESTAMP NOT NULL,
+    end_date TIMESTAMP NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    target_segments JSONB NOT NULL DEFAULT '[]',
+    created_at TIMESTAMP,
+    updated_at TIMESTAMP
+);
+
+ALTER TABLE promotions ADD COLUMN IF NOT EXISTS target_segments JSONB NOT NULL DEFAULT '[]';
@@ -0,0 +1,25 @@
+-- phase: expand
+-- Bundles and kits: sellable products made of other SKUs, ordered under their own
+-- product id.
+
+SET lock_timeout = '5s';
+
+CREATE TABLE IF NOT EXISTS product_bundles (
+    id UUID PRIMARY KEY,
+    sku VARCHAR(64) NOT NULL UNIQUE,
+    product_id UUID NOT NULL UNIQUE,
+    name VARCHAR(255) NOT NULL,
+    kind VARCHAR(16) NOT NULL,
+    active BOOLEAN NOT NULL DEFAULT true,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS product_bundle_components (
+    id UUID PRIMARY KEY,
+    bundle_id UUID NOT NULL REFERENCES product_bundles (id) ON UPDATE CASCADE ON DELETE CASCADE,
+    component_product_id UUID NOT NULL,
+    component_sku VARCHAR(64) NOT NULL,
+    quantity INTEGER NOT NULL CHECK (quantity >= 1),
+    UNIQUE (bundle_id, component_sku)
+);
@@ -0,0 +1,7 @@
+-- phase: expand
+-- Lot-less rows hold a SKU's level per warehouse, so batch level updates can upsert on
+-- (sku, warehouse). Fails while a SKU has several lot-less rows in one warehouse; merge
+-- those first.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+CREATE UNIQUE INDEX CONCURRENTLY IF NOT EXISTS inventory_items_level_key ON inventory_items (sku, warehouse) WHERE lot_number IS NULL;
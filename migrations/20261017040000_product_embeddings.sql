@@ -0,0 +1,22 @@
+-- phase: expand
+-- requires: vector
+-- Product embeddings for semantic search, searched with pgvector's cosine distance. The
+-- column is as wide as the default embedding provider's vectors (1536); a provider of
+-- another width needs a migration recreating the table, which the backfill then refills.
+-- Stays pending where pgvector is not available, as semantic search is optional, and
+-- applies on the first run after it is installed.
+-- CREATE INDEX CONCURRENTLY cannot run inside a transaction; run this migration without one.
+
+SET lock_timeout = '5s';
+
+CREATE EXTENSION IF NOT EXISTS vector;
+
+CREATE TABLE IF NOT EXISTS product_embeddings (
+    sku TEXT PRIMARY KEY,
+    embedding vector(1536) NOT NULL,
+    content_hash TEXT NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX CONCURRENTLY IF NOT EXISTS product_embeddings_hnsw
+    ON product_embeddings USING hnsw (embedding vector_cosine_ops);
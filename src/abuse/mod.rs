@@ -0,0 +1,872 @@
+// abuse/mod.rs
+
+use axum::{
+    extract::State,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use lazy_static::lazy_static;
+use prometheus::IntCounterVec;
+use rand::RngCore;
+use sea_orm::{ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, Set};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+use tokio::sync::RwLock;
+use tracing::{error, info, warn};
+
+use crate::models::abuse_block::{self, AbuseBlockKind, Entity as AbuseBlock};
+use crate::network_acl::{self, CidrList};
+
+type HmacSha256 = Hmac<Sha256>;
+
+lazy_static! {
+    static ref ABUSE_VERDICTS: IntCounterVec =
+        IntCounterVec::new(
+            "abuse_verdicts_total",
+            "Total number of requests to protected endpoints by abuse detection verdict",
+            &["verdict"]
+        ).expect("metric can be created");
+
+    // Only flagged requests are labelled by fingerprint, so the series grow with the
+    // number of suspects rather than the number of clients
+    static ref ABUSE_FLAGGED: IntCounterVec =
+        IntCounterVec::new(
+            "abuse_flagged_requests_total",
+            "Total number of challenged, tarpitted or blocked requests by fingerprint",
+            &["fingerprint", "verdict"]
+        ).expect("metric can be created");
+}
+
+/// Header a client resends a request with after solving a challenge, as
+/// `<challenge>:<nonce>`.
+pub const CHALLENGE_HEADER: &str = "X-Abuse-Challenge";
+
+/// How long an issued challenge can be answered.
+const CHALLENGE_TTL_SECS: i64 = 300;
+
+/// Scraper detection settings, loaded from the `abuse` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AbuseConfig {
+    /// Enables the middleware (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Path prefixes that are watched (default: product listings and the agentic feed).
+    #[serde(default = "default_protected_paths")]
+    pub protected_paths: Vec<String>,
+
+    /// Length of the window requests are counted in (default: 60 s).
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+
+    /// Weighted requests per window after which a fingerprint has to solve a challenge
+    /// (default: 120).
+    #[serde(default = "default_challenge_after")]
+    pub challenge_after: u32,
+
+    /// Weighted requests per window after which even fingerprints that solved a
+    /// challenge are slowed down (default: 600).
+    #[serde(default = "default_tarpit_after")]
+    pub tarpit_after: u32,
+
+    /// Delay added to tarpitted requests (default: 2000 ms).
+    #[serde(default = "default_tarpit_delay_ms")]
+    pub tarpit_delay_ms: u64,
+
+    /// Case-insensitive user-agent substrings whose requests count double, as do
+    /// requests without a user agent.
+    #[serde(default = "default_suspicious_user_agents")]
+    pub suspicious_user_agents: Vec<String>,
+
+    /// Header carrying the JA3 hash of the client's TLS handshake, e.g.
+    /// `X-JA3-Fingerprint`. Only set this when a fronting proxy or CDN computes it.
+    #[serde(default)]
+    pub ja3_header: Option<String>,
+
+    /// Leading zero bits a challenge solution's SHA-256 needs (default: 18).
+    #[serde(default = "default_challenge_difficulty")]
+    pub challenge_difficulty: u8,
+
+    /// How long a solved challenge exempts a fingerprint from new ones (default: 900 s).
+    #[serde(default = "default_challenge_pass_secs")]
+    pub challenge_pass_secs: u64,
+
+    /// Key challenges are signed with. Replicas behind one load balancer need the same
+    /// key; a random one is generated at startup when unset.
+    #[serde(default)]
+    pub challenge_secret: Option<String>,
+
+    /// Fingerprints, and separately addresses, API keys and JA3 hashes, tracked at once;
+    /// beyond this the least recently seen are evicted (default: 100000).
+    #[serde(default = "default_max_tracked")]
+    pub max_tracked: usize,
+
+    /// How often the blocklist is reloaded from the database, so blocks added on other
+    /// replicas take effect (default: 60 s).
+    #[serde(default = "default_refresh_interval_secs")]
+    pub refresh_interval_secs: u64,
+}
+
+fn default_protected_paths() -> Vec<String> {
+    vec!["/api/v1/products".to_string(), "/api/v1/agentic".to_string()]
+}
+
+fn default_window_secs() -> u64 {
+    60
+}
+
+fn default_challenge_after() -> u32 {
+    120
+}
+
+fn default_tarpit_after() -> u32 {
+    600
+}
+
+fn default_tarpit_delay_ms() -> u64 {
+    2000
+}
+
+fn default_suspicious_user_agents() -> Vec<String> {
+    ["curl", "wget", "python-requests", "python-urllib", "scrapy", "go-http-client", "headlesschrome", "phantomjs"]
+        .iter()
+        .map(|agent| agent.to_string())
+        .collect()
+}
+
+fn default_challenge_difficulty() -> u8 {
+    18
+}
+
+fn default_challenge_pass_secs() -> u64 {
+    900
+}
+
+fn default_max_tracked() -> usize {
+    100_000
+}
+
+fn default_refresh_interval_secs() -> u64 {
+    60
+}
+
+impl Default for AbuseConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            protected_paths: default_protected_paths(),
+            window_secs: default_window_secs(),
+            challenge_after: default_challenge_after(),
+            tarpit_after: default_tarpit_after(),
+            tarpit_delay_ms: default_tarpit_delay_ms(),
+            suspicious_user_agents: default_suspicious_user_agents(),
+            ja3_header: None,
+            challenge_difficulty: default_challenge_difficulty(),
+            challenge_pass_secs: default_challenge_pass_secs(),
+            challenge_secret: None,
+            max_tracked: default_max_tracked(),
+            refresh_interval_secs: default_refresh_interval_secs(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum AbuseError {
+    #[error("Invalid block: {0}")]
+    InvalidBlock(String),
+
+    #[error("Block not found: {0}")]
+    NotFound(i32),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for AbuseError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            AbuseError::InvalidBlock(_) => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_block"),
+            AbuseError::NotFound(_) => (StatusCode::NOT_FOUND, "block_not_found"),
+            AbuseError::Database(e) => {
+                error!("Abuse blocklist query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "abuse_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// What identifies a client across requests.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Fingerprint {
+    /// Hash of the other fields; the key used by metrics and the blocklist.
+    pub id: String,
+    pub ip: Option<IpAddr>,
+    pub user_agent: Option<String>,
+    pub api_key_prefix: Option<String>,
+    pub ja3: Option<String>,
+}
+
+impl Fingerprint {
+    pub fn new(
+        ip: Option<IpAddr>,
+        user_agent: Option<String>,
+        api_key_prefix: Option<String>,
+        ja3: Option<String>,
+    ) -> Self {
+        let mut hasher = Sha256::new();
+        for part in [ip.map(|ip| ip.to_string()), user_agent.clone(), api_key_prefix.clone(), ja3.clone()] {
+            hasher.update(part.as_deref().unwrap_or_default().as_bytes());
+            // Separator, so ("ab", "c") and ("a", "bc") hash differently
+            hasher.update([0u8]);
+        }
+        Self {
+            id: hex::encode(&hasher.finalize()[..8]),
+            ip,
+            user_agent,
+            api_key_prefix,
+            ja3,
+        }
+    }
+
+    /// Parts of the fingerprint that are also counted on their own, so a client can't
+    /// reset its score by varying the others, e.g. rotating user agents from one
+    /// address. User agents are shared by too many clients to be counted alone; they
+    /// count through the full fingerprint and the suspicious-agent weight.
+    fn dimensions(&self) -> impl Iterator<Item = String> + '_ {
+        [
+            self.ip.map(|ip| format!("ip:{}", ip)),
+            self.api_key_prefix.as_ref().map(|key| format!("key:{}", key)),
+            self.ja3.as_ref().map(|ja3| format!("ja3:{}", ja3.to_ascii_lowercase())),
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+/// What happens to a request for a protected path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Allow,
+    /// Served after the delay.
+    Tarpit(Duration),
+    /// Refused until the client resends it with a solved challenge.
+    Challenge,
+    Block,
+}
+
+impl Verdict {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Verdict::Allow => "allow",
+            Verdict::Tarpit(_) => "tarpit",
+            Verdict::Challenge => "challenge",
+            Verdict::Block => "block",
+        }
+    }
+}
+
+/// Activity of one fingerprint, as reported by the admin API.
+#[derive(Debug, Clone, Serialize)]
+pub struct FingerprintStats {
+    #[serde(flatten)]
+    pub fingerprint: Fingerprint,
+    /// Weighted requests in the current window.
+    pub score: u32,
+    pub requests: u64,
+    pub challenged: u64,
+    pub tarpitted: u64,
+    pub blocked: u64,
+    /// Solved a challenge that is still valid.
+    pub cleared: bool,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Weighted requests in the current window.
+struct Window {
+    started: Instant,
+    score: u32,
+    last_active: Instant,
+}
+
+impl Window {
+    fn new(now: Instant) -> Self {
+        Self { started: now, score: 0, last_active: now }
+    }
+
+    fn add(&mut self, weight: u32, now: Instant, length: Duration) -> u32 {
+        if now.duration_since(self.started) >= length {
+            self.started = now;
+            self.score = 0;
+        }
+        self.last_active = now;
+        self.score = self.score.saturating_add(weight);
+        self.score
+    }
+
+    fn current(&self, now: Instant, length: Duration) -> u32 {
+        if now.duration_since(self.started) < length { self.score } else { 0 }
+    }
+}
+
+struct Tracked {
+    fingerprint: Fingerprint,
+    window: Window,
+    requests: u64,
+    challenged: u64,
+    tarpitted: u64,
+    blocked: u64,
+    cleared_until: Option<Instant>,
+    last_seen: DateTime<Utc>,
+}
+
+impl Tracked {
+    fn new(fingerprint: Fingerprint, now: Instant) -> Self {
+        Self {
+            fingerprint,
+            window: Window::new(now),
+            requests: 0,
+            challenged: 0,
+            tarpitted: 0,
+            blocked: 0,
+            cleared_until: None,
+            last_seen: Utc::now(),
+        }
+    }
+
+    fn cleared(&self, now: Instant) -> bool {
+        self.cleared_until.is_some_and(|until| until > now)
+    }
+}
+
+#[derive(Default)]
+struct Tracker {
+    fingerprints: HashMap<String, Tracked>,
+    dimensions: HashMap<String, Window>,
+}
+
+/// Makes room for a new key by evicting the least recently active entries, a
+/// hundredth of the map at a time so a full map isn't scanned on every request.
+fn make_room<T>(map: &mut HashMap<String, T>, max: usize, last_active: impl Fn(&T) -> Instant) {
+    if map.len() < max.max(1) {
+        return;
+    }
+    let mut ages: Vec<(Instant, String)> = map.iter().map(|(key, value)| (last_active(value), key.clone())).collect();
+    let evict = (ages.len() / 100).max(ages.len() + 1 - max.max(1));
+    ages.select_nth_unstable(evict - 1);
+    for (_, key) in ages.into_iter().take(evict) {
+        map.remove(&key);
+    }
+}
+
+/// A blocklist row prepared for matching.
+struct BlockRule {
+    id: i32,
+    kind: AbuseBlockKind,
+    value: String,
+    networks: CidrList,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl BlockRule {
+    fn new(block: &abuse_block::Model) -> Self {
+        let networks = match block.kind {
+            AbuseBlockKind::Network => CidrList::parse(&[&block.value]),
+            _ => CidrList::default(),
+        };
+        Self {
+            id: block.id,
+            kind: block.kind,
+            value: block.value.clone(),
+            networks,
+            expires_at: block.expires_at,
+        }
+    }
+
+    fn matches(&self, fingerprint: &Fingerprint, now: DateTime<Utc>) -> bool {
+        if self.expires_at.is_some_and(|expires_at| expires_at <= now) {
+            return false;
+        }
+        match self.kind {
+            AbuseBlockKind::Fingerprint => fingerprint.id == self.value,
+            AbuseBlockKind::Network => fingerprint.ip.is_some_and(|ip| self.networks.contains(ip)),
+            AbuseBlockKind::Ja3 => fingerprint.ja3.as_deref().is_some_and(|ja3| ja3.eq_ignore_ascii_case(&self.value)),
+        }
+    }
+}
+
+/// A block to add through the admin API.
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewBlock {
+    pub kind: AbuseBlockKind,
+    pub value: String,
+    pub reason: Option<String>,
+    /// Lifts the block automatically after this many seconds.
+    pub expires_in_secs: Option<u64>,
+}
+
+/// Number of leading zero bits in a digest.
+pub fn leading_zero_bits(digest: &[u8]) -> u32 {
+    let mut bits = 0;
+    for byte in digest {
+        bits += byte.leading_zeros();
+        if *byte != 0 {
+            break;
+        }
+    }
+    bits
+}
+
+/// Scores fingerprints on protected paths and holds the blocklist.
+pub struct AbuseDetector {
+    db: Arc<DatabaseConnection>,
+    config: AbuseConfig,
+    trusted_proxies: CidrList,
+    secret: Vec<u8>,
+    tracked: Mutex<Tracker>,
+    blocks: RwLock<Vec<BlockRule>>,
+}
+
+impl AbuseDetector {
+    pub fn new(db: Arc<DatabaseConnection>, config: AbuseConfig, trusted_proxies: CidrList) -> Self {
+        let secret = match &config.challenge_secret {
+            Some(secret) => secret.as_bytes().to_vec(),
+            None => {
+                let mut secret = vec![0u8; 32];
+                rand::thread_rng().fill_bytes(&mut secret);
+                secret
+            }
+        };
+        Self {
+            db,
+            config,
+            trusted_proxies,
+            secret,
+            tracked: Mutex::new(Tracker::default()),
+            blocks: RwLock::new(Vec::new()),
+        }
+    }
+
+    /// Replaces the in-memory blocklist with the active rows of `abuse_blocks`.
+    pub async fn refresh_from_db(&self) -> Result<usize, DbErr> {
+        let rules: Vec<BlockRule> = AbuseBlock::find()
+            .filter(abuse_block::Column::IsActive.eq(true))
+            .all(self.db.as_ref())
+            .await?
+            .iter()
+            .map(BlockRule::new)
+            .collect();
+        let count = rules.len();
+        *self.blocks.write().await = rules;
+        info!(entries = count, "Abuse blocklist refreshed from database");
+        Ok(count)
+    }
+
+    pub fn protects(&self, path: &str) -> bool {
+        self.config.protected_paths.iter().any(|prefix| path.starts_with(prefix.as_str()))
+    }
+
+    pub fn fingerprint<B>(&self, req: &Request<B>) -> Fingerprint {
+        let header = |name: &str| {
+            req.headers()
+                .get(name)
+                .and_then(|h| h.to_str().ok())
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty())
+        };
+        Fingerprint::new(
+            network_acl::client_ip(req, &self.trusted_proxies),
+            header("User-Agent"),
+            network_acl::api_key_prefix(req).map(str::to_string),
+            self.config.ja3_header.as_deref().and_then(header),
+        )
+    }
+
+    fn suspicious(&self, user_agent: Option<&str>) -> bool {
+        match user_agent {
+            None => true,
+            Some(agent) => {
+                let agent = agent.to_ascii_lowercase();
+                self.config
+                    .suspicious_user_agents
+                    .iter()
+                    .any(|pattern| agent.contains(&pattern.to_ascii_lowercase()))
+            }
+        }
+    }
+
+    fn sign(&self, message: &str) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(message.as_bytes());
+        mac
+    }
+
+    /// A challenge bound to the fingerprint: `<fingerprint>.<expires>.<signature>`.
+    pub fn issue_challenge(&self, fingerprint: &Fingerprint) -> String {
+        let message = format!("{}.{}", fingerprint.id, Utc::now().timestamp() + CHALLENGE_TTL_SECS);
+        let signature = hex::encode(self.sign(&message).finalize().into_bytes());
+        format!("{}.{}", message, signature)
+    }
+
+    /// Checks a `<challenge>:<nonce>` answer: the challenge must have been issued to this
+    /// fingerprint and not expired, and the answer's SHA-256 must start with the
+    /// configured number of zero bits.
+    pub fn verify_solution(&self, fingerprint: &Fingerprint, answer: &str) -> bool {
+        let Some((challenge, _nonce)) = answer.rsplit_once(':') else { return false };
+        let mut parts = challenge.splitn(3, '.');
+        let (Some(id), Some(expires), Some(signature)) = (parts.next(), parts.next(), parts.next()) else {
+            return false;
+        };
+        if id != fingerprint.id || !expires.parse::<i64>().is_ok_and(|expires| expires >= Utc::now().timestamp()) {
+            return false;
+        }
+        let Ok(signature) = hex::decode(signature) else { return false };
+        if self.sign(&format!("{}.{}", id, expires)).verify_slice(&signature).is_err() {
+            return false;
+        }
+        leading_zero_bits(&Sha256::digest(answer.as_bytes())) >= u32::from(self.config.challenge_difficulty)
+    }
+
+    /// Decides what happens to a request for a protected path. `solved` is true when it
+    /// carries a valid challenge solution.
+    pub async fn assess(&self, fingerprint: &Fingerprint, solved: bool) -> Verdict {
+        let now = Utc::now();
+        let blocked = self.blocks.read().await.iter().any(|rule| rule.matches(fingerprint, now));
+        self.score(fingerprint, solved, blocked, Instant::now())
+    }
+
+    /// Scores the request against the fingerprint and each of its dimensions; the
+    /// highest score decides.
+    fn score(&self, fingerprint: &Fingerprint, solved: bool, blocked: bool, now: Instant) -> Verdict {
+        let window = Duration::from_secs(self.config.window_secs);
+        let mut tracker = self.tracked.lock().expect("abuse tracker lock poisoned");
+        let Tracker { fingerprints, dimensions } = &mut *tracker;
+        if !fingerprints.contains_key(&fingerprint.id) {
+            make_room(fingerprints, self.config.max_tracked, |t| t.window.last_active);
+        }
+
+        let entry = fingerprints
+            .entry(fingerprint.id.clone())
+            .or_insert_with(|| Tracked::new(fingerprint.clone(), now));
+        entry.requests += 1;
+        entry.last_seen = Utc::now();
+        if blocked {
+            entry.blocked += 1;
+            entry.window.last_active = now;
+            return Verdict::Block;
+        }
+        // Solving doesn't reset the score, so a solver is still tarpitted at high rates
+        if solved {
+            entry.cleared_until = Some(now + Duration::from_secs(self.config.challenge_pass_secs));
+        }
+        let weight = if self.suspicious(fingerprint.user_agent.as_deref()) { 2 } else { 1 };
+        let mut score = entry.window.add(weight, now, window);
+        for dimension in fingerprint.dimensions() {
+            if !dimensions.contains_key(&dimension) {
+                make_room(dimensions, self.config.max_tracked, |w| w.last_active);
+            }
+            let counter = dimensions.entry(dimension).or_insert_with(|| Window::new(now));
+            score = score.max(counter.add(weight, now, window));
+        }
+
+        if !entry.cleared(now) && score > self.config.challenge_after {
+            entry.challenged += 1;
+            Verdict::Challenge
+        } else if score > self.config.tarpit_after {
+            entry.tarpitted += 1;
+            Verdict::Tarpit(Duration::from_millis(self.config.tarpit_delay_ms))
+        } else {
+            Verdict::Allow
+        }
+    }
+
+    /// Tracked fingerprints with the highest current scores first.
+    pub fn top_fingerprints(&self, limit: usize) -> Vec<FingerprintStats> {
+        let now = Instant::now();
+        let window = Duration::from_secs(self.config.window_secs);
+        let tracker = self.tracked.lock().expect("abuse tracker lock poisoned");
+        let mut stats: Vec<FingerprintStats> = tracker
+            .fingerprints
+            .values()
+            .map(|t| FingerprintStats {
+                fingerprint: t.fingerprint.clone(),
+                score: t.window.current(now, window),
+                requests: t.requests,
+                challenged: t.challenged,
+                tarpitted: t.tarpitted,
+                blocked: t.blocked,
+                cleared: t.cleared(now),
+                last_seen: t.last_seen,
+            })
+            .collect();
+        stats.sort_by(|a, b| b.score.cmp(&a.score).then(b.requests.cmp(&a.requests)));
+        stats.truncate(limit);
+        stats
+    }
+
+    pub async fn list_blocks(&self) -> Result<Vec<abuse_block::Model>, AbuseError> {
+        Ok(AbuseBlock::find()
+            .filter(abuse_block::Column::IsActive.eq(true))
+            .order_by_desc(abuse_block::Column::CreatedAt)
+            .all(self.db.as_ref())
+            .await?)
+    }
+
+    pub async fn add_block(&self, block: NewBlock, actor: &str) -> Result<abuse_block::Model, AbuseError> {
+        let value = block.value.trim().to_string();
+        let valid = match block.kind {
+            AbuseBlockKind::Fingerprint => value.len() == 16 && value.chars().all(|c| c.is_ascii_hexdigit()),
+            AbuseBlockKind::Network => !CidrList::parse(&[&value]).is_empty(),
+            AbuseBlockKind::Ja3 => value.len() == 32 && value.chars().all(|c| c.is_ascii_hexdigit()),
+        };
+        if !valid {
+            return Err(AbuseError::InvalidBlock(format!("{:?} value {:?} is malformed", block.kind, value)));
+        }
+        let now = Utc::now();
+        let expires_at = match block.expires_in_secs {
+            Some(secs) => {
+                let secs = i64::try_from(secs).map_err(|_| AbuseError::InvalidBlock("expires_in_secs is too large".to_string()))?;
+                Some(now + chrono::Duration::seconds(secs))
+            }
+            None => None,
+        };
+        let saved = abuse_block::ActiveModel {
+            kind: Set(block.kind),
+            value: Set(value),
+            reason: Set(block.reason),
+            expires_at: Set(expires_at),
+            is_active: Set(true),
+            created_by: Set(Some(actor.to_string())),
+            created_at: Set(now),
+            ..Default::default()
+        }
+        .insert(self.db.as_ref())
+        .await?;
+        self.blocks.write().await.push(BlockRule::new(&saved));
+        info!(id = saved.id, kind = ?saved.kind, value = %saved.value, actor, "Abuse block added");
+        Ok(saved)
+    }
+
+    /// Lifts a block; the row is kept for history.
+    pub async fn remove_block(&self, id: i32, actor: &str) -> Result<abuse_block::Model, AbuseError> {
+        let block = AbuseBlock::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?
+            .filter(|block| block.is_active)
+            .ok_or(AbuseError::NotFound(id))?;
+        let mut active: abuse_block::ActiveModel = block.into();
+        active.is_active = Set(false);
+        let saved = active.update(self.db.as_ref()).await?;
+        self.blocks.write().await.retain(|rule| rule.id != id);
+        info!(id, actor, "Abuse block removed");
+        Ok(saved)
+    }
+}
+
+/// Reloads the blocklist from the database at `interval`, picking up blocks added or
+/// lifted through other replicas and dropping expired ones.
+pub fn spawn_refresh(detector: Arc<AbuseDetector>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The first tick completes immediately and startup has just loaded the list
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            if let Err(e) = detector.refresh_from_db().await {
+                warn!(error = %e, "Failed to refresh abuse blocklist; keeping the previous one");
+            }
+        }
+    });
+}
+
+/// Middleware fingerprinting requests for protected paths and tarpitting, challenging
+/// or refusing suspected scrapers. Layered inside the network ACL and outside of
+/// authentication, so scrapers are slowed down before any work is done for them.
+pub async fn abuse_detection_middleware<B>(
+    State(detector): State<Arc<AbuseDetector>>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if !detector.protects(req.uri().path()) {
+        return next.run(req).await;
+    }
+    let fingerprint = detector.fingerprint(&req);
+    let solved = req
+        .headers()
+        .get(CHALLENGE_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .is_some_and(|answer| detector.verify_solution(&fingerprint, answer));
+    let verdict = detector.assess(&fingerprint, solved).await;
+    ABUSE_VERDICTS.with_label_values(&[verdict.code()]).inc();
+    if verdict != Verdict::Allow {
+        ABUSE_FLAGGED.with_label_values(&[&fingerprint.id, verdict.code()]).inc();
+    }
+
+    match verdict {
+        Verdict::Allow => next.run(req).await,
+        Verdict::Tarpit(delay) => {
+            tokio::time::sleep(delay).await;
+            next.run(req).await
+        }
+        Verdict::Challenge => (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(json!({
+                "error": "Too Many Requests",
+                "code": "challenge_required",
+                "challenge": detector.issue_challenge(&fingerprint),
+                "difficulty": detector.config.challenge_difficulty,
+                "header": CHALLENGE_HEADER,
+                "details": "Find a nonce for which SHA-256 of `<challenge>:<nonce>` starts with `difficulty` zero bits and resend the request with that string in the header",
+            })),
+        )
+            .into_response(),
+        Verdict::Block => {
+            warn!(
+                fingerprint = %fingerprint.id,
+                ip = ?fingerprint.ip,
+                path = %req.uri().path(),
+                "Request blocked by abuse blocklist"
+            );
+            (
+                StatusCode::FORBIDDEN,
+                Json(json!({
+                    "error": "Forbidden",
+                    "code": "client_blocked",
+                    "details": "Requests from this client are not permitted",
+                })),
+            )
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detector(config: AbuseConfig) -> AbuseDetector {
+        AbuseDetector::new(
+            Arc::new(DatabaseConnection::Disconnected),
+            AbuseConfig { challenge_secret: Some("test-secret".to_string()), ..config },
+            CidrList::default(),
+        )
+    }
+
+    fn browser() -> Fingerprint {
+        Fingerprint::new(Some("203.0.113.7".parse().unwrap()), Some("Mozilla/5.0".to_string()), None, None)
+    }
+
+    #[test]
+    fn test_fingerprint_is_stable_and_distinguishes_clients() {
+        assert_eq!(browser().id, browser().id);
+        assert_eq!(browser().id.len(), 16);
+        let curl = Fingerprint::new(Some("203.0.113.7".parse().unwrap()), Some("curl/8.4".to_string()), None, None);
+        assert_ne!(browser().id, curl.id);
+    }
+
+    #[test]
+    fn test_thresholds_challenge_then_tarpit_after_solving() {
+        let detector = detector(AbuseConfig { challenge_after: 3, tarpit_after: 5, ..Default::default() });
+        let fingerprint = browser();
+        let now = Instant::now();
+        let verdicts: Vec<Verdict> = (0..4).map(|_| detector.score(&fingerprint, false, false, now)).collect();
+        assert_eq!(verdicts[2], Verdict::Allow);
+        assert_eq!(verdicts[3], Verdict::Challenge);
+
+        assert_eq!(detector.score(&fingerprint, true, false, now), Verdict::Allow);
+        assert!(matches!(detector.score(&fingerprint, false, false, now), Verdict::Tarpit(_)));
+
+        // A new window starts from zero
+        let later = now + Duration::from_secs(61);
+        assert_eq!(detector.score(&fingerprint, false, false, later), Verdict::Allow);
+    }
+
+    #[test]
+    fn test_suspicious_user_agents_count_double() {
+        let detector = detector(AbuseConfig { challenge_after: 3, ..Default::default() });
+        let scraper = Fingerprint::new(None, Some("python-requests/2.31".to_string()), None, None);
+        let now = Instant::now();
+        assert_eq!(detector.score(&scraper, false, false, now), Verdict::Allow);
+        assert_eq!(detector.score(&scraper, false, false, now), Verdict::Challenge);
+    }
+
+    #[test]
+    fn test_rotating_user_agents_share_the_address_score() {
+        let detector = detector(AbuseConfig { challenge_after: 3, ..Default::default() });
+        let now = Instant::now();
+        let verdicts: Vec<Verdict> = (0..4)
+            .map(|i| {
+                let agent = format!("Mozilla/5.0 ({})", i);
+                let fingerprint = Fingerprint::new(Some("203.0.113.7".parse().unwrap()), Some(agent), None, None);
+                detector.score(&fingerprint, false, false, now)
+            })
+            .collect();
+        assert_eq!(verdicts[2], Verdict::Allow);
+        assert_eq!(verdicts[3], Verdict::Challenge);
+    }
+
+    #[test]
+    fn test_full_tracker_evicts_least_recently_seen() {
+        let detector = detector(AbuseConfig { challenge_after: 1, max_tracked: 2, ..Default::default() });
+        let client = |ip: &str| Fingerprint::new(Some(ip.parse().unwrap()), Some("Mozilla/5.0".to_string()), None, None);
+        let now = Instant::now();
+        detector.score(&client("203.0.113.1"), false, false, now);
+        detector.score(&client("203.0.113.2"), false, false, now + Duration::from_secs(1));
+        // A new client is still scored once the tracker is full
+        detector.score(&client("203.0.113.3"), false, false, now + Duration::from_secs(2));
+        assert_eq!(detector.score(&client("203.0.113.3"), false, false, now + Duration::from_secs(2)), Verdict::Challenge);
+
+        let tracker = detector.tracked.lock().unwrap();
+        assert!(tracker.fingerprints.len() <= 2);
+        assert!(!tracker.dimensions.contains_key("ip:203.0.113.1"));
+    }
+
+    #[test]
+    fn test_challenge_solution() {
+        let detector = detector(AbuseConfig { challenge_difficulty: 8, ..Default::default() });
+        let fingerprint = browser();
+        let challenge = detector.issue_challenge(&fingerprint);
+        let answer = (0u64..)
+            .map(|nonce| format!("{}:{}", challenge, nonce))
+            .find(|answer| leading_zero_bits(&Sha256::digest(answer.as_bytes())) >= 8)
+            .unwrap();
+        assert!(detector.verify_solution(&fingerprint, &answer));
+
+        let other = Fingerprint::new(None, Some("Mozilla/5.0".to_string()), None, None);
+        assert!(!detector.verify_solution(&other, &answer));
+        let forged = answer.replacen(&fingerprint.id, &other.id, 1);
+        assert!(!detector.verify_solution(&other, &forged));
+    }
+
+    #[test]
+    fn test_block_rules() {
+        let block = |kind, value: &str, expires_at| abuse_block::Model {
+            id: 1,
+            kind,
+            value: value.to_string(),
+            reason: None,
+            expires_at,
+            is_active: true,
+            created_by: None,
+            created_at: Utc::now(),
+        };
+        let now = Utc::now();
+        let fingerprint = browser();
+        assert!(BlockRule::new(&block(AbuseBlockKind::Network, "203.0.113.0/24", None)).matches(&fingerprint, now));
+        assert!(BlockRule::new(&block(AbuseBlockKind::Fingerprint, &fingerprint.id, None)).matches(&fingerprint, now));
+        let expired = block(AbuseBlockKind::Network, "203.0.113.7", Some(now - chrono::Duration::minutes(1)));
+        assert!(!BlockRule::new(&expired).matches(&fingerprint, now));
+    }
+}
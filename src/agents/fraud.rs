@@ -0,0 +1,215 @@
+// agents/fraud.rs
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use sea_orm::{DatabaseConnection, FromQueryResult};
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use uuid::Uuid;
+
+use super::{Agent, AgentError, Decision};
+use crate::db::dialect;
+
+/// Order screening, loaded from the `order_fraud` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct OrderFraudConfig {
+    /// Registers the `fraud` agent, which screens orders placed since its last run.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Minutes of a customer's recent orders the velocity signal looks at.
+    #[serde(default = "default_window_minutes")]
+    pub window_minutes: i64,
+
+    /// More orders than this from one email within the window fires the velocity signal.
+    #[serde(default = "default_max_orders_in_window")]
+    pub max_orders_in_window: i64,
+
+    /// Orders worth at least this, in cents, are flagged.
+    #[serde(default = "default_review_amount_cents")]
+    pub review_amount_cents: i64,
+
+    /// Cash-on-delivery orders worth at least this, in cents, are flagged.
+    #[serde(default = "default_review_cod_amount_cents")]
+    pub review_cod_amount_cents: i64,
+}
+
+fn default_window_minutes() -> i64 {
+    60
+}
+
+fn default_max_orders_in_window() -> i64 {
+    5
+}
+
+fn default_review_amount_cents() -> i64 {
+    500_000
+}
+
+fn default_review_cod_amount_cents() -> i64 {
+    100_000
+}
+
+impl Default for OrderFraudConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_minutes: default_window_minutes(),
+            max_orders_in_window: default_max_orders_in_window(),
+            review_amount_cents: default_review_amount_cents(),
+            review_cod_amount_cents: default_review_cod_amount_cents(),
+        }
+    }
+}
+
+/// Orders placed in `($1, $2]`, with their value and the orders from the same email
+/// since `$3`.
+const ORDERS_SQL: &str = r#"
+SELECT o.id AS order_id,
+       o.order_number,
+       o.customer_email,
+       o.is_cod,
+       o.created_date,
+       COALESCE((SELECT SUM(li.sale_price * li.quantity) FROM order_line_items li WHERE li.order_id = o.id), 0)::BIGINT AS total_cents,
+       (SELECT COUNT(*) FROM orders p
+        WHERE p.customer_email = o.customer_email AND p.created_date > $3 AND p.created_date <= o.created_date)::BIGINT AS recent_orders
+FROM orders o
+WHERE o.created_date > $1 AND o.created_date <= $2
+ORDER BY o.created_date
+"#;
+
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct ScreenedOrder {
+    pub order_id: Uuid,
+    pub order_number: String,
+    pub customer_email: String,
+    pub is_cod: bool,
+    pub created_date: DateTime<Utc>,
+    pub total_cents: i64,
+    pub recent_orders: i64,
+}
+
+/// Why an order looks risky; empty when it does not.
+pub fn signals(order: &ScreenedOrder, config: &OrderFraudConfig) -> Vec<String> {
+    let mut signals = Vec::new();
+    if order.recent_orders > config.max_orders_in_window {
+        signals.push(format!(
+            "{} orders from {} within {} minutes",
+            order.recent_orders, order.customer_email, config.window_minutes
+        ));
+    }
+    if order.total_cents >= config.review_amount_cents {
+        signals.push(format!("order value {} cents at or above {}", order.total_cents, config.review_amount_cents));
+    }
+    if order.is_cod && order.total_cents >= config.review_cod_amount_cents {
+        signals.push(format!(
+            "cash-on-delivery order value {} cents at or above {}",
+            order.total_cents, config.review_cod_amount_cents
+        ));
+    }
+    signals
+}
+
+/// Screens new orders for fraud signals and flags risky ones for a person to review
+/// before they ship. Each order is screened once: the agent moves on past the orders
+/// of a run only once that run's decisions are recorded.
+pub struct FraudAgent {
+    db: Arc<DatabaseConnection>,
+    config: OrderFraudConfig,
+    /// Orders placed up to this time have been screened and recorded.
+    screened_until: Mutex<DateTime<Utc>>,
+    /// End of the last run's range, which becomes `screened_until` once it is recorded.
+    pending: Mutex<Option<DateTime<Utc>>>,
+}
+
+impl FraudAgent {
+    /// Screens orders placed from now on.
+    pub fn new(db: Arc<DatabaseConnection>, config: OrderFraudConfig) -> Self {
+        Self { db, config, screened_until: Mutex::new(Utc::now()), pending: Mutex::new(None) }
+    }
+}
+
+pub fn decision_for(order: &ScreenedOrder, signals: Vec<String>) -> Decision {
+    Decision {
+        subject: format!("order:{}", order.order_id),
+        action: "flagged_for_review".to_string(),
+        rationale: signals.join("; "),
+        details: json!({
+            "order_number": order.order_number,
+            "customer_email": order.customer_email,
+            "total_cents": order.total_cents,
+            "recent_orders": order.recent_orders,
+            "is_cod": order.is_cod,
+            "signals": signals,
+        }),
+    }
+}
+
+#[async_trait]
+impl Agent for FraudAgent {
+    fn name(&self) -> &'static str {
+        "fraud"
+    }
+
+    fn default_interval(&self) -> Duration {
+        Duration::from_secs(5 * 60)
+    }
+
+    async fn run(&self) -> Result<Vec<Decision>, AgentError> {
+        let from = *self.screened_until.lock().expect("screened_until lock poisoned");
+        let until = Utc::now();
+        let window_start = from - ChronoDuration::minutes(self.config.window_minutes);
+        let orders = ScreenedOrder::find_by_statement(dialect::statement(
+            self.db.as_ref(),
+            ORDERS_SQL,
+            [from.into(), until.into(), window_start.into()],
+        ))
+        .all(self.db.as_ref())
+        .await?;
+        *self.pending.lock().expect("pending lock poisoned") = Some(until);
+        Ok(orders
+            .iter()
+            .filter_map(|order| {
+                let signals = signals(order, &self.config);
+                (!signals.is_empty()).then(|| decision_for(order, signals))
+            })
+            .collect())
+    }
+
+    fn recorded(&self) {
+        if let Some(until) = self.pending.lock().expect("pending lock poisoned").take() {
+            *self.screened_until.lock().expect("screened_until lock poisoned") = until;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(total_cents: i64, recent_orders: i64, is_cod: bool) -> ScreenedOrder {
+        ScreenedOrder {
+            order_id: Uuid::new_v4(),
+            order_number: "SO-1001".to_string(),
+            customer_email: "ada@example.com".to_string(),
+            is_cod,
+            created_date: Utc::now(),
+            total_cents,
+            recent_orders,
+        }
+    }
+
+    #[test]
+    fn test_signals() {
+        let config = OrderFraudConfig::default();
+        assert!(signals(&order(10_000, 1, false), &config).is_empty());
+        assert_eq!(signals(&order(10_000, 6, false), &config).len(), 1);
+        assert_eq!(signals(&order(500_000, 1, false), &config).len(), 1);
+        assert_eq!(signals(&order(150_000, 1, true), &config).len(), 1);
+        assert!(signals(&order(150_000, 1, false), &config).is_empty());
+    }
+}
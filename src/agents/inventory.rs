@@ -0,0 +1,164 @@
+// agents/inventory.rs
+
+use async_trait::async_trait;
+use sea_orm::{ColumnTrait, DatabaseConnection, EntityTrait, FromQueryResult, QueryFilter, QuerySelect};
+use serde_json::json;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use super::{Agent, AgentError, Decision};
+use crate::models::inventory_items;
+
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct StockRow {
+    pub sku: String,
+    pub warehouse: i32,
+    pub available: i32,
+    pub reserved_quantity: Option<i32>,
+    pub reorder_point: Option<i32>,
+    pub economic_order_quantity: Option<i32>,
+}
+
+impl StockRow {
+    fn sellable(&self) -> i32 {
+        self.available - self.reserved_quantity.unwrap_or(0)
+    }
+
+    fn is_low(&self) -> bool {
+        self.reorder_point.is_some_and(|point| self.sellable() <= point)
+    }
+}
+
+/// Suggests replenishment when sellable stock of a SKU in a warehouse falls to its
+/// reorder point. A location is reported once when it drops low and again only after
+/// it has recovered in between.
+pub struct InventoryAgent {
+    db: Arc<DatabaseConnection>,
+    /// Locations (SKU, warehouse) flagged as low by the last recorded run.
+    flagged: Mutex<HashSet<(String, i32)>>,
+    /// Locations low in the last run, which become `flagged` once its decisions are recorded.
+    pending: Mutex<Option<HashSet<(String, i32)>>>,
+}
+
+impl InventoryAgent {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db, flagged: Mutex::new(HashSet::new()), pending: Mutex::new(None) }
+    }
+}
+
+/// Decisions for locations that are low now but were not flagged before, and the
+/// current low set.
+pub fn newly_low(flagged: &HashSet<(String, i32)>, rows: &[StockRow]) -> (Vec<Decision>, HashSet<(String, i32)>) {
+    let low: HashMap<(String, i32), &StockRow> = rows
+        .iter()
+        .filter(|row| row.is_low())
+        .map(|row| ((row.sku.clone(), row.warehouse), row))
+        .collect();
+    let decisions = low
+        .iter()
+        .filter(|(key, _)| !flagged.contains(*key))
+        .map(|((sku, warehouse), row)| Decision {
+            subject: format!("sku:{}@{}", sku, warehouse),
+            action: "reorder_suggested".to_string(),
+            rationale: format!(
+                "{} sellable units at or below the reorder point of {}",
+                row.sellable(),
+                row.reorder_point.unwrap_or(0)
+            ),
+            details: json!({
+                "sku": sku,
+                "warehouse": warehouse,
+                "sellable": row.sellable(),
+                "reorder_point": row.reorder_point,
+                "suggested_quantity": row.economic_order_quantity,
+            }),
+        })
+        .collect();
+    (decisions, low.into_keys().collect())
+}
+
+#[async_trait]
+impl Agent for InventoryAgent {
+    fn name(&self) -> &'static str {
+        "inventory"
+    }
+
+    fn default_interval(&self) -> Duration {
+        Duration::from_secs(15 * 60)
+    }
+
+    async fn run(&self) -> Result<Vec<Decision>, AgentError> {
+        let rows = inventory_items::Entity::find()
+            .select_only()
+            .column(inventory_items::Column::Sku)
+            .column(inventory_items::Column::Warehouse)
+            .column(inventory_items::Column::Available)
+            .column(inventory_items::Column::ReservedQuantity)
+            .column(inventory_items::Column::ReorderPoint)
+            .column(inventory_items::Column::EconomicOrderQuantity)
+            .filter(inventory_items::Column::ReorderPoint.is_not_null())
+            .into_model::<StockRow>()
+            .all(self.db.as_ref())
+            .await?;
+        let (mut decisions, low) = newly_low(&self.flagged.lock().expect("flagged lock poisoned"), &rows);
+        *self.pending.lock().expect("pending lock poisoned") = Some(low);
+        decisions.sort_by(|a, b| a.subject.cmp(&b.subject));
+        Ok(decisions)
+    }
+
+    fn recorded(&self) {
+        if let Some(low) = self.pending.lock().expect("pending lock poisoned").take() {
+            *self.flagged.lock().expect("flagged lock poisoned") = low;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(sku: &str, available: i32, reserved: i32, reorder_point: i32) -> StockRow {
+        StockRow {
+            sku: sku.to_string(),
+            warehouse: 1,
+            available,
+            reserved_quantity: Some(reserved),
+            reorder_point: Some(reorder_point),
+            economic_order_quantity: Some(100),
+        }
+    }
+
+    #[test]
+    fn test_reserved_units_count_against_reorder_point() {
+        assert!(row("A", 12, 4, 8).is_low());
+        assert!(!row("A", 12, 3, 8).is_low());
+    }
+
+    #[test]
+    fn test_location_is_flagged_once_until_it_recovers() {
+        let (first, flagged) = newly_low(&HashSet::new(), &[row("A", 5, 0, 10), row("B", 50, 0, 10)]);
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].subject, "sku:A@1");
+
+        let (again, flagged) = newly_low(&flagged, &[row("A", 4, 0, 10)]);
+        assert!(again.is_empty());
+        let (recovered, flagged) = newly_low(&flagged, &[row("A", 40, 0, 10)]);
+        assert!(recovered.is_empty());
+        assert_eq!(newly_low(&flagged, &[row("A", 3, 0, 10)]).0.len(), 1);
+    }
+
+    #[test]
+    fn test_flags_are_kept_only_once_recorded() {
+        let agent = InventoryAgent::new(Arc::new(DatabaseConnection::Disconnected));
+        let (_, low) = newly_low(&HashSet::new(), &[row("A", 5, 0, 10)]);
+        *agent.pending.lock().unwrap() = Some(low);
+        assert!(agent.flagged.lock().unwrap().is_empty());
+
+        agent.recorded();
+        assert!(agent.flagged.lock().unwrap().contains(&("A".to_string(), 1)));
+        assert!(agent.pending.lock().unwrap().is_none());
+    }
+}
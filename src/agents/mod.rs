@@ -0,0 +1,421 @@
+// agents/mod.rs
+
+use async_trait::async_trait;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use prometheus::IntCounterVec;
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use thiserror::Error;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::models::agent_decision::{self, Entity as AgentDecision};
+use crate::shutdown::Drain;
+use crate::utils::pagination::PaginationParams;
+
+pub mod fraud;
+pub mod inventory;
+pub mod return_fraud;
+pub mod returns;
+
+lazy_static! {
+    static ref AGENT_RUNS: IntCounterVec =
+        IntCounterVec::new("agent_runs_total", "Background agent runs", &["agent", "outcome"])
+            .expect("metric can be created");
+    static ref AGENT_DECISIONS: IntCounterVec =
+        IntCounterVec::new("agent_decisions_total", "Decisions recorded by background agents", &["agent"])
+            .expect("metric can be created");
+}
+
+/// Consecutive failed runs after which an agent reports as failing rather than degraded.
+const FAILING_AFTER: u32 = 3;
+
+/// Agent settings, loaded from the `agents` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AgentsConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Per-agent run interval in seconds, overriding the agent's default.
+    #[serde(default)]
+    pub intervals: HashMap<String, u64>,
+
+    /// Agents that start paused; resume them with `POST /api/v1/agents/:name/resume`.
+    #[serde(default)]
+    pub paused: Vec<String>,
+
+    /// Agents that are not registered at all.
+    #[serde(default)]
+    pub disabled: Vec<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for AgentsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            intervals: HashMap::new(),
+            paused: Vec::new(),
+            disabled: Vec::new(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum AgentError {
+    #[error("Unknown agent: {0}")]
+    UnknownAgent(String),
+
+    #[error("Agent run failed: {0}")]
+    Run(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for AgentError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            AgentError::UnknownAgent(_) => (StatusCode::NOT_FOUND, "agent_not_found"),
+            AgentError::Run(_) => (StatusCode::INTERNAL_SERVER_ERROR, "agent_run_failed"),
+            AgentError::Database(e) => {
+                error!("Agent query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "agent_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// Something an agent decided, recorded in `agent_decisions` for audit.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Decision {
+    pub subject: String,
+    pub action: String,
+    pub rationale: String,
+    pub details: Value,
+}
+
+/// A background agent: inspects state on a schedule and decides what to do about it.
+/// Agents act through the regular services; the registry records their decisions.
+#[async_trait]
+pub trait Agent: Send + Sync {
+    /// Stable name used in the config, the admin endpoints and the audit trail.
+    fn name(&self) -> &'static str;
+
+    fn default_interval(&self) -> Duration;
+
+    async fn run(&self) -> Result<Vec<Decision>, AgentError>;
+
+    /// Called once the decisions of the last run are recorded. Agents that remember what
+    /// they already decided commit that memory here, so a run whose decisions could not
+    /// be recorded decides again.
+    fn recorded(&self) {}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    /// Registered but has not run yet.
+    Idle,
+    Healthy,
+    /// The last run failed.
+    Degraded,
+    /// Several runs in a row failed.
+    Failing,
+    Paused,
+}
+
+#[derive(Debug, Clone, Default)]
+struct AgentState {
+    paused: bool,
+    runs: u64,
+    decisions: u64,
+    consecutive_failures: u32,
+    last_run_at: Option<DateTime<Utc>>,
+    last_success_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+}
+
+impl AgentState {
+    fn status(&self) -> HealthStatus {
+        if self.paused {
+            HealthStatus::Paused
+        } else if self.consecutive_failures >= FAILING_AFTER {
+            HealthStatus::Failing
+        } else if self.consecutive_failures > 0 {
+            HealthStatus::Degraded
+        } else if self.last_run_at.is_none() {
+            HealthStatus::Idle
+        } else {
+            HealthStatus::Healthy
+        }
+    }
+}
+
+/// Health of one agent as reported by `GET /api/v1/agents`.
+#[derive(Debug, Clone, Serialize)]
+pub struct AgentHealth {
+    pub name: String,
+    pub status: HealthStatus,
+    pub interval_secs: u64,
+    pub runs: u64,
+    pub decisions: u64,
+    pub consecutive_failures: u32,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+struct Registered {
+    agent: Arc<dyn Agent>,
+    interval: Duration,
+    state: Mutex<AgentState>,
+}
+
+impl Registered {
+    fn health(&self) -> AgentHealth {
+        let state = self.state.lock().expect("agent state lock poisoned");
+        AgentHealth {
+            name: self.agent.name().to_string(),
+            status: state.status(),
+            interval_secs: self.interval.as_secs(),
+            runs: state.runs,
+            decisions: state.decisions,
+            consecutive_failures: state.consecutive_failures,
+            last_run_at: state.last_run_at,
+            last_success_at: state.last_success_at,
+            last_error: state.last_error.clone(),
+        }
+    }
+}
+
+/// Holds the registered agents, runs each on its own interval, and records their
+/// decisions and health.
+pub struct AgentRegistry {
+    db: Arc<DatabaseConnection>,
+    config: AgentsConfig,
+    agents: Vec<Arc<Registered>>,
+}
+
+impl AgentRegistry {
+    pub fn new(db: Arc<DatabaseConnection>, config: AgentsConfig) -> Self {
+        Self { db, config, agents: Vec::new() }
+    }
+
+    /// Adds an agent unless it is disabled in the config. Call before [`start`](Self::start).
+    pub fn register(&mut self, agent: Arc<dyn Agent>) -> &mut Self {
+        let name = agent.name();
+        if self.config.disabled.iter().any(|disabled| disabled == name) {
+            info!(agent = name, "Agent disabled by configuration");
+            return self;
+        }
+        let interval = self
+            .config
+            .intervals
+            .get(name)
+            .map(|secs| Duration::from_secs(*secs))
+            .unwrap_or_else(|| agent.default_interval());
+        let state = AgentState {
+            paused: self.config.paused.iter().any(|paused| paused == name),
+            ..AgentState::default()
+        };
+        self.agents.push(Arc::new(Registered {
+            agent,
+            interval,
+            state: Mutex::new(state),
+        }));
+        self
+    }
+
+    /// Spawns one loop per agent. Paused agents keep their schedule but skip their runs.
+    /// On shutdown each agent finishes and records the run in progress, then stops.
+    pub fn start(&self, drain: &Arc<Drain>) {
+        for registered in &self.agents {
+            let registered = registered.clone();
+            let db = self.db.clone();
+            let mut worker = drain.worker();
+            tokio::spawn(async move {
+                let mut ticker = tokio::time::interval(registered.interval.max(Duration::from_secs(1)));
+                ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                loop {
+                    tokio::select! {
+                        _ = ticker.tick() => {}
+                        _ = worker.stopping() => break,
+                    }
+                    if registered.state.lock().expect("agent state lock poisoned").paused {
+                        continue;
+                    }
+                    run_once(&db, &registered).await;
+                }
+            });
+            info!(agent = registered.agent.name(), interval_secs = registered.interval.as_secs(), "Agent started");
+        }
+    }
+
+    pub fn health(&self) -> Vec<AgentHealth> {
+        self.agents.iter().map(|registered| registered.health()).collect()
+    }
+
+    fn find(&self, name: &str) -> Result<&Arc<Registered>, AgentError> {
+        self.agents
+            .iter()
+            .find(|registered| registered.agent.name() == name)
+            .ok_or_else(|| AgentError::UnknownAgent(name.to_string()))
+    }
+
+    pub fn set_paused(&self, name: &str, paused: bool, actor: &str) -> Result<AgentHealth, AgentError> {
+        let registered = self.find(name)?;
+        registered.state.lock().expect("agent state lock poisoned").paused = paused;
+        info!(agent = name, paused, %actor, "Agent pause state changed");
+        Ok(registered.health())
+    }
+
+    /// Recorded decisions, newest first, optionally for one agent or subject.
+    pub async fn decisions(
+        &self,
+        agent: Option<String>,
+        subject: Option<String>,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<agent_decision::Model>, u64), AgentError> {
+        let mut query = AgentDecision::find().order_by_desc(agent_decision::Column::CreatedAt);
+        if let Some(agent) = agent {
+            query = query.filter(agent_decision::Column::Agent.eq(agent));
+        }
+        if let Some(subject) = subject {
+            query = query.filter(agent_decision::Column::Subject.eq(subject));
+        }
+        let paginator = query.paginate(self.db.as_ref(), pagination.limit());
+        let total = paginator.num_items().await?;
+        let decisions = paginator.fetch_page(pagination.page_index()).await?;
+        Ok((decisions, total))
+    }
+}
+
+async fn run_once(db: &DatabaseConnection, registered: &Registered) {
+    let name = registered.agent.name();
+    let started = Utc::now();
+    let outcome = match registered.agent.run().await {
+        Ok(decisions) => record_decisions(db, name, &decisions).await.map(|_| decisions.len()),
+        Err(e) => Err(e),
+    };
+    if outcome.is_ok() {
+        registered.agent.recorded();
+    }
+
+    let mut state = registered.state.lock().expect("agent state lock poisoned");
+    state.runs += 1;
+    state.last_run_at = Some(started);
+    match outcome {
+        Ok(count) => {
+            AGENT_RUNS.with_label_values(&[name, "success"]).inc();
+            AGENT_DECISIONS.with_label_values(&[name]).inc_by(count as u64);
+            state.decisions += count as u64;
+            state.consecutive_failures = 0;
+            state.last_success_at = Some(started);
+            state.last_error = None;
+        }
+        Err(e) => {
+            AGENT_RUNS.with_label_values(&[name, "failure"]).inc();
+            warn!(agent = name, "Agent run failed: {}", e);
+            state.consecutive_failures += 1;
+            state.last_error = Some(e.to_string());
+        }
+    }
+}
+
+async fn record_decisions(db: &DatabaseConnection, agent: &str, decisions: &[Decision]) -> Result<(), AgentError> {
+    if decisions.is_empty() {
+        return Ok(());
+    }
+    let run_id = Uuid::new_v4();
+    let now = Utc::now();
+    let rows = decisions.iter().map(|decision| agent_decision::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        agent: Set(agent.to_string()),
+        run_id: Set(run_id),
+        subject: Set(decision.subject.clone()),
+        action: Set(decision.action.clone()),
+        rationale: Set(decision.rationale.clone()),
+        details: Set(decision.details.clone()),
+        created_at: Set(now),
+    });
+    AgentDecision::insert_many(rows).exec(db).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_status() {
+        let mut state = AgentState::default();
+        assert_eq!(state.status(), HealthStatus::Idle);
+        state.last_run_at = Some(Utc::now());
+        assert_eq!(state.status(), HealthStatus::Healthy);
+        state.consecutive_failures = 1;
+        assert_eq!(state.status(), HealthStatus::Degraded);
+        state.consecutive_failures = FAILING_AFTER;
+        assert_eq!(state.status(), HealthStatus::Failing);
+        state.paused = true;
+        assert_eq!(state.status(), HealthStatus::Paused);
+    }
+
+    struct NoopAgent;
+
+    #[async_trait]
+    impl Agent for NoopAgent {
+        fn name(&self) -> &'static str {
+            "noop"
+        }
+
+        fn default_interval(&self) -> Duration {
+            Duration::from_secs(60)
+        }
+
+        async fn run(&self) -> Result<Vec<Decision>, AgentError> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[test]
+    fn test_register_applies_config() {
+        let db = Arc::new(DatabaseConnection::Disconnected);
+        let config = AgentsConfig {
+            intervals: HashMap::from([("noop".to_string(), 5)]),
+            paused: vec!["noop".to_string()],
+            ..AgentsConfig::default()
+        };
+        let mut registry = AgentRegistry::new(db.clone(), config);
+        registry.register(Arc::new(NoopAgent));
+        let health = registry.health();
+        assert_eq!(health[0].interval_secs, 5);
+        assert_eq!(health[0].status, HealthStatus::Paused);
+
+        assert_eq!(registry.set_paused("noop", false, "user:1").unwrap().status, HealthStatus::Idle);
+        assert!(matches!(registry.set_paused("missing", true, "user:1"), Err(AgentError::UnknownAgent(_))));
+
+        let disabled = AgentsConfig { disabled: vec!["noop".to_string()], ..AgentsConfig::default() };
+        let mut registry = AgentRegistry::new(db, disabled);
+        registry.register(Arc::new(NoopAgent));
+        assert!(registry.health().is_empty());
+    }
+}
@@ -0,0 +1,65 @@
+// agents/return_fraud.rs
+
+use async_trait::async_trait;
+use serde_json::json;
+use std::{sync::Arc, time::Duration};
+
+use super::{Agent, AgentError, Decision};
+use crate::models::return_entity;
+use crate::services::return_fraud::{ReturnFraudService, RiskAssessment};
+
+/// Returns scored per run; the rest wait for the next run.
+const BATCH_SIZE: u64 = 100;
+
+/// Scores new returns for fraud, and again once their serial number is known. Risky
+/// returns are flagged for inspection; every score is recorded as a decision.
+pub struct ReturnFraudAgent {
+    fraud: Arc<ReturnFraudService>,
+}
+
+impl ReturnFraudAgent {
+    pub fn new(fraud: Arc<ReturnFraudService>) -> Self {
+        Self { fraud }
+    }
+}
+
+pub fn decision_for(ret: &return_entity::Model, assessment: &RiskAssessment, flagged: bool) -> Decision {
+    let rationale = if assessment.signals.is_empty() {
+        "no fraud signals".to_string()
+    } else {
+        assessment.signals.iter().map(|s| s.detail.as_str()).collect::<Vec<_>>().join("; ")
+    };
+    Decision {
+        subject: format!("return:{}", ret.id),
+        action: if flagged { "flagged_for_inspection" } else { "risk_scored" }.to_string(),
+        rationale,
+        details: json!({
+            "rma": ret.rma,
+            "order_id": ret.order_id,
+            "score": assessment.score,
+            "signals": assessment.signals,
+        }),
+    }
+}
+
+#[async_trait]
+impl Agent for ReturnFraudAgent {
+    fn name(&self) -> &'static str {
+        "return_fraud"
+    }
+
+    fn default_interval(&self) -> Duration {
+        Duration::from_secs(5 * 60)
+    }
+
+    async fn run(&self) -> Result<Vec<Decision>, AgentError> {
+        let run_error = |e: crate::errors::ServiceError| AgentError::Run(e.to_string());
+        let mut decisions = Vec::new();
+        for ret in self.fraud.pending(BATCH_SIZE).await.map_err(run_error)? {
+            let assessment = self.fraud.evaluate(&ret).await.map_err(run_error)?;
+            let flagged = self.fraud.apply(&ret, &assessment).await.map_err(run_error)?;
+            decisions.push(decision_for(&ret, &assessment, flagged));
+        }
+        Ok(decisions)
+    }
+}
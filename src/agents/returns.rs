@@ -0,0 +1,71 @@
+// agents/returns.rs
+
+use async_trait::async_trait;
+use serde_json::json;
+use std::{sync::Arc, time::Duration};
+
+use super::{Agent, AgentError, Decision};
+use crate::models::return_entity;
+use crate::services::return_triage::{ReturnTriage, TriageDecision, TriageOutcome};
+
+/// Returns triaged per run; the rest wait for the next run.
+const BATCH_SIZE: u64 = 100;
+
+/// Triages newly requested returns: low-risk ones are approved, the rest are flagged
+/// for inspection. Every outcome is recorded as a decision, including the reasons.
+pub struct ReturnTriageAgent {
+    triage: Arc<ReturnTriage>,
+}
+
+impl ReturnTriageAgent {
+    pub fn new(triage: Arc<ReturnTriage>) -> Self {
+        Self { triage }
+    }
+}
+
+pub fn decision_for(ret: &return_entity::Model, outcome: &TriageOutcome) -> Decision {
+    Decision {
+        subject: format!("return:{}", ret.id),
+        action: match outcome.decision {
+            TriageDecision::AutoApprove => "auto_approved",
+            TriageDecision::Review => "routed_to_review",
+        }
+        .to_string(),
+        rationale: outcome.reasons.join("; "),
+        details: json!({
+            "rma": ret.rma,
+            "order_id": ret.order_id,
+            "amount": ret.amount,
+            "reason_category": ret.reason_category,
+            "reasons": outcome.reasons,
+        }),
+    }
+}
+
+#[async_trait]
+impl Agent for ReturnTriageAgent {
+    fn name(&self) -> &'static str {
+        "returns"
+    }
+
+    fn default_interval(&self) -> Duration {
+        Duration::from_secs(5 * 60)
+    }
+
+    async fn run(&self) -> Result<Vec<Decision>, AgentError> {
+        let mut decisions = Vec::new();
+        for ret in self.triage.pending(BATCH_SIZE).await? {
+            let outcome = self.triage.evaluate(&ret).await?;
+            // Skipped when someone acted on the return while it was being evaluated
+            let applied = self
+                .triage
+                .apply(&ret, &outcome)
+                .await
+                .map_err(|e| AgentError::Run(e.to_string()))?;
+            if applied {
+                decisions.push(decision_for(&ret, &outcome));
+            }
+        }
+        Ok(decisions)
+    }
+}
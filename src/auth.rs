@@ -10,11 +10,30 @@ use serde::{Deserialize, Serialize};
 use std::{
     collections::HashSet,
     sync::Arc,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use thiserror::Error;
 use tracing::{error, info, instrument};
 
+use crate::config::secrets::{SecretStore, JWT_SECRET};
+
+pub mod service_accounts;
+
+/// Role of tokens issued to shoppers, whose `sub` is their customer id.
+pub const CUSTOMER_ROLE: &str = "customer";
+
+/// Path prefixes customer tokens may call; every other route is for staff and integrations.
+const CUSTOMER_PATHS: &[&str] = &["/api/v1/assist"];
+
+/// Kind of principal behind a token, recorded in audit logs.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ActorType {
+    #[default]
+    Human,
+    ServiceAccount,
+}
+
 /// Claims structure for JWT
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
@@ -24,6 +43,56 @@ pub struct Claims {
     pub aud: String,             // Audience
     pub role: String,            // User role
     pub permissions: Option<Vec<String>>, // Optional permissions
+    #[serde(default)]
+    pub actor_type: ActorType,           // Human user or service account
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant_id: Option<String>,       // Tenant whose data key encrypts the caller's writes
+}
+
+impl Claims {
+    /// Actor identifier for audit logs, e.g. `user:42` or `service_account:<uuid>`.
+    pub fn actor(&self) -> String {
+        match self.actor_type {
+            ActorType::Human => format!("user:{}", self.sub),
+            ActorType::ServiceAccount => format!("service_account:{}", self.sub),
+        }
+    }
+
+    /// Returns true if the token carries the given permission scope.
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions
+            .as_ref()
+            .map(|p| p.iter().any(|granted| granted == permission))
+            .unwrap_or(false)
+    }
+
+    /// Whether the caller may act under `permission`: admins may do anything.
+    pub fn is_allowed(&self, permission: &str) -> bool {
+        self.role == "admin" || self.has_permission(permission)
+    }
+}
+
+/// A 403 response unless the caller is an admin or holds `permission`. Handlers return it
+/// before doing any work: `if let Some(response) = forbidden(&claims, "x:write") { .. }`.
+pub fn forbidden(claims: &Claims, permission: &str) -> Option<Response> {
+    (!claims.is_allowed(permission)).then(|| {
+        (
+            StatusCode::FORBIDDEN,
+            axum::Json(serde_json::json!({ "error": format!("Missing permission: {}", permission), "code": "forbidden" })),
+        )
+            .into_response()
+    })
+}
+
+/// A 403 response unless the caller is an admin, for operator-only endpoints.
+pub fn admin_only(claims: &Claims) -> Option<Response> {
+    (claims.role != "admin").then(|| {
+        (
+            StatusCode::FORBIDDEN,
+            axum::Json(serde_json::json!({ "error": "Admin role required", "code": "forbidden" })),
+        )
+            .into_response()
+    })
 }
 
 /// Custom error type for authentication errors
@@ -41,6 +110,10 @@ pub enum AuthError {
     MissingAuthHeader,
     #[error("Invalid issuer or audience")]
     InvalidIssuerAudience,
+    #[error("Authentication backend error: {0}")]
+    Internal(String),
+    #[error("{0}")]
+    BadRequest(String),
 }
 
 /// Implement IntoResponse to convert AuthError into HTTP responses
@@ -53,6 +126,8 @@ impl IntoResponse for AuthError {
             AuthError::JWTError(_) => (StatusCode::UNAUTHORIZED, "Invalid token"),
             AuthError::MissingAuthHeader => (StatusCode::UNAUTHORIZED, "Missing authorization header"),
             AuthError::InvalidIssuerAudience => (StatusCode::UNAUTHORIZED, "Invalid issuer or audience"),
+            AuthError::Internal(_) => (StatusCode::INTERNAL_SERVER_ERROR, "Authentication unavailable"),
+            AuthError::BadRequest(message) => (StatusCode::BAD_REQUEST, message.as_str()),
         };
 
         let body = serde_json::json!({
@@ -71,6 +146,42 @@ pub struct AuthConfig {
     pub audience: String,              // Expected audience
     pub allowed_roles: HashSet<String>, // Set of allowed roles
     pub token_expiration: usize,       // Token expiration in seconds
+    /// A `jwt_secret` here replaces `secret` from the moment it is rotated in.
+    pub secrets: SecretStore,
+}
+
+impl AuthConfig {
+    /// Builds the JWT authentication settings from the application config
+    pub fn from_app_config(config: &crate::config::AppConfig) -> Self {
+        Self {
+            secret: config.jwt_secret.clone(),
+            issuer: "stateset-api".to_string(),
+            audience: "stateset-api".to_string(),
+            allowed_roles: ["user", "admin", CUSTOMER_ROLE, service_accounts::SERVICE_ACCOUNT_ROLE]
+                .iter()
+                .map(|r| r.to_string())
+                .collect(),
+            token_expiration: config.jwt_expiration,
+            secrets: SecretStore::default(),
+        }
+    }
+
+    pub fn with_secrets(mut self, secrets: SecretStore) -> Self {
+        self.secrets = secrets;
+        self
+    }
+
+    /// The key tokens are signed and validated with, read on every use.
+    pub fn signing_secret(&self) -> String {
+        self.secrets.get_or(JWT_SECRET, &self.secret)
+    }
+
+    /// The key in use before `jwt_secret` was last rotated, accepted for `token_expiration`
+    /// afterwards so tokens signed with it stay valid until they expire.
+    pub fn previous_signing_secret(&self) -> Option<String> {
+        self.secrets
+            .previous_or(JWT_SECRET, &self.secret, Duration::from_secs(self.token_expiration as u64))
+    }
 }
 
 /// Service state containing the authentication configuration
@@ -86,6 +197,12 @@ pub async fn auth_middleware<B>(
     mut req: Request<B>,
     next: Next<B>,
 ) -> Result<Response, AuthError> {
+    // Requests already authenticated upstream (e.g. by service account API key) pass through
+    if let Some(claims) = req.extensions().get::<Claims>() {
+        service_accounts::check_scope(claims, req.method(), req.uri().path())?;
+        return Ok(next.run(req).await);
+    }
+
     // Extract the Authorization header
     let bearer_token = req
         .headers()
@@ -96,9 +213,11 @@ pub async fn auth_middleware<B>(
 
     // Validate the token
     let claims = validate_token(bearer_token, &state.auth_config)?;
+    service_accounts::check_scope(&claims, req.method(), req.uri().path())?;
+    check_customer_path(&claims, req.uri().path())?;
     info!(
-        "Authenticated user {} with role {}",
-        claims.sub, claims.role
+        "Authenticated {} with role {}",
+        claims.actor(), claims.role
     );
 
     // Insert the claims into request extensions for later use
@@ -108,11 +227,21 @@ pub async fn auth_middleware<B>(
     Ok(next.run(req).await)
 }
 
-/// Generates a JWT token
+/// Keeps customer tokens to the routes built for customers.
+pub fn check_customer_path(claims: &Claims, path: &str) -> Result<(), AuthError> {
+    if claims.role == CUSTOMER_ROLE && !CUSTOMER_PATHS.iter().any(|prefix| path.starts_with(prefix)) {
+        return Err(AuthError::InsufficientPermissions);
+    }
+    Ok(())
+}
+
+/// Generates a JWT token. `tenant_id` is the tenant the user belongs to, whose data key
+/// encrypts what they write.
 pub fn generate_token(
     user_id: &str,
     role: &str,
     permissions: Option<Vec<String>>,
+    tenant_id: Option<&str>,
     config: &AuthConfig,
 ) -> Result<String, AuthError> {
     let expiration = SystemTime::now()
@@ -128,27 +257,36 @@ pub fn generate_token(
         aud: config.audience.clone(),
         role: role.to_owned(),
         permissions,
+        actor_type: ActorType::Human,
+        tenant_id: tenant_id.map(str::to_owned),
     };
 
     let header = Header::new(Algorithm::HS256);
     encode(
         &header,
         &claims,
-        &EncodingKey::from_secret(config.secret.as_bytes()),
+        &EncodingKey::from_secret(config.signing_secret().as_bytes()),
     )
     .map_err(AuthError::JWTError)
 }
 
 /// Validates a JWT token
 pub fn validate_token(token: &str, config: &AuthConfig) -> Result<Claims, AuthError> {
-    let decoding_key = DecodingKey::from_secret(config.secret.as_bytes());
-
     let mut validation = Validation::new(Algorithm::HS256);
     validation.set_audience(&[config.audience.clone()]);
     validation.set_issuer(&[config.issuer.clone()]);
     validation.validate_exp = true;
 
-    let token_data = decode::<Claims>(token, &decoding_key, &validation)
+    let decode_with = |secret: String| decode::<Claims>(token, &DecodingKey::from_secret(secret.as_bytes()), &validation);
+    let mut decoded = decode_with(config.signing_secret());
+    // Tokens issued before a key rotation are accepted until they would have expired
+    if matches!(&decoded, Err(e) if matches!(e.kind(), jsonwebtoken::errors::ErrorKind::InvalidSignature)) {
+        if let Some(previous) = config.previous_signing_secret() {
+            decoded = decode_with(previous);
+        }
+    }
+
+    let token_data = decoded
         .map_err(|e| match e.kind() {
             jsonwebtoken::errors::ErrorKind::ExpiredSignature => AuthError::ExpiredToken,
             jsonwebtoken::errors::ErrorKind::InvalidIssuer => AuthError::InvalidIssuerAudience,
@@ -156,7 +294,7 @@ pub fn validate_token(token: &str, config: &AuthConfig) -> Result<Claims, AuthEr
             _ => AuthError::InvalidToken,
         })?;
 
-    // Check if the user's role is allowed
+    // Check if the role is allowed; this applies to service accounts as well as users
     if !config.allowed_roles.contains(&token_data.claims.role) {
         return Err(AuthError::InsufficientPermissions);
     }
@@ -219,6 +357,7 @@ mod tests {
             audience: "test_audience".to_string(),
             allowed_roles: ["user".to_string(), "admin".to_string()].iter().cloned().collect(),
             token_expiration: 3600, // 1 hour
+            secrets: SecretStore::default(),
         };
 
         let app_state = AppState {
@@ -235,7 +374,7 @@ mod tests {
             .with_state(app_state.clone());
 
         // Generate a valid token
-        let token = generate_token("user123", "user", Some(vec!["read".to_string()]), &auth_config).unwrap();
+        let token = generate_token("user123", "user", Some(vec!["read".to_string()]), None, &auth_config).unwrap();
 
         // Create a request with a valid token
         let valid_request = Request::builder()
@@ -282,10 +421,11 @@ mod tests {
             audience: "test_audience".to_string(),
             allowed_roles: ["user".to_string()].iter().cloned().collect(),
             token_expiration: 3600,
+            secrets: SecretStore::default(),
         };
 
         // Generate a token
-        let token = generate_token("user123", "user", Some(vec!["read".to_string()]), &auth_config).unwrap();
+        let token = generate_token("user123", "user", Some(vec!["read".to_string()]), None, &auth_config).unwrap();
 
         // Validate the token
         let claims = validate_token(&token, &auth_config).unwrap();
@@ -302,10 +442,11 @@ mod tests {
             audience: "test_audience".to_string(),
             allowed_roles: ["user".to_string()].iter().cloned().collect(),
             token_expiration: 0, // Immediate expiration
+            secrets: SecretStore::default(),
         };
 
         // Generate a token that is already expired
-        let token = generate_token("user123", "user", Some(vec!["read".to_string()]), &auth_config).unwrap();
+        let token = generate_token("user123", "user", Some(vec!["read".to_string()]), None, &auth_config).unwrap();
 
         // Validate the token
         let result = validate_token(&token, &auth_config);
@@ -320,10 +461,11 @@ mod tests {
             audience: "test_audience".to_string(),
             allowed_roles: ["admin".to_string()].iter().cloned().collect(), // Only admin allowed
             token_expiration: 3600,
+            secrets: SecretStore::default(),
         };
 
         // Generate a token with role 'user' which is not allowed
-        let token = generate_token("user123", "user", Some(vec!["read".to_string()]), &auth_config).unwrap();
+        let token = generate_token("user123", "user", Some(vec!["read".to_string()]), None, &auth_config).unwrap();
 
         // Validate the token
         let result = validate_token(&token, &auth_config);
@@ -338,6 +480,7 @@ mod tests {
             audience: "expected_audience".to_string(),
             allowed_roles: ["user".to_string()].iter().cloned().collect(),
             token_expiration: 3600,
+            secrets: SecretStore::default(),
         };
 
         // Generate a token with incorrect issuer and audience
@@ -352,6 +495,8 @@ mod tests {
             aud: "wrong_audience".to_string(),
             role: "user".to_string(),
             permissions: Some(vec!["read".to_string()]),
+            actor_type: ActorType::Human,
+            tenant_id: None,
         };
 
         let header = Header::new(Algorithm::HS256);
@@ -366,4 +511,47 @@ mod tests {
         let result = validate_token(&token, &auth_config);
         assert!(matches!(result, Err(AuthError::InvalidIssuerAudience)));
     }
+
+    #[test]
+    fn test_tokens_signed_before_a_key_rotation_stay_valid() {
+        let auth_config = AuthConfig {
+            secret: "test_secret".to_string(),
+            issuer: "test_issuer".to_string(),
+            audience: "test_audience".to_string(),
+            allowed_roles: ["user".to_string()].iter().cloned().collect(),
+            token_expiration: 3600,
+            secrets: SecretStore::default(),
+        };
+        let old = generate_token("user123", "user", None, None, &auth_config).unwrap();
+
+        auth_config
+            .secrets
+            .replace([(JWT_SECRET.to_string(), "rotated_secret".to_string())].into_iter().collect());
+        let new = generate_token("user123", "user", None, None, &auth_config).unwrap();
+        assert!(validate_token(&old, &auth_config).is_ok());
+        assert!(validate_token(&new, &auth_config).is_ok());
+
+        // Once no token signed with it can still be valid, the old key is refused
+        let expired_grace = AuthConfig { token_expiration: 0, ..auth_config.clone() };
+        assert!(matches!(validate_token(&old, &expired_grace), Err(AuthError::InvalidToken)));
+    }
+
+    #[test]
+    fn test_customer_tokens_only_reach_customer_routes() {
+        let auth_config = AuthConfig {
+            secret: "test_secret".to_string(),
+            issuer: "test_issuer".to_string(),
+            audience: "test_audience".to_string(),
+            allowed_roles: [CUSTOMER_ROLE.to_string()].iter().cloned().collect(),
+            token_expiration: 3600,
+            secrets: SecretStore::default(),
+        };
+        let token = generate_token("42", CUSTOMER_ROLE, None, None, &auth_config).unwrap();
+        let claims = validate_token(&token, &auth_config).unwrap();
+        assert!(check_customer_path(&claims, "/api/v1/assist/chat").is_ok());
+        assert!(matches!(check_customer_path(&claims, "/orders"), Err(AuthError::InsufficientPermissions)));
+
+        let staff = Claims { role: "user".to_string(), ..claims };
+        assert!(check_customer_path(&staff, "/orders").is_ok());
+    }
 }
@@ -0,0 +1,473 @@
+use axum::{
+    extract::State,
+    http::{HeaderMap, Method, Request},
+    middleware::Next,
+    response::Response,
+};
+use chrono::Utc;
+use rand::{distributions::Alphanumeric, Rng};
+use sea_orm::*;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashSet,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tracing::{debug, info, instrument, warn};
+use uuid::Uuid;
+
+use super::{ActorType, AuthConfig, AuthError, Claims};
+use crate::models::service_account::{self, Entity as ServiceAccount};
+use crate::network_acl::{client_ip, forwarded_client_ip, CidrList};
+use crate::tls::ClientCertificate;
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+
+/// Role carried in tokens issued to service accounts.
+pub const SERVICE_ACCOUNT_ROLE: &str = "service_account";
+
+/// Header carrying a service account API key.
+pub const API_KEY_HEADER: &str = "X-API-Key";
+
+const API_KEY_PREFIX: &str = "ssk";
+
+/// Credentials returned exactly once when a service account is created or rotated.
+#[derive(Debug, serde::Serialize)]
+pub struct IssuedCredentials {
+    pub service_account_id: Uuid,
+    pub client_id: String,
+    pub client_secret: String,
+    pub api_key: String,
+}
+
+/// Hashes a secret for storage. API keys and client secrets are random and high-entropy,
+/// so a fast digest is sufficient and allows indexed lookup by hash.
+pub fn hash_secret(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+fn random_token(len: usize) -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(len)
+        .map(char::from)
+        .collect()
+}
+
+/// Generates a new API key of the form `ssk_<prefix>_<secret>` and returns `(key, prefix)`.
+pub fn generate_api_key() -> (String, String) {
+    let prefix = random_token(8);
+    let key = format!("{}_{}_{}", API_KEY_PREFIX, prefix, random_token(40));
+    (key, prefix)
+}
+
+/// Scope a service account needs for a request: `<resource>:read` for safe methods and
+/// `<resource>:write` otherwise. The resource is the first path segment after `/api/v1/`
+/// (or the first segment of unversioned paths), with dashes as underscores, so
+/// `GET /api/v1/work-orders/7` needs `work_orders:read`.
+pub fn required_scope(method: &Method, path: &str) -> Option<String> {
+    let path = path.strip_prefix("/api/v1").unwrap_or(path);
+    let resource = path.split('/').find(|segment| !segment.is_empty())?.replace('-', "_");
+    let access = if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) { "read" } else { "write" };
+    Some(format!("{}:{}", resource, access))
+}
+
+/// Refuses service-account requests outside the scopes their credentials were granted.
+/// Other principals are left to the handlers' permission checks.
+pub fn check_scope(claims: &Claims, method: &Method, path: &str) -> Result<(), AuthError> {
+    if claims.actor_type != ActorType::ServiceAccount {
+        return Ok(());
+    }
+    match required_scope(method, path) {
+        Some(scope) if !claims.has_permission(&scope) => {
+            warn!(service_account_id = %claims.sub, scope = %scope, "Service account request outside its scopes");
+            Err(AuthError::InsufficientPermissions)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Returns true when `ip` matches any entry of the allowlist. Entries may be single
+/// addresses or CIDR ranges; an empty allowlist allows every address.
+pub fn ip_allowed(allowlist: &[String], ip: IpAddr) -> bool {
+    allowlist.is_empty() || CidrList::parse(allowlist).contains(ip)
+}
+
+/// Authenticates service accounts and issues their tokens.
+#[derive(Clone)]
+pub struct ServiceAccountAuthenticator {
+    db: Arc<DatabaseConnection>,
+    auth_config: Arc<AuthConfig>,
+    /// Proxies whose `X-Forwarded-For` is believed when checking account IP allowlists.
+    trusted_proxies: CidrList,
+}
+
+impl ServiceAccountAuthenticator {
+    pub fn new(db: Arc<DatabaseConnection>, auth_config: Arc<AuthConfig>, trusted_proxies: CidrList) -> Self {
+        Self { db, auth_config, trusted_proxies }
+    }
+
+    /// The client address of a request, as the account IP allowlists see it.
+    pub fn client_ip<B>(&self, req: &Request<B>) -> Option<IpAddr> {
+        client_ip(req, &self.trusted_proxies)
+    }
+
+    /// [`Self::client_ip`] from a connection's peer address and the request headers.
+    pub fn peer_ip(&self, peer: SocketAddr, headers: &HeaderMap) -> Option<IpAddr> {
+        forwarded_client_ip(peer.ip(), headers, &self.trusted_proxies)
+    }
+
+    /// Service accounts authenticate only while their role is among the allowed roles.
+    fn check_role(&self) -> Result<(), AuthError> {
+        if self.auth_config.allowed_roles.contains(SERVICE_ACCOUNT_ROLE) {
+            Ok(())
+        } else {
+            Err(AuthError::InsufficientPermissions)
+        }
+    }
+
+    /// Creates a service account and returns its one-time credentials.
+    #[instrument(skip(self))]
+    pub async fn create(
+        &self,
+        name: String,
+        description: Option<String>,
+        scopes: Vec<String>,
+        allowed_ips: Vec<String>,
+        tenant_id: Option<String>,
+        created_by: String,
+    ) -> Result<IssuedCredentials, AuthError> {
+        let id = Uuid::new_v4();
+        let client_id = format!("sa_{}", random_token(20));
+        let client_secret = random_token(48);
+        let (api_key, api_key_prefix) = generate_api_key();
+
+        service_account::ActiveModel {
+            id: Set(id),
+            name: Set(name),
+            description: Set(description),
+            client_id: Set(client_id.clone()),
+            client_secret_hash: Set(hash_secret(&client_secret)),
+            api_key_prefix: Set(api_key_prefix),
+            api_key_hash: Set(hash_secret(&api_key)),
+            scopes: Set(serde_json::json!(scopes)),
+            allowed_ips: Set(serde_json::json!(allowed_ips)),
+            tenant_id: Set(tenant_id),
+            is_active: Set(true),
+            created_by: Set(created_by.clone()),
+            created_at: Set(Utc::now()),
+            last_used_at: Set(None),
+            revoked_at: Set(None),
+        }
+        .insert(self.db.as_ref())
+        .await
+        .map_err(|e| AuthError::Internal(e.to_string()))?;
+
+        info!(service_account_id = %id, created_by = %created_by, "Service account created");
+
+        Ok(IssuedCredentials {
+            service_account_id: id,
+            client_id,
+            client_secret,
+            api_key,
+        })
+    }
+
+    /// Replaces the API key and client secret of an account, invalidating the old ones.
+    #[instrument(skip(self))]
+    pub async fn rotate(&self, id: Uuid) -> Result<IssuedCredentials, AuthError> {
+        let account = self.find_active(id).await?;
+        let client_secret = random_token(48);
+        let (api_key, api_key_prefix) = generate_api_key();
+        let client_id = account.client_id.clone();
+
+        let mut active: service_account::ActiveModel = account.into();
+        active.client_secret_hash = Set(hash_secret(&client_secret));
+        active.api_key_prefix = Set(api_key_prefix);
+        active.api_key_hash = Set(hash_secret(&api_key));
+        active
+            .update(self.db.as_ref())
+            .await
+            .map_err(|e| AuthError::Internal(e.to_string()))?;
+
+        info!(service_account_id = %id, "Service account credentials rotated");
+
+        Ok(IssuedCredentials {
+            service_account_id: id,
+            client_id,
+            client_secret,
+            api_key,
+        })
+    }
+
+    /// Deactivates an account so none of its credentials authenticate anymore.
+    #[instrument(skip(self))]
+    pub async fn revoke(&self, id: Uuid) -> Result<(), AuthError> {
+        let account = self.find_active(id).await?;
+        let mut active: service_account::ActiveModel = account.into();
+        active.is_active = Set(false);
+        active.revoked_at = Set(Some(Utc::now()));
+        active
+            .update(self.db.as_ref())
+            .await
+            .map_err(|e| AuthError::Internal(e.to_string()))?;
+        info!(service_account_id = %id, "Service account revoked");
+        Ok(())
+    }
+
+    /// Lists all service accounts (secrets are never serialized).
+    pub async fn list(&self) -> Result<Vec<service_account::Model>, AuthError> {
+        ServiceAccount::find()
+            .order_by_asc(service_account::Column::Name)
+            .all(self.db.as_ref())
+            .await
+            .map_err(|e| AuthError::Internal(e.to_string()))
+    }
+
+    async fn find_active(&self, id: Uuid) -> Result<service_account::Model, AuthError> {
+        ServiceAccount::find_by_id(id)
+            .filter(service_account::Column::IsActive.eq(true))
+            .one(self.db.as_ref())
+            .await
+            .map_err(|e| AuthError::Internal(e.to_string()))?
+            .ok_or(AuthError::InvalidToken)
+    }
+
+    /// Authenticates a raw API key from the given client IP and returns the claims for it.
+    #[instrument(skip(self, api_key))]
+    pub async fn authenticate_api_key(
+        &self,
+        api_key: &str,
+        ip: Option<IpAddr>,
+    ) -> Result<Claims, AuthError> {
+        self.check_role()?;
+        let account = ServiceAccount::find()
+            .filter(service_account::Column::ApiKeyHash.eq(hash_secret(api_key)))
+            .filter(service_account::Column::IsActive.eq(true))
+            .one(self.db.as_ref())
+            .await
+            .map_err(|e| AuthError::Internal(e.to_string()))?
+            .ok_or(AuthError::InvalidToken)?;
+
+        self.check_ip(&account, ip)?;
+        self.touch(&account).await;
+        Ok(self.claims_for(&account, account.scope_list()))
+    }
+
+    /// Authenticates a verified TLS client certificate whose subject CN is an active
+    /// account's `client_id`. The handshake already proved possession of the key.
+    #[instrument(skip(self, certificate), fields(fingerprint = %certificate.fingerprint))]
+    pub async fn authenticate_client_certificate(
+        &self,
+        certificate: &ClientCertificate,
+        ip: Option<IpAddr>,
+    ) -> Result<Claims, AuthError> {
+        let client_id = certificate.common_name.as_deref().ok_or(AuthError::InvalidToken)?;
+        self.check_role()?;
+        let account = ServiceAccount::find()
+            .filter(service_account::Column::ClientId.eq(client_id))
+            .filter(service_account::Column::IsActive.eq(true))
+            .one(self.db.as_ref())
+            .await
+            .map_err(|e| AuthError::Internal(e.to_string()))?
+            .ok_or(AuthError::InvalidToken)?;
+
+        self.check_ip(&account, ip)?;
+        self.touch(&account).await;
+        Ok(self.claims_for(&account, account.scope_list()))
+    }
+
+    /// OAuth2 client-credentials grant. The granted scopes are the intersection of the
+    /// requested scopes (all, if none requested) and the account's allowed scopes.
+    #[instrument(skip(self, client_secret))]
+    pub async fn client_credentials_grant(
+        &self,
+        client_id: &str,
+        client_secret: &str,
+        requested_scopes: Option<Vec<String>>,
+        ip: Option<IpAddr>,
+    ) -> Result<String, AuthError> {
+        self.check_role()?;
+        let account = ServiceAccount::find()
+            .filter(service_account::Column::ClientId.eq(client_id))
+            .filter(service_account::Column::ClientSecretHash.eq(hash_secret(client_secret)))
+            .filter(service_account::Column::IsActive.eq(true))
+            .one(self.db.as_ref())
+            .await
+            .map_err(|e| AuthError::Internal(e.to_string()))?
+            .ok_or(AuthError::InvalidToken)?;
+
+        self.check_ip(&account, ip)?;
+
+        let allowed: HashSet<String> = account.scope_list().into_iter().collect();
+        let granted: Vec<String> = match requested_scopes {
+            Some(requested) => {
+                if let Some(denied) = requested.iter().find(|s| !allowed.contains(*s)) {
+                    warn!(service_account_id = %account.id, scope = %denied, "Service account requested scope it does not hold");
+                    return Err(AuthError::InsufficientPermissions);
+                }
+                requested
+            }
+            None => allowed.into_iter().collect(),
+        };
+
+        self.touch(&account).await;
+        let claims = self.claims_for(&account, granted);
+        encode(
+            &Header::new(Algorithm::HS256),
+            &claims,
+            &EncodingKey::from_secret(self.auth_config.signing_secret().as_bytes()),
+        )
+        .map_err(AuthError::JWTError)
+    }
+
+    fn check_ip(&self, account: &service_account::Model, ip: Option<IpAddr>) -> Result<(), AuthError> {
+        let allowlist = account.allowed_ip_list();
+        if allowlist.is_empty() {
+            return Ok(());
+        }
+        match ip {
+            Some(ip) if ip_allowed(&allowlist, ip) => Ok(()),
+            _ => {
+                warn!(service_account_id = %account.id, ip = ?ip, "Service account used from disallowed IP");
+                Err(AuthError::InsufficientPermissions)
+            }
+        }
+    }
+
+    async fn touch(&self, account: &service_account::Model) {
+        let mut active: service_account::ActiveModel = account.clone().into();
+        active.last_used_at = Set(Some(Utc::now()));
+        if let Err(e) = active.update(self.db.as_ref()).await {
+            warn!(service_account_id = %account.id, "Failed to record service account usage: {}", e);
+        }
+    }
+
+    fn claims_for(&self, account: &service_account::Model, scopes: Vec<String>) -> Claims {
+        let exp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs() as usize
+            + self.auth_config.token_expiration;
+
+        Claims {
+            sub: account.id.to_string(),
+            exp,
+            iss: self.auth_config.issuer.clone(),
+            aud: self.auth_config.audience.clone(),
+            role: SERVICE_ACCOUNT_ROLE.to_string(),
+            permissions: Some(scopes),
+            actor_type: ActorType::ServiceAccount,
+            tenant_id: account.tenant_id.clone(),
+        }
+    }
+}
+
+/// Middleware authenticating requests that carry an `X-API-Key` header. On success the
+/// service account's claims are placed in the request extensions so `auth_middleware`
+/// lets the request through without a bearer token.
+pub async fn api_key_middleware<B>(
+    State(authenticator): State<Arc<ServiceAccountAuthenticator>>,
+    mut req: Request<B>,
+    next: Next<B>,
+) -> Result<Response, AuthError> {
+    let api_key = req
+        .headers()
+        .get(API_KEY_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .map(str::to_owned);
+
+    if let Some(api_key) = api_key {
+        let ip = authenticator.client_ip(&req);
+        let claims = authenticator.authenticate_api_key(&api_key, ip).await?;
+        info!(
+            actor_type = "service_account",
+            service_account_id = %claims.sub,
+            "Authenticated service account via API key"
+        );
+        req.extensions_mut().insert(claims);
+    }
+
+    Ok(next.run(req).await)
+}
+
+/// Middleware authenticating requests whose TLS client certificate maps to a service
+/// account. Runs after `api_key_middleware`, so an explicit API key takes precedence;
+/// certificates that map to no account fall through to bearer-token auth.
+pub async fn client_certificate_middleware<B>(
+    State(authenticator): State<Arc<ServiceAccountAuthenticator>>,
+    mut req: Request<B>,
+    next: Next<B>,
+) -> Result<Response, AuthError> {
+    let certificate = req.extensions().get::<ClientCertificate>().cloned();
+    if let (Some(certificate), None) = (certificate, req.extensions().get::<Claims>()) {
+        let ip = authenticator.client_ip(&req);
+        match authenticator.authenticate_client_certificate(&certificate, ip).await {
+            Ok(claims) => {
+                info!(
+                    actor_type = "service_account",
+                    service_account_id = %claims.sub,
+                    "Authenticated service account via client certificate"
+                );
+                req.extensions_mut().insert(claims);
+            }
+            Err(AuthError::InvalidToken) => {
+                debug!(common_name = ?certificate.common_name, "Client certificate maps to no service account");
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(next.run(req).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_api_key_format() {
+        let (key, prefix) = generate_api_key();
+        assert!(key.starts_with(&format!("ssk_{}_", prefix)));
+        assert_eq!(prefix.len(), 8);
+        assert_ne!(hash_secret(&key), key);
+        assert_eq!(hash_secret(&key), hash_secret(&key));
+    }
+
+    #[test]
+    fn test_required_scope_follows_resource_and_method() {
+        assert_eq!(required_scope(&Method::GET, "/api/v1/work-orders/7").as_deref(), Some("work_orders:read"));
+        assert_eq!(required_scope(&Method::POST, "/api/v1/orders").as_deref(), Some("orders:write"));
+        assert_eq!(required_scope(&Method::DELETE, "/orders/7").as_deref(), Some("orders:write"));
+        assert_eq!(required_scope(&Method::GET, "/"), None);
+    }
+
+    #[test]
+    fn test_service_accounts_stay_within_their_scopes() {
+        let claims = |actor_type| Claims {
+            sub: "sa".to_string(),
+            exp: 0,
+            iss: String::new(),
+            aud: String::new(),
+            role: SERVICE_ACCOUNT_ROLE.to_string(),
+            permissions: Some(vec!["orders:read".to_string()]),
+            actor_type,
+            tenant_id: None,
+        };
+        let account = claims(ActorType::ServiceAccount);
+        assert!(check_scope(&account, &Method::GET, "/api/v1/orders").is_ok());
+        assert!(check_scope(&account, &Method::POST, "/api/v1/orders").is_err());
+        assert!(check_scope(&account, &Method::GET, "/api/v1/returns").is_err());
+        assert!(check_scope(&claims(ActorType::Human), &Method::POST, "/api/v1/returns").is_ok());
+    }
+
+    #[test]
+    fn test_ip_allowlist() {
+        let allowlist = vec!["10.0.0.0/8".to_string(), "192.168.1.5".to_string()];
+        assert!(ip_allowed(&allowlist, "10.1.2.3".parse().unwrap()));
+        assert!(ip_allowed(&allowlist, "192.168.1.5".parse().unwrap()));
+        assert!(!ip_allowed(&allowlist, "192.168.1.6".parse().unwrap()));
+        assert!(!ip_allowed(&allowlist, "8.8.8.8".parse().unwrap()));
+        assert!(ip_allowed(&[], "8.8.8.8".parse().unwrap()));
+    }
+}
@@ -0,0 +1,358 @@
+//! Operator CLI for routine administration against the configured database, so common
+//! fixes don't require psql access. Reads the same configuration and secrets as the server.
+//!
+//! ```sh
+//! stateset-cli migrate
+//! stateset-cli service-accounts create --name erp-sync --scope orders:read --scope orders:write
+//! stateset-cli tokens issue --subject ops-oncall --role admin
+//! stateset-cli orders rebuild 7f0c...
+//! stateset-cli webhooks replay 3b1e... --from 2024-05-01T00:00:00Z --to 2024-05-02T00:00:00Z
+//! stateset-cli migration-plan --strict
+//! ```
+
+use clap::{Args, Parser, Subcommand};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{sync::Arc, time::Duration};
+use uuid::Uuid;
+
+use stateset_api::{
+    auth::{self, service_accounts::ServiceAccountAuthenticator, AuthConfig},
+    commands::orders::OrderEventStore,
+    config::{self, AppConfig},
+    customer_segments::CustomerSegmentService, db, inventory_snapshots::InventorySnapshotService, jobs::JobRunner,
+    network_acl::CidrList, partitioning, request_archive, retention, seed,
+    webhooks::{self, ReplayRequest, WebhookService},
+};
+
+type CliResult<T = ()> = Result<T, Box<dyn std::error::Error>>;
+
+/// Longest the CLI waits for a webhook replay; one left running is marked interrupted.
+const REPLAY_WAIT: Duration = Duration::from_secs(6 * 60 * 60);
+
+#[derive(Parser)]
+#[command(name = "stateset-cli", version, about = "StateSet API administration")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Apply pending database migrations
+    Migrate,
+    /// Print lock-risk and expand/contract analysis of the SQL migrations
+    MigrationPlan(MigrationPlanArgs),
+    /// Manage service accounts and their API keys
+    #[command(subcommand)]
+    ServiceAccounts(ServiceAccountCommand),
+    /// Issue access tokens
+    #[command(subcommand)]
+    Tokens(TokenCommand),
+    /// Inspect and repair orders
+    #[command(subcommand)]
+    Orders(OrderCommand),
+    /// Re-deliver outbox events to webhook subscribers
+    #[command(subcommand)]
+    Webhooks(WebhookCommand),
+    /// Customer maintenance
+    #[command(subcommand)]
+    Customers(CustomerCommand),
+    /// Inventory maintenance
+    #[command(subcommand)]
+    Inventory(InventoryCommand),
+    /// Run every configured retention policy once
+    Retention,
+    /// Table partition maintenance
+    #[command(subcommand)]
+    Partitions(PartitionCommand),
+    /// Write deterministic demo data (refused in production)
+    Seed(SeedArgs),
+}
+
+#[derive(Subcommand)]
+enum ServiceAccountCommand {
+    /// Create an account; the credentials are printed once and never stored in clear
+    Create {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        description: Option<String>,
+        #[arg(long = "scope")]
+        scopes: Vec<String>,
+        #[arg(long = "allowed-ip")]
+        allowed_ips: Vec<String>,
+        /// Tenant the account acts for
+        #[arg(long = "tenant")]
+        tenant_id: Option<String>,
+        #[arg(long, default_value = "stateset-cli")]
+        created_by: String,
+    },
+    List,
+    /// Replace an account's API key and client secret
+    Rotate { id: Uuid },
+    Revoke { id: Uuid },
+}
+
+#[derive(Subcommand)]
+enum TokenCommand {
+    /// Mint a JWT for a user, e.g. for break-glass access
+    Issue {
+        #[arg(long)]
+        subject: String,
+        #[arg(long, default_value = "user")]
+        role: String,
+        #[arg(long = "permission")]
+        permissions: Vec<String>,
+        /// Tenant the user belongs to
+        #[arg(long = "tenant")]
+        tenant_id: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum OrderCommand {
+    /// Replay an order's event stream and print the rebuilt state
+    Rebuild { id: Uuid },
+}
+
+#[derive(Subcommand)]
+enum WebhookCommand {
+    /// Re-deliver the outbox events in `[from, to)` to a subscription, including those
+    /// whose delivery was given up on, and wait for the replay to finish
+    Replay {
+        id: Uuid,
+        #[arg(long)]
+        from: DateTime<Utc>,
+        #[arg(long)]
+        to: DateTime<Utc>,
+        #[arg(long = "event-type")]
+        event_types: Vec<String>,
+    },
+}
+
+#[derive(Subcommand)]
+enum CustomerCommand {
+    /// Recompute customer segments from the configured rules
+    EvaluateSegments,
+}
+
+#[derive(Subcommand)]
+enum InventoryCommand {
+    /// Record current stock levels as the snapshot for a day (default: today, UTC)
+    Snapshot {
+        #[arg(long)]
+        date: Option<chrono::NaiveDate>,
+    },
+}
+
+#[derive(Subcommand)]
+enum PartitionCommand {
+    /// Print a migration creating upcoming partitions and detaching expired ones for the
+    /// configured tables
+    Plan,
+    /// Copy the rows of a configured table into its partitioned copy, in batches
+    Backfill {
+        table: String,
+        #[arg(long, default_value_t = partitioning::DEFAULT_BACKFILL_BATCH)]
+        batch_size: u32,
+    },
+    /// Print the contract migration swapping backfilled tables for their partitioned copies
+    Swap,
+}
+
+#[derive(Args)]
+struct MigrationPlanArgs {
+    #[arg(long, default_value = "migrations")]
+    dir: std::path::PathBuf,
+    /// Exit non-zero on unsafe statements not allowed by the migration, for CI
+    #[arg(long)]
+    strict: bool,
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(Args)]
+struct SeedArgs {
+    #[arg(long, default_value_t = 42)]
+    seed: u64,
+    #[arg(long)]
+    products: Option<usize>,
+    #[arg(long)]
+    customers: Option<usize>,
+    #[arg(long)]
+    orders: Option<usize>,
+}
+
+#[tokio::main]
+async fn main() {
+    dotenv::dotenv().ok();
+    let cli = Cli::parse();
+    if let Err(e) = run(cli.command).await {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(command: Command) -> CliResult {
+    // Works on files alone, so CI can run it without a database
+    if let Command::MigrationPlan(args) = command {
+        return migration_plan(args);
+    }
+    let (config, _secrets) = config::load_with_secrets().await?;
+    let db = Arc::new(db::connect_pool(&config.database_url, &config.database_pool.tools, &config.database_pool).await?);
+
+    match command {
+        Command::Migrate => {
+            db::run_migrations(&db).await?;
+            println!("Migrations applied");
+        }
+        Command::MigrationPlan(_) => unreachable!("handled before connecting"),
+        Command::ServiceAccounts(command) => {
+            let authenticator = ServiceAccountAuthenticator::new(
+                db,
+                Arc::new(AuthConfig::from_app_config(&config)),
+                CidrList::parse(&config.network_acl.trusted_proxies),
+            );
+            service_accounts(&authenticator, command).await?;
+        }
+        Command::Tokens(TokenCommand::Issue { subject, role, permissions, tenant_id }) => {
+            let permissions = (!permissions.is_empty()).then_some(permissions);
+            let token = auth::generate_token(
+                &subject,
+                &role,
+                permissions,
+                tenant_id.as_deref(),
+                &AuthConfig::from_app_config(&config),
+            )?;
+            println!("{}", token);
+        }
+        Command::Orders(OrderCommand::Rebuild { id }) => {
+            let (projection, events) = OrderEventStore::rebuild(db.as_ref(), id).await?;
+            eprintln!("Replayed {} events", events.len());
+            print_json(&projection)?;
+        }
+        Command::Webhooks(WebhookCommand::Replay { id, from, to, event_types }) => {
+            let service = Arc::new(
+                WebhookService::new(db.clone(), config.webhooks.clone()).with_cdc_relay(config.cdc.enabled),
+            );
+            let runner = JobRunner::new(db);
+            let request = ReplayRequest { from, to, event_types };
+            let job_id = webhooks::submit_replay(&runner, service, id, request, Some("stateset-cli".to_string())).await?;
+            eprintln!("Replaying as job {}", job_id);
+            // The replay runs in this process, so it has to finish before the CLI exits
+            runner.drain(REPLAY_WAIT).await;
+            print_json(&runner.get(job_id).await?)?;
+        }
+        Command::Customers(CustomerCommand::EvaluateSegments) => {
+            let service = CustomerSegmentService::new(db, config.customer_segments.rules.clone());
+            print_json(&service.evaluate_all(None).await?)?;
+        }
+        Command::Inventory(InventoryCommand::Snapshot { date }) => {
+            let date = date.unwrap_or_else(|| chrono::Utc::now().date_naive());
+            let rows = InventorySnapshotService::new(db).take_snapshot(date).await?;
+            println!("Snapshot for {} recorded {} SKU/warehouse rows", date, rows);
+        }
+        Command::Retention => run_retention(&config, db).await?,
+        Command::Partitions(PartitionCommand::Plan) => {
+            let manager = partitioning::PartitionManager::new(db, config.partitioning.clone())?;
+            let due = manager.maintenance_plan().await?;
+            if due.is_empty() {
+                eprintln!("No partition maintenance is due");
+            } else {
+                print!("{}", due.migration_sql());
+            }
+        }
+        Command::Partitions(PartitionCommand::Backfill { table, batch_size }) => {
+            let manager = partitioning::PartitionManager::new(db, config.partitioning.clone())?;
+            let rows = manager.backfill(&table, batch_size).await?;
+            println!("Copied {} rows of {} into its partitioned copy", rows, table);
+        }
+        Command::Partitions(PartitionCommand::Swap) => {
+            let manager = partitioning::PartitionManager::new(db, config.partitioning.clone())?;
+            match manager.swap_plan().await? {
+                Some(migration) => print!("{}", migration),
+                None => eprintln!("No partitioned copy awaits its swap"),
+            }
+        }
+        Command::Seed(args) => {
+            if config.is_production() {
+                return Err("Refusing to seed demo data in production".into());
+            }
+            let defaults = seed::SeedOptions::default();
+            let options = seed::SeedOptions {
+                seed: args.seed,
+                products: args.products.unwrap_or(defaults.products),
+                customers: args.customers.unwrap_or(defaults.customers),
+                orders: args.orders.unwrap_or(defaults.orders),
+                ..defaults
+            };
+            let summary = seed::persist(&db, &seed::generate(&options)).await?;
+            print_json(&summary)?;
+        }
+    }
+    Ok(())
+}
+
+async fn service_accounts(authenticator: &ServiceAccountAuthenticator, command: ServiceAccountCommand) -> CliResult {
+    match command {
+        ServiceAccountCommand::Create { name, description, scopes, allowed_ips, tenant_id, created_by } => {
+            let credentials = authenticator
+                .create(name, description, scopes, allowed_ips, tenant_id, created_by)
+                .await?;
+            print_json(&credentials)
+        }
+        ServiceAccountCommand::List => print_json(&authenticator.list().await?),
+        ServiceAccountCommand::Rotate { id } => print_json(&authenticator.rotate(id).await?),
+        ServiceAccountCommand::Revoke { id } => {
+            authenticator.revoke(id).await?;
+            println!("Service account {} revoked", id);
+            Ok(())
+        }
+    }
+}
+
+async fn run_retention(config: &AppConfig, db: Arc<sea_orm::DatabaseConnection>) -> CliResult {
+    let export_sink: Option<Arc<dyn request_archive::ArchiveSink>> = match &config.retention.export_bucket {
+        Some(bucket) => Some(Arc::new(request_archive::S3Sink::new(bucket.clone()).await)),
+        None => None,
+    };
+    let runner = retention::RetentionRunner::new(db, config.retention.policies.clone(), export_sink)?;
+    runner.run_all().await;
+    println!("Ran {} retention policies; see retention_runs for results", config.retention.policies.len());
+    Ok(())
+}
+
+fn migration_plan(args: MigrationPlanArgs) -> CliResult {
+    let plans = db::migration_safety::analyze_dir(&args.dir)?;
+    if args.json {
+        print_json(&plans)?;
+    } else {
+        for plan in &plans {
+            println!("{} ({:?}, strongest lock: {:?})", plan.name, plan.phase, plan.strongest_lock());
+            for finding in &plan.findings {
+                let allowed = if finding.allowed { " [allowed]" } else { "" };
+                println!(
+                    "  #{} {:?} {:?} {}{}: {}",
+                    finding.statement, finding.risk, finding.lock, finding.rule, allowed, finding.message
+                );
+                if !finding.sql.is_empty() {
+                    println!("      {}", finding.sql);
+                }
+                if let Some(suggestion) = finding.suggestion {
+                    println!("      -> {}", suggestion);
+                }
+            }
+        }
+    }
+    let violations: usize = plans.iter().map(|plan| plan.violations().count()).sum();
+    if args.strict && violations > 0 {
+        return Err(format!("{} unsafe migration statements", violations).into());
+    }
+    Ok(())
+}
+
+fn print_json<T: Serialize>(value: &T) -> CliResult {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
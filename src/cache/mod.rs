@@ -13,6 +13,8 @@ use uuid::Uuid;
 use dashmap::DashMap;
 use tokio::time::{sleep, Instant};
 
+pub mod swr;
+
 /// Comprehensive error type for cache operations.
 #[derive(Error, Debug)]
 pub enum CacheError {
@@ -0,0 +1,299 @@
+// cache/swr.rs
+
+use axum::{
+    http::{header, HeaderName, HeaderValue},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::HashMap, fmt::Display, future::Future, sync::Arc, time::Duration};
+use tracing::{debug, warn};
+
+use super::Cache;
+
+/// Tells clients how current a cached report is: `fresh`, `stale` or `miss`.
+pub const FRESHNESS_HEADER: &str = "x-data-freshness";
+
+/// How long a report is served as is, and for how long after that it is still served
+/// while a refresh runs in the background.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+pub struct SwrPolicy {
+    /// Age up to which results are served without refreshing (default: 300 s).
+    #[serde(default = "default_fresh_secs")]
+    pub fresh_secs: u64,
+
+    /// Further age up to which results are served stale while refreshing (default: 3600 s).
+    #[serde(default = "default_stale_secs")]
+    pub stale_secs: u64,
+}
+
+fn default_fresh_secs() -> u64 {
+    300
+}
+
+fn default_stale_secs() -> u64 {
+    3600
+}
+
+impl Default for SwrPolicy {
+    fn default() -> Self {
+        Self {
+            fresh_secs: default_fresh_secs(),
+            stale_secs: default_stale_secs(),
+        }
+    }
+}
+
+/// Analytics caching settings, loaded from the `analytics_cache` section of the config.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct AnalyticsCacheConfig {
+    /// Caches analytics reports in Redis (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Policy for reports without their own entry.
+    #[serde(default)]
+    pub default: SwrPolicy,
+
+    /// Per-report policies keyed by report name, e.g. `shipment_sla`, `supplier_scorecard`
+    /// or `cogs`. They replace the built-in policy of the report, if it has one.
+    #[serde(default)]
+    pub reports: HashMap<String, SwrPolicy>,
+}
+
+/// Policies of reports that are heavy to compute and mostly read at month end, where
+/// data a few minutes old is fine.
+fn builtin_policy(report: &str) -> Option<SwrPolicy> {
+    let (fresh_secs, stale_secs) = match report {
+        "sales" | "sales_by_product" | "cogs" => (900, 6 * 3600),
+        "work_order_efficiency" => (900, 2 * 3600),
+        "inventory" => (300, 1800),
+        _ => return None,
+    };
+    Some(SwrPolicy { fresh_secs, stale_secs })
+}
+
+impl AnalyticsCacheConfig {
+    pub fn policy(&self, report: &str) -> SwrPolicy {
+        self.reports.get(report).copied().or_else(|| builtin_policy(report)).unwrap_or(self.default)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freshness {
+    Fresh,
+    /// Served while a refresh runs.
+    Stale,
+    /// Computed for this request.
+    Miss,
+}
+
+impl Freshness {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Freshness::Fresh => "fresh",
+            Freshness::Stale => "stale",
+            Freshness::Miss => "miss",
+        }
+    }
+}
+
+/// Where an entry of the given age stands under a policy; `Miss` once it is too old to
+/// serve.
+pub fn classify(age: Duration, policy: SwrPolicy) -> Freshness {
+    if age < Duration::from_secs(policy.fresh_secs) {
+        Freshness::Fresh
+    } else if age < Duration::from_secs(policy.fresh_secs + policy.stale_secs) {
+        Freshness::Stale
+    } else {
+        Freshness::Miss
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Stamped<T> {
+    value: T,
+    cached_at: DateTime<Utc>,
+}
+
+/// A value with how current it is.
+#[derive(Debug, Clone)]
+pub struct Cached<T> {
+    pub value: T,
+    pub freshness: Freshness,
+    /// Time since the value was computed.
+    pub age: Duration,
+}
+
+impl IntoResponse for Cached<Value> {
+    fn into_response(self) -> Response {
+        let mut response = Json(self.value).into_response();
+        let headers = response.headers_mut();
+        headers.insert(
+            HeaderName::from_static(FRESHNESS_HEADER),
+            HeaderValue::from_static(self.freshness.as_str()),
+        );
+        headers.insert(header::AGE, HeaderValue::from(self.age.as_secs()));
+        response
+    }
+}
+
+/// Stale-while-revalidate on top of a cache: fresh entries are served as is, stale ones
+/// are served while one background refresh per key and instance recomputes them.
+pub struct SwrCache<C> {
+    cache: Arc<C>,
+    refreshing: Arc<DashMap<String, ()>>,
+}
+
+impl<C: Cache + 'static> SwrCache<C> {
+    pub fn new(cache: Arc<C>) -> Self {
+        Self { cache, refreshing: Arc::new(DashMap::new()) }
+    }
+
+    async fn store<T: Serialize + Send + Sync>(cache: &C, key: &str, value: &T, policy: SwrPolicy) {
+        let stamped = Stamped { value, cached_at: Utc::now() };
+        let ttl = Duration::from_secs(policy.fresh_secs + policy.stale_secs);
+        if let Err(e) = cache.set(key, &stamped, Some(ttl)).await {
+            warn!(key, "Failed to cache report: {}", e);
+        }
+    }
+
+    /// Returns the cached value for `key`, or computes it with `load`. Cache failures
+    /// fall back to computing, so a cache outage only costs latency.
+    pub async fn get_or_load<T, E, F, Fut>(&self, key: &str, policy: SwrPolicy, load: F) -> Result<Cached<T>, E>
+    where
+        T: Serialize + DeserializeOwned + Send + Sync + 'static,
+        E: Display + Send + 'static,
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+    {
+        let cached = match self.cache.get::<Stamped<T>>(key).await {
+            Ok(cached) => cached,
+            Err(e) => {
+                warn!(key, "Failed to read cached report: {}", e);
+                None
+            }
+        };
+
+        if let Some(stamped) = cached {
+            let age = (Utc::now() - stamped.cached_at).to_std().unwrap_or_default();
+            match classify(age, policy) {
+                Freshness::Fresh => return Ok(Cached { value: stamped.value, freshness: Freshness::Fresh, age }),
+                Freshness::Stale => {
+                    self.refresh(key, policy, load);
+                    return Ok(Cached { value: stamped.value, freshness: Freshness::Stale, age });
+                }
+                Freshness::Miss => {}
+            }
+        }
+
+        let value = load().await?;
+        Self::store(&self.cache, key, &value, policy).await;
+        Ok(Cached { value, freshness: Freshness::Miss, age: Duration::ZERO })
+    }
+
+    fn refresh<T, E, F, Fut>(&self, key: &str, policy: SwrPolicy, load: F)
+    where
+        T: Serialize + Send + Sync + 'static,
+        E: Display + Send + 'static,
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T, E>> + Send + 'static,
+    {
+        if self.refreshing.insert(key.to_string(), ()).is_some() {
+            return;
+        }
+        let cache = self.cache.clone();
+        let refreshing = self.refreshing.clone();
+        let key = key.to_string();
+        tokio::spawn(async move {
+            match load().await {
+                Ok(value) => {
+                    Self::store(&cache, &key, &value, policy).await;
+                    debug!(key = %key, "Stale report refreshed");
+                }
+                // The stale value keeps being served until it ages out
+                Err(e) => warn!(key = %key, "Failed to refresh stale report: {}", e),
+            }
+            refreshing.remove(&key);
+        });
+    }
+}
+
+/// Analytics reports behind per-report SWR policies.
+pub struct ReportCache<C> {
+    swr: SwrCache<C>,
+    config: AnalyticsCacheConfig,
+}
+
+impl<C: Cache + 'static> ReportCache<C> {
+    pub fn new(cache: Arc<C>, config: AnalyticsCacheConfig) -> Self {
+        Self { swr: SwrCache::new(cache), config }
+    }
+
+    /// The report `report` for the parameters in `key`, cached under its policy; computed
+    /// every time while caching is disabled.
+    pub async fn report<E, F, Fut>(&self, report: &str, key: &str, load: F) -> Result<Cached<Value>, E>
+    where
+        E: Display + Send + 'static,
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Value, E>> + Send + 'static,
+    {
+        if !self.config.enabled {
+            let value = load().await?;
+            return Ok(Cached { value, freshness: Freshness::Miss, age: Duration::ZERO });
+        }
+        let key = format!("analytics:{}:{}", report, key);
+        self.swr.get_or_load(&key, self.config.policy(report), load).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::InMemoryCache;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[test]
+    fn test_report_policies() {
+        let mut config = AnalyticsCacheConfig::default();
+        assert_eq!(config.policy("cogs"), SwrPolicy { fresh_secs: 900, stale_secs: 6 * 3600 });
+        assert_eq!(config.policy("shipment_sla"), SwrPolicy::default());
+        let configured = SwrPolicy { fresh_secs: 60, stale_secs: 60 };
+        config.reports.insert("cogs".to_string(), configured);
+        assert_eq!(config.policy("cogs"), configured);
+    }
+
+    #[test]
+    fn test_classify() {
+        let policy = SwrPolicy { fresh_secs: 60, stale_secs: 600 };
+        assert_eq!(classify(Duration::from_secs(10), policy), Freshness::Fresh);
+        assert_eq!(classify(Duration::from_secs(60), policy), Freshness::Stale);
+        assert_eq!(classify(Duration::from_secs(700), policy), Freshness::Miss);
+    }
+
+    #[tokio::test]
+    async fn test_stale_value_is_served_while_refreshing() {
+        let cache = SwrCache::new(Arc::new(InMemoryCache::new(100, Duration::from_secs(60))));
+        // Everything is stale straight away, but servable for a minute
+        let policy = SwrPolicy { fresh_secs: 0, stale_secs: 60 };
+        let loads = Arc::new(AtomicU64::new(0));
+        let load = || {
+            let loads = loads.clone();
+            move || async move { Ok::<_, String>(json!({ "run": loads.fetch_add(1, Ordering::SeqCst) + 1 })) }
+        };
+
+        let first = cache.get_or_load("report", policy, load()).await.unwrap();
+        assert_eq!((first.freshness, first.value["run"].as_u64()), (Freshness::Miss, Some(1)));
+
+        let second = cache.get_or_load("report", policy, load()).await.unwrap();
+        assert_eq!((second.freshness, second.value["run"].as_u64()), (Freshness::Stale, Some(1)));
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let third = cache.get_or_load("report", policy, load()).await.unwrap();
+        assert_eq!((third.freshness, third.value["run"].as_u64()), (Freshness::Stale, Some(2)));
+    }
+}
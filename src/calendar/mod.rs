@@ -0,0 +1,285 @@
+// calendar/mod.rs
+
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+
+/// Business calendars, loaded from the `calendar` section of the config.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct CalendarConfig {
+    /// Calendar of warehouses without their own entry, and of shipments, which do not
+    /// record the warehouse they left from.
+    #[serde(default)]
+    pub default: WarehouseCalendarConfig,
+
+    /// Per-warehouse calendars keyed by warehouse code.
+    #[serde(default)]
+    pub warehouses: HashMap<String, WarehouseCalendarConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct WarehouseCalendarConfig {
+    /// IANA time zone, e.g. `America/Chicago` (default: UTC).
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+
+    /// ISO weekdays the warehouse ships on, 1 (Monday) to 7 (default: Monday to Friday).
+    #[serde(default = "default_working_days")]
+    pub working_days: Vec<u8>,
+
+    /// Local dates the warehouse is closed.
+    #[serde(default)]
+    pub holidays: Vec<NaiveDate>,
+
+    /// Local time after which orders ship the next business day (default: 14:00).
+    #[serde(default = "default_cutoff")]
+    pub cutoff: NaiveTime,
+}
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+fn default_working_days() -> Vec<u8> {
+    vec![1, 2, 3, 4, 5]
+}
+
+fn default_cutoff() -> NaiveTime {
+    NaiveTime::from_hms_opt(14, 0, 0).expect("14:00 is valid")
+}
+
+impl Default for WarehouseCalendarConfig {
+    fn default() -> Self {
+        Self {
+            timezone: default_timezone(),
+            working_days: default_working_days(),
+            holidays: Vec::new(),
+            cutoff: default_cutoff(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum CalendarError {
+    #[error("Unknown time zone: {0}")]
+    UnknownTimezone(String),
+    #[error("Invalid working day {0}; expected 1 (Monday) to 7 (Sunday)")]
+    InvalidWorkingDay(u8),
+    #[error("A calendar needs at least one working day")]
+    NoWorkingDays,
+}
+
+/// A warehouse's local time zone, working week, holidays and same-day cutoff.
+#[derive(Clone, Debug)]
+pub struct BusinessCalendar {
+    tz: Tz,
+    /// Indexed by days from Monday.
+    working_days: [bool; 7],
+    holidays: HashSet<NaiveDate>,
+    cutoff: NaiveTime,
+}
+
+impl BusinessCalendar {
+    pub fn from_config(config: &WarehouseCalendarConfig) -> Result<Self, CalendarError> {
+        let tz = config
+            .timezone
+            .parse::<Tz>()
+            .map_err(|_| CalendarError::UnknownTimezone(config.timezone.clone()))?;
+        let mut working_days = [false; 7];
+        for day in &config.working_days {
+            if !(1..=7).contains(day) {
+                return Err(CalendarError::InvalidWorkingDay(*day));
+            }
+            working_days[*day as usize - 1] = true;
+        }
+        if !working_days.contains(&true) {
+            return Err(CalendarError::NoWorkingDays);
+        }
+        Ok(Self {
+            tz,
+            working_days,
+            holidays: config.holidays.iter().copied().collect(),
+            cutoff: config.cutoff,
+        })
+    }
+
+    pub fn timezone(&self) -> Tz {
+        self.tz
+    }
+
+    /// The warehouse's calendar date at `at`.
+    pub fn local_date(&self, at: DateTime<Utc>) -> NaiveDate {
+        at.with_timezone(&self.tz).date_naive()
+    }
+
+    pub fn is_business_day(&self, date: NaiveDate) -> bool {
+        self.working_days[date.weekday().num_days_from_monday() as usize] && !self.holidays.contains(&date)
+    }
+
+    /// The first business day after `date`.
+    pub fn next_business_day(&self, date: NaiveDate) -> NaiveDate {
+        let mut next = date + ChronoDuration::days(1);
+        while !self.is_business_day(next) {
+            next += ChronoDuration::days(1);
+        }
+        next
+    }
+
+    /// `date` moved forward by `days` business days; non-business days never count.
+    pub fn add_business_days(&self, date: NaiveDate, days: i64) -> NaiveDate {
+        (0..days).fold(date, |date, _| self.next_business_day(date))
+    }
+
+    /// Local date an order placed at `at` ships: the same day when that is a business
+    /// day and the cutoff has not passed, otherwise the next business day.
+    pub fn ship_date(&self, at: DateTime<Utc>) -> NaiveDate {
+        let local = at.with_timezone(&self.tz);
+        let date = local.date_naive();
+        if self.is_business_day(date) && local.time() < self.cutoff {
+            date
+        } else {
+            self.next_business_day(date)
+        }
+    }
+
+    /// The instant `time` occurs locally on `date`. Times skipped by a DST change resolve
+    /// to the first valid time after them.
+    fn instant(&self, date: NaiveDate, time: NaiveTime) -> DateTime<Utc> {
+        let local = date.and_time(time);
+        (0..=2)
+            .find_map(|hours| self.tz.from_local_datetime(&(local + ChronoDuration::hours(hours))).earliest())
+            .map(|at| at.with_timezone(&Utc))
+            .unwrap_or_else(|| local.and_utc())
+    }
+
+    /// Start of the local day `date`.
+    pub fn start_of_day(&self, date: NaiveDate) -> DateTime<Utc> {
+        self.instant(date, NaiveTime::MIN)
+    }
+
+    /// End of the local day `date`, exclusive: the start of the day after.
+    pub fn end_of_day(&self, date: NaiveDate) -> DateTime<Utc> {
+        self.start_of_day(date.succ_opt().unwrap_or(NaiveDate::MAX))
+    }
+
+    /// The same-day shipping cutoff on `date`.
+    pub fn cutoff_at(&self, date: NaiveDate) -> DateTime<Utc> {
+        self.instant(date, self.cutoff)
+    }
+}
+
+impl Default for BusinessCalendar {
+    fn default() -> Self {
+        Self::from_config(&WarehouseCalendarConfig::default()).expect("the default calendar is valid")
+    }
+}
+
+/// Calendars of all configured warehouses.
+#[derive(Clone, Debug, Default)]
+pub struct Calendars {
+    default: BusinessCalendar,
+    warehouses: HashMap<String, BusinessCalendar>,
+}
+
+impl Calendars {
+    pub fn from_config(config: &CalendarConfig) -> Result<Self, CalendarError> {
+        Ok(Self {
+            default: BusinessCalendar::from_config(&config.default)?,
+            warehouses: config
+                .warehouses
+                .iter()
+                .map(|(code, calendar)| Ok((code.clone(), BusinessCalendar::from_config(calendar)?)))
+                .collect::<Result<_, CalendarError>>()?,
+        })
+    }
+
+    /// The calendar of `warehouse`; the default one for unconfigured warehouses or none.
+    pub fn for_warehouse(&self, warehouse: Option<&str>) -> &BusinessCalendar {
+        warehouse.and_then(|code| self.warehouses.get(code)).unwrap_or(&self.default)
+    }
+
+    pub fn default_calendar(&self) -> &BusinessCalendar {
+        &self.default
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    fn chicago() -> BusinessCalendar {
+        BusinessCalendar::from_config(&WarehouseCalendarConfig {
+            timezone: "America/Chicago".to_string(),
+            holidays: vec![date(7, 4)],
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_invalid_calendars_are_rejected() {
+        let config = |timezone: &str, working_days: Vec<u8>| WarehouseCalendarConfig {
+            timezone: timezone.to_string(),
+            working_days,
+            ..Default::default()
+        };
+        assert!(matches!(
+            BusinessCalendar::from_config(&config("Mars/Olympus", vec![1])),
+            Err(CalendarError::UnknownTimezone(_))
+        ));
+        assert!(matches!(BusinessCalendar::from_config(&config("UTC", vec![0])), Err(CalendarError::InvalidWorkingDay(0))));
+        assert!(matches!(BusinessCalendar::from_config(&config("UTC", vec![])), Err(CalendarError::NoWorkingDays)));
+    }
+
+    #[test]
+    fn test_business_days_skip_weekends_and_holidays() {
+        let calendar = chicago();
+        // Wednesday 3 July; the 4th is a holiday and the 6th and 7th a weekend
+        assert_eq!(calendar.next_business_day(date(7, 3)), date(7, 5));
+        assert_eq!(calendar.add_business_days(date(7, 3), 2), date(7, 8));
+        assert_eq!(calendar.add_business_days(date(7, 3), 0), date(7, 3));
+    }
+
+    #[test]
+    fn test_ship_date_follows_local_cutoff() {
+        let calendar = chicago();
+        // 13:30 and 14:30 in Chicago (UTC-5) on Tuesday 2 July
+        assert_eq!(calendar.ship_date(Utc.with_ymd_and_hms(2024, 7, 2, 18, 30, 0).unwrap()), date(7, 2));
+        assert_eq!(calendar.ship_date(Utc.with_ymd_and_hms(2024, 7, 2, 19, 30, 0).unwrap()), date(7, 3));
+        // 20:00 on the 3rd is already the 4th in UTC, but still the 3rd locally
+        let evening = Utc.with_ymd_and_hms(2024, 7, 4, 1, 0, 0).unwrap();
+        assert_eq!(calendar.local_date(evening), date(7, 3));
+        assert_eq!(calendar.ship_date(evening), date(7, 5));
+    }
+
+    #[test]
+    fn test_local_day_bounds() {
+        let calendar = chicago();
+        assert_eq!(calendar.start_of_day(date(7, 2)), Utc.with_ymd_and_hms(2024, 7, 2, 5, 0, 0).unwrap());
+        assert_eq!(calendar.end_of_day(date(7, 2)), Utc.with_ymd_and_hms(2024, 7, 3, 5, 0, 0).unwrap());
+        assert_eq!(calendar.cutoff_at(date(12, 2)), Utc.with_ymd_and_hms(2024, 12, 2, 20, 0, 0).unwrap());
+        // Clocks went back on 3 November, so that day lasts 25 hours
+        assert_eq!(calendar.end_of_day(date(11, 3)) - calendar.start_of_day(date(11, 3)), ChronoDuration::hours(25));
+    }
+
+    #[test]
+    fn test_unconfigured_warehouses_use_the_default_calendar() {
+        let config = CalendarConfig {
+            warehouses: HashMap::from([(
+                "ORD1".to_string(),
+                WarehouseCalendarConfig { timezone: "America/Chicago".to_string(), ..Default::default() },
+            )]),
+            ..Default::default()
+        };
+        let calendars = Calendars::from_config(&config).unwrap();
+        assert_eq!(calendars.for_warehouse(Some("ORD1")).timezone(), chrono_tz::America::Chicago);
+        assert_eq!(calendars.for_warehouse(Some("LAX1")).timezone(), chrono_tz::UTC);
+        assert_eq!(calendars.for_warehouse(None).timezone(), chrono_tz::UTC);
+    }
+}
@@ -0,0 +1,305 @@
+// carriers/mod.rs
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fmt::Write as _;
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::deadline;
+use crate::models::shipment::ShippingCarrier;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ManifestAdapterKind {
+    /// No electronic submission; the printed manifest is handed to the driver.
+    Document,
+    /// Posts the manifest as JSON to the carrier's (or a carrier gateway's) endpoint.
+    Http,
+}
+
+/// How end-of-day manifests are submitted to one carrier.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ManifestAccount {
+    pub carrier: ShippingCarrier,
+    pub adapter: ManifestAdapterKind,
+    /// Shipper account number printed on the manifest and sent with submissions.
+    #[serde(default)]
+    pub account_number: Option<String>,
+    /// Manifest submission URL, for the `http` adapter.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Environment variable holding the bearer token for the `http` adapter.
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+}
+
+/// Carrier integration settings, loaded from the `carriers` section of the config.
+/// Carriers without a manifest account get a printed manifest only.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct CarriersConfig {
+    #[serde(default)]
+    pub manifests: Vec<ManifestAccount>,
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum CarrierError {
+    #[error("Carrier adapter is misconfigured: {0}")]
+    Misconfigured(String),
+
+    /// The carrier refused the manifest, e.g. an unknown tracking number.
+    #[error("Manifest rejected: {0}")]
+    Rejected(String),
+
+    #[error("Carrier unavailable: {0}")]
+    Unavailable(String),
+}
+
+/// One shipment on a manifest.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ManifestLine {
+    pub tracking_number: String,
+    pub order_id: i32,
+    pub service: String,
+    pub destination: String,
+    /// UN descriptions of dangerous goods in the package.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dangerous_goods: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ManifestRequest<'a> {
+    pub carrier: ShippingCarrier,
+    pub date: NaiveDate,
+    pub account_number: Option<&'a str>,
+    pub lines: &'a [ManifestLine],
+}
+
+/// What a carrier returned for a submitted manifest.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ManifestReceipt {
+    /// The carrier's manifest or pickup reference, if it issues one.
+    pub reference: Option<String>,
+}
+
+/// Submits end-of-day manifests to a carrier.
+#[async_trait]
+pub trait ManifestAdapter: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn submit(&self, request: &ManifestRequest<'_>) -> Result<ManifestReceipt, CarrierError>;
+}
+
+/// For carriers that only need the printed manifest at pickup.
+pub struct DocumentOnly;
+
+#[async_trait]
+impl ManifestAdapter for DocumentOnly {
+    fn name(&self) -> &'static str {
+        "document"
+    }
+
+    async fn submit(&self, _request: &ManifestRequest<'_>) -> Result<ManifestReceipt, CarrierError> {
+        Ok(ManifestReceipt { reference: None })
+    }
+}
+
+pub struct HttpManifestAdapter {
+    client: reqwest::Client,
+    endpoint: String,
+    api_key: Option<String>,
+}
+
+impl HttpManifestAdapter {
+    pub fn from_account(account: &ManifestAccount) -> Result<Self, CarrierError> {
+        let endpoint = account
+            .endpoint
+            .clone()
+            .ok_or_else(|| CarrierError::Misconfigured(format!("{:?} manifest endpoint is not set", account.carrier)))?;
+        let api_key = match &account.api_key_env {
+            Some(var) => Some(
+                std::env::var(var).map_err(|_| CarrierError::Misconfigured(format!("{} is not set", var)))?,
+            ),
+            None => None,
+        };
+        Ok(Self { client: reqwest::Client::new(), endpoint, api_key })
+    }
+}
+
+#[async_trait]
+impl ManifestAdapter for HttpManifestAdapter {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    async fn submit(&self, request: &ManifestRequest<'_>) -> Result<ManifestReceipt, CarrierError> {
+        #[derive(Deserialize)]
+        struct Receipt {
+            #[serde(default)]
+            reference: Option<String>,
+        }
+
+        let mut call = self.client.post(&self.endpoint).json(&json!({
+            "carrier": request.carrier,
+            "date": request.date,
+            "account_number": request.account_number,
+            "shipments": request.lines,
+        }));
+        if let Some(key) = &self.api_key {
+            call = call.bearer_auth(key);
+        }
+        let response = deadline::bounded(call).send().await.map_err(|e| CarrierError::Unavailable(e.to_string()))?;
+        let status = response.status();
+        if status.is_client_error() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(CarrierError::Rejected(format!("{}: {}", status, body)));
+        }
+        if !status.is_success() {
+            return Err(CarrierError::Unavailable(status.to_string()));
+        }
+        let receipt: Receipt = response.json().await.map_err(|e| CarrierError::Unavailable(e.to_string()))?;
+        Ok(ManifestReceipt { reference: receipt.reference })
+    }
+}
+
+/// Picks the manifest adapter and account for each carrier.
+pub struct CarrierRegistry {
+    accounts: Vec<(ManifestAccount, Arc<dyn ManifestAdapter>)>,
+}
+
+impl CarrierRegistry {
+    pub fn from_config(config: &CarriersConfig) -> Result<Self, CarrierError> {
+        let accounts = config
+            .manifests
+            .iter()
+            .map(|account| {
+                let adapter: Arc<dyn ManifestAdapter> = match account.adapter {
+                    ManifestAdapterKind::Document => Arc::new(DocumentOnly),
+                    ManifestAdapterKind::Http => Arc::new(HttpManifestAdapter::from_account(account)?),
+                };
+                Ok((account.clone(), adapter))
+            })
+            .collect::<Result<_, CarrierError>>()?;
+        Ok(Self { accounts })
+    }
+
+    /// The adapter and account number for `carrier`; the printed manifest only when the
+    /// carrier has no account configured.
+    pub fn manifest_adapter(&self, carrier: ShippingCarrier) -> (Arc<dyn ManifestAdapter>, Option<String>) {
+        self.accounts
+            .iter()
+            .find(|(account, _)| account.carrier == carrier)
+            .map(|(account, adapter)| (adapter.clone(), account.account_number.clone()))
+            .unwrap_or_else(|| (Arc::new(DocumentOnly), None))
+    }
+}
+
+/// The driver's copy: a plain-text manifest listing every shipment, with a count and
+/// signature lines.
+pub fn manifest_document(request: &ManifestRequest<'_>, reference: Option<&str>) -> String {
+    let mut doc = String::new();
+    let _ = writeln!(doc, "END OF DAY MANIFEST - {:?}", request.carrier);
+    let _ = writeln!(doc, "Date: {}", request.date);
+    if let Some(account) = request.account_number {
+        let _ = writeln!(doc, "Account: {}", account);
+    }
+    if let Some(reference) = reference {
+        let _ = writeln!(doc, "Carrier reference: {}", reference);
+    }
+    let _ = writeln!(doc);
+    let _ = writeln!(doc, "{:<4} {:<30} {:<10} {:<16} Destination", "#", "Tracking number", "Order", "Service");
+    for (i, line) in request.lines.iter().enumerate() {
+        let destination: String = line.destination.lines().next().unwrap_or("").chars().take(40).collect();
+        let _ = writeln!(
+            doc,
+            "{:<4} {:<30} {:<10} {:<16} {}",
+            i + 1,
+            line.tracking_number,
+            line.order_id,
+            line.service,
+            destination
+        );
+        for description in &line.dangerous_goods {
+            let _ = writeln!(doc, "{:<4} DANGEROUS GOODS: {}", "", description);
+        }
+    }
+    let _ = writeln!(doc);
+    let _ = writeln!(doc, "Total packages: {}", request.lines.len());
+    let dangerous = request.lines.iter().filter(|line| !line.dangerous_goods.is_empty()).count();
+    if dangerous > 0 {
+        let _ = writeln!(doc, "Dangerous goods packages: {}", dangerous);
+    }
+    let _ = writeln!(doc);
+    let _ = writeln!(doc, "Shipper signature: ______________________   Time: ________");
+    let _ = writeln!(doc, "Driver signature:  ______________________   Time: ________");
+    doc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines() -> Vec<ManifestLine> {
+        vec![
+            ManifestLine {
+                tracking_number: "1Z999AA10123456784".to_string(),
+                order_id: 1001,
+                service: "Ground".to_string(),
+                destination: "1 Market St, San Francisco".to_string(),
+                dangerous_goods: vec![],
+            },
+            ManifestLine {
+                tracking_number: "1Z999AA10123456785".to_string(),
+                order_id: 1002,
+                service: "Next Day".to_string(),
+                destination: "5 Main St\nSuite 2".to_string(),
+                dangerous_goods: vec!["UN1266, Perfume products, 3, PG II (Limited Quantity)".to_string()],
+            },
+        ]
+    }
+
+    #[test]
+    fn test_manifest_document_lists_every_package() {
+        let lines = lines();
+        let request = ManifestRequest {
+            carrier: ShippingCarrier::UPS,
+            date: NaiveDate::from_ymd_opt(2026, 10, 16).unwrap(),
+            account_number: Some("A1B2C3"),
+            lines: &lines,
+        };
+        let doc = manifest_document(&request, Some("MAN-77"));
+        assert!(doc.starts_with("END OF DAY MANIFEST - UPS\nDate: 2026-10-16\nAccount: A1B2C3\n"));
+        assert!(doc.contains("Carrier reference: MAN-77"));
+        assert!(doc.contains("1Z999AA10123456785"));
+        assert!(!doc.contains("Suite 2"));
+        assert!(doc.contains("DANGEROUS GOODS: UN1266, Perfume products, 3, PG II"));
+        assert!(doc.contains("Total packages: 2\nDangerous goods packages: 1\n"));
+    }
+
+    #[test]
+    fn test_unconfigured_carriers_get_document_only() {
+        let config: CarriersConfig = serde_json::from_value(json!({
+            "manifests": [{ "carrier": "FedEx", "adapter": "document", "account_number": "510087" }]
+        }))
+        .unwrap();
+        let registry = CarrierRegistry::from_config(&config).unwrap();
+        let (adapter, account) = registry.manifest_adapter(ShippingCarrier::FedEx);
+        assert_eq!((adapter.name(), account.as_deref()), ("document", Some("510087")));
+        let (adapter, account) = registry.manifest_adapter(ShippingCarrier::DHL);
+        assert_eq!((adapter.name(), account), ("document", None));
+    }
+
+    #[test]
+    fn test_http_adapter_needs_endpoint() {
+        let account = ManifestAccount {
+            carrier: ShippingCarrier::UPS,
+            adapter: ManifestAdapterKind::Http,
+            account_number: None,
+            endpoint: None,
+            api_key_env: None,
+        };
+        assert!(matches!(HttpManifestAdapter::from_account(&account), Err(CarrierError::Misconfigured(_))));
+    }
+}
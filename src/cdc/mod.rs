@@ -0,0 +1,636 @@
+// cdc/mod.rs
+
+use chrono::Utc;
+use lazy_static::lazy_static;
+use prometheus::IntCounterVec;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, ModelTrait,
+    QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use thiserror::Error;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::db::dialect;
+use crate::message_queue::{MessageQueue, MessageQueueError};
+use crate::models::cdc_offset::{self, Entity as CdcOffset};
+use crate::models::cdc_row_image::{self, Entity as CdcRowImage};
+use crate::models::webhook_event::{self, Entity as WebhookEvent};
+use crate::models::{credit_memo, dispute, order, return_entity, shipment, subscription, work_order};
+use crate::shutdown::Worker;
+use crate::webhooks::EVENTS_LOST_EVENT;
+
+/// The relay's row in `cdc_offsets`, also the advisory lock replicas take turns on.
+pub(crate) const RELAY_NAME: &str = "cdc_relay";
+
+/// Database and schema named in each record's `source`, as Debezium's Postgres connector
+/// names them.
+const SOURCE_DB: &str = "stateset";
+const SOURCE_SCHEMA: &str = "public";
+
+lazy_static! {
+    static ref CDC_RECORDS: IntCounterVec =
+        IntCounterVec::new(
+            "cdc_records_published_total",
+            "Change records published by table and operation",
+            &["table", "op"]
+        ).expect("metric can be created");
+}
+
+/// Change data capture settings, loaded from the `cdc` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CdcConfig {
+    /// Publishes row changes to the message queue (default: false). Every event is then
+    /// kept in the webhook outbox, not only those with subscribers.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Debezium's `topic.prefix`: changes to a table are published to the
+    /// `<prefix>.public.<table>` queue (default: `stateset`).
+    #[serde(default = "default_topic_prefix")]
+    pub topic_prefix: String,
+
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+
+    /// Outbox events read per poll (default: 500).
+    #[serde(default = "default_batch_size")]
+    pub batch_size: u64,
+
+    /// Events younger than this wait for the next poll, so ones still being written by
+    /// concurrent transactions are not skipped (default: 5 s).
+    #[serde(default = "default_settle_secs")]
+    pub settle_secs: u64,
+}
+
+fn default_topic_prefix() -> String {
+    "stateset".to_string()
+}
+
+fn default_poll_interval_secs() -> u64 {
+    5
+}
+
+fn default_batch_size() -> u64 {
+    500
+}
+
+fn default_settle_secs() -> u64 {
+    5
+}
+
+impl Default for CdcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            topic_prefix: default_topic_prefix(),
+            poll_interval_secs: default_poll_interval_secs(),
+            batch_size: default_batch_size(),
+            settle_secs: default_settle_secs(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum CdcError {
+    #[error("Message queue error: {0}")]
+    Queue(#[from] MessageQueueError),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+/// Debezium's operation codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Op {
+    #[serde(rename = "c")]
+    Create,
+    #[serde(rename = "u")]
+    Update,
+    #[serde(rename = "d")]
+    Delete,
+    /// A snapshot read, published when the feed re-syncs a table.
+    #[serde(rename = "r")]
+    Read,
+}
+
+impl Op {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Op::Create => "c",
+            Op::Update => "u",
+            Op::Delete => "d",
+            Op::Read => "r",
+        }
+    }
+}
+
+/// Tables the feed covers.
+const TABLES: &[Table] = &[
+    Table::Orders,
+    Table::Returns,
+    Table::Shipments,
+    Table::WorkOrders,
+    Table::CreditMemos,
+    Table::Subscriptions,
+    Table::Disputes,
+];
+
+/// A table the feed covers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Table {
+    Orders,
+    Returns,
+    Shipments,
+    WorkOrders,
+    CreditMemos,
+    Subscriptions,
+    Disputes,
+}
+
+impl Table {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Table::Orders => "orders",
+            Table::Returns => "returns",
+            Table::Shipments => "shipments",
+            Table::WorkOrders => "work_orders",
+            Table::CreditMemos => "credit_memos",
+            Table::Subscriptions => "subscriptions",
+            Table::Disputes => "disputes",
+        }
+    }
+
+    /// The row with primary key `key` as it is now, serialized like its model.
+    async fn load<C: ConnectionTrait>(&self, db: &C, key: &str) -> Result<Option<Value>, DbErr> {
+        fn image<M: Serialize>(model: Option<M>) -> Option<Value> {
+            model.and_then(|model| serde_json::to_value(model).ok())
+        }
+        Ok(match (self, key.parse::<Uuid>()) {
+            (Table::Shipments, _) => match key.parse::<i32>() {
+                Ok(id) => image(shipment::Entity::find_by_id(id).one(db).await?),
+                Err(_) => None,
+            },
+            (_, Err(_)) => None,
+            (Table::Orders, Ok(id)) => image(order::Entity::find_by_id(id).one(db).await?),
+            (Table::Returns, Ok(id)) => image(return_entity::Entity::find_by_id(id).one(db).await?),
+            (Table::WorkOrders, Ok(id)) => image(work_order::Entity::find_by_id(id).one(db).await?),
+            (Table::CreditMemos, Ok(id)) => image(credit_memo::Entity::find_by_id(id).one(db).await?),
+            (Table::Subscriptions, Ok(id)) => image(subscription::Entity::find_by_id(id).one(db).await?),
+            (Table::Disputes, Ok(id)) => image(dispute::Entity::find_by_id(id).one(db).await?),
+        })
+    }
+
+    /// Up to `limit` rows with primary keys after `after`, in key order, each with its key
+    /// as text and its image.
+    async fn page<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        after: Option<&str>,
+        limit: u64,
+    ) -> Result<Vec<(String, Value)>, DbErr> {
+        macro_rules! page {
+            ($entity:ident, $key:ty) => {{
+                let mut query = $entity::Entity::find();
+                if let Some(after) = after.and_then(|after| after.parse::<$key>().ok()) {
+                    query = query.filter($entity::Column::Id.gt(after));
+                }
+                query
+                    .order_by_asc($entity::Column::Id)
+                    .limit(limit)
+                    .all(db)
+                    .await?
+                    .into_iter()
+                    .filter_map(|row| Some((row.id.to_string(), serde_json::to_value(row).ok()?)))
+                    .collect()
+            }};
+        }
+        Ok(match self {
+            Table::Orders => page!(order, Uuid),
+            Table::Returns => page!(return_entity, Uuid),
+            Table::Shipments => page!(shipment, i32),
+            Table::WorkOrders => page!(work_order, Uuid),
+            Table::CreditMemos => page!(credit_memo, Uuid),
+            Table::Subscriptions => page!(subscription, Uuid),
+            Table::Disputes => page!(dispute, Uuid),
+        })
+    }
+}
+
+/// Outbox events that change a row: the table, the operation, and the field of the event
+/// data holding the row's id (`None` when the data is the id itself).
+const CHANGES: &[(&str, Table, Op, Option<&str>)] = &[
+    ("order_created", Table::Orders, Op::Create, None),
+    ("order_updated", Table::Orders, Op::Update, None),
+    ("order_cancelled", Table::Orders, Op::Update, None),
+    ("order_completed", Table::Orders, Op::Update, None),
+    ("order_refunded", Table::Orders, Op::Update, None),
+    ("order_tagged", Table::Orders, Op::Update, None),
+    ("order_split", Table::Orders, Op::Update, None),
+    ("order_exchanged", Table::Orders, Op::Update, None),
+    ("order_on_hold", Table::Orders, Op::Update, None),
+    ("order_released_from_hold", Table::Orders, Op::Update, None),
+    ("order_shipped", Table::Orders, Op::Update, None),
+    ("order_item_added", Table::Orders, Op::Update, None),
+    ("orders_merged", Table::Orders, Op::Update, None),
+    ("shipment_shipped", Table::Orders, Op::Update, Some("order_id")),
+    ("order_deleted", Table::Orders, Op::Delete, None),
+    ("return_created", Table::Returns, Op::Create, None),
+    ("return_initiated", Table::Returns, Op::Update, None),
+    ("return_approved", Table::Returns, Op::Update, None),
+    ("return_rejected", Table::Returns, Op::Update, None),
+    ("return_processed", Table::Returns, Op::Update, None),
+    ("return_completed", Table::Returns, Op::Update, None),
+    ("return_refunded", Table::Returns, Op::Update, None),
+    ("return_cancelled", Table::Returns, Op::Update, None),
+    ("return_closed", Table::Returns, Op::Update, None),
+    ("return_deleted", Table::Returns, Op::Delete, None),
+    ("shipment_booked", Table::Shipments, Op::Update, Some("shipment_id")),
+    ("work_order_created", Table::WorkOrders, Op::Create, None),
+    ("work_order_updated", Table::WorkOrders, Op::Update, None),
+    ("work_order_assigned", Table::WorkOrders, Op::Update, None),
+    ("work_order_unassigned", Table::WorkOrders, Op::Update, None),
+    ("work_order_started", Table::WorkOrders, Op::Update, None),
+    ("work_order_issued", Table::WorkOrders, Op::Update, None),
+    ("work_order_picked", Table::WorkOrders, Op::Update, None),
+    ("work_order_completed", Table::WorkOrders, Op::Update, None),
+    ("work_order_cancelled", Table::WorkOrders, Op::Update, None),
+    ("work_order_cost_rolled_up", Table::WorkOrders, Op::Update, Some("work_order_id")),
+    ("credit_memo_issued", Table::CreditMemos, Op::Update, Some("credit_memo_id")),
+    ("credit_memo_applied", Table::CreditMemos, Op::Update, Some("credit_memo_id")),
+    ("subscription_past_due", Table::Subscriptions, Op::Update, Some("subscription_id")),
+    ("subscription_recovered", Table::Subscriptions, Op::Update, Some("subscription_id")),
+    ("subscription_canceled", Table::Subscriptions, Op::Update, Some("subscription_id")),
+    ("dispute_opened", Table::Disputes, Op::Create, Some("dispute_id")),
+    ("dispute_closed", Table::Disputes, Op::Update, Some("dispute_id")),
+];
+
+/// A row changed by an outbox event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RowChange {
+    pub table: Table,
+    pub op: Op,
+    /// Primary key, as it appears in the row.
+    pub key: Value,
+}
+
+impl RowChange {
+    fn key_text(&self) -> String {
+        match &self.key {
+            Value::String(key) => key.clone(),
+            key => key.to_string(),
+        }
+    }
+}
+
+/// The row an outbox event changed, when it is in a table the feed covers.
+pub fn row_change(event_type: &str, data: &Value) -> Option<RowChange> {
+    let (_, table, op, field) = CHANGES.iter().find(|(name, ..)| *name == event_type)?;
+    let key = match field {
+        Some(field) => data.get(*field)?,
+        None => data,
+    };
+    if !(key.is_string() || key.is_number()) {
+        return None;
+    }
+    Some(RowChange { table: *table, op: *op, key: key.clone() })
+}
+
+/// A change event value in Debezium's envelope, as its JSON converter writes it with
+/// schemas disabled.
+pub fn envelope(
+    change: &RowChange,
+    before: Option<Value>,
+    after: Option<Value>,
+    event: &webhook_event::Model,
+    topic_prefix: &str,
+) -> Value {
+    json!({
+        "before": before,
+        "after": after,
+        "source": {
+            "version": env!("CARGO_PKG_VERSION"),
+            "connector": "stateset",
+            "name": topic_prefix,
+            "ts_ms": event.occurred_at.timestamp_millis(),
+            "snapshot": "false",
+            "db": SOURCE_DB,
+            "schema": SOURCE_SCHEMA,
+            "table": change.table.name(),
+            "event_id": event.id,
+            "event_type": event.event_type,
+        },
+        "op": change.op,
+        "ts_ms": Utc::now().timestamp_millis(),
+        "transaction": null,
+    })
+}
+
+/// Tails the webhook outbox and publishes a Debezium-style change record for each row an
+/// event changed, with the row before (its last published image) and after.
+pub struct CdcRelay<Q> {
+    db: Arc<DatabaseConnection>,
+    queue: Arc<Q>,
+    config: CdcConfig,
+}
+
+impl<Q: MessageQueue + 'static> CdcRelay<Q> {
+    pub fn new(db: Arc<DatabaseConnection>, queue: Arc<Q>, config: CdcConfig) -> Self {
+        Self { db, queue, config }
+    }
+
+    /// Queue that changes to `table` are published to.
+    pub fn topic(&self, table: Table) -> String {
+        format!("{}.{}.{}", self.config.topic_prefix, SOURCE_SCHEMA, table.name())
+    }
+
+    /// Publishes the changes of the next batch of settled outbox events and returns how
+    /// many events were read. Replicas take turns on an advisory lock; delivery is at least
+    /// once, as a poll that fails part way is retried from its last saved offset.
+    pub async fn poll(&self) -> Result<usize, CdcError> {
+        let txn = self.db.begin().await?;
+        dialect::advisory_xact_lock(&txn, RELAY_NAME).await?;
+
+        let offset = CdcOffset::find_by_id(RELAY_NAME.to_string()).one(&txn).await?;
+        let settled = Utc::now() - chrono::Duration::seconds(self.config.settle_secs as i64);
+        let mut query = WebhookEvent::find().filter(webhook_event::Column::OccurredAt.lt(settled));
+        if let Some(offset) = &offset {
+            query = query.filter(
+                Condition::any()
+                    .add(webhook_event::Column::OccurredAt.gt(offset.occurred_at))
+                    .add(
+                        Condition::all()
+                            .add(webhook_event::Column::OccurredAt.eq(offset.occurred_at))
+                            .add(webhook_event::Column::Id.gt(offset.event_id)),
+                    ),
+            );
+        }
+        let events = query
+            .order_by_asc(webhook_event::Column::OccurredAt)
+            .order_by_asc(webhook_event::Column::Id)
+            .limit(self.config.batch_size)
+            .all(&txn)
+            .await?;
+
+        let mut read = 0;
+        let mut failure = None;
+        for event in &events {
+            if let Err(e) = self.relay(&txn, event).await {
+                failure = Some(e);
+                break;
+            }
+            read += 1;
+        }
+        if let Some(last) = events[..read].last() {
+            let saved = cdc_offset::ActiveModel {
+                name: Set(RELAY_NAME.to_string()),
+                occurred_at: Set(last.occurred_at),
+                event_id: Set(last.id),
+                updated_at: Set(Utc::now()),
+            };
+            if offset.is_some() {
+                saved.update(&txn).await?;
+            } else {
+                saved.insert(&txn).await?;
+            }
+        }
+        txn.commit().await?;
+        match failure {
+            Some(e) => Err(e),
+            None => Ok(read),
+        }
+    }
+
+    /// Publishes the row change of one outbox event, if it has one.
+    async fn relay<C: ConnectionTrait>(&self, db: &C, event: &webhook_event::Model) -> Result<(), CdcError> {
+        if event.event_type == EVENTS_LOST_EVENT {
+            warn!(lost = %event.payload["data"], "Outbox lost events; re-syncing the change feed");
+            let published = self.resync(db, event).await?;
+            info!(published, "Change feed re-synced");
+            return Ok(());
+        }
+        let Some(change) = row_change(&event.event_type, &event.payload["data"]) else {
+            return Ok(());
+        };
+        let key = change.key_text();
+        let image = CdcRowImage::find_by_id((change.table.name().to_string(), key.clone())).one(db).await?;
+        let after = match change.op {
+            Op::Delete => None,
+            // Order events carry the order as it was when they happened
+            _ => match event.payload.get("entity").filter(|e| change.table == Table::Orders && !e.is_null()) {
+                Some(entity) => Some(entity.clone()),
+                None => change.table.load(db, &key).await?,
+            },
+        };
+        if change.op != Op::Delete && after.is_none() {
+            debug!(table = change.table.name(), key = %key, "Changed row is gone; its delete is published instead");
+            return Ok(());
+        }
+        // Without a previous image a delete carries only the key, like Debezium's with the
+        // default replica identity
+        let before = match (&image, change.op) {
+            (Some(image), _) => Some(image.image.clone()),
+            (None, Op::Delete) => Some(json!({ "id": change.key })),
+            (None, _) => None,
+        };
+        if before == after {
+            return Ok(());
+        }
+
+        self.publish(db, &change, key, image, before, after, event).await
+    }
+
+    /// Publishes a change record and keeps `after` as the row's image for its next one.
+    #[allow(clippy::too_many_arguments)]
+    async fn publish<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        change: &RowChange,
+        key: String,
+        image: Option<cdc_row_image::Model>,
+        before: Option<Value>,
+        after: Option<Value>,
+        event: &webhook_event::Model,
+    ) -> Result<(), CdcError> {
+        let record = envelope(change, before, after.clone(), event, &self.config.topic_prefix);
+        self.queue.publish(&self.topic(change.table), &record).await?;
+        CDC_RECORDS.with_label_values(&[change.table.name(), change.op.code()]).inc();
+
+        match (after, image) {
+            (Some(after), Some(image)) => {
+                let mut image: cdc_row_image::ActiveModel = image.into();
+                image.image = Set(after);
+                image.updated_at = Set(Utc::now());
+                image.update(db).await?;
+            }
+            (Some(after), None) => {
+                cdc_row_image::ActiveModel {
+                    table_name: Set(change.table.name().to_string()),
+                    row_key: Set(key),
+                    image: Set(after),
+                    updated_at: Set(Utc::now()),
+                }
+                .insert(db)
+                .await?;
+            }
+            (None, Some(image)) => {
+                image.delete(db).await?;
+            }
+            (None, None) => {}
+        }
+        Ok(())
+    }
+
+    /// Brings every covered table's feed back in line after the outbox lost events, whose
+    /// changes were never published. Rows whose image differs from the last one published
+    /// go out as snapshot reads, and rows that are gone as deletes. Returns how many
+    /// records were published.
+    async fn resync<C: ConnectionTrait>(&self, db: &C, marker: &webhook_event::Model) -> Result<u64, CdcError> {
+        let mut published = 0;
+        for table in TABLES {
+            let mut after = None;
+            loop {
+                let rows = table.page(db, after.as_deref(), self.config.batch_size).await?;
+                let Some((last, _)) = rows.last() else {
+                    break;
+                };
+                after = Some(last.clone());
+                let mut images: HashMap<String, cdc_row_image::Model> = CdcRowImage::find()
+                    .filter(cdc_row_image::Column::TableName.eq(table.name()))
+                    .filter(cdc_row_image::Column::RowKey.is_in(rows.iter().map(|(key, _)| key.clone())))
+                    .all(db)
+                    .await?
+                    .into_iter()
+                    .map(|image| (image.row_key.clone(), image))
+                    .collect();
+                for (key, row) in rows {
+                    let image = images.remove(&key);
+                    if image.as_ref().map(|image| &image.image) == Some(&row) {
+                        continue;
+                    }
+                    let change = RowChange { table: *table, op: Op::Read, key: Value::String(key.clone()) };
+                    self.publish(db, &change, key, image, None, Some(row), marker).await?;
+                    published += 1;
+                }
+            }
+
+            let mut after = String::new();
+            loop {
+                let images = CdcRowImage::find()
+                    .filter(cdc_row_image::Column::TableName.eq(table.name()))
+                    .filter(cdc_row_image::Column::RowKey.gt(after.clone()))
+                    .order_by_asc(cdc_row_image::Column::RowKey)
+                    .limit(self.config.batch_size)
+                    .all(db)
+                    .await?;
+                let Some(last) = images.last() else {
+                    break;
+                };
+                after = last.row_key.clone();
+                for image in images {
+                    if table.load(db, &image.row_key).await?.is_some() {
+                        continue;
+                    }
+                    let key = image.row_key.clone();
+                    let change = RowChange { table: *table, op: Op::Delete, key: Value::String(key.clone()) };
+                    let before = Some(image.image.clone());
+                    self.publish(db, &change, key, Some(image), before, None, marker).await?;
+                    published += 1;
+                }
+            }
+        }
+        Ok(published)
+    }
+}
+
+/// Polls the outbox every `poll_interval_secs`, draining a backlog batch after batch.
+/// On shutdown it stops after the batch in flight; unpublished rows stay in the outbox.
+pub fn spawn_relay<Q: MessageQueue + 'static>(relay: Arc<CdcRelay<Q>>, mut worker: Worker) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(relay.config.poll_interval_secs.max(1)));
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = worker.stopping() => break,
+            }
+            while !worker.is_stopping() {
+                match relay.poll().await {
+                    Ok(read) if read as u64 >= relay.config.batch_size => {
+                        info!(events = read, "CDC relay caught up a batch; continuing");
+                    }
+                    Ok(_) => break,
+                    Err(e) => {
+                        error!("CDC relay poll failed: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_row_change_from_event_data() {
+        let id = Uuid::new_v4();
+        assert_eq!(
+            row_change("order_created", &json!(id)),
+            Some(RowChange { table: Table::Orders, op: Op::Create, key: json!(id) })
+        );
+        assert_eq!(
+            row_change("shipment_booked", &json!({ "shipment_id": 42, "tracking_number": "1Z" })),
+            Some(RowChange { table: Table::Shipments, op: Op::Update, key: json!(42) })
+        );
+        assert_eq!(row_change("return_deleted", &json!(id)).map(|c| c.op), Some(Op::Delete));
+        // Not a row in a covered table
+        assert_eq!(row_change("inventory_adjusted", &json!({ "product_id": id, "adjustment": 1 })), None);
+        assert_eq!(row_change("dispute_closed", &json!({ "won": true })), None);
+    }
+
+    #[test]
+    fn test_envelope_is_debezium_shaped() {
+        let id = Uuid::new_v4();
+        let event = webhook_event::Model {
+            id: Uuid::new_v4(),
+            event_type: "order_updated".to_string(),
+            payload: json!({}),
+            occurred_at: Utc::now(),
+        };
+        let change = RowChange { table: Table::Orders, op: Op::Update, key: json!(id) };
+        let record = envelope(
+            &change,
+            Some(json!({ "id": id, "status": "pending" })),
+            Some(json!({ "id": id, "status": "shipped" })),
+            &event,
+            "stateset",
+        );
+        assert_eq!(record["op"], "u");
+        assert_eq!(record["before"]["status"], "pending");
+        assert_eq!(record["after"]["status"], "shipped");
+        assert_eq!(record["source"]["table"], "orders");
+        assert_eq!(record["source"]["ts_ms"], event.occurred_at.timestamp_millis());
+        assert!(record["transaction"].is_null());
+    }
+
+    #[test]
+    fn test_resync_records_are_snapshot_reads() {
+        let event = webhook_event::Model {
+            id: Uuid::new_v4(),
+            event_type: EVENTS_LOST_EVENT.to_string(),
+            payload: json!({ "data": { "missed": 3 } }),
+            occurred_at: Utc::now(),
+        };
+        let change = RowChange { table: Table::Shipments, op: Op::Read, key: json!("42") };
+        let record = envelope(&change, None, Some(json!({ "id": 42 })), &event, "stateset");
+        assert_eq!(record["op"], "r");
+        assert!(record["before"].is_null());
+        assert_eq!(record["source"]["event_type"], EVENTS_LOST_EVENT);
+    }
+}
@@ -0,0 +1,342 @@
+// change_feed/mod.rs
+
+//! Incremental sync for offline and mobile clients. List endpoints given
+//! `?updated_since=` return what changed since then instead of a page of the collection:
+//! records written at or after that instant, and tombstones for records deleted since.
+//!
+//! Changes are tracked in the database so every write path is covered: a trigger stamps
+//! `sync_updated_at` on each insert and update, and a delete trigger records a row in
+//! `sync_tombstones`. Tombstones should be purged by a `retention` policy that keeps them
+//! at least [`TOMBSTONE_RETENTION_DAYS`]; clients asking for older changes must resync.
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{DatabaseConnection, DbErr, FromQueryResult};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{fmt::Display, future::Future, str::FromStr};
+use thiserror::Error;
+use tracing::error;
+
+use crate::db::dialect::{self, Dialect};
+
+/// Changes returned when the request names no limit.
+const DEFAULT_LIMIT: u64 = 500;
+
+const MAX_LIMIT: u64 = 1000;
+
+/// Changes newer than this are held back until transactions that started before them
+/// have had time to commit, so a client never skips a row that commits late.
+const SETTLE_SECS: i64 = 5;
+
+/// How long tombstones are kept; `updated_since` may not be older than this.
+pub const TOMBSTONE_RETENTION_DAYS: i64 = 90;
+
+/// Collections that can be synced incrementally.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncEntity {
+    Order,
+    Product,
+    Inventory,
+    Customer,
+}
+
+impl SyncEntity {
+    /// Name recorded in `sync_tombstones.entity`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncEntity::Order => "order",
+            SyncEntity::Product => "product",
+            SyncEntity::Inventory => "inventory",
+            SyncEntity::Customer => "customer",
+        }
+    }
+
+    fn table(&self) -> &'static str {
+        match self {
+            SyncEntity::Order => "orders",
+            SyncEntity::Product => "product_listings",
+            SyncEntity::Inventory => "inventory_items",
+            SyncEntity::Customer => "customers",
+        }
+    }
+
+    fn key(&self) -> &'static str {
+        match self {
+            SyncEntity::Product => "sku",
+            _ => "id",
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ChangeFeedError {
+    #[error("Changes are only kept for {TOMBSTONE_RETENTION_DAYS} days; download the collection again")]
+    ResyncRequired,
+
+    #[error("Incremental sync requires Postgres")]
+    Unsupported,
+
+    #[error("Could not load changed records: {0}")]
+    Load(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for ChangeFeedError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            ChangeFeedError::ResyncRequired => (StatusCode::GONE, "RESYNC_REQUIRED"),
+            ChangeFeedError::Unsupported => (StatusCode::NOT_IMPLEMENTED, "SYNC_UNSUPPORTED"),
+            ChangeFeedError::Load(_) | ChangeFeedError::Database(_) => {
+                error!("Change feed failed: {}", self);
+                (StatusCode::INTERNAL_SERVER_ERROR, "SYNC_FAILED")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// `?updated_since=&after=&limit=` query parameters of list endpoints.
+#[derive(Debug, Default, Deserialize)]
+pub struct SyncParams {
+    /// Returns changes at or after this instant instead of a page of the collection.
+    pub updated_since: Option<DateTime<Utc>>,
+
+    /// Key of the last change already received at `updated_since`, from `next_after`.
+    pub after: Option<String>,
+
+    /// Changes per response (default: 500, at most 1000).
+    pub limit: Option<u64>,
+}
+
+impl SyncParams {
+    fn limit(&self) -> u64 {
+        self.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, FromQueryResult)]
+pub struct ChangeRow {
+    /// `upsert` or `delete`.
+    pub kind: String,
+    pub record_id: String,
+    pub changed_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Tombstone {
+    pub id: String,
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// One response of the feed. Clients apply `changes` and `tombstones`, then pass
+/// `next_updated_since` and `next_after` on the next request: immediately while
+/// `has_more`, otherwise at the next sync.
+#[derive(Debug, Serialize)]
+pub struct SyncPage<T> {
+    pub changes: Vec<T>,
+    pub tombstones: Vec<Tombstone>,
+    pub has_more: bool,
+    pub next_updated_since: DateTime<Utc>,
+    pub next_after: Option<String>,
+}
+
+/// Keys of the changed records and the deleted ones, oldest change first.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ChangeSet {
+    pub upserted: Vec<String>,
+    pub tombstones: Vec<Tombstone>,
+    pub has_more: bool,
+    pub next_updated_since: DateTime<Utc>,
+    pub next_after: Option<String>,
+}
+
+/// Upserts and tombstones of `entity` in `[since, until)` after the `(since, after)`
+/// position, ordered by change time then key. `$1` since, `$2` after, `$3` until,
+/// `$4` entity name, `$5` limit.
+pub fn changes_sql(entity: SyncEntity) -> String {
+    format!(
+        "SELECT kind, record_id, changed_at FROM ( \
+         SELECT 'upsert' AS kind, {key}::text AS record_id, sync_updated_at AS changed_at FROM {table} \
+         WHERE sync_updated_at >= $1 AND sync_updated_at < $3 \
+         UNION ALL \
+         SELECT 'delete' AS kind, record_id, deleted_at AS changed_at FROM sync_tombstones \
+         WHERE entity = $4 AND deleted_at >= $1 AND deleted_at < $3 \
+         ) changes \
+         WHERE (changed_at, record_id) > ($1, $2) \
+         ORDER BY changed_at, record_id \
+         LIMIT $5",
+        key = entity.key(),
+        table = entity.table(),
+    )
+}
+
+/// Splits fetched rows into a change set. `rows` holds up to `limit + 1` changes; the
+/// extra one only says there are more. Without more, the next sync starts at `until`.
+pub fn change_set(
+    mut rows: Vec<ChangeRow>,
+    limit: usize,
+    since: DateTime<Utc>,
+    after: Option<String>,
+    until: DateTime<Utc>,
+) -> ChangeSet {
+    let has_more = rows.len() > limit;
+    rows.truncate(limit);
+    let (next_updated_since, next_after) = match rows.last() {
+        Some(last) if has_more => (last.changed_at, Some(last.record_id.clone())),
+        _ if until > since => (until, None),
+        // Nothing has settled since the last sync
+        _ => (since, after),
+    };
+    let mut upserted = Vec::new();
+    let mut tombstones = Vec::new();
+    for row in rows {
+        if row.kind == "delete" {
+            tombstones.push(Tombstone { id: row.record_id, deleted_at: row.changed_at });
+        } else {
+            upserted.push(row.record_id);
+        }
+    }
+    ChangeSet { upserted, tombstones, has_more, next_updated_since, next_after }
+}
+
+#[derive(FromQueryResult)]
+struct DatabaseNow {
+    now: DateTime<Utc>,
+}
+
+/// Changes to `entity` since `since`, read against the database clock, which is the one
+/// the triggers stamp rows with.
+pub async fn changes(
+    db: &DatabaseConnection,
+    entity: SyncEntity,
+    since: DateTime<Utc>,
+    params: &SyncParams,
+) -> Result<ChangeSet, ChangeFeedError> {
+    if Dialect::of(db) != Dialect::Postgres {
+        return Err(ChangeFeedError::Unsupported);
+    }
+    let now = DatabaseNow::find_by_statement(dialect::raw(db, "SELECT NOW() AS now"))
+        .one(db)
+        .await?
+        .map(|row| row.now)
+        .unwrap_or_else(Utc::now);
+    if since < now - Duration::days(TOMBSTONE_RETENTION_DAYS) {
+        return Err(ChangeFeedError::ResyncRequired);
+    }
+    let until = now - Duration::seconds(SETTLE_SECS);
+    let limit = params.limit();
+    let after = params.after.clone().unwrap_or_default();
+    let rows = ChangeRow::find_by_statement(dialect::statement(
+        db,
+        &changes_sql(entity),
+        [
+            since.into(),
+            after.into(),
+            until.into(),
+            entity.as_str().into(),
+            ((limit + 1) as i64).into(),
+        ],
+    ))
+    .all(db)
+    .await?;
+    Ok(change_set(rows, limit as usize, since, params.after.clone(), until))
+}
+
+/// Serves one page of the feed, with the changed records loaded by `load` from their
+/// parsed keys. Records deleted between the two reads are left out; their tombstones
+/// arrive with the next sync.
+pub async fn sync_response<K, T, E, F, Fut>(
+    db: &DatabaseConnection,
+    entity: SyncEntity,
+    since: DateTime<Utc>,
+    params: &SyncParams,
+    load: F,
+) -> Result<Response, ChangeFeedError>
+where
+    K: FromStr,
+    T: Serialize,
+    E: Display,
+    F: FnOnce(Vec<K>) -> Fut,
+    Fut: Future<Output = Result<Vec<T>, E>>,
+{
+    let set = changes(db, entity, since, params).await?;
+    let keys = set.upserted.iter().filter_map(|key| key.parse().ok()).collect();
+    let changes = if set.upserted.is_empty() {
+        Vec::new()
+    } else {
+        load(keys).await.map_err(|e| ChangeFeedError::Load(e.to_string()))?
+    };
+    Ok(Json(SyncPage {
+        changes,
+        tombstones: set.tombstones,
+        has_more: set.has_more,
+        next_updated_since: set.next_updated_since,
+        next_after: set.next_after,
+    })
+    .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, second).unwrap()
+    }
+
+    fn row(kind: &str, id: &str, second: u32) -> ChangeRow {
+        ChangeRow { kind: kind.to_string(), record_id: id.to_string(), changed_at: at(second) }
+    }
+
+    #[test]
+    fn test_full_page_continues_from_its_last_change() {
+        let rows = vec![row("upsert", "a", 1), row("delete", "b", 1), row("upsert", "c", 2)];
+        let set = change_set(rows, 2, at(0), None, at(30));
+        assert!(set.has_more);
+        assert_eq!(set.upserted, vec!["a"]);
+        assert_eq!(set.tombstones, vec![Tombstone { id: "b".to_string(), deleted_at: at(1) }]);
+        assert_eq!((set.next_updated_since, set.next_after.as_deref()), (at(1), Some("b")));
+    }
+
+    #[test]
+    fn test_last_page_continues_from_the_settled_bound() {
+        let set = change_set(vec![row("upsert", "a", 1)], 2, at(0), Some("z".to_string()), at(30));
+        assert!(!set.has_more);
+        assert_eq!((set.next_updated_since, set.next_after), (at(30), None));
+
+        // Polling again before anything settles keeps the client where it was
+        let set = change_set(Vec::new(), 2, at(40), Some("z".to_string()), at(30));
+        assert_eq!((set.next_updated_since, set.next_after.as_deref()), (at(40), Some("z")));
+    }
+
+    #[test]
+    fn test_changes_sql_reads_the_entity_table_and_its_tombstones() {
+        let sql = changes_sql(SyncEntity::Product);
+        assert!(sql.contains("sku::text AS record_id"));
+        assert!(sql.contains("FROM product_listings"));
+        assert!(sql.contains("FROM sync_tombstones"));
+        assert!(sql.contains("ORDER BY changed_at, record_id"));
+    }
+
+    #[test]
+    fn test_limit_is_clamped() {
+        assert_eq!(SyncParams::default().limit(), DEFAULT_LIMIT);
+        assert_eq!(SyncParams { limit: Some(0), ..Default::default() }.limit(), 1);
+        assert_eq!(SyncParams { limit: Some(50_000), ..Default::default() }.limit(), MAX_LIMIT);
+    }
+
+    #[test]
+    fn test_migration_passes_migration_checks() {
+        let sql = include_str!("../../migrations/20261016150000_change_feed.sql");
+        assert_eq!(crate::db::migration_safety::analyze("change_feed.sql", sql).violations().count(), 0);
+    }
+}
@@ -0,0 +1,762 @@
+// checkout/mod.rs
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use rust_decimal::Decimal;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait,
+    FromQueryResult, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use thiserror::Error;
+use tracing::{error, warn};
+use uuid::Uuid;
+
+use crate::cache::{Cache, RedisCache};
+use crate::models::checkout_session::{self, CheckoutStatus, Entity as CheckoutSession};
+use crate::models::inventory_items;
+use crate::models::product_listing::{self, Entity as ProductListing};
+use crate::models::vault_token::TokenStatus;
+use crate::services::payment_vault::{PaymentVaultService, Redemption};
+use crate::utils::pagination::PaginationParams;
+
+/// Checkout session settings, loaded from the `checkout` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CheckoutConfig {
+    /// How long an untouched open session stays resumable (default: 24 hours).
+    #[serde(default = "default_session_ttl_secs")]
+    pub session_ttl_secs: u64,
+
+    /// Serve session reads from Redis (default: true). The database stays authoritative.
+    #[serde(default = "default_cache_sessions")]
+    pub cache_sessions: bool,
+
+    /// Re-check the cart against current prices and stock before a session completes
+    /// (default: true). Clients can always run the check themselves via `/validate`.
+    #[serde(default = "default_validate_on_complete")]
+    pub validate_on_complete: bool,
+}
+
+fn default_session_ttl_secs() -> u64 {
+    24 * 60 * 60
+}
+
+fn default_cache_sessions() -> bool {
+    true
+}
+
+fn default_validate_on_complete() -> bool {
+    true
+}
+
+impl Default for CheckoutConfig {
+    fn default() -> Self {
+        Self {
+            session_ttl_secs: default_session_ttl_secs(),
+            cache_sessions: default_cache_sessions(),
+            validate_on_complete: default_validate_on_complete(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum CheckoutError {
+    #[error("Checkout session not found: {0}")]
+    NotFound(Uuid),
+
+    #[error("Checkout session {0} is {1:?} and can no longer be changed")]
+    Closed(Uuid, CheckoutStatus),
+
+    #[error("Checkout session {id} was changed elsewhere (now at version {current})")]
+    VersionConflict { id: Uuid, current: i32 },
+
+    #[error("Invalid cart items: {0}")]
+    InvalidItems(String),
+
+    #[error("Invalid payment: {0}")]
+    Payment(String),
+
+    #[error("Cart of checkout session {} changed since it was priced", .0.session_id)]
+    CartChanged(Box<CartValidation>),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for CheckoutError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            CheckoutError::NotFound(_) => (StatusCode::NOT_FOUND, "checkout_session_not_found"),
+            CheckoutError::Closed(..) => (StatusCode::CONFLICT, "checkout_session_closed"),
+            CheckoutError::VersionConflict { .. } => (StatusCode::CONFLICT, "version_conflict"),
+            CheckoutError::InvalidItems(_) => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_cart_items"),
+            CheckoutError::Payment(_) => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_payment"),
+            CheckoutError::CartChanged(validation) => {
+                let body = json!({ "error": self.to_string(), "code": "cart_changed", "validation": validation });
+                return (StatusCode::CONFLICT, Json(body)).into_response();
+            }
+            CheckoutError::Database(e) => {
+                error!("Checkout session query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "checkout_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewCheckoutSession {
+    pub customer_id: Option<i32>,
+    #[serde(default = "empty_array")]
+    pub items: Value,
+    #[serde(default = "default_currency")]
+    pub currency: String,
+    #[serde(default = "empty_object")]
+    pub metadata: Value,
+}
+
+fn empty_array() -> Value {
+    json!([])
+}
+
+fn empty_object() -> Value {
+    json!({})
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+/// Changes to an open session. Omitted fields are left as they are.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CheckoutSessionPatch {
+    /// Version the client last saw; the update is rejected if the session moved on.
+    pub version: i32,
+    pub customer_id: Option<i32>,
+    pub items: Option<Value>,
+    pub shipping_address: Option<Value>,
+    pub payment: Option<Value>,
+    pub metadata: Option<Value>,
+}
+
+/// The session as clients see it; open sessions past their expiry read as expired.
+pub fn effective(mut session: checkout_session::Model, now: DateTime<Utc>) -> checkout_session::Model {
+    if session.status.is_open() && session.expires_at <= now {
+        session.status = CheckoutStatus::Expired;
+    }
+    session
+}
+
+/// Applies `patch` on top of `session`, bumping the version and sliding the expiry.
+pub fn apply_patch(
+    session: &checkout_session::Model,
+    patch: CheckoutSessionPatch,
+    now: DateTime<Utc>,
+    ttl: ChronoDuration,
+) -> checkout_session::Model {
+    let mut next = session.clone();
+    if let Some(customer_id) = patch.customer_id {
+        next.customer_id = Some(customer_id);
+    }
+    if let Some(items) = patch.items {
+        next.items = items;
+    }
+    if let Some(address) = patch.shipping_address {
+        next.shipping_address = Some(address);
+    }
+    if let Some(payment) = patch.payment {
+        next.payment = Some(payment);
+    }
+    if let Some(metadata) = patch.metadata {
+        next.metadata = metadata;
+    }
+    next.version += 1;
+    next.updated_at = now;
+    next.expires_at = now + ttl;
+    next
+}
+
+fn closed(session: &checkout_session::Model, status: CheckoutStatus, order_id: Option<Uuid>) -> checkout_session::Model {
+    let mut next = session.clone();
+    next.status = status;
+    next.order_id = order_id;
+    next.version += 1;
+    next.updated_at = Utc::now();
+    next
+}
+
+/// A cart line as stored in `items`. `unit_price` is the price the shopper was shown;
+/// lines without one are priced from the listing and never report a price change.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CartItem {
+    pub sku: String,
+    pub quantity: i32,
+    #[serde(default, with = "crate::money::option_amount", skip_serializing_if = "Option::is_none")]
+    pub unit_price: Option<Decimal>,
+}
+
+/// Parses the session's `items`, rejecting lines without a SKU or a positive quantity.
+pub fn cart_items(items: &Value) -> Result<Vec<CartItem>, CheckoutError> {
+    let lines: Vec<CartItem> =
+        serde_json::from_value(items.clone()).map_err(|e| CheckoutError::InvalidItems(e.to_string()))?;
+    if let Some(line) = lines.iter().find(|l| l.sku.trim().is_empty() || l.quantity <= 0) {
+        return Err(CheckoutError::InvalidItems(format!(
+            "line '{}' needs a SKU and a positive quantity",
+            line.sku
+        )));
+    }
+    Ok(lines)
+}
+
+/// Why a line can no longer be bought at all.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnavailableReason {
+    /// No listing for the SKU.
+    NotListed,
+    /// The listing was deactivated.
+    Inactive,
+    /// The listing is priced in another currency than the session.
+    CurrencyMismatch,
+}
+
+/// One difference between the cart as the shopper saw it and current data.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CartChange {
+    PriceChanged {
+        sku: String,
+        #[serde(with = "crate::money::amount")]
+        previous: Decimal,
+        #[serde(with = "crate::money::amount")]
+        current: Decimal,
+    },
+    InsufficientStock { sku: String, requested: i32, available: i32 },
+    OutOfStock { sku: String, requested: i32 },
+    Unavailable { sku: String, reason: UnavailableReason },
+}
+
+/// Result of a pre-flight check. `items` is the cart repriced and trimmed to what can be
+/// bought now, ready to be sent back as a patch once the shopper accepts the changes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CartValidation {
+    pub session_id: Uuid,
+    pub version: i32,
+    pub valid: bool,
+    pub changes: Vec<CartChange>,
+    pub items: Vec<CartItem>,
+    #[serde(with = "crate::money::amount")]
+    pub subtotal: Decimal,
+    pub currency: String,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// What a SKU sells for and how many units can be sold right now.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SkuOffer {
+    pub price: Decimal,
+    pub currency: String,
+    pub active: bool,
+    pub available: i64,
+}
+
+/// Compares `items` against current offers. Quantities of the same SKU on several lines
+/// draw on the same stock, in cart order.
+pub fn diff_cart(
+    items: &[CartItem],
+    offers: &HashMap<String, SkuOffer>,
+    currency: &str,
+) -> (Vec<CartChange>, Vec<CartItem>) {
+    let mut changes = Vec::new();
+    let mut adjusted = Vec::new();
+    let mut remaining: HashMap<&str, i64> = offers.iter().map(|(sku, o)| (sku.as_str(), o.available.max(0))).collect();
+
+    for item in items {
+        let sku = item.sku.clone();
+        let Some(offer) = offers.get(&item.sku) else {
+            changes.push(CartChange::Unavailable { sku, reason: UnavailableReason::NotListed });
+            continue;
+        };
+        if !offer.active {
+            changes.push(CartChange::Unavailable { sku, reason: UnavailableReason::Inactive });
+            continue;
+        }
+        if !offer.currency.eq_ignore_ascii_case(currency) {
+            changes.push(CartChange::Unavailable { sku, reason: UnavailableReason::CurrencyMismatch });
+            continue;
+        }
+        if let Some(previous) = item.unit_price {
+            if previous != offer.price {
+                changes.push(CartChange::PriceChanged { sku: sku.clone(), previous, current: offer.price });
+            }
+        }
+
+        let left = remaining.entry(item.sku.as_str()).or_default();
+        let quantity = i64::from(item.quantity).min(*left) as i32;
+        *left -= i64::from(quantity);
+        if quantity == 0 {
+            changes.push(CartChange::OutOfStock { sku, requested: item.quantity });
+            continue;
+        }
+        if quantity < item.quantity {
+            changes.push(CartChange::InsufficientStock { sku: sku.clone(), requested: item.quantity, available: quantity });
+        }
+        adjusted.push(CartItem { sku, quantity, unit_price: Some(offer.price) });
+    }
+    (changes, adjusted)
+}
+
+#[derive(Debug, FromQueryResult)]
+struct StockLevel {
+    sku: String,
+    available: Option<i64>,
+}
+
+/// Database-backed checkout sessions with optional Redis read-through. Writes go to the
+/// database first and then refresh the cache; cache errors are logged and never fail a
+/// request.
+pub struct CheckoutSessionStore {
+    db: Arc<DatabaseConnection>,
+    cache: Option<Arc<RedisCache>>,
+    ttl: ChronoDuration,
+    validate_on_complete: bool,
+    vault: Option<Arc<PaymentVaultService>>,
+}
+
+impl CheckoutSessionStore {
+    pub fn new(db: Arc<DatabaseConnection>, cache: Option<Arc<RedisCache>>, config: &CheckoutConfig) -> Self {
+        Self {
+            db,
+            cache,
+            ttl: ChronoDuration::seconds(config.session_ttl_secs as i64),
+            validate_on_complete: config.validate_on_complete,
+            vault: None,
+        }
+    }
+
+    /// Lets sessions pay with payment vault tokens.
+    pub fn with_vault(mut self, vault: Option<Arc<PaymentVaultService>>) -> Self {
+        self.vault = vault;
+        self
+    }
+
+    /// Replaces a `vault_token` in the payment with the token's id and card display
+    /// details, so the bearer token itself is never stored with the session.
+    async fn resolve_payment(&self, session_id: Uuid, mut payment: Value) -> Result<Value, CheckoutError> {
+        let Some(token) = payment.get("vault_token").and_then(Value::as_str).map(str::to_string) else {
+            return Ok(payment);
+        };
+        let vault = self
+            .vault
+            .as_ref()
+            .ok_or_else(|| CheckoutError::Payment("the payment vault is not enabled".to_string()))?;
+        let record = vault.lookup(&token).await.map_err(|e| CheckoutError::Payment(e.to_string()))?;
+        if record.status != TokenStatus::Active {
+            return Err(CheckoutError::Payment(format!("vault token {} is {:?}", record.token_prefix, record.status)));
+        }
+        if record.checkout_session_id.is_some_and(|bound| bound != session_id) {
+            return Err(CheckoutError::Payment(format!(
+                "vault token {} is restricted to another checkout",
+                record.token_prefix
+            )));
+        }
+        if let Some(fields) = payment.as_object_mut() {
+            fields.remove("vault_token");
+            fields.insert("vault_token_id".to_string(), json!(record.id));
+            fields.insert("brand".to_string(), json!(record.brand));
+            fields.insert("last4".to_string(), json!(record.last4));
+        }
+        Ok(payment)
+    }
+
+    fn cache_key(id: Uuid) -> String {
+        format!("checkout_session:{}", id)
+    }
+
+    async fn cache_put(&self, session: &checkout_session::Model) {
+        if let Some(cache) = &self.cache {
+            let ttl = (session.expires_at - Utc::now()).to_std().unwrap_or(Duration::from_secs(1));
+            if let Err(e) = cache.set(&Self::cache_key(session.id), session, Some(ttl)).await {
+                warn!(session_id = %session.id, "Failed to cache checkout session: {}", e);
+            }
+        }
+    }
+
+    pub async fn create(&self, new_session: NewCheckoutSession, created_by: String) -> Result<checkout_session::Model, CheckoutError> {
+        let now = Utc::now();
+        let session = checkout_session::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            customer_id: Set(new_session.customer_id),
+            status: Set(CheckoutStatus::Open),
+            items: Set(new_session.items),
+            shipping_address: Set(None),
+            payment: Set(None),
+            currency: Set(new_session.currency),
+            metadata: Set(new_session.metadata),
+            order_id: Set(None),
+            version: Set(1),
+            created_by: Set(created_by),
+            created_at: Set(now),
+            updated_at: Set(now),
+            expires_at: Set(now + self.ttl),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+        self.cache_put(&session).await;
+        Ok(session)
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<checkout_session::Model, CheckoutError> {
+        if let Some(cache) = &self.cache {
+            match cache.get::<checkout_session::Model>(&Self::cache_key(id)).await {
+                Ok(Some(session)) => return Ok(effective(session, Utc::now())),
+                Ok(None) => {}
+                Err(e) => warn!(session_id = %id, "Checkout session cache read failed: {}", e),
+            }
+        }
+        let session = CheckoutSession::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or(CheckoutError::NotFound(id))?;
+        if session.status.is_open() {
+            self.cache_put(&session).await;
+        }
+        Ok(effective(session, Utc::now()))
+    }
+
+    /// Sessions of a customer, most recently updated first, and the total.
+    pub async fn list_for_customer(
+        &self,
+        customer_id: i32,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<checkout_session::Model>, u64), CheckoutError> {
+        let paginator = CheckoutSession::find()
+            .filter(checkout_session::Column::CustomerId.eq(customer_id))
+            .order_by_desc(checkout_session::Column::UpdatedAt)
+            .paginate(self.db.as_ref(), pagination.limit());
+        let total = paginator.num_items().await?;
+        let now = Utc::now();
+        let sessions = paginator
+            .fetch_page(pagination.page_index())
+            .await?
+            .into_iter()
+            .map(|s| effective(s, now))
+            .collect();
+        Ok((sessions, total))
+    }
+
+    /// Writes `next` only if the stored session is still at `expected_version`, so two
+    /// devices editing the same session cannot silently overwrite each other.
+    async fn compare_and_swap(
+        &self,
+        expected_version: i32,
+        next: checkout_session::Model,
+    ) -> Result<checkout_session::Model, CheckoutError> {
+        let id = next.id;
+        let result = CheckoutSession::update_many()
+            .col_expr(checkout_session::Column::CustomerId, Expr::value(next.customer_id))
+            .col_expr(checkout_session::Column::Status, Expr::value(next.status))
+            .col_expr(checkout_session::Column::Items, Expr::value(next.items.clone()))
+            .col_expr(checkout_session::Column::ShippingAddress, Expr::value(next.shipping_address.clone()))
+            .col_expr(checkout_session::Column::Payment, Expr::value(next.payment.clone()))
+            .col_expr(checkout_session::Column::Metadata, Expr::value(next.metadata.clone()))
+            .col_expr(checkout_session::Column::OrderId, Expr::value(next.order_id))
+            .col_expr(checkout_session::Column::Version, Expr::value(next.version))
+            .col_expr(checkout_session::Column::UpdatedAt, Expr::value(next.updated_at))
+            .col_expr(checkout_session::Column::ExpiresAt, Expr::value(next.expires_at))
+            .filter(checkout_session::Column::Id.eq(id))
+            .filter(checkout_session::Column::Version.eq(expected_version))
+            .exec(self.db.as_ref())
+            .await?;
+        if result.rows_affected == 0 {
+            let current = CheckoutSession::find_by_id(id)
+                .one(self.db.as_ref())
+                .await?
+                .ok_or(CheckoutError::NotFound(id))?;
+            return Err(CheckoutError::VersionConflict { id, current: current.version });
+        }
+        self.cache_put(&next).await;
+        Ok(next)
+    }
+
+    /// Loads the authoritative copy of an open session, bypassing the cache.
+    async fn load_open(&self, id: Uuid) -> Result<checkout_session::Model, CheckoutError> {
+        let session = CheckoutSession::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?
+            .map(|s| effective(s, Utc::now()))
+            .ok_or(CheckoutError::NotFound(id))?;
+        if !session.status.is_open() {
+            return Err(CheckoutError::Closed(id, session.status));
+        }
+        Ok(session)
+    }
+
+    pub async fn update(&self, id: Uuid, patch: CheckoutSessionPatch) -> Result<checkout_session::Model, CheckoutError> {
+        let session = self.load_open(id).await?;
+        if session.version != patch.version {
+            return Err(CheckoutError::VersionConflict { id, current: session.version });
+        }
+        let mut patch = patch;
+        if let Some(payment) = patch.payment.take() {
+            patch.payment = Some(self.resolve_payment(id, payment).await?);
+        }
+        let expected = patch.version;
+        self.compare_and_swap(expected, apply_patch(&session, patch, Utc::now(), self.ttl)).await
+    }
+
+    /// Re-checks an open session's cart against current listing prices and unreserved
+    /// stock. Nothing is written; the session stays as the client left it.
+    pub async fn validate(&self, id: Uuid) -> Result<CartValidation, CheckoutError> {
+        let session = self.load_open(id).await?;
+        self.validate_session(session).await
+    }
+
+    async fn validate_session(&self, session: checkout_session::Model) -> Result<CartValidation, CheckoutError> {
+        let items = cart_items(&session.items)?;
+        let offers = self.offers(items.iter().map(|i| i.sku.clone()).collect()).await?;
+        let (changes, items) = diff_cart(&items, &offers, &session.currency);
+        let subtotal: Decimal = items
+            .iter()
+            .map(|i| i.unit_price.unwrap_or_default() * Decimal::from(i.quantity))
+            .sum();
+        Ok(CartValidation {
+            session_id: session.id,
+            version: session.version,
+            valid: changes.is_empty(),
+            changes,
+            items,
+            subtotal,
+            currency: session.currency,
+            checked_at: Utc::now(),
+        })
+    }
+
+    async fn offers(&self, mut skus: Vec<String>) -> Result<HashMap<String, SkuOffer>, CheckoutError> {
+        skus.sort();
+        skus.dedup();
+        if skus.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let listings = ProductListing::find()
+            .filter(product_listing::Column::Sku.is_in(skus.clone()))
+            .all(self.db.as_ref())
+            .await?;
+        let stock: HashMap<String, i64> = inventory_items::Entity::find()
+            .select_only()
+            .column(inventory_items::Column::Sku)
+            .column_as(Expr::cust("SUM(available - COALESCE(reserved_quantity, 0))"), "available")
+            .filter(inventory_items::Column::Sku.is_in(skus))
+            .group_by(inventory_items::Column::Sku)
+            .into_model::<StockLevel>()
+            .all(self.db.as_ref())
+            .await?
+            .into_iter()
+            .map(|level| (level.sku, level.available.unwrap_or(0)))
+            .collect();
+        Ok(listings
+            .into_iter()
+            .map(|listing| {
+                let offer = SkuOffer {
+                    price: listing.price,
+                    currency: listing.currency,
+                    active: listing.active,
+                    available: stock.get(&listing.sku).copied().unwrap_or(0),
+                };
+                (listing.sku, offer)
+            })
+            .collect())
+    }
+
+    /// Completes an open session with the order it produced. Unless disabled, the cart
+    /// is re-validated first and any drift is returned as `CartChanged` so the storefront
+    /// can prompt the shopper before payment is authorized. A vault token in the payment
+    /// is redeemed for `amount`, the amount charged. A token is never redeemed twice:
+    /// completing again after a version conflict redeems a fresh token issued for the
+    /// same payment method, which the completed session records.
+    pub async fn complete(
+        &self,
+        id: Uuid,
+        order_id: Uuid,
+        amount: Option<Decimal>,
+    ) -> Result<checkout_session::Model, CheckoutError> {
+        let mut session = self.load_open(id).await?;
+        let vault_token_id = session
+            .payment
+            .as_ref()
+            .and_then(|p| p.get("vault_token_id"))
+            .and_then(|v| serde_json::from_value::<Uuid>(v.clone()).ok());
+        if self.validate_on_complete || vault_token_id.is_some() {
+            let validation = self.validate_session(session.clone()).await?;
+            if self.validate_on_complete && !validation.valid {
+                return Err(CheckoutError::CartChanged(Box::new(validation)));
+            }
+            if let Some(token_id) = vault_token_id {
+                let vault = self
+                    .vault
+                    .as_ref()
+                    .ok_or_else(|| CheckoutError::Payment("the payment vault is not enabled".to_string()))?;
+                let amount = amount.ok_or_else(|| {
+                    CheckoutError::Payment("the charged amount is required to pay with a vault token".to_string())
+                })?;
+                let redemption = Redemption {
+                    amount,
+                    currency: session.currency.clone(),
+                    checkout_session_id: Some(id),
+                    used_by: format!("checkout:{}", id),
+                };
+                let redeemed = vault
+                    .redeem_retry(token_id, redemption)
+                    .await
+                    .map_err(|e| CheckoutError::Payment(e.to_string()))?;
+                if let Some(payment) = session.payment.as_mut().and_then(Value::as_object_mut) {
+                    payment.insert("vault_token_id".to_string(), json!(redeemed.id));
+                }
+            }
+        }
+        // Swapping against the validated version keeps a concurrent cart edit from
+        // slipping through unchecked.
+        self.compare_and_swap(session.version, closed(&session, CheckoutStatus::Completed, Some(order_id))).await
+    }
+
+    /// Closes an open session as completed (with the order it produced) or cancelled.
+    pub async fn close(&self, id: Uuid, status: CheckoutStatus, order_id: Option<Uuid>) -> Result<checkout_session::Model, CheckoutError> {
+        let session = self.load_open(id).await?;
+        self.compare_and_swap(session.version, closed(&session, status, order_id)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn session(now: DateTime<Utc>) -> checkout_session::Model {
+        checkout_session::Model {
+            id: Uuid::new_v4(),
+            customer_id: None,
+            status: CheckoutStatus::Open,
+            items: json!([{ "sku": "MUG", "quantity": 1 }]),
+            shipping_address: None,
+            payment: None,
+            currency: "USD".to_string(),
+            metadata: json!({}),
+            order_id: None,
+            version: 3,
+            created_by: "user:42".to_string(),
+            created_at: now,
+            updated_at: now,
+            expires_at: now + ChronoDuration::hours(24),
+        }
+    }
+
+    #[test]
+    fn test_patch_keeps_omitted_fields_and_bumps_version() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        let later = now + ChronoDuration::hours(2);
+        let patch = CheckoutSessionPatch {
+            version: 3,
+            customer_id: Some(7),
+            shipping_address: Some(json!({ "city": "Lisbon" })),
+            ..Default::default()
+        };
+        let next = apply_patch(&session(now), patch, later, ChronoDuration::hours(24));
+        assert_eq!(next.version, 4);
+        assert_eq!(next.customer_id, Some(7));
+        assert_eq!(next.items, json!([{ "sku": "MUG", "quantity": 1 }]));
+        assert_eq!(next.shipping_address, Some(json!({ "city": "Lisbon" })));
+        assert_eq!(next.expires_at, later + ChronoDuration::hours(24));
+    }
+
+    #[test]
+    fn test_open_sessions_past_expiry_read_as_expired() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        let stale = effective(session(now), now + ChronoDuration::hours(25));
+        assert_eq!(stale.status, CheckoutStatus::Expired);
+
+        let mut completed = session(now);
+        completed.status = CheckoutStatus::Completed;
+        let completed = effective(completed, now + ChronoDuration::hours(25));
+        assert_eq!(completed.status, CheckoutStatus::Completed);
+    }
+
+    fn offer(price: &str, available: i64) -> SkuOffer {
+        SkuOffer { price: price.parse().unwrap(), currency: "USD".to_string(), active: true, available }
+    }
+
+    fn item(sku: &str, quantity: i32, unit_price: Option<&str>) -> CartItem {
+        CartItem { sku: sku.to_string(), quantity, unit_price: unit_price.map(|p| p.parse().unwrap()) }
+    }
+
+    #[test]
+    fn test_cart_items_rejects_lines_without_quantity() {
+        let items = cart_items(&json!([{ "sku": "MUG", "quantity": 2, "unit_price": "12.50" }])).unwrap();
+        assert_eq!(items, vec![item("MUG", 2, Some("12.50"))]);
+
+        let err = cart_items(&json!([{ "sku": "MUG", "quantity": 0 }])).unwrap_err();
+        assert!(matches!(err, CheckoutError::InvalidItems(_)));
+    }
+
+    #[test]
+    fn test_diff_cart_reports_price_and_stock_drift() {
+        let offers = HashMap::from([
+            ("MUG".to_string(), offer("14.00", 10)),
+            ("CAP".to_string(), offer("20.00", 1)),
+            ("TEE".to_string(), offer("9.00", 0)),
+        ]);
+        let items = vec![
+            item("MUG", 2, Some("12.50")),
+            item("CAP", 3, Some("20.00")),
+            item("TEE", 1, None),
+            item("GONE", 1, Some("5.00")),
+        ];
+        let (changes, adjusted) = diff_cart(&items, &offers, "USD");
+        assert_eq!(
+            changes,
+            vec![
+                CartChange::PriceChanged {
+                    sku: "MUG".to_string(),
+                    previous: "12.50".parse().unwrap(),
+                    current: "14.00".parse().unwrap(),
+                },
+                CartChange::InsufficientStock { sku: "CAP".to_string(), requested: 3, available: 1 },
+                CartChange::OutOfStock { sku: "TEE".to_string(), requested: 1 },
+                CartChange::Unavailable { sku: "GONE".to_string(), reason: UnavailableReason::NotListed },
+            ]
+        );
+        assert_eq!(adjusted, vec![item("MUG", 2, Some("14.00")), item("CAP", 1, Some("20.00"))]);
+    }
+
+    #[test]
+    fn test_diff_cart_shares_stock_across_lines_of_one_sku() {
+        let offers = HashMap::from([("MUG".to_string(), offer("14.00", 3))]);
+        let items = vec![item("MUG", 2, Some("14.00")), item("MUG", 2, Some("14.00"))];
+        let (changes, adjusted) = diff_cart(&items, &offers, "usd");
+        assert_eq!(changes, vec![CartChange::InsufficientStock { sku: "MUG".to_string(), requested: 2, available: 1 }]);
+        assert_eq!(adjusted.iter().map(|i| i.quantity).sum::<i32>(), 3);
+    }
+
+    #[test]
+    fn test_diff_cart_flags_inactive_and_foreign_currency_listings() {
+        let mut inactive = offer("14.00", 5);
+        inactive.active = false;
+        let mut euro = offer("14.00", 5);
+        euro.currency = "EUR".to_string();
+        let offers = HashMap::from([("MUG".to_string(), inactive), ("CAP".to_string(), euro)]);
+        let (changes, adjusted) = diff_cart(&[item("MUG", 1, None), item("CAP", 1, None)], &offers, "USD");
+        assert_eq!(
+            changes,
+            vec![
+                CartChange::Unavailable { sku: "MUG".to_string(), reason: UnavailableReason::Inactive },
+                CartChange::Unavailable { sku: "CAP".to_string(), reason: UnavailableReason::CurrencyMismatch },
+            ]
+        );
+        assert!(adjusted.is_empty());
+    }
+}
@@ -36,7 +36,8 @@ pub struct AddItemToASNCommand {
     #[validate]
     pub serial_numbers: Option<Vec<String>>,
     pub expiration_date: Option<String>,
-    pub customs_value: Option<f64>,
+    #[serde(default, with = "crate::money::option_amount")]
+    pub customs_value: Option<rust_decimal::Decimal>,
     pub country_of_origin: Option<String>,
 }
 
@@ -2,14 +2,14 @@ use std::sync::Arc;
 use sea_orm::*;
 use crate::{
     db::DbPool,
-    errors::InventoryError,
+    errors::ServiceError,
     events::{Event, EventSender},
     models::{
-        inventory_level_entity::{self, Entity as InventoryLevel},
         inventory_transaction_entity::{self, Entity as InventoryTransaction},
         InventoryTransactionType,
     },
 };
+use super::inventory_locking::{apply_quantity_delta, lock_inventory_level, transaction_error, with_conflict_retry};
 use serde::{Deserialize, Serialize};
 use tracing::{error, info, instrument, warn};
 use uuid::Uuid;
@@ -67,12 +67,12 @@ impl Command for AdjustInventoryCommand {
         &self,
         db_pool: Arc<DbPool>,
         event_sender: Arc<EventSender>,
-    ) -> Result<Self::Result, InventoryError> {
+    ) -> Result<Self::Result, ServiceError> {
         self.validate().map_err(|e| {
             INVENTORY_ADJUSTMENT_FAILURES.with_label_values(&["validation_error"]).inc();
             let msg = format!("Invalid input: {}", e);
             error!("{}", msg);
-            InventoryError::ValidationError(msg)
+            ServiceError::ValidationError(msg)
         })?;
 
         let db = db_pool.as_ref();
@@ -96,7 +96,7 @@ impl AdjustInventoryCommand {
     async fn validate_reason_code(
         &self,
         db: &DatabaseConnection,
-    ) -> Result<(), InventoryError> {
+    ) -> Result<(), ServiceError> {
         // Here you would validate against a table of valid reason codes
         // For example: damaged, found, lost, cycle_count, etc.
         let valid_reasons = vec![
@@ -107,7 +107,7 @@ impl AdjustInventoryCommand {
 
         if !valid_reasons.contains(&self.reason_code.to_uppercase().as_str()) {
             INVENTORY_ADJUSTMENT_FAILURES.with_label_values(&["invalid_reason"]).inc();
-            return Err(InventoryError::InvalidReasonCode(self.reason_code.clone()));
+            return Err(ServiceError::ValidationError(format!("Invalid reason code: {}", self.reason_code)));
         }
 
         Ok(())
@@ -116,35 +116,36 @@ impl AdjustInventoryCommand {
     async fn adjust_inventory_in_db(
         &self,
         db: &DatabaseConnection,
-    ) -> Result<AdjustInventoryResult, InventoryError> {
-        db.transaction::<_, AdjustInventoryResult, InventoryError>(|txn| {
+    ) -> Result<AdjustInventoryResult, ServiceError> {
+        with_conflict_retry("adjust", || self.adjust_inventory_once(db)).await
+    }
+
+    async fn adjust_inventory_once(
+        &self,
+        db: &DatabaseConnection,
+    ) -> Result<AdjustInventoryResult, ServiceError> {
+        db.transaction::<_, AdjustInventoryResult, ServiceError>(|txn| {
             Box::pin(async move {
-                // Get current inventory level
-                let current_inventory = InventoryLevel::find()
-                    .filter(
-                        Condition::all()
-                            .add(inventory_level_entity::Column::WarehouseId.eq(&self.warehouse_id))
-                            .add(inventory_level_entity::Column::ProductId.eq(self.product_id))
-                    )
-                    .one(txn)
-                    .await
-                    .map_err(|e| InventoryError::DatabaseError(e.to_string()))?
-                    .ok_or_else(|| InventoryError::NotFound(format!(
-                        "Inventory level not found for product {} in warehouse {}", 
-                        self.product_id, self.warehouse_id
-                    )))?;
+                // Lock the inventory row so parallel adjustments serialize on it
+                let current_inventory = lock_inventory_level(txn, &self.warehouse_id, self.product_id).await?;
 
                 // Check version for optimistic locking
                 if current_inventory.version != self.version {
                     warn!("Concurrent modification detected for inventory {}", current_inventory.id);
-                    return Err(InventoryError::ConcurrentModification(current_inventory.id));
+                    return Err(ServiceError::InvalidOperation(format!(
+                        "Inventory {} changed since version {}",
+                        current_inventory.id, self.version
+                    )));
                 }
 
                 // Validate the adjustment won't result in negative inventory
                 let new_quantity = current_inventory.quantity + self.adjustment_quantity;
                 if new_quantity < 0 {
                     INVENTORY_ADJUSTMENT_FAILURES.with_label_values(&["negative_inventory"]).inc();
-                    return Err(InventoryError::NegativeInventory(self.product_id));
+                    return Err(ServiceError::InvalidOperation(format!(
+                        "Adjustment would make inventory of product {} negative",
+                        self.product_id
+                    )));
                 }
 
                 // Create inventory transaction record
@@ -165,16 +166,10 @@ impl AdjustInventoryCommand {
                 };
 
                 let saved_transaction = transaction.insert(txn).await
-                    .map_err(|e| InventoryError::DatabaseError(e.to_string()))?;
-
-                // Update inventory level
-                let mut inventory: inventory_level_entity::ActiveModel = current_inventory.clone().into();
-                inventory.quantity = Set(new_quantity);
-                inventory.version = Set(self.version + 1);
-                inventory.last_updated_at = Set(Utc::now().naive_utc());
+                    .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
 
-                let updated_inventory = inventory.update(txn).await
-                    .map_err(|e| InventoryError::DatabaseError(e.to_string()))?;
+                // Update inventory level atomically, guarded by version and non-negative quantity
+                apply_quantity_delta(txn, current_inventory.id, current_inventory.version, self.adjustment_quantity).await?;
 
                 Ok(AdjustInventoryResult {
                     id: saved_transaction.id,
@@ -187,14 +182,16 @@ impl AdjustInventoryCommand {
                     reference_number: self.reference_number.clone(),
                 })
             })
-        }).await
+        })
+        .await
+        .map_err(transaction_error)
     }
 
     async fn log_and_trigger_event(
         &self,
         event_sender: &EventSender,
         result: &AdjustInventoryResult,
-    ) -> Result<(), InventoryError> {
+    ) -> Result<(), ServiceError> {
         info!(
             warehouse_id = %self.warehouse_id,
             product_id = %self.product_id,
@@ -219,39 +216,7 @@ impl AdjustInventoryCommand {
                 INVENTORY_ADJUSTMENT_FAILURES.with_label_values(&["event_error"]).inc();
                 let msg = format!("Failed to send event for inventory adjustment: {}", e);
                 error!("{}", msg);
-                InventoryError::EventError(msg)
+                ServiceError::EventError(msg)
             })
     }
 }
-
-#[derive(thiserror::Error, Debug)]
-pub enum InventoryError {
-    #[error("Inventory not found: {0}")]
-    NotFound(String),
-    #[error("Invalid reason code: {0}")]
-    InvalidReasonCode(String),
-    #[error("Would result in negative inventory for product {0}")]
-    NegativeInventory(Uuid),
-    #[error("Database error: {0}")]
-    DatabaseError(String),
-    #[error("Event error: {0}")]
-    EventError(String),
-    #[error("Concurrent modification of inventory {0}")]
-    ConcurrentModification(Uuid),
-    #[error("Validation error: {0}")]
-    ValidationError(String),
-}
-
-impl InventoryError {
-    pub fn error_type(&self) -> &str {
-        match self {
-            InventoryError::NotFound(_) => "not_found",
-            InventoryError::InvalidReasonCode(_) => "invalid_reason_code",
-            InventoryError::NegativeInventory(_) => "negative_inventory",
-            InventoryError::DatabaseError(_) => "database_error",
-            InventoryError::EventError(_) => "event_error",
-            InventoryError::ConcurrentModification(_) => "concurrent_modification",
-            InventoryError::ValidationError(_) => "validation_error",
-        }
-    }
-}
\ No newline at end of file
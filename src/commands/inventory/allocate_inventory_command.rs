@@ -10,8 +10,10 @@ use crate::{
         inventory_reservation_entity::{self, Entity as InventoryReservation},
         AllocationStatus,
     },
+    services::sourcing::SourcedShipment,
 };
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use tracing::{error, info, instrument, warn};
 use uuid::Uuid;
 use validator::Validate;
@@ -112,7 +114,8 @@ impl Command for AllocateInventoryCommand {
 
         let db = db_pool.as_ref();
 
-        // Check if there are any existing allocations for this reference
+        // Check if there are any existing allocations for this reference in this warehouse;
+        // a sourced order may be allocated from several
         self.check_existing_allocations(db).await?;
 
         // Perform the allocations within a transaction
@@ -136,6 +139,45 @@ impl Command for AllocateInventoryCommand {
 }
 
 impl AllocateInventoryCommand {
+    /// Allocates what a sourcing plan ships from one location, e.g. one shipment of a
+    /// routed order. `product_ids` maps the plan's SKUs to the products allocated.
+    pub fn for_shipment(
+        reference_id: Uuid,
+        reference_type: &str,
+        shipment: &SourcedShipment,
+        product_ids: &HashMap<String, Uuid>,
+    ) -> Result<Self, InventoryError> {
+        let allocations = shipment
+            .lines
+            .iter()
+            .map(|line| {
+                let product_id = product_ids.get(&line.sku).copied().ok_or_else(|| {
+                    InventoryError::ValidationError(format!("No product to allocate SKU {} under", line.sku))
+                })?;
+                let quantity = i32::try_from(line.quantity).map_err(|_| {
+                    InventoryError::ValidationError(format!("Quantity of SKU {} is out of range", line.sku))
+                })?;
+                Ok(AllocationRequest {
+                    product_id,
+                    quantity,
+                    lot_number: None,
+                    location_id: None,
+                    substitution_group: None,
+                })
+            })
+            .collect::<Result<Vec<_>, InventoryError>>()?;
+        Ok(Self {
+            warehouse_id: shipment.warehouse.to_string(),
+            allocations,
+            allocation_type: AllocationType::Order,
+            reference_id,
+            reference_type: reference_type.to_string(),
+            notes: None,
+            priority: None,
+            expiration: None,
+        })
+    }
+
     async fn check_existing_allocations(
         &self,
         db: &DatabaseConnection,
@@ -144,6 +186,7 @@ impl AllocateInventoryCommand {
             .filter(
                 inventory_allocation_entity::Column::ReferenceId.eq(self.reference_id)
                     .and(inventory_allocation_entity::Column::ReferenceType.eq(&self.reference_type))
+                    .and(inventory_allocation_entity::Column::WarehouseId.eq(&self.warehouse_id))
             )
             .count(db)
             .await
@@ -0,0 +1,102 @@
+use std::sync::Arc;
+use sea_orm::*;
+use crate::{
+    db::DbPool,
+    errors::InventoryError,
+    events::{Event, EventSender},
+    models::{
+        inventory_reservation_entity::{self, Entity as InventoryReservation},
+        ReservationStatus,
+    },
+    reservation_expiry::expiration_for,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument};
+use uuid::Uuid;
+use validator::Validate;
+use chrono::{DateTime, Utc};
+
+/// Turns a checkout's holds into sales order reservations in one transaction. Either
+/// every hold is converted or, if any has expired, none is and the caller must reserve
+/// again, so an order is never placed against stock that was already released.
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ConvertReservationCommand {
+    /// Reference the holds were placed under, e.g. the checkout session id.
+    pub reference_id: Uuid,
+    pub reference_type: String,
+    pub order_id: Uuid,
+    /// How long the order keeps the stock (default 7 days).
+    #[validate(range(min = 1, max = 365))]
+    pub duration_days: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConvertReservationResult {
+    pub order_id: Uuid,
+    pub converted: usize,
+    pub expiration_date: DateTime<Utc>,
+}
+
+#[async_trait::async_trait]
+impl Command for ConvertReservationCommand {
+    type Result = ConvertReservationResult;
+
+    #[instrument(skip(self, db_pool, event_sender))]
+    async fn execute(
+        &self,
+        db_pool: Arc<DbPool>,
+        event_sender: Arc<EventSender>,
+    ) -> Result<Self::Result, InventoryError> {
+        self.validate()
+            .map_err(|e| InventoryError::ValidationError(format!("Invalid input: {}", e)))?;
+
+        let reference_id = self.reference_id;
+        let reference_type = self.reference_type.clone();
+        let order_id = self.order_id;
+        let now = Utc::now();
+        let expiration_date = expiration_for(now, None, self.duration_days);
+
+        let converted = db_pool
+            .transaction::<_, usize, InventoryError>(|txn| {
+                Box::pin(async move {
+                    let holds = InventoryReservation::find()
+                        .filter(inventory_reservation_entity::Column::ReferenceId.eq(reference_id))
+                        .filter(inventory_reservation_entity::Column::ReferenceType.eq(reference_type))
+                        .filter(inventory_reservation_entity::Column::Status.eq(ReservationStatus::Active.to_string()))
+                        .lock_exclusive()
+                        .all(txn)
+                        .await
+                        .map_err(|e| InventoryError::DatabaseError(e.to_string()))?;
+                    if holds.is_empty() || holds.iter().any(|h| h.expiration_date <= now.naive_utc()) {
+                        return Err(InventoryError::ReservationExpired(reference_id));
+                    }
+                    let count = holds.len();
+                    for hold in holds {
+                        let mut active: inventory_reservation_entity::ActiveModel = hold.into();
+                        active.reference_id = Set(order_id);
+                        active.reference_type = Set("SALES_ORDER".to_string());
+                        active.reservation_type = Set("SalesOrder".to_string());
+                        active.expiration_date = Set(expiration_date.naive_utc());
+                        active
+                            .update(txn)
+                            .await
+                            .map_err(|e| InventoryError::DatabaseError(e.to_string()))?;
+                    }
+                    Ok(count)
+                })
+            })
+            .await
+            .map_err(|e| match e {
+                TransactionError::Connection(e) => InventoryError::DatabaseError(e.to_string()),
+                TransactionError::Transaction(e) => e,
+            })?;
+
+        info!(reference_id = %self.reference_id, order_id = %self.order_id, converted, "Reservations converted to order");
+        let _ = event_sender.send(Event::ReservationsConverted {
+            from_reference_id: self.reference_id,
+            order_id: self.order_id,
+            reservations: converted,
+        });
+        Ok(ConvertReservationResult { order_id: self.order_id, converted, expiration_date })
+    }
+}
@@ -0,0 +1,92 @@
+use std::sync::Arc;
+use sea_orm::*;
+use crate::{
+    db::DbPool,
+    errors::InventoryError,
+    events::EventSender,
+    models::{
+        inventory_reservation_entity::{self, Entity as InventoryReservation},
+        ReservationStatus,
+    },
+    reservation_expiry::expiration_for,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{info, instrument};
+use uuid::Uuid;
+use validator::Validate;
+use chrono::{DateTime, Utc};
+
+/// Pushes back the expiry of every live hold for a reference, e.g. while a shopper is
+/// still active in checkout. Holds that already expired are not revived.
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ExtendReservationCommand {
+    pub reference_id: Uuid,
+    pub reference_type: String,
+    /// New hold length, counted from now.
+    #[validate(range(min = 60, max = 31536000))]
+    pub ttl_seconds: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExtendReservationResult {
+    pub reference_id: Uuid,
+    pub extended: usize,
+    pub expiration_date: DateTime<Utc>,
+}
+
+#[async_trait::async_trait]
+impl Command for ExtendReservationCommand {
+    type Result = ExtendReservationResult;
+
+    #[instrument(skip(self, db_pool, _event_sender))]
+    async fn execute(
+        &self,
+        db_pool: Arc<DbPool>,
+        _event_sender: Arc<EventSender>,
+    ) -> Result<Self::Result, InventoryError> {
+        self.validate()
+            .map_err(|e| InventoryError::ValidationError(format!("Invalid input: {}", e)))?;
+
+        let reference_id = self.reference_id;
+        let reference_type = self.reference_type.clone();
+        let now = Utc::now();
+        let expiration_date = expiration_for(now, Some(self.ttl_seconds), None);
+
+        let extended = db_pool
+            .transaction::<_, usize, InventoryError>(|txn| {
+                Box::pin(async move {
+                    // Locking the live holds keeps the expiry sweep from releasing them mid-update
+                    let live = InventoryReservation::find()
+                        .filter(inventory_reservation_entity::Column::ReferenceId.eq(reference_id))
+                        .filter(inventory_reservation_entity::Column::ReferenceType.eq(reference_type))
+                        .filter(inventory_reservation_entity::Column::Status.eq(ReservationStatus::Active.to_string()))
+                        .filter(inventory_reservation_entity::Column::ExpirationDate.gt(now.naive_utc()))
+                        .lock_exclusive()
+                        .all(txn)
+                        .await
+                        .map_err(|e| InventoryError::DatabaseError(e.to_string()))?;
+                    if live.is_empty() {
+                        return Err(InventoryError::ReservationExpired(reference_id));
+                    }
+                    let count = live.len();
+                    for reservation in live {
+                        let mut active: inventory_reservation_entity::ActiveModel = reservation.into();
+                        active.expiration_date = Set(expiration_date.naive_utc());
+                        active
+                            .update(txn)
+                            .await
+                            .map_err(|e| InventoryError::DatabaseError(e.to_string()))?;
+                    }
+                    Ok(count)
+                })
+            })
+            .await
+            .map_err(|e| match e {
+                TransactionError::Connection(e) => InventoryError::DatabaseError(e.to_string()),
+                TransactionError::Transaction(e) => e,
+            })?;
+
+        info!(reference_id = %self.reference_id, extended, expiration = %expiration_date, "Reservations extended");
+        Ok(ExtendReservationResult { reference_id: self.reference_id, extended, expiration_date })
+    }
+}
@@ -0,0 +1,268 @@
+use std::future::Future;
+use std::time::Duration;
+use sea_orm::{sea_query::Expr, *};
+use crate::models::inventory_level_entity::{self, Entity as InventoryLevel};
+use crate::errors::ServiceError;
+use tracing::{instrument, warn};
+use uuid::Uuid;
+use prometheus::IntCounterVec;
+use lazy_static::lazy_static;
+use chrono::Utc;
+
+lazy_static! {
+    static ref INVENTORY_LOCK_RETRIES: IntCounterVec =
+        IntCounterVec::new(
+            "inventory_lock_retries_total",
+            "Total number of inventory operations retried after a lock conflict",
+            &["operation"]
+        ).expect("metric can be created");
+
+    static ref INVENTORY_LOCK_EXHAUSTED: IntCounterVec =
+        IntCounterVec::new(
+            "inventory_lock_retries_exhausted_total",
+            "Total number of inventory operations that gave up after repeated lock conflicts",
+            &["operation"]
+        ).expect("metric can be created");
+}
+
+/// Maximum number of attempts for an inventory write before giving up on a conflict.
+pub const MAX_LOCK_ATTEMPTS: u32 = 5;
+
+/// Base delay between attempts; doubled on each retry.
+const BASE_RETRY_DELAY: Duration = Duration::from_millis(20);
+
+/// Postgres SQLSTATEs (and MySQL wording) that indicate the transaction lost a race
+/// and can safely be re-run from the start, plus the message of [`lock_conflict`].
+const RETRYABLE_DB_ERRORS: [&str; 6] = [
+    "40001",                   // serialization_failure
+    "40P01",                   // deadlock_detected
+    "could not serialize access",
+    "deadlock detected",
+    "Deadlock found",
+    LOCK_CONFLICT,
+];
+
+const LOCK_CONFLICT: &str = "Inventory lock conflict";
+
+/// The error for a guarded update that matched no row because another writer got there
+/// first.
+pub fn lock_conflict(inventory_id: impl std::fmt::Display) -> ServiceError {
+    ServiceError::DatabaseError(format!("{} on {}", LOCK_CONFLICT, inventory_id))
+}
+
+/// Returns true when the failure was caused by a concurrent writer and the whole
+/// transaction can be retried without changing the caller's intent.
+pub fn is_retryable(error: &ServiceError) -> bool {
+    match error {
+        ServiceError::DatabaseError(msg) => RETRYABLE_DB_ERRORS.iter().any(|needle| msg.contains(needle)),
+        _ => false,
+    }
+}
+
+/// Unwraps the error of a `db.transaction(..)` call.
+pub fn transaction_error(error: TransactionError<ServiceError>) -> ServiceError {
+    match error {
+        TransactionError::Connection(e) => ServiceError::DatabaseError(e.to_string()),
+        TransactionError::Transaction(e) => e,
+    }
+}
+
+/// Runs `operation` and re-runs it with exponential backoff while it fails with a
+/// retryable conflict. Each attempt must open its own transaction so that a retry
+/// re-reads the rows it locks.
+pub async fn with_conflict_retry<F, Fut, T>(
+    operation_name: &str,
+    mut operation: F,
+) -> Result<T, ServiceError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, ServiceError>>,
+{
+    let mut attempt = 1;
+    loop {
+        match operation().await {
+            Err(e) if is_retryable(&e) && attempt < MAX_LOCK_ATTEMPTS => {
+                INVENTORY_LOCK_RETRIES.with_label_values(&[operation_name]).inc();
+                warn!(
+                    operation = operation_name,
+                    attempt,
+                    error = %e,
+                    "Inventory write conflicted with a concurrent transaction, retrying"
+                );
+                tokio::time::sleep(BASE_RETRY_DELAY * 2u32.pow(attempt - 1)).await;
+                attempt += 1;
+            }
+            Err(e) => {
+                if is_retryable(&e) {
+                    INVENTORY_LOCK_EXHAUSTED.with_label_values(&[operation_name]).inc();
+                }
+                return Err(e);
+            }
+            Ok(value) => return Ok(value),
+        }
+    }
+}
+
+/// Loads the inventory level for a product in a warehouse with `SELECT ... FOR UPDATE`,
+/// blocking other writers to the same row until the surrounding transaction ends.
+#[instrument(skip(txn))]
+pub async fn lock_inventory_level<C>(
+    txn: &C,
+    warehouse_id: &str,
+    product_id: Uuid,
+) -> Result<inventory_level_entity::Model, ServiceError>
+where
+    C: ConnectionTrait,
+{
+    lock_query(warehouse_id, product_id)
+        .one(txn)
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| ServiceError::NotFound(format!(
+            "Inventory level not found for product {} in warehouse {}",
+            product_id, warehouse_id
+        )))
+}
+
+fn lock_query(warehouse_id: &str, product_id: Uuid) -> Select<InventoryLevel> {
+    InventoryLevel::find()
+        .filter(
+            Condition::all()
+                .add(inventory_level_entity::Column::WarehouseId.eq(warehouse_id))
+                .add(inventory_level_entity::Column::ProductId.eq(product_id))
+        )
+        .lock_exclusive()
+}
+
+/// Applies `delta` to an inventory level with a single conditional UPDATE that only
+/// matches when the row is still at `expected_version` and the result stays
+/// non-negative. Zero affected rows means another writer got there first.
+#[instrument(skip(txn))]
+pub async fn apply_quantity_delta<C>(
+    txn: &C,
+    inventory_id: Uuid,
+    expected_version: i32,
+    delta: i32,
+) -> Result<(), ServiceError>
+where
+    C: ConnectionTrait,
+{
+    let result = InventoryLevel::update_many()
+        .col_expr(
+            inventory_level_entity::Column::Quantity,
+            Expr::col(inventory_level_entity::Column::Quantity).add(delta),
+        )
+        .col_expr(
+            inventory_level_entity::Column::Version,
+            Expr::col(inventory_level_entity::Column::Version).add(1),
+        )
+        .col_expr(
+            inventory_level_entity::Column::LastUpdatedAt,
+            Expr::value(Utc::now().naive_utc()),
+        )
+        .filter(inventory_level_entity::Column::Id.eq(inventory_id))
+        .filter(inventory_level_entity::Column::Version.eq(expected_version))
+        .filter(
+            Expr::col(inventory_level_entity::Column::Quantity)
+                .add(delta)
+                .gte(0),
+        )
+        .exec(txn)
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
+    if result.rows_affected == 0 {
+        return Err(lock_conflict(inventory_id));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    };
+
+    #[test]
+    fn test_retryable_classification() {
+        assert!(is_retryable(&lock_conflict(Uuid::new_v4())));
+        assert!(is_retryable(&ServiceError::DatabaseError(
+            "error returned from database: deadlock detected".to_string()
+        )));
+        assert!(is_retryable(&ServiceError::DatabaseError(
+            "SQLSTATE 40001: could not serialize access due to concurrent update".to_string()
+        )));
+        assert!(!is_retryable(&ServiceError::DatabaseError("connection refused".to_string())));
+        assert!(!is_retryable(&ServiceError::InvalidOperation("stale version".to_string())));
+        assert!(!is_retryable(&ServiceError::NotFound("inventory".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_retry_gives_up_after_max_attempts() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let counter = attempts.clone();
+
+        let result: Result<(), _> = with_conflict_retry("test", || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Err(lock_conflict(Uuid::nil()))
+            }
+        })
+        .await;
+
+        assert!(result.is_err_and(|e| is_retryable(&e)));
+        assert_eq!(attempts.load(Ordering::SeqCst), MAX_LOCK_ATTEMPTS);
+    }
+
+    #[tokio::test]
+    async fn test_non_retryable_errors_fail_fast() {
+        let attempts = Arc::new(AtomicU32::new(0));
+        let counter = attempts.clone();
+
+        let result: Result<(), _> = with_conflict_retry("test", || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Err(ServiceError::InvalidOperation("negative inventory".to_string()))
+            }
+        })
+        .await;
+
+        assert!(matches!(result, Err(ServiceError::InvalidOperation(_))));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_inventory_row_is_read_for_update() {
+        let sql = lock_query("WH-1", Uuid::nil()).build(DbBackend::Postgres).to_string();
+        assert!(sql.ends_with("FOR UPDATE"), "{}", sql);
+        assert!(sql.contains(r#""warehouse_id" = 'WH-1'"#), "{}", sql);
+    }
+
+    #[tokio::test]
+    async fn test_lost_update_is_retried_against_the_database() {
+        // The first UPDATE matches no row, as when another writer bumped the version
+        // between our read and write; the retry must issue the guarded UPDATE again.
+        let db = MockDatabase::new(DbBackend::Postgres)
+            .append_exec_results([
+                MockExecResult { last_insert_id: 0, rows_affected: 0 },
+                MockExecResult { last_insert_id: 0, rows_affected: 1 },
+            ])
+            .into_connection();
+        let inventory_id = Uuid::new_v4();
+
+        with_conflict_retry("test", || apply_quantity_delta(&db, inventory_id, 7, -3))
+            .await
+            .unwrap();
+
+        let log = db.into_transaction_log();
+        assert_eq!(log.len(), 2);
+        let sql = format!("{:?}", log[1]);
+        assert!(sql.contains(r#""version" = $"#), "{}", sql);
+        assert!(sql.contains(r#""quantity" + $"#) && sql.contains(">= $"), "{}", sql);
+    }
+}
@@ -0,0 +1,19 @@
+pub mod adjust_inventory_command;
+pub mod allocate_inventory_command;
+pub mod convert_reservation_command;
+pub mod deallocate_inventory_command;
+pub mod extend_reservation_command;
+pub mod get_stock_safety_command;
+pub mod release_inventory_command;
+pub mod reserve_inventory_command;
+pub mod inventory_locking;
+
+// Re-export commands for easier access
+pub use adjust_inventory_command::AdjustInventoryCommand;
+pub use allocate_inventory_command::AllocateInventoryCommand;
+pub use convert_reservation_command::ConvertReservationCommand;
+pub use deallocate_inventory_command::DeallocateInventoryCommand;
+pub use extend_reservation_command::ExtendReservationCommand;
+pub use get_stock_safety_command::GetStockSafetyCommand;
+pub use release_inventory_command::ReleaseInventoryCommand;
+pub use reserve_inventory_command::ReserveInventoryCommand;
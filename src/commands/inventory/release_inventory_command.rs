@@ -2,13 +2,14 @@ use std::sync::Arc;
 use sea_orm::*;
 use crate::{
     db::DbPool,
-    errors::InventoryError,
+    errors::ServiceError,
     events::{Event, EventSender},
     models::{
         inventory_reservation_entity::{self, Entity as InventoryReservation},
         ReservationStatus,
     },
 };
+use super::inventory_locking::{transaction_error, with_conflict_retry};
 use serde::{Deserialize, Serialize};
 use tracing::{error, info, instrument, warn};
 use uuid::Uuid;
@@ -84,12 +85,12 @@ impl Command for ReleaseInventoryCommand {
         &self,
         db_pool: Arc<DbPool>,
         event_sender: Arc<EventSender>,
-    ) -> Result<Self::Result, InventoryError> {
+    ) -> Result<Self::Result, ServiceError> {
         self.validate().map_err(|e| {
             INVENTORY_RELEASE_FAILURES.with_label_values(&["validation_error"]).inc();
             let msg = format!("Invalid input: {}", e);
             error!("{}", msg);
-            InventoryError::ValidationError(msg)
+            ServiceError::ValidationError(msg)
         })?;
 
         let db = db_pool.as_ref();
@@ -118,7 +119,7 @@ impl Command for ReleaseInventoryCommand {
 }
 
 impl ReleaseInventoryCommand {
-    fn validate_reason_code(&self) -> Result<(), InventoryError> {
+    fn validate_reason_code(&self) -> Result<(), ServiceError> {
         let valid_reasons = [
             "ORDER_FULFILLED",
             "ORDER_CANCELLED",
@@ -131,7 +132,7 @@ impl ReleaseInventoryCommand {
 
         if !valid_reasons.contains(&self.reason_code.as_str()) {
             INVENTORY_RELEASE_FAILURES.with_label_values(&["invalid_reason"]).inc();
-            return Err(InventoryError::InvalidReasonCode(self.reason_code.clone()));
+            return Err(ServiceError::ValidationError(format!("Invalid reason code: {}", self.reason_code)));
         }
 
         Ok(())
@@ -140,8 +141,15 @@ impl ReleaseInventoryCommand {
     async fn release_inventory_in_db(
         &self,
         db: &DatabaseConnection,
-    ) -> Result<ReleaseInventoryResult, InventoryError> {
-        db.transaction::<_, ReleaseInventoryResult, InventoryError>(|txn| {
+    ) -> Result<ReleaseInventoryResult, ServiceError> {
+        with_conflict_retry("release", || self.release_inventory_once(db)).await
+    }
+
+    async fn release_inventory_once(
+        &self,
+        db: &DatabaseConnection,
+    ) -> Result<ReleaseInventoryResult, ServiceError> {
+        db.transaction::<_, ReleaseInventoryResult, ServiceError>(|txn| {
             Box::pin(async move {
                 let mut release_results = Vec::new();
                 let mut fully_released = true;
@@ -156,8 +164,10 @@ impl ReleaseInventoryCommand {
                                 .add(inventory_reservation_entity::Column::ReferenceType.eq(&self.reference_type))
                                 .add(inventory_reservation_entity::Column::Status.eq(ReservationStatus::Active.to_string()))
                         )
+                        .lock_exclusive()
                         .all(txn)
-                        .await?
+                        .await
+                        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?
                 } else {
                     // Process specific release requests
                     let mut reservations = Vec::new();
@@ -180,7 +190,12 @@ impl ReleaseInventoryCommand {
                             query = query.filter(inventory_reservation_entity::Column::LotNumbers.is_in(lot_numbers.clone()));
                         }
 
-                        let mut found_reservations = query.all(txn).await?;
+                        // Lock matched reservations so a parallel release cannot free them twice
+                        let mut found_reservations = query
+                            .lock_exclusive()
+                            .all(txn)
+                            .await
+                            .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
                         reservations.append(&mut found_reservations);
                     }
                     reservations
@@ -209,7 +224,10 @@ impl ReleaseInventoryCommand {
                     res.release_reason = Set(Some(self.reason_code.clone()));
                     res.notes = Set(self.notes.clone());
 
-                    let updated_reservation = res.update(txn).await?;
+                    let updated_reservation = res
+                        .update(txn)
+                        .await
+                        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
 
                     release_results.push(ReleaseResult {
                         reservation_id: updated_reservation.id,
@@ -233,14 +251,16 @@ impl ReleaseInventoryCommand {
                     release_date: Utc::now(),
                 })
             })
-        }).await
+        })
+        .await
+        .map_err(transaction_error)
     }
 
     async fn log_and_trigger_events(
         &self,
         event_sender: &EventSender,
         results: &ReleaseInventoryResult,
-    ) -> Result<(), InventoryError> {
+    ) -> Result<(), ServiceError> {
         info!(
             reference_id = %self.reference_id,
             reference_type = %self.reference_type,
@@ -273,21 +293,7 @@ impl ReleaseInventoryCommand {
                 INVENTORY_RELEASE_FAILURES.with_label_values(&["event_error"]).inc();
                 let msg = format!("Failed to send event for inventory release: {}", e);
                 error!("{}", msg);
-                InventoryError::EventError(msg)
+                ServiceError::EventError(msg)
             })
     }
 }
-
-#[derive(thiserror::Error, Debug)]
-pub enum InventoryError {
-    #[error("Invalid reason code: {0}")]
-    InvalidReasonCode(String),
-    #[error("Reservation not found: {0}")]
-    NotFound(String),
-    #[error("Database error: {0}")]
-    DatabaseError(String),
-    #[error("Event error: {0}")]
-    EventError(String),
-    #[error("Validation error: {0}")]
-    ValidationError(String),
-}
\ No newline at end of file
@@ -2,21 +2,23 @@ use std::sync::Arc;
 use sea_orm::*;
 use crate::{
     db::DbPool,
-    errors::InventoryError,
+    errors::ServiceError,
     events::{Event, EventSender},
     models::{
-        inventory_level_entity::{self, Entity as InventoryLevel},
         inventory_reservation_entity::{self, Entity as InventoryReservation},
         ReservationStatus,
     },
+    reservation_expiry::expiration_for,
+    services::bundle_service::{components_for, explode},
 };
+use super::inventory_locking::{lock_inventory_level, transaction_error, with_conflict_retry};
 use serde::{Deserialize, Serialize};
 use tracing::{error, info, instrument, warn};
 use uuid::Uuid;
 use validator::Validate;
 use prometheus::{IntCounter, IntCounterVec};
 use lazy_static::lazy_static;
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Utc};
 
 lazy_static! {
     static ref INVENTORY_RESERVATIONS: IntCounter = 
@@ -48,13 +50,17 @@ pub struct ReserveInventoryCommand {
     pub reservation_type: ReservationType,
     #[validate(range(min = 1, max = 365))]
     pub duration_days: Option<i32>, // How long to hold the reservation
+    /// Hold length in seconds for short holds such as carts (e.g. 900); takes precedence
+    /// over `duration_days`. Expired holds are released by the reservation expiry sweep.
+    #[validate(range(min = 60, max = 31536000))]
+    pub ttl_seconds: Option<i64>,
     pub priority: Option<i32>,      // Higher priority reservations take precedence
     #[validate(length(max = 500))]
     pub notes: Option<String>,
     pub reservation_strategy: ReservationStrategy,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
 pub struct ReservationRequest {
     pub product_id: Uuid,
     #[validate(range(min = 1))]
@@ -74,7 +80,20 @@ pub enum ReservationType {
     SafetyStock,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl std::fmt::Display for ReservationType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ReservationType::SalesOrder => "SALES_ORDER",
+            ReservationType::CustomerHold => "CUSTOMER_HOLD",
+            ReservationType::Production => "PRODUCTION",
+            ReservationType::QualityHold => "QUALITY_HOLD",
+            ReservationType::PreOrder => "PRE_ORDER",
+            ReservationType::SafetyStock => "SAFETY_STOCK",
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ReservationStrategy {
     Strict,          // Must reserve exact quantity or fail
     Partial,         // Allow partial reservations
@@ -114,21 +133,18 @@ impl Command for ReserveInventoryCommand {
         &self,
         db_pool: Arc<DbPool>,
         event_sender: Arc<EventSender>,
-    ) -> Result<Self::Result, InventoryError> {
+    ) -> Result<Self::Result, ServiceError> {
         self.validate().map_err(|e| {
             INVENTORY_RESERVATION_FAILURES.with_label_values(&["validation_error"]).inc();
             let msg = format!("Invalid input: {}", e);
             error!("{}", msg);
-            InventoryError::ValidationError(msg)
+            ServiceError::ValidationError(msg)
         })?;
 
         let db = db_pool.as_ref();
 
-        // Check for existing reservations
-        self.check_existing_reservations(db).await?;
-
         // Calculate expiration date
-        let expiration_date = Utc::now() + Duration::days(self.duration_days.unwrap_or(7) as i64);
+        let expiration_date = expiration_for(Utc::now(), self.ttl_seconds, self.duration_days);
 
         // Perform the reservations within a transaction
         let reservation_results = self.reserve_inventory_in_db(db, expiration_date).await?;
@@ -151,10 +167,14 @@ impl Command for ReserveInventoryCommand {
 }
 
 impl ReserveInventoryCommand {
-    async fn check_existing_reservations(
+    /// Refuses a reference that already holds active reservations. Runs inside the
+    /// reservation transaction once the first item's inventory row is locked, so a
+    /// concurrent request for the same reference waits for this one to commit and then
+    /// sees its reservations.
+    async fn check_existing_reservations<C: ConnectionTrait>(
         &self,
-        db: &DatabaseConnection,
-    ) -> Result<(), InventoryError> {
+        txn: &C,
+    ) -> Result<(), ServiceError> {
         let existing = InventoryReservation::find()
             .filter(
                 Condition::all()
@@ -163,37 +183,30 @@ impl ReserveInventoryCommand {
                     .add(inventory_reservation_entity::Column::Status.eq(ReservationStatus::Active.to_string()))
                     .add(inventory_reservation_entity::Column::ExpirationDate.gt(Utc::now().naive_utc()))
             )
-            .count(db)
+            .count(txn)
             .await
-            .map_err(|e| InventoryError::DatabaseError(e.to_string()))?;
+            .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
 
         if existing > 0 {
             INVENTORY_RESERVATION_FAILURES.with_label_values(&["duplicate_reservation"]).inc();
-            return Err(InventoryError::DuplicateReservation(self.reference_id));
+            return Err(ServiceError::InvalidOperation(format!(
+                "Reference {} already holds active reservations",
+                self.reference_id
+            )));
         }
 
         Ok(())
     }
 
-    async fn check_available_quantity(
+    async fn check_available_quantity<C: ConnectionTrait>(
         &self,
-        db: &DatabaseConnection,
+        txn: &C,
         product_id: Uuid,
         requested_quantity: i32,
-    ) -> Result<i32, InventoryError> {
-        let inventory = InventoryLevel::find()
-            .filter(
-                Condition::all()
-                    .add(inventory_level_entity::Column::WarehouseId.eq(&self.warehouse_id))
-                    .add(inventory_level_entity::Column::ProductId.eq(product_id))
-            )
-            .one(db)
-            .await
-            .map_err(|e| InventoryError::DatabaseError(e.to_string()))?
-            .ok_or_else(|| InventoryError::NotFound(format!(
-                "Inventory level not found for product {} in warehouse {}", 
-                product_id, self.warehouse_id
-            )))?;
+    ) -> Result<i32, ServiceError> {
+        // Hold the inventory row for the rest of the transaction so concurrent
+        // reservations for the same product cannot both see the same availability
+        let inventory = lock_inventory_level(txn, &self.warehouse_id, product_id).await?;
 
         // Get existing reservations for this product
         let existing_reservations = InventoryReservation::find()
@@ -204,9 +217,9 @@ impl ReserveInventoryCommand {
                     .add(inventory_reservation_entity::Column::Status.eq(ReservationStatus::Active.to_string()))
                     .add(inventory_reservation_entity::Column::ExpirationDate.gt(Utc::now().naive_utc()))
             )
-            .all(db)
+            .all(txn)
             .await
-            .map_err(|e| InventoryError::DatabaseError(e.to_string()))?;
+            .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
 
         let total_reserved: i32 = existing_reservations.iter()
             .map(|r| r.quantity)
@@ -218,7 +231,7 @@ impl ReserveInventoryCommand {
         if reserve_quantity <= 0 {
             INVENTORY_RESERVATION_FAILURES.with_label_values(&["insufficient_inventory"]).inc();
             if self.reservation_strategy == ReservationStrategy::Strict {
-                return Err(InventoryError::InsufficientInventory(product_id));
+                return Err(ServiceError::InvalidOperation(format!("Insufficient inventory for product {}", product_id)));
             }
         }
 
@@ -229,13 +242,28 @@ impl ReserveInventoryCommand {
         &self,
         db: &DatabaseConnection,
         expiration_date: DateTime<Utc>,
-    ) -> Result<ReserveInventoryResult, InventoryError> {
-        db.transaction::<_, ReserveInventoryResult, InventoryError>(|txn| {
+    ) -> Result<ReserveInventoryResult, ServiceError> {
+        with_conflict_retry("reserve", || self.reserve_inventory_once(db, expiration_date)).await
+    }
+
+    async fn reserve_inventory_once(
+        &self,
+        db: &DatabaseConnection,
+        expiration_date: DateTime<Utc>,
+    ) -> Result<ReserveInventoryResult, ServiceError> {
+        db.transaction::<_, ReserveInventoryResult, ServiceError>(|txn| {
             Box::pin(async move {
                 let mut reservation_results = Vec::new();
                 let mut fully_reserved = true;
+                let items = self.expand_bundles(txn).await?;
+
+                // Check for existing reservations under the row lock
+                if let Some(first) = items.first() {
+                    lock_inventory_level(txn, &self.warehouse_id, first.product_id).await?;
+                }
+                self.check_existing_reservations(txn).await?;
 
-                for request in &self.items {
+                for request in &items {
                     let mut reserved_quantity = 0;
                     let mut product_id = request.product_id;
 
@@ -280,7 +308,10 @@ impl ReserveInventoryCommand {
                     if reserved_quantity < request.quantity {
                         fully_reserved = false;
                         if self.reservation_strategy == ReservationStrategy::Strict {
-                            return Err(InventoryError::InsufficientInventory(request.product_id));
+                            return Err(ServiceError::InvalidOperation(format!(
+                                "Insufficient inventory for product {}",
+                                request.product_id
+                            )));
                         }
                     }
 
@@ -308,17 +339,45 @@ impl ReserveInventoryCommand {
                     expiration_date,
                 })
             })
-        }).await
+        })
+        .await
+        .map_err(transaction_error)
+    }
+
+    /// Replaces requests for bundles and kits, matched on the bundle's product id, with
+    /// requests for their components, so ordering a kit reserves component stock. Substitutes do not apply to components.
+    async fn expand_bundles<C: ConnectionTrait>(&self, txn: &C) -> Result<Vec<ReservationRequest>, ServiceError> {
+        let product_ids = self.items.iter().map(|request| request.product_id).collect();
+        let bundles = components_for(txn, product_ids)
+            .await
+            .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+        Ok(self
+            .items
+            .iter()
+            .flat_map(|request| match bundles.get(&request.product_id) {
+                Some(components) => explode(components, request.quantity)
+                    .into_iter()
+                    .map(|(product_id, _, quantity)| ReservationRequest {
+                        product_id,
+                        quantity,
+                        lot_numbers: None,
+                        location_id: request.location_id.clone(),
+                        substitutes: None,
+                    })
+                    .collect(),
+                None => vec![request.clone()],
+            })
+            .collect())
     }
 
-    async fn create_reservation(
+    async fn create_reservation<C: ConnectionTrait>(
         &self,
-        txn: &DatabaseConnection,
+        txn: &C,
         product_id: Uuid,
         quantity: i32,
         request: &ReservationRequest,
         expiration_date: DateTime<Utc>,
-    ) -> Result<i32, InventoryError> {
+    ) -> Result<i32, ServiceError> {
         let reservation = inventory_reservation_entity::ActiveModel {
             id: Set(Uuid::new_v4()),
             warehouse_id: Set(self.warehouse_id.clone()),
@@ -340,7 +399,7 @@ impl ReserveInventoryCommand {
 
         reservation.insert(txn)
             .await
-            .map_err(|e| InventoryError::DatabaseError(e.to_string()))?;
+            .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
 
         Ok(quantity)
     }
@@ -349,7 +408,7 @@ impl ReserveInventoryCommand {
         &self,
         event_sender: &EventSender,
         results: &ReserveInventoryResult,
-    ) -> Result<(), InventoryError> {
+    ) -> Result<(), ServiceError> {
         info!(
             reference_id = %self.reference_id,
             reference_type = %self.reference_type,
@@ -383,7 +442,7 @@ impl ReserveInventoryCommand {
                 INVENTORY_RESERVATION_FAILURES.with_label_values(&["event_error"]).inc();
                 let msg = format!("Failed to send event for inventory reservation: {}", e);
                 error!("{}", msg);
-                InventoryError::EventError(msg)
+                ServiceError::EventError(msg)
             })?;
 
         if !results.fully_reserved {
@@ -396,9 +455,59 @@ impl ReserveInventoryCommand {
                     expiration_date: results.expiration_date,
                 })
                 .await
-                .map_err(|e| InventoryError::EventError(e.to_string()))?;
+                .map_err(|e| ServiceError::EventError(e.to_string()))?;
         }
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::product_bundle;
+
+    fn command() -> ReserveInventoryCommand {
+        ReserveInventoryCommand {
+            warehouse_id: "WH-1".to_string(),
+            reference_id: Uuid::new_v4(),
+            reference_type: "SALES_ORDER".to_string(),
+            items: vec![ReservationRequest {
+                product_id: Uuid::new_v4(),
+                quantity: 2,
+                lot_numbers: None,
+                location_id: None,
+                substitutes: None,
+            }],
+            reservation_type: ReservationType::SalesOrder,
+            duration_days: None,
+            ttl_seconds: Some(900),
+            priority: None,
+            notes: None,
+            reservation_strategy: ReservationStrategy::Strict,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reservation_locks_the_row_and_retries_serialization_failures() {
+        // First attempt: no bundles, then the locking read loses a serialization race.
+        // Second attempt: no bundles, and the inventory row is gone.
+        let db = MockDatabase::new(DbBackend::Postgres)
+            .append_query_results([Vec::<product_bundle::Model>::new()])
+            .append_query_errors([DbErr::Custom(
+                "could not serialize access due to concurrent update".to_string(),
+            )])
+            .append_query_results([Vec::<product_bundle::Model>::new(), Vec::new()])
+            .into_connection();
+
+        let result = command().reserve_inventory_in_db(&db, Utc::now()).await;
+
+        assert!(matches!(result, Err(ServiceError::NotFound(_))), "{:?}", result);
+        let log = db.into_transaction_log();
+        assert_eq!(log.len(), 2, "each attempt runs in its own transaction");
+        for attempt in &log {
+            let sql = format!("{:?}", attempt);
+            assert!(sql.contains("FOR UPDATE"), "{}", sql);
+        }
+    }
+}
@@ -8,6 +8,7 @@ use crate::{
         order_item_entity::{self, Entity as OrderItem},
     },
 };
+use super::order_event_store::{OrderDomainEvent, OrderEventStore};
 use serde::{Deserialize, Serialize};
 use tracing::{error, info, instrument};
 use uuid::Uuid;
@@ -31,6 +32,9 @@ pub struct AddItemToOrderCommand {
     pub product_id: Uuid,
     #[validate(range(min = 1))]
     pub quantity: i32,
+    /// Recorded in the order's event stream; set by the caller, never by the client.
+    #[serde(skip_deserializing)]
+    pub actor: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -39,7 +43,8 @@ pub struct AddItemToOrderResult {
     pub order_id: Uuid,
     pub product_id: Uuid,
     pub quantity: i32,
-    pub unit_price: f64,
+    #[serde(with = "crate::money::amount")]
+    pub unit_price: rust_decimal::Decimal,
 }
 
 #[async_trait::async_trait]
@@ -90,12 +95,24 @@ impl AddItemToOrderCommand {
             ..Default::default()
         };
 
-        new_item.insert(db).await.map_err(|e| {
+        let txn = db.begin().await.map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+        let saved_item = new_item.insert(&txn).await.map_err(|e| {
             ORDER_ITEM_ADD_FAILURES.inc();
             let msg = format!("Failed to add item to order {}: {}", self.order_id, e);
             error!("{}", msg);
             ServiceError::DatabaseError(msg)
-        })
+        })?;
+
+        OrderEventStore::append(
+            &txn,
+            self.order_id,
+            self.actor.clone(),
+            vec![OrderDomainEvent::ItemAdded { product_id: self.product_id, quantity: self.quantity }],
+        )
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+        txn.commit().await.map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+        Ok(saved_item)
     }
 
     async fn log_and_trigger_event(
@@ -8,6 +8,7 @@ use tracing::{info, error, instrument};
 use sea_orm::{DatabaseConnection, EntityTrait, ActiveModelTrait, Set};
 use prometheus::IntCounter;
 use lazy_static::lazy_static;
+use rust_decimal::Decimal;
 
 lazy_static! {
     static ref ORDER_DISCOUNTS_APPLIED: IntCounter = 
@@ -24,8 +25,9 @@ pub struct ApplyOrderDiscountCommand {
     #[validate(range(min = 1))]
     pub order_id: i32,
 
-    #[validate(range(min = 0.01, message = "Discount amount must be greater than zero"))]
-    pub discount_amount: f64,
+    #[validate(custom(function = "crate::money::validate_positive", message = "Discount amount must be greater than zero"))]
+    #[serde(with = "crate::money::amount")]
+    pub discount_amount: Decimal,
 }
 
 #[async_trait::async_trait]
@@ -10,6 +10,7 @@ use crate::{
         OrderStatus,
     },
 };
+use super::order_event_store::{OrderDomainEvent, OrderEventStore};
 use serde::{Deserialize, Serialize};
 use tracing::{error, info, instrument, warn};
 use uuid::Uuid;
@@ -37,6 +38,9 @@ pub struct CancelOrderCommand {
     #[validate(length(min = 1, max = 500, message = "Reason must be between 1 and 500 characters"))]
     pub reason: String,
     pub version: i32,  // For optimistic locking
+    /// Recorded in the order's event stream; set by the caller, never by the client.
+    #[serde(skip_deserializing)]
+    pub actor: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -117,6 +121,13 @@ impl CancelOrderCommand {
                 new_note.insert(txn).await
                     .map_err(|e| OrderError::DatabaseError(e.to_string()))?;
 
+                OrderEventStore::append(
+                    txn,
+                    self.order_id,
+                    self.actor.clone(),
+                    vec![OrderDomainEvent::Cancelled { reason: self.reason.clone() }],
+                ).await?;
+
                 Ok(updated_order)
             })
         }).await
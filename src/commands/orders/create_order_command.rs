@@ -4,12 +4,14 @@ use crate::{
     db::DbPool,
     errors::ServiceError,
     events::{Event, EventSender},
+    services::duplicate_orders::{self, OrderSignature},
     models::{
         order_entity::{self, Entity as Order},
         order_item_entity::{self, Entity as OrderItem},
         OrderStatus,
     },
 };
+use super::order_event_store::{OrderDomainEvent, OrderEventStore};
 use serde::{Deserialize, Serialize};
 use tracing::{error, info, instrument};
 use uuid::Uuid;
@@ -17,6 +19,7 @@ use validator::Validate;
 use prometheus::IntCounter;
 use lazy_static::lazy_static;
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 
 lazy_static! {
     static ref ORDER_CREATIONS: IntCounter = 
@@ -33,6 +36,16 @@ pub struct CreateOrderCommand {
     pub customer_id: Uuid,
     #[validate(length(min = 1, message = "At least one item is required"))]
     pub items: Vec<OrderItem>,
+    /// Order total as the storefront computed it; compared by duplicate detection.
+    #[serde(default)]
+    pub total: Option<Decimal>,
+    /// Place the order even if it looks like a duplicate of a recent one.
+    #[serde(default)]
+    pub allow_duplicate: bool,
+    /// Who placed the order, recorded in the order's event stream and when a duplicate
+    /// is let through.
+    #[serde(skip)]
+    pub placed_by: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -93,6 +106,14 @@ impl CreateOrderCommand {
     ) -> Result<order_entity::Model, ServiceError> {
         db.transaction::<_, order_entity::Model, ServiceError>(|txn| {
             Box::pin(async move {
+                let now = Utc::now();
+                let signature = OrderSignature {
+                    customer_id: self.customer_id,
+                    lines: self.items.iter().map(|i| (i.product_id.to_string(), i.quantity)).collect(),
+                    total: self.total,
+                };
+                let screening = duplicate_orders::screen(txn, &signature, self.allow_duplicate, now).await?;
+
                 let new_order = order_entity::ActiveModel {
                     customer_id: Set(self.customer_id),
                     status: Set(OrderStatus::Pending.to_string()),
@@ -120,6 +141,20 @@ impl CreateOrderCommand {
                     })?;
                 }
 
+                duplicate_orders::record(txn, saved_order.id, &signature, screening, self.placed_by.clone(), now).await?;
+
+                let mut events = vec![OrderDomainEvent::OrderCreated {
+                    customer_id: self.customer_id,
+                    status: saved_order.status.clone(),
+                }];
+                events.extend(self.items.iter().map(|item| OrderDomainEvent::ItemAdded {
+                    product_id: item.product_id,
+                    quantity: item.quantity,
+                }));
+                OrderEventStore::append(txn, saved_order.id, self.placed_by.clone(), events)
+                    .await
+                    .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+
                 Ok(saved_order)
             })
         }).await
@@ -27,6 +27,10 @@ pub struct CreatePromotionCommand {
     pub end_date: chrono::NaiveDateTime,
 
     pub applicable_products: Option<Vec<i32>>, // Product IDs this promotion applies to
+
+    /// Customer segments or tags the promotion is limited to (e.g. `vip`); empty or
+    /// absent means every customer. Checked with `CustomerSegmentService::is_eligible`.
+    pub target_segments: Option<Vec<String>>,
 }
 
 #[async_trait::async_trait]
@@ -66,6 +70,7 @@ impl CreatePromotionCommand {
             start_date: Set(self.start_date),
             end_date: Set(self.end_date),
             status: Set(PromotionStatus::Active),
+            target_segments: Set(serde_json::json!(self.target_segments.clone().unwrap_or_default())),
             created_at: Set(Some(chrono::Utc::now().naive_utc())),
             updated_at: Set(Some(chrono::Utc::now().naive_utc())),
             ..Default::default()
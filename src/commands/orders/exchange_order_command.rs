@@ -11,6 +11,7 @@ use crate::{
         OrderStatus,
     },
 };
+use super::order_event_store::{OrderDomainEvent, OrderEventStore};
 use serde::{Deserialize, Serialize};
 use tracing::{error, info, instrument};
 use uuid::Uuid;
@@ -38,6 +39,10 @@ pub struct ExchangeOrderCommand {
 
     #[validate(length(min = 1, message = "At least one new item is required"))]
     pub new_items: Vec<OrderItemInput>,
+
+    /// Recorded in the order's event stream; set by the caller, never by the client.
+    #[serde(skip_deserializing)]
+    pub actor: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
@@ -107,7 +112,22 @@ impl ExchangeOrderCommand {
             Box::pin(async move {
                 self.insert_return_items(txn).await?;
                 self.insert_new_items(txn).await?;
-                self.update_order_status(txn).await
+                let (from, updated_order) = self.update_order_status(txn).await?;
+
+                let mut events: Vec<OrderDomainEvent> = self
+                    .return_items
+                    .iter()
+                    .map(|item| OrderDomainEvent::ItemRemoved { product_id: item.product_id })
+                    .collect();
+                events.extend(self.new_items.iter().map(|item| OrderDomainEvent::ItemAdded {
+                    product_id: item.product_id,
+                    quantity: item.quantity,
+                }));
+                events.push(OrderDomainEvent::StatusChanged { from: Some(from), to: updated_order.status.clone() });
+                OrderEventStore::append(txn, self.order_id, self.actor.clone(), events)
+                    .await
+                    .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+                Ok(updated_order)
             })
         }).await
     }
@@ -150,7 +170,8 @@ impl ExchangeOrderCommand {
         Ok(())
     }
 
-    async fn update_order_status(&self, txn: &DatabaseTransaction) -> Result<order_entity::Model, ServiceError> {
+    /// Marks the order exchanged; returns the status it had before.
+    async fn update_order_status(&self, txn: &DatabaseTransaction) -> Result<(String, order_entity::Model), ServiceError> {
         let order = Order::find_by_id(self.order_id)
             .one(txn)
             .await
@@ -167,16 +188,18 @@ impl ExchangeOrderCommand {
                 ServiceError::NotFound(msg)
             })?;
 
+        let from = order.status.clone();
         let mut order: order_entity::ActiveModel = order.into();
         order.status = Set(OrderStatus::Exchanged.to_string());
         order.updated_at = Set(Utc::now().naive_utc());
 
-        order.update(txn).await.map_err(|e| {
+        let updated_order = order.update(txn).await.map_err(|e| {
             ORDER_EXCHANGE_FAILURES.inc();
             let msg = format!("Failed to update order status to Exchanged for order ID {}: {}", self.order_id, e);
             error!("{}", msg);
             ServiceError::DatabaseError(msg)
-        })
+        })?;
+        Ok((from, updated_order))
     }
 
     async fn log_and_trigger_event(
@@ -10,6 +10,7 @@ use crate::{
         OrderStatus,
     },
 };
+use super::order_event_store::{OrderDomainEvent, OrderEventStore};
 use serde::{Deserialize, Serialize};
 use tracing::{error, info, instrument};
 use uuid::Uuid;
@@ -30,6 +31,9 @@ pub struct HoldOrderCommand {
     #[validate(length(min = 1, max = 500, message = "Reason must be between 1 and 500 characters"))]
     pub reason: String,
     pub version: i32,  // For optimistic locking
+    /// Recorded in the order's event stream; set by the caller, never by the client.
+    #[serde(skip_deserializing)]
+    pub actor: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -110,6 +114,13 @@ impl HoldOrderCommand {
                 new_note.insert(txn).await
                     .map_err(|e| OrderError::DatabaseError(e.to_string()))?;
 
+                OrderEventStore::append(
+                    txn,
+                    self.order_id,
+                    self.actor.clone(),
+                    vec![OrderDomainEvent::PlacedOnHold { reason: self.reason.clone() }],
+                ).await?;
+
                 Ok(updated_order)
             })
         }).await
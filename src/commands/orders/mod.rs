@@ -17,6 +17,7 @@ pub mod update_shipping_address_command;
 pub mod refund_order_command;
 pub mod update_order_status_command;
 pub mod archive_order_command;
+pub mod order_event_store;
 
 
 // Re-export commands for easier access
@@ -38,4 +39,5 @@ pub use merge_orders_command::MergeOrdersCommand;
 pub use update_shipping_address_command::UpdateShippingAddressCommand;
 pub use refund_order_command::RefundOrderCommand;
 pub use update_order_status_command::UpdateOrderStatusCommand;
-pub use archive_order_command::ArchiveOrderCommand;
\ No newline at end of file
+pub use archive_order_command::ArchiveOrderCommand;
+pub use order_event_store::{OrderDomainEvent, OrderEventStore, OrderProjector};
\ No newline at end of file
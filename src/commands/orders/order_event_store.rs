@@ -0,0 +1,315 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use sea_orm::*;
+use crate::{
+    db::dialect::advisory_xact_lock,
+    errors::OrderError,
+    models::order_event::{self, Entity as OrderEventEntity},
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error, instrument};
+use uuid::Uuid;
+use prometheus::IntCounterVec;
+use lazy_static::lazy_static;
+use chrono::{DateTime, Utc};
+
+lazy_static! {
+    static ref ORDER_EVENTS_APPENDED: IntCounterVec =
+        IntCounterVec::new(
+            "order_events_appended_total",
+            "Total number of domain events appended to order streams",
+            &["event_type"]
+        ).expect("metric can be created");
+}
+
+/// Event sourcing for orders is opt-in; when disabled commands only write order rows.
+static EVENT_SOURCING_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Turns order event sourcing on or off for the process. Called once at startup from config.
+pub fn set_event_sourcing_enabled(enabled: bool) {
+    EVENT_SOURCING_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Returns whether order commands should append to the `order_events` stream.
+pub fn event_sourcing_enabled() -> bool {
+    EVENT_SOURCING_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Domain events recorded in an order's stream.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum OrderDomainEvent {
+    OrderCreated { customer_id: Uuid, status: String },
+    StatusChanged { from: Option<String>, to: String },
+    ItemAdded { product_id: Uuid, quantity: i32 },
+    ItemRemoved { product_id: Uuid },
+    NoteAdded { note: String },
+    PlacedOnHold { reason: String },
+    ReleasedFromHold,
+    Cancelled { reason: String },
+    Shipped { tracking_number: Option<String> },
+}
+
+impl OrderDomainEvent {
+    /// Name stored in `order_events.event_type`.
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            OrderDomainEvent::OrderCreated { .. } => "OrderCreated",
+            OrderDomainEvent::StatusChanged { .. } => "StatusChanged",
+            OrderDomainEvent::ItemAdded { .. } => "ItemAdded",
+            OrderDomainEvent::ItemRemoved { .. } => "ItemRemoved",
+            OrderDomainEvent::NoteAdded { .. } => "NoteAdded",
+            OrderDomainEvent::PlacedOnHold { .. } => "PlacedOnHold",
+            OrderDomainEvent::ReleasedFromHold => "ReleasedFromHold",
+            OrderDomainEvent::Cancelled { .. } => "Cancelled",
+            OrderDomainEvent::Shipped { .. } => "Shipped",
+        }
+    }
+}
+
+/// A domain event as read back from the stream.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedOrderEvent {
+    pub sequence: i64,
+    pub event: OrderDomainEvent,
+    pub actor: Option<String>,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Order state rebuilt purely from its event stream.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrderProjection {
+    pub order_id: Uuid,
+    pub customer_id: Option<Uuid>,
+    pub status: Option<String>,
+    pub items: Vec<ProjectedOrderItem>,
+    pub notes: Vec<String>,
+    pub hold_reason: Option<String>,
+    pub cancellation_reason: Option<String>,
+    pub tracking_number: Option<String>,
+    pub version: i64,
+    pub last_event_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectedOrderItem {
+    pub product_id: Uuid,
+    pub quantity: i32,
+}
+
+/// Folds an order's events into an `OrderProjection`.
+pub struct OrderProjector;
+
+impl OrderProjector {
+    /// Rebuilds the projection from a full stream, in sequence order.
+    pub fn project(order_id: Uuid, events: &[RecordedOrderEvent]) -> OrderProjection {
+        let mut state = OrderProjection {
+            order_id,
+            ..Default::default()
+        };
+        for recorded in events {
+            Self::apply(&mut state, recorded);
+        }
+        state
+    }
+
+    /// Applies a single event on top of an existing projection.
+    pub fn apply(state: &mut OrderProjection, recorded: &RecordedOrderEvent) {
+        match &recorded.event {
+            OrderDomainEvent::OrderCreated { customer_id, status } => {
+                state.customer_id = Some(*customer_id);
+                state.status = Some(status.clone());
+            }
+            OrderDomainEvent::StatusChanged { to, .. } => {
+                state.status = Some(to.clone());
+            }
+            OrderDomainEvent::ItemAdded { product_id, quantity } => {
+                match state.items.iter_mut().find(|i| i.product_id == *product_id) {
+                    Some(item) => item.quantity += quantity,
+                    None => state.items.push(ProjectedOrderItem {
+                        product_id: *product_id,
+                        quantity: *quantity,
+                    }),
+                }
+            }
+            OrderDomainEvent::ItemRemoved { product_id } => {
+                state.items.retain(|i| i.product_id != *product_id);
+            }
+            OrderDomainEvent::NoteAdded { note } => {
+                state.notes.push(note.clone());
+            }
+            OrderDomainEvent::PlacedOnHold { reason } => {
+                state.status = Some("OnHold".to_string());
+                state.hold_reason = Some(reason.clone());
+            }
+            OrderDomainEvent::ReleasedFromHold => {
+                state.status = Some("Pending".to_string());
+                state.hold_reason = None;
+            }
+            OrderDomainEvent::Cancelled { reason } => {
+                state.status = Some("Cancelled".to_string());
+                state.cancellation_reason = Some(reason.clone());
+            }
+            OrderDomainEvent::Shipped { tracking_number } => {
+                state.status = Some("Shipped".to_string());
+                state.tracking_number = tracking_number.clone();
+            }
+        }
+        state.version = recorded.sequence;
+        state.last_event_at = Some(recorded.recorded_at);
+    }
+}
+
+/// Reads and appends order event streams.
+pub struct OrderEventStore;
+
+impl OrderEventStore {
+    /// Appends events to an order's stream inside the caller's transaction. Does nothing
+    /// unless event sourcing is enabled, so commands can call it unconditionally.
+    #[instrument(skip(txn, events))]
+    pub async fn append<C: ConnectionTrait>(
+        txn: &C,
+        order_id: Uuid,
+        actor: Option<String>,
+        events: Vec<OrderDomainEvent>,
+    ) -> Result<(), OrderError> {
+        if !event_sourcing_enabled() || events.is_empty() {
+            return Ok(());
+        }
+
+        // Appends to one stream queue up here; the unique (order_id, sequence) index is
+        // the backstop should anything write to the table without taking the lock
+        advisory_xact_lock(txn, &format!("order_events:{}", order_id))
+            .await
+            .map_err(|e| OrderError::DatabaseError(e.to_string()))?;
+        let last_sequence = OrderEventEntity::find()
+            .filter(order_event::Column::OrderId.eq(order_id))
+            .order_by_desc(order_event::Column::Sequence)
+            .one(txn)
+            .await
+            .map_err(|e| OrderError::DatabaseError(e.to_string()))?
+            .map(|e| e.sequence)
+            .unwrap_or(0);
+
+        let now = Utc::now();
+        for (offset, event) in events.into_iter().enumerate() {
+            let event_type = event.event_type();
+            let payload = serde_json::to_value(&event).map_err(|e| {
+                error!("Failed to serialize order event {}: {}", event_type, e);
+                OrderError::EventError(e.to_string())
+            })?;
+
+            order_event::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                order_id: Set(order_id),
+                sequence: Set(last_sequence + offset as i64 + 1),
+                event_type: Set(event_type.to_string()),
+                payload: Set(payload),
+                actor: Set(actor.clone()),
+                created_at: Set(now),
+            }
+            .insert(txn)
+            .await
+            .map_err(|e| OrderError::DatabaseError(e.to_string()))?;
+
+            ORDER_EVENTS_APPENDED.with_label_values(&[event_type]).inc();
+        }
+
+        Ok(())
+    }
+
+    /// Loads the full stream for an order in sequence order.
+    #[instrument(skip(db))]
+    pub async fn load_stream<C: ConnectionTrait>(
+        db: &C,
+        order_id: Uuid,
+    ) -> Result<Vec<RecordedOrderEvent>, OrderError> {
+        let rows = OrderEventEntity::find()
+            .filter(order_event::Column::OrderId.eq(order_id))
+            .order_by_asc(order_event::Column::Sequence)
+            .all(db)
+            .await
+            .map_err(|e| OrderError::DatabaseError(e.to_string()))?;
+
+        rows.into_iter()
+            .map(|row| {
+                let event = serde_json::from_value(row.payload).map_err(|e| {
+                    error!("Corrupt order event {} for order {}: {}", row.id, order_id, e);
+                    OrderError::EventError(e.to_string())
+                })?;
+                Ok(RecordedOrderEvent {
+                    sequence: row.sequence,
+                    event,
+                    actor: row.actor,
+                    recorded_at: row.created_at,
+                })
+            })
+            .collect()
+    }
+
+    /// Loads the stream and rebuilds the order state from it.
+    pub async fn rebuild<C: ConnectionTrait>(
+        db: &C,
+        order_id: Uuid,
+    ) -> Result<(OrderProjection, Vec<RecordedOrderEvent>), OrderError> {
+        let events = Self::load_stream(db, order_id).await?;
+        if events.is_empty() {
+            return Err(OrderError::NotFound(order_id));
+        }
+        Ok((OrderProjector::project(order_id, &events), events))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded(sequence: i64, event: OrderDomainEvent) -> RecordedOrderEvent {
+        RecordedOrderEvent {
+            sequence,
+            event,
+            actor: Some("user-1".to_string()),
+            recorded_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_projection_rebuilds_state() {
+        let order_id = Uuid::new_v4();
+        let customer_id = Uuid::new_v4();
+        let product_a = Uuid::new_v4();
+        let product_b = Uuid::new_v4();
+
+        let events = vec![
+            recorded(1, OrderDomainEvent::OrderCreated { customer_id, status: "Pending".to_string() }),
+            recorded(2, OrderDomainEvent::ItemAdded { product_id: product_a, quantity: 2 }),
+            recorded(3, OrderDomainEvent::ItemAdded { product_id: product_b, quantity: 1 }),
+            recorded(4, OrderDomainEvent::ItemAdded { product_id: product_a, quantity: 3 }),
+            recorded(5, OrderDomainEvent::ItemRemoved { product_id: product_b }),
+            recorded(6, OrderDomainEvent::PlacedOnHold { reason: "fraud review".to_string() }),
+            recorded(7, OrderDomainEvent::ReleasedFromHold),
+            recorded(8, OrderDomainEvent::Cancelled { reason: "customer request".to_string() }),
+        ];
+
+        let state = OrderProjector::project(order_id, &events);
+        assert_eq!(state.customer_id, Some(customer_id));
+        assert_eq!(state.status.as_deref(), Some("Cancelled"));
+        assert_eq!(state.items, vec![ProjectedOrderItem { product_id: product_a, quantity: 5 }]);
+        assert_eq!(state.hold_reason, None);
+        assert_eq!(state.cancellation_reason.as_deref(), Some("customer request"));
+        assert_eq!(state.version, 8);
+    }
+
+    #[test]
+    fn test_event_payload_roundtrip() {
+        let event = OrderDomainEvent::Shipped { tracking_number: Some("1Z999".to_string()) };
+        let value = serde_json::to_value(&event).unwrap();
+        assert_eq!(value["type"], "Shipped");
+        let back: OrderDomainEvent = serde_json::from_value(value).unwrap();
+        assert_eq!(back, event);
+    }
+
+    #[test]
+    fn test_event_sourcing_is_opt_in() {
+        assert!(!event_sourcing_enabled());
+    }
+}
@@ -1,37 +1,34 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 use sea_orm::*;
 use crate::{
+    commands::inventory::{allocate_inventory_command::AllocationResult, AllocateInventoryCommand},
     db::DbPool,
     errors::ServiceError,
     events::{Event, EventSender},
-    models::{
-        order_entity::{self, Entity as Order},
-        order_item_entity::{self, Entity as OrderItem},
-        facility_entity::{self, Entity as Facility},
-        inventory_item_entity::{self, Entity as InventoryItem},
-        incoming_inventory_entity::{self, Entity as IncomingInventory},
-    },
-    services::geocoding::GeocodingService,
-    ml::routing_model::RoutingModel,
+    models::order::{self, Entity as Order},
+    services::sourcing::{OrderLine, SourcedShipment, SourcingLine, SourcingPlan, SourcingService},
 };
 use serde::{Deserialize, Serialize};
-use tracing::{error, info, instrument};
+use tracing::{error, info, instrument, warn};
 use uuid::Uuid;
 use validator::Validate;
 use prometheus::IntCounter;
 use lazy_static::lazy_static;
-use chrono::{DateTime, Duration, Utc};
 
 lazy_static! {
-    static ref ORDERS_ROUTED: IntCounter = 
+    static ref ORDERS_ROUTED: IntCounter =
         IntCounter::new("orders_routed_total", "Total number of orders routed")
             .expect("metric can be created");
 
-    static ref ORDER_ROUTING_FAILURES: IntCounter = 
+    static ref ORDER_ROUTING_FAILURES: IntCounter =
         IntCounter::new("order_routing_failures_total", "Total number of failed order routings")
             .expect("metric can be created");
 }
 
+/// Routes an order to the locations that ship it: sources its lines under the sourcing
+/// rules, overridden by the first matching routing rule, then allocates each sourced
+/// shipment at its location.
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct OrderRoutingCommand {
     pub order_id: Uuid,
@@ -40,61 +37,57 @@ pub struct OrderRoutingCommand {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OrderRoutingResult {
     pub original_order_id: Uuid,
+    /// Routing rule whose overrides applied, if any.
+    pub routing_rule_id: Option<Uuid>,
     pub routed_orders: Vec<RoutedOrder>,
+    /// What no location could ship, or could not be allocated where it was sourced.
+    pub unsourced: Vec<SourcingLine>,
+    pub fully_allocated: bool,
 }
 
+/// The part of the order one location ships.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RoutedOrder {
-    pub id: Uuid,
-    pub facility_id: Uuid,
+    pub warehouse: i32,
+    pub lines: Vec<SourcingLine>,
     pub item_count: usize,
 }
 
-#[derive(Debug)]
-struct FacilityScore {
-    facility_id: Uuid,
-    score: f64,
-    inventory_allocation: Vec<(Uuid, i32)>, // (product_id, quantity)
-}
-
 #[async_trait::async_trait]
 impl Command for OrderRoutingCommand {
     type Result = OrderRoutingResult;
 
-    #[instrument(skip(self, db_pool, event_sender, geocoding_service, routing_model))]
+    #[instrument(skip(self, db_pool, event_sender, sourcing))]
     async fn execute(
         &self,
         db_pool: Arc<DbPool>,
         event_sender: Arc<EventSender>,
-        geocoding_service: Arc<GeocodingService>,
-        routing_model: Arc<RoutingModel>,
+        sourcing: Arc<SourcingService>,
     ) -> Result<Self::Result, ServiceError> {
-        let db = db_pool.as_ref();
-
-        let (order, order_items) = self.fetch_order_and_items(db).await?;
-        let facilities = self.fetch_facilities(db).await?;
-        let facility_scores = self.score_facilities(db, &facilities, &order_items, &order, &geocoding_service, &routing_model).await?;
-        let allocated_orders = self.allocate_inventory(db, order, order_items, facility_scores).await?;
-        let updated_orders = self.update_orders(db, allocated_orders).await?;
-
-        self.log_and_trigger_events(&event_sender, &updated_orders).await?;
+        let order = self.fetch_order(db_pool.as_ref()).await?;
+        let (mut request, lines) = sourcing.order_request(self.order_id).await.map_err(|e| {
+            ORDER_ROUTING_FAILURES.inc();
+            e
+        })?;
+        // Routing rules on channel match the channel the order came in through
+        request.channel = order.source;
+        let plan = sourcing.simulate(request).await.map_err(|e| {
+            ORDER_ROUTING_FAILURES.inc();
+            e
+        })?;
+        let product_ids = self.product_ids(&lines)?;
+        let result = self.allocate(&db_pool, &event_sender, plan, &product_ids).await?;
+
+        self.log_and_trigger_events(&event_sender, &result).await?;
 
         ORDERS_ROUTED.inc();
-
-        Ok(OrderRoutingResult {
-            original_order_id: self.order_id,
-            routed_orders: updated_orders.into_iter().map(|o| RoutedOrder {
-                id: o.id,
-                facility_id: o.facility_id.unwrap(),
-                item_count: 0, // You might want to calculate this based on the actual items
-            }).collect(),
-        })
+        Ok(result)
     }
 }
 
 impl OrderRoutingCommand {
-    async fn fetch_order_and_items(&self, db: &DatabaseConnection) -> Result<(order_entity::Model, Vec<order_item_entity::Model>), ServiceError> {
-        let order = Order::find_by_id(self.order_id)
+    async fn fetch_order(&self, db: &DatabaseConnection) -> Result<order::Model, ServiceError> {
+        Order::find_by_id(self.order_id)
             .one(db)
             .await
             .map_err(|e| {
@@ -108,116 +101,84 @@ impl OrderRoutingCommand {
                 let msg = format!("Order {} not found", self.order_id);
                 error!("{}", msg);
                 ServiceError::NotFound(msg)
-            })?;
-
-        let order_items = OrderItem::find()
-            .filter(order_item_entity::Column::OrderId.eq(self.order_id))
-            .all(db)
-            .await
-            .map_err(|e| {
-                ORDER_ROUTING_FAILURES.inc();
-                let msg = format!("Failed to fetch order items for order {}: {}", self.order_id, e);
-                error!("{}", msg);
-                ServiceError::DatabaseError(msg)
-            })?;
-
-        Ok((order, order_items))
+            })
     }
 
-    async fn fetch_facilities(&self, db: &DatabaseConnection) -> Result<Vec<facility_entity::Model>, ServiceError> {
-        Facility::find()
-            .all(db)
-            .await
-            .map_err(|e| {
-                ORDER_ROUTING_FAILURES.inc();
-                let msg = format!("Failed to fetch facilities: {}", e);
-                error!("{}", msg);
-                ServiceError::DatabaseError(msg)
+    /// Products the order's SKUs are allocated under.
+    fn product_ids(&self, lines: &[OrderLine]) -> Result<HashMap<String, Uuid>, ServiceError> {
+        lines
+            .iter()
+            .map(|line| {
+                let product_id = Uuid::parse_str(&line.product_id).map_err(|_| {
+                    ORDER_ROUTING_FAILURES.inc();
+                    ServiceError::ValidationError(format!(
+                        "Order {} line {} has no product id to allocate under",
+                        self.order_id, line.sku
+                    ))
+                })?;
+                Ok((line.sku.clone(), product_id))
             })
+            .collect()
     }
 
-    async fn score_facilities(
+    /// Allocates each sourced shipment at its location. What a location can no longer
+    /// allocate, because its stock moved since the plan was made, is left unsourced.
+    async fn allocate(
         &self,
-        db: &DatabaseConnection,
-        facilities: &[facility_entity::Model],
-        order_items: &[order_item_entity::Model],
-        order: &order_entity::Model,
-        geocoding_service: &GeocodingService,
-        routing_model: &RoutingModel,
-    ) -> Result<Vec<FacilityScore>, ServiceError> {
-        let mut facility_scores = Vec::new();
-        for facility in facilities {
-            let score = self.score_facility(db, facility, order_items, order, geocoding_service, routing_model).await?;
-            facility_scores.push(score);
+        db_pool: &Arc<DbPool>,
+        event_sender: &Arc<EventSender>,
+        plan: SourcingPlan,
+        product_ids: &HashMap<String, Uuid>,
+    ) -> Result<OrderRoutingResult, ServiceError> {
+        let mut unsourced = plan.unsourced;
+        let mut routed_orders = Vec::new();
+        for shipment in plan.shipments {
+            let mut command = AllocateInventoryCommand::for_shipment(self.order_id, "ORDER", &shipment, product_ids)
+                .map_err(|e| ServiceError::ValidationError(e.to_string()))?;
+            command.notes = plan.routing_rule_id.map(|id| format!("Routed by routing rule {}", id));
+            let allocated = command
+                .execute(db_pool.clone(), event_sender.clone())
+                .await
+                .map_err(|e| {
+                    ORDER_ROUTING_FAILURES.inc();
+                    let msg = format!(
+                        "Failed to allocate order {} at warehouse {}: {}",
+                        self.order_id, shipment.warehouse, e
+                    );
+                    error!("{}", msg);
+                    ServiceError::InvalidOperation(msg)
+                })?;
+            let lines = allocated_lines(&shipment, product_ids, &allocated.allocations);
+            unsourced.extend(shortfall(&shipment.lines, &lines));
+            if !lines.is_empty() {
+                routed_orders.push(RoutedOrder { warehouse: shipment.warehouse, item_count: lines.len(), lines });
+            }
         }
-        facility_scores.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
-        Ok(facility_scores)
-    }
-
-    async fn score_facility(
-        &self,
-        db: &DatabaseConnection,
-        facility: &facility_entity::Model,
-        order_items: &[order_item_entity::Model],
-        order: &order_entity::Model,
-        geocoding_service: &GeocodingService,
-        routing_model: &RoutingModel,
-    ) -> Result<FacilityScore, ServiceError> {
-        // ... (keep the existing logic for scoring facilities)
-        // Remember to update any i32 IDs to Uuid
-        todo!("Implement facility scoring logic")
-    }
-
-    async fn allocate_inventory(
-        &self,
-        db: &DatabaseConnection,
-        original_order: order_entity::Model,
-        order_items: Vec<order_item_entity::Model>,
-        facility_scores: Vec<FacilityScore>,
-    ) -> Result<Vec<order_entity::Model>, ServiceError> {
-        // ... (keep the existing logic for allocating inventory)
-        // Remember to update any i32 IDs to Uuid
-        todo!("Implement inventory allocation logic")
-    }
-
-    async fn update_orders(
-        &self,
-        db: &DatabaseConnection,
-        allocated_orders: Vec<order_entity::Model>,
-    ) -> Result<Vec<order_entity::Model>, ServiceError> {
-        db.transaction::<_, Vec<order_entity::Model>, ServiceError>(|txn| {
-            Box::pin(async move {
-                let mut results = Vec::new();
-                for order in allocated_orders {
-                    let mut order: order_entity::ActiveModel = order.into();
-                    order.facility_id = Set(order.facility_id);
-                    let updated_order = order.update(txn).await.map_err(|e| {
-                        let msg = format!("Failed to update order: {}", e);
-                        error!("{}", msg);
-                        ServiceError::DatabaseError(msg)
-                    })?;
-                    results.push(updated_order);
-                }
-                Ok(results)
-            })
-        }).await
+        Ok(OrderRoutingResult {
+            original_order_id: self.order_id,
+            routing_rule_id: plan.routing_rule_id,
+            routed_orders,
+            fully_allocated: unsourced.is_empty(),
+            unsourced,
+        })
     }
 
     async fn log_and_trigger_events(
         &self,
         event_sender: &EventSender,
-        updated_orders: &[order_entity::Model],
+        result: &OrderRoutingResult,
     ) -> Result<(), ServiceError> {
-        for order in updated_orders {
+        for routed in &result.routed_orders {
             info!(
-                original_order_id = %self.order_id,
-                routed_order_id = %order.id,
-                facility_id = %order.facility_id.unwrap(),
+                order_id = %self.order_id,
+                routing_rule_id = ?result.routing_rule_id,
+                warehouse = routed.warehouse,
+                lines = routed.item_count,
                 "Order routed successfully"
             );
 
             event_sender
-                .send(Event::OrderRouted(order.id, order.facility_id.unwrap()))
+                .send(Event::OrderRouted(self.order_id, routed.warehouse))
                 .await
                 .map_err(|e| {
                     ORDER_ROUTING_FAILURES.inc();
@@ -226,6 +187,41 @@ impl OrderRoutingCommand {
                     ServiceError::EventError(msg)
                 })?;
         }
+        if !result.fully_allocated {
+            warn!(order_id = %self.order_id, unsourced = result.unsourced.len(), "Order only partly routed");
+        }
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// Units of the shipment's SKUs the allocation actually took.
+fn allocated_lines(
+    shipment: &SourcedShipment,
+    product_ids: &HashMap<String, Uuid>,
+    allocations: &[AllocationResult],
+) -> Vec<SourcingLine> {
+    shipment
+        .lines
+        .iter()
+        .filter_map(|line| {
+            let product_id = product_ids.get(&line.sku)?;
+            let quantity: i64 = allocations
+                .iter()
+                .filter(|a| a.product_id == *product_id)
+                .map(|a| i64::from(a.allocated_quantity))
+                .sum();
+            (quantity > 0).then(|| SourcingLine { sku: line.sku.clone(), quantity })
+        })
+        .collect()
+}
+
+/// What was sourced from a location but not allocated there.
+fn shortfall(sourced: &[SourcingLine], allocated: &[SourcingLine]) -> Vec<SourcingLine> {
+    sourced
+        .iter()
+        .filter_map(|line| {
+            let taken = allocated.iter().find(|a| a.sku == line.sku).map_or(0, |a| a.quantity);
+            (taken < line.quantity).then(|| SourcingLine { sku: line.sku.clone(), quantity: line.quantity - taken })
+        })
+        .collect()
+}
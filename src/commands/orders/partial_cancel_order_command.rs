@@ -73,7 +73,12 @@ impl PartialCancelOrderCommand {
             })?;
 
         // Calculate the new total
-        let new_total: f64 = remaining_items.iter().map(|item| item.price * item.quantity as f64).sum();
+        let new_total: rust_decimal::Decimal = crate::money::round_currency(
+            remaining_items
+                .iter()
+                .map(|item| item.price * rust_decimal::Decimal::from(item.quantity))
+                .sum(),
+        );
 
         // Update the order with the new total
         let order = Order::find_by_id(self.order_id)
@@ -14,12 +14,14 @@ use tracing::{error, info, instrument};
 use uuid::Uuid;
 use validator::Validate;
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct RefundOrderCommand {
     pub order_id: Uuid,
-    #[validate(range(min = 0.01))]
-    pub refund_amount: f64,
+    #[validate(custom = "crate::money::validate_positive")]
+    #[serde(with = "crate::money::amount")]
+    pub refund_amount: Decimal,
     #[validate(length(min = 1, max = 500))]
     pub reason: String,
 }
@@ -27,8 +29,10 @@ pub struct RefundOrderCommand {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RefundOrderResult {
     pub order_id: Uuid,
-    pub refunded_amount: f64,
-    pub new_total_amount: f64,
+    #[serde(with = "crate::money::amount")]
+    pub refunded_amount: Decimal,
+    #[serde(with = "crate::money::amount")]
+    pub new_total_amount: Decimal,
     pub refund_reason: String,
     pub refunded_at: DateTime<Utc>,
 }
@@ -9,6 +9,7 @@ use crate::{
         OrderStatus,
     },
 };
+use super::order_event_store::{OrderDomainEvent, OrderEventStore};
 use serde::{Deserialize, Serialize};
 use tracing::{error, info, instrument};
 use uuid::Uuid;
@@ -30,6 +31,9 @@ lazy_static! {
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct ReleaseOrderFromHoldCommand {
     pub order_id: Uuid,
+    /// Recorded in the order's event stream; set by the caller, never by the client.
+    #[serde(skip_deserializing)]
+    pub actor: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -70,8 +74,10 @@ impl ReleaseOrderFromHoldCommand {
         &self,
         db: &DatabaseConnection,
     ) -> Result<order_entity::Model, ServiceError> {
+        let txn = db.begin().await.map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
         let order = Order::find_by_id(self.order_id)
-            .one(db)
+            .lock_exclusive()
+            .one(&txn)
             .await
             .map_err(|e| {
                 ORDER_RELEASES_FROM_HOLD_FAILURES.inc();
@@ -97,12 +103,18 @@ impl ReleaseOrderFromHoldCommand {
         order.status = Set(OrderStatus::Pending.to_string());
         order.updated_at = Set(Utc::now().naive_utc());
 
-        order.update(db).await.map_err(|e| {
+        let updated_order = order.update(&txn).await.map_err(|e| {
             ORDER_RELEASES_FROM_HOLD_FAILURES.inc();
             let msg = format!("Failed to update order status to Pending for order ID {}: {}", self.order_id, e);
             error!("{}", msg);
             ServiceError::DatabaseError(msg)
-        })
+        })?;
+
+        OrderEventStore::append(&txn, self.order_id, self.actor.clone(), vec![OrderDomainEvent::ReleasedFromHold])
+            .await
+            .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+        txn.commit().await.map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+        Ok(updated_order)
     }
 
     async fn log_and_trigger_event(
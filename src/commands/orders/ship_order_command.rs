@@ -9,6 +9,7 @@ use crate::{
         OrderStatus,
     },
 };
+use super::order_event_store::{OrderDomainEvent, OrderEventStore};
 use serde::{Deserialize, Serialize};
 use tracing::{error, info, instrument};
 use uuid::Uuid;
@@ -73,8 +74,10 @@ impl ShipOrderCommand {
         &self,
         db: &DatabaseConnection,
     ) -> Result<order_entity::Model, ServiceError> {
+        let txn = db.begin().await.map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
         let order = Order::find_by_id(self.order_id)
-            .one(db)
+            .lock_exclusive()
+            .one(&txn)
             .await
             .map_err(|e| {
                 ORDER_SHIP_FAILURES.inc();
@@ -101,12 +104,23 @@ impl ShipOrderCommand {
         order.updated_at = Set(Utc::now().naive_utc());
         order.shipped_by = Set(Some(self.user_id));
 
-        order.update(db).await.map_err(|e| {
+        let shipped_order = order.update(&txn).await.map_err(|e| {
             ORDER_SHIP_FAILURES.inc();
             let msg = format!("Failed to update order status to 'Shipped' for order {}: {}", self.order_id, e);
             error!("{}", msg);
             ServiceError::DatabaseError(msg)
-        })
+        })?;
+
+        OrderEventStore::append(
+            &txn,
+            self.order_id,
+            Some(format!("user:{}", self.user_id)),
+            vec![OrderDomainEvent::Shipped { tracking_number: None }],
+        )
+        .await
+        .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+        txn.commit().await.map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+        Ok(shipped_order)
     }
 
     async fn log_and_trigger_event(
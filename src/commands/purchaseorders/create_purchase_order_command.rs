@@ -17,6 +17,8 @@ use validator::Validate;
 use prometheus::IntCounter;
 use lazy_static::lazy_static;
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use crate::money::round_currency;
 
 lazy_static! {
     static ref PO_CREATIONS: IntCounter = 
@@ -47,9 +49,12 @@ pub struct PurchaseOrderItem {
     pub product_id: Uuid,
     #[validate(range(min = 1))]
     pub quantity: i32,
-    #[validate(range(min = 0.0))]
-    pub unit_price: f64,
-    pub tax_rate: Option<f64>,
+    #[validate(custom = "crate::money::validate_non_negative")]
+    #[serde(with = "crate::money::amount")]
+    pub unit_price: Decimal,
+    /// Tax rate as a fraction, e.g. `0.0825`.
+    #[serde(default, with = "crate::money::option_amount")]
+    pub tax_rate: Option<Decimal>,
     pub currency: Option<String>,
     #[validate(length(max = 500))]
     pub description: Option<String>,
@@ -77,7 +82,8 @@ pub struct CreatePurchaseOrderResult {
     pub po_number: String,
     pub created_at: DateTime<Utc>,
     pub expected_delivery_date: DateTime<Utc>,
-    pub total_amount: f64,
+    #[serde(with = "crate::money::amount")]
+    pub total_amount: Decimal,
     pub currency: String,
     pub items: Vec<PurchaseOrderItem>,
 }
@@ -138,12 +144,18 @@ impl CreatePurchaseOrderCommand {
         format!("PO-{}", Uuid::new_v4().simple())
     }
 
-    fn calculate_total_amount(&self) -> f64 {
-        self.items.iter().fold(0.0, |acc, item| {
-            let item_total = item.unit_price * item.quantity as f64;
-            let tax_amount = item.tax_rate.unwrap_or(0.0) * item_total;
-            acc + item_total + tax_amount
-        })
+    fn calculate_total_amount(&self) -> Decimal {
+        self.items
+            .iter()
+            .map(|item| Self::item_total(item))
+            .sum()
+    }
+
+    /// Line total including tax, rounded to currency precision.
+    fn item_total(item: &PurchaseOrderItem) -> Decimal {
+        let item_total = item.unit_price * Decimal::from(item.quantity);
+        let tax_amount = item.tax_rate.unwrap_or(Decimal::ZERO) * item_total;
+        round_currency(item_total + tax_amount)
     }
 
     async fn create_purchase_order(
@@ -178,8 +190,6 @@ impl CreatePurchaseOrderCommand {
                 })?;
 
                 for item in &self.items {
-                    let item_total = item.unit_price * item.quantity as f64;
-                    let tax_amount = item.tax_rate.unwrap_or(0.0) * item_total;
 
                     let new_item = purchase_order_item_entity::ActiveModel {
                         purchase_order_id: Set(saved_po.id),
@@ -188,7 +198,7 @@ impl CreatePurchaseOrderCommand {
                         unit_price: Set(item.unit_price),
                         currency: Set(item.currency.clone().unwrap_or(self.currency.clone())),
                         tax_rate: Set(item.tax_rate),
-                        total_amount: Set(item_total + tax_amount),
+                        total_amount: Set(Self::item_total(item)),
                         description: Set(item.description.clone()),
                         status: Set(PurchaseOrderStatus::Draft.to_string()),
                         created_at: Set(Utc::now().naive_utc()),
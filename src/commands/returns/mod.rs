@@ -6,6 +6,8 @@ pub mod complete_return_command;
 pub mod refund_return_command;
 pub mod approve_return_command;
 pub mod reject_return_command;
+pub mod reopen_return_command;
+pub mod restock_returned_items_command;
 
 // Re-export commands for easier access
 pub use create_return_command::InitiateReturnCommand;
@@ -16,3 +18,5 @@ pub use complete_return_command::CompleteReturnCommand;
 pub use refund_return_command::RefundReturnCommand;
 pub use approve_return_command::ApproveReturnCommand;
 pub use reject_return_command::RejectReturnCommand;
+pub use reopen_return_command::ReopenReturnCommand;
+pub use restock_returned_items_command::RestockReturnedItemsCommand;
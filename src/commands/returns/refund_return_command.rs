@@ -13,19 +13,22 @@ use serde::{Deserialize, Serialize};
 use tracing::{error, info, instrument};
 use uuid::Uuid;
 use validator::Validate;
+use rust_decimal::Decimal;
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct RefundReturnCommand {
     pub return_id: Uuid,
-    #[validate(range(min = 0.0))]
-    pub refund_amount: f64,
+    #[validate(custom = "crate::money::validate_non_negative")]
+    #[serde(with = "crate::money::amount")]
+    pub refund_amount: Decimal,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RefundReturnResult {
     pub id: Uuid,
     pub status: String,
-    pub refund_amount: f64,
+    #[serde(with = "crate::money::amount")]
+    pub refund_amount: Decimal,
 }
 
 #[async_trait::async_trait]
@@ -1,10 +1,11 @@
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
-use crate::{errors::ServiceError, db::DbPool, models::{shipment, Shipment}};
+use crate::{errors::ServiceError, db::DbPool, models::{shipment, shipment::ShippingCarrier, Shipment}};
+use crate::services::hazmat_service;
 use crate::events::{Event, EventSender};
 use tracing::{info, error, instrument};
-use sea_orm::{ActiveModelTrait, ActiveValue::Set, EntityTrait};
+use sea_orm::{ActiveEnum, ActiveModelTrait, ActiveValue::Set, EntityTrait};
 use validator::Validate;
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
@@ -45,9 +46,25 @@ impl AssignShipmentCarrierCommand {
                 ServiceError::NotFound
             })?;
 
+        let carrier = ShippingCarrier::try_from_value(&self.carrier_name)
+            .map_err(|_| ServiceError::ValidationError(format!("Unknown carrier: {}", self.carrier_name)))?;
+
+        // Dangerous goods already declared in the shipment must be acceptable to the new carrier
+        let violations = hazmat_service::selection_violations(db, self.shipment_id, carrier, &shipment.shipping_method)
+            .await
+            .map_err(|e| ServiceError::DatabaseError(format!("Failed to check dangerous goods: {}", e)))?;
+        if !violations.is_empty() {
+            return Err(ServiceError::ValidationError(format!(
+                "{} cannot carry shipment {}: {}",
+                self.carrier_name,
+                self.shipment_id,
+                violations.join("; ")
+            )));
+        }
+
         let mut shipment_active_model: shipment::ActiveModel = shipment.into();
 
-        shipment_active_model.carrier = Set(self.carrier_name.clone());
+        shipment_active_model.carrier = Set(carrier);
 
         shipment_active_model
             .update(db)
@@ -60,6 +77,10 @@ impl AssignShipmentCarrierCommand {
 
     async fn log_and_trigger_event(&self, event_sender: Arc<EventSender>, shipment: &shipment::Model) -> Result<(), ServiceError> {
         info!("Carrier assigned to shipment ID: {}. Carrier: {}", self.shipment_id, self.carrier_name);
+        let _ = event_sender.send(Event::ShipmentBooked {
+            shipment_id: shipment.id,
+            tracking_number: shipment.tracking_number.clone(),
+        });
         event_sender.send(Event::CarrierAssignedToShipment(self.shipment_id, self.carrier_name.clone()))
             .await
             .map_err(|e| {
@@ -1,8 +1,10 @@
 use async_trait::async_trait;
 use serde::{Serialize, Deserialize};
+use std::str::FromStr;
 use std::sync::Arc;
 use crate::{errors::ServiceError, db::DbPool, models::{work_order_entity, bill_of_materials_entity, bom_item_entity, inventory_item_entity}};
 use crate::events::{Event, EventSender};
+use crate::services::work_order_operations;
 use validator::Validate;
 use tracing::{info, error, instrument};
 use prometheus::IntCounter;
@@ -70,8 +72,17 @@ impl Command for CalculateCOGSCommand {
                 ServiceError::NotFound(format!("BOM for work order {} not found", self.work_order_number))
             })?;
 
-        // Calculate total cost
-        let total_cost = self.calculate_total_cost(&db, &bom).await?;
+        // Material cost from the BOM plus labor and machine time actually recorded
+        let material_cost = self.calculate_total_cost(&db, &bom).await?;
+        let conversion_cost = work_order_operations::conversion_cost(&db, work_order.id)
+            .await
+            .map_err(|e| {
+                COGS_CALCULATION_FAILURES.inc();
+                error!("Failed to fetch recorded time for work order {}: {}", self.work_order_number, e);
+                ServiceError::DatabaseError(format!("Failed to fetch recorded time: {}", e))
+            })?;
+        let total_cost = material_cost
+            + BigDecimal::from_str(&conversion_cost.to_string()).expect("decimal renders as a valid number");
 
         // Calculate quantity produced
         let quantity_produced = work_order_entity::Entity::find()
@@ -0,0 +1,193 @@
+// compression/mod.rs
+
+use axum::{
+    body::HttpBody,
+    extract::State,
+    http::{Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use lazy_static::lazy_static;
+use prometheus::IntCounter;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tower_http::compression::{
+    predicate::{NotForContentType, Predicate, SizeAbove},
+    CompressionLayer,
+};
+use tracing::warn;
+
+lazy_static! {
+    static ref OVERSIZED_RESPONSES: IntCounter =
+        IntCounter::new(
+            "oversized_responses_total",
+            "List responses replaced because they exceeded the response size budget"
+        ).expect("metric can be created");
+}
+
+/// Response compression and size settings, loaded from the `compression` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CompressionConfig {
+    /// Offers Brotli to clients that accept it; preferred over gzip (default: true).
+    #[serde(default = "default_true")]
+    pub brotli: bool,
+
+    #[serde(default = "default_true")]
+    pub gzip: bool,
+
+    /// Smaller responses are sent as is (default: 1 KiB).
+    #[serde(default = "default_min_size_bytes")]
+    pub min_size_bytes: u16,
+
+    /// Path prefixes whose responses are never compressed, e.g. routes serving archives.
+    #[serde(default)]
+    pub skip_paths: Vec<String>,
+
+    /// Largest JSON response a GET may return; bigger ones are replaced with a pointer to
+    /// the export API. Unlimited when unset.
+    #[serde(default)]
+    pub max_response_bytes: Option<u64>,
+
+    /// Path prefixes exempt from the size budget.
+    #[serde(default)]
+    pub budget_exempt_paths: Vec<String>,
+
+    /// Where clients are told to request an export instead.
+    #[serde(default = "default_export_path")]
+    pub export_path: String,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_min_size_bytes() -> u16 {
+    1024
+}
+
+fn default_export_path() -> String {
+    "/exports".to_string()
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            brotli: true,
+            gzip: true,
+            min_size_bytes: default_min_size_bytes(),
+            skip_paths: Vec::new(),
+            max_response_bytes: None,
+            budget_exempt_paths: Vec::new(),
+            export_path: default_export_path(),
+        }
+    }
+}
+
+/// Response extension that keeps the compression layer off a response. Handlers
+/// returning already-compressed content add it to their response.
+#[derive(Clone, Copy, Debug)]
+pub struct SkipCompression;
+
+#[derive(Clone, Copy, Debug)]
+pub struct NotSkipped;
+
+impl Predicate for NotSkipped {
+    fn should_compress<B: HttpBody>(&self, response: &axum::http::Response<B>) -> bool {
+        response.extensions().get::<SkipCompression>().is_none()
+    }
+}
+
+/// The compression layer: Brotli and/or gzip, leaving out small responses, streams and
+/// content that is compressed already.
+pub fn layer(config: &CompressionConfig) -> CompressionLayer<impl Predicate> {
+    let predicate = SizeAbove::new(config.min_size_bytes)
+        .and(NotForContentType::GRPC)
+        .and(NotForContentType::IMAGES)
+        .and(NotForContentType::SSE)
+        .and(NotForContentType::const_new("application/pdf"))
+        .and(NotForContentType::const_new("application/zip"))
+        .and(NotForContentType::const_new("application/gzip"))
+        .and(NotSkipped);
+    CompressionLayer::new()
+        .br(config.brotli)
+        .gzip(config.gzip)
+        .compress_when(predicate)
+}
+
+fn matches_prefix(path: &str, prefixes: &[String]) -> bool {
+    prefixes.iter().any(|prefix| path.starts_with(prefix.as_str()))
+}
+
+/// Whether a response of `size` bytes to `path` breaks the budget.
+pub fn over_budget(config: &CompressionConfig, path: &str, size: u64) -> bool {
+    match config.max_response_bytes {
+        Some(limit) => size > limit && !matches_prefix(path, &config.budget_exempt_paths),
+        None => false,
+    }
+}
+
+fn is_json(response: &Response) -> bool {
+    response
+        .headers()
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map_or(false, |v| v.starts_with("application/json"))
+}
+
+/// Applies `skip_paths` and the response size budget. Runs inside the compression layer,
+/// so sizes are uncompressed; streamed bodies of unknown size are let through.
+pub async fn response_policy_middleware<B>(
+    State(config): State<Arc<CompressionConfig>>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let path = req.uri().path().to_string();
+    let query = req.uri().query().map(str::to_string);
+    let is_get = req.method() == Method::GET;
+    let mut response = next.run(req).await;
+
+    if matches_prefix(&path, &config.skip_paths) {
+        response.extensions_mut().insert(SkipCompression);
+    }
+    if is_get && response.status().is_success() && is_json(&response) {
+        if let Some(size) = response.body().size_hint().exact().filter(|size| over_budget(&config, &path, *size)) {
+            warn!(%path, size, "Response exceeded the size budget; suggesting an export");
+            OVERSIZED_RESPONSES.inc();
+            let body = json!({
+                "error": format!(
+                    "Response of {} bytes exceeds the {} byte limit; narrow the query or request an export",
+                    size,
+                    config.max_response_bytes.unwrap_or_default()
+                ),
+                "code": "response_too_large",
+                "export": {
+                    "method": "POST",
+                    "path": config.export_path,
+                    "body": { "resource": path, "query": query },
+                },
+            });
+            return (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response();
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_applies_outside_exempt_paths() {
+        let config = CompressionConfig {
+            max_response_bytes: Some(1_000),
+            budget_exempt_paths: vec!["/api/v1/admin/usage/export".to_string()],
+            ..Default::default()
+        };
+        assert!(over_budget(&config, "/orders", 1_001));
+        assert!(!over_budget(&config, "/orders", 1_000));
+        assert!(!over_budget(&config, "/api/v1/admin/usage/export", 5_000));
+        assert!(!over_budget(&CompressionConfig::default(), "/orders", u64::MAX));
+    }
+}
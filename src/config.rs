@@ -1,11 +1,77 @@
 use serde::Deserialize;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::env;
 use std::sync::Arc;
 use config::{Config, ConfigError, Environment, File};
 use validator::{Validate, ValidationError};
 use thiserror::Error;
 use tracing::{error, info};
+use crate::db::DatabasePoolConfig;
+use crate::network_acl::NetworkAclConfig;
+use crate::middleware_helpers::request_signing::RequestSigningConfig;
+use crate::logging::redaction::RedactionConfig;
+use crate::request_archive::RequestArchiveConfig;
+use crate::agents::fraud::OrderFraudConfig;
+use crate::agents::AgentsConfig;
+use crate::assist::AssistConfig;
+use crate::services::duplicate_orders::DuplicateOrderConfig;
+use crate::services::return_triage::ReturnTriageConfig;
+use crate::services::return_fraud::ReturnFraudConfig;
+use crate::services::inventory_aging::InventoryAgingConfig;
+use crate::services::sourcing::SourcingConfig;
+use crate::shipment_sla::ShipmentSlaConfig;
+use crate::services::quality_service::QualityConfig;
+use crate::workflow::WorkflowConfig;
+use crate::integrations::accounting::AccountingConfig;
+use crate::integrations::amazon::AmazonConfig;
+use crate::ledger::LedgerConfig;
+use crate::payments::PaymentsConfig;
+use crate::dunning::DunningConfig;
+use crate::services::payment_vault::PaymentVaultConfig;
+use crate::disputes::DisputesConfig;
+use crate::checkout::CheckoutConfig;
+use crate::customer_segments::CustomerSegmentsConfig;
+use crate::embeddings::EmbeddingsConfig;
+use crate::geocoding::GeocodingConfig;
+use crate::carriers::CarriersConfig;
+use crate::hazmat::HazmatConfig;
+use crate::customs::CustomsConfig;
+use crate::dropship::DropshipConfig;
+use crate::metering::MeteringConfig;
+use crate::encryption::EncryptionConfig;
+use crate::partitioning::PartitioningConfig;
+use crate::compression::CompressionConfig;
+use crate::deadline::DeadlineConfig;
+use crate::shutdown::ShutdownConfig;
+use crate::http_server::HttpServerConfig;
+use crate::tls::TlsConfig;
+use crate::abuse::AbuseConfig;
+use crate::maintenance::MaintenanceConfig;
+use crate::slo::SloConfig;
+use crate::synthetic::SyntheticConfig;
+use crate::cache::swr::AnalyticsCacheConfig;
+use crate::cdc::CdcConfig;
+use crate::stock_alerts::StockAlertsConfig;
+use crate::calendar::CalendarConfig;
+use crate::backfill::BackfillConfig;
+use crate::developer_logs::DeveloperLogsConfig;
+use crate::tenant_export::TenantExportConfig;
+use crate::webhooks::WebhooksConfig;
+use crate::ingest::IngestConfig;
+use crate::inbound_email::InboundEmailConfig;
+use crate::services::case_service::CasesConfig;
+use crate::inventory_snapshots::InventorySnapshotConfig;
+use crate::product_feed::ProductFeedConfig;
+use crate::reservation_expiry::ReservationExpiryConfig;
+use crate::retention::RetentionConfig;
+use crate::semantic_search::SemanticSearchConfig;
+use crate::websocket::WebSocketConfig;
+
+pub mod secrets;
+pub mod watcher;
+
+use secrets::{SecretStore, SecretsConfig, SecretsError};
+use watcher::RateLimitSettings;
 
 /// Default log level if not specified in configuration.
 fn default_log_level() -> String {
@@ -19,11 +85,16 @@ pub struct AppConfig {
     #[validate(url)]
     pub database_url: String,
 
+    /// Connection pool sizing, statement timeout and slow-acquire alerting.
+    #[serde(default)]
+    pub database_pool: DatabasePoolConfig,
+
     /// Redis connection URL.
     #[validate(url)]
     pub redis_url: String,
 
-    /// Secret key for signing JWT tokens.
+    /// Secret key for signing JWT tokens. May be omitted when resolved from a secret store.
+    #[serde(default)]
     #[validate(length(min = 32))]
     pub jwt_secret: String,
 
@@ -47,9 +118,269 @@ pub struct AppConfig {
     #[validate(length(min = 1))]
     pub environment: String,
 
-    /// Logging level (default: "info").
+    /// Logging level (default: "info"). Reloadable.
     #[serde(default = "default_log_level")]
     pub log_level: String,
+
+    /// Global rate limit. Reloadable.
+    #[serde(default)]
+    pub rate_limit: RateLimitSettings,
+
+    /// Feature flags keyed by name. Reloadable.
+    #[serde(default)]
+    pub feature_flags: HashMap<String, bool>,
+
+    /// Origins allowed to make cross-origin requests; `*` allows any. Reloadable.
+    #[serde(default)]
+    pub cors_origins: Vec<String>,
+
+    /// Request body logging and PII redaction.
+    #[serde(default)]
+    pub log_redaction: RedactionConfig,
+
+    /// Sampled request/response archive to object storage.
+    #[serde(default)]
+    pub request_archive: RequestArchiveConfig,
+
+    /// Per-entity data retention policies.
+    #[serde(default)]
+    pub retention: RetentionConfig,
+
+    /// Live updates pushed over `/api/v1/ws`.
+    #[serde(default)]
+    pub websocket: WebSocketConfig,
+
+    /// Nightly inventory snapshots backing `/api/v1/inventory/history`.
+    #[serde(default)]
+    pub inventory_snapshots: InventorySnapshotConfig,
+
+    /// Customer segment rules and evaluation schedule.
+    #[serde(default)]
+    pub customer_segments: CustomerSegmentsConfig,
+
+    /// Sweep that releases expired inventory reservations such as abandoned cart holds.
+    #[serde(default)]
+    pub reservation_expiry: ReservationExpiryConfig,
+
+    /// Persistence of checkout sessions under `/api/v1/checkout`.
+    #[serde(default)]
+    pub checkout: CheckoutConfig,
+
+    /// Merchant details and shipping options published in the agentic product feed.
+    #[serde(default)]
+    pub product_feed: ProductFeedConfig,
+
+    /// Embedding-based product search under `/api/v1/products/search/semantic`.
+    #[serde(default)]
+    pub semantic_search: SemanticSearchConfig,
+
+    /// Embedding provider used by semantic search: OpenAI or a local model.
+    #[serde(default)]
+    pub embeddings: EmbeddingsConfig,
+
+    /// Order support chat under `/api/v1/assist`.
+    #[serde(default)]
+    pub assist: AssistConfig,
+
+    /// Background agents: run intervals and which start paused or disabled.
+    #[serde(default)]
+    pub agents: AgentsConfig,
+
+    /// Signals the `fraud` agent flags new orders on.
+    #[serde(default)]
+    pub order_fraud: OrderFraudConfig,
+
+    /// Guardrails for automatically approving requested returns.
+    #[serde(default)]
+    pub return_triage: ReturnTriageConfig,
+
+    /// Fraud signals and the risk score above which returns are flagged for inspection.
+    #[serde(default)]
+    pub return_fraud: ReturnFraudConfig,
+
+    /// Obsolescence reserve rates and the demand cover behind excess-stock suggestions.
+    #[serde(default)]
+    pub inventory_aging: InventoryAgingConfig,
+
+    /// Rules for choosing the warehouses and stores an order ships from.
+    #[serde(default)]
+    pub sourcing: SourcingConfig,
+
+    /// Transit-time expectations and late-delivery alert recipients for shipments.
+    #[serde(default)]
+    pub shipment_sla: ShipmentSlaConfig,
+
+    /// Where failed inspection quantities are held.
+    #[serde(default)]
+    pub quality: QualityConfig,
+
+    /// Approval chains per subject and escalation of overdue approvals.
+    #[serde(default)]
+    pub workflow: WorkflowConfig,
+
+    /// Journal entry export to QuickBooks, NetSuite or Xero.
+    #[serde(default)]
+    pub accounting: AccountingConfig,
+
+    /// Amazon marketplace order and FBA inventory sync.
+    #[serde(default)]
+    pub amazon: AmazonConfig,
+
+    /// Internal double-entry ledger fed by domain events.
+    #[serde(default)]
+    pub ledger: LedgerConfig,
+
+    /// Payment gateway and when authorized payments are captured.
+    #[serde(default)]
+    pub payments: PaymentsConfig,
+
+    /// Retry curve and subscriber notices for failed subscription charges.
+    #[serde(default)]
+    pub dunning: DunningConfig,
+
+    /// Vault tokens standing in for stored payment methods in checkout and subscriptions.
+    #[serde(default)]
+    pub payment_vault: PaymentVaultConfig,
+
+    /// Dispute webhook verification and evidence deadline warnings.
+    #[serde(default)]
+    pub disputes: DisputesConfig,
+
+    /// Interval in seconds at which reloadable settings are re-read; 0 reloads on SIGHUP only.
+    #[serde(default)]
+    pub config_reload_interval_secs: u64,
+
+    /// Record order changes in the `order_events` stream (default: false).
+    #[serde(default)]
+    pub order_event_sourcing: bool,
+
+    /// Flagging or blocking of orders that repeat a recent order from the same customer.
+    #[serde(default)]
+    pub duplicate_orders: DuplicateOrderConfig,
+
+    /// Address geocoding used to place customers and warehouses in shipping zones.
+    #[serde(default)]
+    pub geocoding: GeocodingConfig,
+
+    /// Carrier accounts and how end-of-day manifests are submitted.
+    #[serde(default)]
+    pub carriers: CarriersConfig,
+
+    /// Dangerous goods contracts and emergency contact for hazmat shipments.
+    #[serde(default)]
+    pub hazmat: HazmatConfig,
+
+    /// Ship-from country and destination duty schedules for international shipments.
+    #[serde(default)]
+    pub customs: CustomsConfig,
+
+    /// Suppliers that ship dropship orders and how orders are forwarded to them.
+    #[serde(default)]
+    pub dropship: DropshipConfig,
+
+    /// Per-tenant usage counting for billing.
+    #[serde(default)]
+    pub metering: MeteringConfig,
+
+    /// Envelope encryption of customer PII and payment references with per-tenant keys.
+    #[serde(default)]
+    pub encryption: EncryptionConfig,
+
+    /// Time-range partitioning of high-volume tables and scheduled partition maintenance.
+    #[serde(default)]
+    pub partitioning: PartitioningConfig,
+
+    /// Response compression (Brotli, gzip) and the response size budget.
+    #[serde(default)]
+    pub compression: CompressionConfig,
+
+    /// Per-request deadlines from `X-Request-Timeout`/`X-Request-Deadline`.
+    #[serde(default)]
+    pub deadlines: DeadlineConfig,
+
+    /// How long in-flight requests and jobs may take to finish on shutdown.
+    #[serde(default)]
+    pub shutdown: ShutdownConfig,
+
+    /// HTTP/2, keep-alive and TCP listener tuning.
+    #[serde(default)]
+    pub http_server: HttpServerConfig,
+
+    /// Native TLS from certificate files or ACME, and mTLS for service accounts.
+    #[serde(default)]
+    pub tls: TlsConfig,
+
+    /// Scraper detection on product endpoints: tarpits, challenges and the blocklist.
+    #[serde(default)]
+    pub abuse: AbuseConfig,
+
+    /// Route groups behind kill switches; the switches themselves are feature flags.
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+
+    /// Availability and latency objectives per route group, with burn-rate tracking.
+    #[serde(default)]
+    pub slo: SloConfig,
+
+    /// Scheduled order → reserve → cancel runs behind `/health/synthetic`.
+    #[serde(default)]
+    pub synthetic: SyntheticConfig,
+
+    /// Stale-while-revalidate caching of analytics reports, with per-report policies.
+    #[serde(default)]
+    pub analytics_cache: AnalyticsCacheConfig,
+
+    /// Row-change feed for the data warehouse, published to the message queue.
+    #[serde(default)]
+    pub cdc: CdcConfig,
+
+    /// Low-stock alerts against per-SKU reorder thresholds.
+    #[serde(default)]
+    pub stock_alerts: StockAlertsConfig,
+
+    /// Warehouse time zones, working weeks, holidays and same-day cutoffs.
+    #[serde(default)]
+    pub calendar: CalendarConfig,
+
+    /// Chunk size, rate limit and SQL definitions of background backfills.
+    #[serde(default)]
+    pub backfills: BackfillConfig,
+
+    /// Developer console logs of API-key requests and webhook deliveries.
+    #[serde(default)]
+    pub developer_logs: DeveloperLogsConfig,
+
+    /// Bucket, link lifetime and table selection of tenant offboarding exports.
+    #[serde(default)]
+    pub tenant_export: TenantExportConfig,
+
+    /// Entity webhook delivery and the keys redacted from payloads as PII.
+    #[serde(default)]
+    pub webhooks: WebhooksConfig,
+
+    /// Inbound partner webhook sources: signature scheme, payload schema and mappings.
+    #[serde(default)]
+    pub ingest: IngestConfig,
+
+    /// Inbound return/warranty emails from SES or SendGrid, turned into draft returns.
+    #[serde(default)]
+    pub inbound_email: InboundEmailConfig,
+
+    /// First-response and resolution SLA targets per support case priority.
+    #[serde(default)]
+    pub cases: CasesConfig,
+
+    /// Network ACL applied before authentication.
+    #[serde(default)]
+    pub network_acl: NetworkAclConfig,
+
+    /// HMAC request signing for payment and admin endpoints.
+    #[serde(default)]
+    pub request_signing: RequestSigningConfig,
+
+    /// External secret store (Vault / AWS Secrets Manager) to resolve secrets from.
+    #[serde(default)]
+    pub secrets: SecretsConfig,
 }
 
 impl AppConfig {
@@ -82,6 +413,9 @@ pub enum AppConfigError {
 
     #[error("Invalid configuration: {0}")]
     ValidationError(#[from] validator::ValidationErrors),
+
+    #[error("Failed to resolve secrets: {0}")]
+    SecretsError(#[from] SecretsError),
 }
 
 /// Loads the application configuration.
@@ -100,6 +434,33 @@ pub fn load_config() -> Result<AppConfig, AppConfigError> {
     // Initialize tracing for configuration loading
     tracing_subscriber::fmt::init();
 
+    let app_config = read_config()?;
+
+    // Validate the configuration
+    app_config.validate()?;
+
+    info!("Configuration loaded successfully.");
+    Ok(app_config)
+}
+
+/// Loads the application configuration like [`load_config`], then resolves secrets from
+/// the configured secret store before validating. The returned [`SecretStore`] keeps
+/// every fetched secret in memory and is refreshed in the background when rotation is on.
+pub async fn load_with_secrets() -> Result<(AppConfig, SecretStore), AppConfigError> {
+    tracing_subscriber::fmt::init();
+
+    let mut app_config = read_config()?;
+    let store = secrets::resolve(&mut app_config).await?;
+
+    app_config.validate()?;
+
+    info!("Configuration loaded successfully.");
+    Ok((app_config, store))
+}
+
+/// Builds and deserializes the layered configuration without validating it.
+fn read_config() -> Result<AppConfig, AppConfigError> {
+
     // Determine the current environment, defaulting to "development"
     let run_env = env::var("RUN_ENV").unwrap_or_else(|_| "development".to_string());
     info!("Loading configuration for environment: {}", run_env);
@@ -123,13 +484,7 @@ pub fn load_config() -> Result<AppConfig, AppConfigError> {
     let config = builder.build()?;
 
     // Deserialize into AppConfig
-    let app_config: AppConfig = config.try_deserialize()?;
-
-    // Validate the configuration
-    app_config.validate()?;
-
-    info!("Configuration loaded successfully.");
-    Ok(app_config)
+    Ok(config.try_deserialize()?)
 }
 
 #[cfg(test)]
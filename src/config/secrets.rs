@@ -0,0 +1,428 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+use tracing::{info, warn};
+
+use super::AppConfig;
+
+/// Well-known secret names that are copied into `AppConfig` at startup.
+pub const JWT_SECRET: &str = "jwt_secret";
+/// Only applied when the database pool is created; a rotated password takes effect on the
+/// next restart, so the database should accept the previous password until then.
+pub const DATABASE_PASSWORD: &str = "database_password";
+pub const STRIPE_SECRET_KEY: &str = "stripe_secret_key";
+pub const STRIPE_WEBHOOK_SECRET: &str = "stripe_webhook_secret";
+
+/// Prefix of request signing secrets, followed by the key ID, e.g. `request_signing_k1`.
+pub const REQUEST_SIGNING_PREFIX: &str = "request_signing_";
+/// Prefix of inbound webhook secrets, followed by the ingest source name.
+pub const INGEST_PREFIX: &str = "ingest_";
+
+/// Errors raised while resolving secrets from an external store.
+#[derive(Debug, Error)]
+pub enum SecretsError {
+    #[error("Secrets provider is misconfigured: {0}")]
+    Misconfigured(String),
+
+    #[error("Failed to fetch secrets from {provider}: {message}")]
+    Fetch { provider: &'static str, message: String },
+
+    #[error("Secret payload is malformed: {0}")]
+    Malformed(String),
+}
+
+/// Which external store secrets are resolved from.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SecretsProvider {
+    /// Secrets come from config files and environment variables only.
+    #[default]
+    None,
+    Vault,
+    AwsSecretsManager,
+}
+
+/// Settings for a HashiCorp Vault KV v2 secret.
+#[derive(Clone, Debug, Deserialize)]
+pub struct VaultSettings {
+    /// Vault address, e.g. `https://vault.internal:8200`.
+    pub address: String,
+
+    /// Environment variable holding the Vault token (default: `VAULT_TOKEN`).
+    #[serde(default = "default_vault_token_env")]
+    pub token_env: String,
+
+    /// KV v2 mount point (default: `secret`).
+    #[serde(default = "default_vault_mount")]
+    pub mount: String,
+
+    /// Path of the secret within the mount, e.g. `stateset/api`.
+    pub path: String,
+}
+
+fn default_vault_token_env() -> String {
+    "VAULT_TOKEN".to_string()
+}
+
+fn default_vault_mount() -> String {
+    "secret".to_string()
+}
+
+/// Settings for an AWS Secrets Manager secret whose value is a JSON object.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AwsSecretsSettings {
+    /// Secret name or ARN.
+    pub secret_id: String,
+
+    /// AWS region; falls back to the default provider chain when unset.
+    pub region: Option<String>,
+}
+
+/// Secrets management settings, loaded from the `secrets` config section.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct SecretsConfig {
+    /// Secret store to resolve from (default: none).
+    #[serde(default)]
+    pub provider: SecretsProvider,
+
+    pub vault: Option<VaultSettings>,
+
+    pub aws: Option<AwsSecretsSettings>,
+
+    /// How often secrets are re-fetched to pick up rotations, in seconds. 0 disables rotation.
+    #[serde(default)]
+    pub refresh_interval_secs: u64,
+}
+
+/// A secret value that never appears in `Debug` or `Display` output.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretValue(String);
+
+impl SecretValue {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SecretValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SecretValue(***)")
+    }
+}
+
+/// A source of secrets, returning a flat name → value map.
+#[async_trait]
+pub trait SecretSource: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn fetch(&self) -> Result<HashMap<String, String>, SecretsError>;
+}
+
+/// Reads a KV v2 secret from HashiCorp Vault.
+pub struct VaultSource {
+    settings: VaultSettings,
+    token: String,
+    client: reqwest::Client,
+}
+
+impl VaultSource {
+    pub fn new(settings: VaultSettings) -> Result<Self, SecretsError> {
+        let token = std::env::var(&settings.token_env).map_err(|_| {
+            SecretsError::Misconfigured(format!("{} is not set", settings.token_env))
+        })?;
+        Ok(Self {
+            settings,
+            token,
+            client: reqwest::Client::new(),
+        })
+    }
+}
+
+#[async_trait]
+impl SecretSource for VaultSource {
+    fn name(&self) -> &'static str {
+        "vault"
+    }
+
+    async fn fetch(&self) -> Result<HashMap<String, String>, SecretsError> {
+        let url = format!(
+            "{}/v1/{}/data/{}",
+            self.settings.address.trim_end_matches('/'),
+            self.settings.mount,
+            self.settings.path
+        );
+        let fetch_err = |e: reqwest::Error| SecretsError::Fetch {
+            provider: "vault",
+            message: e.to_string(),
+        };
+
+        let body: serde_json::Value = self
+            .client
+            .get(&url)
+            .header("X-Vault-Token", &self.token)
+            .send()
+            .await
+            .map_err(fetch_err)?
+            .error_for_status()
+            .map_err(fetch_err)?
+            .json()
+            .await
+            .map_err(fetch_err)?;
+
+        // KV v2 nests the secret under data.data
+        parse_secret_map(&body["data"]["data"])
+    }
+}
+
+/// Reads a JSON secret from AWS Secrets Manager.
+pub struct AwsSecretsSource {
+    settings: AwsSecretsSettings,
+    client: aws_sdk_secretsmanager::Client,
+}
+
+impl AwsSecretsSource {
+    pub async fn new(settings: AwsSecretsSettings) -> Self {
+        let mut loader = aws_config::from_env();
+        if let Some(region) = settings.region.clone() {
+            loader = loader.region(aws_config::Region::new(region));
+        }
+        let client = aws_sdk_secretsmanager::Client::new(&loader.load().await);
+        Self { settings, client }
+    }
+}
+
+#[async_trait]
+impl SecretSource for AwsSecretsSource {
+    fn name(&self) -> &'static str {
+        "aws_secrets_manager"
+    }
+
+    async fn fetch(&self) -> Result<HashMap<String, String>, SecretsError> {
+        let output = self
+            .client
+            .get_secret_value()
+            .secret_id(&self.settings.secret_id)
+            .send()
+            .await
+            .map_err(|e| SecretsError::Fetch {
+                provider: "aws_secrets_manager",
+                message: e.to_string(),
+            })?;
+
+        let raw = output
+            .secret_string()
+            .ok_or_else(|| SecretsError::Malformed("secret has no string value".to_string()))?;
+        let value: serde_json::Value =
+            serde_json::from_str(raw).map_err(|e| SecretsError::Malformed(e.to_string()))?;
+        parse_secret_map(&value)
+    }
+}
+
+/// Flattens a JSON object of string values into a map; non-string values are rejected.
+fn parse_secret_map(value: &serde_json::Value) -> Result<HashMap<String, String>, SecretsError> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| SecretsError::Malformed("expected a JSON object".to_string()))?;
+    object
+        .iter()
+        .map(|(k, v)| {
+            v.as_str()
+                .map(|s| (k.clone(), s.to_string()))
+                .ok_or_else(|| SecretsError::Malformed(format!("value of '{}' is not a string", k)))
+        })
+        .collect()
+}
+
+/// In-memory store of resolved secrets. Values are never written to disk or the environment.
+/// Consumers read a secret when they use it rather than keeping a copy, so rotations
+/// apply without a restart, except for `database_password`.
+#[derive(Clone, Default)]
+pub struct SecretStore {
+    values: Arc<RwLock<HashMap<String, SecretValue>>>,
+    /// The value each rotated secret had before its last rotation (`None` if it was not in
+    /// the store), and when it was rotated.
+    replaced: Arc<RwLock<HashMap<String, (Option<SecretValue>, Instant)>>>,
+}
+
+impl SecretStore {
+    /// Returns the current value of a secret, if present.
+    pub fn get(&self, name: &str) -> Option<SecretValue> {
+        self.values.read().expect("secret store lock poisoned").get(name).cloned()
+    }
+
+    /// The current value of `name`, or `fallback` (the value from config or the
+    /// environment) when the store does not have it.
+    pub fn get_or(&self, name: &str, fallback: &str) -> String {
+        self.get(name).map_or_else(|| fallback.to_string(), |secret| secret.expose().to_string())
+    }
+
+    /// The value `name` had before a rotation less than `grace` ago, or `fallback` if it
+    /// was not in the store then. `None` when it has not been rotated within `grace`.
+    pub fn previous_or(&self, name: &str, fallback: &str, grace: Duration) -> Option<String> {
+        let replaced = self.replaced.read().expect("secret store lock poisoned");
+        let (previous, at) = replaced.get(name)?;
+        (at.elapsed() < grace)
+            .then(|| previous.as_ref().map_or_else(|| fallback.to_string(), |secret| secret.expose().to_string()))
+    }
+
+    pub(crate) fn replace(&self, fetched: HashMap<String, String>) -> Vec<String> {
+        let mut values = self.values.write().expect("secret store lock poisoned");
+        let mut changed: Vec<String> = fetched
+            .iter()
+            .filter(|(k, v)| values.get(*k).map_or(true, |old| old.expose() != v.as_str()))
+            .map(|(k, _)| k.clone())
+            .collect();
+        changed.sort();
+        let now = Instant::now();
+        let mut replaced = self.replaced.write().expect("secret store lock poisoned");
+        for name in &changed {
+            replaced.insert(name.clone(), (values.get(name).cloned(), now));
+        }
+        *values = fetched.into_iter().map(|(k, v)| (k, SecretValue(v))).collect();
+        changed
+    }
+}
+
+/// Copies well-known secrets into the config, overriding file and environment values.
+fn apply_to_config(config: &mut AppConfig, secrets: &HashMap<String, String>) -> Result<(), SecretsError> {
+    if let Some(jwt_secret) = secrets.get(JWT_SECRET) {
+        config.jwt_secret = jwt_secret.clone();
+    }
+    if let Some(password) = secrets.get(DATABASE_PASSWORD) {
+        let mut url = url::Url::parse(&config.database_url)
+            .map_err(|e| SecretsError::Misconfigured(format!("database_url: {}", e)))?;
+        url.set_password(Some(password))
+            .map_err(|_| SecretsError::Misconfigured("database_url cannot carry a password".to_string()))?;
+        config.database_url = url.to_string();
+    }
+    Ok(())
+}
+
+async fn build_source(config: &SecretsConfig) -> Result<Option<Arc<dyn SecretSource>>, SecretsError> {
+    match config.provider {
+        SecretsProvider::None => Ok(None),
+        SecretsProvider::Vault => {
+            let settings = config
+                .vault
+                .clone()
+                .ok_or_else(|| SecretsError::Misconfigured("missing [secrets.vault] section".to_string()))?;
+            Ok(Some(Arc::new(VaultSource::new(settings)?)))
+        }
+        SecretsProvider::AwsSecretsManager => {
+            let settings = config
+                .aws
+                .clone()
+                .ok_or_else(|| SecretsError::Misconfigured("missing [secrets.aws] section".to_string()))?;
+            Ok(Some(Arc::new(AwsSecretsSource::new(settings).await)))
+        }
+    }
+}
+
+/// Resolves secrets from the configured provider into `config` and returns the store
+/// holding every fetched secret. With no provider configured this is a no-op.
+pub async fn resolve(config: &mut AppConfig) -> Result<SecretStore, SecretsError> {
+    let store = SecretStore::default();
+    let source = match build_source(&config.secrets).await? {
+        Some(source) => source,
+        None => return Ok(store),
+    };
+
+    let fetched = source.fetch().await?;
+    apply_to_config(config, &fetched)?;
+    let count = fetched.len();
+    store.replace(fetched);
+    info!(provider = source.name(), secrets = count, "Resolved secrets from secret store");
+
+    if config.secrets.refresh_interval_secs > 0 {
+        spawn_rotation(
+            store.clone(),
+            source,
+            Duration::from_secs(config.secrets.refresh_interval_secs),
+        );
+    }
+
+    Ok(store)
+}
+
+/// Periodically re-fetches secrets so rotated values are picked up without a restart,
+/// apart from the database password. Fetch failures keep the previous values in place.
+pub fn spawn_rotation(store: SecretStore, source: Arc<dyn SecretSource>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            match source.fetch().await {
+                Ok(fetched) => {
+                    let changed = store.replace(fetched);
+                    if !changed.is_empty() {
+                        info!(provider = source.name(), rotated = ?changed, "Secrets rotated");
+                    }
+                    if changed.iter().any(|name| name == DATABASE_PASSWORD) {
+                        warn!("database_password rotated; connections keep using the previous one until restart");
+                    }
+                }
+                Err(e) => warn!(provider = source.name(), "Secret refresh failed, keeping previous values: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_secret_value_is_redacted() {
+        let value = SecretValue("sk_live_123".to_string());
+        assert_eq!(format!("{:?}", value), "SecretValue(***)");
+        assert_eq!(value.expose(), "sk_live_123");
+    }
+
+    #[test]
+    fn test_parse_secret_map_rejects_non_strings() {
+        assert!(parse_secret_map(&json!({"jwt_secret": "abc"})).is_ok());
+        assert!(matches!(
+            parse_secret_map(&json!({"jwt_secret": 1})),
+            Err(SecretsError::Malformed(_))
+        ));
+        assert!(parse_secret_map(&json!("not an object")).is_err());
+    }
+
+    #[test]
+    fn test_store_reports_rotated_keys() {
+        let store = SecretStore::default();
+        let mut first = HashMap::new();
+        first.insert(STRIPE_SECRET_KEY.to_string(), "old".to_string());
+        first.insert(JWT_SECRET.to_string(), "same".to_string());
+        store.replace(first);
+
+        let mut second = HashMap::new();
+        second.insert(STRIPE_SECRET_KEY.to_string(), "new".to_string());
+        second.insert(JWT_SECRET.to_string(), "same".to_string());
+        assert_eq!(store.replace(second), vec![STRIPE_SECRET_KEY.to_string()]);
+        assert_eq!(store.get(STRIPE_SECRET_KEY).unwrap().expose(), "new");
+        assert_eq!(store.get_or(STRIPE_SECRET_KEY, "from env"), "new");
+        assert_eq!(store.get_or(STRIPE_WEBHOOK_SECRET, "from env"), "from env");
+    }
+
+    #[test]
+    fn test_previous_values_are_kept_for_the_grace_period() {
+        let store = SecretStore::default();
+        assert_eq!(store.previous_or(JWT_SECRET, "from env", Duration::from_secs(60)), None);
+
+        store.replace(HashMap::from([(JWT_SECRET.to_string(), "first".to_string())]));
+        assert_eq!(store.previous_or(JWT_SECRET, "from env", Duration::from_secs(60)).as_deref(), Some("from env"));
+
+        store.replace(HashMap::from([(JWT_SECRET.to_string(), "second".to_string())]));
+        assert_eq!(store.previous_or(JWT_SECRET, "from env", Duration::from_secs(60)).as_deref(), Some("first"));
+        assert_eq!(store.previous_or(JWT_SECRET, "from env", Duration::ZERO), None);
+    }
+}
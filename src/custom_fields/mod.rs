@@ -0,0 +1,540 @@
+// custom_fields/mod.rs
+
+use async_trait::async_trait;
+use axum::{
+    extract::{FromRequestParts, Query},
+    http::{request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{NaiveDate, Utc};
+use sea_orm::{
+    sea_query::{Alias, Expr, Order, Query as SelectQuery},
+    ActiveModelTrait, ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::{collections::BTreeMap, convert::Infallible, sync::Arc};
+use thiserror::Error;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::db::dialect;
+use crate::models::custom_field_definition::{self, Entity as CustomFieldDefinition, FieldEntity, FieldType};
+use crate::network_acl::TENANT_HEADER;
+use crate::utils::pagination::PaginationParams;
+
+/// Tenant of requests without `X-Tenant-ID`.
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Query parameters starting with this filter list endpoints on metadata, e.g.
+/// `?metadata.channel=wholesale`.
+pub const FILTER_PREFIX: &str = "metadata.";
+
+/// Largest metadata object accepted on one record, serialized.
+pub const MAX_METADATA_BYTES: usize = 16 * 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FieldViolation {
+    pub key: String,
+    pub message: String,
+}
+
+#[derive(Error, Debug)]
+pub enum CustomFieldError {
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Invalid custom field: {0}")]
+    Invalid(String),
+
+    #[error("Metadata does not match the custom field definitions")]
+    Violations(Vec<FieldViolation>),
+
+    #[error("Missing permission: {0}")]
+    Forbidden(&'static str),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for CustomFieldError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            CustomFieldError::NotFound(_) => (StatusCode::NOT_FOUND, "not_found"),
+            CustomFieldError::Invalid(_) => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_custom_field"),
+            CustomFieldError::Violations(violations) => {
+                let body = json!({ "error": self.to_string(), "code": "invalid_metadata", "violations": violations });
+                return (StatusCode::UNPROCESSABLE_ENTITY, Json(body)).into_response();
+            }
+            CustomFieldError::Forbidden(_) => (StatusCode::FORBIDDEN, "forbidden"),
+            CustomFieldError::Database(e) => {
+                error!("Custom field query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "custom_field_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// Creates or replaces the definition of a key.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FieldInput {
+    pub entity: FieldEntity,
+    pub key: String,
+    pub field_type: FieldType,
+    #[serde(default)]
+    pub required: bool,
+    /// Values an `enum` field accepts; required for `enum` fields only.
+    #[serde(default)]
+    pub allowed_values: Vec<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Metadata keys are lower-case letters, digits and underscores, so they can be used in
+/// query parameters as they are.
+pub fn valid_key(key: &str) -> bool {
+    !key.is_empty()
+        && key.len() <= 64
+        && key.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_')
+}
+
+fn validate_input(input: &FieldInput) -> Result<(), CustomFieldError> {
+    if !valid_key(&input.key) {
+        return Err(CustomFieldError::Invalid(format!(
+            "key `{}` must be 1-64 lower-case letters, digits or underscores",
+            input.key
+        )));
+    }
+    match (input.field_type, input.allowed_values.is_empty()) {
+        (FieldType::Enum, true) => Err(CustomFieldError::Invalid("enum fields need allowed_values".to_string())),
+        (FieldType::Enum, false) => Ok(()),
+        (_, false) => Err(CustomFieldError::Invalid("allowed_values only apply to enum fields".to_string())),
+        (_, true) => Ok(()),
+    }
+}
+
+/// Why `value` does not fit the definition, if it does not.
+fn type_error(definition: &custom_field_definition::Model, value: &Value) -> Option<String> {
+    let fits = match definition.field_type {
+        FieldType::String => value.is_string(),
+        FieldType::Number => value.is_number(),
+        FieldType::Boolean => value.is_boolean(),
+        FieldType::Date => value.as_str().map_or(false, |s| NaiveDate::parse_from_str(s, "%Y-%m-%d").is_ok()),
+        FieldType::Enum => {
+            let allowed = definition.allowed_values.as_ref().and_then(Value::as_array);
+            return match value.as_str() {
+                Some(s) if allowed.map_or(false, |values| values.iter().any(|v| v.as_str() == Some(s))) => None,
+                _ => Some(format!("must be one of {}", allowed.map(|v| Value::Array(v.clone())).unwrap_or_default())),
+            };
+        }
+    };
+    match (fits, definition.field_type) {
+        (true, _) => None,
+        (false, FieldType::Date) => Some("must be a date, YYYY-MM-DD".to_string()),
+        (false, field_type) => Some(format!("must be a {}", json!(field_type).as_str().unwrap_or_default())),
+    }
+}
+
+/// Every way `metadata` breaks the definitions: missing required keys and values of the
+/// wrong type. Keys without a definition are free-form but must still be valid keys.
+pub fn violations(definitions: &[custom_field_definition::Model], metadata: &Map<String, Value>) -> Vec<FieldViolation> {
+    let violation = |key: &str, message: String| FieldViolation { key: key.to_string(), message };
+    let mut violations: Vec<FieldViolation> = metadata
+        .keys()
+        .filter(|key| !valid_key(key))
+        .map(|key| violation(key, "is not a valid key".to_string()))
+        .collect();
+    for definition in definitions {
+        match metadata.get(&definition.key).filter(|value| !value.is_null()) {
+            None if definition.required => violations.push(violation(&definition.key, "is required".to_string())),
+            None => {}
+            Some(value) => {
+                if let Some(message) = type_error(definition, value) {
+                    violations.push(violation(&definition.key, message));
+                }
+            }
+        }
+    }
+    violations
+}
+
+/// JSON values a filter's query string value may be stored as: always the string, plus
+/// the number or boolean it spells.
+pub fn filter_candidates(value: &str) -> Vec<Value> {
+    let mut candidates = vec![Value::String(value.to_string())];
+    if let Ok(number) = value.parse::<i64>() {
+        candidates.push(json!(number));
+    } else if let Some(number) = value.parse::<f64>().ok().and_then(serde_json::Number::from_f64) {
+        candidates.push(Value::Number(number));
+    }
+    if let Ok(flag) = value.parse::<bool>() {
+        candidates.push(Value::Bool(flag));
+    }
+    candidates
+}
+
+/// Matches records whose metadata `column` holds every filtered key with the given
+/// value. Uses JSONB containment, so it is served by a GIN index on the column.
+pub fn metadata_condition(column: &str, filters: &BTreeMap<String, String>) -> Condition {
+    filters.iter().fold(Condition::all(), |all, (key, value)| {
+        let any = filter_candidates(value).into_iter().fold(Condition::any(), |any, candidate| {
+            let mut contained = Map::new();
+            contained.insert(key.clone(), candidate);
+            any.add(Expr::cust_with_values(format!("{} @> $1::jsonb", column), [Value::Object(contained)]))
+        });
+        all.add(any)
+    })
+}
+
+/// `metadata.<key>=<value>` query parameters of a list request.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MetadataFilter(pub BTreeMap<String, String>);
+
+impl MetadataFilter {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn condition(&self, column: &str) -> Condition {
+        metadata_condition(column, &self.0)
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for MetadataFilter
+where
+    S: Send + Sync,
+{
+    type Rejection = CustomFieldError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Query(params) = Query::<Vec<(String, String)>>::try_from_uri(&parts.uri)
+            .map_err(|e| CustomFieldError::Invalid(e.to_string()))?;
+        let mut filters = BTreeMap::new();
+        for (name, value) in params {
+            if let Some(key) = name.strip_prefix(FILTER_PREFIX) {
+                if !valid_key(key) {
+                    return Err(CustomFieldError::Invalid(format!("`{}` is not a valid metadata filter", name)));
+                }
+                filters.insert(key.to_string(), value);
+            }
+        }
+        Ok(MetadataFilter(filters))
+    }
+}
+
+/// The tenant named in `X-Tenant-ID`, or [`DEFAULT_TENANT`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestTenant(pub String);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequestTenant
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let tenant = parts
+            .headers
+            .get(TENANT_HEADER)
+            .and_then(|h| h.to_str().ok())
+            .filter(|t| !t.is_empty())
+            .unwrap_or(DEFAULT_TENANT);
+        Ok(RequestTenant(tenant.to_string()))
+    }
+}
+
+/// Table and key column holding each record type's metadata.
+fn record_table(entity: FieldEntity) -> (&'static str, &'static str) {
+    match entity {
+        FieldEntity::Order => ("orders", "id"),
+        FieldEntity::Customer => ("customers", "id"),
+        FieldEntity::Product => ("product_listings", "sku"),
+    }
+}
+
+/// The record id from the path, typed as its key column.
+fn record_key(entity: FieldEntity, id: &str) -> Result<sea_orm::Value, CustomFieldError> {
+    let invalid = || CustomFieldError::Invalid(format!("`{}` is not a valid {:?} id", id, entity));
+    Ok(match entity {
+        FieldEntity::Order => id.parse::<Uuid>().map_err(|_| invalid())?.into(),
+        FieldEntity::Customer => id.parse::<i32>().map_err(|_| invalid())?.into(),
+        FieldEntity::Product => id.to_string().into(),
+    })
+}
+
+/// One page of ids of customers whose metadata matches `filter`, and the total. Customers
+/// have no entity, so this queries the table directly, as tag filters do.
+pub async fn customers_with_metadata(
+    db: &DatabaseConnection,
+    filter: &MetadataFilter,
+    pagination: PaginationParams,
+) -> Result<(Vec<i32>, u64), DbErr> {
+    let backend = db.get_database_backend();
+    let mut count = SelectQuery::select();
+    count.expr(Expr::cust("COUNT(*)")).from(Alias::new("customers")).cond_where(filter.condition("metadata"));
+    let total = match db.query_one(backend.build(&count)).await? {
+        Some(row) => row.try_get_by_index::<i64>(0)?,
+        None => 0,
+    };
+    let mut page = SelectQuery::select();
+    page.column(Alias::new("id"))
+        .from(Alias::new("customers"))
+        .cond_where(filter.condition("metadata"))
+        .order_by(Alias::new("id"), Order::Asc)
+        .limit(pagination.limit())
+        .offset(pagination.page_index() * pagination.limit());
+    let ids = db
+        .query_all(backend.build(&page))
+        .await?
+        .iter()
+        .map(|row| row.try_get_by_index::<i32>(0))
+        .collect::<Result<_, _>>()?;
+    Ok((ids, total.max(0) as u64))
+}
+
+/// Per-tenant custom field definitions, and the validated metadata of orders, customers
+/// and products.
+pub struct CustomFieldService {
+    db: Arc<DatabaseConnection>,
+}
+
+impl CustomFieldService {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    pub async fn fields(
+        &self,
+        tenant: &str,
+        entity: Option<FieldEntity>,
+    ) -> Result<Vec<custom_field_definition::Model>, CustomFieldError> {
+        let mut query = CustomFieldDefinition::find().filter(custom_field_definition::Column::TenantId.eq(tenant));
+        if let Some(entity) = entity {
+            query = query.filter(custom_field_definition::Column::Entity.eq(entity));
+        }
+        Ok(query
+            .order_by_asc(custom_field_definition::Column::Entity)
+            .order_by_asc(custom_field_definition::Column::Key)
+            .all(self.db.as_ref())
+            .await?)
+    }
+
+    /// Creates or replaces the definition of a key. Records written earlier are not
+    /// re-checked; the new definition applies from their next write.
+    pub async fn define(
+        &self,
+        tenant: &str,
+        input: FieldInput,
+        actor: &str,
+    ) -> Result<custom_field_definition::Model, CustomFieldError> {
+        validate_input(&input)?;
+        let db = self.db.as_ref();
+        let existing = CustomFieldDefinition::find()
+            .filter(custom_field_definition::Column::TenantId.eq(tenant))
+            .filter(custom_field_definition::Column::Entity.eq(input.entity))
+            .filter(custom_field_definition::Column::Key.eq(input.key.as_str()))
+            .one(db)
+            .await?;
+        let now = Utc::now();
+        let allowed_values = (!input.allowed_values.is_empty()).then(|| json!(input.allowed_values));
+        let definition = match existing {
+            Some(existing) => {
+                let mut active: custom_field_definition::ActiveModel = existing.into();
+                active.field_type = Set(input.field_type);
+                active.required = Set(input.required);
+                active.allowed_values = Set(allowed_values);
+                active.description = Set(input.description);
+                active.updated_at = Set(now);
+                active.update(db).await?
+            }
+            None => {
+                custom_field_definition::ActiveModel {
+                    id: Set(Uuid::new_v4()),
+                    tenant_id: Set(tenant.to_string()),
+                    entity: Set(input.entity),
+                    key: Set(input.key),
+                    field_type: Set(input.field_type),
+                    required: Set(input.required),
+                    allowed_values: Set(allowed_values),
+                    description: Set(input.description),
+                    created_by: Set(actor.to_string()),
+                    created_at: Set(now),
+                    updated_at: Set(now),
+                }
+                .insert(db)
+                .await?
+            }
+        };
+        info!(tenant, entity = ?definition.entity, key = %definition.key, "Custom field defined by {}", actor);
+        Ok(definition)
+    }
+
+    /// Removes a definition; values already stored under the key are kept.
+    pub async fn delete(&self, tenant: &str, id: Uuid) -> Result<(), CustomFieldError> {
+        let result = CustomFieldDefinition::delete_many()
+            .filter(custom_field_definition::Column::Id.eq(id))
+            .filter(custom_field_definition::Column::TenantId.eq(tenant))
+            .exec(self.db.as_ref())
+            .await?;
+        if result.rows_affected == 0 {
+            return Err(CustomFieldError::NotFound(format!("custom field {}", id)));
+        }
+        Ok(())
+    }
+
+    /// Metadata of a record; an empty object when it has none.
+    pub async fn metadata(&self, entity: FieldEntity, id: &str) -> Result<Value, CustomFieldError> {
+        let (table, key) = record_table(entity);
+        let db = self.db.as_ref();
+        let sql = format!("SELECT metadata FROM {} WHERE {} = $1", table, key);
+        let row = db
+            .query_one(dialect::statement(db, &sql, [record_key(entity, id)?]))
+            .await?
+            .ok_or_else(|| CustomFieldError::NotFound(format!("{:?} {}", entity, id)))?;
+        Ok(row.try_get::<Option<Value>>("", "metadata")?.unwrap_or_else(|| json!({})))
+    }
+
+    /// Replaces the metadata of a record after checking it against the tenant's
+    /// definitions for the record type.
+    pub async fn set_metadata(
+        &self,
+        tenant: &str,
+        entity: FieldEntity,
+        id: &str,
+        metadata: Value,
+    ) -> Result<Value, CustomFieldError> {
+        let Value::Object(fields) = &metadata else {
+            return Err(CustomFieldError::Invalid("metadata must be a JSON object".to_string()));
+        };
+        if metadata.to_string().len() > MAX_METADATA_BYTES {
+            return Err(CustomFieldError::Invalid(format!("metadata is limited to {} bytes", MAX_METADATA_BYTES)));
+        }
+        let definitions = self.fields(tenant, Some(entity)).await?;
+        let violations = violations(&definitions, fields);
+        if !violations.is_empty() {
+            return Err(CustomFieldError::Violations(violations));
+        }
+
+        let (table, key) = record_table(entity);
+        let db = self.db.as_ref();
+        let sql = format!("UPDATE {} SET metadata = $1 WHERE {} = $2", table, key);
+        let result = db
+            .execute(dialect::statement(db, &sql, [metadata.clone().into(), record_key(entity, id)?]))
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(CustomFieldError::NotFound(format!("{:?} {}", entity, id)));
+        }
+        Ok(metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(key: &str, field_type: FieldType, required: bool) -> custom_field_definition::Model {
+        let now = Utc::now();
+        custom_field_definition::Model {
+            id: Uuid::new_v4(),
+            tenant_id: DEFAULT_TENANT.to_string(),
+            entity: FieldEntity::Order,
+            key: key.to_string(),
+            field_type,
+            required,
+            allowed_values: (field_type == FieldType::Enum).then(|| json!(["retail", "wholesale"])),
+            description: None,
+            created_by: "admin".to_string(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn object(value: Value) -> Map<String, Value> {
+        value.as_object().cloned().unwrap()
+    }
+
+    #[test]
+    fn test_keys() {
+        assert!(valid_key("erp_id2"));
+        assert!(!valid_key("ERP-id"));
+        assert!(!valid_key(""));
+        assert!(!valid_key(&"k".repeat(65)));
+    }
+
+    #[test]
+    fn test_metadata_is_checked_against_definitions() {
+        let definitions = [
+            field("erp_id", FieldType::String, true),
+            field("priority", FieldType::Number, false),
+            field("gift", FieldType::Boolean, false),
+            field("promised_on", FieldType::Date, false),
+            field("channel", FieldType::Enum, false),
+        ];
+        let valid = object(json!({
+            "erp_id": "SO-1", "priority": 2, "gift": true, "promised_on": "2026-10-20",
+            "channel": "wholesale", "free_form": ["anything"],
+        }));
+        assert!(violations(&definitions, &valid).is_empty());
+
+        let invalid = object(json!({
+            "priority": "high", "gift": "yes", "promised_on": "20/10/2026", "channel": "b2b", "Bad Key": 1,
+        }));
+        let keys: Vec<_> = violations(&definitions, &invalid).into_iter().map(|v| v.key).collect();
+        assert_eq!(keys, ["Bad Key", "erp_id", "priority", "gift", "promised_on", "channel"]);
+    }
+
+    #[test]
+    fn test_null_counts_as_missing() {
+        let definitions = [field("erp_id", FieldType::String, true)];
+        let missing = violations(&definitions, &object(json!({ "erp_id": null })));
+        assert_eq!(missing, vec![FieldViolation { key: "erp_id".to_string(), message: "is required".to_string() }]);
+    }
+
+    #[test]
+    fn test_enum_fields_need_allowed_values() {
+        let input = |field_type, allowed_values: &[&str]| FieldInput {
+            entity: FieldEntity::Product,
+            key: "season".to_string(),
+            field_type,
+            required: false,
+            allowed_values: allowed_values.iter().map(|v| v.to_string()).collect(),
+            description: None,
+        };
+        assert!(validate_input(&input(FieldType::Enum, &["summer"])).is_ok());
+        assert!(validate_input(&input(FieldType::Enum, &[])).is_err());
+        assert!(validate_input(&input(FieldType::String, &["summer"])).is_err());
+    }
+
+    #[test]
+    fn test_filter_values_match_typed_metadata() {
+        assert_eq!(filter_candidates("wholesale"), vec![json!("wholesale")]);
+        assert_eq!(filter_candidates("42"), vec![json!("42"), json!(42)]);
+        assert_eq!(filter_candidates("2.5"), vec![json!("2.5"), json!(2.5)]);
+        assert_eq!(filter_candidates("true"), vec![json!("true"), json!(true)]);
+    }
+
+    #[test]
+    fn test_migration_passes_migration_checks() {
+        let sql = include_str!("../../migrations/20261016130000_custom_fields.sql");
+        assert_eq!(crate::db::migration_safety::analyze("custom_fields.sql", sql).violations().count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_filter_is_read_from_prefixed_query_parameters() {
+        let request = |uri: &str| axum::http::Request::builder().uri(uri).body(()).unwrap().into_parts().0;
+        let mut parts = request("/orders?page=2&metadata.channel=wholesale&metadata.priority=1");
+        let filter = MetadataFilter::from_request_parts(&mut parts, &()).await.unwrap();
+        assert_eq!(
+            filter.0,
+            BTreeMap::from([("channel".to_string(), "wholesale".to_string()), ("priority".to_string(), "1".to_string())])
+        );
+
+        let mut parts = request("/orders?metadata.Channel=x");
+        assert!(MetadataFilter::from_request_parts(&mut parts, &()).await.is_err());
+    }
+}
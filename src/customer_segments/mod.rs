@@ -0,0 +1,473 @@
+// customer_segments/mod.rs
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait,
+    FromQueryResult, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+    TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+use thiserror::Error;
+use tracing::{error, info};
+
+use crate::db::dialect;
+use crate::jobs::{JobContext, JobRunner};
+use crate::models::customer_tag::{self, Entity as CustomerTag, TagSource};
+use crate::utils::pagination::PaginationParams;
+
+/// Job kind recorded in the `jobs` table for evaluator runs.
+pub const EVALUATOR_JOB_KIND: &str = "customer_segments";
+
+/// Segment settings, loaded from the `customer_segments` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CustomerSegmentsConfig {
+    /// Re-evaluate segments on a schedule (default: false). Runs can also be started
+    /// through `POST /customers/segments/evaluate`.
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+
+    #[serde(default = "default_rules")]
+    pub rules: Vec<SegmentRule>,
+}
+
+fn default_interval_secs() -> u64 {
+    6 * 60 * 60
+}
+
+impl Default for CustomerSegmentsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: default_interval_secs(),
+            rules: default_rules(),
+        }
+    }
+}
+
+/// A named segment and the condition a customer must meet to be in it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SegmentRule {
+    pub segment: String,
+    pub when: SegmentCondition,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SegmentCondition {
+    /// Sum of line item sale prices across all orders, in cents.
+    LifetimeSpendAtLeast(i64),
+    OrderCountAtLeast(i64),
+    /// Returns divided by orders, only for customers with at least `min_orders` orders.
+    ReturnRateAtLeast { rate: f64, min_orders: i64 },
+    /// Customer carries a manual tag, e.g. one set by sales on account approval.
+    HasTag(String),
+    All(Vec<SegmentCondition>),
+    Any(Vec<SegmentCondition>),
+}
+
+fn default_rules() -> Vec<SegmentRule> {
+    vec![
+        SegmentRule {
+            segment: "vip".to_string(),
+            when: SegmentCondition::LifetimeSpendAtLeast(100_000),
+        },
+        SegmentRule {
+            segment: "high_return_rate".to_string(),
+            when: SegmentCondition::ReturnRateAtLeast { rate: 0.3, min_orders: 3 },
+        },
+        SegmentRule {
+            segment: "wholesale".to_string(),
+            when: SegmentCondition::HasTag("wholesale_account".to_string()),
+        },
+    ]
+}
+
+/// Order history figures the rules are evaluated against.
+#[derive(Clone, Debug, Default, PartialEq, FromQueryResult)]
+pub struct CustomerStats {
+    pub customer_id: i32,
+    pub order_count: i64,
+    pub lifetime_spend_cents: i64,
+    pub return_count: i64,
+}
+
+impl SegmentCondition {
+    pub fn matches(&self, stats: &CustomerStats, manual_tags: &BTreeSet<String>) -> bool {
+        match self {
+            SegmentCondition::LifetimeSpendAtLeast(cents) => stats.lifetime_spend_cents >= *cents,
+            SegmentCondition::OrderCountAtLeast(count) => stats.order_count >= *count,
+            SegmentCondition::ReturnRateAtLeast { rate, min_orders } => {
+                stats.order_count > 0
+                    && stats.order_count >= *min_orders
+                    && stats.return_count as f64 / stats.order_count as f64 >= *rate
+            }
+            SegmentCondition::HasTag(tag) => manual_tags.contains(&normalize_tag(tag)),
+            SegmentCondition::All(conditions) => conditions.iter().all(|c| c.matches(stats, manual_tags)),
+            SegmentCondition::Any(conditions) => conditions.iter().any(|c| c.matches(stats, manual_tags)),
+        }
+    }
+}
+
+/// Segments a customer belongs to under `rules`, sorted and without duplicates.
+pub fn evaluate(rules: &[SegmentRule], stats: &CustomerStats, manual_tags: &BTreeSet<String>) -> BTreeSet<String> {
+    rules
+        .iter()
+        .filter(|rule| rule.when.matches(stats, manual_tags))
+        .map(|rule| normalize_tag(&rule.segment))
+        .collect()
+}
+
+/// Tags are compared case-insensitively and stored lower-case.
+pub fn normalize_tag(tag: &str) -> String {
+    tag.trim().to_lowercase()
+}
+
+/// True when a promotion limited to `target_segments` applies to a customer with `tags`.
+/// An empty target list means the promotion is open to everyone.
+pub fn is_targeted(target_segments: &[String], tags: &BTreeSet<String>) -> bool {
+    target_segments.is_empty() || target_segments.iter().any(|segment| tags.contains(&normalize_tag(segment)))
+}
+
+#[derive(Error, Debug)]
+pub enum SegmentError {
+    #[error("Invalid tag: tags must be 1–64 characters of letters, digits, '-' or '_'")]
+    InvalidTag,
+
+    #[error("Tag '{0}' is computed by the segment evaluator and cannot be changed by hand")]
+    ComputedSegment(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for SegmentError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            SegmentError::InvalidTag => (StatusCode::BAD_REQUEST, "invalid_tag"),
+            SegmentError::ComputedSegment(_) => (StatusCode::CONFLICT, "computed_segment"),
+            SegmentError::Database(e) => {
+                error!("Customer segment query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "segment_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+fn valid_tag(tag: &str) -> bool {
+    (1..=64).contains(&tag.len()) && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// One page of ids of customers carrying `tag` (manual or computed), and the total.
+pub async fn customers_with_tag(
+    db: &DatabaseConnection,
+    tag: &str,
+    pagination: PaginationParams,
+) -> Result<(Vec<i32>, u64), SegmentError> {
+    let paginator = CustomerTag::find()
+        .filter(customer_tag::Column::Tag.eq(normalize_tag(tag)))
+        .order_by_asc(customer_tag::Column::CustomerId)
+        .paginate(db, pagination.limit());
+    let total = paginator.num_items().await?;
+    let ids = paginator
+        .fetch_page(pagination.page_index())
+        .await?
+        .into_iter()
+        .map(|t| t.customer_id)
+        .collect();
+    Ok((ids, total))
+}
+
+/// Orders and returns are linked to customers by email address. Each is aggregated per
+/// email once and joined, rather than counted per customer.
+const STATS_SQL: &str = r#"
+WITH order_totals AS (
+    SELECT o.customer_email,
+           COUNT(DISTINCT o.id) AS order_count,
+           COALESCE(SUM(li.sale_price * li.quantity), 0) AS lifetime_spend_cents
+    FROM orders o
+    LEFT JOIN order_line_items li ON li.order_id = o.id
+    GROUP BY o.customer_email
+), return_totals AS (
+    SELECT customer_email, COUNT(*) AS return_count
+    FROM returns
+    GROUP BY customer_email
+)
+SELECT c.id AS customer_id,
+       COALESCE(ot.order_count, 0) AS order_count,
+       COALESCE(ot.lifetime_spend_cents, 0) AS lifetime_spend_cents,
+       COALESCE(rt.return_count, 0) AS return_count
+FROM customers c
+LEFT JOIN order_totals ot ON ot.customer_email = c.email
+LEFT JOIN return_totals rt ON rt.customer_email = c.email
+"#;
+
+#[derive(Debug, Serialize)]
+pub struct EvaluationSummary {
+    pub customers: usize,
+    pub memberships: usize,
+    pub per_segment: HashMap<String, usize>,
+}
+
+pub struct CustomerSegmentService {
+    db: Arc<DatabaseConnection>,
+    rules: Vec<SegmentRule>,
+}
+
+impl CustomerSegmentService {
+    pub fn new(db: Arc<DatabaseConnection>, rules: Vec<SegmentRule>) -> Self {
+        Self { db, rules }
+    }
+
+    fn is_computed(&self, tag: &str) -> bool {
+        self.rules.iter().any(|rule| normalize_tag(&rule.segment) == tag)
+    }
+
+    pub async fn tags_for(&self, customer_id: i32) -> Result<Vec<customer_tag::Model>, SegmentError> {
+        Ok(CustomerTag::find()
+            .filter(customer_tag::Column::CustomerId.eq(customer_id))
+            .order_by_asc(customer_tag::Column::Tag)
+            .all(self.db.as_ref())
+            .await?)
+    }
+
+    /// Adds a manual tag. Re-adding an existing tag is a no-op.
+    pub async fn add_tag(&self, customer_id: i32, tag: &str, actor: String) -> Result<customer_tag::Model, SegmentError> {
+        let tag = normalize_tag(tag);
+        if !valid_tag(&tag) {
+            return Err(SegmentError::InvalidTag);
+        }
+        if self.is_computed(&tag) {
+            return Err(SegmentError::ComputedSegment(tag));
+        }
+        if let Some(existing) = CustomerTag::find_by_id((customer_id, tag.clone())).one(self.db.as_ref()).await? {
+            return Ok(existing);
+        }
+        Ok(customer_tag::ActiveModel {
+            customer_id: Set(customer_id),
+            tag: Set(tag),
+            source: Set(TagSource::Manual),
+            created_by: Set(Some(actor)),
+            created_at: Set(Utc::now()),
+        }
+        .insert(self.db.as_ref())
+        .await?)
+    }
+
+    /// Removes a manual tag. Returns whether the tag was present.
+    pub async fn remove_tag(&self, customer_id: i32, tag: &str) -> Result<bool, SegmentError> {
+        let tag = normalize_tag(tag);
+        if self.is_computed(&tag) {
+            return Err(SegmentError::ComputedSegment(tag));
+        }
+        let result = CustomerTag::delete_by_id((customer_id, tag)).exec(self.db.as_ref()).await?;
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Whether a promotion restricted to `target_segments` may be used by the customer.
+    pub async fn is_eligible(&self, customer_id: i32, target_segments: &[String]) -> Result<bool, SegmentError> {
+        Ok(self.eligible_customers(&[customer_id], target_segments).await?.contains(&customer_id))
+    }
+
+    /// Which of `customer_ids` may use a promotion restricted to `target_segments`, in
+    /// one query for all of them.
+    pub async fn eligible_customers(
+        &self,
+        customer_ids: &[i32],
+        target_segments: &[String],
+    ) -> Result<HashSet<i32>, SegmentError> {
+        if target_segments.is_empty() {
+            return Ok(customer_ids.iter().copied().collect());
+        }
+        let segments: Vec<String> = target_segments.iter().map(|segment| normalize_tag(segment)).collect();
+        Ok(CustomerTag::find()
+            .select_only()
+            .column(customer_tag::Column::CustomerId)
+            .distinct()
+            .filter(customer_tag::Column::CustomerId.is_in(customer_ids.iter().copied()))
+            .filter(customer_tag::Column::Tag.is_in(segments))
+            .into_tuple::<i32>()
+            .all(self.db.as_ref())
+            .await?
+            .into_iter()
+            .collect())
+    }
+
+    /// Recomputes every customer's segments and replaces the stored segment rows in one
+    /// transaction, so readers never see a half-evaluated state.
+    pub async fn evaluate_all(&self, context: Option<&JobContext>) -> Result<EvaluationSummary, SegmentError> {
+        let stats = CustomerStats::find_by_statement(dialect::raw(self.db.as_ref(), STATS_SQL))
+            .all(self.db.as_ref())
+            .await?;
+        if let Some(context) = context {
+            context.report_progress(30, format!("Loaded order history for {} customers", stats.len())).await;
+        }
+
+        let mut manual: HashMap<i32, BTreeSet<String>> = HashMap::new();
+        let manual_rows = CustomerTag::find()
+            .filter(customer_tag::Column::Source.eq(TagSource::Manual))
+            .select_only()
+            .column(customer_tag::Column::CustomerId)
+            .column(customer_tag::Column::Tag)
+            .into_tuple::<(i32, String)>()
+            .all(self.db.as_ref())
+            .await?;
+        for (customer_id, tag) in manual_rows {
+            manual.entry(customer_id).or_default().insert(tag);
+        }
+
+        let now = Utc::now();
+        let empty = BTreeSet::new();
+        let mut per_segment: HashMap<String, usize> = HashMap::new();
+        let mut rows = Vec::new();
+        for customer in &stats {
+            let tags = manual.get(&customer.customer_id).unwrap_or(&empty);
+            for segment in evaluate(&self.rules, customer, tags) {
+                *per_segment.entry(segment.clone()).or_default() += 1;
+                rows.push(customer_tag::ActiveModel {
+                    customer_id: Set(customer.customer_id),
+                    tag: Set(segment),
+                    source: Set(TagSource::Segment),
+                    created_by: Set(None),
+                    created_at: Set(now),
+                });
+            }
+        }
+
+        let memberships = rows.len();
+        let txn = self.db.begin().await?;
+        CustomerTag::delete_many()
+            .filter(customer_tag::Column::Source.eq(TagSource::Segment))
+            .exec(&txn)
+            .await?;
+        for chunk in rows.chunks(1000) {
+            CustomerTag::insert_many(chunk.to_vec()).exec(&txn).await?;
+        }
+        txn.commit().await?;
+
+        info!(customers = stats.len(), memberships, "Customer segments evaluated");
+        Ok(EvaluationSummary { customers: stats.len(), memberships, per_segment })
+    }
+}
+
+/// Submits an evaluator run to the job runner and returns its job id.
+pub async fn submit_evaluation(
+    runner: &JobRunner,
+    service: Arc<CustomerSegmentService>,
+    created_by: Option<String>,
+) -> Result<uuid::Uuid, crate::jobs::JobError> {
+    runner
+        .submit(EVALUATOR_JOB_KIND, created_by, move |context| async move {
+            let summary = service.evaluate_all(Some(&context)).await.map_err(|e| e.to_string())?;
+            serde_json::to_value(summary).map_err(|e| e.to_string())
+        })
+        .await
+}
+
+/// Re-evaluates segments every `interval` through the job runner, so each run is visible
+/// under `/api/v1/jobs`.
+pub fn spawn_scheduler(runner: Arc<JobRunner>, service: Arc<CustomerSegmentService>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = submit_evaluation(&runner, service.clone(), Some("system".to_string())).await {
+                error!("Failed to start customer segment evaluation: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(order_count: i64, lifetime_spend_cents: i64, return_count: i64) -> CustomerStats {
+        CustomerStats { customer_id: 1, order_count, lifetime_spend_cents, return_count }
+    }
+
+    fn tags(values: &[&str]) -> BTreeSet<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_default_rules() {
+        let rules = default_rules();
+        assert_eq!(evaluate(&rules, &stats(12, 250_000, 0), &tags(&[])), tags(&["vip"]));
+        assert_eq!(evaluate(&rules, &stats(4, 20_000, 2), &tags(&[])), tags(&["high_return_rate"]));
+        assert_eq!(
+            evaluate(&rules, &stats(0, 0, 0), &tags(&["wholesale_account"])),
+            tags(&["wholesale"])
+        );
+        assert!(evaluate(&rules, &stats(0, 0, 0), &tags(&[])).is_empty());
+    }
+
+    #[test]
+    fn test_return_rate_needs_minimum_orders() {
+        let condition = SegmentCondition::ReturnRateAtLeast { rate: 0.5, min_orders: 3 };
+        assert!(!condition.matches(&stats(1, 0, 1), &tags(&[])));
+        assert!(condition.matches(&stats(4, 0, 2), &tags(&[])));
+    }
+
+    #[test]
+    fn test_composite_conditions() {
+        let condition = SegmentCondition::All(vec![
+            SegmentCondition::OrderCountAtLeast(5),
+            SegmentCondition::Any(vec![
+                SegmentCondition::HasTag("B2B".to_string()),
+                SegmentCondition::LifetimeSpendAtLeast(1_000),
+            ]),
+        ]);
+        assert!(condition.matches(&stats(5, 0, 0), &tags(&["b2b"])));
+        assert!(condition.matches(&stats(5, 1_000, 0), &tags(&[])));
+        assert!(!condition.matches(&stats(4, 1_000, 0), &tags(&["b2b"])));
+    }
+
+    #[test]
+    fn test_rules_deserialize_from_config() {
+        let config: CustomerSegmentsConfig = serde_json::from_value(json!({
+            "enabled": true,
+            "rules": [{ "segment": "loyal", "when": { "order_count_at_least": 10 } }]
+        }))
+        .unwrap();
+        assert_eq!(config.interval_secs, default_interval_secs());
+        assert_eq!(config.rules[0].when, SegmentCondition::OrderCountAtLeast(10));
+    }
+
+    #[test]
+    fn test_promotion_targeting() {
+        assert!(is_targeted(&[], &tags(&[])));
+        assert!(is_targeted(&["VIP".to_string()], &tags(&["vip"])));
+        assert!(!is_targeted(&["vip".to_string()], &tags(&["wholesale"])));
+    }
+
+    #[test]
+    fn test_promotions_migration_is_safe() {
+        let plan = crate::db::migration_safety::analyze(
+            "20261017010000_promotions.sql",
+            include_str!("../../migrations/20261017010000_promotions.sql"),
+        );
+        assert_eq!(plan.violations().count(), 0);
+    }
+
+    #[test]
+    fn test_tag_validation() {
+        assert!(valid_tag("wholesale_account"));
+        assert!(!valid_tag(""));
+        assert!(!valid_tag("has space"));
+    }
+}
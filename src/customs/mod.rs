@@ -0,0 +1,254 @@
+// customs/mod.rs
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use validator::ValidationError;
+
+/// Import duties and taxes charged by one destination country.
+#[derive(Clone, Debug, Deserialize)]
+pub struct DutySchedule {
+    /// ISO 3166-1 alpha-2.
+    pub country: String,
+    /// Currency the schedule's thresholds are in; estimates are only given in it.
+    pub currency: String,
+    /// Shipments whose goods are worth no more than this pay no duty.
+    #[serde(default)]
+    pub de_minimis: Decimal,
+    /// Duty rate for goods without a more specific rate, e.g. `0.05` for 5%.
+    #[serde(default)]
+    pub duty_rate: Decimal,
+    /// Rates by HS code prefix; the longest matching prefix wins.
+    #[serde(default)]
+    pub rates: Vec<HsRate>,
+    /// Origins that import duty-free under a trade agreement.
+    #[serde(default)]
+    pub duty_free_origins: Vec<String>,
+    /// VAT or GST charged on goods, shipping and duty.
+    #[serde(default)]
+    pub tax_rate: Decimal,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct HsRate {
+    pub prefix: String,
+    pub rate: Decimal,
+}
+
+/// Customs settings, loaded from the `customs` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CustomsConfig {
+    /// Country shipments leave from; shipments to anywhere else are international.
+    #[serde(default = "default_origin_country")]
+    pub origin_country: String,
+
+    #[serde(default)]
+    pub schedules: Vec<DutySchedule>,
+}
+
+fn default_origin_country() -> String {
+    "US".to_string()
+}
+
+impl Default for CustomsConfig {
+    fn default() -> Self {
+        Self { origin_country: default_origin_country(), schedules: Vec::new() }
+    }
+}
+
+impl CustomsConfig {
+    pub fn is_international(&self, destination_country: &str) -> bool {
+        !self.origin_country.eq_ignore_ascii_case(destination_country)
+    }
+
+    pub fn schedule(&self, destination_country: &str) -> Option<&DutySchedule> {
+        self.schedules.iter().find(|s| s.country.eq_ignore_ascii_case(destination_country))
+    }
+}
+
+/// HS codes are written with or without dots; they are stored as digits only.
+pub fn normalize_hs_code(code: &str) -> String {
+    code.chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
+/// Six digits of the international Harmonized System, optionally extended to ten by the
+/// importing country.
+pub fn validate_hs_code(code: &str) -> Result<(), ValidationError> {
+    let digits = normalize_hs_code(code);
+    let only_digits_and_dots = code.chars().all(|c| c.is_ascii_digit() || c == '.');
+    if only_digits_and_dots && (6..=10).contains(&digits.len()) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("hs_code_must_have_6_to_10_digits"))
+    }
+}
+
+/// One kind of goods in an international shipment.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DutiableLine {
+    pub sku: String,
+    pub description: String,
+    pub hs_code: Option<String>,
+    pub country_of_origin: Option<String>,
+    pub quantity: i32,
+    pub unit_value: Decimal,
+}
+
+impl DutiableLine {
+    pub fn value(&self) -> Decimal {
+        self.unit_value * Decimal::from(self.quantity)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DutyLine {
+    pub sku: String,
+    pub hs_code: Option<String>,
+    pub country_of_origin: Option<String>,
+    pub value: Decimal,
+    pub duty_rate: Decimal,
+    pub duty: Decimal,
+}
+
+/// Landed cost on top of the goods, for quoting delivered duty paid.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DutyEstimate {
+    pub destination_country: String,
+    pub currency: String,
+    pub goods_value: Decimal,
+    pub shipping: Decimal,
+    pub duties: Decimal,
+    pub taxes: Decimal,
+    /// Duties plus taxes.
+    pub landed_cost: Decimal,
+    pub de_minimis_applied: bool,
+    pub lines: Vec<DutyLine>,
+    /// Lines estimated at the default rate for want of an HS code or origin.
+    pub warnings: Vec<String>,
+}
+
+/// The duty rate for goods with `hs_code` made in `origin`.
+pub fn duty_rate(schedule: &DutySchedule, hs_code: Option<&str>, origin: Option<&str>) -> Decimal {
+    if origin.is_some_and(|o| schedule.duty_free_origins.iter().any(|f| f.eq_ignore_ascii_case(o))) {
+        return Decimal::ZERO;
+    }
+    hs_code
+        .and_then(|code| {
+            schedule
+                .rates
+                .iter()
+                .filter(|r| code.starts_with(&normalize_hs_code(&r.prefix)))
+                .max_by_key(|r| normalize_hs_code(&r.prefix).len())
+        })
+        .map(|r| r.rate)
+        .unwrap_or(schedule.duty_rate)
+}
+
+pub fn estimate(schedule: &DutySchedule, lines: &[DutiableLine], shipping: Decimal) -> DutyEstimate {
+    let goods_value: Decimal = lines.iter().map(DutiableLine::value).sum();
+    let de_minimis_applied = goods_value <= schedule.de_minimis;
+    let mut warnings = Vec::new();
+    let duty_lines: Vec<DutyLine> = lines
+        .iter()
+        .map(|line| {
+            if line.hs_code.is_none() || line.country_of_origin.is_none() {
+                warnings.push(format!("{} has no HS code or country of origin; default duty rate applied", line.sku));
+            }
+            let duty_rate = if de_minimis_applied {
+                Decimal::ZERO
+            } else {
+                duty_rate(schedule, line.hs_code.as_deref(), line.country_of_origin.as_deref())
+            };
+            DutyLine {
+                sku: line.sku.clone(),
+                hs_code: line.hs_code.clone(),
+                country_of_origin: line.country_of_origin.clone(),
+                value: line.value(),
+                duty_rate,
+                duty: (line.value() * duty_rate).round_dp(2),
+            }
+        })
+        .collect();
+    let duties: Decimal = duty_lines.iter().map(|l| l.duty).sum();
+    let taxes = ((goods_value + shipping + duties) * schedule.tax_rate).round_dp(2);
+    DutyEstimate {
+        destination_country: schedule.country.to_uppercase(),
+        currency: schedule.currency.to_uppercase(),
+        goods_value,
+        shipping,
+        duties,
+        taxes,
+        landed_cost: duties + taxes,
+        de_minimis_applied,
+        lines: duty_lines,
+        warnings,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn schedule() -> DutySchedule {
+        DutySchedule {
+            country: "GB".to_string(),
+            currency: "GBP".to_string(),
+            de_minimis: dec!(135),
+            duty_rate: dec!(0.04),
+            rates: vec![
+                HsRate { prefix: "61".to_string(), rate: dec!(0.12) },
+                HsRate { prefix: "6109.10".to_string(), rate: dec!(0.08) },
+            ],
+            duty_free_origins: vec!["CA".to_string()],
+            tax_rate: dec!(0.20),
+        }
+    }
+
+    fn line(sku: &str, hs_code: Option<&str>, origin: Option<&str>, quantity: i32, unit_value: Decimal) -> DutiableLine {
+        DutiableLine {
+            sku: sku.to_string(),
+            description: "Cotton tee".to_string(),
+            hs_code: hs_code.map(str::to_string),
+            country_of_origin: origin.map(str::to_string),
+            quantity,
+            unit_value,
+        }
+    }
+
+    #[test]
+    fn test_hs_codes() {
+        assert_eq!(normalize_hs_code("6109.10.00"), "61091000");
+        assert!(validate_hs_code("6109.10").is_ok());
+        assert!(validate_hs_code("61091").is_err());
+        assert!(validate_hs_code("6109-10").is_err());
+    }
+
+    #[test]
+    fn test_longest_prefix_and_free_trade_origins() {
+        let schedule = schedule();
+        assert_eq!(duty_rate(&schedule, Some("610910"), Some("CN")), dec!(0.08));
+        assert_eq!(duty_rate(&schedule, Some("611020"), Some("CN")), dec!(0.12));
+        assert_eq!(duty_rate(&schedule, Some("420222"), Some("CN")), dec!(0.04));
+        assert_eq!(duty_rate(&schedule, Some("610910"), Some("ca")), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_estimate_charges_tax_on_goods_shipping_and_duty() {
+        let lines = [line("TEE", Some("610910"), Some("CN"), 10, dec!(20)), line("BAG", None, None, 1, dec!(50))];
+        let estimate = estimate(&schedule(), &lines, dec!(15));
+        assert_eq!(estimate.goods_value, dec!(250));
+        assert_eq!(estimate.duties, dec!(18.00));
+        assert_eq!(estimate.taxes, dec!(56.60));
+        assert_eq!(estimate.landed_cost, dec!(74.60));
+        assert_eq!(estimate.warnings.len(), 1);
+    }
+
+    #[test]
+    fn test_de_minimis_waives_duty_but_not_tax() {
+        let lines = [line("TEE", Some("610910"), Some("CN"), 5, dec!(20))];
+        let estimate = estimate(&schedule(), &lines, dec!(10));
+        assert!(estimate.de_minimis_applied);
+        assert_eq!(estimate.duties, Decimal::ZERO);
+        assert_eq!(estimate.taxes, dec!(22.00));
+    }
+}
@@ -0,0 +1,231 @@
+//! Runs the hand-written SQL in services on both supported databases. Queries are written
+//! once, in Postgres syntax, and rewritten for SQLite when the connection is SQLite:
+//!
+//! * `$1` placeholders become `?1`, so a parameter may still be used more than once
+//! * `::type` casts are dropped; SQLite's column affinity does the conversion
+//! * `GREATEST`/`LEAST` become the multi-argument `MAX`/`MIN`
+//! * `date_trunc('unit', x)` becomes `strftime(..)` of the start of the unit
+//! * `gen_random_uuid()` becomes `randomblob(16)`, which is how SQLite stores UUIDs
+//! * `NOW()` becomes `CURRENT_TIMESTAMP`
+//!
+//! Anything beyond that (data-modifying CTEs, `ctid`, pgvector) is Postgres-only; callers
+//! check with [`require_postgres`] and fail with a clear error instead of a syntax error.
+
+use sea_orm::{ConnectionTrait, DbBackend, DbErr, FromQueryResult, Statement, Value};
+use std::borrow::Cow;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Dialect {
+    Postgres,
+    Sqlite,
+}
+
+impl Dialect {
+    pub fn of<C: ConnectionTrait>(conn: &C) -> Self {
+        Self::from_backend(conn.get_database_backend())
+    }
+
+    /// MySQL is not supported; its SQL is treated as Postgres and fails loudly.
+    pub fn from_backend(backend: DbBackend) -> Self {
+        match backend {
+            DbBackend::Sqlite => Dialect::Sqlite,
+            DbBackend::Postgres | DbBackend::MySql => Dialect::Postgres,
+        }
+    }
+
+    pub fn translate<'a>(&self, sql: &'a str) -> Cow<'a, str> {
+        match self {
+            Dialect::Postgres => Cow::Borrowed(sql),
+            Dialect::Sqlite => Cow::Owned(to_sqlite(sql)),
+        }
+    }
+}
+
+/// A statement for `conn` from Postgres-syntax `sql`.
+pub fn statement<C, I>(conn: &C, sql: &str, values: I) -> Statement
+where
+    C: ConnectionTrait,
+    I: IntoIterator<Item = Value>,
+{
+    let backend = conn.get_database_backend();
+    Statement::from_sql_and_values(backend, Dialect::from_backend(backend).translate(sql), values)
+}
+
+/// A statement without parameters for `conn` from Postgres-syntax `sql`.
+pub fn raw<C: ConnectionTrait>(conn: &C, sql: &str) -> Statement {
+    let backend = conn.get_database_backend();
+    Statement::from_string(backend, Dialect::from_backend(backend).translate(sql).into_owned())
+}
+
+/// Fails on anything but Postgres, for features built on Postgres-only SQL.
+pub fn require_postgres<C: ConnectionTrait>(conn: &C, feature: &str) -> Result<(), DbErr> {
+    match Dialect::of(conn) {
+        Dialect::Postgres => Ok(()),
+        Dialect::Sqlite => Err(DbErr::Custom(format!("{} requires Postgres", feature))),
+    }
+}
+
+/// Serializes transactions on `key` until the current transaction ends. SQLite already
+/// allows a single writer at a time, so there it is a no-op.
+pub async fn advisory_xact_lock<C: ConnectionTrait>(txn: &C, key: &str) -> Result<(), DbErr> {
+    if Dialect::of(txn) == Dialect::Sqlite {
+        return Ok(());
+    }
+    txn.execute(statement(txn, "SELECT pg_advisory_xact_lock(hashtext($1))", [key.into()]))
+        .await
+        .map(|_| ())
+}
+
+#[derive(Debug, FromQueryResult)]
+struct DatabaseSize {
+    bytes: i64,
+}
+
+/// Size of the database on disk, in bytes.
+pub async fn database_size<C: ConnectionTrait>(conn: &C) -> Result<i64, DbErr> {
+    let sql = match Dialect::of(conn) {
+        Dialect::Postgres => "SELECT pg_database_size(current_database()) AS bytes",
+        Dialect::Sqlite => "SELECT page_count * page_size AS bytes FROM pragma_page_count(), pragma_page_size()",
+    };
+    let size = DatabaseSize::find_by_statement(Statement::from_string(conn.get_database_backend(), sql))
+        .one(conn)
+        .await?;
+    Ok(size.map(|s| s.bytes).unwrap_or(0))
+}
+
+fn date_trunc_format(unit: &str) -> Option<&'static str> {
+    Some(match unit.to_ascii_lowercase().as_str() {
+        "year" => "%Y-01-01",
+        "month" => "%Y-%m-01",
+        "day" => "%Y-%m-%d",
+        "hour" => "%Y-%m-%d %H:00:00",
+        "minute" => "%Y-%m-%d %H:%M:00",
+        _ => return None,
+    })
+}
+
+/// Rewrites Postgres-syntax `sql` for SQLite. String literals are left alone.
+pub fn to_sqlite(sql: &str) -> String {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut out = String::with_capacity(sql.len());
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\'' {
+            // Copy the literal through, including doubled quotes
+            out.push(c);
+            i += 1;
+            while i < chars.len() {
+                out.push(chars[i]);
+                if chars[i] == '\'' {
+                    if chars.get(i + 1) == Some(&'\'') {
+                        out.push('\'');
+                        i += 2;
+                        continue;
+                    }
+                    i += 1;
+                    break;
+                }
+                i += 1;
+            }
+        } else if c == '$' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit()) {
+            out.push('?');
+            i += 1;
+        } else if c == ':' && chars.get(i + 1) == Some(&':') {
+            i += 2;
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let cast: String = chars[start..i].iter().collect();
+            if cast.eq_ignore_ascii_case("double") {
+                let rest: String = chars[i..].iter().take(10).collect();
+                if rest.to_ascii_uppercase() == " PRECISION" {
+                    i += 10;
+                }
+            }
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let preceded_by_dot = start > 0 && chars[start - 1] == '.';
+            let called = chars.get(i) == Some(&'(');
+            match word.to_ascii_lowercase().as_str() {
+                "greatest" if called && !preceded_by_dot => out.push_str("MAX"),
+                "least" if called && !preceded_by_dot => out.push_str("MIN"),
+                "now" if called && chars.get(i + 1) == Some(&')') => {
+                    out.push_str("CURRENT_TIMESTAMP");
+                    i += 2;
+                }
+                "gen_random_uuid" if called && chars.get(i + 1) == Some(&')') => {
+                    out.push_str("randomblob(16)");
+                    i += 2;
+                }
+                "date_trunc" if called => {
+                    let rest: String = chars[i + 1..].iter().collect();
+                    let unit = rest.trim_start().strip_prefix('\'').and_then(|r| r.split_once('\''));
+                    match unit.and_then(|(unit, after)| date_trunc_format(unit).map(|f| (f, after))) {
+                        Some((format, after)) => {
+                            // Continue after the unit's closing quote, at the comma
+                            out.push_str(&format!("strftime('{}'", format));
+                            i = chars.len() - after.chars().count();
+                        }
+                        None => out.push_str(&word),
+                    }
+                }
+                _ => out.push_str(&word),
+            }
+        } else {
+            out.push(c);
+            i += 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_postgres_sql_is_untouched() {
+        let sql = "SELECT SUM(quantity)::BIGINT FROM t WHERE a = $1";
+        assert!(matches!(Dialect::Postgres.translate(sql), Cow::Borrowed(s) if s == sql));
+    }
+
+    #[test]
+    fn test_placeholders_and_casts() {
+        assert_eq!(
+            to_sqlite("SELECT SUM(quantity)::BIGINT AS q FROM t WHERE ($3::TEXT IS NULL OR sku = $3) AND a >= $1"),
+            "SELECT SUM(quantity) AS q FROM t WHERE (?3 IS NULL OR sku = ?3) AND a >= ?1"
+        );
+        assert_eq!(to_sqlite("SELECT SUM(h)::DOUBLE PRECISION AS hours"), "SELECT SUM(h) AS hours");
+        assert_eq!(to_sqlite("SELECT id::text AS id"), "SELECT id AS id");
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(
+            to_sqlite("SET quantity = GREATEST(usage_records.quantity, EXCLUDED.quantity)"),
+            "SET quantity = MAX(usage_records.quantity, EXCLUDED.quantity)"
+        );
+        assert_eq!(
+            to_sqlite("date_trunc('month', o.created_date)::date AS month"),
+            "strftime('%Y-%m-01', o.created_date) AS month"
+        );
+        assert_eq!(to_sqlite("SELECT gen_random_uuid(), NOW()"), "SELECT randomblob(16), CURRENT_TIMESTAMP");
+        // Columns that merely share a name are left alone
+        assert_eq!(to_sqlite("SELECT t.greatest, least FROM t"), "SELECT t.greatest, least FROM t");
+    }
+
+    #[test]
+    fn test_string_literals_are_left_alone() {
+        assert_eq!(
+            to_sqlite("WHERE note = 'costs $1::money, it''s GREATEST(x)' AND id = $2"),
+            "WHERE note = 'costs $1::money, it''s GREATEST(x)' AND id = ?2"
+        );
+        assert_eq!(to_sqlite("WHERE c NOT LIKE 'enc:%'"), "WHERE c NOT LIKE 'enc:%'");
+    }
+}
@@ -0,0 +1,377 @@
+//! Suggests indexes for the filter and sort combinations the API issues. The known list
+//! endpoint patterns are always checked against the indexes that exist; when the
+//! `pg_stat_statements` extension is installed, the most expensive statements on the
+//! watched tables are checked too. Suggestions come as `CREATE INDEX CONCURRENTLY`
+//! statements ready for a migration.
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, FromQueryResult, Statement};
+use serde::Serialize;
+use serde_json::json;
+use std::collections::HashSet;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{error, warn};
+
+use super::dialect;
+use super::migration_safety::create_index_concurrently;
+
+/// Statements read from `pg_stat_statements`, most total time first.
+const STATEMENT_LIMIT: i64 = 200;
+
+#[derive(Error, Debug)]
+pub enum IndexAdvisorError {
+    #[error("Index advice requires Postgres")]
+    Unsupported,
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for IndexAdvisorError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            IndexAdvisorError::Unsupported => (StatusCode::NOT_IMPLEMENTED, "index_advice_unsupported"),
+            IndexAdvisorError::Database(e) => {
+                error!("Index advice failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "index_advice_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// A filter/sort combination: equality filters on `equality`, ordered by `sort`.
+#[derive(Debug, Clone, Copy)]
+pub struct QueryPattern {
+    pub name: &'static str,
+    pub table: &'static str,
+    pub equality: &'static [&'static str],
+    pub sort: Option<&'static str>,
+}
+
+/// What the list and lookup endpoints filter and sort on.
+pub const KNOWN_PATTERNS: &[QueryPattern] = &[
+    QueryPattern { name: "orders_list", table: "orders", equality: &[], sort: Some("created_date") },
+    QueryPattern {
+        name: "orders_by_status",
+        table: "orders",
+        equality: &["order_status"],
+        sort: Some("created_date"),
+    },
+    QueryPattern {
+        name: "orders_by_customer",
+        table: "orders",
+        equality: &["customer_email"],
+        sort: Some("created_date"),
+    },
+    QueryPattern { name: "returns_list", table: "returns", equality: &[], sort: Some("created_date") },
+    QueryPattern { name: "returns_by_status", table: "returns", equality: &["status"], sort: Some("created_date") },
+    QueryPattern {
+        name: "returns_by_customer",
+        table: "returns",
+        equality: &["customer_id"],
+        sort: Some("created_date"),
+    },
+    QueryPattern { name: "returns_by_order", table: "returns", equality: &["order_id"], sort: Some("created_date") },
+    QueryPattern {
+        name: "inventory_by_location",
+        table: "inventory_items",
+        equality: &["sku", "warehouse"],
+        sort: None,
+    },
+    QueryPattern { name: "shipments_by_tracking", table: "shipments", equality: &["tracking_number"], sort: None },
+];
+
+/// Tables whose statements are checked in `pg_stat_statements`.
+fn watched_tables() -> Vec<&'static str> {
+    let mut tables: Vec<&'static str> = KNOWN_PATTERNS.iter().map(|p| p.table).collect();
+    tables.dedup();
+    tables
+}
+
+/// An existing index: its table and key columns in order.
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct ExistingIndex {
+    pub table_name: String,
+    pub index_name: String,
+    /// Comma-separated key columns.
+    pub columns: String,
+}
+
+/// Whether an index with key `columns` serves equality filters on `equality` then a sort
+/// on `sort`: its leading columns are the filtered ones, in any order, followed by the
+/// sorted one. A btree scans backwards, so the direction does not matter.
+pub fn covers(columns: &[&str], equality: &[&str], sort: Option<&str>) -> bool {
+    let needed = equality.len() + usize::from(sort.is_some());
+    if needed == 0 || columns.len() < needed {
+        return false;
+    }
+    let leading: HashSet<&str> = columns[..equality.len()].iter().copied().collect();
+    let filtered: HashSet<&str> = equality.iter().copied().collect();
+    leading == filtered && sort.map_or(true, |sort| columns[equality.len()] == sort)
+}
+
+/// Equality-filtered and sort columns of a statement on `table` as SeaORM writes it,
+/// e.g. `... FROM "orders" WHERE "orders"."order_status" = $1 ORDER BY "orders"."created_date" DESC`.
+pub fn observed_pattern(query: &str, table: &str) -> Option<(Vec<String>, Option<String>)> {
+    let quoted = format!("\"{}\"", table);
+    if !query.contains(&format!("FROM {}", quoted)) {
+        return None;
+    }
+    let qualified = format!("{}.\"", quoted);
+    let column_after = |text: &str| -> Option<String> {
+        let start = text.find(&qualified)? + qualified.len();
+        text[start..].split('"').next().map(str::to_string)
+    };
+    let (body, order) = match query.split_once(" ORDER BY ") {
+        Some((body, order)) => (body, Some(order)),
+        None => (query, None),
+    };
+    let mut equality = Vec::new();
+    if let Some((_, filters)) = body.split_once(" WHERE ") {
+        for condition in filters.split(" AND ") {
+            if let Some((left, _)) = condition.split_once(" = ") {
+                if let Some(column) = column_after(left) {
+                    if !equality.contains(&column) {
+                        equality.push(column);
+                    }
+                }
+            }
+        }
+    }
+    let sort = order.and_then(column_after);
+    if equality.is_empty() && sort.is_none() {
+        return None;
+    }
+    Some((equality, sort))
+}
+
+/// A missing index and why it is suggested.
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexSuggestion {
+    pub table: String,
+    pub columns: Vec<String>,
+    /// `CREATE INDEX CONCURRENTLY` statement for a migration.
+    pub sql: String,
+    /// Known pattern name, or `pg_stat_statements`.
+    pub source: String,
+    /// Statement calls and mean time, for suggestions from `pg_stat_statements`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub calls: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean_ms: Option<f64>,
+}
+
+fn suggestion(table: &str, equality: &[String], sort: Option<&String>, source: String) -> IndexSuggestion {
+    let mut columns: Vec<String> = equality.to_vec();
+    let mut definition: Vec<String> = equality.to_vec();
+    if let Some(sort) = sort {
+        columns.push(sort.clone());
+        definition.push(format!("{} DESC", sort));
+    }
+    let name = format!("idx_{}_{}", table, columns.join("_"));
+    let definition: Vec<&str> = definition.iter().map(String::as_str).collect();
+    IndexSuggestion {
+        sql: create_index_concurrently(&name, table, &definition),
+        table: table.to_string(),
+        columns,
+        source,
+        calls: None,
+        mean_ms: None,
+    }
+}
+
+/// Scan counts of a watched table; many sequential scans of a large table point at a
+/// missing index.
+#[derive(Debug, Clone, Serialize, FromQueryResult)]
+pub struct TableScans {
+    pub table_name: String,
+    pub seq_scan: i64,
+    pub idx_scan: Option<i64>,
+    pub live_rows: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexAdvice {
+    /// Whether `pg_stat_statements` was available to check.
+    pub pg_stat_statements: bool,
+    pub suggestions: Vec<IndexSuggestion>,
+    pub tables: Vec<TableScans>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct StatementStats {
+    query: String,
+    calls: i64,
+    mean_ms: f64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct Installed {
+    installed: bool,
+}
+
+pub struct IndexAdvisor {
+    db: Arc<DatabaseConnection>,
+}
+
+impl IndexAdvisor {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    async fn indexes(&self) -> Result<Vec<ExistingIndex>, DbErr> {
+        let sql = "SELECT t.relname AS table_name, i.relname AS index_name, \
+                   string_agg(a.attname, ',' ORDER BY k.ord) AS columns \
+                   FROM pg_index x \
+                   JOIN pg_class t ON t.oid = x.indrelid \
+                   JOIN pg_class i ON i.oid = x.indexrelid \
+                   JOIN pg_namespace n ON n.oid = t.relnamespace \
+                   CROSS JOIN LATERAL unnest(x.indkey) WITH ORDINALITY AS k(attnum, ord) \
+                   JOIN pg_attribute a ON a.attrelid = t.oid AND a.attnum = k.attnum \
+                   WHERE n.nspname = current_schema() AND x.indpred IS NULL AND x.indexprs IS NULL \
+                   GROUP BY t.relname, i.relname";
+        ExistingIndex::find_by_statement(dialect::raw(self.db.as_ref(), sql)).all(self.db.as_ref()).await
+    }
+
+    async fn table_scans(&self) -> Result<Vec<TableScans>, DbErr> {
+        let sql = "SELECT relname AS table_name, seq_scan, idx_scan, n_live_tup AS live_rows \
+                   FROM pg_stat_user_tables WHERE schemaname = current_schema() ORDER BY relname";
+        let watched = watched_tables();
+        Ok(TableScans::find_by_statement(dialect::raw(self.db.as_ref(), sql))
+            .all(self.db.as_ref())
+            .await?
+            .into_iter()
+            .filter(|t| watched.contains(&t.table_name.as_str()))
+            .collect())
+    }
+
+    /// The costliest statements, or `None` without the extension.
+    async fn statements(&self) -> Result<Option<Vec<StatementStats>>, DbErr> {
+        let db = self.db.as_ref();
+        let installed = Installed::find_by_statement(dialect::raw(
+            db,
+            "SELECT EXISTS (SELECT 1 FROM pg_extension WHERE extname = 'pg_stat_statements') AS installed",
+        ))
+        .one(db)
+        .await?
+        .is_some_and(|row| row.installed);
+        if !installed {
+            return Ok(None);
+        }
+        let sql = format!(
+            "SELECT query, calls, mean_exec_time AS mean_ms FROM pg_stat_statements \
+             WHERE query LIKE 'SELECT %' ORDER BY total_exec_time DESC LIMIT {}",
+            STATEMENT_LIMIT
+        );
+        match StatementStats::find_by_statement(Statement::from_string(db.get_database_backend(), sql)).all(db).await {
+            Ok(rows) => Ok(Some(rows)),
+            // Installed but not loaded through shared_preload_libraries, or too old a version
+            Err(e) => {
+                warn!("pg_stat_statements is installed but could not be read: {}", e);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Missing indexes for the known patterns and, when available, for the costliest
+    /// statements on the watched tables.
+    pub async fn advise(&self) -> Result<IndexAdvice, IndexAdvisorError> {
+        if dialect::Dialect::of(self.db.as_ref()) != dialect::Dialect::Postgres {
+            return Err(IndexAdvisorError::Unsupported);
+        }
+        let indexes = self.indexes().await?;
+        let covered = |table: &str, equality: &[&str], sort: Option<&str>| {
+            indexes.iter().filter(|index| index.table_name == table).any(|index| {
+                let columns: Vec<&str> = index.columns.split(',').collect();
+                covers(&columns, equality, sort)
+            })
+        };
+
+        let mut suggestions: Vec<IndexSuggestion> = Vec::new();
+        let mut seen: HashSet<(String, Vec<String>)> = HashSet::new();
+        for pattern in KNOWN_PATTERNS {
+            if covered(pattern.table, pattern.equality, pattern.sort) {
+                continue;
+            }
+            let equality: Vec<String> = pattern.equality.iter().map(|c| c.to_string()).collect();
+            let sort = pattern.sort.map(str::to_string);
+            let suggestion = suggestion(pattern.table, &equality, sort.as_ref(), pattern.name.to_string());
+            seen.insert((suggestion.table.clone(), suggestion.columns.clone()));
+            suggestions.push(suggestion);
+        }
+
+        let statements = self.statements().await?;
+        for stats in statements.iter().flatten() {
+            for table in watched_tables() {
+                let Some((equality, sort)) = observed_pattern(&stats.query, table) else {
+                    continue;
+                };
+                let filtered: Vec<&str> = equality.iter().map(String::as_str).collect();
+                if covered(table, &filtered, sort.as_deref()) {
+                    continue;
+                }
+                let mut suggestion = suggestion(table, &equality, sort.as_ref(), "pg_stat_statements".to_string());
+                if !seen.insert((suggestion.table.clone(), suggestion.columns.clone())) {
+                    continue;
+                }
+                suggestion.calls = Some(stats.calls);
+                suggestion.mean_ms = Some(stats.mean_ms);
+                suggestions.push(suggestion);
+            }
+        }
+
+        Ok(IndexAdvice {
+            pg_stat_statements: statements.is_some(),
+            suggestions,
+            tables: self.table_scans().await?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::migration_safety::analyze;
+
+    #[test]
+    fn test_index_covers_filters_then_sort() {
+        assert!(covers(&["order_status", "created_date"], &["order_status"], Some("created_date")));
+        assert!(covers(&["warehouse", "sku", "id"], &["sku", "warehouse"], None));
+        assert!(!covers(&["created_date", "order_status"], &["order_status"], Some("created_date")));
+        assert!(!covers(&["order_status"], &["order_status"], Some("created_date")));
+        assert!(!covers(&["id"], &[], None));
+    }
+
+    #[test]
+    fn test_observed_pattern_from_seaorm_statement() {
+        let query = "SELECT \"orders\".\"id\" FROM \"orders\" WHERE \"orders\".\"order_status\" = $1 \
+                     AND \"orders\".\"customer_email\" = $2 ORDER BY \"orders\".\"created_date\" DESC LIMIT $3";
+        assert_eq!(
+            observed_pattern(query, "orders"),
+            Some((
+                vec!["order_status".to_string(), "customer_email".to_string()],
+                Some("created_date".to_string())
+            ))
+        );
+        assert_eq!(observed_pattern(query, "returns"), None);
+        assert_eq!(observed_pattern("SELECT \"orders\".\"id\" FROM \"orders\" LIMIT $1", "orders"), None);
+    }
+
+    #[test]
+    fn test_suggested_sql_passes_migration_checks() {
+        let sql = suggestion("orders", &["order_status".to_string()], Some(&"created_date".to_string()), String::new()).sql;
+        assert_eq!(
+            sql,
+            "CREATE INDEX CONCURRENTLY IF NOT EXISTS idx_orders_order_status_created_date ON orders (order_status, created_date DESC)"
+        );
+        assert_eq!(analyze("suggestion.sql", &sql).violations().count(), 0);
+        let shipped = include_str!("../../migrations/20261016120000_list_endpoint_indexes.sql");
+        assert_eq!(analyze("list_endpoint_indexes.sql", shipped).violations().count(), 0);
+    }
+}
@@ -0,0 +1,550 @@
+//! Expand/contract checks for SQL migrations, so schema changes never break the release
+//! that is still serving traffic while the next one rolls out.
+//!
+//! Each file in `migrations/` declares its phase in a header comment:
+//!
+//! * `-- phase: expand` (the default): additive changes the running release tolerates —
+//!   new tables, nullable columns, concurrent indexes, `NOT VALID` constraints
+//! * `-- phase: contract`: removals, shipped only once no live release uses what is removed
+//!
+//! [`analyze`] reports the lock each statement takes and flags statements that block
+//! traffic or break the live release. Code it cannot see into, a `DO` block or a function
+//! whose body alters, renames, drops or attaches something, counts as unsafe. A reviewed
+//! exception is recorded in the file with `-- safety: allow <rule>`. The helpers at the bottom build the safe form of the
+//! common changes.
+
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Phase {
+    #[default]
+    Expand,
+    Contract,
+}
+
+/// Postgres table lock a statement takes, weakest first.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockLevel {
+    None,
+    RowExclusive,
+    ShareUpdateExclusive,
+    /// Blocks writes.
+    Share,
+    /// Blocks writes.
+    ShareRowExclusive,
+    /// Blocks reads and writes.
+    AccessExclusive,
+}
+
+impl LockLevel {
+    pub fn blocks_writes(&self) -> bool {
+        *self >= LockLevel::Share
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Risk {
+    Safe,
+    /// Worth a look in review, e.g. a brief exclusive lock.
+    Caution,
+    /// Blocks traffic for the duration of a scan or rewrite, or breaks the live release.
+    Unsafe,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Finding {
+    /// Position of the statement in the file, from 1; 0 for findings about the file.
+    pub statement: usize,
+    pub sql: String,
+    pub lock: LockLevel,
+    pub risk: Risk,
+    pub rule: &'static str,
+    pub message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<&'static str>,
+    /// Set when the file allows this rule with `-- safety: allow`.
+    pub allowed: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MigrationPlan {
+    pub name: String,
+    pub phase: Phase,
+    pub findings: Vec<Finding>,
+}
+
+impl MigrationPlan {
+    /// Unsafe findings the file does not explicitly allow; CI fails on any.
+    pub fn violations(&self) -> impl Iterator<Item = &Finding> {
+        self.findings.iter().filter(|f| f.risk == Risk::Unsafe && !f.allowed)
+    }
+
+    pub fn strongest_lock(&self) -> LockLevel {
+        self.findings.iter().map(|f| f.lock).max().unwrap_or(LockLevel::None)
+    }
+}
+
+/// Splits SQL into statements on `;`, ignoring semicolons in literals, quoted identifiers,
+/// comments and dollar-quoted bodies.
+pub fn split_statements(sql: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut chars = sql.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                current.push(c);
+                for next in chars.by_ref() {
+                    current.push(next);
+                    if next == c {
+                        break;
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        current.push('\n');
+                        break;
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'$') => {
+                chars.next();
+                current.push_str("$$");
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    current.push(next);
+                    if previous == '$' && next == '$' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            ';' => {
+                if !current.trim().is_empty() {
+                    statements.push(current.trim().to_string());
+                }
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+    if !current.trim().is_empty() {
+        statements.push(current.trim().to_string());
+    }
+    statements
+}
+
+fn directives(sql: &str) -> (Phase, Vec<String>) {
+    let mut phase = Phase::default();
+    let mut allowed = Vec::new();
+    for line in sql.lines() {
+        let Some(comment) = line.trim().strip_prefix("--") else { continue };
+        let comment = comment.trim().to_ascii_lowercase();
+        if let Some(value) = comment.strip_prefix("phase:") {
+            if value.trim() == "contract" {
+                phase = Phase::Contract;
+            }
+        } else if let Some(rules) = comment.strip_prefix("safety: allow") {
+            allowed.extend(rules.split([',', ' ']).filter(|r| !r.is_empty()).map(str::to_string));
+        }
+    }
+    (phase, allowed)
+}
+
+/// Upper-cased with whitespace collapsed, so rules can match on keyword sequences.
+fn normalize(statement: &str) -> String {
+    statement.split_whitespace().collect::<Vec<_>>().join(" ").to_ascii_uppercase()
+}
+
+const VOLATILE_DEFAULTS: [&str; 4] = ["GEN_RANDOM_UUID(", "RANDOM(", "CLOCK_TIMESTAMP(", "UUID_GENERATE_V4("];
+
+/// Keywords of the schema changes a procedural body may hide from the checks, typically
+/// behind `EXECUTE format(..)`.
+const HIDDEN_DDL: [&str; 4] = ["ALTER", "RENAME", "DROP", "ATTACH"];
+
+fn procedural(sql: &str) -> bool {
+    sql.starts_with("DO ")
+        || ["FUNCTION ", "PROCEDURE "]
+            .iter()
+            .any(|kind| sql.starts_with(&format!("CREATE {}", kind)) || sql.starts_with(&format!("CREATE OR REPLACE {}", kind)))
+}
+
+struct Rule {
+    lock: LockLevel,
+    risk: Risk,
+    rule: &'static str,
+    message: &'static str,
+    suggestion: Option<&'static str>,
+}
+
+const fn rule(lock: LockLevel, risk: Risk, rule: &'static str, message: &'static str) -> Rule {
+    Rule { lock, risk, rule, message, suggestion: None }
+}
+
+/// What earlier statements of the file established.
+#[derive(Default)]
+struct Context {
+    /// Columns with a validated `CHECK (col IS NOT NULL)`.
+    proven_not_null: Vec<String>,
+    pending_not_null: Vec<String>,
+}
+
+fn classify(sql: &str, phase: Phase, context: &Context) -> Vec<Rule> {
+    use LockLevel::*;
+    use Risk::*;
+
+    let destructive = |name: &'static str, message: &'static str| match phase {
+        Phase::Expand => Rule {
+            suggestion: Some("Move it to a contract migration shipped after no live release uses it"),
+            ..rule(AccessExclusive, Unsafe, name, message)
+        },
+        Phase::Contract => rule(AccessExclusive, Caution, name, message),
+    };
+
+    if sql.starts_with("SET ") || sql.starts_with("RESET ") || sql == "BEGIN" || sql == "COMMIT" {
+        return Vec::new();
+    }
+    if sql.starts_with("CREATE TABLE") || sql.starts_with("CREATE SEQUENCE") || sql.starts_with("CREATE TYPE") {
+        return vec![rule(None, Safe, "create", "Creates a new object")];
+    }
+    if sql.starts_with("CREATE INDEX") || sql.starts_with("CREATE UNIQUE INDEX") {
+        return if sql.contains(" CONCURRENTLY ") {
+            vec![rule(ShareUpdateExclusive, Safe, "create_index", "Builds the index without blocking writes")]
+        } else {
+            vec![Rule {
+                suggestion: Some("CREATE INDEX CONCURRENTLY, in a migration without a transaction"),
+                ..rule(Share, Unsafe, "index_not_concurrent", "Blocks writes to the table while the index builds")
+            }]
+        };
+    }
+    if sql.starts_with("DROP INDEX") {
+        return if sql.contains(" CONCURRENTLY ") {
+            vec![rule(ShareUpdateExclusive, Safe, "drop_index", "Drops the index without blocking")]
+        } else {
+            vec![Rule {
+                suggestion: Some("DROP INDEX CONCURRENTLY"),
+                ..rule(AccessExclusive, Caution, "drop_index", "Briefly blocks reads and writes to the table")
+            }]
+        };
+    }
+    if sql.starts_with("DROP TABLE") || sql.starts_with("TRUNCATE") {
+        return vec![destructive("drop_table", "Removes a table and its data")];
+    }
+    if procedural(sql) && sql.split(|c: char| !c.is_ascii_alphanumeric() && c != '_').any(|word| HIDDEN_DDL.contains(&word)) {
+        return vec![Rule {
+            suggestion: Some("Write the statements out in the migration so each is checked, or review the body and allow hidden_ddl"),
+            ..rule(AccessExclusive, Unsafe, "hidden_ddl", "Alters, renames, drops or attaches from a procedural body the checks cannot see into")
+        }];
+    }
+    if sql.starts_with("LOCK ") {
+        return vec![rule(AccessExclusive, Caution, "explicit_lock", "Takes an explicit table lock")];
+    }
+    if sql.starts_with("UPDATE ") || sql.starts_with("DELETE ") || sql.starts_with("INSERT ") {
+        return vec![Rule {
+            suggestion: Some("Backfill large tables in batches outside the migration"),
+            ..rule(RowExclusive, Caution, "data_change", "Changes rows inside the migration")
+        }];
+    }
+    if !sql.starts_with("ALTER TABLE") {
+        return vec![rule(AccessExclusive, Caution, "unrecognized", "Statement not recognized; assume an exclusive lock")];
+    }
+
+    let mut rules = Vec::new();
+    if sql.contains(" RENAME ") {
+        rules.push(Rule {
+            suggestion: Some("Add the new column, write both, backfill, switch reads, then drop the old one in a contract migration"),
+            ..rule(AccessExclusive, Unsafe, "rename", "Renames break the release that is still live")
+        });
+    }
+    if sql.contains(" DROP COLUMN ") {
+        rules.push(destructive("drop_column", "Drops a column the live release may still read"));
+    }
+    if (sql.contains(" TYPE ") && sql.contains(" ALTER COLUMN ")) || sql.contains(" SET DATA TYPE ") {
+        rules.push(Rule {
+            suggestion: Some("Add a column of the new type and migrate to it as for a rename"),
+            ..rule(AccessExclusive, Unsafe, "column_type", "Changing a column type rewrites the table under an exclusive lock")
+        });
+    }
+    let proven = context.proven_not_null.iter().any(|column| sql.contains(&format!(" ALTER COLUMN {} SET NOT NULL", column)));
+    if sql.contains(" SET NOT NULL") && proven {
+        rules.push(rule(AccessExclusive, Safe, "set_not_null", "A validated check proves the column, so there is no scan"));
+    } else if sql.contains(" SET NOT NULL") {
+        rules.push(Rule {
+            suggestion: Some("Add CHECK (col IS NOT NULL) NOT VALID, VALIDATE it, then SET NOT NULL"),
+            ..rule(AccessExclusive, Unsafe, "set_not_null", "Scans the table under an exclusive lock")
+        });
+    }
+    if let Some(added) = sql.split(" ADD COLUMN ").nth(1) {
+        let volatile = VOLATILE_DEFAULTS.iter().any(|f| added.contains(f));
+        if added.contains(" NOT NULL") && !added.contains(" DEFAULT ") {
+            rules.push(Rule {
+                suggestion: Some("Add it nullable or with a constant default"),
+                ..rule(AccessExclusive, Unsafe, "add_column_not_null", "Fails on a table with rows and breaks inserts of the live release")
+            });
+        } else if volatile {
+            rules.push(Rule {
+                suggestion: Some("Add it without a default, then backfill in batches"),
+                ..rule(AccessExclusive, Unsafe, "volatile_default", "A volatile default rewrites the table")
+            });
+        } else {
+            rules.push(rule(AccessExclusive, Safe, "add_column", "Adds a column; the lock is brief"));
+        }
+    }
+    if sql.contains(" FOREIGN KEY ") || sql.contains(" CHECK ") {
+        let (lock, kind) = if sql.contains(" FOREIGN KEY ") {
+            (ShareRowExclusive, "foreign_key")
+        } else {
+            (AccessExclusive, "check_constraint")
+        };
+        if sql.contains(" NOT VALID") {
+            rules.push(rule(lock, Safe, kind, "Adds the constraint without checking existing rows"));
+        } else {
+            rules.push(Rule {
+                suggestion: Some("Add it NOT VALID, then VALIDATE CONSTRAINT in a later statement"),
+                ..rule(lock, Unsafe, kind, "Checks every existing row while holding the lock")
+            });
+        }
+    }
+    if (sql.contains(" PRIMARY KEY") || sql.contains(" UNIQUE")) && sql.contains(" ADD ") && !sql.contains(" USING INDEX") {
+        rules.push(Rule {
+            suggestion: Some("CREATE UNIQUE INDEX CONCURRENTLY, then ADD CONSTRAINT ... USING INDEX"),
+            ..rule(AccessExclusive, Unsafe, "unique_constraint", "Builds an index while blocking reads and writes")
+        });
+    }
+    if sql.contains(" VALIDATE CONSTRAINT ") {
+        rules.push(rule(ShareUpdateExclusive, Safe, "validate_constraint", "Validates without blocking writes"));
+    }
+    if rules.is_empty() {
+        rules.push(rule(AccessExclusive, Caution, "alter_table", "Briefly blocks reads and writes to the table"));
+    }
+    rules
+}
+
+fn excerpt(statement: &str) -> String {
+    let flat = statement.split_whitespace().collect::<Vec<_>>().join(" ");
+    if flat.chars().count() > 120 {
+        format!("{}…", flat.chars().take(120).collect::<String>())
+    } else {
+        flat
+    }
+}
+
+/// Lock and expand/contract analysis of one migration file.
+pub fn analyze(name: &str, sql: &str) -> MigrationPlan {
+    let (phase, allowed) = directives(sql);
+    let mut findings = Vec::new();
+    let mut lock_timeout = false;
+    let mut context = Context::default();
+    for (index, statement) in split_statements(sql).iter().enumerate() {
+        let normalized = normalize(statement);
+        if normalized.starts_with("SET LOCK_TIMEOUT") || normalized.starts_with("SET LOCAL LOCK_TIMEOUT") {
+            lock_timeout = true;
+        }
+        for rule in classify(&normalized, phase, &context) {
+            findings.push(Finding {
+                statement: index + 1,
+                sql: excerpt(statement),
+                lock: rule.lock,
+                risk: rule.risk,
+                rule: rule.rule,
+                message: rule.message.to_string(),
+                suggestion: rule.suggestion,
+                allowed: allowed.iter().any(|a| a == rule.rule),
+            });
+        }
+        if let Some(column) = normalized.split(" CHECK (").nth(1).and_then(|c| c.strip_suffix(" IS NOT NULL) NOT VALID")) {
+            context.pending_not_null.push(column.to_string());
+        }
+        if normalized.contains(" VALIDATE CONSTRAINT ") {
+            let pending = std::mem::take(&mut context.pending_not_null);
+            context.proven_not_null.extend(pending);
+        }
+    }
+    // Without a lock timeout a statement waiting behind a long query queues every other
+    // query on the table behind itself
+    if !lock_timeout && findings.iter().any(|f| f.lock.blocks_writes()) {
+        findings.push(Finding {
+            statement: 0,
+            sql: String::new(),
+            lock: LockLevel::None,
+            risk: Risk::Unsafe,
+            rule: "missing_lock_timeout",
+            message: "Takes blocking locks without a lock_timeout".to_string(),
+            suggestion: Some("Start the migration with SET lock_timeout = '5s' and retry on timeout"),
+            allowed: allowed.iter().any(|a| a == "missing_lock_timeout"),
+        });
+    }
+    MigrationPlan { name: name.to_string(), phase, findings }
+}
+
+/// Analyzes every `.sql` file in `dir`, in name order. A missing directory has no plans.
+pub fn analyze_dir(dir: &Path) -> std::io::Result<Vec<MigrationPlan>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut paths: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().map_or(false, |ext| ext == "sql"))
+        .collect();
+    paths.sort();
+    paths
+        .iter()
+        .map(|path| {
+            let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+            Ok(analyze(&name, &std::fs::read_to_string(path)?))
+        })
+        .collect()
+}
+
+/// Bounds how long a statement waits for its lock, so it fails instead of stalling traffic.
+pub fn lock_timeout(ms: u64) -> String {
+    format!("SET lock_timeout = '{}ms'", ms)
+}
+
+pub fn add_nullable_column(table: &str, column: &str, sql_type: &str) -> String {
+    format!("ALTER TABLE {} ADD COLUMN IF NOT EXISTS {} {}", table, column, sql_type)
+}
+
+/// Must run outside a transaction.
+pub fn create_index_concurrently(name: &str, table: &str, columns: &[&str]) -> String {
+    format!("CREATE INDEX CONCURRENTLY IF NOT EXISTS {} ON {} ({})", name, table, columns.join(", "))
+}
+
+/// Makes a column NOT NULL without a long exclusive lock; Postgres skips the scan when a
+/// validated check already proves it.
+pub fn set_not_null(table: &str, column: &str) -> Vec<String> {
+    let check = format!("{}_{}_not_null", table, column);
+    vec![
+        format!("ALTER TABLE {} ADD CONSTRAINT {} CHECK ({} IS NOT NULL) NOT VALID", table, check, column),
+        format!("ALTER TABLE {} VALIDATE CONSTRAINT {}", table, check),
+        format!("ALTER TABLE {} ALTER COLUMN {} SET NOT NULL", table, column),
+        format!("ALTER TABLE {} DROP CONSTRAINT {}", table, check),
+    ]
+}
+
+pub fn add_foreign_key(table: &str, column: &str, references: &str, referenced_column: &str) -> Vec<String> {
+    let name = format!("{}_{}_fkey", table, column);
+    vec![
+        format!(
+            "ALTER TABLE {} ADD CONSTRAINT {} FOREIGN KEY ({}) REFERENCES {} ({}) NOT VALID",
+            table, name, column, references, referenced_column
+        ),
+        format!("ALTER TABLE {} VALIDATE CONSTRAINT {}", table, name),
+    ]
+}
+
+/// A rename as two migrations. Between them the application writes both columns and
+/// switches reads to the new one; the backfill runs in batches for large tables.
+#[derive(Debug, Serialize)]
+pub struct RenamePlan {
+    pub expand: Vec<String>,
+    pub backfill: String,
+    pub contract: Vec<String>,
+}
+
+pub fn rename_column(table: &str, from: &str, to: &str, sql_type: &str) -> RenamePlan {
+    RenamePlan {
+        expand: vec![add_nullable_column(table, to, sql_type)],
+        backfill: format!("UPDATE {} SET {} = {} WHERE {} IS NULL AND {} IS NOT NULL", table, to, from, to, from),
+        contract: vec![format!("ALTER TABLE {} DROP COLUMN IF EXISTS {}", table, from)],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(plan: &MigrationPlan) -> Vec<(&'static str, Risk)> {
+        plan.findings.iter().map(|f| (f.rule, f.risk)).collect()
+    }
+
+    #[test]
+    fn test_split_statements_respects_literals_and_comments() {
+        let sql = "-- phase: expand\nUPDATE t SET note = 'a;b'; -- trailing; comment\nCREATE FUNCTION f() RETURNS int AS $$ SELECT 1; $$ LANGUAGE sql;";
+        let statements = split_statements(sql);
+        assert_eq!(statements.len(), 2);
+        assert!(statements[1].ends_with("LANGUAGE sql"));
+    }
+
+    #[test]
+    fn test_rename_and_blocking_index_are_unsafe() {
+        let plan = analyze("0042.sql", "ALTER TABLE orders RENAME COLUMN status TO state;\nCREATE INDEX orders_state ON orders (state);");
+        assert_eq!(
+            rules(&plan),
+            vec![
+                ("rename", Risk::Unsafe),
+                ("index_not_concurrent", Risk::Unsafe),
+                ("missing_lock_timeout", Risk::Unsafe),
+            ]
+        );
+        assert_eq!(plan.strongest_lock(), LockLevel::AccessExclusive);
+    }
+
+    #[test]
+    fn test_safe_expand_migration_passes() {
+        let mut sql = vec![lock_timeout(5_000), add_nullable_column("orders", "state", "TEXT")];
+        sql.extend(set_not_null("orders", "state"));
+        sql.extend(add_foreign_key("orders", "customer_id", "customers", "id"));
+        let plan = analyze("0043.sql", &sql.join(";\n"));
+        assert_eq!(plan.violations().count(), 0, "{:?}", rules(&plan));
+
+        let concurrent = analyze("0044.sql", &create_index_concurrently("orders_state", "orders", &["state"]));
+        assert_eq!(concurrent.violations().count(), 0);
+    }
+
+    #[test]
+    fn test_destructive_changes_need_contract_phase_or_allowance() {
+        let drop = "SET lock_timeout = '5s'; ALTER TABLE orders DROP COLUMN status;";
+        assert_eq!(analyze("a.sql", drop).violations().count(), 1);
+        assert_eq!(analyze("b.sql", &format!("-- phase: contract\n{}", drop)).violations().count(), 0);
+        assert_eq!(analyze("c.sql", &format!("-- safety: allow drop_column\n{}", drop)).violations().count(), 0);
+    }
+
+    #[test]
+    fn test_ddl_in_procedural_bodies_is_unsafe() {
+        // The first partition migration renamed tables, attached partitions and dropped
+        // foreign keys from a function, which passed as an unrecognized statement
+        let function = "SET lock_timeout = '5s';
+            CREATE OR REPLACE FUNCTION partition_by_month(tbl TEXT, col TEXT) RETURNS VOID AS $$
+            BEGIN
+                EXECUTE format('ALTER TABLE %I RENAME TO %I', tbl, tbl || '_legacy');
+                EXECUTE format('ALTER TABLE %I ATTACH PARTITION %I DEFAULT', tbl, tbl || '_legacy');
+            END;
+            $$ LANGUAGE plpgsql;
+            SELECT partition_by_month('orders', 'created_date');";
+        let plan = analyze("partition.sql", function);
+        assert_eq!(rules(&plan)[0], ("hidden_ddl", Risk::Unsafe));
+        assert_eq!(plan.violations().count(), 1);
+
+        let block = "SET lock_timeout = '5s'; DO $$ BEGIN EXECUTE 'ALTER TABLE order_items DROP CONSTRAINT order_items_order_id_fkey'; END $$;";
+        assert_eq!(analyze("block.sql", block).violations().count(), 1);
+        assert_eq!(analyze("allowed.sql", &format!("-- safety: allow hidden_ddl\n{}", block)).violations().count(), 0);
+
+        // Bodies that only read and write rows stay a review note
+        let trigger = "SET lock_timeout = '5s'; CREATE FUNCTION touch() RETURNS trigger AS $$ BEGIN NEW.updated_at := now(); RETURN NEW; END; $$ LANGUAGE plpgsql;";
+        assert_eq!(rules(&analyze("touch.sql", trigger)), vec![("unrecognized", Risk::Caution)]);
+
+        let partition = analyze(
+            "20261016200000_partition_high_volume_tables.sql",
+            include_str!("../../migrations/20261016200000_partition_high_volume_tables.sql"),
+        );
+        assert_eq!(partition.violations().count(), 0);
+        assert!(partition.findings.iter().all(|f| f.rule != "hidden_ddl"));
+    }
+
+    #[test]
+    fn test_added_columns() {
+        let not_null = analyze("a.sql", "SET lock_timeout = '5s'; ALTER TABLE orders ADD COLUMN region TEXT NOT NULL");
+        assert_eq!(rules(&not_null), vec![("add_column_not_null", Risk::Unsafe)]);
+        let volatile = analyze("b.sql", "SET lock_timeout = '5s'; ALTER TABLE orders ADD COLUMN token UUID DEFAULT gen_random_uuid()");
+        assert_eq!(rules(&volatile), vec![("volatile_default", Risk::Unsafe)]);
+        let constant = analyze("c.sql", "SET lock_timeout = '5s'; ALTER TABLE orders ADD COLUMN flagged BOOLEAN NOT NULL DEFAULT false");
+        assert_eq!(rules(&constant), vec![("add_column", Risk::Safe)]);
+    }
+}
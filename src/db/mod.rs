@@ -0,0 +1,456 @@
+use arc_swap::ArcSwap;
+use lazy_static::lazy_static;
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts};
+use sea_orm::{ConnectOptions, ConnectionTrait, Database, DatabaseConnection, DbErr};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::time::MissedTickBehavior;
+use tracing::{error, warn};
+use url::Url;
+use crate::errors::AppError;
+
+pub mod dialect;
+pub mod index_advisor;
+pub mod migration_safety;
+
+/// Type alias for a database connection pool
+pub type DbPool = DatabaseConnection;
+
+/// Connection pool metrics, labelled by pool name.
+pub struct DatabaseMetrics {
+    pub connections: IntGaugeVec,
+    pub idle_connections: IntGaugeVec,
+    pub max_connections: IntGaugeVec,
+    pub acquire_seconds: HistogramVec,
+    pub acquire_timeouts: IntCounterVec,
+    pub slow_acquires: IntCounterVec,
+}
+
+impl DatabaseMetrics {
+    fn new() -> Self {
+        let gauge = |name: &str, help: &str| {
+            IntGaugeVec::new(Opts::new(name, help), &["pool"]).expect("metric can be created")
+        };
+        let counter = |name: &str, help: &str| {
+            IntCounterVec::new(Opts::new(name, help), &["pool"]).expect("metric can be created")
+        };
+        Self {
+            connections: gauge("db_pool_connections", "Open connections, idle or in use"),
+            idle_connections: gauge("db_pool_idle_connections", "Open connections waiting to be used"),
+            max_connections: gauge("db_pool_max_connections", "Most connections the pool may open"),
+            acquire_seconds: HistogramVec::new(
+                HistogramOpts::new("db_pool_acquire_seconds", "Time the pool monitor waited for a connection")
+                    .buckets(vec![0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]),
+                &["pool"],
+            )
+            .expect("metric can be created"),
+            acquire_timeouts: counter("db_pool_acquire_timeouts_total", "Connection waits that hit the acquire timeout"),
+            slow_acquires: counter("db_pool_slow_acquires_total", "Connection waits over the slow-acquire threshold"),
+        }
+    }
+}
+
+lazy_static! {
+    pub static ref DATABASE_METRICS: DatabaseMetrics = DatabaseMetrics::new();
+}
+
+/// Size and timeouts of one connection pool.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PoolSizing {
+    #[serde(default = "default_max_connections")]
+    pub max_connections: u32,
+
+    #[serde(default = "default_min_connections")]
+    pub min_connections: u32,
+
+    /// How long a request waits for a free connection before failing.
+    #[serde(default = "default_acquire_timeout_secs")]
+    pub acquire_timeout_secs: u64,
+
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+
+    /// Idle connections above `min_connections` are closed after this long.
+    #[serde(default = "default_idle_timeout_secs")]
+    pub idle_timeout_secs: u64,
+
+    #[serde(default = "default_max_lifetime_secs")]
+    pub max_lifetime_secs: u64,
+}
+
+fn default_max_connections() -> u32 {
+    20
+}
+
+fn default_min_connections() -> u32 {
+    2
+}
+
+fn default_acquire_timeout_secs() -> u64 {
+    5
+}
+
+fn default_connect_timeout_secs() -> u64 {
+    10
+}
+
+fn default_idle_timeout_secs() -> u64 {
+    600
+}
+
+fn default_max_lifetime_secs() -> u64 {
+    1800
+}
+
+impl Default for PoolSizing {
+    fn default() -> Self {
+        Self {
+            max_connections: default_max_connections(),
+            min_connections: default_min_connections(),
+            acquire_timeout_secs: default_acquire_timeout_secs(),
+            connect_timeout_secs: default_connect_timeout_secs(),
+            idle_timeout_secs: default_idle_timeout_secs(),
+            max_lifetime_secs: default_max_lifetime_secs(),
+        }
+    }
+}
+
+/// Connection pool settings, loaded from the `database_pool` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct DatabasePoolConfig {
+    /// The pool serving API requests and background workers.
+    #[serde(default)]
+    pub api: PoolSizing,
+
+    /// The pool of one-off tools: the CLI and the seeder.
+    #[serde(default = "default_tools_pool")]
+    pub tools: PoolSizing,
+
+    /// Server-side limit on each statement; unlimited when unset.
+    #[serde(default)]
+    pub statement_timeout_ms: Option<u64>,
+
+    /// Connection waits longer than this are logged and counted.
+    #[serde(default = "default_slow_acquire_ms")]
+    pub slow_acquire_ms: u64,
+
+    /// How often pool statistics are sampled.
+    #[serde(default = "default_sample_interval_secs")]
+    pub sample_interval_secs: u64,
+}
+
+fn default_tools_pool() -> PoolSizing {
+    PoolSizing { max_connections: 4, min_connections: 0, ..PoolSizing::default() }
+}
+
+fn default_slow_acquire_ms() -> u64 {
+    250
+}
+
+fn default_sample_interval_secs() -> u64 {
+    15
+}
+
+impl Default for DatabasePoolConfig {
+    fn default() -> Self {
+        Self {
+            api: PoolSizing::default(),
+            tools: default_tools_pool(),
+            statement_timeout_ms: None,
+            slow_acquire_ms: default_slow_acquire_ms(),
+            sample_interval_secs: default_sample_interval_secs(),
+        }
+    }
+}
+
+/// Establishes a connection pool to the database
+pub async fn establish_connection(database_url: &str) -> Result<DbPool, AppError> {
+    Database::connect(database_url)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+}
+
+/// Establishes a connection pool sized by `sizing`, applying the statement timeout.
+pub async fn connect_pool(
+    database_url: &str,
+    sizing: &PoolSizing,
+    config: &DatabasePoolConfig,
+) -> Result<DbPool, AppError> {
+    // SQLite has no statement timeout; the busy timeout of its driver applies instead
+    let url = match config.statement_timeout_ms {
+        Some(ms) if !database_url.starts_with("sqlite:") => with_statement_timeout(database_url, ms)?,
+        _ => database_url.to_string(),
+    };
+    let mut options = ConnectOptions::new(url);
+    options
+        .max_connections(sizing.max_connections.max(1))
+        .min_connections(sizing.min_connections.min(sizing.max_connections))
+        .acquire_timeout(Duration::from_secs(sizing.acquire_timeout_secs))
+        .connect_timeout(Duration::from_secs(sizing.connect_timeout_secs))
+        .idle_timeout(Duration::from_secs(sizing.idle_timeout_secs))
+        .max_lifetime(Duration::from_secs(sizing.max_lifetime_secs))
+        .sqlx_logging(false);
+    Database::connect(options)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+}
+
+/// Adds a Postgres `statement_timeout` to the connection URL, so every connection the
+/// pool opens starts with it.
+pub fn with_statement_timeout(database_url: &str, timeout_ms: u64) -> Result<String, AppError> {
+    let mut url = Url::parse(database_url).map_err(|e| AppError::ConfigError(e.to_string()))?;
+    let mut options: Vec<String> = url
+        .query_pairs()
+        .filter(|(key, _)| key == "options")
+        .map(|(_, value)| value.into_owned())
+        .collect();
+    options.push(format!("-c statement_timeout={}", timeout_ms));
+    let others: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| key != "options")
+        .map(|(k, v)| (k.into_owned(), v.into_owned()))
+        .collect();
+    url.query_pairs_mut()
+        .clear()
+        .extend_pairs(others)
+        .append_pair("options", &options.join(" "));
+    Ok(url.to_string())
+}
+
+/// A sample of one pool's state.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct PoolStats {
+    pub pool: String,
+    pub connections: u32,
+    pub idle: u32,
+    pub active: u32,
+    pub max_connections: u32,
+    /// How long the last probe waited for a connection.
+    pub acquire_ms: Option<u64>,
+    pub acquire_timeouts: u64,
+    pub slow_acquires: u64,
+    pub sampled_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolHealth {
+    Ok,
+    /// Every connection is in use or waits are slow; requests are about to queue.
+    Degraded,
+    /// The last probe could not get a connection in time.
+    Unavailable,
+}
+
+impl PoolStats {
+    pub fn health(&self, slow_acquire_ms: u64) -> PoolHealth {
+        match self.acquire_ms {
+            None if self.sampled_at.is_some() => PoolHealth::Unavailable,
+            Some(ms) if ms > slow_acquire_ms => PoolHealth::Degraded,
+            _ if self.max_connections > 0 && self.active >= self.max_connections => PoolHealth::Degraded,
+            _ => PoolHealth::Ok,
+        }
+    }
+}
+
+/// Samples a pool on an interval into `DATABASE_METRICS`, alerting on slow waits and
+/// exhaustion. Waits are measured by acquiring a connection like a request would.
+pub struct PoolMonitor {
+    name: String,
+    db: Arc<DbPool>,
+    config: DatabasePoolConfig,
+    acquire_timeout: Duration,
+    stats: ArcSwap<PoolStats>,
+}
+
+impl PoolMonitor {
+    pub fn new(name: &str, db: Arc<DbPool>, sizing: &PoolSizing, config: DatabasePoolConfig) -> Self {
+        Self {
+            name: name.to_string(),
+            db,
+            config,
+            acquire_timeout: Duration::from_secs(sizing.acquire_timeout_secs),
+            stats: ArcSwap::from_pointee(PoolStats { pool: name.to_string(), ..PoolStats::default() }),
+        }
+    }
+
+    pub fn stats(&self) -> Arc<PoolStats> {
+        self.stats.load_full()
+    }
+
+    pub fn health(&self) -> PoolHealth {
+        self.stats().health(self.config.slow_acquire_ms)
+    }
+
+    pub async fn sample(&self) -> PoolStats {
+        let probe = match self.db.get_database_backend() {
+            #[cfg(feature = "sqlite")]
+            sea_orm::DbBackend::Sqlite => probe(self.db.get_sqlite_connection_pool(), self.acquire_timeout).await,
+            _ => probe(self.db.get_postgres_connection_pool(), self.acquire_timeout).await,
+        };
+        let previous = self.stats();
+        let mut stats = PoolStats {
+            pool: self.name.clone(),
+            connections: probe.connections,
+            idle: probe.idle,
+            active: probe.connections.saturating_sub(probe.idle),
+            max_connections: probe.max_connections,
+            acquire_ms: None,
+            acquire_timeouts: previous.acquire_timeouts,
+            slow_acquires: previous.slow_acquires,
+            sampled_at: Some(chrono::Utc::now()),
+        };
+        let labels = [self.name.as_str()];
+        match probe.acquired {
+            Ok(Ok(waited)) => {
+                stats.acquire_ms = Some(waited.as_millis() as u64);
+                DATABASE_METRICS.acquire_seconds.with_label_values(&labels).observe(waited.as_secs_f64());
+                if waited.as_millis() as u64 > self.config.slow_acquire_ms {
+                    stats.slow_acquires += 1;
+                    DATABASE_METRICS.slow_acquires.with_label_values(&labels).inc();
+                    warn!(pool = %self.name, waited_ms = waited.as_millis() as u64, "Slow database connection acquire");
+                }
+            }
+            Ok(Err(e)) => error!(pool = %self.name, "Database connection acquire failed: {}", e),
+            Err(_) => {
+                stats.acquire_timeouts += 1;
+                DATABASE_METRICS.acquire_timeouts.with_label_values(&labels).inc();
+                error!(pool = %self.name, "Database pool exhausted: no connection within {:?}", self.acquire_timeout);
+            }
+        }
+        if stats.max_connections > 0 && stats.active >= stats.max_connections {
+            warn!(pool = %self.name, active = stats.active, "Every database connection is in use");
+        }
+        DATABASE_METRICS.connections.with_label_values(&labels).set(stats.connections as i64);
+        DATABASE_METRICS.idle_connections.with_label_values(&labels).set(stats.idle as i64);
+        DATABASE_METRICS.max_connections.with_label_values(&labels).set(stats.max_connections as i64);
+        self.stats.store(Arc::new(stats.clone()));
+        stats
+    }
+}
+
+struct Probe {
+    connections: u32,
+    idle: u32,
+    max_connections: u32,
+    acquired: Result<Result<Duration, sea_orm::sqlx::Error>, tokio::time::error::Elapsed>,
+}
+
+/// Counts a pool's connections, then times how long it takes to get one.
+async fn probe<DB: sea_orm::sqlx::Database>(pool: &sea_orm::sqlx::Pool<DB>, timeout: Duration) -> Probe {
+    // Counted before the probe takes a connection of its own
+    let (connections, idle) = (pool.size(), pool.num_idle() as u32);
+    let started = Instant::now();
+    let acquired = tokio::time::timeout(timeout, pool.acquire())
+        .await
+        .map(|result| result.map(|_connection| started.elapsed()));
+    Probe { connections, idle, max_connections: pool.options().get_max_connections(), acquired }
+}
+
+pub fn spawn_pool_monitor(monitor: Arc<PoolMonitor>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(monitor.config.sample_interval_secs.max(1)));
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            monitor.sample().await;
+        }
+    });
+}
+
+/// Applies the pending SQL files in `migrations/`.
+pub async fn run_migrations(pool: &DbPool) -> Result<(), AppError> {
+    crate::migrator::Migrator
+        .up(pool, None)
+        .await
+        .map_err(|e| AppError::MigrationError(e.to_string()))
+}
+
+/// Provides a connection from the pool
+pub fn get_connection(pool: &DbPool) -> Result<&DbPool, AppError> {
+    Ok(pool)
+}
+
+/// Trait for adding async database operations
+#[async_trait::async_trait]
+pub trait AsyncDatabase {
+    async fn execute_async<F, T>(&self, f: F) -> Result<T, AppError>
+    where
+        F: FnOnce(&DbPool) -> Result<T, DbErr> + Send + 'static,
+        T: Send + 'static;
+}
+
+#[async_trait::async_trait]
+impl AsyncDatabase for DbPool {
+    async fn execute_async<F, T>(&self, f: F) -> Result<T, AppError>
+    where
+        F: FnOnce(&DbPool) -> Result<T, DbErr> + Send + 'static,
+        T: Send + 'static,
+    {
+        let pool = self.clone();
+        tokio::task::spawn_blocking(move || f(&pool))
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::runtime::Runtime;
+
+    #[test]
+    fn test_statement_timeout_is_added_to_connection_options() {
+        let url = with_statement_timeout("postgres://u:p@db/app?sslmode=require", 5000).unwrap();
+        let parsed = Url::parse(&url).unwrap();
+        let pairs: Vec<(String, String)> = parsed.query_pairs().map(|(k, v)| (k.into_owned(), v.into_owned())).collect();
+        assert!(pairs.contains(&("sslmode".to_string(), "require".to_string())));
+        assert!(pairs.contains(&("options".to_string(), "-c statement_timeout=5000".to_string())));
+
+        let url = with_statement_timeout("postgres://u:p@db/app?options=-c%20search_path%3Dapp", 100).unwrap();
+        let options = Url::parse(&url).unwrap().query_pairs().find(|(k, _)| k == "options").unwrap().1.into_owned();
+        assert_eq!(options, "-c search_path=app -c statement_timeout=100");
+    }
+
+    #[test]
+    fn test_pool_health() {
+        let stats = PoolStats {
+            connections: 10,
+            idle: 4,
+            active: 6,
+            max_connections: 10,
+            acquire_ms: Some(3),
+            sampled_at: Some(chrono::Utc::now()),
+            ..PoolStats::default()
+        };
+        assert_eq!(stats.health(250), PoolHealth::Ok);
+        assert_eq!(PoolStats { acquire_ms: Some(400), ..stats.clone() }.health(250), PoolHealth::Degraded);
+        assert_eq!(PoolStats { active: 10, idle: 0, ..stats.clone() }.health(250), PoolHealth::Degraded);
+        assert_eq!(PoolStats { acquire_ms: None, ..stats.clone() }.health(250), PoolHealth::Unavailable);
+        assert_eq!(PoolStats::default().health(250), PoolHealth::Ok);
+    }
+
+    #[test]
+    fn test_establish_connection() {
+        let rt = Runtime::new().unwrap();
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+
+        rt.block_on(async {
+            let pool = establish_connection(&database_url).await.expect("Failed to establish connection");
+            assert!(pool.is_active().await);
+        });
+    }
+
+    #[test]
+    fn test_run_migrations() {
+        let rt = Runtime::new().unwrap();
+        let database_url = std::env::var("DATABASE_URL").expect("DATABASE_URL must be set");
+
+        rt.block_on(async {
+            let pool = establish_connection(&database_url).await.expect("Failed to establish connection");
+            assert!(run_migrations(&pool).await.is_ok());
+        });
+    }
+}
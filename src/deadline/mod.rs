@@ -0,0 +1,315 @@
+// deadline/mod.rs
+
+use axum::{
+    extract::State,
+    http::{Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use prometheus::IntCounter;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use thiserror::Error;
+use tokio::time::Instant;
+use tracing::warn;
+
+lazy_static! {
+    static ref DEADLINES_EXCEEDED: IntCounter =
+        IntCounter::new(
+            "request_deadlines_exceeded_total",
+            "Requests cancelled because their deadline passed"
+        ).expect("metric can be created");
+}
+
+/// Time budget for the request, e.g. `2500`, `2500ms` or `2.5s`.
+pub const TIMEOUT_HEADER: &str = "X-Request-Timeout";
+
+/// Absolute deadline for the request as an RFC 3339 timestamp.
+pub const DEADLINE_HEADER: &str = "X-Request-Deadline";
+
+/// Request deadline settings, loaded from the `deadlines` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct DeadlineConfig {
+    /// Enables the middleware (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Budget for requests that send no deadline; unbounded when unset.
+    #[serde(default)]
+    pub default_timeout_ms: Option<u64>,
+
+    /// Longest budget a client may ask for (default: 60 s).
+    #[serde(default = "default_max_timeout_ms")]
+    pub max_timeout_ms: u64,
+
+    /// Path prefixes that run without any deadline, e.g. routes that move money, where
+    /// even an outbound call cut short can leave the gateway and the database apart.
+    #[serde(default = "default_exempt_paths")]
+    pub exempt_paths: Vec<String>,
+}
+
+fn default_max_timeout_ms() -> u64 {
+    60_000
+}
+
+fn default_exempt_paths() -> Vec<String> {
+    [
+        "/api/v1/checkout",
+        "/api/v1/payment-authorizations",
+        "/api/v1/payment-vault",
+        "/api/v1/subscriptions",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+impl Default for DeadlineConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            default_timeout_ms: None,
+            max_timeout_ms: default_max_timeout_ms(),
+            exempt_paths: default_exempt_paths(),
+        }
+    }
+}
+
+/// How a request's deadline is enforced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Enforcement {
+    /// No deadline at all.
+    Exempt,
+    /// The deadline bounds outbound calls and [`check`]-ing loops, but the handler always
+    /// runs to completion, so a write is never abandoned halfway.
+    Cooperative,
+    /// The handler is dropped at the deadline. Only for requests that change nothing.
+    Cancel,
+}
+
+/// Reads may be cancelled outright; writes only cooperatively, and exempt paths not at all.
+pub fn enforcement(config: &DeadlineConfig, method: &Method, path: &str) -> Enforcement {
+    if config.exempt_paths.iter().any(|prefix| path.starts_with(prefix.as_str())) {
+        Enforcement::Exempt
+    } else if matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS) {
+        Enforcement::Cancel
+    } else {
+        Enforcement::Cooperative
+    }
+}
+
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("Request deadline exceeded")]
+pub struct DeadlineExceeded;
+
+/// A point a request reached before its deadline, reported when it is exceeded.
+#[derive(Clone, Debug, Serialize)]
+pub struct Checkpoint {
+    pub stage: String,
+    pub at_ms: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct Deadline {
+    started: Instant,
+    expires_at: Instant,
+    trail: Arc<Mutex<Vec<Checkpoint>>>,
+}
+
+impl Deadline {
+    pub fn after(budget: Duration) -> Self {
+        let started = Instant::now();
+        Self { started, expires_at: started + budget, trail: Arc::default() }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.expires_at.saturating_duration_since(Instant::now())
+    }
+
+    fn record(&self, stage: String) {
+        let at_ms = self.started.elapsed().as_millis() as u64;
+        if let Ok(mut trail) = self.trail.lock() {
+            trail.push(Checkpoint { stage, at_ms });
+        }
+    }
+
+    fn checkpoints(&self) -> Vec<Checkpoint> {
+        self.trail.lock().map(|trail| trail.clone()).unwrap_or_default()
+    }
+}
+
+tokio::task_local! {
+    static DEADLINE: Deadline;
+}
+
+/// Time left for the current request; `None` outside a request or without a deadline.
+pub fn remaining() -> Option<Duration> {
+    DEADLINE.try_with(Deadline::remaining).ok()
+}
+
+/// Notes that the current request reached `stage`, for the diagnostics of a 504.
+pub fn checkpoint(stage: impl Into<String>) {
+    let _ = DEADLINE.try_with(|deadline| deadline.record(stage.into()));
+}
+
+/// Fails once the current request's deadline has passed, for loops doing work in steps.
+pub fn check() -> Result<(), DeadlineExceeded> {
+    match remaining() {
+        Some(left) if left.is_zero() => Err(DeadlineExceeded),
+        _ => Ok(()),
+    }
+}
+
+/// Runs `work` within the current request's remaining time.
+pub async fn run<F: Future>(work: F) -> Result<F::Output, DeadlineExceeded> {
+    match remaining() {
+        Some(left) => tokio::time::timeout(left, work).await.map_err(|_| DeadlineExceeded),
+        None => Ok(work.await),
+    }
+}
+
+/// Bounds an outbound call by the current request's remaining time and passes the budget
+/// on, so downstream services stop when the caller has given up.
+pub fn bounded(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match remaining() {
+        Some(left) => {
+            checkpoint("outbound request");
+            request.timeout(left).header(TIMEOUT_HEADER, format!("{}ms", left.as_millis()))
+        }
+        None => request,
+    }
+}
+
+/// Parses a timeout header: milliseconds, with an optional `ms` or `s` suffix.
+pub fn parse_timeout(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Some(ms) = value.strip_suffix("ms") {
+        return ms.trim().parse::<u64>().ok().map(Duration::from_millis);
+    }
+    if let Some(secs) = value.strip_suffix('s') {
+        return secs.trim().parse::<f64>().ok().filter(|s| s.is_finite() && *s >= 0.0).map(Duration::from_secs_f64);
+    }
+    value.parse::<u64>().ok().map(Duration::from_millis)
+}
+
+/// Budget until an RFC 3339 deadline header; zero if it already passed.
+pub fn parse_deadline(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let deadline = DateTime::parse_from_rfc3339(value.trim()).ok()?.with_timezone(&Utc);
+    Some((deadline - now).to_std().unwrap_or_default())
+}
+
+/// The budget for a request: the tighter of its headers, else the configured default,
+/// capped at the maximum.
+pub fn budget(config: &DeadlineConfig, timeout: Option<&str>, deadline: Option<&str>) -> Option<Duration> {
+    let requested = [timeout.and_then(parse_timeout), deadline.and_then(|d| parse_deadline(d, Utc::now()))]
+        .into_iter()
+        .flatten()
+        .min();
+    requested
+        .or(config.default_timeout_ms.map(Duration::from_millis))
+        .map(|budget| budget.min(Duration::from_millis(config.max_timeout_ms)))
+}
+
+fn header<'a, B>(req: &'a Request<B>, name: &str) -> Option<&'a str> {
+    req.headers().get(name).and_then(|h| h.to_str().ok())
+}
+
+/// Runs the request under its deadline. Past it a read is dropped, which cancels its
+/// pending database and outbound calls, and a 504 lists the checkpoints it reached. Writes
+/// only see the deadline through [`remaining`], [`check`] and [`bounded`] and always finish;
+/// see [`enforcement`].
+pub async fn deadline_middleware<B>(
+    State(config): State<Arc<DeadlineConfig>>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let path = req.uri().path().to_string();
+    let enforcement = enforcement(&config, req.method(), &path);
+    let budget = match enforcement {
+        Enforcement::Exempt => None,
+        _ => budget(&config, header(&req, TIMEOUT_HEADER), header(&req, DEADLINE_HEADER)),
+    };
+    let Some(budget) = budget else {
+        return next.run(req).await;
+    };
+    let deadline = Deadline::after(budget);
+    if enforcement == Enforcement::Cooperative {
+        return DEADLINE.scope(deadline, next.run(req)).await;
+    }
+    let scoped = DEADLINE.scope(deadline.clone(), tokio::time::timeout(budget, next.run(req)));
+    match scoped.await {
+        Ok(response) => response,
+        Err(_) => {
+            DEADLINES_EXCEEDED.inc();
+            let elapsed_ms = deadline.started.elapsed().as_millis() as u64;
+            warn!(%path, elapsed_ms, "Request cancelled at its deadline");
+            let body = json!({
+                "error": format!("Request deadline of {} ms exceeded", budget.as_millis()),
+                "code": "deadline_exceeded",
+                "timeout_ms": budget.as_millis() as u64,
+                "elapsed_ms": elapsed_ms,
+                "checkpoints": deadline.checkpoints(),
+            });
+            (StatusCode::GATEWAY_TIMEOUT, Json(body)).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_timeout() {
+        assert_eq!(parse_timeout("2500"), Some(Duration::from_millis(2500)));
+        assert_eq!(parse_timeout("2500ms"), Some(Duration::from_millis(2500)));
+        assert_eq!(parse_timeout("2.5s"), Some(Duration::from_millis(2500)));
+        assert_eq!(parse_timeout("soon"), None);
+        assert_eq!(parse_timeout("-1s"), None);
+    }
+
+    #[test]
+    fn test_budget_takes_tighter_header_and_caps() {
+        let config = DeadlineConfig {
+            enabled: true,
+            default_timeout_ms: Some(10_000),
+            max_timeout_ms: 30_000,
+            exempt_paths: Vec::new(),
+        };
+        assert_eq!(budget(&config, None, None), Some(Duration::from_secs(10)));
+        assert_eq!(budget(&config, Some("120s"), None), Some(Duration::from_secs(30)));
+        let soon = (Utc::now() + chrono::Duration::seconds(1)).to_rfc3339();
+        assert!(budget(&config, Some("5s"), Some(&soon)).unwrap() <= Duration::from_secs(1));
+        assert_eq!(budget(&DeadlineConfig::default(), None, None), None);
+    }
+
+    #[test]
+    fn test_only_reads_are_cancelled() {
+        let config = DeadlineConfig::default();
+        assert_eq!(enforcement(&config, &Method::GET, "/api/v1/orders"), Enforcement::Cancel);
+        assert_eq!(enforcement(&config, &Method::POST, "/api/v1/orders"), Enforcement::Cooperative);
+        assert_eq!(enforcement(&config, &Method::DELETE, "/api/v1/orders/1"), Enforcement::Cooperative);
+        assert_eq!(enforcement(&config, &Method::GET, "/api/v1/checkout/abc"), Enforcement::Exempt);
+        assert_eq!(
+            enforcement(&config, &Method::POST, "/api/v1/payment-authorizations/1/capture"),
+            Enforcement::Exempt
+        );
+    }
+
+    #[tokio::test]
+    async fn test_run_outside_a_request_is_unbounded() {
+        assert_eq!(remaining(), None);
+        assert_eq!(run(async { 7 }).await, Ok(7));
+        let deadline = Deadline::after(Duration::from_millis(5));
+        let result = DEADLINE
+            .scope(deadline, run(tokio::time::sleep(Duration::from_secs(5))))
+            .await;
+        assert_eq!(result, Err(DeadlineExceeded));
+    }
+}
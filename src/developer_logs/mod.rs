@@ -0,0 +1,454 @@
+// developer_logs/mod.rs
+
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use hyper::body::HttpBody;
+use lazy_static::lazy_static;
+use prometheus::IntCounterVec;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{sync::Arc, time::{Duration, Instant}};
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tracing::warn;
+use uuid::Uuid;
+
+use crate::auth::{ActorType, Claims};
+use crate::logging::redaction::Redactor;
+use crate::middleware_helpers::body::{read_limited, BodyReadError};
+use crate::models::{
+    developer_request_log::{self, Entity as DeveloperRequestLog},
+    webhook_delivery_log::{self, Entity as WebhookDeliveryLog},
+    webhook_subscription::{self, Entity as WebhookSubscription},
+};
+use crate::network_acl::api_key_prefix;
+
+lazy_static! {
+    static ref DEVELOPER_LOG_RECORDS: IntCounterVec =
+        IntCounterVec::new(
+            "developer_log_records_total",
+            "Developer console log records by kind and outcome",
+            &["kind", "outcome"]
+        ).expect("metric can be created");
+}
+
+/// Path prefix of the developer console itself, whose requests are not logged.
+const CONSOLE_PATH: &str = "/api/v1/developer";
+
+/// Records waiting to be written; when the queue is full new records are dropped.
+const QUEUE_CAPACITY: usize = 1024;
+
+/// Bodies larger than this are neither buffered nor redacted; a size marker is logged.
+const MAX_REDACTED_BYTES: usize = 1024 * 1024;
+
+/// Developer console settings, loaded from the `developer_logs` section of the config.
+/// Rows are purged by `retention` policies on `developer_request_logs` and
+/// `webhook_delivery_logs`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct DeveloperLogsConfig {
+    /// Records requests made with API keys and webhook delivery attempts (default: false).
+    /// Already recorded logs can be read either way.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Bytes of each redacted body kept (default: 4 KiB).
+    #[serde(default = "default_max_body_bytes")]
+    pub max_body_bytes: usize,
+
+    /// Most entries of each kind returned by one read (default: 200).
+    #[serde(default = "default_max_limit")]
+    pub max_limit: u64,
+}
+
+fn default_max_body_bytes() -> usize {
+    4 * 1024
+}
+
+fn default_max_limit() -> u64 {
+    200
+}
+
+impl Default for DeveloperLogsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_body_bytes: default_max_body_bytes(),
+            max_limit: default_max_limit(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum DeveloperLogError {
+    #[error("Developer logs are only available to requests made with an API key")]
+    ApiKeyRequired,
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for DeveloperLogError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            DeveloperLogError::ApiKeyRequired => (StatusCode::FORBIDDEN, "API_KEY_REQUIRED"),
+            DeveloperLogError::Database(_) => (StatusCode::INTERNAL_SERVER_ERROR, "DATABASE_ERROR"),
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// A redacted body cut to at most `max` bytes on a character boundary, with a marker
+/// saying how much was dropped.
+pub fn truncated_body(redactor: &Redactor, body: &[u8], max: usize) -> String {
+    if body.is_empty() {
+        return String::new();
+    }
+    if body.len() > MAX_REDACTED_BYTES {
+        return format!("[{} bytes]", body.len());
+    }
+    let mut redacted = redactor.redact_body(body);
+    if redacted.len() > max {
+        let mut end = max;
+        while !redacted.is_char_boundary(end) {
+            end -= 1;
+        }
+        let dropped = redacted.len() - end;
+        redacted.truncate(end);
+        redacted.push_str(&format!("…[{} more bytes]", dropped));
+    }
+    redacted
+}
+
+/// One attempt at delivering a webhook, as seen by the delivery loop.
+pub struct DeliveryAttempt<'a> {
+    pub subscription: &'a webhook_subscription::Model,
+    pub event: &'a str,
+    pub attempt: u32,
+    pub replay: bool,
+    pub latency: Duration,
+    /// Status and body of the response, or why there was none.
+    pub outcome: Result<(u16, &'a [u8]), &'a str>,
+}
+
+enum LogRecord {
+    Request(developer_request_log::ActiveModel),
+    Delivery(webhook_delivery_log::ActiveModel),
+}
+
+impl LogRecord {
+    fn kind(&self) -> &'static str {
+        match self {
+            LogRecord::Request(_) => "request",
+            LogRecord::Delivery(_) => "delivery",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogQuery {
+    /// Entries of each kind, newest first (default: 50).
+    pub limit: Option<u64>,
+    /// Only entries older than this, for paging back.
+    pub before: Option<DateTime<Utc>>,
+    /// Only failed requests (status 400 and up) and failed delivery attempts.
+    #[serde(default)]
+    pub errors_only: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeveloperLogs {
+    pub api_key_prefix: String,
+    pub requests: Vec<developer_request_log::Model>,
+    pub webhook_deliveries: Vec<webhook_delivery_log::Model>,
+}
+
+/// Records API-key requests and webhook deliveries for the developer console and reads
+/// them back. Recording never blocks requests or deliveries: records are queued and
+/// written by a background task.
+pub struct DeveloperLogService {
+    db: Arc<DatabaseConnection>,
+    config: DeveloperLogsConfig,
+    redactor: Redactor,
+    sender: mpsc::Sender<LogRecord>,
+}
+
+impl DeveloperLogService {
+    pub fn start(db: Arc<DatabaseConnection>, config: DeveloperLogsConfig, redactor: Redactor) -> Arc<Self> {
+        let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+        tokio::spawn(run_writer(db.clone(), receiver));
+        Arc::new(Self { db, config, redactor, sender })
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    fn body(&self, bytes: &[u8]) -> String {
+        truncated_body(&self.redactor, bytes, self.config.max_body_bytes)
+    }
+
+    fn logged_body(&self, body: &LoggedBody) -> String {
+        match body {
+            LoggedBody::Buffered(bytes) => self.body(bytes),
+            LoggedBody::Streamed(Some(length)) => format!("[{} bytes]", length),
+            LoggedBody::Streamed(None) => "[streamed]".to_string(),
+        }
+    }
+
+    fn enqueue(&self, record: LogRecord) {
+        let kind = record.kind();
+        match self.sender.try_send(record) {
+            Ok(()) => DEVELOPER_LOG_RECORDS.with_label_values(&[kind, "queued"]).inc(),
+            Err(_) => DEVELOPER_LOG_RECORDS.with_label_values(&[kind, "dropped"]).inc(),
+        }
+    }
+
+    pub fn record_delivery(&self, delivery: DeliveryAttempt<'_>) {
+        if !self.config.enabled {
+            return;
+        }
+        let (status, error, body) = match delivery.outcome {
+            Ok((status, body)) => (Some(status as i32), None, self.body(body)),
+            Err(error) => (None, Some(error.to_string()), String::new()),
+        };
+        self.enqueue(LogRecord::Delivery(webhook_delivery_log::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            subscription_id: Set(delivery.subscription.id),
+            event_type: Set(delivery.event.to_string()),
+            url: Set(delivery.subscription.url.clone()),
+            attempt: Set(delivery.attempt as i32),
+            replay: Set(delivery.replay),
+            status: Set(status),
+            error: Set(error),
+            latency_ms: Set(delivery.latency.as_millis() as i64),
+            response_body: Set(body),
+            created_at: Set(Utc::now()),
+        }));
+    }
+
+    /// Recent requests made with the API key `prefix`, and recent deliveries to the
+    /// webhook subscriptions `actor` created.
+    pub async fn logs(&self, prefix: &str, actor: &str, query: &LogQuery) -> Result<DeveloperLogs, DeveloperLogError> {
+        let limit = query.limit.unwrap_or(50).clamp(1, self.config.max_limit.max(1));
+
+        let mut requests = DeveloperRequestLog::find()
+            .filter(developer_request_log::Column::ApiKeyPrefix.eq(prefix));
+        if let Some(before) = query.before {
+            requests = requests.filter(developer_request_log::Column::CreatedAt.lt(before));
+        }
+        if query.errors_only {
+            requests = requests.filter(developer_request_log::Column::Status.gte(400));
+        }
+        let requests = requests
+            .order_by_desc(developer_request_log::Column::CreatedAt)
+            .limit(limit)
+            .all(self.db.as_ref())
+            .await?;
+
+        let subscriptions: Vec<Uuid> = WebhookSubscription::find()
+            .filter(webhook_subscription::Column::CreatedBy.eq(actor))
+            .all(self.db.as_ref())
+            .await?
+            .into_iter()
+            .map(|subscription| subscription.id)
+            .collect();
+        let webhook_deliveries = if subscriptions.is_empty() {
+            Vec::new()
+        } else {
+            let mut deliveries = WebhookDeliveryLog::find()
+                .filter(webhook_delivery_log::Column::SubscriptionId.is_in(subscriptions));
+            if let Some(before) = query.before {
+                deliveries = deliveries.filter(webhook_delivery_log::Column::CreatedAt.lt(before));
+            }
+            if query.errors_only {
+                deliveries = deliveries.filter(
+                    webhook_delivery_log::Column::Status
+                        .is_null()
+                        .or(webhook_delivery_log::Column::Status.lt(200))
+                        .or(webhook_delivery_log::Column::Status.gte(300)),
+                );
+            }
+            deliveries
+                .order_by_desc(webhook_delivery_log::Column::CreatedAt)
+                .limit(limit)
+                .all(self.db.as_ref())
+                .await?
+        };
+
+        Ok(DeveloperLogs { api_key_prefix: prefix.to_string(), requests, webhook_deliveries })
+    }
+}
+
+async fn run_writer(db: Arc<DatabaseConnection>, mut receiver: mpsc::Receiver<LogRecord>) {
+    while let Some(record) = receiver.recv().await {
+        let kind = record.kind();
+        let written = match record {
+            LogRecord::Request(row) => row.insert(db.as_ref()).await.map(|_| ()),
+            LogRecord::Delivery(row) => row.insert(db.as_ref()).await.map(|_| ()),
+        };
+        if let Err(e) = written {
+            DEVELOPER_LOG_RECORDS.with_label_values(&[kind, "failed"]).inc();
+            warn!(kind, "Could not write a developer log record: {}", e);
+        }
+    }
+}
+
+/// Whether a request is recorded: it was authenticated with an API key and is not a
+/// console read. Keys that failed authentication never get this far.
+fn should_record<B>(req: &Request<B>) -> Option<String> {
+    if req.uri().path().starts_with(CONSOLE_PATH) {
+        return None;
+    }
+    let authenticated = req
+        .extensions()
+        .get::<Claims>()
+        .is_some_and(|claims| claims.actor_type == ActorType::ServiceAccount);
+    if !authenticated {
+        return None;
+    }
+    api_key_prefix(req).filter(|prefix| !prefix.is_empty()).map(str::to_string)
+}
+
+/// Buffers a body for the log when it declares a length of at most
+/// [`MAX_REDACTED_BYTES`], handing back a copy built by `rebuild` to pass on. Others are
+/// left to stream and logged as their size.
+async fn buffer<B>(body: B, rebuild: impl FnOnce(Bytes) -> B) -> Result<(LoggedBody, B), BodyReadError>
+where
+    B: HttpBody<Data = Bytes> + Unpin,
+    B::Error: std::fmt::Display,
+{
+    match body.size_hint().exact() {
+        Some(length) if length <= MAX_REDACTED_BYTES as u64 => {
+            let bytes = read_limited(body, MAX_REDACTED_BYTES).await?;
+            Ok((LoggedBody::Buffered(bytes.clone()), rebuild(bytes)))
+        }
+        length => Ok((LoggedBody::Streamed(length), body)),
+    }
+}
+
+enum LoggedBody {
+    Buffered(Bytes),
+    /// Not buffered; the declared length, if any.
+    Streamed(Option<u64>),
+}
+
+/// Middleware recording requests authenticated with an API key, with redacted and
+/// truncated bodies. Layered inside API-key authentication; other requests pass
+/// through without their bodies being buffered, and so do bodies too large to log.
+pub async fn developer_log_middleware(
+    State(logs): State<Arc<DeveloperLogService>>,
+    req: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    let prefix = match should_record(&req) {
+        Some(prefix) if logs.enabled() => prefix,
+        _ => return next.run(req).await,
+    };
+
+    let started = Instant::now();
+    let (parts, body) = req.into_parts();
+    let (request_body, body) = match buffer(body, Body::from).await {
+        Ok(buffered) => buffered,
+        Err(e) => {
+            warn!("Failed to buffer request body for the developer log: {}", e);
+            return (StatusCode::BAD_REQUEST, Json(json!({ "error": "Request body could not be read" }))).into_response();
+        }
+    };
+
+    let mut row = developer_request_log::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        api_key_prefix: Set(prefix),
+        method: Set(parts.method.to_string()),
+        path: Set(parts.uri.path().to_string()),
+        query: Set(parts.uri.query().map(|q| logs.redactor.redact_query(q))),
+        request_body: Set(logs.logged_body(&request_body)),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    };
+
+    let response = next.run(Request::from_parts(parts, body)).await;
+    let (parts, body) = response.into_parts();
+    let (response_body, body) = match buffer(body, |bytes| axum::body::boxed(Body::from(bytes))).await {
+        Ok(buffered) => buffered,
+        Err(e) => {
+            warn!("Failed to buffer response body for the developer log: {}", e);
+            return Response::from_parts(parts, axum::body::boxed(Body::empty()));
+        }
+    };
+
+    row.status = Set(parts.status.as_u16() as i32);
+    row.latency_ms = Set(started.elapsed().as_millis() as i64);
+    row.response_body = Set(logs.logged_body(&response_body));
+    logs.enqueue(LogRecord::Request(row));
+
+    Response::from_parts(parts, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(uri: &str, api_key: Option<&str>) -> Request<()> {
+        let mut builder = Request::builder().uri(uri);
+        if let Some(key) = api_key {
+            builder = builder.header(crate::auth::service_accounts::API_KEY_HEADER, key);
+        }
+        builder.body(()).unwrap()
+    }
+
+    fn authenticated(mut req: Request<()>) -> Request<()> {
+        req.extensions_mut().insert(Claims {
+            sub: "sa".to_string(),
+            exp: 0,
+            iss: String::new(),
+            aud: String::new(),
+            role: "service_account".to_string(),
+            permissions: None,
+            actor_type: ActorType::ServiceAccount,
+            tenant_id: None,
+        });
+        req
+    }
+
+    #[test]
+    fn test_only_api_key_requests_outside_the_console_are_recorded() {
+        let recorded = |uri, key| should_record(&authenticated(request(uri, key)));
+        assert_eq!(recorded("/api/v1/orders", Some("ssk_ab12cd34_secret")).as_deref(), Some("ab12cd34"));
+        assert_eq!(recorded("/api/v1/orders", None), None);
+        assert_eq!(recorded("/api/v1/developer/logs", Some("ssk_ab12cd34_secret")), None);
+        assert_eq!(recorded("/api/v1/orders", Some("garbage")), None);
+        // Keys that failed authentication are not recorded
+        assert_eq!(should_record(&request("/api/v1/orders", Some("ssk_ab12cd34_secret"))), None);
+    }
+
+    #[test]
+    fn test_bodies_are_redacted_before_truncation() {
+        let redactor = Redactor::default();
+        let body = br#"{"email":"jane@example.com","card":"4111 1111 1111 1111"}"#;
+        let logged = truncated_body(&redactor, body, 4096);
+        assert!(!logged.contains("jane@example.com"));
+        assert!(!logged.contains("4111 1111 1111 1111"));
+        assert!(logged.contains("1111"));
+
+        let logged = truncated_body(&redactor, "é".repeat(10).as_bytes(), 5);
+        assert_eq!(logged, "éé…[16 more bytes]");
+        assert_eq!(truncated_body(&redactor, b"", 5), "");
+        assert_eq!(truncated_body(&redactor, &vec![b'a'; MAX_REDACTED_BYTES + 1], 5), format!("[{} bytes]", MAX_REDACTED_BYTES + 1));
+    }
+
+    #[test]
+    fn test_migration_passes_migration_checks() {
+        let sql = include_str!("../../migrations/20261016140000_developer_logs.sql");
+        assert_eq!(crate::db::migration_safety::analyze("developer_logs.sql", sql).violations().count(), 0);
+    }
+}
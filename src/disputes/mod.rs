@@ -0,0 +1,709 @@
+// disputes/mod.rs
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use hmac::{Hmac, Mac};
+use rust_decimal::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, FromQueryResult,
+    PaginatorTrait, QueryFilter, QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::{collections::BTreeMap, sync::Arc};
+use thiserror::Error;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::config::secrets::{SecretStore, STRIPE_WEBHOOK_SECRET};
+use crate::db::dialect;
+use crate::events::{Event, EventSender};
+use crate::models::{
+    dispute::{self, DisputeStatus, Entity as Dispute},
+    dispute_event::{self, Entity as DisputeEvent},
+    payment_authorization::{self, Entity as PaymentAuthorization},
+};
+use crate::payments::from_minor_units;
+use crate::utils::pagination::PaginationParams;
+
+type HmacSha256 = Hmac<Sha256>;
+
+pub const STRIPE_SIGNATURE_HEADER: &str = "Stripe-Signature";
+
+/// Dispute settings, loaded from the `disputes` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct DisputesConfig {
+    /// Run the evidence deadline monitor.
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+
+    /// Environment variable holding the Stripe webhook signing secret, used when the
+    /// secret store has no `stripe_webhook_secret`.
+    #[serde(default = "default_webhook_secret_env")]
+    pub stripe_webhook_secret_env: String,
+
+    /// Maximum age in seconds of a signed webhook.
+    #[serde(default = "default_signature_tolerance_secs")]
+    pub signature_tolerance_secs: i64,
+
+    /// Disputes still needing a response this many hours before their deadline are flagged.
+    #[serde(default = "default_deadline_warning_hours")]
+    pub deadline_warning_hours: i64,
+
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_webhook_secret_env() -> String {
+    "STRIPE_WEBHOOK_SECRET".to_string()
+}
+
+fn default_signature_tolerance_secs() -> i64 {
+    300
+}
+
+fn default_deadline_warning_hours() -> i64 {
+    72
+}
+
+fn default_interval_secs() -> u64 {
+    3600
+}
+
+impl Default for DisputesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            stripe_webhook_secret_env: default_webhook_secret_env(),
+            signature_tolerance_secs: default_signature_tolerance_secs(),
+            deadline_warning_hours: default_deadline_warning_hours(),
+            interval_secs: default_interval_secs(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum DisputeError {
+    #[error("Webhook signature rejected: {0}")]
+    InvalidSignature(String),
+
+    #[error("Dispute webhooks are not configured: {0}")]
+    Misconfigured(String),
+
+    #[error("{0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for DisputeError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            DisputeError::InvalidSignature(_) => (StatusCode::UNAUTHORIZED, "invalid_signature"),
+            DisputeError::Misconfigured(_) => (StatusCode::SERVICE_UNAVAILABLE, "disputes_misconfigured"),
+            DisputeError::NotFound(_) => (StatusCode::NOT_FOUND, "not_found"),
+            DisputeError::Invalid(_) => (StatusCode::BAD_REQUEST, "invalid_request"),
+            DisputeError::Database(e) => {
+                error!("Dispute query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "dispute_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// Checks a `Stripe-Signature` header (`t=<unix>,v1=<hex>,...`) against the raw payload.
+pub fn verify_stripe_signature(
+    payload: &[u8],
+    header: &str,
+    secret: &str,
+    now: DateTime<Utc>,
+    tolerance_secs: i64,
+) -> Result<(), DisputeError> {
+    let mut timestamp = None;
+    let mut signatures = Vec::new();
+    for part in header.split(',') {
+        match part.trim().split_once('=') {
+            Some(("t", value)) => timestamp = value.parse::<i64>().ok(),
+            Some(("v1", value)) => signatures.push(value),
+            _ => {}
+        }
+    }
+    let timestamp = timestamp.ok_or_else(|| DisputeError::InvalidSignature("missing timestamp".to_string()))?;
+    if (now.timestamp() - timestamp).abs() > tolerance_secs {
+        return Err(DisputeError::InvalidSignature("timestamp outside tolerance".to_string()));
+    }
+    let matches = signatures.iter().filter_map(|s| hex::decode(s).ok()).any(|signature| {
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+        mac.update(timestamp.to_string().as_bytes());
+        mac.update(b".");
+        mac.update(payload);
+        mac.verify_slice(&signature).is_ok()
+    });
+    if matches {
+        Ok(())
+    } else {
+        Err(DisputeError::InvalidSignature("signature mismatch".to_string()))
+    }
+}
+
+/// A dispute as reported by a provider, normalized.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisputeNotice {
+    pub provider: &'static str,
+    pub external_id: String,
+    pub payment_ref: String,
+    pub amount: Decimal,
+    pub currency: String,
+    pub reason: String,
+    pub status: DisputeStatus,
+    pub is_inquiry: bool,
+    pub evidence_due_by: Option<DateTime<Utc>>,
+}
+
+/// Reads a Stripe `charge.dispute.*` event. Other event types yield `None`.
+pub fn parse_stripe_event(event: &Value) -> Result<Option<DisputeNotice>, DisputeError> {
+    let kind = event["type"].as_str().unwrap_or_default();
+    if !kind.starts_with("charge.dispute.") {
+        return Ok(None);
+    }
+    let object = &event["data"]["object"];
+    let field = |name: &str| {
+        object[name]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| DisputeError::Invalid(format!("Dispute event is missing {}", name)))
+    };
+    let (status, is_inquiry) = match field("status")?.as_str() {
+        "warning_needs_response" => (DisputeStatus::NeedsResponse, true),
+        "warning_under_review" => (DisputeStatus::UnderReview, true),
+        // The inquiry closed without becoming a chargeback
+        "warning_closed" => (DisputeStatus::Won, true),
+        "needs_response" => (DisputeStatus::NeedsResponse, false),
+        "under_review" => (DisputeStatus::UnderReview, false),
+        "won" => (DisputeStatus::Won, false),
+        "lost" => (DisputeStatus::Lost, false),
+        "charge_refunded" => (DisputeStatus::Accepted, false),
+        other => return Err(DisputeError::Invalid(format!("Unknown dispute status: {}", other))),
+    };
+    let currency = field("currency")?.to_uppercase();
+    let amount = object["amount"]
+        .as_i64()
+        .ok_or_else(|| DisputeError::Invalid("Dispute event is missing amount".to_string()))?;
+    let payment_ref = object["payment_intent"]
+        .as_str()
+        .or_else(|| object["charge"].as_str())
+        .ok_or_else(|| DisputeError::Invalid("Dispute event has no charge".to_string()))?;
+    Ok(Some(DisputeNotice {
+        provider: "stripe",
+        external_id: field("id")?,
+        payment_ref: payment_ref.to_string(),
+        amount: from_minor_units(amount, &currency),
+        currency,
+        reason: object["reason"].as_str().unwrap_or("general").to_string(),
+        status,
+        is_inquiry,
+        evidence_due_by: object["evidence_details"]["due_by"]
+            .as_i64()
+            .and_then(|due_by| Utc.timestamp_opt(due_by, 0).single()),
+    }))
+}
+
+/// Status changes an operator may make; providers may report any status.
+pub fn can_transition(from: DisputeStatus, to: DisputeStatus) -> bool {
+    matches!(
+        (from, to),
+        (DisputeStatus::NeedsResponse, DisputeStatus::UnderReview)
+            | (DisputeStatus::NeedsResponse, DisputeStatus::Accepted)
+            | (DisputeStatus::UnderReview, DisputeStatus::Won)
+            | (DisputeStatus::UnderReview, DisputeStatus::Lost)
+    )
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Evidence {
+    pub summary: String,
+    /// Links to receipts, tracking confirmations, correspondence and the like.
+    #[serde(default)]
+    pub documents: Vec<String>,
+    /// Provider-specific evidence fields, e.g. Stripe's `shipping_tracking_number`.
+    #[serde(default)]
+    pub fields: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DisputeFilter {
+    pub status: Option<DisputeStatus>,
+    pub order_id: Option<Uuid>,
+    pub reason: Option<String>,
+    /// Only disputes needing a response due within this many hours.
+    pub due_within_hours: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DisputeWithHistory {
+    #[serde(flatten)]
+    pub dispute: dispute::Model,
+    pub history: Vec<dispute_event::Model>,
+}
+
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct DisputeTally {
+    pub status: DisputeStatus,
+    pub reason: String,
+    pub count: i64,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ReasonStats {
+    pub reason: String,
+    pub count: i64,
+    pub won: i64,
+    pub lost: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DisputeAnalytics {
+    pub total: i64,
+    pub open: i64,
+    pub won: i64,
+    pub lost: i64,
+    pub accepted: i64,
+    /// Won over decided (won plus lost) disputes; `None` until one is decided.
+    pub win_rate: Option<Decimal>,
+    #[serde(with = "crate::money::amount")]
+    pub disputed_amount: Decimal,
+    #[serde(with = "crate::money::amount")]
+    pub lost_amount: Decimal,
+    pub by_reason: Vec<ReasonStats>,
+}
+
+/// Rolls per-status, per-reason tallies up into win/loss figures. Accepted disputes count
+/// toward `lost_amount` since the funds are gone either way.
+pub fn summarize(tallies: &[DisputeTally]) -> DisputeAnalytics {
+    let mut analytics = DisputeAnalytics::default();
+    let mut reasons: BTreeMap<&str, ReasonStats> = BTreeMap::new();
+    for tally in tallies {
+        let reason = reasons.entry(tally.reason.as_str()).or_insert_with(|| ReasonStats {
+            reason: tally.reason.clone(),
+            ..Default::default()
+        });
+        reason.count += tally.count;
+        analytics.total += tally.count;
+        analytics.disputed_amount += tally.amount;
+        match tally.status {
+            DisputeStatus::Won => {
+                analytics.won += tally.count;
+                reason.won += tally.count;
+            }
+            DisputeStatus::Lost => {
+                analytics.lost += tally.count;
+                analytics.lost_amount += tally.amount;
+                reason.lost += tally.count;
+            }
+            DisputeStatus::Accepted => {
+                analytics.accepted += tally.count;
+                analytics.lost_amount += tally.amount;
+            }
+            DisputeStatus::NeedsResponse | DisputeStatus::UnderReview => analytics.open += tally.count,
+        }
+    }
+    let decided = analytics.won + analytics.lost;
+    analytics.win_rate = (decided > 0).then(|| (Decimal::from(analytics.won) / Decimal::from(decided)).round_dp(4));
+    analytics.by_reason = reasons.into_values().collect();
+    analytics.by_reason.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.reason.cmp(&b.reason)));
+    analytics
+}
+
+const TALLY_SQL: &str = r#"
+SELECT status, reason, COUNT(*) AS count, COALESCE(SUM(amount), 0) AS amount
+FROM disputes
+WHERE created_at >= $1 AND created_at < $2
+GROUP BY status, reason
+"#;
+
+/// Records disputes reported by payment providers, links them to orders through the
+/// payment authorization, and tracks their evidence deadlines and outcomes.
+pub struct DisputeService {
+    db: Arc<DatabaseConnection>,
+    events: EventSender,
+    secrets: SecretStore,
+    config: DisputesConfig,
+}
+
+impl DisputeService {
+    pub fn new(db: Arc<DatabaseConnection>, events: EventSender, secrets: SecretStore, config: DisputesConfig) -> Self {
+        Self { db, events, secrets, config }
+    }
+
+    /// The Stripe webhook signing secret; the secret store wins so rotations apply without a restart.
+    async fn stripe_webhook_secret(&self) -> Result<String, DisputeError> {
+        if let Some(secret) = self.secrets.get(STRIPE_WEBHOOK_SECRET) {
+            return Ok(secret.expose().to_string());
+        }
+        std::env::var(&self.config.stripe_webhook_secret_env)
+            .map_err(|_| DisputeError::Misconfigured(format!("{} is not set", self.config.stripe_webhook_secret_env)))
+    }
+
+    /// Verifies and ingests a Stripe webhook. Returns `None` for event types other than disputes.
+    pub async fn stripe_webhook(&self, payload: &[u8], signature: &str) -> Result<Option<dispute::Model>, DisputeError> {
+        let secret = self.stripe_webhook_secret().await?;
+        verify_stripe_signature(payload, signature, &secret, Utc::now(), self.config.signature_tolerance_secs)?;
+        let event: Value =
+            serde_json::from_slice(payload).map_err(|e| DisputeError::Invalid(format!("Malformed webhook: {}", e)))?;
+        match parse_stripe_event(&event)? {
+            Some(notice) => self.ingest(notice).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Creates or updates the dispute a provider reported. Providers are authoritative, so
+    /// their status is applied whatever the current one.
+    pub async fn ingest(&self, notice: DisputeNotice) -> Result<dispute::Model, DisputeError> {
+        let source = format!("webhook:{}", notice.provider);
+        let existing = Dispute::find()
+            .filter(dispute::Column::ExternalId.eq(notice.external_id.as_str()))
+            .one(self.db.as_ref())
+            .await?;
+        let Some(existing) = existing else {
+            return self.open(notice, &source).await;
+        };
+        let now = Utc::now();
+        let previous = existing.status;
+        let mut active: dispute::ActiveModel = existing.into();
+        active.amount = Set(notice.amount);
+        active.reason = Set(notice.reason);
+        active.is_inquiry = Set(notice.is_inquiry);
+        active.evidence_due_by = Set(notice.evidence_due_by);
+        active.updated_at = Set(now);
+        self.apply_status(active, previous, notice.status, &source, None).await
+    }
+
+    async fn open(&self, notice: DisputeNotice, source: &str) -> Result<dispute::Model, DisputeError> {
+        let authorization = PaymentAuthorization::find()
+            .filter(payment_authorization::Column::AuthorizationRef.eq(notice.payment_ref.as_str()))
+            .one(self.db.as_ref())
+            .await?;
+        if authorization.is_none() {
+            warn!(dispute = %notice.external_id, payment = %notice.payment_ref, "Dispute for an unknown payment");
+        }
+        let now = Utc::now();
+        let dispute = dispute::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            provider: Set(notice.provider.to_string()),
+            external_id: Set(notice.external_id),
+            payment_ref: Set(notice.payment_ref),
+            order_id: Set(authorization.as_ref().map(|a| a.order_id)),
+            authorization_id: Set(authorization.as_ref().map(|a| a.id)),
+            amount: Set(notice.amount),
+            currency: Set(notice.currency),
+            reason: Set(notice.reason),
+            status: Set(notice.status),
+            is_inquiry: Set(notice.is_inquiry),
+            evidence_due_by: Set(notice.evidence_due_by),
+            evidence: Set(None),
+            evidence_submitted_at: Set(None),
+            deadline_alerted_at: Set(None),
+            closed_at: Set(notice.status.is_closed().then_some(now)),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+        self.record_event(dispute.id, None, dispute.status, source, None).await?;
+        info!(dispute_id = %dispute.id, order_id = ?dispute.order_id, amount = %dispute.amount, "Dispute opened");
+        let _ = self.events.send(Event::DisputeOpened {
+            dispute_id: dispute.id,
+            order_id: dispute.order_id,
+            amount: dispute.amount,
+        });
+        Ok(dispute)
+    }
+
+    async fn apply_status(
+        &self,
+        mut active: dispute::ActiveModel,
+        from: DisputeStatus,
+        to: DisputeStatus,
+        source: &str,
+        note: Option<String>,
+    ) -> Result<dispute::Model, DisputeError> {
+        if from != to {
+            active.status = Set(to);
+            if to.is_closed() {
+                active.closed_at = Set(Some(Utc::now()));
+            }
+        }
+        let dispute = active.update(self.db.as_ref()).await?;
+        if from != to {
+            self.record_event(dispute.id, Some(from), to, source, note).await?;
+            if to.is_closed() {
+                info!(dispute_id = %dispute.id, status = ?to, "Dispute closed");
+                let _ = self.events.send(Event::DisputeClosed {
+                    dispute_id: dispute.id,
+                    order_id: dispute.order_id,
+                    won: to == DisputeStatus::Won,
+                    amount: dispute.amount,
+                });
+            }
+        }
+        Ok(dispute)
+    }
+
+    async fn record_event(
+        &self,
+        dispute_id: Uuid,
+        from: Option<DisputeStatus>,
+        to: DisputeStatus,
+        source: &str,
+        note: Option<String>,
+    ) -> Result<(), DisputeError> {
+        dispute_event::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            dispute_id: Set(dispute_id),
+            from_status: Set(from),
+            to_status: Set(to),
+            source: Set(source.to_string()),
+            note: Set(note),
+            created_at: Set(Utc::now()),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+        Ok(())
+    }
+
+    async fn find(&self, id: Uuid) -> Result<dispute::Model, DisputeError> {
+        Dispute::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| DisputeError::NotFound(format!("Dispute not found: {}", id)))
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<DisputeWithHistory, DisputeError> {
+        let dispute = self.find(id).await?;
+        let history = DisputeEvent::find()
+            .filter(dispute_event::Column::DisputeId.eq(id))
+            .order_by_asc(dispute_event::Column::CreatedAt)
+            .all(self.db.as_ref())
+            .await?;
+        Ok(DisputeWithHistory { dispute, history })
+    }
+
+    pub async fn list(
+        &self,
+        filter: DisputeFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<dispute::Model>, u64), DisputeError> {
+        let mut condition = Condition::all();
+        if let Some(status) = filter.status {
+            condition = condition.add(dispute::Column::Status.eq(status));
+        }
+        if let Some(order_id) = filter.order_id {
+            condition = condition.add(dispute::Column::OrderId.eq(order_id));
+        }
+        if let Some(reason) = filter.reason {
+            condition = condition.add(dispute::Column::Reason.eq(reason));
+        }
+        if let Some(hours) = filter.due_within_hours {
+            condition = condition
+                .add(dispute::Column::Status.eq(DisputeStatus::NeedsResponse))
+                .add(dispute::Column::EvidenceDueBy.lte(Utc::now() + Duration::hours(hours)));
+        }
+        let paginator = Dispute::find()
+            .filter(condition)
+            .order_by_asc(dispute::Column::EvidenceDueBy)
+            .order_by_desc(dispute::Column::CreatedAt)
+            .paginate(self.db.as_ref(), pagination.limit());
+        let total = paginator.num_items().await?;
+        let items = paginator.fetch_page(pagination.page_index()).await?;
+        Ok((items, total))
+    }
+
+    /// Records the evidence assembled for a dispute and moves it under review. Submitting
+    /// it to the provider's dashboard or API is up to the operator.
+    pub async fn submit_evidence(&self, id: Uuid, evidence: Evidence, actor: &str) -> Result<dispute::Model, DisputeError> {
+        if evidence.summary.trim().is_empty() {
+            return Err(DisputeError::Invalid("Evidence needs a summary".to_string()));
+        }
+        let dispute = self.find(id).await?;
+        if dispute.status != DisputeStatus::NeedsResponse {
+            return Err(DisputeError::Invalid(format!("Dispute is {:?}", dispute.status)));
+        }
+        let now = Utc::now();
+        if dispute.evidence_due_by.map_or(false, |due_by| due_by < now) {
+            return Err(DisputeError::Invalid("The evidence deadline has passed".to_string()));
+        }
+        let previous = dispute.status;
+        let mut active: dispute::ActiveModel = dispute.into();
+        active.evidence = Set(Some(json!({
+            "summary": evidence.summary,
+            "documents": evidence.documents,
+            "fields": evidence.fields,
+        })));
+        active.evidence_submitted_at = Set(Some(now));
+        active.updated_at = Set(now);
+        self.apply_status(active, previous, DisputeStatus::UnderReview, actor, Some("Evidence submitted".to_string()))
+            .await
+    }
+
+    /// Moves a dispute along the workflow by hand, e.g. to accept it or record an outcome
+    /// the provider reported elsewhere.
+    pub async fn transition(
+        &self,
+        id: Uuid,
+        to: DisputeStatus,
+        note: Option<String>,
+        actor: &str,
+    ) -> Result<dispute::Model, DisputeError> {
+        let dispute = self.find(id).await?;
+        if !can_transition(dispute.status, to) {
+            return Err(DisputeError::Invalid(format!("Cannot move a dispute from {:?} to {:?}", dispute.status, to)));
+        }
+        let previous = dispute.status;
+        let mut active: dispute::ActiveModel = dispute.into();
+        active.updated_at = Set(Utc::now());
+        self.apply_status(active, previous, to, actor, note).await
+    }
+
+    /// Win/loss figures for disputes opened in `[from, to]`.
+    pub async fn analytics(&self, from: NaiveDate, to: NaiveDate) -> Result<DisputeAnalytics, DisputeError> {
+        if to < from {
+            return Err(DisputeError::Invalid("`to` is before `from`".to_string()));
+        }
+        let start = from.and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc();
+        let end = to.succ_opt().unwrap_or(to).and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc();
+        let tallies = DisputeTally::find_by_statement(dialect::statement(
+            self.db.as_ref(),
+            TALLY_SQL,
+            [start.into(), end.into()],
+        ))
+        .all(self.db.as_ref())
+        .await?;
+        Ok(summarize(&tallies))
+    }
+
+    /// Flags disputes whose evidence deadline falls within the warning window, once each.
+    pub async fn flag_due(&self) -> Result<usize, DisputeError> {
+        let now = Utc::now();
+        let due = Dispute::find()
+            .filter(dispute::Column::Status.eq(DisputeStatus::NeedsResponse))
+            .filter(dispute::Column::DeadlineAlertedAt.is_null())
+            .filter(dispute::Column::EvidenceDueBy.lte(now + Duration::hours(self.config.deadline_warning_hours)))
+            .all(self.db.as_ref())
+            .await?;
+        let flagged = due.len();
+        for dispute in due {
+            let due_by = dispute.evidence_due_by.unwrap_or(now);
+            warn!(dispute_id = %dispute.id, %due_by, "Dispute evidence due soon");
+            let _ = self.events.send(Event::DisputeEvidenceDue { dispute_id: dispute.id, due_by });
+            let mut active: dispute::ActiveModel = dispute.into();
+            active.deadline_alerted_at = Set(Some(now));
+            active.update(self.db.as_ref()).await?;
+        }
+        Ok(flagged)
+    }
+}
+
+pub fn spawn_deadline_monitor(service: Arc<DisputeService>, interval: std::time::Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = service.flag_due().await {
+                error!("Dispute deadline check failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn sign(payload: &str, secret: &str, timestamp: i64) -> String {
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(format!("{}.{}", timestamp, payload).as_bytes());
+        format!("t={},v1={}", timestamp, hex::encode(mac.finalize().into_bytes()))
+    }
+
+    #[test]
+    fn test_stripe_signature_verification() {
+        let now = Utc.with_ymd_and_hms(2024, 7, 1, 12, 0, 0).unwrap();
+        let payload = r#"{"type":"charge.dispute.created"}"#;
+        let header = sign(payload, "whsec_test", now.timestamp());
+        assert!(verify_stripe_signature(payload.as_bytes(), &header, "whsec_test", now, 300).is_ok());
+        assert!(verify_stripe_signature(b"{}", &header, "whsec_test", now, 300).is_err());
+        assert!(verify_stripe_signature(payload.as_bytes(), &header, "whsec_other", now, 300).is_err());
+        let later = now + Duration::minutes(10);
+        assert!(verify_stripe_signature(payload.as_bytes(), &header, "whsec_test", later, 300).is_err());
+    }
+
+    #[test]
+    fn test_parse_stripe_dispute() {
+        let event = json!({
+            "type": "charge.dispute.created",
+            "data": { "object": {
+                "id": "dp_123",
+                "amount": 4999,
+                "currency": "usd",
+                "reason": "product_not_received",
+                "status": "needs_response",
+                "charge": "ch_1",
+                "payment_intent": "pi_1",
+                "evidence_details": { "due_by": 1720000000 }
+            }}
+        });
+        let notice = parse_stripe_event(&event).unwrap().unwrap();
+        assert_eq!(notice.external_id, "dp_123");
+        assert_eq!(notice.payment_ref, "pi_1");
+        assert_eq!(notice.amount, dec!(49.99));
+        assert_eq!(notice.status, DisputeStatus::NeedsResponse);
+        assert!(!notice.is_inquiry);
+        assert_eq!(notice.evidence_due_by, Utc.timestamp_opt(1720000000, 0).single());
+
+        assert_eq!(parse_stripe_event(&json!({ "type": "charge.succeeded" })).unwrap(), None);
+    }
+
+    #[test]
+    fn test_workflow_transitions() {
+        assert!(can_transition(DisputeStatus::NeedsResponse, DisputeStatus::UnderReview));
+        assert!(can_transition(DisputeStatus::UnderReview, DisputeStatus::Lost));
+        assert!(!can_transition(DisputeStatus::Won, DisputeStatus::Lost));
+        assert!(!can_transition(DisputeStatus::NeedsResponse, DisputeStatus::Won));
+    }
+
+    #[test]
+    fn test_win_rate_counts_decided_disputes() {
+        let tally = |status, reason: &str, count, amount| DisputeTally { status, reason: reason.to_string(), count, amount };
+        let analytics = summarize(&[
+            tally(DisputeStatus::Won, "fraudulent", 3, dec!(300)),
+            tally(DisputeStatus::Lost, "fraudulent", 1, dec!(80)),
+            tally(DisputeStatus::Accepted, "duplicate", 1, dec!(20)),
+            tally(DisputeStatus::NeedsResponse, "product_not_received", 2, dec!(150)),
+        ]);
+        assert_eq!(analytics.total, 7);
+        assert_eq!(analytics.open, 2);
+        assert_eq!(analytics.win_rate, Some(dec!(0.75)));
+        assert_eq!(analytics.lost_amount, dec!(100));
+        assert_eq!(analytics.disputed_amount, dec!(550));
+        assert_eq!(analytics.by_reason[0].reason, "fraudulent");
+        assert_eq!(analytics.by_reason[0].won, 3);
+    }
+}
@@ -0,0 +1,315 @@
+// dropship/mod.rs
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::deadline;
+use crate::models::dropship_source;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ForwardAdapterKind {
+    /// No electronic forwarding; the supplier picks new orders up from the dropship API.
+    Manual,
+    /// Posts the purchase order and ship-to address as JSON to the supplier's endpoint.
+    Http,
+}
+
+/// How dropship orders are forwarded to one supplier.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SupplierEndpoint {
+    pub supplier_id: Uuid,
+    pub adapter: ForwardAdapterKind,
+    /// Order intake URL, for the `http` adapter.
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    /// Environment variable holding the bearer token for the `http` adapter.
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+}
+
+/// Dropship settings, loaded from the `dropship` section of the config. Suppliers without
+/// an endpoint are forwarded manually.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct DropshipConfig {
+    #[serde(default)]
+    pub suppliers: Vec<SupplierEndpoint>,
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum DropshipError {
+    #[error("Supplier adapter is misconfigured: {0}")]
+    Misconfigured(String),
+
+    /// The supplier refused the order, e.g. a discontinued part.
+    #[error("Order rejected by supplier: {0}")]
+    Rejected(String),
+
+    #[error("Supplier unavailable: {0}")]
+    Unavailable(String),
+}
+
+/// One order line shipped by the supplier.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ForwardLine {
+    pub sku: String,
+    /// The supplier's part number, when it differs from ours.
+    pub supplier_sku: Option<String>,
+    pub quantity: i32,
+}
+
+/// What the supplier needs to ship to the customer.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ForwardRequest<'a> {
+    pub po_number: &'a str,
+    pub order_number: &'a str,
+    pub customer_name: &'a str,
+    pub customer_email: &'a str,
+    pub ship_to: &'a str,
+    pub lines: &'a [ForwardLine],
+}
+
+/// What a supplier returned for a forwarded order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ForwardReceipt {
+    /// The supplier's order reference, if it issues one.
+    pub reference: Option<String>,
+}
+
+/// Hands dropship orders to a supplier.
+#[async_trait]
+pub trait SupplierForwarder: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn forward(&self, request: &ForwardRequest<'_>) -> Result<ForwardReceipt, DropshipError>;
+}
+
+/// For suppliers that read new orders from the dropship API themselves.
+pub struct Manual;
+
+#[async_trait]
+impl SupplierForwarder for Manual {
+    fn name(&self) -> &'static str {
+        "manual"
+    }
+
+    async fn forward(&self, _request: &ForwardRequest<'_>) -> Result<ForwardReceipt, DropshipError> {
+        Ok(ForwardReceipt { reference: None })
+    }
+}
+
+pub struct HttpForwarder {
+    client: reqwest::Client,
+    endpoint: String,
+    api_key: Option<String>,
+}
+
+impl HttpForwarder {
+    pub fn from_endpoint(supplier: &SupplierEndpoint) -> Result<Self, DropshipError> {
+        let endpoint = supplier.endpoint.clone().ok_or_else(|| {
+            DropshipError::Misconfigured(format!("Order endpoint of supplier {} is not set", supplier.supplier_id))
+        })?;
+        let api_key = match &supplier.api_key_env {
+            Some(var) => Some(
+                std::env::var(var).map_err(|_| DropshipError::Misconfigured(format!("{} is not set", var)))?,
+            ),
+            None => None,
+        };
+        Ok(Self { client: reqwest::Client::new(), endpoint, api_key })
+    }
+}
+
+#[async_trait]
+impl SupplierForwarder for HttpForwarder {
+    fn name(&self) -> &'static str {
+        "http"
+    }
+
+    async fn forward(&self, request: &ForwardRequest<'_>) -> Result<ForwardReceipt, DropshipError> {
+        #[derive(Deserialize)]
+        struct Receipt {
+            #[serde(default)]
+            reference: Option<String>,
+        }
+
+        let mut call = self.client.post(&self.endpoint).json(&json!({
+            "po_number": request.po_number,
+            "order_number": request.order_number,
+            "ship_to": {
+                "name": request.customer_name,
+                "email": request.customer_email,
+                "address": request.ship_to,
+            },
+            "lines": request.lines,
+        }));
+        if let Some(key) = &self.api_key {
+            call = call.bearer_auth(key);
+        }
+        let response = deadline::bounded(call).send().await.map_err(|e| DropshipError::Unavailable(e.to_string()))?;
+        let status = response.status();
+        if status.is_client_error() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(DropshipError::Rejected(format!("{}: {}", status, body)));
+        }
+        if !status.is_success() {
+            return Err(DropshipError::Unavailable(status.to_string()));
+        }
+        let receipt: Receipt = response.json().await.map_err(|e| DropshipError::Unavailable(e.to_string()))?;
+        Ok(ForwardReceipt { reference: receipt.reference })
+    }
+}
+
+/// Picks the forwarder for each supplier.
+pub struct SupplierRegistry {
+    forwarders: HashMap<Uuid, Arc<dyn SupplierForwarder>>,
+}
+
+impl SupplierRegistry {
+    pub fn from_config(config: &DropshipConfig) -> Result<Self, DropshipError> {
+        let forwarders = config
+            .suppliers
+            .iter()
+            .map(|supplier| {
+                let forwarder: Arc<dyn SupplierForwarder> = match supplier.adapter {
+                    ForwardAdapterKind::Manual => Arc::new(Manual),
+                    ForwardAdapterKind::Http => Arc::new(HttpForwarder::from_endpoint(supplier)?),
+                };
+                Ok((supplier.supplier_id, forwarder))
+            })
+            .collect::<Result<_, DropshipError>>()?;
+        Ok(Self { forwarders })
+    }
+
+    pub fn forwarder(&self, supplier_id: Uuid) -> Arc<dyn SupplierForwarder> {
+        self.forwarders.get(&supplier_id).cloned().unwrap_or_else(|| Arc::new(Manual))
+    }
+}
+
+/// Units of one SKU flagged for dropship on a sales order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DropshipLine {
+    pub sku: String,
+    pub quantity: i32,
+}
+
+/// Groups dropship lines by the supplier that ships them, in a stable order. SKUs without
+/// an active source are returned as the error.
+pub fn route_lines(
+    lines: &[DropshipLine],
+    sources: &HashMap<String, dropship_source::Model>,
+) -> Result<BTreeMap<Uuid, Vec<(DropshipLine, dropship_source::Model)>>, Vec<String>> {
+    let mut routed: BTreeMap<Uuid, Vec<(DropshipLine, dropship_source::Model)>> = BTreeMap::new();
+    let mut unsourced = Vec::new();
+    for line in lines {
+        match sources.get(&line.sku).filter(|s| s.active) {
+            Some(source) => routed.entry(source.supplier_id).or_default().push((line.clone(), source.clone())),
+            None => unsourced.push(line.sku.clone()),
+        }
+    }
+    if unsourced.is_empty() {
+        Ok(routed)
+    } else {
+        Err(unsourced)
+    }
+}
+
+/// One package on its way to the customer, whoever ships it.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrackedPackage {
+    /// `warehouse`, or `supplier` for dropship packages.
+    pub shipped_by: String,
+    pub carrier: String,
+    pub tracking_number: String,
+    pub tracking_url: Option<String>,
+    pub status: String,
+    pub shipped_at: Option<DateTime<Utc>>,
+}
+
+/// Every package of an order, earliest shipped first; packages not shipped yet come last.
+pub fn combine_tracking(mut packages: Vec<TrackedPackage>) -> Vec<TrackedPackage> {
+    packages.sort_by(|a, b| match (a.shipped_at, b.shipped_at) {
+        (Some(a), Some(b)) => a.cmp(&b),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.tracking_number.cmp(&b.tracking_number),
+    });
+    packages.dedup_by(|a, b| a.tracking_number == b.tracking_number && a.carrier == b.carrier);
+    packages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    fn source(sku: &str, supplier_id: Uuid, active: bool) -> dropship_source::Model {
+        let at = Utc.with_ymd_and_hms(2026, 10, 1, 12, 0, 0).unwrap();
+        dropship_source::Model {
+            sku: sku.to_string(),
+            supplier_id,
+            supplier_sku: None,
+            unit_cost: dec!(12.50),
+            currency: "USD".to_string(),
+            lead_time_days: 2,
+            active,
+            updated_by: "buyer".to_string(),
+            created_at: at,
+            updated_at: at,
+        }
+    }
+
+    fn line(sku: &str, quantity: i32) -> DropshipLine {
+        DropshipLine { sku: sku.to_string(), quantity }
+    }
+
+    fn package(tracking_number: &str, shipped_hour: Option<u32>) -> TrackedPackage {
+        TrackedPackage {
+            shipped_by: "supplier".to_string(),
+            carrier: "ups".to_string(),
+            tracking_number: tracking_number.to_string(),
+            tracking_url: None,
+            status: "shipped".to_string(),
+            shipped_at: shipped_hour.map(|h| Utc.with_ymd_and_hms(2026, 10, 2, h, 0, 0).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_lines_are_grouped_by_supplier() {
+        let (acme, globex) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let sources = HashMap::from([
+            ("CHAIR".to_string(), source("CHAIR", acme, true)),
+            ("DESK".to_string(), source("DESK", globex, true)),
+            ("LAMP".to_string(), source("LAMP", acme, true)),
+        ]);
+        let routed = route_lines(&[line("CHAIR", 2), line("DESK", 1), line("LAMP", 4)], &sources).unwrap();
+        assert_eq!(routed.len(), 2);
+        assert_eq!(routed[&acme].iter().map(|(l, _)| l.sku.as_str()).collect::<Vec<_>>(), ["CHAIR", "LAMP"]);
+        assert_eq!(routed[&globex][0].0.quantity, 1);
+    }
+
+    #[test]
+    fn test_unsourced_and_inactive_skus_are_reported() {
+        let sources = HashMap::from([("DESK".to_string(), source("DESK", Uuid::from_u128(2), false))]);
+        let unsourced = route_lines(&[line("DESK", 1), line("SOFA", 1)], &sources).unwrap_err();
+        assert_eq!(unsourced, ["DESK", "SOFA"]);
+    }
+
+    #[test]
+    fn test_combined_tracking_orders_by_ship_time() {
+        let packages = combine_tracking(vec![
+            package("1Z3", None),
+            package("1Z2", Some(15)),
+            package("1Z1", Some(9)),
+            package("1Z2", Some(15)),
+        ]);
+        let numbers: Vec<&str> = packages.iter().map(|p| p.tracking_number.as_str()).collect();
+        assert_eq!(numbers, ["1Z1", "1Z2", "1Z3"]);
+    }
+}
@@ -0,0 +1,709 @@
+// dunning/mod.rs
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use lazy_static::lazy_static;
+use prometheus::IntCounterVec;
+use rust_decimal::Decimal;
+use sea_orm::{
+    sea_query::{LockBehavior, LockType},
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    QuerySelect, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{collections::BTreeMap, sync::Arc};
+use thiserror::Error;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::events::{Event, EventSender};
+use crate::i18n::Locale;
+use crate::models::{
+    dunning_case::{self, DunningStatus, Entity as DunningCase},
+    subscription::{self, Entity as Subscription, SubscriptionStatus},
+    vault_token::{self, TokenUsage},
+};
+use crate::notifications::{create_billing_notification, NotificationService};
+use crate::payments::{ChargeRequest, GatewayError, PaymentGateway};
+use crate::services::payment_vault::{check_redemption, PaymentVaultService, Redemption};
+use crate::shutdown::Worker;
+use crate::utils::pagination::PaginationParams;
+
+lazy_static! {
+    static ref DUNNING_RETRIES: IntCounterVec =
+        IntCounterVec::new(
+            "dunning_retries_total",
+            "Retried subscription charges, by outcome",
+            &["outcome"]
+        )
+        .expect("metric can be created");
+}
+
+/// How long to wait before retrying when the gateway itself was unreachable. Such
+/// attempts don't count against the retry curve.
+const UNAVAILABLE_RETRY_MINUTES: i64 = 15;
+
+/// How long a claimed case is hidden from other workers while its retry runs. A worker
+/// that dies mid-retry leaves the case to be picked up again after this, with the same
+/// idempotency key.
+const CLAIM_MINUTES: i64 = 10;
+
+/// Points in the dunning process at which the subscriber is notified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DunningStage {
+    /// The original recurring charge failed.
+    PaymentFailed,
+    RetryFailed,
+    /// A retry failed and only one attempt is left before cancellation.
+    FinalNotice,
+    Canceled,
+    Recovered,
+}
+
+impl DunningStage {
+    pub fn default_template(self) -> &'static str {
+        match self {
+            DunningStage::PaymentFailed => "notifications.dunning.payment_failed",
+            DunningStage::RetryFailed => "notifications.dunning.retry_failed",
+            DunningStage::FinalNotice => "notifications.dunning.final_notice",
+            DunningStage::Canceled => "notifications.dunning.canceled",
+            DunningStage::Recovered => "notifications.dunning.recovered",
+        }
+    }
+}
+
+/// Dunning settings, loaded from the `dunning` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct DunningConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Hours to wait after each failed attempt before the next retry. The subscription is
+    /// canceled when a charge fails with no entry left, so `[24, 72, 168]` allows three retries.
+    #[serde(default = "default_retry_hours")]
+    pub retry_hours: Vec<i64>,
+
+    /// Message catalog key per stage, overriding `DunningStage::default_template`.
+    #[serde(default)]
+    pub templates: BTreeMap<DunningStage, String>,
+
+    /// Locale of subscriber notifications, e.g. `de-DE`.
+    #[serde(default = "default_locale")]
+    pub notification_locale: String,
+
+    /// Seconds between checks for due retries.
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+
+    #[serde(default = "default_batch_size")]
+    pub batch_size: u64,
+}
+
+fn default_retry_hours() -> Vec<i64> {
+    vec![24, 72, 120, 168]
+}
+
+fn default_locale() -> String {
+    Locale::EnUs.tag().to_string()
+}
+
+fn default_interval_secs() -> u64 {
+    300
+}
+
+fn default_batch_size() -> u64 {
+    50
+}
+
+impl Default for DunningConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retry_hours: default_retry_hours(),
+            templates: BTreeMap::new(),
+            notification_locale: default_locale(),
+            interval_secs: default_interval_secs(),
+            batch_size: default_batch_size(),
+        }
+    }
+}
+
+impl DunningConfig {
+    pub fn template(&self, stage: DunningStage) -> &str {
+        self.templates
+            .get(&stage)
+            .map(String::as_str)
+            .unwrap_or_else(|| stage.default_template())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum DunningError {
+    #[error("Subscription billing is disabled")]
+    Disabled,
+
+    #[error("{0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for DunningError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            DunningError::Disabled => (StatusCode::SERVICE_UNAVAILABLE, "dunning_unavailable"),
+            DunningError::NotFound(_) => (StatusCode::NOT_FOUND, "not_found"),
+            DunningError::Invalid(_) => (StatusCode::BAD_REQUEST, "invalid_request"),
+            DunningError::Database(e) => {
+                error!("Dunning query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "dunning_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// When to retry after the `failures`-th failed attempt, or `None` once the curve is used up.
+pub fn next_retry(retry_hours: &[i64], failures: i32, from: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let index = usize::try_from(failures).ok()?.checked_sub(1)?;
+    retry_hours.get(index).map(|hours| from + Duration::hours(*hours))
+}
+
+/// Which notice to send after the `failures`-th failed attempt.
+pub fn stage_after_failure(retry_hours: &[i64], failures: i32) -> DunningStage {
+    let failures = failures.max(1) as usize;
+    if failures > retry_hours.len() {
+        DunningStage::Canceled
+    } else if failures == 1 {
+        DunningStage::PaymentFailed
+    } else if failures == retry_hours.len() {
+        DunningStage::FinalNotice
+    } else {
+        DunningStage::RetryFailed
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct NewSubscription {
+    pub customer_id: Uuid,
+    pub user_id: Option<i32>,
+    #[validate(length(min = 1, max = 100))]
+    pub plan: String,
+    #[validate(custom = "crate::money::validate_positive")]
+    #[serde(with = "crate::money::amount")]
+    pub amount: Decimal,
+    #[validate(length(equal = 3))]
+    pub currency: String,
+    /// Required unless `vault_token` is given.
+    #[serde(default)]
+    #[validate(length(min = 1))]
+    pub gateway_customer_ref: String,
+    /// Required unless `vault_token` is given.
+    #[serde(default)]
+    #[validate(length(min = 1))]
+    pub payment_method_ref: String,
+    /// A reusable payment vault token to bill instead of raw processor references.
+    /// Revoking the token stops further charges.
+    pub vault_token: Option<String>,
+    pub current_period_end: DateTime<Utc>,
+}
+
+/// A failed recurring charge reported by billing or a gateway webhook.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FailedCharge {
+    /// Defaults to the subscription's amount.
+    #[serde(default, with = "crate::money::option_amount")]
+    pub amount: Option<Decimal>,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CaseFilter {
+    pub status: Option<DunningStatus>,
+    pub subscription_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SubscriptionWithDunning {
+    #[serde(flatten)]
+    pub subscription: subscription::Model,
+    pub dunning_cases: Vec<dunning_case::Model>,
+}
+
+/// Retries failed subscription charges along the configured curve, moving subscriptions
+/// to `past_due` on the first failure and to `canceled` when retries run out, and notifies
+/// the subscriber at each stage.
+pub struct DunningService {
+    db: Arc<DatabaseConnection>,
+    events: EventSender,
+    gateway: Arc<dyn PaymentGateway>,
+    notifications: Arc<dyn NotificationService>,
+    vault: Option<Arc<PaymentVaultService>>,
+    config: DunningConfig,
+}
+
+impl DunningService {
+    pub fn new(
+        db: Arc<DatabaseConnection>,
+        events: EventSender,
+        gateway: Arc<dyn PaymentGateway>,
+        notifications: Arc<dyn NotificationService>,
+        config: DunningConfig,
+    ) -> Self {
+        Self { db, events, gateway, notifications, vault: None, config }
+    }
+
+    /// Lets subscriptions be billed through payment vault tokens.
+    pub fn with_vault(mut self, vault: Option<Arc<PaymentVaultService>>) -> Self {
+        self.vault = vault;
+        self
+    }
+
+    /// Resolves a vault token a subscription is to be billed through. Only reusable
+    /// tokens attached to a processor customer qualify, and they must cover `amount`.
+    async fn vault_token(&self, token: &str, amount: Decimal, currency: &str) -> Result<vault_token::Model, DunningError> {
+        let vault = self
+            .vault
+            .as_ref()
+            .ok_or_else(|| DunningError::Invalid("The payment vault is not enabled".to_string()))?;
+        let record = vault.lookup(token).await.map_err(|e| DunningError::Invalid(e.to_string()))?;
+        if record.usage != TokenUsage::Reusable {
+            return Err(DunningError::Invalid("Subscriptions need a reusable vault token".to_string()));
+        }
+        if record.customer_ref.is_none() {
+            return Err(DunningError::Invalid(
+                "The vault token's payment method is not attached to a processor customer".to_string(),
+            ));
+        }
+        let redemption = Redemption {
+            amount,
+            currency: currency.to_string(),
+            checkout_session_id: None,
+            used_by: "subscription".to_string(),
+        };
+        check_redemption(&record, &redemption, Utc::now()).map_err(DunningError::Invalid)?;
+        Ok(record)
+    }
+
+    pub async fn create_subscription(&self, mut input: NewSubscription) -> Result<subscription::Model, DunningError> {
+        let mut vault_token_id = None;
+        if let Some(token) = input.vault_token.take() {
+            let record = self.vault_token(&token, input.amount, &input.currency).await?;
+            vault_token_id = Some(record.id);
+            input.gateway_customer_ref = record.customer_ref.unwrap_or_default();
+            input.payment_method_ref = record.payment_method_ref;
+        }
+        input
+            .validate()
+            .map_err(|e| DunningError::Invalid(format!("Invalid subscription: {}", e)))?;
+        let now = Utc::now();
+        Ok(subscription::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            customer_id: Set(input.customer_id),
+            user_id: Set(input.user_id),
+            plan: Set(input.plan),
+            amount: Set(input.amount),
+            currency: Set(input.currency.to_uppercase()),
+            gateway_customer_ref: Set(input.gateway_customer_ref),
+            payment_method_ref: Set(input.payment_method_ref),
+            vault_token_id: Set(vault_token_id),
+            status: Set(SubscriptionStatus::Active),
+            current_period_end: Set(input.current_period_end),
+            past_due_since: Set(None),
+            canceled_at: Set(None),
+            cancel_reason: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(self.db.as_ref())
+        .await?)
+    }
+
+    async fn subscription(&self, id: Uuid) -> Result<subscription::Model, DunningError> {
+        Subscription::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| DunningError::NotFound(format!("Subscription not found: {}", id)))
+    }
+
+    async fn case(&self, id: Uuid) -> Result<dunning_case::Model, DunningError> {
+        DunningCase::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| DunningError::NotFound(format!("Dunning case not found: {}", id)))
+    }
+
+    async fn open_case(&self, subscription_id: Uuid) -> Result<Option<dunning_case::Model>, DunningError> {
+        Ok(DunningCase::find()
+            .filter(dunning_case::Column::SubscriptionId.eq(subscription_id))
+            .filter(dunning_case::Column::Status.eq(DunningStatus::Retrying))
+            .one(self.db.as_ref())
+            .await?)
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<SubscriptionWithDunning, DunningError> {
+        let subscription = self.subscription(id).await?;
+        let dunning_cases = DunningCase::find()
+            .filter(dunning_case::Column::SubscriptionId.eq(id))
+            .order_by_desc(dunning_case::Column::CreatedAt)
+            .all(self.db.as_ref())
+            .await?;
+        Ok(SubscriptionWithDunning { subscription, dunning_cases })
+    }
+
+    pub async fn cases(
+        &self,
+        filter: CaseFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<dunning_case::Model>, u64), DunningError> {
+        let mut query = DunningCase::find();
+        if let Some(status) = filter.status {
+            query = query.filter(dunning_case::Column::Status.eq(status));
+        }
+        if let Some(subscription_id) = filter.subscription_id {
+            query = query.filter(dunning_case::Column::SubscriptionId.eq(subscription_id));
+        }
+        let paginator = query
+            .order_by_desc(dunning_case::Column::CreatedAt)
+            .paginate(self.db.as_ref(), pagination.limit());
+        let total = paginator.num_items().await?;
+        let items = paginator.fetch_page(pagination.page_index()).await?;
+        Ok((items, total))
+    }
+
+    /// Opens a dunning case for a failed recurring charge and marks the subscription past
+    /// due. A charge reported again while a case is open returns that case.
+    pub async fn record_failure(&self, subscription_id: Uuid, failed: FailedCharge) -> Result<dunning_case::Model, DunningError> {
+        let subscription = self.subscription(subscription_id).await?;
+        if subscription.status == SubscriptionStatus::Canceled {
+            return Err(DunningError::Invalid(format!("Subscription {} is canceled", subscription_id)));
+        }
+        if let Some(open) = self.open_case(subscription_id).await? {
+            return Ok(open);
+        }
+        let amount = failed.amount.unwrap_or(subscription.amount);
+        if amount <= Decimal::ZERO {
+            return Err(DunningError::Invalid("Charge amount must be positive".to_string()));
+        }
+        let now = Utc::now();
+        let case = dunning_case::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            subscription_id: Set(subscription_id),
+            amount: Set(amount),
+            currency: Set(subscription.currency.clone()),
+            status: Set(DunningStatus::Retrying),
+            failures: Set(1),
+            next_retry_at: Set(next_retry(&self.config.retry_hours, 1, now)),
+            last_error: Set(Some(failed.error)),
+            gateway_ref: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+            resolved_at: Set(None),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+
+        let mut active: subscription::ActiveModel = subscription.clone().into();
+        active.status = Set(SubscriptionStatus::PastDue);
+        active.past_due_since = Set(Some(now));
+        active.updated_at = Set(now);
+        let subscription = active.update(self.db.as_ref()).await?;
+        info!(subscription_id = %subscription_id, %amount, "Subscription past due");
+        let _ = self.events.send(Event::SubscriptionPastDue { subscription_id, amount });
+
+        self.after_failure(case, subscription).await
+    }
+
+    /// Notifies the subscriber of a failure, and cancels the subscription once no retry is left.
+    async fn after_failure(
+        &self,
+        case: dunning_case::Model,
+        subscription: subscription::Model,
+    ) -> Result<dunning_case::Model, DunningError> {
+        let stage = stage_after_failure(&self.config.retry_hours, case.failures);
+        if case.next_retry_at.is_some() {
+            self.notify(&subscription, stage, case.next_retry_at).await;
+            return Ok(case);
+        }
+        let now = Utc::now();
+        let mut exhausted: dunning_case::ActiveModel = case.into();
+        exhausted.status = Set(DunningStatus::Exhausted);
+        exhausted.resolved_at = Set(Some(now));
+        exhausted.updated_at = Set(now);
+        let case = exhausted.update(self.db.as_ref()).await?;
+        let subscription = self.cancel_subscription(subscription, "payment_failed").await?;
+        self.notify(&subscription, stage, None).await;
+        Ok(case)
+    }
+
+    async fn cancel_subscription(&self, subscription: subscription::Model, reason: &str) -> Result<subscription::Model, DunningError> {
+        let now = Utc::now();
+        let mut active: subscription::ActiveModel = subscription.into();
+        active.status = Set(SubscriptionStatus::Canceled);
+        active.canceled_at = Set(Some(now));
+        active.cancel_reason = Set(Some(reason.to_string()));
+        active.updated_at = Set(now);
+        let subscription = active.update(self.db.as_ref()).await?;
+        info!(subscription_id = %subscription.id, reason, "Subscription canceled");
+        let _ = self.events.send(Event::SubscriptionCanceled {
+            subscription_id: subscription.id,
+            reason: reason.to_string(),
+        });
+        Ok(subscription)
+    }
+
+    async fn notify(&self, subscription: &subscription::Model, stage: DunningStage, next_retry: Option<DateTime<Utc>>) {
+        let Some(user_id) = subscription.user_id else {
+            return;
+        };
+        let locale = Locale::parse(&self.config.notification_locale).unwrap_or(Locale::EnUs);
+        let notification =
+            create_billing_notification(user_id, self.config.template(stage), subscription, next_retry, locale);
+        if let Err(e) = self.notifications.send_notification(notification).await {
+            error!("Failed to send {:?} notice for subscription {}: {}", stage, subscription.id, e);
+        }
+    }
+
+    /// Retries every case whose next attempt is due. Returns how many were recovered.
+    /// Cases are claimed first, so instances running the worker side by side never retry
+    /// the same case at once.
+    pub async fn run_due(&self) -> Result<usize, DunningError> {
+        let due = self.claim_due().await?;
+        let mut recovered = 0;
+        for case in due {
+            if self.attempt(case).await?.status == DunningStatus::Recovered {
+                recovered += 1;
+            }
+        }
+        Ok(recovered)
+    }
+
+    /// Locks due cases, skipping those another worker holds, and moves their next retry
+    /// out by [`CLAIM_MINUTES`] before releasing the lock. The retry itself sets the real
+    /// next attempt.
+    async fn claim_due(&self) -> Result<Vec<dunning_case::Model>, DunningError> {
+        let now = Utc::now();
+        let txn = self.db.begin().await?;
+        let due = DunningCase::find()
+            .filter(dunning_case::Column::Status.eq(DunningStatus::Retrying))
+            .filter(dunning_case::Column::NextRetryAt.lte(now))
+            .order_by_asc(dunning_case::Column::NextRetryAt)
+            .limit(self.config.batch_size.max(1))
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+            .all(&txn)
+            .await?;
+        let mut claimed = Vec::with_capacity(due.len());
+        for case in due {
+            let mut active: dunning_case::ActiveModel = case.into();
+            active.next_retry_at = Set(Some(now + Duration::minutes(CLAIM_MINUTES)));
+            claimed.push(active.update(&txn).await?);
+        }
+        txn.commit().await?;
+        Ok(claimed)
+    }
+
+    /// Retries a case now, e.g. after the subscriber updated their card. A new payment
+    /// method or vault token replaces the subscription's stored one.
+    pub async fn retry_now(
+        &self,
+        case_id: Uuid,
+        payment_method_ref: Option<String>,
+        vault_token: Option<String>,
+    ) -> Result<dunning_case::Model, DunningError> {
+        let case = self.case(case_id).await?;
+        if case.status != DunningStatus::Retrying {
+            return Err(DunningError::Invalid(format!("Dunning case is {:?}", case.status)));
+        }
+        if let Some(token) = vault_token {
+            let record = self.vault_token(&token, case.amount, &case.currency).await?;
+            let mut subscription: subscription::ActiveModel = self.subscription(case.subscription_id).await?.into();
+            subscription.gateway_customer_ref = Set(record.customer_ref.unwrap_or_default());
+            subscription.payment_method_ref = Set(record.payment_method_ref);
+            subscription.vault_token_id = Set(Some(record.id));
+            subscription.updated_at = Set(Utc::now());
+            subscription.update(self.db.as_ref()).await?;
+        } else if let Some(payment_method_ref) = payment_method_ref {
+            let mut subscription: subscription::ActiveModel = self.subscription(case.subscription_id).await?.into();
+            subscription.payment_method_ref = Set(payment_method_ref);
+            subscription.vault_token_id = Set(None);
+            subscription.updated_at = Set(Utc::now());
+            subscription.update(self.db.as_ref()).await?;
+        }
+        self.attempt(case).await
+    }
+
+    /// Redeems the subscription's vault token for this charge, if it is billed through
+    /// one. A revoked or expired token fails the attempt like a declined card. Retries
+    /// go through `redeem_retry`, so a token is never redeemed twice for them.
+    async fn redeem_vault_token(&self, subscription: &subscription::Model, amount: Decimal, currency: &str) -> Result<(), GatewayError> {
+        let Some(token_id) = subscription.vault_token_id else {
+            return Ok(());
+        };
+        let vault = self
+            .vault
+            .as_ref()
+            .ok_or_else(|| GatewayError::Unavailable("the payment vault is not enabled".to_string()))?;
+        let redemption = Redemption {
+            amount,
+            currency: currency.to_string(),
+            checkout_session_id: None,
+            used_by: format!("subscription:{}", subscription.id),
+        };
+        match vault.redeem_retry(token_id, redemption).await {
+            Ok(_) => Ok(()),
+            Err(crate::errors::ServiceError::DatabaseError(e)) => Err(GatewayError::Unavailable(e)),
+            Err(e) => Err(GatewayError::Declined(e.to_string())),
+        }
+    }
+
+    async fn attempt(&self, case: dunning_case::Model) -> Result<dunning_case::Model, DunningError> {
+        let subscription = self.subscription(case.subscription_id).await?;
+        let description = format!("{} subscription", subscription.plan);
+        // One key per attempt, so a retry of the same attempt never charges twice
+        let idempotency_key = format!("dunning:{}:{}", case.id, case.failures);
+        let outcome = match self.redeem_vault_token(&subscription, case.amount, &case.currency).await {
+            Ok(()) => {
+                self.gateway
+                    .charge(ChargeRequest {
+                        customer_ref: &subscription.gateway_customer_ref,
+                        payment_method_ref: &subscription.payment_method_ref,
+                        amount: case.amount,
+                        currency: &case.currency,
+                        description: &description,
+                        idempotency_key: &idempotency_key,
+                    })
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+
+        let now = Utc::now();
+        let failures = case.failures;
+        let amount = case.amount;
+        let mut active: dunning_case::ActiveModel = case.into();
+        active.updated_at = Set(now);
+        match outcome {
+            Ok(gateway_ref) => {
+                DUNNING_RETRIES.with_label_values(&["recovered"]).inc();
+                active.status = Set(DunningStatus::Recovered);
+                active.gateway_ref = Set(Some(gateway_ref));
+                active.next_retry_at = Set(None);
+                active.resolved_at = Set(Some(now));
+                let case = active.update(self.db.as_ref()).await?;
+
+                let mut restored: subscription::ActiveModel = subscription.into();
+                restored.status = Set(SubscriptionStatus::Active);
+                restored.past_due_since = Set(None);
+                restored.updated_at = Set(now);
+                let subscription = restored.update(self.db.as_ref()).await?;
+                info!(subscription_id = %subscription.id, %amount, "Subscription payment recovered");
+                self.notify(&subscription, DunningStage::Recovered, None).await;
+                let _ = self.events.send(Event::SubscriptionRecovered { subscription_id: subscription.id, amount });
+                Ok(case)
+            }
+            Err(GatewayError::Unavailable(e)) => {
+                DUNNING_RETRIES.with_label_values(&["unavailable"]).inc();
+                warn!(subscription_id = %subscription.id, "Dunning retry deferred, gateway unavailable: {}", e);
+                active.next_retry_at = Set(Some(now + Duration::minutes(UNAVAILABLE_RETRY_MINUTES)));
+                Ok(active.update(self.db.as_ref()).await?)
+            }
+            Err(e) => {
+                DUNNING_RETRIES.with_label_values(&["failed"]).inc();
+                warn!(subscription_id = %subscription.id, failures = failures + 1, "Dunning retry failed: {}", e);
+                active.failures = Set(failures + 1);
+                active.last_error = Set(Some(e.to_string()));
+                active.next_retry_at = Set(next_retry(&self.config.retry_hours, failures + 1, now));
+                let case = active.update(self.db.as_ref()).await?;
+                self.after_failure(case, subscription).await
+            }
+        }
+    }
+
+    /// Cancels a subscription at the subscriber's or an operator's request, closing any open
+    /// dunning case.
+    pub async fn cancel(&self, id: Uuid, reason: Option<String>) -> Result<subscription::Model, DunningError> {
+        let subscription = self.subscription(id).await?;
+        if subscription.status == SubscriptionStatus::Canceled {
+            return Err(DunningError::Invalid(format!("Subscription {} is already canceled", id)));
+        }
+        if let Some(open) = self.open_case(id).await? {
+            let now = Utc::now();
+            let mut closed: dunning_case::ActiveModel = open.into();
+            closed.status = Set(DunningStatus::Closed);
+            closed.next_retry_at = Set(None);
+            closed.resolved_at = Set(Some(now));
+            closed.updated_at = Set(now);
+            closed.update(self.db.as_ref()).await?;
+        }
+        self.cancel_subscription(subscription, reason.as_deref().unwrap_or("requested"))
+            .await
+    }
+}
+
+pub fn spawn_worker(service: Arc<DunningService>, interval: std::time::Duration, mut worker: Worker) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = worker.stopping() => break,
+            }
+            match service.run_due().await {
+                Ok(0) => {}
+                Ok(recovered) => info!(recovered, "Recovered subscription payments"),
+                Err(e) => error!("Dunning run failed: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_retries_follow_the_curve_then_stop() {
+        let curve = [24, 72, 168];
+        let failed_at = Utc.with_ymd_and_hms(2024, 6, 1, 9, 0, 0).unwrap();
+        assert_eq!(next_retry(&curve, 1, failed_at), Some(failed_at + Duration::hours(24)));
+        assert_eq!(next_retry(&curve, 3, failed_at), Some(failed_at + Duration::hours(168)));
+        assert_eq!(next_retry(&curve, 4, failed_at), None);
+        assert_eq!(next_retry(&[], 1, failed_at), None);
+    }
+
+    #[test]
+    fn test_stages_escalate_to_cancellation() {
+        let curve = [24, 72, 168];
+        assert_eq!(stage_after_failure(&curve, 1), DunningStage::PaymentFailed);
+        assert_eq!(stage_after_failure(&curve, 2), DunningStage::RetryFailed);
+        assert_eq!(stage_after_failure(&curve, 3), DunningStage::FinalNotice);
+        assert_eq!(stage_after_failure(&curve, 4), DunningStage::Canceled);
+        assert_eq!(stage_after_failure(&[], 1), DunningStage::Canceled);
+    }
+
+    #[test]
+    fn test_templates_can_be_overridden_per_stage() {
+        let config: DunningConfig = serde_json::from_value(json!({
+            "retry_hours": [12, 48],
+            "templates": { "final_notice": "notifications.custom_final" }
+        }))
+        .unwrap();
+        assert_eq!(config.retry_hours, vec![12, 48]);
+        assert_eq!(config.template(DunningStage::FinalNotice), "notifications.custom_final");
+        assert_eq!(config.template(DunningStage::Canceled), "notifications.dunning.canceled");
+    }
+}
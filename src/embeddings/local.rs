@@ -0,0 +1,153 @@
+// embeddings/local.rs
+
+use async_trait::async_trait;
+use candle_core::{Device, Tensor};
+use candle_nn::VarBuilder;
+use candle_transformers::models::bert::{BertModel, Config, DTYPE};
+use std::{path::Path, sync::Arc};
+use tokenizers::{PaddingParams, Tokenizer, TruncationParams};
+
+use super::{EmbeddingError, EmbeddingProvider};
+
+/// Longest input in tokens; longer text is truncated.
+const MAX_TOKENS: usize = 256;
+
+/// Sentence embeddings from a BERT-family model on the CPU: mean pooling over
+/// non-padding tokens, L2-normalized, as sentence-transformers models expect.
+pub struct LocalBertProvider {
+    model: Arc<BertModel>,
+    tokenizer: Arc<Tokenizer>,
+    dimensions: usize,
+}
+
+fn model_error(e: impl std::fmt::Display) -> EmbeddingError {
+    EmbeddingError::Model(e.to_string())
+}
+
+impl LocalBertProvider {
+    /// Loads `config.json`, `tokenizer.json` and `model.safetensors` from `dir`.
+    pub fn load(dir: impl AsRef<Path>) -> Result<Self, EmbeddingError> {
+        let dir = dir.as_ref();
+        let misconfigured = |e: String| EmbeddingError::Misconfigured(format!("{}: {}", dir.display(), e));
+
+        let raw_config = std::fs::read_to_string(dir.join("config.json")).map_err(|e| misconfigured(e.to_string()))?;
+        let config: Config = serde_json::from_str(&raw_config).map_err(|e| misconfigured(e.to_string()))?;
+        // `Config` keeps its fields private; the width is read from the same file
+        let dimensions = serde_json::from_str::<serde_json::Value>(&raw_config)
+            .ok()
+            .and_then(|raw| raw["hidden_size"].as_u64())
+            .ok_or_else(|| misconfigured("config.json has no hidden_size".to_string()))? as usize;
+
+        let mut tokenizer = Tokenizer::from_file(dir.join("tokenizer.json")).map_err(|e| misconfigured(e.to_string()))?;
+        tokenizer.with_padding(Some(PaddingParams::default()));
+        tokenizer
+            .with_truncation(Some(TruncationParams { max_length: MAX_TOKENS, ..Default::default() }))
+            .map_err(|e| misconfigured(e.to_string()))?;
+
+        // Safety: the weights file is only read, and not modified while mapped
+        let weights = unsafe {
+            VarBuilder::from_mmaped_safetensors(&[dir.join("model.safetensors")], DTYPE, &Device::Cpu)
+                .map_err(|e| misconfigured(e.to_string()))?
+        };
+        let model = BertModel::load(weights, &config).map_err(|e| misconfigured(e.to_string()))?;
+
+        Ok(Self {
+            model: Arc::new(model),
+            tokenizer: Arc::new(tokenizer),
+            dimensions,
+        })
+    }
+}
+
+fn run(model: &BertModel, tokenizer: &Tokenizer, inputs: Vec<String>) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+    let encodings = tokenizer.encode_batch(inputs, true).map_err(model_error)?;
+    let ids = encodings
+        .iter()
+        .map(|e| Tensor::new(e.get_ids(), &Device::Cpu))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(model_error)?;
+    let masks = encodings
+        .iter()
+        .map(|e| Tensor::new(e.get_attention_mask(), &Device::Cpu))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(model_error)?;
+    let ids = Tensor::stack(&ids, 0).map_err(model_error)?;
+    let mask = Tensor::stack(&masks, 0).map_err(model_error)?;
+    let token_types = ids.zeros_like().map_err(model_error)?;
+
+    let hidden = model
+        .forward(&ids, &token_types, Some(&mask))
+        .and_then(|t| t.to_vec3::<f32>())
+        .map_err(model_error)?;
+    Ok(hidden
+        .iter()
+        .zip(&encodings)
+        .map(|(tokens, encoding)| l2_normalize(mean_pool(tokens, encoding.get_attention_mask())))
+        .collect())
+}
+
+/// Average of the token vectors whose mask is set.
+fn mean_pool(tokens: &[Vec<f32>], mask: &[u32]) -> Vec<f32> {
+    let width = tokens.first().map_or(0, Vec::len);
+    let mut sum = vec![0.0; width];
+    let mut count = 0.0;
+    for (token, _) in tokens.iter().zip(mask).filter(|(_, &m)| m != 0) {
+        for (acc, value) in sum.iter_mut().zip(token) {
+            *acc += value;
+        }
+        count += 1.0;
+    }
+    if count > 0.0 {
+        sum.iter_mut().for_each(|v| *v /= count);
+    }
+    sum
+}
+
+fn l2_normalize(mut vector: Vec<f32>) -> Vec<f32> {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+    vector
+}
+
+#[async_trait]
+impl EmbeddingProvider for LocalBertProvider {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+        // Inference is CPU-bound; keep it off the async workers
+        let model = self.model.clone();
+        let tokenizer = self.tokenizer.clone();
+        let inputs = inputs.to_vec();
+        tokio::task::spawn_blocking(move || run(&model, &tokenizer, inputs))
+            .await
+            .map_err(model_error)?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mean_pool_skips_padding() {
+        let tokens = vec![vec![1.0, 3.0], vec![3.0, 5.0], vec![100.0, 100.0]];
+        assert_eq!(mean_pool(&tokens, &[1, 1, 0]), vec![2.0, 4.0]);
+    }
+
+    #[test]
+    fn test_l2_normalize() {
+        assert_eq!(l2_normalize(vec![3.0, 4.0]), vec![0.6, 0.8]);
+        assert_eq!(l2_normalize(vec![0.0, 0.0]), vec![0.0, 0.0]);
+    }
+}
@@ -0,0 +1,236 @@
+// embeddings/mod.rs
+
+use async_trait::async_trait;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use thiserror::Error;
+
+#[cfg(feature = "local-embeddings")]
+mod local;
+
+#[cfg(feature = "local-embeddings")]
+pub use local::LocalBertProvider;
+
+/// Which embedding model backs semantic features.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EmbeddingBackend {
+    /// OpenAI's embeddings API; needs network access and an API key.
+    #[serde(rename = "openai")]
+    OpenAi,
+    /// A BERT-style sentence model loaded from disk and run in-process, for air-gapped
+    /// deployments. Requires the `local-embeddings` build feature.
+    Local,
+}
+
+/// Embedding settings, loaded from the `embeddings` section of the config. Like any
+/// section it can differ per environment, e.g. `local` in `config/airgapped.toml`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct EmbeddingsConfig {
+    #[serde(default = "default_backend")]
+    pub provider: EmbeddingBackend,
+
+    /// Environment variable holding the OpenAI API key. Only required for OpenAI itself;
+    /// a self-hosted `openai_base_url` is called without a key when it is unset.
+    #[serde(default = "default_api_key_env")]
+    pub openai_api_key_env: String,
+
+    #[serde(default = "default_openai_model")]
+    pub openai_model: String,
+
+    /// Base URL of an OpenAI-compatible API, e.g. an internal gateway.
+    #[serde(default = "default_openai_base_url")]
+    pub openai_base_url: String,
+
+    /// Embedding width requested from OpenAI. Local models use their hidden size.
+    #[serde(default = "default_dimensions")]
+    pub dimensions: usize,
+
+    /// Directory with `config.json`, `tokenizer.json` and `model.safetensors`
+    /// (e.g. a download of `sentence-transformers/all-MiniLM-L6-v2`).
+    #[serde(default)]
+    pub local_model_dir: Option<String>,
+}
+
+fn default_backend() -> EmbeddingBackend {
+    EmbeddingBackend::OpenAi
+}
+
+fn default_api_key_env() -> String {
+    "OPENAI_API_KEY".to_string()
+}
+
+fn default_openai_model() -> String {
+    "text-embedding-3-small".to_string()
+}
+
+fn default_openai_base_url() -> String {
+    "https://api.openai.com/v1".to_string()
+}
+
+fn default_dimensions() -> usize {
+    1536
+}
+
+impl Default for EmbeddingsConfig {
+    fn default() -> Self {
+        Self {
+            provider: default_backend(),
+            openai_api_key_env: default_api_key_env(),
+            openai_model: default_openai_model(),
+            openai_base_url: default_openai_base_url(),
+            dimensions: default_dimensions(),
+            local_model_dir: None,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum EmbeddingError {
+    #[error("Embedding provider is misconfigured: {0}")]
+    Misconfigured(String),
+
+    #[error("Embedding request failed: {0}")]
+    Request(String),
+
+    #[error("Embedding model failed: {0}")]
+    Model(String),
+}
+
+/// Turns text into fixed-width vectors. Implementations return one vector per input,
+/// in input order, each `dimensions()` wide.
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn dimensions(&self) -> usize;
+
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError>;
+}
+
+/// Calls the OpenAI (or a compatible) embeddings API.
+pub struct OpenAiProvider {
+    client: reqwest::Client,
+    api_key: Option<String>,
+    base_url: String,
+    model: String,
+    dimensions: usize,
+}
+
+impl OpenAiProvider {
+    pub fn from_config(config: &EmbeddingsConfig) -> Result<Self, EmbeddingError> {
+        let api_key = std::env::var(&config.openai_api_key_env).ok().filter(|key| !key.is_empty());
+        if api_key.is_none() && config.openai_base_url == default_openai_base_url() {
+            return Err(EmbeddingError::Misconfigured(format!("{} is not set", config.openai_api_key_env)));
+        }
+        Ok(Self {
+            client: reqwest::Client::new(),
+            api_key,
+            base_url: config.openai_base_url.trim_end_matches('/').to_string(),
+            model: config.openai_model.clone(),
+            dimensions: config.dimensions,
+        })
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for OpenAiProvider {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    async fn embed(&self, inputs: &[String]) -> Result<Vec<Vec<f32>>, EmbeddingError> {
+        #[derive(Deserialize)]
+        struct Item {
+            index: usize,
+            embedding: Vec<f32>,
+        }
+        #[derive(Deserialize)]
+        struct EmbeddingResponse {
+            data: Vec<Item>,
+        }
+
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+        let fail = |e: reqwest::Error| EmbeddingError::Request(e.to_string());
+        let mut request = self.client.post(format!("{}/embeddings", self.base_url));
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let mut response: EmbeddingResponse = request
+            .json(&json!({ "model": self.model, "input": inputs, "dimensions": self.dimensions }))
+            .send()
+            .await
+            .map_err(fail)?
+            .error_for_status()
+            .map_err(fail)?
+            .json()
+            .await
+            .map_err(fail)?;
+        response.data.sort_by_key(|item| item.index);
+        Ok(response.data.into_iter().map(|item| item.embedding).collect())
+    }
+}
+
+/// Builds the provider selected in the config.
+pub fn from_config(config: &EmbeddingsConfig) -> Result<Arc<dyn EmbeddingProvider>, EmbeddingError> {
+    match config.provider {
+        EmbeddingBackend::OpenAi => Ok(Arc::new(OpenAiProvider::from_config(config)?)),
+        EmbeddingBackend::Local => local_provider(config),
+    }
+}
+
+#[cfg(feature = "local-embeddings")]
+fn local_provider(config: &EmbeddingsConfig) -> Result<Arc<dyn EmbeddingProvider>, EmbeddingError> {
+    let dir = config
+        .local_model_dir
+        .as_deref()
+        .ok_or_else(|| EmbeddingError::Misconfigured("local_model_dir is not set".to_string()))?;
+    Ok(Arc::new(LocalBertProvider::load(dir)?))
+}
+
+#[cfg(not(feature = "local-embeddings"))]
+fn local_provider(_config: &EmbeddingsConfig) -> Result<Arc<dyn EmbeddingProvider>, EmbeddingError> {
+    Err(EmbeddingError::Misconfigured(
+        "the local provider needs a build with the `local-embeddings` feature".to_string(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_names_deserialize() {
+        let config: EmbeddingsConfig = serde_json::from_value(json!({ "provider": "local" })).unwrap();
+        assert_eq!(config.provider, EmbeddingBackend::Local);
+        let config: EmbeddingsConfig = serde_json::from_value(json!({ "provider": "openai" })).unwrap();
+        assert_eq!(config.provider, EmbeddingBackend::OpenAi);
+    }
+
+    #[test]
+    fn test_local_provider_requires_model() {
+        let config = EmbeddingsConfig {
+            provider: EmbeddingBackend::Local,
+            ..EmbeddingsConfig::default()
+        };
+        assert!(matches!(from_config(&config), Err(EmbeddingError::Misconfigured(_))));
+    }
+
+    #[test]
+    fn test_self_hosted_openai_api_needs_no_key() {
+        let config = EmbeddingsConfig {
+            openai_api_key_env: "STATESET_TEST_UNSET_EMBEDDINGS_KEY".to_string(),
+            ..EmbeddingsConfig::default()
+        };
+        assert!(matches!(OpenAiProvider::from_config(&config), Err(EmbeddingError::Misconfigured(_))));
+        let config = EmbeddingsConfig { openai_base_url: "http://embeddings.internal:8080/v1".to_string(), ..config };
+        assert!(OpenAiProvider::from_config(&config).is_ok());
+    }
+}
@@ -0,0 +1,130 @@
+use sea_orm::sea_query::{ArrayType, ColumnType, Nullable, Value, ValueType, ValueTypeErr};
+use sea_orm::{ColIdx, DbErr, QueryResult, TryGetError, TryGetable};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Deref;
+
+/// A text column stored encrypted with the current tenant's data key. Entities hold and
+/// serialize the plaintext; encryption happens when the value is bound to a query and
+/// decryption when a row is read. Rows written before encryption was enabled are read
+/// as they are until the rotation job encrypts them.
+///
+/// Ciphertexts differ on every write, so encrypted columns cannot be filtered on.
+#[derive(Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct EncryptedText(String);
+
+impl EncryptedText {
+    pub fn new(plaintext: impl Into<String>) -> Self {
+        Self(plaintext.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl Deref for EncryptedText {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<String> for EncryptedText {
+    fn from(plaintext: String) -> Self {
+        Self(plaintext)
+    }
+}
+
+impl From<&str> for EncryptedText {
+    fn from(plaintext: &str) -> Self {
+        Self(plaintext.to_string())
+    }
+}
+
+impl PartialEq<str> for EncryptedText {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for EncryptedText {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl fmt::Display for EncryptedText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Kept out of logs: `Debug` never prints the plaintext.
+impl fmt::Debug for EncryptedText {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptedText(..)")
+    }
+}
+
+impl validator::HasLen for &EncryptedText {
+    fn length(&self) -> u64 {
+        self.0.chars().count() as u64
+    }
+}
+
+/// A value that cannot be sealed is bound as NULL, which the NOT NULL encrypted columns
+/// refuse; `ensure_sealable` in the entities' `before_save` reports the cause first.
+impl From<EncryptedText> for Value {
+    fn from(text: EncryptedText) -> Self {
+        match super::seal(&text.0) {
+            Ok(sealed) => Value::String(Some(Box::new(sealed))),
+            Err(e) => {
+                tracing::error!("Encrypted value not written: {}", e);
+                Value::String(None)
+            }
+        }
+    }
+}
+
+impl TryGetable for EncryptedText {
+    fn try_get_by<I: ColIdx>(res: &QueryResult, index: I) -> Result<Self, TryGetError> {
+        let stored = String::try_get_by(res, index)?;
+        super::open(&stored)
+            .map(Self)
+            .map_err(|e| TryGetError::DbErr(DbErr::Type(e.to_string())))
+    }
+}
+
+impl ValueType for EncryptedText {
+    fn try_from(value: Value) -> Result<Self, ValueTypeErr> {
+        match value {
+            Value::String(Some(stored)) => super::open(&stored).map(Self).map_err(|_| ValueTypeErr),
+            _ => Err(ValueTypeErr),
+        }
+    }
+
+    fn type_name() -> String {
+        "EncryptedText".to_string()
+    }
+
+    fn array_type() -> ArrayType {
+        ArrayType::String
+    }
+
+    fn column_type() -> ColumnType {
+        ColumnType::Text
+    }
+}
+
+impl Nullable for EncryptedText {
+    fn null() -> Value {
+        Value::String(None)
+    }
+}
@@ -0,0 +1,832 @@
+// encryption/mod.rs
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Nonce,
+};
+use arc_swap::ArcSwapOption;
+use async_trait::async_trait;
+use axum::{
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{Duration, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    FromQueryResult, QueryFilter, QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::auth::Claims;
+use crate::db::dialect;
+use crate::jobs::{JobContext, JobError, JobRunner};
+use crate::models::tenant_data_key::{self, DataKeyStatus, Entity as TenantDataKey};
+
+pub mod column;
+
+pub use column::EncryptedText;
+
+pub const ROTATION_JOB_KIND: &str = "encryption_key_rotation";
+
+/// Marks a stored value as ciphertext: `enc:v1:<data key id>:<base64 nonce and ciphertext>`.
+const PREFIX: &str = "enc:v1:";
+
+const NONCE_LEN: usize = 12;
+
+/// Columns holding `EncryptedText`, visited by the rotation job.
+pub const ENCRYPTED_COLUMNS: &[(&str, &str)] = &[
+    ("orders", "customer_name"),
+    ("orders", "delivery_address"),
+    ("dropship_orders", "customer_name"),
+    ("dropship_orders", "customer_email"),
+    ("dropship_orders", "ship_to"),
+    ("pos_payments", "reference"),
+];
+
+static KEYRING: ArcSwapOption<Keyring> = ArcSwapOption::const_empty();
+
+/// Set once an `EncryptionService` exists; from then on values are never stored in the clear.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+tokio::task_local! {
+    static TENANT: String;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyProviderKind {
+    /// Master key from an environment variable, for development.
+    #[default]
+    Local,
+    /// Master key held in AWS KMS; data keys are generated and unwrapped by KMS.
+    Kms,
+}
+
+/// Column encryption settings, loaded from the `encryption` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct EncryptionConfig {
+    /// Enables encryption of new values (default: false). Values already encrypted
+    /// can only be read while it is enabled.
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default)]
+    pub provider: KeyProviderKind,
+
+    /// KMS key id or ARN, for the `kms` provider.
+    #[serde(default)]
+    pub kms_key_id: Option<String>,
+
+    /// AWS region; falls back to the default provider chain when unset.
+    #[serde(default)]
+    pub region: Option<String>,
+
+    /// Environment variable holding the base64 master key, for the `local` provider.
+    #[serde(default = "default_master_key_env")]
+    pub master_key_env: String,
+
+    /// Tenant whose key encrypts values written outside of a tenant's request, and for
+    /// tenants without a key of their own.
+    #[serde(default = "default_tenant")]
+    pub default_tenant: String,
+
+    /// Data keys older than this are rotated automatically; manual only when unset.
+    #[serde(default)]
+    pub rotation_interval_days: Option<i64>,
+
+    /// Rows re-encrypted per query by the rotation job.
+    #[serde(default = "default_rotation_batch_size")]
+    pub rotation_batch_size: u64,
+}
+
+fn default_master_key_env() -> String {
+    "STATESET_MASTER_KEY".to_string()
+}
+
+fn default_tenant() -> String {
+    "default".to_string()
+}
+
+fn default_rotation_batch_size() -> u64 {
+    500
+}
+
+impl Default for EncryptionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider: KeyProviderKind::default(),
+            kms_key_id: None,
+            region: None,
+            master_key_env: default_master_key_env(),
+            default_tenant: default_tenant(),
+            rotation_interval_days: None,
+            rotation_batch_size: default_rotation_batch_size(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum EncryptionError {
+    #[error("Encryption is misconfigured: {0}")]
+    Misconfigured(String),
+
+    #[error("KMS request failed: {0}")]
+    Kms(String),
+
+    #[error("Data key {0} is not loaded")]
+    UnknownKey(Uuid),
+
+    /// The value was tampered with or is not one of ours.
+    #[error("Encrypted value is corrupt: {0}")]
+    Corrupt(String),
+
+    #[error(transparent)]
+    Job(#[from] JobError),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for EncryptionError {
+    fn into_response(self) -> Response {
+        if let EncryptionError::Job(e) = self {
+            return e.into_response();
+        }
+        let (status, code) = match &self {
+            EncryptionError::Misconfigured(_) => (StatusCode::SERVICE_UNAVAILABLE, "encryption_misconfigured"),
+            EncryptionError::Kms(_) => (StatusCode::BAD_GATEWAY, "kms_unavailable"),
+            EncryptionError::UnknownKey(_) | EncryptionError::Corrupt(_) => {
+                error!("{}", self);
+                (StatusCode::INTERNAL_SERVER_ERROR, "encryption_failed")
+            }
+            EncryptionError::Job(_) => unreachable!("job errors respond on their own"),
+            EncryptionError::Database(e) => {
+                error!("Encryption query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "encryption_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// Associated data of a value: the tenant owning the data key, and the key id.
+fn aad(tenant_id: &str, key_id: Uuid) -> Vec<u8> {
+    format!("{}:{}", tenant_id, key_id).into_bytes()
+}
+
+/// Encrypts `plaintext` with a data key of `tenant_id`. The tenant and key id are
+/// authenticated along with the ciphertext, so a value cannot be passed off as another
+/// key's or another tenant's.
+pub fn encrypt_with(cipher: &Aes256Gcm, tenant_id: &str, key_id: Uuid, plaintext: &str) -> String {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let sealed = cipher
+        .encrypt(&nonce, Payload { msg: plaintext.as_bytes(), aad: &aad(tenant_id, key_id) })
+        .expect("AES-GCM encryption does not fail for in-memory buffers");
+    let mut envelope = nonce.to_vec();
+    envelope.extend_from_slice(&sealed);
+    format!("{}{}:{}", PREFIX, key_id, STANDARD.encode(envelope))
+}
+
+/// The data key id and encoded ciphertext of a stored value; `None` for plaintext.
+pub fn parse_envelope(stored: &str) -> Option<Result<(Uuid, &str), EncryptionError>> {
+    let rest = stored.strip_prefix(PREFIX)?;
+    Some(
+        rest.split_once(':')
+            .and_then(|(id, body)| Uuid::parse_str(id).ok().map(|id| (id, body)))
+            .ok_or_else(|| EncryptionError::Corrupt("malformed envelope".to_string())),
+    )
+}
+
+pub fn decrypt_with(cipher: &Aes256Gcm, tenant_id: &str, key_id: Uuid, body: &str) -> Result<String, EncryptionError> {
+    let envelope = STANDARD.decode(body).map_err(|e| EncryptionError::Corrupt(e.to_string()))?;
+    if envelope.len() <= NONCE_LEN {
+        return Err(EncryptionError::Corrupt("ciphertext too short".to_string()));
+    }
+    let (nonce, sealed) = envelope.split_at(NONCE_LEN);
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), Payload { msg: sealed, aad: &aad(tenant_id, key_id) })
+        .map_err(|_| EncryptionError::Corrupt("authentication failed".to_string()))?;
+    String::from_utf8(plaintext).map_err(|e| EncryptionError::Corrupt(e.to_string()))
+}
+
+/// Unwrapped data keys, by id with the tenant owning them, and each tenant's active key.
+pub struct Keyring {
+    keys: HashMap<Uuid, (String, Aes256Gcm)>,
+    active: HashMap<String, Uuid>,
+    default_tenant: String,
+}
+
+impl Keyring {
+    pub fn new(default_tenant: String) -> Self {
+        Self { keys: HashMap::new(), active: HashMap::new(), default_tenant }
+    }
+
+    pub fn insert(&mut self, tenant_id: &str, key_id: Uuid, key: &[u8], active: bool) -> Result<(), EncryptionError> {
+        let cipher = Aes256Gcm::new_from_slice(key).map_err(|_| EncryptionError::Corrupt("data key is not 256 bits".to_string()))?;
+        self.keys.insert(key_id, (tenant_id.to_string(), cipher));
+        if active {
+            self.active.insert(tenant_id.to_string(), key_id);
+        }
+        Ok(())
+    }
+
+    /// The key new values of `tenant_id` are encrypted with.
+    pub fn active_key(&self, tenant_id: &str) -> Option<Uuid> {
+        self.active.get(tenant_id).or_else(|| self.active.get(&self.default_tenant)).copied()
+    }
+
+    pub fn encrypt(&self, tenant_id: &str, plaintext: &str) -> Option<String> {
+        let key_id = self.active_key(tenant_id)?;
+        let (owner, cipher) = &self.keys[&key_id];
+        Some(encrypt_with(cipher, owner, key_id, plaintext))
+    }
+
+    /// Encrypts with the key of the tenant being served, or of the default tenant outside
+    /// of a tenant's request.
+    pub fn encrypt_for_current_tenant(&self, plaintext: &str) -> Result<String, EncryptionError> {
+        let tenant = current_tenant().unwrap_or_else(|| self.default_tenant.clone());
+        self.encrypt(&tenant, plaintext)
+            .ok_or_else(|| EncryptionError::Misconfigured(format!("no data key for tenant {}", tenant)))
+    }
+
+    pub fn decrypt(&self, stored: &str) -> Result<String, EncryptionError> {
+        match parse_envelope(stored) {
+            None => Ok(stored.to_string()),
+            Some(envelope) => {
+                let (key_id, body) = envelope?;
+                let (owner, cipher) = self.keys.get(&key_id).ok_or(EncryptionError::UnknownKey(key_id))?;
+                decrypt_with(cipher, owner, key_id, body)
+            }
+        }
+    }
+}
+
+/// The tenant of the request being served, if its credentials carry one.
+pub fn current_tenant() -> Option<String> {
+    TENANT.try_with(|tenant| tenant.clone()).ok()
+}
+
+/// Runs `work` on behalf of `tenant_id`, so values it writes use that tenant's key.
+pub async fn with_tenant<F: Future>(tenant_id: String, work: F) -> F::Output {
+    TENANT.scope(tenant_id, work).await
+}
+
+/// Encrypts a value for storage with the current tenant's key. Values are stored as
+/// they are while encryption is disabled; once it is enabled, a value no key is loaded
+/// for is an error rather than plaintext.
+pub(crate) fn seal(plaintext: &str) -> Result<String, EncryptionError> {
+    if !ENABLED.load(Ordering::Acquire) || plaintext.is_empty() {
+        return Ok(plaintext.to_string());
+    }
+    let keyring = KEYRING
+        .load_full()
+        .ok_or_else(|| EncryptionError::Misconfigured("keyring is not loaded".to_string()))?;
+    keyring.encrypt_for_current_tenant(plaintext)
+}
+
+/// Fails writes of entities with encrypted columns while their values could not be
+/// sealed. Called from those entities' `before_save`.
+pub fn ensure_sealable() -> Result<(), DbErr> {
+    seal("-").map(|_| ()).map_err(|e| DbErr::Custom(e.to_string()))
+}
+
+/// Decrypts a stored value. Plaintext written before encryption was enabled is returned
+/// as it is.
+pub(crate) fn open(stored: &str) -> Result<String, EncryptionError> {
+    if !stored.starts_with(PREFIX) {
+        return Ok(stored.to_string());
+    }
+    match KEYRING.load().as_ref() {
+        Some(keyring) => keyring.decrypt(stored),
+        None => Err(EncryptionError::Misconfigured("encrypted value read while encryption is disabled".to_string())),
+    }
+}
+
+/// Scopes each request to the tenant of its verified claims. Runs inside the auth
+/// middleware; a tenant named only in a header is not trusted.
+pub async fn tenant_scope_middleware<B>(req: Request<B>, next: Next<B>) -> Response {
+    match req.extensions().get::<Claims>().and_then(|c| c.tenant_id.clone()).filter(|t| !t.is_empty()) {
+        Some(tenant) => with_tenant(tenant, next.run(req)).await,
+        None => next.run(req).await,
+    }
+}
+
+/// A data key as generated by the key provider.
+pub struct GeneratedKey {
+    pub plaintext: Vec<u8>,
+    pub wrapped: Vec<u8>,
+}
+
+/// Generates and unwraps data keys with the master key. The tenant is bound to the
+/// wrapped key, so one tenant's key cannot be unwrapped as another's.
+#[async_trait]
+pub trait KeyProvider: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn master_key_id(&self) -> &str;
+
+    async fn generate(&self, tenant_id: &str) -> Result<GeneratedKey, EncryptionError>;
+
+    async fn unwrap(&self, tenant_id: &str, wrapped: &[u8]) -> Result<Vec<u8>, EncryptionError>;
+}
+
+pub struct LocalKeyProvider {
+    master: Aes256Gcm,
+}
+
+impl LocalKeyProvider {
+    pub fn from_config(config: &EncryptionConfig) -> Result<Self, EncryptionError> {
+        let encoded = std::env::var(&config.master_key_env)
+            .map_err(|_| EncryptionError::Misconfigured(format!("{} is not set", config.master_key_env)))?;
+        let key = STANDARD
+            .decode(encoded.trim())
+            .map_err(|_| EncryptionError::Misconfigured(format!("{} is not base64", config.master_key_env)))?;
+        let master = Aes256Gcm::new_from_slice(&key)
+            .map_err(|_| EncryptionError::Misconfigured("master key must be 32 bytes".to_string()))?;
+        Ok(Self { master })
+    }
+}
+
+#[async_trait]
+impl KeyProvider for LocalKeyProvider {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    fn master_key_id(&self) -> &str {
+        "local"
+    }
+
+    async fn generate(&self, tenant_id: &str) -> Result<GeneratedKey, EncryptionError> {
+        let plaintext = Aes256Gcm::generate_key(&mut OsRng).to_vec();
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let sealed = self
+            .master
+            .encrypt(&nonce, Payload { msg: &plaintext, aad: tenant_id.as_bytes() })
+            .map_err(|_| EncryptionError::Corrupt("data key wrap failed".to_string()))?;
+        let mut wrapped = nonce.to_vec();
+        wrapped.extend_from_slice(&sealed);
+        Ok(GeneratedKey { plaintext, wrapped })
+    }
+
+    async fn unwrap(&self, tenant_id: &str, wrapped: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        if wrapped.len() <= NONCE_LEN {
+            return Err(EncryptionError::Corrupt("wrapped key too short".to_string()));
+        }
+        let (nonce, sealed) = wrapped.split_at(NONCE_LEN);
+        self.master
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: sealed, aad: tenant_id.as_bytes() })
+            .map_err(|_| EncryptionError::Corrupt(format!("data key of tenant {} does not unwrap", tenant_id)))
+    }
+}
+
+pub struct KmsKeyProvider {
+    client: aws_sdk_kms::Client,
+    key_id: String,
+}
+
+impl KmsKeyProvider {
+    pub async fn from_config(config: &EncryptionConfig) -> Result<Self, EncryptionError> {
+        let key_id = config
+            .kms_key_id
+            .clone()
+            .ok_or_else(|| EncryptionError::Misconfigured("encryption.kms_key_id is not set".to_string()))?;
+        let mut loader = aws_config::from_env();
+        if let Some(region) = &config.region {
+            loader = loader.region(aws_config::Region::new(region.clone()));
+        }
+        Ok(Self { client: aws_sdk_kms::Client::new(&loader.load().await), key_id })
+    }
+}
+
+#[async_trait]
+impl KeyProvider for KmsKeyProvider {
+    fn name(&self) -> &'static str {
+        "kms"
+    }
+
+    fn master_key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    async fn generate(&self, tenant_id: &str) -> Result<GeneratedKey, EncryptionError> {
+        let output = self
+            .client
+            .generate_data_key()
+            .key_id(&self.key_id)
+            .key_spec(aws_sdk_kms::types::DataKeySpec::Aes256)
+            .encryption_context("tenant_id", tenant_id)
+            .send()
+            .await
+            .map_err(|e| EncryptionError::Kms(e.to_string()))?;
+        let plaintext = output.plaintext().ok_or_else(|| EncryptionError::Kms("no plaintext key returned".to_string()))?;
+        let wrapped = output
+            .ciphertext_blob()
+            .ok_or_else(|| EncryptionError::Kms("no wrapped key returned".to_string()))?;
+        Ok(GeneratedKey { plaintext: plaintext.as_ref().to_vec(), wrapped: wrapped.as_ref().to_vec() })
+    }
+
+    async fn unwrap(&self, tenant_id: &str, wrapped: &[u8]) -> Result<Vec<u8>, EncryptionError> {
+        let output = self
+            .client
+            .decrypt()
+            .key_id(&self.key_id)
+            .ciphertext_blob(aws_sdk_kms::primitives::Blob::new(wrapped.to_vec()))
+            .encryption_context("tenant_id", tenant_id)
+            .send()
+            .await
+            .map_err(|e| EncryptionError::Kms(e.to_string()))?;
+        output
+            .plaintext()
+            .map(|key| key.as_ref().to_vec())
+            .ok_or_else(|| EncryptionError::Kms("no plaintext key returned".to_string()))
+    }
+}
+
+pub async fn provider_from_config(config: &EncryptionConfig) -> Result<Arc<dyn KeyProvider>, EncryptionError> {
+    Ok(match config.provider {
+        KeyProviderKind::Local => Arc::new(LocalKeyProvider::from_config(config)?),
+        KeyProviderKind::Kms => Arc::new(KmsKeyProvider::from_config(config).await?),
+    })
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RotationSummary {
+    pub tenant_id: String,
+    pub key_id: Uuid,
+    pub reencrypted: u64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct StoredValue {
+    id: Uuid,
+    value: String,
+}
+
+/// Manages tenant data keys and keeps the process keyring in step with them.
+pub struct EncryptionService {
+    db: Arc<DatabaseConnection>,
+    provider: Arc<dyn KeyProvider>,
+    config: EncryptionConfig,
+}
+
+impl EncryptionService {
+    pub fn new(db: Arc<DatabaseConnection>, provider: Arc<dyn KeyProvider>, config: EncryptionConfig) -> Self {
+        ENABLED.store(true, Ordering::Release);
+        Self { db, provider, config }
+    }
+
+    /// Unwraps every data key into the keyring, creating the default tenant's first key.
+    /// Must finish before requests are served.
+    pub async fn load(&self) -> Result<usize, EncryptionError> {
+        let default_has_key = TenantDataKey::find()
+            .filter(tenant_data_key::Column::TenantId.eq(self.config.default_tenant.as_str()))
+            .filter(tenant_data_key::Column::Status.eq(DataKeyStatus::Active))
+            .one(self.db.as_ref())
+            .await?
+            .is_some();
+        if !default_has_key {
+            self.create_key(&self.config.default_tenant).await?;
+        }
+        let keys = TenantDataKey::find().all(self.db.as_ref()).await?;
+        let mut keyring = Keyring::new(self.config.default_tenant.clone());
+        for key in &keys {
+            let wrapped = STANDARD.decode(&key.wrapped_key).map_err(|e| EncryptionError::Corrupt(e.to_string()))?;
+            let plaintext = self.provider.unwrap(&key.tenant_id, &wrapped).await?;
+            keyring.insert(&key.tenant_id, key.id, &plaintext, key.status == DataKeyStatus::Active)?;
+        }
+        KEYRING.store(Some(Arc::new(keyring)));
+        info!(keys = keys.len(), provider = self.provider.name(), "Encryption keyring loaded");
+        Ok(keys.len())
+    }
+
+    pub async fn keys(&self, tenant_id: Option<String>) -> Result<Vec<tenant_data_key::Model>, EncryptionError> {
+        let mut query = TenantDataKey::find();
+        if let Some(tenant_id) = tenant_id {
+            query = query.filter(tenant_data_key::Column::TenantId.eq(tenant_id));
+        }
+        Ok(query
+            .order_by_asc(tenant_data_key::Column::TenantId)
+            .order_by_desc(tenant_data_key::Column::Version)
+            .all(self.db.as_ref())
+            .await?)
+    }
+
+    /// Generates a new active key for the tenant and retires the previous one. The
+    /// keyring is reloaded, so new values use the new key immediately.
+    pub async fn create_key(&self, tenant_id: &str) -> Result<tenant_data_key::Model, EncryptionError> {
+        if tenant_id.trim().is_empty() {
+            return Err(EncryptionError::Misconfigured("tenant id is required".to_string()));
+        }
+        let generated = self.provider.generate(tenant_id).await?;
+        let previous = TenantDataKey::find()
+            .filter(tenant_data_key::Column::TenantId.eq(tenant_id))
+            .order_by_desc(tenant_data_key::Column::Version)
+            .all(self.db.as_ref())
+            .await?;
+        let now = Utc::now();
+        for key in previous.iter().filter(|k| k.status == DataKeyStatus::Active) {
+            let mut active: tenant_data_key::ActiveModel = key.clone().into();
+            active.status = Set(DataKeyStatus::Retired);
+            active.retired_at = Set(Some(now));
+            active.update(self.db.as_ref()).await?;
+        }
+        let key = tenant_data_key::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            tenant_id: Set(tenant_id.to_string()),
+            version: Set(previous.first().map_or(1, |k| k.version + 1)),
+            wrapped_key: Set(STANDARD.encode(&generated.wrapped)),
+            provider: Set(self.provider.name().to_string()),
+            master_key_id: Set(self.provider.master_key_id().to_string()),
+            status: Set(DataKeyStatus::Active),
+            remaining_values: Set(None),
+            created_at: Set(now),
+            retired_at: Set(None),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+        if let Some(current) = KEYRING.load_full() {
+            let mut keyring = Keyring::new(current.default_tenant.clone());
+            keyring.keys = current.keys.clone();
+            keyring.active = current.active.clone();
+            keyring.insert(tenant_id, key.id, &generated.plaintext, true)?;
+            KEYRING.store(Some(Arc::new(keyring)));
+        }
+        info!(tenant_id, version = key.version, "Created tenant data key");
+        Ok(key)
+    }
+
+    /// Re-encrypts the tenant's values held under retired keys with its active key. For
+    /// the default tenant, values stored before encryption was enabled are encrypted too.
+    pub async fn reencrypt(&self, tenant_id: &str, context: Option<&JobContext>) -> Result<RotationSummary, EncryptionError> {
+        let keyring = KEYRING
+            .load_full()
+            .ok_or_else(|| EncryptionError::Misconfigured("keyring is not loaded".to_string()))?;
+        let active = keyring
+            .active
+            .get(tenant_id)
+            .copied()
+            .ok_or_else(|| EncryptionError::Misconfigured(format!("tenant {} has no data key", tenant_id)))?;
+        let retired = TenantDataKey::find()
+            .filter(tenant_data_key::Column::TenantId.eq(tenant_id))
+            .filter(tenant_data_key::Column::Status.eq(DataKeyStatus::Retired))
+            .all(self.db.as_ref())
+            .await?;
+        let mut selectors: Vec<Option<String>> = retired.iter().map(|k| Some(format!("{}{}:%", PREFIX, k.id))).collect();
+        if tenant_id == self.config.default_tenant {
+            selectors.push(None);
+        }
+
+        let mut summary = RotationSummary { tenant_id: tenant_id.to_string(), key_id: active, reencrypted: 0 };
+        for (index, (table, column)) in ENCRYPTED_COLUMNS.iter().enumerate() {
+            for pattern in &selectors {
+                summary.reencrypted += self.reencrypt_column(&keyring, tenant_id, table, column, pattern.clone()).await?;
+            }
+            if let Some(context) = context {
+                let percent = ((index + 1) * 100 / ENCRYPTED_COLUMNS.len()) as i32;
+                context.report_progress(percent, format!("{}.{} re-encrypted", table, column)).await;
+            }
+        }
+        for key in retired {
+            let mut done: tenant_data_key::ActiveModel = key.into();
+            done.remaining_values = Set(Some(0));
+            done.update(self.db.as_ref()).await?;
+        }
+        info!(?summary, "Tenant values re-encrypted");
+        Ok(summary)
+    }
+
+    /// Rewrites one column's values matching `pattern`, or its plaintext values when
+    /// `pattern` is `None`, in batches. Rows changed meanwhile are left to the next run.
+    async fn reencrypt_column(
+        &self,
+        keyring: &Keyring,
+        tenant_id: &str,
+        table: &str,
+        column: &str,
+        pattern: Option<String>,
+    ) -> Result<u64, EncryptionError> {
+        let select = match pattern {
+            Some(_) => format!("SELECT id, {c} AS value FROM {t} WHERE {c} LIKE $1 LIMIT $2", t = table, c = column),
+            None => format!(
+                "SELECT id, {c} AS value FROM {t} WHERE {c} <> '' AND {c} NOT LIKE 'enc:%' AND $1::text IS NULL LIMIT $2",
+                t = table,
+                c = column
+            ),
+        };
+        let update = format!("UPDATE {t} SET {c} = $1 WHERE id = $2 AND {c} = $3", t = table, c = column);
+        let mut rewritten = 0;
+        loop {
+            let rows = StoredValue::find_by_statement(dialect::statement(
+                self.db.as_ref(),
+                &select,
+                [pattern.clone().into(), (self.config.rotation_batch_size.max(1) as i64).into()],
+            ))
+            .all(self.db.as_ref())
+            .await?;
+            if rows.is_empty() {
+                return Ok(rewritten);
+            }
+            let mut progressed = false;
+            for row in rows {
+                let plaintext = keyring.decrypt(&row.value)?;
+                let sealed = keyring
+                    .encrypt(tenant_id, &plaintext)
+                    .ok_or_else(|| EncryptionError::Misconfigured(format!("tenant {} has no data key", tenant_id)))?;
+                let result = self
+                    .db
+                    .execute(dialect::statement(
+                        self.db.as_ref(),
+                        &update,
+                        [sealed.into(), row.id.into(), row.value.into()],
+                    ))
+                    .await?;
+                if result.rows_affected() > 0 {
+                    rewritten += 1;
+                    progressed = true;
+                }
+            }
+            if !progressed {
+                return Ok(rewritten);
+            }
+        }
+    }
+
+    /// Tenants whose active key is older than the rotation interval.
+    async fn due_for_rotation(&self) -> Result<Vec<String>, EncryptionError> {
+        let Some(days) = self.config.rotation_interval_days else { return Ok(Vec::new()) };
+        let cutoff = Utc::now() - Duration::days(days.max(1));
+        Ok(TenantDataKey::find()
+            .filter(tenant_data_key::Column::Status.eq(DataKeyStatus::Active))
+            .filter(tenant_data_key::Column::CreatedAt.lt(cutoff))
+            .all(self.db.as_ref())
+            .await?
+            .into_iter()
+            .map(|k| k.tenant_id)
+            .collect())
+    }
+}
+
+/// Rotates a tenant's data key and re-encrypts its values as a job.
+pub async fn submit_rotation(
+    runner: &JobRunner,
+    service: Arc<EncryptionService>,
+    tenant_id: String,
+    created_by: Option<String>,
+) -> Result<Uuid, EncryptionError> {
+    let key = service.create_key(&tenant_id).await?;
+    let job_id = runner
+        .submit(ROTATION_JOB_KIND, created_by, move |context| async move {
+            let summary = service.reencrypt(&tenant_id, Some(&context)).await.map_err(|e| e.to_string())?;
+            serde_json::to_value(summary).map_err(|e| e.to_string())
+        })
+        .await?;
+    info!(tenant_id = %key.tenant_id, %job_id, "Data key rotation started");
+    Ok(job_id)
+}
+
+/// Rotates keys past the rotation interval, checking daily.
+pub fn spawn_scheduler(runner: Arc<JobRunner>, service: Arc<EncryptionService>) {
+    if service.config.rotation_interval_days.is_none() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(24 * 60 * 60));
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            let tenants = match service.due_for_rotation().await {
+                Ok(tenants) => tenants,
+                Err(e) => {
+                    error!("Checking data key age failed: {}", e);
+                    continue;
+                }
+            };
+            for tenant_id in tenants {
+                if let Err(e) = submit_rotation(&runner, service.clone(), tenant_id.clone(), Some("system".to_string())).await {
+                    error!(tenant_id, "Scheduled data key rotation failed: {}", e);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{self, AppState, AuthConfig};
+    use axum::{body::Body, middleware, routing::post, Router};
+    use tower::ServiceExt;
+
+    fn keyring() -> (Keyring, Uuid, Uuid) {
+        let (acme, fallback) = (Uuid::from_u128(1), Uuid::from_u128(2));
+        let mut keyring = Keyring::new("default".to_string());
+        keyring.insert("acme", acme, &[7u8; 32], true).unwrap();
+        keyring.insert("default", fallback, &[9u8; 32], true).unwrap();
+        (keyring, acme, fallback)
+    }
+
+    #[test]
+    fn test_values_round_trip_under_the_tenant_key() {
+        let (keyring, acme, _) = keyring();
+        let sealed = keyring.encrypt("acme", "12 Harbour Road").unwrap();
+        assert!(sealed.starts_with(&format!("enc:v1:{}:", acme)));
+        assert_ne!(sealed, keyring.encrypt("acme", "12 Harbour Road").unwrap());
+        assert_eq!(keyring.decrypt(&sealed).unwrap(), "12 Harbour Road");
+    }
+
+    #[test]
+    fn test_tenants_without_keys_use_the_default_key() {
+        let (keyring, _, fallback) = keyring();
+        assert_eq!(keyring.active_key("globex"), Some(fallback));
+    }
+
+    #[test]
+    fn test_values_are_bound_to_the_key_tenant() {
+        let cipher = Aes256Gcm::new_from_slice(&[7u8; 32]).unwrap();
+        let key_id = Uuid::from_u128(1);
+        let sealed = encrypt_with(&cipher, "acme", key_id, "12 Harbour Road");
+        let (_, body) = parse_envelope(&sealed).unwrap().unwrap();
+        assert_eq!(decrypt_with(&cipher, "acme", key_id, body).unwrap(), "12 Harbour Road");
+        assert!(matches!(decrypt_with(&cipher, "globex", key_id, body), Err(EncryptionError::Corrupt(_))));
+    }
+
+    #[test]
+    fn test_plaintext_passes_through_and_tampering_is_detected() {
+        let (keyring, acme, fallback) = keyring();
+        assert_eq!(keyring.decrypt("Ada Lovelace").unwrap(), "Ada Lovelace");
+        let sealed = keyring.encrypt("acme", "Ada Lovelace").unwrap();
+        let relabelled = sealed.replace(&acme.to_string(), &fallback.to_string());
+        assert!(matches!(keyring.decrypt(&relabelled), Err(EncryptionError::Corrupt(_))));
+        assert!(matches!(keyring.decrypt("enc:v1:garbage"), Err(EncryptionError::Corrupt(_))));
+        let unknown = sealed.replace(&acme.to_string(), &Uuid::from_u128(3).to_string());
+        assert!(matches!(keyring.decrypt(&unknown), Err(EncryptionError::UnknownKey(_))));
+    }
+
+    /// Seals a value inside a request authenticated with a token of `tenant_id`, the way
+    /// an entity write would.
+    async fn seal_as(keyring: Arc<Keyring>, tenant_id: Option<&str>) -> String {
+        let config = AuthConfig {
+            secret: "test_secret".to_string(),
+            issuer: "stateset-api".to_string(),
+            audience: "stateset-api".to_string(),
+            allowed_roles: ["user".to_string()].into_iter().collect(),
+            token_expiration: 3600,
+            secrets: Default::default(),
+        };
+        let token = auth::generate_token("user-7", "user", None, tenant_id, &config).unwrap();
+        let state = AppState { auth_config: Arc::new(config) };
+        let app = Router::new()
+            .route(
+                "/",
+                post(move || {
+                    let keyring = keyring.clone();
+                    async move { keyring.encrypt_for_current_tenant("12 Harbour Road").unwrap() }
+                }),
+            )
+            .layer(middleware::from_fn(tenant_scope_middleware))
+            .layer(middleware::from_fn_with_state(state, auth::auth_middleware));
+        let request = Request::post("/")
+            .header("Authorization", format!("Bearer {}", token))
+            .body(Body::empty())
+            .unwrap();
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        String::from_utf8(hyper::body::to_bytes(response.into_body()).await.unwrap().to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_requests_encrypt_with_the_key_of_the_token_tenant() {
+        let (mut keyring, acme, fallback) = keyring();
+        let globex = Uuid::from_u128(3);
+        keyring.insert("globex", globex, &[8u8; 32], true).unwrap();
+        let keyring = Arc::new(keyring);
+
+        let sealed = seal_as(keyring.clone(), Some("acme")).await;
+        assert!(sealed.starts_with(&format!("enc:v1:{}:", acme)));
+        assert_eq!(keyring.decrypt(&sealed).unwrap(), "12 Harbour Road");
+
+        // Another tenant's key opens neither the value nor a copy relabelled as its own
+        let (_, body) = parse_envelope(&sealed).unwrap().unwrap();
+        let globex_cipher = Aes256Gcm::new_from_slice(&[8u8; 32]).unwrap();
+        assert!(matches!(decrypt_with(&globex_cipher, "globex", acme, body), Err(EncryptionError::Corrupt(_))));
+        let relabelled = sealed.replace(&acme.to_string(), &globex.to_string());
+        assert!(matches!(keyring.decrypt(&relabelled), Err(EncryptionError::Corrupt(_))));
+
+        let untenanted = seal_as(keyring, None).await;
+        assert!(untenanted.starts_with(&format!("enc:v1:{}:", fallback)));
+    }
+}
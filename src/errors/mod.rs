@@ -68,6 +68,53 @@ impl IntoResponse for ApiError {
     }
 }
 
+impl ApiError {
+    /// Message catalog key for the error's headline.
+    pub fn message_key(&self) -> &'static str {
+        match self {
+            ApiError::InternalServerError => "errors.internal_server_error",
+            ApiError::BadRequest(_) => "errors.bad_request",
+            ApiError::Unauthorized => "errors.unauthorized",
+            ApiError::Forbidden => "errors.forbidden",
+            ApiError::NotFound => "errors.not_found",
+            ApiError::UnprocessableEntity(_) => "errors.unprocessable_entity",
+            ApiError::TooManyRequests => "errors.too_many_requests",
+            ApiError::ServiceUnavailable(_) => "errors.service_unavailable",
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::InternalServerError => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::Forbidden => StatusCode::FORBIDDEN,
+            ApiError::NotFound => StatusCode::NOT_FOUND,
+            ApiError::UnprocessableEntity(_) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::ServiceUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+
+    /// Like `into_response`, but with the headline translated for `locale`.
+    /// `details` stays untranslated since it carries developer-facing context.
+    pub fn into_localized_response(self, locale: crate::i18n::Locale) -> Response {
+        error!("API Error occurred: {:?}", self);
+
+        let body = Json(json!({
+            "error": crate::i18n::t(locale, self.message_key(), &[]),
+            "details": self.to_string(),
+        }));
+
+        let mut response = (self.status_code(), body).into_response();
+        response.headers_mut().insert(
+            axum::http::header::CONTENT_LANGUAGE,
+            axum::http::HeaderValue::from_static(locale.tag()),
+        );
+        response
+    }
+}
+
 // Helper function to convert any error into an ApiError
 pub fn handle_error<E>(err: E) -> ApiError
 where
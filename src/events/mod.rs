@@ -43,6 +43,18 @@ pub enum Event {
     ShipmentUpdated(Uuid),
     ShipmentTracked(Uuid),
     InventoryAdjusted { product_id: Uuid, adjustment: i32 },
+    InventoryLevelChanged { warehouse_id: i32, sku: String, available: i32 },
+    ReservationExpired {
+        reservation_id: Uuid,
+        reference_id: Uuid,
+        reference_type: String,
+        warehouse_id: String,
+        product_id: Uuid,
+        quantity: i32,
+    },
+    ReservationsConverted { from_reference_id: Uuid, order_id: Uuid, reservations: usize },
+    /// A catalog listing was created or changed; carries the SKU.
+    ProductListingChanged(String),
     WorkOrderCreated(Uuid),
     WorkOrderStarted(Uuid),
     WorkOrderUnassigned(Uuid),
@@ -53,6 +65,162 @@ pub enum Event {
     WorkOrderIssued(Uuid),
     WorkOrderPicked(Uuid),
     WorkOrderUpdatedYielded(Uuid),
+    /// Recorded labor or machine time changed a work order's actual conversion cost.
+    WorkOrderCostRolledUp {
+        work_order_id: Uuid,
+        labor_cost: rust_decimal::Decimal,
+        machine_cost: rust_decimal::Decimal,
+    },
+    /// Inspected units were moved to quarantine; `disposition` is `fail` or `quarantine`.
+    QualityHoldCreated {
+        inspection_id: Uuid,
+        sku: String,
+        warehouse: i32,
+        quantity: i32,
+        disposition: String,
+    },
+    /// Units held by an inspection were returned to the inventory row they came from.
+    QualityHoldReleased {
+        inspection_id: Uuid,
+        sku: String,
+        warehouse: i32,
+        quantity: i32,
+    },
+    /// Units were received or produced into an inventory row; quality opens an
+    /// inspection when a plan covers them.
+    LotReceived {
+        source: crate::models::inspection_plan::InspectionSource,
+        reference: String,
+        sku: String,
+        warehouse: i32,
+        inventory_item_id: String,
+        quantity: i32,
+    },
+    /// A requisition passed its last approval step and became a purchase order.
+    RequisitionApproved {
+        requisition_id: Uuid,
+        purchase_order_id: Uuid,
+    },
+    /// A credit memo became available to apply, on issue for a return or on approval.
+    CreditMemoIssued {
+        credit_memo_id: Uuid,
+        customer_id: Uuid,
+        amount: rust_decimal::Decimal,
+    },
+    /// Part of a credit memo was applied to an invoice or refunded.
+    CreditMemoApplied {
+        credit_memo_id: Uuid,
+        application_id: Uuid,
+        kind: String,
+        amount: rust_decimal::Decimal,
+    },
+    /// A gift card was sold or issued; its balance is owed to the holder.
+    GiftCardIssued {
+        gift_card_id: Uuid,
+        amount: rust_decimal::Decimal,
+    },
+    /// A shipment for an order left the warehouse. `amount` is the value of the goods in
+    /// it when an order ships in parts; `None` means the rest of the order.
+    ShipmentShipped {
+        order_id: Uuid,
+        tracking_number: String,
+        amount: Option<rust_decimal::Decimal>,
+    },
+    /// A subscription's recurring charge failed and retries were scheduled.
+    SubscriptionPastDue {
+        subscription_id: Uuid,
+        amount: rust_decimal::Decimal,
+    },
+    /// A retried recurring charge succeeded.
+    SubscriptionRecovered {
+        subscription_id: Uuid,
+        amount: rust_decimal::Decimal,
+    },
+    SubscriptionCanceled {
+        subscription_id: Uuid,
+        reason: String,
+    },
+    /// A payment provider reported a chargeback or inquiry.
+    DisputeOpened {
+        dispute_id: Uuid,
+        order_id: Option<Uuid>,
+        amount: rust_decimal::Decimal,
+    },
+    /// A dispute's evidence deadline is approaching with no response submitted.
+    DisputeEvidenceDue {
+        dispute_id: Uuid,
+        due_by: chrono::DateTime<chrono::Utc>,
+    },
+    DisputeClosed {
+        dispute_id: Uuid,
+        order_id: Option<Uuid>,
+        won: bool,
+        amount: rust_decimal::Decimal,
+    },
+    /// Funds were captured against a payment authorization.
+    PaymentCaptured {
+        order_id: Uuid,
+        authorization_id: Uuid,
+        capture_id: Uuid,
+        amount: rust_decimal::Decimal,
+    },
+    /// Gift card balance was spent on an order.
+    GiftCardRedeemed {
+        gift_card_id: Uuid,
+        order_id: Uuid,
+        amount: rust_decimal::Decimal,
+    },
+    /// Stock value changed outside a sale, e.g. a write-off or cost correction. `amount`
+    /// is signed: negative when value is lost.
+    InventoryRevalued {
+        sku: String,
+        warehouse: i32,
+        amount: rust_decimal::Decimal,
+        reason: String,
+    },
+    /// Inbound ASN units were routed to backorders or transfer orders, skipping put-away.
+    CrossDockPlanned {
+        asn_id: Uuid,
+        allocations: usize,
+        quantity: i32,
+    },
+    /// A kitting or de-kitting work order converted component and kit stock. `labor_cost`
+    /// was added to the value of the stock produced.
+    KitWorkOrderCompleted {
+        work_order_id: Uuid,
+        kit_sku: String,
+        kitted: bool,
+        quantity: i32,
+        labor_cost: rust_decimal::Decimal,
+    },
+    /// An ECO released its BOM revisions; work orders on superseded revisions were held.
+    EngineeringChangeImplemented {
+        eco_id: Uuid,
+        held_work_orders: usize,
+    },
+    /// A preventive or corrective maintenance work order was raised for a piece of equipment.
+    MaintenanceWorkOrderCreated {
+        work_order_id: Uuid,
+        equipment_id: Uuid,
+        preventive: bool,
+    },
+    /// A maintenance job finished; `downtime_hours` is the planned downtime it took, if any.
+    MaintenanceWorkOrderCompleted {
+        work_order_id: Uuid,
+        equipment_id: Uuid,
+        downtime_hours: f64,
+    },
+    /// A carrier accepted a shipment; its shipping label can be printed.
+    ShipmentBooked {
+        shipment_id: i32,
+        tracking_number: String,
+    },
+    /// A supplier confirmed shipping a package of a dropship order to the customer.
+    DropshipShipped {
+        order_id: Uuid,
+        dropship_order_id: Uuid,
+        tracking_number: String,
+    },
 }
 
 // Define a trait for handling events. Handlers implementing this trait will process events asynchronously.
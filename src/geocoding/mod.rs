@@ -0,0 +1,261 @@
+// geocoding/mod.rs
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use thiserror::Error;
+use validator::Validate;
+
+use crate::deadline;
+
+/// Which service turns addresses into coordinates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GeocoderBackend {
+    /// No geocoding; zones are matched on country, region and postal code only.
+    Disabled,
+    /// OpenStreetMap Nominatim, public or self-hosted.
+    Nominatim,
+}
+
+/// Geocoding settings, loaded from the `geocoding` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct GeocodingConfig {
+    #[serde(default = "default_backend")]
+    pub provider: GeocoderBackend,
+
+    #[serde(default = "default_nominatim_base_url")]
+    pub nominatim_base_url: String,
+
+    /// Sent as the User-Agent; the public Nominatim service requires one that identifies
+    /// the application.
+    #[serde(default = "default_user_agent")]
+    pub user_agent: String,
+}
+
+fn default_backend() -> GeocoderBackend {
+    GeocoderBackend::Disabled
+}
+
+fn default_nominatim_base_url() -> String {
+    "https://nominatim.openstreetmap.org".to_string()
+}
+
+fn default_user_agent() -> String {
+    concat!("stateset-api/", env!("CARGO_PKG_VERSION")).to_string()
+}
+
+impl Default for GeocodingConfig {
+    fn default() -> Self {
+        Self {
+            provider: default_backend(),
+            nominatim_base_url: default_nominatim_base_url(),
+            user_agent: default_user_agent(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum GeocodingError {
+    #[error("Geocoding is not configured: {0}")]
+    Misconfigured(String),
+
+    #[error("Geocoding request failed: {0}")]
+    Request(String),
+}
+
+/// A postal address as entered. `country` is an ISO 3166-1 alpha-2 code.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Validate)]
+pub struct Address {
+    #[validate(length(max = 255))]
+    pub line1: Option<String>,
+    #[validate(length(max = 255))]
+    pub line2: Option<String>,
+    #[validate(length(max = 100))]
+    pub city: Option<String>,
+    /// State, province or county.
+    #[validate(length(max = 100))]
+    pub region: Option<String>,
+    #[validate(length(max = 20))]
+    pub postal_code: Option<String>,
+    #[validate(length(equal = 2))]
+    pub country: String,
+}
+
+impl Address {
+    /// The address on one line, for display and as a cache key.
+    pub fn one_line(&self) -> String {
+        [&self.line1, &self.line2, &self.city, &self.region, &self.postal_code]
+            .into_iter()
+            .flatten()
+            .map(|part| part.trim())
+            .filter(|part| !part.is_empty())
+            .chain(std::iter::once(self.country.trim()))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Where an address is, and the region and postal code the geocoder settled on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Geocode {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub country: Option<String>,
+    pub region: Option<String>,
+    pub postal_code: Option<String>,
+}
+
+/// Looks up coordinates for an address; `None` when the address cannot be found.
+#[async_trait]
+pub trait Geocoder: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    async fn geocode(&self, address: &Address) -> Result<Option<Geocode>, GeocodingError>;
+}
+
+/// Calls the Nominatim structured search API.
+pub struct NominatimGeocoder {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl NominatimGeocoder {
+    pub fn from_config(config: &GeocodingConfig) -> Result<Self, GeocodingError> {
+        let client = reqwest::Client::builder()
+            .user_agent(config.user_agent.clone())
+            .build()
+            .map_err(|e| GeocodingError::Misconfigured(e.to_string()))?;
+        Ok(Self { client, base_url: config.nominatim_base_url.trim_end_matches('/').to_string() })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct NominatimPlace {
+    lat: String,
+    lon: String,
+    #[serde(default)]
+    address: NominatimAddress,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct NominatimAddress {
+    country_code: Option<String>,
+    state: Option<String>,
+    postcode: Option<String>,
+}
+
+fn parse_place(place: NominatimPlace) -> Result<Geocode, GeocodingError> {
+    let coordinate = |value: &str| {
+        value
+            .parse::<f64>()
+            .map_err(|_| GeocodingError::Request(format!("Invalid coordinate in response: {}", value)))
+    };
+    Ok(Geocode {
+        latitude: coordinate(&place.lat)?,
+        longitude: coordinate(&place.lon)?,
+        country: place.address.country_code.map(|c| c.to_ascii_uppercase()),
+        region: place.address.state,
+        postal_code: place.address.postcode,
+    })
+}
+
+#[async_trait]
+impl Geocoder for NominatimGeocoder {
+    fn name(&self) -> &'static str {
+        "nominatim"
+    }
+
+    async fn geocode(&self, address: &Address) -> Result<Option<Geocode>, GeocodingError> {
+        let street = [&address.line1, &address.line2]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let mut query = vec![
+            ("format", "jsonv2".to_string()),
+            ("addressdetails", "1".to_string()),
+            ("limit", "1".to_string()),
+            ("countrycodes", address.country.to_ascii_lowercase()),
+        ];
+        if !street.is_empty() {
+            query.push(("street", street));
+        }
+        for (key, value) in [("city", &address.city), ("state", &address.region), ("postalcode", &address.postal_code)] {
+            if let Some(value) = value {
+                query.push((key, value.clone()));
+            }
+        }
+        let fail = |e: reqwest::Error| GeocodingError::Request(e.to_string());
+        let request = self.client.get(format!("{}/search", self.base_url)).query(&query);
+        let places: Vec<NominatimPlace> = deadline::bounded(request)
+            .send()
+            .await
+            .map_err(fail)?
+            .error_for_status()
+            .map_err(fail)?
+            .json()
+            .await
+            .map_err(fail)?;
+        places.into_iter().next().map(parse_place).transpose()
+    }
+}
+
+/// Builds the geocoder selected in the config; `None` when geocoding is disabled.
+pub fn from_config(config: &GeocodingConfig) -> Result<Option<Arc<dyn Geocoder>>, GeocodingError> {
+    match config.provider {
+        GeocoderBackend::Disabled => Ok(None),
+        GeocoderBackend::Nominatim => Ok(Some(Arc::new(NominatimGeocoder::from_config(config)?))),
+    }
+}
+
+/// Great-circle distance in kilometres.
+pub fn distance_km(from: (f64, f64), to: (f64, f64)) -> f64 {
+    const EARTH_RADIUS_KM: f64 = 6371.0088;
+    let (lat1, lat2) = (from.0.to_radians(), to.0.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (to.1 - from.1).to_radians();
+    let h = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * h.sqrt().asin()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_line_skips_blank_parts() {
+        let address = Address {
+            line1: Some("1 Market St".to_string()),
+            line2: Some(" ".to_string()),
+            city: Some("San Francisco".to_string()),
+            region: Some("CA".to_string()),
+            postal_code: Some("94105".to_string()),
+            country: "US".to_string(),
+        };
+        assert_eq!(address.one_line(), "1 Market St, San Francisco, CA, 94105, US");
+    }
+
+    #[test]
+    fn test_parse_nominatim_place() {
+        let place: NominatimPlace = serde_json::from_value(serde_json::json!({
+            "lat": "37.7936",
+            "lon": "-122.3950",
+            "address": { "country_code": "us", "state": "California", "postcode": "94105" }
+        }))
+        .unwrap();
+        let geocode = parse_place(place).unwrap();
+        assert_eq!(geocode.country.as_deref(), Some("US"));
+        assert!((geocode.latitude - 37.7936).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_distance_km() {
+        let london = (51.5074, -0.1278);
+        let paris = (48.8566, 2.3522);
+        let km = distance_km(london, paris);
+        assert!((km - 343.5).abs() < 2.0, "{}", km);
+        assert_eq!(distance_km(paris, paris), 0.0);
+    }
+}
@@ -0,0 +1,84 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::abuse::{AbuseDetector, AbuseError, NewBlock};
+use crate::auth::{admin_only, AuthUser};
+
+const MAX_FINGERPRINTS: usize = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct FingerprintQuery {
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+fn default_limit() -> usize {
+    50
+}
+
+/// Tracked fingerprints, most active first.
+async fn list_fingerprints(
+    State(detector): State<Arc<AbuseDetector>>,
+    Query(query): Query<FingerprintQuery>,
+    AuthUser(claims): AuthUser,
+) -> Response {
+    if let Some(response) = admin_only(&claims) {
+        return response;
+    }
+    let items = detector.top_fingerprints(query.limit.clamp(1, MAX_FINGERPRINTS));
+    Json(json!({ "items": items })).into_response()
+}
+
+async fn list_blocks(
+    State(detector): State<Arc<AbuseDetector>>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, AbuseError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let items = detector.list_blocks().await?;
+    Ok(Json(json!({ "items": items })).into_response())
+}
+
+async fn add_block(
+    State(detector): State<Arc<AbuseDetector>>,
+    AuthUser(claims): AuthUser,
+    Json(block): Json<NewBlock>,
+) -> Result<Response, AbuseError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let block = detector.add_block(block, &claims.actor()).await?;
+    Ok((StatusCode::CREATED, Json(block)).into_response())
+}
+
+async fn remove_block(
+    State(detector): State<Arc<AbuseDetector>>,
+    Path(id): Path<i32>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, AbuseError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let block = detector.remove_block(id, &claims.actor()).await?;
+    Ok(Json(block).into_response())
+}
+
+pub fn abuse_routes<S>(detector: Arc<AbuseDetector>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/fingerprints", get(list_fingerprints))
+        .route("/blocklist", get(list_blocks).post(add_block))
+        .route("/blocklist/:id", delete(remove_block))
+        .with_state(detector)
+}
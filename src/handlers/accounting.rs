@@ -0,0 +1,129 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::integrations::accounting::{AccountingError, AccountingExporter, ExportFilter};
+use crate::models::accounting_export::DocumentKind;
+use crate::utils::pagination::PaginationParams;
+
+type Exporter = Option<Arc<AccountingExporter>>;
+
+#[derive(Debug, Deserialize)]
+struct DocumentRef {
+    document_kind: DocumentKind,
+    document_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReconciliationParams {
+    from: NaiveDate,
+    to: NaiveDate,
+}
+
+async fn list_exports(
+    State(exporter): State<Exporter>,
+    Query(filter): Query<ExportFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, AccountingError> {
+    if let Some(response) = forbidden(&claims, "accounting:read") {
+        return Ok(response);
+    }
+    let exporter = exporter.ok_or(AccountingError::Disabled)?;
+    let (items, total) = exporter.list(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+/// Queues a document by hand: invoices, which have no lifecycle events here, and documents
+/// missed while the recorder was down. Already-queued documents are returned as is.
+async fn queue_export(
+    State(exporter): State<Exporter>,
+    AuthUser(claims): AuthUser,
+    Json(document): Json<DocumentRef>,
+) -> Result<Response, AccountingError> {
+    if let Some(response) = forbidden(&claims, "accounting:write") {
+        return Ok(response);
+    }
+    let exporter = exporter.ok_or(AccountingError::Disabled)?;
+    let export = exporter.enqueue_document(document.document_kind, &document.document_id).await?;
+    Ok((StatusCode::ACCEPTED, Json(export)).into_response())
+}
+
+async fn get_export(
+    State(exporter): State<Exporter>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, AccountingError> {
+    if let Some(response) = forbidden(&claims, "accounting:read") {
+        return Ok(response);
+    }
+    let exporter = exporter.ok_or(AccountingError::Disabled)?;
+    Ok(Json(exporter.get(id).await?).into_response())
+}
+
+async fn retry_export(
+    State(exporter): State<Exporter>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, AccountingError> {
+    if let Some(response) = forbidden(&claims, "accounting:write") {
+        return Ok(response);
+    }
+    let exporter = exporter.ok_or(AccountingError::Disabled)?;
+    Ok(Json(exporter.retry(id).await?).into_response())
+}
+
+/// Reconciliation status of one document.
+async fn document_status(
+    State(exporter): State<Exporter>,
+    Path((kind, id)): Path<(DocumentKind, String)>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, AccountingError> {
+    if let Some(response) = forbidden(&claims, "accounting:read") {
+        return Ok(response);
+    }
+    let exporter = exporter.ok_or(AccountingError::Disabled)?;
+    Ok(Json(exporter.for_document(kind, &id).await?).into_response())
+}
+
+async fn reconciliation(
+    State(exporter): State<Exporter>,
+    Query(params): Query<ReconciliationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, AccountingError> {
+    if let Some(response) = forbidden(&claims, "accounting:read") {
+        return Ok(response);
+    }
+    let exporter = exporter.ok_or(AccountingError::Disabled)?;
+    let rows = exporter.reconciliation(params.from, params.to).await?;
+    Ok(Json(json!({ "from": params.from, "to": params.to, "rows": rows })).into_response())
+}
+
+pub fn accounting_routes<S>(exporter: Option<Arc<AccountingExporter>>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/exports", get(list_exports).post(queue_export))
+        .route("/exports/:id", get(get_export))
+        .route("/exports/:id/retry", post(retry_export))
+        .route("/documents/:kind/:id", get(document_status))
+        .route("/reconciliation", get(reconciliation))
+        .with_state(exporter)
+}
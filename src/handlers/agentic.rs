@@ -0,0 +1,44 @@
+use axum::{
+    extract::{Query, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::product_feed::{parse_updated_since, FeedError, ProductFeedService};
+use crate::utils::pagination::PaginationParams;
+
+#[derive(Debug, Deserialize)]
+pub struct FeedParams {
+    /// RFC 3339 timestamp; usually the `generated_at` of the previous fetch.
+    pub updated_since: Option<String>,
+}
+
+/// Serves the product catalog in the agentic-commerce feed format. Without
+/// `updated_since` this is the full feed of active products; with it, only products
+/// whose listing or stock changed since then, including delisted ones.
+async fn get_product_feed(
+    State(feed): State<Arc<ProductFeedService>>,
+    Query(params): Query<FeedParams>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, FeedError> {
+    if let Some(response) = forbidden(&claims, "catalog:read") {
+        return Ok(response);
+    }
+    let updated_since = params.updated_since.as_deref().map(parse_updated_since).transpose()?;
+    let page = feed.feed(updated_since, pagination).await?;
+    Ok(Json(page).into_response())
+}
+
+pub fn agentic_routes<S>(feed: Arc<ProductFeedService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/product-feed", get(get_product_feed))
+        .with_state(feed)
+}
@@ -0,0 +1,84 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::agents::{AgentError, AgentRegistry};
+use crate::auth::{admin_only, AuthUser};
+use crate::utils::pagination::PaginationParams;
+
+#[derive(Debug, Deserialize)]
+pub struct DecisionFilter {
+    pub agent: Option<String>,
+    pub subject: Option<String>,
+}
+
+/// Health of every registered agent.
+async fn list_agents(
+    State(registry): State<Arc<AgentRegistry>>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, AgentError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    Ok(Json(json!({ "agents": registry.health() })).into_response())
+}
+
+async fn pause_agent(
+    State(registry): State<Arc<AgentRegistry>>,
+    Path(name): Path<String>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, AgentError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    Ok(Json(registry.set_paused(&name, true, &claims.actor())?).into_response())
+}
+
+async fn resume_agent(
+    State(registry): State<Arc<AgentRegistry>>,
+    Path(name): Path<String>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, AgentError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    Ok(Json(registry.set_paused(&name, false, &claims.actor())?).into_response())
+}
+
+/// Audit trail of agent decisions, newest first.
+async fn list_decisions(
+    State(registry): State<Arc<AgentRegistry>>,
+    Query(filter): Query<DecisionFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, AgentError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let (decisions, total) = registry.decisions(filter.agent, filter.subject, pagination).await?;
+    Ok(Json(json!({
+        "decisions": decisions,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+pub fn agent_routes<S>(registry: Arc<AgentRegistry>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_agents))
+        .route("/decisions", get(list_decisions))
+        .route("/:name/pause", post(pause_agent))
+        .route("/:name/resume", post(resume_agent))
+        .with_state(registry)
+}
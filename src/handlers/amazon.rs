@@ -0,0 +1,132 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::auth::{forbidden, AuthUser, Claims};
+use crate::integrations::amazon::{self, AmazonError, AmazonOrderFilter, AmazonSync, ListingInput, SyncKind};
+use crate::jobs::JobRunner;
+use crate::utils::pagination::PaginationParams;
+
+#[derive(Clone)]
+pub struct AmazonRoutesState {
+    pub sync: Option<Arc<AmazonSync>>,
+    pub jobs: Arc<JobRunner>,
+}
+
+impl AmazonRoutesState {
+    fn sync(&self) -> Result<Arc<AmazonSync>, AmazonError> {
+        self.sync.clone().ok_or(AmazonError::Disabled)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ListingParams {
+    sku: Option<String>,
+}
+
+async fn list_listings(
+    State(state): State<AmazonRoutesState>,
+    Query(params): Query<ListingParams>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, AmazonError> {
+    if let Some(response) = forbidden(&claims, "amazon:read") {
+        return Ok(response);
+    }
+    let (items, total) = state.sync()?.list_listings(params.sku, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+/// Maps a seller SKU to one of our SKUs. Mappings learned during sync are overwritten.
+async fn put_listing(
+    State(state): State<AmazonRoutesState>,
+    Path(seller_sku): Path<String>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<ListingInput>,
+) -> Result<Response, AmazonError> {
+    if let Some(response) = forbidden(&claims, "amazon:write") {
+        return Ok(response);
+    }
+    let listing = state.sync()?.set_listing(&seller_sku, input, &claims.actor()).await?;
+    info!("Amazon listing {} mapped to {} by {}", seller_sku, listing.sku, claims.actor());
+    Ok(Json(listing).into_response())
+}
+
+async fn delete_listing(
+    State(state): State<AmazonRoutesState>,
+    Path(seller_sku): Path<String>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, AmazonError> {
+    if let Some(response) = forbidden(&claims, "amazon:write") {
+        return Ok(response);
+    }
+    state.sync()?.remove_listing(&seller_sku).await?;
+    info!("Amazon listing {} removed by {}", seller_sku, claims.actor());
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn list_orders(
+    State(state): State<AmazonRoutesState>,
+    Query(filter): Query<AmazonOrderFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, AmazonError> {
+    if let Some(response) = forbidden(&claims, "amazon:read") {
+        return Ok(response);
+    }
+    let (items, total) = state.sync()?.list_orders(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+async fn start_sync(state: AmazonRoutesState, kind: SyncKind, claims: Claims) -> Result<Response, AmazonError> {
+    if let Some(response) = forbidden(&claims, "amazon:write") {
+        return Ok(response);
+    }
+    let job_id = amazon::submit_sync(&state.jobs, state.sync()?, kind, Some(claims.actor())).await?;
+    Ok((StatusCode::ACCEPTED, Json(json!({ "job_id": job_id }))).into_response())
+}
+
+/// Runs an order sync now instead of waiting for the schedule.
+async fn sync_orders(State(state): State<AmazonRoutesState>, AuthUser(claims): AuthUser) -> Result<Response, AmazonError> {
+    start_sync(state, SyncKind::Orders, claims).await
+}
+
+async fn sync_inventory(
+    State(state): State<AmazonRoutesState>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, AmazonError> {
+    start_sync(state, SyncKind::Inventory, claims).await
+}
+
+pub fn amazon_routes<S>(sync: Option<Arc<AmazonSync>>, jobs: Arc<JobRunner>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/listings", get(list_listings))
+        .route("/listings/:seller_sku", put(put_listing).delete(delete_listing))
+        .route("/orders", get(list_orders))
+        .route("/sync/orders", post(sync_orders))
+        .route("/sync/inventory", post(sync_inventory))
+        .with_state(AmazonRoutesState { sync, jobs })
+}
@@ -0,0 +1,152 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::cache::{swr::ReportCache, RedisCache};
+use crate::errors::ServiceError;
+use crate::services::inventory_aging::InventoryAgingService;
+use crate::services::supplier_scorecard::{SupplierScorecardService, TrendInterval};
+use crate::shipment_sla::{ShipmentSlaService, SlaError};
+
+#[derive(Debug, Deserialize)]
+pub struct SlaParams {
+    /// First ship date included, `YYYY-MM-DD`; defaults to 30 days before `to`.
+    pub from: Option<NaiveDate>,
+    /// Last ship date included; defaults to today.
+    pub to: Option<NaiveDate>,
+    /// Warehouse whose time zone and calendar the dates are in; the default calendar
+    /// when unset.
+    pub warehouse: Option<String>,
+}
+
+/// Carrier SLA compliance for shipments shipped in the range: on-time and late
+/// deliveries against the expected date, and shipments still overdue in transit.
+async fn shipment_sla(
+    State((sla, reports)): State<(Arc<ShipmentSlaService>, Arc<ReportCache<RedisCache>>)>,
+    Query(params): Query<SlaParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, SlaError> {
+    if !claims.is_allowed("shipments:read") {
+        return Err(SlaError::Forbidden);
+    }
+    let to = params.to.unwrap_or_else(|| sla.calendar(params.warehouse.as_deref()).local_date(Utc::now()));
+    let from = params.from.unwrap_or(to - Duration::days(30));
+    let warehouse = params.warehouse;
+    let key = format!("{}:{}:{}", from, to, warehouse.as_deref().unwrap_or_default());
+    let report = reports
+        .report("shipment_sla", &key, move || async move {
+            Ok::<Value, SlaError>(json!(sla.report(from, to, warehouse.as_deref()).await?))
+        })
+        .await?;
+    Ok(report.into_response())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScorecardParams {
+    /// First receipt date included; defaults to 90 days before `to`.
+    pub from: Option<NaiveDate>,
+    /// Last receipt date included; defaults to today.
+    pub to: Option<NaiveDate>,
+    /// Trend granularity, `week` or `month` (the default).
+    #[serde(default)]
+    pub interval: TrendInterval,
+    /// Warehouse whose time zone dates and buckets receipts; the default calendar when
+    /// unset.
+    pub warehouse: Option<String>,
+}
+
+/// On-time delivery, fill rate and quality rejection rate for one supplier, overall and
+/// per week or month.
+async fn supplier_scorecard(
+    State((scorecards, reports)): State<(Arc<SupplierScorecardService>, Arc<ReportCache<RedisCache>>)>,
+    Path(supplier_id): Path<Uuid>,
+    Query(params): Query<ScorecardParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "suppliers:read") {
+        return Ok(response);
+    }
+    let to = params.to.unwrap_or_else(|| scorecards.calendar(params.warehouse.as_deref()).local_date(Utc::now()));
+    let from = params.from.unwrap_or(to - Duration::days(90));
+    let interval = params.interval;
+    let warehouse = params.warehouse;
+    let key = format!("{}:{}:{}:{:?}:{}", supplier_id, from, to, interval, warehouse.as_deref().unwrap_or_default());
+    let report = reports
+        .report("supplier_scorecard", &key, move || async move {
+            let scorecard = scorecards.scorecard(supplier_id, from, to, interval, warehouse.as_deref()).await?;
+            Ok::<Value, ServiceError>(json!(scorecard))
+        })
+        .await?;
+    Ok(report.into_response())
+}
+
+/// Most SKUs and suggestions an aging report lists.
+const MAX_AGING_LIMIT: usize = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct AgingParams {
+    /// Date lot ages are counted to; defaults to today.
+    pub as_of: Option<NaiveDate>,
+    /// Only stock in this warehouse; all warehouses when unset.
+    pub warehouse: Option<i32>,
+    /// SKUs and suggestions listed (default: 100, at most 500).
+    pub limit: Option<usize>,
+}
+
+/// On-hand stock bucketed by receipt age (0-30, 31-60, 61-90, 91-180 and 180+ days), its
+/// obsolescence exposure by value, and markdown, liquidation or write-off suggestions
+/// for stock beyond the configured days of demand.
+async fn inventory_aging(
+    State((aging, reports)): State<(Arc<InventoryAgingService>, Arc<ReportCache<RedisCache>>)>,
+    Query(params): Query<AgingParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "inventory:read") {
+        return Ok(response);
+    }
+    let as_of = params.as_of.unwrap_or_else(|| aging.calendar().local_date(Utc::now()));
+    let warehouse = params.warehouse;
+    let limit = params.limit.unwrap_or(100).clamp(1, MAX_AGING_LIMIT);
+    let key = format!("{}:{}:{}", as_of, warehouse.map(|w| w.to_string()).unwrap_or_default(), limit);
+    let report = reports
+        .report("inventory_aging", &key, move || async move {
+            Ok::<Value, ServiceError>(json!(aging.report(as_of, warehouse, limit).await?))
+        })
+        .await?;
+    Ok(report.into_response())
+}
+
+/// Analytics routes; reports are served through `reports`, which adds the
+/// `x-data-freshness` and `Age` headers.
+pub fn analytics_routes<S>(
+    sla: Arc<ShipmentSlaService>,
+    scorecards: Arc<SupplierScorecardService>,
+    aging: Arc<InventoryAgingService>,
+    reports: Arc<ReportCache<RedisCache>>,
+) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/shipments/sla", get(shipment_sla))
+        .with_state((sla, reports.clone()))
+        .merge(
+            Router::new()
+                .route("/suppliers/:id/scorecard", get(supplier_scorecard))
+                .with_state((scorecards, reports.clone())),
+        )
+        .merge(
+            Router::new()
+                .route("/inventory/aging", get(inventory_aging))
+                .with_state((aging, reports)),
+        )
+}
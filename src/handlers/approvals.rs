@@ -0,0 +1,98 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::models::approval_step::ApprovalSubject;
+use crate::workflow::{ApprovalEngine, DecisionInput};
+
+#[derive(Debug, Deserialize)]
+struct ApprovalRequest {
+    subject: ApprovalSubject,
+    subject_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SubjectQuery {
+    subject: ApprovalSubject,
+    subject_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DelegateRequest {
+    delegate: String,
+}
+
+/// Submits a record for approval through its subject's chain. Requisitions are usually
+/// submitted with `POST /api/v1/requisitions/:id/submit`, which does the same.
+async fn request_approval(
+    State(engine): State<Arc<ApprovalEngine>>,
+    AuthUser(claims): AuthUser,
+    Json(request): Json<ApprovalRequest>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "approvals:request") {
+        return Ok(response);
+    }
+    let steps = engine.request(request.subject, &request.subject_id, &claims.actor()).await?;
+    Ok((StatusCode::CREATED, Json(json!({ "steps": steps }))).into_response())
+}
+
+/// Approval history of one record, e.g. `?subject=return&subject_id=<uuid>`.
+async fn list_steps(
+    State(engine): State<Arc<ApprovalEngine>>,
+    Query(query): Query<SubjectQuery>,
+    AuthUser(_claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    Ok(Json(engine.steps(query.subject, &query.subject_id).await?).into_response())
+}
+
+/// Steps waiting on the caller, assigned by name or to the caller's role.
+async fn pending(
+    State(engine): State<Arc<ApprovalEngine>>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    Ok(Json(engine.pending_for(&claims).await?).into_response())
+}
+
+async fn decide(
+    State(engine): State<Arc<ApprovalEngine>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<DecisionInput>,
+) -> Result<Response, ServiceError> {
+    let decision = input.decision;
+    let step = engine.decide(id, &claims, input).await?;
+    info!("Approval step {} decided ({:?}) by {}", id, decision, claims.actor());
+    Ok(Json(step).into_response())
+}
+
+async fn delegate(
+    State(engine): State<Arc<ApprovalEngine>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(request): Json<DelegateRequest>,
+) -> Result<Response, ServiceError> {
+    Ok(Json(engine.delegate(id, &claims, &request.delegate).await?).into_response())
+}
+
+pub fn approval_routes<S>(engine: Arc<ApprovalEngine>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_steps).post(request_approval))
+        .route("/pending", get(pending))
+        .route("/:id/decision", post(decide))
+        .route("/:id/delegate", post(delegate))
+        .with_state(engine)
+}
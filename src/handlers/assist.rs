@@ -0,0 +1,32 @@
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use std::sync::Arc;
+
+use crate::assist::{AssistError, AssistService, ChatRequest};
+use crate::auth::AuthUser;
+
+/// Answers a support question from the caller's own orders, shipments and returns, or,
+/// for staff, from the order or customer named in the request. Cited records are
+/// returned alongside the answer.
+async fn chat(
+    State(assist): State<Option<Arc<AssistService>>>,
+    AuthUser(claims): AuthUser,
+    Json(request): Json<ChatRequest>,
+) -> Result<Response, AssistError> {
+    let assist = assist.ok_or(AssistError::Disabled)?;
+    let response = assist.chat(&claims, request).await?;
+    Ok(Json(response).into_response())
+}
+
+pub fn assist_routes<S>(assist: Option<Arc<AssistService>>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/chat", post(chat))
+        .with_state(assist)
+}
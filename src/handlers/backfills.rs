@@ -0,0 +1,120 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::auth::{admin_only, AuthUser};
+use crate::backfill::{self, BackfillError, BackfillService};
+use crate::jobs::JobRunner;
+
+#[derive(Clone)]
+pub struct BackfillRoutesState {
+    pub service: Arc<BackfillService>,
+    pub db: Arc<DatabaseConnection>,
+    pub jobs: Arc<JobRunner>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct StartBackfillRequest {
+    /// Starts over from the first row instead of the saved cursor.
+    #[serde(default)]
+    pub restart: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReindexRequest {
+    pub index: String,
+}
+
+/// Registered backfills with their progress.
+async fn list_backfills(
+    State(state): State<BackfillRoutesState>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, BackfillError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let backfills = state.service.list().await?;
+    Ok(Json(json!({ "items": backfills })).into_response())
+}
+
+async fn get_backfill(
+    State(state): State<BackfillRoutesState>,
+    AuthUser(claims): AuthUser,
+    Path(name): Path<String>,
+) -> Result<Response, BackfillError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    Ok(Json(state.service.get(&name).await?).into_response())
+}
+
+/// Starts a backfill, or resumes a paused or failed one from its cursor.
+async fn start_backfill(
+    State(state): State<BackfillRoutesState>,
+    AuthUser(claims): AuthUser,
+    Path(name): Path<String>,
+    body: Option<Json<StartBackfillRequest>>,
+) -> Result<Response, BackfillError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let restart = body.map_or(false, |Json(body)| body.restart);
+    let job_id =
+        backfill::submit(&state.jobs, state.service.clone(), name.clone(), restart, Some(claims.actor())).await?;
+    info!("Backfill {} started by {}", name, claims.actor());
+    Ok((StatusCode::ACCEPTED, Json(json!({ "job_id": job_id }))).into_response())
+}
+
+/// Stops a running backfill after its current chunk; starting it again resumes.
+async fn pause_backfill(
+    State(state): State<BackfillRoutesState>,
+    AuthUser(claims): AuthUser,
+    Path(name): Path<String>,
+) -> Result<Response, BackfillError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    state.service.pause(&name).await?;
+    info!("Backfill {} paused by {}", name, claims.actor());
+    Ok((StatusCode::ACCEPTED, Json(json!({ "name": name, "status": "pausing" }))).into_response())
+}
+
+/// Rebuilds an index concurrently as a job.
+async fn reindex(
+    State(state): State<BackfillRoutesState>,
+    AuthUser(claims): AuthUser,
+    Json(body): Json<ReindexRequest>,
+) -> Result<Response, BackfillError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let job_id = backfill::submit_reindex(&state.jobs, state.db.clone(), body.index.clone(), Some(claims.actor())).await?;
+    info!("Reindex of {} requested by {}", body.index, claims.actor());
+    Ok((StatusCode::ACCEPTED, Json(json!({ "job_id": job_id }))).into_response())
+}
+
+pub fn backfill_routes<S>(
+    service: Arc<BackfillService>,
+    db: Arc<DatabaseConnection>,
+    jobs: Arc<JobRunner>,
+) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_backfills))
+        .route("/reindex", post(reindex))
+        .route("/:name", get(get_backfill))
+        .route("/:name/start", post(start_backfill))
+        .route("/:name/pause", post(pause_backfill))
+        .with_state(BackfillRoutesState { service, db, jobs })
+}
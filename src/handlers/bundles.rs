@@ -0,0 +1,103 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::bundle_service::{BundleService, FulfillmentLine, NewBundle};
+
+#[derive(Debug, Deserialize)]
+pub struct AvailabilityParams {
+    pub warehouse: Option<i32>,
+}
+
+async fn create_bundle(
+    State(bundles): State<Arc<BundleService>>,
+    AuthUser(claims): AuthUser,
+    Json(new_bundle): Json<NewBundle>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "bundles:write") {
+        return Ok(response);
+    }
+    let created = bundles.create_bundle(new_bundle).await?;
+    info!("Bundle {} created by {}", created.bundle.sku, claims.actor());
+    Ok((StatusCode::CREATED, Json(created)).into_response())
+}
+
+async fn list_bundles(
+    State(bundles): State<Arc<BundleService>>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "bundles:read") {
+        return Ok(response);
+    }
+    Ok(Json(bundles.list_bundles().await?).into_response())
+}
+
+async fn get_bundle(
+    State(bundles): State<Arc<BundleService>>,
+    Path(sku): Path<String>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "bundles:read") {
+        return Ok(response);
+    }
+    Ok(Json(bundles.get_by_sku(&sku).await?).into_response())
+}
+
+async fn deactivate_bundle(
+    State(bundles): State<Arc<BundleService>>,
+    Path(sku): Path<String>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "bundles:write") {
+        return Ok(response);
+    }
+    bundles.deactivate(&sku).await?;
+    info!("Bundle {} deactivated by {}", sku, claims.actor());
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// Sellable quantity, computed from the limiting component.
+async fn get_availability(
+    State(bundles): State<Arc<BundleService>>,
+    Path(sku): Path<String>,
+    Query(params): Query<AvailabilityParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "bundles:read") {
+        return Ok(response);
+    }
+    Ok(Json(bundles.availability(&sku, params.warehouse).await?).into_response())
+}
+
+/// Turns order lines into pick lines, exploding bundles into their components.
+async fn explode_pick_lines(
+    State(bundles): State<Arc<BundleService>>,
+    AuthUser(claims): AuthUser,
+    Json(lines): Json<Vec<FulfillmentLine>>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "bundles:read") {
+        return Ok(response);
+    }
+    Ok(Json(bundles.pick_lines(&lines).await?).into_response())
+}
+
+pub fn bundle_routes<S>(bundles: Arc<BundleService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_bundles).post(create_bundle))
+        .route("/pick_lines", post(explode_pick_lines))
+        .route("/:sku", get(get_bundle).delete(deactivate_bundle))
+        .route("/:sku/availability", get(get_availability))
+        .with_state(bundles)
+}
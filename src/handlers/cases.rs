@@ -0,0 +1,125 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::case_service::{CaseFilter, CaseLinks, CaseService, CaseTransition, NewCase, Triage};
+use crate::utils::pagination::PaginationParams;
+
+/// Cases soonest due first, e.g. `?assignee=alex&breached=true` or `?order_id=...`.
+async fn list_cases(
+    State(cases): State<Arc<CaseService>>,
+    Query(filter): Query<CaseFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "cases:read") {
+        return Ok(response);
+    }
+    let (items, total) = cases.list(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+/// Opens a case; SLA due times follow its priority.
+async fn create_case(
+    State(cases): State<Arc<CaseService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewCase>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "cases:write") {
+        return Ok(response);
+    }
+    let case = cases.create(input, &claims.actor()).await?;
+    info!("Case {} opened by {}", case.case.id, claims.actor());
+    Ok((StatusCode::CREATED, Json(case)).into_response())
+}
+
+async fn get_case(
+    State(cases): State<Arc<CaseService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "cases:read") {
+        return Ok(response);
+    }
+    Ok(Json(cases.get(id).await?).into_response())
+}
+
+async fn transition_case(
+    State(cases): State<Arc<CaseService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(transition): Json<CaseTransition>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "cases:write") {
+        return Ok(response);
+    }
+    let case = cases.transition(id, transition).await?;
+    info!("Case {} moved to {:?} by {}", id, case.case.status, claims.actor());
+    Ok(Json(case).into_response())
+}
+
+async fn triage_case(
+    State(cases): State<Arc<CaseService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(triage): Json<Triage>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "cases:write") {
+        return Ok(response);
+    }
+    Ok(Json(cases.triage(id, triage).await?).into_response())
+}
+
+async fn link_case(
+    State(cases): State<Arc<CaseService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(links): Json<CaseLinks>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "cases:write") {
+        return Ok(response);
+    }
+    Ok(Json(cases.link(id, links).await?).into_response())
+}
+
+/// Records that the customer got a reply, stopping the first-response timer.
+async fn record_response(
+    State(cases): State<Arc<CaseService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "cases:write") {
+        return Ok(response);
+    }
+    Ok(Json(cases.record_response(id).await?).into_response())
+}
+
+pub fn case_routes<S>(cases: Arc<CaseService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_cases).post(create_case))
+        .route("/:id", get(get_case))
+        .route("/:id/status", post(transition_case))
+        .route("/:id/triage", put(triage_case))
+        .route("/:id/links", put(link_case))
+        .route("/:id/responses", post(record_response))
+        .with_state(cases)
+}
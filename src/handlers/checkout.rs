@@ -0,0 +1,135 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser, Claims};
+use crate::checkout::{CheckoutError, CheckoutSessionPatch, CheckoutSessionStore, NewCheckoutSession};
+use crate::models::checkout_session::{self, CheckoutStatus};
+use crate::utils::pagination::PaginationParams;
+
+#[derive(Debug, Deserialize)]
+pub struct CustomerSessionsParams {
+    pub customer_id: i32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompleteRequest {
+    pub order_id: Uuid,
+    /// Amount charged for the order, shipping and tax included. Required when the
+    /// session pays with a vault token, which is redeemed for this amount.
+    #[serde(default, with = "crate::money::option_amount")]
+    pub amount: Option<Decimal>,
+}
+
+/// Sessions are visible to the actor that opened them, to admins and to integrations
+/// holding `checkout:write` (e.g. the checkout agent resuming on another device).
+/// Others get a 404 so session ids cannot be probed.
+fn ensure_access(claims: &Claims, session: &checkout_session::Model) -> Result<(), CheckoutError> {
+    if claims.is_allowed("checkout:write") || session.created_by == claims.actor() {
+        Ok(())
+    } else {
+        Err(CheckoutError::NotFound(session.id))
+    }
+}
+
+async fn create_session(
+    State(store): State<Arc<CheckoutSessionStore>>,
+    AuthUser(claims): AuthUser,
+    Json(request): Json<NewCheckoutSession>,
+) -> Result<Response, CheckoutError> {
+    let session = store.create(request, claims.actor()).await?;
+    Ok((StatusCode::CREATED, Json(session)).into_response())
+}
+
+async fn get_session(
+    State(store): State<Arc<CheckoutSessionStore>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, CheckoutError> {
+    let session = store.get(id).await?;
+    ensure_access(&claims, &session)?;
+    Ok(Json(session).into_response())
+}
+
+/// Lists a customer's sessions for support and audit. Admins and `checkout:read` only.
+async fn list_sessions(
+    State(store): State<Arc<CheckoutSessionStore>>,
+    Query(params): Query<CustomerSessionsParams>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, CheckoutError> {
+    if let Some(response) = forbidden(&claims, "checkout:read") {
+        return Ok(response);
+    }
+    let (sessions, total) = store.list_for_customer(params.customer_id, pagination).await?;
+    Ok(Json(json!({
+        "sessions": sessions,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+async fn update_session(
+    State(store): State<Arc<CheckoutSessionStore>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(patch): Json<CheckoutSessionPatch>,
+) -> Result<Response, CheckoutError> {
+    ensure_access(&claims, &store.get(id).await?)?;
+    Ok(Json(store.update(id, patch).await?).into_response())
+}
+
+async fn complete_session(
+    State(store): State<Arc<CheckoutSessionStore>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(request): Json<CompleteRequest>,
+) -> Result<Response, CheckoutError> {
+    ensure_access(&claims, &store.get(id).await?)?;
+    Ok(Json(store.complete(id, request.order_id, request.amount).await?).into_response())
+}
+
+/// Pre-flight check before payment: re-verifies prices and stock and returns what
+/// changed, so the storefront can prompt the shopper instead of failing after
+/// authorization.
+async fn validate_session(
+    State(store): State<Arc<CheckoutSessionStore>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, CheckoutError> {
+    ensure_access(&claims, &store.get(id).await?)?;
+    Ok(Json(store.validate(id).await?).into_response())
+}
+
+async fn cancel_session(
+    State(store): State<Arc<CheckoutSessionStore>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, CheckoutError> {
+    ensure_access(&claims, &store.get(id).await?)?;
+    Ok(Json(store.close(id, CheckoutStatus::Cancelled, None).await?).into_response())
+}
+
+pub fn checkout_routes<S>(store: Arc<CheckoutSessionStore>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/sessions", get(list_sessions).post(create_session))
+        .route("/sessions/:id", get(get_session).patch(update_session))
+        .route("/sessions/:id/validate", post(validate_session))
+        .route("/sessions/:id/complete", post(complete_session))
+        .route("/sessions/:id/cancel", post(cancel_session))
+        .with_state(store)
+}
@@ -0,0 +1,185 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::models::credit_memo_application::ApplicationKind;
+use crate::services::credit_memo_service::{
+    CreditMemoFilter, CreditMemoService, ExportAck, NewApplication, NewCreditMemo, ReturnCredit,
+};
+use crate::utils::pagination::PaginationParams;
+
+#[derive(Debug, Deserialize)]
+struct RefundRequest {
+    #[serde(with = "crate::money::amount")]
+    amount: rust_decimal::Decimal,
+    reference: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InvoiceApplication {
+    invoice_id: String,
+    #[serde(with = "crate::money::amount")]
+    amount: rust_decimal::Decimal,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportParams {
+    limit: Option<u64>,
+}
+
+async fn list_credit_memos(
+    State(memos): State<Arc<CreditMemoService>>,
+    Query(filter): Query<CreditMemoFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "credit_memos:read") {
+        return Ok(response);
+    }
+    let (items, total) = memos.list(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+/// Records a manual adjustment; it opens once its approval chain completes.
+async fn create_credit_memo(
+    State(memos): State<Arc<CreditMemoService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewCreditMemo>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "credit_memos:write") {
+        return Ok(response);
+    }
+    let memo = memos.create_manual(input, &claims.actor()).await?;
+    info!("Credit memo {} created by {}", memo.memo.memo_number, claims.actor());
+    Ok((StatusCode::CREATED, Json(memo)).into_response())
+}
+
+async fn issue_for_return(
+    State(memos): State<Arc<CreditMemoService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<ReturnCredit>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "credit_memos:write") {
+        return Ok(response);
+    }
+    let memo = memos.issue_for_return(input, &claims.actor()).await?;
+    Ok((StatusCode::CREATED, Json(memo)).into_response())
+}
+
+async fn get_credit_memo(
+    State(memos): State<Arc<CreditMemoService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "credit_memos:read") {
+        return Ok(response);
+    }
+    Ok(Json(memos.get(id).await?).into_response())
+}
+
+async fn apply_to_invoice(
+    State(memos): State<Arc<CreditMemoService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<InvoiceApplication>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "credit_memos:write") {
+        return Ok(response);
+    }
+    let application = NewApplication {
+        kind: ApplicationKind::Invoice,
+        invoice_id: Some(input.invoice_id),
+        reference: None,
+        amount: input.amount,
+    };
+    let application = memos.apply(id, application, &claims.actor()).await?;
+    Ok((StatusCode::CREATED, Json(application)).into_response())
+}
+
+async fn refund_credit_memo(
+    State(memos): State<Arc<CreditMemoService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<RefundRequest>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "credit_memos:write") {
+        return Ok(response);
+    }
+    let application = NewApplication {
+        kind: ApplicationKind::Refund,
+        invoice_id: None,
+        reference: input.reference,
+        amount: input.amount,
+    };
+    let application = memos.apply(id, application, &claims.actor()).await?;
+    Ok((StatusCode::CREATED, Json(application)).into_response())
+}
+
+async fn void_credit_memo(
+    State(memos): State<Arc<CreditMemoService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "credit_memos:write") {
+        return Ok(response);
+    }
+    Ok(Json(memos.void(id).await?).into_response())
+}
+
+/// Feed for accounting connectors: memos and applications not yet acknowledged.
+async fn export_feed(
+    State(memos): State<Arc<CreditMemoService>>,
+    Query(params): Query<ExportParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "credit_memos:export") {
+        return Ok(response);
+    }
+    let batch = memos.unexported(params.limit.unwrap_or(100)).await?;
+    Ok(Json(batch).into_response())
+}
+
+async fn acknowledge_export(
+    State(memos): State<Arc<CreditMemoService>>,
+    AuthUser(claims): AuthUser,
+    Json(ack): Json<ExportAck>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "credit_memos:export") {
+        return Ok(response);
+    }
+    let acknowledged = memos.acknowledge_export(ack).await?;
+    Ok(Json(json!({ "acknowledged": acknowledged })).into_response())
+}
+
+pub fn credit_memo_routes<S>(memos: Arc<CreditMemoService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_credit_memos).post(create_credit_memo))
+        .route("/from-return", post(issue_for_return))
+        .route("/export", get(export_feed))
+        .route("/export/ack", post(acknowledge_export))
+        .route("/:id", get(get_credit_memo))
+        .route("/:id/apply", post(apply_to_invoice))
+        .route("/:id/refund", post(refund_credit_memo))
+        .route("/:id/void", post(void_credit_memo))
+        .with_state(memos)
+}
@@ -0,0 +1,156 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::cross_dock_service::{
+    AsnFilter, CrossDockService, DemandFilter, NewAsn, NewBackorder, NewTransferOrder,
+};
+use crate::utils::pagination::PaginationParams;
+
+fn page<T: serde::Serialize>(items: Vec<T>, total: u64, pagination: &PaginationParams) -> Response {
+    Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response()
+}
+
+async fn list_asns(
+    State(cross_dock): State<Arc<CrossDockService>>,
+    Query(filter): Query<AsnFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "asns:read") {
+        return Ok(response);
+    }
+    let (items, total) = cross_dock.list_asns(filter, pagination).await?;
+    Ok(page(items, total, &pagination))
+}
+
+async fn create_asn(
+    State(cross_dock): State<Arc<CrossDockService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewAsn>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "asns:write") {
+        return Ok(response);
+    }
+    let asn = cross_dock.create_asn(input).await?;
+    info!("ASN {} created by {}", asn.asn.asn_number, claims.actor());
+    Ok((StatusCode::CREATED, Json(asn)).into_response())
+}
+
+async fn get_asn(
+    State(cross_dock): State<Arc<CrossDockService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "asns:read") {
+        return Ok(response);
+    }
+    Ok(Json(cross_dock.get_asn(id).await?).into_response())
+}
+
+/// Routes inbound units to open backorders and transfer orders and reserves them on the
+/// ASN so they go straight to outbound shipping.
+async fn plan_cross_dock(
+    State(cross_dock): State<Arc<CrossDockService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "asns:write") {
+        return Ok(response);
+    }
+    Ok(Json(cross_dock.plan_cross_dock(id, &claims.actor()).await?).into_response())
+}
+
+async fn list_backorders(
+    State(cross_dock): State<Arc<CrossDockService>>,
+    Query(filter): Query<DemandFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "inventory:read") {
+        return Ok(response);
+    }
+    let (items, total) = cross_dock.list_backorders(filter, pagination).await?;
+    Ok(page(items, total, &pagination))
+}
+
+async fn create_backorder(
+    State(cross_dock): State<Arc<CrossDockService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewBackorder>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "inventory:write") {
+        return Ok(response);
+    }
+    Ok((StatusCode::CREATED, Json(cross_dock.create_backorder(input).await?)).into_response())
+}
+
+async fn list_transfer_orders(
+    State(cross_dock): State<Arc<CrossDockService>>,
+    Query(filter): Query<DemandFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "inventory:read") {
+        return Ok(response);
+    }
+    let (items, total) = cross_dock.list_transfer_orders(filter, pagination).await?;
+    Ok(page(items, total, &pagination))
+}
+
+async fn create_transfer_order(
+    State(cross_dock): State<Arc<CrossDockService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewTransferOrder>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "inventory:write") {
+        return Ok(response);
+    }
+    let transfer = cross_dock.create_transfer_order(input, &claims.actor()).await?;
+    Ok((StatusCode::CREATED, Json(transfer)).into_response())
+}
+
+pub fn asn_routes<S>(cross_dock: Arc<CrossDockService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_asns).post(create_asn))
+        .route("/:id", get(get_asn))
+        .route("/:id/cross-dock-plan", post(plan_cross_dock))
+        .with_state(cross_dock)
+}
+
+pub fn backorder_routes<S>(cross_dock: Arc<CrossDockService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_backorders).post(create_backorder))
+        .with_state(cross_dock)
+}
+
+pub fn transfer_order_routes<S>(cross_dock: Arc<CrossDockService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_transfer_orders).post(create_transfer_order))
+        .with_state(cross_dock)
+}
@@ -0,0 +1,105 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::{AuthUser, Claims};
+use crate::custom_fields::{CustomFieldError, CustomFieldService, FieldInput, RequestTenant};
+use crate::models::custom_field_definition::FieldEntity;
+
+fn require(claims: &Claims, permission: &'static str) -> Result<(), CustomFieldError> {
+    if claims.is_allowed(permission) {
+        Ok(())
+    } else {
+        Err(CustomFieldError::Forbidden(permission))
+    }
+}
+
+/// Permission needed to read or write a record type's metadata.
+fn record_permission(entity: FieldEntity, write: bool) -> &'static str {
+    match (entity, write) {
+        (FieldEntity::Order, false) => "orders:read",
+        (FieldEntity::Order, true) => "orders:write",
+        (FieldEntity::Customer, false) => "customers:read",
+        (FieldEntity::Customer, true) => "customers:write",
+        (FieldEntity::Product, false) => "catalog:read",
+        (FieldEntity::Product, true) => "catalog:write",
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct FieldParams {
+    pub entity: Option<FieldEntity>,
+}
+
+/// The caller's tenant's field definitions, optionally for one record type.
+async fn list_fields(
+    State(fields): State<Arc<CustomFieldService>>,
+    Query(params): Query<FieldParams>,
+    RequestTenant(tenant): RequestTenant,
+    AuthUser(_claims): AuthUser,
+) -> Result<Response, CustomFieldError> {
+    Ok(Json(fields.fields(&tenant, params.entity).await?).into_response())
+}
+
+/// Creates or replaces the definition of a key. Admins and `custom_fields:write` only.
+async fn define_field(
+    State(fields): State<Arc<CustomFieldService>>,
+    RequestTenant(tenant): RequestTenant,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<FieldInput>,
+) -> Result<Response, CustomFieldError> {
+    require(&claims, "custom_fields:write")?;
+    Ok(Json(fields.define(&tenant, input, &claims.actor()).await?).into_response())
+}
+
+async fn delete_field(
+    State(fields): State<Arc<CustomFieldService>>,
+    Path(id): Path<Uuid>,
+    RequestTenant(tenant): RequestTenant,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, CustomFieldError> {
+    require(&claims, "custom_fields:write")?;
+    fields.delete(&tenant, id).await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn get_metadata(
+    State(fields): State<Arc<CustomFieldService>>,
+    Path((entity, id)): Path<(FieldEntity, String)>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, CustomFieldError> {
+    require(&claims, record_permission(entity, false))?;
+    Ok(Json(fields.metadata(entity, &id).await?).into_response())
+}
+
+/// Replaces a record's metadata; rejected with the list of violations when it does not
+/// match the tenant's definitions.
+async fn put_metadata(
+    State(fields): State<Arc<CustomFieldService>>,
+    Path((entity, id)): Path<(FieldEntity, String)>,
+    RequestTenant(tenant): RequestTenant,
+    AuthUser(claims): AuthUser,
+    Json(metadata): Json<Value>,
+) -> Result<Response, CustomFieldError> {
+    require(&claims, record_permission(entity, true))?;
+    Ok(Json(fields.set_metadata(&tenant, entity, &id, metadata).await?).into_response())
+}
+
+pub fn custom_field_routes<S>(fields: Arc<CustomFieldService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_fields).put(define_field))
+        .route("/:id", delete(delete_field))
+        .route("/records/:entity/:id", get(get_metadata).put(put_metadata))
+        .with_state(fields)
+}
@@ -0,0 +1,77 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::auth::{admin_only, AuthUser};
+use crate::customer_segments::{self, CustomerSegmentService, SegmentError};
+use crate::jobs::{JobError, JobRunner};
+
+#[derive(Clone)]
+pub struct SegmentRoutesState {
+    pub segments: Arc<CustomerSegmentService>,
+    pub jobs: Arc<JobRunner>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddTagRequest {
+    pub tag: String,
+}
+
+/// Manual tags and computed segments of a customer.
+async fn list_tags(
+    State(state): State<SegmentRoutesState>,
+    Path(customer_id): Path<i32>,
+    AuthUser(_claims): AuthUser,
+) -> Result<Response, SegmentError> {
+    let tags = state.segments.tags_for(customer_id).await?;
+    Ok(Json(json!({ "customer_id": customer_id, "tags": tags })).into_response())
+}
+
+async fn add_tag(
+    State(state): State<SegmentRoutesState>,
+    Path(customer_id): Path<i32>,
+    AuthUser(claims): AuthUser,
+    Json(request): Json<AddTagRequest>,
+) -> Result<Response, SegmentError> {
+    let tag = state.segments.add_tag(customer_id, &request.tag, claims.actor()).await?;
+    Ok((StatusCode::CREATED, Json(tag)).into_response())
+}
+
+async fn remove_tag(
+    State(state): State<SegmentRoutesState>,
+    Path((customer_id, tag)): Path<(i32, String)>,
+    AuthUser(_claims): AuthUser,
+) -> Result<Response, SegmentError> {
+    let removed = state.segments.remove_tag(customer_id, &tag).await?;
+    Ok(if removed { StatusCode::NO_CONTENT } else { StatusCode::NOT_FOUND }.into_response())
+}
+
+/// Starts a segment evaluation run and returns the job to poll. Admin only.
+async fn evaluate_segments(
+    State(state): State<SegmentRoutesState>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, JobError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let job_id = customer_segments::submit_evaluation(&state.jobs, state.segments.clone(), Some(claims.actor())).await?;
+    Ok((StatusCode::ACCEPTED, Json(json!({ "job_id": job_id }))).into_response())
+}
+
+pub fn segment_routes<S>(segments: Arc<CustomerSegmentService>, jobs: Arc<JobRunner>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/segments/evaluate", post(evaluate_segments))
+        .route("/:id/tags", get(list_tags).post(add_tag))
+        .route("/:id/tags/:tag", delete(remove_tag))
+        .with_state(SegmentRoutesState { segments, jobs })
+}
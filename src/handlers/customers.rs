@@ -1,7 +1,7 @@
 use axum::{
     routing::{post, get, put, delete},
     extract::{State, Path, Query, Json},
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     Router,
 };
 use crate::db::DbPool;
@@ -10,6 +10,11 @@ use crate::errors::ServiceError;
 use crate::services::customers::{create_customer, get_customer, update_customer, delete_customer, list_customers, search_customers, get_customer_orders, get_customer_returns};
 use crate::auth::AuthenticatedUser;
 use crate::utils::pagination::PaginationParams;
+use crate::change_feed::{self, SyncEntity, SyncParams};
+use crate::customer_segments::customers_with_tag;
+use crate::custom_fields::{customers_with_metadata, MetadataFilter};
+use serde::Deserialize;
+use serde_json::json;
 use validator::Validate;
 use std::sync::Arc;
 
@@ -52,13 +57,60 @@ async fn delete_customer(
     Ok(axum::http::StatusCode::NO_CONTENT)
 }
 
+#[derive(Debug, Deserialize)]
+pub struct CustomerTagFilter {
+    /// Only customers carrying this manual tag or computed segment, e.g. `vip`.
+    pub tag: Option<String>,
+}
+
+/// Lists customers, filtered by tag or by `metadata.<key>` parameters; not both at once.
+/// With `updated_since`, the customers changed and deleted since then, unfiltered.
 async fn list_customers(
     State(pool): State<Arc<DbPool>>,
     Query(query): Query<PaginationParams>,
+    Query(filter): Query<CustomerTagFilter>,
+    Query(sync): Query<SyncParams>,
+    metadata: MetadataFilter,
     AuthenticatedUser(_user): AuthenticatedUser,
-) -> Result<impl IntoResponse, ServiceError> {
-    let customers = list_customers(&pool, query).await?;
-    Ok(Json(customers))
+) -> Result<Response, ServiceError> {
+    if let Some(since) = sync.updated_since {
+        let load = |ids: Vec<i32>| async {
+            let mut customers = Vec::with_capacity(ids.len());
+            for id in ids {
+                customers.push(get_customer(&pool, id).await?);
+            }
+            Ok::<_, ServiceError>(customers)
+        };
+        return Ok(change_feed::sync_response(&pool, SyncEntity::Customer, since, &sync, load)
+            .await
+            .into_response());
+    }
+    let (ids, total) = match (filter.tag, metadata.is_empty()) {
+        (None, true) => {
+            let customers = list_customers(&pool, query).await?;
+            return Ok(Json(json!(customers)).into_response());
+        }
+        (Some(tag), true) => customers_with_tag(&pool, &tag, query)
+            .await
+            .map_err(|e| ServiceError::DatabaseError(e.to_string()))?,
+        (None, false) => customers_with_metadata(&pool, &metadata, query)
+            .await
+            .map_err(|e| ServiceError::DatabaseError(e.to_string()))?,
+        (Some(_), false) => {
+            return Err(ServiceError::ValidationError("Filter by tag or by metadata, not both".to_string()));
+        }
+    };
+    let mut customers = Vec::with_capacity(ids.len());
+    for id in ids {
+        customers.push(get_customer(&pool, id).await?);
+    }
+    Ok(Json(json!({
+        "customers": customers,
+        "total": total,
+        "page": query.page,
+        "per_page": query.per_page,
+    }))
+    .into_response())
 }
 
 async fn search_customers(
@@ -0,0 +1,59 @@
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::customs_service::{CustomsDeclarationRequest, CustomsService, DutyQuoteRequest};
+
+/// Landed duty and tax estimate for a cart, for DDP quotes at checkout. Open to any
+/// authenticated caller, like checkout itself.
+async fn estimate_duties(
+    State(customs): State<Arc<CustomsService>>,
+    AuthUser(_claims): AuthUser,
+    Json(request): Json<DutyQuoteRequest>,
+) -> Result<Response, ServiceError> {
+    Ok(Json(customs.estimate(request).await?).into_response())
+}
+
+async fn get_customs_lines(
+    State(customs): State<Arc<CustomsService>>,
+    Path(shipment_id): Path<i32>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "shipments:read") {
+        return Ok(response);
+    }
+    let lines = customs.customs_lines(shipment_id).await?;
+    Ok(Json(json!({ "shipment_id": shipment_id, "items": lines })).into_response())
+}
+
+async fn put_customs_lines(
+    State(customs): State<Arc<CustomsService>>,
+    Path(shipment_id): Path<i32>,
+    AuthUser(claims): AuthUser,
+    Json(request): Json<CustomsDeclarationRequest>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "shipments:write") {
+        return Ok(response);
+    }
+    let lines = customs.declare(shipment_id, request).await?;
+    info!("Customs lines of shipment {} generated by {}", shipment_id, claims.actor());
+    Ok(Json(json!({ "shipment_id": shipment_id, "items": lines })).into_response())
+}
+
+pub fn customs_routes<S>(customs: Arc<CustomsService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/estimate", post(estimate_duties))
+        .route("/shipments/:id", get(get_customs_lines).put(put_customs_lines))
+        .with_state(customs)
+}
@@ -0,0 +1,50 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::auth::{admin_only, AuthUser};
+use crate::db::index_advisor::{IndexAdvisor, IndexAdvisorError};
+use crate::db::{PoolHealth, PoolMonitor};
+
+/// Pool statistics from the last sample. Answers 503 when the pool could not hand out a
+/// connection in time, so load balancers can take the instance out.
+async fn database_health(State(monitor): State<Arc<PoolMonitor>>) -> Response {
+    let health = monitor.health();
+    let status = match health {
+        PoolHealth::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+        PoolHealth::Ok | PoolHealth::Degraded => StatusCode::OK,
+    };
+    (status, Json(json!({ "status": health, "pools": [monitor.stats().as_ref()] }))).into_response()
+}
+
+pub fn database_health_routes<S>(monitor: Arc<PoolMonitor>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new().route("/health/db", get(database_health)).with_state(monitor)
+}
+
+/// Missing indexes for known list endpoint patterns and, with `pg_stat_statements`, for the
+/// costliest statements, plus scan counts of the tables involved.
+async fn index_advice(
+    State(advisor): State<Arc<IndexAdvisor>>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, IndexAdvisorError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    Ok(Json(advisor.advise().await?).into_response())
+}
+
+pub fn database_admin_routes<S>(advisor: Arc<IndexAdvisor>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new().route("/indexes", get(index_advice)).with_state(advisor)
+}
@@ -0,0 +1,34 @@
+use axum::{
+    extract::{Query, State},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use std::sync::Arc;
+
+use crate::auth::{ActorType, AuthUser};
+use crate::developer_logs::{DeveloperLogError, DeveloperLogService, LogQuery};
+use crate::network_acl::api_key_prefix_of;
+
+/// Recent requests made with the caller's API key and deliveries to the webhook
+/// subscriptions its service account created, newest first.
+async fn get_logs(
+    State(logs): State<Arc<DeveloperLogService>>,
+    Query(query): Query<LogQuery>,
+    headers: HeaderMap,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, DeveloperLogError> {
+    if claims.actor_type != ActorType::ServiceAccount {
+        return Err(DeveloperLogError::ApiKeyRequired);
+    }
+    let prefix = api_key_prefix_of(&headers).ok_or(DeveloperLogError::ApiKeyRequired)?;
+    Ok(Json(logs.logs(prefix, &claims.actor(), &query).await?).into_response())
+}
+
+pub fn developer_log_routes<S>(logs: Arc<DeveloperLogService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new().route("/logs", get(get_logs)).with_state(logs)
+}
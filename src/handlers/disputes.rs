@@ -0,0 +1,135 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::disputes::{DisputeError, DisputeFilter, DisputeService, Evidence, STRIPE_SIGNATURE_HEADER};
+use crate::models::dispute::DisputeStatus;
+use crate::utils::pagination::PaginationParams;
+
+#[derive(Debug, Deserialize)]
+struct TransitionRequest {
+    status: DisputeStatus,
+    note: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnalyticsParams {
+    from: NaiveDate,
+    to: NaiveDate,
+}
+
+async fn list_disputes(
+    State(disputes): State<Arc<DisputeService>>,
+    Query(filter): Query<DisputeFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, DisputeError> {
+    if let Some(response) = forbidden(&claims, "disputes:read") {
+        return Ok(response);
+    }
+    let (items, total) = disputes.list(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+async fn get_dispute(
+    State(disputes): State<Arc<DisputeService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, DisputeError> {
+    if let Some(response) = forbidden(&claims, "disputes:read") {
+        return Ok(response);
+    }
+    Ok(Json(disputes.get(id).await?).into_response())
+}
+
+async fn submit_evidence(
+    State(disputes): State<Arc<DisputeService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(evidence): Json<Evidence>,
+) -> Result<Response, DisputeError> {
+    if let Some(response) = forbidden(&claims, "disputes:write") {
+        return Ok(response);
+    }
+    Ok(Json(disputes.submit_evidence(id, evidence, &claims.actor()).await?).into_response())
+}
+
+async fn transition(
+    State(disputes): State<Arc<DisputeService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<TransitionRequest>,
+) -> Result<Response, DisputeError> {
+    if let Some(response) = forbidden(&claims, "disputes:write") {
+        return Ok(response);
+    }
+    let dispute = disputes.transition(id, input.status, input.note, &claims.actor()).await?;
+    Ok(Json(dispute).into_response())
+}
+
+async fn analytics(
+    State(disputes): State<Arc<DisputeService>>,
+    Query(params): Query<AnalyticsParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, DisputeError> {
+    if let Some(response) = forbidden(&claims, "disputes:read") {
+        return Ok(response);
+    }
+    let analytics = disputes.analytics(params.from, params.to).await?;
+    Ok(Json(json!({ "from": params.from, "to": params.to, "analytics": analytics })).into_response())
+}
+
+/// Stripe dispute webhooks. Authenticated by the `Stripe-Signature` header rather than a
+/// bearer token; events other than disputes are acknowledged and ignored.
+async fn stripe_webhook(
+    State(disputes): State<Arc<DisputeService>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, DisputeError> {
+    let signature = headers
+        .get(STRIPE_SIGNATURE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| DisputeError::InvalidSignature(format!("missing {} header", STRIPE_SIGNATURE_HEADER)))?;
+    let dispute = disputes.stripe_webhook(&body, signature).await?;
+    Ok(Json(json!({ "received": true, "dispute_id": dispute.map(|d| d.id) })).into_response())
+}
+
+pub fn dispute_routes<S>(disputes: Arc<DisputeService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_disputes))
+        .route("/analytics", get(analytics))
+        .route("/:id", get(get_dispute))
+        .route("/:id/evidence", post(submit_evidence))
+        .route("/:id/transition", post(transition))
+        .with_state(disputes)
+}
+
+/// Provider webhooks; merged outside `auth_middleware`.
+pub fn dispute_webhook_routes<S>(disputes: Arc<DisputeService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/api/v1/webhooks/disputes/stripe", post(stripe_webhook))
+        .with_state(disputes)
+}
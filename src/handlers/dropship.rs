@@ -0,0 +1,177 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::dropship_service::{DropshipFilter, DropshipService, ShipmentConfirmation, SourceInput};
+use crate::utils::pagination::PaginationParams;
+
+#[derive(Debug, Deserialize)]
+struct SourceParams {
+    supplier_id: Option<Uuid>,
+}
+
+async fn list_sources(
+    State(dropship): State<Arc<DropshipService>>,
+    Query(params): Query<SourceParams>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "dropship:read") {
+        return Ok(response);
+    }
+    let (items, total) = dropship.list_sources(params.supplier_id, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+async fn get_source(
+    State(dropship): State<Arc<DropshipService>>,
+    Path(sku): Path<String>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "dropship:read") {
+        return Ok(response);
+    }
+    Ok(Json(dropship.get_source(&sku).await?).into_response())
+}
+
+async fn put_source(
+    State(dropship): State<Arc<DropshipService>>,
+    Path(sku): Path<String>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<SourceInput>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "dropship:write") {
+        return Ok(response);
+    }
+    let source = dropship.set_source(&sku, input, &claims.actor()).await?;
+    info!("Dropship source of {} written by {}", sku, claims.actor());
+    Ok(Json(source).into_response())
+}
+
+async fn delete_source(
+    State(dropship): State<Arc<DropshipService>>,
+    Path(sku): Path<String>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "dropship:write") {
+        return Ok(response);
+    }
+    dropship.remove_source(&sku).await?;
+    info!("Dropship source of {} removed by {}", sku, claims.actor());
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+async fn list_orders(
+    State(dropship): State<Arc<DropshipService>>,
+    Query(filter): Query<DropshipFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "dropship:read") {
+        return Ok(response);
+    }
+    let (items, total) = dropship.list(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+async fn get_order(
+    State(dropship): State<Arc<DropshipService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "dropship:read") {
+        return Ok(response);
+    }
+    Ok(Json(dropship.get(id).await?).into_response())
+}
+
+/// Routes a sales order's dropship lines by hand, e.g. after adding a missing source.
+async fn route_order(
+    State(dropship): State<Arc<DropshipService>>,
+    Path(order_id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "dropship:write") {
+        return Ok(response);
+    }
+    let items = dropship.route_order(order_id, &claims.actor()).await?;
+    info!("Order {} routed to dropship suppliers by {}", order_id, claims.actor());
+    Ok(Json(json!({ "order_id": order_id, "items": items })).into_response())
+}
+
+/// Retries forwarding a pending dropship order to its supplier.
+async fn forward_order(
+    State(dropship): State<Arc<DropshipService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "dropship:write") {
+        return Ok(response);
+    }
+    Ok(Json(dropship.forward(id).await?).into_response())
+}
+
+/// Shipment confirmations posted by suppliers, usually from a service account that only
+/// holds `dropship:confirm`.
+async fn confirm_shipment(
+    State(dropship): State<Arc<DropshipService>>,
+    AuthUser(claims): AuthUser,
+    Json(confirmation): Json<ShipmentConfirmation>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "dropship:confirm") {
+        return Ok(response);
+    }
+    let package = dropship.confirm_shipment(confirmation, &claims.actor()).await?;
+    Ok((StatusCode::CREATED, Json(package)).into_response())
+}
+
+/// Every package of an order, whether the warehouse or a supplier shipped it.
+async fn order_tracking(
+    State(dropship): State<Arc<DropshipService>>,
+    Path(order_id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "orders:read") {
+        return Ok(response);
+    }
+    Ok(Json(dropship.tracking(order_id).await?).into_response())
+}
+
+pub fn dropship_routes<S>(dropship: Arc<DropshipService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/sources", get(list_sources))
+        .route("/sources/:sku", get(get_source).put(put_source).delete(delete_source))
+        .route("/orders", get(list_orders))
+        .route("/orders/:id", get(get_order))
+        .route("/orders/:id/forward", post(forward_order))
+        .route("/confirmations", post(confirm_shipment))
+        .route("/sales-orders/:id/route", post(route_order))
+        .route("/sales-orders/:id/tracking", get(order_tracking))
+        .with_state(dropship)
+}
@@ -0,0 +1,59 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::duplicate_orders::{DuplicateOrderService, FlaggedFilter, ReviewDecision};
+use crate::utils::pagination::PaginationParams;
+
+/// Orders flagged as suspected duplicates, pending review unless `status` says otherwise.
+async fn list_flagged(
+    State(duplicates): State<Arc<DuplicateOrderService>>,
+    Query(filter): Query<FlaggedFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "orders:read") {
+        return Ok(response);
+    }
+    let (items, total) = duplicates.list(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+async fn review(
+    State(duplicates): State<Arc<DuplicateOrderService>>,
+    Path(order_id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(decision): Json<ReviewDecision>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "orders:review_duplicates") {
+        return Ok(response);
+    }
+    let flag = duplicates.review(order_id, decision, &claims.actor()).await?;
+    info!("Duplicate flag on order {} closed as {:?} by {}", order_id, flag.review_status, claims.actor());
+    Ok(Json(flag).into_response())
+}
+
+pub fn duplicate_order_routes<S>(duplicates: Arc<DuplicateOrderService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_flagged))
+        .route("/:order_id/review", post(review))
+        .with_state(duplicates)
+}
@@ -0,0 +1,122 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::eco_service::{EcoFilter, EcoService, NewEco};
+use crate::utils::pagination::PaginationParams;
+
+async fn list_ecos(
+    State(ecos): State<Arc<EcoService>>,
+    Query(filter): Query<EcoFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "ecos:read") {
+        return Ok(response);
+    }
+    let (items, total) = ecos.list(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+/// Creates a draft ECO for the caller.
+async fn create_eco(
+    State(ecos): State<Arc<EcoService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewEco>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "ecos:write") {
+        return Ok(response);
+    }
+    let eco = ecos.create(input, &claims.actor()).await?;
+    info!("ECO {} created by {}", eco.eco.number, claims.actor());
+    Ok((StatusCode::CREATED, Json(eco)).into_response())
+}
+
+async fn get_eco(
+    State(ecos): State<Arc<EcoService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "ecos:read") {
+        return Ok(response);
+    }
+    Ok(Json(ecos.get(id).await?).into_response())
+}
+
+/// Open work orders that would be held if the ECO were implemented now.
+async fn affected_work_orders(
+    State(ecos): State<Arc<EcoService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "ecos:read") {
+        return Ok(response);
+    }
+    Ok(Json(json!({ "work_orders": ecos.affected_work_orders(id).await? })).into_response())
+}
+
+async fn submit_eco(
+    State(ecos): State<Arc<EcoService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "ecos:write") {
+        return Ok(response);
+    }
+    Ok(Json(ecos.submit(id, &claims.actor()).await?).into_response())
+}
+
+async fn cancel_eco(
+    State(ecos): State<Arc<EcoService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "ecos:write") {
+        return Ok(response);
+    }
+    Ok(Json(ecos.cancel(id).await?).into_response())
+}
+
+/// Re-pins a held work order to the new revision and restores its status.
+async fn release_hold(
+    State(ecos): State<Arc<EcoService>>,
+    Path((id, work_order_id)): Path<(Uuid, Uuid)>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "work_orders:write") {
+        return Ok(response);
+    }
+    let hold = ecos.release_hold(id, work_order_id, &claims.actor()).await?;
+    info!("Work order {} released from ECO {} by {}", work_order_id, id, claims.actor());
+    Ok(Json(hold).into_response())
+}
+
+pub fn eco_routes<S>(ecos: Arc<EcoService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_ecos).post(create_eco))
+        .route("/:id", get(get_eco))
+        .route("/:id/affected-work-orders", get(affected_work_orders))
+        .route("/:id/submit", post(submit_eco))
+        .route("/:id/cancel", post(cancel_eco))
+        .route("/:id/holds/:work_order_id/release", post(release_hold))
+        .with_state(ecos)
+}
@@ -0,0 +1,72 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::auth::{admin_only, AuthUser};
+use crate::encryption::{self, EncryptionError, EncryptionService};
+use crate::jobs::JobRunner;
+
+#[derive(Clone)]
+pub struct EncryptionRoutesState {
+    pub encryption: Option<Arc<EncryptionService>>,
+    pub jobs: Arc<JobRunner>,
+}
+
+impl EncryptionRoutesState {
+    fn encryption(&self) -> Result<Arc<EncryptionService>, EncryptionError> {
+        self.encryption
+            .clone()
+            .ok_or_else(|| EncryptionError::Misconfigured("encryption is disabled".to_string()))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct KeyParams {
+    tenant_id: Option<String>,
+}
+
+/// Data keys with their status; wrapped key material is never returned.
+async fn list_keys(
+    State(state): State<EncryptionRoutesState>,
+    Query(params): Query<KeyParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, EncryptionError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let keys = state.encryption()?.keys(params.tenant_id).await?;
+    Ok(Json(json!({ "items": keys })).into_response())
+}
+
+/// Issues a new data key for the tenant and re-encrypts its values as a job.
+async fn rotate_key(
+    State(state): State<EncryptionRoutesState>,
+    Path(tenant_id): Path<String>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, EncryptionError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let job_id =
+        encryption::submit_rotation(&state.jobs, state.encryption()?, tenant_id.clone(), Some(claims.actor())).await?;
+    info!("Data key rotation for tenant {} requested by {}", tenant_id, claims.actor());
+    Ok((StatusCode::ACCEPTED, Json(json!({ "job_id": job_id }))).into_response())
+}
+
+pub fn encryption_routes<S>(encryption: Option<Arc<EncryptionService>>, jobs: Arc<JobRunner>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/keys", get(list_keys))
+        .route("/tenants/:tenant_id/rotate", post(rotate_key))
+        .with_state(EncryptionRoutesState { encryption, jobs })
+}
@@ -0,0 +1,151 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::forecast_service::{
+    parse_forecast_csv, ForecastFilter, ForecastInput, ForecastOverride, ForecastService,
+};
+use crate::utils::pagination::PaginationParams;
+
+#[derive(Debug, Deserialize)]
+pub struct AccuracyQuery {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub sku: Option<String>,
+    pub channel: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DemandQuery {
+    pub sku: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+async fn list_forecasts(
+    State(forecasts): State<Arc<ForecastService>>,
+    Query(filter): Query<ForecastFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "forecasts:read") {
+        return Ok(response);
+    }
+    let (items, total) = forecasts.list(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+/// Creates or replaces one forecast by hand.
+async fn save_forecast(
+    State(forecasts): State<Arc<ForecastService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<ForecastInput>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "forecasts:write") {
+        return Ok(response);
+    }
+    Ok(Json(forecasts.save(input).await?).into_response())
+}
+
+/// Loads a `sku,channel,month,quantity` CSV body. Nothing is saved unless every line is valid.
+async fn import_forecasts(
+    State(forecasts): State<Arc<ForecastService>>,
+    AuthUser(claims): AuthUser,
+    body: String,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "forecasts:write") {
+        return Ok(response);
+    }
+    let rows = match parse_forecast_csv(&body) {
+        Ok(rows) => rows,
+        Err(errors) => {
+            return Ok((
+                StatusCode::UNPROCESSABLE_ENTITY,
+                Json(json!({ "error": "Forecast file has invalid lines", "code": "invalid_csv", "lines": errors })),
+            )
+                .into_response())
+        }
+    };
+    let summary = forecasts.import(rows).await?;
+    info!("Forecast import by {}: {} created, {} updated", claims.actor(), summary.created, summary.updated);
+    Ok(Json(summary).into_response())
+}
+
+async fn set_override(
+    State(forecasts): State<Arc<ForecastService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<ForecastOverride>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "forecasts:write") {
+        return Ok(response);
+    }
+    Ok(Json(forecasts.set_override(id, input, &claims.actor()).await?).into_response())
+}
+
+async fn clear_override(
+    State(forecasts): State<Arc<ForecastService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "forecasts:write") {
+        return Ok(response);
+    }
+    Ok(Json(forecasts.clear_override(id).await?).into_response())
+}
+
+async fn accuracy_report(
+    State(forecasts): State<Arc<ForecastService>>,
+    Query(query): Query<AccuracyQuery>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "forecasts:read") {
+        return Ok(response);
+    }
+    let report = forecasts.accuracy(query.from, query.to, query.sku, query.channel).await?;
+    Ok(Json(report).into_response())
+}
+
+/// Monthly forecast demand as consumed by MRP and replenishment.
+async fn demand(
+    State(forecasts): State<Arc<ForecastService>>,
+    Query(query): Query<DemandQuery>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "forecasts:read") {
+        return Ok(response);
+    }
+    let months = forecasts.demand(&query.sku, query.from, query.to).await?;
+    Ok(Json(json!({ "sku": query.sku, "months": months })).into_response())
+}
+
+pub fn forecast_routes<S>(forecasts: Arc<ForecastService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_forecasts).post(save_forecast))
+        .route("/import", post(import_forecasts))
+        .route("/accuracy", get(accuracy_report))
+        .route("/demand", get(demand))
+        .route("/:id/override", put(set_override).delete(clear_override))
+        .with_state(forecasts)
+}
@@ -0,0 +1,125 @@
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::hazmat_service::{DeclareGoods, HazmatCheck, HazmatInput, HazmatService};
+
+async fn get_product(
+    State(hazmat): State<Arc<HazmatService>>,
+    Path(sku): Path<String>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "hazmat:read") {
+        return Ok(response);
+    }
+    Ok(Json(hazmat.get_product(&sku).await?).into_response())
+}
+
+async fn put_product(
+    State(hazmat): State<Arc<HazmatService>>,
+    Path(sku): Path<String>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<HazmatInput>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "hazmat:write") {
+        return Ok(response);
+    }
+    let product = hazmat.set_product(&sku, input, &claims.actor()).await?;
+    info!("Hazmat classification of {} written by {}", sku, claims.actor());
+    Ok(Json(product).into_response())
+}
+
+async fn delete_product(
+    State(hazmat): State<Arc<HazmatService>>,
+    Path(sku): Path<String>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "hazmat:write") {
+        return Ok(response);
+    }
+    hazmat.remove_product(&sku).await?;
+    info!("Hazmat classification of {} removed by {}", sku, claims.actor());
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// Whether a carrier service may carry the given items, and why not.
+async fn check(
+    State(hazmat): State<Arc<HazmatService>>,
+    AuthUser(claims): AuthUser,
+    Json(request): Json<HazmatCheck>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "hazmat:read") {
+        return Ok(response);
+    }
+    Ok(Json(hazmat.check(request).await?).into_response())
+}
+
+async fn get_shipment_goods(
+    State(hazmat): State<Arc<HazmatService>>,
+    Path(shipment_id): Path<i32>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "shipments:read") {
+        return Ok(response);
+    }
+    let goods = hazmat.goods(shipment_id).await?;
+    Ok(Json(json!({ "shipment_id": shipment_id, "items": goods })).into_response())
+}
+
+async fn put_shipment_goods(
+    State(hazmat): State<Arc<HazmatService>>,
+    Path(shipment_id): Path<i32>,
+    AuthUser(claims): AuthUser,
+    Json(request): Json<DeclareGoods>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "shipments:write") {
+        return Ok(response);
+    }
+    let lines = hazmat.declare(shipment_id, request).await?;
+    info!("Dangerous goods of shipment {} declared by {}", shipment_id, claims.actor());
+    Ok(Json(json!({ "shipment_id": shipment_id, "items": lines })).into_response())
+}
+
+/// The shipper's declaration for dangerous goods, to print with the label.
+async fn get_declaration(
+    State(hazmat): State<Arc<HazmatService>>,
+    Path(shipment_id): Path<i32>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "shipments:read") {
+        return Ok(response);
+    }
+    let document = hazmat.declaration(shipment_id).await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/plain; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"dg-declaration-{}.txt\"", shipment_id),
+            ),
+        ],
+        document,
+    )
+        .into_response())
+}
+
+pub fn hazmat_routes<S>(hazmat: Arc<HazmatService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/check", post(check))
+        .route("/products/:sku", get(get_product).put(put_product).delete(delete_product))
+        .route("/shipments/:id", get(get_shipment_goods).put(put_shipment_goods))
+        .route("/shipments/:id/declaration", get(get_declaration))
+        .with_state(hazmat)
+}
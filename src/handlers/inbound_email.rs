@@ -0,0 +1,146 @@
+use axum::{
+    extract::{Multipart, Path, Query, State},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::inbound_email::{InboundEmailError, InboundEmailService};
+use crate::models::inbound_email::InboundEmailStatus;
+use crate::utils::pagination::PaginationParams;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TokenQuery {
+    pub token: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct InboundEmailFilter {
+    pub status: Option<InboundEmailStatus>,
+}
+
+/// SendGrid Inbound Parse. With "POST the raw, full MIME message" on, the message is in
+/// the `email` field; otherwise it is rebuilt from `headers` and `text`.
+async fn sendgrid(
+    State(service): State<Arc<InboundEmailService>>,
+    Query(query): Query<TokenQuery>,
+    mut form: Multipart,
+) -> Result<Response, InboundEmailError> {
+    service.authorize(query.token.as_deref())?;
+    let mut fields = HashMap::new();
+    while let Some(field) = form.next_field().await.map_err(|e| InboundEmailError::Invalid(e.to_string()))? {
+        let Some(name) = field.name().map(str::to_string) else { continue };
+        if matches!(name.as_str(), "email" | "headers" | "text" | "SPF" | "dkim") {
+            let value = field.text().await.map_err(|e| InboundEmailError::Invalid(e.to_string()))?;
+            fields.insert(name, value);
+        }
+    }
+    let raw = match (fields.remove("email"), fields.remove("headers")) {
+        (Some(raw), _) => raw,
+        (None, Some(headers)) => format!("{}\r\n\r\n{}", headers.trim_end(), fields.remove("text").unwrap_or_default()),
+        (None, None) => return Err(InboundEmailError::Invalid("no email or headers field".to_string())),
+    };
+    // Inbound Parse passes SPF results and DKIM verdicts in the form, not the message
+    let email = service.receive("sendgrid", raw.as_bytes(), sendgrid_authenticated(&fields)).await?;
+    Ok(Json(json!({ "id": email.id, "status": email.status, "return_id": email.return_id })).into_response())
+}
+
+/// Whether SendGrid passed the sender's SPF check and at least one DKIM signature, e.g.
+/// `dkim` = `{@example.com : pass}`.
+fn sendgrid_authenticated(fields: &HashMap<String, String>) -> bool {
+    let spf = fields.get("SPF").map_or(false, |spf| spf.trim().eq_ignore_ascii_case("pass"));
+    let dkim = fields.get("dkim").map_or(false, |dkim| dkim.to_ascii_lowercase().contains(": pass"));
+    spf && dkim
+}
+
+/// SES receipt rules publishing to SNS.
+async fn ses(
+    State(service): State<Arc<InboundEmailService>>,
+    Query(query): Query<TokenQuery>,
+    body: String,
+) -> Result<Response, InboundEmailError> {
+    service.authorize(query.token.as_deref())?;
+    // SNS posts JSON as text/plain, so the body is parsed here rather than by `Json`
+    let body: Value = serde_json::from_str(&body).map_err(|e| InboundEmailError::Invalid(e.to_string()))?;
+    let email = service.receive_sns(&body).await?;
+    Ok(Json(json!({
+        "id": email.as_ref().map(|e| e.id),
+        "status": email.as_ref().map(|e| e.status),
+        "return_id": email.and_then(|e| e.return_id),
+    }))
+    .into_response())
+}
+
+async fn list_inbound_emails(
+    State(service): State<Arc<InboundEmailService>>,
+    Query(filter): Query<InboundEmailFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, InboundEmailError> {
+    if let Some(response) = forbidden(&claims, "returns:read") {
+        return Ok(response);
+    }
+    let (items, total) = service.list(filter.status, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+/// An inbound email with its original message, for customer service reviewing a draft.
+async fn get_inbound_email(
+    State(service): State<Arc<InboundEmailService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, InboundEmailError> {
+    if let Some(response) = forbidden(&claims, "returns:read") {
+        return Ok(response);
+    }
+    let email = service.get(id).await?;
+    let raw = email.raw.clone();
+    Ok(Json(json!({ "email": email, "raw": raw })).into_response())
+}
+
+/// Moves the draft return an email produced to `Requested` after review.
+async fn promote_draft(
+    State(service): State<Arc<InboundEmailService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, InboundEmailError> {
+    if let Some(response) = forbidden(&claims, "returns:write") {
+        return Ok(response);
+    }
+    let ret = service.promote(id, &claims.actor()).await?;
+    Ok(Json(ret).into_response())
+}
+
+/// Provider webhooks; merged outside `auth_middleware`, as providers pass a `?token=`.
+pub fn inbound_email_webhook_routes<S>(service: Arc<InboundEmailService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/api/v1/inbound-email/sendgrid", post(sendgrid))
+        .route("/api/v1/inbound-email/ses", post(ses))
+        .with_state(service)
+}
+
+pub fn inbound_email_routes<S>(service: Arc<InboundEmailService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_inbound_emails))
+        .route("/:id", get(get_inbound_email))
+        .route("/:id/promote", post(promote_draft))
+        .with_state(service)
+}
@@ -0,0 +1,105 @@
+use axum::{
+    body::Bytes,
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{admin_only, AuthUser};
+use crate::ingest::{IngestError, IngestService, Outcome};
+use crate::models::ingest_quarantine::QuarantineStatus;
+use crate::utils::pagination::PaginationParams;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct QuarantineFilter {
+    pub source: Option<String>,
+    pub status: Option<QuarantineStatus>,
+}
+
+/// Receives a partner webhook. Quarantined payloads are acknowledged with 202 so the
+/// sender stops retrying; they are fixed up from the quarantine queue instead.
+async fn ingest(
+    State(service): State<Arc<IngestService>>,
+    Path(source): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, IngestError> {
+    let outcome = service.ingest(&source, &headers, &body).await?;
+    let status = match outcome {
+        Outcome::Applied { .. } => StatusCode::OK,
+        Outcome::Quarantined { .. } => StatusCode::ACCEPTED,
+    };
+    Ok((status, Json(outcome)).into_response())
+}
+
+async fn list_quarantine(
+    State(service): State<Arc<IngestService>>,
+    Query(filter): Query<QuarantineFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, IngestError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let (items, total) = service.list_quarantine(filter.source, filter.status, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+/// Runs a quarantined payload through its source's mappings again.
+async fn retry_quarantined(
+    State(service): State<Arc<IngestService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, IngestError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let item = service.retry(id).await?;
+    info!("Quarantined payload {} retried by {}", id, claims.actor());
+    Ok(Json(item).into_response())
+}
+
+async fn discard_quarantined(
+    State(service): State<Arc<IngestService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, IngestError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let item = service.discard(id).await?;
+    info!("Quarantined payload {} discarded by {}", id, claims.actor());
+    Ok(Json(item).into_response())
+}
+
+/// Partner webhooks; merged outside `auth_middleware`, as sources authenticate by signature.
+pub fn ingest_routes<S>(service: Arc<IngestService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new().route("/api/v1/ingest/:source", post(ingest)).with_state(service)
+}
+
+pub fn quarantine_routes<S>(service: Arc<IngestService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_quarantine))
+        .route("/:id/retry", post(retry_quarantined))
+        .route("/:id/discard", post(discard_quarantined))
+        .with_state(service)
+}
@@ -19,7 +19,10 @@ use crate::commands::inventory::{
     DeleteProductCommand,
     ReserveInventoryCommand,
     ReleaseInventoryCommand,
+    ExtendReservationCommand,
+    ConvertReservationCommand,
 };
+use crate::events::EventSender;
 
 async fn create_product(
     State(pool): State<Arc<DbPool>>,
@@ -131,6 +134,28 @@ async fn release_inventory(
     Ok(Json(result))
 }
 
+/// Keeps a checkout's holds alive while the shopper is active.
+async fn extend_reservation(
+    State(pool): State<Arc<DbPool>>,
+    State(event_sender): State<Arc<EventSender>>,
+    AuthenticatedUser(_user): AuthenticatedUser,
+    Json(command): Json<ExtendReservationCommand>,
+) -> Result<impl IntoResponse, ServiceError> {
+    let result = command.execute(pool, event_sender).await?;
+    Ok(Json(result))
+}
+
+/// Converts a checkout's holds into order reservations, or fails if any hold expired.
+async fn convert_reservation(
+    State(pool): State<Arc<DbPool>>,
+    State(event_sender): State<Arc<EventSender>>,
+    AuthenticatedUser(_user): AuthenticatedUser,
+    Json(command): Json<ConvertReservationCommand>,
+) -> Result<impl IntoResponse, ServiceError> {
+    let result = command.execute(pool, event_sender).await?;
+    Ok(Json(result))
+}
+
 pub fn inventory_routes() -> Router {
     Router::new()
         .route("/", post(create_product))
@@ -143,5 +168,7 @@ pub fn inventory_routes() -> Router {
         .route("/low-stock", get(get_low_stock_products))
         .route("/reserve", post(reserve_inventory))
         .route("/release", post(release_inventory))
+        .route("/reservations/extend", post(extend_reservation))
+        .route("/reservations/convert", post(convert_reservation))
         .route("/movement", get(get_inventory_movement))
 }
@@ -0,0 +1,44 @@
+use axum::{
+    extract::{Query, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::auth::AuthUser;
+use crate::inventory_snapshots::{parse_as_of, InventorySnapshotService, SnapshotError};
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryParams {
+    pub sku: String,
+    pub warehouse: Option<i32>,
+    /// `YYYY-MM-DD` (end of that day, UTC) or an RFC 3339 timestamp.
+    pub at: String,
+}
+
+/// Returns on-hand, reserved and allocated quantities of a SKU as of a past date, from
+/// the latest snapshot taken at or before `at`, one entry per warehouse.
+async fn get_history(
+    State(snapshots): State<Arc<InventorySnapshotService>>,
+    Query(params): Query<HistoryParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, SnapshotError> {
+    if !claims.is_allowed("inventory:read") {
+        return Err(SnapshotError::Forbidden);
+    }
+    let at = parse_as_of(&params.at)?;
+    let levels = snapshots.as_of(&params.sku, params.warehouse, at).await?;
+    Ok(Json(json!({ "sku": params.sku, "at": at, "levels": levels })).into_response())
+}
+
+pub fn history_routes<S>(snapshots: Arc<InventorySnapshotService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/history", get(get_history))
+        .with_state(snapshots)
+}
@@ -0,0 +1,64 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, put},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::auth::AuthUser;
+use crate::change_feed::SyncParams;
+use crate::inventory_levels::{InventoryLevelError, InventoryLevelService, LevelBatch};
+use crate::utils::pagination::PaginationParams;
+
+/// Sets on-hand quantities for up to `MAX_BATCH_ROWS` SKU/warehouse rows at once. Rows
+/// whose quantity is unchanged are left alone; `events` picks which level events are sent.
+async fn upsert_levels(
+    State(levels): State<Arc<InventoryLevelService>>,
+    Path(action): Path<String>,
+    AuthUser(claims): AuthUser,
+    Json(batch): Json<LevelBatch>,
+) -> Result<Response, InventoryLevelError> {
+    if action != ":batch" {
+        return Ok(StatusCode::NOT_FOUND.into_response());
+    }
+    if !claims.is_allowed("inventory:write") {
+        return Err(InventoryLevelError::Forbidden);
+    }
+    let summary = levels.upsert_batch(batch).await?;
+    Ok(Json(summary).into_response())
+}
+
+/// Inventory rows by SKU, or with `updated_since` the rows changed and deleted since then.
+async fn list_levels(
+    State(levels): State<Arc<InventoryLevelService>>,
+    Query(pagination): Query<PaginationParams>,
+    Query(sync): Query<SyncParams>,
+    AuthUser(_claims): AuthUser,
+) -> Result<Response, InventoryLevelError> {
+    if let Some(since) = sync.updated_since {
+        return Ok(levels.changes(since, &sync).await.into_response());
+    }
+    let (items, total) = levels.list(pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+pub fn level_routes<S>(levels: Arc<InventoryLevelService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    // The router reads `:action` as a parameter holding the rest of the segment, so
+    // `/levels:batch` arrives with action ":batch"
+    Router::new()
+        .route("/levels", get(list_levels))
+        .route("/levels:action", put(upsert_levels))
+        .with_state(levels)
+}
@@ -0,0 +1,45 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+use std::{sync::Arc, time::Duration};
+use uuid::Uuid;
+
+use crate::auth::{admin_only, AuthUser};
+use crate::jobs::{JobError, JobRunner};
+
+#[derive(Debug, Deserialize)]
+pub struct JobStatusParams {
+    /// Seconds to wait for the job to finish before responding (long-poll), capped at 60.
+    #[serde(default)]
+    pub wait: u64,
+}
+
+/// Returns a job's status, progress and, once finished, its result or error. Jobs are
+/// visible to the actor that submitted them and to admins; others get a 404.
+async fn get_job(
+    State(runner): State<Arc<JobRunner>>,
+    Path(id): Path<Uuid>,
+    Query(params): Query<JobStatusParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, JobError> {
+    // Checked before waiting, so nobody can hold a long-poll open on someone else's job
+    let job = runner.get(id).await?;
+    if admin_only(&claims).is_some() && job.created_by.as_deref() != Some(claims.actor().as_str()) {
+        return Err(JobError::NotFound(id));
+    }
+    let job = runner.wait_for(job, Duration::from_secs(params.wait)).await?;
+    Ok(Json(job).into_response())
+}
+
+pub fn job_routes<S>(runner: Arc<JobRunner>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/:id", get(get_job))
+        .with_state(runner)
+}
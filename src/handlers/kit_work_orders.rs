@@ -0,0 +1,108 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::kitting_service::{KitWorkOrderFilter, KittingService, NewKitWorkOrder};
+use crate::utils::pagination::PaginationParams;
+
+async fn list_kit_work_orders(
+    State(kitting): State<Arc<KittingService>>,
+    Query(filter): Query<KitWorkOrderFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "work_orders:read") {
+        return Ok(response);
+    }
+    let (items, total) = kitting.list(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+async fn create_kit_work_order(
+    State(kitting): State<Arc<KittingService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewKitWorkOrder>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "work_orders:write") {
+        return Ok(response);
+    }
+    let work_order = kitting.create(input, &claims.actor()).await?;
+    info!("Kit work order {} created by {}", work_order.work_order.number, claims.actor());
+    Ok((StatusCode::CREATED, Json(work_order)).into_response())
+}
+
+async fn get_kit_work_order(
+    State(kitting): State<Arc<KittingService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "work_orders:read") {
+        return Ok(response);
+    }
+    Ok(Json(kitting.get(id).await?).into_response())
+}
+
+async fn start_kit_work_order(
+    State(kitting): State<Arc<KittingService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "work_orders:write") {
+        return Ok(response);
+    }
+    Ok(Json(kitting.start(id).await?).into_response())
+}
+
+/// Converts the stock and returns the work order with its rolled-up costs.
+async fn complete_kit_work_order(
+    State(kitting): State<Arc<KittingService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "work_orders:write") {
+        return Ok(response);
+    }
+    let work_order = kitting.complete(id).await?;
+    info!("Kit work order {} completed by {}", work_order.work_order.number, claims.actor());
+    Ok(Json(work_order).into_response())
+}
+
+async fn cancel_kit_work_order(
+    State(kitting): State<Arc<KittingService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "work_orders:write") {
+        return Ok(response);
+    }
+    Ok(Json(kitting.cancel(id).await?).into_response())
+}
+
+pub fn kit_work_order_routes<S>(kitting: Arc<KittingService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_kit_work_orders).post(create_kit_work_order))
+        .route("/:id", get(get_kit_work_order))
+        .route("/:id/start", post(start_kit_work_order))
+        .route("/:id/complete", post(complete_kit_work_order))
+        .route("/:id/cancel", post(cancel_kit_work_order))
+        .with_state(kitting)
+}
@@ -0,0 +1,51 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use std::sync::Arc;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::labels::{render_png, render_zpl, LabelEntity, LabelError, LabelQuery, LabelService, Output};
+
+/// Prints a label for a SKU, bin, shipment or work order, as ZPL for Zebra printers or as
+/// a 203 dpi PNG for everything else.
+async fn get_label(
+    State(labels): State<Arc<LabelService>>,
+    Path((entity, id)): Path<(LabelEntity, String)>,
+    Query(query): Query<LabelQuery>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, LabelError> {
+    if let Some(response) = forbidden(&claims, "labels:print") {
+        return Ok(response);
+    }
+    let label = labels.label(entity, &id).await?;
+    let filename = label.data.replace(|c: char| !c.is_ascii_alphanumeric() && c != '-', "_");
+    Ok(match query.output {
+        Output::Zpl => (
+            [
+                (header::CONTENT_TYPE, "application/zpl".to_string()),
+                (header::CONTENT_DISPOSITION, format!("inline; filename=\"{}.zpl\"", filename)),
+            ],
+            render_zpl(&label, query.format, query.media)?,
+        )
+            .into_response(),
+        Output::Png => (
+            [
+                (header::CONTENT_TYPE, "image/png".to_string()),
+                (header::CONTENT_DISPOSITION, format!("inline; filename=\"{}.png\"", filename)),
+            ],
+            render_png(&label, query.format, query.media)?,
+        )
+            .into_response(),
+    })
+}
+
+pub fn label_routes<S>(labels: Arc<LabelService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new().route("/:entity/:id", get(get_label)).with_state(labels)
+}
@@ -0,0 +1,92 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use chrono::NaiveDate;
+use sea_orm::Iterable;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::ledger::{LedgerError, LedgerService};
+use crate::models::ledger_entry::LedgerAccount;
+use crate::utils::pagination::PaginationParams;
+
+#[derive(Debug, Deserialize)]
+struct TrialBalanceParams {
+    as_of: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ActivityParams {
+    from: NaiveDate,
+    to: NaiveDate,
+}
+
+/// The chart of accounts.
+async fn list_accounts(AuthUser(claims): AuthUser) -> Result<Response, LedgerError> {
+    if let Some(response) = forbidden(&claims, "finance:read") {
+        return Ok(response);
+    }
+    let accounts: Vec<_> = LedgerAccount::iter()
+        .map(|account| json!({ "account": account, "name": account.name(), "normal_balance": account.normal_balance() }))
+        .collect();
+    Ok(Json(json!({ "accounts": accounts })).into_response())
+}
+
+async fn trial_balance(
+    State(ledger): State<Arc<LedgerService>>,
+    Query(params): Query<TrialBalanceParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, LedgerError> {
+    if let Some(response) = forbidden(&claims, "finance:read") {
+        return Ok(response);
+    }
+    Ok(Json(ledger.trial_balance(params.as_of).await?).into_response())
+}
+
+async fn account_activity(
+    State(ledger): State<Arc<LedgerService>>,
+    Path(account): Path<LedgerAccount>,
+    Query(params): Query<ActivityParams>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, LedgerError> {
+    if let Some(response) = forbidden(&claims, "finance:read") {
+        return Ok(response);
+    }
+    let activity = ledger.activity(account, params.from, params.to, pagination).await?;
+    Ok(Json(json!({
+        "activity": activity,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+async fn get_transaction(
+    State(ledger): State<Arc<LedgerService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, LedgerError> {
+    if let Some(response) = forbidden(&claims, "finance:read") {
+        return Ok(response);
+    }
+    Ok(Json(ledger.transaction(id).await?).into_response())
+}
+
+pub fn ledger_routes<S>(ledger: Arc<LedgerService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/accounts", get(list_accounts))
+        .route("/accounts/:account/activity", get(account_activity))
+        .route("/trial-balance", get(trial_balance))
+        .route("/transactions/:id", get(get_transaction))
+        .with_state(ledger)
+}
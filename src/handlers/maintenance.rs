@@ -0,0 +1,26 @@
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use std::sync::Arc;
+
+use crate::auth::{admin_only, AuthUser};
+use crate::maintenance::Maintenance;
+
+/// Active kill switches and read-only mode. They are toggled through the feature flags
+/// in the config, which are picked up on SIGHUP or the next reload interval.
+async fn maintenance_status(State(maintenance): State<Arc<Maintenance>>, AuthUser(claims): AuthUser) -> Response {
+    if let Some(response) = admin_only(&claims) {
+        return response;
+    }
+    Json(maintenance.status()).into_response()
+}
+
+pub fn maintenance_routes<S>(maintenance: Arc<Maintenance>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new().route("/", get(maintenance_status)).with_state(maintenance)
+}
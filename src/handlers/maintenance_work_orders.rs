@@ -0,0 +1,248 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::equipment_service::{
+    CompleteMaintenance, EquipmentFilter, EquipmentService, MaintenanceFilter, MetricsQuery, NewCorrectiveWorkOrder,
+    NewDowntime, NewEquipment, NewMeterReading, NewSchedule, StartMaintenance,
+};
+use crate::utils::pagination::PaginationParams;
+
+#[derive(Debug, Deserialize)]
+pub struct HistoryQuery {
+    #[serde(default = "default_history")]
+    pub limit: u64,
+}
+
+fn default_history() -> u64 {
+    50
+}
+
+async fn list_equipment(
+    State(equipment): State<Arc<EquipmentService>>,
+    Query(filter): Query<EquipmentFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "maintenance:read") {
+        return Ok(response);
+    }
+    let (items, total) = equipment.list_equipment(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+async fn create_equipment(
+    State(equipment): State<Arc<EquipmentService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewEquipment>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "maintenance:write") {
+        return Ok(response);
+    }
+    let created = equipment.create_equipment(input).await?;
+    info!("Equipment {} registered by {}", created.asset_tag, claims.actor());
+    Ok((StatusCode::CREATED, Json(created)).into_response())
+}
+
+async fn get_equipment(
+    State(equipment): State<Arc<EquipmentService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "maintenance:read") {
+        return Ok(response);
+    }
+    Ok(Json(equipment.get_equipment(id).await?).into_response())
+}
+
+async fn retire_equipment(
+    State(equipment): State<Arc<EquipmentService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "maintenance:write") {
+        return Ok(response);
+    }
+    Ok(Json(equipment.retire(id).await?).into_response())
+}
+
+async fn add_schedule(
+    State(equipment): State<Arc<EquipmentService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewSchedule>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "maintenance:write") {
+        return Ok(response);
+    }
+    Ok((StatusCode::CREATED, Json(equipment.add_schedule(id, input).await?)).into_response())
+}
+
+async fn deactivate_schedule(
+    State(equipment): State<Arc<EquipmentService>>,
+    Path((id, schedule_id)): Path<(Uuid, Uuid)>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "maintenance:write") {
+        return Ok(response);
+    }
+    Ok(Json(equipment.deactivate_schedule(id, schedule_id).await?).into_response())
+}
+
+async fn meter_history(
+    State(equipment): State<Arc<EquipmentService>>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<HistoryQuery>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "maintenance:read") {
+        return Ok(response);
+    }
+    Ok(Json(equipment.meter_history(id, query.limit.min(500)).await?).into_response())
+}
+
+/// Records a reading; preventive work orders it makes due are raised right away.
+async fn record_meter(
+    State(equipment): State<Arc<EquipmentService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewMeterReading>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "maintenance:write") {
+        return Ok(response);
+    }
+    Ok((StatusCode::CREATED, Json(equipment.record_meter(id, input, &claims.actor()).await?)).into_response())
+}
+
+async fn start_downtime(
+    State(equipment): State<Arc<EquipmentService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewDowntime>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "maintenance:write") {
+        return Ok(response);
+    }
+    Ok((StatusCode::CREATED, Json(equipment.start_downtime(id, input, &claims.actor()).await?)).into_response())
+}
+
+async fn end_downtime(
+    State(equipment): State<Arc<EquipmentService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "maintenance:write") {
+        return Ok(response);
+    }
+    Ok(Json(equipment.end_downtime(id).await?).into_response())
+}
+
+async fn create_corrective(
+    State(equipment): State<Arc<EquipmentService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewCorrectiveWorkOrder>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "maintenance:write") {
+        return Ok(response);
+    }
+    let work_order = equipment.create_corrective(id, input, &claims.actor()).await?;
+    Ok((StatusCode::CREATED, Json(work_order)).into_response())
+}
+
+async fn list_work_orders(
+    State(equipment): State<Arc<EquipmentService>>,
+    Query(filter): Query<MaintenanceFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "maintenance:read") {
+        return Ok(response);
+    }
+    let (items, total) = equipment.list_work_orders(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+async fn start_work_order(
+    State(equipment): State<Arc<EquipmentService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    input: Option<Json<StartMaintenance>>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "maintenance:write") {
+        return Ok(response);
+    }
+    let input = input.map(|Json(input)| input).unwrap_or_default();
+    Ok(Json(equipment.start_work_order(id, input, &claims.actor()).await?).into_response())
+}
+
+async fn complete_work_order(
+    State(equipment): State<Arc<EquipmentService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    input: Option<Json<CompleteMaintenance>>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "maintenance:write") {
+        return Ok(response);
+    }
+    let input = input.map(|Json(input)| input).unwrap_or_default();
+    let work_order = equipment.complete_work_order(id, input, &claims.actor()).await?;
+    info!("Maintenance work order {} completed by {}", work_order.number, claims.actor());
+    Ok(Json(work_order).into_response())
+}
+
+/// Downtime, MTBF/MTTR and preventive compliance per piece of equipment.
+async fn metrics(
+    State(equipment): State<Arc<EquipmentService>>,
+    Query(query): Query<MetricsQuery>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "maintenance:read") {
+        return Ok(response);
+    }
+    Ok(Json(json!({ "equipment": equipment.metrics(query).await? })).into_response())
+}
+
+pub fn maintenance_routes<S>(equipment: Arc<EquipmentService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/equipment", get(list_equipment).post(create_equipment))
+        .route("/equipment/:id", get(get_equipment))
+        .route("/equipment/:id/retire", post(retire_equipment))
+        .route("/equipment/:id/schedules", post(add_schedule))
+        .route("/equipment/:id/schedules/:schedule_id/deactivate", post(deactivate_schedule))
+        .route("/equipment/:id/meter-readings", get(meter_history).post(record_meter))
+        .route("/equipment/:id/downtime", post(start_downtime))
+        .route("/equipment/:id/downtime/end", post(end_downtime))
+        .route("/equipment/:id/work-orders", post(create_corrective))
+        .route("/work-orders", get(list_work_orders))
+        .route("/work-orders/:id/start", post(start_work_order))
+        .route("/work-orders/:id/complete", post(complete_work_order))
+        .route("/analytics", get(metrics))
+        .with_state(equipment)
+}
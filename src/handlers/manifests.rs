@@ -0,0 +1,99 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::manifest_service::{CloseRequest, ManifestFilter, ManifestService};
+use crate::utils::pagination::PaginationParams;
+
+/// End-of-day close. Responds with one manifest per carrier that had shipments; a
+/// manifest the carrier refused comes back `failed` and its shipments stay open for the
+/// next close.
+async fn close_day(
+    State(manifests): State<Arc<ManifestService>>,
+    AuthUser(claims): AuthUser,
+    body: Option<Json<CloseRequest>>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "shipments:manifest") {
+        return Ok(response);
+    }
+    let request = body.map(|Json(request)| request).unwrap_or_default();
+    let items = manifests.close(request, &claims.actor()).await?;
+    info!("End-of-day close by {}: {} manifests", claims.actor(), items.len());
+    Ok((StatusCode::CREATED, Json(json!({ "items": items }))).into_response())
+}
+
+async fn list_manifests(
+    State(manifests): State<Arc<ManifestService>>,
+    Query(filter): Query<ManifestFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "shipments:read") {
+        return Ok(response);
+    }
+    let (items, total) = manifests.list(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+async fn get_manifest(
+    State(manifests): State<Arc<ManifestService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "shipments:read") {
+        return Ok(response);
+    }
+    let manifest = manifests.get(id).await?;
+    let shipments = manifests.shipments(id).await?;
+    Ok(Json(json!({ "manifest": manifest, "shipments": shipments })).into_response())
+}
+
+/// The printed manifest for the driver.
+async fn manifest_document(
+    State(manifests): State<Arc<ManifestService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "shipments:read") {
+        return Ok(response);
+    }
+    let manifest = manifests.get(id).await?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/plain; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("inline; filename=\"manifest-{:?}-{}.txt\"", manifest.carrier, manifest.manifest_date),
+            ),
+        ],
+        manifest.document,
+    )
+        .into_response())
+}
+
+pub fn manifest_routes<S>(manifests: Arc<ManifestService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_manifests).post(close_day))
+        .route("/:id", get(get_manifest))
+        .route("/:id/document", get(manifest_document))
+        .with_state(manifests)
+}
@@ -1,8 +1,111 @@
+pub mod agentic;
+pub mod accounting;
+pub mod amazon;
+pub mod agents;
+pub mod approvals;
+pub mod analytics;
+pub mod assist;
+pub mod bundles;
 pub mod categories;
+pub mod checkout;
+pub mod credit_memos;
+pub mod payment_captures;
+pub mod payment_vault;
+pub mod sourcing;
+pub mod routing_rules;
+pub mod subscriptions;
+pub mod disputes;
+pub mod write_offs;
+pub mod cross_dock;
+pub mod kit_work_orders;
+pub mod ecos;
+pub mod forecasts;
+pub mod work_centers;
+pub mod maintenance_work_orders;
+pub mod labels;
+pub mod scan;
+pub mod printers;
+pub mod duplicate_orders;
+pub mod shipping_zones;
+pub mod manifests;
+pub mod hazmat;
+pub mod customs;
+pub mod return_dispositions;
+pub mod return_fraud;
+pub mod dropship;
+pub mod pos;
+pub mod usage;
+pub mod webhooks;
+pub mod encryption;
+pub mod database;
+pub mod partitions;
+pub mod backfills;
 pub mod customers;
+pub mod customer_segments;
+pub mod ncr;
+pub mod cases;
 pub mod orders;
+pub mod products;
+pub mod quality;
+pub mod requisitions;
 pub mod returns;
+pub mod service_accounts;
 pub mod warranties;
 pub mod inventory;
+pub mod inventory_history;
+pub mod inventory_levels;
+pub mod ingest;
+pub mod abuse;
+pub mod maintenance;
+pub mod slos;
+pub mod synthetic;
+pub mod stock_alerts;
+pub mod custom_fields;
+pub mod developer_logs;
+pub mod tenant_exports;
+pub mod inbound_email;
+pub mod jobs;
+pub mod ledger;
 pub mod shipments;
-pub mod work_orders;
\ No newline at end of file
+pub mod work_orders;
+pub mod work_order_operations;
+
+use axum::extract::FromRef;
+use std::sync::Arc;
+
+use crate::db::DbPool;
+use crate::services::{
+    order_service::{OrderService, OrderServiceApi},
+    return_service::{ReturnService, ReturnServiceApi},
+};
+
+/// Services shared by the HTTP handlers. Members are trait objects so handler tests can
+/// substitute the `mockall`-generated mocks (`MockOrderServiceApi`, ...) for the
+/// database-backed implementations.
+#[derive(Clone)]
+pub struct AppServices {
+    pub orders: Arc<dyn OrderServiceApi>,
+    pub returns: Arc<dyn ReturnServiceApi>,
+}
+
+impl AppServices {
+    /// Database-backed services.
+    pub fn new(db_pool: Arc<DbPool>) -> Self {
+        Self {
+            orders: Arc::new(OrderService::new(db_pool.clone())),
+            returns: Arc::new(ReturnService::new(db_pool)),
+        }
+    }
+}
+
+impl FromRef<AppServices> for Arc<dyn OrderServiceApi> {
+    fn from_ref(services: &AppServices) -> Self {
+        services.orders.clone()
+    }
+}
+
+impl FromRef<AppServices> for Arc<dyn ReturnServiceApi> {
+    fn from_ref(services: &AppServices) -> Self {
+        services.returns.clone()
+    }
+}
@@ -0,0 +1,171 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::ncr_service::{
+    ActionTransition, Assignment, NcrFilter, NcrTransition, NewAction, NewNcr, NonConformanceService,
+    RecurrenceKey,
+};
+use crate::utils::pagination::PaginationParams;
+
+#[derive(Debug, Deserialize)]
+struct RecurrenceQuery {
+    by: RecurrenceKey,
+    #[serde(default = "default_days")]
+    days: i64,
+    #[serde(default = "default_min_count")]
+    min_count: u64,
+}
+
+fn default_days() -> i64 {
+    90
+}
+
+fn default_min_count() -> u64 {
+    2
+}
+
+async fn list_ncrs(
+    State(ncrs): State<Arc<NonConformanceService>>,
+    Query(filter): Query<NcrFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "quality:read") {
+        return Ok(response);
+    }
+    let (items, total) = ncrs.list(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+/// Raises an NCR against an inspection, return or warranty claim.
+async fn create_ncr(
+    State(ncrs): State<Arc<NonConformanceService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewNcr>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "quality:write") {
+        return Ok(response);
+    }
+    let ncr = ncrs.create(input, &claims.actor()).await?;
+    info!("NCR {} raised by {}", ncr.id, claims.actor());
+    Ok((StatusCode::CREATED, Json(ncr)).into_response())
+}
+
+async fn get_ncr(
+    State(ncrs): State<Arc<NonConformanceService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "quality:read") {
+        return Ok(response);
+    }
+    Ok(Json(ncrs.get(id).await?).into_response())
+}
+
+async fn transition_ncr(
+    State(ncrs): State<Arc<NonConformanceService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(transition): Json<NcrTransition>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "quality:write") {
+        return Ok(response);
+    }
+    let ncr = ncrs.transition(id, transition).await?;
+    info!("NCR {} moved to {:?} by {}", id, ncr.status, claims.actor());
+    Ok(Json(ncr).into_response())
+}
+
+async fn assign_ncr(
+    State(ncrs): State<Arc<NonConformanceService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(assignment): Json<Assignment>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "quality:write") {
+        return Ok(response);
+    }
+    Ok(Json(ncrs.assign(id, assignment).await?).into_response())
+}
+
+async fn add_action(
+    State(ncrs): State<Arc<NonConformanceService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewAction>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "quality:write") {
+        return Ok(response);
+    }
+    Ok((StatusCode::CREATED, Json(ncrs.add_action(id, input).await?)).into_response())
+}
+
+async fn assign_action(
+    State(ncrs): State<Arc<NonConformanceService>>,
+    Path((id, action_id)): Path<(Uuid, Uuid)>,
+    AuthUser(claims): AuthUser,
+    Json(assignment): Json<Assignment>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "quality:write") {
+        return Ok(response);
+    }
+    Ok(Json(ncrs.assign_action(id, action_id, assignment).await?).into_response())
+}
+
+async fn transition_action(
+    State(ncrs): State<Arc<NonConformanceService>>,
+    Path((id, action_id)): Path<(Uuid, Uuid)>,
+    AuthUser(claims): AuthUser,
+    Json(transition): Json<ActionTransition>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "quality:write") {
+        return Ok(response);
+    }
+    Ok(Json(ncrs.transition_action(id, action_id, transition).await?).into_response())
+}
+
+/// SKUs or suppliers with repeated NCRs, e.g. `?by=supplier&days=180&min_count=3`.
+async fn recurrence(
+    State(ncrs): State<Arc<NonConformanceService>>,
+    Query(query): Query<RecurrenceQuery>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "quality:read") {
+        return Ok(response);
+    }
+    Ok(Json(ncrs.recurrence(query.by, query.days, query.min_count).await?).into_response())
+}
+
+pub fn ncr_routes<S>(ncrs: Arc<NonConformanceService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_ncrs).post(create_ncr))
+        .route("/recurrence", get(recurrence))
+        .route("/:id", get(get_ncr))
+        .route("/:id/status", post(transition_ncr))
+        .route("/:id/assignment", put(assign_ncr))
+        .route("/:id/actions", post(add_action))
+        .route("/:id/actions/:action_id/assignment", put(assign_action))
+        .route("/:id/actions/:action_id/status", post(transition_action))
+        .with_state(ncrs)
+}
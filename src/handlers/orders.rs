@@ -1,14 +1,17 @@
 use axum::{
     routing::{post, get, put, delete},
     extract::{State, Path, Query, Json},
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     Router,
 };
 use crate::{
     db::DbPool,
     models::order::{OrderStatus, PaymentMethod},
     errors::ServiceError,
-    auth::AuthenticatedUser,
+    auth::{forbidden, AuthUser, AuthenticatedUser},
+    change_feed::{self, SyncEntity, SyncParams},
+    custom_fields::MetadataFilter,
+    services::order_service::{OrderSearchParams, OrderServiceApi},
     utils::pagination::PaginationParams,
 };
 use std::sync::Arc;
@@ -17,12 +20,13 @@ use validator::Validate;
 use chrono::{DateTime, Utc};
 use serde_json::json;
 use tracing::info;
+use uuid::Uuid;
 
 // Import the commands
 use crate::commands::orders::{
     CreateOrderCommand, ApplyOrderDiscountCommand, CancelOrderCommand,
     UpdateOrderItemsCommand, PartialCancelOrderCommand, AddItemToOrderCommand,
-    RemoveItemFromOrderCommand, ShipOrderCommand,
+    RemoveItemFromOrderCommand, ShipOrderCommand, OrderEventStore,
 };
 
 // Structs remain the same
@@ -41,6 +45,9 @@ async fn create_order(
         shipping_address: order_info.shipping_address,
         billing_address: order_info.billing_address,
         payment_method: order_info.payment_method,
+        total: order_info.total,
+        allow_duplicate: order_info.allow_duplicate,
+        placed_by: Some(format!("user:{}", user.user_id)),
     };
 
     let result = command.execute(db_pool, event_sender).await?;
@@ -49,13 +56,16 @@ async fn create_order(
 }
 
 async fn get_order(
-    State(order_service): State<Arc<OrderService>>,
-    Path(id): Path<i32>,
-    AuthenticatedUser(user): AuthenticatedUser,
-) -> Result<impl IntoResponse, ServiceError> {
-    let order = order_service.get_order(id, user.user_id).await?;
-    info!("Order retrieved by user {}: {:?}", user.user_id, order);
-    Ok(Json(order))
+    State(order_service): State<Arc<dyn OrderServiceApi>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "orders:read") {
+        return Ok(response);
+    }
+    let order = order_service.get_order(id).await?;
+    info!("Order retrieved by {}: {:?}", claims.actor(), order);
+    Ok(Json(order).into_response())
 }
 
 async fn update_order_items(
@@ -77,42 +87,72 @@ async fn update_order_items(
 }
 
 async fn delete_order(
-    State(order_service): State<Arc<OrderService>>,
-    Path(id): Path<i32>,
-    AuthenticatedUser(user): AuthenticatedUser,
-) -> Result<impl IntoResponse, ServiceError> {
-    order_service.delete_order(id, user.user_id).await?;
-    info!("Order deleted by user {}: {}", user.user_id, id);
-    Ok(axum::http::StatusCode::NO_CONTENT)
+    State(order_service): State<Arc<dyn OrderServiceApi>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "orders:write") {
+        return Ok(response);
+    }
+    order_service.delete_order(id).await?;
+    info!("Order deleted by {}: {}", claims.actor(), id);
+    Ok(axum::http::StatusCode::NO_CONTENT.into_response())
 }
 
+/// Lists orders, or with `updated_since` the orders changed and deleted since then.
 async fn list_orders(
-    State(order_service): State<Arc<OrderService>>,
-    AuthenticatedUser(user): AuthenticatedUser,
+    State(order_service): State<Arc<dyn OrderServiceApi>>,
+    State(db_pool): State<Arc<DbPool>>,
+    AuthUser(claims): AuthUser,
     Query(query): Query<PaginationParams>,
-) -> Result<impl IntoResponse, ServiceError> {
-    let (orders, total) = order_service.list_orders(user.user_id, query).await?;
-    info!("Orders listed by user {}: total {}", user.user_id, total);
+    Query(sync): Query<SyncParams>,
+    filter: MetadataFilter,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "orders:read") {
+        return Ok(response);
+    }
+    if let Some(since) = sync.updated_since {
+        info!("Order changes since {} synced by {}", since, claims.actor());
+        let load = |ids| async move { order_service.get_orders(ids).await };
+        return Ok(change_feed::sync_response(&db_pool, SyncEntity::Order, since, &sync, load)
+            .await
+            .into_response());
+    }
+    let (orders, total) = if filter.is_empty() {
+        order_service.list_orders(query).await?
+    } else {
+        let search = OrderSearchParams { metadata: filter.0, ..Default::default() };
+        order_service.search_orders(search, query).await?
+    };
+    info!("Orders listed by {}: total {}", claims.actor(), total);
     Ok(Json(json!({
         "orders": orders,
         "total": total,
         "page": query.page,
         "per_page": query.per_page
-    })))
+    }))
+    .into_response())
 }
 
 async fn search_orders(
-    State(order_service): State<Arc<OrderService>>,
-    AuthenticatedUser(user): AuthenticatedUser,
-    Query(query): Query<OrderSearchParams>,
-) -> Result<impl IntoResponse, ServiceError> {
-    let (orders, total) = order_service.search_orders(user.user_id, query).await?;
-    info!("Orders searched by user {}: total {}", user.user_id, total);
+    State(order_service): State<Arc<dyn OrderServiceApi>>,
+    AuthUser(claims): AuthUser,
+    Query(mut query): Query<OrderSearchParams>,
+    Query(pagination): Query<PaginationParams>,
+    filter: MetadataFilter,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "orders:read") {
+        return Ok(response);
+    }
+    query.metadata = filter.0;
+    let (orders, total) = order_service.search_orders(query.clone(), pagination).await?;
+    info!("Orders searched by {}: total {}", claims.actor(), total);
     Ok(Json(json!({
         "orders": orders,
         "total": total,
         "query": query
-    })))
+    }))
+    .into_response())
 }
 
 async fn add_item_to_order(
@@ -128,6 +168,7 @@ async fn add_item_to_order(
         order_id,
         product_id: item_info.product_id,
         quantity: item_info.quantity,
+        actor: Some(format!("user:{}", user.user_id)),
     };
 
     let order_item = command.execute(db_pool, event_sender).await?;
@@ -178,6 +219,7 @@ async fn cancel_order(
     let command = CancelOrderCommand {
         order_id,
         reason: cancel_info.reason,
+        actor: Some(format!("user:{}", user.user_id)),
     };
 
     let result = command.execute(db_pool, event_sender).await?;
@@ -220,6 +262,42 @@ async fn apply_discount(
     Ok(Json(result))
 }
 
+async fn get_order_history(
+    State(db_pool): State<Arc<DbPool>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "orders:read") {
+        return Ok(response);
+    }
+    let (state, events) = OrderEventStore::rebuild(db_pool.as_ref(), id).await?;
+    info!("Order history retrieved by {}: order_id={}, events={}", claims.actor(), id, events.len());
+    Ok(Json(json!({
+        "order_id": id,
+        "state": state,
+        "events": events,
+    }))
+    .into_response())
+}
+
+/// Everything that happened to an order in one chronological feed, for support agents.
+async fn get_order_timeline(
+    State(order_service): State<Arc<dyn OrderServiceApi>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "orders:read") {
+        return Ok(response);
+    }
+    let timeline = order_service.get_timeline(id).await?;
+    info!("Order timeline retrieved by {}: order_id={}, entries={}", claims.actor(), id, timeline.len());
+    Ok(Json(json!({
+        "order_id": id,
+        "entries": timeline,
+    }))
+    .into_response())
+}
+
 pub fn order_routes() -> Router {
     Router::new()
         .route("/", post(create_order))
@@ -228,6 +306,8 @@ pub fn order_routes() -> Router {
         .route("/create", post(create_order))
         .route("/:id", get(get_order))
         .route("/:id", delete(delete_order))
+        .route("/:id/history", get(get_order_history))
+        .route("/:id/timeline", get(get_order_timeline))
         .route("/:id/items", put(update_order_items))
         .route("/:id/items", post(add_item_to_order))
         .route("/:order_id/items/:item_id", delete(remove_item_from_order))
@@ -0,0 +1,51 @@
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::auth::{admin_only, AuthUser};
+use crate::partitioning::{PartitionError, PartitionManager};
+
+#[derive(Clone)]
+pub struct PartitionRoutesState {
+    pub manager: Arc<PartitionManager>,
+}
+
+/// Configured tables with whether they are partitioned yet and their attached partitions.
+async fn partition_status(
+    State(state): State<PartitionRoutesState>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, PartitionError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let tables = state.manager.status().await?;
+    Ok(Json(json!({ "items": tables })).into_response())
+}
+
+/// Partition changes that are due, with the migration that makes them.
+async fn maintenance_plan(
+    State(state): State<PartitionRoutesState>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, PartitionError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let due = state.manager.maintenance_plan().await?;
+    let migration = (!due.is_empty()).then(|| due.migration_sql());
+    Ok(Json(json!({ "plan": due, "migration": migration })).into_response())
+}
+
+pub fn partition_routes<S>(manager: Arc<PartitionManager>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(partition_status))
+        .route("/plan", get(maintenance_plan))
+        .with_state(PartitionRoutesState { manager })
+}
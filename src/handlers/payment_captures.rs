@@ -0,0 +1,121 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::payment_capture::{ManualCapture, NewAuthorization, PaymentCaptureService};
+
+type Payments = Option<Arc<PaymentCaptureService>>;
+
+fn disabled() -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({ "error": "Payment capture is not enabled", "code": "payments_disabled" })),
+    )
+        .into_response()
+}
+
+#[derive(Debug, Deserialize)]
+struct OrderFilter {
+    order_id: Uuid,
+}
+
+/// Records an authorization taken at checkout; capture-on-order authorizations are
+/// captured straight away.
+async fn create_authorization(
+    State(payments): State<Payments>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewAuthorization>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "payments:write") {
+        return Ok(response);
+    }
+    let Some(payments) = payments else {
+        return Ok(disabled());
+    };
+    let authorization = payments.authorize(input).await?;
+    Ok((StatusCode::CREATED, Json(authorization)).into_response())
+}
+
+async fn list_authorizations(
+    State(payments): State<Payments>,
+    Query(filter): Query<OrderFilter>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "payments:read") {
+        return Ok(response);
+    }
+    let Some(payments) = payments else {
+        return Ok(disabled());
+    };
+    Ok(Json(payments.for_order(filter.order_id).await?).into_response())
+}
+
+async fn get_authorization(
+    State(payments): State<Payments>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "payments:read") {
+        return Ok(response);
+    }
+    let Some(payments) = payments else {
+        return Ok(disabled());
+    };
+    Ok(Json(payments.get(id).await?).into_response())
+}
+
+/// Captures all or part of an authorization. A declined capture is returned with status
+/// `failed` and the gateway's message.
+async fn capture(
+    State(payments): State<Payments>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<ManualCapture>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "payments:write") {
+        return Ok(response);
+    }
+    let Some(payments) = payments else {
+        return Ok(disabled());
+    };
+    let capture = payments.capture_manually(id, input, &claims.actor()).await?;
+    info!("Capture {} on authorization {} by {}", capture.id, id, claims.actor());
+    Ok((StatusCode::CREATED, Json(capture)).into_response())
+}
+
+async fn void_authorization(
+    State(payments): State<Payments>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "payments:write") {
+        return Ok(response);
+    }
+    let Some(payments) = payments else {
+        return Ok(disabled());
+    };
+    Ok(Json(payments.void(id).await?).into_response())
+}
+
+pub fn payment_capture_routes<S>(payments: Payments) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_authorizations).post(create_authorization))
+        .route("/:id", get(get_authorization))
+        .route("/:id/capture", post(capture))
+        .route("/:id/void", post(void_authorization))
+        .with_state(payments)
+}
@@ -0,0 +1,94 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::payment_vault::{NewVaultToken, PaymentVaultService};
+
+type Vault = Option<Arc<PaymentVaultService>>;
+
+fn disabled() -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({ "error": "The payment vault is not enabled", "code": "payment_vault_disabled" })),
+    )
+        .into_response()
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RevokeRequest {
+    /// Also detach the payment method at the processor.
+    #[serde(default)]
+    detach: bool,
+}
+
+/// Wraps a payment method tokenized client-side with the processor in a vault token.
+/// The token is in this response only.
+async fn create_token(
+    State(vault): State<Vault>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewVaultToken>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "payments:write") {
+        return Ok(response);
+    }
+    let Some(vault) = vault else {
+        return Ok(disabled());
+    };
+    let issued = vault.tokenize(input, claims.actor()).await?;
+    info!("Vault token {} issued by {}", issued.record.id, claims.actor());
+    Ok((StatusCode::CREATED, Json(issued)).into_response())
+}
+
+async fn get_token(
+    State(vault): State<Vault>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "payments:read") {
+        return Ok(response);
+    }
+    let Some(vault) = vault else {
+        return Ok(disabled());
+    };
+    Ok(Json(vault.get(id).await?).into_response())
+}
+
+async fn revoke_token(
+    State(vault): State<Vault>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    request: Option<Json<RevokeRequest>>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "payments:write") {
+        return Ok(response);
+    }
+    let Some(vault) = vault else {
+        return Ok(disabled());
+    };
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+    let token = vault.revoke(id, request.detach).await?;
+    info!("Vault token {} revoked by {}", id, claims.actor());
+    Ok(Json(token).into_response())
+}
+
+pub fn payment_vault_routes<S>(vault: Vault) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/tokens", post(create_token))
+        .route("/tokens/:id", get(get_token))
+        .route("/tokens/:id/revoke", post(revoke_token))
+        .with_state(vault)
+}
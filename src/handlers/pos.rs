@@ -0,0 +1,131 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::pos_service::{CloseShift, OpenShift, PosSaleInput, PosService, SaleOutcome, ShiftFilter};
+use crate::utils::pagination::PaginationParams;
+
+#[derive(Debug, Deserialize)]
+struct SaleBatch {
+    sales: Vec<PosSaleInput>,
+}
+
+/// Opens a shift. Registers replay this after an outage like any other queued call.
+async fn open_shift(
+    State(pos): State<Arc<PosService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<OpenShift>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "pos:sell") {
+        return Ok(response);
+    }
+    Ok((StatusCode::CREATED, Json(pos.open_shift(input).await?)).into_response())
+}
+
+async fn close_shift(
+    State(pos): State<Arc<PosService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<CloseShift>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "pos:sell") {
+        return Ok(response);
+    }
+    let shift = pos.close_shift(id, input, &claims.actor()).await?;
+    info!("POS shift {} closed by {}", id, claims.actor());
+    Ok(Json(shift).into_response())
+}
+
+async fn list_shifts(
+    State(pos): State<Arc<PosService>>,
+    Query(filter): Query<ShiftFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "pos:read") {
+        return Ok(response);
+    }
+    let (items, total) = pos.list_shifts(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+async fn get_shift(
+    State(pos): State<Arc<PosService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "pos:read") {
+        return Ok(response);
+    }
+    Ok(Json(pos.shift(id).await?).into_response())
+}
+
+async fn shift_conflicts(
+    State(pos): State<Arc<PosService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "pos:read") {
+        return Ok(response);
+    }
+    Ok(Json(json!({ "shift_id": id, "items": pos.conflicts(id).await? })).into_response())
+}
+
+/// Takes a batch of sales from one register. Every sale gets its own outcome; the
+/// request only fails as a whole on server errors, and is then safe to resend.
+async fn submit_sales(
+    State(pos): State<Arc<PosService>>,
+    Path(register_id): Path<String>,
+    AuthUser(claims): AuthUser,
+    Json(batch): Json<SaleBatch>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "pos:sell") {
+        return Ok(response);
+    }
+    let results = pos.submit_sales(&register_id, batch.sales).await?;
+    let created = results.iter().filter(|r| r.outcome == SaleOutcome::Created).count();
+    info!("Register {} sent {} sales, {} new", register_id, results.len(), created);
+    Ok(Json(json!({ "register_id": register_id, "results": results })).into_response())
+}
+
+async fn sale_payments(
+    State(pos): State<Arc<PosService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "pos:read") {
+        return Ok(response);
+    }
+    Ok(Json(json!({ "sale_id": id, "items": pos.payments(id).await? })).into_response())
+}
+
+pub fn pos_routes<S>(pos: Arc<PosService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/shifts", get(list_shifts).post(open_shift))
+        .route("/shifts/:id", get(get_shift))
+        .route("/shifts/:id/close", post(close_shift))
+        .route("/shifts/:id/conflicts", get(shift_conflicts))
+        .route("/registers/:register_id/sales", post(submit_sales))
+        .route("/sales/:id/payments", get(sale_payments))
+        .with_state(pos)
+}
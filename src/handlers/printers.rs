@@ -0,0 +1,204 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::print_service::{JobResult, NewPrintJob, NewPrinter, PrintJobFilter, PrintService, UpdatePrinter};
+use crate::utils::pagination::PaginationParams;
+
+#[derive(Debug, Deserialize)]
+pub struct PullQuery {
+    #[serde(default = "default_pull")]
+    pub max: u64,
+}
+
+fn default_pull() -> u64 {
+    5
+}
+
+async fn list_printers(
+    State(printing): State<Arc<PrintService>>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "printing:read") {
+        return Ok(response);
+    }
+    let (items, total) = printing.list_printers(pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+async fn register_printer(
+    State(printing): State<Arc<PrintService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewPrinter>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "printers:manage") {
+        return Ok(response);
+    }
+    let printer = printing.register_printer(input).await?;
+    info!("Printer {} registered by {}", printer.name, claims.actor());
+    Ok((StatusCode::CREATED, Json(printer)).into_response())
+}
+
+async fn get_printer(
+    State(printing): State<Arc<PrintService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "printing:read") {
+        return Ok(response);
+    }
+    Ok(Json(printing.get_printer(id).await?).into_response())
+}
+
+async fn update_printer(
+    State(printing): State<Arc<PrintService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<UpdatePrinter>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "printers:manage") {
+        return Ok(response);
+    }
+    Ok(Json(printing.update_printer(id, input).await?).into_response())
+}
+
+async fn enqueue(
+    State(printing): State<Arc<PrintService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewPrintJob>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "printing:write") {
+        return Ok(response);
+    }
+    let job = printing.enqueue(id, input, &claims.actor()).await?;
+    Ok((StatusCode::CREATED, Json(job)).into_response())
+}
+
+/// Called by print agents: claims the printer's next jobs. Content is fetched per job.
+async fn pull(
+    State(printing): State<Arc<PrintService>>,
+    Path(id): Path<Uuid>,
+    Query(query): Query<PullQuery>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "printers:agent") {
+        return Ok(response);
+    }
+    Ok(Json(json!({ "jobs": printing.pull(id, query.max).await? })).into_response())
+}
+
+async fn list_jobs(
+    State(printing): State<Arc<PrintService>>,
+    Query(filter): Query<PrintJobFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "printing:read") {
+        return Ok(response);
+    }
+    let (items, total) = printing.list_jobs(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+async fn get_job(
+    State(printing): State<Arc<PrintService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "printing:read") {
+        return Ok(response);
+    }
+    Ok(Json(printing.get_job(id).await?).into_response())
+}
+
+/// The rendered document, sent to the printer as-is.
+async fn job_content(
+    State(printing): State<Arc<PrintService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if !claims.has_permission("printing:read") {
+        if let Some(response) = forbidden(&claims, "printers:agent") {
+            return Ok(response);
+        }
+    }
+    let job = printing.get_job(id).await?;
+    Ok(([(header::CONTENT_TYPE, job.content_type)], job.content).into_response())
+}
+
+async fn report_job(
+    State(printing): State<Arc<PrintService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(result): Json<JobResult>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "printers:agent") {
+        return Ok(response);
+    }
+    Ok(Json(printing.report(id, result).await?).into_response())
+}
+
+async fn cancel_job(
+    State(printing): State<Arc<PrintService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "printing:write") {
+        return Ok(response);
+    }
+    Ok(Json(printing.cancel(id).await?).into_response())
+}
+
+async fn retry_job(
+    State(printing): State<Arc<PrintService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "printing:write") {
+        return Ok(response);
+    }
+    Ok(Json(printing.retry(id).await?).into_response())
+}
+
+pub fn printer_routes<S>(printing: Arc<PrintService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_printers).post(register_printer))
+        .route("/jobs", get(list_jobs))
+        .route("/jobs/:id", get(get_job))
+        .route("/jobs/:id/content", get(job_content))
+        .route("/jobs/:id/result", post(report_job))
+        .route("/jobs/:id/cancel", post(cancel_job))
+        .route("/jobs/:id/retry", post(retry_job))
+        .route("/:id", get(get_printer).patch(update_printer))
+        .route("/:id/jobs", post(enqueue))
+        .route("/:id/jobs/pull", post(pull))
+        .with_state(printing)
+}
@@ -0,0 +1,112 @@
+use axum::{
+    extract::{Path, Query, State},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use sea_orm::DatabaseConnection;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::change_feed::{self, SyncEntity, SyncParams};
+use crate::custom_fields::MetadataFilter;
+use crate::errors::ServiceError;
+use crate::semantic_search::{SearchError, SemanticSearchService};
+use crate::services::product_listing_service::{ListingFilter, ListingInput, ProductListingService};
+use crate::utils::pagination::PaginationParams;
+
+#[derive(Clone)]
+pub struct ProductRoutesState {
+    pub db: Arc<DatabaseConnection>,
+    pub listings: Arc<ProductListingService>,
+    /// `None` when semantic search is disabled.
+    pub search: Option<Arc<SemanticSearchService>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SemanticSearchRequest {
+    pub query: String,
+    #[serde(default = "default_limit")]
+    pub limit: u64,
+    /// Overrides the configured keyword share of the score.
+    pub keyword_weight: Option<f32>,
+}
+
+fn default_limit() -> u64 {
+    20
+}
+
+/// Listings by SKU, filtered by `active` and `metadata.<key>` parameters. With
+/// `updated_since`, the listings changed and deleted since then, unfiltered.
+async fn list_listings(
+    State(state): State<ProductRoutesState>,
+    Query(filter): Query<ListingFilter>,
+    Query(pagination): Query<PaginationParams>,
+    Query(sync): Query<SyncParams>,
+    metadata: MetadataFilter,
+    AuthUser(_claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(since) = sync.updated_since {
+        let listings = state.listings.clone();
+        let load = |skus| async move { listings.get_many(skus).await };
+        return Ok(change_feed::sync_response(&state.db, SyncEntity::Product, since, &sync, load)
+            .await
+            .into_response());
+    }
+    let (items, total) = state.listings.list(filter, &metadata, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+async fn get_listing(
+    State(state): State<ProductRoutesState>,
+    Path(sku): Path<String>,
+    AuthUser(_claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    Ok(Json(state.listings.get(&sku).await?).into_response())
+}
+
+/// Creates or replaces the catalog listing of a SKU. Admins and `catalog:write` only.
+async fn put_listing(
+    State(state): State<ProductRoutesState>,
+    Path(sku): Path<String>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<ListingInput>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "catalog:write") {
+        return Ok(response);
+    }
+    let listing = state.listings.upsert(&sku, input).await?;
+    info!("Product listing {} written by {}", listing.sku, claims.actor());
+    Ok(Json(listing).into_response())
+}
+
+/// Ranks active listings by meaning and keyword overlap with a free-text query.
+async fn semantic_search(
+    State(state): State<ProductRoutesState>,
+    AuthUser(_claims): AuthUser,
+    Json(request): Json<SemanticSearchRequest>,
+) -> Result<Response, SearchError> {
+    let search = state.search.ok_or(SearchError::Disabled)?;
+    let results = search.search(&request.query, request.limit, request.keyword_weight).await?;
+    Ok(Json(json!({ "query": request.query, "results": results })).into_response())
+}
+
+pub fn product_routes<S>(state: ProductRoutesState) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_listings))
+        .route("/search/semantic", post(semantic_search))
+        .route("/:sku", get(get_listing).put(put_listing))
+        .with_state(state)
+}
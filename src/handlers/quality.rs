@@ -0,0 +1,130 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::quality_service::{
+    InspectionFilter, InspectionResult, NewInspectionPlan, NewReceipt, QualityService,
+};
+use crate::utils::pagination::PaginationParams;
+
+async fn list_plans(
+    State(quality): State<Arc<QualityService>>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "quality:read") {
+        return Ok(response);
+    }
+    Ok(Json(quality.list_plans().await?).into_response())
+}
+
+async fn create_plan(
+    State(quality): State<Arc<QualityService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewInspectionPlan>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "quality:write") {
+        return Ok(response);
+    }
+    let plan = quality.create_plan(input).await?;
+    info!("Inspection plan {} created by {}", plan.id, claims.actor());
+    Ok((StatusCode::CREATED, Json(plan)).into_response())
+}
+
+/// Registers a PO receipt, ASN receipt or work order completion. Responds 201 with the
+/// opened inspection, or 200 with `inspection: null` when no plan covers the goods.
+/// PO and ASN receipts feed the supplier scorecards under the terms of the stored
+/// purchase order they arrived against.
+async fn register_receipt(
+    State(quality): State<Arc<QualityService>>,
+    AuthUser(claims): AuthUser,
+    Json(receipt): Json<NewReceipt>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "quality:write") {
+        return Ok(response);
+    }
+    Ok(match quality.register_receipt(receipt).await? {
+        Some(inspection) => (StatusCode::CREATED, Json(json!({ "inspection": inspection }))).into_response(),
+        None => Json(json!({ "inspection": null })).into_response(),
+    })
+}
+
+async fn list_inspections(
+    State(quality): State<Arc<QualityService>>,
+    Query(filter): Query<InspectionFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "quality:read") {
+        return Ok(response);
+    }
+    let (inspections, total) = quality.list_inspections(filter, pagination).await?;
+    Ok(Json(json!({
+        "inspections": inspections,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+async fn get_inspection(
+    State(quality): State<Arc<QualityService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "quality:read") {
+        return Ok(response);
+    }
+    Ok(Json(quality.get_inspection(id).await?).into_response())
+}
+
+/// Records defects and a disposition; failed or quarantined units move to quarantine.
+async fn record_result(
+    State(quality): State<Arc<QualityService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(result): Json<InspectionResult>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "quality:write") {
+        return Ok(response);
+    }
+    Ok(Json(quality.record_result(id, result, &claims.actor()).await?).into_response())
+}
+
+/// Returns the units an inspection holds in quarantine to the row they came from.
+async fn release_hold(
+    State(quality): State<Arc<QualityService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "quality:write") {
+        return Ok(response);
+    }
+    let inspection = quality.release_hold(id, &claims.actor()).await?;
+    info!("Quarantine hold of inspection {} released by {}", id, claims.actor());
+    Ok(Json(inspection).into_response())
+}
+
+pub fn quality_routes<S>(quality: Arc<QualityService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/plans", get(list_plans).post(create_plan))
+        .route("/receipts", post(register_receipt))
+        .route("/inspections", get(list_inspections))
+        .route("/inspections/:id", get(get_inspection))
+        .route("/inspections/:id/result", post(record_result))
+        .route("/inspections/:id/release", post(release_hold))
+        .with_state(quality)
+}
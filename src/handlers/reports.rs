@@ -1,9 +1,12 @@
 use axum::{
     extract::State,
+    response::{IntoResponse, Response},
     routing::get,
-    Json,
     Router,
 };
+use serde_json::{json, Value};
+use std::sync::Arc;
+use crate::cache::{swr::ReportCache, RedisCache};
 use crate::db::DbPool;
 use crate::errors::ServiceError;
 use crate::services::reports::{
@@ -15,51 +18,75 @@ use crate::services::reports::{
 };
 use crate::auth::AuthenticatedUser;
 
+type ReportsState = (Arc<DbPool>, Arc<ReportCache<RedisCache>>);
+
 async fn sales_report(
-    State(pool): State<DbPool>,
+    State((pool, reports)): State<ReportsState>,
     _user: AuthenticatedUser,
-) -> Result<Json<impl serde::Serialize>, ServiceError> {
-    let report = generate_sales_report(&pool).await?;
-    Ok(Json(report))
+) -> Result<Response, ServiceError> {
+    let report = reports
+        .report("sales", "", move || async move { Ok::<Value, ServiceError>(json!(generate_sales_report(&pool).await?)) })
+        .await?;
+    Ok(report.into_response())
 }
 
 async fn inventory_report(
-    State(pool): State<DbPool>,
+    State((pool, reports)): State<ReportsState>,
     _user: AuthenticatedUser,
-) -> Result<Json<impl serde::Serialize>, ServiceError> {
-    let report = generate_inventory_report(&pool).await?;
-    Ok(Json(report))
+) -> Result<Response, ServiceError> {
+    let report = reports
+        .report("inventory", "", move || async move {
+            Ok::<Value, ServiceError>(json!(generate_inventory_report(&pool).await?))
+        })
+        .await?;
+    Ok(report.into_response())
 }
 
 async fn sales_by_product_report(
-    State(pool): State<DbPool>,
+    State((pool, reports)): State<ReportsState>,
     _user: AuthenticatedUser,
-) -> Result<Json<impl serde::Serialize>, ServiceError> {
-    let report = generate_sales_by_product_report(&pool).await?;
-    Ok(Json(report))
+) -> Result<Response, ServiceError> {
+    let report = reports
+        .report("sales_by_product", "", move || async move {
+            Ok::<Value, ServiceError>(json!(generate_sales_by_product_report(&pool).await?))
+        })
+        .await?;
+    Ok(report.into_response())
 }
 
 async fn cogs_report(
-    State(pool): State<DbPool>,
+    State((pool, reports)): State<ReportsState>,
     _user: AuthenticatedUser,
-) -> Result<Json<impl serde::Serialize>, ServiceError> {
-    let report = generate_cogs_report(&pool).await?;
-    Ok(Json(report))
+) -> Result<Response, ServiceError> {
+    let report = reports
+        .report("cogs", "", move || async move { Ok::<Value, ServiceError>(json!(generate_cogs_report(&pool).await?)) })
+        .await?;
+    Ok(report.into_response())
 }
 
 async fn work_order_efficiency_report(
-    State(pool): State<DbPool>,
+    State((pool, reports)): State<ReportsState>,
     _user: AuthenticatedUser,
-) -> Result<Json<impl serde::Serialize>, ServiceError> {
-    let report = generate_work_order_efficiency_report(&pool).await?;
-    Ok(Json(report))
+) -> Result<Response, ServiceError> {
+    let report = reports
+        .report("work_order_efficiency", "", move || async move {
+            Ok::<Value, ServiceError>(json!(generate_work_order_efficiency_report(&pool).await?))
+        })
+        .await?;
+    Ok(report.into_response())
 }
 
-pub fn reports_router() -> Router<DbPool> {
+/// Report routes; like the analytics routes they are served through `reports`, so the
+/// month-end run on them is absorbed by the cache.
+pub fn routes<S>(pool: Arc<DbPool>, reports: Arc<ReportCache<RedisCache>>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
     Router::new()
         .route("/sales", get(sales_report))
         .route("/inventory", get(inventory_report))
         .route("/sales-by-product", get(sales_by_product_report))
         .route("/cogs", get(cogs_report))
         .route("/work-order-efficiency", get(work_order_efficiency_report))
-}
\ No newline at end of file
+        .with_state((pool, reports))
+}
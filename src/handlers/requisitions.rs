@@ -0,0 +1,95 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::requisition_service::{NewRequisition, RequisitionFilter, RequisitionService};
+use crate::utils::pagination::PaginationParams;
+
+async fn list_requisitions(
+    State(requisitions): State<Arc<RequisitionService>>,
+    Query(filter): Query<RequisitionFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "requisitions:read") {
+        return Ok(response);
+    }
+    let (items, total) = requisitions.list(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+/// Creates a draft requisition for the caller.
+async fn create_requisition(
+    State(requisitions): State<Arc<RequisitionService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewRequisition>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "requisitions:write") {
+        return Ok(response);
+    }
+    let requisition = requisitions.create(input, &claims.actor()).await?;
+    info!("Requisition {} created by {}", requisition.id, claims.actor());
+    Ok((StatusCode::CREATED, Json(requisition)).into_response())
+}
+
+async fn get_requisition(
+    State(requisitions): State<Arc<RequisitionService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "requisitions:read") {
+        return Ok(response);
+    }
+    Ok(Json(requisitions.get(id).await?).into_response())
+}
+
+/// Sends the caller's draft into its approval chain; approvers decide from `/api/v1/approvals`.
+async fn submit_requisition(
+    State(requisitions): State<Arc<RequisitionService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "requisitions:write") {
+        return Ok(response);
+    }
+    Ok(Json(requisitions.submit(id, &claims.actor()).await?).into_response())
+}
+
+async fn cancel_requisition(
+    State(requisitions): State<Arc<RequisitionService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "requisitions:write") {
+        return Ok(response);
+    }
+    Ok(Json(requisitions.cancel(id, &claims.actor()).await?).into_response())
+}
+
+pub fn requisition_routes<S>(requisitions: Arc<RequisitionService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_requisitions).post(create_requisition))
+        .route("/:id", get(get_requisition))
+        .route("/:id/submit", post(submit_requisition))
+        .route("/:id/cancel", post(cancel_requisition))
+        .with_state(requisitions)
+}
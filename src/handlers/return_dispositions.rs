@@ -0,0 +1,83 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::return_disposition_service::{DisposeRequest, DispositionFilter, ReturnDispositionService};
+use crate::utils::pagination::PaginationParams;
+
+#[derive(Debug, Deserialize)]
+struct AnalyticsParams {
+    from: NaiveDate,
+    to: NaiveDate,
+    sku: Option<String>,
+}
+
+/// Restocks, refurbishes, scraps or returns to the vendor the units of a received return.
+async fn dispose(
+    State(dispositions): State<Arc<ReturnDispositionService>>,
+    Path(return_id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(request): Json<DisposeRequest>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "returns:dispose") {
+        return Ok(response);
+    }
+    let items = dispositions.dispose(return_id, request, &claims.actor()).await?;
+    info!("Return {} disposed by {}: {} lines", return_id, claims.actor(), items.len());
+    Ok((StatusCode::CREATED, Json(json!({ "return_id": return_id, "items": items }))).into_response())
+}
+
+async fn list_dispositions(
+    State(dispositions): State<Arc<ReturnDispositionService>>,
+    Query(filter): Query<DispositionFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "returns:read") {
+        return Ok(response);
+    }
+    let (items, total) = dispositions.list(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+/// Where returned units went, by SKU and return reason.
+async fn analytics(
+    State(dispositions): State<Arc<ReturnDispositionService>>,
+    Query(params): Query<AnalyticsParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "returns:read") {
+        return Ok(response);
+    }
+    let skus = dispositions.analytics(params.from, params.to, params.sku).await?;
+    Ok(Json(json!({ "from": params.from, "to": params.to, "skus": skus })).into_response())
+}
+
+pub fn return_disposition_routes<S>(dispositions: Arc<ReturnDispositionService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_dispositions))
+        .route("/analytics", get(analytics))
+        .route("/returns/:id", post(dispose))
+        .with_state(dispositions)
+}
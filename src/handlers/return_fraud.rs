@@ -0,0 +1,60 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::return_fraud::{ReturnFraudService, SerialInput};
+
+#[derive(Debug, Deserialize)]
+struct SerialsRequest {
+    serials: Vec<SerialInput>,
+}
+
+/// Records the serial numbers of the units an order shipped with, at pack or ship time.
+async fn record_serials(
+    State(fraud): State<Arc<ReturnFraudService>>,
+    Path(order_id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(request): Json<SerialsRequest>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "orders:write") {
+        return Ok(response);
+    }
+    let recorded = fraud.record_serials(order_id, request.serials, &claims.actor()).await?;
+    Ok((StatusCode::CREATED, Json(json!({ "order_id": order_id, "recorded": recorded }))).into_response())
+}
+
+/// Scores the return now and flags it for inspection when risky; the agent otherwise
+/// gets to it on its next run.
+async fn assess_return(
+    State(fraud): State<Arc<ReturnFraudService>>,
+    Path(return_id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "returns:write") {
+        return Ok(response);
+    }
+    let assessment = fraud.assess_return(return_id).await?;
+    info!("Return {} assessed by {}: score {}", return_id, claims.actor(), assessment.score);
+    Ok(Json(assessment).into_response())
+}
+
+pub fn return_fraud_routes<S>(fraud: Arc<ReturnFraudService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/orders/:order_id/serials", post(record_serials))
+        .route("/returns/:return_id/assess", post(assess_return))
+        .with_state(fraud)
+}
@@ -1,196 +1,279 @@
 use axum::{
-    routing::{post, get, put},
+    routing::{post, get},
     extract::{Path, State, Query, Json},
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     Router,
 };
-use crate::services::returns::ReturnService;
-use crate::models::{NewReturn, Return, ReturnStatus, ReturnSearchParams};
-use crate::errors::{ServiceError, ReturnError};
-use crate::auth::AuthenticatedUser;
+use crate::services::return_service::{ReturnSearchParams, ReturnServiceApi};
+use crate::models::return_entity;
+use crate::db::DbPool;
+use crate::errors::ServiceError;
+use crate::events::EventSender;
+use crate::auth::{forbidden, AuthUser};
 use crate::utils::pagination::PaginationParams;
+use rust_decimal::Decimal;
+use serde::Deserialize;
 use validator::Validate;
+use serde_json::json;
+use tracing::info;
 use uuid::Uuid;
 use std::sync::Arc;
 
+use crate::commands::Command;
 use crate::commands::returns::{
-    ApproveReturnCommand,
-    RejectReturnCommand,
     CancelReturnCommand,
-    CompleteReturnCommand,
     CloseReturnCommand,
-    ReOpenReturnCommand,
-    RestockReturnedItemsCommand,
-    RefundReturnCommand,
-    ProcessReturnCommand,
+    CompleteReturnCommand,
     DeleteReturnCommand,
-    CreateReturnCommand,
+    InitiateReturnCommand,
     RefundReturnCommand,
-    UpdateReturnCommand,
+    RejectReturnCommand,
+    ReopenReturnCommand,
+    RestockReturnedItemsCommand,
 };
 
-async fn create_return(
-    State(return_service): State<Arc<ReturnService>>,
-    AuthenticatedUser(user): AuthenticatedUser,
-    Json(return_info): Json<NewReturn>,
-) -> Result<impl IntoResponse, ServiceError> {
-    let command = CreateReturnCommand {
-        return_info,
-        user_id: user.user_id,
-    };
+/// Body of the reject and cancel endpoints.
+#[derive(Debug, Deserialize, Validate)]
+pub struct ReasonRequest {
+    #[validate(length(min = 1, max = 500))]
+    pub reason: String,
+}
 
-    let created_return = command.execute(return_service).await?;
-    Ok((axum::http::StatusCode::CREATED, Json(created_return)))
+#[derive(Debug, Deserialize, Validate)]
+pub struct RefundRequest {
+    #[validate(custom = "crate::money::validate_non_negative")]
+    #[serde(with = "crate::money::amount")]
+    pub refund_amount: Decimal,
 }
 
-async fn approve_return(
-    State(return_service): State<Arc<ReturnService>>,
-    Path(return_id): Path<Uuid>,
-) -> Result<impl IntoResponse, ServiceError> {
-    let command = ApproveReturnCommand { return_id };
-    let approved_return = command.execute(return_service).await?;
-    Ok(Json(approved_return))
+async fn create_return(
+    State(db_pool): State<Arc<DbPool>>,
+    State(event_sender): State<Arc<EventSender>>,
+    AuthUser(claims): AuthUser,
+    Json(command): Json<InitiateReturnCommand>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "returns:write") {
+        return Ok(response);
+    }
+    let created_return = command.execute(db_pool, event_sender).await?;
+    info!("Return {} initiated by {}", created_return.id, claims.actor());
+    Ok((axum::http::StatusCode::CREATED, Json(created_return)).into_response())
 }
 
 async fn reject_return(
-    State(return_service): State<Arc<ReturnService>>,
-    Path(return_id): Path<i32>,
-    Json(reject_info): Json<RejectReturnCommand>,
-) -> Result<impl IntoResponse, ServiceError> {
+    State(db_pool): State<Arc<DbPool>>,
+    State(event_sender): State<Arc<EventSender>>,
+    Path(return_id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(reject_info): Json<ReasonRequest>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "returns:write") {
+        return Ok(response);
+    }
+    reject_info.validate()?;
     let command = RejectReturnCommand {
         return_id,
         reason: reject_info.reason,
     };
 
-    let rejected_return = command.execute(return_service).await?;
-    Ok(Json(rejected_return))
+    let rejected_return = command.execute(db_pool, event_sender).await?;
+    info!("Return {} rejected by {}", return_id, claims.actor());
+    Ok(Json(rejected_return).into_response())
 }
 
 async fn cancel_return(
-    State(return_service): State<Arc<ReturnService>>,
-    Path(return_id): Path<i32>,
-    Json(cancel_info): Json<CancelReturnCommand>,
-) -> Result<impl IntoResponse, ServiceError> {
+    State(db_pool): State<Arc<DbPool>>,
+    State(event_sender): State<Arc<EventSender>>,
+    Path(return_id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(cancel_info): Json<ReasonRequest>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "returns:write") {
+        return Ok(response);
+    }
+    cancel_info.validate()?;
     let command = CancelReturnCommand {
         return_id,
         reason: cancel_info.reason,
     };
 
-    let result = command.execute(return_service).await?;
-    Ok(Json(result))
+    let result = command.execute(db_pool, event_sender).await?;
+    info!("Return {} canceled by {}", return_id, claims.actor());
+    Ok(Json(result).into_response())
 }
 
-async fn close_return(
-    State(return_service): State<Arc<ReturnService>>,
-    Path(return_id): Path<i32>,
-) -> Result<impl IntoResponse, ServiceError> {
-    let command = CloseReturnCommand { return_id };
+async fn complete_return(
+    State(db_pool): State<Arc<DbPool>>,
+    State(event_sender): State<Arc<EventSender>>,
+    Path(return_id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "returns:write") {
+        return Ok(response);
+    }
+    let completed_return = CompleteReturnCommand { return_id }.execute(db_pool, event_sender).await?;
+    info!("Return {} completed by {}", return_id, claims.actor());
+    Ok(Json(completed_return).into_response())
+}
 
-    let closed_return = command.execute(return_service).await?;
-    Ok(Json(closed_return))
+async fn close_return(
+    State(db_pool): State<Arc<DbPool>>,
+    State(event_sender): State<Arc<EventSender>>,
+    Path(return_id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "returns:write") {
+        return Ok(response);
+    }
+    let closed_return = CloseReturnCommand { return_id }.execute(db_pool, event_sender).await?;
+    info!("Return {} closed by {}", return_id, claims.actor());
+    Ok(Json(closed_return).into_response())
 }
 
 async fn reopen_return(
-    State(return_service): State<Arc<ReturnService>>,
-    Path(return_id): Path<i32>,
-) -> Result<impl IntoResponse, ServiceError> {
-    let command = ReopenReturnCommand { return_id };
-
-    let reopened_return = command.execute(return_service).await?;
-    Ok(Json(reopened_return))
+    State(db_pool): State<Arc<DbPool>>,
+    State(event_sender): State<Arc<EventSender>>,
+    Path(return_id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "returns:write") {
+        return Ok(response);
+    }
+    let reopened_return = ReopenReturnCommand { return_id }.execute(db_pool, event_sender).await?;
+    info!("Return {} reopened by {}", return_id, claims.actor());
+    Ok(Json(reopened_return).into_response())
 }
 
 async fn delete_return(
-    State(return_service): State<Arc<ReturnService>>,
-    Path(id): Path<Uuid>,
-    AuthenticatedUser(user): AuthenticatedUser,
-) -> Result<impl IntoResponse, ServiceError> {
-
-    let command = DeleteReturnCommand { return_id: id };
-
-    let deleted_return = command.execute(return_service).await?;
-    Ok(Json(deleted_return))
+    State(db_pool): State<Arc<DbPool>>,
+    State(event_sender): State<Arc<EventSender>>,
+    Path(return_id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "returns:write") {
+        return Ok(response);
+    }
+    let deleted_return = DeleteReturnCommand { return_id }.execute(db_pool, event_sender).await?;
+    info!("Return {} deleted by {}", return_id, claims.actor());
+    Ok(Json(deleted_return).into_response())
 }
 
 async fn restock_return(
-    State(return_service): State<Arc<ReturnService>>,
+    State(db_pool): State<Arc<DbPool>>,
+    State(event_sender): State<Arc<EventSender>>,
     Path(return_id): Path<Uuid>,
-) -> Result<impl IntoResponse, ServiceError> {
-    let command = RestockReturnedItemsCommand { return_id };
-    let restocked_return = command.execute(return_service).await?;
-    Ok(Json(restocked_return))
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "returns:write") {
+        return Ok(response);
+    }
+    let restocked = RestockReturnedItemsCommand { return_id }.execute(db_pool, event_sender).await?;
+    info!("Return {} restocked by {}", return_id, claims.actor());
+    Ok(Json(restocked).into_response())
 }
 
 async fn refund_return(
-    State(return_service): State<Arc<ReturnService>>,
+    State(db_pool): State<Arc<DbPool>>,
+    State(event_sender): State<Arc<EventSender>>,
     Path(return_id): Path<Uuid>,
-) -> Result<impl IntoResponse, ServiceError> {
-    let command = RefundReturnCommand { return_id };
-    let refunded_return = command.execute(return_service).await?;
-    Ok(Json(refunded_return))
+    AuthUser(claims): AuthUser,
+    Json(refund_info): Json<RefundRequest>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "returns:write") {
+        return Ok(response);
+    }
+    refund_info.validate()?;
+    let command = RefundReturnCommand {
+        return_id,
+        refund_amount: refund_info.refund_amount,
+    };
+
+    let refunded_return = command.execute(db_pool, event_sender).await?;
+    info!("Return {} refunded by {}", return_id, claims.actor());
+    Ok(Json(refunded_return).into_response())
 }
 
 async fn update_return(
-    State(return_service): State<Arc<ReturnService>>,
+    State(return_service): State<Arc<dyn ReturnServiceApi>>,
     Path(id): Path<Uuid>,
-    AuthenticatedUser(user): AuthenticatedUser,
-    Json(return_info): Json<Return>,
-) -> Result<impl IntoResponse, ServiceError> {
+    AuthUser(claims): AuthUser,
+    Json(return_info): Json<return_entity::Model>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "returns:write") {
+        return Ok(response);
+    }
     return_info.validate().map_err(|e| ServiceError::BadRequest(e.to_string()))?;
-    let updated_return = return_service.update_return(id, return_info, user.user_id)
-        .await
-        .map_err(|e| ServiceError::from(ReturnError::from(e)))?;
-    Ok(Json(updated_return))
+    let updated_return = return_service.update_return(id, return_info).await?;
+    info!("Return {} updated by {}", id, claims.actor());
+    Ok(Json(updated_return).into_response())
 }
 
 async fn get_return(
-    State(return_service): State<Arc<ReturnService>>,
+    State(return_service): State<Arc<dyn ReturnServiceApi>>,
     Path(return_id): Path<Uuid>,
-) -> Result<impl IntoResponse, ServiceError> {
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "returns:read") {
+        return Ok(response);
+    }
     let ret = return_service.get_return(return_id).await?;
-    Ok(Json(ret))
+    Ok(Json(ret).into_response())
 }
 
 async fn list_returns(
-    State(return_service): State<Arc<ReturnService>>,
-    AuthenticatedUser(user): AuthenticatedUser,
+    State(return_service): State<Arc<dyn ReturnServiceApi>>,
+    AuthUser(claims): AuthUser,
     Query(query): Query<PaginationParams>,
-) -> Result<impl IntoResponse, ServiceError> {
-    let (returns, total) = return_service.list_returns(user.user_id, query)
-        .await
-        .map_err(|e| ServiceError::from(ReturnError::from(e)))?;
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "returns:read") {
+        return Ok(response);
+    }
+    let (returns, total) = return_service.list_returns(query).await?;
+    info!("Returns listed by {}: total {}", claims.actor(), total);
     Ok(Json(json!({
         "returns": returns,
         "total": total,
         "page": query.page,
         "per_page": query.per_page
-    })))
+    }))
+    .into_response())
 }
 
 async fn search_returns(
-    State(return_service): State<Arc<ReturnService>>,
-    AuthenticatedUser(user): AuthenticatedUser,
+    State(return_service): State<Arc<dyn ReturnServiceApi>>,
+    AuthUser(claims): AuthUser,
     Query(query): Query<ReturnSearchParams>,
     Query(pagination): Query<PaginationParams>,
-) -> Result<impl IntoResponse, ServiceError> {
-    let (returns, total) = return_service.search_returns(user.user_id, query, pagination)
-        .await
-        .map_err(|e| ServiceError::from(ReturnError::from(e)))?;
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "returns:read") {
+        return Ok(response);
+    }
+    let (returns, total) = return_service.search_returns(query.clone(), pagination).await?;
+    info!("Returns searched by {}: total {}", claims.actor(), total);
     Ok(Json(json!({
         "returns": returns,
         "total": total,
         "query": query,
         "page": pagination.page,
         "per_page": pagination.per_page
-    })))
+    }))
+    .into_response())
 }
 
-pub fn returns_routes() -> Router {
+/// Return endpoints. Reads and updates go through [`ReturnServiceApi`]; state changes
+/// run the return commands against the pool and event sender of the router state.
+/// Returns are approved through their approval chain (`POST /api/v1/approvals`), not here.
+pub fn returns_routes<S>() -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+    Arc<dyn ReturnServiceApi>: axum::extract::FromRef<S>,
+    Arc<DbPool>: axum::extract::FromRef<S>,
+    Arc<EventSender>: axum::extract::FromRef<S>,
+{
     Router::new()
         .route("/", post(create_return).get(list_returns))
         .route("/search", get(search_returns))
         .route("/:id", get(get_return).put(update_return).delete(delete_return))
-        .route("/:id/approve", post(approve_return))
         .route("/:id/reject", post(reject_return))
         .route("/:id/cancel", post(cancel_return))
         .route("/:id/restock", post(restock_return))
@@ -198,5 +281,101 @@ pub fn returns_routes() -> Router {
         .route("/:id/complete", post(complete_return))
         .route("/:id/close", post(close_return))
         .route("/:id/reopen", post(reopen_return))
-        .route("/:id/process", post(process_return))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::Claims;
+    use crate::handlers::AppServices;
+    use crate::models::return_entity::{ActionNeeded, Condition, ReturnStatus};
+    use crate::services::{order_service::MockOrderServiceApi, return_service::MockReturnServiceApi};
+    use axum::{body::Body, http::{Request, StatusCode}};
+    use chrono::Utc;
+    use mockall::predicate::eq;
+    use rust_decimal::Decimal;
+    use tower::ServiceExt;
+
+    fn sample_return(id: Uuid) -> return_entity::Model {
+        return_entity::Model {
+            id,
+            created_date: Utc::now(),
+            amount: Decimal::new(4999, 2),
+            action_needed: ActionNeeded::Inspection,
+            condition: Condition::Used,
+            customer_email: "ada@example.com".to_string(),
+            customer_id: Uuid::new_v4(),
+            description: None,
+            entered_by: None,
+            flat_rate_shipping: Decimal::ZERO,
+            order_date: Utc::now(),
+            order_id: Uuid::new_v4(),
+            reason_category: None,
+            reported_condition: None,
+            requested_date: Utc::now(),
+            risk_score: None,
+            risk_signals: None,
+            rma: "RMA-000001".to_string(),
+            serial_number: None,
+            shipped_date: None,
+            status: ReturnStatus::Requested,
+            tax_refunded: Decimal::ZERO,
+            total_refunded: Decimal::ZERO,
+            tracking_number: None,
+        }
+    }
+
+    fn claims(permissions: &[&str]) -> Claims {
+        Claims {
+            sub: "42".to_string(),
+            exp: usize::MAX,
+            iss: "stateset".to_string(),
+            aud: "stateset-api".to_string(),
+            role: "user".to_string(),
+            permissions: Some(permissions.iter().map(|p| p.to_string()).collect()),
+            actor_type: Default::default(),
+            tenant_id: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_return_reads_through_service() {
+        let id = Uuid::new_v4();
+        let mut returns = MockReturnServiceApi::new();
+        returns
+            .expect_get_return()
+            .with(eq(id))
+            .times(1)
+            .returning(|id| Ok(sample_return(id)));
+
+        let services = AppServices {
+            orders: Arc::new(MockOrderServiceApi::new()),
+            returns: Arc::new(returns),
+        };
+        let app = Router::new().route("/:id", get(get_return)).with_state(services);
+
+        let mut request = Request::builder().uri(format!("/{}", id)).body(Body::empty()).unwrap();
+        request.extensions_mut().insert(claims(&["returns:read"]));
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["id"], id.to_string());
+        assert_eq!(body["rma"], "RMA-000001");
+    }
+
+    #[tokio::test]
+    async fn test_get_return_requires_read_permission() {
+        let services = AppServices {
+            orders: Arc::new(MockOrderServiceApi::new()),
+            returns: Arc::new(MockReturnServiceApi::new()),
+        };
+        let app = Router::new().route("/:id", get(get_return)).with_state(services);
+
+        let mut request = Request::builder().uri(format!("/{}", Uuid::new_v4())).body(Body::empty()).unwrap();
+        request.extensions_mut().insert(claims(&["orders:read"]));
+        let response = app.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
 }
@@ -0,0 +1,97 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::routing_rules::{NewRoutingRule, RoutingRuleFilter, RoutingRuleService, UpdateRoutingRule};
+use crate::utils::pagination::PaginationParams;
+
+async fn list_rules(
+    State(rules): State<Arc<RoutingRuleService>>,
+    Query(filter): Query<RoutingRuleFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "routing_rules:read") {
+        return Ok(response);
+    }
+    let (items, total) = rules.list(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+async fn create_rule(
+    State(rules): State<Arc<RoutingRuleService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewRoutingRule>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "routing_rules:write") {
+        return Ok(response);
+    }
+    let rule = rules.create(input, &claims.actor()).await?;
+    info!("Routing rule {} created by {}", rule.name, claims.actor());
+    Ok((StatusCode::CREATED, Json(rule)).into_response())
+}
+
+async fn get_rule(
+    State(rules): State<Arc<RoutingRuleService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "routing_rules:read") {
+        return Ok(response);
+    }
+    Ok(Json(rules.get(id).await?).into_response())
+}
+
+/// Changes take effect on the next sourcing decision; no restart or deploy is needed.
+async fn update_rule(
+    State(rules): State<Arc<RoutingRuleService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<UpdateRoutingRule>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "routing_rules:write") {
+        return Ok(response);
+    }
+    let rule = rules.update(id, input, &claims.actor()).await?;
+    info!("Routing rule {} updated by {}", rule.name, claims.actor());
+    Ok(Json(rule).into_response())
+}
+
+async fn delete_rule(
+    State(rules): State<Arc<RoutingRuleService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "routing_rules:write") {
+        return Ok(response);
+    }
+    rules.delete(id).await?;
+    info!("Routing rule {} deleted by {}", id, claims.actor());
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+pub fn routing_rule_routes<S>(rules: Arc<RoutingRuleService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_rules).post(create_rule))
+        .route("/:id", get(get_rule).patch(update_rule).delete(delete_rule))
+        .with_state(rules)
+}
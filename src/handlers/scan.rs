@@ -0,0 +1,63 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::scan_service::{ResolveQuery, ScanAction, ScanPost, ScanService};
+
+/// Resolves a scanned barcode to a SKU, bin, ASN, shipment or work order.
+async fn resolve(
+    State(scans): State<Arc<ScanService>>,
+    Query(query): Query<ResolveQuery>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "scan:read") {
+        return Ok(response);
+    }
+    Ok(Json(scans.resolve(query).await?).into_response())
+}
+
+/// Posts a receive, pick, pack or count. Scanners generate the `Idempotency-Key` when the
+/// scan happens so a post queued while offline can be resent safely.
+async fn post_scan(
+    State(scans): State<Arc<ScanService>>,
+    Path(action): Path<ScanAction>,
+    headers: HeaderMap,
+    AuthUser(claims): AuthUser,
+    Json(payload): Json<ScanPost>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "scan:write") {
+        return Ok(response);
+    }
+    let Some(key) = headers.get("idempotency-key").and_then(|v| v.to_str().ok()) else {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "Idempotency-Key header is required", "code": "missing_idempotency_key" })),
+        )
+            .into_response());
+    };
+    let outcome = scans.post(action, payload, key, &claims.actor()).await?;
+    if outcome.replayed {
+        return Ok(([("idempotent-replay", "true")], Json(outcome.result)).into_response());
+    }
+    info!(code = %outcome.result.code, action = ?action, "Scan posted");
+    Ok((StatusCode::CREATED, Json(outcome.result)).into_response())
+}
+
+pub fn scan_routes<S>(scans: Arc<ScanService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/resolve", get(resolve))
+        .route("/:action", post(post_scan))
+        .with_state(scans)
+}
@@ -0,0 +1,137 @@
+use axum::{
+    routing::{post, get, delete},
+    extract::{State, Path, Json, ConnectInfo},
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+    Router,
+};
+use crate::auth::{
+    admin_only,
+    service_accounts::ServiceAccountAuthenticator,
+    AuthError, AuthUser,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::{net::SocketAddr, sync::Arc};
+use tracing::info;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateServiceAccountRequest {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(length(max = 500))]
+    pub description: Option<String>,
+    #[validate(length(min = 1, message = "At least one scope is required"))]
+    pub scopes: Vec<String>,
+    #[serde(default)]
+    pub allowed_ips: Vec<String>,
+    /// Tenant the account acts for.
+    pub tenant_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ClientCredentialsRequest {
+    pub grant_type: String,
+    pub client_id: String,
+    pub client_secret: String,
+    /// Space-separated list of scopes, as in RFC 6749.
+    pub scope: Option<String>,
+}
+
+async fn create_service_account(
+    State(authenticator): State<Arc<ServiceAccountAuthenticator>>,
+    AuthUser(claims): AuthUser,
+    Json(request): Json<CreateServiceAccountRequest>,
+) -> Result<Response, AuthError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    request.validate().map_err(|e| AuthError::BadRequest(e.to_string()))?;
+
+    let credentials = authenticator
+        .create(request.name, request.description, request.scopes, request.allowed_ips, request.tenant_id, claims.actor())
+        .await?;
+    info!("Service account {} created by {}", credentials.service_account_id, claims.actor());
+    Ok((axum::http::StatusCode::CREATED, Json(credentials)).into_response())
+}
+
+async fn list_service_accounts(
+    State(authenticator): State<Arc<ServiceAccountAuthenticator>>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, AuthError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let accounts = authenticator.list().await?;
+    Ok(Json(accounts).into_response())
+}
+
+async fn rotate_service_account(
+    State(authenticator): State<Arc<ServiceAccountAuthenticator>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, AuthError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let credentials = authenticator.rotate(id).await?;
+    info!("Service account {} credentials rotated by {}", id, claims.actor());
+    Ok(Json(credentials).into_response())
+}
+
+async fn revoke_service_account(
+    State(authenticator): State<Arc<ServiceAccountAuthenticator>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, AuthError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    authenticator.revoke(id).await?;
+    info!("Service account {} revoked by {}", id, claims.actor());
+    Ok(axum::http::StatusCode::NO_CONTENT.into_response())
+}
+
+/// OAuth2 token endpoint for the client-credentials grant.
+async fn issue_token(
+    State(authenticator): State<Arc<ServiceAccountAuthenticator>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Json(request): Json<ClientCredentialsRequest>,
+) -> Result<impl IntoResponse, AuthError> {
+    if request.grant_type != "client_credentials" {
+        return Err(AuthError::BadRequest(format!("Unsupported grant_type: {}", request.grant_type)));
+    }
+
+    let scopes = request
+        .scope
+        .map(|s| s.split_whitespace().map(str::to_owned).collect());
+    let token = authenticator
+        .client_credentials_grant(&request.client_id, &request.client_secret, scopes, authenticator.peer_ip(addr, &headers))
+        .await?;
+
+    Ok(Json(json!({
+        "access_token": token,
+        "token_type": "Bearer",
+    })))
+}
+
+pub fn service_account_routes() -> Router {
+    Router::new()
+        .route("/", post(create_service_account))
+        .route("/", get(list_service_accounts))
+        .route("/:id/rotate", post(rotate_service_account))
+        .route("/:id", delete(revoke_service_account))
+}
+
+/// Served outside authentication: the client credentials are the authentication.
+pub fn token_routes<S>(authenticator: Arc<ServiceAccountAuthenticator>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/oauth2/token", post(issue_token))
+        .with_state(authenticator)
+}
@@ -0,0 +1,136 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::geocoding::Address;
+use crate::services::shipping_zone_service::{
+    NewShippingZone, ShippingZoneFilter, ShippingZoneService, UpdateShippingZone, ZoneQuery,
+};
+use crate::utils::pagination::PaginationParams;
+
+async fn list_zones(
+    State(zones): State<Arc<ShippingZoneService>>,
+    Query(filter): Query<ShippingZoneFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "shipping_zones:read") {
+        return Ok(response);
+    }
+    let (items, total) = zones.list(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+async fn create_zone(
+    State(zones): State<Arc<ShippingZoneService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewShippingZone>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "shipping_zones:write") {
+        return Ok(response);
+    }
+    let zone = zones.create(input).await?;
+    info!("Shipping zone {} created by {}", zone.code, claims.actor());
+    Ok((StatusCode::CREATED, Json(zone)).into_response())
+}
+
+async fn get_zone(
+    State(zones): State<Arc<ShippingZoneService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "shipping_zones:read") {
+        return Ok(response);
+    }
+    Ok(Json(zones.get(id).await?).into_response())
+}
+
+async fn update_zone(
+    State(zones): State<Arc<ShippingZoneService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<UpdateShippingZone>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "shipping_zones:write") {
+        return Ok(response);
+    }
+    Ok(Json(zones.update(id, input).await?).into_response())
+}
+
+async fn delete_zone(
+    State(zones): State<Arc<ShippingZoneService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "shipping_zones:write") {
+        return Ok(response);
+    }
+    zones.delete(id).await?;
+    info!("Shipping zone {} deleted by {}", id, claims.actor());
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// The zone an address or coordinate falls in, with its rate group, tax jurisdiction and
+/// delivery promise.
+async fn resolve_zone(
+    State(zones): State<Arc<ShippingZoneService>>,
+    AuthUser(claims): AuthUser,
+    Json(query): Json<ZoneQuery>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "shipping_zones:read") {
+        return Ok(response);
+    }
+    Ok(Json(zones.resolve(query).await?).into_response())
+}
+
+async fn get_address(
+    State(zones): State<Arc<ShippingZoneService>>,
+    Path((subject_type, subject_id)): Path<(String, String)>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "shipping_zones:read") {
+        return Ok(response);
+    }
+    Ok(Json(zones.assignment(&subject_type, &subject_id).await?).into_response())
+}
+
+/// Geocodes a customer or warehouse address and stores the zone it falls in.
+async fn put_address(
+    State(zones): State<Arc<ShippingZoneService>>,
+    Path((subject_type, subject_id)): Path<(String, String)>,
+    AuthUser(claims): AuthUser,
+    Json(address): Json<Address>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "shipping_zones:write") {
+        return Ok(response);
+    }
+    Ok(Json(zones.assign(&subject_type, &subject_id, address).await?).into_response())
+}
+
+pub fn shipping_zone_routes<S>(zones: Arc<ShippingZoneService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_zones).post(create_zone))
+        .route("/resolve", post(resolve_zone))
+        .route("/addresses/:subject_type/:subject_id", get(get_address).put(put_address))
+        .route("/:id", get(get_zone).patch(update_zone).delete(delete_zone))
+        .with_state(zones)
+}
@@ -0,0 +1,41 @@
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+
+use crate::auth::{admin_only, AuthUser};
+use crate::slo::{SloError, SloTracker};
+
+/// Error budget status of every SLO.
+async fn list_slos(State(tracker): State<Arc<SloTracker>>, AuthUser(claims): AuthUser) -> Result<Response, SloError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let items = tracker.evaluate().await?;
+    Ok(Json(json!({ "items": items })).into_response())
+}
+
+async fn get_slo(
+    State(tracker): State<Arc<SloTracker>>,
+    Path(name): Path<String>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, SloError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    Ok(Json(tracker.evaluate_one(&name).await?).into_response())
+}
+
+pub fn slo_routes<S>(tracker: Arc<SloTracker>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_slos))
+        .route("/:name", get(get_slo))
+        .with_state(tracker)
+}
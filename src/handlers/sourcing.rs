@@ -0,0 +1,32 @@
+use axum::{
+    extract::State,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use std::sync::Arc;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::sourcing::{SourcingRequest, SourcingService};
+
+/// Shows which warehouses and stores would ship an order, and what could not be
+/// sourced, before anything is reserved. `rules` in the request overrides the
+/// configured ones, so strategies can be compared side by side.
+async fn simulate(
+    State(sourcing): State<Arc<SourcingService>>,
+    AuthUser(claims): AuthUser,
+    Json(request): Json<SourcingRequest>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "inventory:read") {
+        return Ok(response);
+    }
+    Ok(Json(sourcing.simulate(request).await?).into_response())
+}
+
+pub fn sourcing_routes<S>(sourcing: Arc<SourcingService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new().route("/simulate", post(simulate)).with_state(sourcing)
+}
@@ -0,0 +1,129 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get},
+    Json, Router,
+};
+use serde::Deserialize;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::{AuthUser, Claims};
+use crate::stock_alerts::{NewSubscription, SetThreshold, StockAlertError, StockAlertService};
+
+fn require(claims: &Claims, permission: &'static str) -> Result<(), StockAlertError> {
+    if claims.is_allowed(permission) {
+        Ok(())
+    } else {
+        Err(StockAlertError::Forbidden(permission))
+    }
+}
+
+/// The caller's user id; service accounts and tokens without a numeric subject have none.
+fn user_id(claims: &Claims) -> Result<i32, StockAlertError> {
+    claims
+        .sub
+        .parse()
+        .map_err(|_| StockAlertError::Invalid("the caller has no user id; pass user_id".to_string()))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ThresholdParams {
+    pub sku: Option<String>,
+}
+
+async fn list_thresholds(
+    State(alerts): State<Arc<StockAlertService>>,
+    Query(params): Query<ThresholdParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, StockAlertError> {
+    require(&claims, "inventory:read")?;
+    Ok(Json(alerts.thresholds(params.sku.as_deref()).await?).into_response())
+}
+
+/// Creates or replaces a reorder threshold for a SKU, in one warehouse or by default.
+async fn set_threshold(
+    State(alerts): State<Arc<StockAlertService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<SetThreshold>,
+) -> Result<Response, StockAlertError> {
+    require(&claims, "inventory:write")?;
+    Ok(Json(alerts.set_threshold(input, claims.actor()).await?).into_response())
+}
+
+async fn delete_threshold(
+    State(alerts): State<Arc<StockAlertService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, StockAlertError> {
+    require(&claims, "inventory:write")?;
+    alerts.delete_threshold(id).await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// SKUs currently at or below their threshold.
+async fn low_stock(
+    State(alerts): State<Arc<StockAlertService>>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, StockAlertError> {
+    require(&claims, "inventory:read")?;
+    Ok(Json(alerts.low_stock().await?).into_response())
+}
+
+/// The caller's subscriptions; everyone's for holders of `inventory:write`.
+async fn list_subscriptions(
+    State(alerts): State<Arc<StockAlertService>>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, StockAlertError> {
+    require(&claims, "inventory:read")?;
+    let user = match require(&claims, "inventory:write") {
+        Ok(()) => None,
+        Err(_) => Some(user_id(&claims)?),
+    };
+    Ok(Json(alerts.subscriptions(user).await?).into_response())
+}
+
+async fn subscribe(
+    State(alerts): State<Arc<StockAlertService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewSubscription>,
+) -> Result<Response, StockAlertError> {
+    require(&claims, "inventory:read")?;
+    let user = match input.user_id {
+        Some(user) => {
+            require(&claims, "inventory:write")?;
+            user
+        }
+        None => user_id(&claims)?,
+    };
+    let subscription = alerts.subscribe(user, input, claims.actor()).await?;
+    Ok((StatusCode::CREATED, Json(subscription)).into_response())
+}
+
+/// Removes one of the caller's subscriptions, or anyone's with `inventory:write`.
+async fn unsubscribe(
+    State(alerts): State<Arc<StockAlertService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, StockAlertError> {
+    let subscription = alerts.subscription(id).await?;
+    if require(&claims, "inventory:write").is_err() && user_id(&claims).ok() != Some(subscription.user_id) {
+        return Err(StockAlertError::NotFound(id));
+    }
+    alerts.unsubscribe(id).await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+pub fn stock_alert_routes<S>(alerts: Arc<StockAlertService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/thresholds", get(list_thresholds).put(set_threshold))
+        .route("/thresholds/:id", delete(delete_threshold))
+        .route("/low", get(low_stock))
+        .route("/subscriptions", get(list_subscriptions).post(subscribe))
+        .route("/subscriptions/:id", delete(unsubscribe))
+        .with_state(alerts)
+}
@@ -0,0 +1,132 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::dunning::{CaseFilter, DunningError, DunningService, FailedCharge, NewSubscription};
+use crate::utils::pagination::PaginationParams;
+
+type Dunning = Option<Arc<DunningService>>;
+
+#[derive(Debug, Deserialize)]
+struct CancelRequest {
+    reason: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct RetryRequest {
+    /// Replaces the subscription's stored payment method before retrying.
+    payment_method_ref: Option<String>,
+    /// Replaces it with a reusable payment vault token instead.
+    vault_token: Option<String>,
+}
+
+async fn create_subscription(
+    State(dunning): State<Dunning>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewSubscription>,
+) -> Result<Response, DunningError> {
+    if let Some(response) = forbidden(&claims, "subscriptions:write") {
+        return Ok(response);
+    }
+    let dunning = dunning.ok_or(DunningError::Disabled)?;
+    let subscription = dunning.create_subscription(input).await?;
+    Ok((StatusCode::CREATED, Json(subscription)).into_response())
+}
+
+async fn get_subscription(
+    State(dunning): State<Dunning>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, DunningError> {
+    if let Some(response) = forbidden(&claims, "subscriptions:read") {
+        return Ok(response);
+    }
+    let dunning = dunning.ok_or(DunningError::Disabled)?;
+    Ok(Json(dunning.get(id).await?).into_response())
+}
+
+/// Reports a failed recurring charge, starting dunning for the subscription.
+async fn payment_failed(
+    State(dunning): State<Dunning>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(failed): Json<FailedCharge>,
+) -> Result<Response, DunningError> {
+    if let Some(response) = forbidden(&claims, "subscriptions:write") {
+        return Ok(response);
+    }
+    let dunning = dunning.ok_or(DunningError::Disabled)?;
+    let case = dunning.record_failure(id, failed).await?;
+    Ok((StatusCode::CREATED, Json(case)).into_response())
+}
+
+async fn cancel_subscription(
+    State(dunning): State<Dunning>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<CancelRequest>,
+) -> Result<Response, DunningError> {
+    if let Some(response) = forbidden(&claims, "subscriptions:write") {
+        return Ok(response);
+    }
+    let dunning = dunning.ok_or(DunningError::Disabled)?;
+    Ok(Json(dunning.cancel(id, input.reason).await?).into_response())
+}
+
+async fn list_cases(
+    State(dunning): State<Dunning>,
+    Query(filter): Query<CaseFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, DunningError> {
+    if let Some(response) = forbidden(&claims, "subscriptions:read") {
+        return Ok(response);
+    }
+    let dunning = dunning.ok_or(DunningError::Disabled)?;
+    let (items, total) = dunning.cases(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+/// Retries a dunning case immediately instead of waiting for its scheduled attempt.
+async fn retry_case(
+    State(dunning): State<Dunning>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    input: Option<Json<RetryRequest>>,
+) -> Result<Response, DunningError> {
+    if let Some(response) = forbidden(&claims, "subscriptions:write") {
+        return Ok(response);
+    }
+    let dunning = dunning.ok_or(DunningError::Disabled)?;
+    let Json(input) = input.unwrap_or_default();
+    Ok(Json(dunning.retry_now(id, input.payment_method_ref, input.vault_token).await?).into_response())
+}
+
+pub fn subscription_routes<S>(dunning: Option<Arc<DunningService>>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", post(create_subscription))
+        .route("/dunning", get(list_cases))
+        .route("/dunning/:id/retry", post(retry_case))
+        .route("/:id", get(get_subscription))
+        .route("/:id/payment-failed", post(payment_failed))
+        .route("/:id/cancel", post(cancel_subscription))
+        .with_state(dunning)
+}
@@ -0,0 +1,28 @@
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use std::sync::Arc;
+
+use crate::synthetic::{SyntheticChecker, SyntheticHealth};
+
+/// Outcome of the latest synthetic order runs. Answers 503 once the order flow has
+/// failed `failure_threshold` times in a row, even when every dependency answers pings.
+async fn synthetic_health(State(checker): State<Arc<SyntheticChecker>>) -> Response {
+    let status = checker.status();
+    let code = match status.status {
+        SyntheticHealth::Failing => StatusCode::SERVICE_UNAVAILABLE,
+        SyntheticHealth::Unknown | SyntheticHealth::Passing => StatusCode::OK,
+    };
+    (code, Json(status)).into_response()
+}
+
+pub fn synthetic_health_routes<S>(checker: Arc<SyntheticChecker>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new().route("/health/synthetic", get(synthetic_health)).with_state(checker)
+}
@@ -0,0 +1,62 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::auth::{admin_only, AuthUser};
+use crate::jobs::JobRunner;
+use crate::tenant_export::{self, ExportError, TenantExportService, ALGORITHM};
+
+#[derive(Clone)]
+pub struct TenantExportRoutesState {
+    pub exports: Option<Arc<TenantExportService>>,
+    pub jobs: Arc<JobRunner>,
+}
+
+impl TenantExportRoutesState {
+    fn exports(&self) -> Result<Arc<TenantExportService>, ExportError> {
+        self.exports
+            .clone()
+            .ok_or_else(|| ExportError::Misconfigured("no export bucket is configured".to_string()))
+    }
+}
+
+/// Starts an export of all the tenant's data. The bundle key is in this response only;
+/// the job's result carries the download links once the upload finishes.
+async fn export_tenant(
+    State(state): State<TenantExportRoutesState>,
+    Path(tenant_id): Path<String>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ExportError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let export =
+        tenant_export::submit_export(&state.jobs, state.exports()?, tenant_id.clone(), Some(claims.actor())).await?;
+    info!("Export of tenant {} requested by {}", tenant_id, claims.actor());
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(json!({
+            "job_id": export.job_id,
+            "export_id": export.export_id,
+            "key": export.key,
+            "algorithm": ALGORITHM,
+        })),
+    )
+        .into_response())
+}
+
+pub fn tenant_export_routes<S>(exports: Option<Arc<TenantExportService>>, jobs: Arc<JobRunner>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/:tenant_id/export", post(export_tenant))
+        .with_state(TenantExportRoutesState { exports, jobs })
+}
@@ -0,0 +1,91 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::header,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+
+use crate::auth::{admin_only, AuthUser};
+use crate::metering::{self, Meter, MeteringError, UsageFilter};
+use crate::utils::pagination::PaginationParams;
+
+type Metering = Option<Arc<Meter>>;
+
+fn disabled() -> MeteringError {
+    MeteringError::Misconfigured("metering is disabled".to_string())
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportParams {
+    period: String,
+}
+
+async fn list_usage(
+    State(meter): State<Metering>,
+    Query(filter): Query<UsageFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, MeteringError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let meter = meter.ok_or_else(disabled)?;
+    let (items, total) = meter.list(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+/// A period's usage as CSV, for billing systems that import files.
+async fn export_usage(
+    State(meter): State<Metering>,
+    Query(params): Query<ExportParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, MeteringError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let meter = meter.ok_or_else(disabled)?;
+    let records = meter.period(&params.period).await?;
+    let disposition = format!("attachment; filename=\"usage-{}.csv\"", params.period);
+    Ok((
+        [(header::CONTENT_TYPE, "text/csv".to_string()), (header::CONTENT_DISPOSITION, disposition)],
+        metering::to_csv(&records),
+    )
+        .into_response())
+}
+
+/// Resends a closed period to the billing webhook.
+async fn deliver_usage(
+    State(meter): State<Metering>,
+    Path(period): Path<String>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, MeteringError> {
+    if let Some(response) = admin_only(&claims) {
+        return Ok(response);
+    }
+    let meter = meter.ok_or_else(disabled)?;
+    let tenants = meter.deliver(&period).await?;
+    info!("Usage for {} delivered to billing by {}", period, claims.actor());
+    Ok(Json(json!({ "period": period, "tenants": tenants })).into_response())
+}
+
+pub fn usage_routes<S>(meter: Option<Arc<Meter>>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_usage))
+        .route("/export", get(export_usage))
+        .route("/:period/deliver", post(deliver_usage))
+        .with_state(meter)
+}
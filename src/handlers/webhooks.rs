@@ -0,0 +1,142 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser, Claims};
+use crate::jobs::JobRunner;
+use crate::webhooks::transform::TransformSpec;
+use crate::webhooks::{
+    self, NewWebhookSubscription, ReplayRequest, UpdateWebhookSubscription, WebhookError, WebhookService,
+};
+
+#[derive(Clone)]
+pub struct WebhookRoutesState {
+    pub service: Arc<WebhookService>,
+    pub jobs: Arc<JobRunner>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PreviewRequest {
+    #[serde(default)]
+    pub transform: TransformSpec,
+    pub payload: Value,
+}
+
+/// Subscriptions may only receive PII when set up by someone entitled to it.
+fn check_pii(claims: &Claims, spec: Option<&TransformSpec>) -> Result<(), WebhookError> {
+    let wants_pii = spec.map_or(false, |spec| spec.include_pii);
+    if wants_pii && !claims.is_allowed("webhooks:pii") {
+        return Err(WebhookError::PiiNotPermitted);
+    }
+    Ok(())
+}
+
+async fn list_webhooks(
+    State(state): State<WebhookRoutesState>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, WebhookError> {
+    if let Some(response) = forbidden(&claims, "webhooks:read") {
+        return Ok(response);
+    }
+    Ok(Json(json!({ "items": state.service.list().await? })).into_response())
+}
+
+async fn create_webhook(
+    State(state): State<WebhookRoutesState>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewWebhookSubscription>,
+) -> Result<Response, WebhookError> {
+    if let Some(response) = forbidden(&claims, "webhooks:write") {
+        return Ok(response);
+    }
+    check_pii(&claims, Some(&input.transform))?;
+    let (subscription, secret) = state.service.create(input, Some(claims.actor())).await?;
+    info!("Webhook subscription {} created by {}", subscription.id, claims.actor());
+    Ok((StatusCode::CREATED, Json(json!({ "subscription": subscription, "secret": secret }))).into_response())
+}
+
+async fn get_webhook(
+    State(state): State<WebhookRoutesState>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, WebhookError> {
+    if let Some(response) = forbidden(&claims, "webhooks:read") {
+        return Ok(response);
+    }
+    Ok(Json(state.service.get(id).await?).into_response())
+}
+
+async fn update_webhook(
+    State(state): State<WebhookRoutesState>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<UpdateWebhookSubscription>,
+) -> Result<Response, WebhookError> {
+    if let Some(response) = forbidden(&claims, "webhooks:write") {
+        return Ok(response);
+    }
+    check_pii(&claims, input.transform.as_ref())?;
+    Ok(Json(state.service.update(id, input).await?).into_response())
+}
+
+async fn delete_webhook(
+    State(state): State<WebhookRoutesState>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, WebhookError> {
+    if let Some(response) = forbidden(&claims, "webhooks:write") {
+        return Ok(response);
+    }
+    state.service.delete(id).await?;
+    info!("Webhook subscription {} deleted by {}", id, claims.actor());
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// Applies a transform to a sample payload, exactly as delivery would.
+async fn preview_transform(
+    State(state): State<WebhookRoutesState>,
+    AuthUser(claims): AuthUser,
+    Json(request): Json<PreviewRequest>,
+) -> Result<Response, WebhookError> {
+    if let Some(response) = forbidden(&claims, "webhooks:write") {
+        return Ok(response);
+    }
+    check_pii(&claims, Some(&request.transform))?;
+    Ok(Json(json!({ "body": state.service.preview(&request.transform, request.payload)? })).into_response())
+}
+
+/// Re-delivers past events in a time range to the subscriber, as a job.
+async fn replay_webhook(
+    State(state): State<WebhookRoutesState>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(request): Json<ReplayRequest>,
+) -> Result<Response, WebhookError> {
+    if let Some(response) = forbidden(&claims, "webhooks:write") {
+        return Ok(response);
+    }
+    let job_id = webhooks::submit_replay(&state.jobs, state.service.clone(), id, request, Some(claims.actor())).await?;
+    info!("Webhook replay to {} requested by {}", id, claims.actor());
+    Ok((StatusCode::ACCEPTED, Json(json!({ "job_id": job_id }))).into_response())
+}
+
+pub fn webhook_routes<S>(service: Arc<WebhookService>, jobs: Arc<JobRunner>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_webhooks).post(create_webhook))
+        .route("/preview", post(preview_transform))
+        .route("/:id", get(get_webhook).patch(update_webhook).delete(delete_webhook))
+        .route("/:id/replay", post(replay_webhook))
+        .with_state(WebhookRoutesState { service, jobs })
+}
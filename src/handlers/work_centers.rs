@@ -0,0 +1,119 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{delete, get, post},
+    Json, Router,
+};
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::capacity_service::{CapacityService, LoadQuery, NewHoliday, NewWorkCenter, WorkCenterUpdate};
+use crate::utils::pagination::PaginationParams;
+
+async fn list_work_centers(
+    State(capacity): State<Arc<CapacityService>>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "work_centers:read") {
+        return Ok(response);
+    }
+    let (items, total) = capacity.list(pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+async fn create_work_center(
+    State(capacity): State<Arc<CapacityService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewWorkCenter>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "work_centers:write") {
+        return Ok(response);
+    }
+    let calendar = capacity.create(input).await?;
+    info!("Work center {} created by {}", calendar.work_center.code, claims.actor());
+    Ok((StatusCode::CREATED, Json(calendar)).into_response())
+}
+
+async fn get_work_center(
+    State(capacity): State<Arc<CapacityService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "work_centers:read") {
+        return Ok(response);
+    }
+    Ok(Json(capacity.get(id).await?).into_response())
+}
+
+async fn update_work_center(
+    State(capacity): State<Arc<CapacityService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<WorkCenterUpdate>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "work_centers:write") {
+        return Ok(response);
+    }
+    Ok(Json(capacity.update(id, input).await?).into_response())
+}
+
+async fn add_holiday(
+    State(capacity): State<Arc<CapacityService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewHoliday>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "work_centers:write") {
+        return Ok(response);
+    }
+    Ok((StatusCode::CREATED, Json(capacity.add_holiday(id, input).await?)).into_response())
+}
+
+async fn remove_holiday(
+    State(capacity): State<Arc<CapacityService>>,
+    Path((id, holiday_id)): Path<(Uuid, Uuid)>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "work_centers:write") {
+        return Ok(response);
+    }
+    capacity.remove_holiday(id, holiday_id).await?;
+    Ok(StatusCode::NO_CONTENT.into_response())
+}
+
+/// Scheduled operation hours against work center capacity, by day or week.
+async fn load_report(
+    State(capacity): State<Arc<CapacityService>>,
+    Query(query): Query<LoadQuery>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "work_centers:read") {
+        return Ok(response);
+    }
+    Ok(Json(capacity.load_report(query).await?).into_response())
+}
+
+pub fn work_center_routes<S>(capacity: Arc<CapacityService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_work_centers).post(create_work_center))
+        .route("/load", get(load_report))
+        .route("/:id", get(get_work_center).put(update_work_center))
+        .route("/:id/holidays", post(add_holiday))
+        .route("/:id/holidays/:holiday_id", delete(remove_holiday))
+        .with_state(capacity)
+}
@@ -0,0 +1,108 @@
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::work_order_operations::{MachineTime, NewOperation, WorkOrderOperationService};
+
+async fn list_operations(
+    State(operations): State<Arc<WorkOrderOperationService>>,
+    Path(work_order_id): Path<Uuid>,
+    AuthUser(_claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    Ok(Json(operations.list_operations(work_order_id).await?).into_response())
+}
+
+/// Adds a routing step. Admins and `work_orders:write` only.
+async fn add_operation(
+    State(operations): State<Arc<WorkOrderOperationService>>,
+    Path(work_order_id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewOperation>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "work_orders:write") {
+        return Ok(response);
+    }
+    let operation = operations.add_operation(work_order_id, input).await?;
+    info!("Operation {} added to work order {} by {}", operation.id, work_order_id, claims.actor());
+    Ok((StatusCode::CREATED, Json(operation)).into_response())
+}
+
+/// Clocks the caller in to an operation.
+async fn clock_in(
+    State(operations): State<Arc<WorkOrderOperationService>>,
+    Path((work_order_id, operation_id)): Path<(Uuid, Uuid)>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    let entry = operations.clock_in(work_order_id, operation_id, &claims.actor()).await?;
+    Ok((StatusCode::CREATED, Json(entry)).into_response())
+}
+
+/// Clocks the caller out of an operation; the work order's actual labor is updated.
+async fn clock_out(
+    State(operations): State<Arc<WorkOrderOperationService>>,
+    Path((work_order_id, operation_id)): Path<(Uuid, Uuid)>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    Ok(Json(operations.clock_out(work_order_id, operation_id, &claims.actor()).await?).into_response())
+}
+
+async fn record_machine_time(
+    State(operations): State<Arc<WorkOrderOperationService>>,
+    Path((work_order_id, operation_id)): Path<(Uuid, Uuid)>,
+    AuthUser(claims): AuthUser,
+    Json(time): Json<MachineTime>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "work_orders:write") {
+        return Ok(response);
+    }
+    let entry = operations
+        .record_machine_time(work_order_id, operation_id, time, &claims.actor())
+        .await?;
+    Ok((StatusCode::CREATED, Json(entry)).into_response())
+}
+
+async fn complete_operation(
+    State(operations): State<Arc<WorkOrderOperationService>>,
+    Path((work_order_id, operation_id)): Path<(Uuid, Uuid)>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "work_orders:write") {
+        return Ok(response);
+    }
+    Ok(Json(operations.complete_operation(work_order_id, operation_id).await?).into_response())
+}
+
+/// Actual against standard labor and machine hours and cost, per operation and in total.
+async fn get_costing(
+    State(operations): State<Arc<WorkOrderOperationService>>,
+    Path(work_order_id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "work_orders:read") {
+        return Ok(response);
+    }
+    Ok(Json(operations.rollup(work_order_id).await?).into_response())
+}
+
+pub fn operation_routes<S>(operations: Arc<WorkOrderOperationService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/:id/operations", get(list_operations).post(add_operation))
+        .route("/:id/operations/:operation_id/clock-in", post(clock_in))
+        .route("/:id/operations/:operation_id/clock-out", post(clock_out))
+        .route("/:id/operations/:operation_id/machine-time", post(record_machine_time))
+        .route("/:id/operations/:operation_id/complete", post(complete_operation))
+        .route("/:id/costing", get(get_costing))
+        .with_state(operations)
+}
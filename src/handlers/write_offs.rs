@@ -0,0 +1,99 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::auth::{forbidden, AuthUser};
+use crate::errors::ServiceError;
+use crate::services::write_off_service::{NewWriteOff, WriteOffFilter, WriteOffService};
+use crate::utils::pagination::PaginationParams;
+
+#[derive(Debug, Deserialize)]
+struct ShrinkageParams {
+    from: NaiveDate,
+    to: NaiveDate,
+    warehouse: Option<i32>,
+}
+
+async fn list_write_offs(
+    State(write_offs): State<Arc<WriteOffService>>,
+    Query(filter): Query<WriteOffFilter>,
+    Query(pagination): Query<PaginationParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "inventory:read") {
+        return Ok(response);
+    }
+    let (items, total) = write_offs.list(filter, pagination).await?;
+    Ok(Json(json!({
+        "items": items,
+        "total": total,
+        "page": pagination.page,
+        "per_page": pagination.per_page,
+    }))
+    .into_response())
+}
+
+/// Writes off damaged or missing stock. Posts immediately unless the `write_off` approval
+/// chain applies to its value.
+async fn create_write_off(
+    State(write_offs): State<Arc<WriteOffService>>,
+    AuthUser(claims): AuthUser,
+    Json(input): Json<NewWriteOff>,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "inventory:write") {
+        return Ok(response);
+    }
+    let write_off = write_offs.create(input, &claims.actor()).await?;
+    info!(
+        "Write-off {} of {} x {} requested by {}",
+        write_off.write_off.id,
+        write_off.write_off.quantity,
+        write_off.write_off.sku,
+        claims.actor()
+    );
+    Ok((StatusCode::CREATED, Json(write_off)).into_response())
+}
+
+async fn get_write_off(
+    State(write_offs): State<Arc<WriteOffService>>,
+    Path(id): Path<Uuid>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "inventory:read") {
+        return Ok(response);
+    }
+    Ok(Json(write_offs.get(id).await?).into_response())
+}
+
+async fn shrinkage_report(
+    State(write_offs): State<Arc<WriteOffService>>,
+    Query(params): Query<ShrinkageParams>,
+    AuthUser(claims): AuthUser,
+) -> Result<Response, ServiceError> {
+    if let Some(response) = forbidden(&claims, "inventory:read") {
+        return Ok(response);
+    }
+    let warehouses = write_offs.shrinkage_report(params.from, params.to, params.warehouse).await?;
+    Ok(Json(json!({ "from": params.from, "to": params.to, "warehouses": warehouses })).into_response())
+}
+
+pub fn write_off_routes<S>(write_offs: Arc<WriteOffService>) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    Router::new()
+        .route("/", get(list_write_offs).post(create_write_off))
+        .route("/reports/shrinkage", get(shrinkage_report))
+        .route("/:id", get(get_write_off))
+        .with_state(write_offs)
+}
@@ -0,0 +1,262 @@
+// hazmat/mod.rs
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::sync::RwLock;
+
+use crate::models::{
+    product_hazmat::{self, PackingGroup},
+    shipment::{self, ShippingCarrier},
+    shipment_dangerous_good,
+};
+
+/// Classes and divisions of the UN Model Regulations.
+pub const HAZARD_CLASSES: [&str; 20] = [
+    "1.1", "1.2", "1.3", "1.4", "1.5", "1.6", "2.1", "2.2", "2.3", "3", "4.1", "4.2", "4.3", "5.1", "5.2", "6.1",
+    "6.2", "7", "8", "9",
+];
+
+/// Dangerous goods shipping rules, loaded from the `hazmat` section of the config.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct HazmatConfig {
+    /// Carriers the shipper holds a dangerous goods contract with. Fully regulated goods
+    /// (anything not packed as a limited quantity) only go with these.
+    #[serde(default)]
+    pub contracted_carriers: Vec<ShippingCarrier>,
+
+    /// 24-hour emergency response telephone number printed on declarations.
+    #[serde(default)]
+    pub emergency_phone: Option<String>,
+}
+
+static CONFIG: RwLock<Option<HazmatConfig>> = RwLock::new(None);
+
+/// Sets the dangerous goods rules for the process. Called at startup from config.
+pub fn set_config(config: HazmatConfig) {
+    *CONFIG.write().unwrap_or_else(|e| e.into_inner()) = Some(config);
+}
+
+pub fn config() -> HazmatConfig {
+    CONFIG.read().unwrap_or_else(|e| e.into_inner()).clone().unwrap_or_default()
+}
+
+pub fn valid_un_number(un_number: &str) -> bool {
+    un_number.len() == 6 && un_number.starts_with("UN") && un_number[2..].bytes().all(|b| b.is_ascii_digit())
+}
+
+pub fn valid_hazard_class(class: &str) -> bool {
+    HAZARD_CLASSES.contains(&class)
+}
+
+/// Why a packing group is wrong for a class: classes 3, 4, 5.1, 6.1 and 8 need one;
+/// classes 1, 2, 6.2 and 7 never have one; class 9 depends on the entry.
+pub fn packing_group_error(class: &str, packing_group: Option<PackingGroup>) -> Option<&'static str> {
+    let needs_group = matches!(class, "3" | "4.1" | "4.2" | "4.3" | "5.1" | "6.1" | "8");
+    let has_no_group = class.starts_with('1') || class.starts_with('2') || class == "6.2" || class == "7";
+    match packing_group {
+        None if needs_group => Some("packing_group_required"),
+        Some(_) if has_no_group => Some("packing_group_not_assigned_to_class"),
+        _ => None,
+    }
+}
+
+/// A quantity of one dangerous SKU in a shipment.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DangerousGood {
+    pub sku: String,
+    pub quantity: i32,
+    pub un_number: String,
+    pub proper_shipping_name: String,
+    pub hazard_class: String,
+    pub packing_group: Option<PackingGroup>,
+    pub limited_quantity: bool,
+}
+
+impl DangerousGood {
+    pub fn new(product: &product_hazmat::Model, quantity: i32) -> Self {
+        Self {
+            sku: product.sku.clone(),
+            quantity,
+            un_number: product.un_number.clone(),
+            proper_shipping_name: product.proper_shipping_name.clone(),
+            hazard_class: product.hazard_class.clone(),
+            packing_group: product.packing_group,
+            limited_quantity: product.limited_quantity,
+        }
+    }
+
+    /// The UN description as it appears on documents, e.g.
+    /// `UN1266, Perfume products, 3, PG II`.
+    pub fn description(&self) -> String {
+        let mut description = format!("{}, {}, {}", self.un_number, self.proper_shipping_name, self.hazard_class);
+        if let Some(group) = self.packing_group {
+            let _ = write!(description, ", PG {:?}", group);
+        }
+        if self.limited_quantity {
+            description.push_str(" (Limited Quantity)");
+        }
+        description
+    }
+}
+
+impl From<&shipment_dangerous_good::Model> for DangerousGood {
+    fn from(line: &shipment_dangerous_good::Model) -> Self {
+        Self {
+            sku: line.sku.clone(),
+            quantity: line.quantity,
+            un_number: line.un_number.clone(),
+            proper_shipping_name: line.proper_shipping_name.clone(),
+            hazard_class: line.hazard_class.clone(),
+            packing_group: line.packing_group,
+            limited_quantity: line.limited_quantity,
+        }
+    }
+}
+
+/// Whether a carrier service moves by air. DHL parcels all fly on its express network;
+/// for the others it is read from the service name.
+pub fn is_air_service(carrier: ShippingCarrier, service: &str) -> bool {
+    const AIR: [&str; 8] = ["air", "next day", "overnight", "express", "2day", "2nd day", "2 day", "priority"];
+    let service = service.to_ascii_lowercase();
+    carrier == ShippingCarrier::DHL || AIR.iter().any(|word| service.contains(word))
+}
+
+/// Why `goods` may not go with `carrier` on `service`; empty when they may.
+pub fn violations(
+    carrier: ShippingCarrier,
+    service: &str,
+    goods: &[DangerousGood],
+    config: &HazmatConfig,
+) -> Vec<String> {
+    let air = is_air_service(carrier, service);
+    let mut violations = Vec::new();
+    for good in goods {
+        let division = good.hazard_class.as_str();
+        if division.starts_with('1') || division == "7" {
+            violations.push(format!(
+                "{} ({}): class {} is not accepted by parcel carriers",
+                good.sku, good.un_number, division
+            ));
+            continue;
+        }
+        if carrier == ShippingCarrier::USPS {
+            if air {
+                violations.push(format!("{} ({}): USPS does not carry dangerous goods by air", good.sku, good.un_number));
+            } else if !good.limited_quantity {
+                violations.push(format!(
+                    "{} ({}): USPS accepts dangerous goods only as limited quantities",
+                    good.sku, good.un_number
+                ));
+            }
+            continue;
+        }
+        if air && division == "2.1" {
+            violations.push(format!("{} ({}): flammable gas is forbidden on {}", good.sku, good.un_number, service));
+        }
+        if air && good.packing_group == Some(PackingGroup::I) {
+            violations.push(format!("{} ({}): packing group I is forbidden on {}", good.sku, good.un_number, service));
+        }
+        if !good.limited_quantity && !config.contracted_carriers.contains(&carrier) {
+            violations.push(format!(
+                "{} ({}): no dangerous goods contract with {:?} for fully regulated goods",
+                good.sku, good.un_number, carrier
+            ));
+        }
+    }
+    violations
+}
+
+/// The shipper's declaration for dangerous goods, printed with the shipment documents.
+pub fn declaration(shipment: &shipment::Model, goods: &[DangerousGood], config: &HazmatConfig) -> String {
+    let mut doc = String::new();
+    let _ = writeln!(doc, "SHIPPER'S DECLARATION FOR DANGEROUS GOODS");
+    let _ = writeln!(doc, "Shipment: {}", shipment.id);
+    let _ = writeln!(doc, "Tracking number: {}", shipment.tracking_number);
+    let _ = writeln!(doc, "Carrier: {:?} {}", shipment.carrier, shipment.shipping_method);
+    let mode = if is_air_service(shipment.carrier, &shipment.shipping_method) { "Air" } else { "Ground" };
+    let _ = writeln!(doc, "Transport: {}", mode);
+    let _ = writeln!(doc, "Consignee: {}", shipment.shipping_address.lines().next().unwrap_or(""));
+    let _ = writeln!(doc);
+    let _ = writeln!(doc, "{:<8} {:<8} Description", "UN No.", "Qty");
+    for good in goods {
+        let _ = writeln!(doc, "{:<8} {:<8} {}", good.un_number, good.quantity, good.description());
+    }
+    let _ = writeln!(doc);
+    if let Some(phone) = &config.emergency_phone {
+        let _ = writeln!(doc, "Emergency response telephone: {}", phone);
+    }
+    let _ = writeln!(
+        doc,
+        "I hereby declare that the contents of this consignment are fully and accurately described above by \
+         the proper shipping name, and are classified, packaged, marked and labelled, and are in all respects \
+         in proper condition for transport according to applicable international and national governmental \
+         regulations."
+    );
+    let _ = writeln!(doc);
+    let _ = writeln!(doc, "Name: ______________________   Signature: ______________________   Date: ________");
+    doc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn good(class: &str, packing_group: Option<PackingGroup>, limited_quantity: bool) -> DangerousGood {
+        DangerousGood {
+            sku: "SKU-1".to_string(),
+            quantity: 2,
+            un_number: "UN1266".to_string(),
+            proper_shipping_name: "Perfume products".to_string(),
+            hazard_class: class.to_string(),
+            packing_group,
+            limited_quantity,
+        }
+    }
+
+    #[test]
+    fn test_classification_checks() {
+        assert!(valid_un_number("UN1266"));
+        assert!(!valid_un_number("1266") && !valid_un_number("UN12a6"));
+        assert!(valid_hazard_class("2.1") && valid_hazard_class("9"));
+        assert!(!valid_hazard_class("2") && !valid_hazard_class("10"));
+        assert_eq!(packing_group_error("3", None), Some("packing_group_required"));
+        assert_eq!(packing_group_error("2.2", Some(PackingGroup::II)), Some("packing_group_not_assigned_to_class"));
+        assert_eq!(packing_group_error("9", None), None);
+    }
+
+    #[test]
+    fn test_limited_quantity_goes_by_any_ground_service() {
+        let config = HazmatConfig::default();
+        let goods = [good("3", Some(PackingGroup::II), true)];
+        assert!(violations(ShippingCarrier::UPS, "Ground", &goods, &config).is_empty());
+        assert!(violations(ShippingCarrier::USPS, "Ground Advantage", &goods, &config).is_empty());
+        assert_eq!(violations(ShippingCarrier::USPS, "Priority Mail", &goods, &config).len(), 1);
+    }
+
+    #[test]
+    fn test_fully_regulated_needs_a_contract() {
+        let goods = [good("3", Some(PackingGroup::II), false)];
+        assert_eq!(violations(ShippingCarrier::FedEx, "Ground", &goods, &HazmatConfig::default()).len(), 1);
+        let config = HazmatConfig { contracted_carriers: vec![ShippingCarrier::FedEx], emergency_phone: None };
+        assert!(violations(ShippingCarrier::FedEx, "Ground", &goods, &config).is_empty());
+        assert_eq!(violations(ShippingCarrier::USPS, "Ground Advantage", &goods, &config).len(), 1);
+    }
+
+    #[test]
+    fn test_air_restrictions() {
+        let config = HazmatConfig { contracted_carriers: vec![ShippingCarrier::UPS], emergency_phone: None };
+        let gas = [good("2.1", None, false)];
+        assert!(violations(ShippingCarrier::UPS, "Ground", &gas, &config).is_empty());
+        assert_eq!(violations(ShippingCarrier::UPS, "Next Day Air", &gas, &config).len(), 1);
+        let group_one = [good("8", Some(PackingGroup::I), true)];
+        assert_eq!(violations(ShippingCarrier::DHL, "Parcel", &group_one, &config).len(), 1);
+        let explosive = [good("1.4", None, true)];
+        assert_eq!(violations(ShippingCarrier::UPS, "Ground", &explosive, &config).len(), 1);
+    }
+
+    #[test]
+    fn test_description() {
+        assert_eq!(good("3", Some(PackingGroup::II), false).description(), "UN1266, Perfume products, 3, PG II");
+        assert_eq!(good("9", None, true).description(), "UN1266, Perfume products, 9 (Limited Quantity)");
+    }
+}
@@ -0,0 +1,193 @@
+// http_server/mod.rs
+
+use hyper::server::{conn::AddrIncoming, Builder};
+use serde::Deserialize;
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::net::TcpSocket;
+
+/// Connection-level server settings, loaded from the `http_server` section of the config.
+/// The defaults suit integrators that multiplex many requests over few connections.
+#[derive(Clone, Debug, Deserialize)]
+pub struct HttpServerConfig {
+    /// Accept HTTP/2 (prior knowledge, or via a TLS-terminating proxy) next to HTTP/1.1
+    /// (default: true).
+    #[serde(default = "default_true")]
+    pub http2_enabled: bool,
+
+    /// Refuse HTTP/1.1 entirely (default: false).
+    #[serde(default)]
+    pub http2_only: bool,
+
+    /// Streams one HTTP/2 connection may have open at once (default: 256).
+    #[serde(default = "default_max_concurrent_streams")]
+    pub http2_max_concurrent_streams: u32,
+
+    /// Lets HTTP/2 flow-control windows grow with the bandwidth-delay product, so one
+    /// large response doesn't stall the other streams on its connection (default: true).
+    #[serde(default = "default_true")]
+    pub http2_adaptive_window: bool,
+
+    /// Interval between HTTP/2 PING frames on idle connections; 0 disables them
+    /// (default: 20 s).
+    #[serde(default = "default_http2_keepalive_interval_secs")]
+    pub http2_keepalive_interval_secs: u64,
+
+    /// How long to wait for a PING acknowledgement before closing (default: 20 s).
+    #[serde(default = "default_http2_keepalive_timeout_secs")]
+    pub http2_keepalive_timeout_secs: u64,
+
+    /// Reuse HTTP/1.1 connections across requests (default: true).
+    #[serde(default = "default_true")]
+    pub http1_keepalive: bool,
+
+    /// Idle time before TCP keep-alive probes start; 0 disables them (default: 60 s).
+    #[serde(default = "default_tcp_keepalive_secs")]
+    pub tcp_keepalive_secs: u64,
+
+    /// Disable Nagle's algorithm so small responses aren't delayed (default: true).
+    #[serde(default = "default_true")]
+    pub tcp_nodelay: bool,
+
+    /// Pending connections the kernel queues before refusing new ones (default: 1024).
+    /// Capped by `net.core.somaxconn` on Linux.
+    #[serde(default = "default_backlog")]
+    pub backlog: u32,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_max_concurrent_streams() -> u32 {
+    256
+}
+
+fn default_http2_keepalive_interval_secs() -> u64 {
+    20
+}
+
+fn default_http2_keepalive_timeout_secs() -> u64 {
+    20
+}
+
+fn default_tcp_keepalive_secs() -> u64 {
+    60
+}
+
+fn default_backlog() -> u32 {
+    1024
+}
+
+impl Default for HttpServerConfig {
+    fn default() -> Self {
+        Self {
+            http2_enabled: default_true(),
+            http2_only: false,
+            http2_max_concurrent_streams: default_max_concurrent_streams(),
+            http2_adaptive_window: default_true(),
+            http2_keepalive_interval_secs: default_http2_keepalive_interval_secs(),
+            http2_keepalive_timeout_secs: default_http2_keepalive_timeout_secs(),
+            http1_keepalive: default_true(),
+            tcp_keepalive_secs: default_tcp_keepalive_secs(),
+            tcp_nodelay: default_true(),
+            backlog: default_backlog(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum HttpServerError {
+    #[error("Invalid http_server config: {0}")]
+    Config(String),
+
+    #[error("Cannot listen on {addr}: {source}")]
+    Bind {
+        addr: SocketAddr,
+        #[source]
+        source: io::Error,
+    },
+}
+
+fn seconds(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+impl HttpServerConfig {
+    /// Rejects combinations hyper would silently misapply.
+    pub fn check(&self) -> Result<(), HttpServerError> {
+        if self.http2_only && !self.http2_enabled {
+            return Err(HttpServerError::Config("http2_only needs http2_enabled".to_string()));
+        }
+        if self.http2_max_concurrent_streams == 0 {
+            return Err(HttpServerError::Config("http2_max_concurrent_streams must be positive".to_string()));
+        }
+        if self.http2_keepalive_interval_secs > 0 && self.http2_keepalive_timeout_secs == 0 {
+            return Err(HttpServerError::Config(
+                "http2_keepalive_timeout_secs must be positive when keep-alive pings are on".to_string(),
+            ));
+        }
+        if self.backlog == 0 {
+            return Err(HttpServerError::Config("backlog must be positive".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Listens on `addr` with the configured backlog and per-connection TCP options.
+    pub fn bind(&self, addr: SocketAddr) -> Result<AddrIncoming, HttpServerError> {
+        let bind_error = |source: io::Error| HttpServerError::Bind { addr, source };
+        let socket = if addr.is_ipv4() { TcpSocket::new_v4() } else { TcpSocket::new_v6() }.map_err(bind_error)?;
+        // Restarts shouldn't fail while the previous process' sockets sit in TIME_WAIT
+        socket.set_reuseaddr(true).map_err(bind_error)?;
+        socket.bind(addr).map_err(bind_error)?;
+        let listener = socket.listen(self.backlog).map_err(bind_error)?;
+        let mut incoming = AddrIncoming::from_listener(listener)
+            .map_err(|e| bind_error(io::Error::new(io::ErrorKind::Other, e)))?;
+        incoming.set_nodelay(self.tcp_nodelay);
+        incoming.set_keepalive(seconds(self.tcp_keepalive_secs));
+        Ok(incoming)
+    }
+
+    /// A server builder with the HTTP/1.1 and HTTP/2 settings applied, for plain or TLS
+    /// connections.
+    pub fn builder<I>(&self, incoming: I) -> Builder<I> {
+        hyper::Server::builder(incoming)
+            .http1_only(!self.http2_enabled)
+            .http2_only(self.http2_only)
+            .http1_keepalive(self.http1_keepalive)
+            .http2_max_concurrent_streams(self.http2_max_concurrent_streams)
+            .http2_adaptive_window(self.http2_adaptive_window)
+            .http2_keep_alive_interval(seconds(self.http2_keepalive_interval_secs))
+            .http2_keep_alive_timeout(Duration::from_secs(self.http2_keepalive_timeout_secs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_rejects_inconsistent_settings() {
+        assert!(HttpServerConfig::default().check().is_ok());
+        let http2_only = HttpServerConfig { http2_only: true, http2_enabled: false, ..Default::default() };
+        assert!(http2_only.check().is_err());
+        let no_streams = HttpServerConfig { http2_max_concurrent_streams: 0, ..Default::default() };
+        assert!(no_streams.check().is_err());
+        let pings_off = HttpServerConfig {
+            http2_keepalive_interval_secs: 0,
+            http2_keepalive_timeout_secs: 0,
+            ..Default::default()
+        };
+        assert!(pings_off.check().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_bind_listens_on_the_address() {
+        let config = HttpServerConfig { backlog: 16, ..Default::default() };
+        let incoming = config.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = incoming.local_addr();
+        assert!(tokio::net::TcpStream::connect(addr).await.is_ok());
+    }
+}
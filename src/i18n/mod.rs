@@ -0,0 +1,285 @@
+// i18n/mod.rs
+
+use async_trait::async_trait;
+use axum::{extract::FromRequestParts, http::request::Parts};
+use chrono::NaiveDate;
+use lazy_static::lazy_static;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, convert::Infallible, fmt};
+use tracing::warn;
+
+/// Header that overrides `Accept-Language` with an explicit locale.
+pub const LOCALE_HEADER: &str = "X-Locale";
+
+/// Locales with a message catalog and formatting rules.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "en-US")]
+    EnUs,
+    #[serde(rename = "en-GB")]
+    EnGb,
+    #[serde(rename = "de-DE")]
+    DeDe,
+    #[serde(rename = "fr-FR")]
+    FrFr,
+    #[serde(rename = "es-ES")]
+    EsEs,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 5] = [Locale::EnUs, Locale::EnGb, Locale::DeDe, Locale::FrFr, Locale::EsEs];
+
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Locale::EnUs => "en-US",
+            Locale::EnGb => "en-GB",
+            Locale::DeDe => "de-DE",
+            Locale::FrFr => "fr-FR",
+            Locale::EsEs => "es-ES",
+        }
+    }
+
+    /// Parses a BCP 47 tag. Unknown regions fall back to the language's default
+    /// locale, e.g. `de-AT` → `de-DE`; unknown languages return `None`.
+    pub fn parse(tag: &str) -> Option<Locale> {
+        let tag = tag.trim().replace('_', "-");
+        let mut parts = tag.split('-');
+        let language = parts.next()?.to_ascii_lowercase();
+        let region = parts.next().map(|r| r.to_ascii_uppercase());
+
+        match (language.as_str(), region.as_deref()) {
+            ("en", Some("GB")) | ("en", Some("UK")) | ("en", Some("IE")) => Some(Locale::EnGb),
+            ("en", _) => Some(Locale::EnUs),
+            ("de", _) => Some(Locale::DeDe),
+            ("fr", _) => Some(Locale::FrFr),
+            ("es", _) => Some(Locale::EsEs),
+            _ => None,
+        }
+    }
+
+    /// Picks the best supported locale from an `Accept-Language` header value.
+    pub fn from_accept_language(header: &str) -> Option<Locale> {
+        let mut candidates: Vec<(f32, Locale)> = header
+            .split(',')
+            .filter_map(|entry| {
+                let mut pieces = entry.split(';');
+                let tag = pieces.next()?.trim();
+                let quality = pieces
+                    .find_map(|p| p.trim().strip_prefix("q="))
+                    .and_then(|q| q.parse::<f32>().ok())
+                    .unwrap_or(1.0);
+                Locale::parse(tag).map(|locale| (quality, locale))
+            })
+            .filter(|(quality, _)| *quality > 0.0)
+            .collect();
+        // Stable sort keeps header order for equal weights
+        candidates.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+        candidates.first().map(|(_, locale)| *locale)
+    }
+
+    /// Resolves the locale for a customer-facing message: an explicit customer
+    /// preference wins, then the request's locale.
+    pub fn resolve(customer_locale: Option<&str>, request_locale: Locale) -> Locale {
+        customer_locale.and_then(Locale::parse).unwrap_or(request_locale)
+    }
+
+    fn separators(&self) -> (&'static str, &'static str) {
+        // (thousands, decimal)
+        match self {
+            Locale::EnUs | Locale::EnGb => (",", "."),
+            Locale::DeDe | Locale::EsEs => (".", ","),
+            Locale::FrFr => ("\u{202f}", ","),
+        }
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.tag())
+    }
+}
+
+lazy_static! {
+    static ref CATALOGS: HashMap<Locale, HashMap<String, String>> = {
+        let sources = [
+            (Locale::EnUs, include_str!("locales/en-US.json")),
+            (Locale::EnGb, include_str!("locales/en-GB.json")),
+            (Locale::DeDe, include_str!("locales/de-DE.json")),
+            (Locale::FrFr, include_str!("locales/fr-FR.json")),
+            (Locale::EsEs, include_str!("locales/es-ES.json")),
+        ];
+        sources
+            .into_iter()
+            .map(|(locale, source)| {
+                let messages = serde_json::from_str(source)
+                    .unwrap_or_else(|e| panic!("invalid message catalog for {}: {}", locale, e));
+                (locale, messages)
+            })
+            .collect()
+    };
+}
+
+/// Looks up a message and fills `{name}` placeholders. Missing keys fall back to
+/// en-US, then to the key itself so a gap in a catalog never breaks a response.
+pub fn t(locale: Locale, key: &str, args: &[(&str, &str)]) -> String {
+    let template = CATALOGS
+        .get(&locale)
+        .and_then(|catalog| catalog.get(key))
+        .or_else(|| CATALOGS.get(&Locale::EnUs).and_then(|catalog| catalog.get(key)));
+
+    let mut message = match template {
+        Some(template) => template.clone(),
+        None => {
+            warn!(locale = %locale, key, "Missing i18n message");
+            key.to_string()
+        }
+    };
+    for (name, value) in args {
+        message = message.replace(&format!("{{{}}}", name), value);
+    }
+    message
+}
+
+/// Formats a date in the locale's conventional short form.
+pub fn format_date(locale: Locale, date: NaiveDate) -> String {
+    let pattern = match locale {
+        Locale::EnUs => "%m/%d/%Y",
+        Locale::EnGb | Locale::FrFr | Locale::EsEs => "%d/%m/%Y",
+        Locale::DeDe => "%d.%m.%Y",
+    };
+    date.format(pattern).to_string()
+}
+
+/// Formats a number with the locale's grouping and decimal separators, rounded to `scale` places.
+pub fn format_number(locale: Locale, value: Decimal, scale: u32) -> String {
+    let (group, decimal) = locale.separators();
+    let rounded = value.round_dp(scale).abs();
+    let text = format!("{:.*}", scale as usize, rounded);
+    let (integer, fraction) = match text.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (text.as_str(), None),
+    };
+
+    let mut grouped = String::new();
+    for (i, digit) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            grouped.push_str(group);
+        }
+        grouped.push(digit);
+    }
+
+    let sign = if value.is_sign_negative() && !rounded.is_zero() { "-" } else { "" };
+    match fraction {
+        Some(fraction) => format!("{}{}{}{}", sign, grouped, decimal, fraction),
+        None => format!("{}{}", sign, grouped),
+    }
+}
+
+fn currency_symbol(currency: &str) -> Option<&'static str> {
+    match currency.to_ascii_uppercase().as_str() {
+        "USD" => Some("$"),
+        "EUR" => Some("€"),
+        "GBP" => Some("£"),
+        _ => None,
+    }
+}
+
+/// Formats a money amount with the currency symbol placed per locale convention.
+/// Currencies without a known symbol use the ISO code.
+pub fn format_currency(locale: Locale, amount: Decimal, currency: &str) -> String {
+    let number = format_number(locale, amount, crate::money::CURRENCY_SCALE);
+    let symbol = currency_symbol(currency)
+        .map(str::to_string)
+        .unwrap_or_else(|| currency.to_ascii_uppercase());
+    match locale {
+        Locale::EnUs | Locale::EnGb => match number.strip_prefix('-') {
+            Some(positive) => format!("-{}{}", symbol, positive),
+            None => format!("{}{}", symbol, number),
+        },
+        Locale::DeDe | Locale::FrFr | Locale::EsEs => format!("{}\u{a0}{}", number, symbol),
+    }
+}
+
+/// Extractor resolving the request locale from `X-Locale`, then `Accept-Language`,
+/// defaulting to en-US.
+pub struct RequestLocale(pub Locale);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for RequestLocale
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let header = |name: &str| parts.headers.get(name).and_then(|v| v.to_str().ok());
+        let locale = header(LOCALE_HEADER)
+            .and_then(Locale::parse)
+            .or_else(|| header("Accept-Language").and_then(Locale::from_accept_language))
+            .unwrap_or_default();
+        Ok(RequestLocale(locale))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_locale_parsing_and_negotiation() {
+        assert_eq!(Locale::parse("de-AT"), Some(Locale::DeDe));
+        assert_eq!(Locale::parse("en_gb"), Some(Locale::EnGb));
+        assert_eq!(Locale::parse("ja-JP"), None);
+        assert_eq!(
+            Locale::from_accept_language("ja;q=0.9, fr-CH;q=0.8, de;q=0.9, *;q=0.1"),
+            Some(Locale::DeDe)
+        );
+        assert_eq!(Locale::from_accept_language("ja"), None);
+        assert_eq!(Locale::resolve(Some("fr-FR"), Locale::EnUs), Locale::FrFr);
+        assert_eq!(Locale::resolve(Some("xx"), Locale::EsEs), Locale::EsEs);
+    }
+
+    #[test]
+    fn test_catalogs_cover_every_english_key() {
+        let english = &CATALOGS[&Locale::EnUs];
+        for locale in [Locale::DeDe, Locale::FrFr, Locale::EsEs] {
+            for key in english.keys() {
+                assert!(CATALOGS[&locale].contains_key(key), "{} is missing {}", locale, key);
+            }
+        }
+    }
+
+    #[test]
+    fn test_translation_with_fallback() {
+        assert_eq!(
+            t(Locale::DeDe, "notifications.shipment_update", &[("shipment_id", "S1"), ("update", "zugestellt")]),
+            "Aktualisierung zur Sendung S1: zugestellt"
+        );
+        // en-GB only overrides a few keys and falls back to en-US for the rest
+        assert_eq!(t(Locale::EnGb, "invoice.title", &[]), "Invoice");
+        assert_eq!(t(Locale::EnGb, "invoice.shipping", &[]), "Delivery");
+        assert_eq!(t(Locale::FrFr, "no.such.key", &[]), "no.such.key");
+    }
+
+    #[test]
+    fn test_date_formatting() {
+        let date = NaiveDate::from_ymd_opt(2024, 3, 9).unwrap();
+        assert_eq!(format_date(Locale::EnUs, date), "03/09/2024");
+        assert_eq!(format_date(Locale::EnGb, date), "09/03/2024");
+        assert_eq!(format_date(Locale::DeDe, date), "09.03.2024");
+    }
+
+    #[test]
+    fn test_number_and_currency_formatting() {
+        assert_eq!(format_number(Locale::EnUs, dec!(1234567.891), 2), "1,234,567.89");
+        assert_eq!(format_number(Locale::DeDe, dec!(1234567.891), 2), "1.234.567,89");
+        assert_eq!(format_number(Locale::FrFr, dec!(1234.5), 2), "1\u{202f}234,50");
+        assert_eq!(format_number(Locale::EnUs, dec!(999), 0), "999");
+        assert_eq!(format_currency(Locale::EnUs, dec!(-1234.5), "USD"), "-$1,234.50");
+        assert_eq!(format_currency(Locale::DeDe, dec!(1234.5), "EUR"), "1.234,50\u{a0}€");
+        assert_eq!(format_currency(Locale::EsEs, dec!(10), "CHF"), "10,00\u{a0}CHF");
+    }
+}
@@ -0,0 +1,584 @@
+// inbound_email/mod.rs
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{Duration, Utc};
+use lazy_static::lazy_static;
+use mailparse::{MailHeaderMap, ParsedMail};
+use prometheus::IntCounterVec;
+use rust_decimal::Decimal;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, Set,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::models::inbound_email::{self, Entity as InboundEmail, InboundEmailStatus};
+use crate::models::order::{self, Entity as Order};
+use crate::models::order_fingerprint::{self, Entity as OrderFingerprint};
+use crate::models::return_entity::{self, ActionNeeded, Condition, Entity as Return, ReturnStatus};
+use crate::utils::pagination::PaginationParams;
+
+lazy_static! {
+    static ref INBOUND_EMAILS: IntCounterVec =
+        IntCounterVec::new(
+            "inbound_emails_total",
+            "Inbound return mailbox messages by outcome",
+            &["status"]
+        ).expect("metric can be created");
+}
+
+/// Most candidate order numbers looked up per message.
+const MAX_ORDER_CANDIDATES: usize = 10;
+
+const RETURN_KEYWORDS: &[&str] = &["return", "refund", "rma", "send back", "exchange", "wrong size", "wrong item"];
+const WARRANTY_KEYWORDS: &[&str] = &["warranty", "defective", "stopped working", "broken", "repair", "faulty"];
+
+/// Inbound email settings, loaded from the `inbound_email` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct InboundEmailConfig {
+    /// Accepts messages on the inbound routes (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Environment variable holding the token providers must pass as `?token=`; SendGrid
+    /// Inbound Parse and SNS cannot sign requests themselves.
+    #[serde(default = "default_token_env")]
+    pub token_env: String,
+
+    /// SNS topic SES publishes received mail to; other topics are refused.
+    #[serde(default)]
+    pub sns_topic_arn: Option<String>,
+
+    /// How far back the sender's most recent order is used when the message names none
+    /// (default: 90 days).
+    #[serde(default = "default_order_lookback_days")]
+    pub order_lookback_days: i64,
+}
+
+fn default_token_env() -> String {
+    "INBOUND_EMAIL_TOKEN".to_string()
+}
+
+fn default_order_lookback_days() -> i64 {
+    90
+}
+
+impl Default for InboundEmailConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            token_env: default_token_env(),
+            sns_topic_arn: None,
+            order_lookback_days: default_order_lookback_days(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum InboundEmailError {
+    #[error("Inbound email token rejected")]
+    Unauthorized,
+
+    #[error("Inbound email is not enabled")]
+    Disabled,
+
+    #[error("Invalid inbound message: {0}")]
+    Invalid(String),
+
+    #[error("Inbound email not found: {0}")]
+    NotFound(Uuid),
+
+    #[error("Inbound email is misconfigured: {0}")]
+    Misconfigured(String),
+
+    #[error("Subscription confirmation failed: {0}")]
+    Upstream(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for InboundEmailError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            InboundEmailError::Unauthorized => (StatusCode::UNAUTHORIZED, "invalid_token"),
+            InboundEmailError::Disabled => (StatusCode::NOT_FOUND, "inbound_email_disabled"),
+            InboundEmailError::Invalid(_) => (StatusCode::BAD_REQUEST, "invalid_message"),
+            InboundEmailError::NotFound(_) => (StatusCode::NOT_FOUND, "inbound_email_not_found"),
+            InboundEmailError::Misconfigured(_) => (StatusCode::SERVICE_UNAVAILABLE, "inbound_email_misconfigured"),
+            InboundEmailError::Upstream(_) => (StatusCode::BAD_GATEWAY, "sns_confirmation_failed"),
+            InboundEmailError::Database(e) => {
+                error!("Inbound email query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "inbound_email_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// What a customer is asking for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequestKind {
+    Return,
+    Warranty,
+}
+
+impl RequestKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RequestKind::Return => "return",
+            RequestKind::Warranty => "warranty",
+        }
+    }
+}
+
+/// The parts of a message the channel needs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub message_id: String,
+    pub from: String,
+    pub subject: String,
+    pub text: String,
+}
+
+fn text_body(mail: &ParsedMail) -> Option<String> {
+    if mail.subparts.is_empty() {
+        let mimetype = mail.ctype.mimetype.to_ascii_lowercase();
+        return (mimetype == "text/plain" || mimetype.is_empty()).then(|| mail.get_body().ok()).flatten();
+    }
+    mail.subparts.iter().find_map(text_body)
+}
+
+/// Parses a raw MIME message.
+pub fn parse_message(raw: &[u8]) -> Result<Message, InboundEmailError> {
+    let mail = mailparse::parse_mail(raw).map_err(|e| InboundEmailError::Invalid(e.to_string()))?;
+    let header = |name: &str| mail.headers.get_first_value(name).unwrap_or_default();
+    let from = mailparse::addrparse(&header("From"))
+        .ok()
+        .and_then(|list| list.extract_single_info())
+        .map(|info| info.addr.to_ascii_lowercase())
+        .ok_or_else(|| InboundEmailError::Invalid("no sender address".to_string()))?;
+    let message_id = match header("Message-ID").trim() {
+        "" => format!("<{}@inbound>", Uuid::new_v4()),
+        id => id.to_string(),
+    };
+    Ok(Message { message_id, from, subject: header("Subject"), text: text_body(&mail).unwrap_or_default() })
+}
+
+/// Quoted replies are left out, so an old thread does not change what is asked.
+fn new_text(text: &str) -> String {
+    text.lines()
+        .take_while(|line| !(line.starts_with("On ") && line.trim_end().ends_with("wrote:")))
+        .filter(|line| !line.trim_start().starts_with('>'))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Whether `phrase` starts a word in `content`, so `rma` does not match "information"
+/// while `return` still matches "returned".
+fn mentions(content: &str, phrase: &str) -> bool {
+    content.match_indices(phrase).any(|(i, _)| {
+        content[..i].chars().next_back().map_or(true, |c| !c.is_alphanumeric())
+    })
+}
+
+fn mentions_any(content: &str, phrases: &[&str]) -> bool {
+    phrases.iter().any(|phrase| mentions(content, phrase))
+}
+
+/// Recognises a return or warranty request; warranty wins when both apply, since a
+/// defect is the more specific claim.
+pub fn classify(subject: &str, text: &str) -> Option<RequestKind> {
+    let content = format!("{}\n{}", subject, new_text(text)).to_lowercase();
+    if mentions_any(&content, WARRANTY_KEYWORDS) {
+        Some(RequestKind::Warranty)
+    } else if mentions_any(&content, RETURN_KEYWORDS) {
+        Some(RequestKind::Return)
+    } else {
+        None
+    }
+}
+
+/// The condition the customer describes, if any.
+pub fn reported_condition(subject: &str, text: &str) -> Option<Condition> {
+    let content = format!("{}\n{}", subject, new_text(text)).to_lowercase();
+    if mentions_any(&content, &["defective", "stopped working", "faulty", "doesn't work", "does not work"]) {
+        Some(Condition::Defective)
+    } else if mentions_any(&content, &["damaged", "broken", "cracked", "dented"]) {
+        Some(Condition::Damaged)
+    } else if mentions_any(&content, &["unopened", "never opened", "unused", "still sealed"]) {
+        Some(Condition::New)
+    } else {
+        None
+    }
+}
+
+/// Words that could be order numbers: they contain a digit and are 4 to 32 letters,
+/// digits or dashes, e.g. `ORD-10042` or `#10042`.
+pub fn order_number_candidates(subject: &str, text: &str) -> Vec<String> {
+    let content = format!("{}\n{}", subject, new_text(text));
+    let mut seen = BTreeSet::new();
+    content
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '#'))
+        .map(|word| word.trim_matches(|c| c == '#' || c == '-'))
+        .filter(|word| (4..=32).contains(&word.len()) && word.chars().any(|c| c.is_ascii_digit()))
+        .filter(|word| seen.insert(word.to_ascii_uppercase()))
+        .map(str::to_string)
+        .take(MAX_ORDER_CANDIDATES)
+        .collect()
+}
+
+fn summary(message: &Message) -> String {
+    let text = format!("{}\n\n{}", message.subject, new_text(&message.text).trim());
+    text.chars().take(1000).collect()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Turns return and warranty emails into draft returns for customer service.
+pub struct InboundEmailService {
+    db: Arc<DatabaseConnection>,
+    config: InboundEmailConfig,
+    token: Option<String>,
+    client: reqwest::Client,
+}
+
+impl InboundEmailService {
+    pub fn new(db: Arc<DatabaseConnection>, config: InboundEmailConfig) -> Result<Self, InboundEmailError> {
+        let token = if config.enabled {
+            Some(
+                std::env::var(&config.token_env)
+                    .map_err(|_| InboundEmailError::Misconfigured(format!("{} is not set", config.token_env)))?,
+            )
+        } else {
+            None
+        };
+        Ok(Self { db, config, token, client: reqwest::Client::new() })
+    }
+
+    /// Checks the `?token=` a provider was configured with.
+    pub fn authorize(&self, token: Option<&str>) -> Result<(), InboundEmailError> {
+        let expected = self.token.as_deref().ok_or(InboundEmailError::Disabled)?;
+        match token {
+            Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => Ok(()),
+            _ => Err(InboundEmailError::Unauthorized),
+        }
+    }
+
+    /// The sender's order the message is about: one it names, else their latest recent one.
+    /// Orders of other customers are never matched, whatever number the message quotes.
+    async fn match_order(&self, message: &Message) -> Result<Option<order::Model>, DbErr> {
+        let candidates = order_number_candidates(&message.subject, &message.text);
+        if !candidates.is_empty() {
+            let named = Order::find()
+                .filter(order::Column::OrderNumber.is_in(candidates))
+                .all(self.db.as_ref())
+                .await?
+                .into_iter()
+                .find(|order| order.customer_email.eq_ignore_ascii_case(&message.from));
+            if named.is_some() {
+                return Ok(named);
+            }
+        }
+        Order::find()
+            .filter(order::Column::CustomerEmail.eq(message.from.clone()))
+            .filter(order::Column::CreatedDate.gte(Utc::now() - Duration::days(self.config.order_lookback_days)))
+            .order_by_desc(order::Column::CreatedDate)
+            .one(self.db.as_ref())
+            .await
+    }
+
+    /// The customer who placed `order`, else the one on the sender's earlier returns.
+    async fn customer_id(&self, order: &order::Model, email: &str) -> Result<Option<Uuid>, DbErr> {
+        if let Some(fingerprint) = OrderFingerprint::find()
+            .filter(order_fingerprint::Column::OrderId.eq(order.id))
+            .one(self.db.as_ref())
+            .await?
+        {
+            return Ok(Some(fingerprint.customer_id));
+        }
+        Ok(Return::find()
+            .filter(return_entity::Column::CustomerEmail.eq(email))
+            .order_by_desc(return_entity::Column::CreatedDate)
+            .one(self.db.as_ref())
+            .await?
+            .map(|ret| ret.customer_id))
+    }
+
+    async fn draft_return(
+        &self,
+        message: &Message,
+        kind: RequestKind,
+        order: &order::Model,
+        customer_id: Uuid,
+    ) -> Result<return_entity::Model, DbErr> {
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+        let reported = reported_condition(&message.subject, &message.text);
+        return_entity::ActiveModel {
+            id: Set(id),
+            created_date: Set(now),
+            amount: Set(Decimal::ZERO),
+            action_needed: Set(ActionNeeded::Inspection),
+            // Replaced on inspection
+            condition: Set(reported.clone().unwrap_or(Condition::New)),
+            customer_email: Set(message.from.clone()),
+            customer_id: Set(customer_id),
+            description: Set(Some(summary(message))),
+            entered_by: Set(None),
+            flat_rate_shipping: Set(Decimal::ZERO),
+            order_date: Set(order.created_date),
+            order_id: Set(order.id),
+            reason_category: Set(Some(kind.as_str().to_string())),
+            reported_condition: Set(reported),
+            requested_date: Set(now),
+            risk_score: Set(None),
+            risk_signals: Set(None),
+            rma: Set(format!("EM-{}", &id.simple().to_string()[..8].to_ascii_uppercase())),
+            serial_number: Set(None),
+            shipped_date: Set(None),
+            status: Set(ReturnStatus::Draft),
+            tax_refunded: Set(Decimal::ZERO),
+            total_refunded: Set(Decimal::ZERO),
+            tracking_number: Set(None),
+        }
+        .insert(self.db.as_ref())
+        .await
+    }
+
+    /// Processes a raw message. A message seen before returns its earlier record. A sender
+    /// the provider could not authenticate is recorded as rejected without matching it to
+    /// an order, since the `From` address would be the only proof of who is asking.
+    pub async fn receive(
+        &self,
+        channel: &str,
+        raw: &[u8],
+        authenticated: bool,
+    ) -> Result<inbound_email::Model, InboundEmailError> {
+        let message = parse_message(raw)?;
+        if let Some(existing) = InboundEmail::find()
+            .filter(inbound_email::Column::MessageId.eq(message.message_id.clone()))
+            .one(self.db.as_ref())
+            .await?
+        {
+            return Ok(existing);
+        }
+        let kind = classify(&message.subject, &message.text);
+        let order = match kind {
+            Some(_) if authenticated => self.match_order(&message).await?,
+            _ => None,
+        };
+        let customer_id = match &order {
+            Some(order) => self.customer_id(order, &message.from).await?,
+            None => None,
+        };
+        let drafted = match (kind, &order, customer_id) {
+            (Some(kind), Some(order), Some(customer_id)) => {
+                Some(self.draft_return(&message, kind, order, customer_id).await?)
+            }
+            _ => None,
+        };
+        let status = match (kind, &drafted) {
+            _ if !authenticated => InboundEmailStatus::Rejected,
+            (None, _) => InboundEmailStatus::Ignored,
+            (Some(_), None) => InboundEmailStatus::Unmatched,
+            (Some(_), Some(_)) => InboundEmailStatus::Drafted,
+        };
+        let record = inbound_email::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            message_id: Set(message.message_id.clone()),
+            channel: Set(channel.to_string()),
+            from_address: Set(message.from.clone()),
+            subject: Set(message.subject.clone()),
+            kind: Set(kind.map(|k| k.as_str().to_string())),
+            status: Set(status),
+            order_id: Set(order.map(|o| o.id)),
+            return_id: Set(drafted.as_ref().map(|r| r.id)),
+            raw: Set(String::from_utf8_lossy(raw).into_owned()),
+            received_at: Set(Utc::now()),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+        INBOUND_EMAILS.with_label_values(&[&format!("{:?}", status).to_lowercase()]).inc();
+        match &drafted {
+            Some(ret) => info!(email = %record.id, return_id = %ret.id, rma = %ret.rma, "Draft return created from email"),
+            None => info!(email = %record.id, ?status, "Inbound email not drafted"),
+        }
+        Ok(record)
+    }
+
+    /// Handles an SNS delivery for SES receipt rules: confirms the subscription, or
+    /// processes the received message. The SES action must include the message content.
+    pub async fn receive_sns(&self, body: &Value) -> Result<Option<inbound_email::Model>, InboundEmailError> {
+        let topic = body["TopicArn"].as_str().unwrap_or_default();
+        match &self.config.sns_topic_arn {
+            Some(expected) if expected == topic => {}
+            Some(_) => return Err(InboundEmailError::Unauthorized),
+            None => return Err(InboundEmailError::Misconfigured("sns_topic_arn is not set".to_string())),
+        }
+        match body["Type"].as_str() {
+            Some("SubscriptionConfirmation") => {
+                let subscribe = body["SubscribeURL"].as_str().unwrap_or_default();
+                let url = url::Url::parse(subscribe).map_err(|e| InboundEmailError::Invalid(e.to_string()))?;
+                if url.scheme() != "https" || !url.host_str().map_or(false, |h| h.ends_with(".amazonaws.com")) {
+                    return Err(InboundEmailError::Invalid("SubscribeURL is not an AWS endpoint".to_string()));
+                }
+                let response =
+                    self.client.get(url).send().await.map_err(|e| InboundEmailError::Upstream(e.to_string()))?;
+                if !response.status().is_success() {
+                    return Err(InboundEmailError::Upstream(response.status().to_string()));
+                }
+                info!(topic, "Confirmed SNS subscription for inbound email");
+                Ok(None)
+            }
+            Some("Notification") => {
+                let notification: Value = serde_json::from_str(body["Message"].as_str().unwrap_or_default())
+                    .map_err(|e| InboundEmailError::Invalid(format!("SNS message: {}", e)))?;
+                let content = notification["content"]
+                    .as_str()
+                    .ok_or_else(|| InboundEmailError::Invalid("SES notification has no content".to_string()))?;
+                let raw = if notification["receipt"]["action"]["encoding"].as_str() == Some("BASE64") {
+                    STANDARD.decode(content).map_err(|e| InboundEmailError::Invalid(e.to_string()))?
+                } else {
+                    content.as_bytes().to_vec()
+                };
+                let authenticated = ses_verdicts_pass(&notification["receipt"]);
+                if !authenticated {
+                    warn!(
+                        spf = %notification["receipt"]["spfVerdict"]["status"],
+                        dkim = %notification["receipt"]["dkimVerdict"]["status"],
+                        "SES could not authenticate the sender of an inbound email"
+                    );
+                }
+                Ok(Some(self.receive("ses", &raw, authenticated).await?))
+            }
+            other => {
+                warn!(?other, "Ignoring SNS message type");
+                Ok(None)
+            }
+        }
+    }
+
+    pub async fn list(
+        &self,
+        status: Option<InboundEmailStatus>,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<inbound_email::Model>, u64), InboundEmailError> {
+        let mut query = InboundEmail::find().order_by_desc(inbound_email::Column::ReceivedAt);
+        if let Some(status) = status {
+            query = query.filter(inbound_email::Column::Status.eq(status));
+        }
+        let paginator = query.paginate(self.db.as_ref(), pagination.limit());
+        let total = paginator.num_items().await?;
+        let items = paginator.fetch_page(pagination.page_index()).await?;
+        Ok((items, total))
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<inbound_email::Model, InboundEmailError> {
+        InboundEmail::find_by_id(id).one(self.db.as_ref()).await?.ok_or(InboundEmailError::NotFound(id))
+    }
+
+    /// Turns the draft return an email produced into a request, once customer service has
+    /// reviewed it, so it enters the normal return flow.
+    pub async fn promote(&self, id: Uuid, actor: &str) -> Result<return_entity::Model, InboundEmailError> {
+        let email = self.get(id).await?;
+        let return_id = email
+            .return_id
+            .ok_or_else(|| InboundEmailError::Invalid("the email produced no draft return".to_string()))?;
+        let promoted = Return::update_many()
+            .col_expr(return_entity::Column::Status, Expr::value(ReturnStatus::Requested))
+            .col_expr(return_entity::Column::EnteredBy, Expr::value(Some(actor.to_string())))
+            .filter(return_entity::Column::Id.eq(return_id))
+            .filter(return_entity::Column::Status.eq(ReturnStatus::Draft))
+            .exec(self.db.as_ref())
+            .await?;
+        if promoted.rows_affected == 0 {
+            return Err(InboundEmailError::Invalid(format!("return {} is no longer a draft", return_id)));
+        }
+        info!(email = %id, %return_id, actor, "Draft return from email promoted to requested");
+        Return::find_by_id(return_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or(InboundEmailError::NotFound(return_id))
+    }
+}
+
+/// Whether SES passed the sender's SPF and DKIM checks, from the `receipt` of a
+/// notification. Either one missing or not `PASS` fails.
+pub fn ses_verdicts_pass(receipt: &Value) -> bool {
+    ["spfVerdict", "dkimVerdict"]
+        .iter()
+        .all(|verdict| receipt[verdict]["status"].as_str() == Some("PASS"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RAW: &str = "From: Ada Lovelace <Ada@Example.com>\r\n\
+Message-ID: <abc@mail.example.com>\r\n\
+Subject: Return for order ORD-10042\r\n\
+Content-Type: multipart/alternative; boundary=b\r\n\
+\r\n\
+--b\r\n\
+Content-Type: text/plain\r\n\
+\r\n\
+The kettle arrived damaged, I'd like to send it back.\r\n\
+--b\r\n\
+Content-Type: text/html\r\n\
+\r\n\
+<p>The kettle arrived damaged</p>\r\n\
+--b--\r\n";
+
+    #[test]
+    fn test_parse_message() {
+        let message = parse_message(RAW.as_bytes()).unwrap();
+        assert_eq!(message.from, "ada@example.com");
+        assert_eq!(message.message_id, "<abc@mail.example.com>");
+        assert_eq!(message.subject, "Return for order ORD-10042");
+        assert!(message.text.starts_with("The kettle arrived damaged"));
+        assert!(parse_message(b"Subject: no sender\r\n\r\nhi").is_err());
+    }
+
+    #[test]
+    fn test_classify_ignores_quoted_replies() {
+        assert_eq!(classify("Return for order ORD-10042", ""), Some(RequestKind::Return));
+        assert_eq!(classify("Help", "My blender stopped working after a week"), Some(RequestKind::Warranty));
+        assert_eq!(classify("Thanks!", "Got it.\n> Your refund was issued"), None);
+        assert_eq!(classify("Question", "Great.\nOn Mon, 1 Jan, Support wrote:\nrefund issued"), None);
+        assert_eq!(classify("Question", "More information about the format, please"), None);
+        assert_eq!(reported_condition("", "arrived damaged"), Some(Condition::Damaged));
+        assert_eq!(reported_condition("", "it is defective"), Some(Condition::Defective));
+    }
+
+    #[test]
+    fn test_ses_verdicts_must_both_pass() {
+        let receipt = |spf: &str, dkim: &str| json!({ "spfVerdict": { "status": spf }, "dkimVerdict": { "status": dkim } });
+        assert!(ses_verdicts_pass(&receipt("PASS", "PASS")));
+        assert!(!ses_verdicts_pass(&receipt("PASS", "FAIL")));
+        assert!(!ses_verdicts_pass(&receipt("GRAY", "PASS")));
+        assert!(!ses_verdicts_pass(&json!({})));
+    }
+
+    #[test]
+    fn test_order_number_candidates() {
+        assert_eq!(
+            order_number_candidates("Return for order #10042", "Also ORD-77 and ORD-10042, order 10042 again."),
+            vec!["10042", "ORD-77", "ORD-10042"]
+        );
+        assert!(order_number_candidates("Hello", "no numbers here").is_empty());
+    }
+}
@@ -0,0 +1,548 @@
+// ingest/mod.rs
+
+use async_trait::async_trait;
+use axum::{
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use lazy_static::lazy_static;
+use prometheus::IntCounterVec;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait, QueryFilter, QueryOrder,
+    Set,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::config::secrets::{SecretStore, INGEST_PREFIX};
+use crate::disputes::{verify_stripe_signature, DisputeError};
+use crate::events::{Event, EventSender};
+use crate::inventory_levels::{InventoryLevelService, LevelBatch};
+use crate::models::ingest_quarantine::{self, Entity as IngestQuarantine, QuarantineStatus};
+use crate::utils::pagination::PaginationParams;
+use crate::webhooks::transform::{self, JsonPath};
+
+pub mod schema;
+
+type HmacSha256 = Hmac<Sha256>;
+
+lazy_static! {
+    static ref INGESTED: IntCounterVec =
+        IntCounterVec::new(
+            "ingest_payloads_total",
+            "Inbound webhook payloads by source and outcome",
+            &["source", "outcome"]
+        ).expect("metric can be created");
+}
+
+/// Headers never stored with a quarantined payload.
+const CREDENTIAL_HEADERS: &[&str] = &["authorization", "cookie", "x-api-key"];
+
+/// How a source proves its payloads are genuine. Every source must use one; the endpoint
+/// is reachable without credentials.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SignatureScheme {
+    /// HMAC-SHA256 of the raw body in `header`, hex or base64 encoded, optionally after a
+    /// prefix such as `sha256=`.
+    HmacSha256 {
+        header: String,
+        #[serde(default)]
+        encoding: SignatureEncoding,
+        #[serde(default)]
+        prefix: Option<String>,
+    },
+
+    /// Stripe-style `t=<unix>,v1=<hex>` signatures over `<t>.<body>`.
+    Timestamped {
+        header: String,
+        #[serde(default = "default_tolerance_secs")]
+        tolerance_secs: i64,
+    },
+
+    /// A shared token sent as is in `header`.
+    Token { header: String },
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+fn default_tolerance_secs() -> i64 {
+    300
+}
+
+/// A condition on the payload, e.g. `{ path: "$.type", equals: "stock.updated" }`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Condition {
+    pub path: String,
+    pub equals: Value,
+}
+
+/// Turns matching payloads into a command. `params` is a template whose `{{ path }}`
+/// placeholders are filled from the payload, as in outbound webhook templates.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Mapping {
+    /// All must hold; a mapping without conditions matches every payload.
+    #[serde(default)]
+    pub when: Vec<Condition>,
+
+    /// Registered command name, e.g. `emit_event` or `inventory.set_levels`.
+    pub command: String,
+
+    pub params: Value,
+}
+
+/// One partner sending webhooks to `/api/v1/ingest/{name}`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SourceConfig {
+    pub name: String,
+
+    pub signature: SignatureScheme,
+
+    /// Environment variable holding the source's secret. An `ingest_<name>` secret in
+    /// the secret store takes precedence, and may stand in for it entirely.
+    pub secret_env: String,
+
+    /// Event types the source may publish through `emit_event`, e.g. `OrderUpdated`.
+    #[serde(default)]
+    pub emit_events: Vec<String>,
+
+    /// JSON Schema payloads must satisfy.
+    #[serde(default)]
+    pub schema: Option<Value>,
+
+    /// Tried in order; the first match is applied.
+    pub mappings: Vec<Mapping>,
+}
+
+/// Inbound webhook settings, loaded from the `ingest` section of the config.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct IngestConfig {
+    #[serde(default)]
+    pub sources: Vec<SourceConfig>,
+}
+
+#[derive(Error, Debug)]
+pub enum IngestError {
+    #[error("Unknown ingest source: {0}")]
+    UnknownSource(String),
+
+    #[error("Webhook signature rejected: {0}")]
+    InvalidSignature(String),
+
+    #[error("Ingest source misconfigured: {0}")]
+    Misconfigured(String),
+
+    #[error("Quarantined payload not found: {0}")]
+    NotFound(Uuid),
+
+    #[error("Quarantined payload {0} was already resolved")]
+    AlreadyResolved(Uuid),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for IngestError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            IngestError::UnknownSource(_) => (StatusCode::NOT_FOUND, "unknown_source"),
+            IngestError::InvalidSignature(_) => (StatusCode::UNAUTHORIZED, "invalid_signature"),
+            IngestError::Misconfigured(_) => (StatusCode::SERVICE_UNAVAILABLE, "ingest_misconfigured"),
+            IngestError::NotFound(_) => (StatusCode::NOT_FOUND, "quarantine_not_found"),
+            IngestError::AlreadyResolved(_) => (StatusCode::CONFLICT, "already_resolved"),
+            IngestError::Database(e) => {
+                error!("Ingest query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "ingest_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// An internal operation inbound payloads can be mapped to. `source` is the verified
+/// sender, for commands that limit what each source may do.
+#[async_trait]
+pub trait IngestCommand: Send + Sync {
+    async fn execute(&self, source: &SourceConfig, params: Value) -> Result<Value, String>;
+}
+
+/// Name of a serialized event: the variant tag of `{ "OrderUpdated": "<uuid>" }`, or
+/// the string of a unit variant.
+pub fn event_type(params: &Value) -> Option<&str> {
+    match params {
+        Value::String(name) => Some(name),
+        Value::Object(map) if map.len() == 1 => map.keys().next().map(String::as_str),
+        _ => None,
+    }
+}
+
+/// Publishes an internal event; `params` is the event in its serialized form, e.g.
+/// `{ "OrderUpdated": "<uuid>" }`. Only types in the source's `emit_events` are sent.
+pub struct EmitEvent(pub EventSender);
+
+#[async_trait]
+impl IngestCommand for EmitEvent {
+    async fn execute(&self, source: &SourceConfig, params: Value) -> Result<Value, String> {
+        let name = event_type(&params).ok_or_else(|| "not an event".to_string())?;
+        if !source.emit_events.iter().any(|allowed| allowed == name) {
+            return Err(format!("source {} may not emit {}", source.name, name));
+        }
+        let event: Event = serde_json::from_value(params).map_err(|e| format!("not an event: {}", e))?;
+        let _ = self.0.send(event);
+        Ok(json!({ "emitted": true }))
+    }
+}
+
+/// Sets stock levels, as `POST /api/v1/inventory/levels:batch` does.
+pub struct SetInventoryLevels(pub Arc<InventoryLevelService>);
+
+#[async_trait]
+impl IngestCommand for SetInventoryLevels {
+    async fn execute(&self, _source: &SourceConfig, params: Value) -> Result<Value, String> {
+        let batch: LevelBatch = serde_json::from_value(params).map_err(|e| format!("not a level batch: {}", e))?;
+        let summary = self.0.upsert_batch(batch).await.map_err(|e| e.to_string())?;
+        serde_json::to_value(summary).map_err(|e| e.to_string())
+    }
+}
+
+/// What became of an inbound payload.
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum Outcome {
+    Applied { command: String, result: Value },
+    Quarantined { quarantine_id: Uuid, reason: String },
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+fn header<'a>(headers: &'a HeaderMap, name: &str) -> Result<&'a str, IngestError> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| IngestError::InvalidSignature(format!("missing {} header", name)))
+}
+
+/// Name of a source's secret in the secret store.
+fn secret_name(source: &str) -> String {
+    format!("{}{}", INGEST_PREFIX, source)
+}
+
+/// Verifies a payload under the source's scheme.
+pub fn verify(scheme: &SignatureScheme, secret: &str, headers: &HeaderMap, body: &[u8]) -> Result<(), IngestError> {
+    match scheme {
+        SignatureScheme::HmacSha256 { header: name, encoding, prefix } => {
+            let value = header(headers, name)?;
+            let value = match prefix {
+                Some(prefix) => value.strip_prefix(prefix.as_str()).unwrap_or(value),
+                None => value,
+            };
+            let signature = match encoding {
+                SignatureEncoding::Hex => hex::decode(value.trim()).ok(),
+                SignatureEncoding::Base64 => STANDARD.decode(value.trim()).ok(),
+            }
+            .ok_or_else(|| IngestError::InvalidSignature("malformed signature".to_string()))?;
+            let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+            mac.update(body);
+            mac.verify_slice(&signature).map_err(|_| IngestError::InvalidSignature("signature mismatch".to_string()))
+        }
+        SignatureScheme::Timestamped { header: name, tolerance_secs } => {
+            let signature = header(headers, name)?;
+            verify_stripe_signature(body, signature, secret, Utc::now(), *tolerance_secs).map_err(|e| match e {
+                DisputeError::InvalidSignature(reason) => IngestError::InvalidSignature(reason),
+                other => IngestError::InvalidSignature(other.to_string()),
+            })
+        }
+        SignatureScheme::Token { header: name } => {
+            if constant_time_eq(header(headers, name)?.as_bytes(), secret.as_bytes()) {
+                Ok(())
+            } else {
+                Err(IngestError::InvalidSignature("token mismatch".to_string()))
+            }
+        }
+    }
+}
+
+/// The first mapping whose conditions all hold for the payload.
+pub fn select_mapping<'a>(mappings: &'a [Mapping], payload: &Value) -> Option<&'a Mapping> {
+    mappings.iter().find(|mapping| {
+        mapping.when.iter().all(|condition| {
+            JsonPath::parse(&condition.path).map_or(false, |path| path.get(payload) == condition.equals)
+        })
+    })
+}
+
+struct Source {
+    config: SourceConfig,
+    /// From `secret_env`; empty when only the secret store has the source's secret.
+    secret: String,
+}
+
+/// Verifies, validates and maps inbound webhooks, quarantining what cannot be applied.
+pub struct IngestService {
+    db: Arc<DatabaseConnection>,
+    sources: HashMap<String, Source>,
+    commands: HashMap<String, Arc<dyn IngestCommand>>,
+    secrets: SecretStore,
+}
+
+impl IngestService {
+    pub fn new(db: Arc<DatabaseConnection>, config: IngestConfig, secrets: SecretStore) -> Result<Self, IngestError> {
+        let mut sources = HashMap::new();
+        for source in config.sources {
+            let secret = std::env::var(&source.secret_env).ok().filter(|secret| !secret.is_empty());
+            let secret = match secret {
+                Some(secret) => secret,
+                None if secrets.get(&secret_name(&source.name)).is_some() => String::new(),
+                None => return Err(IngestError::Misconfigured(format!("{} is not set", source.secret_env))),
+            };
+            sources.insert(source.name.clone(), Source { config: source, secret });
+        }
+        Ok(Self { db, sources, commands: HashMap::new(), secrets })
+    }
+
+    pub fn register(&mut self, name: &str, command: Arc<dyn IngestCommand>) {
+        self.commands.insert(name.to_string(), command);
+    }
+
+    /// Fails on mappings naming unregistered commands or paths that do not parse, so a
+    /// bad config is caught at startup rather than by quarantining every payload.
+    pub fn check(&self) -> Result<(), IngestError> {
+        for source in self.sources.values() {
+            for mapping in &source.config.mappings {
+                if !self.commands.contains_key(&mapping.command) {
+                    return Err(IngestError::Misconfigured(format!(
+                        "source {} maps to unknown command {}",
+                        source.config.name, mapping.command
+                    )));
+                }
+                if mapping.command == "emit_event" && source.config.emit_events.is_empty() {
+                    return Err(IngestError::Misconfigured(format!(
+                        "source {} maps to emit_event without listing emit_events",
+                        source.config.name
+                    )));
+                }
+                let paths = mapping
+                    .when
+                    .iter()
+                    .map(|c| c.path.clone())
+                    .chain(transform::template_paths(&mapping.params));
+                for path in paths {
+                    JsonPath::parse(&path).map_err(|e| {
+                        IngestError::Misconfigured(format!("source {}: {}", source.config.name, e))
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Handles a payload received for `source`. Bad signatures are rejected; anything else
+    /// that cannot be applied is quarantined, so the sender does not retry it forever.
+    pub async fn ingest(&self, source: &str, headers: &HeaderMap, body: &[u8]) -> Result<Outcome, IngestError> {
+        let entry = self.sources.get(source).ok_or_else(|| IngestError::UnknownSource(source.to_string()))?;
+        // Read on every payload, so a rotated secret applies to the next one
+        let secret = self.secrets.get_or(&secret_name(source), &entry.secret);
+        if let Err(e) = verify(&entry.config.signature, &secret, headers, body) {
+            INGESTED.with_label_values(&[source, "rejected"]).inc();
+            warn!(source, "Inbound webhook rejected: {}", e);
+            return Err(e);
+        }
+        match self.apply(&entry.config, body).await {
+            Ok((command, result)) => {
+                INGESTED.with_label_values(&[source, "applied"]).inc();
+                Ok(Outcome::Applied { command, result })
+            }
+            Err(reason) => {
+                INGESTED.with_label_values(&[source, "quarantined"]).inc();
+                let quarantine_id = self.quarantine(source, headers, body, &reason).await?;
+                warn!(source, %quarantine_id, "Inbound webhook quarantined: {}", reason);
+                Ok(Outcome::Quarantined { quarantine_id, reason })
+            }
+        }
+    }
+
+    /// Parses, validates, maps and runs a payload; the error is the quarantine reason.
+    async fn apply(&self, source: &SourceConfig, body: &[u8]) -> Result<(String, Value), String> {
+        let payload: Value = serde_json::from_slice(body).map_err(|e| format!("invalid JSON: {}", e))?;
+        if let Some(schema) = &source.schema {
+            let errors = schema::validate(schema, &payload);
+            if !errors.is_empty() {
+                return Err(format!("schema validation failed: {}", errors.join("; ")));
+            }
+        }
+        let mapping = select_mapping(&source.mappings, &payload).ok_or_else(|| "no mapping matched".to_string())?;
+        let command = self
+            .commands
+            .get(&mapping.command)
+            .ok_or_else(|| format!("unknown command {}", mapping.command))?;
+        let params = transform::render(&mapping.params, &payload);
+        let result = command.execute(source, params).await.map_err(|e| format!("{} failed: {}", mapping.command, e))?;
+        Ok((mapping.command.clone(), result))
+    }
+
+    async fn quarantine(&self, source: &str, headers: &HeaderMap, body: &[u8], reason: &str) -> Result<Uuid, DbErr> {
+        let headers: serde_json::Map<String, Value> = headers
+            .iter()
+            .filter(|(name, _)| !CREDENTIAL_HEADERS.contains(&name.as_str()))
+            .filter_map(|(name, value)| Some((name.to_string(), json!(value.to_str().ok()?))))
+            .collect();
+        let item = ingest_quarantine::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            source: Set(source.to_string()),
+            status: Set(QuarantineStatus::Quarantined),
+            reason: Set(reason.to_string()),
+            headers: Set(Value::Object(headers)),
+            body: Set(String::from_utf8_lossy(body).into_owned()),
+            attempts: Set(1),
+            received_at: Set(Utc::now()),
+            resolved_at: Set(None),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+        Ok(item.id)
+    }
+
+    pub async fn list_quarantine(
+        &self,
+        source: Option<String>,
+        status: Option<QuarantineStatus>,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<ingest_quarantine::Model>, u64), IngestError> {
+        let mut query = IngestQuarantine::find().order_by_desc(ingest_quarantine::Column::ReceivedAt);
+        if let Some(source) = source {
+            query = query.filter(ingest_quarantine::Column::Source.eq(source));
+        }
+        if let Some(status) = status {
+            query = query.filter(ingest_quarantine::Column::Status.eq(status));
+        }
+        let paginator = query.paginate(self.db.as_ref(), pagination.limit());
+        let total = paginator.num_items().await?;
+        let items = paginator.fetch_page(pagination.page_index()).await?;
+        Ok((items, total))
+    }
+
+    async fn unresolved(&self, id: Uuid) -> Result<ingest_quarantine::Model, IngestError> {
+        let item = IngestQuarantine::find_by_id(id).one(self.db.as_ref()).await?.ok_or(IngestError::NotFound(id))?;
+        if item.status != QuarantineStatus::Quarantined {
+            return Err(IngestError::AlreadyResolved(id));
+        }
+        Ok(item)
+    }
+
+    /// Runs a quarantined payload again, e.g. after its mapping was fixed. Its signature
+    /// was verified on receipt.
+    pub async fn retry(&self, id: Uuid) -> Result<ingest_quarantine::Model, IngestError> {
+        let item = self.unresolved(id).await?;
+        let source = self.sources.get(&item.source).ok_or_else(|| IngestError::UnknownSource(item.source.clone()))?;
+        let result = self.apply(&source.config, item.body.as_bytes()).await;
+        let attempts = item.attempts + 1;
+        let mut active: ingest_quarantine::ActiveModel = item.into();
+        active.attempts = Set(attempts);
+        match result {
+            Ok((command, _)) => {
+                info!(%id, command = %command, "Quarantined payload applied on retry");
+                active.status = Set(QuarantineStatus::Replayed);
+                active.resolved_at = Set(Some(Utc::now()));
+            }
+            Err(reason) => active.reason = Set(reason),
+        }
+        Ok(active.update(self.db.as_ref()).await?)
+    }
+
+    pub async fn discard(&self, id: Uuid) -> Result<ingest_quarantine::Model, IngestError> {
+        let mut active: ingest_quarantine::ActiveModel = self.unresolved(id).await?.into();
+        active.status = Set(QuarantineStatus::Discarded);
+        active.resolved_at = Set(Some(Utc::now()));
+        Ok(active.update(self.db.as_ref()).await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn signed(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-signature", HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_hmac_schemes() {
+        let body = br#"{"type":"stock.updated"}"#;
+        let mut mac = HmacSha256::new_from_slice(b"secret").unwrap();
+        mac.update(body);
+        let digest = mac.finalize().into_bytes();
+
+        let hex_scheme = SignatureScheme::HmacSha256 {
+            header: "x-signature".to_string(),
+            encoding: SignatureEncoding::Hex,
+            prefix: Some("sha256=".to_string()),
+        };
+        let headers = signed(&format!("sha256={}", hex::encode(digest)));
+        assert!(verify(&hex_scheme, "secret", &headers, body).is_ok());
+        assert!(verify(&hex_scheme, "other", &headers, body).is_err());
+        assert!(verify(&hex_scheme, "secret", &HeaderMap::new(), body).is_err());
+
+        let base64_scheme = SignatureScheme::HmacSha256 {
+            header: "x-signature".to_string(),
+            encoding: SignatureEncoding::Base64,
+            prefix: None,
+        };
+        assert!(verify(&base64_scheme, "secret", &signed(&STANDARD.encode(digest)), body).is_ok());
+
+        let token = SignatureScheme::Token { header: "x-signature".to_string() };
+        assert!(verify(&token, "secret", &signed("secret"), body).is_ok());
+        assert!(verify(&token, "secret", &signed("secreT"), body).is_err());
+    }
+
+    #[test]
+    fn test_unsigned_sources_are_refused() {
+        let source = json!({ "name": "partner", "signature": { "type": "none" }, "secret_env": "X", "mappings": [] });
+        assert!(serde_json::from_value::<SourceConfig>(source).is_err());
+    }
+
+    #[test]
+    fn test_event_type_of_serialized_events() {
+        assert_eq!(event_type(&json!({ "OrderUpdated": "8f0e" })), Some("OrderUpdated"));
+        assert_eq!(event_type(&json!("CacheFlushed")), Some("CacheFlushed"));
+        assert_eq!(event_type(&json!({ "a": 1, "b": 2 })), None);
+        assert_eq!(event_type(&json!(7)), None);
+    }
+
+    #[test]
+    fn test_first_matching_mapping_wins() {
+        let mappings: Vec<Mapping> = serde_json::from_value(json!([
+            {
+                "when": [{ "path": "$.type", "equals": "stock.updated" }],
+                "command": "inventory.set_levels",
+                "params": {}
+            },
+            { "command": "emit_event", "params": {} }
+        ]))
+        .unwrap();
+        let stock = json!({ "type": "stock.updated" });
+        assert_eq!(select_mapping(&mappings, &stock).unwrap().command, "inventory.set_levels");
+        assert_eq!(select_mapping(&mappings, &json!({ "type": "other" })).unwrap().command, "emit_event");
+        assert!(select_mapping(&mappings[..1], &json!({})).is_none());
+    }
+}
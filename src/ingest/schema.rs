@@ -0,0 +1,115 @@
+// ingest/schema.rs
+
+use serde_json::{Map, Value};
+
+/// Checks a payload against a JSON Schema. Covers the keywords partner payload schemas
+/// need: `type`, `required`, `properties`, `items`, `enum`, `minLength` and `minimum`;
+/// others are ignored. Returns every violation, each prefixed with its path.
+pub fn validate(schema: &Value, payload: &Value) -> Vec<String> {
+    let mut errors = Vec::new();
+    check(schema, payload, "$", &mut errors);
+    errors
+}
+
+fn type_matches(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn check(schema: &Value, value: &Value, path: &str, errors: &mut Vec<String>) {
+    let Some(schema) = schema.as_object() else { return };
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|t| type_matches(t, value)) {
+            errors.push(format!("{}: expected {}", path, allowed.join(" or ")));
+            return;
+        }
+    }
+    if let Some(Value::Array(options)) = schema.get("enum") {
+        if !options.contains(value) {
+            errors.push(format!("{}: not one of the allowed values", path));
+        }
+    }
+    if let (Some(min), Some(text)) = (schema.get("minLength").and_then(Value::as_u64), value.as_str()) {
+        if (text.chars().count() as u64) < min {
+            errors.push(format!("{}: shorter than {} characters", path, min));
+        }
+    }
+    if let (Some(min), Some(number)) = (schema.get("minimum").and_then(Value::as_f64), value.as_f64()) {
+        if number < min {
+            errors.push(format!("{}: less than {}", path, min));
+        }
+    }
+    if let Value::Object(object) = value {
+        check_object(schema, object, path, errors);
+    }
+    if let (Some(items), Value::Array(values)) = (schema.get("items"), value) {
+        for (i, item) in values.iter().enumerate() {
+            check(items, item, &format!("{}[{}]", path, i), errors);
+        }
+    }
+}
+
+fn check_object(schema: &Map<String, Value>, object: &Map<String, Value>, path: &str, errors: &mut Vec<String>) {
+    if let Some(Value::Array(required)) = schema.get("required") {
+        for key in required.iter().filter_map(Value::as_str) {
+            if !object.contains_key(key) {
+                errors.push(format!("{}.{}: is required", path, key));
+            }
+        }
+    }
+    if let Some(Value::Object(properties)) = schema.get("properties") {
+        for (key, property) in properties {
+            if let Some(child) = object.get(key) {
+                check(property, child, &format!("{}.{}", path, key), errors);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_reports_each_violation_with_its_path() {
+        let schema = json!({
+            "type": "object",
+            "required": ["type", "data"],
+            "properties": {
+                "type": { "type": "string", "enum": ["stock.updated"] },
+                "data": {
+                    "type": "object",
+                    "required": ["sku"],
+                    "properties": { "qty": { "type": "integer", "minimum": 0 } }
+                },
+                "lines": { "type": "array", "items": { "type": "string", "minLength": 1 } }
+            }
+        });
+        assert!(validate(&schema, &json!({ "type": "stock.updated", "data": { "sku": "A", "qty": 3 } })).is_empty());
+        let errors = validate(&schema, &json!({ "type": "other", "data": { "qty": -1 }, "lines": [""] }));
+        assert_eq!(
+            errors,
+            vec![
+                "$.data.sku: is required",
+                "$.data.qty: less than 0",
+                "$.lines[0]: shorter than 1 characters",
+                "$.type: not one of the allowed values",
+            ]
+        );
+        assert_eq!(validate(&schema, &json!([])), vec!["$: expected object"]);
+    }
+}
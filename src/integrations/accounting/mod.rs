@@ -0,0 +1,885 @@
+// integrations/accounting/mod.rs
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use lazy_static::lazy_static;
+use prometheus::IntCounterVec;
+use rust_decimal::Decimal;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, FromQueryResult,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{collections::BTreeMap, sync::Arc};
+use thiserror::Error;
+use tokio::{sync::broadcast, time::MissedTickBehavior};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::db::dialect;
+use crate::events::{Event, EventSender};
+use crate::models::{
+    accounting_export::{self, DocumentKind, Entity as AccountingExport, ExportStatus},
+    return_entity::Entity as Return,
+};
+use crate::shutdown::Worker;
+use crate::utils::pagination::PaginationParams;
+
+pub mod providers;
+
+pub use providers::{LedgerAdapter, PostError};
+
+lazy_static! {
+    static ref EXPORTS_ATTEMPTED: IntCounterVec =
+        IntCounterVec::new(
+            "accounting_exports_attempted_total",
+            "Journal entries sent to the accounting provider, by outcome",
+            &["provider", "outcome"]
+        ).expect("metric can be created");
+}
+
+/// Longest wait between retries of a failing export.
+const MAX_BACKOFF_SECS: i64 = 6 * 60 * 60;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountingProvider {
+    #[serde(rename = "quickbooks")]
+    QuickBooks,
+    #[serde(rename = "netsuite")]
+    NetSuite,
+    Xero,
+}
+
+impl AccountingProvider {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AccountingProvider::QuickBooks => "quickbooks",
+            AccountingProvider::NetSuite => "netsuite",
+            AccountingProvider::Xero => "xero",
+        }
+    }
+
+    /// Production API root. NetSuite hosts are per account.
+    pub fn default_base_url(self, company_id: &str) -> String {
+        match self {
+            AccountingProvider::QuickBooks => "https://quickbooks.api.intuit.com".to_string(),
+            AccountingProvider::NetSuite => {
+                format!("https://{}.suitetalk.api.netsuite.com", company_id.to_lowercase().replace('_', "-"))
+            }
+            AccountingProvider::Xero => "https://api.xero.com".to_string(),
+        }
+    }
+}
+
+/// Ledger account ids (QuickBooks, NetSuite) or codes (Xero) journal lines post to.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AccountMap {
+    #[serde(default = "default_receivable")]
+    pub receivable: String,
+    #[serde(default = "default_revenue")]
+    pub revenue: String,
+    #[serde(default = "default_sales_tax")]
+    pub sales_tax: String,
+    #[serde(default = "default_cash")]
+    pub cash: String,
+    #[serde(default = "default_sales_returns")]
+    pub sales_returns: String,
+    #[serde(default = "default_cogs")]
+    pub cogs: String,
+    #[serde(default = "default_inventory")]
+    pub inventory: String,
+}
+
+fn default_receivable() -> String {
+    "1200".to_string()
+}
+
+fn default_revenue() -> String {
+    "4000".to_string()
+}
+
+fn default_sales_tax() -> String {
+    "2200".to_string()
+}
+
+fn default_cash() -> String {
+    "1000".to_string()
+}
+
+fn default_sales_returns() -> String {
+    "4100".to_string()
+}
+
+fn default_cogs() -> String {
+    "5000".to_string()
+}
+
+fn default_inventory() -> String {
+    "1300".to_string()
+}
+
+impl Default for AccountMap {
+    fn default() -> Self {
+        Self {
+            receivable: default_receivable(),
+            revenue: default_revenue(),
+            sales_tax: default_sales_tax(),
+            cash: default_cash(),
+            sales_returns: default_sales_returns(),
+            cogs: default_cogs(),
+            inventory: default_inventory(),
+        }
+    }
+}
+
+/// Accounting export settings, loaded from the `accounting` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AccountingConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default = "default_provider")]
+    pub provider: AccountingProvider,
+
+    /// QuickBooks realm id, NetSuite account id or Xero tenant id.
+    #[serde(default)]
+    pub company_id: String,
+
+    /// Overrides the provider's API root, e.g. the QuickBooks sandbox.
+    #[serde(default)]
+    pub base_url: Option<String>,
+
+    /// Environment variable holding the OAuth access token.
+    #[serde(default = "default_access_token_env")]
+    pub access_token_env: String,
+
+    #[serde(default)]
+    pub accounts: AccountMap,
+
+    /// Currency for orders, which do not record one.
+    #[serde(default = "default_currency")]
+    pub default_currency: String,
+
+    /// Post revenue for shipped orders. Turn off when revenue is booked from invoices
+    /// instead, or it is counted twice.
+    #[serde(default = "default_true")]
+    pub post_orders: bool,
+
+    /// Attempts before a failing export is parked as `failed`.
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: i32,
+
+    /// Delay before the first retry; doubles per attempt up to six hours.
+    #[serde(default = "default_retry_base_secs")]
+    pub retry_base_secs: i64,
+
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+
+    /// Exports sent per worker tick.
+    #[serde(default = "default_batch_size")]
+    pub batch_size: u64,
+}
+
+fn default_provider() -> AccountingProvider {
+    AccountingProvider::QuickBooks
+}
+
+fn default_access_token_env() -> String {
+    "ACCOUNTING_ACCESS_TOKEN".to_string()
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_max_attempts() -> i32 {
+    8
+}
+
+fn default_retry_base_secs() -> i64 {
+    60
+}
+
+fn default_interval_secs() -> u64 {
+    30
+}
+
+fn default_batch_size() -> u64 {
+    50
+}
+
+impl Default for AccountingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            provider: default_provider(),
+            company_id: String::new(),
+            base_url: None,
+            access_token_env: default_access_token_env(),
+            accounts: AccountMap::default(),
+            default_currency: default_currency(),
+            post_orders: true,
+            max_attempts: default_max_attempts(),
+            retry_base_secs: default_retry_base_secs(),
+            interval_secs: default_interval_secs(),
+            batch_size: default_batch_size(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum AccountingError {
+    #[error("Accounting export is disabled")]
+    Disabled,
+
+    #[error("Accounting provider is misconfigured: {0}")]
+    Misconfigured(String),
+
+    #[error("{0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for AccountingError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            AccountingError::Disabled => (StatusCode::SERVICE_UNAVAILABLE, "accounting_unavailable"),
+            AccountingError::Misconfigured(_) => (StatusCode::SERVICE_UNAVAILABLE, "accounting_misconfigured"),
+            AccountingError::NotFound(_) => (StatusCode::NOT_FOUND, "not_found"),
+            AccountingError::Invalid(_) => (StatusCode::BAD_REQUEST, "invalid_request"),
+            AccountingError::Database(e) => {
+                error!("Accounting export query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "accounting_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalLine {
+    pub account: String,
+    pub debit: Decimal,
+    pub credit: Decimal,
+    pub description: String,
+}
+
+impl JournalLine {
+    pub fn debit(account: &str, amount: Decimal, description: &str) -> Self {
+        Self { account: account.to_string(), debit: amount, credit: Decimal::ZERO, description: description.to_string() }
+    }
+
+    pub fn credit(account: &str, amount: Decimal, description: &str) -> Self {
+        Self { account: account.to_string(), debit: Decimal::ZERO, credit: amount, description: description.to_string() }
+    }
+}
+
+/// Provider-neutral journal entry; adapters translate it to each API's shape.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub kind: DocumentKind,
+    pub document_id: String,
+    /// Human-facing document number, e.g. the order number or RMA.
+    pub reference: String,
+    pub date: NaiveDate,
+    pub currency: String,
+    pub memo: String,
+    pub lines: Vec<JournalLine>,
+}
+
+impl JournalEntry {
+    /// Stable key for the document, used for idempotent posting where supported.
+    pub fn external_key(&self) -> String {
+        format!("{}:{}", kind_label(self.kind), self.document_id)
+    }
+
+    pub fn is_balanced(&self) -> bool {
+        let debits: Decimal = self.lines.iter().map(|l| l.debit).sum();
+        let credits: Decimal = self.lines.iter().map(|l| l.credit).sum();
+        debits == credits && !debits.is_zero()
+    }
+}
+
+fn kind_label(kind: DocumentKind) -> &'static str {
+    match kind {
+        DocumentKind::Order => "order",
+        DocumentKind::Invoice => "invoice",
+        DocumentKind::Refund => "refund",
+        DocumentKind::Cogs => "cogs",
+    }
+}
+
+/// Amounts pulled from a source document. `amount` includes `tax`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub kind: DocumentKind,
+    pub id: String,
+    pub reference: String,
+    pub date: NaiveDate,
+    pub currency: String,
+    pub amount: Decimal,
+    pub tax: Decimal,
+}
+
+/// Builds the double-entry journal for a document:
+///
+/// * orders and invoices debit receivables and credit revenue and sales tax,
+/// * refunds debit sales returns and sales tax and credit cash,
+/// * COGS debits cost of goods sold and credits inventory.
+pub fn journal_entry(accounts: &AccountMap, document: &Document) -> JournalEntry {
+    let net = document.amount - document.tax;
+    let (memo, mut lines) = match document.kind {
+        DocumentKind::Order | DocumentKind::Invoice => {
+            let label = if document.kind == DocumentKind::Order { "Order" } else { "Invoice" };
+            let mut lines = vec![
+                JournalLine::debit(&accounts.receivable, document.amount, "Accounts receivable"),
+                JournalLine::credit(&accounts.revenue, net, "Sales revenue"),
+            ];
+            if !document.tax.is_zero() {
+                lines.push(JournalLine::credit(&accounts.sales_tax, document.tax, "Sales tax payable"));
+            }
+            (format!("{} {}", label, document.reference), lines)
+        }
+        DocumentKind::Refund => {
+            let mut lines = vec![JournalLine::debit(&accounts.sales_returns, net, "Sales returns")];
+            if !document.tax.is_zero() {
+                lines.push(JournalLine::debit(&accounts.sales_tax, document.tax, "Sales tax refunded"));
+            }
+            lines.push(JournalLine::credit(&accounts.cash, document.amount, "Refund paid"));
+            (format!("Refund {}", document.reference), lines)
+        }
+        DocumentKind::Cogs => (
+            format!("Cost of goods sold {}", document.reference),
+            vec![
+                JournalLine::debit(&accounts.cogs, document.amount, "Cost of goods sold"),
+                JournalLine::credit(&accounts.inventory, document.amount, "Inventory relieved"),
+            ],
+        ),
+    };
+    lines.retain(|line| !(line.debit.is_zero() && line.credit.is_zero()));
+    JournalEntry {
+        kind: document.kind,
+        document_id: document.id.clone(),
+        reference: document.reference.clone(),
+        date: document.date,
+        currency: document.currency.clone(),
+        memo,
+        lines,
+    }
+}
+
+/// Delay before retry number `attempts` (1-based): the base doubled per attempt, capped.
+pub fn backoff(attempts: i32, base_secs: i64) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 20) as u32;
+    Duration::seconds(base_secs.max(1).saturating_mul(1i64 << exponent).min(MAX_BACKOFF_SECS))
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ExportFilter {
+    pub status: Option<ExportStatus>,
+    pub document_kind: Option<DocumentKind>,
+}
+
+/// Export counts and amounts per document kind and status, for reconciling against the ledger.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReconciliationRow {
+    pub document_kind: DocumentKind,
+    pub status: ExportStatus,
+    pub documents: u64,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct OrderTotals {
+    order_number: String,
+    total_cents: i64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct CostTotal {
+    cost: Option<Decimal>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct InvoiceTotals {
+    number: Option<i32>,
+    invoice_date: Option<NaiveDate>,
+    created: Option<DateTime<Utc>>,
+    currency: Option<String>,
+    total: Option<Decimal>,
+    tax_amount: Option<Decimal>,
+}
+
+const ORDER_TOTALS_SQL: &str = r#"
+SELECT o.order_number, COALESCE(SUM(li.sale_price::bigint * li.quantity), 0)::bigint AS total_cents
+FROM orders o
+LEFT JOIN order_line_items li ON li.order_id = o.id
+WHERE o.id = $1
+GROUP BY o.id, o.order_number
+"#;
+
+/// Shipped units at the average unit cost of the SKU across warehouses.
+const ORDER_COST_SQL: &str = r#"
+SELECT SUM(li.quantity * c.unit_cost) AS cost
+FROM order_line_items li
+JOIN (SELECT sku, AVG(unit_cost) AS unit_cost FROM inventory_items WHERE unit_cost IS NOT NULL GROUP BY sku) c
+  ON c.sku = li.seller_sku
+WHERE li.order_id = $1
+"#;
+
+const INVOICE_SQL: &str = r#"
+SELECT number, invoice_date, created, currency, total, tax_amount FROM invoices WHERE id = $1
+"#;
+
+/// Turns orders, invoices, refunds and shipments into journal entries and delivers them to
+/// the configured accounting system. Every document gets one `accounting_exports` row that
+/// tracks whether it reached the ledger; transient failures are retried with backoff.
+pub struct AccountingExporter {
+    db: Arc<DatabaseConnection>,
+    adapter: Arc<dyn LedgerAdapter>,
+    config: AccountingConfig,
+}
+
+impl AccountingExporter {
+    pub fn new(db: Arc<DatabaseConnection>, adapter: Arc<dyn LedgerAdapter>, config: AccountingConfig) -> Self {
+        Self { db, adapter, config }
+    }
+
+    /// Queues the journal entry for a document. Documents already queued or exported are
+    /// returned unchanged.
+    pub async fn enqueue(&self, document: Document) -> Result<accounting_export::Model, AccountingError> {
+        if let Some(existing) = self.find_document(document.kind, &document.id).await? {
+            return Ok(existing);
+        }
+        let entry = journal_entry(&self.config.accounts, &document);
+        if !entry.is_balanced() {
+            return Err(AccountingError::Invalid(format!(
+                "{} {} has no amount to post",
+                kind_label(document.kind),
+                document.reference
+            )));
+        }
+        let now = Utc::now();
+        let export = accounting_export::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            document_kind: Set(document.kind),
+            document_id: Set(document.id),
+            provider: Set(self.adapter.provider().as_str().to_string()),
+            entry: Set(serde_json::to_value(&entry).expect("journal entries serialize")),
+            status: Set(ExportStatus::Pending),
+            attempts: Set(0),
+            next_attempt_at: Set(Some(now)),
+            last_error: Set(None),
+            external_id: Set(None),
+            posted_at: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+        info!(export_id = %export.id, kind = kind_label(export.document_kind), document_id = %export.document_id, "Queued accounting export");
+        Ok(export)
+    }
+
+    /// Queues a document by kind and id, loading its amounts from the database.
+    pub async fn enqueue_document(&self, kind: DocumentKind, id: &str) -> Result<accounting_export::Model, AccountingError> {
+        let document = match kind {
+            DocumentKind::Order => self.order_document(parse_uuid(id)?).await?,
+            DocumentKind::Invoice => self.invoice_document(id).await?,
+            DocumentKind::Refund => self.refund_document(parse_uuid(id)?).await?,
+            DocumentKind::Cogs => self.cogs_document(parse_uuid(id)?).await?,
+        };
+        self.enqueue(document).await
+    }
+
+    async fn find_document(&self, kind: DocumentKind, id: &str) -> Result<Option<accounting_export::Model>, AccountingError> {
+        Ok(AccountingExport::find()
+            .filter(accounting_export::Column::DocumentKind.eq(kind))
+            .filter(accounting_export::Column::DocumentId.eq(id))
+            .one(self.db.as_ref())
+            .await?)
+    }
+
+    async fn order_totals(&self, order_id: Uuid) -> Result<OrderTotals, AccountingError> {
+        OrderTotals::find_by_statement(dialect::statement(
+            self.db.as_ref(),
+            ORDER_TOTALS_SQL,
+            [order_id.into()],
+        ))
+        .one(self.db.as_ref())
+        .await?
+        .ok_or_else(|| AccountingError::NotFound(format!("Order not found: {}", order_id)))
+    }
+
+    /// Order line items are priced in cents and carry no tax.
+    async fn order_document(&self, order_id: Uuid) -> Result<Document, AccountingError> {
+        let totals = self.order_totals(order_id).await?;
+        Ok(Document {
+            kind: DocumentKind::Order,
+            id: order_id.to_string(),
+            reference: totals.order_number,
+            date: Utc::now().date_naive(),
+            currency: self.config.default_currency.clone(),
+            amount: Decimal::new(totals.total_cents, 2),
+            tax: Decimal::ZERO,
+        })
+    }
+
+    async fn cogs_document(&self, order_id: Uuid) -> Result<Document, AccountingError> {
+        let totals = self.order_totals(order_id).await?;
+        let cost = CostTotal::find_by_statement(dialect::statement(
+            self.db.as_ref(),
+            ORDER_COST_SQL,
+            [order_id.into()],
+        ))
+        .one(self.db.as_ref())
+        .await?
+        .and_then(|row| row.cost)
+        .unwrap_or_default();
+        Ok(Document {
+            kind: DocumentKind::Cogs,
+            id: order_id.to_string(),
+            reference: totals.order_number,
+            date: Utc::now().date_naive(),
+            currency: self.config.default_currency.clone(),
+            amount: crate::money::round_currency(cost),
+            tax: Decimal::ZERO,
+        })
+    }
+
+    async fn invoice_document(&self, invoice_id: &str) -> Result<Document, AccountingError> {
+        let invoice = InvoiceTotals::find_by_statement(dialect::statement(
+            self.db.as_ref(),
+            INVOICE_SQL,
+            [invoice_id.into()],
+        ))
+        .one(self.db.as_ref())
+        .await?
+        .ok_or_else(|| AccountingError::NotFound(format!("Invoice not found: {}", invoice_id)))?;
+        Ok(Document {
+            kind: DocumentKind::Invoice,
+            id: invoice_id.to_string(),
+            reference: invoice.number.map(|n| n.to_string()).unwrap_or_else(|| invoice_id.to_string()),
+            date: invoice
+                .invoice_date
+                .or(invoice.created.map(|c| c.date_naive()))
+                .unwrap_or_else(|| Utc::now().date_naive()),
+            currency: invoice
+                .currency
+                .map(|c| c.to_uppercase())
+                .unwrap_or_else(|| self.config.default_currency.clone()),
+            amount: invoice.total.unwrap_or_default(),
+            tax: invoice.tax_amount.unwrap_or_default(),
+        })
+    }
+
+    /// A return's refunds are exported once, when it is refunded.
+    async fn refund_document(&self, return_id: Uuid) -> Result<Document, AccountingError> {
+        let ret = Return::find_by_id(return_id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| AccountingError::NotFound(format!("Return not found: {}", return_id)))?;
+        Ok(Document {
+            kind: DocumentKind::Refund,
+            id: return_id.to_string(),
+            reference: ret.rma,
+            date: Utc::now().date_naive(),
+            currency: self.config.default_currency.clone(),
+            amount: ret.total_refunded,
+            tax: ret.tax_refunded.min(ret.total_refunded),
+        })
+    }
+
+    /// Queues the documents an event produces, if any.
+    pub async fn record(&self, event: &Event) -> Result<(), AccountingError> {
+        match event {
+            Event::OrderShipped(order_id) => {
+                if self.config.post_orders {
+                    self.enqueue_document(DocumentKind::Order, &order_id.to_string()).await?;
+                }
+                // Orders without costed SKUs have nothing to relieve
+                match self.enqueue_document(DocumentKind::Cogs, &order_id.to_string()).await {
+                    Ok(_) | Err(AccountingError::Invalid(_)) => {}
+                    Err(e) => return Err(e),
+                }
+            }
+            Event::ReturnRefunded(return_id) => {
+                self.enqueue_document(DocumentKind::Refund, &return_id.to_string()).await?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Posts pending exports that are due. Returns how many were posted.
+    pub async fn run_due(&self) -> Result<usize, AccountingError> {
+        let due = AccountingExport::find()
+            .filter(accounting_export::Column::Status.eq(ExportStatus::Pending))
+            .filter(
+                Condition::any()
+                    .add(accounting_export::Column::NextAttemptAt.is_null())
+                    .add(accounting_export::Column::NextAttemptAt.lte(Utc::now())),
+            )
+            .order_by_asc(accounting_export::Column::CreatedAt)
+            .limit(self.config.batch_size.max(1))
+            .all(self.db.as_ref())
+            .await?;
+        let mut posted = 0;
+        for export in due {
+            if self.deliver(export).await?.status == ExportStatus::Posted {
+                posted += 1;
+            }
+        }
+        Ok(posted)
+    }
+
+    async fn deliver(&self, export: accounting_export::Model) -> Result<accounting_export::Model, AccountingError> {
+        let provider = self.adapter.provider().as_str();
+        let entry: JournalEntry = serde_json::from_value(export.entry.clone())
+            .map_err(|e| AccountingError::Invalid(format!("Stored journal entry is unreadable: {}", e)))?;
+        let attempts = export.attempts + 1;
+        let outcome = self.adapter.post(&entry).await;
+        let now = Utc::now();
+        let mut active: accounting_export::ActiveModel = export.into();
+        active.attempts = Set(attempts);
+        active.updated_at = Set(now);
+        match outcome {
+            Ok(external_id) => {
+                EXPORTS_ATTEMPTED.with_label_values(&[provider, "posted"]).inc();
+                active.status = Set(ExportStatus::Posted);
+                active.external_id = Set(Some(external_id));
+                active.posted_at = Set(Some(now));
+                active.next_attempt_at = Set(None);
+                active.last_error = Set(None);
+            }
+            Err(e) => {
+                let give_up = matches!(e, PostError::Rejected(_)) || attempts >= self.config.max_attempts;
+                EXPORTS_ATTEMPTED
+                    .with_label_values(&[provider, if give_up { "failed" } else { "retrying" }])
+                    .inc();
+                warn!(document = %entry.external_key(), attempts, "Accounting export failed: {}", e);
+                active.last_error = Set(Some(e.to_string()));
+                if give_up {
+                    active.status = Set(ExportStatus::Failed);
+                    active.next_attempt_at = Set(None);
+                } else {
+                    active.next_attempt_at = Set(Some(now + backoff(attempts, self.config.retry_base_secs)));
+                }
+            }
+        }
+        Ok(active.update(self.db.as_ref()).await?)
+    }
+
+    /// Puts a failed export back in the queue with a fresh attempt budget.
+    pub async fn retry(&self, id: Uuid) -> Result<accounting_export::Model, AccountingError> {
+        let export = self.get(id).await?;
+        if export.status != ExportStatus::Failed {
+            return Err(AccountingError::Invalid(format!("Only failed exports can be retried, not {:?}", export.status)));
+        }
+        let mut active: accounting_export::ActiveModel = export.into();
+        active.status = Set(ExportStatus::Pending);
+        active.attempts = Set(0);
+        active.next_attempt_at = Set(Some(Utc::now()));
+        active.updated_at = Set(Utc::now());
+        Ok(active.update(self.db.as_ref()).await?)
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<accounting_export::Model, AccountingError> {
+        AccountingExport::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| AccountingError::NotFound(format!("Accounting export not found: {}", id)))
+    }
+
+    /// The export for one document, i.e. its reconciliation status.
+    pub async fn for_document(&self, kind: DocumentKind, id: &str) -> Result<accounting_export::Model, AccountingError> {
+        self.find_document(kind, id)
+            .await?
+            .ok_or_else(|| AccountingError::NotFound(format!("{} {} has not been exported", kind_label(kind), id)))
+    }
+
+    pub async fn list(
+        &self,
+        filter: ExportFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<accounting_export::Model>, u64), AccountingError> {
+        let mut query = AccountingExport::find();
+        if let Some(status) = filter.status {
+            query = query.filter(accounting_export::Column::Status.eq(status));
+        }
+        if let Some(kind) = filter.document_kind {
+            query = query.filter(accounting_export::Column::DocumentKind.eq(kind));
+        }
+        let paginator = query
+            .order_by_desc(accounting_export::Column::CreatedAt)
+            .paginate(self.db.as_ref(), pagination.limit());
+        let total = paginator.num_items().await?;
+        let exports = paginator.fetch_page(pagination.page_index()).await?;
+        Ok((exports, total))
+    }
+
+    /// Counts and totals per document kind and status for exports created in the range.
+    pub async fn reconciliation(&self, from: NaiveDate, to: NaiveDate) -> Result<Vec<ReconciliationRow>, AccountingError> {
+        if to < from {
+            return Err(AccountingError::Invalid("`to` is before `from`".to_string()));
+        }
+        let start = from.and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc();
+        let end = (to + Duration::days(1)).and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc();
+        let exports = AccountingExport::find()
+            .filter(accounting_export::Column::CreatedAt.gte(start))
+            .filter(accounting_export::Column::CreatedAt.lt(end))
+            .all(self.db.as_ref())
+            .await?;
+        Ok(reconcile(&exports))
+    }
+}
+
+fn parse_uuid(id: &str) -> Result<Uuid, AccountingError> {
+    Uuid::parse_str(id).map_err(|_| AccountingError::Invalid(format!("Invalid document id: {}", id)))
+}
+
+/// Debit total of a stored entry; zero if it cannot be read.
+fn entry_amount(export: &accounting_export::Model) -> Decimal {
+    serde_json::from_value::<JournalEntry>(export.entry.clone())
+        .map(|entry| entry.lines.iter().map(|line| line.debit).sum())
+        .unwrap_or_default()
+}
+
+fn reconcile(exports: &[accounting_export::Model]) -> Vec<ReconciliationRow> {
+    let mut rows: BTreeMap<(&'static str, &'static str), ReconciliationRow> = BTreeMap::new();
+    for export in exports {
+        let status = match export.status {
+            ExportStatus::Pending => "pending",
+            ExportStatus::Posted => "posted",
+            ExportStatus::Failed => "failed",
+        };
+        let row = rows.entry((kind_label(export.document_kind), status)).or_insert_with(|| ReconciliationRow {
+            document_kind: export.document_kind,
+            status: export.status,
+            documents: 0,
+            amount: Decimal::ZERO,
+        });
+        row.documents += 1;
+        row.amount += entry_amount(export);
+    }
+    rows.into_values().collect()
+}
+
+/// Queues journal entries for orders, refunds and shipments as their events arrive.
+pub fn spawn_recorder(exporter: Arc<AccountingExporter>, events: EventSender, mut worker: Worker) {
+    let mut receiver = events.subscribe();
+    tokio::spawn(async move {
+        loop {
+            let received = tokio::select! {
+                biased;
+                received = receiver.recv() => received,
+                _ = worker.stopping() => break,
+            };
+            match received {
+                Ok(event) => {
+                    if let Err(e) = exporter.record(&event).await {
+                        error!(?event, "Queueing accounting export failed: {}", e);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!(missed, "Accounting recorder lagged; missed documents can be queued via the API");
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Posts due exports on a fixed interval.
+pub fn spawn_worker(exporter: Arc<AccountingExporter>, interval: std::time::Duration, mut worker: Worker) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = worker.stopping() => break,
+            }
+            match exporter.run_due().await {
+                Ok(0) => {}
+                Ok(posted) => info!(posted, "Posted accounting exports"),
+                Err(e) => error!("Accounting export run failed: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn document(kind: DocumentKind, amount: Decimal, tax: Decimal) -> Document {
+        Document {
+            kind,
+            id: Uuid::nil().to_string(),
+            reference: "SO-1".to_string(),
+            date: NaiveDate::from_ymd_opt(2024, 3, 9).unwrap(),
+            currency: "USD".to_string(),
+            amount,
+            tax,
+        }
+    }
+
+    #[test]
+    fn test_invoice_entry_splits_tax() {
+        let entry = journal_entry(&AccountMap::default(), &document(DocumentKind::Invoice, dec!(108), dec!(8)));
+        assert!(entry.is_balanced());
+        assert_eq!(entry.lines[0], JournalLine::debit("1200", dec!(108), "Accounts receivable"));
+        assert_eq!(entry.lines[1].credit, dec!(100));
+        assert_eq!(entry.lines[2].account, "2200");
+    }
+
+    #[test]
+    fn test_untaxed_order_has_two_lines() {
+        let entry = journal_entry(&AccountMap::default(), &document(DocumentKind::Order, dec!(50), dec!(0)));
+        assert_eq!(entry.lines.len(), 2);
+        assert!(entry.is_balanced());
+    }
+
+    #[test]
+    fn test_refund_and_cogs_entries_balance() {
+        let refund = journal_entry(&AccountMap::default(), &document(DocumentKind::Refund, dec!(54), dec!(4)));
+        assert!(refund.is_balanced());
+        assert_eq!(refund.lines.last().unwrap().credit, dec!(54));
+
+        let cogs = journal_entry(&AccountMap::default(), &document(DocumentKind::Cogs, dec!(31.40), dec!(0)));
+        assert!(cogs.is_balanced());
+        assert_eq!(cogs.lines[0].account, "5000");
+    }
+
+    #[test]
+    fn test_zero_amount_entry_is_not_postable() {
+        let entry = journal_entry(&AccountMap::default(), &document(DocumentKind::Cogs, dec!(0), dec!(0)));
+        assert!(entry.lines.is_empty());
+        assert!(!entry.is_balanced());
+    }
+
+    #[test]
+    fn test_backoff_doubles_and_caps() {
+        assert_eq!(backoff(1, 60), Duration::seconds(60));
+        assert_eq!(backoff(3, 60), Duration::seconds(240));
+        assert_eq!(backoff(30, 60), Duration::seconds(MAX_BACKOFF_SECS));
+    }
+}
@@ -0,0 +1,306 @@
+// integrations/accounting/providers.rs
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use super::{AccountingConfig, AccountingError, AccountingProvider, JournalEntry};
+
+/// Why a provider did not accept an entry.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PostError {
+    /// Network errors, rate limiting and 5xx responses; the entry is retried.
+    Unavailable(String),
+    /// The provider refused the entry itself (bad account, closed period, ...); retrying
+    /// the same payload will not help.
+    Rejected(String),
+}
+
+impl std::fmt::Display for PostError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PostError::Unavailable(message) => write!(f, "provider unavailable: {}", message),
+            PostError::Rejected(message) => write!(f, "rejected by provider: {}", message),
+        }
+    }
+}
+
+/// Posts balanced journal entries to an external general ledger.
+#[async_trait]
+pub trait LedgerAdapter: Send + Sync {
+    fn provider(&self) -> AccountingProvider;
+
+    /// Posts the entry and returns the provider's id for the created journal.
+    async fn post(&self, entry: &JournalEntry) -> Result<String, PostError>;
+}
+
+/// Maps a failed request or error status onto retryable vs. permanent failures.
+fn request_error(e: reqwest::Error) -> PostError {
+    match e.status() {
+        Some(status) if status.is_client_error() && status.as_u16() != 429 => PostError::Rejected(e.to_string()),
+        _ => PostError::Unavailable(e.to_string()),
+    }
+}
+
+async fn send(request: reqwest::RequestBuilder) -> Result<reqwest::Response, PostError> {
+    let response = request.send().await.map_err(request_error)?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    let message = format!("{}: {}", status, body.chars().take(500).collect::<String>());
+    if status.is_client_error() && status.as_u16() != 429 {
+        Err(PostError::Rejected(message))
+    } else {
+        Err(PostError::Unavailable(message))
+    }
+}
+
+fn missing_id(provider: &str) -> PostError {
+    PostError::Unavailable(format!("{} response did not include a journal id", provider))
+}
+
+/// QuickBooks Online `JournalEntry` payload.
+pub fn quickbooks_body(entry: &JournalEntry) -> Value {
+    let lines: Vec<Value> = entry
+        .lines
+        .iter()
+        .map(|line| {
+            let (posting, amount) = if line.debit > Decimal::ZERO { ("Debit", line.debit) } else { ("Credit", line.credit) };
+            json!({
+                "Amount": amount,
+                "Description": line.description,
+                "DetailType": "JournalEntryLineDetail",
+                "JournalEntryLineDetail": {
+                    "PostingType": posting,
+                    "AccountRef": { "value": line.account },
+                },
+            })
+        })
+        .collect();
+    json!({
+        "DocNumber": entry.reference,
+        "TxnDate": entry.date.to_string(),
+        "PrivateNote": entry.memo,
+        "CurrencyRef": { "value": entry.currency },
+        "Line": lines,
+    })
+}
+
+/// NetSuite REST record `journalEntry` payload. `externalId` makes re-posts idempotent.
+pub fn netsuite_body(entry: &JournalEntry) -> Value {
+    let lines: Vec<Value> = entry
+        .lines
+        .iter()
+        .map(|line| {
+            let mut item = json!({ "account": { "id": line.account }, "memo": line.description });
+            if line.debit > Decimal::ZERO {
+                item["debit"] = json!(line.debit);
+            } else {
+                item["credit"] = json!(line.credit);
+            }
+            item
+        })
+        .collect();
+    json!({
+        "externalId": entry.external_key(),
+        "tranDate": entry.date.to_string(),
+        "memo": entry.memo,
+        "currency": { "refName": entry.currency },
+        "line": { "items": lines },
+    })
+}
+
+/// Xero `ManualJournals` payload. Xero signs line amounts: debits positive, credits negative.
+pub fn xero_body(entry: &JournalEntry) -> Value {
+    let lines: Vec<Value> = entry
+        .lines
+        .iter()
+        .map(|line| {
+            json!({
+                "LineAmount": line.debit - line.credit,
+                "AccountCode": line.account,
+                "Description": line.description,
+            })
+        })
+        .collect();
+    json!({
+        "ManualJournals": [{
+            "Narration": entry.memo,
+            "Date": entry.date.to_string(),
+            "Status": "POSTED",
+            "JournalLines": lines,
+        }]
+    })
+}
+
+pub struct QuickBooksAdapter {
+    client: reqwest::Client,
+    base_url: String,
+    realm_id: String,
+    token: String,
+}
+
+#[async_trait]
+impl LedgerAdapter for QuickBooksAdapter {
+    fn provider(&self) -> AccountingProvider {
+        AccountingProvider::QuickBooks
+    }
+
+    async fn post(&self, entry: &JournalEntry) -> Result<String, PostError> {
+        let url = format!("{}/v3/company/{}/journalentry", self.base_url, self.realm_id);
+        let response: Value = send(self.client.post(url).bearer_auth(&self.token).json(&quickbooks_body(entry)))
+            .await?
+            .json()
+            .await
+            .map_err(request_error)?;
+        response["JournalEntry"]["Id"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| missing_id("QuickBooks"))
+    }
+}
+
+pub struct NetSuiteAdapter {
+    client: reqwest::Client,
+    base_url: String,
+    token: String,
+}
+
+#[async_trait]
+impl LedgerAdapter for NetSuiteAdapter {
+    fn provider(&self) -> AccountingProvider {
+        AccountingProvider::NetSuite
+    }
+
+    async fn post(&self, entry: &JournalEntry) -> Result<String, PostError> {
+        let url = format!("{}/services/rest/record/v1/journalEntry", self.base_url);
+        let response = send(self.client.post(url).bearer_auth(&self.token).json(&netsuite_body(entry))).await?;
+        // NetSuite answers 204 with the new record's URL in `Location`
+        response
+            .headers()
+            .get(reqwest::header::LOCATION)
+            .and_then(|location| location.to_str().ok())
+            .and_then(|location| location.rsplit('/').next())
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .ok_or_else(|| missing_id("NetSuite"))
+    }
+}
+
+pub struct XeroAdapter {
+    client: reqwest::Client,
+    base_url: String,
+    tenant_id: String,
+    token: String,
+}
+
+#[async_trait]
+impl LedgerAdapter for XeroAdapter {
+    fn provider(&self) -> AccountingProvider {
+        AccountingProvider::Xero
+    }
+
+    async fn post(&self, entry: &JournalEntry) -> Result<String, PostError> {
+        let url = format!("{}/api.xro/2.0/ManualJournals", self.base_url);
+        let response: Value = send(
+            self.client
+                .post(url)
+                .bearer_auth(&self.token)
+                .header("xero-tenant-id", &self.tenant_id)
+                .json(&xero_body(entry)),
+        )
+        .await?
+        .json()
+        .await
+        .map_err(request_error)?;
+        response["ManualJournals"][0]["ManualJournalID"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| missing_id("Xero"))
+    }
+}
+
+/// Builds the adapter for the configured provider.
+pub fn from_config(config: &AccountingConfig) -> Result<Arc<dyn LedgerAdapter>, AccountingError> {
+    let token = std::env::var(&config.access_token_env)
+        .map_err(|_| AccountingError::Misconfigured(format!("{} is not set", config.access_token_env)))?;
+    if config.company_id.trim().is_empty() {
+        return Err(AccountingError::Misconfigured("company_id is not set".to_string()));
+    }
+    let base_url = config
+        .base_url
+        .clone()
+        .unwrap_or_else(|| config.provider.default_base_url(&config.company_id))
+        .trim_end_matches('/')
+        .to_string();
+    let client = reqwest::Client::new();
+    Ok(match config.provider {
+        AccountingProvider::QuickBooks => Arc::new(QuickBooksAdapter {
+            client,
+            base_url,
+            realm_id: config.company_id.clone(),
+            token,
+        }),
+        AccountingProvider::NetSuite => Arc::new(NetSuiteAdapter { client, base_url, token }),
+        AccountingProvider::Xero => Arc::new(XeroAdapter {
+            client,
+            base_url,
+            tenant_id: config.company_id.clone(),
+            token,
+        }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::integrations::accounting::JournalLine;
+    use crate::models::accounting_export::DocumentKind;
+    use chrono::NaiveDate;
+    use rust_decimal_macros::dec;
+
+    fn entry() -> JournalEntry {
+        JournalEntry {
+            kind: DocumentKind::Order,
+            document_id: "7b0c".to_string(),
+            reference: "SO-1001".to_string(),
+            date: NaiveDate::from_ymd_opt(2024, 3, 9).unwrap(),
+            currency: "USD".to_string(),
+            memo: "Order SO-1001".to_string(),
+            lines: vec![
+                JournalLine::debit("1200", dec!(108), "Receivable"),
+                JournalLine::credit("4000", dec!(100), "Revenue"),
+                JournalLine::credit("2200", dec!(8), "Sales tax"),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_quickbooks_posting_types() {
+        let body = quickbooks_body(&entry());
+        assert_eq!(body["DocNumber"], "SO-1001");
+        assert_eq!(body["Line"][0]["JournalEntryLineDetail"]["PostingType"], "Debit");
+        assert_eq!(body["Line"][2]["JournalEntryLineDetail"]["PostingType"], "Credit");
+        assert_eq!(body["Line"][2]["JournalEntryLineDetail"]["AccountRef"]["value"], "2200");
+    }
+
+    #[test]
+    fn test_netsuite_lines_carry_one_side() {
+        let body = netsuite_body(&entry());
+        assert_eq!(body["externalId"], "order:7b0c");
+        let items = body["line"]["items"].as_array().unwrap();
+        assert!(items[0].get("debit").is_some() && items[0].get("credit").is_none());
+        assert!(items[1].get("credit").is_some() && items[1].get("debit").is_none());
+    }
+
+    #[test]
+    fn test_xero_signs_credits_negative() {
+        let body = xero_body(&entry());
+        let lines = body["ManualJournals"][0]["JournalLines"].as_array().unwrap();
+        assert_eq!(lines[0]["LineAmount"], json!(dec!(108)));
+        assert_eq!(lines[1]["LineAmount"], json!(dec!(-100)));
+    }
+}
@@ -0,0 +1,411 @@
+// integrations/amazon/client.rs
+
+use chrono::{DateTime, Utc};
+use reqwest::{Method, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use super::{AmazonConfig, AmazonError, InventorySummary, MarketplaceOrder, OrderItem};
+
+/// Access tokens are refreshed this long before Amazon says they expire.
+const TOKEN_MARGIN: Duration = Duration::from_secs(60);
+
+/// SP-API operations the sync calls, each with its own usage plan.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Operation {
+    GetOrders,
+    GetOrderItems,
+    ConfirmShipment,
+    InventorySummaries,
+    RestrictedDataToken,
+}
+
+impl Operation {
+    /// Default rate (requests per second) and burst of the operation's usage plan. Amazon
+    /// may grant more; the `x-amzn-RateLimit-Limit` header of each response wins.
+    fn usage_plan(self) -> (f64, f64) {
+        match self {
+            Operation::GetOrders => (0.0167, 20.0),
+            Operation::GetOrderItems => (0.5, 30.0),
+            Operation::ConfirmShipment => (2.0, 10.0),
+            Operation::InventorySummaries => (2.0, 2.0),
+            Operation::RestrictedDataToken => (1.0, 10.0),
+        }
+    }
+}
+
+/// Client-side token bucket mirroring one SP-API usage plan, so calls wait for capacity
+/// instead of collecting 429s. Tokens may go negative: each caller reserves its slot and
+/// waits until the bucket has refilled up to it.
+#[derive(Debug, Clone)]
+pub struct TokenBucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    pub fn new(rate: f64, burst: f64, now: Instant) -> Self {
+        Self { rate, burst, tokens: burst, updated: now }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.updated = now;
+    }
+
+    /// Takes a token and returns how long to wait before using it.
+    pub fn acquire(&mut self, now: Instant) -> Duration {
+        self.refill(now);
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+
+    /// Amazon answered 429: whatever we thought was left is gone.
+    pub fn throttled(&mut self, now: Instant) {
+        self.refill(now);
+        self.tokens = self.tokens.min(0.0);
+    }
+
+    pub fn set_rate(&mut self, rate: f64) {
+        if rate > 0.0 {
+            self.rate = rate;
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct AccessToken {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RestrictedToken {
+    restricted_data_token: String,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct OrdersPayload {
+    #[serde(default)]
+    orders: Vec<MarketplaceOrder>,
+    next_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct OrderItemsPayload {
+    #[serde(default)]
+    order_items: Vec<OrderItem>,
+    next_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InventoryPayload {
+    #[serde(default)]
+    inventory_summaries: Vec<InventorySummary>,
+}
+
+/// One page of a paginated SP-API listing.
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub next_token: Option<String>,
+}
+
+/// Selling Partner API client: Login with Amazon token refresh, restricted data tokens for
+/// buyer PII, and client-side rate limiting per operation.
+pub struct SpApiClient {
+    http: reqwest::Client,
+    endpoint: String,
+    token_url: String,
+    marketplace_id: String,
+    client_id: String,
+    client_secret: String,
+    refresh_token: String,
+    max_wait: Duration,
+    max_retries: u32,
+    access_token: tokio::sync::Mutex<Option<(String, Instant)>>,
+    restricted_token: tokio::sync::Mutex<Option<(String, Instant)>>,
+    buckets: std::sync::Mutex<HashMap<Operation, TokenBucket>>,
+}
+
+fn secret(var: &str) -> Result<String, AmazonError> {
+    std::env::var(var).map_err(|_| AmazonError::Misconfigured(format!("{} is not set", var)))
+}
+
+impl SpApiClient {
+    pub fn from_config(config: &AmazonConfig) -> Result<Self, AmazonError> {
+        Ok(Self {
+            http: reqwest::Client::new(),
+            endpoint: config.endpoint.trim_end_matches('/').to_string(),
+            token_url: config.token_url.clone(),
+            marketplace_id: config.marketplace_id.clone(),
+            client_id: secret(&config.client_id_env)?,
+            client_secret: secret(&config.client_secret_env)?,
+            refresh_token: secret(&config.refresh_token_env)?,
+            max_wait: Duration::from_secs(config.max_throttle_wait_secs),
+            max_retries: config.max_throttle_retries,
+            access_token: tokio::sync::Mutex::new(None),
+            restricted_token: tokio::sync::Mutex::new(None),
+            buckets: std::sync::Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub fn marketplace_id(&self) -> &str {
+        &self.marketplace_id
+    }
+
+    /// Waits for a token of `operation`'s bucket. Waits longer than the configured maximum
+    /// end the run instead; the scheduler tries again later.
+    async fn throttle(&self, operation: Operation) -> Result<(), AmazonError> {
+        let wait = {
+            let mut buckets = self.buckets.lock().expect("rate limit buckets lock");
+            let now = Instant::now();
+            buckets
+                .entry(operation)
+                .or_insert_with(|| {
+                    let (rate, burst) = operation.usage_plan();
+                    TokenBucket::new(rate, burst, now)
+                })
+                .acquire(now)
+        };
+        if wait > self.max_wait {
+            return Err(AmazonError::Throttled(format!("{:?} needs a {}s wait", operation, wait.as_secs())));
+        }
+        if !wait.is_zero() {
+            debug!(?operation, wait_ms = wait.as_millis() as u64, "Waiting for SP-API rate limit");
+            tokio::time::sleep(wait).await;
+        }
+        Ok(())
+    }
+
+    fn on_response(&self, operation: Operation, response: &reqwest::Response) {
+        let mut buckets = self.buckets.lock().expect("rate limit buckets lock");
+        let Some(bucket) = buckets.get_mut(&operation) else { return };
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            bucket.throttled(Instant::now());
+        }
+        if let Some(rate) = response
+            .headers()
+            .get("x-amzn-RateLimit-Limit")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<f64>().ok())
+        {
+            bucket.set_rate(rate);
+        }
+    }
+
+    async fn access_token(&self) -> Result<String, AmazonError> {
+        let mut cached = self.access_token.lock().await;
+        if let Some((token, expires)) = cached.as_ref() {
+            if Instant::now() < *expires {
+                return Ok(token.clone());
+            }
+        }
+        let response = self
+            .http
+            .post(&self.token_url)
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", self.refresh_token.as_str()),
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| AmazonError::Unavailable(e.to_string()))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(AmazonError::Misconfigured(format!("Login with Amazon refused the refresh token: {} {}", status, body)));
+        }
+        let token: AccessToken = response.json().await.map_err(|e| AmazonError::Unavailable(e.to_string()))?;
+        let expires = Instant::now() + Duration::from_secs(token.expires_in).saturating_sub(TOKEN_MARGIN);
+        *cached = Some((token.access_token.clone(), expires));
+        Ok(token.access_token)
+    }
+
+    /// Token that unlocks buyer names, emails and full shipping addresses on `getOrders`.
+    async fn restricted_token(&self) -> Result<String, AmazonError> {
+        let mut cached = self.restricted_token.lock().await;
+        if let Some((token, expires)) = cached.as_ref() {
+            if Instant::now() < *expires {
+                return Ok(token.clone());
+            }
+        }
+        let body = json!({
+            "restrictedResources": [{
+                "method": "GET",
+                "path": "/orders/v0/orders",
+                "dataElements": ["buyerInfo", "shippingAddress"],
+            }]
+        });
+        let value = self
+            .send(Operation::RestrictedDataToken, Method::POST, "/tokens/2021-03-01/restrictedDataToken", &[], Some(&body), None)
+            .await?;
+        let token: RestrictedToken =
+            serde_json::from_value(value).map_err(|e| AmazonError::Unavailable(format!("Unreadable token response: {}", e)))?;
+        let expires = Instant::now() + Duration::from_secs(token.expires_in).saturating_sub(TOKEN_MARGIN);
+        *cached = Some((token.restricted_data_token.clone(), expires));
+        Ok(token.restricted_data_token)
+    }
+
+    /// Calls an SP-API operation, retrying 429s up to the configured limit. Returns
+    /// `Value::Null` for empty responses.
+    async fn send(
+        &self,
+        operation: Operation,
+        method: Method,
+        path: &str,
+        query: &[(&str, String)],
+        body: Option<&Value>,
+        token: Option<&str>,
+    ) -> Result<Value, AmazonError> {
+        let mut retries = 0;
+        loop {
+            self.throttle(operation).await?;
+            let token = match token {
+                Some(token) => token.to_string(),
+                None => self.access_token().await?,
+            };
+            let mut request = self
+                .http
+                .request(method.clone(), format!("{}{}", self.endpoint, path))
+                .query(query)
+                .header("x-amz-access-token", token);
+            if let Some(body) = body {
+                request = request.json(body);
+            }
+            let response = request.send().await.map_err(|e| AmazonError::Unavailable(e.to_string()))?;
+            self.on_response(operation, &response);
+            let status = response.status();
+            if status == StatusCode::TOO_MANY_REQUESTS {
+                retries += 1;
+                if retries > self.max_retries {
+                    return Err(AmazonError::Throttled(format!("{:?} still throttled after {} retries", operation, self.max_retries)));
+                }
+                warn!(?operation, retries, "SP-API throttled the request");
+                continue;
+            }
+            if status == StatusCode::NO_CONTENT {
+                return Ok(Value::Null);
+            }
+            let text = response.text().await.map_err(|e| AmazonError::Unavailable(e.to_string()))?;
+            if status.is_client_error() {
+                return Err(AmazonError::Rejected(format!("{:?}: {} {}", operation, status, text.chars().take(500).collect::<String>())));
+            }
+            if !status.is_success() {
+                return Err(AmazonError::Unavailable(format!("{:?}: {}", operation, status)));
+            }
+            if text.trim().is_empty() {
+                return Ok(Value::Null);
+            }
+            return serde_json::from_str(&text).map_err(|e| AmazonError::Unavailable(format!("Unreadable {:?} response: {}", operation, e)));
+        }
+    }
+
+    /// Orders updated in `[after, before)`, with buyer details and shipping address.
+    pub async fn orders(
+        &self,
+        after: DateTime<Utc>,
+        before: DateTime<Utc>,
+        next_token: Option<String>,
+    ) -> Result<Page<MarketplaceOrder>, AmazonError> {
+        let token = self.restricted_token().await?;
+        let mut query = vec![("MarketplaceIds", self.marketplace_id.clone())];
+        match next_token {
+            Some(next) => query.push(("NextToken", next)),
+            None => {
+                query.push(("LastUpdatedAfter", after.to_rfc3339()));
+                query.push(("LastUpdatedBefore", before.to_rfc3339()));
+            }
+        }
+        let value = self.send(Operation::GetOrders, Method::GET, "/orders/v0/orders", &query, None, Some(&token)).await?;
+        let payload: OrdersPayload = serde_json::from_value(value["payload"].clone())
+            .map_err(|e| AmazonError::Unavailable(format!("Unreadable orders page: {}", e)))?;
+        Ok(Page { items: payload.orders, next_token: payload.next_token })
+    }
+
+    pub async fn order_items(&self, amazon_order_id: &str) -> Result<Vec<OrderItem>, AmazonError> {
+        let path = format!("/orders/v0/orders/{}/orderItems", amazon_order_id);
+        let mut items = Vec::new();
+        let mut next_token: Option<String> = None;
+        loop {
+            let query: Vec<(&str, String)> = next_token.into_iter().map(|t| ("NextToken", t)).collect();
+            let value = self.send(Operation::GetOrderItems, Method::GET, &path, &query, None, None).await?;
+            let payload: OrderItemsPayload = serde_json::from_value(value["payload"].clone())
+                .map_err(|e| AmazonError::Unavailable(format!("Unreadable order items: {}", e)))?;
+            items.extend(payload.order_items);
+            match payload.next_token {
+                Some(next) => next_token = Some(next),
+                None => return Ok(items),
+            }
+        }
+    }
+
+    pub async fn inventory(&self, next_token: Option<String>) -> Result<Page<InventorySummary>, AmazonError> {
+        let mut query = vec![
+            ("granularityType", "Marketplace".to_string()),
+            ("granularityId", self.marketplace_id.clone()),
+            ("marketplaceIds", self.marketplace_id.clone()),
+            ("details", "true".to_string()),
+        ];
+        if let Some(next) = next_token {
+            query.push(("nextToken", next));
+        }
+        let value = self
+            .send(Operation::InventorySummaries, Method::GET, "/fba/inventory/v1/summaries", &query, None, None)
+            .await?;
+        let payload: InventoryPayload = serde_json::from_value(value["payload"].clone())
+            .map_err(|e| AmazonError::Unavailable(format!("Unreadable inventory page: {}", e)))?;
+        let next_token = value["pagination"]["nextToken"].as_str().map(str::to_string);
+        Ok(Page { items: payload.inventory_summaries, next_token })
+    }
+
+    pub async fn confirm_shipment(&self, amazon_order_id: &str, package: &Value) -> Result<(), AmazonError> {
+        let path = format!("/orders/v0/orders/{}/shipmentConfirmation", amazon_order_id);
+        let body = json!({ "marketplaceId": self.marketplace_id, "packageDetail": package });
+        self.send(Operation::ConfirmShipment, Method::POST, &path, &[], Some(&body), None).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_spends_burst_then_waits_for_refill() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(0.5, 2.0, start);
+        assert_eq!(bucket.acquire(start), Duration::ZERO);
+        assert_eq!(bucket.acquire(start), Duration::ZERO);
+        assert_eq!(bucket.acquire(start), Duration::from_secs(2));
+        // The next caller queues behind the reservation above
+        assert_eq!(bucket.acquire(start), Duration::from_secs(4));
+        assert_eq!(bucket.acquire(start + Duration::from_secs(10)), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_throttled_bucket_starts_empty() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::new(2.0, 10.0, start);
+        bucket.throttled(start);
+        assert_eq!(bucket.acquire(start), Duration::from_millis(500));
+        bucket.set_rate(0.0);
+        assert_eq!(bucket.rate, 2.0);
+    }
+}
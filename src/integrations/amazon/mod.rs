@@ -0,0 +1,1100 @@
+// integrations/amazon/mod.rs
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    IntoActiveModel, PaginatorTrait, QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc, Mutex,
+};
+use thiserror::Error;
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, error, info, warn};
+use uuid::Uuid;
+
+use crate::db::dialect;
+use crate::events::{Event, EventSender};
+use crate::jobs::{JobContext, JobError, JobRunner};
+use crate::models::{
+    amazon_listing::{self, Entity as AmazonListing},
+    amazon_order::{self, Entity as AmazonOrder, FulfillmentChannel},
+    amazon_sync_cursor::{self, Entity as AmazonSyncCursor},
+    inventory_items::{self, Entity as InventoryItem},
+    order::{self, DeliveryType, Entity as Order, FulfillmentType, OrderStatus},
+    shipment::{self, Entity as Shipment, ShippingCarrier},
+};
+use crate::utils::pagination::PaginationParams;
+
+pub mod client;
+
+pub use client::SpApiClient;
+
+pub const ORDERS_JOB_KIND: &str = "amazon_orders";
+pub const INVENTORY_JOB_KIND: &str = "amazon_inventory";
+
+/// Orders updated in the last two minutes may still be changing; Amazon recommends
+/// leaving them for the next run.
+const SETTLE_MINUTES: i64 = 2;
+
+const SYSTEM_ACTOR: &str = "system";
+
+/// Amazon Selling Partner API settings, loaded from the `amazon` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct AmazonConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Regional SP-API endpoint.
+    #[serde(default = "default_endpoint")]
+    pub endpoint: String,
+
+    /// Login with Amazon token endpoint.
+    #[serde(default = "default_token_url")]
+    pub token_url: String,
+
+    #[serde(default = "default_marketplace_id")]
+    pub marketplace_id: String,
+
+    #[serde(default = "default_client_id_env")]
+    pub client_id_env: String,
+
+    #[serde(default = "default_client_secret_env")]
+    pub client_secret_env: String,
+
+    /// Environment variable holding the seller authorization's refresh token.
+    #[serde(default = "default_refresh_token_env")]
+    pub refresh_token_env: String,
+
+    /// Warehouse ingested orders are assigned to. Merchant-fulfilled orders ship from it.
+    #[serde(default)]
+    pub order_warehouse_id: Uuid,
+
+    /// Warehouse number FBA stock is kept under; no physical warehouse may use it.
+    /// Inventory sync is refused until it is set.
+    #[serde(default)]
+    pub fba_warehouse: Option<i32>,
+
+    /// How far back the first order sync reaches.
+    #[serde(default = "default_initial_lookback_days")]
+    pub initial_lookback_days: i64,
+
+    #[serde(default = "default_order_interval_secs")]
+    pub order_interval_secs: u64,
+
+    #[serde(default = "default_inventory_interval_secs")]
+    pub inventory_interval_secs: u64,
+
+    /// Longest a run waits for rate limit capacity before giving up until the next run.
+    #[serde(default = "default_max_throttle_wait_secs")]
+    pub max_throttle_wait_secs: u64,
+
+    /// 429 responses retried per call.
+    #[serde(default = "default_max_throttle_retries")]
+    pub max_throttle_retries: u32,
+
+    /// Scheduled runs of a sync are skipped this long after it was throttled.
+    #[serde(default = "default_throttle_pause_secs")]
+    pub throttle_pause_secs: u64,
+}
+
+fn default_endpoint() -> String {
+    "https://sellingpartnerapi-na.amazon.com".to_string()
+}
+
+fn default_token_url() -> String {
+    "https://api.amazon.com/auth/o2/token".to_string()
+}
+
+fn default_marketplace_id() -> String {
+    // amazon.com
+    "ATVPDKIKX0DER".to_string()
+}
+
+fn default_client_id_env() -> String {
+    "AMAZON_LWA_CLIENT_ID".to_string()
+}
+
+fn default_client_secret_env() -> String {
+    "AMAZON_LWA_CLIENT_SECRET".to_string()
+}
+
+fn default_refresh_token_env() -> String {
+    "AMAZON_LWA_REFRESH_TOKEN".to_string()
+}
+
+fn default_initial_lookback_days() -> i64 {
+    7
+}
+
+fn default_order_interval_secs() -> u64 {
+    900
+}
+
+fn default_inventory_interval_secs() -> u64 {
+    3600
+}
+
+fn default_max_throttle_wait_secs() -> u64 {
+    90
+}
+
+fn default_max_throttle_retries() -> u32 {
+    5
+}
+
+fn default_throttle_pause_secs() -> u64 {
+    900
+}
+
+impl Default for AmazonConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: default_endpoint(),
+            token_url: default_token_url(),
+            marketplace_id: default_marketplace_id(),
+            client_id_env: default_client_id_env(),
+            client_secret_env: default_client_secret_env(),
+            refresh_token_env: default_refresh_token_env(),
+            order_warehouse_id: Uuid::nil(),
+            fba_warehouse: None,
+            initial_lookback_days: default_initial_lookback_days(),
+            order_interval_secs: default_order_interval_secs(),
+            inventory_interval_secs: default_inventory_interval_secs(),
+            max_throttle_wait_secs: default_max_throttle_wait_secs(),
+            max_throttle_retries: default_max_throttle_retries(),
+            throttle_pause_secs: default_throttle_pause_secs(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum AmazonError {
+    #[error("Amazon integration is disabled")]
+    Disabled,
+
+    #[error("Amazon integration is misconfigured: {0}")]
+    Misconfigured(String),
+
+    #[error("An Amazon {0} sync is already running")]
+    AlreadyRunning(&'static str),
+
+    /// Out of rate limit capacity; the run stops and the next one picks up.
+    #[error("Throttled by Amazon: {0}")]
+    Throttled(String),
+
+    #[error("Rejected by Amazon: {0}")]
+    Rejected(String),
+
+    #[error("Amazon unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("{0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error(transparent)]
+    Job(#[from] JobError),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for AmazonError {
+    fn into_response(self) -> Response {
+        if let AmazonError::Job(e) = self {
+            return e.into_response();
+        }
+        let (status, code) = match &self {
+            AmazonError::Disabled => (StatusCode::SERVICE_UNAVAILABLE, "amazon_unavailable"),
+            AmazonError::Misconfigured(_) => (StatusCode::SERVICE_UNAVAILABLE, "amazon_misconfigured"),
+            AmazonError::AlreadyRunning(_) => (StatusCode::CONFLICT, "sync_running"),
+            AmazonError::Throttled(_) => (StatusCode::TOO_MANY_REQUESTS, "amazon_throttled"),
+            AmazonError::Rejected(_) => (StatusCode::BAD_GATEWAY, "amazon_rejected"),
+            AmazonError::Unavailable(_) => (StatusCode::BAD_GATEWAY, "amazon_unavailable"),
+            AmazonError::NotFound(_) => (StatusCode::NOT_FOUND, "not_found"),
+            AmazonError::Invalid(_) => (StatusCode::BAD_REQUEST, "invalid_request"),
+            AmazonError::Job(_) => unreachable!("job errors respond on their own"),
+            AmazonError::Database(e) => {
+                error!("Amazon sync query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "amazon_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Money {
+    pub currency_code: Option<String>,
+    pub amount: Option<String>,
+}
+
+impl Money {
+    fn value(&self) -> Decimal {
+        self.amount.as_deref().and_then(|a| Decimal::from_str(a).ok()).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct Address {
+    pub name: Option<String>,
+    pub address_line1: Option<String>,
+    pub address_line2: Option<String>,
+    pub address_line3: Option<String>,
+    pub city: Option<String>,
+    pub state_or_region: Option<String>,
+    pub postal_code: Option<String>,
+    pub country_code: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BuyerInfo {
+    pub buyer_email: Option<String>,
+    pub buyer_name: Option<String>,
+}
+
+/// An order as returned by `getOrders`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct MarketplaceOrder {
+    pub amazon_order_id: String,
+    pub purchase_date: DateTime<Utc>,
+    pub last_update_date: DateTime<Utc>,
+    pub order_status: String,
+    /// `AFN` (FBA) or `MFN` (merchant fulfilled).
+    pub fulfillment_channel: Option<String>,
+    pub order_total: Option<Money>,
+    pub buyer_info: Option<BuyerInfo>,
+    pub shipping_address: Option<Address>,
+}
+
+impl MarketplaceOrder {
+    pub fn channel(&self) -> FulfillmentChannel {
+        match self.fulfillment_channel.as_deref() {
+            Some("AFN") => FulfillmentChannel::Afn,
+            _ => FulfillmentChannel::Mfn,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct OrderItem {
+    #[serde(rename = "ASIN")]
+    pub asin: String,
+    #[serde(rename = "SellerSKU")]
+    pub seller_sku: Option<String>,
+    pub order_item_id: String,
+    pub title: Option<String>,
+    pub quantity_ordered: i32,
+    /// Price of all units of the item.
+    pub item_price: Option<Money>,
+    pub promotion_discount: Option<Money>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InventoryDetails {
+    pub fulfillable_quantity: Option<i32>,
+    pub inbound_working_quantity: Option<i32>,
+    pub inbound_shipped_quantity: Option<i32>,
+    pub inbound_receiving_quantity: Option<i32>,
+}
+
+/// FBA stock of one seller SKU, from `getInventorySummaries`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InventorySummary {
+    pub asin: Option<String>,
+    pub seller_sku: String,
+    pub product_name: Option<String>,
+    pub inventory_details: Option<InventoryDetails>,
+    pub total_quantity: Option<i32>,
+}
+
+/// Amazon order item as stored on `amazon_orders.items`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemRef {
+    pub order_item_id: String,
+    pub seller_sku: String,
+    pub sku: String,
+    pub quantity: i32,
+}
+
+/// Our status for an Amazon order status; `None` for orders not to ingest yet, e.g.
+/// `Pending` orders whose payment has not cleared and which carry no address.
+pub fn order_status(amazon_status: &str) -> Option<OrderStatus> {
+    match amazon_status {
+        "Unshipped" => Some(OrderStatus::Pending),
+        "PartiallyShipped" => Some(OrderStatus::Processing),
+        "Shipped" | "InvoiceUnconfirmed" => Some(OrderStatus::Shipped),
+        "Canceled" | "Unfulfillable" => Some(OrderStatus::Cancelled),
+        _ => None,
+    }
+}
+
+pub fn format_address(address: &Address) -> String {
+    let region = [address.state_or_region.as_deref(), address.postal_code.as_deref()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" ");
+    [
+        address.name.clone(),
+        address.address_line1.clone(),
+        address.address_line2.clone(),
+        address.address_line3.clone(),
+        address.city.clone(),
+        Some(region),
+        address.country_code.clone(),
+    ]
+    .into_iter()
+    .flatten()
+    .filter(|part| !part.trim().is_empty())
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+/// Unit prices of an order item in cents: `(sale, original, discount)`. Amazon prices the
+/// whole line, so amounts are divided by the quantity.
+pub fn unit_prices_cents(item: &OrderItem) -> (i32, i32, i32) {
+    let quantity = Decimal::from(item.quantity_ordered.max(1));
+    let cents = |money: &Option<Money>| {
+        let unit = money.as_ref().map(Money::value).unwrap_or_default() / quantity * Decimal::ONE_HUNDRED;
+        unit.round().to_i32().unwrap_or(0).max(0)
+    };
+    let original = cents(&item.item_price);
+    let discount = cents(&item.promotion_discount).min(original);
+    (original - discount, original, discount)
+}
+
+/// Sellable and inbound FBA units of a summary.
+pub fn fba_quantities(summary: &InventorySummary) -> (i32, i32) {
+    let details = summary.inventory_details.clone().unwrap_or_default();
+    let available = details.fulfillable_quantity.or(summary.total_quantity).unwrap_or(0);
+    let inbound = details.inbound_working_quantity.unwrap_or(0)
+        + details.inbound_shipped_quantity.unwrap_or(0)
+        + details.inbound_receiving_quantity.unwrap_or(0);
+    (available.max(0), inbound.max(0))
+}
+
+/// Carrier codes of the `confirmShipment` operation.
+pub fn carrier_code(carrier: ShippingCarrier) -> &'static str {
+    match carrier {
+        ShippingCarrier::UPS => "UPS",
+        ShippingCarrier::FedEx => "FedEx",
+        ShippingCarrier::USPS => "USPS",
+        ShippingCarrier::DHL => "DHL",
+    }
+}
+
+/// `packageDetail` confirming every item of an order shipped in one package.
+pub fn package_detail(items: &[ItemRef], carrier: ShippingCarrier, tracking_number: &str, ship_date: DateTime<Utc>) -> Value {
+    json!({
+        "packageReferenceId": "1",
+        "carrierCode": carrier_code(carrier),
+        "trackingNumber": tracking_number,
+        "shipDate": ship_date.to_rfc3339(),
+        "orderItems": items
+            .iter()
+            .map(|item| json!({ "orderItemId": item.order_item_id, "quantity": item.quantity }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SyncKind {
+    Orders,
+    Inventory,
+}
+
+impl SyncKind {
+    pub fn job_kind(self) -> &'static str {
+        match self {
+            SyncKind::Orders => ORDERS_JOB_KIND,
+            SyncKind::Inventory => INVENTORY_JOB_KIND,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SyncKind::Orders => "orders",
+            SyncKind::Inventory => "inventory",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OrderSyncSummary {
+    pub fetched: usize,
+    pub created: usize,
+    pub updated: usize,
+    /// Orders not ingested yet, e.g. still `Pending` on Amazon.
+    pub skipped: usize,
+    pub confirmed: usize,
+    pub confirmation_failures: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct InventorySyncSummary {
+    pub skus: usize,
+    pub created: usize,
+    pub updated: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ListingInput {
+    pub asin: String,
+    pub sku: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AmazonOrderFilter {
+    pub fulfillment_channel: Option<FulfillmentChannel>,
+    /// Merchant-fulfilled orders whose shipment Amazon has not accepted yet.
+    #[serde(default)]
+    pub unconfirmed: bool,
+}
+
+/// Clears a sync's running flag when its job ends, however it ends.
+struct RunGuard(Arc<AtomicBool>);
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Pulls marketplace orders and FBA stock from Amazon and pushes shipment confirmations
+/// for merchant-fulfilled orders. Syncs run as jobs, one of each kind at a time.
+pub struct AmazonSync {
+    db: Arc<DatabaseConnection>,
+    client: SpApiClient,
+    config: AmazonConfig,
+    events: EventSender,
+    running: HashMap<SyncKind, Arc<AtomicBool>>,
+    paused_until: Mutex<HashMap<SyncKind, DateTime<Utc>>>,
+}
+
+impl AmazonSync {
+    pub fn new(db: Arc<DatabaseConnection>, client: SpApiClient, config: AmazonConfig, events: EventSender) -> Self {
+        let running = [SyncKind::Orders, SyncKind::Inventory]
+            .into_iter()
+            .map(|kind| (kind, Arc::new(AtomicBool::new(false))))
+            .collect();
+        Self { db, client, config, events, running, paused_until: Mutex::new(HashMap::new()) }
+    }
+
+    /// Whether scheduled runs of `kind` are held back after throttling.
+    pub fn is_paused(&self, kind: SyncKind) -> bool {
+        let paused = self.paused_until.lock().expect("pause lock");
+        paused.get(&kind).is_some_and(|until| *until > Utc::now())
+    }
+
+    fn pause(&self, kind: SyncKind) {
+        let until = Utc::now() + Duration::seconds(self.config.throttle_pause_secs as i64);
+        warn!(sync = kind.label(), %until, "Amazon sync throttled; scheduled runs paused");
+        self.paused_until.lock().expect("pause lock").insert(kind, until);
+    }
+
+    async fn cursor(&self, name: &str) -> Result<Option<DateTime<Utc>>, AmazonError> {
+        Ok(AmazonSyncCursor::find_by_id(name.to_string())
+            .one(self.db.as_ref())
+            .await?
+            .map(|c| c.synced_until))
+    }
+
+    async fn save_cursor(&self, name: &str, synced_until: DateTime<Utc>) -> Result<(), AmazonError> {
+        let cursor = amazon_sync_cursor::ActiveModel {
+            name: Set(name.to_string()),
+            synced_until: Set(synced_until),
+            updated_at: Set(Utc::now()),
+        };
+        if AmazonSyncCursor::find_by_id(name.to_string()).one(self.db.as_ref()).await?.is_some() {
+            cursor.update(self.db.as_ref()).await?;
+        } else {
+            cursor.insert(self.db.as_ref()).await?;
+        }
+        Ok(())
+    }
+
+    /// Our SKU for a seller SKU, recording the mapping the first time it is seen.
+    async fn map_sku<C: ConnectionTrait>(&self, db: &C, seller_sku: &str, asin: &str) -> Result<String, AmazonError> {
+        if let Some(listing) = AmazonListing::find_by_id(seller_sku.to_string()).one(db).await? {
+            return Ok(listing.sku);
+        }
+        let now = Utc::now();
+        amazon_listing::ActiveModel {
+            seller_sku: Set(seller_sku.to_string()),
+            asin: Set(asin.to_string()),
+            sku: Set(seller_sku.to_string()),
+            auto_mapped: Set(true),
+            updated_by: Set(SYSTEM_ACTOR.to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(db)
+        .await?;
+        info!(seller_sku, asin, "Mapped new Amazon listing to the SKU of the same name");
+        Ok(seller_sku.to_string())
+    }
+
+    /// Ingests orders updated since the last complete run, then confirms shipped
+    /// merchant-fulfilled orders. The cursor only advances when every page was processed.
+    pub async fn sync_orders(&self, context: Option<&JobContext>) -> Result<OrderSyncSummary, AmazonError> {
+        let until = Utc::now() - Duration::minutes(SETTLE_MINUTES);
+        let since = self
+            .cursor(ORDERS_JOB_KIND)
+            .await?
+            .unwrap_or(until - Duration::days(self.config.initial_lookback_days.max(1)));
+        let mut summary = OrderSyncSummary::default();
+        let mut next_token = None;
+        loop {
+            let page = self.client.orders(since, until, next_token).await?;
+            for order in page.items {
+                summary.fetched += 1;
+                self.ingest(order, &mut summary).await?;
+            }
+            if let Some(context) = context {
+                context.report_progress(50, format!("{} orders fetched", summary.fetched)).await;
+            }
+            match page.next_token {
+                Some(next) => next_token = Some(next),
+                None => break,
+            }
+        }
+        self.confirm_shipments(&mut summary).await?;
+        self.save_cursor(ORDERS_JOB_KIND, until).await?;
+        info!(?summary, "Amazon order sync finished");
+        Ok(summary)
+    }
+
+    async fn ingest(&self, marketplace: MarketplaceOrder, summary: &mut OrderSyncSummary) -> Result<(), AmazonError> {
+        let Some(status) = order_status(&marketplace.order_status) else {
+            summary.skipped += 1;
+            return Ok(());
+        };
+        match AmazonOrder::find_by_id(marketplace.amazon_order_id.clone()).one(self.db.as_ref()).await? {
+            Some(existing) => {
+                if existing.last_update_date < marketplace.last_update_date {
+                    self.apply_update(existing, &marketplace, status).await?;
+                    summary.updated += 1;
+                }
+            }
+            None => {
+                self.create_order(&marketplace, status).await?;
+                summary.created += 1;
+            }
+        }
+        Ok(())
+    }
+
+    async fn create_order(&self, marketplace: &MarketplaceOrder, status: OrderStatus) -> Result<(), AmazonError> {
+        let items = self.client.order_items(&marketplace.amazon_order_id).await?;
+        let buyer = marketplace.buyer_info.clone().unwrap_or_default();
+        let address = marketplace.shipping_address.clone().unwrap_or_default();
+        let now = Utc::now();
+        let shipped = status == OrderStatus::Shipped;
+
+        let txn = self.db.begin().await?;
+        let order = order::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            order_number: Set(format!("AMZ-{}", marketplace.amazon_order_id)),
+            customer_name: Set(buyer.buyer_name.or(address.name.clone()).unwrap_or_default().into()),
+            customer_email: Set(buyer.buyer_email.unwrap_or_default()),
+            delivery_address: Set(format_address(&address).into()),
+            notes: Set(None),
+            warehouse_id: Set(self.config.order_warehouse_id),
+            order_status: Set(status),
+            fulfillment_type: Set(FulfillmentType::Standard),
+            delivery_type: Set(DeliveryType::Home),
+            is_cod: Set(false),
+            is_replacement_order: Set(false),
+            tracking_number: Set(None),
+            seller_note: Set(None),
+            source: Set(Some("amazon".to_string())),
+            created_date: Set(marketplace.purchase_date),
+            updated_date: Set(Some(now)),
+            delivery_date: Set(None),
+            cancel_order_sla_time: Set(None),
+            cancel_reason: Set(None),
+            cancellation_initiator: Set(None),
+        }
+        .insert(&txn)
+        .await?;
+
+        let mut refs = Vec::with_capacity(items.len());
+        for item in &items {
+            let seller_sku = item.seller_sku.clone().unwrap_or_else(|| item.asin.clone());
+            let sku = self.map_sku(&txn, &seller_sku, &item.asin).await?;
+            let (sale, original, discount) = unit_prices_cents(item);
+            txn.execute(dialect::statement(
+                &txn,
+                INSERT_LINE_SQL,
+                [
+                    Uuid::new_v4().into(),
+                    order.id.into(),
+                    item.title.clone().unwrap_or_else(|| sku.clone()).into(),
+                    item.quantity_ordered.into(),
+                    sale.into(),
+                    original.into(),
+                    discount.into(),
+                    item.asin.clone().into(),
+                    sku.clone().into(),
+                    seller_sku.clone().into(),
+                    if shipped { "Shipped" } else { "Pending" }.into(),
+                    now.into(),
+                ],
+            ))
+            .await?;
+            refs.push(ItemRef {
+                order_item_id: item.order_item_id.clone(),
+                seller_sku,
+                sku,
+                quantity: item.quantity_ordered,
+            });
+        }
+
+        amazon_order::ActiveModel {
+            amazon_order_id: Set(marketplace.amazon_order_id.clone()),
+            order_id: Set(order.id),
+            fulfillment_channel: Set(marketplace.channel()),
+            amazon_status: Set(marketplace.order_status.clone()),
+            purchase_date: Set(marketplace.purchase_date),
+            last_update_date: Set(marketplace.last_update_date),
+            items: Set(serde_json::to_value(&refs).expect("item refs serialize")),
+            shipment_confirmed_at: Set(None),
+            confirmation_error: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(&txn)
+        .await?;
+        txn.commit().await?;
+
+        let _ = self.events.send(Event::OrderCreated(order.id));
+        if shipped {
+            let _ = self.events.send(Event::OrderShipped(order.id));
+        }
+        debug!(amazon_order_id = %marketplace.amazon_order_id, order_id = %order.id, "Ingested Amazon order");
+        Ok(())
+    }
+
+    /// Carries Amazon-side cancellations, and shipments of FBA orders, over to our order.
+    async fn apply_update(
+        &self,
+        existing: amazon_order::Model,
+        marketplace: &MarketplaceOrder,
+        status: OrderStatus,
+    ) -> Result<(), AmazonError> {
+        let txn = self.db.begin().await?;
+        let order = Order::find_by_id(existing.order_id).one(&txn).await?;
+        let channel = existing.fulfillment_channel;
+        let mut event = None;
+        if let Some(order) = order {
+            let settled = matches!(order.order_status, OrderStatus::Shipped | OrderStatus::Delivered | OrderStatus::Cancelled);
+            let change = match status {
+                OrderStatus::Cancelled if !settled => Some(Event::OrderCancelled(order.id)),
+                OrderStatus::Shipped if channel == FulfillmentChannel::Afn && !settled => Some(Event::OrderShipped(order.id)),
+                _ => None,
+            };
+            if change.is_some() {
+                let mut active: order::ActiveModel = order.into();
+                if status == OrderStatus::Cancelled {
+                    active.cancel_reason = Set(Some("Cancelled on Amazon".to_string()));
+                    active.cancellation_initiator = Set(Some("amazon".to_string()));
+                }
+                active.order_status = Set(status);
+                active.updated_date = Set(Some(Utc::now()));
+                active.update(&txn).await?;
+                event = change;
+            }
+        }
+        let mut active: amazon_order::ActiveModel = existing.into();
+        active.amazon_status = Set(marketplace.order_status.clone());
+        active.last_update_date = Set(marketplace.last_update_date);
+        active.updated_at = Set(Utc::now());
+        active.update(&txn).await?;
+        txn.commit().await?;
+        if let Some(event) = event {
+            let _ = self.events.send(event);
+        }
+        Ok(())
+    }
+
+    /// Confirms merchant-fulfilled orders we shipped but Amazon has not been told about.
+    async fn confirm_shipments(&self, summary: &mut OrderSyncSummary) -> Result<(), AmazonError> {
+        let pending = AmazonOrder::find()
+            .filter(amazon_order::Column::FulfillmentChannel.eq(FulfillmentChannel::Mfn))
+            .filter(amazon_order::Column::ShipmentConfirmedAt.is_null())
+            .filter(amazon_order::Column::AmazonStatus.is_in(["Unshipped", "PartiallyShipped"]))
+            .all(self.db.as_ref())
+            .await?;
+        for amazon in pending {
+            let Some(order) = Order::find_by_id(amazon.order_id).one(self.db.as_ref()).await? else { continue };
+            if !matches!(order.order_status, OrderStatus::Shipped | OrderStatus::Delivered) {
+                continue;
+            }
+            // Shipments reference orders by tracking number; they carry no order UUID
+            let Some(tracking) = order.tracking_number.as_deref() else { continue };
+            let Some(shipment) = Shipment::find()
+                .filter(shipment::Column::TrackingNumber.eq(tracking))
+                .one(self.db.as_ref())
+                .await?
+            else {
+                continue;
+            };
+            let items: Vec<ItemRef> = serde_json::from_value(amazon.items.clone())
+                .map_err(|e| AmazonError::Invalid(format!("Stored items of {} are unreadable: {}", amazon.amazon_order_id, e)))?;
+            let ship_date = shipment.shipped_at.map(|t| t.with_timezone(&Utc)).unwrap_or_else(Utc::now);
+            let package = package_detail(&items, shipment.carrier, &shipment.tracking_number, ship_date);
+            let outcome = self.client.confirm_shipment(&amazon.amazon_order_id, &package).await;
+            let amazon_order_id = amazon.amazon_order_id.clone();
+            let mut active: amazon_order::ActiveModel = amazon.into();
+            active.updated_at = Set(Utc::now());
+            match outcome {
+                Ok(()) => {
+                    summary.confirmed += 1;
+                    active.shipment_confirmed_at = Set(Some(Utc::now()));
+                    active.confirmation_error = Set(None);
+                }
+                Err(AmazonError::Rejected(message)) => {
+                    summary.confirmation_failures += 1;
+                    warn!(amazon_order_id, "Amazon rejected the shipment confirmation: {}", message);
+                    active.confirmation_error = Set(Some(message));
+                }
+                Err(e) => return Err(e),
+            }
+            active.update(self.db.as_ref()).await?;
+        }
+        Ok(())
+    }
+
+    /// Mirrors FBA stock onto inventory items in the FBA warehouse.
+    pub async fn sync_inventory(&self, context: Option<&JobContext>) -> Result<InventorySyncSummary, AmazonError> {
+        let warehouse = self
+            .config
+            .fba_warehouse
+            .ok_or_else(|| AmazonError::Misconfigured("amazon.fba_warehouse is not set".to_string()))?;
+        let mut summary = InventorySyncSummary::default();
+        let mut next_token = None;
+        loop {
+            let page = self.client.inventory(next_token).await?;
+            for stock in page.items {
+                summary.skus += 1;
+                self.apply_stock(warehouse, &stock, &mut summary).await?;
+            }
+            if let Some(context) = context {
+                context.report_progress(50, format!("{} FBA SKUs synced", summary.skus)).await;
+            }
+            match page.next_token {
+                Some(next) => next_token = Some(next),
+                None => break,
+            }
+        }
+        self.save_cursor(INVENTORY_JOB_KIND, Utc::now()).await?;
+        info!(?summary, "Amazon FBA inventory sync finished");
+        Ok(summary)
+    }
+
+    async fn apply_stock(
+        &self,
+        warehouse: i32,
+        stock: &InventorySummary,
+        summary: &mut InventorySyncSummary,
+    ) -> Result<(), AmazonError> {
+        let db = self.db.as_ref();
+        let sku = self.map_sku(db, &stock.seller_sku, stock.asin.as_deref().unwrap_or_default()).await?;
+        let (available, inbound) = fba_quantities(stock);
+        let existing = InventoryItem::find()
+            .filter(inventory_items::Column::Sku.eq(sku.as_str()))
+            .filter(inventory_items::Column::Warehouse.eq(warehouse))
+            .one(db)
+            .await?;
+        match existing {
+            Some(item) if item.available == available && item.incoming == inbound => return Ok(()),
+            Some(item) => {
+                let mut active: inventory_items::ActiveModel = item.into();
+                active.available = Set(available);
+                active.incoming = Set(inbound);
+                active.last_movement_date = Set(Some(Utc::now()));
+                active.update(db).await?;
+                summary.updated += 1;
+            }
+            None => {
+                let today = Utc::now().date_naive();
+                let item = inventory_items::Model::new(
+                    Uuid::new_v4().to_string(),
+                    sku.clone(),
+                    stock.product_name.clone().unwrap_or_default(),
+                    String::new(),
+                    inbound,
+                    String::new(),
+                    warehouse,
+                    today,
+                    String::new(),
+                    available,
+                    today,
+                    today,
+                    String::new(),
+                )
+                .map_err(|e| AmazonError::Invalid(format!("FBA stock of {} is invalid: {}", sku, e)))?;
+                item.into_active_model().reset_all().insert(db).await?;
+                summary.created += 1;
+            }
+        }
+        let _ = self.events.send(Event::InventoryLevelChanged { warehouse_id: warehouse, sku, available });
+        Ok(())
+    }
+
+    pub async fn list_listings(
+        &self,
+        sku: Option<String>,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<amazon_listing::Model>, u64), AmazonError> {
+        let mut query = AmazonListing::find();
+        if let Some(sku) = sku {
+            query = query.filter(amazon_listing::Column::Sku.eq(sku));
+        }
+        let paginator = query
+            .order_by_asc(amazon_listing::Column::SellerSku)
+            .paginate(self.db.as_ref(), pagination.limit());
+        let total = paginator.num_items().await?;
+        let items = paginator.fetch_page(pagination.page_index()).await?;
+        Ok((items, total))
+    }
+
+    /// Maps a seller SKU and ASIN to one of our SKUs, replacing a learned mapping.
+    pub async fn set_listing(
+        &self,
+        seller_sku: &str,
+        input: ListingInput,
+        actor: &str,
+    ) -> Result<amazon_listing::Model, AmazonError> {
+        if input.asin.trim().len() != 10 || input.sku.trim().is_empty() {
+            return Err(AmazonError::Invalid("An ASIN has 10 characters and the SKU is required".to_string()));
+        }
+        let db = self.db.as_ref();
+        let now = Utc::now();
+        let existing = AmazonListing::find_by_id(seller_sku.to_string()).one(db).await?;
+        let mut listing = amazon_listing::ActiveModel {
+            seller_sku: Set(seller_sku.to_string()),
+            asin: Set(input.asin.trim().to_uppercase()),
+            sku: Set(input.sku.trim().to_string()),
+            auto_mapped: Set(false),
+            updated_by: Set(actor.to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+        };
+        Ok(match existing {
+            Some(existing) => {
+                listing.created_at = Set(existing.created_at);
+                listing.update(db).await?
+            }
+            None => listing.insert(db).await?,
+        })
+    }
+
+    pub async fn remove_listing(&self, seller_sku: &str) -> Result<(), AmazonError> {
+        let result = AmazonListing::delete_by_id(seller_sku.to_string()).exec(self.db.as_ref()).await?;
+        if result.rows_affected == 0 {
+            return Err(AmazonError::NotFound(format!("Amazon listing not found: {}", seller_sku)));
+        }
+        Ok(())
+    }
+
+    pub async fn list_orders(
+        &self,
+        filter: AmazonOrderFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<amazon_order::Model>, u64), AmazonError> {
+        let mut query = AmazonOrder::find();
+        if let Some(channel) = filter.fulfillment_channel {
+            query = query.filter(amazon_order::Column::FulfillmentChannel.eq(channel));
+        }
+        if filter.unconfirmed {
+            query = query
+                .filter(amazon_order::Column::FulfillmentChannel.eq(FulfillmentChannel::Mfn))
+                .filter(amazon_order::Column::ShipmentConfirmedAt.is_null());
+        }
+        let paginator = query
+            .order_by_desc(amazon_order::Column::PurchaseDate)
+            .paginate(self.db.as_ref(), pagination.limit());
+        let total = paginator.num_items().await?;
+        let items = paginator.fetch_page(pagination.page_index()).await?;
+        Ok((items, total))
+    }
+}
+
+const INSERT_LINE_SQL: &str = r#"
+INSERT INTO order_line_items
+    (id, order_id, product_name, quantity, sale_price, original_price, seller_discount, unit,
+     product_id, brand, stock_code, size, seller_sku, sku_id, sku_image, sku_name, sku_type,
+     dropship, status, created_date)
+VALUES ($1, $2, $3, $4, $5, $6, $7, 'pcs', $8, '', '', '', $9, $10, '', $3, 'amazon', FALSE, $11, $12)
+"#;
+
+/// Starts a sync as a job and returns the job to poll. Refused while the same sync runs.
+pub async fn submit_sync(
+    runner: &JobRunner,
+    sync: Arc<AmazonSync>,
+    kind: SyncKind,
+    created_by: Option<String>,
+) -> Result<Uuid, AmazonError> {
+    let running = sync.running[&kind].clone();
+    if running.swap(true, Ordering::SeqCst) {
+        return Err(AmazonError::AlreadyRunning(kind.label()));
+    }
+    let guard = RunGuard(running);
+    let submitted = runner
+        .submit(kind.job_kind(), created_by, move |context| async move {
+            let _guard = guard;
+            let result = match kind {
+                SyncKind::Orders => sync.sync_orders(Some(&context)).await.map(|s| serde_json::to_value(s)),
+                SyncKind::Inventory => sync.sync_inventory(Some(&context)).await.map(|s| serde_json::to_value(s)),
+            };
+            match result {
+                Ok(value) => value.map_err(|e| e.to_string()),
+                Err(e) => {
+                    if matches!(e, AmazonError::Throttled(_)) {
+                        sync.pause(kind);
+                    }
+                    Err(e.to_string())
+                }
+            }
+        })
+        .await?;
+    Ok(submitted)
+}
+
+/// Runs the order and inventory syncs on their intervals through the job runner. Ticks
+/// are skipped while a sync is still running or paused after being throttled.
+pub fn spawn_scheduler(runner: Arc<JobRunner>, sync: Arc<AmazonSync>) {
+    let order_every = std::time::Duration::from_secs(sync.config.order_interval_secs.max(60));
+    let inventory_every = std::time::Duration::from_secs(sync.config.inventory_interval_secs.max(60));
+    tokio::spawn(async move {
+        let mut orders = tokio::time::interval(order_every);
+        let mut inventory = tokio::time::interval(inventory_every);
+        orders.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        inventory.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            let kind = tokio::select! {
+                _ = orders.tick() => SyncKind::Orders,
+                _ = inventory.tick() => SyncKind::Inventory,
+            };
+            if kind == SyncKind::Inventory && sync.config.fba_warehouse.is_none() {
+                continue;
+            }
+            if sync.is_paused(kind) {
+                debug!(sync = kind.label(), "Amazon sync paused after throttling");
+                continue;
+            }
+            match submit_sync(&runner, sync.clone(), kind, Some(SYSTEM_ACTOR.to_string())).await {
+                Ok(_) | Err(AmazonError::AlreadyRunning(_)) => {}
+                Err(e) => error!(sync = kind.label(), "Failed to start Amazon sync: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn money(amount: &str) -> Option<Money> {
+        Some(Money { currency_code: Some("USD".to_string()), amount: Some(amount.to_string()) })
+    }
+
+    fn item(quantity: i32, price: &str, discount: Option<&str>) -> OrderItem {
+        OrderItem {
+            asin: "B000000001".to_string(),
+            seller_sku: Some("TEE-M".to_string()),
+            order_item_id: "11111111111111".to_string(),
+            title: Some("Classic Tee".to_string()),
+            quantity_ordered: quantity,
+            item_price: money(price),
+            promotion_discount: discount.and_then(money),
+        }
+    }
+
+    #[test]
+    fn test_order_statuses() {
+        assert_eq!(order_status("Pending"), None);
+        assert_eq!(order_status("Unshipped"), Some(OrderStatus::Pending));
+        assert_eq!(order_status("InvoiceUnconfirmed"), Some(OrderStatus::Shipped));
+        assert_eq!(order_status("Canceled"), Some(OrderStatus::Cancelled));
+    }
+
+    #[test]
+    fn test_line_prices_are_per_unit_cents() {
+        assert_eq!(unit_prices_cents(&item(2, "39.98", None)), (1999, 1999, 0));
+        assert_eq!(unit_prices_cents(&item(3, "30.00", Some("4.50"))), (850, 1000, 150));
+        assert_eq!(unit_prices_cents(&item(1, "0.00", Some("5.00"))), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_address_skips_missing_parts() {
+        let address = Address {
+            name: Some("Jo Doe".to_string()),
+            address_line1: Some("1 Main St".to_string()),
+            city: Some("Seattle".to_string()),
+            state_or_region: Some("WA".to_string()),
+            postal_code: Some("98101".to_string()),
+            country_code: Some("US".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(format_address(&address), "Jo Doe, 1 Main St, Seattle, WA 98101, US");
+        assert_eq!(format_address(&Address::default()), "");
+    }
+
+    #[test]
+    fn test_fba_quantities() {
+        let summary = InventorySummary {
+            asin: Some("B000000001".to_string()),
+            seller_sku: "TEE-M".to_string(),
+            product_name: None,
+            inventory_details: Some(InventoryDetails {
+                fulfillable_quantity: Some(40),
+                inbound_working_quantity: Some(5),
+                inbound_shipped_quantity: Some(10),
+                inbound_receiving_quantity: None,
+            }),
+            total_quantity: Some(55),
+        };
+        assert_eq!(fba_quantities(&summary), (40, 15));
+    }
+
+    #[test]
+    fn test_package_detail_lists_every_item() {
+        let items = vec![ItemRef {
+            order_item_id: "111".to_string(),
+            seller_sku: "TEE-M".to_string(),
+            sku: "TEE-M".to_string(),
+            quantity: 2,
+        }];
+        let shipped = Utc.with_ymd_and_hms(2026, 10, 2, 15, 0, 0).unwrap();
+        let package = package_detail(&items, ShippingCarrier::FedEx, "7946", shipped);
+        assert_eq!(package["carrierCode"], "FedEx");
+        assert_eq!(package["orderItems"][0]["quantity"], 2);
+        assert_eq!(package["shipDate"], "2026-10-02T15:00:00+00:00");
+    }
+}
@@ -0,0 +1,4 @@
+// integrations/mod.rs
+
+pub mod accounting;
+pub mod amazon;
@@ -0,0 +1,273 @@
+// inventory_levels/mod.rs
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use sea_orm::{
+    ColumnTrait, DatabaseConnection, DbErr, EntityTrait, FromQueryResult, PaginatorTrait, QueryFilter,
+    QueryOrder, TransactionTrait, Value,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::change_feed::{self, ChangeFeedError, SyncEntity, SyncParams};
+use crate::db::dialect;
+use crate::events::{Event, EventSender};
+use crate::models::inventory_items::{self, Entity as InventoryItem};
+use crate::utils::pagination::PaginationParams;
+
+/// Largest batch accepted in one request; a nightly 3PL file is split client-side beyond this.
+pub const MAX_BATCH_ROWS: usize = 10_000;
+
+/// Rows per INSERT statement, keeping the parameter count well under Postgres' limit.
+const CHUNK_ROWS: usize = 1_000;
+
+#[derive(Error, Debug)]
+pub enum InventoryLevelError {
+    #[error("Row {row}: {reason}")]
+    InvalidRow { row: usize, reason: String },
+
+    #[error("Batch must contain between 1 and {max} rows, got {got}")]
+    BatchSize { got: usize, max: usize },
+
+    #[error("Missing permission: inventory:write")]
+    Forbidden,
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for InventoryLevelError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            InventoryLevelError::InvalidRow { .. } => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_row"),
+            InventoryLevelError::BatchSize { .. } => (StatusCode::PAYLOAD_TOO_LARGE, "batch_size"),
+            InventoryLevelError::Forbidden => (StatusCode::FORBIDDEN, "forbidden"),
+            InventoryLevelError::Database(e) => {
+                error!("Inventory level query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "inventory_upsert_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// On-hand quantity of a SKU in a warehouse, as reported by the 3PL.
+#[derive(Clone, Debug, Deserialize)]
+pub struct LevelUpdate {
+    pub sku: String,
+    pub warehouse: i32,
+    pub quantity: i32,
+}
+
+/// Which `InventoryLevelChanged` events a batch emits.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EventMode {
+    /// One event per row in the batch.
+    All,
+    /// Only rows whose quantity changed or that were created.
+    #[default]
+    Changed,
+    /// No events, e.g. for an initial load.
+    None,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LevelBatch {
+    pub levels: Vec<LevelUpdate>,
+    #[serde(default)]
+    pub events: EventMode,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct BatchSummary {
+    pub received: usize,
+    pub created: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub events_emitted: usize,
+}
+
+/// Rejects the whole batch on the first bad row, so a partial file is never applied.
+pub fn validate_batch(levels: &[LevelUpdate]) -> Result<(), InventoryLevelError> {
+    if levels.is_empty() || levels.len() > MAX_BATCH_ROWS {
+        return Err(InventoryLevelError::BatchSize { got: levels.len(), max: MAX_BATCH_ROWS });
+    }
+    let mut seen = HashMap::new();
+    for (row, level) in levels.iter().enumerate() {
+        let invalid = |reason: String| InventoryLevelError::InvalidRow { row, reason };
+        if level.sku.trim().is_empty() {
+            return Err(invalid("sku is required".to_string()));
+        }
+        if level.quantity < 0 {
+            return Err(invalid("quantity must be non-negative".to_string()));
+        }
+        // Postgres refuses to update the same row twice in one statement
+        if let Some(first) = seen.insert((level.sku.as_str(), level.warehouse), row) {
+            return Err(invalid(format!("duplicates row {} for {} in warehouse {}", first, level.sku, level.warehouse)));
+        }
+    }
+    Ok(())
+}
+
+/// Upsert of `rows` levels. `$1` is today's date and `$2` the current time; each row binds
+/// id, sku, warehouse and quantity. Unchanged rows are skipped by the conflict clause, so
+/// only created and changed rows are returned.
+pub fn upsert_sql(rows: usize) -> String {
+    let values: Vec<String> = (0..rows)
+        .map(|i| {
+            let p = 3 + i * 4;
+            format!("(${}, ${}, ${}, ${}, '', '', 0, '', $1, '', $1, $1, '', $2)", p, p + 1, p + 2, p + 3)
+        })
+        .collect();
+    format!(
+        "INSERT INTO inventory_items \
+         (id, sku, warehouse, available, description, size, incoming, color, arriving, purchase_order_id, \
+         delivery_date, arrival_date, upc, last_movement_date) \
+         VALUES {} \
+         ON CONFLICT (sku, warehouse) WHERE lot_number IS NULL DO UPDATE SET \
+         available = EXCLUDED.available, last_movement_date = EXCLUDED.last_movement_date \
+         WHERE inventory_items.available <> EXCLUDED.available \
+         RETURNING id, sku, warehouse, available",
+        values.join(", ")
+    )
+}
+
+#[derive(Debug, FromQueryResult)]
+struct Written {
+    id: String,
+    sku: String,
+    warehouse: i32,
+    available: i32,
+}
+
+/// Sets inventory levels in bulk, for 3PL syncs that would take hours row by row.
+pub struct InventoryLevelService {
+    db: Arc<DatabaseConnection>,
+    events: EventSender,
+}
+
+impl InventoryLevelService {
+    pub fn new(db: Arc<DatabaseConnection>, events: EventSender) -> Self {
+        Self { db, events }
+    }
+
+    /// One page of inventory rows by SKU, and the total.
+    pub async fn list(
+        &self,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<inventory_items::Model>, u64), InventoryLevelError> {
+        let paginator = InventoryItem::find()
+            .order_by_asc(inventory_items::Column::Sku)
+            .order_by_asc(inventory_items::Column::Id)
+            .paginate(self.db.as_ref(), pagination.limit());
+        let total = paginator.num_items().await?;
+        let items = paginator.fetch_page(pagination.page_index()).await?;
+        Ok((items, total))
+    }
+
+    /// Inventory rows changed and deleted since `since`, for incremental sync.
+    pub async fn changes(&self, since: DateTime<Utc>, params: &SyncParams) -> Result<Response, ChangeFeedError> {
+        let load = |ids: Vec<String>| {
+            InventoryItem::find()
+                .filter(inventory_items::Column::Id.is_in(ids))
+                .all(self.db.as_ref())
+        };
+        change_feed::sync_response(self.db.as_ref(), SyncEntity::Inventory, since, params, load).await
+    }
+
+    /// Applies the batch in one transaction, then emits events per `batch.events`.
+    pub async fn upsert_batch(&self, batch: LevelBatch) -> Result<BatchSummary, InventoryLevelError> {
+        validate_batch(&batch.levels)?;
+        let now = Utc::now();
+        let ids: Vec<String> = batch.levels.iter().map(|_| Uuid::new_v4().to_string()).collect();
+
+        let txn = self.db.begin().await?;
+        let mut written = Vec::new();
+        for (levels, ids) in batch.levels.chunks(CHUNK_ROWS).zip(ids.chunks(CHUNK_ROWS)) {
+            let mut values: Vec<Value> = vec![now.date_naive().into(), now.into()];
+            for (level, id) in levels.iter().zip(ids) {
+                values.extend([id.as_str().into(), level.sku.as_str().into(), level.warehouse.into(), level.quantity.into()]);
+            }
+            let rows =
+                Written::find_by_statement(dialect::statement(&txn, &upsert_sql(levels.len()), values)).all(&txn).await?;
+            written.extend(rows);
+        }
+        txn.commit().await?;
+
+        let new_ids: HashSet<&str> = ids.iter().map(String::as_str).collect();
+        let created = written.iter().filter(|row| new_ids.contains(row.id.as_str())).count();
+        let mut summary = BatchSummary {
+            received: batch.levels.len(),
+            created,
+            updated: written.len() - created,
+            unchanged: batch.levels.len() - written.len(),
+            events_emitted: 0,
+        };
+        // Receivers lag past the channel capacity, which is why `changed` is the default
+        let changes: Vec<(i32, String, i32)> = match batch.events {
+            EventMode::All => batch.levels.into_iter().map(|l| (l.warehouse, l.sku, l.quantity)).collect(),
+            EventMode::Changed => written.into_iter().map(|w| (w.warehouse, w.sku, w.available)).collect(),
+            EventMode::None => Vec::new(),
+        };
+        for (warehouse_id, sku, available) in changes {
+            let _ = self.events.send(Event::InventoryLevelChanged { warehouse_id, sku, available });
+            summary.events_emitted += 1;
+        }
+        info!(
+            received = summary.received,
+            created = summary.created,
+            updated = summary.updated,
+            "Inventory level batch applied"
+        );
+        Ok(summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(sku: &str, warehouse: i32, quantity: i32) -> LevelUpdate {
+        LevelUpdate { sku: sku.to_string(), warehouse, quantity }
+    }
+
+    #[test]
+    fn test_migration_is_safe() {
+        let plan = crate::db::migration_safety::analyze(
+            "20261017030000_inventory_level_key.sql",
+            include_str!("../../migrations/20261017030000_inventory_level_key.sql"),
+        );
+        assert_eq!(plan.violations().count(), 0);
+    }
+
+    #[test]
+    fn test_validate_batch() {
+        assert!(validate_batch(&[level("A", 1, 5), level("A", 2, 5), level("B", 1, 0)]).is_ok());
+        assert!(matches!(validate_batch(&[]), Err(InventoryLevelError::BatchSize { got: 0, .. })));
+        assert!(matches!(
+            validate_batch(&[level("A", 1, 5), level("B", 1, -1)]),
+            Err(InventoryLevelError::InvalidRow { row: 1, .. })
+        ));
+        assert!(matches!(
+            validate_batch(&[level("A", 1, 5), level("B", 1, 2), level("A", 1, 7)]),
+            Err(InventoryLevelError::InvalidRow { row: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn test_upsert_sql_numbers_rows_after_shared_params() {
+        let sql = upsert_sql(2);
+        assert!(sql.contains("($3, $4, $5, $6, '', '', 0, '', $1, '', $1, $1, '', $2), ($7, $8, $9, $10,"));
+        assert!(sql.contains("WHERE inventory_items.available <> EXCLUDED.available"));
+    }
+}
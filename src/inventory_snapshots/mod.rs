@@ -0,0 +1,252 @@
+// inventory_snapshots/mod.rs
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, NaiveTime, TimeZone, Utc};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QueryOrder,
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::{collections::HashSet, sync::Arc};
+use thiserror::Error;
+use tracing::{error, info};
+
+use crate::db::dialect;
+use crate::models::inventory_snapshot::{self, Entity as InventorySnapshot};
+
+/// Nightly snapshot settings, loaded from the `inventory_snapshots` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct InventorySnapshotConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// UTC hour (0–23) at which the previous day is snapshotted.
+    #[serde(default)]
+    pub hour_utc: u32,
+}
+
+impl Default for InventorySnapshotConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            hour_utc: 0,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SnapshotError {
+    #[error("Invalid `at` value '{0}': expected YYYY-MM-DD or an RFC 3339 timestamp")]
+    InvalidTimestamp(String),
+
+    #[error("Missing permission: inventory:read")]
+    Forbidden,
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for SnapshotError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            SnapshotError::InvalidTimestamp(_) => (StatusCode::BAD_REQUEST, "invalid_timestamp"),
+            SnapshotError::Forbidden => (StatusCode::FORBIDDEN, "forbidden"),
+            SnapshotError::Database(e) => {
+                error!("Inventory snapshot query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "snapshot_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// Aggregates lots per SKU and warehouse; re-running a date overwrites that date.
+const SNAPSHOT_SQL: &str = r#"
+INSERT INTO inventory_snapshots
+    (id, snapshot_date, taken_at, sku, warehouse, on_hand, reserved, allocated, incoming, unit_cost)
+SELECT gen_random_uuid(), $1, $2, sku, warehouse,
+       SUM(available), SUM(COALESCE(reserved_quantity, 0)), SUM(COALESCE(allocated_quantity, 0)),
+       SUM(incoming), AVG(unit_cost)
+FROM inventory_items
+WHERE TRUE
+GROUP BY sku, warehouse
+ON CONFLICT (snapshot_date, sku, warehouse) DO UPDATE SET
+    taken_at = EXCLUDED.taken_at,
+    on_hand = EXCLUDED.on_hand,
+    reserved = EXCLUDED.reserved,
+    allocated = EXCLUDED.allocated,
+    incoming = EXCLUDED.incoming,
+    unit_cost = EXCLUDED.unit_cost
+"#;
+
+/// Parses an `at` query value. A bare date means the end of that day (UTC), so
+/// `at=2024-01-31` returns month-end levels.
+pub fn parse_as_of(value: &str) -> Result<DateTime<Utc>, SnapshotError> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(value) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.succ_opt())
+        .map(|next| Utc.from_utc_datetime(&next.and_time(NaiveTime::MIN)) - ChronoDuration::nanoseconds(1))
+        .ok_or_else(|| SnapshotError::InvalidTimestamp(value.to_string()))
+}
+
+/// The last day that has fully ended by `at`. A snapshot stands for the end of its day,
+/// so it applies to `at` only if that day is over; the end-of-day `at` of a bare date
+/// includes that date.
+pub fn last_full_day(at: DateTime<Utc>) -> NaiveDate {
+    let next_instant = at + ChronoDuration::nanoseconds(1);
+    next_instant.date_naive().pred_opt().expect("date has a predecessor")
+}
+
+/// Next time the nightly snapshot should run, strictly after `now`.
+pub fn next_run_after(now: DateTime<Utc>, hour_utc: u32) -> DateTime<Utc> {
+    let today = now.date_naive().and_hms_opt(hour_utc.min(23), 0, 0).expect("valid hour");
+    let candidate = Utc.from_utc_datetime(&today);
+    if candidate > now {
+        candidate
+    } else {
+        candidate + ChronoDuration::days(1)
+    }
+}
+
+/// Keeps the latest snapshot per warehouse from rows sorted newest first.
+fn latest_per_warehouse(rows: Vec<inventory_snapshot::Model>) -> Vec<inventory_snapshot::Model> {
+    let mut seen = HashSet::new();
+    rows.into_iter().filter(|row| seen.insert(row.warehouse)).collect()
+}
+
+pub struct InventorySnapshotService {
+    db: Arc<DatabaseConnection>,
+}
+
+impl InventorySnapshotService {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    /// Records current levels as the snapshot for `date`. Returns the number of
+    /// SKU/warehouse rows written.
+    pub async fn take_snapshot(&self, date: NaiveDate) -> Result<u64, SnapshotError> {
+        let result = self
+            .db
+            .execute(dialect::statement(
+                self.db.as_ref(),
+                SNAPSHOT_SQL,
+                [date.into(), Utc::now().into()],
+            ))
+            .await?;
+        info!(%date, rows = result.rows_affected(), "Inventory snapshot taken");
+        Ok(result.rows_affected())
+    }
+
+    /// Levels of `sku` as of `at`, one row per warehouse (or only `warehouse` if given):
+    /// the snapshot of the last day over by then. The snapshot of a day is taken after
+    /// midnight, so this goes by the day it represents, not when it was read.
+    pub async fn as_of(
+        &self,
+        sku: &str,
+        warehouse: Option<i32>,
+        at: DateTime<Utc>,
+    ) -> Result<Vec<inventory_snapshot::Model>, SnapshotError> {
+        let mut query = InventorySnapshot::find()
+            .filter(inventory_snapshot::Column::Sku.eq(sku))
+            .filter(inventory_snapshot::Column::SnapshotDate.lte(last_full_day(at)));
+        if let Some(warehouse) = warehouse {
+            query = query.filter(inventory_snapshot::Column::Warehouse.eq(warehouse));
+        }
+        let rows = query
+            .order_by_desc(inventory_snapshot::Column::SnapshotDate)
+            .all(self.db.as_ref())
+            .await?;
+        Ok(latest_per_warehouse(rows))
+    }
+}
+
+/// Snapshots the previous day every night at the configured hour.
+pub fn spawn_scheduler(service: Arc<InventorySnapshotService>, hour_utc: u32) {
+    tokio::spawn(async move {
+        loop {
+            let now = Utc::now();
+            let next = next_run_after(now, hour_utc);
+            tokio::time::sleep((next - now).to_std().unwrap_or_default()).await;
+            let day = next.date_naive().pred_opt().expect("date has a predecessor");
+            if let Err(e) = service.take_snapshot(day).await {
+                error!(%day, "Nightly inventory snapshot failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(warehouse: i32, on_hand: i64) -> inventory_snapshot::Model {
+        inventory_snapshot::Model {
+            id: uuid::Uuid::new_v4(),
+            snapshot_date: NaiveDate::from_ymd_opt(2024, 1, 31).unwrap(),
+            taken_at: Utc::now(),
+            sku: "SKU-1".to_string(),
+            warehouse,
+            on_hand,
+            reserved: 0,
+            allocated: 0,
+            incoming: 0,
+            unit_cost: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_as_of() {
+        let end_of_month = parse_as_of("2024-01-31").unwrap();
+        assert_eq!(end_of_month.date_naive(), NaiveDate::from_ymd_opt(2024, 1, 31).unwrap());
+        assert!(end_of_month < Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap());
+        assert!(end_of_month > Utc.with_ymd_and_hms(2024, 1, 31, 23, 59, 59).unwrap());
+
+        let exact = parse_as_of("2024-01-15T12:00:00+02:00").unwrap();
+        assert_eq!(exact, Utc.with_ymd_and_hms(2024, 1, 15, 10, 0, 0).unwrap());
+
+        assert!(parse_as_of("last tuesday").is_err());
+    }
+
+    #[test]
+    fn test_last_full_day() {
+        let day = |d| NaiveDate::from_ymd_opt(2024, 1, d).unwrap();
+        assert_eq!(last_full_day(parse_as_of("2024-01-31").unwrap()), day(31));
+        assert_eq!(last_full_day(Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap()), day(14));
+        assert_eq!(last_full_day(Utc.with_ymd_and_hms(2024, 1, 16, 0, 0, 0).unwrap()), day(15));
+    }
+
+    #[test]
+    fn test_migration_is_safe() {
+        let plan = crate::db::migration_safety::analyze(
+            "20261017000000_inventory_snapshots.sql",
+            include_str!("../../migrations/20261017000000_inventory_snapshots.sql"),
+        );
+        assert_eq!(plan.violations().count(), 0);
+    }
+
+    #[test]
+    fn test_next_run_after() {
+        let before = Utc.with_ymd_and_hms(2024, 3, 1, 1, 30, 0).unwrap();
+        assert_eq!(next_run_after(before, 2), Utc.with_ymd_and_hms(2024, 3, 1, 2, 0, 0).unwrap());
+        let after = Utc.with_ymd_and_hms(2024, 3, 1, 2, 0, 0).unwrap();
+        assert_eq!(next_run_after(after, 2), Utc.with_ymd_and_hms(2024, 3, 2, 2, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_latest_per_warehouse_keeps_first_seen() {
+        let rows = vec![snapshot(1, 10), snapshot(2, 5), snapshot(1, 99)];
+        let latest = latest_per_warehouse(rows);
+        assert_eq!(latest.len(), 2);
+        assert_eq!(latest[0].on_hand, 10);
+        assert_eq!(latest[1].warehouse, 2);
+    }
+}
@@ -0,0 +1,382 @@
+// jobs/mod.rs
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use lazy_static::lazy_static;
+use prometheus::IntCounterVec;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, Set,
+};
+use serde_json::{json, Value};
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use thiserror::Error;
+use tokio::sync::{broadcast, watch};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::models::job::{self, Entity as Job, JobStatus};
+
+lazy_static! {
+    static ref JOBS_FINISHED: IntCounterVec =
+        IntCounterVec::new(
+            "jobs_finished_total",
+            "Background jobs that reached a terminal status",
+            &["kind", "status"]
+        ).expect("metric can be created");
+}
+
+/// Longest a status request may wait for a job to finish.
+pub const MAX_WAIT: Duration = Duration::from_secs(60);
+
+/// How long a job's heartbeat stays valid. The runner renews it at a third of this, so
+/// only a job whose process stopped renewing it is taken for interrupted.
+pub const LEASE: Duration = Duration::from_secs(90);
+
+#[derive(Error, Debug)]
+pub enum JobError {
+    #[error("Job not found: {0}")]
+    NotFound(Uuid),
+
+    #[error("Not accepting jobs while shutting down")]
+    ShuttingDown,
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+/// Handle given to a running job for reporting progress.
+#[derive(Clone)]
+pub struct JobContext {
+    pub job_id: Uuid,
+    db: Arc<DatabaseConnection>,
+}
+
+impl JobContext {
+    /// Records progress. `percent` is clamped to 0–100. Failures are logged rather than
+    /// returned so that a progress write never fails the job itself.
+    pub async fn report_progress(&self, percent: i32, message: impl Into<String>) {
+        let update = job::ActiveModel {
+            id: sea_orm::Unchanged(self.job_id),
+            progress: Set(clamp_progress(percent)),
+            progress_message: Set(Some(message.into())),
+            updated_at: Set(Utc::now()),
+            ..Default::default()
+        };
+        if let Err(e) = update.update(self.db.as_ref()).await {
+            warn!(job_id = %self.job_id, "Failed to record job progress: {}", e);
+        }
+    }
+}
+
+fn clamp_progress(percent: i32) -> i32 {
+    percent.clamp(0, 100)
+}
+
+/// Counts a job as running until dropped, so the count drops even if the task recording
+/// the job's outcome panics or is cancelled.
+struct RunningGuard(watch::Sender<usize>);
+
+impl RunningGuard {
+    fn new(running: watch::Sender<usize>) -> Self {
+        running.send_modify(|count| *count += 1);
+        Self(running)
+    }
+}
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.send_modify(|count| *count -= 1);
+    }
+}
+
+/// Runs work in the background and records its lifecycle in the `jobs` table, so callers
+/// can return `202 Accepted` with a job id instead of spawning a task whose outcome is lost.
+/// Each runner holds a lease on its unfinished jobs, renewed by [`spawn_heartbeat`], so
+/// instances sharing the table only ever fail jobs whose process is gone.
+pub struct JobRunner {
+    db: Arc<DatabaseConnection>,
+    instance_id: String,
+    finished: broadcast::Sender<Uuid>,
+    /// Jobs started by this process that have not recorded their outcome yet.
+    running: watch::Sender<usize>,
+    draining: AtomicBool,
+}
+
+impl JobRunner {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        let (finished, _) = broadcast::channel(256);
+        let (running, _) = watch::channel(0);
+        Self { db, instance_id: Uuid::new_v4().to_string(), finished, running, draining: AtomicBool::new(false) }
+    }
+
+    /// Queues a job and starts it immediately. The closure receives a [`JobContext`] for
+    /// progress reports; its `Ok` value becomes the job result and its `Err` the job error.
+    /// A panicking job is recorded as failed.
+    pub async fn submit<F, Fut>(&self, kind: &str, created_by: Option<String>, work: F) -> Result<Uuid, JobError>
+    where
+        F: FnOnce(JobContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<Value, String>> + Send + 'static,
+    {
+        if self.draining.load(Ordering::Acquire) {
+            return Err(JobError::ShuttingDown);
+        }
+        let id = Uuid::new_v4();
+        let now = Utc::now();
+        job::ActiveModel {
+            id: Set(id),
+            kind: Set(kind.to_string()),
+            status: Set(JobStatus::Queued),
+            progress: Set(0),
+            progress_message: Set(None),
+            result: Set(None),
+            error: Set(None),
+            created_by: Set(created_by),
+            instance_id: Set(Some(self.instance_id.clone())),
+            heartbeat_at: Set(Some(now)),
+            created_at: Set(now),
+            started_at: Set(None),
+            finished_at: Set(None),
+            updated_at: Set(now),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+
+        let db = self.db.clone();
+        let finished = self.finished.clone();
+        let running = RunningGuard::new(self.running.clone());
+        let kind = kind.to_string();
+        tokio::spawn(async move {
+            let _running = running;
+            let context = JobContext { job_id: id, db: db.clone() };
+            let started = job::ActiveModel {
+                id: sea_orm::Unchanged(id),
+                status: Set(JobStatus::Running),
+                started_at: Set(Some(Utc::now())),
+                updated_at: Set(Utc::now()),
+                ..Default::default()
+            };
+            if let Err(e) = started.update(db.as_ref()).await {
+                warn!(job_id = %id, "Failed to mark job running: {}", e);
+            }
+
+            let outcome = match tokio::spawn(work(context)).await {
+                Ok(outcome) => outcome,
+                Err(e) if e.is_panic() => Err("Job panicked".to_string()),
+                Err(_) => Err("Job was cancelled".to_string()),
+            };
+
+            let mut done = job::ActiveModel {
+                id: sea_orm::Unchanged(id),
+                finished_at: Set(Some(Utc::now())),
+                updated_at: Set(Utc::now()),
+                ..Default::default()
+            };
+            let status = match outcome {
+                Ok(result) => {
+                    done.progress = Set(100);
+                    done.result = Set(Some(result));
+                    JobStatus::Succeeded
+                }
+                Err(message) => {
+                    error!(job_id = %id, kind = %kind, "Job failed: {}", message);
+                    done.error = Set(Some(message));
+                    JobStatus::Failed
+                }
+            };
+            done.status = Set(status);
+            if let Err(e) = done.update(db.as_ref()).await {
+                error!(job_id = %id, "Failed to record job outcome: {}", e);
+            }
+
+            JOBS_FINISHED.with_label_values(&[kind.as_str(), status_label(status)]).inc();
+            info!(job_id = %id, kind = %kind, status = status_label(status), "Job finished");
+            let _ = finished.send(id);
+        });
+
+        Ok(id)
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<job::Model, JobError> {
+        Job::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or(JobError::NotFound(id))
+    }
+
+    /// Returns `job` once it is finished or `wait` elapses, whichever comes first. Callers
+    /// load the job with [`JobRunner::get`] first, to check who may see it before waiting.
+    /// Only completions observed by this process wake the waiter early; jobs run by other
+    /// instances are picked up when the wait times out.
+    pub async fn wait_for(&self, job: job::Model, wait: Duration) -> Result<job::Model, JobError> {
+        let id = job.id;
+        let mut finished = self.finished.subscribe();
+        if job.status.is_terminal() || wait.is_zero() {
+            return Ok(job);
+        }
+        // It may have finished between the caller's read and the subscription
+        let job = self.get(id).await?;
+        if job.status.is_terminal() {
+            return Ok(job);
+        }
+
+        let deadline = tokio::time::Instant::now() + wait.min(MAX_WAIT);
+        loop {
+            match tokio::time::timeout_at(deadline, finished.recv()).await {
+                Ok(Ok(finished_id)) if finished_id != id => continue,
+                // Our job finished, we lagged and may have missed it, or we timed out
+                _ => return self.get(id).await,
+            }
+        }
+    }
+
+    /// Stops accepting jobs and waits up to `timeout` for running ones to record their
+    /// outcome. Returns how many were still running; once their lease lapses another
+    /// instance, or the next start, marks them failed.
+    pub async fn drain(&self, timeout: Duration) -> usize {
+        self.draining.store(true, Ordering::Release);
+        let mut running = self.running.subscribe();
+        let _ = tokio::time::timeout(timeout, running.wait_for(|count| *count == 0)).await;
+        let left = *self.running.borrow();
+        if left > 0 {
+            warn!(jobs = left, "Jobs still running at shutdown; they will be marked interrupted");
+        }
+        left
+    }
+
+    /// Renews the lease on this runner's unfinished jobs.
+    pub async fn heartbeat(&self) -> Result<u64, JobError> {
+        let result = Job::update_many()
+            .col_expr(job::Column::HeartbeatAt, Expr::value(Utc::now()))
+            .filter(job::Column::InstanceId.eq(self.instance_id.as_str()))
+            .filter(job::Column::Status.is_in([JobStatus::Queued, JobStatus::Running]))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Marks queued or running jobs whose lease lapsed as failed: their process stopped,
+    /// e.g. in a restart or crash. Jobs of live instances keep running. The work itself
+    /// is not resumed.
+    pub async fn fail_interrupted(&self) -> Result<u64, JobError> {
+        let now = Utc::now();
+        let expired = now - chrono::Duration::from_std(LEASE).expect("lease fits a chrono duration");
+        let result = Job::update_many()
+            .col_expr(job::Column::Status, Expr::value(JobStatus::Failed))
+            .col_expr(job::Column::Error, Expr::value("Interrupted: the process running it stopped"))
+            .col_expr(job::Column::FinishedAt, Expr::value(now))
+            .col_expr(job::Column::UpdatedAt, Expr::value(now))
+            .filter(job::Column::Status.is_in([JobStatus::Queued, JobStatus::Running]))
+            .filter(
+                Condition::any()
+                    .add(job::Column::HeartbeatAt.lt(expired))
+                    // Written before jobs carried a lease
+                    .add(job::Column::HeartbeatAt.is_null().and(job::Column::UpdatedAt.lt(expired))),
+            )
+            .exec(self.db.as_ref())
+            .await?;
+        if result.rows_affected > 0 {
+            warn!(jobs = result.rows_affected, "Marked interrupted jobs as failed");
+        }
+        Ok(result.rows_affected)
+    }
+}
+
+/// Renews this runner's leases and fails jobs whose lease lapsed, every third of [`LEASE`].
+pub fn spawn_heartbeat(runner: Arc<JobRunner>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(LEASE / 3);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = runner.heartbeat().await {
+                warn!("Failed to renew job leases: {}", e);
+            }
+            if let Err(e) = runner.fail_interrupted().await {
+                warn!("Failed to fail interrupted jobs: {}", e);
+            }
+        }
+    });
+}
+
+impl IntoResponse for JobError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            JobError::NotFound(_) => (StatusCode::NOT_FOUND, "job_not_found"),
+            JobError::ShuttingDown => (StatusCode::SERVICE_UNAVAILABLE, "shutting_down"),
+            JobError::Database(e) => {
+                error!("Job lookup failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "job_lookup_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+fn status_label(status: JobStatus) -> &'static str {
+    match status {
+        JobStatus::Queued => "queued",
+        JobStatus::Running => "running",
+        JobStatus::Succeeded => "succeeded",
+        JobStatus::Failed => "failed",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_is_clamped() {
+        assert_eq!(clamp_progress(-5), 0);
+        assert_eq!(clamp_progress(42), 42);
+        assert_eq!(clamp_progress(250), 100);
+    }
+
+    #[test]
+    fn test_terminal_statuses() {
+        assert!(!JobStatus::Queued.is_terminal());
+        assert!(!JobStatus::Running.is_terminal());
+        assert!(JobStatus::Succeeded.is_terminal());
+        assert!(JobStatus::Failed.is_terminal());
+    }
+
+    #[tokio::test]
+    async fn test_running_count_drops_when_the_task_panics() {
+        let (running, counter) = watch::channel(0);
+        let guard = RunningGuard::new(running);
+        assert_eq!(*counter.borrow(), 1);
+        let task = tokio::spawn(async move {
+            let _running = guard;
+            panic!("recording the outcome failed");
+        });
+        assert!(task.await.is_err());
+        assert_eq!(*counter.borrow(), 0);
+    }
+
+    #[test]
+    fn test_migration_is_safe() {
+        let plan = crate::db::migration_safety::analyze(
+            "20261016210000_jobs.sql",
+            include_str!("../../migrations/20261016210000_jobs.sql"),
+        );
+        assert_eq!(plan.violations().count(), 0);
+    }
+
+    #[test]
+    fn test_status_serializes_like_column_value() {
+        for status in [JobStatus::Queued, JobStatus::Running, JobStatus::Succeeded, JobStatus::Failed] {
+            assert_eq!(serde_json::to_value(status).unwrap(), status_label(status));
+        }
+    }
+}
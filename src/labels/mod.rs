@@ -0,0 +1,481 @@
+// labels/mod.rs
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use barcoders::sym::code128::Code128;
+use qrcode::{Color, EcLevel, QrCode};
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::error;
+use uuid::Uuid;
+
+use crate::models::{inventory_items, shipment, work_order};
+
+pub mod raster;
+
+use raster::Canvas;
+
+/// Longest value encoded in a barcode.
+pub const MAX_DATA_LEN: usize = 80;
+
+/// Code 128 needs this many modules of blank space on each side to scan.
+const CODE128_QUIET_MODULES: u32 = 10;
+/// QR codes need a blank border four modules wide.
+const QR_QUIET_MODULES: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LabelEntity {
+    Sku,
+    Bin,
+    Shipment,
+    WorkOrder,
+}
+
+impl LabelEntity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LabelEntity::Sku => "sku",
+            LabelEntity::Bin => "bin",
+            LabelEntity::Shipment => "shipment",
+            LabelEntity::WorkOrder => "work_order",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Symbology {
+    #[default]
+    Code128,
+    Qr,
+}
+
+/// Label stock sizes in inches, printed at 203 dpi.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Media {
+    #[default]
+    #[serde(rename = "4x6")]
+    FourBySix,
+    #[serde(rename = "4x2")]
+    FourByTwo,
+    #[serde(rename = "2x1")]
+    TwoByOne,
+}
+
+impl Media {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Media::FourBySix => "4x6",
+            Media::FourByTwo => "4x2",
+            Media::TwoByOne => "2x1",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        [Media::FourBySix, Media::FourByTwo, Media::TwoByOne].into_iter().find(|m| m.as_str() == s)
+    }
+
+    /// Width and height in dots.
+    pub fn dots(self) -> (u32, u32) {
+        match self {
+            Media::FourBySix => (812, 1218),
+            Media::FourByTwo => (812, 406),
+            Media::TwoByOne => (406, 203),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Output {
+    /// Zebra printer language, sent to the printer as-is.
+    #[default]
+    Zpl,
+    Png,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LabelQuery {
+    #[serde(default)]
+    pub format: Symbology,
+    #[serde(default)]
+    pub media: Media,
+    #[serde(default)]
+    pub output: Output,
+}
+
+/// What goes on a label: a heading, a few lines of detail and the value to encode.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub title: String,
+    pub lines: Vec<String>,
+    pub data: String,
+}
+
+#[derive(Error, Debug)]
+pub enum LabelError {
+    #[error("{0} not found")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("Label rendering failed: {0}")]
+    Render(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for LabelError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            LabelError::NotFound(_) => (StatusCode::NOT_FOUND, "not_found"),
+            LabelError::Invalid(_) => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_label"),
+            LabelError::Render(e) => {
+                error!("Label rendering failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "label_failed")
+            }
+            LabelError::Database(e) => {
+                error!("Label query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "label_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// Where things go on a label, in dots. Shared by the ZPL and PNG renderers so both print
+/// the same layout.
+#[derive(Debug, Clone, PartialEq)]
+struct Layout {
+    width: u32,
+    height: u32,
+    margin: u32,
+    title_height: u32,
+    line_height: u32,
+    gap: u32,
+    /// Top of the barcode area; it runs to the bottom margin.
+    barcode_top: u32,
+}
+
+impl Layout {
+    fn new(media: Media, lines: usize) -> Self {
+        let (width, height) = media.dots();
+        let margin = width / 20;
+        let title_height = (height / 12).clamp(21, 84);
+        let line_height = (title_height * 2 / 3).max(14);
+        let gap = title_height / 4;
+        let barcode_top = margin + title_height + gap + lines as u32 * (line_height + gap) + gap;
+        Self { width, height, margin, title_height, line_height, gap, barcode_top }
+    }
+
+    fn line_top(&self, index: usize) -> u32 {
+        self.margin + self.title_height + self.gap + index as u32 * (self.line_height + self.gap)
+    }
+
+    fn barcode_area(&self) -> (u32, u32) {
+        (self.width - 2 * self.margin, self.height.saturating_sub(self.margin + self.barcode_top))
+    }
+}
+
+/// Printable ASCII only: what Code 128 set B and the built-in printer fonts cover.
+fn check_data(data: &str) -> Result<(), LabelError> {
+    if data.is_empty() || data.len() > MAX_DATA_LEN {
+        return Err(LabelError::Invalid(format!("Barcode value must be 1 to {} characters", MAX_DATA_LEN)));
+    }
+    if !data.chars().all(|c| (' '..='~').contains(&c)) {
+        return Err(LabelError::Invalid("Barcode value must be printable ASCII".to_string()));
+    }
+    Ok(())
+}
+
+/// Bars and spaces of a Code 128 symbol, one entry per module, start to stop.
+fn code128_modules(data: &str) -> Result<Vec<u8>, LabelError> {
+    // Set B covers all printable ASCII; barcoders picks the set from a leading marker.
+    let symbol = Code128::new(format!("Ɓ{}", data)).map_err(|e| LabelError::Invalid(e.to_string()))?;
+    Ok(symbol.encode())
+}
+
+fn qr_code(data: &str) -> Result<QrCode, LabelError> {
+    QrCode::with_error_correction_level(data.as_bytes(), EcLevel::M).map_err(|e| LabelError::Invalid(e.to_string()))
+}
+
+/// Widest module and tallest bars that fit the barcode area, or an error when even
+/// one-dot modules are too wide.
+fn code128_fit(layout: &Layout, modules: u32) -> Result<(u32, u32), LabelError> {
+    let (width, height) = layout.barcode_area();
+    let module = (width / (modules + 2 * CODE128_QUIET_MODULES)).min(4);
+    if module == 0 || height < 20 {
+        return Err(LabelError::Invalid("Barcode value is too long for this label size".to_string()));
+    }
+    Ok((module, height.min(module * 60).max(20)))
+}
+
+fn qr_fit(layout: &Layout, modules: u32) -> Result<u32, LabelError> {
+    let (width, height) = layout.barcode_area();
+    let module = (width.min(height) / (modules + 2 * QR_QUIET_MODULES)).min(10);
+    if module == 0 {
+        return Err(LabelError::Invalid("Barcode value is too long for this label size".to_string()));
+    }
+    Ok(module)
+}
+
+/// Escapes field data for `^FH_`: the characters ZPL treats as commands become hex.
+fn zpl_field(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '_' | '^' | '~' => out.push_str(&format!("_{:02X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+pub fn render_zpl(label: &Label, symbology: Symbology, media: Media) -> Result<String, LabelError> {
+    check_data(&label.data)?;
+    let layout = Layout::new(media, label.lines.len());
+    let mut zpl = format!("^XA^CI28^PW{}^LL{}\n", layout.width, layout.height);
+    zpl.push_str(&format!(
+        "^FO{m},{m}^A0N,{h},{h}^FH_^FD{}^FS\n",
+        zpl_field(&label.title),
+        m = layout.margin,
+        h = layout.title_height
+    ));
+    for (i, line) in label.lines.iter().enumerate() {
+        zpl.push_str(&format!(
+            "^FO{},{}^A0N,{h},{h}^FH_^FD{}^FS\n",
+            layout.margin,
+            layout.line_top(i),
+            zpl_field(line),
+            h = layout.line_height
+        ));
+    }
+    match symbology {
+        Symbology::Code128 => {
+            let modules = code128_modules(&label.data)?.len() as u32;
+            let (module, bar_height) = code128_fit(&layout, modules)?;
+            let left = (layout.width - modules * module) / 2;
+            zpl.push_str(&format!(
+                "^FO{},{}^BY{}^BCN,{},N,N,N^FH_^FD{}^FS\n",
+                left,
+                layout.barcode_top,
+                module,
+                bar_height,
+                zpl_field(&label.data)
+            ));
+        }
+        Symbology::Qr => {
+            let modules = qr_code(&label.data)?.width() as u32;
+            let module = qr_fit(&layout, modules)?;
+            let left = (layout.width - modules * module) / 2;
+            zpl.push_str(&format!(
+                "^FO{},{}^BQN,2,{}^FH_^FDMA,{}^FS\n",
+                left,
+                layout.barcode_top,
+                module,
+                zpl_field(&label.data)
+            ));
+        }
+    }
+    zpl.push_str("^XZ\n");
+    Ok(zpl)
+}
+
+fn draw(label: &Label, symbology: Symbology, media: Media) -> Result<Canvas, LabelError> {
+    check_data(&label.data)?;
+    let layout = Layout::new(media, label.lines.len());
+    let mut canvas = Canvas::new(layout.width, layout.height);
+    let text_width = layout.width - 2 * layout.margin;
+    canvas.text(layout.margin, layout.margin, layout.title_height, text_width, &label.title);
+    for (i, line) in label.lines.iter().enumerate() {
+        canvas.text(layout.margin, layout.line_top(i), layout.line_height, text_width, line);
+    }
+    match symbology {
+        Symbology::Code128 => {
+            let modules = code128_modules(&label.data)?;
+            let (module, bar_height) = code128_fit(&layout, modules.len() as u32)?;
+            let left = (layout.width - modules.len() as u32 * module) / 2;
+            for (i, bar) in modules.iter().enumerate() {
+                if *bar == 1 {
+                    canvas.fill(left + i as u32 * module, layout.barcode_top, module, bar_height);
+                }
+            }
+        }
+        Symbology::Qr => {
+            let code = qr_code(&label.data)?;
+            let modules = code.width() as u32;
+            let module = qr_fit(&layout, modules)?;
+            let left = (layout.width - modules * module) / 2;
+            let top = layout.barcode_top + QR_QUIET_MODULES * module;
+            for (i, color) in code.to_colors().iter().enumerate() {
+                if *color == Color::Dark {
+                    let (col, row) = (i as u32 % modules, i as u32 / modules);
+                    canvas.fill(left + col * module, top + row * module, module, module);
+                }
+            }
+        }
+    }
+    Ok(canvas)
+}
+
+pub fn render_png(label: &Label, symbology: Symbology, media: Media) -> Result<Vec<u8>, LabelError> {
+    draw(label, symbology, media)?.to_png()
+}
+
+/// Bin codes are printed as given, so only characters scanners and printers agree on pass.
+fn check_bin(code: &str) -> Result<(), LabelError> {
+    let valid = !code.is_empty()
+        && code.len() <= 32
+        && code.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(LabelError::Invalid(format!("Invalid bin code: {}", code)))
+    }
+}
+
+/// Looks up what to print for each kind of label.
+pub struct LabelService {
+    db: Arc<DatabaseConnection>,
+}
+
+impl LabelService {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    pub async fn label(&self, entity: LabelEntity, id: &str) -> Result<Label, LabelError> {
+        let db = self.db.as_ref();
+        match entity {
+            LabelEntity::Sku => {
+                let item = inventory_items::Entity::find()
+                    .filter(inventory_items::Column::Sku.eq(id))
+                    .order_by_asc(inventory_items::Column::Id)
+                    .one(db)
+                    .await?
+                    .ok_or_else(|| LabelError::NotFound(format!("SKU {}", id)))?;
+                let mut lines = vec![item.description];
+                if !item.upc.is_empty() {
+                    lines.push(format!("UPC {}", item.upc));
+                }
+                Ok(Label { title: item.sku.clone(), lines, data: item.sku })
+            }
+            LabelEntity::Bin => {
+                check_bin(id)?;
+                Ok(Label { title: format!("BIN {}", id), lines: Vec::new(), data: id.to_string() })
+            }
+            LabelEntity::Shipment => {
+                let shipment_id: i32 =
+                    id.parse().map_err(|_| LabelError::Invalid(format!("Invalid shipment id: {}", id)))?;
+                let shipment = shipment::Entity::find_by_id(shipment_id)
+                    .one(db)
+                    .await?
+                    .ok_or_else(|| LabelError::NotFound(format!("Shipment {}", id)))?;
+                Ok(Label {
+                    title: shipment.tracking_number.clone(),
+                    lines: vec![
+                        format!("{:?} {}", shipment.carrier, shipment.shipping_method),
+                        format!("Order {}", shipment.order_id),
+                    ],
+                    data: shipment.tracking_number,
+                })
+            }
+            LabelEntity::WorkOrder => {
+                let work_order_id: Uuid =
+                    id.parse().map_err(|_| LabelError::Invalid(format!("Invalid work order id: {}", id)))?;
+                let work_order = work_order::Entity::find_by_id(work_order_id)
+                    .one(db)
+                    .await?
+                    .ok_or_else(|| LabelError::NotFound(format!("Work order {}", id)))?;
+                Ok(Label {
+                    title: format!("WO {}", work_order.number),
+                    lines: vec![work_order.part, format!("Due {}", work_order.expected_completion_date)],
+                    data: format!("WO-{}", work_order.number),
+                })
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(data: &str) -> Label {
+        Label {
+            title: "MUG-BLUE_12".to_string(),
+            lines: vec!["Blue mug ^ 12oz".to_string()],
+            data: data.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_zpl_escapes_field_data() {
+        let zpl = render_zpl(&label("MUG-BLUE_12"), Symbology::Code128, Media::FourBySix).unwrap();
+        assert!(zpl.starts_with("^XA^CI28^PW812^LL1218"));
+        assert!(zpl.contains("^FDMUG-BLUE_5F12^FS"));
+        assert!(zpl.contains("^FDBlue mug _5E 12oz^FS"));
+        assert!(zpl.contains("^BCN,"));
+        assert!(zpl.trim_end().ends_with("^XZ"));
+    }
+
+    #[test]
+    fn test_zpl_qr() {
+        let zpl = render_zpl(&label("https://example.com/wo/42"), Symbology::Qr, Media::FourByTwo).unwrap();
+        assert!(zpl.contains("^BQN,2,"));
+        assert!(zpl.contains("^FDMA,https://example.com/wo/42^FS"));
+    }
+
+    #[test]
+    fn test_code128_too_long_for_small_media() {
+        let long = "X".repeat(60);
+        assert!(matches!(
+            render_zpl(&label(&long), Symbology::Code128, Media::TwoByOne),
+            Err(LabelError::Invalid(_))
+        ));
+        assert!(render_zpl(&label(&long), Symbology::Code128, Media::FourBySix).is_ok());
+    }
+
+    #[test]
+    fn test_rejects_non_ascii_data() {
+        assert!(render_zpl(&label("CAFÉ"), Symbology::Code128, Media::FourBySix).is_err());
+        assert!(render_zpl(&label(""), Symbology::Qr, Media::FourBySix).is_err());
+    }
+
+    #[test]
+    fn test_png_has_media_size() {
+        let png = render_png(&label("BIN-A-01"), Symbology::Qr, Media::TwoByOne).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        // IHDR width and height follow the signature, length and chunk type
+        assert_eq!(u32::from_be_bytes(png[16..20].try_into().unwrap()), 406);
+        assert_eq!(u32::from_be_bytes(png[20..24].try_into().unwrap()), 203);
+    }
+
+    #[test]
+    fn test_code128_bars_are_centered() {
+        let canvas = draw(&label("A1"), Symbology::Code128, Media::FourByTwo).unwrap();
+        let layout = Layout::new(Media::FourByTwo, 1);
+        let row = layout.barcode_top + 1;
+        let first = (0..layout.width).find(|x| canvas.is_black(*x, row)).unwrap();
+        let last = (0..layout.width).rev().find(|x| canvas.is_black(*x, row)).unwrap();
+        let right = layout.width - 1 - last;
+        assert!(first.abs_diff(right) <= 1);
+        assert!(first >= CODE128_QUIET_MODULES);
+    }
+
+    #[test]
+    fn test_bin_codes() {
+        assert!(check_bin("A-01.03_B").is_ok());
+        assert!(check_bin("A 01").is_err());
+        assert!(check_bin("").is_err());
+    }
+}
@@ -0,0 +1,583 @@
+// ledger/mod.rs
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait,
+    FromQueryResult, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::sync::broadcast;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::db::dialect;
+use crate::events::{Event, EventSender};
+use crate::models::{
+    ledger_entry::{self, Entity as LedgerEntry, LedgerAccount, NormalBalance},
+    ledger_transaction::{self, Entity as LedgerTransaction},
+    return_entity::Entity as Return,
+};
+use crate::shutdown::Worker;
+use crate::utils::pagination::PaginationParams;
+
+/// Ledger settings, loaded from the `ledger` section of the config.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct LedgerConfig {
+    /// Record postings from domain events. Reports work either way.
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+#[derive(Error, Debug)]
+pub enum LedgerError {
+    #[error("Ledger transaction is unbalanced: debits {debits}, credits {credits}")]
+    Unbalanced { debits: Decimal, credits: Decimal },
+
+    #[error("{0}")]
+    NotFound(String),
+
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for LedgerError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            LedgerError::Unbalanced { .. } => (StatusCode::UNPROCESSABLE_ENTITY, "unbalanced"),
+            LedgerError::NotFound(_) => (StatusCode::NOT_FOUND, "not_found"),
+            LedgerError::Invalid(_) => (StatusCode::BAD_REQUEST, "invalid_request"),
+            LedgerError::Database(e) => {
+                error!("Ledger query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "ledger_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// One side of a posting.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Posting {
+    pub account: LedgerAccount,
+    pub debit: Decimal,
+    pub credit: Decimal,
+}
+
+impl Posting {
+    pub fn debit(account: LedgerAccount, amount: Decimal) -> Self {
+        Self { account, debit: amount, credit: Decimal::ZERO }
+    }
+
+    pub fn credit(account: LedgerAccount, amount: Decimal) -> Self {
+        Self { account, debit: Decimal::ZERO, credit: amount }
+    }
+}
+
+/// A balanced transaction derived from a domain event, before it is stored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewTransaction {
+    pub source_key: String,
+    pub kind: &'static str,
+    pub reference: String,
+    pub description: String,
+    pub postings: Vec<Posting>,
+}
+
+fn check_balanced(postings: &[Posting]) -> Result<(), LedgerError> {
+    let debits: Decimal = postings.iter().map(|p| p.debit).sum();
+    let credits: Decimal = postings.iter().map(|p| p.credit).sum();
+    if debits != credits || debits.is_zero() {
+        return Err(LedgerError::Unbalanced { debits, credits });
+    }
+    Ok(())
+}
+
+/// Revenue for a shipped order, billed to receivables.
+pub fn revenue(order_id: Uuid, amount: Decimal) -> NewTransaction {
+    NewTransaction {
+        source_key: format!("order_shipped:{}", order_id),
+        kind: "revenue",
+        reference: order_id.to_string(),
+        description: "Revenue for shipped order".to_string(),
+        postings: vec![
+            Posting::debit(LedgerAccount::AccountsReceivable, amount),
+            Posting::credit(LedgerAccount::Revenue, amount),
+        ],
+    }
+}
+
+/// A refunded return; `tax` is the part of `amount` that reverses sales tax.
+pub fn refund(return_id: Uuid, amount: Decimal, tax: Decimal) -> NewTransaction {
+    let mut postings = vec![Posting::debit(LedgerAccount::SalesReturns, amount - tax)];
+    if !tax.is_zero() {
+        postings.push(Posting::debit(LedgerAccount::SalesTaxPayable, tax));
+    }
+    postings.push(Posting::credit(LedgerAccount::Cash, amount));
+    NewTransaction {
+        source_key: format!("return_refunded:{}", return_id),
+        kind: "refund",
+        reference: return_id.to_string(),
+        description: "Refund for return".to_string(),
+        postings,
+    }
+}
+
+/// Postings for events that carry their own amounts.
+pub fn from_event(event: &Event) -> Option<NewTransaction> {
+    match event {
+        Event::GiftCardIssued { gift_card_id, amount } => Some(NewTransaction {
+            source_key: format!("gift_card_issued:{}", gift_card_id),
+            kind: "gift_card_issued",
+            reference: gift_card_id.to_string(),
+            description: "Gift card sold".to_string(),
+            postings: vec![
+                Posting::debit(LedgerAccount::Cash, *amount),
+                Posting::credit(LedgerAccount::GiftCardLiability, *amount),
+            ],
+        }),
+        Event::GiftCardRedeemed { gift_card_id, order_id, amount } => Some(NewTransaction {
+            source_key: format!("gift_card_redeemed:{}:{}", gift_card_id, order_id),
+            kind: "gift_card_redeemed",
+            reference: gift_card_id.to_string(),
+            description: format!("Gift card applied to order {}", order_id),
+            postings: vec![
+                Posting::debit(LedgerAccount::GiftCardLiability, *amount),
+                Posting::credit(LedgerAccount::AccountsReceivable, *amount),
+            ],
+        }),
+        Event::InventoryRevalued { sku, warehouse, amount, reason } => {
+            let value = amount.abs();
+            let postings = if amount.is_sign_negative() {
+                vec![
+                    Posting::debit(LedgerAccount::InventoryAdjustments, value),
+                    Posting::credit(LedgerAccount::Inventory, value),
+                ]
+            } else {
+                vec![
+                    Posting::debit(LedgerAccount::Inventory, value),
+                    Posting::credit(LedgerAccount::InventoryAdjustments, value),
+                ]
+            };
+            Some(NewTransaction {
+                // Revaluations have no natural id; each event is its own fact
+                source_key: format!("inventory_revalued:{}", Uuid::new_v4()),
+                kind: "inventory_revaluation",
+                reference: format!("{}@{}", sku, warehouse),
+                description: reason.clone(),
+                postings,
+            })
+        }
+        Event::PaymentCaptured { order_id, capture_id, amount, .. } => Some(NewTransaction {
+            source_key: format!("payment_captured:{}", capture_id),
+            kind: "payment_captured",
+            reference: order_id.to_string(),
+            description: format!("Payment captured for order {}", order_id),
+            postings: vec![
+                Posting::debit(LedgerAccount::Cash, *amount),
+                Posting::credit(LedgerAccount::AccountsReceivable, *amount),
+            ],
+        }),
+        _ => None,
+    }
+}
+
+/// Debit minus credit, flipped for credit-normal accounts so balances read positive.
+pub fn balance(account: LedgerAccount, debits: Decimal, credits: Decimal) -> Decimal {
+    match account.normal_balance() {
+        NormalBalance::Debit => debits - credits,
+        NormalBalance::Credit => credits - debits,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrialBalanceRow {
+    pub account: LedgerAccount,
+    pub name: &'static str,
+    pub normal_balance: NormalBalance,
+    pub debits: Decimal,
+    pub credits: Decimal,
+    pub balance: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrialBalance {
+    /// Entries before this instant are included.
+    pub as_of: DateTime<Utc>,
+    pub accounts: Vec<TrialBalanceRow>,
+    pub total_debits: Decimal,
+    pub total_credits: Decimal,
+    pub balanced: bool,
+}
+
+pub fn trial_balance(as_of: DateTime<Utc>, totals: Vec<AccountTotals>) -> TrialBalance {
+    let mut accounts: Vec<TrialBalanceRow> = totals
+        .into_iter()
+        .map(|t| TrialBalanceRow {
+            account: t.account,
+            name: t.account.name(),
+            normal_balance: t.account.normal_balance(),
+            debits: t.debits,
+            credits: t.credits,
+            balance: balance(t.account, t.debits, t.credits),
+        })
+        .collect();
+    accounts.sort_by_key(|row| row.account);
+    let total_debits = accounts.iter().map(|row| row.debits).sum();
+    let total_credits = accounts.iter().map(|row| row.credits).sum();
+    TrialBalance { as_of, accounts, total_debits, total_credits, balanced: total_debits == total_credits }
+}
+
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct AccountTotals {
+    pub account: LedgerAccount,
+    pub debits: Decimal,
+    pub credits: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ActivityLine {
+    #[serde(flatten)]
+    pub entry: ledger_entry::Model,
+    pub kind: String,
+    pub reference: String,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountActivity {
+    pub account: LedgerAccount,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Balance before `from`.
+    pub opening_balance: Decimal,
+    /// Balance through `to`.
+    pub closing_balance: Decimal,
+    pub entries: Vec<ActivityLine>,
+    pub total: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TransactionWithEntries {
+    #[serde(flatten)]
+    pub transaction: ledger_transaction::Model,
+    pub entries: Vec<ledger_entry::Model>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct OrderTotal {
+    total_cents: i64,
+}
+
+const ORDER_TOTAL_SQL: &str = r#"
+SELECT COALESCE(SUM(li.sale_price::bigint * li.quantity), 0)::bigint AS total_cents
+FROM order_line_items li
+WHERE li.order_id = $1
+"#;
+
+fn day_start(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc()
+}
+
+/// Internal double-entry ledger. Domain events are turned into balanced transactions so
+/// finance can pull a trial balance and account activity without querying source tables.
+pub struct LedgerService {
+    db: Arc<DatabaseConnection>,
+}
+
+impl LedgerService {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    /// Stores a transaction. Re-posting the same `source_key` returns the original.
+    pub async fn post(&self, new: NewTransaction) -> Result<ledger_transaction::Model, LedgerError> {
+        check_balanced(&new.postings)?;
+        let txn = self.db.begin().await?;
+        if let Some(existing) = LedgerTransaction::find()
+            .filter(ledger_transaction::Column::SourceKey.eq(new.source_key.as_str()))
+            .one(&txn)
+            .await?
+        {
+            return Ok(existing);
+        }
+        let now = Utc::now();
+        let transaction = ledger_transaction::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            source_key: Set(new.source_key),
+            kind: Set(new.kind.to_string()),
+            reference: Set(new.reference),
+            description: Set(new.description),
+            occurred_at: Set(now),
+            created_at: Set(now),
+        }
+        .insert(&txn)
+        .await?;
+        for posting in new.postings.iter().filter(|p| !(p.debit.is_zero() && p.credit.is_zero())) {
+            ledger_entry::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                transaction_id: Set(transaction.id),
+                account: Set(posting.account),
+                debit: Set(posting.debit),
+                credit: Set(posting.credit),
+                occurred_at: Set(transaction.occurred_at),
+            }
+            .insert(&txn)
+            .await?;
+        }
+        txn.commit().await?;
+        info!(transaction_id = %transaction.id, kind = %transaction.kind, "Ledger transaction posted");
+        Ok(transaction)
+    }
+
+    /// Posts what an event means financially, if anything.
+    pub async fn record(&self, event: &Event) -> Result<(), LedgerError> {
+        let new = match event {
+            Event::OrderShipped(order_id) => {
+                let total = OrderTotal::find_by_statement(dialect::statement(
+                    self.db.as_ref(),
+                    ORDER_TOTAL_SQL,
+                    [(*order_id).into()],
+                ))
+                .one(self.db.as_ref())
+                .await?
+                .map(|row| row.total_cents)
+                .unwrap_or(0);
+                if total == 0 {
+                    return Ok(());
+                }
+                revenue(*order_id, Decimal::new(total, 2))
+            }
+            Event::ReturnRefunded(return_id) => {
+                let ret = Return::find_by_id(*return_id)
+                    .one(self.db.as_ref())
+                    .await?
+                    .ok_or_else(|| LedgerError::NotFound(format!("Return not found: {}", return_id)))?;
+                if ret.total_refunded.is_zero() {
+                    return Ok(());
+                }
+                refund(*return_id, ret.total_refunded, ret.tax_refunded.min(ret.total_refunded))
+            }
+            other => match from_event(other) {
+                Some(new) => new,
+                None => return Ok(()),
+            },
+        };
+        self.post(new).await.map(|_| ())
+    }
+
+    /// Debit and credit totals per account for entries before `before`.
+    async fn totals(&self, before: DateTime<Utc>) -> Result<Vec<AccountTotals>, LedgerError> {
+        Ok(LedgerEntry::find()
+            .select_only()
+            .column(ledger_entry::Column::Account)
+            .column_as(Expr::col(ledger_entry::Column::Debit).sum(), "debits")
+            .column_as(Expr::col(ledger_entry::Column::Credit).sum(), "credits")
+            .filter(ledger_entry::Column::OccurredAt.lt(before))
+            .group_by(ledger_entry::Column::Account)
+            .into_model::<AccountTotals>()
+            .all(self.db.as_ref())
+            .await?)
+    }
+
+    /// Trial balance through the end of `as_of`, or up to now.
+    pub async fn trial_balance(&self, as_of: Option<NaiveDate>) -> Result<TrialBalance, LedgerError> {
+        let cutoff = match as_of {
+            Some(date) => day_start(date + Duration::days(1)),
+            None => Utc::now(),
+        };
+        Ok(trial_balance(cutoff, self.totals(cutoff).await?))
+    }
+
+    /// Entries on one account between `from` and `to` inclusive, oldest first, with the
+    /// opening and closing balances around the range.
+    pub async fn activity(
+        &self,
+        account: LedgerAccount,
+        from: NaiveDate,
+        to: NaiveDate,
+        pagination: PaginationParams,
+    ) -> Result<AccountActivity, LedgerError> {
+        if to < from {
+            return Err(LedgerError::Invalid("`to` is before `from`".to_string()));
+        }
+        let start = day_start(from);
+        let end = day_start(to + Duration::days(1));
+        let balance_of = |totals: Vec<AccountTotals>| {
+            totals
+                .into_iter()
+                .find(|t| t.account == account)
+                .map(|t| balance(account, t.debits, t.credits))
+                .unwrap_or_default()
+        };
+        let opening_balance = balance_of(self.totals(start).await?);
+        let closing_balance = balance_of(self.totals(end).await?);
+
+        let paginator = LedgerEntry::find()
+            .filter(ledger_entry::Column::Account.eq(account))
+            .filter(ledger_entry::Column::OccurredAt.gte(start))
+            .filter(ledger_entry::Column::OccurredAt.lt(end))
+            .order_by_asc(ledger_entry::Column::OccurredAt)
+            .find_also_related(LedgerTransaction)
+            .paginate(self.db.as_ref(), pagination.limit());
+        let total = paginator.num_items().await?;
+        let entries = paginator
+            .fetch_page(pagination.page_index())
+            .await?
+            .into_iter()
+            .map(|(entry, transaction)| {
+                let transaction = transaction.unwrap_or_else(|| ledger_transaction::Model {
+                    id: entry.transaction_id,
+                    source_key: String::new(),
+                    kind: String::new(),
+                    reference: String::new(),
+                    description: String::new(),
+                    occurred_at: entry.occurred_at,
+                    created_at: entry.occurred_at,
+                });
+                ActivityLine {
+                    entry,
+                    kind: transaction.kind,
+                    reference: transaction.reference,
+                    description: transaction.description,
+                }
+            })
+            .collect();
+        Ok(AccountActivity { account, from, to, opening_balance, closing_balance, entries, total })
+    }
+
+    pub async fn transaction(&self, id: Uuid) -> Result<TransactionWithEntries, LedgerError> {
+        let transaction = LedgerTransaction::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or_else(|| LedgerError::NotFound(format!("Ledger transaction not found: {}", id)))?;
+        let entries = LedgerEntry::find()
+            .filter(ledger_entry::Column::TransactionId.eq(id))
+            .all(self.db.as_ref())
+            .await?;
+        Ok(TransactionWithEntries { transaction, entries })
+    }
+}
+
+/// Posts ledger transactions as financial events arrive.
+pub fn spawn_recorder(ledger: Arc<LedgerService>, events: EventSender, mut worker: Worker) {
+    let mut receiver = events.subscribe();
+    tokio::spawn(async move {
+        loop {
+            let received = tokio::select! {
+                biased;
+                received = receiver.recv() => received,
+                _ = worker.stopping() => break,
+            };
+            match received {
+                Ok(event) => {
+                    if let Err(e) = ledger.record(&event).await {
+                        error!(?event, "Ledger posting failed: {}", e);
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!(missed, "Ledger recorder lagged; some events were not posted");
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn totals(postings: &[Posting]) -> (Decimal, Decimal) {
+        (postings.iter().map(|p| p.debit).sum(), postings.iter().map(|p| p.credit).sum())
+    }
+
+    #[test]
+    fn test_refund_reverses_tax() {
+        let refund = refund(Uuid::nil(), dec!(54), dec!(4));
+        assert!(check_balanced(&refund.postings).is_ok());
+        assert_eq!(refund.postings[0], Posting::debit(LedgerAccount::SalesReturns, dec!(50)));
+        assert_eq!(refund.postings[1].account, LedgerAccount::SalesTaxPayable);
+    }
+
+    #[test]
+    fn test_gift_card_lifecycle_nets_out_liability() {
+        let issued = from_event(&Event::GiftCardIssued { gift_card_id: Uuid::nil(), amount: dec!(25) }).unwrap();
+        let redeemed = from_event(&Event::GiftCardRedeemed {
+            gift_card_id: Uuid::nil(),
+            order_id: Uuid::nil(),
+            amount: dec!(25),
+        })
+        .unwrap();
+        let liability: Decimal = issued
+            .postings
+            .iter()
+            .chain(&redeemed.postings)
+            .filter(|p| p.account == LedgerAccount::GiftCardLiability)
+            .map(|p| p.credit - p.debit)
+            .sum();
+        assert_eq!(liability, dec!(0));
+    }
+
+    #[test]
+    fn test_payment_capture_settles_receivable() {
+        let captured = from_event(&Event::PaymentCaptured {
+            order_id: Uuid::nil(),
+            authorization_id: Uuid::nil(),
+            capture_id: Uuid::nil(),
+            amount: dec!(40),
+        })
+        .unwrap();
+        assert!(check_balanced(&captured.postings).is_ok());
+        assert_eq!(captured.postings[1].account, LedgerAccount::AccountsReceivable);
+        assert_eq!(captured.postings[1].credit, dec!(40));
+    }
+
+    #[test]
+    fn test_write_down_credits_inventory() {
+        let event = Event::InventoryRevalued {
+            sku: "WIDGET".to_string(),
+            warehouse: 1,
+            amount: dec!(-12.50),
+            reason: "Damaged".to_string(),
+        };
+        let posting = from_event(&event).unwrap();
+        assert_eq!(totals(&posting.postings), (dec!(12.50), dec!(12.50)));
+        assert_eq!(posting.postings[1], Posting::credit(LedgerAccount::Inventory, dec!(12.50)));
+    }
+
+    #[test]
+    fn test_unbalanced_postings_are_rejected() {
+        let postings = [Posting::debit(LedgerAccount::Cash, dec!(10)), Posting::credit(LedgerAccount::Revenue, dec!(9))];
+        assert!(matches!(check_balanced(&postings), Err(LedgerError::Unbalanced { .. })));
+        assert!(check_balanced(&[]).is_err());
+    }
+
+    #[test]
+    fn test_trial_balance_uses_normal_sides() {
+        let tb = trial_balance(
+            Utc::now(),
+            vec![
+                AccountTotals { account: LedgerAccount::Revenue, debits: dec!(0), credits: dec!(100) },
+                AccountTotals { account: LedgerAccount::AccountsReceivable, debits: dec!(100), credits: dec!(30) },
+                AccountTotals { account: LedgerAccount::Cash, debits: dec!(30), credits: dec!(0) },
+            ],
+        );
+        assert!(tb.balanced);
+        assert_eq!(tb.accounts[0].account, LedgerAccount::Cash);
+        assert_eq!(tb.accounts[1].balance, dec!(70));
+        assert_eq!(tb.accounts[2].balance, dec!(100));
+    }
+}
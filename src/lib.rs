@@ -4,15 +4,79 @@
 //! including database models, services, and utilities for caching and rate limiting.
 
 pub mod schema; // This might need to be updated or removed depending on your SeaORM setup
+pub mod config;
+pub mod agents;
+pub mod auth;
+pub mod assist;
+pub mod logging;
 pub mod models;
 pub mod services;
 pub mod commands;
 pub mod queries;
 pub mod errors;
 pub mod cache;
+pub mod checkout;
+pub mod product_feed;
 pub mod rate_limiter;
+pub mod network_acl;
+pub mod middleware_helpers;
+pub mod request_archive;
+pub mod reservation_expiry;
+pub mod shipment_sla;
+pub mod workflow;
+pub mod integrations;
+pub mod ledger;
+pub mod payments;
+pub mod dunning;
+pub mod disputes;
+pub mod notifications;
+pub mod retention;
+pub mod seed;
+pub mod embeddings;
+pub mod semantic_search;
+pub mod jobs;
+pub mod inventory_snapshots;
+pub mod inventory_levels;
+pub mod customer_segments;
+pub mod websocket;
 pub mod db;
+pub mod migrator;
 pub mod events;
+pub mod money;
+pub mod utils;
+pub mod i18n;
+pub mod labels;
+pub mod geocoding;
+pub mod carriers;
+pub mod hazmat;
+pub mod customs;
+pub mod dropship;
+pub mod metering;
+pub mod encryption;
+pub mod partitioning;
+pub mod compression;
+pub mod deadline;
+pub mod shutdown;
+pub mod backfill;
+pub mod webhooks;
+pub mod ingest;
+pub mod inbound_email;
+pub mod http_server;
+pub mod tls;
+pub mod abuse;
+pub mod maintenance;
+pub mod slo;
+pub mod synthetic;
+pub mod message_queue;
+pub mod cdc;
+pub mod stock_alerts;
+pub mod calendar;
+pub mod custom_fields;
+pub mod developer_logs;
+pub mod change_feed;
+pub mod tenant_export;
+#[cfg(feature = "testing")]
+pub mod testing;
 
 
 // Public re-exports
@@ -0,0 +1,321 @@
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashSet;
+
+/// Replacement written in place of redacted values.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Field names masked by default, compared case-insensitively with `-` and `_` ignored.
+const DEFAULT_SENSITIVE_FIELDS: &[&str] = &[
+    // Credentials and tokens
+    "password",
+    "secret",
+    "token",
+    "accesstoken",
+    "refreshtoken",
+    "apikey",
+    "authorization",
+    "clientsecret",
+    // Card data
+    "cardnumber",
+    "pan",
+    "cvv",
+    "cvc",
+    "securitycode",
+    "expiry",
+    "expirationdate",
+    // Contact details and addresses
+    "email",
+    "phone",
+    "phonenumber",
+    "address",
+    "address1",
+    "address2",
+    "street",
+    "postalcode",
+    "zip",
+    "billingaddress",
+    "shippingaddress",
+    "ssn",
+    "taxid",
+    "dateofbirth",
+];
+
+/// Redaction settings, loaded from the `log_redaction` config section.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct RedactionConfig {
+    /// Log request bodies (after redaction). Off by default.
+    #[serde(default)]
+    pub log_bodies: bool,
+
+    /// Additional JSON field names to mask on top of the built-in list.
+    #[serde(default)]
+    pub extra_fields: Vec<String>,
+}
+
+/// Masks PII in payloads before they reach a log sink.
+///
+/// Values of sensitive fields are replaced entirely. Every remaining string is also
+/// scanned for card numbers, so a PAN is masked even when it appears under an
+/// unexpected key or in a non-JSON body.
+#[derive(Clone, Debug)]
+pub struct Redactor {
+    fields: HashSet<String>,
+}
+
+impl Default for Redactor {
+    fn default() -> Self {
+        Self::new(&RedactionConfig::default())
+    }
+}
+
+fn normalize(field: &str) -> String {
+    field
+        .chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+impl Redactor {
+    pub fn new(config: &RedactionConfig) -> Self {
+        let fields = DEFAULT_SENSITIVE_FIELDS
+            .iter()
+            .map(|f| normalize(f))
+            .chain(config.extra_fields.iter().map(|f| normalize(f)))
+            .collect();
+        Self { fields }
+    }
+
+    pub fn is_sensitive(&self, field: &str) -> bool {
+        self.fields.contains(&normalize(field))
+    }
+
+    /// Redacts a JSON value in place.
+    pub fn redact_value(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                for (key, value) in map.iter_mut() {
+                    if self.is_sensitive(key) {
+                        *value = Value::String(REDACTED.to_string());
+                    } else {
+                        self.redact_value(value);
+                    }
+                }
+            }
+            Value::Array(items) => items.iter_mut().for_each(|item| self.redact_value(item)),
+            Value::String(s) => *s = mask_card_numbers(s),
+            // Card numbers sent as JSON numbers
+            Value::Number(n) => {
+                let digits = n.to_string();
+                if is_card_number(&digits) {
+                    *value = Value::String(mask_pan(&digits));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Redacts a raw body. JSON is redacted field by field; anything else only has
+    /// card numbers masked.
+    pub fn redact_body(&self, body: &[u8]) -> String {
+        match serde_json::from_slice::<Value>(body) {
+            Ok(mut value) => {
+                self.redact_value(&mut value);
+                value.to_string()
+            }
+            Err(_) => mask_card_numbers(&String::from_utf8_lossy(body)),
+        }
+    }
+
+    /// Redacts a URL query string: parameters with sensitive names are replaced and
+    /// card numbers masked in the rest, after percent-decoding so neither survives
+    /// encoding such as `card%5Fnumber` or `4111%201111...`.
+    pub fn redact_query(&self, query: &str) -> String {
+        let mut out = url::form_urlencoded::Serializer::new(String::new());
+        for (key, value) in url::form_urlencoded::parse(query.as_bytes()) {
+            if self.is_sensitive(&key) {
+                out.append_pair(&key, REDACTED);
+            } else {
+                out.append_pair(&key, &mask_card_numbers(&value));
+            }
+        }
+        out.finish()
+    }
+}
+
+/// Luhn checksum over a string of ASCII digits.
+fn luhn_valid(digits: &str) -> bool {
+    let mut sum = 0;
+    for (i, c) in digits.bytes().rev().enumerate() {
+        let mut d = (c - b'0') as u32;
+        if i % 2 == 1 {
+            d *= 2;
+            if d > 9 {
+                d -= 9;
+            }
+        }
+        sum += d;
+    }
+    sum % 10 == 0
+}
+
+fn is_card_number(digits: &str) -> bool {
+    (13..=19).contains(&digits.len()) && digits.bytes().all(|b| b.is_ascii_digit()) && luhn_valid(digits)
+}
+
+fn mask_pan(digits: &str) -> String {
+    format!("{}{}", "*".repeat(digits.len() - 4), &digits[digits.len() - 4..])
+}
+
+/// Masks every Luhn-valid 13–19 digit sequence in `text`, allowing single spaces or
+/// dashes between digits, keeping only the last four digits.
+pub fn mask_card_numbers(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut out = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        if !chars[i].is_ascii_digit() || (i > 0 && chars[i - 1].is_ascii_digit()) {
+            out.push(chars[i]);
+            i += 1;
+            continue;
+        }
+
+        // Collect a run of digits joined by single separators
+        let mut end = i;
+        let mut digits = String::new();
+        let mut j = i;
+        while j < chars.len() {
+            if chars[j].is_ascii_digit() {
+                digits.push(chars[j]);
+                j += 1;
+                end = j;
+            } else if (chars[j] == ' ' || chars[j] == '-')
+                && j + 1 < chars.len()
+                && chars[j + 1].is_ascii_digit()
+                && !digits.is_empty()
+            {
+                j += 1;
+            } else {
+                break;
+            }
+        }
+
+        // Look for the longest card number starting at `i` within the run
+        let mut masked = false;
+        for len in (13..=digits.len().min(19)).rev() {
+            if is_card_number(&digits[..len]) {
+                // Map the digit count back to a char offset within the run
+                let mut seen = 0;
+                let mut k = i;
+                while seen < len {
+                    if chars[k].is_ascii_digit() {
+                        seen += 1;
+                    }
+                    k += 1;
+                }
+                if k < chars.len() && chars[k].is_ascii_digit() {
+                    continue;
+                }
+                out.push_str(&mask_pan(&digits[..len]));
+                i = k;
+                masked = true;
+                break;
+            }
+        }
+
+        if !masked {
+            // No card starts here; emit this digit group and retry at the next one
+            while i < end && chars[i].is_ascii_digit() {
+                out.push(chars[i]);
+                i += 1;
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const PANS: &[&str] = &["4111111111111111", "5555555555554444", "378282246310005", "6011111111111117"];
+
+    #[test]
+    fn test_sensitive_fields_masked_case_insensitively() {
+        let redactor = Redactor::default();
+        let mut value = json!({
+            "Email": "jane@example.com",
+            "shipping_address": {"street": "1 Main St"},
+            "card-number": "4111111111111111",
+            "order_id": "ord_123",
+        });
+        redactor.redact_value(&mut value);
+        assert_eq!(value["Email"], REDACTED);
+        assert_eq!(value["shipping_address"], REDACTED);
+        assert_eq!(value["card-number"], REDACTED);
+        assert_eq!(value["order_id"], "ord_123");
+    }
+
+    #[test]
+    fn test_extra_fields_from_config() {
+        let redactor = Redactor::new(&RedactionConfig {
+            log_bodies: true,
+            extra_fields: vec!["loyalty_id".to_string()],
+        });
+        let mut value = json!({"loyaltyId": "L-1"});
+        redactor.redact_value(&mut value);
+        assert_eq!(value["loyaltyId"], REDACTED);
+    }
+
+    #[test]
+    fn test_pan_never_survives_redaction() {
+        let redactor = Redactor::default();
+        for pan in PANS {
+            let spaced: String = pan
+                .chars()
+                .enumerate()
+                .flat_map(|(i, c)| if i > 0 && i % 4 == 0 { vec![' ', c] } else { vec![c] })
+                .collect();
+            let bodies = vec![
+                json!({"note": format!("card {} please", pan)}).to_string(),
+                json!({"items": [{"memo": spaced.clone()}]}).to_string(),
+                json!({"unexpected": pan.parse::<u64>().unwrap()}).to_string(),
+                format!("raw={}&dashed={}", pan, spaced.replace(' ', "-")),
+                format!("not json {{ {}", spaced),
+            ];
+            for body in bodies {
+                let redacted = redactor.redact_body(body.as_bytes());
+                let digits_only: String = redacted.chars().filter(char::is_ascii_digit).collect();
+                assert!(!digits_only.contains(pan), "PAN leaked in {}", redacted);
+                assert!(redacted.contains(&pan[pan.len() - 4..]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_pan_after_other_digits_masked() {
+        assert_eq!(mask_card_numbers("qty 12 4111111111111111"), "qty 12 ************1111");
+    }
+
+    #[test]
+    fn test_query_strings_redacted_by_key_and_pattern() {
+        let redactor = Redactor::default();
+        let redacted = redactor.redact_query("token=abc&card%5Fnumber=4111111111111111&note=4111%201111%201111%201111&page=2");
+        assert!(!redacted.contains("abc"));
+        assert!(!redacted.contains("4111111111111111"));
+        assert!(!redacted.contains("4111+1111+1111+1111"));
+        assert!(redacted.contains("page=2"));
+        assert!(redacted.contains("card_number=%5BREDACTED%5D"));
+    }
+
+    #[test]
+    fn test_non_card_numbers_untouched() {
+        assert_eq!(mask_card_numbers("order 1234567890123 qty 5"), "order 1234567890123 qty 5");
+        assert_eq!(mask_card_numbers("phone 555-0100"), "phone 555-0100");
+        assert_eq!(mask_card_numbers(""), "");
+    }
+}
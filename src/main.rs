@@ -7,7 +7,7 @@ use tokio::sync::broadcast;
 use slog::{info, o, Drain, Logger};
 use dotenv::dotenv;
 use opentelemetry::global;
-use tower_http::{compression::CompressionLayer, trace::TraceLayer};
+use tower_http::trace::TraceLayer;
 
 mod config;
 mod services;
@@ -19,12 +19,70 @@ mod queries;
 mod errors;
 mod logging;
 mod cache;
+mod agents;
+mod assist;
+mod checkout;
+mod product_feed;
 mod rate_limiter;
+mod network_acl;
+mod middleware_helpers;
+mod request_archive;
+mod reservation_expiry;
+mod shipment_sla;
+mod workflow;
+mod integrations;
+mod ledger;
+mod payments;
+mod dunning;
+mod disputes;
+mod notifications;
+mod retention;
+mod seed;
+mod embeddings;
+mod semantic_search;
+mod jobs;
+mod inventory_snapshots;
+mod inventory_levels;
+mod customer_segments;
+mod websocket;
 mod message_queue;
 mod circuit_breaker;
 mod tracing;
 mod health;
 mod db;
+mod migrator;
+mod money;
+mod utils;
+mod i18n;
+mod labels;
+mod geocoding;
+mod carriers;
+mod hazmat;
+mod customs;
+mod dropship;
+mod metering;
+mod encryption;
+mod partitioning;
+mod compression;
+mod deadline;
+mod shutdown;
+mod backfill;
+mod webhooks;
+mod ingest;
+mod inbound_email;
+mod http_server;
+mod tls;
+mod abuse;
+mod maintenance;
+mod slo;
+mod synthetic;
+mod cdc;
+mod stock_alerts;
+mod calendar;
+mod custom_fields;
+mod developer_logs;
+mod change_feed;
+mod tenant_export;
 mod proto;
 mod auth;
 mod grpc_server;
@@ -36,6 +94,7 @@ use errors::AppError;
 #[derive(Clone)]
 struct AppState {
     config: Arc<AppConfig>,
+    secrets: config::secrets::SecretStore,
     db_pool: Arc<db::DbPool>,
     redis_client: Arc<redis::Client>,
     event_sender: broadcast::Sender<events::Event>,
@@ -103,15 +162,52 @@ struct Services {
 #[tokio::main]
 async fn main() -> Result<(), AppError> {
     dotenv().ok();
-    let config = Arc::new(config::load()?);
-    let log = setup_logger(&config);
+    let (config, secrets) = config::load_with_secrets().await?;
+    let config = Arc::new(config);
+    let log_level = logging::LevelHandle::new(config.log_level.parse().unwrap());
+    let log = setup_logger(log_level.clone());
+
+    // Reloadable settings are applied live on SIGHUP or at the configured interval
+    let config_watcher = Arc::new(config::watcher::ConfigWatcher::new(&config));
+    config_watcher.on_change(move |settings| {
+        if let Ok(level) = settings.log_level.parse() {
+            log_level.set(level);
+        }
+    });
+
+    // `stateset-api seed [--seed N] ...` writes demo data and exits
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("seed") {
+        return run_seed(&config, args[1..].to_vec(), &log).await;
+    }
 
     info!(log, "Starting StateSet API"; 
         "environment" => &config.environment,
         "version" => env!("CARGO_PKG_VERSION")
     );
 
-    let app_state = build_app_state(&config, &log).await?;
+    commands::orders::order_event_store::set_event_sourcing_enabled(config.order_event_sourcing);
+    services::duplicate_orders::set_policy(config.duplicate_orders.clone());
+    hazmat::set_config(config.hazmat.clone());
+
+    let app_state = build_app_state(&config, secrets, &config_watcher, &log).await?;
+    config_watcher.clone().spawn(
+        (config.config_reload_interval_secs > 0)
+            .then(|| std::time::Duration::from_secs(config.config_reload_interval_secs)),
+    );
+
+    // Outbox publishers and other loops that write state register here, so shutdown can
+    // let them reach a safe point before the process exits
+    let drain = shutdown::Drain::new();
+
+    // Pool statistics feed DATABASE_METRICS and /health/db so exhaustion shows before requests fail
+    let pool_monitor = Arc::new(db::PoolMonitor::new(
+        "api",
+        app_state.db_pool.clone(),
+        &config.database_pool.api,
+        config.database_pool.clone(),
+    ));
+    db::spawn_pool_monitor(pool_monitor.clone());
 
     let schema = Arc::new(graphql::create_schema(
         app_state.services.orders.clone(),
@@ -182,9 +278,631 @@ async fn main() -> Result<(), AppError> {
     #[cfg(feature = "grpc")]
     let grpc_server = grpc_server::start(config.clone(), app_state.services.clone()).await?;
 
+    // Retention policies archive or purge expired rows on a schedule
+    if config.retention.enabled {
+        let export_sink: Option<Arc<dyn request_archive::ArchiveSink>> = match &config.retention.export_bucket {
+            Some(bucket) => Some(Arc::new(request_archive::S3Sink::new(bucket.clone()).await)),
+            None => None,
+        };
+        let runner = retention::RetentionRunner::new(
+            app_state.db_pool.clone(),
+            config.retention.policies.clone(),
+            export_sink,
+        )?;
+        retention::spawn_scheduler(
+            Arc::new(runner),
+            std::time::Duration::from_secs(config.retention.interval_secs),
+        );
+    }
+
+    // History queries read snapshots even when this instance doesn't take them
+    let inventory_snapshots = Arc::new(inventory_snapshots::InventorySnapshotService::new(
+        app_state.db_pool.clone(),
+    ));
+    if config.inventory_snapshots.enabled {
+        inventory_snapshots::spawn_scheduler(inventory_snapshots.clone(), config.inventory_snapshots.hour_utc);
+    }
+
+    let inventory_levels = Arc::new(inventory_levels::InventoryLevelService::new(
+        app_state.db_pool.clone(),
+        app_state.event_sender.clone(),
+    ));
+
+    // Partner webhooks are mapped onto registered commands; a bad mapping fails startup
+    let mut ingest_service = ingest::IngestService::new(
+        app_state.db_pool.clone(),
+        config.ingest.clone(),
+        app_state.secrets.clone(),
+    )
+        .map_err(|e| AppError::ConfigError(e.to_string()))?;
+    ingest_service.register("emit_event", Arc::new(ingest::EmitEvent(app_state.event_sender.clone())));
+    ingest_service.register("inventory.set_levels", Arc::new(ingest::SetInventoryLevels(inventory_levels.clone())));
+    ingest_service.check().map_err(|e| AppError::ConfigError(e.to_string()))?;
+    let ingest_service = Arc::new(ingest_service);
+
+    // Return and warranty emails become draft returns for customer service to confirm
+    let inbound_email_service = Arc::new(
+        inbound_email::InboundEmailService::new(app_state.db_pool.clone(), config.inbound_email.clone())
+            .map_err(|e| AppError::ConfigError(e.to_string()))?,
+    );
+
+    // Abandoned cart holds must not pin stock forever
+    if config.reservation_expiry.enabled {
+        reservation_expiry::spawn_scheduler(
+            app_state.db_pool.clone(),
+            app_state.event_sender.clone(),
+            std::time::Duration::from_secs(config.reservation_expiry.interval_secs),
+        );
+    }
+
+    // Warehouse time zones and working calendars for expected dates, cutoffs and reports
+    let calendars =
+        Arc::new(calendar::Calendars::from_config(&config.calendar).map_err(|e| AppError::ConfigError(e.to_string()))?);
+
+    // Carrier SLA reporting, plus alerts when in-transit shipments run past their expected date
+    let shipment_sla = Arc::new(shipment_sla::ShipmentSlaService::new(
+        app_state.db_pool.clone(),
+        config.shipment_sla.clone(),
+        calendars.clone(),
+    ));
+    if config.shipment_sla.enabled {
+        let notifier = Arc::new(notifications::RedisNotificationService::new(
+            (*app_state.redis_client).clone(),
+            log.clone(),
+        ));
+        shipment_sla::spawn_monitor(
+            Arc::new(shipment_sla::SlaMonitor::new(shipment_sla.clone(), notifier)),
+            std::time::Duration::from_secs(config.shipment_sla.interval_secs),
+        );
+    }
+
+    // Month-end report storms are absorbed by serving cached analytics stale while one
+    // refresh per report runs in the background
+    let analytics_reports = Arc::new(cache::swr::ReportCache::new(
+        Arc::new(cache::RedisCache::new(&config.redis_url)?),
+        config.analytics_cache.clone(),
+    ));
+
+    // Per-SKU reorder thresholds; subscribers are notified only about the SKUs they follow
+    let stock_alerts = Arc::new(stock_alerts::StockAlertService::new(app_state.db_pool.clone()));
+    if config.stock_alerts.enabled {
+        let notifier = Arc::new(notifications::RedisNotificationService::new(
+            (*app_state.redis_client).clone(),
+            log.clone(),
+        ));
+        stock_alerts::spawn_monitor(
+            Arc::new(stock_alerts::StockAlertMonitor::new(stock_alerts.clone(), notifier)),
+            std::time::Duration::from_secs(config.stock_alerts.interval_secs),
+        );
+    }
+
+    // Approval chains for requisitions, returns, credit memos, write-offs and ECOs; steps
+    // left undecided past their deadline are escalated
+    let mut approval_engine = workflow::ApprovalEngine::new(
+        app_state.db_pool.clone(),
+        app_state.event_sender.clone(),
+        config.workflow.clone(),
+    );
+    approval_engine.register(Arc::new(services::requisition_service::RequisitionApprovals));
+    approval_engine.register(Arc::new(services::return_service::ReturnApprovals));
+    approval_engine.register(Arc::new(services::credit_memo_service::CreditMemoApprovals));
+    approval_engine.register(Arc::new(services::write_off_service::WriteOffApprovals));
+    approval_engine.register(Arc::new(services::eco_service::EcoApprovals));
+    let approval_engine = Arc::new(approval_engine);
+    workflow::spawn_escalator(
+        approval_engine.clone(),
+        std::time::Duration::from_secs(config.workflow.escalation_interval_secs),
+    );
+    let requisitions = Arc::new(services::requisition_service::RequisitionService::new(
+        app_state.db_pool.clone(),
+        approval_engine.clone(),
+    ));
+    let credit_memos = Arc::new(services::credit_memo_service::CreditMemoService::new(
+        app_state.db_pool.clone(),
+        app_state.event_sender.clone(),
+        approval_engine.clone(),
+    ));
+    let write_offs = Arc::new(services::write_off_service::WriteOffService::new(
+        app_state.db_pool.clone(),
+        approval_engine.clone(),
+    ));
+    let cross_dock = Arc::new(services::cross_dock_service::CrossDockService::new(
+        app_state.db_pool.clone(),
+        app_state.event_sender.clone(),
+    ));
+    let kitting = Arc::new(services::kitting_service::KittingService::new(
+        app_state.db_pool.clone(),
+        app_state.event_sender.clone(),
+    ));
+    // Approved ECOs waiting on their effective date are checked with the escalations
+    let ecos = Arc::new(services::eco_service::EcoService::new(
+        app_state.db_pool.clone(),
+        app_state.event_sender.clone(),
+        approval_engine.clone(),
+    ));
+    services::eco_service::spawn_effectivity_worker(
+        ecos.clone(),
+        std::time::Duration::from_secs(config.workflow.escalation_interval_secs),
+    );
+    let forecasts = Arc::new(services::forecast_service::ForecastService::new(app_state.db_pool.clone()));
+    let work_centers = Arc::new(services::capacity_service::CapacityService::new(app_state.db_pool.clone()));
+    let equipment = Arc::new(services::equipment_service::EquipmentService::new(
+        app_state.db_pool.clone(),
+        app_state.event_sender.clone(),
+    ));
+    services::equipment_service::spawn_pm_scheduler(
+        equipment.clone(),
+        std::time::Duration::from_secs(config.workflow.escalation_interval_secs),
+    );
+    let scans = Arc::new(services::scan_service::ScanService::new(
+        app_state.db_pool.clone(),
+        app_state.event_sender.clone(),
+    ));
+    let duplicate_orders = Arc::new(services::duplicate_orders::DuplicateOrderService::new(app_state.db_pool.clone()));
+    let geocoder = geocoding::from_config(&config.geocoding).map_err(|e| AppError::ConfigError(e.to_string()))?;
+    let shipping_zones =
+        Arc::new(services::shipping_zone_service::ShippingZoneService::new(app_state.db_pool.clone(), geocoder));
+    let carrier_registry = Arc::new(
+        carriers::CarrierRegistry::from_config(&config.carriers).map_err(|e| AppError::ConfigError(e.to_string()))?,
+    );
+    let manifests = Arc::new(services::manifest_service::ManifestService::new(
+        app_state.db_pool.clone(),
+        carrier_registry,
+        calendars.clone(),
+    ));
+    let hazmat = Arc::new(services::hazmat_service::HazmatService::new(app_state.db_pool.clone()));
+    let customs = Arc::new(services::customs_service::CustomsService::new(
+        app_state.db_pool.clone(),
+        Arc::new(config.customs.clone()),
+    ));
+    let return_dispositions = Arc::new(services::return_disposition_service::ReturnDispositionService::new(
+        app_state.db_pool.clone(),
+        app_state.event_sender.clone(),
+    ));
+    let supplier_registry = Arc::new(
+        dropship::SupplierRegistry::from_config(&config.dropship).map_err(|e| AppError::ConfigError(e.to_string()))?,
+    );
+    let dropship = Arc::new(services::dropship_service::DropshipService::new(
+        app_state.db_pool.clone(),
+        supplier_registry,
+        app_state.event_sender.clone(),
+    ));
+    services::dropship_service::spawn_dropship_listener(dropship.clone(), app_state.event_sender.clone(), drain.worker());
+    // Lots produced by work orders and received by other services are inspected per plan
+    let quality = Arc::new(services::quality_service::QualityService::new(
+        app_state.db_pool.clone(),
+        app_state.event_sender.clone(),
+        config.quality.clone(),
+    ));
+    services::quality_service::spawn_receipt_listener(quality.clone(), app_state.event_sender.clone());
+    let pos = Arc::new(services::pos_service::PosService::new(
+        app_state.db_pool.clone(),
+        app_state.event_sender.clone(),
+    ));
+    let printing = Arc::new(services::print_service::PrintService::new(app_state.db_pool.clone()));
+    services::print_service::spawn_shipment_print_listener(printing.clone(), app_state.event_sender.clone());
+
+    // Vault tokens stand in for payment methods the processor holds; checkout and
+    // subscription billing redeem them instead of handling processor ids
+    let payment_vault = if config.payment_vault.enabled {
+        let gateway = payments::from_config(&config.payments, &app_state.secrets).map_err(|e| AppError::ConfigError(e.to_string()))?;
+        Some(Arc::new(services::payment_vault::PaymentVaultService::new(
+            app_state.db_pool.clone(),
+            gateway,
+            config.payment_vault.clone(),
+        )))
+    } else {
+        None
+    };
+
+    // Routing rules are read on every sourcing decision, so ops edits apply without a deploy
+    let routing_rules = Arc::new(services::routing_rules::RoutingRuleService::new(app_state.db_pool.clone()));
+
+    // Checkout sessions live in the database; Redis only accelerates reads
+    let checkout_cache = if config.checkout.cache_sessions {
+        Some(Arc::new(cache::RedisCache::new(&config.redis_url)?))
+    } else {
+        None
+    };
+    let checkout_sessions = Arc::new(
+        checkout::CheckoutSessionStore::new(app_state.db_pool.clone(), checkout_cache, &config.checkout)
+            .with_vault(payment_vault.clone()),
+    );
+
+    // Listings are embedded as they are written; searches fall back to 503 when disabled
+    let semantic_search = if config.semantic_search.enabled {
+        let embeddings =
+            embeddings::from_config(&config.embeddings).map_err(|e| AppError::ConfigError(e.to_string()))?;
+        info!(log, "Semantic search enabled"; "embeddings" => embeddings.name(), "dimensions" => embeddings.dimensions());
+        let store = Arc::new(semantic_search::PgVectorStore::new(app_state.db_pool.clone()));
+        store
+            .check_schema(embeddings.dimensions())
+            .await
+            .map_err(|e| AppError::ConfigError(e.to_string()))?;
+        let service = Arc::new(semantic_search::SemanticSearchService::new(
+            app_state.db_pool.clone(),
+            store,
+            embeddings,
+            config.semantic_search.clone(),
+        ));
+        semantic_search::spawn_indexer(service.clone(), app_state.event_sender.clone());
+        Some(service)
+    } else {
+        None
+    };
+
+    // Internal ledger; reports are served even when this instance doesn't record postings
+    let ledger = Arc::new(ledger::LedgerService::new(app_state.db_pool.clone()));
+    if config.ledger.enabled {
+        ledger::spawn_recorder(ledger.clone(), app_state.event_sender.clone(), drain.worker());
+    }
+
+    // Developer console: API-key requests and webhook deliveries, redacted and truncated
+    let developer_logs = developer_logs::DeveloperLogService::start(
+        app_state.db_pool.clone(),
+        config.developer_logs.clone(),
+        logging::redaction::Redactor::new(&config.log_redaction),
+    );
+
+    // Entity webhooks; payload transforms are applied per delivery by the dispatcher
+    let webhook_service = Arc::new(
+        webhooks::WebhookService::new(app_state.db_pool.clone(), config.webhooks.clone())
+            .with_cdc_relay(config.cdc.enabled)
+            .with_delivery_log(developer_logs.enabled().then(|| developer_logs.clone())),
+    );
+    if config.webhooks.enabled || config.cdc.enabled {
+        webhooks::spawn_dispatcher(webhook_service.clone(), app_state.event_sender.clone(), drain.worker());
+        webhooks::spawn_purger(webhook_service.clone(), drain.worker());
+    }
+
+    // Change data capture for the data warehouse: row changes derived from the outbox are
+    // published to the message queue in Debezium envelopes
+    if config.cdc.enabled {
+        let connection = message_queue::create_rabbitmq_connection(&config.rabbitmq_url).await?;
+        let channel = message_queue::create_rabbitmq_channel(&connection).await?;
+        let queue = Arc::new(message_queue::RabbitMQ::new(channel, std::time::Duration::from_secs(1), 3));
+        cdc::spawn_relay(
+            Arc::new(cdc::CdcRelay::new(app_state.db_pool.clone(), queue, config.cdc.clone())),
+            drain.worker(),
+        );
+    }
+
+    // Authorized payments are captured on order, as shipments go out, or by hand
+    let payment_captures = if config.payments.enabled {
+        let gateway = payments::from_config(&config.payments, &app_state.secrets).map_err(|e| AppError::ConfigError(e.to_string()))?;
+        let service = Arc::new(services::payment_capture::PaymentCaptureService::new(
+            app_state.db_pool.clone(),
+            app_state.event_sender.clone(),
+            gateway,
+            config.payments.clone(),
+        ));
+        services::payment_capture::spawn_capture_listener(
+            service.clone(),
+            app_state.event_sender.clone(),
+            drain.worker(),
+        );
+        services::payment_capture::spawn_reconciler(
+            service.clone(),
+            std::time::Duration::from_secs(config.payments.reconcile_interval_secs.max(1)),
+            drain.worker(),
+        );
+        Some(service)
+    } else {
+        None
+    };
+
+    // Failed subscription charges are retried along the configured curve, notifying the
+    // subscriber at each stage
+    let dunning = if config.dunning.enabled {
+        let gateway = payments::from_config(&config.payments, &app_state.secrets).map_err(|e| AppError::ConfigError(e.to_string()))?;
+        let notifier = Arc::new(notifications::RedisNotificationService::new(
+            (*app_state.redis_client).clone(),
+            log.clone(),
+        ));
+        let service = Arc::new(
+            dunning::DunningService::new(
+                app_state.db_pool.clone(),
+                app_state.event_sender.clone(),
+                gateway,
+                notifier,
+                config.dunning.clone(),
+            )
+            .with_vault(payment_vault.clone()),
+        );
+        dunning::spawn_worker(
+            service.clone(),
+            std::time::Duration::from_secs(config.dunning.interval_secs),
+            drain.worker(),
+        );
+        Some(service)
+    } else {
+        None
+    };
+
+    // Chargebacks reported by payment provider webhooks; disputes nearing their evidence
+    // deadline are flagged
+    let disputes = Arc::new(disputes::DisputeService::new(
+        app_state.db_pool.clone(),
+        app_state.event_sender.clone(),
+        app_state.secrets.clone(),
+        config.disputes.clone(),
+    ));
+    if config.disputes.enabled {
+        disputes::spawn_deadline_monitor(disputes.clone(), std::time::Duration::from_secs(config.disputes.interval_secs));
+    }
+
+    // Journal entries for orders, refunds and COGS are queued from events and posted
+    // to the accounting system in the background
+    let accounting = if config.accounting.enabled {
+        let adapter = integrations::accounting::providers::from_config(&config.accounting)
+            .map_err(|e| AppError::ConfigError(e.to_string()))?;
+        info!(log, "Accounting export enabled"; "provider" => adapter.provider().as_str());
+        let exporter = Arc::new(integrations::accounting::AccountingExporter::new(
+            app_state.db_pool.clone(),
+            adapter,
+            config.accounting.clone(),
+        ));
+        integrations::accounting::spawn_recorder(exporter.clone(), app_state.event_sender.clone(), drain.worker());
+        integrations::accounting::spawn_worker(
+            exporter.clone(),
+            std::time::Duration::from_secs(config.accounting.interval_secs),
+            drain.worker(),
+        );
+        Some(exporter)
+    } else {
+        None
+    };
+
+    // Billable usage is counted per tenant and rolled into monthly usage records
+    let meter = if config.metering.enabled {
+        let meter = Arc::new(
+            metering::Meter::new(app_state.db_pool.clone(), config.metering.clone())
+                .map_err(|e| AppError::ConfigError(e.to_string()))?,
+        );
+        metering::spawn_order_listener(meter.clone(), app_state.event_sender.clone());
+        metering::spawn_worker(meter.clone());
+        Some(meter)
+    } else {
+        None
+    };
+
+    // Order support chat; answers are grounded in records the caller may see
+    let assist_service = if config.assist.enabled {
+        let model = assist::OpenAiChat::from_config(&config.assist).map_err(|e| AppError::ConfigError(e.to_string()))?;
+        Some(Arc::new(assist::AssistService::new(
+            app_state.db_pool.clone(),
+            Arc::new(model),
+            config.assist.clone(),
+        )))
+    } else {
+        None
+    };
+
+    // Background agents run on their own intervals and record decisions for audit
+    let mut agent_registry = agents::AgentRegistry::new(app_state.db_pool.clone(), config.agents.clone());
+    agent_registry.register(Arc::new(agents::inventory::InventoryAgent::new(app_state.db_pool.clone())));
+    if config.order_fraud.enabled {
+        agent_registry.register(Arc::new(agents::fraud::FraudAgent::new(
+            app_state.db_pool.clone(),
+            config.order_fraud.clone(),
+        )));
+    }
+    if config.return_triage.enabled {
+        // Comment assessment reuses the chat model configured for assist
+        let model: Option<Arc<dyn assist::ChatModel>> = if config.return_triage.assess_comments {
+            let model = assist::OpenAiChat::from_config(&config.assist).map_err(|e| AppError::ConfigError(e.to_string()))?;
+            Some(Arc::new(model))
+        } else {
+            None
+        };
+        let triage = services::return_triage::ReturnTriage::new(
+            app_state.db_pool.clone(),
+            Arc::new(services::return_service::ReturnService::new(app_state.db_pool.clone())),
+            app_state.event_sender.clone(),
+            config.return_triage.clone(),
+            model,
+        );
+        agent_registry.register(Arc::new(agents::returns::ReturnTriageAgent::new(Arc::new(triage))));
+    }
+    // Scoring runs only when enabled; serials can be recorded and returns assessed either way
+    let return_fraud = Arc::new(services::return_fraud::ReturnFraudService::new(
+        app_state.db_pool.clone(),
+        config.return_fraud.clone(),
+    ));
+    if config.return_fraud.enabled {
+        agent_registry.register(Arc::new(agents::return_fraud::ReturnFraudAgent::new(return_fraud.clone())));
+    }
+    let agent_registry = Arc::new(agent_registry);
+    if config.agents.enabled {
+        agent_registry.start(&drain);
+    }
+
+    let auth_config = Arc::new(auth::AuthConfig::from_app_config(&config).with_secrets(app_state.secrets.clone()));
+    let service_account_authenticator = Arc::new(auth::service_accounts::ServiceAccountAuthenticator::new(
+        app_state.db_pool.clone(),
+        auth_config.clone(),
+        network_acl::CidrList::parse(&config.network_acl.trusted_proxies),
+    ));
+
+    let network_acl = Arc::new(network_acl::NetworkAcl::new(&config.network_acl));
+    if config.network_acl.enabled {
+        network_acl.refresh_from_db(&app_state.db_pool).await?;
+        network_acl::spawn_refresh(
+            network_acl.clone(),
+            app_state.db_pool.clone(),
+            std::time::Duration::from_secs(config.network_acl.refresh_interval_secs),
+        );
+    }
+
+    // The blocklist is managed through the admin routes even while detection is off
+    let abuse_detector = Arc::new(abuse::AbuseDetector::new(
+        app_state.db_pool.clone(),
+        config.abuse.clone(),
+        network_acl::CidrList::parse(&config.network_acl.trusted_proxies),
+    ));
+    if config.abuse.enabled {
+        abuse_detector.refresh_from_db().await?;
+        abuse::spawn_refresh(
+            abuse_detector.clone(),
+            std::time::Duration::from_secs(config.abuse.refresh_interval_secs),
+        );
+    }
+
+    // Kill switches and read-only mode follow the live feature flags
+    let maintenance = Arc::new(maintenance::Maintenance::new(config.maintenance.clone(), config_watcher.handle()));
+
+    // Objectives are evaluated from the buckets of every instance, so the admin routes
+    // work on instances that don't record
+    let slo_tracker = Arc::new(
+        slo::SloTracker::new(app_state.db_pool.clone(), config.slo.clone())
+            .map_err(|e| AppError::ConfigError(e.to_string()))?,
+    );
+    if config.slo.enabled {
+        slo::spawn_flusher(slo_tracker.clone());
+    }
+
+    // Until enabled, /health/synthetic reports unknown rather than failing
+    let synthetic_checker = Arc::new(synthetic::SyntheticChecker::new(app_state.db_pool.clone(), config.synthetic.clone()));
+    if config.synthetic.enabled {
+        synthetic::spawn_scheduler(synthetic_checker.clone());
+    }
+
+    let signature_verifier = config.request_signing.enabled.then(|| {
+        Arc::new(
+            middleware_helpers::request_signing::SignatureVerifier::new(
+                &config.request_signing,
+                Arc::new(middleware_helpers::request_signing::RedisNonceStore::new(app_state.redis_client.clone())),
+            )
+            .with_secrets(app_state.secrets.clone()),
+        )
+    });
+    let signed = |router: Router| middleware_helpers::request_signing::signed(router, signature_verifier.clone());
+
+    // Long-running work reports status through /api/v1/jobs instead of fire-and-forget spawns
+    let job_runner = Arc::new(jobs::JobRunner::new(app_state.db_pool.clone()));
+    job_runner.fail_interrupted().await?;
+    jobs::spawn_heartbeat(job_runner.clone());
+
+    // Data keys are unwrapped before serving so encrypted columns read back as plaintext
+    let encryption_service = if config.encryption.enabled {
+        let provider = encryption::provider_from_config(&config.encryption)
+            .await
+            .map_err(|e| AppError::ConfigError(e.to_string()))?;
+        let service = Arc::new(encryption::EncryptionService::new(
+            app_state.db_pool.clone(),
+            provider,
+            config.encryption.clone(),
+        ));
+        let keys = service.load().await.map_err(|e| AppError::ConfigError(e.to_string()))?;
+        info!(log, "Column encryption enabled"; "keys" => keys);
+        encryption::spawn_scheduler(job_runner.clone(), service.clone());
+        Some(service)
+    } else {
+        None
+    };
+
+    // Due maintenance is only checked for when enabled; the admin routes work either way
+    let partition_manager = Arc::new(
+        partitioning::PartitionManager::new(app_state.db_pool.clone(), config.partitioning.clone())
+            .map_err(|e| AppError::ConfigError(e.to_string()))?,
+    );
+    if config.partitioning.enabled {
+        partitioning::spawn_scheduler(partition_manager.clone());
+    }
+
+    // Offboarding exports need somewhere to put bundles; without a bucket the route answers 503
+    let tenant_exports = match &config.tenant_export.bucket {
+        Some(bucket) => Some(Arc::new(tenant_export::TenantExportService::new(
+            app_state.db_pool.clone(),
+            Arc::new(tenant_export::S3ExportStore::new(bucket.clone()).await),
+            config.tenant_export.clone(),
+        ))),
+        None => None,
+    };
+
+    // Backfills whose process stopped wait paused until resumed by hand
+    let backfill_service = Arc::new(
+        backfill::BackfillService::new(app_state.db_pool.clone(), config.backfills.clone())
+            .map_err(|e| AppError::ConfigError(e.to_string()))?,
+    );
+    backfill::spawn_recovery(backfill_service.clone());
+
+    // Segment evaluations run as jobs so their outcome is visible under /api/v1/jobs
+    let customer_segments = Arc::new(customer_segments::CustomerSegmentService::new(
+        app_state.db_pool.clone(),
+        config.customer_segments.rules.clone(),
+    ));
+    if config.customer_segments.enabled {
+        customer_segments::spawn_scheduler(
+            job_runner.clone(),
+            customer_segments.clone(),
+            std::time::Duration::from_secs(config.customer_segments.interval_secs),
+        );
+    }
+
+    // Amazon orders and FBA stock are pulled on a schedule through the job runner
+    let amazon_sync = if config.amazon.enabled {
+        let client = integrations::amazon::SpApiClient::from_config(&config.amazon)
+            .map_err(|e| AppError::ConfigError(e.to_string()))?;
+        info!(log, "Amazon sync enabled"; "marketplace" => client.marketplace_id());
+        let sync = Arc::new(integrations::amazon::AmazonSync::new(
+            app_state.db_pool.clone(),
+            client,
+            config.amazon.clone(),
+            app_state.event_sender.clone(),
+        ));
+        integrations::amazon::spawn_scheduler(job_runner.clone(), sync.clone());
+        Some(sync)
+    } else {
+        None
+    };
+
+    // Live order board updates; the handshake authenticates itself, so these routes
+    // are merged outside of `auth_middleware`
+    let websocket_routes = if config.websocket.enabled {
+        websocket::router(Arc::new(websocket::WebSocketHub::new(
+            Arc::new(app_state.event_sender.clone()),
+            auth_config.clone(),
+            config.websocket.clone(),
+        )))
+    } else {
+        Router::new()
+    };
+
+    // Every admin route requires a signed request when signing is enabled
+    let admin_routes = Router::new()
+        .nest("/usage", handlers::usage::usage_routes(meter.clone()))
+        .nest(
+            "/encryption",
+            handlers::encryption::encryption_routes(encryption_service.clone(), job_runner.clone()),
+        )
+        .nest(
+            "/tenants",
+            handlers::tenant_exports::tenant_export_routes(tenant_exports.clone(), job_runner.clone()),
+        )
+        .nest("/partitions", handlers::partitions::partition_routes(partition_manager.clone()))
+        .nest(
+            "/backfills",
+            handlers::backfills::backfill_routes(backfill_service, app_state.db_pool.clone(), job_runner.clone()),
+        )
+        .nest("/ingest/quarantine", handlers::ingest::quarantine_routes(ingest_service.clone()))
+        .nest("/abuse", handlers::abuse::abuse_routes(abuse_detector.clone()))
+        .nest("/maintenance", handlers::maintenance::maintenance_routes(maintenance.clone()))
+        .nest("/slos", handlers::slos::slo_routes(slo_tracker.clone()))
+        .nest(
+            "/database",
+            handlers::database::database_admin_routes(Arc::new(db::index_advisor::IndexAdvisor::new(
+                app_state.db_pool.clone(),
+            ))),
+        )
+        .nest("/inbound-emails", handlers::inbound_email::inbound_email_routes(inbound_email_service.clone()))
+        .nest("/routing-rules", handlers::routing_rules::routing_rule_routes(routing_rules.clone()));
+
     // Build our application with routes
     let app = Router::new()
         .route("/health", get(handlers::health::health_check))
+        .merge(handlers::database::database_health_routes(pool_monitor))
+        .merge(handlers::synthetic::synthetic_health_routes(synthetic_checker))
         .nest("/orders", handlers::orders::routes())
         .nest("/inventory", handlers::inventory::routes())
         .nest("/returns", handlers::returns::routes())
@@ -210,7 +928,7 @@ async fn main() -> Result<(), AppError> {
         .nest("/logistics", handlers::logistics::routes())
         .nest("/warehousing", handlers::warehousing::routes())
         .nest("/invoicing", handlers::invoicing::routes())
-        .nest("/payments", handlers::payments::routes())
+        .nest("/payments", signed(handlers::payments::routes()))
         .nest("/accounting", handlers::accounting::routes())
         .nest("/budgeting", handlers::budgeting::routes())
         .nest("/financial_reporting", handlers::financial_reporting::routes())
@@ -235,45 +953,352 @@ async fn main() -> Result<(), AppError> {
         .nest("/configurations", handlers::configurations::routes())
         .nest("/notifications", handlers::notifications::routes())
         .nest("/logs", handlers::logs::routes())
-        .nest("/reports", handlers::reports::routes())
+        .nest("/reports", handlers::reports::routes(app_state.db_pool.clone(), analytics_reports.clone()))
         .nest("/exports", handlers::exports::routes())
         .nest("/imports", handlers::imports::routes())
         .nest("/alerts", handlers::alerts::routes())
         .nest("/oauth", handlers::oauth::routes())
         .nest("/notes", handlers::notes::routes())
         .nest("/users", handlers::users::routes())
+        .nest("/admin/service_accounts", signed(handlers::service_accounts::service_account_routes()))
+        .nest("/api/v1/admin", signed(admin_routes))
+        .nest("/api/v1/jobs", handlers::jobs::job_routes(job_runner.clone()))
+        .nest("/api/v1/webhooks", handlers::webhooks::webhook_routes(webhook_service, job_runner.clone()))
+        .nest(
+            "/api/v1/inventory",
+            handlers::inventory_history::history_routes(inventory_snapshots)
+                .merge(handlers::inventory_levels::level_routes(inventory_levels)),
+        )
+        .nest("/api/v1/checkout", handlers::checkout::checkout_routes(checkout_sessions))
+        .nest(
+            "/api/v1/products",
+            handlers::products::product_routes(handlers::products::ProductRoutesState {
+                db: app_state.db_pool.clone(),
+                listings: Arc::new(services::product_listing_service::ProductListingService::new(
+                    app_state.db_pool.clone(),
+                    app_state.event_sender.clone(),
+                )),
+                search: semantic_search,
+            }),
+        )
+        .nest("/api/v1/agents", handlers::agents::agent_routes(agent_registry))
+        .nest("/api/v1/assist", handlers::assist::assist_routes(assist_service))
+        .nest(
+            "/api/v1/analytics",
+            handlers::analytics::analytics_routes(
+                shipment_sla,
+                Arc::new(services::supplier_scorecard::SupplierScorecardService::new(
+                    app_state.db_pool.clone(),
+                    calendars.clone(),
+                )),
+                Arc::new(services::inventory_aging::InventoryAgingService::new(
+                    app_state.db_pool.clone(),
+                    calendars.clone(),
+                    config.inventory_aging.clone(),
+                )),
+                analytics_reports,
+            ),
+        )
+        .nest(
+            "/api/v1/agentic",
+            handlers::agentic::agentic_routes(Arc::new(product_feed::ProductFeedService::new(
+                app_state.db_pool.clone(),
+                config.product_feed.clone(),
+            ))),
+        )
+        .nest(
+            "/api/v1/bundles",
+            handlers::bundles::bundle_routes(Arc::new(services::bundle_service::BundleService::new(
+                app_state.db_pool.clone(),
+            ))),
+        )
+        .nest(
+            "/api/v1/customers",
+            handlers::customer_segments::segment_routes(customer_segments, job_runner.clone()),
+        )
+        .nest(
+            "/api/v1/quality",
+            handlers::quality::quality_routes(quality),
+        )
+        .nest("/api/v1/requisitions", handlers::requisitions::requisition_routes(requisitions))
+        .nest("/api/v1/ledger", handlers::ledger::ledger_routes(ledger))
+        .nest("/api/v1/accounting", handlers::accounting::accounting_routes(accounting))
+        .nest(
+            "/api/v1/integrations/amazon",
+            handlers::amazon::amazon_routes(amazon_sync, job_runner.clone()),
+        )
+        .nest("/api/v1/credit-memos", handlers::credit_memos::credit_memo_routes(credit_memos))
+        .nest("/api/v1/write-offs", handlers::write_offs::write_off_routes(write_offs))
+        .nest("/api/v1/asns", handlers::cross_dock::asn_routes(cross_dock.clone()))
+        .nest("/api/v1/backorders", handlers::cross_dock::backorder_routes(cross_dock.clone()))
+        .nest("/api/v1/transfer-orders", handlers::cross_dock::transfer_order_routes(cross_dock))
+        .nest("/api/v1/kit-work-orders", handlers::kit_work_orders::kit_work_order_routes(kitting))
+        .nest("/api/v1/ecos", handlers::ecos::eco_routes(ecos))
+        .nest("/api/v1/forecasts", handlers::forecasts::forecast_routes(forecasts))
+        .nest("/api/v1/work-centers", handlers::work_centers::work_center_routes(work_centers))
+        .nest("/api/v1/maintenance", handlers::maintenance_work_orders::maintenance_routes(equipment))
+        .nest(
+            "/api/v1/labels",
+            handlers::labels::label_routes(Arc::new(labels::LabelService::new(app_state.db_pool.clone()))),
+        )
+        .nest("/api/v1/scan", handlers::scan::scan_routes(scans))
+        .nest("/api/v1/printers", handlers::printers::printer_routes(printing))
+        .nest(
+            "/api/v1/order-duplicates",
+            handlers::duplicate_orders::duplicate_order_routes(duplicate_orders),
+        )
+        .nest("/api/v1/shipping-zones", handlers::shipping_zones::shipping_zone_routes(shipping_zones))
+        .nest(
+            "/api/v1/sourcing",
+            handlers::sourcing::sourcing_routes(Arc::new(services::sourcing::SourcingService::new(
+                app_state.db_pool.clone(),
+                routing_rules.clone(),
+                config.sourcing.clone(),
+            ))),
+        )
+        .nest("/api/v1/stock-alerts", handlers::stock_alerts::stock_alert_routes(stock_alerts))
+        .nest("/api/v1/developer", handlers::developer_logs::developer_log_routes(developer_logs.clone()))
+        .nest(
+            "/api/v1/custom-fields",
+            handlers::custom_fields::custom_field_routes(Arc::new(custom_fields::CustomFieldService::new(
+                app_state.db_pool.clone(),
+            ))),
+        )
+        .nest("/api/v1/shipments/manifests", handlers::manifests::manifest_routes(manifests))
+        .nest("/api/v1/hazmat", handlers::hazmat::hazmat_routes(hazmat))
+        .nest("/api/v1/customs", handlers::customs::customs_routes(customs))
+        .nest(
+            "/api/v1/return-dispositions",
+            handlers::return_dispositions::return_disposition_routes(return_dispositions),
+        )
+        .nest("/api/v1/return-fraud", handlers::return_fraud::return_fraud_routes(return_fraud))
+        .nest("/api/v1/dropship", handlers::dropship::dropship_routes(dropship))
+        .nest("/api/v1/pos", handlers::pos::pos_routes(pos))
+        // Routes moving money require signed requests, like /payments
+        .nest(
+            "/api/v1/payment-authorizations",
+            signed(handlers::payment_captures::payment_capture_routes(payment_captures)),
+        )
+        .nest("/api/v1/payment-vault", signed(handlers::payment_vault::payment_vault_routes(payment_vault)))
+        .nest("/api/v1/subscriptions", signed(handlers::subscriptions::subscription_routes(dunning)))
+        .nest("/api/v1/disputes", handlers::disputes::dispute_routes(disputes.clone()))
+        .nest("/api/v1/approvals", handlers::approvals::approval_routes(approval_engine))
+        .nest(
+            "/api/v1/ncrs",
+            handlers::ncr::ncr_routes(Arc::new(services::ncr_service::NonConformanceService::new(
+                app_state.db_pool.clone(),
+            ))),
+        )
+        .nest(
+            "/api/v1/cases",
+            handlers::cases::case_routes(Arc::new(services::case_service::CaseService::new(
+                app_state.db_pool.clone(),
+                config.cases.clone(),
+            ))),
+        )
+        .nest(
+            "/api/v1/work-orders",
+            handlers::work_order_operations::operation_routes(Arc::new(
+                services::work_order_operations::WorkOrderOperationService::new(
+                    app_state.db_pool.clone(),
+                    app_state.event_sender.clone(),
+                ),
+            )),
+        )
         .route("/proto_endpoint", post(handle_proto_request))
         .layer(Extension(app_state))
         .layer(Extension(schema))
-        .layer(TraceLayer::new_for_http())
-        .layer(CompressionLayer::new())
+        .layer(TraceLayer::new_for_http().make_span_with(logging::RedactedMakeSpan::new(
+            logging::redaction::Redactor::new(&config.log_redaction),
+        )))
+        .layer(axum::middleware::from_fn_with_state(
+            Arc::new(config.compression.clone()),
+            compression::response_policy_middleware,
+        ))
+        .layer(compression::layer(&config.compression))
+        // Values written during a request are encrypted with the key of the tenant in
+        // the caller's claims, so this runs inside auth
+        .layer(axum::middleware::from_fn(encryption::tenant_scope_middleware))
+        // API calls are billed to the tenant in the verified claims, once the tenant
+        // allowlist let them through
+        .layer(axum::middleware::from_fn_with_state(meter.clone(), metering::metering_middleware))
+        // Tenant allowlists need the verified claims, so they are checked inside auth
+        .layer(axum::middleware::from_fn_with_state(network_acl.clone(), network_acl::tenant_acl_middleware))
         .layer(axum::middleware::from_fn(auth::auth_middleware))
-        .layer(axum::middleware::from_fn(rate_limiter::rate_limit_middleware));
+        .layer(axum::middleware::from_fn_with_state(
+            service_account_authenticator.clone(),
+            auth::service_accounts::client_certificate_middleware,
+        ))
+        // Requests made with API keys are kept for their owners' developer console. This
+        // runs inside API-key authentication, so only keys that authenticated are logged
+        .layer(axum::middleware::from_fn_with_state(
+            developer_logs.clone(),
+            developer_logs::developer_log_middleware,
+        ))
+        .layer(axum::middleware::from_fn_with_state(
+            service_account_authenticator.clone(),
+            auth::service_accounts::api_key_middleware,
+        ))
+        .with_state(service_account_authenticator.clone())
+        // Token requests carry client credentials rather than a token, so they skip auth
+        .merge(handlers::service_accounts::token_routes(service_account_authenticator))
+        .merge(websocket_routes)
+        .merge(handlers::disputes::dispute_webhook_routes(disputes))
+        .merge(handlers::ingest::ingest_routes(ingest_service))
+        .merge(handlers::inbound_email::inbound_email_webhook_routes(inbound_email_service))
+        .layer(axum::middleware::from_fn(rate_limiter::rate_limit_middleware))
+        .layer(config::watcher::cors_layer(config_watcher.handle()));
+
+    // Sampled request/response pairs are archived to S3 after redaction
+    let app = if config.request_archive.enabled {
+        let archiver = request_archive::RequestArchiver::start(
+            config.request_archive.clone(),
+            logging::redaction::Redactor::new(&config.log_redaction),
+            Arc::new(request_archive::S3Sink::new(config.request_archive.bucket.clone()).await),
+        );
+        app.layer(axum::middleware::from_fn_with_state(archiver, request_archive::request_archive_middleware))
+    } else {
+        app
+    };
+
+    // Request bodies are only logged when enabled, and always with PII redacted
+    let app = if config.log_redaction.log_bodies {
+        let logging_state = Arc::new(logging::LoggingState::with_redactor(
+            log.clone(),
+            logging::redaction::Redactor::new(&config.log_redaction),
+        ));
+        app.layer(axum::middleware::from_fn_with_state(logging_state, logging::body_logging_middleware))
+    } else {
+        app
+    };
+
+    // Deadlines bound everything behind them, including outbound calls made by handlers;
+    // only reads are cut off mid-flight
+    let app = if config.deadlines.enabled {
+        app.layer(axum::middleware::from_fn_with_state(
+            Arc::new(config.deadlines.clone()),
+            deadline::deadline_middleware,
+        ))
+    } else {
+        app
+    };
+
+    // Requests refused for maintenance, abuse or by the ACL don't count against SLOs
+    let app = if config.slo.enabled {
+        app.layer(axum::middleware::from_fn_with_state(slo_tracker, slo::slo_middleware))
+    } else {
+        app
+    };
+
+    // Switched-off route groups are refused before auth and metering
+    let app = app.layer(axum::middleware::from_fn_with_state(maintenance, maintenance::maintenance_middleware));
+
+    // Scrapers are fingerprinted after the network ACL and before metering, so challenged
+    // and blocked requests aren't metered
+    let app = if config.abuse.enabled {
+        app.layer(axum::middleware::from_fn_with_state(abuse_detector, abuse::abuse_detection_middleware))
+    } else {
+        app
+    };
+
+    // The network ACL is the outermost layer so blocked clients never reach auth
+    let app = if config.network_acl.enabled {
+        app.layer(axum::middleware::from_fn_with_state(network_acl, network_acl::network_acl_middleware))
+    } else {
+        app
+    };
 
     // Run our app with Hyper
     let addr = format!("{}:{}", config.host, config.port);
-    info!(log, "StateSet API server running"; "address" => &addr);
-    axum::Server::bind(&addr.parse().unwrap())
-        .serve(app.into_make_service())
-        .await
-        .unwrap();
+    info!(log, "StateSet API server running"; "address" => &addr, "tls" => config.tls.enabled);
+    let (signal, notice) = shutdown::signal_with_notice();
+    config.http_server.check().map_err(|e| AppError::ConfigError(e.to_string()))?;
+    let incoming = config
+        .http_server
+        .bind(addr.parse().unwrap())
+        .map_err(|e| AppError::ConfigError(e.to_string()))?;
+    let drain_timeout = std::time::Duration::from_secs(config.shutdown.drain_timeout_secs);
+    let (deadline, drained) = if config.tls.enabled {
+        let tls_config = config
+            .tls
+            .server_config(config.http_server.http2_enabled)
+            .map_err(|e| AppError::ConfigError(e.to_string()))?;
+        let handshake_timeout = std::time::Duration::from_secs(config.tls.handshake_timeout_secs);
+        // Outermost, so the network ACL sees the socket address of TLS connections too
+        let app = app.layer(axum::middleware::from_fn(tls::connect_info_middleware));
+        let server = config
+            .http_server
+            .builder(tls::accept(incoming, tls_config, handshake_timeout))
+            .serve(app.into_make_service_with_connect_info::<tls::TlsConnectInfo>())
+            .with_graceful_shutdown(signal);
+        shutdown::drain_server(server, notice, drain_timeout).await
+    } else {
+        let server = config
+            .http_server
+            .builder(incoming)
+            .serve(app.into_make_service_with_connect_info::<std::net::SocketAddr>())
+            .with_graceful_shutdown(signal);
+        shutdown::drain_server(server, notice, drain_timeout).await
+    };
+
+    // Background loops, the outbox publishers among them, and jobs share what is left of
+    // the drain window. Loops stop after their current unit of work; jobs still running
+    // are marked interrupted once their lease lapses rather than left looking alive
+    let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+    let (loops_left, jobs_left) = tokio::join!(drain.wait(remaining), job_runner.drain(remaining));
+    if let Some(meter) = &meter {
+        if let Err(e) = meter.flush().await {
+            slog::warn!(log, "Flushing usage counters failed"; "error" => e.to_string());
+        }
+    }
+    global::shutdown_tracer_provider();
 
-    info!(log, "Shutting down");
+    info!(log, "Shut down"; "requests_drained" => drained, "loops_left" => loops_left, "jobs_left" => jobs_left);
     Ok(())
 }
 
-/// Sets up the logger using slog
-fn setup_logger(config: &AppConfig) -> Logger {
+/// Sets up the logger using slog; the level can be changed at runtime through `level`
+fn setup_logger(level: logging::LevelHandle) -> Logger {
     let decorator = slog_term::TermDecorator::new().build();
     let drain = slog_term::FullFormat::new(decorator).build().fuse();
     let drain = slog_async::Async::new(drain).build().fuse();
-    let drain = slog::LevelFilter::new(drain, config.log_level.parse().unwrap()).fuse();
+    let drain = logging::ReloadableLevelFilter::new(drain, level).fuse();
     slog::Logger::root(drain, o!())
 }
 
+/// Generates deterministic demo data and writes it to the configured database.
+/// Refuses to run against a production environment.
+async fn run_seed(config: &AppConfig, args: Vec<String>, log: &Logger) -> Result<(), AppError> {
+    if config.is_production() {
+        return Err(AppError::ConfigError("Refusing to seed demo data in production".to_string()));
+    }
+    let options = seed::SeedOptions::from_args(args).map_err(AppError::ConfigError)?;
+    let db = db::connect_pool(&config.database_url, &config.database_pool.tools, &config.database_pool).await?;
+    let data = seed::generate(&options);
+    let summary = seed::persist(&db, &data)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    info!(log, "Seed data written";
+        "seed" => options.seed,
+        "product_listings" => summary.product_listings,
+        "customers" => summary.customers,
+        "inventory_items" => summary.inventory_items,
+        "orders" => summary.orders,
+        "order_line_items" => summary.order_line_items,
+        "shipments" => summary.shipments,
+        "returns" => summary.returns
+    );
+    Ok(())
+}
+
 /// Builds the application state by initializing the database, cache, message queues, and services
-async fn build_app_state(config: &Arc<AppConfig>, log: &Logger) -> Result<AppState, AppError> {
-    let db_pool = Arc::new(db::establish_connection(&config.database_url).await?);
+async fn build_app_state(
+    config: &Arc<AppConfig>,
+    secrets: config::secrets::SecretStore,
+    config_watcher: &config::watcher::ConfigWatcher,
+    log: &Logger,
+) -> Result<AppState, AppError> {
+    let db_pool = Arc::new(db::connect_pool(&config.database_url, &config.database_pool.api, &config.database_pool).await?);
     let redis_client = Arc::new(redis::Client::open(&config.redis_url)?);
     let rabbit_conn = message_queue::connect_rabbitmq(&config.rabbitmq_url).await?;
     let (event_sender, _) = broadcast::channel::<events::Event>(100);
@@ -283,12 +1308,14 @@ async fn build_app_state(config: &Arc<AppConfig>, log: &Logger) -> Result<AppSta
         redis_client.clone(),
         rabbit_conn,
         event_sender.clone(),
+        config_watcher,
         log.clone(),
     )
     .await?;
 
     Ok(AppState {
         config: config.clone(),
+        secrets,
         db_pool,
         redis_client,
         event_sender,
@@ -303,10 +1330,21 @@ async fn initialize_services(
     redis_client: Arc<redis::Client>,
     rabbit_conn: lapin::Connection,
     event_sender: broadcast::Sender<events::Event>,
+    config_watcher: &config::watcher::ConfigWatcher,
     log: Logger,
 ) -> Result<Services, AppError> {
     // Initialize common components
-    let rate_limiter = Arc::new(rate_limiter::RateLimiter::new(redis_client.clone(), "global", 1000, 60));
+    let limits = config_watcher.handle().load().rate_limit.clone();
+    let rate_limiter = Arc::new(rate_limiter::RateLimiter::new(
+        redis_client.clone(),
+        "global",
+        limits.max_requests,
+        limits.window_seconds,
+    ));
+    let live_limiter = rate_limiter.clone();
+    config_watcher.on_change(move |settings| {
+        live_limiter.update_limits(settings.rate_limit.max_requests, settings.rate_limit.window_seconds);
+    });
     let message_queue = Arc::new(message_queue::RabbitMQ::new(rabbit_conn));
     let circuit_breaker = Arc::new(circuit_breaker::CircuitBreaker::new(5, std::time::Duration::from_secs(60)));
 
@@ -0,0 +1,260 @@
+// maintenance/mod.rs
+
+use axum::{
+    extract::State,
+    http::{header, HeaderValue, Method, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use lazy_static::lazy_static;
+use prometheus::IntCounterVec;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{collections::BTreeMap, sync::Arc};
+use tracing::debug;
+
+use crate::config::watcher::{LiveSettings, SettingsHandle};
+
+lazy_static! {
+    static ref MAINTENANCE_REJECTED: IntCounterVec =
+        IntCounterVec::new(
+            "maintenance_rejected_total",
+            "Total number of requests refused by kill switches or read-only mode",
+            &["reason"]
+        ).expect("metric can be created");
+}
+
+/// Feature flag that puts the whole API in read-only mode.
+pub const READ_ONLY_FLAG: &str = "read_only_mode";
+
+/// Prefix of the feature flags that switch off a route group, e.g. `maintenance_checkout`.
+pub const KILL_SWITCH_PREFIX: &str = "maintenance_";
+
+/// Route groups and read-only exemptions, loaded from the `maintenance` section of the
+/// config. Which switches are on comes from the reloadable feature flags.
+#[derive(Clone, Debug, Deserialize)]
+pub struct MaintenanceConfig {
+    /// Path prefixes of each route group a kill switch can take offline.
+    #[serde(default = "default_route_groups")]
+    pub route_groups: BTreeMap<String, Vec<String>>,
+
+    /// Paths that still accept writes in read-only mode (default: token issuance).
+    #[serde(default = "default_read_only_exempt_paths")]
+    pub read_only_exempt_paths: Vec<String>,
+
+    /// Sent as `Retry-After` with maintenance responses (default: 300 s).
+    #[serde(default = "default_retry_after_secs")]
+    pub retry_after_secs: u64,
+
+    /// Shown to clients in maintenance responses.
+    #[serde(default = "default_message")]
+    pub message: String,
+}
+
+fn default_route_groups() -> BTreeMap<String, Vec<String>> {
+    let group = |prefixes: &[&str]| prefixes.iter().map(|prefix| prefix.to_string()).collect();
+    BTreeMap::from([
+        ("checkout".to_string(), group(&["/api/v1/checkout"])),
+        ("payments".to_string(), group(&["/payments", "/api/v1/payment-authorizations"])),
+        ("orders".to_string(), group(&["/orders"])),
+        ("returns".to_string(), group(&["/returns", "/api/v1/return-dispositions"])),
+        ("ingest".to_string(), group(&["/api/v1/ingest"])),
+    ])
+}
+
+fn default_read_only_exempt_paths() -> Vec<String> {
+    vec!["/oauth2/token".to_string()]
+}
+
+fn default_retry_after_secs() -> u64 {
+    300
+}
+
+fn default_message() -> String {
+    "This part of the API is temporarily unavailable for maintenance".to_string()
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            route_groups: default_route_groups(),
+            read_only_exempt_paths: default_read_only_exempt_paths(),
+            retry_after_secs: default_retry_after_secs(),
+            message: default_message(),
+        }
+    }
+}
+
+/// Why a request was refused.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Refusal {
+    /// The kill switch of this route group is on.
+    RouteGroup(String),
+    ReadOnly,
+}
+
+/// Active switches, as reported by the admin API.
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceStatus {
+    pub read_only: bool,
+    pub disabled_groups: Vec<String>,
+    /// Groups that can be switched off, with their flag names.
+    pub switches: BTreeMap<String, String>,
+}
+
+fn under(path: &str, prefix: &str) -> bool {
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+/// Flag name of a route group's kill switch.
+pub fn kill_switch_flag(group: &str) -> String {
+    format!("{}{}", KILL_SWITCH_PREFIX, group)
+}
+
+/// Kill switches and read-only mode driven by the live feature flags.
+pub struct Maintenance {
+    config: MaintenanceConfig,
+    settings: SettingsHandle,
+}
+
+impl Maintenance {
+    pub fn new(config: MaintenanceConfig, settings: SettingsHandle) -> Self {
+        Self { config, settings }
+    }
+
+    /// Decides whether a request may proceed under the given settings. Kill switches
+    /// apply to every method; read-only mode only to writes.
+    pub fn check(&self, settings: &LiveSettings, method: &Method, path: &str) -> Option<Refusal> {
+        let disabled = self.config.route_groups.iter().find(|(group, prefixes)| {
+            settings.feature_enabled(&kill_switch_flag(group)) && prefixes.iter().any(|prefix| under(path, prefix))
+        });
+        if let Some((group, _)) = disabled {
+            return Some(Refusal::RouteGroup(group.clone()));
+        }
+
+        let write = !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS);
+        let exempt = self.config.read_only_exempt_paths.iter().any(|prefix| under(path, prefix));
+        (write && !exempt && settings.feature_enabled(READ_ONLY_FLAG)).then_some(Refusal::ReadOnly)
+    }
+
+    pub fn status(&self) -> MaintenanceStatus {
+        let settings = self.settings.load();
+        MaintenanceStatus {
+            read_only: settings.feature_enabled(READ_ONLY_FLAG),
+            disabled_groups: self
+                .config
+                .route_groups
+                .keys()
+                .filter(|group| settings.feature_enabled(&kill_switch_flag(group)))
+                .cloned()
+                .collect(),
+            switches: self
+                .config
+                .route_groups
+                .keys()
+                .map(|group| (group.clone(), kill_switch_flag(group)))
+                .collect(),
+        }
+    }
+
+    fn refuse(&self, refusal: Refusal) -> Response {
+        let (reason, code, group, details) = match &refusal {
+            Refusal::RouteGroup(group) => (group.as_str(), "maintenance", Some(group.as_str()), self.config.message.as_str()),
+            Refusal::ReadOnly => (
+                "read_only",
+                "read_only",
+                None,
+                "The API is in read-only mode during incident response; retry writes later",
+            ),
+        };
+        MAINTENANCE_REJECTED.with_label_values(&[reason]).inc();
+        let mut response = (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "error": "Service Unavailable",
+                "code": code,
+                "group": group,
+                "details": details,
+                "retry_after_secs": self.config.retry_after_secs,
+            })),
+        )
+            .into_response();
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, HeaderValue::from(self.config.retry_after_secs));
+        response
+    }
+}
+
+/// Middleware refusing requests to switched-off route groups, and writes in read-only
+/// mode, with 503 and a structured maintenance body. Flags are read per request, so a
+/// config reload takes effect immediately.
+pub async fn maintenance_middleware<B>(
+    State(maintenance): State<Arc<Maintenance>>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let settings = maintenance.settings.load();
+    match maintenance.check(&settings, req.method(), req.uri().path()) {
+        None => next.run(req).await,
+        Some(refusal) => {
+            debug!(method = %req.method(), path = %req.uri().path(), refusal = ?refusal, "Request refused for maintenance");
+            maintenance.refuse(refusal)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::watcher::RateLimitSettings;
+    use arc_swap::ArcSwap;
+    use std::collections::HashMap;
+
+    fn settings(flags: &[&str]) -> LiveSettings {
+        LiveSettings {
+            log_level: "info".to_string(),
+            rate_limit: RateLimitSettings::default(),
+            feature_flags: flags.iter().map(|flag| (flag.to_string(), true)).collect::<HashMap<_, _>>(),
+            cors_origins: Vec::new(),
+        }
+    }
+
+    fn maintenance() -> Maintenance {
+        Maintenance::new(MaintenanceConfig::default(), Arc::new(ArcSwap::from_pointee(settings(&[]))))
+    }
+
+    #[test]
+    fn test_kill_switch_disables_its_route_group() {
+        let maintenance = maintenance();
+        let live = settings(&["maintenance_checkout"]);
+        assert_eq!(
+            maintenance.check(&live, &Method::GET, "/api/v1/checkout/sessions"),
+            Some(Refusal::RouteGroup("checkout".to_string()))
+        );
+        assert_eq!(maintenance.check(&live, &Method::POST, "/orders"), None);
+        // Prefixes match whole segments
+        assert_eq!(maintenance.check(&settings(&["maintenance_orders"]), &Method::GET, "/orders_archive"), None);
+    }
+
+    #[test]
+    fn test_read_only_mode_rejects_writes_only() {
+        let maintenance = maintenance();
+        let live = settings(&[READ_ONLY_FLAG]);
+        assert_eq!(maintenance.check(&live, &Method::GET, "/orders/1"), None);
+        assert_eq!(maintenance.check(&live, &Method::DELETE, "/orders/1"), Some(Refusal::ReadOnly));
+        assert_eq!(maintenance.check(&live, &Method::POST, "/oauth2/token"), None);
+    }
+
+    #[test]
+    fn test_status_lists_active_switches() {
+        let maintenance = maintenance();
+        maintenance.settings.store(Arc::new(settings(&["maintenance_payments"])));
+        let status = maintenance.status();
+        assert!(!status.read_only);
+        assert_eq!(status.disabled_groups, vec!["payments".to_string()]);
+        assert_eq!(status.switches["checkout"], "maintenance_checkout");
+    }
+}
@@ -0,0 +1,560 @@
+// metering/mod.rs
+
+use axum::{
+    extract::State,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Months, NaiveDate, Utc};
+use hmac::{Hmac, Mac};
+use lazy_static::lazy_static;
+use prometheus::IntCounter;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait,
+    PaginatorTrait, QueryFilter, QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tokio::{sync::broadcast, time::MissedTickBehavior};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::auth::Claims;
+use crate::db::dialect;
+use crate::events::{Event, EventSender};
+use crate::models::{
+    order::Entity as Order,
+    usage_record::{self, Entity as UsageRecord, UsageMetric},
+};
+use crate::utils::pagination::PaginationParams;
+
+lazy_static! {
+    static ref USAGE_FLUSH_FAILURES: IntCounter =
+        IntCounter::new(
+            "usage_flush_failures_total",
+            "Usage counter flushes that could not be written and were retried"
+        ).expect("metric can be created");
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Header carrying the HMAC-SHA256 of usage webhook bodies, hex encoded.
+pub const SIGNATURE_HEADER: &str = "X-Usage-Signature";
+
+/// Usage metering settings, loaded from the `metering` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct MeteringConfig {
+    /// Enables metering (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Tenant usage is billed to when a request names none.
+    #[serde(default = "default_tenant")]
+    pub default_tenant: String,
+
+    /// Tenants of orders by order source, e.g. `amazon`. Orders carry no tenant, so
+    /// orders from other sources are billed to the default tenant.
+    #[serde(default)]
+    pub source_tenants: HashMap<String, String>,
+
+    /// How often counters are written to usage records.
+    #[serde(default = "default_flush_interval_secs")]
+    pub flush_interval_secs: u64,
+
+    /// How often database size is sampled for the storage metric.
+    #[serde(default = "default_storage_interval_secs")]
+    pub storage_interval_secs: u64,
+
+    /// Billing system endpoint closed months are posted to, one request per tenant.
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+
+    /// Environment variable holding the key usage webhooks are signed with.
+    #[serde(default)]
+    pub webhook_secret_env: Option<String>,
+}
+
+fn default_tenant() -> String {
+    "default".to_string()
+}
+
+fn default_flush_interval_secs() -> u64 {
+    60
+}
+
+fn default_storage_interval_secs() -> u64 {
+    3600
+}
+
+impl Default for MeteringConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            default_tenant: default_tenant(),
+            source_tenants: HashMap::new(),
+            flush_interval_secs: default_flush_interval_secs(),
+            storage_interval_secs: default_storage_interval_secs(),
+            webhook_url: None,
+            webhook_secret_env: None,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum MeteringError {
+    #[error("Metering is misconfigured: {0}")]
+    Misconfigured(String),
+
+    #[error("{0}")]
+    Invalid(String),
+
+    #[error("Usage webhook failed: {0}")]
+    Webhook(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for MeteringError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            MeteringError::Misconfigured(_) => (StatusCode::SERVICE_UNAVAILABLE, "metering_misconfigured"),
+            MeteringError::Invalid(_) => (StatusCode::BAD_REQUEST, "invalid_request"),
+            MeteringError::Webhook(_) => (StatusCode::BAD_GATEWAY, "usage_webhook_failed"),
+            MeteringError::Database(e) => {
+                error!("Usage query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "usage_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// The billing period `at` falls in, as `YYYY-MM`.
+pub fn period_of(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m").to_string()
+}
+
+/// First day of a `YYYY-MM` period.
+pub fn parse_period(period: &str) -> Result<NaiveDate, MeteringError> {
+    NaiveDate::parse_from_str(&format!("{}-01", period), "%Y-%m-%d")
+        .map_err(|_| MeteringError::Invalid(format!("Period must be YYYY-MM, got {}", period)))
+}
+
+/// Whether a period has ended, so its usage is final.
+pub fn is_closed(period: &str, now: DateTime<Utc>) -> Result<bool, MeteringError> {
+    let start = parse_period(period)?;
+    let next = start + Months::new(1);
+    Ok(now.date_naive() >= next)
+}
+
+/// Usage of one tenant in one period, as posted to the billing system.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TenantUsage {
+    pub tenant_id: String,
+    pub period: String,
+    pub usage: BTreeMap<&'static str, i64>,
+}
+
+/// Groups records by tenant, in tenant order.
+pub fn by_tenant(records: &[usage_record::Model]) -> Vec<TenantUsage> {
+    let mut tenants: BTreeMap<(&str, &str), TenantUsage> = BTreeMap::new();
+    for record in records {
+        tenants
+            .entry((&record.tenant_id, &record.period))
+            .or_insert_with(|| TenantUsage {
+                tenant_id: record.tenant_id.clone(),
+                period: record.period.clone(),
+                usage: BTreeMap::new(),
+            })
+            .usage
+            .insert(record.metric.as_str(), record.quantity);
+    }
+    tenants.into_values().collect()
+}
+
+/// `tenant_id,period,metric,quantity` export of usage records.
+pub fn to_csv(records: &[usage_record::Model]) -> String {
+    let mut csv = String::from("tenant_id,period,metric,quantity\n");
+    for record in records {
+        let tenant = if record.tenant_id.contains([',', '"', '\n']) {
+            format!("\"{}\"", record.tenant_id.replace('"', "\"\""))
+        } else {
+            record.tenant_id.clone()
+        };
+        csv.push_str(&format!("{},{},{},{}\n", tenant, record.period, record.metric.as_str(), record.quantity));
+    }
+    csv
+}
+
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(body);
+    hex::encode(mac.finalize().into_bytes())
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UsageFilter {
+    pub tenant_id: Option<String>,
+    pub period: Option<String>,
+    pub metric: Option<UsageMetric>,
+}
+
+/// Adds to a counter metric of the current period.
+const ADD_USAGE_SQL: &str = r#"
+INSERT INTO usage_records (id, tenant_id, period, metric, quantity, created_at, updated_at)
+VALUES ($1, $2, $3, $4, $5, $6, $6)
+ON CONFLICT (tenant_id, period, metric)
+DO UPDATE SET quantity = usage_records.quantity + EXCLUDED.quantity, updated_at = EXCLUDED.updated_at
+"#;
+
+/// Raises a gauge metric of the current period to a new peak.
+const PEAK_USAGE_SQL: &str = r#"
+INSERT INTO usage_records (id, tenant_id, period, metric, quantity, created_at, updated_at)
+VALUES ($1, $2, $3, $4, $5, $6, $6)
+ON CONFLICT (tenant_id, period, metric)
+DO UPDATE SET quantity = GREATEST(usage_records.quantity, EXCLUDED.quantity), updated_at = EXCLUDED.updated_at
+"#;
+
+/// Counts billable operations per tenant. Counts are kept in memory and written to the
+/// month's usage records in batches, so metering adds no query to the request path.
+pub struct Meter {
+    db: Arc<DatabaseConnection>,
+    config: MeteringConfig,
+    client: reqwest::Client,
+    webhook_secret: Option<String>,
+    pending: Mutex<HashMap<(String, UsageMetric), i64>>,
+}
+
+impl Meter {
+    pub fn new(db: Arc<DatabaseConnection>, config: MeteringConfig) -> Result<Self, MeteringError> {
+        let webhook_secret = match &config.webhook_secret_env {
+            Some(var) => Some(std::env::var(var).map_err(|_| MeteringError::Misconfigured(format!("{} is not set", var)))?),
+            None => None,
+        };
+        Ok(Self { db, config, client: reqwest::Client::new(), webhook_secret, pending: Mutex::new(HashMap::new()) })
+    }
+
+    pub fn default_tenant(&self) -> &str {
+        &self.config.default_tenant
+    }
+
+    pub fn record(&self, tenant_id: &str, metric: UsageMetric, quantity: i64) {
+        let mut pending = self.pending.lock().expect("usage counters lock");
+        *pending.entry((tenant_id.to_string(), metric)).or_insert(0) += quantity;
+    }
+
+    /// Writes pending counts to the current period. Counts that fail to write are kept
+    /// for the next flush.
+    pub async fn flush(&self) -> Result<usize, MeteringError> {
+        let pending = std::mem::take(&mut *self.pending.lock().expect("usage counters lock"));
+        if pending.is_empty() {
+            return Ok(0);
+        }
+        let now = Utc::now();
+        let period = period_of(now);
+        let mut written = 0;
+        let mut failed = None;
+        for ((tenant_id, metric), quantity) in pending {
+            if failed.is_some() {
+                self.record(&tenant_id, metric, quantity);
+                continue;
+            }
+            let statement = dialect::statement(
+                self.db.as_ref(),
+                ADD_USAGE_SQL,
+                [
+                    Uuid::new_v4().into(),
+                    tenant_id.clone().into(),
+                    period.clone().into(),
+                    metric.as_str().into(),
+                    quantity.into(),
+                    now.into(),
+                ],
+            );
+            match self.db.execute(statement).await {
+                Ok(_) => written += 1,
+                Err(e) => {
+                    self.record(&tenant_id, metric, quantity);
+                    failed = Some(e);
+                }
+            }
+        }
+        match failed {
+            Some(e) => {
+                USAGE_FLUSH_FAILURES.inc();
+                Err(e.into())
+            }
+            None => Ok(written),
+        }
+    }
+
+    /// Samples the database size as the default tenant's storage. Deployments share one
+    /// database per tenant, so the whole database is the tenant's footprint.
+    pub async fn sample_storage(&self) -> Result<i64, MeteringError> {
+        let bytes = dialect::database_size(self.db.as_ref()).await?;
+        let now = Utc::now();
+        self.db
+            .execute(dialect::statement(
+                self.db.as_ref(),
+                PEAK_USAGE_SQL,
+                [
+                    Uuid::new_v4().into(),
+                    self.config.default_tenant.clone().into(),
+                    period_of(now).into(),
+                    UsageMetric::StorageBytes.as_str().into(),
+                    bytes.into(),
+                    now.into(),
+                ],
+            ))
+            .await?;
+        Ok(bytes)
+    }
+
+    /// Counts an order against the tenant of its source.
+    async fn record_order(&self, order_id: Uuid) -> Result<(), MeteringError> {
+        let source = Order::find_by_id(order_id).one(self.db.as_ref()).await?.and_then(|o| o.source);
+        let tenant = source
+            .and_then(|s| self.config.source_tenants.get(&s).cloned())
+            .unwrap_or_else(|| self.config.default_tenant.clone());
+        self.record(&tenant, UsageMetric::OrdersCreated, 1);
+        Ok(())
+    }
+
+    pub async fn list(
+        &self,
+        filter: UsageFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<usage_record::Model>, u64), MeteringError> {
+        let mut query = UsageRecord::find();
+        if let Some(tenant_id) = filter.tenant_id {
+            query = query.filter(usage_record::Column::TenantId.eq(tenant_id));
+        }
+        if let Some(period) = filter.period {
+            parse_period(&period)?;
+            query = query.filter(usage_record::Column::Period.eq(period));
+        }
+        if let Some(metric) = filter.metric {
+            query = query.filter(usage_record::Column::Metric.eq(metric));
+        }
+        let paginator = query
+            .order_by_desc(usage_record::Column::Period)
+            .order_by_asc(usage_record::Column::TenantId)
+            .order_by_asc(usage_record::Column::Metric)
+            .paginate(self.db.as_ref(), pagination.limit());
+        let total = paginator.num_items().await?;
+        let items = paginator.fetch_page(pagination.page_index()).await?;
+        Ok((items, total))
+    }
+
+    pub async fn period(&self, period: &str) -> Result<Vec<usage_record::Model>, MeteringError> {
+        parse_period(period)?;
+        Ok(UsageRecord::find()
+            .filter(usage_record::Column::Period.eq(period))
+            .order_by_asc(usage_record::Column::TenantId)
+            .order_by_asc(usage_record::Column::Metric)
+            .all(self.db.as_ref())
+            .await?)
+    }
+
+    /// Posts a closed period's usage to the billing webhook, one request per tenant, and
+    /// marks the records delivered. Delivering a period again resends it.
+    pub async fn deliver(&self, period: &str) -> Result<usize, MeteringError> {
+        let url = self
+            .config
+            .webhook_url
+            .clone()
+            .ok_or_else(|| MeteringError::Misconfigured("metering.webhook_url is not set".to_string()))?;
+        if !is_closed(period, Utc::now())? {
+            return Err(MeteringError::Invalid(format!("Period {} has not ended yet", period)));
+        }
+        let records = self.period(period).await?;
+        let mut delivered = 0;
+        for tenant in by_tenant(&records) {
+            let body = serde_json::to_vec(&json!({ "event": "usage.period_closed", "data": tenant }))
+                .expect("usage serializes");
+            let mut request = self.client.post(&url).header("Content-Type", "application/json");
+            if let Some(secret) = &self.webhook_secret {
+                request = request.header(SIGNATURE_HEADER, sign(secret, &body));
+            }
+            let response = request.body(body).send().await.map_err(|e| MeteringError::Webhook(e.to_string()))?;
+            if !response.status().is_success() {
+                return Err(MeteringError::Webhook(format!("{} for tenant {}", response.status(), tenant.tenant_id)));
+            }
+            let now = Utc::now();
+            for record in records.iter().filter(|r| r.tenant_id == tenant.tenant_id) {
+                let mut active: usage_record::ActiveModel = record.clone().into();
+                active.exported_at = Set(Some(now));
+                active.update(self.db.as_ref()).await?;
+            }
+            delivered += 1;
+        }
+        Ok(delivered)
+    }
+
+    /// Delivers every closed period that still has undelivered records.
+    async fn deliver_closed(&self) -> Result<usize, MeteringError> {
+        if self.config.webhook_url.is_none() {
+            return Ok(0);
+        }
+        let current = period_of(Utc::now());
+        let mut periods: Vec<String> = UsageRecord::find()
+            .filter(usage_record::Column::ExportedAt.is_null())
+            .filter(usage_record::Column::Period.lt(current))
+            .all(self.db.as_ref())
+            .await?
+            .into_iter()
+            .map(|r| r.period)
+            .collect();
+        periods.sort();
+        periods.dedup();
+        let mut delivered = 0;
+        for period in periods {
+            delivered += self.deliver(&period).await?;
+        }
+        Ok(delivered)
+    }
+}
+
+/// Counts API calls against the tenant of the caller's verified claims, or the default
+/// tenant. Layered inside authentication, so a client cannot bill another tenant by
+/// naming it in a header. Does nothing while metering is disabled.
+pub async fn metering_middleware<B>(
+    State(meter): State<Option<Arc<Meter>>>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if let Some(meter) = meter {
+        let tenant = req
+            .extensions()
+            .get::<Claims>()
+            .and_then(|claims| claims.tenant_id.as_deref())
+            .filter(|t| !t.is_empty())
+            .unwrap_or(meter.default_tenant())
+            .to_string();
+        meter.record(&tenant, UsageMetric::ApiCalls, 1);
+    }
+    next.run(req).await
+}
+
+/// Counts created orders as their events arrive.
+pub fn spawn_order_listener(meter: Arc<Meter>, events: EventSender) {
+    let mut receiver = events.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(Event::OrderCreated(order_id)) => {
+                    if let Err(e) = meter.record_order(order_id).await {
+                        error!(%order_id, "Metering order failed: {}", e);
+                    }
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!(missed, "Usage meter lagged; orders created meanwhile were not counted");
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+/// Flushes counters, samples storage and delivers closed months on their intervals.
+pub fn spawn_worker(meter: Arc<Meter>) {
+    tokio::spawn(async move {
+        let mut flush = tokio::time::interval(std::time::Duration::from_secs(meter.config.flush_interval_secs.max(1)));
+        let mut storage =
+            tokio::time::interval(std::time::Duration::from_secs(meter.config.storage_interval_secs.max(60)));
+        flush.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        storage.set_missed_tick_behavior(MissedTickBehavior::Skip);
+        loop {
+            tokio::select! {
+                _ = flush.tick() => {
+                    if let Err(e) = meter.flush().await {
+                        error!("Usage flush failed; counts are kept for the next flush: {}", e);
+                    }
+                }
+                _ = storage.tick() => {
+                    if let Err(e) = meter.sample_storage().await {
+                        error!("Storage usage sample failed: {}", e);
+                    }
+                    match meter.deliver_closed().await {
+                        Ok(0) => {}
+                        Ok(delivered) => info!(delivered, "Delivered closed usage periods to billing"),
+                        Err(e) => error!("Usage delivery failed: {}", e),
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn record(tenant_id: &str, metric: UsageMetric, quantity: i64) -> usage_record::Model {
+        let at = Utc.with_ymd_and_hms(2026, 9, 30, 23, 0, 0).unwrap();
+        usage_record::Model {
+            id: Uuid::new_v4(),
+            tenant_id: tenant_id.to_string(),
+            period: "2026-09".to_string(),
+            metric,
+            quantity,
+            exported_at: None,
+            created_at: at,
+            updated_at: at,
+        }
+    }
+
+    #[test]
+    fn test_periods() {
+        let at = Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap();
+        assert_eq!(period_of(at), "2026-10");
+        assert!(is_closed("2026-09", at).unwrap());
+        assert!(!is_closed("2026-10", at).unwrap());
+        assert!(parse_period("2026-13").is_err());
+        assert!(parse_period("september").is_err());
+    }
+
+    #[test]
+    fn test_usage_grouped_by_tenant() {
+        let records = [
+            record("globex", UsageMetric::ApiCalls, 40),
+            record("acme", UsageMetric::ApiCalls, 1200),
+            record("acme", UsageMetric::OrdersCreated, 35),
+        ];
+        let usage = by_tenant(&records);
+        assert_eq!(usage.iter().map(|u| u.tenant_id.as_str()).collect::<Vec<_>>(), ["acme", "globex"]);
+        assert_eq!(usage[0].usage["orders_created"], 35);
+        assert_eq!(usage[1].usage.len(), 1);
+    }
+
+    #[test]
+    fn test_csv_quotes_tenants() {
+        let csv = to_csv(&[record("acme, inc", UsageMetric::StorageBytes, 1024)]);
+        assert_eq!(csv, "tenant_id,period,metric,quantity\n\"acme, inc\",2026-09,storage_bytes,1024\n");
+    }
+
+    #[test]
+    fn test_counts_accumulate_in_memory() {
+        let meter = Meter {
+            db: Arc::new(DatabaseConnection::Disconnected),
+            config: MeteringConfig::default(),
+            client: reqwest::Client::new(),
+            webhook_secret: None,
+            pending: Mutex::new(HashMap::new()),
+        };
+        meter.record("acme", UsageMetric::ApiCalls, 1);
+        meter.record("acme", UsageMetric::ApiCalls, 2);
+        let pending = meter.pending.lock().unwrap();
+        assert_eq!(pending[&("acme".to_string(), UsageMetric::ApiCalls)], 3);
+        assert_eq!(sign("secret", b"{}").len(), 64);
+    }
+}
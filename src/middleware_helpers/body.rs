@@ -0,0 +1,59 @@
+use axum::body::Bytes;
+use hyper::body::HttpBody;
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum BodyReadError {
+    #[error("Request body exceeds {0} bytes")]
+    TooLarge(usize),
+    #[error("Failed to read request body: {0}")]
+    Read(String),
+}
+
+/// Buffers a body of at most `limit` bytes. Reading stops at the first chunk past the
+/// limit, so an oversized or endless body is never held in memory.
+pub async fn read_limited<B>(mut body: B, limit: usize) -> Result<Bytes, BodyReadError>
+where
+    B: HttpBody + Unpin,
+    B::Error: std::fmt::Display,
+{
+    if body.size_hint().lower() > limit as u64 {
+        return Err(BodyReadError::TooLarge(limit));
+    }
+    let mut buffer = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|e| BodyReadError::Read(e.to_string()))?;
+        if buffer.len() + chunk.len() > limit {
+            return Err(BodyReadError::TooLarge(limit));
+        }
+        buffer.extend_from_slice(&chunk);
+    }
+    Ok(Bytes::from(buffer))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::Body;
+
+    #[tokio::test]
+    async fn test_bodies_within_the_limit_are_read() {
+        let bytes = read_limited(Body::from("hello"), 5).await.unwrap();
+        assert_eq!(bytes, "hello".as_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_bodies_are_refused() {
+        assert!(matches!(read_limited(Body::from("hello!"), 5).await, Err(BodyReadError::TooLarge(5))));
+
+        let (mut sender, body) = Body::channel();
+        tokio::spawn(async move {
+            for _ in 0..4 {
+                if sender.send_data(Bytes::from_static(b"abc")).await.is_err() {
+                    break;
+                }
+            }
+        });
+        assert!(matches!(read_limited(body, 10).await, Err(BodyReadError::TooLarge(10))));
+    }
+}
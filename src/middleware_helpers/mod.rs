@@ -0,0 +1,4 @@
+//! Reusable request-level middleware shared across route groups.
+
+pub mod body;
+pub mod request_signing;
@@ -0,0 +1,430 @@
+use async_trait::async_trait;
+use axum::{
+    body::Body,
+    extract::{OriginalUri, State},
+    http::{Request, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use hmac::{Hmac, Mac};
+use lazy_static::lazy_static;
+use prometheus::IntCounterVec;
+use redis::AsyncCommands;
+use serde::Deserialize;
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use thiserror::Error;
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use super::body::{read_limited, BodyReadError};
+use crate::config::secrets::{SecretStore, REQUEST_SIGNING_PREFIX};
+
+type HmacSha256 = Hmac<Sha256>;
+
+lazy_static! {
+    static ref SIGNATURE_REJECTIONS: IntCounterVec =
+        IntCounterVec::new(
+            "request_signature_rejections_total",
+            "Total number of requests rejected by HMAC signature verification",
+            &["reason"]
+        ).expect("metric can be created");
+}
+
+pub const SIGNATURE_HEADER: &str = "X-Signature";
+pub const TIMESTAMP_HEADER: &str = "X-Signature-Timestamp";
+pub const NONCE_HEADER: &str = "X-Signature-Nonce";
+pub const KEY_ID_HEADER: &str = "X-Signature-Key-Id";
+
+/// Largest request body that will be buffered for signature verification.
+const MAX_SIGNED_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+#[derive(Error, Debug)]
+pub enum SignatureError {
+    #[error("Missing signature header: {0}")]
+    MissingHeader(&'static str),
+    #[error("Unknown signing key")]
+    UnknownKey,
+    #[error("Invalid timestamp")]
+    InvalidTimestamp,
+    #[error("Timestamp outside the allowed window")]
+    StaleTimestamp,
+    #[error("Nonce has already been used")]
+    ReplayedNonce,
+    #[error("Signature mismatch")]
+    InvalidSignature,
+    #[error("Request body too large to verify")]
+    BodyTooLarge,
+    #[error("Request body could not be read")]
+    UnreadableBody,
+    #[error("Nonce store error: {0}")]
+    NonceStore(String),
+}
+
+impl SignatureError {
+    fn code(&self) -> &'static str {
+        match self {
+            SignatureError::MissingHeader(_) => "missing_header",
+            SignatureError::UnknownKey => "unknown_key",
+            SignatureError::InvalidTimestamp => "invalid_timestamp",
+            SignatureError::StaleTimestamp => "stale_timestamp",
+            SignatureError::ReplayedNonce => "replayed_nonce",
+            SignatureError::InvalidSignature => "invalid_signature",
+            SignatureError::BodyTooLarge => "body_too_large",
+            SignatureError::UnreadableBody => "unreadable_body",
+            SignatureError::NonceStore(_) => "nonce_store_error",
+        }
+    }
+}
+
+impl IntoResponse for SignatureError {
+    fn into_response(self) -> Response {
+        SIGNATURE_REJECTIONS.with_label_values(&[self.code()]).inc();
+        let status = match self {
+            SignatureError::NonceStore(_) => StatusCode::SERVICE_UNAVAILABLE,
+            SignatureError::BodyTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            SignatureError::UnreadableBody => StatusCode::BAD_REQUEST,
+            _ => StatusCode::UNAUTHORIZED,
+        };
+        (
+            status,
+            Json(json!({
+                "error": "Invalid request signature",
+                "code": self.code(),
+                "details": self.to_string(),
+            })),
+        )
+            .into_response()
+    }
+}
+
+/// Settings for HMAC request signing, loaded from the `request_signing` config section.
+#[derive(Clone, Debug, Deserialize)]
+pub struct RequestSigningConfig {
+    /// Enables signature verification on payment and admin routes (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Shared secrets keyed by key ID, so keys can be rotated without downtime.
+    #[serde(default)]
+    pub secrets: HashMap<String, String>,
+
+    /// Allowed clock skew in seconds; also the nonce retention window (default: 300).
+    #[serde(default = "default_tolerance_secs")]
+    pub tolerance_secs: u64,
+}
+
+fn default_tolerance_secs() -> u64 {
+    300
+}
+
+impl Default for RequestSigningConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            secrets: HashMap::new(),
+            tolerance_secs: default_tolerance_secs(),
+        }
+    }
+}
+
+/// Records nonces so each signed request can only be accepted once.
+#[async_trait]
+pub trait NonceStore: Send + Sync {
+    /// Stores `nonce` for `ttl`; returns false if it was already present.
+    async fn insert_if_absent(&self, nonce: &str, ttl: Duration) -> Result<bool, SignatureError>;
+}
+
+/// Redis-backed nonce cache, shared by all API instances.
+pub struct RedisNonceStore {
+    client: Arc<redis::Client>,
+}
+
+impl RedisNonceStore {
+    pub fn new(client: Arc<redis::Client>) -> Self {
+        Self { client }
+    }
+}
+
+#[async_trait]
+impl NonceStore for RedisNonceStore {
+    async fn insert_if_absent(&self, nonce: &str, ttl: Duration) -> Result<bool, SignatureError> {
+        let mut conn = self
+            .client
+            .get_async_connection()
+            .await
+            .map_err(|e| SignatureError::NonceStore(e.to_string()))?;
+        let key = format!("request_signing:nonce:{}", nonce);
+        let inserted: Option<String> = redis::cmd("SET")
+            .arg(&key)
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(ttl.as_secs().max(1))
+            .query_async(&mut conn)
+            .await
+            .map_err(|e| SignatureError::NonceStore(e.to_string()))?;
+        Ok(inserted.is_some())
+    }
+}
+
+/// Process-local nonce cache for tests and single-instance deployments.
+#[derive(Default)]
+pub struct InMemoryNonceStore {
+    seen: Mutex<HashMap<String, SystemTime>>,
+}
+
+#[async_trait]
+impl NonceStore for InMemoryNonceStore {
+    async fn insert_if_absent(&self, nonce: &str, ttl: Duration) -> Result<bool, SignatureError> {
+        let now = SystemTime::now();
+        let mut seen = self.seen.lock().await;
+        seen.retain(|_, expires| *expires > now);
+        if seen.contains_key(nonce) {
+            return Ok(false);
+        }
+        seen.insert(nonce.to_string(), now + ttl);
+        Ok(true)
+    }
+}
+
+/// Builds the string that is signed: method, path, query, timestamp, nonce and the
+/// hex SHA-256 of the body, separated by newlines.
+pub fn canonical_request(method: &str, path: &str, query: &str, timestamp: &str, nonce: &str, body: &[u8]) -> String {
+    format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method.to_uppercase(),
+        path,
+        query,
+        timestamp,
+        nonce,
+        hex::encode(Sha256::digest(body))
+    )
+}
+
+/// Computes the hex HMAC-SHA256 of a canonical request. Used by clients and tests.
+pub fn sign(secret: &str, canonical: &str) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(canonical.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Verifies timestamped HMAC signatures with replay protection.
+pub struct SignatureVerifier {
+    secrets: HashMap<String, String>,
+    /// `request_signing_<key id>` secrets here take precedence over `secrets`, so keys
+    /// rotated in the secret store apply to the next request.
+    store: SecretStore,
+    tolerance: Duration,
+    nonces: Arc<dyn NonceStore>,
+}
+
+impl SignatureVerifier {
+    pub fn new(config: &RequestSigningConfig, nonces: Arc<dyn NonceStore>) -> Self {
+        Self {
+            secrets: config.secrets.clone(),
+            store: SecretStore::default(),
+            tolerance: Duration::from_secs(config.tolerance_secs),
+            nonces,
+        }
+    }
+
+    pub fn with_secrets(mut self, store: SecretStore) -> Self {
+        self.store = store;
+        self
+    }
+
+    fn secret(&self, key_id: &str) -> Option<String> {
+        match self.store.get(&format!("{}{}", REQUEST_SIGNING_PREFIX, key_id)) {
+            Some(secret) => Some(secret.expose().to_string()),
+            None => self.secrets.get(key_id).cloned(),
+        }
+    }
+
+    /// Verifies a request's signature headers against its body.
+    pub async fn verify(
+        &self,
+        method: &str,
+        path: &str,
+        query: &str,
+        headers: &axum::http::HeaderMap,
+        body: &[u8],
+    ) -> Result<(), SignatureError> {
+        let header = |name: &'static str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .ok_or(SignatureError::MissingHeader(name))
+        };
+        let signature = header(SIGNATURE_HEADER)?;
+        let timestamp = header(TIMESTAMP_HEADER)?;
+        let nonce = header(NONCE_HEADER)?;
+        let key_id = header(KEY_ID_HEADER)?;
+
+        let secret = self.secret(key_id).ok_or(SignatureError::UnknownKey)?;
+
+        let sent_at: u64 = timestamp.parse().map_err(|_| SignatureError::InvalidTimestamp)?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Time went backwards")
+            .as_secs();
+        if now.abs_diff(sent_at) > self.tolerance.as_secs() {
+            return Err(SignatureError::StaleTimestamp);
+        }
+
+        let canonical = canonical_request(method, path, query, timestamp, nonce, body);
+        let expected = hex::decode(signature).map_err(|_| SignatureError::InvalidSignature)?;
+        let mut mac = HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+        mac.update(canonical.as_bytes());
+        mac.verify_slice(&expected).map_err(|_| SignatureError::InvalidSignature)?;
+
+        // Only burn the nonce once the signature is known to be genuine
+        let nonce_key = format!("{}:{}", key_id, nonce);
+        if !self.nonces.insert_if_absent(&nonce_key, self.tolerance * 2).await? {
+            return Err(SignatureError::ReplayedNonce);
+        }
+
+        Ok(())
+    }
+}
+
+/// Middleware enforcing HMAC signatures. The body is buffered, verified and then
+/// handed to the inner service unchanged. Clients sign the path they requested, so the
+/// original URI is verified rather than the one left after nesting strips its prefix.
+pub async fn require_signature(
+    State(verifier): State<Arc<SignatureVerifier>>,
+    req: Request<Body>,
+    next: Next<Body>,
+) -> Result<Response, SignatureError> {
+    let (parts, body) = req.into_parts();
+    let bytes = read_limited(body, MAX_SIGNED_BODY_BYTES).await.map_err(|e| match e {
+        BodyReadError::TooLarge(_) => SignatureError::BodyTooLarge,
+        BodyReadError::Read(_) => SignatureError::UnreadableBody,
+    })?;
+
+    let uri = parts
+        .extensions
+        .get::<OriginalUri>()
+        .map(|original| original.0.clone())
+        .unwrap_or_else(|| parts.uri.clone());
+    if let Err(e) = verifier
+        .verify(parts.method.as_str(), uri.path(), uri.query().unwrap_or(""), &parts.headers, &bytes)
+        .await
+    {
+        warn!(path = %uri.path(), reason = e.code(), "Rejected request with invalid signature");
+        return Err(e);
+    }
+
+    Ok(next.run(Request::from_parts(parts, Body::from(bytes))).await)
+}
+
+/// Requires a valid signature on every route of `router`. Routes are left as they are
+/// when signing is disabled, i.e. there is no verifier.
+pub fn signed(router: Router, verifier: Option<Arc<SignatureVerifier>>) -> Router {
+    match verifier {
+        Some(verifier) => router.layer(middleware::from_fn_with_state(verifier, require_signature)),
+        None => router,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::{HeaderMap, HeaderValue};
+    use axum::routing::post;
+    use tower::ServiceExt;
+
+    fn verifier() -> SignatureVerifier {
+        let mut secrets = HashMap::new();
+        secrets.insert("k1".to_string(), "super_secret".to_string());
+        SignatureVerifier::new(
+            &RequestSigningConfig { enabled: true, secrets, tolerance_secs: 300 },
+            Arc::new(InMemoryNonceStore::default()),
+        )
+    }
+
+    fn signed_headers(body: &[u8], nonce: &str, timestamp: u64) -> HeaderMap {
+        let ts = timestamp.to_string();
+        let canonical = canonical_request("POST", "/payments/charge", "a=1", &ts, nonce, body);
+        let mut headers = HeaderMap::new();
+        headers.insert(SIGNATURE_HEADER, HeaderValue::from_str(&sign("super_secret", &canonical)).unwrap());
+        headers.insert(TIMESTAMP_HEADER, HeaderValue::from_str(&ts).unwrap());
+        headers.insert(NONCE_HEADER, HeaderValue::from_str(nonce).unwrap());
+        headers.insert(KEY_ID_HEADER, HeaderValue::from_static("k1"));
+        headers
+    }
+
+    fn now() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+    }
+
+    #[tokio::test]
+    async fn test_valid_signature_accepted_once() {
+        let verifier = verifier();
+        let body = br#"{"amount":"10.00"}"#;
+        let headers = signed_headers(body, "n-1", now());
+
+        assert!(verifier.verify("POST", "/payments/charge", "a=1", &headers, body).await.is_ok());
+        assert!(matches!(
+            verifier.verify("POST", "/payments/charge", "a=1", &headers, body).await,
+            Err(SignatureError::ReplayedNonce)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tampered_body_rejected() {
+        let verifier = verifier();
+        let headers = signed_headers(br#"{"amount":"10.00"}"#, "n-2", now());
+        assert!(matches!(
+            verifier.verify("POST", "/payments/charge", "a=1", &headers, br#"{"amount":"99.00"}"#).await,
+            Err(SignatureError::InvalidSignature)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_stale_timestamp_rejected() {
+        let verifier = verifier();
+        let body = b"{}";
+        let headers = signed_headers(body, "n-3", now() - 3600);
+        assert!(matches!(
+            verifier.verify("POST", "/payments/charge", "a=1", &headers, body).await,
+            Err(SignatureError::StaleTimestamp)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_missing_headers_rejected() {
+        let verifier = verifier();
+        assert!(matches!(
+            verifier.verify("POST", "/payments/charge", "", &HeaderMap::new(), b"").await,
+            Err(SignatureError::MissingHeader(SIGNATURE_HEADER))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_signed_routes_refuse_unsigned_requests() {
+        let app = |verifier: Option<SignatureVerifier>| {
+            let charge = Router::new().route("/charge", post(|| async { "charged" }));
+            Router::new().nest("/payments", signed(charge, verifier.map(Arc::new)))
+        };
+        let request = || Request::post("/payments/charge?a=1").body(Body::from("{}")).unwrap();
+
+        let response = app(Some(verifier())).oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        // Signed over the path the client requested, before nesting stripped it
+        let mut signed_request = request();
+        *signed_request.headers_mut() = signed_headers(b"{}", "n-4", now());
+        let response = app(Some(verifier())).oneshot(signed_request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app(None).oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+}
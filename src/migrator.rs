@@ -0,0 +1,288 @@
+//! Applies the SQL files in `migrations/`, oldest first, and records each one in
+//! `schema_migrations` so it runs once. The files are embedded at build time; the test at
+//! the bottom fails when a file in the directory is missing from [`MIGRATIONS`].
+//!
+//! A file runs inside one transaction unless it builds indexes `CONCURRENTLY`, which
+//! Postgres refuses inside a transaction. Those files run statement by statement and
+//! rely on `IF NOT EXISTS` to pick up where a failed run stopped.
+//!
+//! A file headed `-- requires: <extension>` stays pending while the database cannot
+//! install that extension, and applies on the first run after it can.
+//!
+//! The files are Postgres DDL. On SQLite, used by tests, they are skipped.
+
+use sea_orm::{ConnectionTrait, DatabaseConnection, DbErr, FromQueryResult, TransactionTrait};
+use std::collections::HashSet;
+use tracing::{debug, info, warn};
+
+use crate::db::dialect::{self, Dialect};
+use crate::db::migration_safety::{analyze, split_statements};
+
+/// One file from `migrations/`.
+#[derive(Clone, Copy, Debug)]
+pub struct SqlMigration {
+    /// File name without `.sql`, e.g. `20261016120000_list_endpoint_indexes`.
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+impl SqlMigration {
+    /// Whether the file can run inside a transaction.
+    pub fn transactional(&self) -> bool {
+        !self.sql.to_uppercase().contains("CONCURRENTLY")
+    }
+
+    /// Extension named by a `-- requires:` header, e.g. `vector`.
+    pub fn required_extension(&self) -> Option<&'static str> {
+        self.sql
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("--"))
+            .find_map(|comment| comment.trim().strip_prefix("requires:"))
+            .map(str::trim)
+    }
+
+    /// Refuses a file with migration safety violations it does not explicitly allow.
+    pub fn check(&self) -> Result<(), DbErr> {
+        let plan = analyze(&format!("{}.sql", self.name), self.sql);
+        match plan.violations().next() {
+            Some(violation) => Err(DbErr::Migration(format!(
+                "{}: refused, statement {} breaks {}: {}",
+                self.name, violation.statement, violation.rule, violation.message
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+macro_rules! migration {
+    ($name:literal) => {
+        SqlMigration {
+            name: $name,
+            sql: include_str!(concat!("../migrations/", $name, ".sql")),
+        }
+    };
+}
+
+/// Every migration, in the order it applies. Names sort by their timestamp prefix.
+pub const MIGRATIONS: &[SqlMigration] = &[
+    migration!("20261016000000_order_events"),
+    migration!("20261016001000_service_accounts"),
+    migration!("20261016002000_network_acl_entries"),
+    migration!("20261016003000_retention_runs"),
+    migration!("20261016004000_customer_locale"),
+    migration!("20261016005000_customer_tags"),
+    migration!("20261016010000_inventory_reservations"),
+    migration!("20261016011000_checkout_sessions"),
+    migration!("20261016012000_product_listings"),
+    migration!("20261016013000_agent_decisions"),
+    migration!("20261016014000_shipment_delivered_at"),
+    migration!("20261016015000_work_order_operations"),
+    migration!("20261016020000_quality_inspections"),
+    migration!("20261016021000_non_conformances"),
+    migration!("20261016022000_supplier_receipts"),
+    migration!("20261016023000_purchase_orders"),
+    migration!("20261016024000_approval_steps"),
+    migration!("20261016025000_credit_memos"),
+    migration!("20261016030000_accounting_exports"),
+    migration!("20261016031000_ledger"),
+    migration!("20261016032000_payment_captures"),
+    migration!("20261016033000_subscriptions"),
+    migration!("20261016034000_disputes"),
+    migration!("20261016035000_inventory_write_offs"),
+    migration!("20261016040000_asns"),
+    migration!("20261016041000_kit_work_orders"),
+    migration!("20261016042000_bom_revisions"),
+    migration!("20261016043000_sales_forecasts"),
+    migration!("20261016044000_work_centers"),
+    migration!("20261016045000_equipment"),
+    migration!("20261016050000_scan_transactions"),
+    migration!("20261016051000_print_jobs"),
+    migration!("20261016052000_order_fingerprints"),
+    migration!("20261016053000_shipping_zones"),
+    migration!("20261016054000_carrier_manifests"),
+    migration!("20261016055000_product_hazmat"),
+    migration!("20261016060000_shipment_customs_lines"),
+    migration!("20261016061000_return_dispositions"),
+    migration!("20261016062000_dropship"),
+    migration!("20261016063000_amazon"),
+    migration!("20261016064000_pos"),
+    migration!("20261016065000_usage_records"),
+    migration!("20261016070000_tenant_data_keys"),
+    migration!("20261016071000_webhook_subscriptions"),
+    migration!("20261016072000_webhook_events"),
+    migration!("20261016073000_ingest_quarantine"),
+    migration!("20261016074000_inbound_emails"),
+    migration!("20261016075000_support_cases"),
+    migration!("20261016080000_abuse_blocks"),
+    migration!("20261016081000_slo_buckets"),
+    migration!("20261016082000_cdc"),
+    migration!("20261016083000_stock_alerts"),
+    migration!("20261016120000_list_endpoint_indexes"),
+    migration!("20261016130000_custom_fields"),
+    migration!("20261016140000_developer_logs"),
+    migration!("20261016150000_change_feed"),
+    migration!("20261016160000_return_fraud"),
+    migration!("20261016170000_payment_vault"),
+    migration!("20261016180000_routing_rules"),
+    migration!("20261016190000_order_events_sequence"),
+    migration!("20261016200000_partition_high_volume_tables"),
+    migration!("20261016210000_jobs"),
+    migration!("20261016220000_backfill_states"),
+    migration!("20261016230000_stock_alert_states"),
+    migration!("20261017000000_inventory_snapshots"),
+    migration!("20261017010000_promotions"),
+    migration!("20261017020000_product_bundles"),
+    migration!("20261017030000_inventory_level_key"),
+    migration!("20261017040000_product_embeddings"),
+];
+
+const CREATE_HISTORY: &str = "CREATE TABLE IF NOT EXISTS schema_migrations (
+    name TEXT PRIMARY KEY,
+    applied_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+)";
+
+#[derive(Debug, FromQueryResult)]
+struct Applied {
+    name: String,
+}
+
+pub struct Migrator;
+
+impl Migrator {
+    /// Names of the migrations already applied to `db`.
+    pub async fn applied(&self, db: &DatabaseConnection) -> Result<HashSet<String>, DbErr> {
+        db.execute(dialect::raw(db, CREATE_HISTORY)).await?;
+        Ok(Applied::find_by_statement(dialect::raw(db, "SELECT name FROM schema_migrations"))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|row| row.name)
+            .collect())
+    }
+
+    /// Migrations not yet applied to `db`, oldest first.
+    pub async fn pending(&self, db: &DatabaseConnection) -> Result<Vec<&'static SqlMigration>, DbErr> {
+        let applied = self.applied(db).await?;
+        Ok(MIGRATIONS.iter().filter(|m| !applied.contains(m.name)).collect())
+    }
+
+    /// Applies up to `steps` pending migrations, or all of them. Nothing is applied when
+    /// any of them fails the safety checks.
+    pub async fn up(&self, db: &DatabaseConnection, steps: Option<u32>) -> Result<(), DbErr> {
+        if Dialect::of(db) == Dialect::Sqlite {
+            debug!("Skipping SQL migrations on SQLite");
+            return Ok(());
+        }
+        let pending = self.pending(db).await?;
+        let limit = steps.map_or(pending.len(), |steps| steps as usize);
+        for migration in pending.iter().take(limit) {
+            migration.check()?;
+        }
+        for migration in pending.into_iter().take(limit) {
+            if let Some(extension) = migration.required_extension() {
+                if !self.extension_available(db, extension).await? {
+                    warn!(migration = migration.name, extension, "Extension not available; migration left pending");
+                    continue;
+                }
+            }
+            self.apply(db, migration).await?;
+            info!(migration = migration.name, "Migration applied");
+        }
+        Ok(())
+    }
+
+    async fn extension_available(&self, db: &DatabaseConnection, extension: &str) -> Result<bool, DbErr> {
+        let row = db
+            .query_one(dialect::statement(
+                db,
+                "SELECT name FROM pg_available_extensions WHERE name = $1",
+                [extension.into()],
+            ))
+            .await?;
+        Ok(row.is_some())
+    }
+
+    async fn apply(&self, db: &DatabaseConnection, migration: &SqlMigration) -> Result<(), DbErr> {
+        let record = dialect::statement(
+            db,
+            "INSERT INTO schema_migrations (name) VALUES ($1) ON CONFLICT (name) DO NOTHING",
+            [migration.name.into()],
+        );
+        let failed = |e: DbErr| DbErr::Migration(format!("{}: {}", migration.name, e));
+
+        if migration.transactional() {
+            let txn = db.begin().await?;
+            for statement in split_statements(migration.sql) {
+                txn.execute(dialect::raw(&txn, &statement)).await.map_err(failed)?;
+            }
+            txn.execute(record).await?;
+            return txn.commit().await;
+        }
+        // Session settings would stick to whichever pooled connection ran them, so they
+        // are applied with SET LOCAL to each statement that can take a transaction.
+        let (settings, statements): (Vec<String>, Vec<String>) =
+            split_statements(migration.sql).into_iter().partition(|s| is_setting(s));
+        for statement in statements {
+            if statement.to_uppercase().contains("CONCURRENTLY") {
+                db.execute(dialect::raw(db, &statement)).await.map_err(failed)?;
+                continue;
+            }
+            let txn = db.begin().await?;
+            for setting in &settings {
+                txn.execute(dialect::raw(&txn, &format!("SET LOCAL {}", &setting.trim_start()[4..])))
+                    .await
+                    .map_err(failed)?;
+            }
+            txn.execute(dialect::raw(&txn, &statement)).await.map_err(failed)?;
+            txn.commit().await?;
+        }
+        db.execute(record).await.map(|_| ())
+    }
+}
+
+fn is_setting(statement: &str) -> bool {
+    statement.trim_start().get(..4).is_some_and(|prefix| prefix.eq_ignore_ascii_case("SET "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_migration_file_is_applied() {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("migrations");
+        let mut files: Vec<String> = std::fs::read_dir(&dir)
+            .expect("migrations are readable")
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter_map(|name| name.strip_suffix(".sql").map(str::to_string))
+            .collect();
+        files.sort();
+        let listed: Vec<String> = MIGRATIONS.iter().map(|m| m.name.to_string()).collect();
+        assert_eq!(listed, files);
+    }
+
+    #[test]
+    fn test_unsafe_migrations_are_refused() {
+        for migration in MIGRATIONS {
+            assert!(migration.check().is_ok(), "{} fails the safety checks", migration.name);
+        }
+        let unsafe_index = SqlMigration { name: "t", sql: "CREATE INDEX idx_t_c ON t (c);" };
+        assert!(unsafe_index.check().is_err());
+    }
+
+    #[test]
+    fn test_concurrent_index_files_run_outside_a_transaction() {
+        let indexes = MIGRATIONS.iter().find(|m| m.name.ends_with("list_endpoint_indexes")).unwrap();
+        assert!(!indexes.transactional());
+        let plain = SqlMigration { name: "t", sql: "ALTER TABLE t ADD COLUMN c TEXT;" };
+        assert!(plain.transactional());
+    }
+
+    #[test]
+    fn test_required_extension_is_read_from_the_header() {
+        let embeddings = MIGRATIONS.iter().find(|m| m.name.ends_with("product_embeddings")).unwrap();
+        assert_eq!(embeddings.required_extension(), Some("vector"));
+        let plain = SqlMigration { name: "t", sql: "-- phase: expand\nALTER TABLE t ADD COLUMN c TEXT;" };
+        assert_eq!(plain.required_extension(), None);
+    }
+}
@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// What an abuse block matches a request on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum AbuseBlockKind {
+    /// A request fingerprint ID as reported by the admin API.
+    #[sea_orm(string_value = "fingerprint")]
+    Fingerprint,
+    /// A single address or CIDR range.
+    #[sea_orm(string_value = "network")]
+    Network,
+    /// A JA3 TLS client hash, which stays the same across rotating addresses.
+    #[sea_orm(string_value = "ja3")]
+    Ja3,
+}
+
+/// The `abuse_blocks` table: clients refused on protected endpoints, managed through
+/// `/api/v1/admin/abuse/blocklist`.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "abuse_blocks")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+
+    pub kind: AbuseBlockKind,
+
+    pub value: String,
+
+    pub reason: Option<String>,
+
+    /// Blocks without an expiry stay until removed.
+    pub expires_at: Option<DateTime<Utc>>,
+
+    /// Removed blocks are kept for history but not enforced.
+    pub is_active: bool,
+
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,79 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Business document a journal entry is derived from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum DocumentKind {
+    #[sea_orm(string_value = "order")]
+    Order,
+    #[sea_orm(string_value = "invoice")]
+    Invoice,
+    #[sea_orm(string_value = "refund")]
+    Refund,
+    #[sea_orm(string_value = "cogs")]
+    Cogs,
+}
+
+/// Where a document stands against the external ledger.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum ExportStatus {
+    /// Queued, or waiting for its next retry after a transient failure.
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    /// Accepted by the provider; `external_id` holds its journal id.
+    #[sea_orm(string_value = "posted")]
+    Posted,
+    /// Rejected by the provider or out of attempts; needs a manual retry.
+    #[sea_orm(string_value = "failed")]
+    Failed,
+}
+
+/// The `accounting_exports` table: one journal entry per document, with its delivery
+/// state in the configured accounting system. A document is exported at most once.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "accounting_exports")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    pub document_kind: DocumentKind,
+
+    /// Id of the order, invoice, return or credit memo application.
+    #[sea_orm(indexed)]
+    pub document_id: String,
+
+    /// Provider the entry is posted to, e.g. `quickbooks`.
+    pub provider: String,
+
+    /// The journal entry as built at enqueue time.
+    #[sea_orm(column_type = "JsonBinary")]
+    pub entry: Json,
+
+    #[sea_orm(indexed)]
+    pub status: ExportStatus,
+
+    pub attempts: i32,
+
+    pub next_attempt_at: Option<DateTime<Utc>>,
+
+    pub last_error: Option<String>,
+
+    /// Journal id assigned by the provider.
+    pub external_id: Option<String>,
+
+    pub posted_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `address_geocodes` table: the geocoded address and shipping zone of a customer or
+/// warehouse, one row per `(subject_type, subject_id)`. Zones are re-resolved when zones
+/// change; the address is geocoded again only when it changes.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "address_geocodes")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    /// `customer` or `warehouse`.
+    pub subject_type: String,
+
+    pub subject_id: String,
+
+    /// The address on one line, as geocoded.
+    pub address: String,
+
+    pub latitude: Option<f64>,
+
+    pub longitude: Option<f64>,
+
+    pub country: String,
+
+    pub region: Option<String>,
+
+    pub postal_code: Option<String>,
+
+    pub zone_id: Option<Uuid>,
+
+    /// Geocoder that produced the coordinates, if any.
+    pub provider: Option<String>,
+
+    pub geocoded_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::shipping_zone::Entity",
+        from = "Column::ZoneId",
+        to = "super::shipping_zone::Column::Id"
+    )]
+    Zone,
+}
+
+impl Related<super::shipping_zone::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Zone.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,38 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+/// The `agent_decisions` table: audit trail of what background agents decided, why,
+/// and on which record.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "agent_decisions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    /// Registered agent name, e.g. `inventory`.
+    #[sea_orm(indexed)]
+    pub agent: String,
+
+    /// Groups the decisions made in one run.
+    pub run_id: Uuid,
+
+    /// Record the decision is about, e.g. `sku:ABC-1@3` or `return:<uuid>`.
+    #[sea_orm(indexed)]
+    pub subject: String,
+
+    /// What the agent decided, e.g. `reorder_suggested` or `auto_approved`.
+    pub action: String,
+
+    pub rationale: String,
+
+    #[sea_orm(column_type = "JsonBinary")]
+    pub details: Json,
+
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
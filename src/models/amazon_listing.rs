@@ -0,0 +1,33 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `amazon_listings` table: which of our SKUs an Amazon seller SKU and ASIN sell.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "amazon_listings")]
+pub struct Model {
+    /// Seller SKU on Amazon; unique per seller account.
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub seller_sku: String,
+
+    #[sea_orm(indexed)]
+    pub asin: String,
+
+    /// Our SKU, used on ingested orders and for FBA stock.
+    #[sea_orm(indexed)]
+    pub sku: String,
+
+    /// Set when the mapping was learned from an order rather than entered by hand.
+    pub auto_mapped: bool,
+
+    pub updated_by: String,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
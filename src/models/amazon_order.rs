@@ -0,0 +1,55 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(8))")]
+#[serde(rename_all = "snake_case")]
+pub enum FulfillmentChannel {
+    /// Fulfilled by Amazon from FBA stock.
+    #[sea_orm(string_value = "afn")]
+    Afn,
+    /// Fulfilled by us; Amazon needs a shipment confirmation.
+    #[sea_orm(string_value = "mfn")]
+    Mfn,
+}
+
+/// The `amazon_orders` table: marketplace orders ingested from the Selling Partner API and
+/// the order each was created as.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "amazon_orders")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub amazon_order_id: String,
+
+    #[sea_orm(unique)]
+    pub order_id: Uuid,
+
+    pub fulfillment_channel: FulfillmentChannel,
+
+    /// Amazon's order status, e.g. `Unshipped` or `Shipped`.
+    pub amazon_status: String,
+
+    pub purchase_date: DateTime<Utc>,
+
+    pub last_update_date: DateTime<Utc>,
+
+    /// Amazon order item ids with their seller SKU and quantity, needed to confirm
+    /// shipment.
+    #[sea_orm(column_type = "JsonBinary")]
+    pub items: Json,
+
+    /// When Amazon accepted our shipment confirmation; FBM orders only.
+    pub shipment_confirmed_at: Option<DateTime<Utc>>,
+
+    pub confirmation_error: Option<String>,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
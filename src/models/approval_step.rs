@@ -0,0 +1,102 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `approval_steps` table: one step of the approval chain of a requisition, return or
+/// other approvable record. Steps are decided in order; only the lowest undecided step of
+/// a subject is pending.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "approval_steps")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub subject: ApprovalSubject,
+
+    /// Id of the record being approved.
+    #[sea_orm(indexed)]
+    pub subject_id: String,
+
+    pub step: i32,
+
+    pub approver_kind: ApproverKind,
+
+    /// Role name or actor who must decide the step, after any delegation or escalation.
+    #[sea_orm(indexed)]
+    pub approver: String,
+
+    /// The approver the step was delegated or escalated away from, e.g. `role:finance`.
+    pub delegated_from: Option<String>,
+
+    #[sea_orm(indexed)]
+    pub status: ApprovalStatus,
+
+    /// When a pending step escalates if still undecided.
+    pub due_at: Option<DateTime<Utc>>,
+
+    pub escalated_at: Option<DateTime<Utc>>,
+
+    pub decided_by: Option<String>,
+
+    pub comment: Option<String>,
+
+    pub decided_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalSubject {
+    #[sea_orm(string_value = "requisition")]
+    Requisition,
+    #[sea_orm(string_value = "return")]
+    Return,
+    #[sea_orm(string_value = "purchase_order")]
+    PurchaseOrder,
+    #[sea_orm(string_value = "credit_memo")]
+    CreditMemo,
+    #[sea_orm(string_value = "price_override")]
+    PriceOverride,
+    #[sea_orm(string_value = "write_off")]
+    WriteOff,
+    #[sea_orm(string_value = "engineering_change")]
+    EngineeringChange,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum ApproverKind {
+    /// Anyone whose token carries the role may decide.
+    #[sea_orm(string_value = "role")]
+    Role,
+    /// Only the named actor, e.g. `user:42`, may decide.
+    #[sea_orm(string_value = "actor")]
+    Actor,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalStatus {
+    /// An earlier step is still undecided.
+    #[sea_orm(string_value = "waiting")]
+    Waiting,
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "approved")]
+    Approved,
+    #[sea_orm(string_value = "rejected")]
+    Rejected,
+    /// Not needed after an earlier step rejected or the request was withdrawn.
+    #[sea_orm(string_value = "skipped")]
+    Skipped,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,68 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(24))")]
+#[serde(rename_all = "snake_case")]
+pub enum AsnStatus {
+    #[sea_orm(string_value = "expected")]
+    Expected,
+    #[sea_orm(string_value = "in_transit")]
+    InTransit,
+    #[sea_orm(string_value = "received")]
+    Received,
+    #[sea_orm(string_value = "cancelled")]
+    Cancelled,
+}
+
+impl AsnStatus {
+    /// Goods that have not arrived yet can still be planned for cross-docking.
+    pub fn is_inbound(self) -> bool {
+        matches!(self, AsnStatus::Expected | AsnStatus::InTransit)
+    }
+}
+
+/// The `asns` table: an advance shipping notice for goods on their way to a warehouse.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "asns")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    /// The supplier's shipment reference.
+    #[sea_orm(unique)]
+    pub asn_number: String,
+
+    #[sea_orm(indexed)]
+    pub supplier_id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub purchase_order_id: Option<Uuid>,
+
+    /// Receiving warehouse.
+    pub warehouse: i32,
+
+    #[sea_orm(indexed)]
+    pub status: AsnStatus,
+
+    pub expected_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::asn_line::Entity")]
+    Lines,
+}
+
+impl Related<super::asn_line::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Lines.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,3 +1,5 @@
+use sea_orm::entity::prelude::*;
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel)]
 #[sea_orm(table_name = "asn_items")]
 pub struct Model {
@@ -10,7 +12,8 @@ pub struct Model {
     pub lot_number: Option<String>,
     pub serial_numbers: Option<Vec<String>>,
     pub expiration_date: Option<String>,
-    pub customs_value: Option<f64>,
+    #[sea_orm(column_type = "Decimal(Some((10, 2)))", nullable)]
+    pub customs_value: Option<Decimal>,
     pub country_of_origin: Option<String>,
     pub status: String,
     pub created_at: DateTime,
@@ -0,0 +1,39 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `asn_lines` table: one SKU on an advance shipping notice.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "asn_lines")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub asn_id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub sku: String,
+
+    pub quantity: i32,
+
+    /// Units reserved for cross-dock allocations; only the rest is put away on receipt.
+    pub cross_dock_quantity: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::asn::Entity",
+        from = "Column::AsnId",
+        to = "super::asn::Column::Id"
+    )]
+    Asn,
+}
+
+impl Related<super::asn::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Asn.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
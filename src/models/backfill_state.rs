@@ -0,0 +1,52 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum BackfillStatus {
+    #[sea_orm(string_value = "running")]
+    Running,
+    #[sea_orm(string_value = "paused")]
+    Paused,
+    #[sea_orm(string_value = "completed")]
+    Completed,
+    #[sea_orm(string_value = "failed")]
+    Failed,
+}
+
+/// The `backfill_states` table: how far each backfill got, so a paused, failed or
+/// interrupted backfill resumes where it stopped.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "backfill_states")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub name: String,
+
+    pub status: BackfillStatus,
+
+    /// Key of the last row processed; chunks continue after it.
+    pub cursor: Option<String>,
+
+    pub rows_processed: i64,
+
+    /// Job of the latest run, for progress under `/api/v1/jobs`.
+    pub job_id: Option<Uuid>,
+
+    pub error: Option<String>,
+
+    /// Set by a pause request; the instance running the backfill stops after its current
+    /// chunk.
+    pub pause_requested: bool,
+
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
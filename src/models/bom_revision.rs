@@ -0,0 +1,49 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum BomRevisionStatus {
+    /// The revision new work orders are built to. At most one per BOM.
+    #[sea_orm(string_value = "released")]
+    Released,
+    #[sea_orm(string_value = "superseded")]
+    Superseded,
+}
+
+/// The `bom_revisions` table: released revisions of a bill of materials, each introduced by
+/// an engineering change order. `(bom_id, revision)` is unique.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "bom_revisions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub bom_id: i32,
+
+    /// Revision label, e.g. `B`.
+    pub revision: String,
+
+    #[sea_orm(indexed)]
+    pub status: BomRevisionStatus,
+
+    pub effective_from: NaiveDate,
+
+    /// The ECO that released the revision.
+    pub eco_id: Option<Uuid>,
+
+    /// Line changes from the previous revision, as listed on the ECO.
+    pub changes: Json,
+
+    pub created_at: DateTime<Utc>,
+
+    pub superseded_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
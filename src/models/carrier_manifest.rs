@@ -0,0 +1,55 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::shipment::ShippingCarrier;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum ManifestStatus {
+    /// Accepted by the carrier, or printed for carriers that take a paper manifest.
+    #[sea_orm(string_value = "submitted")]
+    Submitted,
+    /// The carrier refused or could not be reached; its shipments stay unmanifested.
+    #[sea_orm(string_value = "failed")]
+    Failed,
+}
+
+/// The `carrier_manifests` table: one carrier's end-of-day close. Shipments on a submitted
+/// manifest point at it through `shipments.manifest_id`.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "carrier_manifests")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    pub carrier: ShippingCarrier,
+
+    pub manifest_date: NaiveDate,
+
+    pub status: ManifestStatus,
+
+    /// The carrier's manifest or pickup reference.
+    pub reference: Option<String>,
+
+    /// Adapter the manifest was submitted through.
+    pub adapter: String,
+
+    pub shipment_count: i32,
+
+    /// Printed manifest for the driver.
+    #[serde(skip)]
+    pub document: String,
+
+    pub error: Option<String>,
+
+    pub created_by: String,
+
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
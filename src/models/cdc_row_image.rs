@@ -0,0 +1,26 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `cdc_row_images` table: the last published image of each row, which becomes the
+/// `before` of its next change record.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "cdc_row_images")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub table_name: String,
+
+    /// The row's primary key as text.
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub row_key: String,
+
+    #[sea_orm(column_type = "JsonBinary")]
+    pub image: Json,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
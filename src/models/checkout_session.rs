@@ -0,0 +1,76 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+/// Lifecycle of a checkout session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum CheckoutStatus {
+    #[sea_orm(string_value = "open")]
+    Open,
+    #[sea_orm(string_value = "completed")]
+    Completed,
+    #[sea_orm(string_value = "cancelled")]
+    Cancelled,
+    #[sea_orm(string_value = "expired")]
+    Expired,
+}
+
+impl CheckoutStatus {
+    /// Only open sessions can be changed.
+    pub fn is_open(&self) -> bool {
+        matches!(self, CheckoutStatus::Open)
+    }
+}
+
+/// The `checkout_sessions` table: carts and checkouts driven by shoppers or agents,
+/// persisted so they survive restarts and can be resumed from another device.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "checkout_sessions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    /// Owning customer; `None` for guest sessions until the shopper identifies.
+    #[sea_orm(indexed)]
+    pub customer_id: Option<i32>,
+
+    pub status: CheckoutStatus,
+
+    /// Line items as submitted by the client.
+    #[sea_orm(column_type = "JsonBinary")]
+    pub items: Json,
+
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub shipping_address: Option<Json>,
+
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub payment: Option<Json>,
+
+    pub currency: String,
+
+    /// Free-form client or agent context, e.g. the agent that opened the session.
+    #[sea_orm(column_type = "JsonBinary")]
+    pub metadata: Json,
+
+    /// Order created when the session completed.
+    pub order_id: Option<Uuid>,
+
+    /// Incremented on every change; updates must name the version they were based on.
+    pub version: i32,
+
+    /// Actor that created the session.
+    pub created_by: String,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+
+    /// Open sessions past this time are treated as expired.
+    pub expires_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
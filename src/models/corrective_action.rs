@@ -0,0 +1,81 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `corrective_actions` table: corrective and preventive actions (CAPA) raised
+/// against a non-conformance report.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "corrective_actions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub ncr_id: Uuid,
+
+    pub kind: ActionKind,
+
+    pub description: String,
+
+    #[sea_orm(indexed)]
+    pub status: ActionStatus,
+
+    pub assignee: Option<String>,
+
+    pub due_date: NaiveDate,
+
+    /// How the action was checked to be effective.
+    pub verification_notes: Option<String>,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum ActionKind {
+    #[sea_orm(string_value = "corrective")]
+    Corrective,
+    #[sea_orm(string_value = "preventive")]
+    Preventive,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum ActionStatus {
+    #[sea_orm(string_value = "open")]
+    Open,
+    #[sea_orm(string_value = "in_progress")]
+    InProgress,
+    #[sea_orm(string_value = "implemented")]
+    Implemented,
+    #[sea_orm(string_value = "verified")]
+    Verified,
+    #[sea_orm(string_value = "cancelled")]
+    Cancelled,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::non_conformance::Entity",
+        from = "Column::NcrId",
+        to = "super::non_conformance::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    NonConformance,
+}
+
+impl Related<super::non_conformance::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::NonConformance.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
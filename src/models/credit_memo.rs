@@ -0,0 +1,90 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `credit_memos` table: credit owed to a customer, issued for a return or as a manual
+/// adjustment, and drawn down by applying it to invoices or refunding it.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "credit_memos")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(unique)]
+    pub memo_number: String,
+
+    #[sea_orm(indexed)]
+    pub customer_id: Uuid,
+
+    pub source: CreditMemoSource,
+
+    /// The return the memo was issued for; at most one memo per return.
+    #[sea_orm(unique)]
+    pub return_id: Option<Uuid>,
+
+    pub reason: String,
+
+    pub currency: String,
+
+    #[serde(with = "crate::money::amount")]
+    pub amount: Decimal,
+
+    /// Credit not yet applied or refunded.
+    #[serde(with = "crate::money::amount")]
+    pub remaining: Decimal,
+
+    #[sea_orm(indexed)]
+    pub status: CreditMemoStatus,
+
+    pub created_by: String,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+
+    /// When an accounting export picked up the memo.
+    pub exported_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum CreditMemoSource {
+    #[sea_orm(string_value = "return")]
+    Return,
+    #[sea_orm(string_value = "manual")]
+    Manual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum CreditMemoStatus {
+    /// Manual adjustments wait on the `credit_memo` approval chain.
+    #[sea_orm(string_value = "pending_approval")]
+    PendingApproval,
+    #[sea_orm(string_value = "open")]
+    Open,
+    #[sea_orm(string_value = "partially_applied")]
+    PartiallyApplied,
+    /// Fully applied or refunded.
+    #[sea_orm(string_value = "closed")]
+    Closed,
+    #[sea_orm(string_value = "voided")]
+    Voided,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::credit_memo_application::Entity")]
+    Applications,
+}
+
+impl Related<super::credit_memo_application::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Applications.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
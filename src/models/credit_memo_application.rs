@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `credit_memo_applications` table: part of a credit memo applied to an invoice or
+/// refunded to the customer.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "credit_memo_applications")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub credit_memo_id: Uuid,
+
+    pub kind: ApplicationKind,
+
+    /// Invoice credited, for invoice applications.
+    #[sea_orm(indexed)]
+    pub invoice_id: Option<String>,
+
+    /// Payment processor or check reference, for refunds.
+    pub reference: Option<String>,
+
+    #[serde(with = "crate::money::amount")]
+    pub amount: Decimal,
+
+    pub applied_by: String,
+
+    pub applied_at: DateTime<Utc>,
+
+    pub exported_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum ApplicationKind {
+    #[sea_orm(string_value = "invoice")]
+    Invoice,
+    #[sea_orm(string_value = "refund")]
+    Refund,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::credit_memo::Entity",
+        from = "Column::CreditMemoId",
+        to = "super::credit_memo::Column::Id"
+    )]
+    CreditMemo,
+}
+
+impl Related<super::credit_memo::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CreditMemo.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,67 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The outbound demand an inbound unit is routed to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(24))")]
+#[serde(rename_all = "snake_case")]
+pub enum CrossDockTarget {
+    #[sea_orm(string_value = "backorder")]
+    Backorder,
+    #[sea_orm(string_value = "transfer_order")]
+    TransferOrder,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(24))")]
+#[serde(rename_all = "snake_case")]
+pub enum CrossDockStatus {
+    #[sea_orm(string_value = "planned")]
+    Planned,
+    #[sea_orm(string_value = "shipped")]
+    Shipped,
+    #[sea_orm(string_value = "cancelled")]
+    Cancelled,
+}
+
+/// The `cross_dock_allocations` table: inbound ASN units sent straight from the receiving
+/// dock to an outbound shipment instead of being put away.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "cross_dock_allocations")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub asn_id: Uuid,
+
+    pub asn_line_id: Uuid,
+
+    pub sku: String,
+
+    pub quantity: i32,
+
+    pub target: CrossDockTarget,
+
+    /// The backorder or transfer order.
+    #[sea_orm(indexed)]
+    pub target_id: Uuid,
+
+    /// Set for backorders: the order the units ship to.
+    pub order_id: Option<Uuid>,
+
+    /// Set for transfer orders: the warehouse the units ship to.
+    pub to_warehouse: Option<i32>,
+
+    pub status: CrossDockStatus,
+
+    pub created_by: String,
+
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Records that carry custom metadata.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum FieldEntity {
+    #[sea_orm(string_value = "order")]
+    Order,
+    #[sea_orm(string_value = "customer")]
+    Customer,
+    #[sea_orm(string_value = "product")]
+    Product,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum FieldType {
+    #[sea_orm(string_value = "string")]
+    String,
+    #[sea_orm(string_value = "number")]
+    Number,
+    #[sea_orm(string_value = "boolean")]
+    Boolean,
+    /// `YYYY-MM-DD`.
+    #[sea_orm(string_value = "date")]
+    Date,
+    /// One of `allowed_values`.
+    #[sea_orm(string_value = "enum")]
+    Enum,
+}
+
+/// The `custom_field_definitions` table: a tenant's typed metadata keys per record type,
+/// checked whenever metadata is written. `(tenant_id, entity, key)` is unique.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "custom_field_definitions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub tenant_id: String,
+
+    pub entity: FieldEntity,
+
+    /// Metadata key, e.g. `erp_id`.
+    pub key: String,
+
+    pub field_type: FieldType,
+
+    /// Writes without the key are rejected.
+    pub required: bool,
+
+    /// JSON array of the values an `enum` field accepts.
+    pub allowed_values: Option<Json>,
+
+    pub description: Option<String>,
+
+    pub created_by: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -24,4 +24,12 @@ pub struct Customer {
     pub birthdate: Option<NaiveDateTime>,
     #[validate(length(min = 1, max = 50, message = "Country must be between 1 and 50 characters"))]
     pub country: String,
+    /// Preferred locale for notifications and documents (BCP 47, e.g. `de-DE`).
+    /// Falls back to the request locale when unset.
+    #[validate(length(min = 2, max = 35, message = "Locale must be a valid language tag"))]
+    pub locale: Option<String>,
+    /// Integrator-defined fields, checked against the tenant's custom field definitions.
+    /// Written through the custom fields API.
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
 }
@@ -0,0 +1,39 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+/// Whether a tag was applied by a person or computed by the segment evaluator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum TagSource {
+    #[sea_orm(string_value = "manual")]
+    Manual,
+    #[sea_orm(string_value = "segment")]
+    Segment,
+}
+
+/// The `customer_tags` table: tags and computed segments per customer. Segment rows are
+/// replaced wholesale on every evaluator run; manual rows are only changed through the API.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "customer_tags")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub customer_id: i32,
+
+    /// Lower-case tag or segment name, e.g. `vip` or `wholesale`.
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub tag: String,
+
+    pub source: TagSource,
+
+    /// Who applied a manual tag; `None` for segments.
+    pub created_by: Option<String>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
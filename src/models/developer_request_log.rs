@@ -0,0 +1,33 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `developer_request_logs` table: recent API-key requests, shown to the key's owner
+/// in the developer console.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "developer_request_logs")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    /// Non-secret prefix of the API key the request carried.
+    #[sea_orm(indexed)]
+    pub api_key_prefix: String,
+
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    pub status: i32,
+    pub latency_ms: i64,
+
+    /// Redacted and truncated bodies.
+    pub request_body: String,
+    pub response_body: String,
+
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(24))")]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeStatus {
+    /// Evidence is due; funds may already be withdrawn.
+    #[sea_orm(string_value = "needs_response")]
+    NeedsResponse,
+    /// Evidence was submitted and the issuer is deciding.
+    #[sea_orm(string_value = "under_review")]
+    UnderReview,
+    #[sea_orm(string_value = "won")]
+    Won,
+    #[sea_orm(string_value = "lost")]
+    Lost,
+    /// Conceded without contesting, e.g. by refunding the charge.
+    #[sea_orm(string_value = "accepted")]
+    Accepted,
+}
+
+impl DisputeStatus {
+    pub fn is_closed(self) -> bool {
+        matches!(self, DisputeStatus::Won | DisputeStatus::Lost | DisputeStatus::Accepted)
+    }
+}
+
+/// The `disputes` table: a chargeback or inquiry raised against a payment.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "disputes")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    /// Payment provider that reported the dispute, e.g. `stripe`.
+    pub provider: String,
+
+    /// The provider's dispute id.
+    #[sea_orm(unique)]
+    pub external_id: String,
+
+    /// The disputed charge or payment intent.
+    #[sea_orm(indexed)]
+    pub payment_ref: String,
+
+    /// Set when the payment matches a recorded authorization.
+    #[sea_orm(indexed)]
+    pub order_id: Option<Uuid>,
+
+    pub authorization_id: Option<Uuid>,
+
+    #[serde(with = "crate::money::amount")]
+    pub amount: Decimal,
+
+    pub currency: String,
+
+    /// The provider's reason code, e.g. `fraudulent` or `product_not_received`.
+    #[sea_orm(indexed)]
+    pub reason: String,
+
+    #[sea_orm(indexed)]
+    pub status: DisputeStatus,
+
+    /// Inquiries that become chargebacks only if left unanswered.
+    pub is_inquiry: bool,
+
+    #[sea_orm(indexed)]
+    pub evidence_due_by: Option<DateTime<Utc>>,
+
+    pub evidence: Option<Json>,
+
+    pub evidence_submitted_at: Option<DateTime<Utc>>,
+
+    /// When the approaching deadline was flagged, so it is flagged once.
+    pub deadline_alerted_at: Option<DateTime<Utc>>,
+
+    pub closed_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::dispute_event::Entity")]
+    Events,
+}
+
+impl Related<super::dispute_event::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Events.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,46 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::dispute::DisputeStatus;
+
+/// The `dispute_events` table: the history of a dispute's status changes.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "dispute_events")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub dispute_id: Uuid,
+
+    /// `None` for the event that opened the dispute.
+    pub from_status: Option<DisputeStatus>,
+
+    pub to_status: DisputeStatus,
+
+    /// Who made the change: `webhook:<provider>` or an actor such as `user:42`.
+    pub source: String,
+
+    pub note: Option<String>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::dispute::Entity",
+        from = "Column::DisputeId",
+        to = "super::dispute::Column::Id"
+    )]
+    Dispute,
+}
+
+impl Related<super::dispute::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Dispute.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
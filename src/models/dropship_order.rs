@@ -0,0 +1,76 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::encryption::EncryptedText;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum DropshipStatus {
+    /// The purchase order is raised but the supplier has not accepted it yet.
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    /// The supplier has the order and the customer's shipping details.
+    #[sea_orm(string_value = "forwarded")]
+    Forwarded,
+    /// The supplier confirmed at least one package.
+    #[sea_orm(string_value = "shipped")]
+    Shipped,
+}
+
+/// The `dropship_orders` table: the part of a sales order one supplier ships directly to
+/// the customer, with the vendor purchase order raised for it.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "dropship_orders")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub order_id: Uuid,
+
+    #[sea_orm(unique)]
+    pub purchase_order_id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub supplier_id: Uuid,
+
+    #[sea_orm(column_type = "Text")]
+    pub customer_name: EncryptedText,
+
+    #[sea_orm(column_type = "Text")]
+    pub customer_email: EncryptedText,
+
+    #[sea_orm(column_type = "Text")]
+    pub ship_to: EncryptedText,
+
+    #[sea_orm(indexed)]
+    pub status: DropshipStatus,
+
+    /// The supplier's order reference, once forwarded.
+    pub supplier_reference: Option<String>,
+
+    pub forward_attempts: i32,
+
+    /// Why the last forwarding attempt failed.
+    pub forward_error: Option<String>,
+
+    pub forwarded_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+#[async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    /// Refuses the write while encrypted columns could not be sealed.
+    async fn before_save<C: ConnectionTrait>(self, _db: &C, _insert: bool) -> Result<Self, DbErr> {
+        crate::encryption::ensure_sealable()?;
+        Ok(self)
+    }
+}
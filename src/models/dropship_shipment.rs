@@ -0,0 +1,34 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `dropship_shipments` table: packages a supplier confirmed shipping for a dropship
+/// order.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "dropship_shipments")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub dropship_order_id: Uuid,
+
+    pub carrier: String,
+
+    #[sea_orm(indexed)]
+    pub tracking_number: String,
+
+    pub tracking_url: Option<String>,
+
+    pub shipped_at: DateTime<Utc>,
+
+    /// Service account or user that reported the shipment.
+    pub confirmed_by: String,
+
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `dropship_sources` table: the supplier that ships a SKU straight to customers, and
+/// what it charges for it.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "dropship_sources")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub sku: String,
+
+    #[sea_orm(indexed)]
+    pub supplier_id: Uuid,
+
+    /// The supplier's own part number, sent on forwarded orders.
+    pub supplier_sku: Option<String>,
+
+    #[serde(with = "crate::money::amount")]
+    pub unit_cost: Decimal,
+
+    pub currency: String,
+
+    /// Days from forwarding until the supplier ships.
+    pub lead_time_days: i32,
+
+    /// Inactive sources are kept for history but no longer routed to.
+    pub active: bool,
+
+    pub updated_by: String,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,77 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum DunningStatus {
+    /// Waiting for the next scheduled retry.
+    #[sea_orm(string_value = "retrying")]
+    Retrying,
+    /// A retry succeeded.
+    #[sea_orm(string_value = "recovered")]
+    Recovered,
+    /// Every retry failed and the subscription was canceled.
+    #[sea_orm(string_value = "exhausted")]
+    Exhausted,
+    /// The subscription was canceled while retries were pending.
+    #[sea_orm(string_value = "closed")]
+    Closed,
+}
+
+/// The `dunning_cases` table: a failed recurring charge and its retries. A subscription
+/// has at most one case in `retrying`.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "dunning_cases")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub subscription_id: Uuid,
+
+    #[serde(with = "crate::money::amount")]
+    pub amount: Decimal,
+
+    pub currency: String,
+
+    #[sea_orm(indexed)]
+    pub status: DunningStatus,
+
+    /// Failed attempts so far, counting the original charge.
+    pub failures: i32,
+
+    #[sea_orm(indexed)]
+    pub next_retry_at: Option<DateTime<Utc>>,
+
+    pub last_error: Option<String>,
+
+    /// The processor's id for the charge that recovered the payment.
+    pub gateway_ref: Option<String>,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::subscription::Entity",
+        from = "Column::SubscriptionId",
+        to = "super::subscription::Column::Id"
+    )]
+    Subscription,
+}
+
+impl Related<super::subscription::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Subscription.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
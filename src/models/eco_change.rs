@@ -0,0 +1,44 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `eco_changes` table: one BOM an ECO revises.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "eco_changes")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub eco_id: Uuid,
+
+    /// The bill of materials; work orders reference it by `bill_of_materials_number`.
+    #[sea_orm(indexed)]
+    pub bom_id: i32,
+
+    /// The revision released when the ECO is implemented.
+    pub to_revision: String,
+
+    /// The revision it superseded; set on implementation, `None` for a first revision.
+    pub from_revision: Option<String>,
+
+    /// Line changes: `[{ "action": "add", "part_number": "...", "quantity": 2.0 }, ...]`.
+    pub changes: Json,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::engineering_change_order::Entity",
+        from = "Column::EcoId",
+        to = "super::engineering_change_order::Column::Id"
+    )]
+    Eco,
+}
+
+impl Related<super::engineering_change_order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Eco.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::work_order::WorkOrderStatus;
+
+/// The `eco_work_order_holds` table: an open work order put on hold because an ECO
+/// superseded the BOM revision it was pinned to.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "eco_work_order_holds")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub eco_id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub work_order_id: Uuid,
+
+    pub bom_id: i32,
+
+    /// The superseded revision the work order was pinned to.
+    pub revision: String,
+
+    /// Status restored on release.
+    pub previous_status: WorkOrderStatus,
+
+    pub held_at: DateTime<Utc>,
+
+    pub released_at: Option<DateTime<Utc>>,
+
+    pub released_by: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,75 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(24))")]
+#[serde(rename_all = "snake_case")]
+pub enum EcoStatus {
+    #[sea_orm(string_value = "draft")]
+    Draft,
+    #[sea_orm(string_value = "pending_approval")]
+    PendingApproval,
+    /// Approved and waiting for its effective date.
+    #[sea_orm(string_value = "approved")]
+    Approved,
+    /// New revisions are released and affected work orders held.
+    #[sea_orm(string_value = "implemented")]
+    Implemented,
+    #[sea_orm(string_value = "rejected")]
+    Rejected,
+    #[sea_orm(string_value = "cancelled")]
+    Cancelled,
+}
+
+/// The `engineering_change_orders` table: a set of BOM revision changes approved and
+/// released together.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "engineering_change_orders")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(unique)]
+    pub number: String,
+
+    pub title: String,
+
+    pub description: Option<String>,
+
+    /// Why the change is needed, e.g. a supplier part going end-of-life.
+    pub reason: Option<String>,
+
+    #[sea_orm(indexed)]
+    pub status: EcoStatus,
+
+    /// First day the new revisions apply; approval before then waits for it.
+    #[sea_orm(indexed)]
+    pub effective_date: NaiveDate,
+
+    pub requested_by: String,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+
+    pub submitted_at: Option<DateTime<Utc>>,
+
+    pub approved_at: Option<DateTime<Utc>>,
+
+    pub implemented_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::eco_change::Entity")]
+    Changes,
+}
+
+impl Related<super::eco_change::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Changes.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,85 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum EquipmentStatus {
+    #[sea_orm(string_value = "operational")]
+    Operational,
+    /// Has an open downtime event.
+    #[sea_orm(string_value = "down")]
+    Down,
+    /// Out of service for good; no new maintenance is scheduled.
+    #[sea_orm(string_value = "retired")]
+    Retired,
+}
+
+/// The `equipment` table: the asset registry maintenance is planned against. `asset_tag`
+/// is unique.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "equipment")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(unique)]
+    pub asset_tag: String,
+
+    pub name: String,
+
+    pub category: Option<String>,
+
+    pub location: Option<String>,
+
+    /// Code of the work center the equipment belongs to.
+    pub work_center: Option<String>,
+
+    /// Row in `machines` when the equipment is also tracked there.
+    pub machine_id: Option<i32>,
+
+    pub status: EquipmentStatus,
+
+    /// What the meter counts, e.g. `hours` or `cycles`; equipment without a meter has none.
+    pub meter_unit: Option<String>,
+
+    /// Latest meter reading.
+    pub meter_reading: Option<f64>,
+
+    pub installed_on: Option<NaiveDate>,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::maintenance_schedule::Entity")]
+    Schedules,
+    #[sea_orm(has_many = "super::meter_reading::Entity")]
+    MeterReadings,
+    #[sea_orm(has_many = "super::equipment_downtime::Entity")]
+    Downtime,
+}
+
+impl Related<super::maintenance_schedule::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Schedules.def()
+    }
+}
+
+impl Related<super::meter_reading::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MeterReadings.def()
+    }
+}
+
+impl Related<super::equipment_downtime::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Downtime.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
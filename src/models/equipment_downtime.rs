@@ -0,0 +1,47 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `equipment_downtime` table: a period a piece of equipment was out of service. Open
+/// events have no `ended_at`; equipment has at most one open event.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "equipment_downtime")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub equipment_id: Uuid,
+
+    /// Maintenance work order the equipment was down for, if any.
+    pub work_order_id: Option<Uuid>,
+
+    /// Planned downtime is taken for preventive maintenance; anything else is a breakdown.
+    pub planned: bool,
+
+    pub reason: String,
+
+    pub started_at: DateTime<Utc>,
+
+    pub ended_at: Option<DateTime<Utc>>,
+
+    pub reported_by: String,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::equipment::Entity",
+        from = "Column::EquipmentId",
+        to = "super::equipment::Column::Id"
+    )]
+    Equipment,
+}
+
+impl Related<super::equipment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Equipment.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
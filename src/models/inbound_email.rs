@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum InboundEmailStatus {
+    /// A draft return was created from the message.
+    #[sea_orm(string_value = "drafted")]
+    Drafted,
+    /// Looked like a return or warranty request, but no order of the sender matched, or
+    /// the sender could not be resolved to a customer.
+    #[sea_orm(string_value = "unmatched")]
+    Unmatched,
+    /// Failed the provider's SPF or DKIM check; the sender may be forged.
+    #[sea_orm(string_value = "rejected")]
+    Rejected,
+    /// Not a return or warranty request.
+    #[sea_orm(string_value = "ignored")]
+    Ignored,
+}
+
+/// The `inbound_emails` table: messages received on the returns mailbox, with the
+/// original MIME source kept as the attachment of the draft return they produced.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "inbound_emails")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    /// `Message-ID` header; unique, so a redelivered message is not processed twice.
+    #[sea_orm(unique)]
+    pub message_id: String,
+
+    /// Where the message came in: `ses` or `sendgrid`.
+    pub channel: String,
+
+    pub from_address: String,
+    pub subject: String,
+
+    /// `return` or `warranty` when the message was recognised as a request.
+    pub kind: Option<String>,
+
+    pub status: InboundEmailStatus,
+
+    #[sea_orm(indexed)]
+    pub order_id: Option<Uuid>,
+
+    #[sea_orm(indexed)]
+    pub return_id: Option<Uuid>,
+
+    /// The original message as received.
+    #[sea_orm(column_type = "Text")]
+    #[serde(skip_serializing)]
+    pub raw: String,
+
+    pub received_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,51 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum QuarantineStatus {
+    #[sea_orm(string_value = "quarantined")]
+    Quarantined,
+    /// Processed successfully on a retry.
+    #[sea_orm(string_value = "replayed")]
+    Replayed,
+    #[sea_orm(string_value = "discarded")]
+    Discarded,
+}
+
+/// The `ingest_quarantine` table: signed inbound webhooks that could not be parsed,
+/// validated, mapped or applied, kept for inspection and retry.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "ingest_quarantine")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    /// Configured source the payload arrived for, e.g. `shopify`.
+    #[sea_orm(indexed)]
+    pub source: String,
+
+    pub status: QuarantineStatus,
+
+    /// Why the payload was quarantined, from the latest attempt.
+    pub reason: String,
+
+    /// Request headers, without credentials.
+    #[sea_orm(column_type = "JsonBinary")]
+    pub headers: Json,
+
+    #[sea_orm(column_type = "Text")]
+    pub body: String,
+
+    pub attempts: i32,
+
+    pub received_at: DateTime<Utc>,
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,64 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `inspection_plans` table: which receipts or completions get inspected, how many
+/// units are sampled, and how many defects a sample may have before the lot fails.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "inspection_plans")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    pub name: String,
+
+    #[sea_orm(indexed)]
+    pub source: InspectionSource,
+
+    /// Applies to every SKU from `source` when `None`; a SKU-specific plan wins over that.
+    pub sku: Option<String>,
+
+    pub sampling: SamplingRule,
+
+    /// Units for `fixed` sampling, percent of the lot for `percent`; unused for `full`.
+    pub sample_value: i32,
+
+    /// Most defects a sample may contain and still pass.
+    pub acceptance_number: i32,
+
+    pub active: bool,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What brought the goods in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum InspectionSource {
+    #[sea_orm(string_value = "po_receipt")]
+    PoReceipt,
+    #[sea_orm(string_value = "asn_receipt")]
+    AsnReceipt,
+    #[sea_orm(string_value = "work_order_completion")]
+    WorkOrderCompletion,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum SamplingRule {
+    #[sea_orm(string_value = "full")]
+    Full,
+    #[sea_orm(string_value = "fixed")]
+    Fixed,
+    #[sea_orm(string_value = "percent")]
+    Percent,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
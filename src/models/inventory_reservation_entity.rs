@@ -0,0 +1,72 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::NaiveDateTime;
+use std::fmt;
+
+/// Lifecycle of a reservation. Stored as text in `inventory_reservations.status`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ReservationStatus {
+    Active,
+    Released,
+    /// Reached its expiration date before being released or converted.
+    Expired,
+}
+
+impl fmt::Display for ReservationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReservationStatus::Active => write!(f, "Active"),
+            ReservationStatus::Released => write!(f, "Released"),
+            ReservationStatus::Expired => write!(f, "Expired"),
+        }
+    }
+}
+
+/// The `inventory_reservations` table: stock held for an order, cart or other reference
+/// until it is released, converted or expires.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "inventory_reservations")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    pub warehouse_id: String,
+
+    #[sea_orm(indexed)]
+    pub product_id: Uuid,
+
+    /// What the stock is held for, e.g. an order or checkout session id.
+    #[sea_orm(indexed)]
+    pub reference_id: Uuid,
+
+    /// `SALES_ORDER`, `CART`, `CUSTOMER_HOLD`, ...
+    pub reference_type: String,
+
+    pub quantity: i32,
+
+    /// A [`ReservationStatus`] value.
+    pub status: String,
+
+    pub reservation_type: String,
+    pub lot_numbers: Option<Vec<String>>,
+    pub location_id: Option<String>,
+    pub priority: Option<i32>,
+    pub notes: Option<String>,
+
+    /// Active reservations stop counting against availability after this time and are
+    /// marked expired by the reservation expiry sweep.
+    #[sea_orm(indexed)]
+    pub expiration_date: NaiveDateTime,
+
+    pub created_at: NaiveDateTime,
+    pub created_by: Option<String>,
+    pub release_date: Option<NaiveDateTime>,
+
+    /// Reason code of the release, e.g. `ORDER_CANCELLED` or `RESERVATION_EXPIRED`.
+    pub release_reason: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,35 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// The `inventory_snapshots` table: stock levels per SKU and warehouse as of the end of
+/// a day. Unique on `(snapshot_date, sku, warehouse)`; re-running a day replaces it.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "inventory_snapshots")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    /// Business day the snapshot represents.
+    pub snapshot_date: NaiveDate,
+
+    /// When the levels were read.
+    pub taken_at: DateTime<Utc>,
+
+    pub sku: String,
+    pub warehouse: i32,
+
+    /// Available quantity across all lots of the SKU in the warehouse.
+    pub on_hand: i64,
+    pub reserved: i64,
+    pub allocated: i64,
+    pub incoming: i64,
+
+    /// Average unit cost across lots, when known.
+    pub unit_cost: Option<Decimal>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,99 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Why stock is being written off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(24))")]
+#[serde(rename_all = "snake_case")]
+pub enum WriteOffReason {
+    #[sea_orm(string_value = "damaged")]
+    Damaged,
+    #[sea_orm(string_value = "expired")]
+    Expired,
+    #[sea_orm(string_value = "theft")]
+    Theft,
+    #[sea_orm(string_value = "lost")]
+    Lost,
+    /// Shortfall found by a cycle count or stocktake.
+    #[sea_orm(string_value = "count_variance")]
+    CountVariance,
+    /// Requires notes explaining the loss.
+    #[sea_orm(string_value = "other")]
+    Other,
+}
+
+impl WriteOffReason {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WriteOffReason::Damaged => "damaged",
+            WriteOffReason::Expired => "expired",
+            WriteOffReason::Theft => "theft",
+            WriteOffReason::Lost => "lost",
+            WriteOffReason::CountVariance => "count_variance",
+            WriteOffReason::Other => "other",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(24))")]
+#[serde(rename_all = "snake_case")]
+pub enum WriteOffStatus {
+    #[sea_orm(string_value = "pending_approval")]
+    PendingApproval,
+    /// Stock was reduced and the loss sent to the ledger.
+    #[sea_orm(string_value = "posted")]
+    Posted,
+    #[sea_orm(string_value = "rejected")]
+    Rejected,
+}
+
+/// The `inventory_write_offs` table: stock removed as damaged, expired or otherwise lost.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "inventory_write_offs")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub inventory_item_id: String,
+
+    #[sea_orm(indexed)]
+    pub sku: String,
+
+    #[sea_orm(indexed)]
+    pub warehouse: i32,
+
+    pub quantity: i32,
+
+    /// Cost per unit when the write-off was requested.
+    #[serde(with = "crate::money::amount")]
+    pub unit_cost: Decimal,
+
+    /// Value written off; routes the approval chain.
+    #[serde(with = "crate::money::amount")]
+    pub amount: Decimal,
+
+    pub reason: WriteOffReason,
+
+    pub notes: Option<String>,
+
+    #[sea_orm(indexed)]
+    pub status: WriteOffStatus,
+
+    pub requested_by: String,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+
+    #[sea_orm(indexed)]
+    pub posted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
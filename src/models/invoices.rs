@@ -4,6 +4,7 @@ use validator::Validate;
 use chrono::{DateTime, Utc, NaiveDate};
 use rust_decimal::Decimal;
 use uuid::Uuid;
+use crate::i18n::{self, Locale};
 
 // Invoice Model (updated to include relation to line items)
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, Validate)]
@@ -183,6 +184,42 @@ impl Model {
 
         Ok(())
     }
+
+    /// Renders the invoice summary as plain text for PDF and email generation, with
+    /// labels, dates and amounts formatted for `locale`.
+    pub fn render_summary(&self, locale: Locale) -> String {
+        let currency = self.currency.as_deref().unwrap_or("USD");
+        let money = |amount: Option<Decimal>| i18n::format_currency(locale, amount.unwrap_or(Decimal::ZERO), currency);
+        let mut lines = vec![i18n::t(locale, "invoice.title", &[])];
+
+        if let Some(number) = self.number {
+            lines.push(format!("{}: {}", i18n::t(locale, "invoice.number", &[]), number));
+        }
+        if let Some(date) = self.invoice_date {
+            lines.push(format!("{}: {}", i18n::t(locale, "invoice.date", &[]), i18n::format_date(locale, date)));
+        }
+        if let Some(date) = self.due_date {
+            lines.push(format!("{}: {}", i18n::t(locale, "invoice.due_date", &[]), i18n::format_date(locale, date)));
+        }
+        if let Some(name) = &self.customer_name {
+            lines.push(format!("{}: {}", i18n::t(locale, "invoice.bill_to", &[]), name));
+        }
+
+        lines.push(String::new());
+        lines.push(format!("{}: {}", i18n::t(locale, "invoice.subtotal", &[]), money(self.subtotal)));
+        if self.discount_amount.map_or(false, |d| !d.is_zero()) {
+            lines.push(format!("{}: -{}", i18n::t(locale, "invoice.discount", &[]), money(self.discount_amount)));
+        }
+        lines.push(format!("{}: {}", i18n::t(locale, "invoice.tax", &[]), money(self.tax_amount)));
+        if self.shipping_amount.map_or(false, |s| !s.is_zero()) {
+            lines.push(format!("{}: {}", i18n::t(locale, "invoice.shipping", &[]), money(self.shipping_amount)));
+        }
+        lines.push(format!("{}: {}", i18n::t(locale, "invoice.total", &[]), money(self.total)));
+        lines.push(format!("{}: {}", i18n::t(locale, "invoice.amount_paid", &[]), money(self.amount_paid)));
+        lines.push(format!("{}: {}", i18n::t(locale, "invoice.amount_due", &[]), money(self.amount_remaining)));
+
+        lines.join("\n")
+    }
 }
 
 impl InvoiceLineItem {
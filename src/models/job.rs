@@ -0,0 +1,71 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+/// Lifecycle of a background job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    #[sea_orm(string_value = "queued")]
+    Queued,
+    #[sea_orm(string_value = "running")]
+    Running,
+    #[sea_orm(string_value = "succeeded")]
+    Succeeded,
+    #[sea_orm(string_value = "failed")]
+    Failed,
+}
+
+impl JobStatus {
+    /// Succeeded and failed jobs never change again.
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, JobStatus::Succeeded | JobStatus::Failed)
+    }
+}
+
+/// The `jobs` table: status, progress and outcome of long-running work such as bulk
+/// imports, exports and report generation.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "jobs")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    /// What the job does, e.g. `inventory_import` or `sales_report`.
+    pub kind: String,
+
+    pub status: JobStatus,
+
+    /// Completion percentage, 0–100.
+    pub progress: i32,
+
+    /// Human-readable progress detail, e.g. `Imported 4,000 of 10,000 rows`.
+    pub progress_message: Option<String>,
+
+    /// Job-specific result payload, set when the job succeeds.
+    pub result: Option<Json>,
+
+    /// Failure message, set when the job fails.
+    pub error: Option<String>,
+
+    /// Actor that submitted the job, e.g. `user:42`.
+    pub created_by: Option<String>,
+
+    /// Process running the job; see [`crate::jobs::JobRunner`].
+    pub instance_id: Option<String>,
+
+    /// Last time that process confirmed the job is still running. A job whose heartbeat
+    /// is older than the lease has lost its process.
+    pub heartbeat_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,95 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum KitOperation {
+    /// Consumes components and produces kits.
+    #[sea_orm(string_value = "kit")]
+    Kit,
+    /// Breaks kits back down into their components.
+    #[sea_orm(string_value = "dekit")]
+    Dekit,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum KitWorkOrderStatus {
+    #[sea_orm(string_value = "open")]
+    Open,
+    /// Inputs are picked and at the assembly bench.
+    #[sea_orm(string_value = "in_progress")]
+    InProgress,
+    #[sea_orm(string_value = "completed")]
+    Completed,
+    #[sea_orm(string_value = "cancelled")]
+    Cancelled,
+}
+
+/// The `kit_work_orders` table: light assembly that converts component stock into kit
+/// stock or back, with a single open → in progress → completed routing. Full manufacturing
+/// runs through `work_orders` instead.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "kit_work_orders")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(unique)]
+    pub number: String,
+
+    pub operation: KitOperation,
+
+    #[sea_orm(indexed)]
+    pub kit_sku: String,
+
+    pub warehouse: i32,
+
+    /// Kits built or broken down.
+    pub quantity: i32,
+
+    /// Flat assembly labor charged per kit.
+    #[serde(with = "crate::money::amount")]
+    pub labor_cost_per_kit: Decimal,
+
+    /// Cost of the stock consumed; set on completion.
+    #[serde(default, with = "crate::money::option_amount")]
+    pub material_cost: Option<Decimal>,
+
+    #[serde(default, with = "crate::money::option_amount")]
+    pub labor_cost: Option<Decimal>,
+
+    /// Rolled-up cost of one kit produced; set when kitting completes.
+    pub kit_unit_cost: Option<Decimal>,
+
+    #[sea_orm(indexed)]
+    pub status: KitWorkOrderStatus,
+
+    pub created_by: String,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+
+    pub started_at: Option<DateTime<Utc>>,
+
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::kit_work_order_component::Entity")]
+    Components,
+}
+
+impl Related<super::kit_work_order_component::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Components.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,39 @@
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `kit_work_order_components` table: a component SKU and how many go into one kit.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "kit_work_order_components")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub work_order_id: Uuid,
+
+    pub component_sku: String,
+
+    pub quantity_per_kit: i32,
+
+    /// Cost per unit consumed when kitting, or assigned when de-kitting; set on completion.
+    pub unit_cost: Option<Decimal>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::kit_work_order::Entity",
+        from = "Column::WorkOrderId",
+        to = "super::kit_work_order::Column::Id"
+    )]
+    WorkOrder,
+}
+
+impl Related<super::kit_work_order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WorkOrder.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,106 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Which side of an account increases its balance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NormalBalance {
+    Debit,
+    Credit,
+}
+
+/// The internal chart of accounts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(32))")]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerAccount {
+    #[sea_orm(string_value = "cash")]
+    Cash,
+    #[sea_orm(string_value = "accounts_receivable")]
+    AccountsReceivable,
+    #[sea_orm(string_value = "inventory")]
+    Inventory,
+    #[sea_orm(string_value = "sales_tax_payable")]
+    SalesTaxPayable,
+    #[sea_orm(string_value = "gift_card_liability")]
+    GiftCardLiability,
+    #[sea_orm(string_value = "revenue")]
+    Revenue,
+    #[sea_orm(string_value = "sales_returns")]
+    SalesReturns,
+    #[sea_orm(string_value = "inventory_adjustments")]
+    InventoryAdjustments,
+}
+
+impl LedgerAccount {
+    pub fn name(self) -> &'static str {
+        match self {
+            LedgerAccount::Cash => "Cash",
+            LedgerAccount::AccountsReceivable => "Accounts receivable",
+            LedgerAccount::Inventory => "Inventory",
+            LedgerAccount::SalesTaxPayable => "Sales tax payable",
+            LedgerAccount::GiftCardLiability => "Gift card liability",
+            LedgerAccount::Revenue => "Sales revenue",
+            LedgerAccount::SalesReturns => "Sales returns",
+            LedgerAccount::InventoryAdjustments => "Inventory adjustments",
+        }
+    }
+
+    /// Assets, expenses and contra-revenue accounts carry debit balances.
+    pub fn normal_balance(self) -> NormalBalance {
+        match self {
+            LedgerAccount::Cash
+            | LedgerAccount::AccountsReceivable
+            | LedgerAccount::Inventory
+            | LedgerAccount::SalesReturns
+            | LedgerAccount::InventoryAdjustments => NormalBalance::Debit,
+            LedgerAccount::SalesTaxPayable | LedgerAccount::GiftCardLiability | LedgerAccount::Revenue => {
+                NormalBalance::Credit
+            }
+        }
+    }
+}
+
+/// The `ledger_entries` table: a single debit or credit line of a ledger transaction.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "ledger_entries")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub transaction_id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub account: LedgerAccount,
+
+    #[serde(with = "crate::money::amount")]
+    pub debit: Decimal,
+
+    #[serde(with = "crate::money::amount")]
+    pub credit: Decimal,
+
+    /// Copied from the transaction so balances can be cut off by date without a join.
+    #[sea_orm(indexed)]
+    pub occurred_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::ledger_transaction::Entity",
+        from = "Column::TransactionId",
+        to = "super::ledger_transaction::Column::Id"
+    )]
+    Transaction,
+}
+
+impl Related<super::ledger_transaction::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Transaction.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,44 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `ledger_transactions` table: one balanced posting to the internal ledger, grouping
+/// its `ledger_entries`.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "ledger_transactions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    /// Identifies the business fact posted, e.g. `order_shipped:<order id>`; a fact is
+    /// posted at most once.
+    #[sea_orm(unique)]
+    pub source_key: String,
+
+    /// What was posted, e.g. `revenue` or `gift_card_issued`.
+    #[sea_orm(indexed)]
+    pub kind: String,
+
+    /// Id of the order, return, gift card or SKU the posting concerns.
+    pub reference: String,
+
+    pub description: String,
+
+    pub occurred_at: DateTime<Utc>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::ledger_entry::Entity")]
+    Entries,
+}
+
+impl Related<super::ledger_entry::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Entries.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
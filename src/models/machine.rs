@@ -82,7 +82,9 @@ pub struct MaintenanceRecord {
     pub maintenance_date: NaiveDate,
     pub description: String,
     pub performed_by: String,
-    pub cost: Option<f64>,
+    #[sea_orm(column_type = "Decimal(Some((10, 2)))", nullable)]
+    #[serde(default, with = "crate::money::option_amount")]
+    pub cost: Option<Decimal>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -109,7 +111,7 @@ impl MaintenanceRecord {
         maintenance_date: NaiveDate,
         description: String,
         performed_by: String,
-        cost: Option<f64>,
+        cost: Option<Decimal>,
     ) -> Self {
         Self {
             id: 0, // This will be set by the database
@@ -0,0 +1,58 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `maintenance_schedules` table: a recurring preventive maintenance task. It recurs
+/// every `interval_days`, every `meter_interval` units of meter, or whichever comes first
+/// when both are set.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "maintenance_schedules")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub equipment_id: Uuid,
+
+    pub name: String,
+
+    pub instructions: Option<String>,
+
+    pub interval_days: Option<i32>,
+
+    pub meter_interval: Option<f64>,
+
+    /// Work orders are generated this many days before the due date.
+    pub lead_days: i32,
+
+    /// Hours the task is expected to take, used as the work order's standard labor hours.
+    pub estimated_hours: f64,
+
+    pub next_due_date: Option<NaiveDate>,
+
+    pub next_due_meter: Option<f64>,
+
+    pub active: bool,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::equipment::Entity",
+        from = "Column::EquipmentId",
+        to = "super::equipment::Column::Id"
+    )]
+    Equipment,
+}
+
+impl Related<super::equipment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Equipment.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
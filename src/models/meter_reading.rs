@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `meter_readings` table: a reading of an equipment meter. Readings never decrease
+/// for one piece of equipment.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "meter_readings")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub equipment_id: Uuid,
+
+    pub reading: f64,
+
+    pub recorded_by: String,
+
+    pub recorded_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::equipment::Entity",
+        from = "Column::EquipmentId",
+        to = "super::equipment::Column::Id"
+    )]
+    Equipment,
+}
+
+impl Related<super::equipment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Equipment.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -1,14 +1,115 @@
 pub mod shipment;
 pub mod work_order;
+pub mod work_order_operation;
+pub mod work_order_time_entry;
 pub mod warranty;
 pub mod customer;
 pub mod order;
+pub mod order_event;
 pub mod inventory_items;
+pub mod inventory_snapshot;
+pub mod inventory_reservation_entity;
 pub mod manufacture_orders;
 pub mod waste_and_scrap;
 pub mod return_entity;
 pub mod picks;
 pub mod inventory_forecasts;
 pub mod machine;
+pub mod network_acl_entry;
+pub mod retention_run;
+pub mod job;
+pub mod agent_decision;
+pub mod customer_tag;
+pub mod product_bundle;
+pub mod product_bundle_component;
+pub mod checkout_session;
+pub mod product_listing;
 pub mod supplier;
-pub mod billofmaterials;
\ No newline at end of file
+pub mod service_account;
+pub mod billofmaterials;
+pub mod inspection_plan;
+pub mod quality_inspection;
+pub mod non_conformance;
+pub mod corrective_action;
+pub mod supplier_receipt;
+pub mod purchase_order;
+pub mod purchase_order_line;
+pub mod requisition;
+pub mod approval_step;
+pub mod credit_memo;
+pub mod credit_memo_application;
+pub mod inventory_write_off;
+pub mod accounting_export;
+pub mod ledger_transaction;
+pub mod ledger_entry;
+pub mod payment_authorization;
+pub mod payment_capture;
+pub mod subscription;
+pub mod dunning_case;
+pub mod dispute;
+pub mod dispute_event;
+pub mod asn;
+pub mod asn_line;
+pub mod backorder;
+pub mod transfer_order;
+pub mod cross_dock_allocation;
+pub mod kit_work_order;
+pub mod kit_work_order_component;
+pub mod bom_revision;
+pub mod engineering_change_order;
+pub mod eco_change;
+pub mod eco_work_order_hold;
+pub mod sales_forecast;
+pub mod work_center;
+pub mod work_center_shift;
+pub mod work_center_holiday;
+pub mod equipment;
+pub mod maintenance_schedule;
+pub mod meter_reading;
+pub mod equipment_downtime;
+pub mod scan_transaction;
+pub mod printer;
+pub mod print_job;
+pub mod order_fingerprint;
+pub mod shipping_zone;
+pub mod address_geocode;
+pub mod carrier_manifest;
+pub mod product_hazmat;
+pub mod shipment_dangerous_good;
+pub mod shipment_customs_line;
+pub mod return_disposition;
+pub mod vendor_return;
+pub mod dropship_source;
+pub mod dropship_order;
+pub mod dropship_shipment;
+pub mod amazon_listing;
+pub mod amazon_order;
+pub mod amazon_sync_cursor;
+pub mod pos_shift;
+pub mod pos_sale;
+pub mod pos_payment;
+pub mod usage_record;
+pub mod tenant_data_key;
+pub mod backfill_state;
+pub mod webhook_subscription;
+pub mod webhook_event;
+pub mod ingest_quarantine;
+pub mod inbound_email;
+pub mod support_case;
+pub mod abuse_block;
+pub mod slo_bucket;
+pub mod cdc_offset;
+pub mod cdc_row_image;
+pub mod stock_alert_threshold;
+pub mod stock_alert_subscription;
+pub mod stock_alert_state;
+pub mod custom_field_definition;
+pub mod developer_request_log;
+pub mod webhook_delivery_log;
+pub mod shipped_serial;
+pub mod vault_token;
+pub mod routing_rule;
+pub mod promotion;
+
+pub use inventory_reservation_entity::ReservationStatus;
+pub use promotion::PromotionStatus;
@@ -0,0 +1,28 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+/// The `network_acl_entries` table: globally denied addresses managed at runtime.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "network_acl_entries")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+
+    /// Single address or CIDR range, e.g. `198.51.100.0/24`.
+    pub cidr: String,
+
+    /// Why the range was blocked.
+    pub reason: Option<String>,
+
+    /// Inactive entries are kept for history but not enforced.
+    pub is_active: bool,
+
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
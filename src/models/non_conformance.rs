@@ -0,0 +1,102 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `non_conformances` table: non-conformance reports (NCRs) raised from a failed
+/// inspection, a return or a warranty claim, worked through investigation to closure.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "non_conformances")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    pub source: NcrSource,
+
+    /// Id of the inspection, return or warranty the NCR was raised from.
+    #[sea_orm(indexed)]
+    pub source_id: String,
+
+    #[sea_orm(indexed)]
+    pub sku: Option<String>,
+
+    #[sea_orm(indexed)]
+    pub supplier_id: Option<String>,
+
+    pub description: String,
+
+    pub severity: Severity,
+
+    #[sea_orm(indexed)]
+    pub status: NcrStatus,
+
+    pub assignee: Option<String>,
+
+    pub due_date: Option<NaiveDate>,
+
+    /// Required before an NCR leaves investigation.
+    pub root_cause: Option<String>,
+
+    pub created_by: String,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum NcrSource {
+    #[sea_orm(string_value = "inspection")]
+    Inspection,
+    #[sea_orm(string_value = "return")]
+    Return,
+    #[sea_orm(string_value = "warranty_claim")]
+    WarrantyClaim,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    #[sea_orm(string_value = "minor")]
+    Minor,
+    #[sea_orm(string_value = "major")]
+    Major,
+    #[sea_orm(string_value = "critical")]
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum NcrStatus {
+    #[sea_orm(string_value = "open")]
+    Open,
+    #[sea_orm(string_value = "investigating")]
+    Investigating,
+    /// Corrective or preventive actions must be verified before closing.
+    #[sea_orm(string_value = "action_required")]
+    ActionRequired,
+    #[sea_orm(string_value = "closed")]
+    Closed,
+    /// Raised in error.
+    #[sea_orm(string_value = "voided")]
+    Voided,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::corrective_action::Entity")]
+    CorrectiveActions,
+}
+
+impl Related<super::corrective_action::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::CorrectiveActions.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
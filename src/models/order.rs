@@ -4,6 +4,8 @@ use validator::{Validate, ValidationError};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 
+use crate::encryption::EncryptedText;
+
 /// Enum representing the possible statuses of an order.
 #[derive(Clone, Debug, PartialEq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
 #[sea_orm(rs_type = "String", db_type = "String(Some(32))")]
@@ -56,7 +58,8 @@ pub struct Model {
 
     /// Name of the customer who placed the order.
     #[validate(length(min = 1))]
-    pub customer_name: String,
+    #[sea_orm(column_type = "Text")]
+    pub customer_name: EncryptedText,
 
     /// Email of the customer.
     #[validate(email)]
@@ -64,12 +67,17 @@ pub struct Model {
 
     /// Delivery address for the order.
     #[validate(length(min = 10))]
-    pub delivery_address: String,
+    #[sea_orm(column_type = "Text")]
+    pub delivery_address: EncryptedText,
 
     /// Optional notes associated with the order.
     #[validate(length(max = 500))]
     pub notes: Option<String>,
 
+    /// Integrator-defined fields, checked against the tenant's custom field definitions.
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub metadata: Option<Json>,
+
     /// Foreign key referencing the warehouse handling the order.
     #[sea_orm(column_type = "Uuid")]
     pub warehouse_id: Uuid,
@@ -153,7 +161,14 @@ impl Related<super::warehouse::Entity> for Entity {
     }
 }
 
-impl ActiveModelBehavior for ActiveModel {}
+#[async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    /// Refuses the write while encrypted columns could not be sealed.
+    async fn before_save<C: ConnectionTrait>(self, _db: &C, _insert: bool) -> Result<Self, DbErr> {
+        crate::encryption::ensure_sealable()?;
+        Ok(self)
+    }
+}
 
 /// Implementation block for the `Order` model.
 impl Model {
@@ -188,10 +203,11 @@ impl Model {
         Self {
             id: Uuid::new_v4(),
             order_number,
-            customer_name,
+            customer_name: customer_name.into(),
             customer_email,
-            delivery_address,
+            delivery_address: delivery_address.into(),
             notes: None,
+            metadata: None,
             warehouse_id,
             order_status,
             fulfillment_type,
@@ -315,6 +331,9 @@ pub struct OrderLineItemModel {
     #[validate(length(max = 50))]
     pub sku_type: String,
 
+    /// Shipped by the SKU's supplier straight to the customer instead of from a warehouse.
+    pub dropship: bool,
+
     /// Timestamp when the line item was created.
     pub created_date: DateTime<Utc>,
 
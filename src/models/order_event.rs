@@ -0,0 +1,55 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// The `order_events` table: an append-only stream of domain events per order.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "order_events")]
+pub struct Model {
+    /// Primary key: Unique identifier for the event.
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    /// The order stream this event belongs to.
+    #[sea_orm(column_type = "Uuid", indexed)]
+    pub order_id: Uuid,
+
+    /// Position of the event within the order's stream, starting at 1.
+    /// `(order_id, sequence)` is unique so concurrent appends cannot interleave.
+    pub sequence: i64,
+
+    /// Discriminator of the domain event (e.g. `OrderCancelled`).
+    pub event_type: String,
+
+    /// Serialized event body.
+    #[sea_orm(column_type = "JsonBinary")]
+    pub payload: Json,
+
+    /// Who caused the event (user id, service account, or `system`).
+    pub actor: Option<String>,
+
+    /// Timestamp when the event was recorded.
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    /// Each event belongs to an order.
+    #[sea_orm(
+        belongs_to = "super::order::Entity",
+        from = "Column::OrderId",
+        to = "super::order::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Order,
+}
+
+impl Related<super::order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Order.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
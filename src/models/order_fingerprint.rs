@@ -0,0 +1,56 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateReview {
+    /// Flagged as a suspected duplicate and waiting for someone to look at it.
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    /// Placed on purpose despite matching an earlier order.
+    #[sea_orm(string_value = "overridden")]
+    Overridden,
+    /// Reviewed and found to be a separate order.
+    #[sea_orm(string_value = "dismissed")]
+    Dismissed,
+    /// Reviewed and found to be a double submit.
+    #[sea_orm(string_value = "confirmed")]
+    Confirmed,
+}
+
+/// The `order_fingerprints` table: a hash of each new order's customer, lines and total,
+/// used to spot the same order submitted twice. `duplicate_of` is the earlier order it
+/// matched, if any.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "order_fingerprints")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(unique)]
+    pub order_id: Uuid,
+
+    pub customer_id: Uuid,
+
+    pub fingerprint: String,
+
+    pub total: Option<Decimal>,
+
+    pub duplicate_of: Option<Uuid>,
+
+    /// Set when the order matched an earlier one.
+    pub review_status: Option<DuplicateReview>,
+
+    pub reviewed_by: Option<String>,
+
+    pub reviewed_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
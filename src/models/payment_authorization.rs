@@ -0,0 +1,92 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// When an authorization is captured.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureStrategy {
+    /// In full as soon as the authorization is recorded.
+    #[sea_orm(string_value = "on_order")]
+    OnOrder,
+    /// As shipments leave, in parts for split shipments.
+    #[sea_orm(string_value = "on_shipment")]
+    OnShipment,
+    /// Only through the capture endpoint.
+    #[sea_orm(string_value = "manual")]
+    Manual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(24))")]
+#[serde(rename_all = "snake_case")]
+pub enum AuthorizationStatus {
+    #[sea_orm(string_value = "authorized")]
+    Authorized,
+    #[sea_orm(string_value = "partially_captured")]
+    PartiallyCaptured,
+    /// Fully captured, or closed by a final partial capture.
+    #[sea_orm(string_value = "captured")]
+    Captured,
+    #[sea_orm(string_value = "voided")]
+    Voided,
+}
+
+impl AuthorizationStatus {
+    /// Authorizations that can still be captured against.
+    pub fn is_open(self) -> bool {
+        matches!(self, AuthorizationStatus::Authorized | AuthorizationStatus::PartiallyCaptured)
+    }
+}
+
+/// The `payment_authorizations` table: funds a processor has reserved for an order.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "payment_authorizations")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub order_id: Uuid,
+
+    /// Gateway holding the authorization, e.g. `stripe`.
+    pub gateway: String,
+
+    /// The processor's authorization id, e.g. a Stripe PaymentIntent id.
+    #[sea_orm(unique)]
+    pub authorization_ref: String,
+
+    pub currency: String,
+
+    #[serde(with = "crate::money::amount")]
+    pub authorized_amount: Decimal,
+
+    /// Sum of succeeded and in-flight captures.
+    #[serde(with = "crate::money::amount")]
+    pub captured_amount: Decimal,
+
+    pub capture_strategy: CaptureStrategy,
+
+    #[sea_orm(indexed)]
+    pub status: AuthorizationStatus,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::payment_capture::Entity")]
+    Captures,
+}
+
+impl Related<super::payment_capture::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Captures.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
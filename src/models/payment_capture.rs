@@ -0,0 +1,73 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum CaptureStatus {
+    /// Sent to the gateway, outcome not yet recorded.
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "succeeded")]
+    Succeeded,
+    #[sea_orm(string_value = "failed")]
+    Failed,
+}
+
+/// The `payment_captures` table: one capture attempt against an authorization.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "payment_captures")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub authorization_id: Uuid,
+
+    /// Tracking number of the shipment that triggered the capture, if any.
+    pub shipment_reference: Option<String>,
+
+    #[serde(with = "crate::money::amount")]
+    pub amount: Decimal,
+
+    /// Whether the capture released the rest of the authorization.
+    pub final_capture: bool,
+
+    pub status: CaptureStatus,
+
+    /// Sent to the gateway so a repeated trigger captures once.
+    #[sea_orm(unique)]
+    pub idempotency_key: String,
+
+    /// The processor's reference for the capture.
+    pub gateway_ref: Option<String>,
+
+    pub error: Option<String>,
+
+    /// `system` for automatic captures, otherwise the actor.
+    pub created_by: String,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::payment_authorization::Entity",
+        from = "Column::AuthorizationId",
+        to = "super::payment_authorization::Column::Id"
+    )]
+    Authorization,
+}
+
+impl Related<super::payment_authorization::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Authorization.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
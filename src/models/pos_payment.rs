@@ -0,0 +1,63 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::encryption::EncryptedText;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum Tender {
+    #[sea_orm(string_value = "cash")]
+    Cash,
+    /// Card payments taken on the terminal; the processor reference is kept.
+    #[sea_orm(string_value = "card")]
+    Card,
+    #[sea_orm(string_value = "gift_card")]
+    GiftCard,
+}
+
+/// The `pos_payments` table: one tender of a register sale.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "pos_payments")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub sale_id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub shift_id: Uuid,
+
+    pub tender: Tender,
+
+    /// What the tender paid towards the sale.
+    #[serde(with = "crate::money::amount")]
+    pub amount: Decimal,
+
+    /// Cash handed over by the customer.
+    #[serde(with = "crate::money::option_amount")]
+    pub tendered: Option<Decimal>,
+
+    #[serde(with = "crate::money::option_amount")]
+    pub change_given: Option<Decimal>,
+
+    #[sea_orm(column_type = "Text", nullable)]
+    pub reference: Option<EncryptedText>,
+
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+#[async_trait]
+impl ActiveModelBehavior for ActiveModel {
+    /// Refuses the write while encrypted columns could not be sealed.
+    async fn before_save<C: ConnectionTrait>(self, _db: &C, _insert: bool) -> Result<Self, DbErr> {
+        crate::encryption::ensure_sealable()?;
+        Ok(self)
+    }
+}
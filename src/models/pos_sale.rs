@@ -0,0 +1,49 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `pos_sales` table: a register sale and the order it became. The id is generated by
+/// the register and is also the order's id, so replaying a sale never creates a second order.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "pos_sales")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub register_id: String,
+
+    #[sea_orm(indexed)]
+    pub shift_id: Uuid,
+
+    pub cashier: String,
+
+    /// Register clock time of the sale.
+    pub sold_at: DateTime<Utc>,
+
+    #[serde(with = "crate::money::amount")]
+    pub total: Decimal,
+
+    pub currency: String,
+
+    /// Hash of the sale as first received; replays are compared against it.
+    pub fingerprint: String,
+
+    /// Whether the sale was rung up while the register was offline.
+    pub offline: bool,
+
+    pub replays: i32,
+
+    /// Fingerprint of the last replay that differed from the accepted sale.
+    pub conflict_fingerprint: Option<String>,
+
+    pub last_conflict_at: Option<DateTime<Utc>>,
+
+    pub received_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
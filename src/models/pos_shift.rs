@@ -0,0 +1,67 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum PosShiftStatus {
+    #[sea_orm(string_value = "open")]
+    Open,
+    /// The drawer was counted. Sales rung up before the close may still arrive from offline
+    /// registers and are added to the shift.
+    #[sea_orm(string_value = "closed")]
+    Closed,
+}
+
+/// The `pos_shifts` table: one cashier's session on a register, with the cash drawer it
+/// started and ended with. Ids are generated by the register so shifts can be opened offline.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "pos_shifts")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub register_id: String,
+
+    /// The store; POS orders are fulfilled from its stock.
+    pub warehouse_id: Uuid,
+
+    pub cashier: String,
+
+    #[sea_orm(indexed)]
+    pub status: PosShiftStatus,
+
+    #[serde(with = "crate::money::amount")]
+    pub opening_float: Decimal,
+
+    /// Cash taken less change given, across every sale received for the shift.
+    #[serde(with = "crate::money::amount")]
+    pub cash_sales: Decimal,
+
+    #[serde(with = "crate::money::option_amount")]
+    pub counted_cash: Option<Decimal>,
+
+    /// Counted cash less the float and cash sales; negative when the drawer is short.
+    #[serde(with = "crate::money::option_amount")]
+    pub cash_variance: Option<Decimal>,
+
+    pub sales_count: i32,
+
+    pub opened_at: DateTime<Utc>,
+
+    pub closed_at: Option<DateTime<Utc>>,
+
+    pub closed_by: Option<String>,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,84 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum PrintJobStatus {
+    #[sea_orm(string_value = "queued")]
+    Queued,
+    /// Pulled by an agent and not yet confirmed.
+    #[sea_orm(string_value = "printing")]
+    Printing,
+    #[sea_orm(string_value = "printed")]
+    Printed,
+    #[sea_orm(string_value = "failed")]
+    Failed,
+    #[sea_orm(string_value = "cancelled")]
+    Cancelled,
+}
+
+impl PrintJobStatus {
+    pub fn is_finished(self) -> bool {
+        matches!(self, PrintJobStatus::Printed | PrintJobStatus::Failed | PrintJobStatus::Cancelled)
+    }
+}
+
+/// The `print_jobs` table: a rendered document waiting for, or sent to, a printer. The
+/// document is rendered when the job is queued, so reprints match the original.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "print_jobs")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    pub printer_id: Uuid,
+
+    pub status: PrintJobStatus,
+
+    /// What was printed: `sku`, `bin`, `shipment` or `work_order`.
+    pub document_type: String,
+
+    pub document_id: String,
+
+    pub content_type: String,
+
+    #[serde(skip)]
+    pub content: Vec<u8>,
+
+    pub copies: i32,
+
+    /// Times an agent has pulled the job.
+    pub attempts: i32,
+
+    pub error: Option<String>,
+
+    pub created_by: String,
+
+    pub claimed_at: Option<DateTime<Utc>>,
+
+    pub completed_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::printer::Entity",
+        from = "Column::PrinterId",
+        to = "super::printer::Column::Id"
+    )]
+    Printer,
+}
+
+impl Related<super::printer::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Printer.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(8))")]
+#[serde(rename_all = "snake_case")]
+pub enum PrinterLanguage {
+    /// Zebra and compatible thermal printers.
+    #[sea_orm(string_value = "zpl")]
+    Zpl,
+    /// Anything the agent can print an image on.
+    #[sea_orm(string_value = "png")]
+    Png,
+}
+
+/// The `printers` table: label printers that print agents pull jobs for. `name` is unique.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "printers")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(unique)]
+    pub name: String,
+
+    /// Packing or shipping station the printer sits at.
+    pub station: Option<String>,
+
+    pub warehouse: Option<i32>,
+
+    pub language: PrinterLanguage,
+
+    /// Label stock loaded: `4x6`, `4x2` or `2x1`.
+    pub media: String,
+
+    /// Receives shipping labels automatically when shipments are booked.
+    pub auto_print_shipments: bool,
+
+    pub active: bool,
+
+    /// Last time an agent pulled jobs for the printer.
+    pub last_seen_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::print_job::Entity")]
+    Jobs,
+}
+
+impl Related<super::print_job::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Jobs.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
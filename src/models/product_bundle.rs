@@ -0,0 +1,56 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+/// How a bundle is sold and fulfilled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum BundleKind {
+    /// Components are picked separately and shipped together.
+    #[sea_orm(string_value = "bundle")]
+    Bundle,
+    /// Components are picked together and packed as one unit.
+    #[sea_orm(string_value = "kit")]
+    Kit,
+}
+
+/// The `product_bundles` table: sellable SKUs made of other SKUs. A bundle holds no stock
+/// of its own; ordering it reserves and picks its components.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "product_bundles")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    /// SKU customers order. Unique, and never also an inventory SKU.
+    #[sea_orm(unique)]
+    pub sku: String,
+
+    /// Product order lines and reservations refer to the bundle by. Unique.
+    #[sea_orm(unique)]
+    pub product_id: Uuid,
+
+    pub name: String,
+    pub kind: BundleKind,
+
+    /// Inactive bundles can no longer be ordered.
+    pub active: bool,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::product_bundle_component::Entity")]
+    Components,
+}
+
+impl Related<super::product_bundle_component::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Components.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,43 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `product_bundle_components` table: the SKUs, and how many of each, in one unit of a
+/// bundle. `(bundle_id, component_sku)` is unique.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "product_bundle_components")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub bundle_id: Uuid,
+
+    /// Product reserved for this component.
+    pub component_product_id: Uuid,
+
+    /// Inventory SKU picked for this component.
+    pub component_sku: String,
+
+    /// Units of the component per bundle; at least 1.
+    pub quantity: i32,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::product_bundle::Entity",
+        from = "Column::BundleId",
+        to = "super::product_bundle::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Bundle,
+}
+
+impl Related<super::product_bundle::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Bundle.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,50 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Degree of danger within a hazard class, I being the greatest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(3))")]
+pub enum PackingGroup {
+    #[sea_orm(string_value = "I")]
+    I,
+    #[sea_orm(string_value = "II")]
+    II,
+    #[sea_orm(string_value = "III")]
+    III,
+}
+
+/// The `product_hazmat` table: dangerous goods classification of a SKU. SKUs without a
+/// row ship as ordinary goods.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "product_hazmat")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub sku: String,
+
+    /// e.g. `UN1266`.
+    pub un_number: String,
+
+    pub proper_shipping_name: String,
+
+    /// Class or division, e.g. `3` or `2.1`.
+    pub hazard_class: String,
+
+    /// Not assigned for classes 1, 2, 6.2 and 7, nor for some class 9 entries.
+    pub packing_group: Option<PackingGroup>,
+
+    /// Packed as a limited quantity, which most carriers accept without a dangerous goods
+    /// contract.
+    pub limited_quantity: bool,
+
+    pub updated_by: String,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,56 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+/// The `product_listings` table: the sellable catalog, one row per SKU. Stock comes
+/// from `inventory_items`; the listing carries what shoppers and agents see.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "product_listings")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub sku: String,
+
+    pub title: String,
+    pub description: String,
+    pub brand: Option<String>,
+    pub gtin: Option<String>,
+
+    /// Groups variants (sizes, colors) of the same product.
+    pub item_group_id: Option<String>,
+    pub color: Option<String>,
+    pub size: Option<String>,
+
+    /// Harmonized System tariff code, declared to customs on international shipments.
+    pub hs_code: Option<String>,
+    /// ISO 3166-1 alpha-2 country the product was made in.
+    pub country_of_origin: Option<String>,
+
+    #[serde(with = "crate::money::amount")]
+    pub price: Decimal,
+    pub currency: String,
+
+    /// Product page; defaults to `<seller_url>/products/<sku>` in the feed.
+    pub link: Option<String>,
+    pub image_link: Option<String>,
+
+    /// Inactive listings are left out of full feeds and sent as delisted in incremental ones.
+    pub active: bool,
+
+    /// Whether agents may purchase the product directly.
+    pub enable_checkout: bool,
+
+    /// Integrator-defined fields, checked against the tenant's custom field definitions.
+    /// Written through the custom fields API, not with the listing.
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub metadata: Option<Json>,
+
+    pub created_at: DateTime<Utc>,
+    #[sea_orm(indexed)]
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
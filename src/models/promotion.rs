@@ -0,0 +1,48 @@
+use chrono::NaiveDateTime;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum PromotionStatus {
+    #[sea_orm(string_value = "active")]
+    Active,
+    #[sea_orm(string_value = "inactive")]
+    Inactive,
+}
+
+/// The `promotions` table.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "promotions")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+
+    pub name: String,
+
+    pub discount_percentage: f64,
+
+    pub start_date: NaiveDateTime,
+    pub end_date: NaiveDateTime,
+
+    pub status: PromotionStatus,
+
+    /// Customer segments or tags the promotion is limited to, as a JSON list; empty for
+    /// every customer.
+    pub target_segments: Json,
+
+    pub created_at: Option<NaiveDateTime>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    pub fn target_segments(&self) -> Vec<String> {
+        serde_json::from_value(self.target_segments.clone()).unwrap_or_default()
+    }
+}
@@ -0,0 +1,71 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `purchase_orders` table: orders placed with a supplier, currently raised from
+/// approved requisitions.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "purchase_orders")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(unique)]
+    pub po_number: String,
+
+    #[sea_orm(indexed)]
+    pub supplier_id: Uuid,
+
+    /// The requisition the order was converted from.
+    #[sea_orm(indexed)]
+    pub requisition_id: Option<Uuid>,
+
+    #[sea_orm(indexed)]
+    pub status: PurchaseOrderStatus,
+
+    pub currency: String,
+
+    #[serde(with = "crate::money::amount")]
+    pub total_amount: Decimal,
+
+    pub expected_delivery_date: NaiveDate,
+
+    #[sea_orm(column_type = "JsonBinary")]
+    pub shipping_address: Json,
+
+    pub created_by: String,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum PurchaseOrderStatus {
+    /// Created but not yet sent to the supplier.
+    #[sea_orm(string_value = "open")]
+    Open,
+    #[sea_orm(string_value = "sent")]
+    Sent,
+    #[sea_orm(string_value = "received")]
+    Received,
+    #[sea_orm(string_value = "cancelled")]
+    Cancelled,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::purchase_order_line::Entity")]
+    Lines,
+}
+
+impl Related<super::purchase_order_line::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Lines.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,51 @@
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `purchase_order_lines` table: one SKU ordered on a purchase order.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "purchase_order_lines")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub purchase_order_id: Uuid,
+
+    pub line_number: i32,
+
+    pub sku: String,
+
+    pub description: Option<String>,
+
+    pub quantity: i32,
+
+    #[serde(with = "crate::money::amount")]
+    pub unit_price: Decimal,
+
+    /// Tax rate as a fraction, e.g. `0.0825`.
+    #[serde(default, with = "crate::money::option_amount")]
+    pub tax_rate: Option<Decimal>,
+
+    /// Quantity times unit price plus tax, rounded to currency precision.
+    #[serde(with = "crate::money::amount")]
+    pub line_total: Decimal,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::purchase_order::Entity",
+        from = "Column::PurchaseOrderId",
+        to = "super::purchase_order::Column::Id"
+    )]
+    PurchaseOrder,
+}
+
+impl Related<super::purchase_order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::PurchaseOrder.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,105 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::inspection_plan::InspectionSource;
+
+/// The `quality_inspections` table: one inspection of a received or produced lot. Failed
+/// and quarantined units are moved to the warehouse's quarantine location when the result
+/// is recorded, and back when the hold is released.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "quality_inspections")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    pub plan_id: Uuid,
+
+    pub source: InspectionSource,
+
+    /// Purchase order, ASN or work order the lot came from.
+    #[sea_orm(indexed)]
+    pub reference: String,
+
+    pub sku: String,
+
+    /// Supplier of a purchase order or ASN lot.
+    #[sea_orm(indexed)]
+    pub supplier_id: Option<Uuid>,
+
+    pub warehouse: i32,
+
+    /// Inventory row the lot was received into.
+    pub inventory_item_id: String,
+
+    pub lot_quantity: i32,
+
+    pub sample_size: i32,
+
+    #[sea_orm(indexed)]
+    pub status: InspectionStatus,
+
+    pub disposition: Option<Disposition>,
+
+    pub defects: Option<i32>,
+
+    /// Units moved to quarantine by the disposition.
+    pub quarantined_quantity: Option<i32>,
+
+    /// Inventory row holding the quarantined units.
+    pub quarantine_item_id: Option<String>,
+
+    pub inspector: Option<String>,
+
+    pub notes: Option<String>,
+
+    pub created_at: DateTime<Utc>,
+
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum InspectionStatus {
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "completed")]
+    Completed,
+    /// Completed, and the units it held in quarantine were released back to stock.
+    #[sea_orm(string_value = "released")]
+    Released,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum Disposition {
+    /// The lot is released as received.
+    #[sea_orm(string_value = "pass")]
+    Pass,
+    /// The failed units are rejected and held in quarantine for return or scrap.
+    #[sea_orm(string_value = "fail")]
+    Fail,
+    /// The whole lot is held pending further review.
+    #[sea_orm(string_value = "quarantine")]
+    Quarantine,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::inspection_plan::Entity",
+        from = "Column::PlanId",
+        to = "super::inspection_plan::Column::Id"
+    )]
+    Plan,
+}
+
+impl Related<super::inspection_plan::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Plan.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,68 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `requisitions` table: internal requests to buy, routed through the `requisition`
+/// approval chain and converted to a purchase order on final approval.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "requisitions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub requested_by: String,
+
+    pub supplier_id: Uuid,
+
+    pub currency: String,
+
+    /// `RequisitionLine`s as JSON.
+    #[sea_orm(column_type = "JsonBinary")]
+    pub lines: Json,
+
+    #[sea_orm(column_type = "JsonBinary")]
+    pub shipping_address: Json,
+
+    /// Sum of the line totals including tax; drives approval routing.
+    #[serde(with = "crate::money::amount")]
+    pub total_amount: Decimal,
+
+    pub needed_by: NaiveDate,
+
+    pub justification: Option<String>,
+
+    #[sea_orm(indexed)]
+    pub status: RequisitionStatus,
+
+    /// Set once the requisition is converted.
+    pub purchase_order_id: Option<Uuid>,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+
+    pub submitted_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum RequisitionStatus {
+    #[sea_orm(string_value = "draft")]
+    Draft,
+    #[sea_orm(string_value = "pending_approval")]
+    PendingApproval,
+    #[sea_orm(string_value = "approved")]
+    Approved,
+    #[sea_orm(string_value = "rejected")]
+    Rejected,
+    #[sea_orm(string_value = "cancelled")]
+    Cancelled,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
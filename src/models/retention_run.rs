@@ -0,0 +1,40 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+/// The `retention_runs` table: an audit record of every retention policy execution.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "retention_runs")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    /// Name of the policy that ran, e.g. `orders`.
+    pub policy: String,
+
+    /// Table the policy applies to.
+    pub table_name: String,
+
+    /// `archive`, `purge` or `export`.
+    pub action: String,
+
+    /// Rows older than this were affected.
+    pub cutoff: DateTime<Utc>,
+
+    /// Number of rows archived, exported or purged.
+    pub rows_affected: i64,
+
+    /// Object storage key of exported rows, for `export` runs.
+    pub export_key: Option<String>,
+
+    /// Failure message when the run did not complete.
+    pub error: Option<String>,
+
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
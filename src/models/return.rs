@@ -9,6 +9,9 @@ use uuid::Uuid;
 #[derive(Debug, Clone, PartialEq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
 #[sea_orm(rs_type = "String", db_type = "String(Some(32))")]
 pub enum ReturnStatus {
+    /// Created from an inbound email; not a request until customer service confirms it.
+    #[sea_orm(string_value = "Draft")]
+    Draft,
     #[sea_orm(string_value = "Requested")]
     Requested,
     #[sea_orm(string_value = "Approved")]
@@ -107,6 +110,12 @@ pub struct Model {
     /// Date when the return was requested.
     pub requested_date: DateTime<Utc>,
 
+    /// Fraud risk from 0 to 100; `None` until the return has been assessed.
+    pub risk_score: Option<i32>,
+
+    /// The fraud signals that make up `risk_score`.
+    pub risk_signals: Option<Json>,
+
     /// Return Merchandise Authorization (RMA) number.
     #[validate(length(min = 1, message = "RMA cannot be empty"))]
     pub rma: String,
@@ -325,6 +334,8 @@ impl Model {
             reason_category: None,
             reported_condition: None,
             requested_date: now,
+            risk_score: None,
+            risk_signals: None,
             rma,
             serial_number: None,
             shipped_date: None,
@@ -641,6 +652,8 @@ mod tests {
             reason_category: Some("Damaged".to_string()),
             reported_condition: Some(Condition::Damaged),
             requested_date: Utc::now(),
+            risk_score: None,
+            risk_signals: None,
             rma: "".to_string(), // Invalid RMA
             serial_number: Some("SN1234567890".to_string()),
             shipped_date: None,
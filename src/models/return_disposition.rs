@@ -0,0 +1,68 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// What happens to returned units once inspected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(24))")]
+#[serde(rename_all = "snake_case")]
+pub enum Disposition {
+    /// Back into sellable stock.
+    #[sea_orm(string_value = "restock")]
+    Restock,
+    /// Repaired or repackaged under a work order before going back to stock.
+    #[sea_orm(string_value = "refurbish")]
+    Refurbish,
+    /// Destroyed; recorded as a damaged write-off.
+    #[sea_orm(string_value = "scrap")]
+    Scrap,
+    /// Sent back to the supplier against the purchase order it came in on.
+    #[sea_orm(string_value = "return_to_vendor")]
+    ReturnToVendor,
+}
+
+/// The `return_dispositions` table: where returned units of a SKU went, with the record
+/// the disposition created.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "return_dispositions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub return_id: Uuid,
+
+    pub rma: String,
+
+    #[sea_orm(indexed)]
+    pub sku: String,
+
+    pub warehouse: i32,
+
+    pub quantity: i32,
+
+    pub disposition: Disposition,
+
+    /// Return reason category, `unspecified` when the return has none.
+    pub reason: String,
+
+    /// Stock item the units were put back on, for restocks.
+    pub inventory_item_id: Option<String>,
+
+    pub work_order_id: Option<Uuid>,
+
+    pub write_off_id: Option<Uuid>,
+
+    pub vendor_return_id: Option<Uuid>,
+
+    pub notes: Option<String>,
+
+    pub disposed_by: String,
+
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
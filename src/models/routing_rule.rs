@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `routing_rules` table: sourcing overrides ops can change without a deploy, e.g. to
+/// route around a warehouse whose carrier is down. An order takes the first active rule,
+/// by ascending `priority`, whose every condition it meets; empty conditions match
+/// anything. Without a matching rule the `sourcing` config applies.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "routing_rules")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(unique)]
+    pub name: String,
+
+    pub description: Option<String>,
+
+    #[sea_orm(indexed)]
+    pub priority: i32,
+
+    /// ISO 3166-1 alpha-2 destination countries.
+    pub countries: Json,
+
+    /// Destination state or province names or codes.
+    pub regions: Json,
+
+    /// Sales channels, e.g. `web`, `pos` or `amazon`.
+    pub channels: Json,
+
+    /// SKU classes, e.g. ABC classes; the rule matches when any line is in one.
+    pub sku_classes: Json,
+
+    /// Sourcing rules to rank locations by, replacing the configured ones when not empty.
+    pub strategy: Json,
+
+    /// Locations never sourced from while the rule applies.
+    pub excluded_warehouses: Json,
+
+    /// Overrides `ship_from_store` from the config.
+    pub ship_from_store: Option<bool>,
+
+    /// Overrides `max_splits` from the config.
+    pub max_splits: Option<i32>,
+
+    pub active: bool,
+
+    pub created_by: String,
+
+    pub updated_by: String,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
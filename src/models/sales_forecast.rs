@@ -0,0 +1,63 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum ForecastSource {
+    /// Loaded from a CSV file, typically exported from a planning tool.
+    #[sea_orm(string_value = "import")]
+    Import,
+    #[sea_orm(string_value = "manual")]
+    Manual,
+}
+
+/// The `sales_forecasts` table: expected unit sales of a SKU through one channel in one
+/// month. `(sku, channel, month)` is unique. `channel` matches `orders.source`; orders
+/// without a source count as `direct`.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "sales_forecasts")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub sku: String,
+
+    pub channel: String,
+
+    /// First day of the forecast month.
+    #[sea_orm(indexed)]
+    pub month: NaiveDate,
+
+    /// Baseline forecast from the last import or manual entry.
+    pub quantity: i32,
+
+    /// Planner override; takes precedence over `quantity` and survives re-imports.
+    pub override_quantity: Option<i32>,
+
+    pub override_reason: Option<String>,
+
+    pub overridden_by: Option<String>,
+
+    pub overridden_at: Option<DateTime<Utc>>,
+
+    pub source: ForecastSource,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Model {
+    /// The quantity planning uses: the override when there is one.
+    pub fn effective_quantity(&self) -> i32 {
+        self.override_quantity.unwrap_or(self.quantity)
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `scan_transactions` table: a stock or shipment movement posted from a scanner.
+/// `idempotency_key` is unique, so a scanner retrying after a dropped connection gets the
+/// stored `response` back instead of posting twice.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "scan_transactions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(unique)]
+    pub idempotency_key: String,
+
+    /// `receive`, `pick`, `pack` or `count`.
+    pub action: String,
+
+    pub code: String,
+
+    pub quantity: Option<i32>,
+
+    pub warehouse: Option<i32>,
+
+    /// Order, ASN or other document the scan was made against.
+    pub reference: Option<String>,
+
+    pub device_id: Option<String>,
+
+    pub actor: String,
+
+    pub response: Json,
+
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,81 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use validator::Validate;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// The `service_accounts` table: non-human principals used by machine-to-machine integrations.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, Validate)]
+#[sea_orm(table_name = "service_accounts")]
+pub struct Model {
+    /// Primary key: Unique identifier for the service account.
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    /// Human-readable name of the integration (e.g. "erp-sync").
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+
+    /// Optional description of what the integration does.
+    #[validate(length(max = 500))]
+    pub description: Option<String>,
+
+    /// Public client identifier for the client-credentials grant.
+    #[sea_orm(unique)]
+    pub client_id: String,
+
+    /// SHA-256 hash of the client secret. The plaintext is only shown once at creation.
+    #[serde(skip_serializing)]
+    pub client_secret_hash: String,
+
+    /// Non-secret prefix of the API key, shown in listings to identify which key is in use.
+    pub api_key_prefix: String,
+
+    /// SHA-256 hash of the API key. The plaintext is only shown once at creation.
+    #[sea_orm(unique)]
+    #[serde(skip_serializing)]
+    pub api_key_hash: String,
+
+    /// Permission scopes this account may use (e.g. `orders:read`).
+    #[sea_orm(column_type = "JsonBinary")]
+    pub scopes: Json,
+
+    /// IPs or CIDR ranges the account may authenticate from. Empty means any.
+    #[sea_orm(column_type = "JsonBinary")]
+    pub allowed_ips: Json,
+
+    /// Tenant the account acts for; its writes are encrypted with that tenant's key.
+    pub tenant_id: Option<String>,
+
+    /// Whether the account can currently authenticate.
+    pub is_active: bool,
+
+    /// User who created the account.
+    pub created_by: String,
+
+    /// Timestamp when the account was created.
+    pub created_at: DateTime<Utc>,
+
+    /// Timestamp of the last successful authentication.
+    pub last_used_at: Option<DateTime<Utc>>,
+
+    /// Timestamp when the account was revoked.
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
+
+impl Model {
+    /// Returns the scopes granted to this account.
+    pub fn scope_list(&self) -> Vec<String> {
+        serde_json::from_value(self.scopes.clone()).unwrap_or_default()
+    }
+
+    /// Returns the configured IP allowlist entries.
+    pub fn allowed_ip_list(&self) -> Vec<String> {
+        serde_json::from_value(self.allowed_ips.clone()).unwrap_or_default()
+    }
+}
@@ -27,6 +27,12 @@ pub struct Model {
     pub shipped_at: Option<DateTimeWithTimeZone>,
     
     pub estimated_delivery: Option<DateTimeWithTimeZone>,
+
+    /// When the carrier confirmed delivery; compared with `estimated_delivery` for SLA reporting.
+    pub delivered_at: Option<DateTimeWithTimeZone>,
+
+    /// End-of-day manifest the shipment was handed over on.
+    pub manifest_id: Option<Uuid>,
     
     pub created_at: DateTimeWithTimeZone,
     
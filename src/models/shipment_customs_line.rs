@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `shipment_customs_lines` table: the customs declaration of an international
+/// shipment, one line per SKU. Tariff code and origin are copied from the listing when
+/// the lines are generated.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "shipment_customs_lines")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub shipment_id: i32,
+
+    pub destination_country: String,
+
+    pub sku: String,
+
+    pub description: String,
+
+    pub hs_code: String,
+
+    pub country_of_origin: String,
+
+    pub quantity: i32,
+
+    #[serde(with = "crate::money::amount")]
+    pub unit_value: Decimal,
+
+    pub currency: String,
+
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
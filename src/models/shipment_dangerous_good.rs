@@ -0,0 +1,39 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::product_hazmat::PackingGroup;
+
+/// The `shipment_dangerous_goods` table: dangerous goods declared in a shipment. The
+/// classification is copied from `product_hazmat` when declared, so documents keep
+/// printing what was shipped.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "shipment_dangerous_goods")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub shipment_id: i32,
+
+    pub sku: String,
+
+    pub quantity: i32,
+
+    pub un_number: String,
+
+    pub proper_shipping_name: String,
+
+    pub hazard_class: String,
+
+    pub packing_group: Option<PackingGroup>,
+
+    pub limited_quantity: bool,
+
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,30 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `shipped_serials` table: serial numbers of the units that left in an order,
+/// recorded at pack or ship time and checked against the serial of a returned unit.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "shipped_serials")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub order_id: Uuid,
+
+    pub sku: String,
+
+    /// Trimmed and upper-cased, as scanners disagree on case.
+    #[sea_orm(indexed)]
+    pub serial_number: String,
+
+    pub recorded_by: String,
+
+    pub recorded_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,57 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `shipping_zones` table: a named area that rate rules, tax jurisdictions and
+/// delivery promises are keyed on. An address is in a zone when it meets every criterion
+/// the zone sets; when several zones match, the lowest `priority` wins. `code` is unique.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "shipping_zones")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(unique)]
+    pub code: String,
+
+    pub name: String,
+
+    pub priority: i32,
+
+    /// ISO 3166-1 alpha-2 codes; empty means any country.
+    pub countries: Json,
+
+    /// State or province names or codes; empty means any region.
+    pub regions: Json,
+
+    /// Postal code prefixes, compared without spaces; empty means any postal code.
+    pub postal_prefixes: Json,
+
+    /// Centre of a radius zone; needs geocoded addresses.
+    pub center_latitude: Option<f64>,
+
+    pub center_longitude: Option<f64>,
+
+    pub radius_km: Option<f64>,
+
+    /// Rate rules group the zone is priced under.
+    pub rate_group: Option<String>,
+
+    pub tax_jurisdiction: Option<String>,
+
+    /// Delivery promise range in business days.
+    pub transit_days_min: Option<i32>,
+
+    pub transit_days_max: Option<i32>,
+
+    pub active: bool,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
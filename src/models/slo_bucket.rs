@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `slo_buckets` table: request counts of one route group over one flush interval,
+/// written by every instance. SLOs are evaluated by summing rows over a window.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "slo_buckets")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i64,
+
+    #[sea_orm(indexed)]
+    pub route_group: String,
+
+    /// Start of the interval the counts cover.
+    #[sea_orm(indexed)]
+    pub bucket_start: DateTime<Utc>,
+
+    pub requests: i64,
+
+    /// Requests answered with a 5xx.
+    pub errors: i64,
+
+    /// Requests answered within each latency bound, cumulative like a Prometheus histogram.
+    pub le_50ms: i64,
+    pub le_100ms: i64,
+    pub le_250ms: i64,
+    pub le_500ms: i64,
+    pub le_1000ms: i64,
+    pub le_2500ms: i64,
+    pub le_5000ms: i64,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,22 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `stock_alert_states` table: locations currently alerted as low. A row is removed
+/// once the location recovers, so it alerts again the next time it drops.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "stock_alert_states")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub sku: String,
+
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub warehouse: i32,
+
+    pub flagged_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `stock_alert_subscriptions` table: who is notified when which SKUs run low.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "stock_alert_subscriptions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub user_id: i32,
+
+    /// A SKU, or a prefix ending in `*` such as `APP-*`.
+    pub sku_pattern: String,
+
+    /// `None` for every warehouse.
+    pub warehouse: Option<i32>,
+
+    /// Locale of the alert messages, e.g. `de-DE`.
+    pub locale: String,
+
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,29 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `stock_alert_thresholds` table: sellable quantity at or below which a SKU is low.
+/// `(sku, warehouse)` is unique; a threshold for a warehouse overrides the SKU's default.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "stock_alert_thresholds")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub sku: String,
+
+    /// `None` for the SKU's default across warehouses.
+    pub warehouse: Option<i32>,
+
+    pub threshold: i32,
+
+    pub updated_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,78 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionStatus {
+    #[sea_orm(string_value = "active")]
+    Active,
+    /// A recurring charge failed and is being retried.
+    #[sea_orm(string_value = "past_due")]
+    PastDue,
+    #[sea_orm(string_value = "canceled")]
+    Canceled,
+}
+
+/// The `subscriptions` table: a customer's recurring plan and the stored payment method
+/// it is charged to.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "subscriptions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub customer_id: Uuid,
+
+    /// User notified about billing problems, if the customer has an account.
+    pub user_id: Option<i32>,
+
+    pub plan: String,
+
+    /// Amount charged each period.
+    #[serde(with = "crate::money::amount")]
+    pub amount: Decimal,
+
+    pub currency: String,
+
+    /// The processor's customer id, e.g. a Stripe customer.
+    pub gateway_customer_ref: String,
+
+    /// The stored payment method recurring charges are made against.
+    pub payment_method_ref: String,
+
+    /// Payment vault token the subscription is billed through, redeemed on every charge.
+    pub vault_token_id: Option<Uuid>,
+
+    #[sea_orm(indexed)]
+    pub status: SubscriptionStatus,
+
+    pub current_period_end: DateTime<Utc>,
+
+    pub past_due_since: Option<DateTime<Utc>>,
+
+    pub canceled_at: Option<DateTime<Utc>>,
+
+    pub cancel_reason: Option<String>,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::dunning_case::Entity")]
+    DunningCases,
+}
+
+impl Related<super::dunning_case::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::DunningCases.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,43 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `supplier_receipts` table: one received purchase order or ASN line, kept for
+/// supplier on-time and fill-rate reporting.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "supplier_receipts")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub supplier_id: Uuid,
+
+    /// The purchase order received against, directly or through an ASN.
+    #[sea_orm(indexed)]
+    pub purchase_order_id: Option<Uuid>,
+
+    /// Purchase order or ASN the goods arrived against.
+    pub reference: String,
+
+    pub sku: String,
+
+    /// Units of the SKU on the purchase order.
+    pub ordered_quantity: i32,
+
+    pub received_quantity: i32,
+
+    /// Delivery date promised on the purchase order.
+    pub promised_date: NaiveDate,
+
+    /// The quality inspection opened for the lot, if any.
+    pub inspection_id: Option<Uuid>,
+
+    #[sea_orm(indexed)]
+    pub received_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,90 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `support_cases` table: customer issues tracked by the support team, linked to the
+/// order, return and shipment they concern, with first-response and resolution SLA timers.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "support_cases")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    pub subject: String,
+
+    pub description: String,
+
+    #[sea_orm(indexed)]
+    pub customer_email: String,
+
+    #[sea_orm(indexed)]
+    pub customer_id: Option<Uuid>,
+
+    #[sea_orm(indexed)]
+    pub order_id: Option<Uuid>,
+
+    #[sea_orm(indexed)]
+    pub return_id: Option<Uuid>,
+
+    #[sea_orm(indexed)]
+    pub shipment_id: Option<i32>,
+
+    pub priority: CasePriority,
+
+    #[sea_orm(indexed)]
+    pub status: CaseStatus,
+
+    #[sea_orm(indexed)]
+    pub assignee: Option<String>,
+
+    pub first_response_due_at: DateTime<Utc>,
+
+    pub resolution_due_at: DateTime<Utc>,
+
+    pub first_responded_at: Option<DateTime<Utc>>,
+
+    /// Set while the case waits on the customer; the resolution timer is paused.
+    pub sla_paused_at: Option<DateTime<Utc>>,
+
+    pub created_by: String,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+
+    pub resolved_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum CasePriority {
+    #[sea_orm(string_value = "low")]
+    Low,
+    #[sea_orm(string_value = "normal")]
+    Normal,
+    #[sea_orm(string_value = "high")]
+    High,
+    #[sea_orm(string_value = "urgent")]
+    Urgent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum CaseStatus {
+    #[sea_orm(string_value = "open")]
+    Open,
+    /// Waiting on the customer.
+    #[sea_orm(string_value = "pending")]
+    Pending,
+    #[sea_orm(string_value = "resolved")]
+    Resolved,
+    #[sea_orm(string_value = "closed")]
+    Closed,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,53 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum DataKeyStatus {
+    /// Encrypts new values for the tenant. One per tenant.
+    #[sea_orm(string_value = "active")]
+    Active,
+    /// Replaced by a newer key; still decrypts values the rotation job has not reached.
+    #[sea_orm(string_value = "retired")]
+    Retired,
+}
+
+/// The `tenant_data_keys` table: per-tenant data keys, stored only wrapped by the master
+/// key in KMS.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "tenant_data_keys")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub tenant_id: String,
+
+    pub version: i32,
+
+    /// The data key encrypted by the master key, base64 encoded.
+    #[serde(skip_serializing)]
+    pub wrapped_key: String,
+
+    /// `kms` or `local`.
+    pub provider: String,
+
+    pub master_key_id: String,
+
+    #[sea_orm(indexed)]
+    pub status: DataKeyStatus,
+
+    /// Values still encrypted with this key, as of the last rotation run.
+    pub remaining_values: Option<i64>,
+
+    pub created_at: DateTime<Utc>,
+
+    pub retired_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
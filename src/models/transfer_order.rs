@@ -0,0 +1,52 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(24))")]
+#[serde(rename_all = "snake_case")]
+pub enum TransferOrderStatus {
+    #[sea_orm(string_value = "open")]
+    Open,
+    /// Every unit is covered by inbound stock.
+    #[sea_orm(string_value = "allocated")]
+    Allocated,
+    #[sea_orm(string_value = "shipped")]
+    Shipped,
+    #[sea_orm(string_value = "cancelled")]
+    Cancelled,
+}
+
+/// The `transfer_orders` table: stock to move from one warehouse to another.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "transfer_orders")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub sku: String,
+
+    pub from_warehouse: i32,
+
+    pub to_warehouse: i32,
+
+    pub quantity: i32,
+
+    /// Units covered by cross-dock allocations.
+    pub allocated_quantity: i32,
+
+    #[sea_orm(indexed)]
+    pub status: TransferOrderStatus,
+
+    pub created_by: String,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
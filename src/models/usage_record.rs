@@ -0,0 +1,58 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(32))")]
+#[serde(rename_all = "snake_case")]
+pub enum UsageMetric {
+    #[sea_orm(string_value = "orders_created")]
+    OrdersCreated,
+    #[sea_orm(string_value = "api_calls")]
+    ApiCalls,
+    /// Peak bytes stored during the month, rather than a running count.
+    #[sea_orm(string_value = "storage_bytes")]
+    StorageBytes,
+}
+
+impl UsageMetric {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UsageMetric::OrdersCreated => "orders_created",
+            UsageMetric::ApiCalls => "api_calls",
+            UsageMetric::StorageBytes => "storage_bytes",
+        }
+    }
+}
+
+/// The `usage_records` table: one tenant's usage of one billable metric in a calendar
+/// month. Unique on `(tenant_id, period, metric)`.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "usage_records")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub tenant_id: String,
+
+    /// `YYYY-MM`, in UTC.
+    #[sea_orm(indexed)]
+    pub period: String,
+
+    pub metric: UsageMetric,
+
+    pub quantity: i64,
+
+    /// When the closed month was delivered to the billing system.
+    pub exported_at: Option<DateTime<Utc>>,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,97 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// How often a vault token may be redeemed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum TokenUsage {
+    /// Redeemed once, e.g. a payment delegated to an agent for one checkout.
+    #[sea_orm(string_value = "single_use")]
+    SingleUse,
+    /// Redeemed repeatedly until revoked or expired, e.g. for subscription renewals.
+    #[sea_orm(string_value = "reusable")]
+    Reusable,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum TokenStatus {
+    #[sea_orm(string_value = "active")]
+    Active,
+    /// A single-use token that was redeemed.
+    #[sea_orm(string_value = "consumed")]
+    Consumed,
+    #[sea_orm(string_value = "revoked")]
+    Revoked,
+    #[sea_orm(string_value = "expired")]
+    Expired,
+}
+
+/// The `payment_vault_tokens` table: opaque tokens standing in for payment methods held
+/// by the processor. Card data is never stored; the token itself only as a hash.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "payment_vault_tokens")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    /// SHA-256 of the token, for lookup. The token is shown once, when issued.
+    #[sea_orm(unique)]
+    #[serde(skip_serializing)]
+    pub token_hash: String,
+
+    /// First characters of the token, to tell tokens apart in logs and support tools.
+    pub token_prefix: String,
+
+    /// Gateway holding the payment method, e.g. `stripe`.
+    pub provider: String,
+
+    /// The processor's payment method, e.g. a Stripe `pm_` id.
+    pub payment_method_ref: String,
+
+    /// The processor's customer the method is attached to, if any.
+    pub customer_ref: Option<String>,
+
+    pub brand: Option<String>,
+    pub last4: Option<String>,
+    pub exp_month: Option<i32>,
+    pub exp_year: Option<i32>,
+
+    pub usage: TokenUsage,
+
+    #[sea_orm(indexed)]
+    pub status: TokenStatus,
+
+    /// Largest amount a single redemption may be for.
+    #[serde(default, with = "crate::money::option_amount")]
+    pub max_amount: Option<Decimal>,
+
+    /// Currency redemptions must be in, required with `max_amount`.
+    pub currency: Option<String>,
+
+    /// Checkout session the token is restricted to, if any.
+    #[sea_orm(indexed)]
+    pub checkout_session_id: Option<Uuid>,
+
+    /// What redeemed the token last, e.g. `checkout:<id>` or `subscription:<id>`.
+    pub last_used_by: Option<String>,
+    pub last_used_at: Option<DateTime<Utc>>,
+
+    /// Actor that issued the token.
+    pub created_by: String,
+
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+
+    /// Active tokens past this time are treated as expired.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
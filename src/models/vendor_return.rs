@@ -0,0 +1,60 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(Some(16))")]
+#[serde(rename_all = "snake_case")]
+pub enum VendorReturnStatus {
+    /// Waiting to be shipped back to the supplier.
+    #[sea_orm(string_value = "open")]
+    Open,
+    #[sea_orm(string_value = "shipped")]
+    Shipped,
+    /// The supplier issued a credit.
+    #[sea_orm(string_value = "credited")]
+    Credited,
+}
+
+/// The `vendor_returns` table: goods going back to a supplier, valued at the price paid on
+/// the purchase order they were bought on.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "vendor_returns")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub supplier_id: Uuid,
+
+    pub purchase_order_id: Option<Uuid>,
+
+    pub sku: String,
+
+    pub quantity: i32,
+
+    #[serde(with = "crate::money::amount")]
+    pub unit_cost: Decimal,
+
+    #[serde(with = "crate::money::amount")]
+    pub amount: Decimal,
+
+    /// Customer return the goods came back on.
+    pub rma: Option<String>,
+
+    pub reason: String,
+
+    pub status: VendorReturnStatus,
+
+    pub created_by: String,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
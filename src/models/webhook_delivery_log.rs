@@ -0,0 +1,38 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `webhook_delivery_logs` table: one row per attempt to deliver an event to a
+/// subscription.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "webhook_delivery_logs")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub subscription_id: Uuid,
+
+    pub event_type: String,
+    pub url: String,
+    pub attempt: i32,
+    pub replay: bool,
+
+    /// Response status; `None` when no response was received.
+    pub status: Option<i32>,
+
+    /// Why the attempt failed without a response, e.g. a timeout.
+    pub error: Option<String>,
+
+    pub latency_ms: i64,
+
+    /// The subscriber's response, redacted and truncated.
+    pub response_body: String,
+
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,28 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `webhook_events` table: outbox of dispatched webhook payloads before any
+/// subscription's transform, kept so events can be replayed to a subscriber.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "webhook_events")]
+pub struct Model {
+    /// Same as the payload's `id`, so receivers can tell a replay from a new event.
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    /// Event name, e.g. `order_created`.
+    #[sea_orm(indexed)]
+    pub event_type: String,
+
+    #[sea_orm(column_type = "JsonBinary")]
+    pub payload: Json,
+
+    #[sea_orm(indexed)]
+    pub occurred_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,52 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `webhook_subscriptions` table: endpoints notified of entity events, with the
+/// transform applied to each payload before delivery.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "webhook_subscriptions")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    pub name: String,
+
+    pub url: String,
+
+    /// Event names delivered, e.g. `["order_created", "order_shipped"]`; empty for all.
+    #[sea_orm(column_type = "JsonBinary")]
+    pub event_types: Json,
+
+    /// Key the payload is signed with.
+    #[serde(skip_serializing)]
+    pub secret: String,
+
+    /// Paths kept in the payload, e.g. `["$.event", "$.entity.order_number"]`; all when null.
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub fields: Option<Json>,
+
+    /// Paths removed from the payload after `fields` is applied.
+    #[sea_orm(column_type = "JsonBinary")]
+    pub exclude: Json,
+
+    /// Shape of the delivered body; strings like `{{ $.entity.id }}` are filled in from
+    /// the payload. The filtered payload is sent when null.
+    #[sea_orm(column_type = "JsonBinary", nullable)]
+    pub template: Option<Json>,
+
+    /// Whether customer PII is delivered; redacted otherwise. Only holders of
+    /// `webhooks:pii` may turn it on.
+    pub include_pii: bool,
+
+    pub active: bool,
+
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {}
+
+impl ActiveModelBehavior for ActiveModel {}
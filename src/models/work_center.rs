@@ -0,0 +1,48 @@
+use chrono::{DateTime, Utc};
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `work_centers` table: a group of machines or people that routing steps run in.
+/// `code` is unique and is what `work_order_operations.work_center` refers to.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "work_centers")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(unique)]
+    pub code: String,
+
+    pub name: String,
+
+    /// Share of scheduled shift hours that turns into productive hours, e.g. `0.85`.
+    pub efficiency: f64,
+
+    pub active: bool,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(has_many = "super::work_center_shift::Entity")]
+    Shifts,
+    #[sea_orm(has_many = "super::work_center_holiday::Entity")]
+    Holidays,
+}
+
+impl Related<super::work_center_shift::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Shifts.def()
+    }
+}
+
+impl Related<super::work_center_holiday::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Holidays.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
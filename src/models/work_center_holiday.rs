@@ -0,0 +1,38 @@
+use chrono::NaiveDate;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `work_center_holidays` table: a day a work center is closed regardless of its
+/// shifts. `(work_center_id, date)` is unique.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "work_center_holidays")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub work_center_id: Uuid,
+
+    pub date: NaiveDate,
+
+    pub description: Option<String>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::work_center::Entity",
+        from = "Column::WorkCenterId",
+        to = "super::work_center::Column::Id",
+        on_delete = "Cascade"
+    )]
+    WorkCenter,
+}
+
+impl Related<super::work_center::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WorkCenter.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
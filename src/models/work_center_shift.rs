@@ -0,0 +1,49 @@
+use chrono::NaiveTime;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `work_center_shifts` table: a weekly recurring shift of a work center. A shift that
+/// ends at or before its start time runs past midnight and counts toward its start day.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "work_center_shifts")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub work_center_id: Uuid,
+
+    /// ISO weekday, 1 for Monday through 7 for Sunday.
+    pub weekday: i16,
+
+    pub start_time: NaiveTime,
+
+    pub end_time: NaiveTime,
+}
+
+impl Model {
+    pub fn hours(&self) -> f64 {
+        let seconds = (self.end_time - self.start_time).num_seconds();
+        let seconds = if seconds <= 0 { seconds + 24 * 3600 } else { seconds };
+        seconds as f64 / 3600.0
+    }
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::work_center::Entity",
+        from = "Column::WorkCenterId",
+        to = "super::work_center::Column::Id",
+        on_delete = "Cascade"
+    )]
+    WorkCenter,
+}
+
+impl Related<super::work_center::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WorkCenter.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
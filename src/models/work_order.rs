@@ -35,6 +35,13 @@ pub struct Model {
     pub bill_of_materials_id: Uuid,
     #[sea_orm(column_type = "Uuid")]
     pub cogs_data_id: Uuid,
+    /// BOM revision the work order is built to; work orders pinned to a revision an ECO
+    /// supersedes are put on hold.
+    pub bom_revision: Option<String>,
+    /// Equipment a maintenance work order services.
+    pub equipment_id: Option<Uuid>,
+    /// Preventive maintenance schedule that generated the work order.
+    pub maintenance_schedule_id: Option<Uuid>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -62,6 +69,9 @@ pub enum WorkOrderStatus {
     Completed,
     #[sea_orm(string_value = "Cancelled")]
     Cancelled,
+    /// Held by an engineering change until it is re-pinned to a current BOM revision.
+    #[sea_orm(string_value = "On Hold")]
+    OnHold,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
@@ -155,6 +165,9 @@ impl Model {
             capacity_utilization_id,
             bill_of_materials_id,
             cogs_data_id,
+            bom_revision: None,
+            equipment_id: None,
+            maintenance_schedule_id: None,
         };
         work_order.validate()?;
         Ok(work_order)
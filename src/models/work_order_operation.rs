@@ -0,0 +1,88 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `work_order_operations` table: the routing steps of a work order with the hours and
+/// rates they were planned at. `(work_order_id, sequence)` is unique.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "work_order_operations")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub work_order_id: Uuid,
+
+    /// Position in the routing; steps run in ascending order.
+    pub sequence: i32,
+
+    pub name: String,
+
+    /// Code of the work center the step runs in; see `work_centers`.
+    pub work_center: Option<String>,
+
+    /// Day the step is scheduled to run; capacity planning falls back to the work order's
+    /// issue date.
+    pub scheduled_date: Option<NaiveDate>,
+
+    /// Machine the step runs on, used for machine time recorded without one.
+    pub machine_id: Option<i32>,
+
+    pub standard_labor_hours: f64,
+
+    pub standard_machine_hours: f64,
+
+    /// Cost of one labor hour on this step.
+    #[serde(with = "crate::money::amount")]
+    pub labor_rate: Decimal,
+
+    /// Cost of one machine hour on this step.
+    #[serde(with = "crate::money::amount")]
+    pub machine_rate: Decimal,
+
+    pub status: OperationStatus,
+
+    pub created_at: DateTime<Utc>,
+
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+pub enum OperationStatus {
+    #[sea_orm(string_value = "Pending")]
+    Pending,
+    #[sea_orm(string_value = "In Progress")]
+    InProgress,
+    #[sea_orm(string_value = "Completed")]
+    Completed,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::work_order::Entity",
+        from = "Column::WorkOrderId",
+        to = "super::work_order::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    WorkOrder,
+    #[sea_orm(has_many = "super::work_order_time_entry::Entity")]
+    TimeEntries,
+}
+
+impl Related<super::work_order::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::WorkOrder.def()
+    }
+}
+
+impl Related<super::work_order_time_entry::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::TimeEntries.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,70 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// The `work_order_time_entries` table: labor a worker clocked, or machine time captured,
+/// against a work order operation. A labor entry stays open, without `ended_at`, until the
+/// worker clocks out.
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize)]
+#[sea_orm(table_name = "work_order_time_entries")]
+pub struct Model {
+    #[sea_orm(primary_key, auto_increment = false)]
+    pub id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub operation_id: Uuid,
+
+    #[sea_orm(indexed)]
+    pub work_order_id: Uuid,
+
+    pub kind: TimeEntryKind,
+
+    /// Actor who clocked in; `None` for machine time.
+    #[sea_orm(indexed)]
+    pub worker: Option<String>,
+
+    pub machine_id: Option<i32>,
+
+    pub started_at: DateTime<Utc>,
+
+    pub ended_at: Option<DateTime<Utc>>,
+
+    /// The operation's labor or machine rate when the entry was opened.
+    #[serde(with = "crate::money::amount")]
+    pub rate: Decimal,
+
+    pub recorded_by: String,
+
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "Text")]
+#[serde(rename_all = "snake_case")]
+pub enum TimeEntryKind {
+    #[sea_orm(string_value = "labor")]
+    Labor,
+    #[sea_orm(string_value = "machine")]
+    Machine,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::work_order_operation::Entity",
+        from = "Column::OperationId",
+        to = "super::work_order_operation::Column::Id",
+        on_update = "Cascade",
+        on_delete = "Cascade"
+    )]
+    Operation,
+}
+
+impl Related<super::work_order_operation::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Operation.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,179 @@
+//! Monetary amounts.
+//!
+//! Money is always `rust_decimal::Decimal`, never `f64`, and is serialized as a string
+//! (`"12.34"`) so amounts survive JSON round-trips exactly. For compatibility with
+//! older clients, deserialization also accepts JSON numbers; these are converted via
+//! their shortest decimal representation so `0.1` becomes exactly `0.1`.
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use serde::{de, Deserializer, Serializer};
+use std::{fmt, str::FromStr};
+use validator::ValidationError;
+
+/// Number of decimal places amounts are rounded to when stored or returned.
+pub const CURRENCY_SCALE: u32 = 2;
+
+/// Rounds an amount to currency precision, half away from zero.
+pub fn round_currency(amount: Decimal) -> Decimal {
+    amount.round_dp_with_strategy(CURRENCY_SCALE, RoundingStrategy::MidpointAwayFromZero)
+}
+
+/// Validator for amounts that must be greater than zero.
+pub fn validate_positive(amount: &Decimal) -> Result<(), ValidationError> {
+    if amount.is_sign_positive() && !amount.is_zero() {
+        Ok(())
+    } else {
+        Err(ValidationError::new("amount_must_be_positive"))
+    }
+}
+
+/// Validator for amounts that must not be negative.
+pub fn validate_non_negative(amount: &Decimal) -> Result<(), ValidationError> {
+    if amount.is_sign_negative() && !amount.is_zero() {
+        Err(ValidationError::new("amount_must_not_be_negative"))
+    } else {
+        Ok(())
+    }
+}
+
+struct AmountVisitor;
+
+impl<'de> de::Visitor<'de> for AmountVisitor {
+    type Value = Decimal;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a decimal amount as a string or number")
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Decimal, E> {
+        Decimal::from_str(v.trim()).map_err(|_| E::custom(format!("invalid amount '{}'", v)))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Decimal, E> {
+        Ok(Decimal::from(v))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Decimal, E> {
+        Ok(Decimal::from(v))
+    }
+
+    // Legacy clients send floats. `f64`'s Display is the shortest string that
+    // round-trips, so parsing it avoids picking up binary representation error.
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Decimal, E> {
+        if !v.is_finite() {
+            return Err(E::custom("amount must be finite"));
+        }
+        Decimal::from_str(&v.to_string())
+            .or_else(|_| Decimal::from_scientific(&format!("{:e}", v)))
+            .map_err(|_| E::custom(format!("amount {} is out of range", v)))
+    }
+}
+
+/// `#[serde(with = "crate::money::amount")]` for `Decimal` fields.
+pub mod amount {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Decimal, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Decimal, D::Error> {
+        deserializer.deserialize_any(AmountVisitor)
+    }
+}
+
+/// `#[serde(with = "crate::money::option_amount")]` for `Option<Decimal>` fields.
+pub mod option_amount {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(value: &Option<Decimal>, serializer: S) -> Result<S::Ok, S::Error> {
+        match value {
+            Some(value) => serializer.serialize_str(&value.to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Decimal>, D::Error> {
+        struct OptionVisitor;
+
+        impl<'de> de::Visitor<'de> for OptionVisitor {
+            type Value = Option<Decimal>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an optional decimal amount")
+            }
+
+            fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(None)
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(None)
+            }
+
+            fn visit_some<D: Deserializer<'de>>(self, d: D) -> Result<Self::Value, D::Error> {
+                d.deserialize_any(AmountVisitor).map(Some)
+            }
+        }
+
+        deserializer.deserialize_option(OptionVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Refund {
+        #[serde(with = "amount")]
+        amount: Decimal,
+        #[serde(default, with = "option_amount")]
+        fee: Option<Decimal>,
+    }
+
+    #[test]
+    fn test_serializes_as_string() {
+        let refund = Refund { amount: dec!(19.99), fee: None };
+        assert_eq!(serde_json::to_string(&refund).unwrap(), r#"{"amount":"19.99","fee":null}"#);
+    }
+
+    #[test]
+    fn test_accepts_strings_and_legacy_numbers() {
+        let from_string: Refund = serde_json::from_str(r#"{"amount":"0.10","fee":"1.5"}"#).unwrap();
+        assert_eq!(from_string.amount, dec!(0.10));
+        assert_eq!(from_string.fee, Some(dec!(1.5)));
+
+        let from_float: Refund = serde_json::from_str(r#"{"amount":0.1}"#).unwrap();
+        assert_eq!(from_float.amount, dec!(0.1));
+        assert_eq!(from_float.fee, None);
+
+        let from_int: Refund = serde_json::from_str(r#"{"amount":42}"#).unwrap();
+        assert_eq!(from_int.amount, dec!(42));
+
+        assert!(serde_json::from_str::<Refund>(r#"{"amount":"abc"}"#).is_err());
+    }
+
+    #[test]
+    fn test_no_cent_drift_on_repeated_refunds() {
+        // Ten refunds of 0.10 against 1.00 must leave exactly zero
+        let mut total = dec!(1.00);
+        for _ in 0..10 {
+            let refund: Refund = serde_json::from_str(r#"{"amount":0.1}"#).unwrap();
+            total -= refund.amount;
+        }
+        assert_eq!(total, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_rounding_and_validation() {
+        assert_eq!(round_currency(dec!(2.345)), dec!(2.35));
+        assert_eq!(round_currency(dec!(-2.345)), dec!(-2.35));
+        assert!(validate_positive(&dec!(0.01)).is_ok());
+        assert!(validate_positive(&Decimal::ZERO).is_err());
+        assert!(validate_non_negative(&Decimal::ZERO).is_ok());
+        assert!(validate_non_negative(&dec!(-0.01)).is_err());
+    }
+}
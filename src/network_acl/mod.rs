@@ -0,0 +1,494 @@
+// network_acl/mod.rs
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use ipnet::IpNet;
+use lazy_static::lazy_static;
+use prometheus::IntCounterVec;
+use sea_orm::{DatabaseConnection, DbErr, EntityTrait, QueryFilter, ColumnTrait};
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use crate::auth::Claims;
+use crate::models::network_acl_entry::{self, Entity as NetworkAclEntry};
+
+lazy_static! {
+    static ref NETWORK_ACL_BLOCKED: IntCounterVec =
+        IntCounterVec::new(
+            "network_acl_blocked_total",
+            "Total number of requests rejected by the network ACL",
+            &["reason"]
+        ).expect("metric can be created");
+}
+
+/// Header identifying the tenant a request is made on behalf of.
+pub const TENANT_HEADER: &str = "X-Tenant-ID";
+
+/// A parsed list of single addresses and CIDR ranges.
+#[derive(Debug, Clone, Default)]
+pub struct CidrList {
+    networks: Vec<IpNet>,
+}
+
+impl CidrList {
+    /// Parses entries such as `10.0.0.0/8` or `192.168.1.5`. Malformed entries are
+    /// logged and skipped rather than failing the whole list.
+    pub fn parse<S: AsRef<str>>(entries: &[S]) -> Self {
+        let networks = entries
+            .iter()
+            .filter_map(|entry| {
+                let entry = entry.as_ref().trim();
+                entry
+                    .parse::<IpNet>()
+                    .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+                    .map_err(|_| warn!("Ignoring malformed network ACL entry: {}", entry))
+                    .ok()
+            })
+            .collect();
+        Self { networks }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.networks.is_empty()
+    }
+
+    /// Returns true if any range in the list contains `ip`.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.networks.iter().any(|net| net.contains(&ip))
+    }
+}
+
+/// Network ACL settings, loaded from the `network_acl` section of the config.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct NetworkAclConfig {
+    /// Enables the middleware (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Addresses and ranges that are always rejected.
+    #[serde(default)]
+    pub global_denylist: Vec<String>,
+
+    /// Per-tenant allowlists keyed by the tenant ID of the caller's token.
+    #[serde(default)]
+    pub tenant_allowlists: HashMap<String, Vec<String>>,
+
+    /// Per-API-key allowlists keyed by API key prefix.
+    #[serde(default)]
+    pub api_key_allowlists: HashMap<String, Vec<String>>,
+
+    /// Proxies allowed to report the client address through `X-Forwarded-For`. The
+    /// header is ignored on connections from anywhere else. Also used by abuse
+    /// detection and service-account IP allowlists.
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+
+    /// How often the database denylist is reloaded, in seconds (default: 60).
+    #[serde(default = "default_refresh_interval_secs")]
+    pub refresh_interval_secs: u64,
+}
+
+fn default_refresh_interval_secs() -> u64 {
+    60
+}
+
+/// Outcome of evaluating a request against the ACL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AclDecision {
+    Allow,
+    Deny(DenyReason),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DenyReason {
+    GlobalDenylist,
+    TenantAllowlist,
+    ApiKeyAllowlist,
+    UnknownClientAddress,
+}
+
+impl DenyReason {
+    pub fn code(&self) -> &'static str {
+        match self {
+            DenyReason::GlobalDenylist => "ip_denylisted",
+            DenyReason::TenantAllowlist => "ip_not_allowed_for_tenant",
+            DenyReason::ApiKeyAllowlist => "ip_not_allowed_for_api_key",
+            DenyReason::UnknownClientAddress => "unknown_client_address",
+        }
+    }
+}
+
+#[derive(Default)]
+struct AclRules {
+    global_denylist: CidrList,
+    db_denylist: CidrList,
+    tenant_allowlists: HashMap<String, CidrList>,
+    api_key_allowlists: HashMap<String, CidrList>,
+}
+
+/// Evaluates client addresses against the configured and DB-backed rules.
+pub struct NetworkAcl {
+    enabled: bool,
+    rules: RwLock<AclRules>,
+    trusted_proxies: CidrList,
+}
+
+impl NetworkAcl {
+    pub fn new(config: &NetworkAclConfig) -> Self {
+        let rules = AclRules {
+            global_denylist: CidrList::parse(&config.global_denylist),
+            db_denylist: CidrList::default(),
+            tenant_allowlists: config
+                .tenant_allowlists
+                .iter()
+                .map(|(tenant, list)| (tenant.clone(), CidrList::parse(list)))
+                .collect(),
+            api_key_allowlists: config
+                .api_key_allowlists
+                .iter()
+                .map(|(prefix, list)| (prefix.clone(), CidrList::parse(list)))
+                .collect(),
+        };
+        Self {
+            enabled: config.enabled,
+            rules: RwLock::new(rules),
+            trusted_proxies: CidrList::parse(&config.trusted_proxies),
+        }
+    }
+
+    /// Replaces the DB-sourced denylist with the active rows of `network_acl_entries`.
+    pub async fn refresh_from_db(&self, db: &DatabaseConnection) -> Result<usize, DbErr> {
+        let entries: Vec<String> = NetworkAclEntry::find()
+            .filter(network_acl_entry::Column::IsActive.eq(true))
+            .all(db)
+            .await?
+            .into_iter()
+            .map(|entry| entry.cidr)
+            .collect();
+
+        let count = entries.len();
+        self.rules.write().await.db_denylist = CidrList::parse(&entries);
+        info!(entries = count, "Network ACL denylist refreshed from database");
+        Ok(count)
+    }
+
+    /// Evaluates an address. Denylists win over allowlists; an allowlist only applies
+    /// when one is configured for the tenant or API key.
+    pub async fn evaluate(
+        &self,
+        ip: Option<IpAddr>,
+        tenant_id: Option<&str>,
+        api_key_prefix: Option<&str>,
+    ) -> AclDecision {
+        let rules = self.rules.read().await;
+
+        let ip = match ip {
+            Some(ip) => ip,
+            None => {
+                let restricted = tenant_id.map_or(false, |t| rules.tenant_allowlists.contains_key(t))
+                    || api_key_prefix.map_or(false, |k| rules.api_key_allowlists.contains_key(k));
+                return if restricted {
+                    AclDecision::Deny(DenyReason::UnknownClientAddress)
+                } else {
+                    AclDecision::Allow
+                };
+            }
+        };
+
+        if rules.global_denylist.contains(ip) || rules.db_denylist.contains(ip) {
+            return AclDecision::Deny(DenyReason::GlobalDenylist);
+        }
+
+        if let Some(list) = tenant_id.and_then(|t| rules.tenant_allowlists.get(t)) {
+            if !list.contains(ip) {
+                return AclDecision::Deny(DenyReason::TenantAllowlist);
+            }
+        }
+
+        if let Some(list) = api_key_prefix.and_then(|k| rules.api_key_allowlists.get(k)) {
+            if !list.contains(ip) {
+                return AclDecision::Deny(DenyReason::ApiKeyAllowlist);
+            }
+        }
+
+        AclDecision::Allow
+    }
+
+    fn client_ip<B>(&self, req: &Request<B>) -> Option<IpAddr> {
+        client_ip(req, &self.trusted_proxies)
+    }
+}
+
+/// The address of the connected socket, when the server records it.
+pub(crate) fn socket_ip<B>(req: &Request<B>) -> Option<IpAddr> {
+    req.extensions()
+        .get::<axum::extract::ConnectInfo<SocketAddr>>()
+        .map(|info| info.0.ip())
+}
+
+/// Determines the client IP. `X-Forwarded-For` is only believed when the socket peer is
+/// a trusted proxy, and then the rightmost hop not added by a trusted proxy is the
+/// client; hops further left are whatever the client chose to send.
+pub fn client_ip<B>(req: &Request<B>, trusted_proxies: &CidrList) -> Option<IpAddr> {
+    forwarded_client_ip(socket_ip(req)?, req.headers(), trusted_proxies)
+}
+
+/// [`client_ip`] for handlers that have the peer address and headers rather than the request.
+/// Hops that are not addresses are skipped rather than failing the lookup, which would let
+/// a client hide from the denylist and blocklist by sending one.
+pub fn forwarded_client_ip(peer: IpAddr, headers: &HeaderMap, trusted_proxies: &CidrList) -> Option<IpAddr> {
+    if !trusted_proxies.contains(peer) {
+        return Some(peer);
+    }
+    let forwarded: Vec<IpAddr> = headers
+        .get_all("X-Forwarded-For")
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(','))
+        .filter_map(|hop| hop.trim().parse().ok())
+        .collect();
+    Some(
+        forwarded
+            .into_iter()
+            .rev()
+            .find(|hop| !trusted_proxies.contains(*hop))
+            .unwrap_or(peer),
+    )
+}
+
+/// Extracts the non-secret prefix from an `ssk_<prefix>_<secret>` API key header.
+pub(crate) fn api_key_prefix<B>(req: &Request<B>) -> Option<&str> {
+    api_key_prefix_of(req.headers())
+}
+
+/// [`api_key_prefix`] over bare headers, for handlers.
+pub(crate) fn api_key_prefix_of(headers: &HeaderMap) -> Option<&str> {
+    headers
+        .get(crate::auth::service_accounts::API_KEY_HEADER)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|key| key.split('_').nth(1))
+}
+
+/// Reloads the database denylist at `interval`, so entries added or deactivated take
+/// effect without a restart.
+pub fn spawn_refresh(acl: Arc<NetworkAcl>, db: Arc<DatabaseConnection>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        // The first tick completes immediately and startup has just loaded the list
+        ticker.tick().await;
+        loop {
+            ticker.tick().await;
+            if let Err(e) = acl.refresh_from_db(&db).await {
+                warn!(error = %e, "Failed to refresh network ACL denylist; keeping the previous one");
+            }
+        }
+    });
+}
+
+fn blocked<B>(req: &Request<B>, ip: Option<IpAddr>, tenant_id: Option<&str>, reason: DenyReason) -> Response {
+    NETWORK_ACL_BLOCKED.with_label_values(&[reason.code()]).inc();
+    warn!(
+        ip = ?ip,
+        tenant_id = ?tenant_id,
+        reason = reason.code(),
+        path = %req.uri().path(),
+        "Request blocked by network ACL"
+    );
+    (
+        StatusCode::FORBIDDEN,
+        Json(json!({
+            "error": "Forbidden",
+            "code": reason.code(),
+            "details": "Requests from this network address are not permitted",
+        })),
+    )
+        .into_response()
+}
+
+/// Middleware rejecting requests whose client address is denylisted or outside its API
+/// key's allowlist. Must be layered outside of authentication so blocked clients never
+/// reach token checks; tenant allowlists are applied by [`tenant_acl_middleware`].
+pub async fn network_acl_middleware<B>(
+    State(acl): State<Arc<NetworkAcl>>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let ip = acl.client_ip(&req);
+    match acl.evaluate(ip, None, api_key_prefix(&req)).await {
+        AclDecision::Allow => next.run(req).await,
+        AclDecision::Deny(reason) => blocked(&req, ip, None, reason),
+    }
+}
+
+/// Middleware applying tenant allowlists. Layered inside authentication, since the
+/// tenant comes from the verified claims rather than anything the client can set.
+pub async fn tenant_acl_middleware<B>(
+    State(acl): State<Arc<NetworkAcl>>,
+    req: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let tenant_id = req.extensions().get::<Claims>().and_then(|claims| claims.tenant_id.clone());
+    let Some(tenant_id) = tenant_id.filter(|_| acl.enabled) else {
+        return next.run(req).await;
+    };
+    let ip = acl.client_ip(&req);
+    match acl.evaluate(ip, Some(&tenant_id), None).await {
+        AclDecision::Allow => next.run(req).await,
+        AclDecision::Deny(reason) => blocked(&req, ip, Some(&tenant_id), reason),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{self, AppState, AuthConfig};
+    use axum::{body::Body, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    fn acl() -> NetworkAcl {
+        let mut tenant_allowlists = HashMap::new();
+        tenant_allowlists.insert("acme".to_string(), vec!["10.0.0.0/8".to_string()]);
+        let mut api_key_allowlists = HashMap::new();
+        api_key_allowlists.insert("abcd1234".to_string(), vec!["203.0.113.7".to_string()]);
+
+        NetworkAcl::new(&NetworkAclConfig {
+            enabled: true,
+            global_denylist: vec!["198.51.100.0/24".to_string(), "not-an-ip".to_string()],
+            tenant_allowlists,
+            api_key_allowlists,
+            trusted_proxies: vec!["10.0.0.1".to_string()],
+            refresh_interval_secs: 60,
+        })
+    }
+
+    #[test]
+    fn test_cidr_list_parsing() {
+        let list = CidrList::parse(&["10.0.0.0/8", "2001:db8::/32", "192.168.1.1", "garbage"]);
+        assert!(list.contains("10.20.30.40".parse().unwrap()));
+        assert!(list.contains("2001:db8::1".parse().unwrap()));
+        assert!(list.contains("192.168.1.1".parse().unwrap()));
+        assert!(!list.contains("192.168.1.2".parse().unwrap()));
+    }
+
+    fn request(peer: &str, forwarded_for: Option<&str>) -> Request<()> {
+        let mut builder = Request::builder();
+        if let Some(forwarded_for) = forwarded_for {
+            builder = builder.header("X-Forwarded-For", forwarded_for);
+        }
+        let mut req = builder.body(()).unwrap();
+        let addr = SocketAddr::new(peer.parse().unwrap(), 443);
+        req.extensions_mut().insert(axum::extract::ConnectInfo(addr));
+        req
+    }
+
+    #[test]
+    fn test_forwarded_for_only_trusted_from_proxies() {
+        let proxies = CidrList::parse(&["10.0.0.0/8"]);
+        let ip = |s: &str| Some(s.parse::<IpAddr>().unwrap());
+
+        // Direct clients cannot claim another address
+        assert_eq!(client_ip(&request("203.0.113.7", Some("10.1.1.1")), &proxies), ip("203.0.113.7"));
+        // Behind the proxy, the rightmost untrusted hop is the client, whatever it prepended
+        assert_eq!(
+            client_ip(&request("10.0.0.1", Some("198.51.100.1, 203.0.113.7, 10.0.0.2")), &proxies),
+            ip("203.0.113.7")
+        );
+        assert_eq!(client_ip(&request("10.0.0.1", None), &proxies), ip("10.0.0.1"));
+        // Unparseable hops are skipped instead of making the address unknown
+        assert_eq!(client_ip(&request("10.0.0.1", Some("garbage")), &proxies), ip("10.0.0.1"));
+        assert_eq!(
+            client_ip(&request("10.0.0.1", Some("198.51.100.1, garbage, 203.0.113.7")), &proxies),
+            ip("203.0.113.7")
+        );
+        assert_eq!(client_ip(&Request::new(()), &proxies), None);
+    }
+
+    #[tokio::test]
+    async fn test_global_denylist_wins() {
+        let acl = acl();
+        let decision = acl.evaluate(Some("198.51.100.20".parse().unwrap()), Some("acme"), None).await;
+        assert_eq!(decision, AclDecision::Deny(DenyReason::GlobalDenylist));
+    }
+
+    #[tokio::test]
+    async fn test_tenant_allowlist() {
+        let acl = acl();
+        assert_eq!(acl.evaluate(Some("10.1.1.1".parse().unwrap()), Some("acme"), None).await, AclDecision::Allow);
+        assert_eq!(
+            acl.evaluate(Some("172.16.0.1".parse().unwrap()), Some("acme"), None).await,
+            AclDecision::Deny(DenyReason::TenantAllowlist)
+        );
+        // Tenants without an allowlist are unrestricted
+        assert_eq!(acl.evaluate(Some("172.16.0.1".parse().unwrap()), Some("other"), None).await, AclDecision::Allow);
+    }
+
+    #[tokio::test]
+    async fn test_api_key_allowlist() {
+        let acl = acl();
+        assert_eq!(acl.evaluate(Some("203.0.113.7".parse().unwrap()), None, Some("abcd1234")).await, AclDecision::Allow);
+        assert_eq!(
+            acl.evaluate(Some("203.0.113.8".parse().unwrap()), None, Some("abcd1234")).await,
+            AclDecision::Deny(DenyReason::ApiKeyAllowlist)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_unknown_address_only_blocked_when_restricted() {
+        let acl = acl();
+        assert_eq!(acl.evaluate(None, None, None).await, AclDecision::Allow);
+        assert_eq!(
+            acl.evaluate(None, Some("acme"), None).await,
+            AclDecision::Deny(DenyReason::UnknownClientAddress)
+        );
+    }
+
+    /// Status of a request from `peer` through auth and the tenant ACL, with a token of
+    /// `tenant_id` and an `X-Tenant-ID` header of `header_tenant`.
+    async fn tenant_request(peer: &str, tenant_id: Option<&str>, header_tenant: Option<&str>) -> StatusCode {
+        let config = AuthConfig {
+            secret: "test_secret".to_string(),
+            issuer: "stateset-api".to_string(),
+            audience: "stateset-api".to_string(),
+            allowed_roles: ["user".to_string()].into_iter().collect(),
+            token_expiration: 3600,
+            secrets: Default::default(),
+        };
+        let token = auth::generate_token("user-7", "user", None, tenant_id, &config).unwrap();
+        let app = Router::new()
+            .route("/", get(|| async { "ok" }))
+            .layer(middleware::from_fn_with_state(Arc::new(acl()), tenant_acl_middleware))
+            .layer(middleware::from_fn_with_state(AppState { auth_config: Arc::new(config) }, auth::auth_middleware));
+
+        let mut builder = Request::get("/").header("Authorization", format!("Bearer {}", token));
+        if let Some(header_tenant) = header_tenant {
+            builder = builder.header(TENANT_HEADER, header_tenant);
+        }
+        let mut req = builder.body(Body::empty()).unwrap();
+        req.extensions_mut()
+            .insert(axum::extract::ConnectInfo(SocketAddr::new(peer.parse().unwrap(), 443)));
+        app.oneshot(req).await.unwrap().status()
+    }
+
+    #[tokio::test]
+    async fn test_tenant_allowlist_follows_the_token_tenant() {
+        assert_eq!(tenant_request("10.1.2.3", Some("acme"), None).await, StatusCode::OK);
+        assert_eq!(tenant_request("172.16.0.1", Some("acme"), None).await, StatusCode::FORBIDDEN);
+        // Naming another tenant in a header neither escapes the allowlist nor applies one
+        assert_eq!(tenant_request("172.16.0.1", Some("acme"), Some("other")).await, StatusCode::FORBIDDEN);
+        assert_eq!(tenant_request("172.16.0.1", Some("other"), Some("acme")).await, StatusCode::OK);
+        assert_eq!(tenant_request("172.16.0.1", None, Some("acme")).await, StatusCode::OK);
+    }
+}
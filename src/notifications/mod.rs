@@ -9,6 +9,10 @@ use tokio::sync::Mutex;
 use slog::{info, Logger};
 use tracing::{instrument, error};
 
+use crate::i18n::{self, Locale};
+use crate::models::subscription;
+use crate::shipment_sla::ShipmentSla;
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Notification {
     pub id: Uuid,
@@ -25,6 +29,7 @@ pub enum NotificationType {
     ShipmentUpdate,
     InventoryAlert,
     SystemMessage,
+    Billing,
 }
 
 #[derive(Debug, Error)]
@@ -254,126 +259,122 @@ impl NotificationService for RedisNotificationService {
 
 // Utility functions for creating specific types of notifications.
 
-/// Creates an order status notification for a user.
-pub fn create_order_status_notification(user_id: i32, order_id: String, status: String) -> Notification {
+/// Creates an order status notification for a user, in the user's locale.
+pub fn create_order_status_notification(user_id: i32, order_id: String, status: String, locale: Locale) -> Notification {
     Notification {
         id: Uuid::new_v4(),
         user_id,
-        message: format!("Your order {} status has been updated to: {}", order_id, status),
+        message: i18n::t(
+            locale,
+            "notifications.order_status_updated",
+            &[("order_id", &order_id), ("status", &status)],
+        ),
         notification_type: NotificationType::OrderStatus,
         read: false,
         created_at: Utc::now(),
     }
 }
 
-/// Creates a shipment update notification for a user.
-pub fn create_shipment_update_notification(user_id: i32, shipment_id: String, update: String) -> Notification {
+/// Creates a shipment update notification for a user, in the user's locale.
+pub fn create_shipment_update_notification(user_id: i32, shipment_id: String, update: String, locale: Locale) -> Notification {
     Notification {
         id: Uuid::new_v4(),
         user_id,
-        message: format!("Shipment {} update: {}", shipment_id, update),
+        message: i18n::t(
+            locale,
+            "notifications.shipment_update",
+            &[("shipment_id", &shipment_id), ("update", &update)],
+        ),
         notification_type: NotificationType::ShipmentUpdate,
         read: false,
         created_at: Utc::now(),
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use mockall::predicate::*;
-    use mockall::mock;
-    use redis::AsyncCommands;
-    use tokio::sync::Mutex as TokioMutex;
-    use std::collections::HashMap;
-    use uuid::Uuid;
-    use slog::Logger;
-    use slog::Drain;
-    use serde_json::json;
-
-    // Mock Redis for testing purposes
-    struct MockRedis {
-        data: Arc<TokioMutex<HashMap<String, Vec<String>>>>,
+/// Creates a late-delivery alert for an overdue in-transit shipment, in the given locale.
+pub fn create_shipment_late_notification(user_id: i32, shipment: &ShipmentSla, locale: Locale) -> Notification {
+    Notification {
+        id: Uuid::new_v4(),
+        user_id,
+        message: i18n::t(
+            locale,
+            "notifications.shipment_late",
+            &[
+                ("shipment_id", &shipment.shipment_id.to_string()),
+                ("carrier", &format!("{:?}", shipment.carrier)),
+                ("tracking_number", &shipment.tracking_number),
+                ("expected", &i18n::format_date(locale, shipment.expected_delivery.date_naive())),
+            ],
+        ),
+        notification_type: NotificationType::ShipmentUpdate,
+        read: false,
+        created_at: Utc::now(),
     }
+}
 
-    impl MockRedis {
-        fn new() -> Self {
-            Self {
-                data: Arc::new(TokioMutex::new(HashMap::new())),
-            }
-        }
+/// Creates a low-stock alert for a SKU in a warehouse, in the given locale.
+pub fn create_low_stock_notification(
+    user_id: i32,
+    sku: &str,
+    warehouse: i32,
+    sellable: i32,
+    threshold: i32,
+    locale: Locale,
+) -> Notification {
+    Notification {
+        id: Uuid::new_v4(),
+        user_id,
+        message: i18n::t(
+            locale,
+            "notifications.low_stock",
+            &[
+                ("sku", sku),
+                ("warehouse", &warehouse.to_string()),
+                ("sellable", &sellable.to_string()),
+                ("threshold", &threshold.to_string()),
+            ],
+        ),
+        notification_type: NotificationType::InventoryAlert,
+        read: false,
+        created_at: Utc::now(),
     }
+}
 
-    #[async_trait]
-    impl redis::AsyncCommands for MockRedis {
-        async fn get<T>(&mut self, key: &str) -> redis::RedisResult<T>
-        where
-            T: redis::FromRedisValue,
-        {
-            let data = self.data.lock().await;
-            if let Some(values) = data.get(key) {
-                if let Some(first) = values.first() {
-                    let value = serde_json::from_str(first).map_err(|e| {
-                        redis::RedisError::from((redis::ErrorKind::TypeError, "Failed to deserialize", e.to_string()))
-                    })?;
-                    Ok(value)
-                } else {
-                    Ok(serde_json::from_str("null").unwrap())
-                }
-            } else {
-                Ok(serde_json::from_str("null").unwrap())
-            }
-        }
-
-        async fn set<K: redis::ToRedisArgs, V: redis::ToRedisArgs>(&mut self, key: K, value: V) -> redis::RedisResult<()> {
-            let mut data = self.data.lock().await;
-            let key_str = std::str::from_utf8(&key.to_redis_args()[0]).unwrap().to_string();
-            let value_str = std::str::from_utf8(&value.to_redis_args()[0]).unwrap().to_string();
-            data.insert(key_str, vec![value_str]);
-            Ok(())
-        }
-
-        async fn lrange<K: redis::ToRedisArgs, V: redis::FromRedisValue>(&mut self, key: K, start: isize, stop: isize) -> redis::RedisResult<Vec<V>> {
-            let data = self.data.lock().await;
-            let key_str = std::str::from_utf8(&key.to_redis_args()[0]).unwrap();
-            if let Some(values) = data.get(key_str) {
-                let slice = if stop >= 0 {
-                    &values[start as usize..=stop as usize]
-                } else {
-                    &values[start as usize..]
-                };
-                let deserialized = slice.iter().map(|v| serde_json::from_str(v)).collect::<Result<Vec<V>, _>>()?;
-                Ok(deserialized)
-            } else {
-                Ok(Vec::new())
-            }
-        }
-
-        async fn lpush<K: redis::ToRedisArgs, V: redis::ToRedisArgs>(&mut self, key: K, value: V) -> redis::RedisResult<i64> {
-            let mut data = self.data.lock().await;
-            let key_str = std::str::from_utf8(&key.to_redis_args()[0]).unwrap().to_string();
-            let value_str = std::str::from_utf8(&value.to_redis_args()[0]).unwrap().to_string();
-            let entry = data.entry(key_str).or_insert_with(Vec::new);
-            entry.insert(0, value_str);
-            Ok(entry.len() as i64)
-        }
-
-        async fn del<K: redis::ToRedisArgs>(&mut self, key: K) -> redis::RedisResult<i32> {
-            let mut data = self.data.lock().await;
-            let key_str = std::str::from_utf8(&key.to_redis_args()[0]).unwrap().to_string();
-            let removed = data.remove(&key_str).map(|_| 1).unwrap_or(0);
-            Ok(removed)
-        }
-
-        async fn eval<K: redis::ToRedisArgs, KEYS: redis::ToRedisArgs, ARGS: redis::ToRedisArgs>(&mut self, script: K, keys: &[KEYS], args: &[ARGS]) -> redis::RedisResult<usize> {
-            // For simplicity, we'll mock the Lua script execution related to rate limiting
-            // Since this is a notification service, we don't need to implement eval
-            Ok(0)
-        }
-
-        // Implement other required methods as no-ops or default
-        // ...
+/// Creates a billing notice about a subscription's failed payment from a message template,
+/// in the given locale. Templates may use `{plan}`, `{amount}` and `{retry_date}`.
+pub fn create_billing_notification(
+    user_id: i32,
+    template: &str,
+    subscription: &subscription::Model,
+    next_retry: Option<DateTime<Utc>>,
+    locale: Locale,
+) -> Notification {
+    let retry_date = next_retry
+        .map(|at| i18n::format_date(locale, at.date_naive()))
+        .unwrap_or_default();
+    Notification {
+        id: Uuid::new_v4(),
+        user_id,
+        message: i18n::t(
+            locale,
+            template,
+            &[
+                ("plan", &subscription.plan),
+                ("amount", &i18n::format_currency(locale, subscription.amount, &subscription.currency)),
+                ("retry_date", &retry_date),
+            ],
+        ),
+        notification_type: NotificationType::Billing,
+        read: false,
+        created_at: Utc::now(),
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slog::Drain;
+    use std::collections::HashMap;
 
     #[tokio::test]
     async fn test_send_notification() {
@@ -382,9 +383,7 @@ mod tests {
         let drain = slog_term::CompactFormat::new(decorator).build().fuse();
         let logger = Logger::root(drain, slog::o!());
 
-        // Setup mock Redis client
-        let mock_redis = MockRedis::new();
-        let client = redis::Client::open("redis://127.0.0.1/").unwrap(); // URL is irrelevant for the mock
+        let client = redis::Client::open("redis://127.0.0.1/").unwrap();
 
         // Initialize the service
         let service = RedisNotificationService {
@@ -394,7 +393,7 @@ mod tests {
         };
 
         // Create a notification
-        let notification = create_order_status_notification(1, "ORDER123".to_string(), "Shipped".to_string());
+        let notification = create_order_status_notification(1, "ORDER123".to_string(), "Shipped".to_string(), Locale::EnUs);
 
         // Send the notification
         let result = service.send_notification(notification.clone()).await;
@@ -413,9 +412,7 @@ mod tests {
         let drain = slog_term::CompactFormat::new(decorator).build().fuse();
         let logger = Logger::root(drain, slog::o!());
 
-        // Setup mock Redis client
-        let mock_redis = MockRedis::new();
-        let client = redis::Client::open("redis://127.0.0.1/").unwrap(); // URL is irrelevant for the mock
+        let client = redis::Client::open("redis://127.0.0.1/").unwrap();
 
         // Initialize the service
         let service = RedisNotificationService {
@@ -425,7 +422,7 @@ mod tests {
         };
 
         // Create and send a notification
-        let notification = create_order_status_notification(1, "ORDER123".to_string(), "Shipped".to_string());
+        let notification = create_order_status_notification(1, "ORDER123".to_string(), "Shipped".to_string(), Locale::EnUs);
         service.send_notification(notification.clone()).await.unwrap();
 
         // Mark the notification as read
@@ -445,9 +442,7 @@ mod tests {
         let drain = slog_term::CompactFormat::new(decorator).build().fuse();
         let logger = Logger::root(drain, slog::o!());
 
-        // Setup mock Redis client
-        let mock_redis = MockRedis::new();
-        let client = redis::Client::open("redis://127.0.0.1/").unwrap(); // URL is irrelevant for the mock
+        let client = redis::Client::open("redis://127.0.0.1/").unwrap();
 
         // Initialize the service
         let service = RedisNotificationService {
@@ -457,7 +452,7 @@ mod tests {
         };
 
         // Create and send a notification
-        let notification = create_order_status_notification(1, "ORDER123".to_string(), "Shipped".to_string());
+        let notification = create_order_status_notification(1, "ORDER123".to_string(), "Shipped".to_string(), Locale::EnUs);
         service.send_notification(notification.clone()).await.unwrap();
 
         // Delete the notification
@@ -0,0 +1,867 @@
+// partitioning/mod.rs
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{Datelike, Days, Months, NaiveDate, Utc};
+use lazy_static::lazy_static;
+use prometheus::IntGauge;
+use sea_orm::{DatabaseConnection, DbErr, FromQueryResult};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio::time::MissedTickBehavior;
+use tracing::{error, warn};
+
+use crate::db::dialect;
+
+lazy_static! {
+    static ref PARTITION_CHANGES_DUE: IntGauge =
+        IntGauge::new(
+            "partition_changes_due",
+            "Partition changes due that no migration has made yet"
+        ).expect("metric can be created");
+}
+
+#[derive(Error, Debug)]
+pub enum PartitionError {
+    #[error("Invalid partitioned table '{table}': {reason}")]
+    InvalidTable { table: String, reason: String },
+
+    #[error("The partitioned copy of '{table}' misses {missing} rows; finish its backfill first")]
+    BackfillIncomplete { table: String, missing: i64 },
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for PartitionError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            PartitionError::InvalidTable { .. } => (StatusCode::SERVICE_UNAVAILABLE, "partitioning_misconfigured"),
+            PartitionError::BackfillIncomplete { .. } => (StatusCode::CONFLICT, "partition_backfill_incomplete"),
+            PartitionError::Database(e) => {
+                error!("Partition query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "partition_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// The time range each partition covers.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PartitionInterval {
+    Daily,
+    /// Weeks start on Monday.
+    Weekly,
+    #[default]
+    Monthly,
+}
+
+/// A table range-partitioned on a timestamp column.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PartitionedTable {
+    pub table: String,
+
+    /// Timestamp column the table is partitioned on (default: `created_at`).
+    #[serde(default = "default_column")]
+    pub column: String,
+
+    #[serde(default)]
+    pub interval: PartitionInterval,
+
+    /// Partitions kept ready ahead of the current one (default: 3).
+    #[serde(default = "default_premake")]
+    pub premake: u32,
+
+    /// Partitions older than this many intervals are detached, to be archived or dropped
+    /// by an operator. Kept attached when unset.
+    #[serde(default)]
+    pub detach_after: Option<u32>,
+}
+
+fn default_column() -> String {
+    "created_at".to_string()
+}
+
+fn default_premake() -> u32 {
+    3
+}
+
+/// Partitioning settings, loaded from the `partitioning` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PartitioningConfig {
+    /// Checks on a schedule whether partition maintenance is due (default: true). The
+    /// `partition_high_volume_tables` migration creates the partitioned copies of the
+    /// tables, which replace them once `stateset-cli partitions swap` ships; once the
+    /// partitions made by migrations run out, rows land in the default partition.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Seconds between maintenance checks (default: daily).
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+
+    #[serde(default = "default_tables")]
+    pub tables: Vec<PartitionedTable>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    24 * 60 * 60
+}
+
+/// The tables the `partition_high_volume_tables` migration makes partitioned copies of.
+fn default_tables() -> Vec<PartitionedTable> {
+    [
+        ("orders", "created_date"),
+        ("order_items", "created_at"),
+        ("inventory_transactions", "created_at"),
+        ("outbox", "created_at"),
+        ("audit_logs", "created_at"),
+    ]
+        .into_iter()
+        .map(|(table, column)| PartitionedTable {
+            table: table.to_string(),
+            column: column.to_string(),
+            interval: PartitionInterval::Monthly,
+            premake: default_premake(),
+            detach_after: None,
+        })
+        .collect()
+}
+
+impl Default for PartitioningConfig {
+    fn default() -> Self {
+        Self { enabled: default_enabled(), interval_secs: default_interval_secs(), tables: default_tables() }
+    }
+}
+
+/// Only plain SQL identifiers are accepted so table config can never inject SQL. Table
+/// names leave room for the `_partitioned` suffix within Postgres' 63 characters.
+fn valid_identifier(ident: &str, max_len: usize) -> bool {
+    !ident.is_empty()
+        && ident.len() <= max_len
+        && ident.chars().next().map_or(false, |c| c.is_ascii_lowercase() || c == '_')
+        && ident.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+impl PartitionedTable {
+    pub fn validate(&self) -> Result<(), PartitionError> {
+        let invalid = |reason: &str| PartitionError::InvalidTable { table: self.table.clone(), reason: reason.to_string() };
+        if !valid_identifier(&self.table, 51) {
+            return Err(invalid("table is not a valid identifier of at most 51 characters"));
+        }
+        if !valid_identifier(&self.column, 63) {
+            return Err(invalid("column is not a valid identifier"));
+        }
+        if self.detach_after == Some(0) {
+            return Err(invalid("detach_after must be at least 1"));
+        }
+        Ok(())
+    }
+
+    fn default_partition(&self) -> String {
+        format!("{}_default", self.table)
+    }
+
+    /// The partitioned copy the table is swapped for, filled by the copy trigger and
+    /// [`PartitionManager::backfill`].
+    pub fn partitioned_copy(&self) -> String {
+        format!("{}_partitioned", self.table)
+    }
+
+    /// What the unpartitioned table is renamed to by the swap.
+    fn legacy(&self) -> String {
+        format!("{}_legacy", self.table)
+    }
+}
+
+/// Start of the interval containing `date`.
+pub fn period_start(date: NaiveDate, interval: PartitionInterval) -> NaiveDate {
+    match interval {
+        PartitionInterval::Daily => date,
+        PartitionInterval::Weekly => date - Days::new(date.weekday().num_days_from_monday() as u64),
+        PartitionInterval::Monthly => date.with_day(1).expect("first of the month is valid"),
+    }
+}
+
+/// Moves a period start by `periods` intervals, backwards when negative.
+pub fn shift_period(start: NaiveDate, interval: PartitionInterval, periods: i64) -> NaiveDate {
+    let forward = periods >= 0;
+    let n = periods.unsigned_abs();
+    let shifted = match interval {
+        PartitionInterval::Daily if forward => start.checked_add_days(Days::new(n)),
+        PartitionInterval::Daily => start.checked_sub_days(Days::new(n)),
+        PartitionInterval::Weekly if forward => start.checked_add_days(Days::new(n * 7)),
+        PartitionInterval::Weekly => start.checked_sub_days(Days::new(n * 7)),
+        PartitionInterval::Monthly if forward => start.checked_add_months(Months::new(n as u32)),
+        PartitionInterval::Monthly => start.checked_sub_months(Months::new(n as u32)),
+    };
+    shifted.expect("partition bounds stay within the calendar")
+}
+
+pub fn partition_name(table: &str, start: NaiveDate, interval: PartitionInterval) -> String {
+    match interval {
+        PartitionInterval::Monthly => format!("{}_p{}", table, start.format("%Y%m")),
+        PartitionInterval::Daily | PartitionInterval::Weekly => format!("{}_p{}", table, start.format("%Y%m%d")),
+    }
+}
+
+/// Lower and upper bound of a range partition, from `pg_get_expr(relpartbound, ..)`:
+/// `FOR VALUES FROM ('2024-05-01 00:00:00+00') TO ('2024-06-01 00:00:00+00')`.
+/// `None` for the default partition and `MINVALUE`/`MAXVALUE` bounds.
+pub fn parse_bound(bound: &str) -> Option<(NaiveDate, NaiveDate)> {
+    let rest = bound.strip_prefix("FOR VALUES FROM ('")?;
+    let (from, rest) = rest.split_once("')")?;
+    let to = rest.strip_prefix(" TO ('")?.split_once("')")?.0;
+    let date = |value: &str| NaiveDate::parse_from_str(value.get(..10)?, "%Y-%m-%d").ok();
+    Some((date(from)?, date(to)?))
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum PartitionChange {
+    Create { name: String, from: NaiveDate, to: NaiveDate },
+    Detach { name: String },
+}
+
+/// What maintenance should do to `spec` given its attached partitions: create the current
+/// and the next `premake` partitions, and detach partitions past `detach_after`.
+pub fn plan(spec: &PartitionedTable, today: NaiveDate, existing: &[PartitionInfo]) -> Vec<PartitionChange> {
+    let current = period_start(today, spec.interval);
+    let mut changes = Vec::new();
+    for offset in 0..=spec.premake as i64 {
+        let from = shift_period(current, spec.interval, offset);
+        let to = shift_period(from, spec.interval, 1);
+        let covered = existing.iter().any(|p| p.from.zip(p.to).map_or(false, |(f, t)| f < to && from < t));
+        if !covered {
+            changes.push(PartitionChange::Create { name: partition_name(&spec.table, from, spec.interval), from, to });
+        }
+    }
+    if let Some(keep) = spec.detach_after {
+        let cutoff = shift_period(current, spec.interval, -(keep as i64));
+        for partition in existing {
+            if partition.to.map_or(false, |to| to <= cutoff) {
+                changes.push(PartitionChange::Detach { name: partition.name.clone() });
+            }
+        }
+    }
+    changes
+}
+
+/// One backfill batch: copies the `batch` rows following `after` in id order into the
+/// partitioned copy, returning the last id and the number of rows it went through. The
+/// rows are share-locked while they are copied, so a concurrent update or delete waits
+/// for the batch and its copy trigger then finds the row in the copy.
+pub fn backfill_sql(spec: &PartitionedTable, after: Option<&str>, batch: u32) -> String {
+    // The id is compared as a literal so it casts to whatever type the key has
+    let after = after.map(|id| format!("WHERE id > '{}' ", id.replace('\'', "''"))).unwrap_or_default();
+    format!(
+        "WITH batch AS (SELECT * FROM {t} {after}ORDER BY id LIMIT {n} FOR SHARE), \
+         inserted AS (INSERT INTO {c} SELECT * FROM batch ON CONFLICT DO NOTHING) \
+         SELECT (SELECT id::text FROM batch ORDER BY id DESC LIMIT 1) AS last_id, \
+         (SELECT COUNT(*) FROM batch) AS copied",
+        t = spec.table,
+        c = spec.partitioned_copy(),
+        after = after,
+        n = batch
+    )
+}
+
+/// A single-column foreign key from or into a table being swapped for its partitioned
+/// copy. Such keys cannot carry over, as nothing can reference `id` alone once the key
+/// includes the partition column, so the swap replaces them with trigger checks.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ForeignKey {
+    pub name: String,
+    pub referencing: String,
+    pub referencing_column: String,
+    pub referenced: String,
+    pub referenced_column: String,
+    /// `ON DELETE CASCADE`; anything else is kept as a restriction.
+    pub cascade: bool,
+}
+
+/// Trigger names are limited to 63 characters like every identifier.
+fn trigger_name(constraint: &str, suffix: &str) -> String {
+    format!("{}_{}", &constraint[..constraint.len().min(63 - suffix.len() - 1)], suffix)
+}
+
+/// The contract migration swapping each table for its backfilled partitioned copy, in
+/// one transaction. The old table is kept as `<table>_legacy` for an operator to drop.
+/// Each foreign key touching a swapped table is dropped together with adding the trigger
+/// checks standing in for it (`partition_reference_check` and
+/// `partition_referenced_check` from the `partition_high_volume_tables` migration), so
+/// there is no moment without the check.
+pub fn swap_migration_sql(tables: &[PartitionedTable], foreign_keys: &[ForeignKey]) -> String {
+    let swapped = |name: &str| tables.iter().find(|spec| spec.table == name);
+    let mut locked = Vec::new();
+    for spec in tables {
+        locked.push(spec.table.clone());
+        locked.push(spec.partitioned_copy());
+    }
+    let mut statements = vec![format!("LOCK TABLE {} IN ACCESS EXCLUSIVE MODE", locked.join(", "))];
+    for spec in tables {
+        statements.push(format!("ALTER TABLE {} RENAME TO {}", spec.table, spec.legacy()));
+        statements.push(format!("ALTER TABLE {} RENAME TO {}", spec.partitioned_copy(), spec.table));
+        statements.push(format!("DROP TRIGGER partition_copy ON {}", spec.legacy()));
+        statements.push(format!("ALTER SEQUENCE IF EXISTS {t}_id_seq OWNED BY {t}.id", t = spec.table));
+    }
+    for fk in foreign_keys {
+        // The constraint stayed on the old table through the rename
+        let holder = swapped(&fk.referencing).map_or_else(|| fk.referencing.clone(), PartitionedTable::legacy);
+        statements.push(format!("ALTER TABLE {} DROP CONSTRAINT {}", holder, fk.name));
+        statements.push(format!(
+            "CREATE TRIGGER {n} AFTER INSERT OR UPDATE OF {rc} ON {r} FOR EACH ROW \
+             EXECUTE FUNCTION partition_reference_check('{p}', '{pc}', '{rc}')",
+            n = trigger_name(&fk.name, "check"),
+            r = fk.referencing,
+            rc = fk.referencing_column,
+            p = fk.referenced,
+            pc = fk.referenced_column,
+        ));
+        statements.push(format!(
+            "CREATE TRIGGER {n} AFTER UPDATE OF {pc} OR DELETE ON {p} FOR EACH ROW \
+             EXECUTE FUNCTION partition_referenced_check('{r}', '{rc}', '{pc}', '{action}')",
+            n = trigger_name(&fk.name, "referenced"),
+            r = fk.referencing,
+            rc = fk.referencing_column,
+            p = fk.referenced,
+            pc = fk.referenced_column,
+            action = if fk.cascade { "cascade" } else { "restrict" },
+        ));
+    }
+
+    let mut sql = String::from(
+        "-- phase: contract\n\
+         -- Swaps the tables below for their backfilled partitioned copies and replaces the foreign\n\
+         -- keys touching them with trigger checks. The renames only change the catalog, so the\n\
+         -- exclusive locks are brief; the old tables stay behind as `<table>_legacy`.\n\
+         -- safety: allow rename\n\n\
+         SET lock_timeout = '5s';\n",
+    );
+    for statement in statements {
+        sql.push('\n');
+        sql.push_str(&statement);
+        sql.push_str(";\n");
+    }
+    sql
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PartitionInfo {
+    pub name: String,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TableStatus {
+    pub table: String,
+    pub partitioned: bool,
+    /// The partitioned copy exists and awaits its backfill and swap; `partitions` are
+    /// the copy's.
+    pub swap_pending: bool,
+    pub partitions: Vec<PartitionInfo>,
+}
+
+/// Partition changes that are due, and the statements making them. They are never run
+/// by the application: they ship as a migration, so every schema change goes through the
+/// checked migrator.
+#[derive(Debug, Default, Serialize)]
+pub struct MaintenancePlan {
+    pub create: Vec<String>,
+    pub detach: Vec<String>,
+    /// Tables that are not partitioned yet.
+    pub skipped: Vec<String>,
+    pub statements: Vec<String>,
+}
+
+impl MaintenancePlan {
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty()
+    }
+
+    /// The plan as an expand migration, to save in `migrations/` and register.
+    pub fn migration_sql(&self) -> String {
+        let mut sql = String::from(
+            "-- phase: expand\n-- Partition maintenance: creates upcoming partitions and detaches expired ones.\n\n\
+             SET lock_timeout = '5s';\n",
+        );
+        for statement in &self.statements {
+            sql.push('\n');
+            sql.push_str(statement);
+            sql.push_str(";\n");
+        }
+        sql
+    }
+}
+
+/// The statement making one change. Detaching cannot be `CONCURRENTLY` while a default
+/// partition exists, so it takes a brief exclusive lock.
+pub fn change_sql(parent: &str, change: &PartitionChange) -> String {
+    match change {
+        // Fails if rows for the range already sit in the default partition; those have to
+        // be moved by hand
+        PartitionChange::Create { name, from, to } => format!(
+            "CREATE TABLE IF NOT EXISTS {n} PARTITION OF {t} FOR VALUES FROM ('{from} 00:00:00+00') TO ('{to} 00:00:00+00')",
+            n = name,
+            t = parent,
+        ),
+        PartitionChange::Detach { name } => format!("ALTER TABLE {t} DETACH PARTITION {n}", t = parent, n = name),
+    }
+}
+
+#[derive(Debug, FromQueryResult)]
+struct PartitionRow {
+    name: String,
+    bound: String,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct Partitioned {
+    partitioned: bool,
+}
+
+const PARTITIONED_SQL: &str = r#"
+SELECT EXISTS (
+    SELECT 1 FROM pg_partitioned_table pt
+    JOIN pg_class c ON c.oid = pt.partrelid
+    WHERE c.relname = $1 AND pg_table_is_visible(c.oid)
+) AS partitioned
+"#;
+
+const PARTITIONS_SQL: &str = r#"
+SELECT child.relname AS name, pg_get_expr(child.relpartbound, child.oid) AS bound
+FROM pg_inherits i
+JOIN pg_class parent ON parent.oid = i.inhparent
+JOIN pg_class child ON child.oid = i.inhrelid
+WHERE parent.relname = $1 AND pg_table_is_visible(parent.oid)
+ORDER BY child.relname
+"#;
+
+const FOREIGN_KEYS_SQL: &str = r#"
+SELECT c.conname AS name, c.conrelid::regclass::text AS referencing, a.attname AS referencing_column,
+       c.confrelid::regclass::text AS referenced, fa.attname AS referenced_column,
+       c.confdeltype = 'c' AS cascade, cardinality(c.conkey) AS columns
+FROM pg_constraint c
+JOIN pg_attribute a ON a.attrelid = c.conrelid AND a.attnum = c.conkey[1]
+JOIN pg_attribute fa ON fa.attrelid = c.confrelid AND fa.attnum = c.confkey[1]
+WHERE c.contype = 'f' AND (c.conrelid = to_regclass($1) OR c.confrelid = to_regclass($1))
+ORDER BY c.conname
+"#;
+
+#[derive(Debug, FromQueryResult)]
+struct ForeignKeyRow {
+    name: String,
+    referencing: String,
+    referencing_column: String,
+    referenced: String,
+    referenced_column: String,
+    cascade: bool,
+    columns: i32,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct BackfillBatch {
+    last_id: Option<String>,
+    copied: i64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct MissingRows {
+    missing: i64,
+}
+
+/// Rows per backfill batch unless the operator picks another size.
+pub const DEFAULT_BACKFILL_BATCH: u32 = 1_000;
+
+/// Plans upcoming partitions and the detaching of expired ones for the configured tables,
+/// and drives their conversion: the `partition_high_volume_tables` migration creates a
+/// partitioned copy of each table, [`PartitionManager::backfill`] fills it and
+/// [`PartitionManager::swap_plan`] writes the contract migration swapping it in. Tables
+/// without either are skipped.
+pub struct PartitionManager {
+    db: Arc<DatabaseConnection>,
+    config: PartitioningConfig,
+}
+
+impl PartitionManager {
+    pub fn new(db: Arc<DatabaseConnection>, config: PartitioningConfig) -> Result<Self, PartitionError> {
+        for table in &config.tables {
+            table.validate()?;
+        }
+        Ok(Self { db, config })
+    }
+
+    pub fn tables(&self) -> &[PartitionedTable] {
+        &self.config.tables
+    }
+
+    async fn is_partitioned(&self, table: &str) -> Result<bool, PartitionError> {
+        let row = Partitioned::find_by_statement(dialect::statement(self.db.as_ref(), PARTITIONED_SQL, [table.into()]))
+            .one(self.db.as_ref())
+            .await?;
+        Ok(row.map_or(false, |r| r.partitioned))
+    }
+
+    async fn partitions(&self, parent: &str) -> Result<Vec<PartitionInfo>, PartitionError> {
+        let rows = PartitionRow::find_by_statement(dialect::statement(self.db.as_ref(), PARTITIONS_SQL, [parent.into()]))
+        .all(self.db.as_ref())
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| {
+                let bounds = parse_bound(&row.bound);
+                PartitionInfo { name: row.name, from: bounds.map(|b| b.0), to: bounds.map(|b| b.1) }
+            })
+            .collect())
+    }
+
+    /// The table partitions are made on: the table once swapped, its partitioned copy
+    /// before that, or `None` when neither is partitioned.
+    async fn partition_parent(&self, spec: &PartitionedTable) -> Result<Option<String>, PartitionError> {
+        if self.is_partitioned(&spec.table).await? {
+            return Ok(Some(spec.table.clone()));
+        }
+        let copy = spec.partitioned_copy();
+        Ok(self.is_partitioned(&copy).await?.then_some(copy))
+    }
+
+    pub async fn status(&self) -> Result<Vec<TableStatus>, PartitionError> {
+        dialect::require_postgres(self.db.as_ref(), "Table partitioning")?;
+        let mut statuses = Vec::new();
+        for spec in &self.config.tables {
+            let parent = self.partition_parent(spec).await?;
+            let partitions = match &parent {
+                Some(parent) => self.partitions(parent).await?,
+                None => Vec::new(),
+            };
+            statuses.push(TableStatus {
+                table: spec.table.clone(),
+                partitioned: parent.as_deref() == Some(spec.table.as_str()),
+                swap_pending: parent.is_some_and(|parent| parent != spec.table),
+                partitions,
+            });
+        }
+        Ok(statuses)
+    }
+
+    /// Partition changes due for every configured table as of today.
+    pub async fn maintenance_plan(&self) -> Result<MaintenancePlan, PartitionError> {
+        dialect::require_postgres(self.db.as_ref(), "Table partitioning")?;
+        let today = Utc::now().date_naive();
+        let mut due = MaintenancePlan::default();
+        for spec in &self.config.tables {
+            // A copy awaiting its swap needs the same partitions ahead as the table
+            let Some(parent) = self.partition_parent(spec).await? else {
+                due.skipped.push(spec.table.clone());
+                continue;
+            };
+            let existing = self.partitions(&parent).await?;
+            if !existing.iter().any(|p| p.name == spec.default_partition()) {
+                due.statements.push(format!(
+                    "CREATE TABLE IF NOT EXISTS {d} PARTITION OF {t} DEFAULT",
+                    d = spec.default_partition(),
+                    t = parent
+                ));
+            }
+            for change in plan(spec, today, &existing) {
+                due.statements.push(change_sql(&parent, &change));
+                match change {
+                    PartitionChange::Create { name, .. } => due.create.push(name),
+                    PartitionChange::Detach { name } => due.detach.push(name),
+                }
+            }
+        }
+        Ok(due)
+    }
+
+    fn spec(&self, table: &str) -> Result<&PartitionedTable, PartitionError> {
+        self.config.tables.iter().find(|spec| spec.table == table).ok_or_else(|| PartitionError::InvalidTable {
+            table: table.to_string(),
+            reason: "not listed in partitioning.tables".to_string(),
+        })
+    }
+
+    /// Copies the rows of `table` into its partitioned copy in batches of `batch` rows,
+    /// each in its own transaction, and returns how many rows it went through. Safe to
+    /// stop and rerun: rows already copied are skipped.
+    pub async fn backfill(&self, table: &str, batch: u32) -> Result<u64, PartitionError> {
+        dialect::require_postgres(self.db.as_ref(), "Table partitioning")?;
+        let spec = self.spec(table)?;
+        if !self.is_partitioned(&spec.partitioned_copy()).await? {
+            return Err(PartitionError::InvalidTable {
+                table: table.to_string(),
+                reason: format!("{} does not exist; run the migrations first", spec.partitioned_copy()),
+            });
+        }
+        let mut after: Option<String> = None;
+        let mut total = 0u64;
+        loop {
+            let sql = backfill_sql(spec, after.as_deref(), batch.max(1));
+            let Some(done) = BackfillBatch::find_by_statement(dialect::raw(self.db.as_ref(), &sql))
+                .one(self.db.as_ref())
+                .await?
+            else {
+                break;
+            };
+            total += done.copied as u64;
+            match done.last_id {
+                Some(last) if done.copied > 0 => after = Some(last),
+                _ => break,
+            }
+        }
+        Ok(total)
+    }
+
+    /// Rows of `spec`'s table missing from its partitioned copy.
+    async fn missing_rows(&self, spec: &PartitionedTable) -> Result<i64, PartitionError> {
+        let sql = format!(
+            "SELECT COUNT(*) AS missing FROM {t} s WHERE NOT EXISTS (SELECT 1 FROM {c} p WHERE p.id = s.id)",
+            t = spec.table,
+            c = spec.partitioned_copy()
+        );
+        let row = MissingRows::find_by_statement(dialect::raw(self.db.as_ref(), &sql)).one(self.db.as_ref()).await?;
+        Ok(row.map_or(0, |r| r.missing))
+    }
+
+    /// The contract migration swapping every table whose partitioned copy is backfilled,
+    /// or `None` when no copy awaits its swap. Refuses while any copy misses rows.
+    pub async fn swap_plan(&self) -> Result<Option<String>, PartitionError> {
+        dialect::require_postgres(self.db.as_ref(), "Table partitioning")?;
+        let mut tables = Vec::new();
+        for spec in &self.config.tables {
+            if self.is_partitioned(&spec.table).await? || !self.is_partitioned(&spec.partitioned_copy()).await? {
+                continue;
+            }
+            let missing = self.missing_rows(spec).await?;
+            if missing > 0 {
+                return Err(PartitionError::BackfillIncomplete { table: spec.table.clone(), missing });
+            }
+            tables.push(spec.clone());
+        }
+        if tables.is_empty() {
+            return Ok(None);
+        }
+        let mut foreign_keys: Vec<ForeignKey> = Vec::new();
+        for spec in &tables {
+            let rows = ForeignKeyRow::find_by_statement(dialect::statement(
+                self.db.as_ref(),
+                FOREIGN_KEYS_SQL,
+                [spec.table.as_str().into()],
+            ))
+            .all(self.db.as_ref())
+            .await?;
+            for row in rows {
+                if row.columns != 1 {
+                    return Err(PartitionError::InvalidTable {
+                        table: spec.table.clone(),
+                        reason: format!("foreign key {} spans several columns; replace it by hand", row.name),
+                    });
+                }
+                let fk = ForeignKey {
+                    name: row.name,
+                    referencing: row.referencing,
+                    referencing_column: row.referencing_column,
+                    referenced: row.referenced,
+                    referenced_column: row.referenced_column,
+                    cascade: row.cascade,
+                };
+                // A key between two swapped tables shows up for both
+                if !foreign_keys.contains(&fk) {
+                    foreign_keys.push(fk);
+                }
+            }
+        }
+        Ok(Some(swap_migration_sql(&tables, &foreign_keys)))
+    }
+}
+
+/// Checks at the configured interval, starting at once, whether partition maintenance is
+/// due, and warns until the migration making it ships.
+pub fn spawn_scheduler(manager: Arc<PartitionManager>) {
+    let interval = Duration::from_secs(manager.config.interval_secs.max(60));
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            match manager.maintenance_plan().await {
+                Ok(due) => {
+                    PARTITION_CHANGES_DUE.set(due.statements.len() as i64);
+                    if !due.is_empty() {
+                        warn!(
+                            create = due.create.len(),
+                            detach = due.detach.len(),
+                            "Partition maintenance is due; ship it as a migration from `stateset-cli partitions plan`"
+                        );
+                    }
+                }
+                Err(e) => error!("Checking partition maintenance failed: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn spec(interval: PartitionInterval, detach_after: Option<u32>) -> PartitionedTable {
+        PartitionedTable {
+            table: "orders".to_string(),
+            column: "created_date".to_string(),
+            interval,
+            premake: 2,
+            detach_after,
+        }
+    }
+
+    fn partition(name: &str, from: NaiveDate, to: NaiveDate) -> PartitionInfo {
+        PartitionInfo { name: name.to_string(), from: Some(from), to: Some(to) }
+    }
+
+    #[test]
+    fn test_periods() {
+        assert_eq!(period_start(date(2024, 5, 17), PartitionInterval::Monthly), date(2024, 5, 1));
+        assert_eq!(period_start(date(2024, 5, 17), PartitionInterval::Weekly), date(2024, 5, 13));
+        assert_eq!(shift_period(date(2024, 12, 1), PartitionInterval::Monthly, 1), date(2025, 1, 1));
+        assert_eq!(shift_period(date(2024, 1, 1), PartitionInterval::Monthly, -2), date(2023, 11, 1));
+        assert_eq!(partition_name("orders", date(2024, 5, 1), PartitionInterval::Monthly), "orders_p202405");
+        assert_eq!(partition_name("orders", date(2024, 5, 13), PartitionInterval::Weekly), "orders_p20240513");
+    }
+
+    #[test]
+    fn test_bounds_are_parsed() {
+        assert_eq!(
+            parse_bound("FOR VALUES FROM ('2024-05-01 00:00:00+00') TO ('2024-06-01 00:00:00+00')"),
+            Some((date(2024, 5, 1), date(2024, 6, 1)))
+        );
+        assert_eq!(parse_bound("DEFAULT"), None);
+        assert_eq!(parse_bound("FOR VALUES FROM (MINVALUE) TO ('2024-05-01 00:00:00+00')"), None);
+    }
+
+    #[test]
+    fn test_plan_creates_missing_partitions_ahead() {
+        let existing = vec![partition("orders_p202405", date(2024, 5, 1), date(2024, 6, 1))];
+        let changes = plan(&spec(PartitionInterval::Monthly, None), date(2024, 5, 17), &existing);
+        assert_eq!(
+            changes,
+            vec![
+                PartitionChange::Create { name: "orders_p202406".to_string(), from: date(2024, 6, 1), to: date(2024, 7, 1) },
+                PartitionChange::Create { name: "orders_p202407".to_string(), from: date(2024, 7, 1), to: date(2024, 8, 1) },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_detaches_expired_partitions() {
+        let existing = vec![
+            PartitionInfo { name: "orders_history".to_string(), from: None, to: None },
+            partition("orders_p202402", date(2024, 2, 1), date(2024, 3, 1)),
+            partition("orders_p202403", date(2024, 3, 1), date(2024, 4, 1)),
+            partition("orders_p202404", date(2024, 4, 1), date(2024, 5, 1)),
+            partition("orders_p202405", date(2024, 5, 1), date(2024, 6, 1)),
+            partition("orders_p202406", date(2024, 6, 1), date(2024, 7, 1)),
+            partition("orders_p202407", date(2024, 7, 1), date(2024, 8, 1)),
+        ];
+        let changes = plan(&spec(PartitionInterval::Monthly, Some(2)), date(2024, 5, 17), &existing);
+        assert_eq!(
+            changes,
+            vec![
+                PartitionChange::Detach { name: "orders_p202402".to_string() },
+                PartitionChange::Detach { name: "orders_p202403".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_maintenance_migration_is_safe() {
+        let spec = spec(PartitionInterval::Monthly, Some(2));
+        let due = MaintenancePlan {
+            statements: vec![
+                change_sql(&spec.table, &PartitionChange::Create {
+                    name: "orders_p202406".to_string(),
+                    from: date(2024, 6, 1),
+                    to: date(2024, 7, 1),
+                }),
+                change_sql(&spec.table, &PartitionChange::Detach { name: "orders_p202402".to_string() }),
+            ],
+            ..Default::default()
+        };
+        let plan = crate::db::migration_safety::analyze("partition_maintenance.sql", &due.migration_sql());
+        assert_eq!(plan.violations().count(), 0);
+        assert_eq!(plan.findings.len(), 2);
+    }
+
+    #[test]
+    fn test_backfill_continues_after_the_last_id() {
+        let spec = spec(PartitionInterval::Monthly, None);
+        let first = backfill_sql(&spec, None, 500);
+        assert!(first.starts_with("WITH batch AS (SELECT * FROM orders ORDER BY id LIMIT 500 FOR SHARE)"));
+        assert!(first.contains("INSERT INTO orders_partitioned SELECT * FROM batch ON CONFLICT DO NOTHING"));
+        let next = backfill_sql(&spec, Some("o'1"), 500);
+        assert!(next.contains("FROM orders WHERE id > 'o''1' ORDER BY id"));
+    }
+
+    #[test]
+    fn test_swap_replaces_foreign_keys_with_checks() {
+        let orders = spec(PartitionInterval::Monthly, None);
+        let fk = ForeignKey {
+            name: "order_items_order_id_fkey".to_string(),
+            referencing: "order_items".to_string(),
+            referencing_column: "order_id".to_string(),
+            referenced: "orders".to_string(),
+            referenced_column: "id".to_string(),
+            cascade: true,
+        };
+        let sql = swap_migration_sql(&[orders], &[fk]);
+        let statements = crate::db::migration_safety::split_statements(&sql);
+        assert_eq!(statements[1], "LOCK TABLE orders, orders_partitioned IN ACCESS EXCLUSIVE MODE");
+        assert_eq!(statements[2], "ALTER TABLE orders RENAME TO orders_legacy");
+        assert_eq!(statements[3], "ALTER TABLE orders_partitioned RENAME TO orders");
+        assert_eq!(statements[4], "DROP TRIGGER partition_copy ON orders_legacy");
+        // The key is dropped in the same transaction that adds the checks replacing it
+        assert_eq!(statements[6], "ALTER TABLE order_items DROP CONSTRAINT order_items_order_id_fkey");
+        assert!(statements[7].contains("ON order_items FOR EACH ROW EXECUTE FUNCTION partition_reference_check('orders', 'id', 'order_id')"));
+        assert!(statements[8].contains("OR DELETE ON orders FOR EACH ROW"));
+        assert!(statements[8].ends_with("partition_referenced_check('order_items', 'order_id', 'id', 'cascade')"));
+
+        let plan = crate::db::migration_safety::analyze("partition_swap.sql", &sql);
+        assert_eq!(plan.phase, crate::db::migration_safety::Phase::Contract);
+        assert_eq!(plan.violations().count(), 0);
+    }
+
+    #[test]
+    fn test_migration_is_safe() {
+        let plan = crate::db::migration_safety::analyze(
+            "20261016200000_partition_high_volume_tables.sql",
+            include_str!("../../migrations/20261016200000_partition_high_volume_tables.sql"),
+        );
+        assert_eq!(plan.violations().count(), 0);
+    }
+
+    #[test]
+    fn test_identifiers_validated() {
+        assert!(spec(PartitionInterval::Daily, None).validate().is_ok());
+        let mut bad = spec(PartitionInterval::Daily, None);
+        bad.table = "orders; DROP TABLE customers".to_string();
+        assert!(bad.validate().is_err());
+        assert!(spec(PartitionInterval::Daily, Some(0)).validate().is_err());
+    }
+}
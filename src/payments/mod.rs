@@ -0,0 +1,363 @@
+// payments/mod.rs
+
+use async_trait::async_trait;
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::config::secrets::{SecretStore, STRIPE_SECRET_KEY};
+use crate::deadline;
+use crate::models::payment_authorization::CaptureStrategy;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GatewayKind {
+    Stripe,
+}
+
+/// Payment capture settings, loaded from the `payments` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PaymentsConfig {
+    /// Record authorizations and capture them through the gateway.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// When authorized payments are captured, unless the authorization says otherwise.
+    #[serde(default = "default_strategy")]
+    pub capture_strategy: CaptureStrategy,
+
+    #[serde(default = "default_gateway")]
+    pub gateway: GatewayKind,
+
+    /// Environment variable holding the Stripe secret key.
+    #[serde(default = "default_stripe_key_env")]
+    pub stripe_api_key_env: String,
+
+    #[serde(default = "default_stripe_base_url")]
+    pub stripe_base_url: String,
+
+    /// Seconds after which a capture still `pending` is assumed to have lost its outcome,
+    /// e.g. to a restart mid-call, and is resolved by the reconciliation sweep.
+    #[serde(default = "default_stale_capture_secs")]
+    pub stale_capture_secs: u64,
+
+    /// Interval in seconds between reconciliation sweeps.
+    #[serde(default = "default_reconcile_interval_secs")]
+    pub reconcile_interval_secs: u64,
+}
+
+fn default_strategy() -> CaptureStrategy {
+    CaptureStrategy::OnShipment
+}
+
+fn default_gateway() -> GatewayKind {
+    GatewayKind::Stripe
+}
+
+fn default_stripe_key_env() -> String {
+    "STRIPE_SECRET_KEY".to_string()
+}
+
+fn default_stripe_base_url() -> String {
+    "https://api.stripe.com".to_string()
+}
+
+fn default_stale_capture_secs() -> u64 {
+    600
+}
+
+fn default_reconcile_interval_secs() -> u64 {
+    300
+}
+
+impl Default for PaymentsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            capture_strategy: default_strategy(),
+            gateway: default_gateway(),
+            stripe_api_key_env: default_stripe_key_env(),
+            stripe_base_url: default_stripe_base_url(),
+            stale_capture_secs: default_stale_capture_secs(),
+            reconcile_interval_secs: default_reconcile_interval_secs(),
+        }
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum GatewayError {
+    #[error("Payment gateway is misconfigured: {0}")]
+    Misconfigured(String),
+
+    /// The gateway refused the capture, e.g. an expired authorization.
+    #[error("Capture declined: {0}")]
+    Declined(String),
+
+    #[error("Payment gateway unavailable: {0}")]
+    Unavailable(String),
+}
+
+/// A capture request against an earlier authorization.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CaptureRequest<'a> {
+    pub authorization_ref: &'a str,
+    pub amount: Decimal,
+    pub currency: &'a str,
+    /// Releases the uncaptured remainder of the authorization.
+    pub final_capture: bool,
+    /// Repeated requests with the same key capture once.
+    pub idempotency_key: &'a str,
+}
+
+/// An off-session charge to a customer's stored payment method, e.g. a subscription renewal.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChargeRequest<'a> {
+    pub customer_ref: &'a str,
+    pub payment_method_ref: &'a str,
+    pub amount: Decimal,
+    pub currency: &'a str,
+    pub description: &'a str,
+    pub idempotency_key: &'a str,
+}
+
+/// What the processor reports about a stored payment method. Only display details; the
+/// card number itself never leaves the processor.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaymentMethodDetails {
+    pub payment_method_ref: String,
+    /// The processor's customer the method is attached to, if any.
+    pub customer_ref: Option<String>,
+    pub brand: Option<String>,
+    pub last4: Option<String>,
+    pub exp_month: Option<i32>,
+    pub exp_year: Option<i32>,
+}
+
+/// Captures and voids authorizations held by a payment processor.
+#[async_trait]
+pub trait PaymentGateway: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Captures funds and returns the processor's reference for the capture.
+    async fn capture(&self, request: CaptureRequest<'_>) -> Result<String, GatewayError>;
+
+    /// Releases an authorization that will not be captured (further).
+    async fn void(&self, authorization_ref: &str) -> Result<(), GatewayError>;
+
+    /// Charges a stored payment method and returns the processor's reference for the charge.
+    async fn charge(&self, request: ChargeRequest<'_>) -> Result<String, GatewayError>;
+
+    /// Looks up a payment method tokenized client-side with the processor.
+    async fn payment_method(&self, payment_method_ref: &str) -> Result<PaymentMethodDetails, GatewayError>;
+
+    /// Detaches a stored payment method so it can no longer be charged.
+    async fn detach_payment_method(&self, payment_method_ref: &str) -> Result<(), GatewayError>;
+}
+
+/// Amount in the currency's minor unit, as Stripe expects. Zero-decimal currencies are
+/// sent as is.
+pub fn minor_units(amount: Decimal, currency: &str) -> i64 {
+    let scale = currency_scale(currency);
+    (amount.round_dp(scale) * Decimal::from(10i64.pow(scale))).trunc().to_i64().unwrap_or(0)
+}
+
+/// Inverse of `minor_units`, for amounts reported by the processor.
+pub fn from_minor_units(minor: i64, currency: &str) -> Decimal {
+    Decimal::new(minor, currency_scale(currency))
+}
+
+fn currency_scale(currency: &str) -> u32 {
+    const ZERO_DECIMAL: &[&str] = &["JPY", "KRW", "VND", "CLP", "ISK", "UGX", "XAF", "XOF"];
+    if ZERO_DECIMAL.contains(&currency.to_uppercase().as_str()) {
+        0
+    } else {
+        2
+    }
+}
+
+/// Stripe PaymentIntents. Partial captures for split shipments use multicapture
+/// (`final_capture=false`), which must be enabled on the Stripe account.
+pub struct StripeGateway {
+    client: reqwest::Client,
+    api_key: String,
+    /// A `stripe_secret_key` here replaces `api_key` from the moment it is rotated in.
+    secrets: SecretStore,
+    base_url: String,
+}
+
+impl StripeGateway {
+    pub fn from_config(config: &PaymentsConfig, secrets: &SecretStore) -> Result<Self, GatewayError> {
+        let api_key = match std::env::var(&config.stripe_api_key_env) {
+            Ok(api_key) => api_key,
+            Err(_) if secrets.get(STRIPE_SECRET_KEY).is_some() => String::new(),
+            Err(_) => {
+                return Err(GatewayError::Misconfigured(format!("{} is not set", config.stripe_api_key_env)))
+            }
+        };
+        Ok(Self {
+            client: reqwest::Client::new(),
+            api_key,
+            secrets: secrets.clone(),
+            base_url: config.stripe_base_url.trim_end_matches('/').to_string(),
+        })
+    }
+
+    /// The key requests are made with, read on every request so a rotation applies to
+    /// the next one.
+    fn api_key(&self) -> String {
+        self.secrets.get_or(STRIPE_SECRET_KEY, &self.api_key)
+    }
+
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<serde_json::Value, GatewayError> {
+        let response = deadline::bounded(request.basic_auth(self.api_key(), None::<&str>))
+            .send()
+            .await
+            .map_err(|e| GatewayError::Unavailable(e.to_string()))?;
+        let status = response.status();
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| GatewayError::Unavailable(e.to_string()))?;
+        if status.is_success() {
+            return Ok(body);
+        }
+        let message = body["error"]["message"].as_str().unwrap_or("unknown error").to_string();
+        if status.is_client_error() && status.as_u16() != 429 {
+            Err(GatewayError::Declined(message))
+        } else {
+            Err(GatewayError::Unavailable(format!("{}: {}", status, message)))
+        }
+    }
+}
+
+#[async_trait]
+impl PaymentGateway for StripeGateway {
+    fn name(&self) -> &'static str {
+        "stripe"
+    }
+
+    async fn capture(&self, request: CaptureRequest<'_>) -> Result<String, GatewayError> {
+        let url = format!("{}/v1/payment_intents/{}/capture", self.base_url, request.authorization_ref);
+        let form = [
+            ("amount_to_capture", minor_units(request.amount, request.currency).to_string()),
+            ("final_capture", request.final_capture.to_string()),
+        ];
+        let body = self
+            .send(
+                self.client
+                    .post(url)
+                    .header("Idempotency-Key", request.idempotency_key)
+                    .form(&form),
+            )
+            .await?;
+        Ok(body["latest_charge"]
+            .as_str()
+            .or_else(|| body["id"].as_str())
+            .unwrap_or(request.authorization_ref)
+            .to_string())
+    }
+
+    async fn void(&self, authorization_ref: &str) -> Result<(), GatewayError> {
+        let url = format!("{}/v1/payment_intents/{}/cancel", self.base_url, authorization_ref);
+        self.send(self.client.post(url)).await.map(|_| ())
+    }
+
+    async fn charge(&self, request: ChargeRequest<'_>) -> Result<String, GatewayError> {
+        let url = format!("{}/v1/payment_intents", self.base_url);
+        let form = [
+            ("amount", minor_units(request.amount, request.currency).to_string()),
+            ("currency", request.currency.to_lowercase()),
+            ("customer", request.customer_ref.to_string()),
+            ("payment_method", request.payment_method_ref.to_string()),
+            ("description", request.description.to_string()),
+            ("off_session", "true".to_string()),
+            ("confirm", "true".to_string()),
+        ];
+        let body = self
+            .send(
+                self.client
+                    .post(url)
+                    .header("Idempotency-Key", request.idempotency_key)
+                    .form(&form),
+            )
+            .await?;
+        match body["status"].as_str() {
+            Some("succeeded") => Ok(body["id"].as_str().unwrap_or_default().to_string()),
+            status => Err(GatewayError::Declined(format!(
+                "Payment intent {} is {}",
+                body["id"].as_str().unwrap_or("unknown"),
+                status.unwrap_or("unknown")
+            ))),
+        }
+    }
+
+    async fn payment_method(&self, payment_method_ref: &str) -> Result<PaymentMethodDetails, GatewayError> {
+        let url = format!("{}/v1/payment_methods/{}", self.base_url, payment_method_ref);
+        Ok(stripe_payment_method(&self.send(self.client.get(url)).await?))
+    }
+
+    async fn detach_payment_method(&self, payment_method_ref: &str) -> Result<(), GatewayError> {
+        let url = format!("{}/v1/payment_methods/{}/detach", self.base_url, payment_method_ref);
+        self.send(self.client.post(url)).await.map(|_| ())
+    }
+}
+
+/// Card details from a Stripe PaymentMethod object.
+pub fn stripe_payment_method(body: &serde_json::Value) -> PaymentMethodDetails {
+    let card = &body["card"];
+    PaymentMethodDetails {
+        payment_method_ref: body["id"].as_str().unwrap_or_default().to_string(),
+        customer_ref: body["customer"].as_str().map(str::to_string),
+        brand: card["brand"].as_str().map(str::to_string),
+        last4: card["last4"].as_str().map(str::to_string),
+        exp_month: card["exp_month"].as_i64().map(|m| m as i32),
+        exp_year: card["exp_year"].as_i64().map(|y| y as i32),
+    }
+}
+
+/// Builds the gateway selected in the config.
+pub fn from_config(config: &PaymentsConfig, secrets: &SecretStore) -> Result<Arc<dyn PaymentGateway>, GatewayError> {
+    match config.gateway {
+        GatewayKind::Stripe => Ok(Arc::new(StripeGateway::from_config(config, secrets)?)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_minor_units() {
+        assert_eq!(minor_units(dec!(12.34), "usd"), 1234);
+        assert_eq!(minor_units(dec!(19.999), "EUR"), 2000);
+        assert_eq!(minor_units(dec!(1500), "JPY"), 1500);
+        assert_eq!(from_minor_units(1234, "usd"), dec!(12.34));
+        assert_eq!(from_minor_units(1500, "JPY"), dec!(1500));
+    }
+
+    #[test]
+    fn test_stripe_payment_method_keeps_display_details_only() {
+        let body = serde_json::json!({
+            "id": "pm_123",
+            "customer": "cus_9",
+            "type": "card",
+            "card": { "brand": "visa", "last4": "4242", "exp_month": 8, "exp_year": 2028, "fingerprint": "Xt5E" },
+        });
+        let details = stripe_payment_method(&body);
+        assert_eq!(details.payment_method_ref, "pm_123");
+        assert_eq!(details.customer_ref.as_deref(), Some("cus_9"));
+        assert_eq!(details.brand.as_deref(), Some("visa"));
+        assert_eq!(details.last4.as_deref(), Some("4242"));
+        assert_eq!((details.exp_month, details.exp_year), (Some(8), Some(2028)));
+    }
+
+    #[test]
+    fn test_strategy_names_deserialize() {
+        let config: PaymentsConfig =
+            serde_json::from_value(serde_json::json!({ "capture_strategy": "on_order" })).unwrap();
+        assert_eq!(config.capture_strategy, CaptureStrategy::OnOrder);
+        assert_eq!(PaymentsConfig::default().capture_strategy, CaptureStrategy::OnShipment);
+    }
+}
@@ -0,0 +1,412 @@
+// product_feed/mod.rs
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use sea_orm::{
+    sea_query::{Expr, Query},
+    ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, FromQueryResult,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{collections::HashMap, sync::Arc};
+use thiserror::Error;
+use tracing::error;
+
+use crate::models::{
+    inventory_items,
+    product_listing::{self, Entity as ProductListing},
+};
+use crate::money::round_currency;
+use crate::utils::pagination::PaginationParams;
+
+/// Product feed settings, loaded from the `product_feed` section of the config. The
+/// merchant fields are copied onto every feed item as the format requires.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ProductFeedConfig {
+    #[serde(default = "default_seller_name")]
+    pub seller_name: String,
+
+    #[serde(default = "default_seller_url")]
+    pub seller_url: String,
+
+    #[serde(default)]
+    pub privacy_policy_url: Option<String>,
+
+    #[serde(default)]
+    pub terms_of_service_url: Option<String>,
+
+    #[serde(default)]
+    pub return_policy_url: Option<String>,
+
+    /// Days after delivery within which returns are accepted.
+    #[serde(default = "default_return_window_days")]
+    pub return_window_days: u32,
+
+    /// Shipping options offered on every product.
+    #[serde(default = "default_shipping")]
+    pub shipping: Vec<ShippingOption>,
+}
+
+fn default_seller_name() -> String {
+    "StateSet".to_string()
+}
+
+fn default_seller_url() -> String {
+    "https://example.com".to_string()
+}
+
+fn default_return_window_days() -> u32 {
+    30
+}
+
+fn default_shipping() -> Vec<ShippingOption> {
+    vec![ShippingOption {
+        country: "US".to_string(),
+        region: None,
+        service: "Standard".to_string(),
+        price: Decimal::ZERO,
+        currency: "USD".to_string(),
+    }]
+}
+
+impl Default for ProductFeedConfig {
+    fn default() -> Self {
+        Self {
+            seller_name: default_seller_name(),
+            seller_url: default_seller_url(),
+            privacy_policy_url: None,
+            terms_of_service_url: None,
+            return_policy_url: None,
+            return_window_days: default_return_window_days(),
+            shipping: default_shipping(),
+        }
+    }
+}
+
+/// A shipping rate offered to a country or region.
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct ShippingOption {
+    /// ISO 3166-1 alpha-2 country code.
+    pub country: String,
+    /// State or region code; `None` for the whole country.
+    #[serde(default)]
+    pub region: Option<String>,
+    pub service: String,
+    pub price: Decimal,
+    #[serde(default = "default_currency")]
+    pub currency: String,
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+impl ShippingOption {
+    /// `country:region:service:price`, e.g. `US:CA:Overnight:16.00 USD`.
+    pub fn to_feed_value(&self) -> String {
+        format!(
+            "{}:{}:{}:{}",
+            self.country,
+            self.region.as_deref().unwrap_or(""),
+            self.service,
+            format_price(self.price, &self.currency)
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Availability {
+    InStock,
+    OutOfStock,
+}
+
+/// One product in the agentic-commerce feed format.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FeedItem {
+    pub id: String,
+    pub title: String,
+    pub description: String,
+    pub link: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image_link: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub brand: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gtin: Option<String>,
+    pub mpn: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_group_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<String>,
+    /// `<amount> <currency>`, e.g. `79.99 USD`.
+    pub price: String,
+    pub availability: Availability,
+    pub inventory_quantity: i64,
+    pub enable_search: bool,
+    pub enable_checkout: bool,
+    pub shipping: Vec<String>,
+    pub seller_name: String,
+    pub seller_url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seller_privacy_policy: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seller_tos: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub return_policy: Option<String>,
+    pub return_window: u32,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// A page of the feed. Pass `generated_at` back as `updated_since` to fetch only what
+/// changed afterwards.
+#[derive(Debug, Clone, Serialize)]
+pub struct FeedPage {
+    pub products: Vec<FeedItem>,
+    pub total: u64,
+    pub page: u64,
+    pub per_page: u64,
+    pub generated_at: DateTime<Utc>,
+}
+
+#[derive(Error, Debug)]
+pub enum FeedError {
+    #[error("Invalid updated_since '{0}': expected an RFC 3339 timestamp")]
+    InvalidTimestamp(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for FeedError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            FeedError::InvalidTimestamp(_) => (StatusCode::BAD_REQUEST, "invalid_timestamp"),
+            FeedError::Database(e) => {
+                error!("Product feed query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "product_feed_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+pub fn parse_updated_since(raw: &str) -> Result<DateTime<Utc>, FeedError> {
+    DateTime::parse_from_rfc3339(raw.trim())
+        .map(|at| at.with_timezone(&Utc))
+        .map_err(|_| FeedError::InvalidTimestamp(raw.to_string()))
+}
+
+fn format_price(amount: Decimal, currency: &str) -> String {
+    format!("{:.2} {}", round_currency(amount), currency)
+}
+
+/// Builds the feed entry for a listing with `stock` sellable units across warehouses.
+/// Inactive listings stay in the entry with search and checkout disabled so incremental
+/// consumers delist them.
+pub fn build_item(listing: &product_listing::Model, stock: i64, config: &ProductFeedConfig) -> FeedItem {
+    let stock = stock.max(0);
+    let seller_url = config.seller_url.trim_end_matches('/');
+    FeedItem {
+        id: listing.sku.clone(),
+        title: listing.title.clone(),
+        description: listing.description.clone(),
+        link: listing
+            .link
+            .clone()
+            .unwrap_or_else(|| format!("{}/products/{}", seller_url, listing.sku)),
+        image_link: listing.image_link.clone(),
+        brand: listing.brand.clone(),
+        gtin: listing.gtin.clone(),
+        mpn: listing.sku.clone(),
+        item_group_id: listing.item_group_id.clone(),
+        color: listing.color.clone(),
+        size: listing.size.clone(),
+        price: format_price(listing.price, &listing.currency),
+        availability: if listing.active && stock > 0 { Availability::InStock } else { Availability::OutOfStock },
+        inventory_quantity: if listing.active { stock } else { 0 },
+        enable_search: listing.active,
+        enable_checkout: listing.active && listing.enable_checkout,
+        shipping: config.shipping.iter().map(ShippingOption::to_feed_value).collect(),
+        seller_name: config.seller_name.clone(),
+        seller_url: seller_url.to_string(),
+        seller_privacy_policy: config.privacy_policy_url.clone(),
+        seller_tos: config.terms_of_service_url.clone(),
+        return_policy: config.return_policy_url.clone(),
+        return_window: config.return_window_days,
+        updated_at: listing.updated_at,
+    }
+}
+
+#[derive(Debug, FromQueryResult)]
+struct StockLevel {
+    sku: String,
+    on_hand: Option<i64>,
+}
+
+/// Builds the product feed from `product_listings`, with availability from current
+/// inventory (available less reserved, summed over warehouses).
+pub struct ProductFeedService {
+    db: Arc<DatabaseConnection>,
+    config: ProductFeedConfig,
+}
+
+impl ProductFeedService {
+    pub fn new(db: Arc<DatabaseConnection>, config: ProductFeedConfig) -> Self {
+        Self { db, config }
+    }
+
+    /// A full feed of active listings, or with `updated_since` the listings whose
+    /// details or stock changed after that time, including ones since deactivated.
+    pub async fn feed(
+        &self,
+        updated_since: Option<DateTime<Utc>>,
+        pagination: PaginationParams,
+    ) -> Result<FeedPage, FeedError> {
+        let generated_at = Utc::now();
+        let condition = match updated_since {
+            None => Condition::all().add(product_listing::Column::Active.eq(true)),
+            Some(since) => Condition::any()
+                .add(product_listing::Column::UpdatedAt.gt(since))
+                .add(
+                    product_listing::Column::Sku.in_subquery(
+                        Query::select()
+                            .column(inventory_items::Column::Sku)
+                            .from(inventory_items::Entity)
+                            .and_where(inventory_items::Column::LastMovementDate.gt(since))
+                            .to_owned(),
+                    ),
+                ),
+        };
+
+        let paginator = ProductListing::find()
+            .filter(condition)
+            .order_by_asc(product_listing::Column::Sku)
+            .paginate(self.db.as_ref(), pagination.limit());
+        let total = paginator.num_items().await?;
+        let listings = paginator.fetch_page(pagination.page_index()).await?;
+
+        let stock = self.stock_levels(listings.iter().map(|l| l.sku.clone()).collect()).await?;
+        let products = listings
+            .iter()
+            .map(|listing| build_item(listing, stock.get(&listing.sku).copied().unwrap_or(0), &self.config))
+            .collect();
+
+        Ok(FeedPage {
+            products,
+            total,
+            page: pagination.page,
+            per_page: pagination.limit(),
+            generated_at,
+        })
+    }
+
+    async fn stock_levels(&self, skus: Vec<String>) -> Result<HashMap<String, i64>, FeedError> {
+        if skus.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let levels = inventory_items::Entity::find()
+            .select_only()
+            .column(inventory_items::Column::Sku)
+            .column_as(
+                Expr::cust("SUM(available - COALESCE(reserved_quantity, 0))"),
+                "on_hand",
+            )
+            .filter(inventory_items::Column::Sku.is_in(skus))
+            .group_by(inventory_items::Column::Sku)
+            .into_model::<StockLevel>()
+            .all(self.db.as_ref())
+            .await?;
+        Ok(levels
+            .into_iter()
+            .map(|level| (level.sku, level.on_hand.unwrap_or(0)))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn listing() -> product_listing::Model {
+        let at = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        product_listing::Model {
+            sku: "TEE-BLK-M".to_string(),
+            title: "Classic Tee".to_string(),
+            description: "Cotton crew neck".to_string(),
+            brand: Some("StateSet".to_string()),
+            gtin: None,
+            item_group_id: Some("TEE".to_string()),
+            color: Some("Black".to_string()),
+            size: Some("M".to_string()),
+            hs_code: None,
+            country_of_origin: None,
+            price: Decimal::new(2450, 2),
+            currency: "USD".to_string(),
+            link: None,
+            image_link: None,
+            active: true,
+            enable_checkout: true,
+            metadata: None,
+            created_at: at,
+            updated_at: at,
+        }
+    }
+
+    #[test]
+    fn test_item_carries_price_stock_and_merchant_policies() {
+        let config = ProductFeedConfig {
+            seller_url: "https://shop.example.com/".to_string(),
+            return_policy_url: Some("https://shop.example.com/returns".to_string()),
+            shipping: vec![ShippingOption {
+                country: "US".to_string(),
+                region: Some("CA".to_string()),
+                service: "Overnight".to_string(),
+                price: Decimal::new(16, 0),
+                currency: "USD".to_string(),
+            }],
+            ..ProductFeedConfig::default()
+        };
+        let item = build_item(&listing(), 12, &config);
+        assert_eq!(item.price, "24.50 USD");
+        assert_eq!(item.availability, Availability::InStock);
+        assert_eq!(item.inventory_quantity, 12);
+        assert_eq!(item.link, "https://shop.example.com/products/TEE-BLK-M");
+        assert_eq!(item.shipping, ["US:CA:Overnight:16.00 USD"]);
+        assert_eq!(item.return_policy.as_deref(), Some("https://shop.example.com/returns"));
+        assert!(item.enable_checkout);
+    }
+
+    #[test]
+    fn test_oversold_stock_reads_out_of_stock() {
+        let item = build_item(&listing(), -3, &ProductFeedConfig::default());
+        assert_eq!(item.availability, Availability::OutOfStock);
+        assert_eq!(item.inventory_quantity, 0);
+    }
+
+    #[test]
+    fn test_inactive_listing_is_delisted() {
+        let mut inactive = listing();
+        inactive.active = false;
+        let item = build_item(&inactive, 40, &ProductFeedConfig::default());
+        assert_eq!(item.availability, Availability::OutOfStock);
+        assert!(!item.enable_search);
+        assert!(!item.enable_checkout);
+    }
+
+    #[test]
+    fn test_parse_updated_since() {
+        let at = parse_updated_since("2024-06-01T12:00:00+02:00").unwrap();
+        assert_eq!(at, Utc.with_ymd_and_hms(2024, 6, 1, 10, 0, 0).unwrap());
+        assert!(matches!(parse_updated_since("yesterday"), Err(FeedError::InvalidTimestamp(_))));
+    }
+}
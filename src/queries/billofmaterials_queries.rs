@@ -13,6 +13,7 @@ use sea_orm::{
 };
 use crate::{errors::ServiceError, db::DbPool, models::*};
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 
 use sea_orm::Order
 
@@ -103,15 +104,18 @@ pub struct BOMCostItem {
     pub component_id: i32,
     pub component_name: String,
     pub quantity: f64,
-    pub unit_cost: f64,
-    pub total_cost: f64,
+    #[serde(with = "crate::money::amount")]
+    pub unit_cost: Decimal,
+    #[serde(with = "crate::money::amount")]
+    pub total_cost: Decimal,
 }
 
 #[derive(Debug, Serialize)]
 pub struct BOMCostAnalysis {
     pub product_id: i32,
     pub product_name: String,
-    pub total_cost: f64,
+    #[serde(with = "crate::money::amount")]
+    pub total_cost: Decimal,
     pub items: Vec<BOMCostItem>,
 }
 
@@ -136,12 +140,14 @@ impl Query for GetBOMCostAnalysisQuery {
             .await
             .map_err(|_| ServiceError::DatabaseError)?;
 
-        let mut total_cost = 0.0;
+        let mut total_cost = Decimal::ZERO;
         let cost_items: Vec<BOMCostItem> = items
             .into_iter()
             .map(|((bom_item, component), inventory_item)| {
                 let unit_cost = inventory_item.unwrap().unit_cost;
-                let item_total_cost = bom_item.quantity * unit_cost;
+                // BOM quantities are fractional; convert once rather than doing money math in f64
+                let quantity = Decimal::try_from(bom_item.quantity).unwrap_or(Decimal::ZERO);
+                let item_total_cost = crate::money::round_currency(quantity * unit_cost);
                 total_cost += item_total_cost;
                 BOMCostItem {
                     component_id: component.unwrap().id,
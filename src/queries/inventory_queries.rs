@@ -13,6 +13,7 @@ use sea_orm::{
 };
 use crate::{errors::ServiceError, db::DbPool, models::*};
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 
 use crate::billofmaterials::BillOfMaterialsLineItemRelation::BillOfMaterials;
 use crate::inventory_item::InventoryItem;
@@ -91,7 +92,8 @@ pub struct GetInventoryValueQuery {}
 
 #[derive(Debug, Serialize)]
 pub struct InventoryValue {
-    pub total_value: f64,
+    #[serde(with = "crate::money::amount")]
+    pub total_value: Decimal,
     pub total_items: i64,
 }
 
@@ -105,7 +107,7 @@ impl Query for GetInventoryValueQuery {
         let result = InventoryItem::find()
             .select_only()
             .column_as(
-                sum(InventoryItem::Column::Quantity.cast::<f64>() * InventoryItem::Column::UnitCost),
+                sum(InventoryItem::Column::Quantity.cast::<Decimal>() * InventoryItem::Column::UnitCost),
                 "total_value",
             )
             .column_as(count(InventoryItem::Column::Id), "total_items")
@@ -115,7 +117,7 @@ impl Query for GetInventoryValueQuery {
             .map_err(|_| ServiceError::DatabaseError)?;
 
         Ok(InventoryValue {
-            total_value: result.0.unwrap_or(0.0),
+            total_value: result.0.unwrap_or(Decimal::ZERO),
             total_items: result.1.unwrap_or(0),
         })
     }
@@ -161,7 +163,8 @@ pub struct TopSellingProduct {
     pub product_id: i32,
     pub product_name: String,
     pub quantity_sold: i64,
-    pub total_revenue: f64,
+    #[serde(with = "crate::money::amount")]
+    pub total_revenue: Decimal,
 }
 
 #[async_trait]
@@ -182,7 +185,7 @@ impl Query for GetTopSellingProductsQuery {
             .column(Product::Column::Name)
             .column_as(sum(OrderItem::Column::Quantity), "quantity_sold")
             .column_as(
-                sum(OrderItem::Column::Quantity.cast::<f64>() * OrderItem::Column::UnitPrice),
+                sum(OrderItem::Column::Quantity.cast::<Decimal>() * OrderItem::Column::UnitPrice),
                 "total_revenue",
             )
             .into_tuple()
@@ -196,7 +199,7 @@ impl Query for GetTopSellingProductsQuery {
                 product_id,
                 product_name,
                 quantity_sold: quantity_sold.unwrap_or(0),
-                total_revenue: total_revenue.unwrap_or(0.0),
+                total_revenue: total_revenue.unwrap_or(Decimal::ZERO),
             })
             .collect())
     }
@@ -211,8 +214,10 @@ pub struct GetInventoryTurnoverRatioQuery {
 #[derive(Debug, Serialize)]
 pub struct InventoryTurnoverRatio {
     pub ratio: f64,
-    pub average_inventory_value: f64,
-    pub cost_of_goods_sold: f64,
+    #[serde(with = "crate::money::amount")]
+    pub average_inventory_value: Decimal,
+    #[serde(with = "crate::money::amount")]
+    pub cost_of_goods_sold: Decimal,
 }
 
 #[async_trait]
@@ -230,20 +235,25 @@ impl Query for GetInventoryTurnoverRatioQuery {
             .one(&db)
             .await
             .map_err(|_| ServiceError::DatabaseError)?
-            .unwrap_or(0.0);
+            .unwrap_or(Decimal::ZERO);
 
         let cogs = OrderItem::find()
             .inner_join(Order)
             .filter(Order::Column::OrderDate.between(self.start_date, self.end_date))
             .select_only()
-            .column_as(sum(OrderItem::Column::Quantity.cast::<f64>() * OrderItem::Column::UnitCost), "cost_of_goods_sold")
+            .column_as(sum(OrderItem::Column::Quantity.cast::<Decimal>() * OrderItem::Column::UnitCost), "cost_of_goods_sold")
             .into_tuple()
             .one(&db)
             .await
             .map_err(|_| ServiceError::DatabaseError)?
-            .unwrap_or(0.0);
-
-        let ratio = if avg_inventory > 0.0 { cogs / avg_inventory } else { 0.0 };
+            .unwrap_or(Decimal::ZERO);
+
+        // The ratio is dimensionless, so it is the one value left as f64
+        let ratio = if avg_inventory > Decimal::ZERO {
+            (cogs / avg_inventory).to_string().parse().unwrap_or(0.0)
+        } else {
+            0.0
+        };
 
         Ok(InventoryTurnoverRatio {
             ratio,
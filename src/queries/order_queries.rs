@@ -7,6 +7,7 @@ use sea_orm::{
     EntityTrait, QueryFilter, QueryOrder, QuerySelect, RelationTrait,
 };
 use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
 
 use crate::{
     errors::ServiceError,
@@ -333,7 +334,7 @@ pub struct GetAverageOrderValueQuery {
 
 #[async_trait]
 impl Query for GetAverageOrderValueQuery {
-    type Result = f64;
+    type Result = Decimal;
 
     async fn execute(&self, db_pool: Arc<DbPool>) -> Result<Self::Result, ServiceError> {
         let db = get_db(&db_pool).await?;
@@ -342,7 +343,7 @@ impl Query for GetAverageOrderValueQuery {
             .select_only()
             .column_as(Function::Avg(Order::Column::TotalAmount), "average_value")
             .filter(Order::Column::CreatedAt.between(self.start_date, self.end_date))
-            .into_model::<Option<f64>>()
+            .into_model::<Option<Decimal>>()
             .one(&db)
             .await
             .map_err(|e| {
@@ -350,8 +351,8 @@ impl Query for GetAverageOrderValueQuery {
                 ServiceError::DatabaseError
             })?
             .unwrap_or(None)
-            .unwrap_or(0.0);
+            .unwrap_or(Decimal::ZERO);
 
-        Ok(average)
+        Ok(crate::money::round_currency(average))
     }
 }
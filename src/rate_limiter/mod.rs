@@ -6,7 +6,10 @@ use axum::{
 use redis::AsyncCommands;
 use slog::{info, Logger};
 use std::{
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
     time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use thiserror::Error;
@@ -27,8 +30,8 @@ pub enum RateLimitError {
 pub struct RateLimiter {
     redis: Arc<redis::aio::ConnectionManager>,
     key_prefix: String,
-    max_requests: usize,
-    window_seconds: usize,
+    max_requests: Arc<AtomicUsize>,
+    window_seconds: Arc<AtomicUsize>,
     logger: Logger,
     // To prevent multiple Lua scripts from running simultaneously for the same key
     locks: Arc<Mutex<std::collections::HashMap<String, ()>>>,
@@ -49,13 +52,23 @@ impl RateLimiter {
         Ok(Self {
             redis: Arc::new(conn_manager),
             key_prefix: key_prefix.to_string(),
-            max_requests,
-            window_seconds,
+            max_requests: Arc::new(AtomicUsize::new(max_requests)),
+            window_seconds: Arc::new(AtomicUsize::new(window_seconds)),
             logger,
             locks: Arc::new(Mutex::new(std::collections::HashMap::new())),
         })
     }
 
+    /// Changes the limits for subsequent requests; clones share the new values.
+    pub fn update_limits(&self, max_requests: usize, window_seconds: usize) {
+        self.max_requests.store(max_requests, Ordering::Relaxed);
+        self.window_seconds.store(window_seconds, Ordering::Relaxed);
+        info!(
+            self.logger,
+            "Rate limits updated"; "max_requests" => max_requests, "window_seconds" => window_seconds
+        );
+    }
+
     /// Checks if the given key has exceeded the rate limit.
     pub async fn is_rate_limited(&self, key: &str) -> Result<bool, RateLimitError> {
         let full_key = format!("{}:{}", self.key_prefix, key);
@@ -87,7 +100,7 @@ impl RateLimiter {
 
         // Execute the Lua script
         let current: usize = redis
-            .eval(lua_script, &[&full_key], &[self.window_seconds.to_string()])
+            .eval(lua_script, &[&full_key], &[self.window_seconds.load(Ordering::Relaxed).to_string()])
             .await?;
 
         // Release the lock
@@ -96,7 +109,7 @@ impl RateLimiter {
             locks.remove(&full_key);
         }
 
-        if current > self.max_requests {
+        if current > self.max_requests.load(Ordering::Relaxed) {
             info!(
                 self.logger,
                 "Rate limit exceeded"; "key" => key, "current" => current
@@ -114,8 +127,8 @@ impl Clone for RateLimiter {
         Self {
             redis: Arc::clone(&self.redis),
             key_prefix: self.key_prefix.clone(),
-            max_requests: self.max_requests,
-            window_seconds: self.window_seconds,
+            max_requests: Arc::clone(&self.max_requests),
+            window_seconds: Arc::clone(&self.window_seconds),
             logger: self.logger.clone(),
             locks: Arc::clone(&self.locks),
         }
@@ -1,17 +1,21 @@
 use serde::{Serialize, Deserialize};
+use rust_decimal::Decimal;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SalesReport {
-    pub total_sales: f64,
+    #[serde(with = "crate::money::amount")]
+    pub total_sales: Decimal,
     pub total_orders: i32,
-    pub average_order_value: f64,
+    #[serde(with = "crate::money::amount")]
+    pub average_order_value: Decimal,
     pub top_selling_products: Vec<(String, i32)>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct InventoryReport {
     pub total_products: i32,
-    pub total_stock_value: f64,
+    #[serde(with = "crate::money::amount")]
+    pub total_stock_value: Decimal,
     pub low_stock_products: Vec<(String, i32)>,
     pub out_of_stock_products: Vec<String>,
 }
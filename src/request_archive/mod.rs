@@ -0,0 +1,469 @@
+// request_archive/mod.rs
+
+use async_trait::async_trait;
+use axum::{
+    body::Body,
+    extract::State,
+    http::{HeaderMap, Request},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use prometheus::{IntCounter, IntCounterVec};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc, time::{Duration, Instant}};
+use thiserror::Error;
+use tokio::sync::mpsc;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::logging::redaction::{Redactor, REDACTED};
+
+lazy_static! {
+    static ref ARCHIVED_EXCHANGES: IntCounterVec =
+        IntCounterVec::new(
+            "request_archive_exchanges_total",
+            "Request/response pairs handled by the request archive",
+            &["outcome"]
+        ).expect("metric can be created");
+
+    static ref ARCHIVE_BATCH_FAILURES: IntCounter =
+        IntCounter::new(
+            "request_archive_batch_failures_total",
+            "Archive batches that could not be written to object storage"
+        ).expect("metric can be created");
+}
+
+/// Headers that are never archived, regardless of redaction settings.
+const DROPPED_HEADERS: &[&str] = &["authorization", "cookie", "set-cookie", "x-api-key", "x-signature"];
+
+#[derive(Error, Debug)]
+pub enum ArchiveError {
+    #[error("Failed to encode archive batch: {0}")]
+    Encode(#[from] serde_json::Error),
+
+    #[error("Failed to upload archive batch: {0}")]
+    Upload(String),
+}
+
+/// Request archive settings, loaded from the `request_archive` config section.
+#[derive(Clone, Debug, Deserialize)]
+pub struct RequestArchiveConfig {
+    /// Enables the archive (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Destination S3 bucket.
+    #[serde(default)]
+    pub bucket: String,
+
+    /// Key prefix for batch objects (default: `request-archive`).
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+
+    /// Fraction of requests archived when no route rule matches, 0.0–1.0 (default: 0.0).
+    #[serde(default)]
+    pub default_sample_rate: f64,
+
+    /// Sample rates keyed by path prefix; the longest matching prefix wins.
+    #[serde(default)]
+    pub routes: HashMap<String, f64>,
+
+    /// Exchanges per uploaded object (default: 500).
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+
+    /// Maximum seconds a partial batch waits before upload (default: 60).
+    #[serde(default = "default_flush_interval_secs")]
+    pub flush_interval_secs: u64,
+
+    /// Bodies larger than this are archived as a size marker only (default: 256 KiB).
+    #[serde(default = "default_max_body_bytes")]
+    pub max_body_bytes: usize,
+}
+
+fn default_prefix() -> String {
+    "request-archive".to_string()
+}
+
+fn default_batch_size() -> usize {
+    500
+}
+
+fn default_flush_interval_secs() -> u64 {
+    60
+}
+
+fn default_max_body_bytes() -> usize {
+    256 * 1024
+}
+
+impl Default for RequestArchiveConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bucket: String::new(),
+            prefix: default_prefix(),
+            default_sample_rate: 0.0,
+            routes: HashMap::new(),
+            batch_size: default_batch_size(),
+            flush_interval_secs: default_flush_interval_secs(),
+            max_body_bytes: default_max_body_bytes(),
+        }
+    }
+}
+
+impl RequestArchiveConfig {
+    /// Sample rate for a path: the longest configured prefix, else the default.
+    pub fn sample_rate(&self, path: &str) -> f64 {
+        self.routes
+            .iter()
+            .filter(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, rate)| *rate)
+            .unwrap_or(self.default_sample_rate)
+            .clamp(0.0, 1.0)
+    }
+}
+
+/// One archived request/response pair, written as a line of JSONL.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArchivedExchange {
+    pub id: Uuid,
+    pub timestamp: DateTime<Utc>,
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    pub status: u16,
+    pub duration_ms: u64,
+    pub request_headers: HashMap<String, String>,
+    pub request_body: String,
+    pub response_body: String,
+}
+
+/// Destination for encoded archive batches.
+#[async_trait]
+pub trait ArchiveSink: Send + Sync {
+    async fn write(&self, key: &str, body: Vec<u8>) -> Result<(), ArchiveError>;
+}
+
+/// Writes batches to S3.
+pub struct S3Sink {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+}
+
+impl S3Sink {
+    pub async fn new(bucket: String) -> Self {
+        let config = aws_config::load_from_env().await;
+        Self {
+            client: aws_sdk_s3::Client::new(&config),
+            bucket,
+        }
+    }
+}
+
+#[async_trait]
+impl ArchiveSink for S3Sink {
+    async fn write(&self, key: &str, body: Vec<u8>) -> Result<(), ArchiveError> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type("application/x-ndjson")
+            .body(body.into())
+            .send()
+            .await
+            .map_err(|e| ArchiveError::Upload(e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Encodes exchanges as newline-delimited JSON.
+pub fn encode_jsonl(batch: &[ArchivedExchange]) -> Result<Vec<u8>, ArchiveError> {
+    let mut out = Vec::new();
+    for exchange in batch {
+        serde_json::to_writer(&mut out, exchange)?;
+        out.push(b'\n');
+    }
+    Ok(out)
+}
+
+/// Object key for a batch, partitioned by date and hour for cheap querying.
+fn batch_key(prefix: &str, now: DateTime<Utc>) -> String {
+    format!(
+        "{}/dt={}/hour={}/{}.jsonl",
+        prefix.trim_end_matches('/'),
+        now.format("%Y-%m-%d"),
+        now.format("%H"),
+        Uuid::new_v4()
+    )
+}
+
+/// Samples, redacts and queues exchanges; a background task batches them to the sink.
+/// Archiving never blocks requests: when the queue is full the exchange is dropped.
+pub struct RequestArchiver {
+    config: RequestArchiveConfig,
+    redactor: Redactor,
+    sender: mpsc::Sender<ArchivedExchange>,
+}
+
+impl RequestArchiver {
+    pub fn start(config: RequestArchiveConfig, redactor: Redactor, sink: Arc<dyn ArchiveSink>) -> Arc<Self> {
+        let (sender, receiver) = mpsc::channel(config.batch_size.max(1) * 4);
+        tokio::spawn(run_batcher(
+            receiver,
+            sink,
+            config.prefix.clone(),
+            config.batch_size.max(1),
+            Duration::from_secs(config.flush_interval_secs.max(1)),
+        ));
+        Arc::new(Self { config, redactor, sender })
+    }
+
+    fn should_sample(&self, path: &str) -> bool {
+        let rate = self.config.sample_rate(path);
+        rate > 0.0 && rand::thread_rng().gen::<f64>() < rate
+    }
+
+    fn body_for_archive(&self, bytes: &[u8]) -> String {
+        if bytes.len() > self.config.max_body_bytes {
+            format!("[TRUNCATED {} bytes]", bytes.len())
+        } else {
+            self.redactor.redact_body(bytes)
+        }
+    }
+
+    fn headers_for_archive(&self, headers: &HeaderMap) -> HashMap<String, String> {
+        headers
+            .iter()
+            .filter(|(name, _)| !DROPPED_HEADERS.contains(&name.as_str()))
+            .map(|(name, value)| {
+                let value = if self.redactor.is_sensitive(name.as_str()) {
+                    REDACTED.to_string()
+                } else {
+                    value.to_str().unwrap_or("[binary]").to_string()
+                };
+                (name.as_str().to_string(), value)
+            })
+            .collect()
+    }
+
+    fn enqueue(&self, exchange: ArchivedExchange) {
+        match self.sender.try_send(exchange) {
+            Ok(()) => ARCHIVED_EXCHANGES.with_label_values(&["queued"]).inc(),
+            Err(_) => ARCHIVED_EXCHANGES.with_label_values(&["dropped"]).inc(),
+        }
+    }
+}
+
+async fn run_batcher(
+    mut receiver: mpsc::Receiver<ArchivedExchange>,
+    sink: Arc<dyn ArchiveSink>,
+    prefix: String,
+    batch_size: usize,
+    flush_interval: Duration,
+) {
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut ticker = tokio::time::interval(flush_interval);
+
+    loop {
+        let closed = tokio::select! {
+            exchange = receiver.recv() => match exchange {
+                Some(exchange) => {
+                    batch.push(exchange);
+                    if batch.len() < batch_size {
+                        continue;
+                    }
+                    false
+                }
+                None => true,
+            },
+            _ = ticker.tick() => false,
+        };
+
+        if !batch.is_empty() {
+            flush(&sink, &prefix, std::mem::take(&mut batch)).await;
+        }
+        if closed {
+            info!("Request archive queue closed, batcher stopping");
+            return;
+        }
+    }
+}
+
+async fn flush(sink: &Arc<dyn ArchiveSink>, prefix: &str, batch: Vec<ArchivedExchange>) {
+    let count = batch.len();
+    let result = match encode_jsonl(&batch) {
+        Ok(body) => sink.write(&batch_key(prefix, Utc::now()), body).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        ARCHIVE_BATCH_FAILURES.inc();
+        error!(exchanges = count, "Failed to archive request batch: {}", e);
+    }
+}
+
+/// Middleware archiving sampled request/response pairs after redaction. Unsampled
+/// requests pass through without their bodies being buffered.
+pub async fn request_archive_middleware(
+    State(archiver): State<Arc<RequestArchiver>>,
+    req: Request<Body>,
+    next: Next<Body>,
+) -> Response {
+    if !archiver.should_sample(req.uri().path()) {
+        return next.run(req).await;
+    }
+
+    let started = Instant::now();
+    let (parts, body) = req.into_parts();
+    let request_bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Failed to buffer request body for archive: {}", e);
+            return next.run(Request::from_parts(parts, Body::empty())).await;
+        }
+    };
+
+    let mut exchange = ArchivedExchange {
+        id: Uuid::new_v4(),
+        timestamp: Utc::now(),
+        method: parts.method.to_string(),
+        path: parts.uri.path().to_string(),
+        query: parts.uri.query().map(|q| archiver.redactor.redact_query(q)),
+        status: 0,
+        duration_ms: 0,
+        request_headers: archiver.headers_for_archive(&parts.headers),
+        request_body: archiver.body_for_archive(&request_bytes),
+        response_body: String::new(),
+    };
+
+    let response = next.run(Request::from_parts(parts, Body::from(request_bytes))).await;
+    let (parts, body) = response.into_parts();
+    let response_bytes = match hyper::body::to_bytes(body).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            warn!("Failed to buffer response body for archive: {}", e);
+            return Response::from_parts(parts, axum::body::boxed(Body::empty()));
+        }
+    };
+
+    exchange.status = parts.status.as_u16();
+    exchange.duration_ms = started.elapsed().as_millis() as u64;
+    exchange.response_body = archiver.body_for_archive(&response_bytes);
+    archiver.enqueue(exchange);
+
+    Response::from_parts(parts, axum::body::boxed(Body::from(response_bytes)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::Mutex;
+
+    #[derive(Default)]
+    struct MemorySink {
+        objects: Mutex<Vec<(String, Vec<u8>)>>,
+    }
+
+    #[async_trait]
+    impl ArchiveSink for MemorySink {
+        async fn write(&self, key: &str, body: Vec<u8>) -> Result<(), ArchiveError> {
+            self.objects.lock().await.push((key.to_string(), body));
+            Ok(())
+        }
+    }
+
+    fn exchange(path: &str) -> ArchivedExchange {
+        ArchivedExchange {
+            id: Uuid::new_v4(),
+            timestamp: Utc::now(),
+            method: "POST".to_string(),
+            path: path.to_string(),
+            query: None,
+            status: 200,
+            duration_ms: 3,
+            request_headers: HashMap::new(),
+            request_body: "{}".to_string(),
+            response_body: "{}".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_longest_route_prefix_wins() {
+        let mut routes = HashMap::new();
+        routes.insert("/orders".to_string(), 0.5);
+        routes.insert("/orders/bulk".to_string(), 1.0);
+        routes.insert("/health".to_string(), 0.0);
+        let config = RequestArchiveConfig {
+            default_sample_rate: 0.1,
+            routes,
+            ..Default::default()
+        };
+
+        assert_eq!(config.sample_rate("/orders/bulk/import"), 1.0);
+        assert_eq!(config.sample_rate("/orders/123"), 0.5);
+        assert_eq!(config.sample_rate("/health"), 0.0);
+        assert_eq!(config.sample_rate("/inventory"), 0.1);
+    }
+
+    #[test]
+    fn test_jsonl_encoding() {
+        let batch = vec![exchange("/a"), exchange("/b")];
+        let encoded = String::from_utf8(encode_jsonl(&batch).unwrap()).unwrap();
+        let lines: Vec<&str> = encoded.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let decoded: ArchivedExchange = serde_json::from_str(lines[1]).unwrap();
+        assert_eq!(decoded.path, "/b");
+    }
+
+    #[test]
+    fn test_sensitive_headers_not_archived() {
+        let (sender, _receiver) = mpsc::channel(1);
+        let archiver = RequestArchiver {
+            config: RequestArchiveConfig::default(),
+            redactor: Redactor::default(),
+            sender,
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", "Bearer abc".parse().unwrap());
+        headers.insert("x-api-key", "ssk_abc".parse().unwrap());
+        headers.insert("content-type", "application/json".parse().unwrap());
+
+        let archived = archiver.headers_for_archive(&headers);
+        assert_eq!(archived.len(), 1);
+        assert_eq!(archived["content-type"], "application/json");
+    }
+
+    #[tokio::test]
+    async fn test_batches_flush_when_full() {
+        let sink = Arc::new(MemorySink::default());
+        let archiver = RequestArchiver::start(
+            RequestArchiveConfig {
+                enabled: true,
+                batch_size: 2,
+                flush_interval_secs: 3600,
+                ..Default::default()
+            },
+            Redactor::default(),
+            sink.clone(),
+        );
+
+        archiver.enqueue(exchange("/a"));
+        archiver.enqueue(exchange("/b"));
+
+        for _ in 0..50 {
+            if !sink.objects.lock().await.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let objects = sink.objects.lock().await;
+        assert_eq!(objects.len(), 1);
+        assert!(objects[0].0.starts_with("request-archive/dt="));
+        assert_eq!(String::from_utf8_lossy(&objects[0].1).lines().count(), 2);
+    }
+}
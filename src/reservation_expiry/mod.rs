@@ -0,0 +1,157 @@
+// reservation_expiry/mod.rs
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use lazy_static::lazy_static;
+use prometheus::IntCounter;
+use sea_orm::{
+    sea_query::{LockBehavior, LockType},
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter,
+    QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use serde::Deserialize;
+use std::{sync::Arc, time::Duration};
+use tracing::{error, info};
+
+use crate::events::{Event, EventSender};
+use crate::models::inventory_reservation_entity::{self, Entity as InventoryReservation, ReservationStatus};
+
+lazy_static! {
+    static ref RESERVATIONS_EXPIRED: IntCounter =
+        IntCounter::new("inventory_reservations_expired_total", "Reservations released because they expired")
+            .expect("metric can be created");
+}
+
+/// Release reason recorded on expired reservations.
+pub const EXPIRED_REASON: &str = "RESERVATION_EXPIRED";
+
+/// Shortest and longest hold a caller may request in seconds.
+pub const MIN_TTL_SECS: i64 = 60;
+pub const MAX_TTL_SECS: i64 = 365 * 24 * 60 * 60;
+
+/// Reservations expired per transaction; the sweep loops until none are due.
+const BATCH_SIZE: u64 = 500;
+
+/// Expiry sweep settings, loaded from the `reservation_expiry` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ReservationExpiryConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Seconds between sweeps. Expired holds stop counting against availability
+    /// immediately; the sweep records the release and emits `ReservationExpired`.
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    30
+}
+
+impl Default for ReservationExpiryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_secs: default_interval_secs(),
+        }
+    }
+}
+
+/// When a hold placed at `now` expires. A TTL in seconds (cart holds) takes precedence
+/// over a duration in days; without either the hold lasts a week.
+pub fn expiration_for(now: DateTime<Utc>, ttl_secs: Option<i64>, duration_days: Option<i32>) -> DateTime<Utc> {
+    match (ttl_secs, duration_days) {
+        (Some(ttl), _) => now + ChronoDuration::seconds(ttl.clamp(MIN_TTL_SECS, MAX_TTL_SECS)),
+        (None, Some(days)) => now + ChronoDuration::days(i64::from(days)),
+        (None, None) => now + ChronoDuration::days(7),
+    }
+}
+
+/// Marks active reservations past their expiration date as expired and emits
+/// `ReservationExpired` for each. Rows locked by a concurrent checkout are skipped and
+/// picked up by the next sweep, so a conversion never races an expiry.
+pub async fn expire_due(db: &DatabaseConnection, events: &EventSender) -> Result<u64, DbErr> {
+    let mut total = 0;
+    loop {
+        let now = Utc::now().naive_utc();
+        let txn = db.begin().await?;
+        let due = InventoryReservation::find()
+            .filter(inventory_reservation_entity::Column::Status.eq(ReservationStatus::Active.to_string()))
+            .filter(inventory_reservation_entity::Column::ExpirationDate.lte(now))
+            .order_by_asc(inventory_reservation_entity::Column::ExpirationDate)
+            .limit(BATCH_SIZE)
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+            .all(&txn)
+            .await?;
+        let batch = due.len() as u64;
+
+        let mut expired = Vec::with_capacity(due.len());
+        for reservation in due {
+            let mut active: inventory_reservation_entity::ActiveModel = reservation.clone().into();
+            active.status = Set(ReservationStatus::Expired.to_string());
+            active.release_date = Set(Some(now));
+            active.release_reason = Set(Some(EXPIRED_REASON.to_string()));
+            active.update(&txn).await?;
+            expired.push(reservation);
+        }
+        txn.commit().await?;
+
+        // Events go out only once the release is durable
+        for reservation in expired {
+            let _ = events.send(Event::ReservationExpired {
+                reservation_id: reservation.id,
+                reference_id: reservation.reference_id,
+                reference_type: reservation.reference_type,
+                warehouse_id: reservation.warehouse_id,
+                product_id: reservation.product_id,
+                quantity: reservation.quantity,
+            });
+        }
+        RESERVATIONS_EXPIRED.inc_by(batch);
+        total += batch;
+        if batch < BATCH_SIZE {
+            break;
+        }
+    }
+    if total > 0 {
+        info!(reservations = total, "Expired inventory reservations released");
+    }
+    Ok(total)
+}
+
+pub fn spawn_scheduler(db: Arc<DatabaseConnection>, events: EventSender, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval.max(Duration::from_secs(1)));
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = expire_due(&db, &events).await {
+                error!("Reservation expiry sweep failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_ttl_takes_precedence_over_days() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        assert_eq!(expiration_for(now, Some(900), Some(3)), now + ChronoDuration::minutes(15));
+        assert_eq!(expiration_for(now, None, Some(3)), now + ChronoDuration::days(3));
+        assert_eq!(expiration_for(now, None, None), now + ChronoDuration::days(7));
+    }
+
+    #[test]
+    fn test_ttl_is_clamped() {
+        let now = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        assert_eq!(expiration_for(now, Some(5), None), now + ChronoDuration::seconds(MIN_TTL_SECS));
+        assert_eq!(expiration_for(now, Some(i64::MAX), None), now + ChronoDuration::seconds(MAX_TTL_SECS));
+    }
+}
@@ -0,0 +1,396 @@
+// retention/mod.rs
+
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use lazy_static::lazy_static;
+use prometheus::IntCounterVec;
+use sea_orm::{
+    ActiveModelTrait, ConnectionTrait, DatabaseConnection, DbBackend, DbErr, Set, Statement,
+    TransactionTrait,
+};
+use serde::Deserialize;
+use std::{sync::Arc, time::Duration};
+use thiserror::Error;
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+
+use crate::db::dialect;
+use crate::models::retention_run;
+use crate::request_archive::{ArchiveError, ArchiveSink};
+
+lazy_static! {
+    static ref RETENTION_ROWS: IntCounterVec =
+        IntCounterVec::new(
+            "retention_rows_total",
+            "Rows archived, exported or purged by retention policies",
+            &["policy", "action"]
+        ).expect("metric can be created");
+}
+
+#[derive(Error, Debug)]
+pub enum RetentionError {
+    #[error("Invalid retention policy '{policy}': {reason}")]
+    InvalidPolicy { policy: String, reason: String },
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+
+    #[error("Export failed: {0}")]
+    Export(#[from] ArchiveError),
+
+    #[error("Policy '{0}' exports rows but no cold storage sink is configured")]
+    NoSink(String),
+}
+
+/// What happens to rows past their retention period.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionAction {
+    /// Move rows into `<archive_table>` in the same database.
+    Archive,
+    /// Write rows as JSONL to cold storage, then delete them.
+    Export,
+    /// Delete rows.
+    Purge,
+}
+
+impl RetentionAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            RetentionAction::Archive => "archive",
+            RetentionAction::Export => "export",
+            RetentionAction::Purge => "purge",
+        }
+    }
+}
+
+/// A per-entity retention policy.
+#[derive(Clone, Debug, Deserialize)]
+pub struct RetentionPolicy {
+    /// Policy name used in logs, metrics and the `retention_runs` table.
+    pub name: String,
+
+    /// Table the policy applies to.
+    pub table: String,
+
+    /// Timestamp column compared against the cutoff (default: `created_at`).
+    #[serde(default = "default_timestamp_column")]
+    pub timestamp_column: String,
+
+    /// Primary key columns rows are deleted by (default: `id`). Partitioned tables list
+    /// the partition column too.
+    #[serde(default = "default_primary_key")]
+    pub primary_key: Vec<String>,
+
+    pub action: RetentionAction,
+
+    /// Rows older than this many days are affected.
+    pub max_age_days: i64,
+
+    /// Target table for `archive`; defaults to `<table>_archive`.
+    pub archive_table: Option<String>,
+
+    /// Rows handled per statement, to keep locks short (default: 1000).
+    #[serde(default = "default_batch_size")]
+    pub batch_size: u64,
+}
+
+fn default_timestamp_column() -> String {
+    "created_at".to_string()
+}
+
+fn default_primary_key() -> Vec<String> {
+    vec!["id".to_string()]
+}
+
+fn default_batch_size() -> u64 {
+    1000
+}
+
+/// Retention settings, loaded from the `retention` config section.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct RetentionConfig {
+    /// Enables scheduled retention runs (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Seconds between runs (default: daily).
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+
+    /// Cold storage bucket for `export` policies.
+    pub export_bucket: Option<String>,
+
+    #[serde(default)]
+    pub policies: Vec<RetentionPolicy>,
+}
+
+fn default_interval_secs() -> u64 {
+    24 * 60 * 60
+}
+
+/// Only plain SQL identifiers are accepted so policy config can never inject SQL.
+fn valid_identifier(ident: &str) -> bool {
+    !ident.is_empty()
+        && ident.len() <= 63
+        && ident.chars().next().map_or(false, |c| c.is_ascii_lowercase() || c == '_')
+        && ident.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+impl RetentionPolicy {
+    pub fn validate(&self) -> Result<(), RetentionError> {
+        let invalid = |reason: String| RetentionError::InvalidPolicy {
+            policy: self.name.clone(),
+            reason,
+        };
+
+        let archive_table = self.archive_table();
+        let identifiers = [&self.table, &self.timestamp_column, &archive_table];
+        for ident in identifiers.into_iter().chain(&self.primary_key) {
+            if !valid_identifier(ident) {
+                return Err(invalid(format!("'{}' is not a valid identifier", ident)));
+            }
+        }
+        if self.primary_key.is_empty() {
+            return Err(invalid("primary_key must name at least one column".to_string()));
+        }
+        if self.max_age_days < 1 {
+            return Err(invalid("max_age_days must be at least 1".to_string()));
+        }
+        if self.batch_size == 0 {
+            return Err(invalid("batch_size must be greater than zero".to_string()));
+        }
+        Ok(())
+    }
+
+    fn archive_table(&self) -> String {
+        self.archive_table
+            .clone()
+            .unwrap_or_else(|| format!("{}_archive", self.table))
+    }
+
+    pub fn cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - ChronoDuration::days(self.max_age_days)
+    }
+
+    /// Deletes one batch of expired rows and returns them; the outer statement
+    /// decides what happens with the returned rows. Rows are matched by primary key: a
+    /// `ctid` changes when a concurrent update moves the row and repeats across
+    /// partitions, so it could delete a different row than the one selected.
+    fn delete_batch_cte(&self) -> String {
+        let key = self.primary_key.join(", ");
+        format!(
+            "WITH expired AS (DELETE FROM {table} WHERE ({key}) IN \
+             (SELECT {key} FROM {table} WHERE {ts} < $1 ORDER BY {ts} LIMIT $2 FOR UPDATE SKIP LOCKED) \
+             RETURNING *)",
+            table = self.table,
+            key = key,
+            ts = self.timestamp_column,
+        )
+    }
+
+    fn batch_sql(&self) -> String {
+        match self.action {
+            RetentionAction::Archive => format!(
+                "{} INSERT INTO {} SELECT * FROM expired",
+                self.delete_batch_cte(),
+                self.archive_table()
+            ),
+            RetentionAction::Export => format!(
+                "{} SELECT row_to_json(expired)::text AS row FROM expired",
+                self.delete_batch_cte()
+            ),
+            RetentionAction::Purge => format!("{} SELECT count(*) FROM expired", self.delete_batch_cte()),
+        }
+    }
+}
+
+/// Runs retention policies and records each run in `retention_runs`.
+pub struct RetentionRunner {
+    db: Arc<DatabaseConnection>,
+    policies: Vec<RetentionPolicy>,
+    export_sink: Option<Arc<dyn ArchiveSink>>,
+}
+
+impl RetentionRunner {
+    /// Validates all policies up front so a typo fails at startup, not at 3am.
+    pub fn new(
+        db: Arc<DatabaseConnection>,
+        policies: Vec<RetentionPolicy>,
+        export_sink: Option<Arc<dyn ArchiveSink>>,
+    ) -> Result<Self, RetentionError> {
+        for policy in &policies {
+            policy.validate()?;
+            if policy.action == RetentionAction::Export && export_sink.is_none() {
+                return Err(RetentionError::NoSink(policy.name.clone()));
+            }
+        }
+        Ok(Self { db, policies, export_sink })
+    }
+
+    /// Runs every policy once. A failing policy is recorded and does not stop the others.
+    pub async fn run_all(&self) {
+        for policy in &self.policies {
+            let started_at = Utc::now();
+            let cutoff = policy.cutoff(started_at);
+            let (rows, export_key, error) = match self.run_policy(policy, cutoff).await {
+                Ok((rows, key)) => (rows, key, None),
+                Err(e) => {
+                    error!(policy = %policy.name, "Retention policy failed: {}", e);
+                    (0, None, Some(e.to_string()))
+                }
+            };
+
+            let record = retention_run::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                policy: Set(policy.name.clone()),
+                table_name: Set(policy.table.clone()),
+                action: Set(policy.action.as_str().to_string()),
+                cutoff: Set(cutoff),
+                rows_affected: Set(rows as i64),
+                export_key: Set(export_key),
+                error: Set(error),
+                started_at: Set(started_at),
+                finished_at: Set(Utc::now()),
+            };
+            if let Err(e) = record.insert(self.db.as_ref()).await {
+                error!(policy = %policy.name, "Failed to record retention run: {}", e);
+            }
+        }
+    }
+
+    /// Processes batches until no expired rows remain. Returns rows affected and, for
+    /// exports, the key prefix the rows were written under.
+    #[instrument(skip(self, policy), fields(policy = %policy.name))]
+    async fn run_policy(
+        &self,
+        policy: &RetentionPolicy,
+        cutoff: DateTime<Utc>,
+    ) -> Result<(u64, Option<String>), RetentionError> {
+        // Batches delete through a data-modifying CTE
+        dialect::require_postgres(self.db.as_ref(), "Retention policies")?;
+        let sql = policy.batch_sql();
+        let export_prefix = format!(
+            "retention/{}/{}",
+            policy.name,
+            Utc::now().format("%Y-%m-%dT%H%M%SZ")
+        );
+        let mut total = 0u64;
+        let mut part = 0;
+
+        loop {
+            let txn = self.db.begin().await?;
+            let stmt = Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                &sql,
+                [cutoff.into(), (policy.batch_size as i64).into()],
+            );
+
+            let affected = match policy.action {
+                RetentionAction::Archive => txn.execute(stmt).await?.rows_affected(),
+                RetentionAction::Purge => {
+                    let row = txn.query_one(stmt).await?;
+                    row.map(|r| r.try_get_by_index::<i64>(0)).transpose()?.unwrap_or(0) as u64
+                }
+                RetentionAction::Export => {
+                    let rows = txn.query_all(stmt).await?;
+                    if !rows.is_empty() {
+                        let mut body = Vec::new();
+                        for row in &rows {
+                            body.extend_from_slice(row.try_get_by_index::<String>(0)?.as_bytes());
+                            body.push(b'\n');
+                        }
+                        let sink = self
+                            .export_sink
+                            .as_ref()
+                            .ok_or_else(|| RetentionError::NoSink(policy.name.clone()))?;
+                        // Upload before commit so rows are only deleted once safely stored
+                        sink.write(&format!("{}/part-{:05}.jsonl", export_prefix, part), body).await?;
+                        part += 1;
+                    }
+                    rows.len() as u64
+                }
+            };
+
+            txn.commit().await?;
+            total += affected;
+            RETENTION_ROWS
+                .with_label_values(&[&policy.name, policy.action.as_str()])
+                .inc_by(affected);
+
+            if affected < policy.batch_size {
+                break;
+            }
+        }
+
+        info!(policy = %policy.name, action = policy.action.as_str(), rows = total, %cutoff, "Retention policy completed");
+        let export_key = (policy.action == RetentionAction::Export && total > 0).then_some(export_prefix);
+        Ok((total, export_key))
+    }
+}
+
+/// Runs all policies at the configured interval.
+pub fn spawn_scheduler(runner: Arc<RetentionRunner>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            runner.run_all().await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(action: RetentionAction) -> RetentionPolicy {
+        RetentionPolicy {
+            name: "orders".to_string(),
+            table: "orders".to_string(),
+            timestamp_column: "created_date".to_string(),
+            primary_key: vec!["id".to_string()],
+            action,
+            max_age_days: 7 * 365,
+            archive_table: None,
+            batch_size: 500,
+        }
+    }
+
+    #[test]
+    fn test_identifiers_validated() {
+        assert!(policy(RetentionAction::Archive).validate().is_ok());
+
+        let mut bad = policy(RetentionAction::Purge);
+        bad.table = "orders; DROP TABLE customers".to_string();
+        assert!(matches!(bad.validate(), Err(RetentionError::InvalidPolicy { .. })));
+
+        let mut bad = policy(RetentionAction::Purge);
+        bad.max_age_days = 0;
+        assert!(bad.validate().is_err());
+
+        let mut bad = policy(RetentionAction::Purge);
+        bad.primary_key = Vec::new();
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn test_batch_sql_per_action() {
+        let archive = policy(RetentionAction::Archive).batch_sql();
+        assert!(archive.contains("DELETE FROM orders"));
+        assert!(archive.contains("created_date < $1"));
+        assert!(archive.contains("WHERE (id) IN (SELECT id FROM orders"));
+        assert!(!archive.contains("ctid"));
+        assert!(archive.ends_with("INSERT INTO orders_archive SELECT * FROM expired"));
+
+        assert!(policy(RetentionAction::Export).batch_sql().contains("row_to_json(expired)"));
+        assert!(policy(RetentionAction::Purge).batch_sql().ends_with("SELECT count(*) FROM expired"));
+    }
+
+    #[test]
+    fn test_cutoff() {
+        let now = Utc::now();
+        let mut p = policy(RetentionAction::Purge);
+        p.max_age_days = 30;
+        assert_eq!(now - p.cutoff(now), ChronoDuration::days(30));
+    }
+}
@@ -0,0 +1,559 @@
+// semantic_search/mod.rs
+
+use async_trait::async_trait;
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use rust_decimal::Decimal;
+use sea_orm::{
+    sea_query::{Expr, Func},
+    ColumnTrait, Condition, ConnectionTrait, DatabaseConnection, DbBackend, DbErr, EntityTrait,
+    FromQueryResult, QueryFilter, QuerySelect, Statement,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, sync::Arc};
+use thiserror::Error;
+use tokio::sync::broadcast;
+use tracing::{error, info, warn};
+
+use crate::db::dialect;
+use crate::embeddings::{EmbeddingError, EmbeddingProvider};
+use crate::events::{Event, EventSender};
+use crate::models::product_listing::{self, Entity as ProductListing};
+
+/// Semantic search settings, loaded from the `semantic_search` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SemanticSearchConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Share of the score that comes from keyword matches, `0.0..=1.0` (default: 0.3).
+    #[serde(default = "default_keyword_weight")]
+    pub keyword_weight: f32,
+
+    /// Candidates fetched from each of the vector and keyword searches before ranking.
+    #[serde(default = "default_candidates")]
+    pub candidates: u64,
+}
+
+fn default_keyword_weight() -> f32 {
+    0.3
+}
+
+fn default_candidates() -> u64 {
+    50
+}
+
+impl Default for SemanticSearchConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            keyword_weight: default_keyword_weight(),
+            candidates: default_candidates(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SearchError {
+    #[error("Search query must not be empty")]
+    EmptyQuery,
+
+    #[error(transparent)]
+    Embedding(#[from] EmbeddingError),
+
+    #[error("Semantic search is disabled")]
+    Disabled,
+
+    #[error("Semantic search schema: {0}")]
+    Schema(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for SearchError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            SearchError::EmptyQuery => (StatusCode::BAD_REQUEST, "empty_query"),
+            SearchError::Embedding(e) => {
+                error!("Query embedding failed: {}", e);
+                (StatusCode::BAD_GATEWAY, "embedding_failed")
+            }
+            SearchError::Disabled | SearchError::Schema(_) => (StatusCode::SERVICE_UNAVAILABLE, "search_unavailable"),
+            SearchError::Database(e) => {
+                error!("Semantic search query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "search_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// Storage for product embeddings with nearest-neighbour lookup. pgvector is the
+/// default; an external engine such as Qdrant can be plugged in by implementing this.
+#[async_trait]
+pub trait VectorStore: Send + Sync {
+    async fn upsert(&self, sku: &str, embedding: &[f32], content_hash: &str) -> Result<(), SearchError>;
+
+    async fn delete(&self, sku: &str) -> Result<(), SearchError>;
+
+    /// Content hashes of the given SKUs that are indexed.
+    async fn content_hashes(&self, skus: &[String]) -> Result<HashMap<String, String>, SearchError>;
+
+    /// The `limit` SKUs closest to `embedding`, with cosine similarity in `-1.0..=1.0`.
+    async fn nearest(&self, embedding: &[f32], limit: u64) -> Result<Vec<(String, f32)>, SearchError>;
+}
+
+/// Embeddings in the `product_embeddings` table, searched with pgvector's cosine distance.
+pub struct PgVectorStore {
+    db: Arc<DatabaseConnection>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct Neighbour {
+    sku: String,
+    similarity: f64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct EmbeddingColumn {
+    column_type: String,
+}
+
+/// The declared type of the embedding column, e.g. `vector(1536)`; no row without the table.
+const EMBEDDING_COLUMN_SQL: &str = "SELECT format_type(a.atttypid, a.atttypmod) AS column_type \
+     FROM pg_attribute a \
+     WHERE a.attrelid = to_regclass('product_embeddings') AND a.attname = 'embedding' AND NOT a.attisdropped";
+
+#[derive(Debug, FromQueryResult)]
+struct IndexedHash {
+    sku: String,
+    content_hash: String,
+}
+
+/// pgvector's text input format, e.g. `[0.1,0.2]`.
+fn vector_literal(embedding: &[f32]) -> String {
+    let values: Vec<String> = embedding.iter().map(f32::to_string).collect();
+    format!("[{}]", values.join(","))
+}
+
+impl PgVectorStore {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    /// Checks that the `product_embeddings` migration created the table and that its
+    /// column is as wide as the provider's embeddings.
+    pub async fn check_schema(&self, dimensions: usize) -> Result<(), SearchError> {
+        dialect::require_postgres(self.db.as_ref(), "Semantic search")?;
+        let column = EmbeddingColumn::find_by_statement(Statement::from_string(DbBackend::Postgres, EMBEDDING_COLUMN_SQL))
+            .one(self.db.as_ref())
+            .await?;
+        let expected = format!("vector({})", dimensions);
+        match column {
+            None => Err(SearchError::Schema(
+                "product_embeddings is missing; install pgvector and run the migrations".to_string(),
+            )),
+            Some(column) if column.column_type != expected => Err(SearchError::Schema(format!(
+                "product_embeddings.embedding is {} but the embedding provider returns {}; \
+                 recreate the table in a migration",
+                column.column_type, expected
+            ))),
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl VectorStore for PgVectorStore {
+    async fn upsert(&self, sku: &str, embedding: &[f32], content_hash: &str) -> Result<(), SearchError> {
+        self.db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "INSERT INTO product_embeddings (sku, embedding, content_hash, updated_at) \
+                 VALUES ($1, $2::vector, $3, $4) \
+                 ON CONFLICT (sku) DO UPDATE SET embedding = EXCLUDED.embedding, \
+                 content_hash = EXCLUDED.content_hash, updated_at = EXCLUDED.updated_at",
+                [
+                    sku.into(),
+                    vector_literal(embedding).into(),
+                    content_hash.into(),
+                    Utc::now().into(),
+                ],
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, sku: &str) -> Result<(), SearchError> {
+        self.db
+            .execute(Statement::from_sql_and_values(
+                DbBackend::Postgres,
+                "DELETE FROM product_embeddings WHERE sku = $1",
+                [sku.into()],
+            ))
+            .await?;
+        Ok(())
+    }
+
+    async fn content_hashes(&self, skus: &[String]) -> Result<HashMap<String, String>, SearchError> {
+        if skus.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let placeholders: Vec<String> = (1..=skus.len()).map(|i| format!("${}", i)).collect();
+        let rows = IndexedHash::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            format!(
+                "SELECT sku, content_hash FROM product_embeddings WHERE sku IN ({})",
+                placeholders.join(", ")
+            ),
+            skus.iter().map(|sku| sku.as_str().into()),
+        ))
+        .all(self.db.as_ref())
+        .await?;
+        Ok(rows.into_iter().map(|row| (row.sku, row.content_hash)).collect())
+    }
+
+    async fn nearest(&self, embedding: &[f32], limit: u64) -> Result<Vec<(String, f32)>, SearchError> {
+        let rows = Neighbour::find_by_statement(Statement::from_sql_and_values(
+            DbBackend::Postgres,
+            "SELECT sku, 1 - (embedding <=> $1::vector) AS similarity \
+             FROM product_embeddings ORDER BY embedding <=> $1::vector LIMIT $2",
+            [vector_literal(embedding).into(), (limit as i64).into()],
+        ))
+        .all(self.db.as_ref())
+        .await?;
+        Ok(rows.into_iter().map(|row| (row.sku, row.similarity as f32)).collect())
+    }
+}
+
+/// The text embedded for a listing.
+pub fn embedding_text(listing: &product_listing::Model) -> String {
+    let mut text = listing.title.clone();
+    for extra in [&listing.brand, &listing.color, &listing.size].into_iter().flatten() {
+        text.push_str(", ");
+        text.push_str(extra);
+    }
+    if !listing.description.is_empty() {
+        text.push('\n');
+        text.push_str(&listing.description);
+    }
+    text
+}
+
+/// Identifies the embedded text so unchanged listings are not re-embedded.
+pub fn content_hash(text: &str) -> String {
+    hex::encode(Sha256::digest(text.as_bytes()))
+}
+
+/// Lower-cased query words, ignoring one-letter noise.
+pub fn query_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = query
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|term| term.chars().count() > 1)
+        .map(str::to_lowercase)
+        .collect();
+    terms.dedup();
+    terms
+}
+
+/// Share of `terms` found in the listing; a title match counts fully, a description
+/// match half.
+pub fn keyword_score(listing: &product_listing::Model, terms: &[String]) -> f32 {
+    if terms.is_empty() {
+        return 0.0;
+    }
+    let title = listing.title.to_lowercase();
+    let description = listing.description.to_lowercase();
+    let matched: f32 = terms
+        .iter()
+        .map(|term| {
+            if title.contains(term.as_str()) {
+                1.0
+            } else if description.contains(term.as_str()) {
+                0.5
+            } else {
+                0.0
+            }
+        })
+        .sum();
+    matched / terms.len() as f32
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub sku: String,
+    pub title: String,
+    pub description: String,
+    #[serde(with = "crate::money::amount")]
+    pub price: Decimal,
+    pub currency: String,
+    pub score: f32,
+    pub vector_score: f32,
+    pub keyword_score: f32,
+}
+
+/// Blends vector similarity with keyword overlap and sorts best first. Candidates
+/// missing from the vector results (e.g. not yet embedded) score on keywords alone.
+pub fn hybrid_rank(
+    listings: Vec<product_listing::Model>,
+    similarities: &HashMap<String, f32>,
+    terms: &[String],
+    keyword_weight: f32,
+) -> Vec<SearchHit> {
+    let keyword_weight = keyword_weight.clamp(0.0, 1.0);
+    let mut hits: Vec<SearchHit> = listings
+        .into_iter()
+        .map(|listing| {
+            let vector_score = similarities.get(&listing.sku).copied().unwrap_or(0.0).max(0.0);
+            let keyword_score = keyword_score(&listing, terms);
+            SearchHit {
+                score: (1.0 - keyword_weight) * vector_score + keyword_weight * keyword_score,
+                vector_score,
+                keyword_score,
+                sku: listing.sku,
+                title: listing.title,
+                description: listing.description,
+                price: listing.price,
+                currency: listing.currency,
+            }
+        })
+        .collect();
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.sku.cmp(&b.sku)));
+    hits
+}
+
+/// Keeps product embeddings current and answers hybrid keyword + vector queries.
+pub struct SemanticSearchService {
+    db: Arc<DatabaseConnection>,
+    store: Arc<dyn VectorStore>,
+    embeddings: Arc<dyn EmbeddingProvider>,
+    config: SemanticSearchConfig,
+}
+
+impl SemanticSearchService {
+    pub fn new(
+        db: Arc<DatabaseConnection>,
+        store: Arc<dyn VectorStore>,
+        embeddings: Arc<dyn EmbeddingProvider>,
+        config: SemanticSearchConfig,
+    ) -> Self {
+        Self { db, store, embeddings, config }
+    }
+
+    /// Embeds the listing for `sku` if its text changed; inactive or deleted listings
+    /// are removed from the index.
+    pub async fn index(&self, sku: &str) -> Result<(), SearchError> {
+        let listing = ProductListing::find_by_id(sku.to_string()).one(self.db.as_ref()).await?;
+        match listing {
+            Some(listing) if listing.active => self.index_listings(vec![listing]).await.map(|_| ()),
+            _ => self.store.delete(sku).await,
+        }
+    }
+
+    /// Embeds every active listing that is missing from the index or out of date.
+    pub async fn backfill(&self) -> Result<usize, SearchError> {
+        let listings = ProductListing::find()
+            .filter(product_listing::Column::Active.eq(true))
+            .all(self.db.as_ref())
+            .await?;
+        let mut indexed = 0;
+        for chunk in listings.chunks(100) {
+            indexed += self.index_listings(chunk.to_vec()).await?;
+        }
+        if indexed > 0 {
+            info!(listings = indexed, "Product embeddings refreshed");
+        }
+        Ok(indexed)
+    }
+
+    async fn index_listings(&self, listings: Vec<product_listing::Model>) -> Result<usize, SearchError> {
+        let skus: Vec<String> = listings.iter().map(|l| l.sku.clone()).collect();
+        let indexed = self.store.content_hashes(&skus).await?;
+        let stale: Vec<(String, String, String)> = listings
+            .iter()
+            .map(|listing| {
+                let text = embedding_text(listing);
+                (listing.sku.clone(), content_hash(&text), text)
+            })
+            .filter(|(sku, hash, _)| indexed.get(sku) != Some(hash))
+            .collect();
+        if stale.is_empty() {
+            return Ok(0);
+        }
+
+        let texts: Vec<String> = stale.iter().map(|(_, _, text)| text.clone()).collect();
+        let vectors = self.embeddings.embed(&texts).await?;
+        for ((sku, hash, _), vector) in stale.iter().zip(&vectors) {
+            self.store.upsert(sku, vector, hash).await?;
+        }
+        Ok(stale.len())
+    }
+
+    /// Active listings ranked by a blend of semantic similarity and keyword overlap.
+    pub async fn search(
+        &self,
+        query: &str,
+        limit: u64,
+        keyword_weight: Option<f32>,
+    ) -> Result<Vec<SearchHit>, SearchError> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Err(SearchError::EmptyQuery);
+        }
+        let terms = query_terms(query);
+        let embedding = self
+            .embeddings
+            .embed(&[query.to_string()])
+            .await?
+            .pop()
+            .ok_or_else(|| EmbeddingError::Request("empty embedding response".to_string()))?;
+        let similarities: HashMap<String, f32> = self
+            .store
+            .nearest(&embedding, self.config.candidates)
+            .await?
+            .into_iter()
+            .collect();
+
+        let mut candidates = Condition::any()
+            .add(product_listing::Column::Sku.is_in(similarities.keys().cloned().collect::<Vec<_>>()));
+        for term in &terms {
+            let pattern = format!("%{}%", term);
+            candidates = candidates
+                .add(Expr::expr(Func::lower(Expr::col(product_listing::Column::Title))).like(pattern.clone()))
+                .add(Expr::expr(Func::lower(Expr::col(product_listing::Column::Description))).like(pattern));
+        }
+        let listings = ProductListing::find()
+            .filter(product_listing::Column::Active.eq(true))
+            .filter(candidates)
+            .limit(self.config.candidates * 2)
+            .all(self.db.as_ref())
+            .await?;
+
+        let mut hits = hybrid_rank(
+            listings,
+            &similarities,
+            &terms,
+            keyword_weight.unwrap_or(self.config.keyword_weight),
+        );
+        hits.truncate(limit.clamp(1, 100) as usize);
+        Ok(hits)
+    }
+}
+
+/// Re-embeds listings as `ProductListingChanged` events arrive, after a backfill of
+/// anything written while the indexer was not running.
+pub fn spawn_indexer(service: Arc<SemanticSearchService>, events: EventSender) {
+    let mut receiver = events.subscribe();
+    tokio::spawn(async move {
+        if let Err(e) = service.backfill().await {
+            error!("Product embedding backfill failed: {}", e);
+        }
+        loop {
+            match receiver.recv().await {
+                Ok(Event::ProductListingChanged(sku)) => {
+                    if let Err(e) = service.index(&sku).await {
+                        error!(%sku, "Product embedding failed: {}", e);
+                    }
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!(missed, "Product indexer lagged; running a backfill");
+                    if let Err(e) = service.backfill().await {
+                        error!("Product embedding backfill failed: {}", e);
+                    }
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn listing(sku: &str, title: &str, description: &str) -> product_listing::Model {
+        let at = Utc.with_ymd_and_hms(2024, 6, 1, 12, 0, 0).unwrap();
+        product_listing::Model {
+            sku: sku.to_string(),
+            title: title.to_string(),
+            description: description.to_string(),
+            brand: None,
+            gtin: None,
+            item_group_id: None,
+            color: None,
+            size: None,
+            hs_code: None,
+            country_of_origin: None,
+            price: Decimal::new(1000, 2),
+            currency: "USD".to_string(),
+            link: None,
+            image_link: None,
+            active: true,
+            enable_checkout: true,
+            metadata: None,
+            created_at: at,
+            updated_at: at,
+        }
+    }
+
+    #[test]
+    fn test_migration_is_safe() {
+        let plan = crate::db::migration_safety::analyze(
+            "20261017040000_product_embeddings.sql",
+            include_str!("../../migrations/20261017040000_product_embeddings.sql"),
+        );
+        assert_eq!(plan.violations().count(), 0);
+    }
+
+    #[test]
+    fn test_query_terms() {
+        assert_eq!(query_terms("Waterproof  hiking-boots, a"), ["waterproof", "hiking", "boots"]);
+    }
+
+    #[test]
+    fn test_keyword_score_weights_title_over_description() {
+        let boots = listing("B1", "Hiking Boots", "Waterproof leather");
+        let terms = query_terms("waterproof boots");
+        assert_eq!(keyword_score(&boots, &terms), 0.75);
+        assert_eq!(keyword_score(&boots, &[]), 0.0);
+    }
+
+    #[test]
+    fn test_hybrid_rank_blends_scores() {
+        let listings = vec![
+            listing("A", "Trail Runner", "Lightweight shoe"),
+            listing("B", "Rain Boots", "Rubber boots"),
+            listing("C", "Wool Socks", "Warm socks"),
+        ];
+        let similarities = HashMap::from([("A".to_string(), 0.9), ("B".to_string(), 0.5)]);
+        let hits = hybrid_rank(listings, &similarities, &query_terms("boots"), 0.5);
+        let order: Vec<_> = hits.iter().map(|h| h.sku.as_str()).collect();
+        assert_eq!(order, ["B", "A", "C"]);
+        assert_eq!(hits[0].score, 0.75);
+        assert_eq!(hits[2].score, 0.0);
+    }
+
+    #[test]
+    fn test_content_hash_tracks_embedded_text() {
+        let mut item = listing("A", "Trail Runner", "Lightweight shoe");
+        let before = content_hash(&embedding_text(&item));
+        item.price = Decimal::new(5, 0);
+        assert_eq!(before, content_hash(&embedding_text(&item)));
+        item.color = Some("Red".to_string());
+        assert_ne!(before, content_hash(&embedding_text(&item)));
+    }
+
+    #[test]
+    fn test_vector_literal() {
+        assert_eq!(vector_literal(&[0.5, -1.0]), "[0.5,-1]");
+    }
+}
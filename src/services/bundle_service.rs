@@ -0,0 +1,355 @@
+use chrono::Utc;
+use sea_orm::{sea_query::Expr, *};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::Arc};
+use tracing::{error, instrument};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db::DbPool,
+    errors::ServiceError,
+    models::{
+        inventory_items::{self, Entity as InventoryItem},
+        product_bundle::{self, BundleKind, Entity as ProductBundle},
+        product_bundle_component::{self, Entity as BundleComponent},
+    },
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewBundle {
+    #[validate(length(min = 1, max = 64))]
+    pub sku: String,
+    /// Product the bundle is ordered as.
+    pub product_id: Uuid,
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    pub kind: BundleKind,
+    #[validate(length(min = 1))]
+    pub components: Vec<NewBundleComponent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NewBundleComponent {
+    pub product_id: Uuid,
+    pub sku: String,
+    pub quantity: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleWithComponents {
+    #[serde(flatten)]
+    pub bundle: product_bundle::Model,
+    pub components: Vec<product_bundle_component::Model>,
+}
+
+/// Sellable quantity of a bundle and the component that limits it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BundleAvailability {
+    pub sku: String,
+    pub available: i64,
+    /// Component SKU with the fewest complete sets; `None` only for a bundle without components.
+    pub limiting_sku: Option<String>,
+    pub components: Vec<ComponentAvailability>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ComponentAvailability {
+    pub sku: String,
+    pub per_bundle: i32,
+    pub available: i64,
+}
+
+/// An order line as fulfillment sees it: a SKU and a quantity.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FulfillmentLine {
+    pub sku: String,
+    pub quantity: i32,
+}
+
+/// A line pickers work from. Lines exploded from a bundle carry the bundle SKU so packers
+/// can group them.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PickLine {
+    pub sku: String,
+    pub quantity: i32,
+    pub bundle_sku: Option<String>,
+}
+
+/// Units of each component needed for `quantity` bundles.
+pub fn explode(components: &[product_bundle_component::Model], quantity: i32) -> Vec<(Uuid, String, i32)> {
+    components
+        .iter()
+        .map(|c| (c.component_product_id, c.component_sku.clone(), c.quantity * quantity))
+        .collect()
+}
+
+/// Bundles that can be assembled from `available` component stock: the minimum over
+/// components of `available / per_bundle`.
+pub fn limiting_availability(
+    sku: &str,
+    components: &[product_bundle_component::Model],
+    available: &HashMap<String, i64>,
+) -> BundleAvailability {
+    let components: Vec<ComponentAvailability> = components
+        .iter()
+        .map(|c| ComponentAvailability {
+            sku: c.component_sku.clone(),
+            per_bundle: c.quantity,
+            available: available.get(&c.component_sku).copied().unwrap_or(0).max(0),
+        })
+        .collect();
+    let limiting = components
+        .iter()
+        .min_by_key(|c| c.available / i64::from(c.per_bundle.max(1)));
+    BundleAvailability {
+        sku: sku.to_string(),
+        available: limiting.map(|c| c.available / i64::from(c.per_bundle.max(1))).unwrap_or(0),
+        limiting_sku: limiting.map(|c| c.sku.clone()),
+        components,
+    }
+}
+
+/// Replaces bundle lines with their component lines; other lines pass through unchanged.
+pub fn explode_lines(
+    lines: &[FulfillmentLine],
+    bundles: &HashMap<String, Vec<product_bundle_component::Model>>,
+) -> Vec<PickLine> {
+    lines
+        .iter()
+        .flat_map(|line| match bundles.get(&line.sku) {
+            Some(components) => explode(components, line.quantity)
+                .into_iter()
+                .map(|(_, sku, quantity)| PickLine { sku, quantity, bundle_sku: Some(line.sku.clone()) })
+                .collect::<Vec<_>>(),
+            None => vec![PickLine { sku: line.sku.clone(), quantity: line.quantity, bundle_sku: None }],
+        })
+        .collect()
+}
+
+/// Components of the active bundles ordered as any of `product_ids`, keyed by the bundle's
+/// product id. Used by inventory reservation to reserve components in place of a bundle.
+pub async fn components_for<C: ConnectionTrait>(
+    db: &C,
+    product_ids: Vec<Uuid>,
+) -> Result<HashMap<Uuid, Vec<product_bundle_component::Model>>, DbErr> {
+    let rows = ProductBundle::find()
+        .filter(product_bundle::Column::Active.eq(true))
+        .filter(product_bundle::Column::ProductId.is_in(product_ids))
+        .find_with_related(BundleComponent)
+        .all(db)
+        .await?;
+    Ok(rows.into_iter().map(|(bundle, components)| (bundle.product_id, components)).collect())
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Bundle query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+pub struct BundleService {
+    db_pool: Arc<DbPool>,
+}
+
+impl BundleService {
+    pub fn new(db_pool: Arc<DbPool>) -> Self {
+        Self { db_pool }
+    }
+
+    #[instrument(skip(self, new_bundle), fields(sku = %new_bundle.sku))]
+    pub async fn create_bundle(&self, new_bundle: NewBundle) -> Result<BundleWithComponents, ServiceError> {
+        new_bundle
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid bundle: {}", e)))?;
+        if new_bundle.components.iter().any(|c| c.quantity < 1) {
+            return Err(ServiceError::ValidationError("Component quantities must be at least 1".to_string()));
+        }
+        if new_bundle
+            .components
+            .iter()
+            .any(|c| c.sku == new_bundle.sku || c.product_id == new_bundle.product_id)
+        {
+            return Err(ServiceError::ValidationError("A bundle cannot contain itself".to_string()));
+        }
+        // Bundles hold no stock, so a bundle SKU must not also be an inventory SKU
+        let stocked = InventoryItem::find()
+            .filter(inventory_items::Column::Sku.eq(new_bundle.sku.as_str()))
+            .count(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?;
+        if stocked > 0 {
+            return Err(ServiceError::ValidationError(format!("{} is already an inventory SKU", new_bundle.sku)));
+        }
+
+        let now = Utc::now();
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let bundle = product_bundle::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            sku: Set(new_bundle.sku),
+            product_id: Set(new_bundle.product_id),
+            name: Set(new_bundle.name),
+            kind: Set(new_bundle.kind),
+            active: Set(true),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(&txn)
+        .await
+        .map_err(db_error)?;
+        let mut components = Vec::with_capacity(new_bundle.components.len());
+        for component in new_bundle.components {
+            let row = product_bundle_component::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                bundle_id: Set(bundle.id),
+                component_product_id: Set(component.product_id),
+                component_sku: Set(component.sku),
+                quantity: Set(component.quantity),
+            }
+            .insert(&txn)
+            .await
+            .map_err(db_error)?;
+            components.push(row);
+        }
+        txn.commit().await.map_err(db_error)?;
+        Ok(BundleWithComponents { bundle, components })
+    }
+
+    pub async fn get_by_sku(&self, sku: &str) -> Result<BundleWithComponents, ServiceError> {
+        let (bundle, components) = ProductBundle::find()
+            .filter(product_bundle::Column::Sku.eq(sku))
+            .find_with_related(BundleComponent)
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ServiceError::NotFound(format!("Bundle not found: {}", sku)))?;
+        Ok(BundleWithComponents { bundle, components })
+    }
+
+    pub async fn list_bundles(&self) -> Result<Vec<BundleWithComponents>, ServiceError> {
+        Ok(ProductBundle::find()
+            .order_by_asc(product_bundle::Column::Sku)
+            .find_with_related(BundleComponent)
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(|(bundle, components)| BundleWithComponents { bundle, components })
+            .collect())
+    }
+
+    /// Stops new orders for a bundle. Existing reservations are unaffected.
+    pub async fn deactivate(&self, sku: &str) -> Result<(), ServiceError> {
+        let result = ProductBundle::update_many()
+            .col_expr(product_bundle::Column::Active, Expr::value(false))
+            .col_expr(product_bundle::Column::UpdatedAt, Expr::value(Utc::now()))
+            .filter(product_bundle::Column::Sku.eq(sku))
+            .exec(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?;
+        if result.rows_affected == 0 {
+            return Err(ServiceError::NotFound(format!("Bundle not found: {}", sku)));
+        }
+        Ok(())
+    }
+
+    /// Bundles sellable from unreserved component stock, optionally in one warehouse.
+    pub async fn availability(&self, sku: &str, warehouse: Option<i32>) -> Result<BundleAvailability, ServiceError> {
+        let BundleWithComponents { components, .. } = self.get_by_sku(sku).await?;
+        let skus: Vec<String> = components.iter().map(|c| c.component_sku.clone()).collect();
+        let mut query = InventoryItem::find().filter(inventory_items::Column::Sku.is_in(skus));
+        if let Some(warehouse) = warehouse {
+            query = query.filter(inventory_items::Column::Warehouse.eq(warehouse));
+        }
+        let mut available: HashMap<String, i64> = HashMap::new();
+        for item in query.all(self.db_pool.as_ref()).await.map_err(db_error)? {
+            let free = i64::from(item.available) - i64::from(item.reserved_quantity.unwrap_or(0));
+            *available.entry(item.sku).or_default() += free;
+        }
+        Ok(limiting_availability(sku, &components, &available))
+    }
+
+    /// Pick lines for an order, with bundle lines exploded into their components.
+    pub async fn pick_lines(&self, lines: &[FulfillmentLine]) -> Result<Vec<PickLine>, ServiceError> {
+        let skus: Vec<String> = lines.iter().map(|l| l.sku.clone()).collect();
+        let bundles = ProductBundle::find()
+            .filter(product_bundle::Column::Sku.is_in(skus))
+            .find_with_related(BundleComponent)
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(|(bundle, components)| (bundle.sku, components))
+            .collect();
+        Ok(explode_lines(lines, &bundles))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migration_is_safe() {
+        let plan = crate::db::migration_safety::analyze(
+            "20261017020000_product_bundles.sql",
+            include_str!("../../migrations/20261017020000_product_bundles.sql"),
+        );
+        assert_eq!(plan.violations().count(), 0);
+    }
+
+    fn component(sku: &str, quantity: i32) -> product_bundle_component::Model {
+        product_bundle_component::Model {
+            id: Uuid::new_v4(),
+            bundle_id: Uuid::nil(),
+            component_product_id: Uuid::new_v4(),
+            component_sku: sku.to_string(),
+            quantity,
+        }
+    }
+
+    #[test]
+    fn test_availability_is_limited_by_scarcest_component() {
+        let components = vec![component("CAMERA", 1), component("BATTERY", 2), component("STRAP", 1)];
+        let available = HashMap::from([
+            ("CAMERA".to_string(), 10),
+            ("BATTERY".to_string(), 7),
+            ("STRAP".to_string(), 50),
+        ]);
+        let result = limiting_availability("CAMERA-KIT", &components, &available);
+        assert_eq!(result.available, 3);
+        assert_eq!(result.limiting_sku.as_deref(), Some("BATTERY"));
+    }
+
+    #[test]
+    fn test_missing_component_stock_means_unavailable() {
+        let components = vec![component("CAMERA", 1), component("LENS", 1)];
+        let available = HashMap::from([("CAMERA".to_string(), 10)]);
+        let result = limiting_availability("CAMERA-KIT", &components, &available);
+        assert_eq!(result.available, 0);
+        assert_eq!(result.limiting_sku.as_deref(), Some("LENS"));
+    }
+
+    #[test]
+    fn test_explode_lines() {
+        let bundles = HashMap::from([(
+            "CAMERA-KIT".to_string(),
+            vec![component("CAMERA", 1), component("BATTERY", 2)],
+        )]);
+        let lines = vec![
+            FulfillmentLine { sku: "CAMERA-KIT".to_string(), quantity: 2 },
+            FulfillmentLine { sku: "TRIPOD".to_string(), quantity: 1 },
+        ];
+        let picks = explode_lines(&lines, &bundles);
+        assert_eq!(
+            picks,
+            vec![
+                PickLine { sku: "CAMERA".to_string(), quantity: 2, bundle_sku: Some("CAMERA-KIT".to_string()) },
+                PickLine { sku: "BATTERY".to_string(), quantity: 4, bundle_sku: Some("CAMERA-KIT".to_string()) },
+                PickLine { sku: "TRIPOD".to_string(), quantity: 1, bundle_sku: None },
+            ]
+        );
+    }
+}
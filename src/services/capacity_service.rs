@@ -0,0 +1,596 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveTime, Timelike, Utc};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db::{dialect, DbPool},
+    errors::ServiceError,
+    models::{
+        work_center::{self, Entity as WorkCenter},
+        work_center_holiday::{self, Entity as Holiday},
+        work_center_shift::{self, Entity as Shift},
+    },
+    utils::pagination::PaginationParams,
+};
+
+/// Longest range one load report covers.
+pub const MAX_REPORT_DAYS: i64 = 366;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewShift {
+    #[validate(range(min = 1, max = 7))]
+    pub weekday: i16,
+    pub start_time: NaiveTime,
+    pub end_time: NaiveTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewWorkCenter {
+    #[validate(length(min = 1, max = 64))]
+    pub code: String,
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[serde(default = "full_efficiency")]
+    #[validate(range(min = 0.01, max = 2.0))]
+    pub efficiency: f64,
+    #[serde(default)]
+    #[validate]
+    pub shifts: Vec<NewShift>,
+}
+
+fn full_efficiency() -> f64 {
+    1.0
+}
+
+/// Fields left out are unchanged; `shifts` replaces the whole weekly calendar.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct WorkCenterUpdate {
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+    #[validate(range(min = 0.01, max = 2.0))]
+    pub efficiency: Option<f64>,
+    pub active: Option<bool>,
+    #[validate]
+    pub shifts: Option<Vec<NewShift>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewHoliday {
+    pub date: NaiveDate,
+    #[validate(length(max = 255))]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkCenterCalendar {
+    pub work_center: work_center::Model,
+    pub shifts: Vec<work_center_shift::Model>,
+    pub holidays: Vec<work_center_holiday::Model>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bucket {
+    #[default]
+    Day,
+    /// Weeks start on Monday.
+    Week,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LoadQuery {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    #[serde(default)]
+    pub bucket: Bucket,
+    /// Work center code; all active work centers when absent.
+    pub work_center: Option<String>,
+}
+
+/// Scheduled hours against available hours in one period.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PeriodLoad {
+    pub period_start: NaiveDate,
+    pub capacity_hours: f64,
+    pub load_hours: f64,
+    /// Load over capacity; absent when the work center has no hours in the period.
+    pub utilization: Option<f64>,
+    pub overloaded: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WorkCenterLoad {
+    pub work_center: String,
+    pub name: String,
+    pub capacity_hours: f64,
+    pub load_hours: f64,
+    pub overloaded_periods: usize,
+    pub periods: Vec<PeriodLoad>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LoadReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub bucket: Bucket,
+    pub work_centers: Vec<WorkCenterLoad>,
+    /// Codes on scheduled operations with no active work center; their load is not counted.
+    pub unknown_work_centers: Vec<String>,
+}
+
+/// Scheduled hours of open operations per work center and day.
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct ScheduledLoad {
+    pub work_center: String,
+    pub day: NaiveDate,
+    pub hours: f64,
+}
+
+fn round_hours(hours: f64) -> f64 {
+    (hours * 100.0).round() / 100.0
+}
+
+fn span(start: NaiveTime, end: NaiveTime) -> (i64, i64) {
+    let from = i64::from(start.num_seconds_from_midnight());
+    let mut to = i64::from(end.num_seconds_from_midnight());
+    if to <= from {
+        to += 24 * 3600;
+    }
+    (from, to)
+}
+
+/// Rejects shifts that overlap on the same weekday.
+pub fn check_shifts(shifts: &[NewShift]) -> Result<(), ServiceError> {
+    for (i, a) in shifts.iter().enumerate() {
+        for b in shifts.iter().skip(i + 1).filter(|b| b.weekday == a.weekday) {
+            let ((a_from, a_to), (b_from, b_to)) = (span(a.start_time, a.end_time), span(b.start_time, b.end_time));
+            if a_from < b_to && b_from < a_to {
+                return Err(ServiceError::ValidationError(format!(
+                    "Shifts {}-{} and {}-{} overlap on weekday {}",
+                    a.start_time, a.end_time, b.start_time, b.end_time, a.weekday
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Productive hours of a work center on `date`: its shifts that day scaled by efficiency,
+/// or none on a holiday.
+pub fn daily_capacity(
+    center: &work_center::Model,
+    shifts: &[work_center_shift::Model],
+    holidays: &HashSet<NaiveDate>,
+    date: NaiveDate,
+) -> f64 {
+    if holidays.contains(&date) {
+        return 0.0;
+    }
+    let weekday = date.weekday().number_from_monday() as i16;
+    shifts.iter().filter(|s| s.weekday == weekday).map(|s| s.hours()).sum::<f64>() * center.efficiency
+}
+
+pub fn period_start(date: NaiveDate, bucket: Bucket) -> NaiveDate {
+    match bucket {
+        Bucket::Day => date,
+        Bucket::Week => date - Duration::days(i64::from(date.weekday().num_days_from_monday())),
+    }
+}
+
+/// Compares daily load with capacity over `[from, to]`, summed into buckets. A period is
+/// overloaded when its load exceeds its capacity, including load on a day with no hours.
+pub fn work_center_load(
+    center: &work_center::Model,
+    shifts: &[work_center_shift::Model],
+    holidays: &HashSet<NaiveDate>,
+    daily_load: &BTreeMap<NaiveDate, f64>,
+    from: NaiveDate,
+    to: NaiveDate,
+    bucket: Bucket,
+) -> WorkCenterLoad {
+    let mut periods: BTreeMap<NaiveDate, (f64, f64)> = BTreeMap::new();
+    let mut date = from;
+    while date <= to {
+        let period = periods.entry(period_start(date, bucket)).or_default();
+        period.0 += daily_capacity(center, shifts, holidays, date);
+        period.1 += daily_load.get(&date).copied().unwrap_or(0.0);
+        date += Duration::days(1);
+    }
+
+    let mut load = WorkCenterLoad {
+        work_center: center.code.clone(),
+        name: center.name.clone(),
+        capacity_hours: 0.0,
+        load_hours: 0.0,
+        overloaded_periods: 0,
+        periods: Vec::with_capacity(periods.len()),
+    };
+    for (period_start, (capacity, scheduled)) in periods {
+        let (capacity, scheduled) = (round_hours(capacity), round_hours(scheduled));
+        let overloaded = scheduled > capacity;
+        load.capacity_hours += capacity;
+        load.load_hours += scheduled;
+        load.overloaded_periods += usize::from(overloaded);
+        load.periods.push(PeriodLoad {
+            period_start,
+            capacity_hours: capacity,
+            load_hours: scheduled,
+            utilization: (capacity > 0.0).then(|| round_hours(scheduled / capacity)),
+            overloaded,
+        });
+    }
+    load.capacity_hours = round_hours(load.capacity_hours);
+    load.load_hours = round_hours(load.load_hours);
+    load
+}
+
+/// An operation occupies its work center for the longer of its labor and machine hours,
+/// which run side by side. Completed operations and closed work orders add no load.
+const SCHEDULED_LOAD_SQL: &str = r#"
+SELECT op.work_center,
+       COALESCE(op.scheduled_date, wo.issue_date) AS day,
+       SUM(GREATEST(op.standard_labor_hours, op.standard_machine_hours))::DOUBLE PRECISION AS hours
+FROM work_order_operations op
+JOIN work_orders wo ON wo.id = op.work_order_id
+WHERE op.work_center IS NOT NULL
+  AND op.status <> 'Completed'
+  AND wo.status NOT IN ('Completed', 'Cancelled')
+  AND COALESCE(op.scheduled_date, wo.issue_date) BETWEEN $1 AND $2
+  AND ($3::TEXT IS NULL OR op.work_center = $3)
+GROUP BY 1, 2
+"#;
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Work center query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Work center calendars and the load-versus-capacity report planners level schedules with.
+pub struct CapacityService {
+    db_pool: Arc<DbPool>,
+}
+
+impl CapacityService {
+    pub fn new(db_pool: Arc<DbPool>) -> Self {
+        Self { db_pool }
+    }
+
+    async fn insert_shifts<C: ConnectionTrait>(
+        db: &C,
+        work_center_id: Uuid,
+        shifts: Vec<NewShift>,
+    ) -> Result<(), ServiceError> {
+        for shift in shifts {
+            work_center_shift::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                work_center_id: Set(work_center_id),
+                weekday: Set(shift.weekday),
+                start_time: Set(shift.start_time),
+                end_time: Set(shift.end_time),
+            }
+            .insert(db)
+            .await
+            .map_err(db_error)?;
+        }
+        Ok(())
+    }
+
+    #[instrument(skip(self, input), fields(code = %input.code))]
+    pub async fn create(&self, input: NewWorkCenter) -> Result<WorkCenterCalendar, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid work center: {}", e)))?;
+        check_shifts(&input.shifts)?;
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let taken = WorkCenter::find()
+            .filter(work_center::Column::Code.eq(input.code.as_str()))
+            .count(&txn)
+            .await
+            .map_err(db_error)?;
+        if taken > 0 {
+            return Err(ServiceError::ValidationError(format!("Work center {} already exists", input.code)));
+        }
+        let now = Utc::now();
+        let center = work_center::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            code: Set(input.code),
+            name: Set(input.name),
+            efficiency: Set(input.efficiency),
+            active: Set(true),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(&txn)
+        .await
+        .map_err(db_error)?;
+        Self::insert_shifts(&txn, center.id, input.shifts).await?;
+        txn.commit().await.map_err(db_error)?;
+        info!("Work center {} created", center.code);
+        self.get(center.id).await
+    }
+
+    async fn find<C: ConnectionTrait>(db: &C, id: Uuid) -> Result<work_center::Model, ServiceError> {
+        WorkCenter::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Work center not found: {}", id)))
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<WorkCenterCalendar, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let work_center = Self::find(db, id).await?;
+        let shifts = Shift::find()
+            .filter(work_center_shift::Column::WorkCenterId.eq(id))
+            .order_by_asc(work_center_shift::Column::Weekday)
+            .order_by_asc(work_center_shift::Column::StartTime)
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        let holidays = Holiday::find()
+            .filter(work_center_holiday::Column::WorkCenterId.eq(id))
+            .order_by_asc(work_center_holiday::Column::Date)
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        Ok(WorkCenterCalendar { work_center, shifts, holidays })
+    }
+
+    pub async fn list(&self, pagination: PaginationParams) -> Result<(Vec<work_center::Model>, u64), ServiceError> {
+        let paginator = WorkCenter::find()
+            .order_by_asc(work_center::Column::Code)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let centers = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((centers, total))
+    }
+
+    pub async fn update(&self, id: Uuid, input: WorkCenterUpdate) -> Result<WorkCenterCalendar, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid work center: {}", e)))?;
+        if let Some(shifts) = &input.shifts {
+            check_shifts(shifts)?;
+        }
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let mut active: work_center::ActiveModel = Self::find(&txn, id).await?.into();
+        if let Some(name) = input.name {
+            active.name = Set(name);
+        }
+        if let Some(efficiency) = input.efficiency {
+            active.efficiency = Set(efficiency);
+        }
+        if let Some(is_active) = input.active {
+            active.active = Set(is_active);
+        }
+        active.updated_at = Set(Utc::now());
+        active.update(&txn).await.map_err(db_error)?;
+        if let Some(shifts) = input.shifts {
+            Shift::delete_many()
+                .filter(work_center_shift::Column::WorkCenterId.eq(id))
+                .exec(&txn)
+                .await
+                .map_err(db_error)?;
+            Self::insert_shifts(&txn, id, shifts).await?;
+        }
+        txn.commit().await.map_err(db_error)?;
+        self.get(id).await
+    }
+
+    pub async fn add_holiday(&self, id: Uuid, input: NewHoliday) -> Result<work_center_holiday::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid holiday: {}", e)))?;
+        let db = self.db_pool.as_ref();
+        Self::find(db, id).await?;
+        let taken = Holiday::find()
+            .filter(work_center_holiday::Column::WorkCenterId.eq(id))
+            .filter(work_center_holiday::Column::Date.eq(input.date))
+            .count(db)
+            .await
+            .map_err(db_error)?;
+        if taken > 0 {
+            return Err(ServiceError::ValidationError(format!("{} is already a holiday", input.date)));
+        }
+        work_center_holiday::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            work_center_id: Set(id),
+            date: Set(input.date),
+            description: Set(input.description),
+        }
+        .insert(db)
+        .await
+        .map_err(db_error)
+    }
+
+    pub async fn remove_holiday(&self, id: Uuid, holiday_id: Uuid) -> Result<(), ServiceError> {
+        let result = Holiday::delete_many()
+            .filter(work_center_holiday::Column::Id.eq(holiday_id))
+            .filter(work_center_holiday::Column::WorkCenterId.eq(id))
+            .exec(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?;
+        if result.rows_affected == 0 {
+            return Err(ServiceError::NotFound(format!("Holiday not found: {}", holiday_id)));
+        }
+        Ok(())
+    }
+
+    /// Load against capacity of active work centers over `[from, to]`.
+    #[instrument(skip(self))]
+    pub async fn load_report(&self, query: LoadQuery) -> Result<LoadReport, ServiceError> {
+        let (from, to) = (query.from, query.to);
+        if to < from {
+            return Err(ServiceError::ValidationError("`to` is before `from`".to_string()));
+        }
+        if (to - from).num_days() >= MAX_REPORT_DAYS {
+            return Err(ServiceError::ValidationError(format!(
+                "Reports cover at most {} days",
+                MAX_REPORT_DAYS
+            )));
+        }
+        let db = self.db_pool.as_ref();
+        let mut centers = WorkCenter::find().filter(work_center::Column::Active.eq(true));
+        if let Some(code) = &query.work_center {
+            centers = centers.filter(work_center::Column::Code.eq(code.as_str()));
+        }
+        let centers = centers.order_by_asc(work_center::Column::Code).all(db).await.map_err(db_error)?;
+        let ids: Vec<Uuid> = centers.iter().map(|c| c.id).collect();
+
+        let mut shifts: HashMap<Uuid, Vec<work_center_shift::Model>> = HashMap::new();
+        for shift in Shift::find()
+            .filter(work_center_shift::Column::WorkCenterId.is_in(ids.clone()))
+            .all(db)
+            .await
+            .map_err(db_error)?
+        {
+            shifts.entry(shift.work_center_id).or_default().push(shift);
+        }
+        let mut holidays: HashMap<Uuid, HashSet<NaiveDate>> = HashMap::new();
+        for holiday in Holiday::find()
+            .filter(work_center_holiday::Column::WorkCenterId.is_in(ids))
+            .filter(work_center_holiday::Column::Date.between(from, to))
+            .all(db)
+            .await
+            .map_err(db_error)?
+        {
+            holidays.entry(holiday.work_center_id).or_default().insert(holiday.date);
+        }
+
+        let scheduled = ScheduledLoad::find_by_statement(dialect::statement(
+            db,
+            SCHEDULED_LOAD_SQL,
+            [from.into(), to.into(), query.work_center.clone().into()],
+        ))
+        .all(db)
+        .await
+        .map_err(db_error)?;
+        let mut daily: HashMap<String, BTreeMap<NaiveDate, f64>> = HashMap::new();
+        for row in scheduled {
+            *daily.entry(row.work_center).or_default().entry(row.day).or_default() += row.hours;
+        }
+
+        let work_centers = centers
+            .iter()
+            .map(|center| {
+                work_center_load(
+                    center,
+                    shifts.get(&center.id).map(Vec::as_slice).unwrap_or_default(),
+                    &holidays.get(&center.id).cloned().unwrap_or_default(),
+                    &daily.remove(&center.code).unwrap_or_default(),
+                    from,
+                    to,
+                    query.bucket,
+                )
+            })
+            .collect();
+        let mut unknown_work_centers: Vec<String> = if query.work_center.is_none() {
+            daily.into_keys().collect()
+        } else {
+            Vec::new()
+        };
+        unknown_work_centers.sort();
+        Ok(LoadReport { from, to, bucket: query.bucket, work_centers, unknown_work_centers })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn time(hour: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        // 2026-06-01 is a Monday
+        NaiveDate::from_ymd_opt(2026, 6, day).unwrap()
+    }
+
+    fn center(efficiency: f64) -> work_center::Model {
+        let now = Utc::now();
+        work_center::Model {
+            id: Uuid::new_v4(),
+            code: "WELD".to_string(),
+            name: "Welding".to_string(),
+            efficiency,
+            active: true,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn shift(weekday: i16, start: u32, end: u32) -> work_center_shift::Model {
+        work_center_shift::Model {
+            id: Uuid::new_v4(),
+            work_center_id: Uuid::nil(),
+            weekday,
+            start_time: time(start),
+            end_time: time(end),
+        }
+    }
+
+    fn weekday_shifts() -> Vec<work_center_shift::Model> {
+        (1..=5).map(|weekday| shift(weekday, 8, 16)).collect()
+    }
+
+    #[test]
+    fn test_overnight_shift_hours() {
+        assert_eq!(shift(1, 22, 6).hours(), 8.0);
+        assert_eq!(shift(1, 6, 14).hours(), 8.0);
+    }
+
+    #[test]
+    fn test_overlapping_shifts_rejected() {
+        let new = |weekday, start, end| NewShift { weekday, start_time: time(start), end_time: time(end) };
+        assert!(check_shifts(&[new(1, 6, 14), new(1, 14, 22), new(1, 22, 6)]).is_ok());
+        assert!(check_shifts(&[new(1, 6, 14), new(2, 6, 14)]).is_ok());
+        assert!(check_shifts(&[new(1, 6, 14), new(1, 12, 20)]).is_err());
+        assert!(check_shifts(&[new(1, 20, 4), new(1, 2, 6)]).is_err());
+    }
+
+    #[test]
+    fn test_capacity_skips_weekends_and_holidays() {
+        let center = center(0.75);
+        let holidays = HashSet::from([date(3)]);
+        assert_eq!(daily_capacity(&center, &weekday_shifts(), &holidays, date(1)), 6.0);
+        assert_eq!(daily_capacity(&center, &weekday_shifts(), &holidays, date(3)), 0.0);
+        assert_eq!(daily_capacity(&center, &weekday_shifts(), &holidays, date(6)), 0.0);
+    }
+
+    #[test]
+    fn test_daily_load_flags_overloaded_days() {
+        let daily = BTreeMap::from([(date(1), 10.0), (date(2), 4.0), (date(6), 1.5)]);
+        let load = work_center_load(&center(1.0), &weekday_shifts(), &HashSet::new(), &daily, date(1), date(7), Bucket::Day);
+        assert_eq!(load.periods.len(), 7);
+        assert_eq!(load.capacity_hours, 40.0);
+        assert_eq!(load.load_hours, 15.5);
+        // Monday over its 8 hours, and Saturday with no shift at all
+        assert_eq!(load.overloaded_periods, 2);
+        assert_eq!(load.periods[0].utilization, Some(1.25));
+        assert!(load.periods[5].overloaded);
+        assert_eq!(load.periods[5].utilization, None);
+        assert!(!load.periods[1].overloaded);
+    }
+
+    #[test]
+    fn test_weekly_buckets_start_on_monday() {
+        let daily = BTreeMap::from([(date(1), 30.0), (date(5), 15.0), (date(8), 8.0)]);
+        let load = work_center_load(&center(1.0), &weekday_shifts(), &HashSet::new(), &daily, date(3), date(9), Bucket::Week);
+        let starts: Vec<NaiveDate> = load.periods.iter().map(|p| p.period_start).collect();
+        assert_eq!(starts, vec![date(1), date(8)]);
+        // Only Wednesday to Friday of the first week is in range
+        assert_eq!(load.periods[0].capacity_hours, 24.0);
+        assert_eq!(load.periods[0].load_hours, 15.0);
+        assert_eq!(load.periods[1].capacity_hours, 16.0);
+        assert!(!load.periods[1].overloaded);
+    }
+}
@@ -0,0 +1,502 @@
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{sea_query::Expr, *};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, instrument};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db::DbPool,
+    errors::ServiceError,
+    models::{
+        order::Entity as Order,
+        return_entity::Entity as Return,
+        shipment::Entity as Shipment,
+        support_case::{self, CasePriority, CaseStatus, Entity as SupportCase},
+    },
+    utils::pagination::PaginationParams,
+};
+
+/// Minutes allowed until the first response and until resolution for one priority.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub struct SlaTarget {
+    pub first_response_minutes: i64,
+    pub resolution_minutes: i64,
+}
+
+/// SLA targets per case priority, loaded from the `cases` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct CasesConfig {
+    #[serde(default = "default_low")]
+    pub low: SlaTarget,
+    #[serde(default = "default_normal")]
+    pub normal: SlaTarget,
+    #[serde(default = "default_high")]
+    pub high: SlaTarget,
+    #[serde(default = "default_urgent")]
+    pub urgent: SlaTarget,
+}
+
+fn default_low() -> SlaTarget {
+    SlaTarget { first_response_minutes: 24 * 60, resolution_minutes: 5 * 24 * 60 }
+}
+
+fn default_normal() -> SlaTarget {
+    SlaTarget { first_response_minutes: 8 * 60, resolution_minutes: 2 * 24 * 60 }
+}
+
+fn default_high() -> SlaTarget {
+    SlaTarget { first_response_minutes: 2 * 60, resolution_minutes: 24 * 60 }
+}
+
+fn default_urgent() -> SlaTarget {
+    SlaTarget { first_response_minutes: 30, resolution_minutes: 4 * 60 }
+}
+
+impl Default for CasesConfig {
+    fn default() -> Self {
+        Self { low: default_low(), normal: default_normal(), high: default_high(), urgent: default_urgent() }
+    }
+}
+
+impl CasesConfig {
+    pub fn target(&self, priority: CasePriority) -> SlaTarget {
+        match priority {
+            CasePriority::Low => self.low,
+            CasePriority::Normal => self.normal,
+            CasePriority::High => self.high,
+            CasePriority::Urgent => self.urgent,
+        }
+    }
+}
+
+fn default_priority() -> CasePriority {
+    CasePriority::Normal
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewCase {
+    #[validate(length(min = 1, max = 255))]
+    pub subject: String,
+    #[validate(length(min = 1, max = 4000))]
+    pub description: String,
+    /// Defaults to the email on the linked order.
+    #[validate(email)]
+    pub customer_email: Option<String>,
+    /// Defaults to the customer on the linked return.
+    pub customer_id: Option<Uuid>,
+    #[serde(flatten)]
+    pub links: CaseLinks,
+    #[serde(default = "default_priority")]
+    pub priority: CasePriority,
+    pub assignee: Option<String>,
+}
+
+/// Records a case is about. Fields left out are unchanged; a linked return implies its order.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CaseLinks {
+    pub order_id: Option<Uuid>,
+    pub return_id: Option<Uuid>,
+    pub shipment_id: Option<i32>,
+}
+
+/// Fields left out are unchanged. A new priority moves the SLA due times.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Triage {
+    pub assignee: Option<String>,
+    pub priority: Option<CasePriority>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseTransition {
+    pub status: CaseStatus,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CaseFilter {
+    pub status: Option<CaseStatus>,
+    pub priority: Option<CasePriority>,
+    pub assignee: Option<String>,
+    pub customer_email: Option<String>,
+    pub order_id: Option<Uuid>,
+    pub return_id: Option<Uuid>,
+    pub shipment_id: Option<i32>,
+    /// Only open or pending cases past a first-response or resolution due time.
+    #[serde(default)]
+    pub breached: bool,
+}
+
+/// Which SLA timers a case missed, or is missing as of now.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct SlaBreaches {
+    pub first_response: bool,
+    pub resolution: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseWithSla {
+    #[serde(flatten)]
+    pub case: support_case::Model,
+    pub sla_breached: SlaBreaches,
+}
+
+/// Due times for a case opened at `opened_at`.
+pub fn due_times(target: SlaTarget, opened_at: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    (
+        opened_at + Duration::minutes(target.first_response_minutes),
+        opened_at + Duration::minutes(target.resolution_minutes),
+    )
+}
+
+/// Pushes the resolution due time back by the time spent waiting on the customer.
+pub fn resume_resolution_due(due: DateTime<Utc>, paused_at: DateTime<Utc>, now: DateTime<Utc>) -> DateTime<Utc> {
+    due + (now - paused_at).max(Duration::zero())
+}
+
+/// Allowed case moves: open ⇄ pending, either to resolved, and resolved cases reopen or close.
+pub fn case_transition_allowed(from: CaseStatus, to: CaseStatus) -> bool {
+    use CaseStatus::*;
+    matches!(
+        (from, to),
+        (Open, Pending) | (Pending, Open) | (Open, Resolved) | (Pending, Resolved) | (Resolved, Open) | (Resolved, Closed)
+    )
+}
+
+/// A timer is breached when it stopped, or is still running, past its due time. The
+/// first-response timer stops at the first response or resolution; the resolution timer
+/// at resolution and, while the case waits on the customer, when it was paused.
+pub fn sla_breaches(case: &support_case::Model, now: DateTime<Utc>) -> SlaBreaches {
+    let responded = case.first_responded_at.or(case.resolved_at).unwrap_or(now);
+    let resolved = case.resolved_at.or(case.sla_paused_at).unwrap_or(now);
+    SlaBreaches {
+        first_response: responded > case.first_response_due_at,
+        resolution: resolved > case.resolution_due_at,
+    }
+}
+
+fn with_sla(case: support_case::Model, now: DateTime<Utc>) -> CaseWithSla {
+    let sla_breached = sla_breaches(&case, now);
+    CaseWithSla { case, sla_breached }
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Support case query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+fn not_allowed<T: std::fmt::Debug>(from: T, to: T) -> ServiceError {
+    ServiceError::ValidationError(format!("Cannot move from {:?} to {:?}", from, to))
+}
+
+/// What the linked records say about the customer.
+#[derive(Debug, Default)]
+struct Linked {
+    order_id: Option<Uuid>,
+    customer_email: Option<String>,
+    customer_id: Option<Uuid>,
+}
+
+/// Support cases linking customers to their orders, returns and shipments.
+pub struct CaseService {
+    db_pool: Arc<DbPool>,
+    config: CasesConfig,
+}
+
+impl CaseService {
+    pub fn new(db_pool: Arc<DbPool>, config: CasesConfig) -> Self {
+        Self { db_pool, config }
+    }
+
+    async fn find_case(&self, id: Uuid) -> Result<support_case::Model, ServiceError> {
+        SupportCase::find_by_id(id)
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Case not found: {}", id)))
+    }
+
+    /// Checks the linked records exist and agree with each other.
+    async fn resolve_links(&self, links: &CaseLinks) -> Result<Linked, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let mut linked = Linked { order_id: links.order_id, ..Default::default() };
+        if let Some(return_id) = links.return_id {
+            let ret = Return::find_by_id(return_id)
+                .one(db)
+                .await
+                .map_err(db_error)?
+                .ok_or_else(|| ServiceError::NotFound(format!("Return not found: {}", return_id)))?;
+            if linked.order_id.is_some_and(|order_id| order_id != ret.order_id) {
+                return Err(ServiceError::ValidationError(format!(
+                    "Return {} belongs to order {}, not the linked order",
+                    return_id, ret.order_id
+                )));
+            }
+            linked.order_id = Some(ret.order_id);
+            linked.customer_id = Some(ret.customer_id);
+        }
+        if let Some(order_id) = linked.order_id {
+            let order = Order::find_by_id(order_id)
+                .one(db)
+                .await
+                .map_err(db_error)?
+                .ok_or_else(|| ServiceError::NotFound(format!("Order not found: {}", order_id)))?;
+            linked.customer_email = Some(order.customer_email);
+        }
+        if let Some(shipment_id) = links.shipment_id {
+            Shipment::find_by_id(shipment_id)
+                .one(db)
+                .await
+                .map_err(db_error)?
+                .ok_or_else(|| ServiceError::NotFound(format!("Shipment not found: {}", shipment_id)))?;
+        }
+        Ok(linked)
+    }
+
+    #[instrument(skip(self, input, created_by))]
+    pub async fn create(&self, input: NewCase, created_by: &str) -> Result<CaseWithSla, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid case: {}", e)))?;
+        let linked = self.resolve_links(&input.links).await?;
+        let customer_email = input.customer_email.or(linked.customer_email).ok_or_else(|| {
+            ServiceError::ValidationError("Link an order or return, or give the customer's email".to_string())
+        })?;
+        let now = Utc::now();
+        let (first_response_due_at, resolution_due_at) = due_times(self.config.target(input.priority), now);
+        let case = support_case::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            subject: Set(input.subject),
+            description: Set(input.description),
+            customer_email: Set(customer_email),
+            customer_id: Set(input.customer_id.or(linked.customer_id)),
+            order_id: Set(linked.order_id),
+            return_id: Set(input.links.return_id),
+            shipment_id: Set(input.links.shipment_id),
+            priority: Set(input.priority),
+            status: Set(CaseStatus::Open),
+            assignee: Set(input.assignee),
+            first_response_due_at: Set(first_response_due_at),
+            resolution_due_at: Set(resolution_due_at),
+            first_responded_at: Set(None),
+            sla_paused_at: Set(None),
+            created_by: Set(created_by.to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+            resolved_at: Set(None),
+        }
+        .insert(self.db_pool.as_ref())
+        .await
+        .map_err(db_error)?;
+        Ok(with_sla(case, now))
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<CaseWithSla, ServiceError> {
+        Ok(with_sla(self.find_case(id).await?, Utc::now()))
+    }
+
+    pub async fn list(
+        &self,
+        filter: CaseFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<CaseWithSla>, u64), ServiceError> {
+        let now = Utc::now();
+        let mut query = SupportCase::find();
+        if let Some(status) = filter.status {
+            query = query.filter(support_case::Column::Status.eq(status));
+        }
+        if let Some(priority) = filter.priority {
+            query = query.filter(support_case::Column::Priority.eq(priority));
+        }
+        if let Some(assignee) = filter.assignee {
+            query = query.filter(support_case::Column::Assignee.eq(assignee));
+        }
+        if let Some(customer_email) = filter.customer_email {
+            query = query.filter(support_case::Column::CustomerEmail.eq(customer_email));
+        }
+        if let Some(order_id) = filter.order_id {
+            query = query.filter(support_case::Column::OrderId.eq(order_id));
+        }
+        if let Some(return_id) = filter.return_id {
+            query = query.filter(support_case::Column::ReturnId.eq(return_id));
+        }
+        if let Some(shipment_id) = filter.shipment_id {
+            query = query.filter(support_case::Column::ShipmentId.eq(shipment_id));
+        }
+        if filter.breached {
+            let first_response = Condition::all()
+                .add(support_case::Column::FirstRespondedAt.is_null())
+                .add(support_case::Column::FirstResponseDueAt.lt(now));
+            let running = Condition::all()
+                .add(support_case::Column::SlaPausedAt.is_null())
+                .add(support_case::Column::ResolutionDueAt.lt(now));
+            let paused_late = Expr::col(support_case::Column::SlaPausedAt)
+                .gt(Expr::col(support_case::Column::ResolutionDueAt));
+            query = query
+                .filter(support_case::Column::Status.is_in([CaseStatus::Open, CaseStatus::Pending]))
+                .filter(Condition::any().add(first_response).add(running).add(paused_late));
+        }
+        let paginator = query
+            .order_by_asc(support_case::Column::ResolutionDueAt)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let cases = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((cases.into_iter().map(|c| with_sla(c, now)).collect(), total))
+    }
+
+    /// Reassigns a case or changes its priority. Due times follow the new priority's
+    /// targets from when the case was opened, keeping any time spent waiting on the customer.
+    pub async fn triage(&self, id: Uuid, triage: Triage) -> Result<CaseWithSla, ServiceError> {
+        let case = self.find_case(id).await?;
+        let now = Utc::now();
+        let mut active: support_case::ActiveModel = case.clone().into();
+        if let Some(assignee) = triage.assignee {
+            active.assignee = Set(Some(assignee));
+        }
+        if let Some(priority) = triage.priority.filter(|p| *p != case.priority) {
+            let (_, old_resolution_due) = due_times(self.config.target(case.priority), case.created_at);
+            let waited = (case.resolution_due_at - old_resolution_due).max(Duration::zero());
+            let (first_response_due_at, resolution_due_at) = due_times(self.config.target(priority), case.created_at);
+            active.priority = Set(priority);
+            active.first_response_due_at = Set(first_response_due_at);
+            active.resolution_due_at = Set(resolution_due_at + waited);
+        }
+        active.updated_at = Set(now);
+        let case = active.update(self.db_pool.as_ref()).await.map_err(db_error)?;
+        Ok(with_sla(case, now))
+    }
+
+    /// Adds links to a case; the customer email is kept.
+    pub async fn link(&self, id: Uuid, links: CaseLinks) -> Result<CaseWithSla, ServiceError> {
+        let case = self.find_case(id).await?;
+        let merged = CaseLinks {
+            order_id: links.order_id.or(case.order_id),
+            return_id: links.return_id.or(case.return_id),
+            shipment_id: links.shipment_id.or(case.shipment_id),
+        };
+        let linked = self.resolve_links(&merged).await?;
+        let now = Utc::now();
+        let customer_id = case.customer_id.or(linked.customer_id);
+        let mut active: support_case::ActiveModel = case.into();
+        active.order_id = Set(linked.order_id);
+        active.return_id = Set(merged.return_id);
+        active.shipment_id = Set(merged.shipment_id);
+        active.customer_id = Set(customer_id);
+        active.updated_at = Set(now);
+        let case = active.update(self.db_pool.as_ref()).await.map_err(db_error)?;
+        Ok(with_sla(case, now))
+    }
+
+    /// Stops the first-response timer; later responses leave it unchanged.
+    pub async fn record_response(&self, id: Uuid) -> Result<CaseWithSla, ServiceError> {
+        let case = self.find_case(id).await?;
+        let now = Utc::now();
+        if case.first_responded_at.is_some() {
+            return Ok(with_sla(case, now));
+        }
+        let mut active: support_case::ActiveModel = case.into();
+        active.first_responded_at = Set(Some(now));
+        active.updated_at = Set(now);
+        let case = active.update(self.db_pool.as_ref()).await.map_err(db_error)?;
+        Ok(with_sla(case, now))
+    }
+
+    /// Moves a case through its workflow. Pending pauses the resolution timer and counts as
+    /// a first response, as does resolving. Reopening keeps the original due times.
+    #[instrument(skip(self, transition), fields(case_id = %id))]
+    pub async fn transition(&self, id: Uuid, transition: CaseTransition) -> Result<CaseWithSla, ServiceError> {
+        let case = self.find_case(id).await?;
+        if !case_transition_allowed(case.status, transition.status) {
+            return Err(not_allowed(case.status, transition.status));
+        }
+        let now = Utc::now();
+        let status = transition.status;
+        let mut active: support_case::ActiveModel = case.clone().into();
+        active.status = Set(status);
+        if let Some(paused_at) = case.sla_paused_at {
+            active.resolution_due_at = Set(resume_resolution_due(case.resolution_due_at, paused_at, now));
+            active.sla_paused_at = Set(None);
+        }
+        if matches!(status, CaseStatus::Pending | CaseStatus::Resolved) && case.first_responded_at.is_none() {
+            active.first_responded_at = Set(Some(now));
+        }
+        match status {
+            CaseStatus::Pending => active.sla_paused_at = Set(Some(now)),
+            CaseStatus::Resolved => active.resolved_at = Set(Some(now)),
+            CaseStatus::Open => active.resolved_at = Set(None),
+            CaseStatus::Closed => {}
+        }
+        active.updated_at = Set(now);
+        let case = active.update(self.db_pool.as_ref()).await.map_err(db_error)?;
+        Ok(with_sla(case, now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, 1, hour, 0, 0).unwrap()
+    }
+
+    fn case(priority: CasePriority) -> support_case::Model {
+        let (first_response_due_at, resolution_due_at) = due_times(CasesConfig::default().target(priority), at(0));
+        support_case::Model {
+            id: Uuid::new_v4(),
+            subject: "Parcel never arrived".to_string(),
+            description: "Tracking stopped updating".to_string(),
+            customer_email: "jane@example.com".to_string(),
+            customer_id: None,
+            order_id: None,
+            return_id: None,
+            shipment_id: None,
+            priority,
+            status: CaseStatus::Open,
+            assignee: None,
+            first_response_due_at,
+            resolution_due_at,
+            first_responded_at: None,
+            sla_paused_at: None,
+            created_by: "user:1".to_string(),
+            created_at: at(0),
+            updated_at: at(0),
+            resolved_at: None,
+        }
+    }
+
+    #[test]
+    fn test_case_workflow() {
+        assert!(case_transition_allowed(CaseStatus::Open, CaseStatus::Pending));
+        assert!(case_transition_allowed(CaseStatus::Pending, CaseStatus::Resolved));
+        assert!(case_transition_allowed(CaseStatus::Resolved, CaseStatus::Open));
+        assert!(!case_transition_allowed(CaseStatus::Open, CaseStatus::Closed));
+        assert!(!case_transition_allowed(CaseStatus::Closed, CaseStatus::Open));
+    }
+
+    #[test]
+    fn test_due_times_follow_priority() {
+        let config = CasesConfig::default();
+        assert_eq!(due_times(config.target(CasePriority::Urgent), at(0)), (at(0) + Duration::minutes(30), at(4)));
+        assert_eq!(due_times(config.target(CasePriority::Normal), at(0)).0, at(8));
+        assert_eq!(resume_resolution_due(at(4), at(1), at(3)), at(6));
+        assert_eq!(resume_resolution_due(at(4), at(3), at(1)), at(4));
+    }
+
+    #[test]
+    fn test_breaches_stop_with_response_pause_and_resolution() {
+        let mut urgent = case(CasePriority::Urgent);
+        assert_eq!(sla_breaches(&urgent, at(0)), SlaBreaches::default());
+        assert_eq!(sla_breaches(&urgent, at(5)), SlaBreaches { first_response: true, resolution: true });
+
+        urgent.first_responded_at = Some(at(0));
+        urgent.sla_paused_at = Some(at(2));
+        assert_eq!(sla_breaches(&urgent, at(9)), SlaBreaches::default());
+
+        urgent.sla_paused_at = None;
+        urgent.resolved_at = Some(at(5));
+        assert_eq!(sla_breaches(&urgent, at(9)), SlaBreaches { first_response: false, resolution: true });
+    }
+}
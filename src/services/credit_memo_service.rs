@@ -0,0 +1,500 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use rust_decimal::Decimal;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db::DbPool,
+    errors::ServiceError,
+    events::{Event, EventSender},
+    models::{
+        approval_step::ApprovalSubject,
+        credit_memo::{self, CreditMemoSource, CreditMemoStatus, Entity as CreditMemo},
+        credit_memo_application::{self, ApplicationKind, Entity as CreditMemoApplication},
+        return_entity::{Entity as Return, ReturnStatus},
+    },
+    utils::pagination::PaginationParams,
+    workflow::{ApprovalEngine, ApprovalHandler, Resolution},
+};
+
+/// Most memos or applications returned by one export batch.
+pub const MAX_EXPORT_BATCH: u64 = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewCreditMemo {
+    pub customer_id: Uuid,
+    #[validate(custom = "crate::money::validate_positive")]
+    #[serde(with = "crate::money::amount")]
+    pub amount: Decimal,
+    #[validate(length(equal = 3))]
+    pub currency: String,
+    #[validate(length(min = 1, max = 1000))]
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ReturnCredit {
+    pub return_id: Uuid,
+    #[serde(default = "default_currency")]
+    #[validate(length(equal = 3))]
+    pub currency: String,
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+/// Draws down a memo, against an invoice or as a refund.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewApplication {
+    pub kind: ApplicationKind,
+    /// Required for invoice applications.
+    pub invoice_id: Option<String>,
+    #[validate(length(max = 255))]
+    pub reference: Option<String>,
+    #[validate(custom = "crate::money::validate_positive")]
+    #[serde(with = "crate::money::amount")]
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CreditMemoFilter {
+    pub customer_id: Option<Uuid>,
+    pub status: Option<CreditMemoStatus>,
+    pub source: Option<CreditMemoSource>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CreditMemoWithApplications {
+    #[serde(flatten)]
+    pub memo: credit_memo::Model,
+    pub applications: Vec<credit_memo_application::Model>,
+}
+
+/// Memos and applications not yet picked up by an accounting export.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExportBatch {
+    pub credit_memos: Vec<credit_memo::Model>,
+    pub applications: Vec<credit_memo_application::Model>,
+}
+
+/// Acknowledges records an accounting export has posted.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExportAck {
+    #[serde(default)]
+    pub credit_memo_ids: Vec<Uuid>,
+    #[serde(default)]
+    pub application_ids: Vec<Uuid>,
+}
+
+/// Remaining credit and status after drawing `amount` from a memo.
+pub fn draw_down(memo: &credit_memo::Model, amount: Decimal) -> Result<(Decimal, CreditMemoStatus), String> {
+    if !matches!(memo.status, CreditMemoStatus::Open | CreditMemoStatus::PartiallyApplied) {
+        return Err(format!("Credit memo is {:?}", memo.status));
+    }
+    if amount <= Decimal::ZERO {
+        return Err("Amount must be positive".to_string());
+    }
+    if amount > memo.remaining {
+        return Err(format!("Amount {} exceeds the remaining credit {}", amount, memo.remaining));
+    }
+    let remaining = memo.remaining - amount;
+    let status = if remaining.is_zero() { CreditMemoStatus::Closed } else { CreditMemoStatus::PartiallyApplied };
+    Ok((remaining, status))
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Credit memo query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+async fn find_locked(txn: &DatabaseTransaction, id: Uuid) -> Result<credit_memo::Model, ServiceError> {
+    CreditMemo::find_by_id(id)
+        .lock_exclusive()
+        .one(txn)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| ServiceError::NotFound(format!("Credit memo not found: {}", id)))
+}
+
+fn memo_number() -> String {
+    format!("CM-{}", &Uuid::new_v4().simple().to_string()[..12].to_uppercase())
+}
+
+fn issued(memo: &credit_memo::Model) -> Event {
+    Event::CreditMemoIssued {
+        credit_memo_id: memo.id,
+        customer_id: memo.customer_id,
+        amount: memo.amount,
+    }
+}
+
+/// Customer credit memos. Memos for returns are issued directly; manual adjustments go
+/// through the `credit_memo` approval chain first.
+pub struct CreditMemoService {
+    db_pool: Arc<DbPool>,
+    events: EventSender,
+    approvals: Arc<ApprovalEngine>,
+}
+
+impl CreditMemoService {
+    pub fn new(db_pool: Arc<DbPool>, events: EventSender, approvals: Arc<ApprovalEngine>) -> Self {
+        Self { db_pool, events, approvals }
+    }
+
+    /// Issues a memo for the unrefunded amount of an approved or received return.
+    #[instrument(skip(self))]
+    pub async fn issue_for_return(&self, input: ReturnCredit, actor: &str) -> Result<credit_memo::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid credit memo: {}", e)))?;
+        let db = self.db_pool.as_ref();
+        let ret = Return::find_by_id(input.return_id)
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Return not found: {}", input.return_id)))?;
+        if !matches!(ret.status, ReturnStatus::Approved | ReturnStatus::Received) {
+            return Err(ServiceError::ValidationError(format!(
+                "Credit is only issued for approved or received returns, not {:?}",
+                ret.status
+            )));
+        }
+        let amount = ret.amount - ret.total_refunded;
+        if amount <= Decimal::ZERO {
+            return Err(ServiceError::ValidationError("Return has already been refunded in full".to_string()));
+        }
+        let existing = CreditMemo::find()
+            .filter(credit_memo::Column::ReturnId.eq(ret.id))
+            .one(db)
+            .await
+            .map_err(db_error)?;
+        if let Some(existing) = existing {
+            return Err(ServiceError::ValidationError(format!(
+                "Return already has credit memo {}",
+                existing.memo_number
+            )));
+        }
+
+        let now = Utc::now();
+        let memo = credit_memo::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            memo_number: Set(memo_number()),
+            customer_id: Set(ret.customer_id),
+            source: Set(CreditMemoSource::Return),
+            return_id: Set(Some(ret.id)),
+            reason: Set(format!("Return {}", ret.rma)),
+            currency: Set(input.currency.to_uppercase()),
+            amount: Set(amount),
+            remaining: Set(amount),
+            status: Set(CreditMemoStatus::Open),
+            created_by: Set(actor.to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+            exported_at: Set(None),
+        }
+        .insert(db)
+        .await
+        .map_err(db_error)?;
+        info!(credit_memo_id = %memo.id, return_id = %ret.id, amount = %amount, "Credit memo issued for return");
+        let _ = self.events.send(issued(&memo));
+        Ok(memo)
+    }
+
+    /// Records a manual adjustment and submits it for approval. With no applicable approval
+    /// step the memo is open on return.
+    #[instrument(skip(self, input))]
+    pub async fn create_manual(&self, input: NewCreditMemo, actor: &str) -> Result<CreditMemoWithApplications, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid credit memo: {}", e)))?;
+        let now = Utc::now();
+        let memo = credit_memo::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            memo_number: Set(memo_number()),
+            customer_id: Set(input.customer_id),
+            source: Set(CreditMemoSource::Manual),
+            return_id: Set(None),
+            reason: Set(input.reason),
+            currency: Set(input.currency.to_uppercase()),
+            amount: Set(input.amount),
+            remaining: Set(input.amount),
+            status: Set(CreditMemoStatus::PendingApproval),
+            created_by: Set(actor.to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+            exported_at: Set(None),
+        }
+        .insert(self.db_pool.as_ref())
+        .await
+        .map_err(db_error)?;
+        self.approvals
+            .request(ApprovalSubject::CreditMemo, &memo.id.to_string(), actor)
+            .await?;
+        self.get(memo.id).await
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<CreditMemoWithApplications, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let memo = CreditMemo::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Credit memo not found: {}", id)))?;
+        let applications = CreditMemoApplication::find()
+            .filter(credit_memo_application::Column::CreditMemoId.eq(id))
+            .order_by_asc(credit_memo_application::Column::AppliedAt)
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        Ok(CreditMemoWithApplications { memo, applications })
+    }
+
+    pub async fn list(
+        &self,
+        filter: CreditMemoFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<credit_memo::Model>, u64), ServiceError> {
+        let mut query = CreditMemo::find();
+        if let Some(customer_id) = filter.customer_id {
+            query = query.filter(credit_memo::Column::CustomerId.eq(customer_id));
+        }
+        if let Some(status) = filter.status {
+            query = query.filter(credit_memo::Column::Status.eq(status));
+        }
+        if let Some(source) = filter.source {
+            query = query.filter(credit_memo::Column::Source.eq(source));
+        }
+        let paginator = query
+            .order_by_desc(credit_memo::Column::CreatedAt)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let memos = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((memos, total))
+    }
+
+    /// Applies part of a memo to an invoice, or refunds it to the customer.
+    #[instrument(skip(self, input))]
+    pub async fn apply(
+        &self,
+        id: Uuid,
+        input: NewApplication,
+        actor: &str,
+    ) -> Result<credit_memo_application::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid application: {}", e)))?;
+        let invoice_id = input.invoice_id.filter(|i| !i.trim().is_empty());
+        if input.kind == ApplicationKind::Invoice && invoice_id.is_none() {
+            return Err(ServiceError::ValidationError("invoice_id is required to apply credit to an invoice".to_string()));
+        }
+
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let memo = find_locked(&txn, id).await?;
+        let (remaining, status) = draw_down(&memo, input.amount).map_err(ServiceError::ValidationError)?;
+        let application = credit_memo_application::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            credit_memo_id: Set(id),
+            kind: Set(input.kind),
+            invoice_id: Set(if input.kind == ApplicationKind::Invoice { invoice_id } else { None }),
+            reference: Set(input.reference),
+            amount: Set(input.amount),
+            applied_by: Set(actor.to_string()),
+            applied_at: Set(Utc::now()),
+            exported_at: Set(None),
+        }
+        .insert(&txn)
+        .await
+        .map_err(db_error)?;
+        let mut active: credit_memo::ActiveModel = memo.into();
+        active.remaining = Set(remaining);
+        active.status = Set(status);
+        active.updated_at = Set(Utc::now());
+        active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+
+        let _ = self.events.send(Event::CreditMemoApplied {
+            credit_memo_id: id,
+            application_id: application.id,
+            kind: format!("{:?}", application.kind).to_lowercase(),
+            amount: application.amount,
+        });
+        Ok(application)
+    }
+
+    /// Voids a memo nothing has been drawn from yet.
+    pub async fn void(&self, id: Uuid) -> Result<credit_memo::Model, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let memo = find_locked(&txn, id).await?;
+        if memo.status != CreditMemoStatus::Open {
+            return Err(ServiceError::ValidationError(format!(
+                "Only open memos with nothing applied can be voided, not {:?}",
+                memo.status
+            )));
+        }
+        let mut active: credit_memo::ActiveModel = memo.into();
+        active.status = Set(CreditMemoStatus::Voided);
+        active.remaining = Set(Decimal::ZERO);
+        active.updated_at = Set(Utc::now());
+        let memo = active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+        Ok(memo)
+    }
+
+    /// Issued memos and applications an accounting export has not acknowledged yet, oldest first.
+    pub async fn unexported(&self, limit: u64) -> Result<ExportBatch, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let limit = limit.clamp(1, MAX_EXPORT_BATCH);
+        let credit_memos = CreditMemo::find()
+            .filter(credit_memo::Column::ExportedAt.is_null())
+            .filter(credit_memo::Column::Status.is_not_in([CreditMemoStatus::PendingApproval]))
+            .order_by_asc(credit_memo::Column::CreatedAt)
+            .limit(limit)
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        let applications = CreditMemoApplication::find()
+            .filter(credit_memo_application::Column::ExportedAt.is_null())
+            .order_by_asc(credit_memo_application::Column::AppliedAt)
+            .limit(limit)
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        Ok(ExportBatch { credit_memos, applications })
+    }
+
+    /// Marks records as exported so later batches skip them.
+    pub async fn acknowledge_export(&self, ack: ExportAck) -> Result<u64, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let now = Utc::now();
+        let mut updated = 0;
+        if !ack.credit_memo_ids.is_empty() {
+            updated += CreditMemo::update_many()
+                .col_expr(credit_memo::Column::ExportedAt, Expr::value(now))
+                .filter(credit_memo::Column::Id.is_in(ack.credit_memo_ids))
+                .exec(db)
+                .await
+                .map_err(db_error)?
+                .rows_affected;
+        }
+        if !ack.application_ids.is_empty() {
+            updated += CreditMemoApplication::update_many()
+                .col_expr(credit_memo_application::Column::ExportedAt, Expr::value(now))
+                .filter(credit_memo_application::Column::Id.is_in(ack.application_ids))
+                .exec(db)
+                .await
+                .map_err(db_error)?
+                .rows_affected;
+        }
+        Ok(updated)
+    }
+}
+
+/// Workflow side of manual credit memos: routed on the memo amount, opened on approval
+/// and voided on rejection.
+pub struct CreditMemoApprovals;
+
+async fn find_pending(txn: &DatabaseTransaction, subject_id: &str) -> Result<credit_memo::Model, ServiceError> {
+    let id = Uuid::parse_str(subject_id)
+        .map_err(|_| ServiceError::ValidationError(format!("Invalid credit memo id: {}", subject_id)))?;
+    let memo = find_locked(txn, id).await?;
+    if memo.status != CreditMemoStatus::PendingApproval {
+        return Err(ServiceError::ValidationError(format!("Credit memo is {:?}", memo.status)));
+    }
+    Ok(memo)
+}
+
+#[async_trait]
+impl ApprovalHandler for CreditMemoApprovals {
+    fn subject(&self) -> ApprovalSubject {
+        ApprovalSubject::CreditMemo
+    }
+
+    async fn submit(&self, txn: &DatabaseTransaction, subject_id: &str, _actor: &str) -> Result<Decimal, ServiceError> {
+        Ok(find_pending(txn, subject_id).await?.amount)
+    }
+
+    async fn resolve(
+        &self,
+        txn: &DatabaseTransaction,
+        subject_id: &str,
+        resolution: Resolution,
+        _actor: &str,
+    ) -> Result<Option<Event>, ServiceError> {
+        let memo = find_pending(txn, subject_id).await?;
+        let mut active: credit_memo::ActiveModel = memo.into();
+        active.updated_at = Set(Utc::now());
+        match resolution {
+            Resolution::Approved => {
+                active.status = Set(CreditMemoStatus::Open);
+                let memo = active.update(txn).await.map_err(db_error)?;
+                Ok(Some(issued(&memo)))
+            }
+            Resolution::Rejected => {
+                active.status = Set(CreditMemoStatus::Voided);
+                active.remaining = Set(Decimal::ZERO);
+                active.update(txn).await.map_err(db_error)?;
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn memo(status: CreditMemoStatus, remaining: Decimal) -> credit_memo::Model {
+        let now = Utc::now();
+        credit_memo::Model {
+            id: Uuid::new_v4(),
+            memo_number: memo_number(),
+            customer_id: Uuid::new_v4(),
+            source: CreditMemoSource::Manual,
+            return_id: None,
+            reason: "Price adjustment".to_string(),
+            currency: "USD".to_string(),
+            amount: dec!(100),
+            remaining,
+            status,
+            created_by: "user:1".to_string(),
+            created_at: now,
+            updated_at: now,
+            exported_at: None,
+        }
+    }
+
+    #[test]
+    fn test_draw_down() {
+        assert_eq!(
+            draw_down(&memo(CreditMemoStatus::Open, dec!(100)), dec!(40)),
+            Ok((dec!(60), CreditMemoStatus::PartiallyApplied))
+        );
+        assert_eq!(
+            draw_down(&memo(CreditMemoStatus::PartiallyApplied, dec!(60)), dec!(60)),
+            Ok((dec!(0), CreditMemoStatus::Closed))
+        );
+    }
+
+    #[test]
+    fn test_draw_down_rejects_overdraw_and_unissued_memos() {
+        assert!(draw_down(&memo(CreditMemoStatus::Open, dec!(10)), dec!(10.01)).is_err());
+        assert!(draw_down(&memo(CreditMemoStatus::Open, dec!(10)), dec!(0)).is_err());
+        assert!(draw_down(&memo(CreditMemoStatus::PendingApproval, dec!(100)), dec!(5)).is_err());
+        assert!(draw_down(&memo(CreditMemoStatus::Voided, dec!(0)), dec!(5)).is_err());
+    }
+
+    #[test]
+    fn test_memo_number_format() {
+        let number = memo_number();
+        assert!(number.starts_with("CM-"));
+        assert_eq!(number.len(), 15);
+    }
+}
@@ -0,0 +1,568 @@
+use chrono::{DateTime, Utc};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::{
+    db::DbPool,
+    errors::ServiceError,
+    events::{Event, EventSender},
+    models::{
+        asn::{self, AsnStatus, Entity as Asn},
+        asn_line::{self, Entity as AsnLine},
+        backorder::{self, BackorderStatus, Entity as Backorder},
+        cross_dock_allocation::{self, CrossDockStatus, CrossDockTarget, Entity as CrossDockAllocation},
+        transfer_order::{self, Entity as TransferOrder, TransferOrderStatus},
+    },
+    utils::pagination::PaginationParams,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewAsnLine {
+    #[validate(length(min = 1))]
+    pub sku: String,
+    #[validate(range(min = 1))]
+    pub quantity: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewAsn {
+    #[validate(length(min = 1, max = 100))]
+    pub asn_number: String,
+    pub supplier_id: Uuid,
+    pub purchase_order_id: Option<Uuid>,
+    pub warehouse: i32,
+    pub expected_at: Option<DateTime<Utc>>,
+    #[validate(length(min = 1, max = 500))]
+    #[validate]
+    pub lines: Vec<NewAsnLine>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewBackorder {
+    pub order_id: Uuid,
+    #[validate(length(min = 1))]
+    pub sku: String,
+    pub warehouse: i32,
+    #[validate(range(min = 1))]
+    pub quantity: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_transfer"))]
+pub struct NewTransferOrder {
+    #[validate(length(min = 1))]
+    pub sku: String,
+    pub from_warehouse: i32,
+    pub to_warehouse: i32,
+    #[validate(range(min = 1))]
+    pub quantity: i32,
+}
+
+fn validate_transfer(transfer: &NewTransferOrder) -> Result<(), ValidationError> {
+    if transfer.from_warehouse == transfer.to_warehouse {
+        return Err(ValidationError::new("transfer_to_same_warehouse"));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AsnFilter {
+    pub status: Option<AsnStatus>,
+    pub warehouse: Option<i32>,
+    pub supplier_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DemandFilter {
+    pub sku: Option<String>,
+    pub warehouse: Option<i32>,
+    /// Only demand still waiting on stock.
+    #[serde(default)]
+    pub open: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AsnWithLines {
+    #[serde(flatten)]
+    pub asn: asn::Model,
+    pub lines: Vec<asn_line::Model>,
+    pub cross_dock: Vec<cross_dock_allocation::Model>,
+}
+
+/// The result of planning an ASN: where inbound units go once they reach the dock.
+#[derive(Debug, Clone, Serialize)]
+pub struct CrossDockPlan {
+    pub asn_id: Uuid,
+    /// Allocations added by this run.
+    pub planned: Vec<cross_dock_allocation::Model>,
+    /// Every live allocation for the ASN, including earlier runs.
+    pub allocations: Vec<cross_dock_allocation::Model>,
+    /// Units per SKU left for put-away.
+    pub put_away: BTreeMap<String, i32>,
+}
+
+/// Inbound units on an ASN line not yet routed anywhere.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InboundLine {
+    pub line_id: Uuid,
+    pub sku: String,
+    pub unallocated: i32,
+}
+
+/// Outbound units waiting on stock.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenDemand {
+    pub target: CrossDockTarget,
+    pub id: Uuid,
+    pub sku: String,
+    pub open: i32,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Route {
+    pub line_id: Uuid,
+    pub target: CrossDockTarget,
+    pub target_id: Uuid,
+    pub quantity: i32,
+}
+
+/// Matches inbound lines to open demand of the same SKU. Customer backorders are served
+/// before transfer orders, oldest first; demand can be split across lines.
+pub fn plan_routes(lines: &[InboundLine], demand: &[OpenDemand]) -> Vec<Route> {
+    let mut remaining: Vec<i32> = lines.iter().map(|l| l.unallocated.max(0)).collect();
+    let mut demand: Vec<&OpenDemand> = demand.iter().filter(|d| d.open > 0).collect();
+    demand.sort_by(|a, b| a.target.cmp(&b.target).then(a.created_at.cmp(&b.created_at)));
+
+    let mut routes = Vec::new();
+    for d in demand {
+        let mut open = d.open;
+        for (line, left) in lines.iter().zip(remaining.iter_mut()) {
+            if open == 0 {
+                break;
+            }
+            if line.sku != d.sku || *left == 0 {
+                continue;
+            }
+            let quantity = open.min(*left);
+            *left -= quantity;
+            open -= quantity;
+            routes.push(Route { line_id: line.line_id, target: d.target, target_id: d.id, quantity });
+        }
+    }
+    routes
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Cross-dock query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// ASNs and the outbound demand their goods can be cross-docked to.
+pub struct CrossDockService {
+    db_pool: Arc<DbPool>,
+    events: EventSender,
+}
+
+impl CrossDockService {
+    pub fn new(db_pool: Arc<DbPool>, events: EventSender) -> Self {
+        Self { db_pool, events }
+    }
+
+    #[instrument(skip(self, input), fields(asn_number = %input.asn_number))]
+    pub async fn create_asn(&self, input: NewAsn) -> Result<AsnWithLines, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid ASN: {}", e)))?;
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let existing = Asn::find()
+            .filter(asn::Column::AsnNumber.eq(input.asn_number.as_str()))
+            .one(&txn)
+            .await
+            .map_err(db_error)?;
+        if existing.is_some() {
+            return Err(ServiceError::ValidationError(format!("ASN {} already exists", input.asn_number)));
+        }
+        let now = Utc::now();
+        let asn = asn::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            asn_number: Set(input.asn_number),
+            supplier_id: Set(input.supplier_id),
+            purchase_order_id: Set(input.purchase_order_id),
+            warehouse: Set(input.warehouse),
+            status: Set(AsnStatus::Expected),
+            expected_at: Set(input.expected_at),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(&txn)
+        .await
+        .map_err(db_error)?;
+        let mut lines = Vec::with_capacity(input.lines.len());
+        for line in input.lines {
+            let line = asn_line::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                asn_id: Set(asn.id),
+                sku: Set(line.sku),
+                quantity: Set(line.quantity),
+                cross_dock_quantity: Set(0),
+            }
+            .insert(&txn)
+            .await
+            .map_err(db_error)?;
+            lines.push(line);
+        }
+        txn.commit().await.map_err(db_error)?;
+        Ok(AsnWithLines { asn, lines, cross_dock: Vec::new() })
+    }
+
+    pub async fn get_asn(&self, id: Uuid) -> Result<AsnWithLines, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let asn = Asn::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("ASN not found: {}", id)))?;
+        let lines = AsnLine::find()
+            .filter(asn_line::Column::AsnId.eq(id))
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        let cross_dock = self.allocations(db, id).await?;
+        Ok(AsnWithLines { asn, lines, cross_dock })
+    }
+
+    pub async fn list_asns(
+        &self,
+        filter: AsnFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<asn::Model>, u64), ServiceError> {
+        let mut query = Asn::find();
+        if let Some(status) = filter.status {
+            query = query.filter(asn::Column::Status.eq(status));
+        }
+        if let Some(warehouse) = filter.warehouse {
+            query = query.filter(asn::Column::Warehouse.eq(warehouse));
+        }
+        if let Some(supplier_id) = filter.supplier_id {
+            query = query.filter(asn::Column::SupplierId.eq(supplier_id));
+        }
+        let paginator = query
+            .order_by_desc(asn::Column::CreatedAt)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let asns = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((asns, total))
+    }
+
+    /// Routes the ASN's unallocated units to open backorders and transfer orders leaving
+    /// its warehouse, reserving them on the ASN lines so they skip put-away. Running it
+    /// again only plans units and demand that are still open.
+    #[instrument(skip(self))]
+    pub async fn plan_cross_dock(&self, asn_id: Uuid, actor: &str) -> Result<CrossDockPlan, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let asn = Asn::find_by_id(asn_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("ASN not found: {}", asn_id)))?;
+        if !asn.status.is_inbound() {
+            return Err(ServiceError::ValidationError(format!(
+                "A {:?} ASN cannot be planned for cross-docking",
+                asn.status
+            )));
+        }
+        let lines = AsnLine::find()
+            .filter(asn_line::Column::AsnId.eq(asn_id))
+            .lock_exclusive()
+            .all(&txn)
+            .await
+            .map_err(db_error)?;
+        let skus: Vec<String> = lines.iter().map(|l| l.sku.clone()).collect();
+        let backorders = Backorder::find()
+            .filter(backorder::Column::Warehouse.eq(asn.warehouse))
+            .filter(backorder::Column::Status.eq(BackorderStatus::Open))
+            .filter(backorder::Column::Sku.is_in(skus.clone()))
+            .lock_exclusive()
+            .all(&txn)
+            .await
+            .map_err(db_error)?;
+        let transfers = TransferOrder::find()
+            .filter(transfer_order::Column::FromWarehouse.eq(asn.warehouse))
+            .filter(transfer_order::Column::Status.eq(TransferOrderStatus::Open))
+            .filter(transfer_order::Column::Sku.is_in(skus))
+            .lock_exclusive()
+            .all(&txn)
+            .await
+            .map_err(db_error)?;
+
+        let inbound: Vec<InboundLine> = lines
+            .iter()
+            .map(|l| InboundLine { line_id: l.id, sku: l.sku.clone(), unallocated: l.quantity - l.cross_dock_quantity })
+            .collect();
+        let demand: Vec<OpenDemand> = backorders
+            .iter()
+            .map(|b| OpenDemand {
+                target: CrossDockTarget::Backorder,
+                id: b.id,
+                sku: b.sku.clone(),
+                open: b.quantity - b.allocated_quantity,
+                created_at: b.created_at,
+            })
+            .chain(transfers.iter().map(|t| OpenDemand {
+                target: CrossDockTarget::TransferOrder,
+                id: t.id,
+                sku: t.sku.clone(),
+                open: t.quantity - t.allocated_quantity,
+                created_at: t.created_at,
+            }))
+            .collect();
+        let routes = plan_routes(&inbound, &demand);
+
+        let now = Utc::now();
+        let mut planned = Vec::with_capacity(routes.len());
+        for route in &routes {
+            let line = lines.iter().find(|l| l.id == route.line_id).expect("route from a planned line");
+            let (order_id, to_warehouse) = match route.target {
+                CrossDockTarget::Backorder => {
+                    (backorders.iter().find(|b| b.id == route.target_id).map(|b| b.order_id), None)
+                }
+                CrossDockTarget::TransferOrder => {
+                    (None, transfers.iter().find(|t| t.id == route.target_id).map(|t| t.to_warehouse))
+                }
+            };
+            let allocation = cross_dock_allocation::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                asn_id: Set(asn_id),
+                asn_line_id: Set(line.id),
+                sku: Set(line.sku.clone()),
+                quantity: Set(route.quantity),
+                target: Set(route.target),
+                target_id: Set(route.target_id),
+                order_id: Set(order_id),
+                to_warehouse: Set(to_warehouse),
+                status: Set(CrossDockStatus::Planned),
+                created_by: Set(actor.to_string()),
+                created_at: Set(now),
+            }
+            .insert(&txn)
+            .await
+            .map_err(db_error)?;
+            planned.push(allocation);
+        }
+
+        for line in &lines {
+            let reserved: i32 = routes.iter().filter(|r| r.line_id == line.id).map(|r| r.quantity).sum();
+            if reserved > 0 {
+                let mut active: asn_line::ActiveModel = line.clone().into();
+                active.cross_dock_quantity = Set(line.cross_dock_quantity + reserved);
+                active.update(&txn).await.map_err(db_error)?;
+            }
+        }
+        for order in &backorders {
+            let allocated: i32 = routes.iter().filter(|r| r.target_id == order.id).map(|r| r.quantity).sum();
+            if allocated > 0 {
+                let allocated = order.allocated_quantity + allocated;
+                let mut active: backorder::ActiveModel = order.clone().into();
+                active.allocated_quantity = Set(allocated);
+                if allocated >= order.quantity {
+                    active.status = Set(BackorderStatus::Allocated);
+                }
+                active.updated_at = Set(now);
+                active.update(&txn).await.map_err(db_error)?;
+            }
+        }
+        for transfer in &transfers {
+            let allocated: i32 = routes.iter().filter(|r| r.target_id == transfer.id).map(|r| r.quantity).sum();
+            if allocated > 0 {
+                let allocated = transfer.allocated_quantity + allocated;
+                let mut active: transfer_order::ActiveModel = transfer.clone().into();
+                active.allocated_quantity = Set(allocated);
+                if allocated >= transfer.quantity {
+                    active.status = Set(TransferOrderStatus::Allocated);
+                }
+                active.updated_at = Set(now);
+                active.update(&txn).await.map_err(db_error)?;
+            }
+        }
+        txn.commit().await.map_err(db_error)?;
+
+        let mut put_away: BTreeMap<String, i32> = BTreeMap::new();
+        for line in &lines {
+            let reserved: i32 = routes.iter().filter(|r| r.line_id == line.id).map(|r| r.quantity).sum();
+            *put_away.entry(line.sku.clone()).or_default() += line.quantity - line.cross_dock_quantity - reserved;
+        }
+        let quantity: i32 = routes.iter().map(|r| r.quantity).sum();
+        if !planned.is_empty() {
+            info!(asn_id = %asn_id, allocations = planned.len(), quantity, "Cross-dock planned");
+            let _ = self.events.send(Event::CrossDockPlanned {
+                asn_id,
+                allocations: planned.len(),
+                quantity,
+            });
+        }
+        let allocations = self.allocations(self.db_pool.as_ref(), asn_id).await?;
+        Ok(CrossDockPlan { asn_id, planned, allocations, put_away })
+    }
+
+    async fn allocations<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        asn_id: Uuid,
+    ) -> Result<Vec<cross_dock_allocation::Model>, ServiceError> {
+        CrossDockAllocation::find()
+            .filter(cross_dock_allocation::Column::AsnId.eq(asn_id))
+            .filter(cross_dock_allocation::Column::Status.ne(CrossDockStatus::Cancelled))
+            .order_by_asc(cross_dock_allocation::Column::CreatedAt)
+            .all(db)
+            .await
+            .map_err(db_error)
+    }
+
+    pub async fn create_backorder(&self, input: NewBackorder) -> Result<backorder::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid backorder: {}", e)))?;
+        let now = Utc::now();
+        backorder::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            order_id: Set(input.order_id),
+            sku: Set(input.sku),
+            warehouse: Set(input.warehouse),
+            quantity: Set(input.quantity),
+            allocated_quantity: Set(0),
+            status: Set(BackorderStatus::Open),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(self.db_pool.as_ref())
+        .await
+        .map_err(db_error)
+    }
+
+    pub async fn list_backorders(
+        &self,
+        filter: DemandFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<backorder::Model>, u64), ServiceError> {
+        let mut query = Backorder::find();
+        if let Some(sku) = filter.sku {
+            query = query.filter(backorder::Column::Sku.eq(sku));
+        }
+        if let Some(warehouse) = filter.warehouse {
+            query = query.filter(backorder::Column::Warehouse.eq(warehouse));
+        }
+        if filter.open {
+            query = query.filter(backorder::Column::Status.eq(BackorderStatus::Open));
+        }
+        let paginator = query
+            .order_by_asc(backorder::Column::CreatedAt)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let backorders = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((backorders, total))
+    }
+
+    pub async fn create_transfer_order(
+        &self,
+        input: NewTransferOrder,
+        actor: &str,
+    ) -> Result<transfer_order::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid transfer order: {}", e)))?;
+        let now = Utc::now();
+        transfer_order::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            sku: Set(input.sku),
+            from_warehouse: Set(input.from_warehouse),
+            to_warehouse: Set(input.to_warehouse),
+            quantity: Set(input.quantity),
+            allocated_quantity: Set(0),
+            status: Set(TransferOrderStatus::Open),
+            created_by: Set(actor.to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(self.db_pool.as_ref())
+        .await
+        .map_err(db_error)
+    }
+
+    /// Transfer orders; `warehouse` matches the sending warehouse.
+    pub async fn list_transfer_orders(
+        &self,
+        filter: DemandFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<transfer_order::Model>, u64), ServiceError> {
+        let mut query = TransferOrder::find();
+        if let Some(sku) = filter.sku {
+            query = query.filter(transfer_order::Column::Sku.eq(sku));
+        }
+        if let Some(warehouse) = filter.warehouse {
+            query = query.filter(transfer_order::Column::FromWarehouse.eq(warehouse));
+        }
+        if filter.open {
+            query = query.filter(transfer_order::Column::Status.eq(TransferOrderStatus::Open));
+        }
+        let paginator = query
+            .order_by_asc(transfer_order::Column::CreatedAt)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let transfers = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((transfers, total))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn line(sku: &str, unallocated: i32) -> InboundLine {
+        InboundLine { line_id: Uuid::new_v4(), sku: sku.to_string(), unallocated }
+    }
+
+    fn demand(target: CrossDockTarget, sku: &str, open: i32, age_days: i64) -> OpenDemand {
+        OpenDemand {
+            target,
+            id: Uuid::new_v4(),
+            sku: sku.to_string(),
+            open,
+            created_at: Utc::now() - Duration::days(age_days),
+        }
+    }
+
+    #[test]
+    fn test_backorders_before_transfers_oldest_first() {
+        let lines = [line("MUG", 10)];
+        let transfer = demand(CrossDockTarget::TransferOrder, "MUG", 5, 10);
+        let newer = demand(CrossDockTarget::Backorder, "MUG", 4, 1);
+        let older = demand(CrossDockTarget::Backorder, "MUG", 3, 2);
+        let routes = plan_routes(&lines, &[transfer.clone(), newer.clone(), older.clone()]);
+        let served: Vec<(Uuid, i32)> = routes.iter().map(|r| (r.target_id, r.quantity)).collect();
+        assert_eq!(served, vec![(older.id, 3), (newer.id, 4), (transfer.id, 3)]);
+    }
+
+    #[test]
+    fn test_demand_split_across_lines_of_same_sku() {
+        let lines = [line("MUG", 2), line("LAMP", 9), line("MUG", 5)];
+        let order = demand(CrossDockTarget::Backorder, "MUG", 6, 0);
+        let routes = plan_routes(&lines, &[order]);
+        assert_eq!(routes.len(), 2);
+        assert_eq!((routes[0].line_id, routes[0].quantity), (lines[0].line_id, 2));
+        assert_eq!((routes[1].line_id, routes[1].quantity), (lines[2].line_id, 4));
+    }
+
+    #[test]
+    fn test_nothing_routed_without_matching_demand() {
+        let lines = [line("MUG", 0), line("LAMP", 3)];
+        assert!(plan_routes(&lines, &[demand(CrossDockTarget::Backorder, "MUG", 2, 0)]).is_empty());
+    }
+}
@@ -0,0 +1,272 @@
+use chrono::Utc;
+use rust_decimal::Decimal;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    customs::{self, CustomsConfig, DutiableLine, DutyEstimate},
+    db::DbPool,
+    errors::ServiceError,
+    models::{
+        product_listing::{self, Entity as ProductListing},
+        shipment::Entity as Shipment,
+        shipment_customs_line::{self, Entity as ShipmentCustomsLine},
+    },
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ValuedLine {
+    #[validate(length(min = 1))]
+    pub sku: String,
+    #[validate(range(min = 1))]
+    pub quantity: i32,
+    /// Price paid per unit; the listing price when omitted.
+    #[serde(default, with = "crate::money::option_amount")]
+    pub unit_value: Option<Decimal>,
+}
+
+/// Duties and taxes for a cart, quoted at checkout when selling delivered duty paid.
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct DutyQuoteRequest {
+    #[validate(length(equal = 2))]
+    pub destination_country: String,
+    #[validate(length(equal = 3))]
+    pub currency: String,
+    #[serde(default, with = "crate::money::amount")]
+    #[validate(custom = "crate::money::validate_non_negative")]
+    pub shipping: Decimal,
+    #[validate(length(min = 1))]
+    #[validate]
+    pub items: Vec<ValuedLine>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DutyQuote {
+    pub international: bool,
+    /// `None` for domestic destinations.
+    pub estimate: Option<DutyEstimate>,
+}
+
+/// Contents of an international shipment, from which its customs lines are generated.
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CustomsDeclarationRequest {
+    #[validate(length(equal = 2))]
+    pub destination_country: String,
+    #[validate(length(equal = 3))]
+    pub currency: String,
+    #[validate(length(min = 1))]
+    #[validate]
+    pub items: Vec<ValuedLine>,
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Customs query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Joins each item with its listing. Every SKU must be listed.
+fn dutiable_lines(
+    items: &[ValuedLine],
+    listings: &HashMap<String, product_listing::Model>,
+) -> Result<Vec<DutiableLine>, ServiceError> {
+    items
+        .iter()
+        .map(|item| {
+            let listing = listings
+                .get(&item.sku)
+                .ok_or_else(|| ServiceError::NotFound(format!("Product listing not found: {}", item.sku)))?;
+            Ok(DutiableLine {
+                sku: item.sku.clone(),
+                description: listing.title.clone(),
+                hs_code: listing.hs_code.clone(),
+                country_of_origin: listing.country_of_origin.clone(),
+                quantity: item.quantity,
+                unit_value: item.unit_value.unwrap_or(listing.price),
+            })
+        })
+        .collect()
+}
+
+/// HS code and country-of-origin lookups for international shipments: customs lines for
+/// the carrier and landed duty estimates for checkout.
+pub struct CustomsService {
+    db_pool: Arc<DbPool>,
+    config: Arc<CustomsConfig>,
+}
+
+impl CustomsService {
+    pub fn new(db_pool: Arc<DbPool>, config: Arc<CustomsConfig>) -> Self {
+        Self { db_pool, config }
+    }
+
+    async fn listings<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        items: &[ValuedLine],
+    ) -> Result<HashMap<String, product_listing::Model>, ServiceError> {
+        Ok(ProductListing::find()
+            .filter(product_listing::Column::Sku.is_in(items.iter().map(|i| i.sku.clone())))
+            .all(db)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(|l| (l.sku.clone(), l))
+            .collect())
+    }
+
+    /// Duties and import taxes the shipper pays when quoting DDP to `destination_country`.
+    #[instrument(skip(self, request), fields(destination = %request.destination_country))]
+    pub async fn estimate(&self, request: DutyQuoteRequest) -> Result<DutyQuote, ServiceError> {
+        request.validate().map_err(|e| ServiceError::ValidationError(e.to_string()))?;
+        if !self.config.is_international(&request.destination_country) {
+            return Ok(DutyQuote { international: false, estimate: None });
+        }
+        let schedule = self
+            .config
+            .schedule(&request.destination_country)
+            .filter(|s| s.currency.eq_ignore_ascii_case(&request.currency))
+            .ok_or_else(|| {
+                ServiceError::ValidationError(format!(
+                    "No duty schedule for {} in {}",
+                    request.destination_country.to_uppercase(),
+                    request.currency.to_uppercase()
+                ))
+            })?;
+        let listings = self.listings(self.db_pool.as_ref(), &request.items).await?;
+        let lines = dutiable_lines(&request.items, &listings)?;
+        Ok(DutyQuote { international: true, estimate: Some(customs::estimate(schedule, &lines, request.shipping)) })
+    }
+
+    /// Generates the customs lines of an international shipment, replacing any generated
+    /// before. Every SKU needs an HS code and a country of origin on its listing.
+    #[instrument(skip(self, request))]
+    pub async fn declare(
+        &self,
+        shipment_id: i32,
+        request: CustomsDeclarationRequest,
+    ) -> Result<Vec<shipment_customs_line::Model>, ServiceError> {
+        request.validate().map_err(|e| ServiceError::ValidationError(e.to_string()))?;
+        if !self.config.is_international(&request.destination_country) {
+            return Err(ServiceError::ValidationError(format!(
+                "Shipment {} to {} is domestic and needs no customs lines",
+                shipment_id, request.destination_country
+            )));
+        }
+        Shipment::find_by_id(shipment_id)
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Shipment {} not found", shipment_id)))?;
+
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let listings = self.listings(&txn, &request.items).await?;
+        let lines = dutiable_lines(&request.items, &listings)?;
+        let unclassified: Vec<&str> = lines
+            .iter()
+            .filter(|l| l.hs_code.is_none() || l.country_of_origin.is_none())
+            .map(|l| l.sku.as_str())
+            .collect();
+        if !unclassified.is_empty() {
+            return Err(ServiceError::ValidationError(format!(
+                "HS code or country of origin missing for {}",
+                unclassified.join(", ")
+            )));
+        }
+
+        ShipmentCustomsLine::delete_many()
+            .filter(shipment_customs_line::Column::ShipmentId.eq(shipment_id))
+            .exec(&txn)
+            .await
+            .map_err(db_error)?;
+        let now = Utc::now();
+        let mut saved = Vec::with_capacity(lines.len());
+        for line in lines {
+            let model = shipment_customs_line::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                shipment_id: Set(shipment_id),
+                destination_country: Set(request.destination_country.to_uppercase()),
+                sku: Set(line.sku),
+                description: Set(line.description),
+                hs_code: Set(line.hs_code.unwrap_or_default()),
+                country_of_origin: Set(line.country_of_origin.unwrap_or_default()),
+                quantity: Set(line.quantity),
+                unit_value: Set(line.unit_value),
+                currency: Set(request.currency.to_uppercase()),
+                created_at: Set(now),
+            }
+            .insert(&txn)
+            .await
+            .map_err(db_error)?;
+            saved.push(model);
+        }
+        txn.commit().await.map_err(db_error)?;
+        info!(shipment_id, lines = saved.len(), "Customs lines generated");
+        Ok(saved)
+    }
+
+    pub async fn customs_lines(&self, shipment_id: i32) -> Result<Vec<shipment_customs_line::Model>, ServiceError> {
+        ShipmentCustomsLine::find()
+            .filter(shipment_customs_line::Column::ShipmentId.eq(shipment_id))
+            .order_by_asc(shipment_customs_line::Column::Sku)
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    fn listing(sku: &str, hs_code: Option<&str>) -> product_listing::Model {
+        let at = Utc.with_ymd_and_hms(2026, 10, 1, 12, 0, 0).unwrap();
+        product_listing::Model {
+            sku: sku.to_string(),
+            title: "Classic Tee".to_string(),
+            description: String::new(),
+            brand: None,
+            gtin: None,
+            item_group_id: None,
+            color: None,
+            size: None,
+            hs_code: hs_code.map(str::to_string),
+            country_of_origin: Some("PT".to_string()),
+            price: dec!(24.50),
+            currency: "USD".to_string(),
+            link: None,
+            image_link: None,
+            active: true,
+            enable_checkout: true,
+            metadata: None,
+            created_at: at,
+            updated_at: at,
+        }
+    }
+
+    #[test]
+    fn test_dutiable_lines_default_to_listing_price() {
+        let listings = HashMap::from([("TEE".to_string(), listing("TEE", Some("610910")))]);
+        let items = [
+            ValuedLine { sku: "TEE".to_string(), quantity: 2, unit_value: None },
+            ValuedLine { sku: "TEE".to_string(), quantity: 1, unit_value: Some(dec!(20)) },
+        ];
+        let lines = dutiable_lines(&items, &listings).unwrap();
+        assert_eq!(lines[0].value(), dec!(49.00));
+        assert_eq!(lines[1].value(), dec!(20));
+        assert_eq!(lines[0].hs_code.as_deref(), Some("610910"));
+    }
+
+    #[test]
+    fn test_unlisted_sku_is_not_found() {
+        let items = [ValuedLine { sku: "GHOST".to_string(), quantity: 1, unit_value: None }];
+        assert!(matches!(dutiable_lines(&items, &HashMap::new()), Err(ServiceError::NotFound(_))));
+    }
+}
@@ -0,0 +1,582 @@
+use chrono::{DateTime, Days, Utc};
+use rust_decimal::Decimal;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::{error, info, instrument, warn};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db::{dialect, DbPool},
+    dropship::{self, DropshipLine, ForwardLine, ForwardRequest, SupplierRegistry, TrackedPackage},
+    errors::ServiceError,
+    events::{Event, EventSender},
+    models::{
+        dropship_order::{self, DropshipStatus, Entity as DropshipOrder},
+        dropship_shipment::{self, Entity as DropshipShipment},
+        dropship_source::{self, Entity as DropshipSource},
+        order::{self, Entity as Order, OrderStatus},
+        purchase_order::{self, Entity as PurchaseOrder, PurchaseOrderStatus},
+        purchase_order_line::{self, Entity as PurchaseOrderLine},
+        shipment::{self, Entity as Shipment},
+    },
+    shutdown::Worker,
+    utils::pagination::PaginationParams,
+};
+
+/// Actor recorded on purchase orders raised for new orders.
+const SYSTEM_ACTOR: &str = "system";
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct SourceInput {
+    pub supplier_id: Uuid,
+    #[validate(length(min = 1, max = 64))]
+    pub supplier_sku: Option<String>,
+    #[serde(with = "crate::money::amount")]
+    #[validate(custom = "crate::money::validate_non_negative")]
+    pub unit_cost: Decimal,
+    #[validate(length(equal = 3))]
+    pub currency: String,
+    #[validate(range(min = 0, max = 365))]
+    #[serde(default)]
+    pub lead_time_days: i32,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DropshipFilter {
+    pub order_id: Option<Uuid>,
+    pub supplier_id: Option<Uuid>,
+    pub status: Option<DropshipStatus>,
+}
+
+/// A supplier's confirmation that it shipped (part of) a purchase order.
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct ShipmentConfirmation {
+    #[validate(length(min = 1))]
+    pub po_number: String,
+    #[validate(length(min = 1, max = 64))]
+    pub carrier: String,
+    #[validate(length(min = 1, max = 100))]
+    pub tracking_number: String,
+    #[validate(url)]
+    pub tracking_url: Option<String>,
+    /// When the package left the supplier; now when omitted.
+    pub shipped_at: Option<DateTime<Utc>>,
+}
+
+/// Every package of an order, from the warehouse and from dropship suppliers.
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderTracking {
+    pub order_id: Uuid,
+    pub order_number: String,
+    pub packages: Vec<TrackedPackage>,
+}
+
+/// Flagged units of each SKU on an order.
+#[derive(Debug, Clone, FromQueryResult)]
+struct FlaggedLine {
+    sku: String,
+    quantity: i32,
+}
+
+const DROPSHIP_LINES_SQL: &str = r#"
+SELECT li.seller_sku AS sku, SUM(li.quantity)::INT AS quantity
+FROM order_line_items li
+WHERE li.order_id = $1 AND li.dropship
+GROUP BY li.seller_sku
+ORDER BY li.seller_sku
+"#;
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Dropship query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Routes dropship lines of sales orders to vendor purchase orders, forwards them with the
+/// customer's shipping details, and records the packages suppliers ship.
+pub struct DropshipService {
+    db_pool: Arc<DbPool>,
+    suppliers: Arc<SupplierRegistry>,
+    events: EventSender,
+}
+
+impl DropshipService {
+    pub fn new(db_pool: Arc<DbPool>, suppliers: Arc<SupplierRegistry>, events: EventSender) -> Self {
+        Self { db_pool, suppliers, events }
+    }
+
+    pub async fn get_source(&self, sku: &str) -> Result<dropship_source::Model, ServiceError> {
+        DropshipSource::find_by_id(sku.to_string())
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Dropship source not found: {}", sku)))
+    }
+
+    pub async fn list_sources(
+        &self,
+        supplier_id: Option<Uuid>,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<dropship_source::Model>, u64), ServiceError> {
+        let mut query = DropshipSource::find();
+        if let Some(supplier_id) = supplier_id {
+            query = query.filter(dropship_source::Column::SupplierId.eq(supplier_id));
+        }
+        let paginator = query
+            .order_by_asc(dropship_source::Column::Sku)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let items = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((items, total))
+    }
+
+    /// Creates or replaces the supplier that dropships `sku`.
+    #[instrument(skip(self, input))]
+    pub async fn set_source(
+        &self,
+        sku: &str,
+        input: SourceInput,
+        actor: &str,
+    ) -> Result<dropship_source::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid dropship source: {}", e)))?;
+        let db = self.db_pool.as_ref();
+        let now = Utc::now();
+        let existing = DropshipSource::find_by_id(sku.to_string()).one(db).await.map_err(db_error)?;
+        let mut source = dropship_source::ActiveModel {
+            sku: Set(sku.to_string()),
+            supplier_id: Set(input.supplier_id),
+            supplier_sku: Set(input.supplier_sku),
+            unit_cost: Set(input.unit_cost),
+            currency: Set(input.currency.to_uppercase()),
+            lead_time_days: Set(input.lead_time_days),
+            active: Set(input.active),
+            updated_by: Set(actor.to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+        };
+        let saved = match existing {
+            Some(existing) => {
+                source.created_at = Set(existing.created_at);
+                source.update(db).await
+            }
+            None => source.insert(db).await,
+        }
+        .map_err(db_error)?;
+        Ok(saved)
+    }
+
+    pub async fn remove_source(&self, sku: &str) -> Result<(), ServiceError> {
+        let result = DropshipSource::delete_by_id(sku.to_string())
+            .exec(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?;
+        if result.rows_affected == 0 {
+            return Err(ServiceError::NotFound(format!("Dropship source not found: {}", sku)));
+        }
+        Ok(())
+    }
+
+    /// Raises one vendor purchase order per supplier for the order's dropship lines and
+    /// forwards each to its supplier. Orders already routed are returned as they are.
+    #[instrument(skip(self))]
+    pub async fn route_order(&self, order_id: Uuid, actor: &str) -> Result<Vec<dropship_order::Model>, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let order = Order::find_by_id(order_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Order {} not found", order_id)))?;
+        if order.order_status == OrderStatus::Cancelled {
+            return Err(ServiceError::ValidationError(format!(
+                "Order {} is cancelled and cannot be dropshipped",
+                order.order_number
+            )));
+        }
+        let routed = DropshipOrder::find()
+            .filter(dropship_order::Column::OrderId.eq(order_id))
+            .all(&txn)
+            .await
+            .map_err(db_error)?;
+        if !routed.is_empty() {
+            return Ok(routed);
+        }
+
+        let lines: Vec<DropshipLine> = FlaggedLine::find_by_statement(dialect::statement(
+            &txn,
+            DROPSHIP_LINES_SQL,
+            [order_id.into()],
+        ))
+        .all(&txn)
+        .await
+        .map_err(db_error)?
+        .into_iter()
+        .map(|l| DropshipLine { sku: l.sku, quantity: l.quantity })
+        .collect();
+        if lines.is_empty() {
+            return Ok(Vec::new());
+        }
+        let sources: HashMap<String, dropship_source::Model> = DropshipSource::find()
+            .filter(dropship_source::Column::Sku.is_in(lines.iter().map(|l| l.sku.clone())))
+            .all(&txn)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(|s| (s.sku.clone(), s))
+            .collect();
+        let by_supplier = dropship::route_lines(&lines, &sources).map_err(|unsourced| {
+            ServiceError::ValidationError(format!("No active dropship source for {}", unsourced.join(", ")))
+        })?;
+
+        let now = Utc::now();
+        let mut created = Vec::with_capacity(by_supplier.len());
+        for (supplier_id, supplier_lines) in by_supplier {
+            let lead_time = supplier_lines.iter().map(|(_, s)| s.lead_time_days).max().unwrap_or(0);
+            let total: Decimal = supplier_lines.iter().map(|(l, s)| s.unit_cost * Decimal::from(l.quantity)).sum();
+            let po = purchase_order::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                po_number: Set(format!("PO-{}", Uuid::new_v4().simple())),
+                supplier_id: Set(supplier_id),
+                requisition_id: Set(None),
+                status: Set(PurchaseOrderStatus::Open),
+                currency: Set(supplier_lines[0].1.currency.clone()),
+                total_amount: Set(total),
+                expected_delivery_date: Set(now.date_naive() + Days::new(lead_time.max(0) as u64)),
+                shipping_address: Set(json!({
+                    "name": order.customer_name,
+                    "email": order.customer_email,
+                    "address": order.delivery_address,
+                })),
+                created_by: Set(actor.to_string()),
+                created_at: Set(now),
+                updated_at: Set(now),
+            }
+            .insert(&txn)
+            .await
+            .map_err(db_error)?;
+            for (index, (line, source)) in supplier_lines.iter().enumerate() {
+                purchase_order_line::ActiveModel {
+                    id: Set(Uuid::new_v4()),
+                    purchase_order_id: Set(po.id),
+                    line_number: Set(index as i32 + 1),
+                    sku: Set(line.sku.clone()),
+                    description: Set(Some(format!("Dropship for order {}", order.order_number))),
+                    quantity: Set(line.quantity),
+                    unit_price: Set(source.unit_cost),
+                    tax_rate: Set(None),
+                    line_total: Set(source.unit_cost * Decimal::from(line.quantity)),
+                }
+                .insert(&txn)
+                .await
+                .map_err(db_error)?;
+            }
+            let dropship_order = dropship_order::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                order_id: Set(order.id),
+                purchase_order_id: Set(po.id),
+                supplier_id: Set(supplier_id),
+                customer_name: Set(order.customer_name.clone()),
+                customer_email: Set(order.customer_email.clone().into()),
+                ship_to: Set(order.delivery_address.clone()),
+                status: Set(DropshipStatus::Pending),
+                supplier_reference: Set(None),
+                forward_attempts: Set(0),
+                forward_error: Set(None),
+                forwarded_at: Set(None),
+                created_at: Set(now),
+                updated_at: Set(now),
+            }
+            .insert(&txn)
+            .await
+            .map_err(db_error)?;
+            created.push(dropship_order);
+        }
+        txn.commit().await.map_err(db_error)?;
+        info!(order_id = %order_id, purchase_orders = created.len(), "Dropship lines routed to suppliers");
+
+        // A failed forward leaves the order pending with its error, for a retry; the
+        // purchase orders stand either way
+        let mut forwarded = Vec::with_capacity(created.len());
+        for dropship_order in created {
+            forwarded.push(self.forward(dropship_order.id).await?);
+        }
+        Ok(forwarded)
+    }
+
+    /// Sends a pending dropship order and the customer's shipping details to its supplier.
+    /// A supplier failure is recorded on the order, which stays pending.
+    #[instrument(skip(self))]
+    pub async fn forward(&self, id: Uuid) -> Result<dropship_order::Model, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let dropship_order = self.get(id).await?;
+        if dropship_order.status != DropshipStatus::Pending {
+            return Err(ServiceError::ValidationError(format!(
+                "Dropship order {} is already {:?}",
+                id, dropship_order.status
+            )));
+        }
+        let po = PurchaseOrder::find_by_id(dropship_order.purchase_order_id)
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Purchase order {} not found", dropship_order.purchase_order_id)))?;
+        let order = Order::find_by_id(dropship_order.order_id)
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Order {} not found", dropship_order.order_id)))?;
+        let po_lines = PurchaseOrderLine::find()
+            .filter(purchase_order_line::Column::PurchaseOrderId.eq(po.id))
+            .order_by_asc(purchase_order_line::Column::LineNumber)
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        let supplier_skus: HashMap<String, Option<String>> = DropshipSource::find()
+            .filter(dropship_source::Column::Sku.is_in(po_lines.iter().map(|l| l.sku.clone())))
+            .all(db)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(|s| (s.sku, s.supplier_sku))
+            .collect();
+        let lines: Vec<ForwardLine> = po_lines
+            .iter()
+            .map(|l| ForwardLine {
+                sku: l.sku.clone(),
+                supplier_sku: supplier_skus.get(&l.sku).cloned().flatten(),
+                quantity: l.quantity,
+            })
+            .collect();
+        let request = ForwardRequest {
+            po_number: &po.po_number,
+            order_number: &order.order_number,
+            customer_name: &dropship_order.customer_name,
+            customer_email: &dropship_order.customer_email,
+            ship_to: &dropship_order.ship_to,
+            lines: &lines,
+        };
+        let forwarder = self.suppliers.forwarder(dropship_order.supplier_id);
+        let outcome = forwarder.forward(&request).await;
+
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let now = Utc::now();
+        let attempts = dropship_order.forward_attempts + 1;
+        let mut active: dropship_order::ActiveModel = dropship_order.into();
+        active.forward_attempts = Set(attempts);
+        active.updated_at = Set(now);
+        match &outcome {
+            Ok(receipt) => {
+                active.status = Set(DropshipStatus::Forwarded);
+                active.supplier_reference = Set(receipt.reference.clone());
+                active.forward_error = Set(None);
+                active.forwarded_at = Set(Some(now));
+                let mut po: purchase_order::ActiveModel = po.into();
+                po.status = Set(PurchaseOrderStatus::Sent);
+                po.updated_at = Set(now);
+                po.update(&txn).await.map_err(db_error)?;
+            }
+            Err(e) => active.forward_error = Set(Some(e.to_string())),
+        }
+        let updated = active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+        match outcome {
+            Ok(_) => info!(dropship_order_id = %id, forwarder = forwarder.name(), "Dropship order forwarded"),
+            Err(e) => warn!(dropship_order_id = %id, forwarder = forwarder.name(), "Dropship forwarding failed: {}", e),
+        }
+        Ok(updated)
+    }
+
+    /// Records a package the supplier shipped against one of its dropship purchase orders.
+    /// Confirming the same tracking number again is a no-op.
+    #[instrument(skip(self, confirmation), fields(po_number = %confirmation.po_number))]
+    pub async fn confirm_shipment(
+        &self,
+        confirmation: ShipmentConfirmation,
+        actor: &str,
+    ) -> Result<dropship_shipment::Model, ServiceError> {
+        confirmation
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid shipment confirmation: {}", e)))?;
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let po = PurchaseOrder::find()
+            .filter(purchase_order::Column::PoNumber.eq(confirmation.po_number.as_str()))
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Purchase order not found: {}", confirmation.po_number)))?;
+        let dropship_order = DropshipOrder::find()
+            .filter(dropship_order::Column::PurchaseOrderId.eq(po.id))
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| {
+                ServiceError::ValidationError(format!("{} is not a dropship purchase order", confirmation.po_number))
+            })?;
+        if let Some(existing) = DropshipShipment::find()
+            .filter(dropship_shipment::Column::DropshipOrderId.eq(dropship_order.id))
+            .filter(dropship_shipment::Column::TrackingNumber.eq(confirmation.tracking_number.as_str()))
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+        {
+            return Ok(existing);
+        }
+
+        let now = Utc::now();
+        let package = dropship_shipment::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            dropship_order_id: Set(dropship_order.id),
+            carrier: Set(confirmation.carrier),
+            tracking_number: Set(confirmation.tracking_number),
+            tracking_url: Set(confirmation.tracking_url),
+            shipped_at: Set(confirmation.shipped_at.unwrap_or(now)),
+            confirmed_by: Set(actor.to_string()),
+            created_at: Set(now),
+        }
+        .insert(&txn)
+        .await
+        .map_err(db_error)?;
+        // A supplier that ships before acknowledging the order has evidently received it
+        let order_id = dropship_order.order_id;
+        let mut active: dropship_order::ActiveModel = dropship_order.into();
+        active.status = Set(DropshipStatus::Shipped);
+        active.forward_error = Set(None);
+        active.updated_at = Set(now);
+        let dropship_order = active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+
+        let _ = self.events.send(Event::DropshipShipped {
+            order_id,
+            dropship_order_id: dropship_order.id,
+            tracking_number: package.tracking_number.clone(),
+        });
+        info!(order_id = %order_id, tracking_number = %package.tracking_number, "Dropship package confirmed");
+        Ok(package)
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<dropship_order::Model, ServiceError> {
+        DropshipOrder::find_by_id(id)
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Dropship order {} not found", id)))
+    }
+
+    pub async fn list(
+        &self,
+        filter: DropshipFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<dropship_order::Model>, u64), ServiceError> {
+        let mut query = DropshipOrder::find();
+        if let Some(order_id) = filter.order_id {
+            query = query.filter(dropship_order::Column::OrderId.eq(order_id));
+        }
+        if let Some(supplier_id) = filter.supplier_id {
+            query = query.filter(dropship_order::Column::SupplierId.eq(supplier_id));
+        }
+        if let Some(status) = filter.status {
+            query = query.filter(dropship_order::Column::Status.eq(status));
+        }
+        let paginator = query
+            .order_by_desc(dropship_order::Column::CreatedAt)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let items = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((items, total))
+    }
+
+    /// All packages of an order for the customer: warehouse shipments and whatever the
+    /// dropship suppliers have confirmed.
+    pub async fn tracking(&self, order_id: Uuid) -> Result<OrderTracking, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let order: order::Model = Order::find_by_id(order_id)
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Order {} not found", order_id)))?;
+        let mut packages = Vec::new();
+        // Shipments reference orders by tracking number; they carry no order UUID
+        if let Some(tracking) = &order.tracking_number {
+            for s in Shipment::find()
+                .filter(shipment::Column::TrackingNumber.eq(tracking.as_str()))
+                .all(db)
+                .await
+                .map_err(db_error)?
+            {
+                packages.push(TrackedPackage {
+                    shipped_by: "warehouse".to_string(),
+                    carrier: format!("{:?}", s.carrier),
+                    tracking_number: s.tracking_number,
+                    tracking_url: None,
+                    status: format!("{:?}", s.status).to_lowercase(),
+                    shipped_at: s.shipped_at.map(|t| t.with_timezone(&Utc)),
+                });
+            }
+        }
+        let dropship_orders = DropshipOrder::find()
+            .filter(dropship_order::Column::OrderId.eq(order_id))
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        for p in DropshipShipment::find()
+            .filter(dropship_shipment::Column::DropshipOrderId.is_in(dropship_orders.iter().map(|d| d.id)))
+            .all(db)
+            .await
+            .map_err(db_error)?
+        {
+            packages.push(TrackedPackage {
+                shipped_by: "supplier".to_string(),
+                carrier: p.carrier,
+                tracking_number: p.tracking_number,
+                tracking_url: p.tracking_url,
+                status: "shipped".to_string(),
+                shipped_at: Some(p.shipped_at),
+            });
+        }
+        Ok(OrderTracking {
+            order_id,
+            order_number: order.order_number,
+            packages: dropship::combine_tracking(packages),
+        })
+    }
+}
+
+/// Routes the dropship lines of every new order to their suppliers.
+pub fn spawn_dropship_listener(service: Arc<DropshipService>, events: EventSender, mut worker: Worker) {
+    let mut receiver = events.subscribe();
+    tokio::spawn(async move {
+        loop {
+            let received = tokio::select! {
+                biased;
+                received = receiver.recv() => received,
+                _ = worker.stopping() => break,
+            };
+            match received {
+                Ok(Event::OrderCreated(order_id)) => {
+                    if let Err(e) = service.route_order(order_id, SYSTEM_ACTOR).await {
+                        error!(order_id = %order_id, "Dropship routing failed: {}", e);
+                    }
+                }
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!(missed, "Dropship listener lagged; some orders need routing by hand");
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
@@ -0,0 +1,304 @@
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use tracing::{error, instrument, warn};
+use uuid::Uuid;
+
+use crate::{
+    db::{dialect, DbPool},
+    errors::ServiceError,
+    models::order_fingerprint::{self, DuplicateReview, Entity as OrderFingerprint},
+    utils::pagination::PaginationParams,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateAction {
+    /// Create the order and queue it for review.
+    #[default]
+    Flag,
+    /// Reject the order unless the caller sets `allow_duplicate`.
+    Block,
+}
+
+/// Duplicate order detection, loaded from the `duplicate_orders` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct DuplicateOrderConfig {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// An order matching one from the same customer placed this many minutes earlier is a
+    /// suspected duplicate.
+    #[serde(default = "default_window_minutes")]
+    pub window_minutes: i64,
+
+    #[serde(default)]
+    pub action: DuplicateAction,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_window_minutes() -> i64 {
+    10
+}
+
+impl Default for DuplicateOrderConfig {
+    fn default() -> Self {
+        Self { enabled: default_enabled(), window_minutes: default_window_minutes(), action: DuplicateAction::default() }
+    }
+}
+
+static POLICY: RwLock<Option<DuplicateOrderConfig>> = RwLock::new(None);
+
+/// Sets the duplicate order policy for the process. Called at startup from config.
+pub fn set_policy(config: DuplicateOrderConfig) {
+    *POLICY.write().unwrap_or_else(|e| e.into_inner()) = Some(config);
+}
+
+pub fn policy() -> DuplicateOrderConfig {
+    POLICY.read().unwrap_or_else(|e| e.into_inner()).clone().unwrap_or_default()
+}
+
+/// What makes two orders the same: customer, items with quantities, and total.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderSignature {
+    pub customer_id: Uuid,
+    pub lines: Vec<(String, i32)>,
+    /// `None` when the caller did not send a total; lines alone are compared then.
+    pub total: Option<Decimal>,
+}
+
+impl OrderSignature {
+    /// Hex SHA-256 of the signature. Line order and repeated items do not matter, and
+    /// totals are compared to the cent.
+    pub fn fingerprint(&self) -> String {
+        let mut lines: BTreeMap<&str, i64> = BTreeMap::new();
+        for (item, quantity) in &self.lines {
+            *lines.entry(item.as_str()).or_default() += i64::from(*quantity);
+        }
+        let mut hasher = Sha256::new();
+        hasher.update(self.customer_id.as_bytes());
+        for (item, quantity) in lines {
+            hasher.update(format!("|{}x{}", item, quantity).as_bytes());
+        }
+        if let Some(total) = self.total {
+            hasher.update(format!("|total={}", total.round_dp(2).normalize()).as_bytes());
+        }
+        hex::encode(hasher.finalize())
+    }
+}
+
+/// Outcome of screening a new order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Screening {
+    pub fingerprint: String,
+    /// Most recent matching order inside the window.
+    pub duplicate_of: Option<Uuid>,
+    pub overridden: bool,
+}
+
+/// Whether a match blocks the order under `config`.
+pub fn blocks(config: &DuplicateOrderConfig, screening: &Screening) -> bool {
+    config.action == DuplicateAction::Block && screening.duplicate_of.is_some() && !screening.overridden
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Duplicate order query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Looks for a recent order with the same fingerprint. Run inside the transaction that
+/// creates the order: a transaction-scoped advisory lock on the fingerprint makes a
+/// second submit of the same order wait for the first and see it.
+pub async fn screen(
+    txn: &DatabaseTransaction,
+    signature: &OrderSignature,
+    allow_duplicate: bool,
+    now: DateTime<Utc>,
+) -> Result<Screening, ServiceError> {
+    let config = policy();
+    let fingerprint = signature.fingerprint();
+    if !config.enabled {
+        return Ok(Screening { fingerprint, duplicate_of: None, overridden: false });
+    }
+    dialect::advisory_xact_lock(txn, &fingerprint).await.map_err(db_error)?;
+    let earlier = OrderFingerprint::find()
+        .filter(order_fingerprint::Column::CustomerId.eq(signature.customer_id))
+        .filter(order_fingerprint::Column::Fingerprint.eq(fingerprint.as_str()))
+        .filter(order_fingerprint::Column::CreatedAt.gte(now - Duration::minutes(config.window_minutes)))
+        .order_by_desc(order_fingerprint::Column::CreatedAt)
+        .one(txn)
+        .await
+        .map_err(db_error)?;
+    let screening = Screening {
+        fingerprint,
+        duplicate_of: earlier.map(|e| e.order_id),
+        overridden: allow_duplicate,
+    };
+    if let Some(original) = screening.duplicate_of {
+        if blocks(&config, &screening) {
+            return Err(ServiceError::ValidationError(format!(
+                "Suspected duplicate of order {} placed in the last {} minutes; resubmit with allow_duplicate to place it anyway",
+                original, config.window_minutes
+            )));
+        }
+        warn!(customer_id = %signature.customer_id, %original, "Suspected duplicate order");
+    }
+    Ok(screening)
+}
+
+/// Stores the new order's fingerprint, flagging it for review when it matched.
+pub async fn record(
+    txn: &DatabaseTransaction,
+    order_id: Uuid,
+    signature: &OrderSignature,
+    screening: Screening,
+    placed_by: Option<String>,
+    now: DateTime<Utc>,
+) -> Result<(), ServiceError> {
+    let (review_status, reviewed_by, reviewed_at) = match (screening.duplicate_of, screening.overridden) {
+        (None, _) => (None, None, None),
+        (Some(_), true) => (Some(DuplicateReview::Overridden), placed_by, Some(now)),
+        (Some(_), false) => (Some(DuplicateReview::Pending), None, None),
+    };
+    order_fingerprint::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        order_id: Set(order_id),
+        customer_id: Set(signature.customer_id),
+        fingerprint: Set(screening.fingerprint),
+        total: Set(signature.total),
+        duplicate_of: Set(screening.duplicate_of),
+        review_status: Set(review_status),
+        reviewed_by: Set(reviewed_by),
+        reviewed_at: Set(reviewed_at),
+        created_at: Set(now),
+    }
+    .insert(txn)
+    .await
+    .map_err(db_error)?;
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct FlaggedFilter {
+    /// Defaults to `pending`.
+    pub status: Option<DuplicateReview>,
+    pub customer_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewDecision {
+    /// `dismissed` or `confirmed`.
+    pub decision: DuplicateReview,
+}
+
+/// The review queue for orders flagged as suspected duplicates.
+pub struct DuplicateOrderService {
+    db_pool: Arc<DbPool>,
+}
+
+impl DuplicateOrderService {
+    pub fn new(db_pool: Arc<DbPool>) -> Self {
+        Self { db_pool }
+    }
+
+    pub async fn list(
+        &self,
+        filter: FlaggedFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<order_fingerprint::Model>, u64), ServiceError> {
+        let mut query = OrderFingerprint::find().filter(
+            order_fingerprint::Column::ReviewStatus.eq(filter.status.unwrap_or(DuplicateReview::Pending)),
+        );
+        if let Some(customer_id) = filter.customer_id {
+            query = query.filter(order_fingerprint::Column::CustomerId.eq(customer_id));
+        }
+        let paginator = query
+            .order_by_desc(order_fingerprint::Column::CreatedAt)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let items = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((items, total))
+    }
+
+    /// Closes a pending flag. Confirming does not cancel the order; that goes through the
+    /// normal cancellation so stock and payments are released.
+    #[instrument(skip(self))]
+    pub async fn review(
+        &self,
+        order_id: Uuid,
+        decision: ReviewDecision,
+        actor: &str,
+    ) -> Result<order_fingerprint::Model, ServiceError> {
+        if !matches!(decision.decision, DuplicateReview::Dismissed | DuplicateReview::Confirmed) {
+            return Err(ServiceError::ValidationError("Decision must be dismissed or confirmed".to_string()));
+        }
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let flag = OrderFingerprint::find()
+            .filter(order_fingerprint::Column::OrderId.eq(order_id))
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Order {} has no duplicate check", order_id)))?;
+        if flag.review_status != Some(DuplicateReview::Pending) {
+            return Err(ServiceError::ValidationError(format!("Order {} is not waiting for review", order_id)));
+        }
+        let mut active: order_fingerprint::ActiveModel = flag.into();
+        active.review_status = Set(Some(decision.decision));
+        active.reviewed_by = Set(Some(actor.to_string()));
+        active.reviewed_at = Set(Some(Utc::now()));
+        let flag = active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+        Ok(flag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn signature(lines: &[(&str, i32)], total: Option<Decimal>) -> OrderSignature {
+        OrderSignature {
+            customer_id: Uuid::from_u128(7),
+            lines: lines.iter().map(|(item, qty)| (item.to_string(), *qty)).collect(),
+            total,
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_line_order_and_splits() {
+        let a = signature(&[("mug", 2), ("tee", 1)], Some(dec!(40.00)));
+        let b = signature(&[("tee", 1), ("mug", 1), ("mug", 1)], Some(dec!(40)));
+        assert_eq!(a.fingerprint(), b.fingerprint());
+    }
+
+    #[test]
+    fn test_fingerprint_differs_on_quantity_total_or_customer() {
+        let base = signature(&[("mug", 2)], Some(dec!(40)));
+        assert_ne!(base.fingerprint(), signature(&[("mug", 3)], Some(dec!(40))).fingerprint());
+        assert_ne!(base.fingerprint(), signature(&[("mug", 2)], Some(dec!(40.01))).fingerprint());
+        let mut other = base.clone();
+        other.customer_id = Uuid::from_u128(8);
+        assert_ne!(base.fingerprint(), other.fingerprint());
+    }
+
+    #[test]
+    fn test_only_unapproved_matches_block() {
+        let block = DuplicateOrderConfig { action: DuplicateAction::Block, ..Default::default() };
+        let flag = DuplicateOrderConfig::default();
+        let matched = Screening { fingerprint: String::new(), duplicate_of: Some(Uuid::new_v4()), overridden: false };
+        assert!(blocks(&block, &matched));
+        assert!(!blocks(&flag, &matched));
+        assert!(!blocks(&block, &Screening { overridden: true, ..matched.clone() }));
+        assert!(!blocks(&block, &Screening { duplicate_of: None, ..matched }));
+    }
+}
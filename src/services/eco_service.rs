@@ -0,0 +1,592 @@
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, sync::Arc, time::Duration};
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::{
+    db::DbPool,
+    errors::ServiceError,
+    events::{Event, EventSender},
+    models::{
+        approval_step::{self, ApprovalSubject},
+        bom_revision::{self, BomRevisionStatus, Entity as BomRevision},
+        eco_change::{self, Entity as EcoChange},
+        eco_work_order_hold::{self, Entity as EcoHold},
+        engineering_change_order::{self, EcoStatus, Entity as Eco},
+        work_order::{self, Entity as WorkOrder, WorkOrderStatus},
+    },
+    utils::pagination::PaginationParams,
+    workflow::{ApprovalEngine, ApprovalHandler, Resolution},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineAction {
+    Add,
+    Remove,
+    /// Changes the quantity of a part already on the BOM.
+    Update,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_line_change"))]
+pub struct BomLineChange {
+    pub action: LineAction,
+    #[validate(length(min = 1))]
+    pub part_number: String,
+    pub quantity: Option<f64>,
+}
+
+fn validate_line_change(change: &BomLineChange) -> Result<(), ValidationError> {
+    match (change.action, change.quantity) {
+        (LineAction::Remove, _) => Ok(()),
+        (_, Some(quantity)) if quantity > 0.0 => Ok(()),
+        _ => Err(ValidationError::new("quantity_required")),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewBomChange {
+    pub bom_id: i32,
+    #[validate(length(min = 1, max = 16))]
+    pub to_revision: String,
+    #[validate(length(min = 1))]
+    #[validate]
+    pub changes: Vec<BomLineChange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_boms"))]
+pub struct NewEco {
+    #[validate(length(min = 1, max = 200))]
+    pub title: String,
+    #[validate(length(max = 4000))]
+    pub description: Option<String>,
+    #[validate(length(max = 1000))]
+    pub reason: Option<String>,
+    pub effective_date: NaiveDate,
+    #[validate(length(min = 1, max = 50))]
+    #[validate]
+    pub boms: Vec<NewBomChange>,
+}
+
+fn validate_boms(eco: &NewEco) -> Result<(), ValidationError> {
+    let mut seen = HashSet::new();
+    if eco.boms.iter().all(|b| seen.insert(b.bom_id)) {
+        Ok(())
+    } else {
+        Err(ValidationError::new("bom_listed_twice"))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EcoFilter {
+    pub status: Option<EcoStatus>,
+    pub bom_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EcoDetail {
+    #[serde(flatten)]
+    pub eco: engineering_change_order::Model,
+    pub changes: Vec<eco_change::Model>,
+    pub holds: Vec<eco_work_order_hold::Model>,
+    pub approvals: Vec<approval_step::Model>,
+}
+
+/// An open work order the ECO would hold, and the revision it is pinned to.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AffectedWorkOrder {
+    pub work_order_id: Uuid,
+    pub number: i32,
+    pub bom_id: i32,
+    pub revision: String,
+    pub status: WorkOrderStatus,
+}
+
+/// Approved ECOs are implemented on their effective date, not before.
+pub fn is_effective(effective_date: NaiveDate, today: NaiveDate) -> bool {
+    effective_date <= today
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("ECO query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+async fn find_locked(txn: &DatabaseTransaction, subject_id: &str) -> Result<engineering_change_order::Model, ServiceError> {
+    let id = Uuid::parse_str(subject_id)
+        .map_err(|_| ServiceError::ValidationError(format!("Invalid ECO id: {}", subject_id)))?;
+    Eco::find_by_id(id)
+        .lock_exclusive()
+        .one(txn)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| ServiceError::NotFound(format!("ECO not found: {}", id)))
+}
+
+async fn released_revision<C: ConnectionTrait>(db: &C, bom_id: i32) -> Result<Option<bom_revision::Model>, ServiceError> {
+    BomRevision::find()
+        .filter(bom_revision::Column::BomId.eq(bom_id))
+        .filter(bom_revision::Column::Status.eq(BomRevisionStatus::Released))
+        .one(db)
+        .await
+        .map_err(db_error)
+}
+
+/// Pending and in-progress work orders built to `revision` of `bom_id`.
+async fn pinned_work_orders<C: ConnectionTrait>(
+    db: &C,
+    bom_id: i32,
+    revision: &str,
+) -> Result<Vec<work_order::Model>, ServiceError> {
+    WorkOrder::find()
+        .filter(work_order::Column::BillOfMaterialsNumber.eq(bom_id))
+        .filter(work_order::Column::BomRevision.eq(revision))
+        .filter(work_order::Column::Status.is_in([WorkOrderStatus::Pending, WorkOrderStatus::InProgress]))
+        .all(db)
+        .await
+        .map_err(db_error)
+}
+
+/// Releases the ECO's revisions, supersedes the ones they replace and holds the open work
+/// orders pinned to those. Returns how many work orders were held.
+async fn implement(txn: &DatabaseTransaction, eco: engineering_change_order::Model) -> Result<usize, ServiceError> {
+    let now = Utc::now();
+    let changes = EcoChange::find()
+        .filter(eco_change::Column::EcoId.eq(eco.id))
+        .all(txn)
+        .await
+        .map_err(db_error)?;
+    let mut held = 0;
+    for change in changes {
+        let current = BomRevision::find()
+            .filter(bom_revision::Column::BomId.eq(change.bom_id))
+            .filter(bom_revision::Column::Status.eq(BomRevisionStatus::Released))
+            .lock_exclusive()
+            .one(txn)
+            .await
+            .map_err(db_error)?;
+        if let Some(current) = current {
+            let revision = current.revision.clone();
+            let mut active: bom_revision::ActiveModel = current.into();
+            active.status = Set(BomRevisionStatus::Superseded);
+            active.superseded_at = Set(Some(now));
+            active.update(txn).await.map_err(db_error)?;
+
+            let work_orders = WorkOrder::find()
+                .filter(work_order::Column::BillOfMaterialsNumber.eq(change.bom_id))
+                .filter(work_order::Column::BomRevision.eq(revision.as_str()))
+                .filter(work_order::Column::Status.is_in([WorkOrderStatus::Pending, WorkOrderStatus::InProgress]))
+                .lock_exclusive()
+                .all(txn)
+                .await
+                .map_err(db_error)?;
+            for work_order in work_orders {
+                eco_work_order_hold::ActiveModel {
+                    id: Set(Uuid::new_v4()),
+                    eco_id: Set(eco.id),
+                    work_order_id: Set(work_order.id),
+                    bom_id: Set(change.bom_id),
+                    revision: Set(revision.clone()),
+                    previous_status: Set(work_order.status.clone()),
+                    held_at: Set(now),
+                    released_at: Set(None),
+                    released_by: Set(None),
+                }
+                .insert(txn)
+                .await
+                .map_err(db_error)?;
+                let mut active: work_order::ActiveModel = work_order.into();
+                active.status = Set(WorkOrderStatus::OnHold);
+                active.updated_at = Set(now);
+                active.update(txn).await.map_err(db_error)?;
+                held += 1;
+            }
+
+            let mut active: eco_change::ActiveModel = change.clone().into();
+            active.from_revision = Set(Some(revision));
+            active.update(txn).await.map_err(db_error)?;
+        }
+        bom_revision::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            bom_id: Set(change.bom_id),
+            revision: Set(change.to_revision.clone()),
+            status: Set(BomRevisionStatus::Released),
+            effective_from: Set(eco.effective_date),
+            eco_id: Set(Some(eco.id)),
+            changes: Set(change.changes.clone()),
+            created_at: Set(now),
+            superseded_at: Set(None),
+        }
+        .insert(txn)
+        .await
+        .map_err(db_error)?;
+    }
+
+    let mut active: engineering_change_order::ActiveModel = eco.into();
+    active.status = Set(EcoStatus::Implemented);
+    active.implemented_at = Set(Some(now));
+    active.updated_at = Set(now);
+    let eco = active.update(txn).await.map_err(db_error)?;
+    info!(eco_id = %eco.id, number = %eco.number, held, "ECO implemented");
+    Ok(held)
+}
+
+fn implemented(eco_id: Uuid, held_work_orders: usize) -> Event {
+    Event::EngineeringChangeImplemented { eco_id, held_work_orders }
+}
+
+/// Engineering change orders. Approval runs through the `engineering_change` workflow
+/// chain; ECOs are routed on a zero amount, so only steps without a `min_amount` apply.
+pub struct EcoService {
+    db_pool: Arc<DbPool>,
+    events: EventSender,
+    approvals: Arc<ApprovalEngine>,
+}
+
+impl EcoService {
+    pub fn new(db_pool: Arc<DbPool>, events: EventSender, approvals: Arc<ApprovalEngine>) -> Self {
+        Self { db_pool, events, approvals }
+    }
+
+    #[instrument(skip(self, input), fields(title = %input.title))]
+    pub async fn create(&self, input: NewEco, actor: &str) -> Result<EcoDetail, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid ECO: {}", e)))?;
+        let db = self.db_pool.as_ref();
+        for bom in &input.boms {
+            let exists = BomRevision::find()
+                .filter(bom_revision::Column::BomId.eq(bom.bom_id))
+                .filter(bom_revision::Column::Revision.eq(bom.to_revision.as_str()))
+                .count(db)
+                .await
+                .map_err(db_error)?;
+            if exists > 0 {
+                return Err(ServiceError::ValidationError(format!(
+                    "BOM {} already has revision {}",
+                    bom.bom_id, bom.to_revision
+                )));
+            }
+        }
+
+        let now = Utc::now();
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let eco = engineering_change_order::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            number: Set(format!("ECO-{}", &Uuid::new_v4().simple().to_string()[..10].to_uppercase())),
+            title: Set(input.title),
+            description: Set(input.description),
+            reason: Set(input.reason),
+            status: Set(EcoStatus::Draft),
+            effective_date: Set(input.effective_date),
+            requested_by: Set(actor.to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+            submitted_at: Set(None),
+            approved_at: Set(None),
+            implemented_at: Set(None),
+        }
+        .insert(&txn)
+        .await
+        .map_err(db_error)?;
+        let mut changes = Vec::with_capacity(input.boms.len());
+        for bom in input.boms {
+            let change = eco_change::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                eco_id: Set(eco.id),
+                bom_id: Set(bom.bom_id),
+                to_revision: Set(bom.to_revision),
+                from_revision: Set(None),
+                changes: Set(serde_json::to_value(&bom.changes).expect("line changes serialize")),
+            }
+            .insert(&txn)
+            .await
+            .map_err(db_error)?;
+            changes.push(change);
+        }
+        txn.commit().await.map_err(db_error)?;
+        Ok(EcoDetail { eco, changes, holds: Vec::new(), approvals: Vec::new() })
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<EcoDetail, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let eco = Eco::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("ECO not found: {}", id)))?;
+        let changes = EcoChange::find()
+            .filter(eco_change::Column::EcoId.eq(id))
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        let holds = EcoHold::find()
+            .filter(eco_work_order_hold::Column::EcoId.eq(id))
+            .order_by_asc(eco_work_order_hold::Column::HeldAt)
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        let approvals = self.approvals.steps(ApprovalSubject::EngineeringChange, &id.to_string()).await?;
+        Ok(EcoDetail { eco, changes, holds, approvals })
+    }
+
+    pub async fn list(
+        &self,
+        filter: EcoFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<engineering_change_order::Model>, u64), ServiceError> {
+        let mut query = Eco::find();
+        if let Some(status) = filter.status {
+            query = query.filter(engineering_change_order::Column::Status.eq(status));
+        }
+        if let Some(bom_id) = filter.bom_id {
+            query = query
+                .inner_join(EcoChange)
+                .filter(eco_change::Column::BomId.eq(bom_id));
+        }
+        let paginator = query
+            .order_by_desc(engineering_change_order::Column::CreatedAt)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let ecos = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((ecos, total))
+    }
+
+    /// Open work orders pinned to the revisions the ECO would supersede today.
+    pub async fn affected_work_orders(&self, id: Uuid) -> Result<Vec<AffectedWorkOrder>, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let changes = EcoChange::find()
+            .filter(eco_change::Column::EcoId.eq(id))
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        let mut affected = Vec::new();
+        for change in changes {
+            let Some(current) = released_revision(db, change.bom_id).await? else {
+                continue;
+            };
+            for work_order in pinned_work_orders(db, change.bom_id, &current.revision).await? {
+                affected.push(AffectedWorkOrder {
+                    work_order_id: work_order.id,
+                    number: work_order.number,
+                    bom_id: change.bom_id,
+                    revision: current.revision.clone(),
+                    status: work_order.status,
+                });
+            }
+        }
+        Ok(affected)
+    }
+
+    /// Sends a draft into its approval chain; with no chain configured it is approved, and
+    /// implemented if already effective, straight away.
+    pub async fn submit(&self, id: Uuid, actor: &str) -> Result<EcoDetail, ServiceError> {
+        self.approvals
+            .request(ApprovalSubject::EngineeringChange, &id.to_string(), actor)
+            .await?;
+        self.get(id).await
+    }
+
+    pub async fn cancel(&self, id: Uuid) -> Result<engineering_change_order::Model, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let eco = find_locked(&txn, &id.to_string()).await?;
+        if !matches!(eco.status, EcoStatus::Draft | EcoStatus::PendingApproval | EcoStatus::Approved) {
+            return Err(ServiceError::ValidationError(format!("A {:?} ECO cannot be cancelled", eco.status)));
+        }
+        ApprovalEngine::withdraw(&txn, ApprovalSubject::EngineeringChange, &id.to_string()).await?;
+        let mut active: engineering_change_order::ActiveModel = eco.into();
+        active.status = Set(EcoStatus::Cancelled);
+        active.updated_at = Set(Utc::now());
+        let eco = active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+        Ok(eco)
+    }
+
+    /// Implements approved ECOs whose effective date has arrived.
+    pub async fn implement_due(&self) -> Result<usize, ServiceError> {
+        let today = Utc::now().date_naive();
+        let due = Eco::find()
+            .filter(engineering_change_order::Column::Status.eq(EcoStatus::Approved))
+            .filter(engineering_change_order::Column::EffectiveDate.lte(today))
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?;
+        let mut implemented_count = 0;
+        for eco in due {
+            let txn = self.db_pool.begin().await.map_err(db_error)?;
+            let eco = find_locked(&txn, &eco.id.to_string()).await?;
+            // Cancelled or implemented since the scan
+            if eco.status != EcoStatus::Approved {
+                continue;
+            }
+            let eco_id = eco.id;
+            let held = implement(&txn, eco).await?;
+            txn.commit().await.map_err(db_error)?;
+            let _ = self.events.send(implemented(eco_id, held));
+            implemented_count += 1;
+        }
+        Ok(implemented_count)
+    }
+
+    /// Takes a held work order off hold, re-pinned to the revision the ECO released.
+    pub async fn release_hold(&self, eco_id: Uuid, work_order_id: Uuid, actor: &str) -> Result<eco_work_order_hold::Model, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let hold = EcoHold::find()
+            .filter(eco_work_order_hold::Column::EcoId.eq(eco_id))
+            .filter(eco_work_order_hold::Column::WorkOrderId.eq(work_order_id))
+            .filter(eco_work_order_hold::Column::ReleasedAt.is_null())
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("No open hold on work order {} for ECO {}", work_order_id, eco_id)))?;
+        let change = EcoChange::find()
+            .filter(eco_change::Column::EcoId.eq(eco_id))
+            .filter(eco_change::Column::BomId.eq(hold.bom_id))
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("ECO {} does not change BOM {}", eco_id, hold.bom_id)))?;
+        let work_order = WorkOrder::find_by_id(work_order_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Work order not found: {}", work_order_id)))?;
+
+        let now = Utc::now();
+        // A work order cancelled while held stays cancelled
+        if work_order.status == WorkOrderStatus::OnHold {
+            let mut active: work_order::ActiveModel = work_order.into();
+            active.status = Set(hold.previous_status.clone());
+            active.bom_revision = Set(Some(change.to_revision));
+            active.updated_at = Set(now);
+            active.update(&txn).await.map_err(db_error)?;
+        }
+        let mut active: eco_work_order_hold::ActiveModel = hold.into();
+        active.released_at = Set(Some(now));
+        active.released_by = Set(Some(actor.to_string()));
+        let hold = active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+        Ok(hold)
+    }
+}
+
+/// Checks for approved ECOs reaching their effective date.
+pub fn spawn_effectivity_worker(ecos: Arc<EcoService>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval.max(Duration::from_secs(1)));
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = ecos.implement_due().await {
+                error!("ECO implementation failed: {}", e);
+            }
+        }
+    });
+}
+
+/// Workflow side of ECOs: the requester submits a draft, and final approval implements it
+/// right away when its effective date has arrived.
+pub struct EcoApprovals;
+
+#[async_trait]
+impl ApprovalHandler for EcoApprovals {
+    fn subject(&self) -> ApprovalSubject {
+        ApprovalSubject::EngineeringChange
+    }
+
+    async fn submit(&self, txn: &DatabaseTransaction, subject_id: &str, actor: &str) -> Result<Decimal, ServiceError> {
+        let eco = find_locked(txn, subject_id).await?;
+        if eco.requested_by != actor {
+            return Err(ServiceError::ValidationError("Only the requester can submit an ECO".to_string()));
+        }
+        if eco.status != EcoStatus::Draft {
+            return Err(ServiceError::ValidationError(format!("ECO is {:?}, not a draft", eco.status)));
+        }
+        let now = Utc::now();
+        let mut active: engineering_change_order::ActiveModel = eco.into();
+        active.status = Set(EcoStatus::PendingApproval);
+        active.submitted_at = Set(Some(now));
+        active.updated_at = Set(now);
+        active.update(txn).await.map_err(db_error)?;
+        Ok(Decimal::ZERO)
+    }
+
+    async fn resolve(
+        &self,
+        txn: &DatabaseTransaction,
+        subject_id: &str,
+        resolution: Resolution,
+        _actor: &str,
+    ) -> Result<Option<Event>, ServiceError> {
+        let eco = find_locked(txn, subject_id).await?;
+        let now = Utc::now();
+        let mut active: engineering_change_order::ActiveModel = eco.clone().into();
+        active.updated_at = Set(now);
+        if resolution == Resolution::Rejected {
+            active.status = Set(EcoStatus::Rejected);
+            active.update(txn).await.map_err(db_error)?;
+            return Ok(None);
+        }
+        active.status = Set(EcoStatus::Approved);
+        active.approved_at = Set(Some(now));
+        let eco = active.update(txn).await.map_err(db_error)?;
+        if !is_effective(eco.effective_date, now.date_naive()) {
+            return Ok(None);
+        }
+        let eco_id = eco.id;
+        let held = implement(txn, eco).await?;
+        Ok(Some(implemented(eco_id, held)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(action: LineAction, quantity: Option<f64>) -> BomLineChange {
+        BomLineChange { action, part_number: "P-100".to_string(), quantity }
+    }
+
+    #[test]
+    fn test_line_change_needs_quantity_unless_removed() {
+        assert!(change(LineAction::Remove, None).validate().is_ok());
+        assert!(change(LineAction::Add, Some(2.0)).validate().is_ok());
+        assert!(change(LineAction::Add, None).validate().is_err());
+        assert!(change(LineAction::Update, Some(0.0)).validate().is_err());
+    }
+
+    #[test]
+    fn test_bom_listed_once_per_eco() {
+        let bom = |bom_id| NewBomChange {
+            bom_id,
+            to_revision: "B".to_string(),
+            changes: vec![change(LineAction::Remove, None)],
+        };
+        let eco = |boms| NewEco {
+            title: "Replace EOL capacitor".to_string(),
+            description: None,
+            reason: None,
+            effective_date: NaiveDate::from_ymd_opt(2026, 11, 1).unwrap(),
+            boms,
+        };
+        assert!(eco(vec![bom(1), bom(2)]).validate().is_ok());
+        assert!(eco(vec![bom(1), bom(1)]).validate().is_err());
+    }
+
+    #[test]
+    fn test_effective_on_its_date() {
+        let date = NaiveDate::from_ymd_opt(2026, 11, 1).unwrap();
+        assert!(!is_effective(date, date.pred_opt().unwrap()));
+        assert!(is_effective(date, date));
+    }
+}
@@ -0,0 +1,1100 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::{
+    db::DbPool,
+    errors::ServiceError,
+    events::{Event, EventSender},
+    models::{
+        equipment::{self, Entity as Equipment, EquipmentStatus},
+        equipment_downtime::{self, Entity as Downtime},
+        maintenance_schedule::{self, Entity as Schedule},
+        meter_reading::{self, Entity as MeterReading},
+        work_order::{self, Entity as WorkOrder, WorkOrderPriority, WorkOrderStatus},
+    },
+    utils::pagination::PaginationParams,
+};
+
+/// `work_orders.work_order_type` of work orders generated from a maintenance schedule.
+pub const PREVENTIVE_MAINTENANCE: &str = "preventive_maintenance";
+/// `work_orders.work_order_type` of work orders raised for a breakdown or defect.
+pub const CORRECTIVE_MAINTENANCE: &str = "corrective_maintenance";
+
+const MAINTENANCE_TYPES: [&str; 2] = [PREVENTIVE_MAINTENANCE, CORRECTIVE_MAINTENANCE];
+const SCHEDULER: &str = "system:maintenance";
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewEquipment {
+    #[validate(length(min = 1, max = 64))]
+    pub asset_tag: String,
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[validate(length(max = 100))]
+    pub category: Option<String>,
+    #[validate(length(max = 255))]
+    pub location: Option<String>,
+    pub work_center: Option<String>,
+    pub machine_id: Option<i32>,
+    #[validate(length(min = 1, max = 32))]
+    pub meter_unit: Option<String>,
+    #[validate(range(min = 0.0))]
+    pub meter_reading: Option<f64>,
+    pub installed_on: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EquipmentFilter {
+    pub status: Option<EquipmentStatus>,
+    pub work_center: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_recurrence"))]
+pub struct NewSchedule {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[validate(length(max = 4000))]
+    pub instructions: Option<String>,
+    #[validate(range(min = 1))]
+    pub interval_days: Option<i32>,
+    #[validate(range(min = 0.01))]
+    pub meter_interval: Option<f64>,
+    #[serde(default)]
+    #[validate(range(min = 0, max = 90))]
+    pub lead_days: i32,
+    #[serde(default)]
+    #[validate(range(min = 0.0))]
+    pub estimated_hours: f64,
+    /// First due date; defaults to one interval from today.
+    pub first_due: Option<NaiveDate>,
+}
+
+fn validate_recurrence(schedule: &NewSchedule) -> Result<(), ValidationError> {
+    if schedule.interval_days.is_none() && schedule.meter_interval.is_none() {
+        return Err(ValidationError::new("interval_days_or_meter_interval_required"));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewMeterReading {
+    #[validate(range(min = 0.0))]
+    pub reading: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewDowntime {
+    #[validate(length(min = 1, max = 1000))]
+    pub reason: String,
+    #[serde(default)]
+    pub planned: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewCorrectiveWorkOrder {
+    #[validate(length(min = 1, max = 4000))]
+    pub description: String,
+    pub priority: WorkOrderPriority,
+    pub due_date: NaiveDate,
+    #[serde(default)]
+    #[validate(range(min = 0.0))]
+    pub estimated_hours: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StartMaintenance {
+    /// Takes the equipment out of service for the job as planned downtime.
+    #[serde(default)]
+    pub take_down: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct CompleteMaintenance {
+    /// Meter at completion; the next meter-based due point counts from here.
+    #[validate(range(min = 0.0))]
+    pub meter_reading: Option<f64>,
+    #[validate(length(max = 4000))]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceFilter {
+    pub equipment_id: Option<Uuid>,
+    pub status: Option<WorkOrderStatus>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EquipmentDetail {
+    pub equipment: equipment::Model,
+    pub schedules: Vec<maintenance_schedule::Model>,
+    pub open_downtime: Option<equipment_downtime::Model>,
+}
+
+/// Reliability and maintenance figures of one piece of equipment over a period.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EquipmentMetrics {
+    pub equipment_id: Uuid,
+    pub asset_tag: String,
+    pub downtime_hours: f64,
+    pub unplanned_downtime_hours: f64,
+    /// Share of the period the equipment was in service.
+    pub availability: f64,
+    /// Unplanned downtime events that started in the period.
+    pub failures: usize,
+    /// Mean time between failures: hours in service over failures.
+    pub mtbf_hours: Option<f64>,
+    /// Mean time to repair: unplanned downtime hours over failures.
+    pub mttr_hours: Option<f64>,
+    pub preventive_completed: usize,
+    /// Preventive work orders completed by their due date over all completed.
+    pub pm_compliance: Option<f64>,
+    pub corrective_completed: usize,
+    pub open_work_orders: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MetricsQuery {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub equipment_id: Option<Uuid>,
+}
+
+/// What a new maintenance work order is for.
+struct MaintenanceJob {
+    work_order_type: &'static str,
+    schedule_id: Option<Uuid>,
+    memo: String,
+    priority: WorkOrderPriority,
+    due_date: NaiveDate,
+    estimated_hours: f64,
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn is_open(status: &WorkOrderStatus) -> bool {
+    !matches!(status, WorkOrderStatus::Completed | WorkOrderStatus::Cancelled)
+}
+
+/// Whether a schedule needs a work order on `today`: its due date is within its lead time,
+/// or the equipment meter has reached the due reading.
+pub fn is_due(schedule: &maintenance_schedule::Model, meter: Option<f64>, today: NaiveDate) -> bool {
+    let by_date = schedule
+        .next_due_date
+        .is_some_and(|due| due - Duration::days(i64::from(schedule.lead_days)) <= today);
+    let by_meter = matches!((schedule.next_due_meter, meter), (Some(due), Some(reading)) if reading >= due);
+    schedule.active && (by_date || by_meter)
+}
+
+/// Next due date and meter reading once the task was done on `completed_on` at `meter`.
+pub fn next_due(
+    schedule: &maintenance_schedule::Model,
+    completed_on: NaiveDate,
+    meter: Option<f64>,
+) -> (Option<NaiveDate>, Option<f64>) {
+    let date = schedule.interval_days.map(|days| completed_on + Duration::days(i64::from(days)));
+    let reading = match (schedule.meter_interval, meter) {
+        (Some(interval), Some(reading)) => Some(reading + interval),
+        (Some(_), None) => schedule.next_due_meter,
+        (None, _) => None,
+    };
+    (date, reading)
+}
+
+/// Downtime, failure and preventive-maintenance figures for `[start, end)`. Open downtime
+/// counts up to `now`.
+pub fn equipment_metrics(
+    equipment: &equipment::Model,
+    downtime: &[equipment_downtime::Model],
+    work_orders: &[work_order::Model],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> EquipmentMetrics {
+    let hours = |from: DateTime<Utc>, to: DateTime<Utc>| (to - from).num_seconds().max(0) as f64 / 3600.0;
+    let (mut down, mut unplanned, mut failures) = (0.0, 0.0, 0);
+    for event in downtime.iter().filter(|d| d.equipment_id == equipment.id) {
+        let overlap = hours(event.started_at.max(start), event.ended_at.unwrap_or(now).min(end));
+        down += overlap;
+        if !event.planned {
+            unplanned += overlap;
+            if event.started_at >= start && event.started_at < end {
+                failures += 1;
+            }
+        }
+    }
+    let period = hours(start, end.min(now.max(start)));
+    let in_service = (period - down).max(0.0);
+
+    let mine = || work_orders.iter().filter(|w| w.equipment_id == Some(equipment.id));
+    let completed_in_period = |w: &&work_order::Model| {
+        w.status == WorkOrderStatus::Completed && w.updated_at >= start && w.updated_at < end
+    };
+    let preventive: Vec<_> = mine()
+        .filter(|w| w.work_order_type == PREVENTIVE_MAINTENANCE)
+        .filter(completed_in_period)
+        .collect();
+    let on_time = preventive
+        .iter()
+        .filter(|w| w.updated_at.date_naive() <= w.expected_completion_date)
+        .count();
+
+    EquipmentMetrics {
+        equipment_id: equipment.id,
+        asset_tag: equipment.asset_tag.clone(),
+        downtime_hours: round2(down),
+        unplanned_downtime_hours: round2(unplanned),
+        availability: if period > 0.0 { round2(in_service / period) } else { 1.0 },
+        failures,
+        mtbf_hours: (failures > 0).then(|| round2(in_service / failures as f64)),
+        mttr_hours: (failures > 0).then(|| round2(unplanned / failures as f64)),
+        preventive_completed: preventive.len(),
+        pm_compliance: (!preventive.is_empty()).then(|| round2(on_time as f64 / preventive.len() as f64)),
+        corrective_completed: mine()
+            .filter(|w| w.work_order_type == CORRECTIVE_MAINTENANCE)
+            .filter(completed_in_period)
+            .count(),
+        open_work_orders: mine().filter(|w| is_open(&w.status)).count(),
+    }
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Equipment query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Equipment registry and maintenance work orders. Maintenance jobs are regular work
+/// orders typed `preventive_maintenance` or `corrective_maintenance`, so routing steps and
+/// time booking work on them as on any other work order.
+pub struct EquipmentService {
+    db_pool: Arc<DbPool>,
+    events: EventSender,
+}
+
+impl EquipmentService {
+    pub fn new(db_pool: Arc<DbPool>, events: EventSender) -> Self {
+        Self { db_pool, events }
+    }
+
+    async fn find<C: ConnectionTrait>(db: &C, id: Uuid) -> Result<equipment::Model, ServiceError> {
+        Equipment::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Equipment not found: {}", id)))
+    }
+
+    async fn find_locked(txn: &DatabaseTransaction, id: Uuid) -> Result<equipment::Model, ServiceError> {
+        Equipment::find_by_id(id)
+            .lock_exclusive()
+            .one(txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Equipment not found: {}", id)))
+    }
+
+    async fn open_downtime<C: ConnectionTrait>(
+        db: &C,
+        equipment_id: Uuid,
+    ) -> Result<Option<equipment_downtime::Model>, ServiceError> {
+        Downtime::find()
+            .filter(equipment_downtime::Column::EquipmentId.eq(equipment_id))
+            .filter(equipment_downtime::Column::EndedAt.is_null())
+            .one(db)
+            .await
+            .map_err(db_error)
+    }
+
+    #[instrument(skip(self, input), fields(asset_tag = %input.asset_tag))]
+    pub async fn create_equipment(&self, input: NewEquipment) -> Result<equipment::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid equipment: {}", e)))?;
+        if input.meter_reading.is_some() && input.meter_unit.is_none() {
+            return Err(ServiceError::ValidationError("A meter reading needs a meter unit".to_string()));
+        }
+        let db = self.db_pool.as_ref();
+        let taken = Equipment::find()
+            .filter(equipment::Column::AssetTag.eq(input.asset_tag.as_str()))
+            .count(db)
+            .await
+            .map_err(db_error)?;
+        if taken > 0 {
+            return Err(ServiceError::ValidationError(format!("Asset tag {} is already used", input.asset_tag)));
+        }
+        let now = Utc::now();
+        equipment::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            asset_tag: Set(input.asset_tag),
+            name: Set(input.name),
+            category: Set(input.category),
+            location: Set(input.location),
+            work_center: Set(input.work_center),
+            machine_id: Set(input.machine_id),
+            status: Set(EquipmentStatus::Operational),
+            meter_unit: Set(input.meter_unit),
+            meter_reading: Set(input.meter_reading),
+            installed_on: Set(input.installed_on),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(db)
+        .await
+        .map_err(db_error)
+    }
+
+    pub async fn get_equipment(&self, id: Uuid) -> Result<EquipmentDetail, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let equipment = Self::find(db, id).await?;
+        let schedules = Schedule::find()
+            .filter(maintenance_schedule::Column::EquipmentId.eq(id))
+            .order_by_asc(maintenance_schedule::Column::Name)
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        let open_downtime = Self::open_downtime(db, id).await?;
+        Ok(EquipmentDetail { equipment, schedules, open_downtime })
+    }
+
+    pub async fn list_equipment(
+        &self,
+        filter: EquipmentFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<equipment::Model>, u64), ServiceError> {
+        let mut query = Equipment::find();
+        if let Some(status) = filter.status {
+            query = query.filter(equipment::Column::Status.eq(status));
+        }
+        if let Some(work_center) = filter.work_center {
+            query = query.filter(equipment::Column::WorkCenter.eq(work_center));
+        }
+        let paginator = query
+            .order_by_asc(equipment::Column::AssetTag)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let items = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((items, total))
+    }
+
+    /// Marks equipment retired and deactivates its schedules. Open work orders stay open.
+    pub async fn retire(&self, id: Uuid) -> Result<equipment::Model, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let equipment = Self::find_locked(&txn, id).await?;
+        if Self::open_downtime(&txn, id).await?.is_some() {
+            return Err(ServiceError::ValidationError("End the open downtime before retiring".to_string()));
+        }
+        Schedule::update_many()
+            .col_expr(maintenance_schedule::Column::Active, Expr::value(false))
+            .filter(maintenance_schedule::Column::EquipmentId.eq(id))
+            .exec(&txn)
+            .await
+            .map_err(db_error)?;
+        let mut active: equipment::ActiveModel = equipment.into();
+        active.status = Set(EquipmentStatus::Retired);
+        active.updated_at = Set(Utc::now());
+        let equipment = active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+        Ok(equipment)
+    }
+
+    pub async fn add_schedule(
+        &self,
+        equipment_id: Uuid,
+        input: NewSchedule,
+    ) -> Result<maintenance_schedule::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid schedule: {}", e)))?;
+        let db = self.db_pool.as_ref();
+        let equipment = Self::find(db, equipment_id).await?;
+        if equipment.status == EquipmentStatus::Retired {
+            return Err(ServiceError::ValidationError(format!("{} is retired", equipment.asset_tag)));
+        }
+        if input.meter_interval.is_some() && equipment.meter_unit.is_none() {
+            return Err(ServiceError::ValidationError(format!("{} has no meter", equipment.asset_tag)));
+        }
+        let today = Utc::now().date_naive();
+        let next_due_date = input
+            .first_due
+            .or_else(|| input.interval_days.map(|days| today + Duration::days(i64::from(days))));
+        let next_due_meter = input
+            .meter_interval
+            .map(|interval| equipment.meter_reading.unwrap_or(0.0) + interval);
+        let now = Utc::now();
+        maintenance_schedule::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            equipment_id: Set(equipment_id),
+            name: Set(input.name),
+            instructions: Set(input.instructions),
+            interval_days: Set(input.interval_days),
+            meter_interval: Set(input.meter_interval),
+            lead_days: Set(input.lead_days),
+            estimated_hours: Set(input.estimated_hours),
+            next_due_date: Set(next_due_date),
+            next_due_meter: Set(next_due_meter),
+            active: Set(true),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(db)
+        .await
+        .map_err(db_error)
+    }
+
+    pub async fn deactivate_schedule(
+        &self,
+        equipment_id: Uuid,
+        schedule_id: Uuid,
+    ) -> Result<maintenance_schedule::Model, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let schedule = Schedule::find_by_id(schedule_id)
+            .filter(maintenance_schedule::Column::EquipmentId.eq(equipment_id))
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Schedule not found: {}", schedule_id)))?;
+        let mut active: maintenance_schedule::ActiveModel = schedule.into();
+        active.active = Set(false);
+        active.updated_at = Set(Utc::now());
+        active.update(db).await.map_err(db_error)
+    }
+
+    /// Records a meter reading and raises preventive work orders it makes due.
+    #[instrument(skip(self, input), fields(equipment_id = %equipment_id))]
+    pub async fn record_meter(
+        &self,
+        equipment_id: Uuid,
+        input: NewMeterReading,
+        actor: &str,
+    ) -> Result<meter_reading::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid meter reading: {}", e)))?;
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let equipment = Self::find_locked(&txn, equipment_id).await?;
+        if equipment.meter_unit.is_none() {
+            return Err(ServiceError::ValidationError(format!("{} has no meter", equipment.asset_tag)));
+        }
+        if let Some(last) = equipment.meter_reading.filter(|last| input.reading < *last) {
+            return Err(ServiceError::ValidationError(format!(
+                "Reading {} is below the last reading {}",
+                input.reading, last
+            )));
+        }
+        let now = Utc::now();
+        let reading = meter_reading::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            equipment_id: Set(equipment_id),
+            reading: Set(input.reading),
+            recorded_by: Set(actor.to_string()),
+            recorded_at: Set(now),
+        }
+        .insert(&txn)
+        .await
+        .map_err(db_error)?;
+        let mut active: equipment::ActiveModel = equipment.into();
+        active.meter_reading = Set(Some(input.reading));
+        active.updated_at = Set(now);
+        let equipment = active.update(&txn).await.map_err(db_error)?;
+        let created = self.generate_for(&txn, &equipment, now.date_naive()).await?;
+        txn.commit().await.map_err(db_error)?;
+        self.announce(&created);
+        Ok(reading)
+    }
+
+    pub async fn meter_history(&self, equipment_id: Uuid, limit: u64) -> Result<Vec<meter_reading::Model>, ServiceError> {
+        MeterReading::find()
+            .filter(meter_reading::Column::EquipmentId.eq(equipment_id))
+            .order_by_desc(meter_reading::Column::RecordedAt)
+            .limit(limit)
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)
+    }
+
+    async fn begin_downtime(
+        txn: &DatabaseTransaction,
+        equipment: equipment::Model,
+        input: NewDowntime,
+        work_order_id: Option<Uuid>,
+        actor: &str,
+    ) -> Result<equipment_downtime::Model, ServiceError> {
+        if equipment.status == EquipmentStatus::Retired {
+            return Err(ServiceError::ValidationError(format!("{} is retired", equipment.asset_tag)));
+        }
+        if Self::open_downtime(txn, equipment.id).await?.is_some() {
+            return Err(ServiceError::ValidationError(format!("{} is already down", equipment.asset_tag)));
+        }
+        let now = Utc::now();
+        let downtime = equipment_downtime::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            equipment_id: Set(equipment.id),
+            work_order_id: Set(work_order_id),
+            planned: Set(input.planned),
+            reason: Set(input.reason),
+            started_at: Set(now),
+            ended_at: Set(None),
+            reported_by: Set(actor.to_string()),
+        }
+        .insert(txn)
+        .await
+        .map_err(db_error)?;
+        let mut active: equipment::ActiveModel = equipment.into();
+        active.status = Set(EquipmentStatus::Down);
+        active.updated_at = Set(now);
+        active.update(txn).await.map_err(db_error)?;
+        Ok(downtime)
+    }
+
+    async fn finish_downtime(
+        txn: &DatabaseTransaction,
+        equipment: equipment::Model,
+        downtime: equipment_downtime::Model,
+    ) -> Result<equipment_downtime::Model, ServiceError> {
+        let now = Utc::now();
+        let mut active: equipment_downtime::ActiveModel = downtime.into();
+        active.ended_at = Set(Some(now));
+        let downtime = active.update(txn).await.map_err(db_error)?;
+        let mut active: equipment::ActiveModel = equipment.into();
+        active.status = Set(EquipmentStatus::Operational);
+        active.updated_at = Set(now);
+        active.update(txn).await.map_err(db_error)?;
+        Ok(downtime)
+    }
+
+    /// Reports equipment out of service, e.g. after a breakdown.
+    pub async fn start_downtime(
+        &self,
+        equipment_id: Uuid,
+        input: NewDowntime,
+        actor: &str,
+    ) -> Result<equipment_downtime::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid downtime: {}", e)))?;
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let equipment = Self::find_locked(&txn, equipment_id).await?;
+        let downtime = Self::begin_downtime(&txn, equipment, input, None, actor).await?;
+        txn.commit().await.map_err(db_error)?;
+        info!("Equipment {} down: {}", equipment_id, downtime.reason);
+        Ok(downtime)
+    }
+
+    /// Returns equipment to service.
+    pub async fn end_downtime(&self, equipment_id: Uuid) -> Result<equipment_downtime::Model, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let equipment = Self::find_locked(&txn, equipment_id).await?;
+        let downtime = Self::open_downtime(&txn, equipment_id)
+            .await?
+            .ok_or_else(|| ServiceError::ValidationError(format!("{} is not down", equipment.asset_tag)))?;
+        let downtime = Self::finish_downtime(&txn, equipment, downtime).await?;
+        txn.commit().await.map_err(db_error)?;
+        Ok(downtime)
+    }
+
+    async fn insert_work_order<C: ConnectionTrait>(
+        db: &C,
+        equipment: &equipment::Model,
+        job: MaintenanceJob,
+        actor: &str,
+    ) -> Result<work_order::Model, ServiceError> {
+        let last = WorkOrder::find()
+            .order_by_desc(work_order::Column::Number)
+            .one(db)
+            .await
+            .map_err(db_error)?;
+        let now = Utc::now();
+        work_order::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            number: Set(last.map_or(1, |w| w.number + 1)),
+            site: Set(equipment.location.clone().unwrap_or_default()),
+            work_order_type: Set(job.work_order_type.to_string()),
+            location: Set(equipment.location.clone().unwrap_or_default()),
+            part: Set(equipment.asset_tag.clone()),
+            order_number: Set(String::new()),
+            manufacture_order: Set(String::new()),
+            status: Set(WorkOrderStatus::Pending),
+            created_by: Set(actor.to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+            issue_date: Set(now.date_naive()),
+            expected_completion_date: Set(job.due_date),
+            priority: Set(job.priority),
+            memo: Set(Some(job.memo)),
+            bill_of_materials_number: Set(0),
+            actual_labor_hours: Set(0.0),
+            standard_labor_hours: Set(job.estimated_hours),
+            capacity_utilization_id: Set(Uuid::nil()),
+            bill_of_materials_id: Set(Uuid::nil()),
+            cogs_data_id: Set(Uuid::nil()),
+            bom_revision: Set(None),
+            equipment_id: Set(Some(equipment.id)),
+            maintenance_schedule_id: Set(job.schedule_id),
+        }
+        .insert(db)
+        .await
+        .map_err(db_error)
+    }
+
+    /// Raises a work order for each due schedule of `equipment` that has none open.
+    async fn generate_for(
+        &self,
+        txn: &DatabaseTransaction,
+        equipment: &equipment::Model,
+        today: NaiveDate,
+    ) -> Result<Vec<work_order::Model>, ServiceError> {
+        if equipment.status == EquipmentStatus::Retired {
+            return Ok(Vec::new());
+        }
+        let schedules = Schedule::find()
+            .filter(maintenance_schedule::Column::EquipmentId.eq(equipment.id))
+            .filter(maintenance_schedule::Column::Active.eq(true))
+            .all(txn)
+            .await
+            .map_err(db_error)?;
+        let mut created = Vec::new();
+        for schedule in schedules.iter().filter(|s| is_due(s, equipment.meter_reading, today)) {
+            let open = WorkOrder::find()
+                .filter(work_order::Column::MaintenanceScheduleId.eq(schedule.id))
+                .filter(work_order::Column::Status.is_not_in([WorkOrderStatus::Completed, WorkOrderStatus::Cancelled]))
+                .count(txn)
+                .await
+                .map_err(db_error)?;
+            if open > 0 {
+                continue;
+            }
+            let memo = match &schedule.instructions {
+                Some(instructions) => format!("{}: {}", schedule.name, instructions),
+                None => schedule.name.clone(),
+            };
+            let job = MaintenanceJob {
+                work_order_type: PREVENTIVE_MAINTENANCE,
+                schedule_id: Some(schedule.id),
+                memo,
+                priority: WorkOrderPriority::Medium,
+                due_date: schedule.next_due_date.unwrap_or(today).max(today),
+                estimated_hours: schedule.estimated_hours,
+            };
+            created.push(Self::insert_work_order(txn, equipment, job, SCHEDULER).await?);
+        }
+        Ok(created)
+    }
+
+    fn announce(&self, created: &[work_order::Model]) {
+        for work_order in created {
+            let _ = self.events.send(Event::MaintenanceWorkOrderCreated {
+                work_order_id: work_order.id,
+                equipment_id: work_order.equipment_id.unwrap_or_default(),
+                preventive: work_order.work_order_type == PREVENTIVE_MAINTENANCE,
+            });
+        }
+    }
+
+    /// Raises preventive work orders for every schedule that is due by date or meter.
+    #[instrument(skip(self))]
+    pub async fn generate_due(&self, today: NaiveDate) -> Result<Vec<work_order::Model>, ServiceError> {
+        let equipment_ids: Vec<Uuid> = Schedule::find()
+            .select_only()
+            .column(maintenance_schedule::Column::EquipmentId)
+            .distinct()
+            .filter(maintenance_schedule::Column::Active.eq(true))
+            .into_tuple()
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?;
+        let mut created = Vec::new();
+        for equipment_id in equipment_ids {
+            let txn = self.db_pool.begin().await.map_err(db_error)?;
+            let equipment = Self::find_locked(&txn, equipment_id).await?;
+            let raised = self.generate_for(&txn, &equipment, today).await?;
+            txn.commit().await.map_err(db_error)?;
+            self.announce(&raised);
+            created.extend(raised);
+        }
+        if !created.is_empty() {
+            info!("Raised {} preventive maintenance work orders", created.len());
+        }
+        Ok(created)
+    }
+
+    pub async fn create_corrective(
+        &self,
+        equipment_id: Uuid,
+        input: NewCorrectiveWorkOrder,
+        actor: &str,
+    ) -> Result<work_order::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid work order: {}", e)))?;
+        let db = self.db_pool.as_ref();
+        let equipment = Self::find(db, equipment_id).await?;
+        if equipment.status == EquipmentStatus::Retired {
+            return Err(ServiceError::ValidationError(format!("{} is retired", equipment.asset_tag)));
+        }
+        let job = MaintenanceJob {
+            work_order_type: CORRECTIVE_MAINTENANCE,
+            schedule_id: None,
+            memo: input.description,
+            priority: input.priority,
+            due_date: input.due_date,
+            estimated_hours: input.estimated_hours,
+        };
+        let work_order = Self::insert_work_order(db, &equipment, job, actor).await?;
+        self.announce(std::slice::from_ref(&work_order));
+        Ok(work_order)
+    }
+
+    async fn find_work_order(txn: &DatabaseTransaction, id: Uuid) -> Result<work_order::Model, ServiceError> {
+        WorkOrder::find_by_id(id)
+            .filter(work_order::Column::WorkOrderType.is_in(MAINTENANCE_TYPES))
+            .lock_exclusive()
+            .one(txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Maintenance work order not found: {}", id)))
+    }
+
+    pub async fn list_work_orders(
+        &self,
+        filter: MaintenanceFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<work_order::Model>, u64), ServiceError> {
+        let mut query = WorkOrder::find().filter(work_order::Column::WorkOrderType.is_in(MAINTENANCE_TYPES));
+        if let Some(equipment_id) = filter.equipment_id {
+            query = query.filter(work_order::Column::EquipmentId.eq(equipment_id));
+        }
+        if let Some(status) = filter.status {
+            query = query.filter(work_order::Column::Status.eq(status));
+        }
+        let paginator = query
+            .order_by_asc(work_order::Column::ExpectedCompletionDate)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let items = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((items, total))
+    }
+
+    #[instrument(skip(self, input), fields(work_order_id = %id))]
+    pub async fn start_work_order(
+        &self,
+        id: Uuid,
+        input: StartMaintenance,
+        actor: &str,
+    ) -> Result<work_order::Model, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let work_order = Self::find_work_order(&txn, id).await?;
+        if work_order.status != WorkOrderStatus::Pending {
+            return Err(ServiceError::ValidationError(format!(
+                "Work order {} is {:?}, not pending",
+                work_order.number, work_order.status
+            )));
+        }
+        if input.take_down {
+            let equipment_id = work_order.equipment_id.unwrap_or_default();
+            let equipment = Self::find_locked(&txn, equipment_id).await?;
+            let reason = format!("Maintenance work order {}", work_order.number);
+            Self::begin_downtime(&txn, equipment, NewDowntime { reason, planned: true }, Some(id), actor).await?;
+        }
+        let mut active: work_order::ActiveModel = work_order.into();
+        active.status = Set(WorkOrderStatus::InProgress);
+        active.updated_at = Set(Utc::now());
+        let work_order = active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+        Ok(work_order)
+    }
+
+    /// Completes a maintenance job, returns the equipment to service if the job took it
+    /// down, and moves its schedule to the next due point.
+    #[instrument(skip(self, input), fields(work_order_id = %id))]
+    pub async fn complete_work_order(
+        &self,
+        id: Uuid,
+        input: CompleteMaintenance,
+        actor: &str,
+    ) -> Result<work_order::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid completion: {}", e)))?;
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let work_order = Self::find_work_order(&txn, id).await?;
+        if !is_open(&work_order.status) || work_order.status == WorkOrderStatus::OnHold {
+            return Err(ServiceError::ValidationError(format!(
+                "Work order {} is {:?}",
+                work_order.number, work_order.status
+            )));
+        }
+        let equipment_id = work_order.equipment_id.unwrap_or_default();
+        let mut equipment = Self::find_locked(&txn, equipment_id).await?;
+        let now = Utc::now();
+
+        let mut downtime_hours = 0.0;
+        if let Some(downtime) = Self::open_downtime(&txn, equipment_id).await? {
+            if downtime.work_order_id == Some(id) {
+                downtime_hours = round2((now - downtime.started_at).num_seconds() as f64 / 3600.0);
+                Self::finish_downtime(&txn, equipment.clone(), downtime).await?;
+                equipment.status = EquipmentStatus::Operational;
+            }
+        }
+        if let Some(reading) = input.meter_reading {
+            if equipment.meter_reading.is_some_and(|last| reading < last) {
+                return Err(ServiceError::ValidationError("Meter reading is below the last reading".to_string()));
+            }
+            meter_reading::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                equipment_id: Set(equipment_id),
+                reading: Set(reading),
+                recorded_by: Set(actor.to_string()),
+                recorded_at: Set(now),
+            }
+            .insert(&txn)
+            .await
+            .map_err(db_error)?;
+            let mut active: equipment::ActiveModel = equipment.clone().into();
+            active.meter_reading = Set(Some(reading));
+            active.updated_at = Set(now);
+            equipment = active.update(&txn).await.map_err(db_error)?;
+        }
+        if let Some(schedule_id) = work_order.maintenance_schedule_id {
+            if let Some(schedule) = Schedule::find_by_id(schedule_id).one(&txn).await.map_err(db_error)? {
+                let (date, meter) = next_due(&schedule, now.date_naive(), equipment.meter_reading);
+                let mut active: maintenance_schedule::ActiveModel = schedule.into();
+                active.next_due_date = Set(date);
+                active.next_due_meter = Set(meter);
+                active.updated_at = Set(now);
+                active.update(&txn).await.map_err(db_error)?;
+            }
+        }
+
+        let memo = match (&work_order.memo, input.notes) {
+            (Some(memo), Some(notes)) => Some(format!("{}\n{}", memo, notes)),
+            (memo, notes) => notes.or_else(|| memo.clone()),
+        };
+        let mut active: work_order::ActiveModel = work_order.into();
+        active.status = Set(WorkOrderStatus::Completed);
+        active.memo = Set(memo);
+        active.updated_at = Set(now);
+        let work_order = active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+
+        let _ = self.events.send(Event::MaintenanceWorkOrderCompleted {
+            work_order_id: id,
+            equipment_id,
+            downtime_hours,
+        });
+        Ok(work_order)
+    }
+
+    /// Downtime, MTBF/MTTR and preventive compliance per piece of equipment over
+    /// `[from, to]`.
+    pub async fn metrics(&self, query: MetricsQuery) -> Result<Vec<EquipmentMetrics>, ServiceError> {
+        if query.to < query.from {
+            return Err(ServiceError::ValidationError("`to` is before `from`".to_string()));
+        }
+        let start = query.from.and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc();
+        let end = (query.to + Duration::days(1)).and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc();
+        let db = self.db_pool.as_ref();
+
+        let mut equipment_query = Equipment::find().filter(equipment::Column::Status.ne(EquipmentStatus::Retired));
+        let mut downtime_query = Downtime::find()
+            .filter(equipment_downtime::Column::StartedAt.lt(end))
+            .filter(
+                Condition::any()
+                    .add(equipment_downtime::Column::EndedAt.is_null())
+                    .add(equipment_downtime::Column::EndedAt.gt(start)),
+            );
+        let mut work_order_query = WorkOrder::find()
+            .filter(work_order::Column::WorkOrderType.is_in(MAINTENANCE_TYPES))
+            .filter(
+                Condition::any()
+                    .add(work_order::Column::Status.is_not_in([WorkOrderStatus::Completed, WorkOrderStatus::Cancelled]))
+                    .add(work_order::Column::UpdatedAt.between(start, end)),
+            );
+        if let Some(id) = query.equipment_id {
+            equipment_query = equipment_query.filter(equipment::Column::Id.eq(id));
+            downtime_query = downtime_query.filter(equipment_downtime::Column::EquipmentId.eq(id));
+            work_order_query = work_order_query.filter(work_order::Column::EquipmentId.eq(id));
+        }
+        let equipment = equipment_query
+            .order_by_asc(equipment::Column::AssetTag)
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        let downtime = downtime_query.all(db).await.map_err(db_error)?;
+        let work_orders = work_order_query.all(db).await.map_err(db_error)?;
+        let now = Utc::now();
+        Ok(equipment
+            .iter()
+            .map(|e| equipment_metrics(e, &downtime, &work_orders, start, end, now))
+            .collect())
+    }
+}
+
+/// Raises due preventive maintenance on a fixed interval.
+pub fn spawn_pm_scheduler(equipment: Arc<EquipmentService>, interval: std::time::Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval.max(std::time::Duration::from_secs(1)));
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = equipment.generate_due(Utc::now().date_naive()).await {
+                error!("Preventive maintenance scheduling failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2026, 6, day).unwrap()
+    }
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 6, day, hour, 0, 0).unwrap()
+    }
+
+    fn schedule(interval_days: Option<i32>, meter_interval: Option<f64>) -> maintenance_schedule::Model {
+        maintenance_schedule::Model {
+            id: Uuid::new_v4(),
+            equipment_id: Uuid::nil(),
+            name: "Lubricate".to_string(),
+            instructions: None,
+            interval_days,
+            meter_interval,
+            lead_days: 3,
+            estimated_hours: 2.0,
+            next_due_date: interval_days.map(|_| date(20)),
+            next_due_meter: meter_interval.map(|_| 500.0),
+            active: true,
+            created_at: at(1, 0),
+            updated_at: at(1, 0),
+        }
+    }
+
+    fn press() -> equipment::Model {
+        equipment::Model {
+            id: Uuid::new_v4(),
+            asset_tag: "PRESS-01".to_string(),
+            name: "Press".to_string(),
+            category: None,
+            location: None,
+            work_center: None,
+            machine_id: None,
+            status: EquipmentStatus::Operational,
+            meter_unit: Some("hours".to_string()),
+            meter_reading: Some(100.0),
+            installed_on: None,
+            created_at: at(1, 0),
+            updated_at: at(1, 0),
+        }
+    }
+
+    fn downtime(equipment: &equipment::Model, planned: bool, from: DateTime<Utc>, to: Option<DateTime<Utc>>) -> equipment_downtime::Model {
+        equipment_downtime::Model {
+            id: Uuid::new_v4(),
+            equipment_id: equipment.id,
+            work_order_id: None,
+            planned,
+            reason: "Jam".to_string(),
+            started_at: from,
+            ended_at: to,
+            reported_by: "user:1".to_string(),
+        }
+    }
+
+    fn preventive(equipment: &equipment::Model, due: NaiveDate, completed: DateTime<Utc>) -> work_order::Model {
+        work_order::Model {
+            id: Uuid::new_v4(),
+            number: 1,
+            site: String::new(),
+            work_order_type: PREVENTIVE_MAINTENANCE.to_string(),
+            location: String::new(),
+            part: equipment.asset_tag.clone(),
+            order_number: String::new(),
+            manufacture_order: String::new(),
+            status: WorkOrderStatus::Completed,
+            created_by: SCHEDULER.to_string(),
+            created_at: at(1, 0),
+            updated_at: completed,
+            issue_date: date(1),
+            expected_completion_date: due,
+            priority: WorkOrderPriority::Medium,
+            memo: None,
+            bill_of_materials_number: 0,
+            actual_labor_hours: 0.0,
+            standard_labor_hours: 2.0,
+            capacity_utilization_id: Uuid::nil(),
+            bill_of_materials_id: Uuid::nil(),
+            cogs_data_id: Uuid::nil(),
+            bom_revision: None,
+            equipment_id: Some(equipment.id),
+            maintenance_schedule_id: None,
+        }
+    }
+
+    #[test]
+    fn test_due_by_date_within_lead_time() {
+        let schedule = schedule(Some(30), None);
+        assert!(!is_due(&schedule, None, date(16)));
+        assert!(is_due(&schedule, None, date(17)));
+        assert!(!is_due(&maintenance_schedule::Model { active: false, ..schedule }, None, date(25)));
+    }
+
+    #[test]
+    fn test_due_by_meter_whichever_first() {
+        let schedule = schedule(Some(30), Some(250.0));
+        assert!(!is_due(&schedule, Some(499.0), date(10)));
+        assert!(is_due(&schedule, Some(500.0), date(10)));
+    }
+
+    #[test]
+    fn test_next_due_counts_from_completion() {
+        let schedule = schedule(Some(30), Some(250.0));
+        assert_eq!(next_due(&schedule, date(18), Some(480.0)), (Some(date(18) + Duration::days(30)), Some(730.0)));
+        assert_eq!(next_due(&schedule, date(18), None), (Some(date(18) + Duration::days(30)), Some(500.0)));
+    }
+
+    #[test]
+    fn test_metrics_clip_downtime_to_period() {
+        let press = press();
+        let events = [
+            // 6 hours before the period plus 4 inside it
+            downtime(&press, false, Utc.with_ymd_and_hms(2026, 5, 31, 18, 0, 0).unwrap(), Some(at(1, 4))),
+            downtime(&press, false, at(10, 8), Some(at(10, 14))),
+            downtime(&press, true, at(20, 8), Some(at(20, 10))),
+        ];
+        let start = at(1, 0);
+        let end = at(11, 0);
+        let metrics = equipment_metrics(&press, &events, &[], start, end, at(30, 0));
+        assert_eq!(metrics.downtime_hours, 10.0);
+        assert_eq!(metrics.unplanned_downtime_hours, 10.0);
+        assert_eq!(metrics.failures, 1);
+        assert_eq!(metrics.mtbf_hours, Some(230.0));
+        assert_eq!(metrics.availability, 0.96);
+    }
+
+    #[test]
+    fn test_pm_compliance() {
+        let press = press();
+        let orders = [
+            preventive(&press, date(10), at(9, 12)),
+            preventive(&press, date(10), at(12, 12)),
+            preventive(&press, date(10), at(10, 23)),
+        ];
+        let metrics = equipment_metrics(&press, &[], &orders, at(1, 0), at(30, 0), at(30, 0));
+        assert_eq!(metrics.preventive_completed, 3);
+        assert_eq!(metrics.pm_compliance, Some(0.67));
+        assert_eq!(metrics.failures, 0);
+        assert_eq!(metrics.mtbf_hours, None);
+    }
+}
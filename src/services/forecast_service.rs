@@ -0,0 +1,492 @@
+use chrono::{Datelike, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db::{dialect, DbPool},
+    errors::ServiceError,
+    models::sales_forecast::{self, Entity as SalesForecast, ForecastSource},
+    utils::pagination::PaginationParams,
+};
+
+/// Largest CSV accepted by one import.
+pub const MAX_IMPORT_ROWS: usize = 10_000;
+
+const CSV_HEADER: [&str; 4] = ["sku", "channel", "month", "quantity"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+pub struct ForecastInput {
+    #[validate(length(min = 1, max = 64))]
+    pub sku: String,
+    #[validate(length(min = 1, max = 64))]
+    pub channel: String,
+    /// Any day in the month; stored as the first.
+    pub month: NaiveDate,
+    #[validate(range(min = 0))]
+    pub quantity: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ForecastOverride {
+    #[validate(range(min = 0))]
+    pub quantity: i32,
+    #[validate(length(min = 1, max = 500))]
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ForecastFilter {
+    pub sku: Option<String>,
+    pub channel: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+/// A CSV line that could not be read; `line` counts the header as line 1.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportRowError {
+    pub line: usize,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ImportSummary {
+    pub created: usize,
+    pub updated: usize,
+}
+
+/// Forecast against actual sales for one SKU, channel and month.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct AccuracyLine {
+    pub sku: String,
+    pub channel: String,
+    pub month: NaiveDate,
+    pub forecast: i64,
+    pub actual: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct AccuracyReport {
+    pub forecast: i64,
+    pub actual: i64,
+    /// Weighted absolute percentage error: total absolute error over total actual sales.
+    pub wape: Option<Decimal>,
+    /// Over-forecast (positive) or under-forecast (negative) as a share of actual sales.
+    pub bias: Option<Decimal>,
+    /// `1 - wape`, floored at zero.
+    pub accuracy: Option<Decimal>,
+    pub lines: Vec<AccuracyLine>,
+}
+
+/// Effective forecast demand of a SKU in one month, summed over channels.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MonthlyDemand {
+    pub month: NaiveDate,
+    pub quantity: i64,
+}
+
+/// Units sold per SKU, channel and month, from order lines.
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct ActualSales {
+    pub sku: String,
+    pub channel: String,
+    pub month: NaiveDate,
+    pub quantity: i64,
+}
+
+pub fn month_start(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).expect("day 1 exists in every month")
+}
+
+fn next_month(month: NaiveDate) -> NaiveDate {
+    let (year, month) = if month.month() == 12 { (month.year() + 1, 1) } else { (month.year(), month.month() + 1) };
+    NaiveDate::from_ymd_opt(year, month, 1).expect("first of month is valid")
+}
+
+/// Accepts `2026-03` or a full date such as `2026-03-15`.
+fn parse_month(value: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(&format!("{}-01", value), "%Y-%m-%d"))
+        .ok()
+        .map(month_start)
+}
+
+/// Reads a `sku,channel,month,quantity` CSV. Fields are plain, unquoted values. Every bad
+/// line is reported so a file can be fixed in one pass.
+pub fn parse_forecast_csv(text: &str) -> Result<Vec<ForecastInput>, Vec<ImportRowError>> {
+    let mut lines = text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+    let header: Vec<String> = match lines.next() {
+        Some((_, line)) => line.split(',').map(|h| h.trim().to_lowercase()).collect(),
+        None => return Err(vec![ImportRowError { line: 1, error: "File is empty".to_string() }]),
+    };
+    if header != CSV_HEADER {
+        return Err(vec![ImportRowError {
+            line: 1,
+            error: format!("Header must be {}", CSV_HEADER.join(",")),
+        }]);
+    }
+
+    let (mut rows, mut errors) = (Vec::new(), Vec::new());
+    for (index, line) in lines {
+        let line_number = index + 1;
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let row = match fields.as_slice() {
+            [sku, channel, month, quantity] => match (parse_month(month), quantity.parse::<i32>()) {
+                (Some(month), Ok(quantity)) => {
+                    let row = ForecastInput { sku: sku.to_string(), channel: channel.to_string(), month, quantity };
+                    row.validate().map(|_| row).map_err(|e| e.to_string())
+                }
+                (None, _) => Err(format!("Invalid month: {}", month)),
+                (_, Err(_)) => Err(format!("Invalid quantity: {}", quantity)),
+            },
+            _ => Err(format!("Expected 4 fields, found {}", fields.len())),
+        };
+        match row {
+            Ok(row) => rows.push(row),
+            Err(error) => errors.push(ImportRowError { line: line_number, error }),
+        }
+    }
+    if rows.len() > MAX_IMPORT_ROWS {
+        errors.push(ImportRowError { line: 1, error: format!("At most {} rows per import", MAX_IMPORT_ROWS) });
+    }
+    if errors.is_empty() {
+        Ok(rows)
+    } else {
+        Err(errors)
+    }
+}
+
+fn ratio(numerator: i64, denominator: i64) -> Option<Decimal> {
+    (denominator > 0).then(|| (Decimal::from(numerator) / Decimal::from(denominator)).round_dp(4))
+}
+
+/// Lines up effective forecasts with actual sales. Sales nobody forecast count as a
+/// forecast of zero.
+pub fn accuracy_report(forecasts: &[sales_forecast::Model], actuals: &[ActualSales]) -> AccuracyReport {
+    let mut lines: BTreeMap<(String, String, NaiveDate), (i64, i64)> = BTreeMap::new();
+    for forecast in forecasts {
+        let key = (forecast.sku.clone(), forecast.channel.clone(), forecast.month);
+        lines.entry(key).or_default().0 += i64::from(forecast.effective_quantity());
+    }
+    for sales in actuals {
+        let key = (sales.sku.clone(), sales.channel.clone(), sales.month);
+        lines.entry(key).or_default().1 += sales.quantity;
+    }
+
+    let mut report = AccuracyReport::default();
+    let mut absolute_error = 0;
+    for ((sku, channel, month), (forecast, actual)) in lines {
+        report.forecast += forecast;
+        report.actual += actual;
+        absolute_error += (forecast - actual).abs();
+        report.lines.push(AccuracyLine { sku, channel, month, forecast, actual });
+    }
+    report.wape = ratio(absolute_error, report.actual);
+    report.bias = ratio(report.forecast - report.actual, report.actual);
+    report.accuracy = report.wape.map(|wape| (Decimal::ONE - wape).max(Decimal::ZERO));
+    report
+}
+
+const ACTUAL_SALES_SQL: &str = r#"
+SELECT li.seller_sku AS sku,
+       COALESCE(o.source, 'direct') AS channel,
+       date_trunc('month', o.created_date)::date AS month,
+       SUM(li.quantity)::BIGINT AS quantity
+FROM order_line_items li
+JOIN orders o ON o.id = li.order_id
+WHERE o.order_status <> 'Cancelled'
+  AND o.created_date >= $1 AND o.created_date < $2
+  AND ($3::TEXT IS NULL OR li.seller_sku = $3)
+  AND ($4::TEXT IS NULL OR COALESCE(o.source, 'direct') = $4)
+GROUP BY 1, 2, 3
+"#;
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Forecast query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Sales forecasts per SKU, channel and month. Planning reads effective demand through
+/// `demand`, so overrides apply to replenishment and MRP as soon as they are saved.
+pub struct ForecastService {
+    db_pool: Arc<DbPool>,
+}
+
+impl ForecastService {
+    pub fn new(db_pool: Arc<DbPool>) -> Self {
+        Self { db_pool }
+    }
+
+    /// Creates or replaces the baseline of one forecast. Existing overrides are kept.
+    async fn upsert<C: ConnectionTrait>(
+        db: &C,
+        input: ForecastInput,
+        source: ForecastSource,
+    ) -> Result<(sales_forecast::Model, bool), ServiceError> {
+        let month = month_start(input.month);
+        let now = Utc::now();
+        let existing = SalesForecast::find()
+            .filter(sales_forecast::Column::Sku.eq(input.sku.as_str()))
+            .filter(sales_forecast::Column::Channel.eq(input.channel.as_str()))
+            .filter(sales_forecast::Column::Month.eq(month))
+            .one(db)
+            .await
+            .map_err(db_error)?;
+        match existing {
+            Some(forecast) => {
+                let mut active: sales_forecast::ActiveModel = forecast.into();
+                active.quantity = Set(input.quantity);
+                active.source = Set(source);
+                active.updated_at = Set(now);
+                Ok((active.update(db).await.map_err(db_error)?, false))
+            }
+            None => {
+                let forecast = sales_forecast::ActiveModel {
+                    id: Set(Uuid::new_v4()),
+                    sku: Set(input.sku),
+                    channel: Set(input.channel),
+                    month: Set(month),
+                    quantity: Set(input.quantity),
+                    override_quantity: Set(None),
+                    override_reason: Set(None),
+                    overridden_by: Set(None),
+                    overridden_at: Set(None),
+                    source: Set(source),
+                    created_at: Set(now),
+                    updated_at: Set(now),
+                }
+                .insert(db)
+                .await
+                .map_err(db_error)?;
+                Ok((forecast, true))
+            }
+        }
+    }
+
+    pub async fn save(&self, input: ForecastInput) -> Result<sales_forecast::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid forecast: {}", e)))?;
+        Ok(Self::upsert(self.db_pool.as_ref(), input, ForecastSource::Manual).await?.0)
+    }
+
+    /// Loads already-parsed CSV rows in one transaction.
+    #[instrument(skip(self, rows), fields(rows = rows.len()))]
+    pub async fn import(&self, rows: Vec<ForecastInput>) -> Result<ImportSummary, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let mut summary = ImportSummary::default();
+        for row in rows {
+            if Self::upsert(&txn, row, ForecastSource::Import).await?.1 {
+                summary.created += 1;
+            } else {
+                summary.updated += 1;
+            }
+        }
+        txn.commit().await.map_err(db_error)?;
+        info!(created = summary.created, updated = summary.updated, "Forecasts imported");
+        Ok(summary)
+    }
+
+    pub async fn list(
+        &self,
+        filter: ForecastFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<sales_forecast::Model>, u64), ServiceError> {
+        let mut query = SalesForecast::find();
+        if let Some(sku) = filter.sku {
+            query = query.filter(sales_forecast::Column::Sku.eq(sku));
+        }
+        if let Some(channel) = filter.channel {
+            query = query.filter(sales_forecast::Column::Channel.eq(channel));
+        }
+        if let Some(from) = filter.from {
+            query = query.filter(sales_forecast::Column::Month.gte(month_start(from)));
+        }
+        if let Some(to) = filter.to {
+            query = query.filter(sales_forecast::Column::Month.lte(month_start(to)));
+        }
+        let paginator = query
+            .order_by_asc(sales_forecast::Column::Month)
+            .order_by_asc(sales_forecast::Column::Sku)
+            .order_by_asc(sales_forecast::Column::Channel)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let forecasts = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((forecasts, total))
+    }
+
+    async fn find(&self, id: Uuid) -> Result<sales_forecast::Model, ServiceError> {
+        SalesForecast::find_by_id(id)
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Forecast not found: {}", id)))
+    }
+
+    pub async fn set_override(
+        &self,
+        id: Uuid,
+        input: ForecastOverride,
+        actor: &str,
+    ) -> Result<sales_forecast::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid override: {}", e)))?;
+        let now = Utc::now();
+        let mut active: sales_forecast::ActiveModel = self.find(id).await?.into();
+        active.override_quantity = Set(Some(input.quantity));
+        active.override_reason = Set(Some(input.reason));
+        active.overridden_by = Set(Some(actor.to_string()));
+        active.overridden_at = Set(Some(now));
+        active.updated_at = Set(now);
+        active.update(self.db_pool.as_ref()).await.map_err(db_error)
+    }
+
+    /// Reverts a forecast to its baseline quantity.
+    pub async fn clear_override(&self, id: Uuid) -> Result<sales_forecast::Model, ServiceError> {
+        let mut active: sales_forecast::ActiveModel = self.find(id).await?.into();
+        active.override_quantity = Set(None);
+        active.override_reason = Set(None);
+        active.overridden_by = Set(None);
+        active.overridden_at = Set(None);
+        active.updated_at = Set(Utc::now());
+        active.update(self.db_pool.as_ref()).await.map_err(db_error)
+    }
+
+    /// Effective forecast demand of `sku` per month in `[from, to]`, over all channels.
+    /// Months without a forecast are reported as zero.
+    pub async fn demand(&self, sku: &str, from: NaiveDate, to: NaiveDate) -> Result<Vec<MonthlyDemand>, ServiceError> {
+        let (from, to) = (month_start(from), month_start(to));
+        if to < from {
+            return Err(ServiceError::ValidationError("`to` is before `from`".to_string()));
+        }
+        let forecasts = SalesForecast::find()
+            .filter(sales_forecast::Column::Sku.eq(sku))
+            .filter(sales_forecast::Column::Month.between(from, to))
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?;
+        let mut months = BTreeMap::new();
+        let mut month = from;
+        while month <= to {
+            months.insert(month, 0i64);
+            month = next_month(month);
+        }
+        for forecast in &forecasts {
+            *months.entry(forecast.month).or_default() += i64::from(forecast.effective_quantity());
+        }
+        Ok(months.into_iter().map(|(month, quantity)| MonthlyDemand { month, quantity }).collect())
+    }
+
+    /// Forecast against actual sales for the months `[from, to]`.
+    pub async fn accuracy(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        sku: Option<String>,
+        channel: Option<String>,
+    ) -> Result<AccuracyReport, ServiceError> {
+        let (from, to) = (month_start(from), month_start(to));
+        if to < from {
+            return Err(ServiceError::ValidationError("`to` is before `from`".to_string()));
+        }
+        let db = self.db_pool.as_ref();
+        let mut query = SalesForecast::find().filter(sales_forecast::Column::Month.between(from, to));
+        if let Some(sku) = &sku {
+            query = query.filter(sales_forecast::Column::Sku.eq(sku.as_str()));
+        }
+        if let Some(channel) = &channel {
+            query = query.filter(sales_forecast::Column::Channel.eq(channel.as_str()));
+        }
+        let forecasts = query.all(db).await.map_err(db_error)?;
+
+        let start = from.and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc();
+        let end = next_month(to).and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc();
+        let actuals = ActualSales::find_by_statement(dialect::statement(
+            db,
+            ACTUAL_SALES_SQL,
+            [start.into(), end.into(), sku.into(), channel.into()],
+        ))
+        .all(db)
+        .await
+        .map_err(db_error)?;
+        Ok(accuracy_report(&forecasts, &actuals))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn forecast(sku: &str, month: NaiveDate, quantity: i32, override_quantity: Option<i32>) -> sales_forecast::Model {
+        let now = Utc::now();
+        sales_forecast::Model {
+            id: Uuid::new_v4(),
+            sku: sku.to_string(),
+            channel: "web".to_string(),
+            month,
+            quantity,
+            override_quantity,
+            override_reason: None,
+            overridden_by: None,
+            overridden_at: None,
+            source: ForecastSource::Import,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn sold(sku: &str, month: NaiveDate, quantity: i64) -> ActualSales {
+        ActualSales { sku: sku.to_string(), channel: "web".to_string(), month, quantity }
+    }
+
+    #[test]
+    fn test_parse_csv_normalizes_months() {
+        let rows = parse_forecast_csv("sku,channel,month,quantity\nMUG,web,2026-03,120\n\nMUG,amazon,2026-04-17,80\n").unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].month, date(2026, 3, 1));
+        assert_eq!(rows[1].month, date(2026, 4, 1));
+        assert_eq!(rows[1].quantity, 80);
+    }
+
+    #[test]
+    fn test_parse_csv_reports_every_bad_line() {
+        let errors = parse_forecast_csv("sku,channel,month,quantity\nMUG,web,March,1\nMUG,web,2026-03,-4\nMUG,web\n")
+            .unwrap_err();
+        let lines: Vec<usize> = errors.iter().map(|e| e.line).collect();
+        assert_eq!(lines, vec![2, 3, 4]);
+        assert!(parse_forecast_csv("sku,month,quantity\n").is_err());
+    }
+
+    #[test]
+    fn test_accuracy_uses_overrides_and_unforecast_sales() {
+        let march = date(2026, 3, 1);
+        let report = accuracy_report(
+            &[forecast("MUG", march, 100, Some(80)), forecast("LAMP", march, 50, None)],
+            &[sold("MUG", march, 100), sold("LAMP", march, 40), sold("TEA", march, 10)],
+        );
+        assert_eq!(report.lines.len(), 3);
+        assert_eq!((report.forecast, report.actual), (130, 150));
+        // |80-100| + |50-40| + |0-10| = 40 over 150 sold
+        assert_eq!(report.wape, Some(dec!(0.2667)));
+        assert_eq!(report.bias, Some(dec!(-0.1333)));
+        assert_eq!(report.accuracy, Some(dec!(0.7333)));
+    }
+
+    #[test]
+    fn test_next_month_rolls_over_year() {
+        assert_eq!(next_month(date(2026, 12, 1)), date(2027, 1, 1));
+        assert_eq!(month_start(date(2026, 2, 28)), date(2026, 2, 1));
+    }
+}
@@ -0,0 +1,307 @@
+use chrono::Utc;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::{
+    db::DbPool,
+    errors::ServiceError,
+    hazmat::{self, DangerousGood},
+    models::{
+        product_hazmat::{self, Entity as ProductHazmat, PackingGroup},
+        shipment::{self, Entity as Shipment, ShippingCarrier},
+        shipment_dangerous_good::{self, Entity as ShipmentDangerousGood},
+    },
+};
+
+/// Dangerous goods classification of a SKU, replaced as a whole on every write.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "check_classification"))]
+pub struct HazmatInput {
+    pub un_number: String,
+    #[validate(length(min = 1, max = 255))]
+    pub proper_shipping_name: String,
+    pub hazard_class: String,
+    pub packing_group: Option<PackingGroup>,
+    #[serde(default)]
+    pub limited_quantity: bool,
+}
+
+fn check_classification(input: &HazmatInput) -> Result<(), ValidationError> {
+    if !hazmat::valid_un_number(&input.un_number) {
+        return Err(ValidationError::new("un_number_must_be_un_followed_by_four_digits"));
+    }
+    if !hazmat::valid_hazard_class(&input.hazard_class) {
+        return Err(ValidationError::new("unknown_hazard_class"));
+    }
+    match hazmat::packing_group_error(&input.hazard_class, input.packing_group) {
+        Some(code) => Err(ValidationError::new(code)),
+        None => Ok(()),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct GoodsLine {
+    #[validate(length(min = 1))]
+    pub sku: String,
+    #[validate(range(min = 1))]
+    pub quantity: i32,
+}
+
+/// Contents of a shipment, to be checked against a carrier service before booking it.
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct HazmatCheck {
+    pub carrier: ShippingCarrier,
+    #[serde(default)]
+    pub service: String,
+    #[validate]
+    pub items: Vec<GoodsLine>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HazmatCheckResult {
+    pub allowed: bool,
+    pub air: bool,
+    /// The dangerous items among those checked.
+    pub dangerous_goods: Vec<DangerousGood>,
+    pub violations: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct DeclareGoods {
+    #[validate]
+    pub items: Vec<GoodsLine>,
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Hazmat query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Dangerous goods declared in a shipment, in declaration order.
+pub async fn shipment_goods<C: ConnectionTrait>(db: &C, shipment_id: i32) -> Result<Vec<DangerousGood>, DbErr> {
+    Ok(ShipmentDangerousGood::find()
+        .filter(shipment_dangerous_good::Column::ShipmentId.eq(shipment_id))
+        .order_by_asc(shipment_dangerous_good::Column::CreatedAt)
+        .order_by_asc(shipment_dangerous_good::Column::Sku)
+        .all(db)
+        .await?
+        .iter()
+        .map(DangerousGood::from)
+        .collect())
+}
+
+/// Reasons the goods declared in `shipment_id` may not go with `carrier` on `service`.
+/// Called wherever a shipment's carrier or service is chosen.
+pub async fn selection_violations<C: ConnectionTrait>(
+    db: &C,
+    shipment_id: i32,
+    carrier: ShippingCarrier,
+    service: &str,
+) -> Result<Vec<String>, DbErr> {
+    let goods = shipment_goods(db, shipment_id).await?;
+    Ok(hazmat::violations(carrier, service, &goods, &hazmat::config()))
+}
+
+/// Hazmat attributes of products, carrier compatibility checks and dangerous goods
+/// declarations on shipments.
+pub struct HazmatService {
+    db_pool: Arc<DbPool>,
+}
+
+impl HazmatService {
+    pub fn new(db_pool: Arc<DbPool>) -> Self {
+        Self { db_pool }
+    }
+
+    pub async fn get_product(&self, sku: &str) -> Result<product_hazmat::Model, ServiceError> {
+        ProductHazmat::find_by_id(sku.to_string())
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("No hazmat classification for {}", sku)))
+    }
+
+    /// Creates or replaces the classification of `sku`. Shipments already declared keep
+    /// the classification they were declared with.
+    #[instrument(skip(self, input))]
+    pub async fn set_product(
+        &self,
+        sku: &str,
+        input: HazmatInput,
+        actor: &str,
+    ) -> Result<product_hazmat::Model, ServiceError> {
+        input.validate().map_err(|e| ServiceError::ValidationError(e.to_string()))?;
+        let db = self.db_pool.as_ref();
+        let now = Utc::now();
+        let existing = ProductHazmat::find_by_id(sku.to_string()).one(db).await.map_err(db_error)?;
+        let model = product_hazmat::ActiveModel {
+            sku: Set(sku.to_string()),
+            un_number: Set(input.un_number),
+            proper_shipping_name: Set(input.proper_shipping_name),
+            hazard_class: Set(input.hazard_class),
+            packing_group: Set(input.packing_group),
+            limited_quantity: Set(input.limited_quantity),
+            updated_by: Set(actor.to_string()),
+            created_at: Set(existing.as_ref().map(|e| e.created_at).unwrap_or(now)),
+            updated_at: Set(now),
+        };
+        let saved = match existing {
+            Some(_) => model.update(db).await,
+            None => model.insert(db).await,
+        }
+        .map_err(db_error)?;
+        info!(sku, un_number = %saved.un_number, "Hazmat classification saved");
+        Ok(saved)
+    }
+
+    pub async fn remove_product(&self, sku: &str) -> Result<(), ServiceError> {
+        let result = ProductHazmat::delete_by_id(sku.to_string())
+            .exec(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?;
+        if result.rows_affected == 0 {
+            return Err(ServiceError::NotFound(format!("No hazmat classification for {}", sku)));
+        }
+        Ok(())
+    }
+
+    /// The dangerous items among `items`, in the order given.
+    async fn classify<C: ConnectionTrait>(&self, db: &C, items: &[GoodsLine]) -> Result<Vec<DangerousGood>, ServiceError> {
+        let products: HashMap<String, product_hazmat::Model> = ProductHazmat::find()
+            .filter(product_hazmat::Column::Sku.is_in(items.iter().map(|i| i.sku.clone())))
+            .all(db)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(|p| (p.sku.clone(), p))
+            .collect();
+        Ok(items
+            .iter()
+            .filter_map(|item| products.get(&item.sku).map(|p| DangerousGood::new(p, item.quantity)))
+            .collect())
+    }
+
+    pub async fn check(&self, request: HazmatCheck) -> Result<HazmatCheckResult, ServiceError> {
+        request.validate().map_err(|e| ServiceError::ValidationError(e.to_string()))?;
+        let goods = self.classify(self.db_pool.as_ref(), &request.items).await?;
+        let violations = hazmat::violations(request.carrier, &request.service, &goods, &hazmat::config());
+        Ok(HazmatCheckResult {
+            allowed: violations.is_empty(),
+            air: hazmat::is_air_service(request.carrier, &request.service),
+            dangerous_goods: goods,
+            violations,
+        })
+    }
+
+    async fn shipment(&self, shipment_id: i32) -> Result<shipment::Model, ServiceError> {
+        Shipment::find_by_id(shipment_id)
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Shipment {} not found", shipment_id)))
+    }
+
+    /// Records the dangerous goods packed in a shipment, replacing any earlier declaration.
+    /// Refused when the shipment's carrier and service may not carry them.
+    #[instrument(skip(self, request))]
+    pub async fn declare(
+        &self,
+        shipment_id: i32,
+        request: DeclareGoods,
+    ) -> Result<Vec<shipment_dangerous_good::Model>, ServiceError> {
+        request.validate().map_err(|e| ServiceError::ValidationError(e.to_string()))?;
+        let shipment = self.shipment(shipment_id).await?;
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let goods = self.classify(&txn, &request.items).await?;
+        let violations = hazmat::violations(shipment.carrier, &shipment.shipping_method, &goods, &hazmat::config());
+        if !violations.is_empty() {
+            return Err(ServiceError::ValidationError(format!(
+                "{:?} {} cannot carry this shipment: {}",
+                shipment.carrier,
+                shipment.shipping_method,
+                violations.join("; ")
+            )));
+        }
+
+        ShipmentDangerousGood::delete_many()
+            .filter(shipment_dangerous_good::Column::ShipmentId.eq(shipment_id))
+            .exec(&txn)
+            .await
+            .map_err(db_error)?;
+        let now = Utc::now();
+        let mut lines = Vec::with_capacity(goods.len());
+        for good in goods {
+            let line = shipment_dangerous_good::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                shipment_id: Set(shipment_id),
+                sku: Set(good.sku),
+                quantity: Set(good.quantity),
+                un_number: Set(good.un_number),
+                proper_shipping_name: Set(good.proper_shipping_name),
+                hazard_class: Set(good.hazard_class),
+                packing_group: Set(good.packing_group),
+                limited_quantity: Set(good.limited_quantity),
+                created_at: Set(now),
+            }
+            .insert(&txn)
+            .await
+            .map_err(db_error)?;
+            lines.push(line);
+        }
+        txn.commit().await.map_err(db_error)?;
+        info!(shipment_id, dangerous_goods = lines.len(), "Dangerous goods declared");
+        Ok(lines)
+    }
+
+    pub async fn goods(&self, shipment_id: i32) -> Result<Vec<DangerousGood>, ServiceError> {
+        self.shipment(shipment_id).await?;
+        shipment_goods(self.db_pool.as_ref(), shipment_id).await.map_err(db_error)
+    }
+
+    /// The shipper's declaration for a shipment carrying dangerous goods.
+    pub async fn declaration(&self, shipment_id: i32) -> Result<String, ServiceError> {
+        let shipment = self.shipment(shipment_id).await?;
+        let goods = shipment_goods(self.db_pool.as_ref(), shipment_id).await.map_err(db_error)?;
+        if goods.is_empty() {
+            return Err(ServiceError::NotFound(format!("Shipment {} has no dangerous goods", shipment_id)));
+        }
+        Ok(hazmat::declaration(&shipment, &goods, &hazmat::config()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(class: &str, packing_group: Option<PackingGroup>) -> HazmatInput {
+        HazmatInput {
+            un_number: "UN1266".to_string(),
+            proper_shipping_name: "Perfume products".to_string(),
+            hazard_class: class.to_string(),
+            packing_group,
+            limited_quantity: true,
+        }
+    }
+
+    #[test]
+    fn test_classification_is_validated() {
+        assert!(input("3", Some(PackingGroup::II)).validate().is_ok());
+        assert!(input("3", None).validate().is_err());
+        assert!(input("3.5", Some(PackingGroup::II)).validate().is_err());
+        let mut bad_un = input("9", None);
+        bad_un.un_number = "1266".to_string();
+        assert!(bad_un.validate().is_err());
+    }
+
+    #[test]
+    fn test_goods_lines_need_quantity() {
+        let request = DeclareGoods { items: vec![GoodsLine { sku: "SKU-1".to_string(), quantity: 0 }] };
+        assert!(request.validate().is_err());
+    }
+}
@@ -0,0 +1,507 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+use tracing::error;
+
+use crate::{
+    calendar::{BusinessCalendar, Calendars},
+    db::{dialect, DbPool},
+    errors::ServiceError,
+    models::inventory_items::{self, Entity as InventoryItem},
+};
+
+/// Inventory aging settings, loaded from the `inventory_aging` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct InventoryAgingConfig {
+    /// Share of each bucket's value reserved against obsolescence, overriding the
+    /// defaults of 0, 0, 10%, 25% and 50% from youngest to oldest. Expired lots are
+    /// always reserved in full.
+    #[serde(default)]
+    pub reserve_rates: HashMap<AgingBucket, Decimal>,
+
+    /// Days of sales over which demand is averaged.
+    #[serde(default = "default_demand_window_days")]
+    pub demand_window_days: i64,
+
+    /// Days of demand worth keeping; stock beyond that is excess.
+    #[serde(default = "default_cover_days")]
+    pub cover_days: i64,
+
+    /// Share of cost expected back from marking excess stock down.
+    #[serde(default = "default_markdown_recovery")]
+    pub markdown_recovery: Decimal,
+
+    /// Share of cost expected back from selling excess stock to a liquidator.
+    #[serde(default = "default_liquidation_recovery")]
+    pub liquidation_recovery: Decimal,
+}
+
+fn default_demand_window_days() -> i64 {
+    90
+}
+
+fn default_cover_days() -> i64 {
+    120
+}
+
+fn default_markdown_recovery() -> Decimal {
+    Decimal::new(70, 2)
+}
+
+fn default_liquidation_recovery() -> Decimal {
+    Decimal::new(25, 2)
+}
+
+impl Default for InventoryAgingConfig {
+    fn default() -> Self {
+        Self {
+            reserve_rates: HashMap::new(),
+            demand_window_days: default_demand_window_days(),
+            cover_days: default_cover_days(),
+            markdown_recovery: default_markdown_recovery(),
+            liquidation_recovery: default_liquidation_recovery(),
+        }
+    }
+}
+
+impl InventoryAgingConfig {
+    fn reserve_rate(&self, bucket: AgingBucket) -> Decimal {
+        self.reserve_rates.get(&bucket).copied().unwrap_or_else(|| bucket.default_reserve_rate())
+    }
+}
+
+/// Age of stock since receipt, in days.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum AgingBucket {
+    #[serde(rename = "0-30")]
+    UpTo30,
+    #[serde(rename = "31-60")]
+    UpTo60,
+    #[serde(rename = "61-90")]
+    UpTo90,
+    #[serde(rename = "91-180")]
+    UpTo180,
+    #[serde(rename = "180+")]
+    Over180,
+}
+
+impl AgingBucket {
+    pub const ALL: [AgingBucket; 5] =
+        [AgingBucket::UpTo30, AgingBucket::UpTo60, AgingBucket::UpTo90, AgingBucket::UpTo180, AgingBucket::Over180];
+
+    /// Stock received today is 0 days old; stock received in the future counts as new.
+    pub fn for_age(days: i64) -> Self {
+        match days {
+            i64::MIN..=30 => AgingBucket::UpTo30,
+            31..=60 => AgingBucket::UpTo60,
+            61..=90 => AgingBucket::UpTo90,
+            91..=180 => AgingBucket::UpTo180,
+            _ => AgingBucket::Over180,
+        }
+    }
+
+    fn default_reserve_rate(self) -> Decimal {
+        match self {
+            AgingBucket::UpTo30 | AgingBucket::UpTo60 => Decimal::ZERO,
+            AgingBucket::UpTo90 => Decimal::new(10, 2),
+            AgingBucket::UpTo180 => Decimal::new(25, 2),
+            AgingBucket::Over180 => Decimal::new(50, 2),
+        }
+    }
+}
+
+/// One lot of on-hand stock.
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct AgingLot {
+    pub sku: String,
+    pub warehouse: i32,
+    pub available: i32,
+    /// Receipt date of the lot.
+    pub arrival_date: NaiveDate,
+    pub unit_cost: Option<Decimal>,
+    pub expiration_date: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct BucketTotals {
+    pub lots: u64,
+    pub units: i64,
+    pub value: Decimal,
+    /// Value at risk of obsolescence: `value` times the bucket's reserve rate.
+    pub exposure: Decimal,
+}
+
+impl BucketTotals {
+    fn add(&mut self, units: i64, value: Decimal, exposure: Decimal) {
+        self.lots += 1;
+        self.units += units;
+        self.value += value;
+        self.exposure += exposure;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BucketSummary {
+    pub bucket: AgingBucket,
+    #[serde(flatten)]
+    pub totals: BucketTotals,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LiquidationAction {
+    /// Excess between 91 and 180 days old: discount it while it still sells.
+    Markdown,
+    /// Excess older than 180 days: sell it off to a liquidator.
+    Liquidate,
+    /// Expired: nothing to recover.
+    WriteOff,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LiquidationSuggestion {
+    pub sku: String,
+    pub action: LiquidationAction,
+    pub units: i64,
+    /// Cost of the units.
+    pub value: Decimal,
+    pub estimated_recovery: Decimal,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SkuAging {
+    pub sku: String,
+    pub units: i64,
+    pub value: Decimal,
+    pub exposure: Decimal,
+    pub oldest_receipt: NaiveDate,
+    /// Units sold per day over the demand window.
+    pub daily_demand: Decimal,
+    /// Days the stock lasts at that rate; `None` without sales.
+    pub days_of_cover: Option<i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct InventoryAgingReport {
+    pub as_of: NaiveDate,
+    pub warehouse: Option<i32>,
+    pub units: i64,
+    pub value: Decimal,
+    /// Obsolescence exposure: the reserve across buckets plus expired stock.
+    pub obsolescence_exposure: Decimal,
+    /// On-hand units without a unit cost, which add nothing to value or exposure.
+    pub uncosted_units: i64,
+    pub buckets: Vec<BucketSummary>,
+    pub expired: BucketTotals,
+    /// SKUs with the most exposure first.
+    pub skus: Vec<SkuAging>,
+    /// Largest recoverable value first.
+    pub suggestions: Vec<LiquidationSuggestion>,
+}
+
+#[derive(Default)]
+struct SkuTally {
+    units: i64,
+    value: Decimal,
+    exposure: Decimal,
+    oldest_receipt: Option<NaiveDate>,
+    /// Units and cost by age, for the excess suggestions.
+    by_bucket: BTreeMap<AgingBucket, (i64, Decimal)>,
+    expired: (i64, Decimal),
+}
+
+/// Buckets lots by age as of `as_of` and suggests what to do with excess stock.
+/// `sold` holds units sold per SKU over the demand window.
+pub fn build_report(
+    lots: &[AgingLot],
+    sold: &HashMap<String, i64>,
+    as_of: NaiveDate,
+    warehouse: Option<i32>,
+    config: &InventoryAgingConfig,
+    limit: usize,
+) -> InventoryAgingReport {
+    let mut buckets: BTreeMap<AgingBucket, BucketTotals> =
+        AgingBucket::ALL.iter().map(|b| (*b, BucketTotals::default())).collect();
+    let mut expired = BucketTotals::default();
+    let mut skus: BTreeMap<&str, SkuTally> = BTreeMap::new();
+    let mut uncosted_units = 0;
+
+    for lot in lots.iter().filter(|lot| lot.available > 0) {
+        let units = i64::from(lot.available);
+        let value = lot.unit_cost.map_or(Decimal::ZERO, |cost| cost * Decimal::from(units));
+        if lot.unit_cost.is_none() {
+            uncosted_units += units;
+        }
+        let tally = skus.entry(lot.sku.as_str()).or_default();
+        tally.units += units;
+        tally.value += value;
+        tally.oldest_receipt = Some(tally.oldest_receipt.map_or(lot.arrival_date, |d| d.min(lot.arrival_date)));
+
+        let is_expired = lot.expiration_date.map_or(false, |at| at.date_naive() <= as_of);
+        if is_expired {
+            expired.add(units, value, value);
+            tally.exposure += value;
+            tally.expired.0 += units;
+            tally.expired.1 += value;
+            continue;
+        }
+        let bucket = AgingBucket::for_age((as_of - lot.arrival_date).num_days());
+        let exposure = (value * config.reserve_rate(bucket)).round_dp(2);
+        buckets.get_mut(&bucket).expect("every bucket is present").add(units, value, exposure);
+        tally.exposure += exposure;
+        let aged = tally.by_bucket.entry(bucket).or_default();
+        aged.0 += units;
+        aged.1 += value;
+    }
+
+    let window = Decimal::from(config.demand_window_days.max(1));
+    let mut suggestions = Vec::new();
+    let mut sku_rows = Vec::new();
+    for (sku, tally) in skus {
+        let sold = sold.get(sku).copied().unwrap_or(0).max(0);
+        let daily_demand = (Decimal::from(sold) / window).round_dp(2);
+        let keep = (Decimal::from(sold) * Decimal::from(config.cover_days) / window).ceil();
+        let keep = i64::try_from(keep).unwrap_or(i64::MAX);
+
+        if tally.expired.0 > 0 {
+            suggestions.push(LiquidationSuggestion {
+                sku: sku.to_string(),
+                action: LiquidationAction::WriteOff,
+                units: tally.expired.0,
+                value: tally.expired.1,
+                estimated_recovery: Decimal::ZERO,
+                reason: format!("{} units past their expiration date", tally.expired.0),
+            });
+        }
+        // The oldest stock is what should go, so the excess is taken from the oldest lots first
+        let mut excess = (tally.units - tally.expired.0 - keep).max(0);
+        for (bucket, (units, value)) in tally.by_bucket.iter().rev() {
+            let (action, recovery) = match bucket {
+                AgingBucket::Over180 => (LiquidationAction::Liquidate, config.liquidation_recovery),
+                AgingBucket::UpTo180 => (LiquidationAction::Markdown, config.markdown_recovery),
+                _ => break,
+            };
+            let units_in_excess = excess.min(*units);
+            if units_in_excess == 0 {
+                break;
+            }
+            excess -= units_in_excess;
+            let value = (*value * Decimal::from(units_in_excess) / Decimal::from(*units)).round_dp(2);
+            suggestions.push(LiquidationSuggestion {
+                sku: sku.to_string(),
+                action,
+                units: units_in_excess,
+                value,
+                estimated_recovery: (value * recovery).round_dp(2),
+                reason: format!(
+                    "{} units older than {} days beyond {} days of demand ({} sold in the last {} days)",
+                    units_in_excess,
+                    if *bucket == AgingBucket::Over180 { 180 } else { 90 },
+                    config.cover_days,
+                    sold,
+                    config.demand_window_days
+                ),
+            });
+        }
+
+        sku_rows.push(SkuAging {
+            sku: sku.to_string(),
+            units: tally.units,
+            value: tally.value,
+            exposure: tally.exposure,
+            oldest_receipt: tally.oldest_receipt.unwrap_or(as_of),
+            daily_demand,
+            days_of_cover: (sold > 0).then(|| tally.units * config.demand_window_days.max(1) / sold),
+        });
+    }
+    sku_rows.sort_by(|a, b| b.exposure.cmp(&a.exposure).then_with(|| a.sku.cmp(&b.sku)));
+    sku_rows.truncate(limit);
+    suggestions.sort_by(|a, b| b.estimated_recovery.cmp(&a.estimated_recovery).then_with(|| b.value.cmp(&a.value)));
+    suggestions.truncate(limit);
+
+    let buckets: Vec<BucketSummary> =
+        buckets.into_iter().map(|(bucket, totals)| BucketSummary { bucket, totals }).collect();
+    InventoryAgingReport {
+        as_of,
+        warehouse,
+        units: buckets.iter().map(|b| b.totals.units).sum::<i64>() + expired.units,
+        value: buckets.iter().map(|b| b.totals.value).sum::<Decimal>() + expired.value,
+        obsolescence_exposure: buckets.iter().map(|b| b.totals.exposure).sum::<Decimal>() + expired.exposure,
+        uncosted_units,
+        buckets,
+        expired,
+        skus: sku_rows,
+        suggestions,
+    }
+}
+
+const SOLD_SQL: &str = r#"
+SELECT li.seller_sku AS sku, SUM(li.quantity)::BIGINT AS quantity
+FROM order_line_items li
+JOIN orders o ON o.id = li.order_id
+WHERE o.order_status <> 'Cancelled'
+  AND o.created_date >= $1
+GROUP BY 1
+"#;
+
+#[derive(FromQueryResult)]
+struct SoldRow {
+    sku: String,
+    quantity: i64,
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Inventory aging query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Ages on-hand stock by receipt date and prices its obsolescence risk.
+pub struct InventoryAgingService {
+    db_pool: Arc<DbPool>,
+    calendars: Arc<Calendars>,
+    config: InventoryAgingConfig,
+}
+
+impl InventoryAgingService {
+    pub fn new(db_pool: Arc<DbPool>, calendars: Arc<Calendars>, config: InventoryAgingConfig) -> Self {
+        Self { db_pool, calendars, config }
+    }
+
+    pub fn calendar(&self) -> &BusinessCalendar {
+        self.calendars.default_calendar()
+    }
+
+    /// Aging of stock on hand now, optionally in one warehouse, with lot ages counted
+    /// up to `as_of`. Demand is company-wide, so excess in a single warehouse is understated.
+    pub async fn report(
+        &self,
+        as_of: NaiveDate,
+        warehouse: Option<i32>,
+        limit: usize,
+    ) -> Result<InventoryAgingReport, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let mut query = InventoryItem::find()
+            .select_only()
+            .columns([
+                inventory_items::Column::Sku,
+                inventory_items::Column::Warehouse,
+                inventory_items::Column::Available,
+                inventory_items::Column::ArrivalDate,
+                inventory_items::Column::ExpirationDate,
+            ])
+            .expr_as(sea_query::Expr::cust("COALESCE(unit_cost, average_cost)"), "unit_cost")
+            .filter(inventory_items::Column::Available.gt(0));
+        if let Some(warehouse) = warehouse {
+            query = query.filter(inventory_items::Column::Warehouse.eq(warehouse));
+        }
+        let lots = query.into_model::<AgingLot>().all(db).await.map_err(db_error)?;
+
+        let since = self.calendar().start_of_day(as_of) - chrono::Duration::days(self.config.demand_window_days.max(1));
+        let sold = SoldRow::find_by_statement(dialect::statement(db, SOLD_SQL, [since.into()]))
+            .all(db)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(|row| (row.sku, row.quantity))
+            .collect();
+        Ok(build_report(&lots, &sold, as_of, warehouse, &self.config, limit))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn as_of() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 12, 31).unwrap()
+    }
+
+    fn lot(sku: &str, units: i32, days_old: i64, cost: Option<i64>) -> AgingLot {
+        AgingLot {
+            sku: sku.to_string(),
+            warehouse: 1,
+            available: units,
+            arrival_date: as_of() - Duration::days(days_old),
+            unit_cost: cost.map(|c| Decimal::new(c, 2)),
+            expiration_date: None,
+        }
+    }
+
+    #[test]
+    fn test_bucket_boundaries() {
+        assert_eq!(AgingBucket::for_age(-3), AgingBucket::UpTo30);
+        assert_eq!(AgingBucket::for_age(30), AgingBucket::UpTo30);
+        assert_eq!(AgingBucket::for_age(31), AgingBucket::UpTo60);
+        assert_eq!(AgingBucket::for_age(90), AgingBucket::UpTo90);
+        assert_eq!(AgingBucket::for_age(180), AgingBucket::UpTo180);
+        assert_eq!(AgingBucket::for_age(181), AgingBucket::Over180);
+    }
+
+    #[test]
+    fn test_lots_are_bucketed_and_reserved() {
+        let lots = [lot("MUG", 10, 5, Some(1000)), lot("MUG", 4, 120, Some(1000)), lot("CAP", 2, 400, None)];
+        let report = build_report(&lots, &HashMap::new(), as_of(), None, &InventoryAgingConfig::default(), 50);
+        let bucket = |b: AgingBucket| report.buckets.iter().find(|s| s.bucket == b).unwrap().totals.clone();
+
+        assert_eq!(bucket(AgingBucket::UpTo30).units, 10);
+        assert_eq!(bucket(AgingBucket::UpTo30).exposure, Decimal::ZERO);
+        assert_eq!(bucket(AgingBucket::UpTo180).value, Decimal::new(4000, 2));
+        assert_eq!(bucket(AgingBucket::UpTo180).exposure, Decimal::new(1000, 2));
+        assert_eq!(bucket(AgingBucket::Over180).units, 2);
+        assert_eq!(report.units, 16);
+        assert_eq!(report.value, Decimal::new(14000, 2));
+        assert_eq!(report.obsolescence_exposure, Decimal::new(1000, 2));
+        assert_eq!(report.uncosted_units, 2);
+    }
+
+    #[test]
+    fn test_expired_lots_are_fully_exposed_and_written_off() {
+        let mut expired = lot("MILK", 5, 10, Some(200));
+        expired.expiration_date = Some(Utc.with_ymd_and_hms(2024, 12, 1, 0, 0, 0).unwrap());
+        let report = build_report(&[expired], &HashMap::new(), as_of(), None, &InventoryAgingConfig::default(), 50);
+        assert_eq!(report.expired.units, 5);
+        assert_eq!(report.obsolescence_exposure, Decimal::new(1000, 2));
+        assert_eq!(report.suggestions[0].action, LiquidationAction::WriteOff);
+        assert_eq!(report.suggestions[0].estimated_recovery, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_excess_is_taken_from_the_oldest_stock() {
+        // 30 sold over 90 days keeps 40 units for 120 days of cover; 60 of 100 are excess
+        let lots = [lot("MUG", 40, 10, Some(1000)), lot("MUG", 20, 120, Some(1000)), lot("MUG", 40, 200, Some(1000))];
+        let sold = HashMap::from([("MUG".to_string(), 30)]);
+        let report = build_report(&lots, &sold, as_of(), None, &InventoryAgingConfig::default(), 50);
+
+        let actions: Vec<_> = report.suggestions.iter().map(|s| (s.action, s.units)).collect();
+        assert_eq!(actions, [(LiquidationAction::Markdown, 20), (LiquidationAction::Liquidate, 40)]);
+        assert_eq!(report.suggestions[0].estimated_recovery, Decimal::new(14000, 2));
+        assert_eq!(report.suggestions[1].estimated_recovery, Decimal::new(10000, 2));
+        assert_eq!(report.skus[0].days_of_cover, Some(300));
+    }
+
+    #[test]
+    fn test_fresh_or_selling_stock_has_no_suggestions() {
+        let lots = [lot("MUG", 100, 20, Some(1000)), lot("CAP", 10, 200, Some(1000))];
+        let sold = HashMap::from([("CAP".to_string(), 90)]);
+        let report = build_report(&lots, &sold, as_of(), None, &InventoryAgingConfig::default(), 50);
+        assert!(report.suggestions.is_empty());
+    }
+
+    #[test]
+    fn test_reserve_rates_can_be_overridden() {
+        let config: InventoryAgingConfig = serde_json::from_value(serde_json::json!({
+            "reserve_rates": { "180+": "1.0" }
+        }))
+        .unwrap();
+        assert_eq!(config.reserve_rate(AgingBucket::Over180), Decimal::ONE);
+        assert_eq!(config.reserve_rate(AgingBucket::UpTo90), Decimal::new(10, 2));
+    }
+}
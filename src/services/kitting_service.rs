@@ -0,0 +1,509 @@
+use chrono::Utc;
+use rust_decimal::Decimal;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, sync::Arc};
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::{
+    db::DbPool,
+    errors::ServiceError,
+    events::{Event, EventSender},
+    models::{
+        inspection_plan::InspectionSource,
+        inventory_items::{self, Entity as InventoryItem},
+        kit_work_order::{self, Entity as KitWorkOrder, KitOperation, KitWorkOrderStatus},
+        kit_work_order_component::{self, Entity as KitComponent},
+    },
+    money::round_currency,
+    services::quality_service::QUARANTINE_STATUS,
+    utils::pagination::PaginationParams,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewKitComponent {
+    #[validate(length(min = 1))]
+    pub sku: String,
+    #[validate(range(min = 1))]
+    pub quantity_per_kit: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_components"))]
+pub struct NewKitWorkOrder {
+    pub operation: KitOperation,
+    #[validate(length(min = 1, max = 64))]
+    pub kit_sku: String,
+    pub warehouse: i32,
+    #[validate(range(min = 1))]
+    pub quantity: i32,
+    #[validate(length(min = 1, max = 50))]
+    #[validate]
+    pub components: Vec<NewKitComponent>,
+    #[validate(custom = "crate::money::validate_non_negative")]
+    #[serde(default, with = "crate::money::amount")]
+    pub labor_cost_per_kit: Decimal,
+}
+
+fn validate_components(order: &NewKitWorkOrder) -> Result<(), ValidationError> {
+    let mut seen = HashSet::new();
+    for component in &order.components {
+        if component.sku == order.kit_sku {
+            return Err(ValidationError::new("kit_contains_itself"));
+        }
+        if !seen.insert(component.sku.as_str()) {
+            return Err(ValidationError::new("duplicate_component"));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct KitWorkOrderFilter {
+    pub status: Option<KitWorkOrderStatus>,
+    pub operation: Option<KitOperation>,
+    pub kit_sku: Option<String>,
+    pub warehouse: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KitWorkOrderWithComponents {
+    #[serde(flatten)]
+    pub work_order: kit_work_order::Model,
+    pub components: Vec<kit_work_order_component::Model>,
+}
+
+/// Takes `needed` units from stock rows, fullest first. Returns `(row index, units)` pairs,
+/// or the shortfall when the rows hold too little.
+pub fn draw(available: &[i32], needed: i32) -> Result<Vec<(usize, i32)>, i32> {
+    let mut order: Vec<usize> = (0..available.len()).collect();
+    order.sort_by(|&a, &b| available[b].cmp(&available[a]).then(a.cmp(&b)));
+    let mut left = needed;
+    let mut takes = Vec::new();
+    for i in order {
+        if left == 0 {
+            break;
+        }
+        let take = available[i].max(0).min(left);
+        if take > 0 {
+            takes.push((i, take));
+            left -= take;
+        }
+    }
+    if left > 0 {
+        return Err(left);
+    }
+    Ok(takes)
+}
+
+/// Average cost after adding `quantity` units at `cost` to `on_hand` units at `current`.
+pub fn blend_cost(on_hand: i32, current: Decimal, quantity: i32, cost: Decimal) -> Decimal {
+    let on_hand = on_hand.max(0);
+    let total = on_hand + quantity;
+    if total <= 0 {
+        return cost;
+    }
+    ((current * Decimal::from(on_hand) + cost * Decimal::from(quantity)) / Decimal::from(total)).round_dp(4)
+}
+
+/// Spreads `total` over `(units, current unit cost)` outputs in proportion to their current
+/// value, or evenly per unit when none has a cost. Returns the unit cost of each output.
+pub fn allocate_cost(total: Decimal, outputs: &[(i32, Decimal)]) -> Vec<Decimal> {
+    let value: Decimal = outputs.iter().map(|(units, cost)| Decimal::from(*units) * cost).sum();
+    if value.is_zero() {
+        let units: i32 = outputs.iter().map(|(units, _)| units).sum();
+        let each = if units > 0 { (total / Decimal::from(units)).round_dp(4) } else { Decimal::ZERO };
+        return vec![each; outputs.len()];
+    }
+    outputs.iter().map(|(_, cost)| (total * cost / value).round_dp(4)).collect()
+}
+
+fn row_cost(item: &inventory_items::Model) -> Decimal {
+    item.average_cost.or(item.unit_cost).unwrap_or(Decimal::ZERO)
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Kit work order query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Sellable stock rows of `sku` in `warehouse`; quarantined rows are left alone.
+async fn stock_rows(
+    txn: &DatabaseTransaction,
+    sku: &str,
+    warehouse: i32,
+) -> Result<Vec<inventory_items::Model>, ServiceError> {
+    InventoryItem::find()
+        .filter(inventory_items::Column::Sku.eq(sku))
+        .filter(inventory_items::Column::Warehouse.eq(warehouse))
+        .filter(
+            Condition::any()
+                .add(inventory_items::Column::QualityStatus.is_null())
+                .add(inventory_items::Column::QualityStatus.ne(QUARANTINE_STATUS)),
+        )
+        .order_by_asc(inventory_items::Column::Id)
+        .lock_exclusive()
+        .all(txn)
+        .await
+        .map_err(db_error)
+}
+
+/// Takes `quantity` units of `sku` off its stock rows. Returns the cost of what was taken.
+async fn consume(
+    txn: &DatabaseTransaction,
+    sku: &str,
+    warehouse: i32,
+    quantity: i32,
+) -> Result<Decimal, ServiceError> {
+    let rows = stock_rows(txn, sku, warehouse).await?;
+    let available: Vec<i32> = rows.iter().map(|r| r.available).collect();
+    let takes = draw(&available, quantity).map_err(|short| {
+        ServiceError::ValidationError(format!("Short {} units of {} in warehouse {}", short, sku, warehouse))
+    })?;
+    let now = Utc::now();
+    let mut cost = Decimal::ZERO;
+    for (i, take) in takes {
+        let row = &rows[i];
+        cost += row_cost(row) * Decimal::from(take);
+        let mut active: inventory_items::ActiveModel = row.clone().into();
+        active.available = Set(row.available - take);
+        active.last_movement_date = Set(Some(now));
+        active.update(txn).await.map_err(db_error)?;
+    }
+    Ok(round_currency(cost))
+}
+
+/// Light assembly: kitting components into kit SKUs and de-kitting them again. Inventory is
+/// converted in one transaction when the work order completes.
+pub struct KittingService {
+    db_pool: Arc<DbPool>,
+    events: EventSender,
+}
+
+impl KittingService {
+    pub fn new(db_pool: Arc<DbPool>, events: EventSender) -> Self {
+        Self { db_pool, events }
+    }
+
+    #[instrument(skip(self, input), fields(kit_sku = %input.kit_sku))]
+    pub async fn create(&self, input: NewKitWorkOrder, actor: &str) -> Result<KitWorkOrderWithComponents, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid kit work order: {}", e)))?;
+        // Every SKU is produced in one direction or the other, so each needs a stock row
+        let mut skus: Vec<String> = input.components.iter().map(|c| c.sku.clone()).collect();
+        skus.push(input.kit_sku.clone());
+        let stocked: HashSet<String> = InventoryItem::find()
+            .filter(inventory_items::Column::Sku.is_in(skus.clone()))
+            .filter(inventory_items::Column::Warehouse.eq(input.warehouse))
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(|item| item.sku)
+            .collect();
+        if let Some(missing) = skus.iter().find(|sku| !stocked.contains(*sku)) {
+            return Err(ServiceError::ValidationError(format!(
+                "{} is not stocked in warehouse {}",
+                missing, input.warehouse
+            )));
+        }
+
+        let now = Utc::now();
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let work_order = kit_work_order::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            number: Set(format!("KIT-{}", Uuid::new_v4().simple())),
+            operation: Set(input.operation),
+            kit_sku: Set(input.kit_sku),
+            warehouse: Set(input.warehouse),
+            quantity: Set(input.quantity),
+            labor_cost_per_kit: Set(input.labor_cost_per_kit),
+            material_cost: Set(None),
+            labor_cost: Set(None),
+            kit_unit_cost: Set(None),
+            status: Set(KitWorkOrderStatus::Open),
+            created_by: Set(actor.to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+            started_at: Set(None),
+            completed_at: Set(None),
+        }
+        .insert(&txn)
+        .await
+        .map_err(db_error)?;
+        let mut components = Vec::with_capacity(input.components.len());
+        for component in input.components {
+            let component = kit_work_order_component::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                work_order_id: Set(work_order.id),
+                component_sku: Set(component.sku),
+                quantity_per_kit: Set(component.quantity_per_kit),
+                unit_cost: Set(None),
+            }
+            .insert(&txn)
+            .await
+            .map_err(db_error)?;
+            components.push(component);
+        }
+        txn.commit().await.map_err(db_error)?;
+        Ok(KitWorkOrderWithComponents { work_order, components })
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<KitWorkOrderWithComponents, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let work_order = KitWorkOrder::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Kit work order not found: {}", id)))?;
+        let components = KitComponent::find()
+            .filter(kit_work_order_component::Column::WorkOrderId.eq(id))
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        Ok(KitWorkOrderWithComponents { work_order, components })
+    }
+
+    pub async fn list(
+        &self,
+        filter: KitWorkOrderFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<kit_work_order::Model>, u64), ServiceError> {
+        let mut query = KitWorkOrder::find();
+        if let Some(status) = filter.status {
+            query = query.filter(kit_work_order::Column::Status.eq(status));
+        }
+        if let Some(operation) = filter.operation {
+            query = query.filter(kit_work_order::Column::Operation.eq(operation));
+        }
+        if let Some(kit_sku) = filter.kit_sku {
+            query = query.filter(kit_work_order::Column::KitSku.eq(kit_sku));
+        }
+        if let Some(warehouse) = filter.warehouse {
+            query = query.filter(kit_work_order::Column::Warehouse.eq(warehouse));
+        }
+        let paginator = query
+            .order_by_desc(kit_work_order::Column::CreatedAt)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let work_orders = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((work_orders, total))
+    }
+
+    async fn locked(&self, txn: &DatabaseTransaction, id: Uuid) -> Result<kit_work_order::Model, ServiceError> {
+        KitWorkOrder::find_by_id(id)
+            .lock_exclusive()
+            .one(txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Kit work order not found: {}", id)))
+    }
+
+    /// Moves an open work order to the bench once its inputs are picked.
+    pub async fn start(&self, id: Uuid) -> Result<kit_work_order::Model, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let work_order = self.locked(&txn, id).await?;
+        if work_order.status != KitWorkOrderStatus::Open {
+            return Err(ServiceError::ValidationError(format!(
+                "A {:?} kit work order cannot be started",
+                work_order.status
+            )));
+        }
+        let now = Utc::now();
+        let mut active: kit_work_order::ActiveModel = work_order.into();
+        active.status = Set(KitWorkOrderStatus::InProgress);
+        active.started_at = Set(Some(now));
+        active.updated_at = Set(now);
+        let work_order = active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+        Ok(work_order)
+    }
+
+    pub async fn cancel(&self, id: Uuid) -> Result<kit_work_order::Model, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let work_order = self.locked(&txn, id).await?;
+        if !matches!(work_order.status, KitWorkOrderStatus::Open | KitWorkOrderStatus::InProgress) {
+            return Err(ServiceError::ValidationError(format!(
+                "A {:?} kit work order cannot be cancelled",
+                work_order.status
+            )));
+        }
+        let mut active: kit_work_order::ActiveModel = work_order.into();
+        active.status = Set(KitWorkOrderStatus::Cancelled);
+        active.updated_at = Set(Utc::now());
+        let work_order = active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+        Ok(work_order)
+    }
+
+    /// Converts the inventory and rolls up cost. Kitting consumes components at their
+    /// current cost and adds kits at material plus labor per kit; de-kitting spreads the
+    /// consumed kits' cost plus labor over the components by their current value.
+    #[instrument(skip(self))]
+    pub async fn complete(&self, id: Uuid) -> Result<KitWorkOrderWithComponents, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let work_order = self.locked(&txn, id).await?;
+        if work_order.status != KitWorkOrderStatus::InProgress {
+            return Err(ServiceError::ValidationError(format!(
+                "A {:?} kit work order cannot be completed",
+                work_order.status
+            )));
+        }
+        let components = KitComponent::find()
+            .filter(kit_work_order_component::Column::WorkOrderId.eq(id))
+            .all(&txn)
+            .await
+            .map_err(db_error)?;
+        let (quantity, warehouse) = (work_order.quantity, work_order.warehouse);
+        let labor = round_currency(work_order.labor_cost_per_kit * Decimal::from(quantity));
+
+        let mut unit_costs = Vec::with_capacity(components.len());
+        let mut lots = Vec::new();
+        let (material, kit_unit_cost) = match work_order.operation {
+            KitOperation::Kit => {
+                let mut material = Decimal::ZERO;
+                for component in &components {
+                    let units = component.quantity_per_kit * quantity;
+                    let cost = consume(&txn, &component.component_sku, warehouse, units).await?;
+                    unit_costs.push((cost / Decimal::from(units)).round_dp(4));
+                    material += cost;
+                }
+                let unit_cost = ((material + labor) / Decimal::from(quantity)).round_dp(4);
+                let row = self.receive(&txn, &work_order.kit_sku, warehouse, quantity, unit_cost).await?;
+                lots.push((work_order.kit_sku.clone(), row, quantity));
+                (material, Some(unit_cost))
+            }
+            KitOperation::Dekit => {
+                let material = consume(&txn, &work_order.kit_sku, warehouse, quantity).await?;
+                let mut outputs = Vec::with_capacity(components.len());
+                for component in &components {
+                    let rows = stock_rows(&txn, &component.component_sku, warehouse).await?;
+                    let current = rows.first().map(row_cost).unwrap_or(Decimal::ZERO);
+                    outputs.push((component.quantity_per_kit * quantity, current));
+                }
+                unit_costs = allocate_cost(material + labor, &outputs);
+                for ((component, (units, _)), cost) in components.iter().zip(&outputs).zip(&unit_costs) {
+                    let row = self.receive(&txn, &component.component_sku, warehouse, *units, *cost).await?;
+                    lots.push((component.component_sku.clone(), row, *units));
+                }
+                (material, None)
+            }
+        };
+
+        let mut updated = Vec::with_capacity(components.len());
+        for (component, unit_cost) in components.into_iter().zip(unit_costs) {
+            let mut active: kit_work_order_component::ActiveModel = component.into();
+            active.unit_cost = Set(Some(unit_cost));
+            updated.push(active.update(&txn).await.map_err(db_error)?);
+        }
+        let now = Utc::now();
+        let mut active: kit_work_order::ActiveModel = work_order.into();
+        active.status = Set(KitWorkOrderStatus::Completed);
+        active.material_cost = Set(Some(material));
+        active.labor_cost = Set(Some(labor));
+        active.kit_unit_cost = Set(kit_unit_cost);
+        active.completed_at = Set(Some(now));
+        active.updated_at = Set(now);
+        let work_order = active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+
+        info!(
+            work_order_id = %work_order.id,
+            operation = ?work_order.operation,
+            kit_sku = %work_order.kit_sku,
+            quantity = work_order.quantity,
+            "Kit work order completed"
+        );
+        let _ = self.events.send(Event::KitWorkOrderCompleted {
+            work_order_id: work_order.id,
+            kit_sku: work_order.kit_sku.clone(),
+            kitted: work_order.operation == KitOperation::Kit,
+            quantity: work_order.quantity,
+            labor_cost: labor,
+        });
+        for (sku, inventory_item_id, quantity) in lots {
+            let _ = self.events.send(Event::LotReceived {
+                source: InspectionSource::WorkOrderCompletion,
+                reference: work_order.id.to_string(),
+                sku,
+                warehouse,
+                inventory_item_id,
+                quantity,
+            });
+        }
+        Ok(KitWorkOrderWithComponents { work_order, components: updated })
+    }
+
+    /// Adds produced units to the first stock row of `sku`, blending its average cost.
+    /// Returns the row's id.
+    async fn receive(
+        &self,
+        txn: &DatabaseTransaction,
+        sku: &str,
+        warehouse: i32,
+        quantity: i32,
+        unit_cost: Decimal,
+    ) -> Result<String, ServiceError> {
+        let row = stock_rows(txn, sku, warehouse)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| ServiceError::NotFound(format!("{} is not stocked in warehouse {}", sku, warehouse)))?;
+        let average = blend_cost(row.available, row_cost(&row), quantity, unit_cost);
+        let available = row.available + quantity;
+        let mut active: inventory_items::ActiveModel = row.into();
+        active.available = Set(available);
+        active.average_cost = Set(Some(average));
+        active.last_movement_date = Set(Some(Utc::now()));
+        let row = active.update(txn).await.map_err(db_error)?;
+        Ok(row.id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_draw_takes_fullest_rows_first() {
+        assert_eq!(draw(&[3, 10, 4], 12), Ok(vec![(1, 10), (2, 2)]));
+        assert_eq!(draw(&[3, 0], 3), Ok(vec![(0, 3)]));
+        assert_eq!(draw(&[3, 4], 9), Err(2));
+    }
+
+    #[test]
+    fn test_blend_cost_weights_by_units() {
+        assert_eq!(blend_cost(10, dec!(5), 10, dec!(7)), dec!(6));
+        assert_eq!(blend_cost(0, dec!(5), 4, dec!(7.25)), dec!(7.25));
+    }
+
+    #[test]
+    fn test_allocate_cost_by_value_then_units() {
+        // 2 x $10 and 4 x $5 share $60 equally by value
+        assert_eq!(allocate_cost(dec!(60), &[(2, dec!(10)), (4, dec!(5))]), vec![dec!(15), dec!(7.5)]);
+        assert_eq!(allocate_cost(dec!(30), &[(2, dec!(0)), (4, dec!(0))]), vec![dec!(5), dec!(5)]);
+    }
+
+    #[test]
+    fn test_kit_cannot_contain_itself_or_repeat_components() {
+        let order = |skus: &[&str]| NewKitWorkOrder {
+            operation: KitOperation::Kit,
+            kit_sku: "GIFT-BOX".to_string(),
+            warehouse: 1,
+            quantity: 5,
+            components: skus
+                .iter()
+                .map(|sku| NewKitComponent { sku: sku.to_string(), quantity_per_kit: 1 })
+                .collect(),
+            labor_cost_per_kit: dec!(0.50),
+        };
+        assert!(order(&["MUG", "TEA"]).validate().is_ok());
+        assert!(order(&["MUG", "GIFT-BOX"]).validate().is_err());
+        assert!(order(&["MUG", "MUG"]).validate().is_err());
+    }
+}
@@ -0,0 +1,269 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use sea_orm::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{error, info, instrument, warn};
+use uuid::Uuid;
+
+use crate::{
+    calendar::Calendars,
+    carriers::{manifest_document, CarrierRegistry, ManifestLine, ManifestRequest},
+    db::{dialect, DbPool},
+    errors::ServiceError,
+    hazmat::DangerousGood,
+    models::{
+        carrier_manifest::{self, Entity as CarrierManifest, ManifestStatus},
+        shipment::{self, Entity as Shipment, ShipmentStatus, ShippingCarrier},
+        shipment_dangerous_good::{self, Entity as ShipmentDangerousGood},
+    },
+    utils::pagination::PaginationParams,
+};
+
+/// Closes the day for some or all carriers.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CloseRequest {
+    /// Day being closed; defaults to today in the warehouse's time zone. Unmanifested
+    /// shipments from earlier days are included.
+    pub date: Option<NaiveDate>,
+    /// Carriers to close; all when empty.
+    #[serde(default)]
+    pub carriers: Vec<ShippingCarrier>,
+    /// Warehouse whose calendar sets the day and its cutoff; the default calendar when
+    /// unset.
+    pub warehouse: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ManifestFilter {
+    pub carrier: Option<ShippingCarrier>,
+    pub date: Option<NaiveDate>,
+    pub status: Option<ManifestStatus>,
+}
+
+/// Shipments go on a manifest once booked (they have a tracking number) and until they
+/// are picked up.
+fn manifestable() -> Condition {
+    Condition::all()
+        .add(shipment::Column::ManifestId.is_null())
+        .add(shipment::Column::Status.is_in([ShipmentStatus::Processing, ShipmentStatus::Shipped]))
+        .add(shipment::Column::TrackingNumber.ne(""))
+}
+
+/// One manifest line per shipment, with the UN descriptions of the dangerous goods
+/// declared in it.
+pub fn manifest_lines(
+    shipments: &[shipment::Model],
+    dangerous_goods: &HashMap<i32, Vec<DangerousGood>>,
+) -> Vec<ManifestLine> {
+    shipments
+        .iter()
+        .map(|s| ManifestLine {
+            tracking_number: s.tracking_number.clone(),
+            order_id: s.order_id,
+            service: s.shipping_method.clone(),
+            destination: s.shipping_address.clone(),
+            dangerous_goods: dangerous_goods
+                .get(&s.id)
+                .map(|goods| goods.iter().map(DangerousGood::description).collect())
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Manifest query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// End-of-day carrier close: one manifest per carrier covering its booked, unmanifested
+/// shipments, submitted through the carrier's adapter and kept for driver pickup.
+pub struct ManifestService {
+    db_pool: Arc<DbPool>,
+    carriers: Arc<CarrierRegistry>,
+    calendars: Arc<Calendars>,
+}
+
+impl ManifestService {
+    pub fn new(db_pool: Arc<DbPool>, carriers: Arc<CarrierRegistry>, calendars: Arc<Calendars>) -> Self {
+        Self { db_pool, carriers, calendars }
+    }
+
+    /// Returns the manifests created, including failed submissions. Carriers with nothing
+    /// to ship are skipped. Shipments booked after the day's same-day cutoff wait for the
+    /// next close.
+    #[instrument(skip(self))]
+    pub async fn close(&self, request: CloseRequest, actor: &str) -> Result<Vec<carrier_manifest::Model>, ServiceError> {
+        let calendar = self.calendars.for_warehouse(request.warehouse.as_deref());
+        let date = request.date.unwrap_or_else(|| calendar.local_date(Utc::now()));
+        let cutoff = calendar.cutoff_at(date);
+        let carriers = if request.carriers.is_empty() {
+            ShippingCarrier::iter().collect()
+        } else {
+            request.carriers
+        };
+        let mut manifests = Vec::new();
+        for carrier in carriers {
+            if let Some(manifest) = self.close_carrier(carrier, date, cutoff, actor).await? {
+                manifests.push(manifest);
+            }
+        }
+        Ok(manifests)
+    }
+
+    async fn close_carrier(
+        &self,
+        carrier: ShippingCarrier,
+        date: NaiveDate,
+        cutoff: DateTime<Utc>,
+        actor: &str,
+    ) -> Result<Option<carrier_manifest::Model>, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        // One close per carrier at a time, so no shipment lands on two manifests
+        dialect::advisory_xact_lock(&txn, &format!("carrier_manifest:{:?}", carrier))
+            .await
+            .map_err(db_error)?;
+        let shipments = Shipment::find()
+            .filter(shipment::Column::Carrier.eq(carrier))
+            .filter(manifestable())
+            .filter(shipment::Column::CreatedAt.lt(cutoff))
+            .order_by_asc(shipment::Column::Id)
+            .all(&txn)
+            .await
+            .map_err(db_error)?;
+        if shipments.is_empty() {
+            return Ok(None);
+        }
+
+        let mut dangerous_goods: HashMap<i32, Vec<DangerousGood>> = HashMap::new();
+        for line in ShipmentDangerousGood::find()
+            .filter(shipment_dangerous_good::Column::ShipmentId.is_in(shipments.iter().map(|s| s.id)))
+            .order_by_asc(shipment_dangerous_good::Column::CreatedAt)
+            .all(&txn)
+            .await
+            .map_err(db_error)?
+        {
+            dangerous_goods.entry(line.shipment_id).or_default().push(DangerousGood::from(&line));
+        }
+        let lines = manifest_lines(&shipments, &dangerous_goods);
+        let (adapter, account_number) = self.carriers.manifest_adapter(carrier);
+        let request = ManifestRequest { carrier, date, account_number: account_number.as_deref(), lines: &lines };
+        let (status, reference, failure) = match adapter.submit(&request).await {
+            Ok(receipt) => (ManifestStatus::Submitted, receipt.reference, None),
+            Err(e) => {
+                warn!(?carrier, "Manifest submission failed: {}", e);
+                (ManifestStatus::Failed, None, Some(e.to_string()))
+            }
+        };
+
+        let manifest = carrier_manifest::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            carrier: Set(carrier),
+            manifest_date: Set(date),
+            status: Set(status),
+            document: Set(manifest_document(&request, reference.as_deref())),
+            reference: Set(reference),
+            adapter: Set(adapter.name().to_string()),
+            shipment_count: Set(lines.len() as i32),
+            error: Set(failure),
+            created_by: Set(actor.to_string()),
+            created_at: Set(Utc::now()),
+        }
+        .insert(&txn)
+        .await
+        .map_err(db_error)?;
+        if status == ManifestStatus::Submitted {
+            Shipment::update_many()
+                .col_expr(shipment::Column::ManifestId, Expr::value(manifest.id))
+                .filter(shipment::Column::Id.is_in(shipments.iter().map(|s| s.id)))
+                .exec(&txn)
+                .await
+                .map_err(db_error)?;
+        }
+        txn.commit().await.map_err(db_error)?;
+        info!(manifest_id = %manifest.id, ?carrier, shipments = lines.len(), status = ?status, "Carrier manifest closed");
+        Ok(Some(manifest))
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<carrier_manifest::Model, ServiceError> {
+        CarrierManifest::find_by_id(id)
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Manifest {} not found", id)))
+    }
+
+    pub async fn list(
+        &self,
+        filter: ManifestFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<carrier_manifest::Model>, u64), ServiceError> {
+        let mut query = CarrierManifest::find();
+        if let Some(carrier) = filter.carrier {
+            query = query.filter(carrier_manifest::Column::Carrier.eq(carrier));
+        }
+        if let Some(date) = filter.date {
+            query = query.filter(carrier_manifest::Column::ManifestDate.eq(date));
+        }
+        if let Some(status) = filter.status {
+            query = query.filter(carrier_manifest::Column::Status.eq(status));
+        }
+        let paginator = query
+            .order_by_desc(carrier_manifest::Column::CreatedAt)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let items = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((items, total))
+    }
+
+    /// Shipments handed over on a manifest.
+    pub async fn shipments(&self, id: Uuid) -> Result<Vec<shipment::Model>, ServiceError> {
+        self.get(id).await?;
+        Shipment::find()
+            .filter(shipment::Column::ManifestId.eq(id))
+            .order_by_asc(shipment::Column::Id)
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_lines_follow_shipments() {
+        let now = Utc::now();
+        let shipment = shipment::Model {
+            id: 7,
+            order_id: 1001,
+            tracking_number: "1Z999AA10123456784".to_string(),
+            carrier: ShippingCarrier::UPS,
+            status: ShipmentStatus::Processing,
+            shipping_address: "1 Market St, San Francisco".to_string(),
+            shipping_method: "Ground".to_string(),
+            shipped_at: None,
+            estimated_delivery: None,
+            delivered_at: None,
+            manifest_id: None,
+            created_at: now.into(),
+            updated_at: now.into(),
+        };
+        let lines = manifest_lines(&[shipment], &HashMap::new());
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].dangerous_goods.is_empty());
+        assert_eq!(lines[0].tracking_number, "1Z999AA10123456784");
+        assert_eq!((lines[0].order_id, lines[0].service.as_str()), (1001, "Ground"));
+    }
+
+    #[test]
+    fn test_close_request_defaults_to_all_carriers() {
+        let request: CloseRequest = serde_json::from_value(serde_json::json!({})).unwrap();
+        assert!(request.date.is_none() && request.carriers.is_empty() && request.warehouse.is_none());
+        let request: CloseRequest =
+            serde_json::from_value(serde_json::json!({ "date": "2026-10-16", "carriers": ["FedEx"] })).unwrap();
+        assert_eq!(request.carriers, vec![ShippingCarrier::FedEx]);
+    }
+}
@@ -1,7 +1,41 @@
 pub mod order_service;
+pub mod order_timeline;
 pub mod inventory_service;
 pub mod return_service;
 pub mod warranty_service;
 pub mod shipment_service;
 pub mod work_order_service;
-pub mod category_service;
\ No newline at end of file
+pub mod category_service;
+pub mod bundle_service;
+pub mod product_listing_service;
+pub mod return_triage;
+pub mod return_fraud;
+pub mod inventory_aging;
+pub mod work_order_operations;
+pub mod quality_service;
+pub mod ncr_service;
+pub mod case_service;
+pub mod supplier_scorecard;
+pub mod requisition_service;
+pub mod credit_memo_service;
+pub mod write_off_service;
+pub mod cross_dock_service;
+pub mod kitting_service;
+pub mod eco_service;
+pub mod forecast_service;
+pub mod capacity_service;
+pub mod equipment_service;
+pub mod scan_service;
+pub mod print_service;
+pub mod duplicate_orders;
+pub mod shipping_zone_service;
+pub mod manifest_service;
+pub mod hazmat_service;
+pub mod customs_service;
+pub mod return_disposition_service;
+pub mod dropship_service;
+pub mod pos_service;
+pub mod payment_capture;
+pub mod payment_vault;
+pub mod sourcing;
+pub mod routing_rules;
@@ -0,0 +1,532 @@
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
+use tracing::{error, instrument};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db::DbPool,
+    errors::ServiceError,
+    models::{
+        corrective_action::{self, ActionKind, ActionStatus, Entity as CorrectiveAction},
+        non_conformance::{self, Entity as NonConformance, NcrSource, NcrStatus, Severity},
+        quality_inspection::Entity as QualityInspection,
+        return_entity::Entity as Return,
+        warranty::Entity as Warranty,
+    },
+    utils::pagination::PaginationParams,
+};
+
+/// Longest window accepted by the recurrence report.
+pub const MAX_RECURRENCE_DAYS: i64 = 366;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewNcr {
+    pub source: NcrSource,
+    #[validate(length(min = 1))]
+    pub source_id: String,
+    /// Defaults to the inspected SKU for NCRs raised from an inspection.
+    pub sku: Option<String>,
+    pub supplier_id: Option<String>,
+    #[validate(length(min = 1, max = 4000))]
+    pub description: String,
+    pub severity: Severity,
+    pub assignee: Option<String>,
+    pub due_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewAction {
+    pub kind: ActionKind,
+    #[validate(length(min = 1, max = 4000))]
+    pub description: String,
+    pub assignee: Option<String>,
+    pub due_date: NaiveDate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NcrTransition {
+    pub status: NcrStatus,
+    /// Required to leave investigation unless already recorded.
+    pub root_cause: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionTransition {
+    pub status: ActionStatus,
+    /// Required to verify an action.
+    pub verification_notes: Option<String>,
+}
+
+/// Fields left out are unchanged.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Assignment {
+    pub assignee: Option<String>,
+    pub due_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NcrFilter {
+    pub status: Option<NcrStatus>,
+    pub source: Option<NcrSource>,
+    pub sku: Option<String>,
+    pub supplier_id: Option<String>,
+    pub assignee: Option<String>,
+    /// Only NCRs still open past their due date.
+    #[serde(default)]
+    pub overdue: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NcrWithActions {
+    #[serde(flatten)]
+    pub ncr: non_conformance::Model,
+    pub actions: Vec<corrective_action::Model>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecurrenceKey {
+    Sku,
+    Supplier,
+}
+
+/// How often NCRs were raised against one SKU or supplier.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Recurrence {
+    pub key: String,
+    pub ncrs: u64,
+    /// Not closed yet.
+    pub open: u64,
+    pub critical: u64,
+    pub first_raised_at: DateTime<Utc>,
+    pub last_raised_at: DateTime<Utc>,
+}
+
+/// Allowed NCR workflow moves: open → investigating → action required → closed, with
+/// investigations that need no action closing directly. Only unstarted work can be voided.
+pub fn ncr_transition_allowed(from: NcrStatus, to: NcrStatus) -> bool {
+    use NcrStatus::*;
+    matches!(
+        (from, to),
+        (Open, Investigating)
+            | (Investigating, ActionRequired)
+            | (Investigating, Closed)
+            | (ActionRequired, Closed)
+            | (Open, Voided)
+            | (Investigating, Voided)
+    )
+}
+
+/// Allowed CAPA moves. An implemented action that fails verification goes back in progress.
+pub fn action_transition_allowed(from: ActionStatus, to: ActionStatus) -> bool {
+    use ActionStatus::*;
+    matches!(
+        (from, to),
+        (Open, InProgress)
+            | (InProgress, Implemented)
+            | (Implemented, Verified)
+            | (Implemented, InProgress)
+            | (Open, Cancelled)
+            | (InProgress, Cancelled)
+    )
+}
+
+/// An NCR that required action closes only when at least one action was verified and
+/// every other one is verified or cancelled.
+pub fn actions_allow_close(actions: &[corrective_action::Model]) -> bool {
+    actions.iter().any(|a| a.status == ActionStatus::Verified)
+        && actions
+            .iter()
+            .all(|a| matches!(a.status, ActionStatus::Verified | ActionStatus::Cancelled))
+}
+
+/// Groups NCRs by SKU or supplier, skipping voided NCRs and those without the key, and
+/// keeps groups with at least `min_count` NCRs, most frequent first.
+pub fn recurrence(ncrs: &[non_conformance::Model], by: RecurrenceKey, min_count: u64) -> Vec<Recurrence> {
+    let mut groups: BTreeMap<&str, Recurrence> = BTreeMap::new();
+    for ncr in ncrs.iter().filter(|n| n.status != NcrStatus::Voided) {
+        let key = match by {
+            RecurrenceKey::Sku => ncr.sku.as_deref(),
+            RecurrenceKey::Supplier => ncr.supplier_id.as_deref(),
+        };
+        let Some(key) = key else { continue };
+        let group = groups.entry(key).or_insert_with(|| Recurrence {
+            key: key.to_string(),
+            ncrs: 0,
+            open: 0,
+            critical: 0,
+            first_raised_at: ncr.created_at,
+            last_raised_at: ncr.created_at,
+        });
+        group.ncrs += 1;
+        group.open += u64::from(ncr.status != NcrStatus::Closed);
+        group.critical += u64::from(ncr.severity == Severity::Critical);
+        group.first_raised_at = group.first_raised_at.min(ncr.created_at);
+        group.last_raised_at = group.last_raised_at.max(ncr.created_at);
+    }
+    let mut rows: Vec<Recurrence> = groups.into_values().filter(|r| r.ncrs >= min_count).collect();
+    rows.sort_by(|a, b| b.ncrs.cmp(&a.ncrs).then_with(|| a.key.cmp(&b.key)));
+    rows
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Non-conformance query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+fn not_allowed<T: std::fmt::Debug>(from: T, to: T) -> ServiceError {
+    ServiceError::ValidationError(format!("Cannot move from {:?} to {:?}", from, to))
+}
+
+/// Non-conformance reports and their corrective and preventive actions.
+pub struct NonConformanceService {
+    db_pool: Arc<DbPool>,
+}
+
+impl NonConformanceService {
+    pub fn new(db_pool: Arc<DbPool>) -> Self {
+        Self { db_pool }
+    }
+
+    async fn find_ncr(&self, id: Uuid) -> Result<non_conformance::Model, ServiceError> {
+        NonConformance::find_by_id(id)
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("NCR not found: {}", id)))
+    }
+
+    async fn actions(&self, ncr_id: Uuid) -> Result<Vec<corrective_action::Model>, ServiceError> {
+        CorrectiveAction::find()
+            .filter(corrective_action::Column::NcrId.eq(ncr_id))
+            .order_by_asc(corrective_action::Column::CreatedAt)
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)
+    }
+
+    /// Checks the source record exists; returns the SKU it implies, if any.
+    async fn resolve_source(&self, source: NcrSource, source_id: &str) -> Result<Option<String>, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let invalid = || ServiceError::ValidationError(format!("Invalid {:?} id: {}", source, source_id));
+        let missing = || ServiceError::NotFound(format!("{:?} not found: {}", source, source_id));
+        match source {
+            NcrSource::Inspection => {
+                let id = Uuid::parse_str(source_id).map_err(|_| invalid())?;
+                let inspection = QualityInspection::find_by_id(id).one(db).await.map_err(db_error)?.ok_or_else(missing)?;
+                Ok(Some(inspection.sku))
+            }
+            NcrSource::Return => {
+                let id = Uuid::parse_str(source_id).map_err(|_| invalid())?;
+                Return::find_by_id(id).one(db).await.map_err(db_error)?.ok_or_else(missing)?;
+                Ok(None)
+            }
+            NcrSource::WarrantyClaim => {
+                let id: i32 = source_id.parse().map_err(|_| invalid())?;
+                Warranty::find_by_id(id).one(db).await.map_err(db_error)?.ok_or_else(missing)?;
+                Ok(None)
+            }
+        }
+    }
+
+    #[instrument(skip(self, input, created_by), fields(source_id = %input.source_id))]
+    pub async fn create(&self, input: NewNcr, created_by: &str) -> Result<non_conformance::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid NCR: {}", e)))?;
+        let source_sku = self.resolve_source(input.source, &input.source_id).await?;
+        let now = Utc::now();
+        non_conformance::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            source: Set(input.source),
+            source_id: Set(input.source_id),
+            sku: Set(input.sku.or(source_sku)),
+            supplier_id: Set(input.supplier_id),
+            description: Set(input.description),
+            severity: Set(input.severity),
+            status: Set(NcrStatus::Open),
+            assignee: Set(input.assignee),
+            due_date: Set(input.due_date),
+            root_cause: Set(None),
+            created_by: Set(created_by.to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+            closed_at: Set(None),
+        }
+        .insert(self.db_pool.as_ref())
+        .await
+        .map_err(db_error)
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<NcrWithActions, ServiceError> {
+        let ncr = self.find_ncr(id).await?;
+        let actions = self.actions(id).await?;
+        Ok(NcrWithActions { ncr, actions })
+    }
+
+    pub async fn list(
+        &self,
+        filter: NcrFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<non_conformance::Model>, u64), ServiceError> {
+        let mut query = NonConformance::find();
+        if let Some(status) = filter.status {
+            query = query.filter(non_conformance::Column::Status.eq(status));
+        }
+        if let Some(source) = filter.source {
+            query = query.filter(non_conformance::Column::Source.eq(source));
+        }
+        if let Some(sku) = filter.sku {
+            query = query.filter(non_conformance::Column::Sku.eq(sku));
+        }
+        if let Some(supplier_id) = filter.supplier_id {
+            query = query.filter(non_conformance::Column::SupplierId.eq(supplier_id));
+        }
+        if let Some(assignee) = filter.assignee {
+            query = query.filter(non_conformance::Column::Assignee.eq(assignee));
+        }
+        if filter.overdue {
+            query = query
+                .filter(non_conformance::Column::DueDate.lt(Utc::now().date_naive()))
+                .filter(non_conformance::Column::Status.is_not_in([NcrStatus::Closed, NcrStatus::Voided]));
+        }
+        let paginator = query
+            .order_by_desc(non_conformance::Column::CreatedAt)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let ncrs = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((ncrs, total))
+    }
+
+    pub async fn assign(&self, id: Uuid, assignment: Assignment) -> Result<non_conformance::Model, ServiceError> {
+        let ncr = self.find_ncr(id).await?;
+        let mut active: non_conformance::ActiveModel = ncr.into();
+        if let Some(assignee) = assignment.assignee {
+            active.assignee = Set(Some(assignee));
+        }
+        if let Some(due_date) = assignment.due_date {
+            active.due_date = Set(Some(due_date));
+        }
+        active.updated_at = Set(Utc::now());
+        active.update(self.db_pool.as_ref()).await.map_err(db_error)
+    }
+
+    #[instrument(skip(self, transition), fields(ncr_id = %id))]
+    pub async fn transition(&self, id: Uuid, transition: NcrTransition) -> Result<non_conformance::Model, ServiceError> {
+        let ncr = self.find_ncr(id).await?;
+        if !ncr_transition_allowed(ncr.status, transition.status) {
+            return Err(not_allowed(ncr.status, transition.status));
+        }
+        let root_cause = transition
+            .root_cause
+            .filter(|r| !r.trim().is_empty())
+            .or_else(|| ncr.root_cause.clone());
+        let leaves_investigation = ncr.status == NcrStatus::Investigating && transition.status != NcrStatus::Voided;
+        if leaves_investigation && root_cause.is_none() {
+            return Err(ServiceError::ValidationError("Record a root cause before leaving investigation".to_string()));
+        }
+        if ncr.status == NcrStatus::ActionRequired && !actions_allow_close(&self.actions(id).await?) {
+            return Err(ServiceError::ValidationError(
+                "Every corrective action must be verified or cancelled, with at least one verified".to_string(),
+            ));
+        }
+
+        let now = Utc::now();
+        let status = transition.status;
+        let mut active: non_conformance::ActiveModel = ncr.into();
+        active.status = Set(status);
+        active.root_cause = Set(root_cause);
+        active.updated_at = Set(now);
+        if matches!(status, NcrStatus::Closed | NcrStatus::Voided) {
+            active.closed_at = Set(Some(now));
+        }
+        active.update(self.db_pool.as_ref()).await.map_err(db_error)
+    }
+
+    /// Adds a corrective or preventive action to an NCR under investigation or awaiting action.
+    pub async fn add_action(&self, ncr_id: Uuid, input: NewAction) -> Result<corrective_action::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid action: {}", e)))?;
+        let ncr = self.find_ncr(ncr_id).await?;
+        if !matches!(ncr.status, NcrStatus::Investigating | NcrStatus::ActionRequired) {
+            return Err(ServiceError::ValidationError(format!(
+                "Actions can only be added while investigating or awaiting action, not {:?}",
+                ncr.status
+            )));
+        }
+        let now = Utc::now();
+        corrective_action::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            ncr_id: Set(ncr_id),
+            kind: Set(input.kind),
+            description: Set(input.description),
+            status: Set(ActionStatus::Open),
+            assignee: Set(input.assignee),
+            due_date: Set(input.due_date),
+            verification_notes: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+            completed_at: Set(None),
+        }
+        .insert(self.db_pool.as_ref())
+        .await
+        .map_err(db_error)
+    }
+
+    async fn find_action(&self, ncr_id: Uuid, action_id: Uuid) -> Result<corrective_action::Model, ServiceError> {
+        CorrectiveAction::find_by_id(action_id)
+            .filter(corrective_action::Column::NcrId.eq(ncr_id))
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Corrective action not found: {}", action_id)))
+    }
+
+    pub async fn assign_action(
+        &self,
+        ncr_id: Uuid,
+        action_id: Uuid,
+        assignment: Assignment,
+    ) -> Result<corrective_action::Model, ServiceError> {
+        let action = self.find_action(ncr_id, action_id).await?;
+        let mut active: corrective_action::ActiveModel = action.into();
+        if let Some(assignee) = assignment.assignee {
+            active.assignee = Set(Some(assignee));
+        }
+        if let Some(due_date) = assignment.due_date {
+            active.due_date = Set(due_date);
+        }
+        active.updated_at = Set(Utc::now());
+        active.update(self.db_pool.as_ref()).await.map_err(db_error)
+    }
+
+    pub async fn transition_action(
+        &self,
+        ncr_id: Uuid,
+        action_id: Uuid,
+        transition: ActionTransition,
+    ) -> Result<corrective_action::Model, ServiceError> {
+        let action = self.find_action(ncr_id, action_id).await?;
+        if !action_transition_allowed(action.status, transition.status) {
+            return Err(not_allowed(action.status, transition.status));
+        }
+        let notes = transition.verification_notes.filter(|n| !n.trim().is_empty());
+        if transition.status == ActionStatus::Verified && notes.is_none() {
+            return Err(ServiceError::ValidationError("Describe how the action was verified".to_string()));
+        }
+        let now = Utc::now();
+        let mut active: corrective_action::ActiveModel = action.into();
+        active.status = Set(transition.status);
+        if notes.is_some() {
+            active.verification_notes = Set(notes);
+        }
+        active.completed_at = Set(matches!(transition.status, ActionStatus::Verified | ActionStatus::Cancelled).then_some(now));
+        active.updated_at = Set(now);
+        active.update(self.db_pool.as_ref()).await.map_err(db_error)
+    }
+
+    /// SKUs or suppliers with at least `min_count` NCRs raised in the last `days` days.
+    pub async fn recurrence(&self, by: RecurrenceKey, days: i64, min_count: u64) -> Result<Vec<Recurrence>, ServiceError> {
+        if !(1..=MAX_RECURRENCE_DAYS).contains(&days) {
+            return Err(ServiceError::ValidationError(format!("days must be between 1 and {}", MAX_RECURRENCE_DAYS)));
+        }
+        let ncrs = NonConformance::find()
+            .filter(non_conformance::Column::CreatedAt.gte(Utc::now() - Duration::days(days)))
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?;
+        Ok(recurrence(&ncrs, by, min_count))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn ncr(sku: &str, supplier: Option<&str>, status: NcrStatus, day: u32) -> non_conformance::Model {
+        let at = Utc.with_ymd_and_hms(2024, 5, day, 9, 0, 0).unwrap();
+        non_conformance::Model {
+            id: Uuid::new_v4(),
+            source: NcrSource::Inspection,
+            source_id: Uuid::new_v4().to_string(),
+            sku: Some(sku.to_string()),
+            supplier_id: supplier.map(str::to_string),
+            description: "Scratched housing".to_string(),
+            severity: if day == 3 { Severity::Critical } else { Severity::Minor },
+            status,
+            assignee: None,
+            due_date: None,
+            root_cause: None,
+            created_by: "user:1".to_string(),
+            created_at: at,
+            updated_at: at,
+            closed_at: None,
+        }
+    }
+
+    fn action(status: ActionStatus) -> corrective_action::Model {
+        let at = Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        corrective_action::Model {
+            id: Uuid::new_v4(),
+            ncr_id: Uuid::nil(),
+            kind: ActionKind::Corrective,
+            description: "Replace fixture".to_string(),
+            status,
+            assignee: None,
+            due_date: at.date_naive(),
+            verification_notes: None,
+            created_at: at,
+            updated_at: at,
+            completed_at: None,
+        }
+    }
+
+    #[test]
+    fn test_ncr_workflow() {
+        assert!(ncr_transition_allowed(NcrStatus::Open, NcrStatus::Investigating));
+        assert!(ncr_transition_allowed(NcrStatus::Investigating, NcrStatus::Closed));
+        assert!(!ncr_transition_allowed(NcrStatus::Open, NcrStatus::Closed));
+        assert!(!ncr_transition_allowed(NcrStatus::ActionRequired, NcrStatus::Voided));
+        assert!(!ncr_transition_allowed(NcrStatus::Closed, NcrStatus::Open));
+    }
+
+    #[test]
+    fn test_action_workflow() {
+        assert!(action_transition_allowed(ActionStatus::Implemented, ActionStatus::Verified));
+        assert!(action_transition_allowed(ActionStatus::Implemented, ActionStatus::InProgress));
+        assert!(!action_transition_allowed(ActionStatus::Open, ActionStatus::Verified));
+        assert!(!action_transition_allowed(ActionStatus::Verified, ActionStatus::Cancelled));
+    }
+
+    #[test]
+    fn test_close_needs_a_verified_action() {
+        assert!(!actions_allow_close(&[]));
+        assert!(!actions_allow_close(&[action(ActionStatus::Cancelled)]));
+        assert!(!actions_allow_close(&[action(ActionStatus::Verified), action(ActionStatus::Implemented)]));
+        assert!(actions_allow_close(&[action(ActionStatus::Verified), action(ActionStatus::Cancelled)]));
+    }
+
+    #[test]
+    fn test_recurrence_groups_by_key() {
+        let ncrs = [
+            ncr("A", Some("S1"), NcrStatus::Closed, 1),
+            ncr("A", Some("S2"), NcrStatus::Open, 3),
+            ncr("A", Some("S1"), NcrStatus::Voided, 4),
+            ncr("B", None, NcrStatus::Open, 2),
+        ];
+        let by_sku = recurrence(&ncrs, RecurrenceKey::Sku, 1);
+        assert_eq!(by_sku.len(), 2);
+        assert_eq!((by_sku[0].key.as_str(), by_sku[0].ncrs, by_sku[0].open, by_sku[0].critical), ("A", 2, 1, 1));
+        assert_eq!(by_sku[0].last_raised_at.date_naive().to_string(), "2024-05-03");
+
+        let by_supplier = recurrence(&ncrs, RecurrenceKey::Supplier, 1);
+        assert_eq!(by_supplier.iter().map(|r| r.key.as_str()).collect::<Vec<_>>(), ["S1", "S2"]);
+        assert!(recurrence(&ncrs, RecurrenceKey::Sku, 3).is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+use async_trait::async_trait;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
+use tracing::{error, instrument};
+use uuid::Uuid;
+
+use crate::{
+    commands::orders::OrderEventStore,
+    custom_fields::metadata_condition,
+    db::{dialect, DbPool},
+    errors::ServiceError,
+    models::{
+        order::{self, Entity as Order, OrderStatus},
+        payment_authorization::{self, Entity as PaymentAuthorization},
+        payment_capture::{self, Entity as PaymentCapture},
+        return_entity::{self, Entity as Return},
+        shipment::{self, Entity as Shipment},
+    },
+    services::order_timeline::{build_timeline, OrderNoteRow, OrderRecords, TimelineEntry},
+    utils::pagination::PaginationParams,
+};
+
+/// Filters accepted by `GET /orders/search`. All filters are optional and combined with AND.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrderSearchParams {
+    pub customer_email: Option<String>,
+    pub status: Option<OrderStatus>,
+    /// Substring of the order number.
+    pub order_number: Option<String>,
+    /// Metadata values by key, from the `metadata.<key>` query parameters.
+    #[serde(skip_deserializing)]
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// Order reads used by the HTTP handlers. Handlers depend on this trait rather than on
+/// `OrderService` so their tests can use `MockOrderServiceApi` instead of a database.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait OrderServiceApi: Send + Sync {
+    async fn get_order(&self, id: Uuid) -> Result<order::Model, ServiceError>;
+
+    /// Orders with the given ids, in no particular order; unknown ids are skipped.
+    async fn get_orders(&self, ids: Vec<Uuid>) -> Result<Vec<order::Model>, ServiceError>;
+
+    async fn delete_order(&self, id: Uuid) -> Result<(), ServiceError>;
+
+    /// Returns one page of orders, newest first, and the total number of orders.
+    async fn list_orders(&self, pagination: PaginationParams) -> Result<(Vec<order::Model>, u64), ServiceError>;
+
+    async fn search_orders(
+        &self,
+        params: OrderSearchParams,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<order::Model>, u64), ServiceError>;
+
+    /// Status changes, events, notes, payments, shipments and returns of an order, oldest
+    /// first.
+    async fn get_timeline(&self, id: Uuid) -> Result<Vec<TimelineEntry>, ServiceError>;
+}
+
+/// Database-backed order service.
+pub struct OrderService {
+    db_pool: Arc<DbPool>,
+}
+
+impl OrderService {
+    pub fn new(db_pool: Arc<DbPool>) -> Self {
+        Self { db_pool }
+    }
+
+    async fn page(
+        &self,
+        query: Select<Order>,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<order::Model>, u64), ServiceError> {
+        let paginator = query
+            .order_by_desc(order::Column::CreatedDate)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let orders = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((orders, total))
+    }
+}
+
+const ORDER_NOTES_SQL: &str = r#"
+SELECT note, is_customer_visible, created_at
+FROM order_notes
+WHERE order_id = $1
+ORDER BY created_at
+"#;
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Order query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+#[async_trait]
+impl OrderServiceApi for OrderService {
+    #[instrument(skip(self))]
+    async fn get_order(&self, id: Uuid) -> Result<order::Model, ServiceError> {
+        Order::find_by_id(id)
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Order not found: {}", id)))
+    }
+
+    async fn get_orders(&self, ids: Vec<Uuid>) -> Result<Vec<order::Model>, ServiceError> {
+        Order::find()
+            .filter(order::Column::Id.is_in(ids))
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)
+    }
+
+    #[instrument(skip(self))]
+    async fn delete_order(&self, id: Uuid) -> Result<(), ServiceError> {
+        let result = Order::delete_by_id(id)
+            .exec(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?;
+        if result.rows_affected == 0 {
+            return Err(ServiceError::NotFound(format!("Order not found: {}", id)));
+        }
+        Ok(())
+    }
+
+    async fn list_orders(&self, pagination: PaginationParams) -> Result<(Vec<order::Model>, u64), ServiceError> {
+        self.page(Order::find(), pagination).await
+    }
+
+    async fn search_orders(
+        &self,
+        params: OrderSearchParams,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<order::Model>, u64), ServiceError> {
+        let mut query = Order::find();
+        if let Some(email) = params.customer_email {
+            query = query.filter(order::Column::CustomerEmail.eq(email));
+        }
+        if let Some(status) = params.status {
+            query = query.filter(order::Column::OrderStatus.eq(status));
+        }
+        if let Some(number) = params.order_number {
+            query = query.filter(order::Column::OrderNumber.contains(&number));
+        }
+        if !params.metadata.is_empty() {
+            query = query.filter(metadata_condition("metadata", &params.metadata));
+        }
+        self.page(query, pagination).await
+    }
+
+    #[instrument(skip(self))]
+    async fn get_timeline(&self, id: Uuid) -> Result<Vec<TimelineEntry>, ServiceError> {
+        let order = self.get_order(id).await?;
+        let db = self.db_pool.as_ref();
+        let events = OrderEventStore::load_stream(db, id)
+            .await
+            .map_err(|e| ServiceError::DatabaseError(e.to_string()))?;
+        // Shipments reference orders by tracking number; they carry no order UUID
+        let shipments = match &order.tracking_number {
+            Some(tracking) => Shipment::find()
+                .filter(shipment::Column::TrackingNumber.eq(tracking.as_str()))
+                .all(db)
+                .await
+                .map_err(db_error)?,
+            None => Vec::new(),
+        };
+        let returns = Return::find()
+            .filter(return_entity::Column::OrderId.eq(id))
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        let notes = OrderNoteRow::find_by_statement(dialect::statement(db, ORDER_NOTES_SQL, [id.into()]))
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        let authorizations = PaymentAuthorization::find()
+            .filter(payment_authorization::Column::OrderId.eq(id))
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        let captures = if authorizations.is_empty() {
+            Vec::new()
+        } else {
+            PaymentCapture::find()
+                .filter(payment_capture::Column::AuthorizationId.is_in(authorizations.iter().map(|a| a.id)))
+                .all(db)
+                .await
+                .map_err(db_error)?
+        };
+        let records = OrderRecords { events, notes, authorizations, captures, shipments, returns };
+        Ok(build_timeline(order.created_date, &records))
+    }
+}
@@ -0,0 +1,353 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sea_orm::FromQueryResult;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+use crate::{
+    commands::orders::order_event_store::{OrderDomainEvent, RecordedOrderEvent},
+    models::{
+        payment_authorization::{self, AuthorizationStatus},
+        payment_capture::{self, CaptureStatus},
+        return_entity, shipment,
+    },
+};
+
+/// Where a timeline entry came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineSource {
+    Order,
+    Event,
+    Note,
+    Payment,
+    Shipment,
+    Return,
+}
+
+/// A row of `order_notes`, including the notes cancel, hold and refund commands write.
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct OrderNoteRow {
+    pub note: String,
+    pub is_customer_visible: bool,
+    pub created_at: NaiveDateTime,
+}
+
+/// Everything recorded against an order that the timeline merges.
+#[derive(Debug, Clone, Default)]
+pub struct OrderRecords {
+    pub events: Vec<RecordedOrderEvent>,
+    pub notes: Vec<OrderNoteRow>,
+    pub authorizations: Vec<payment_authorization::Model>,
+    pub captures: Vec<payment_capture::Model>,
+    pub shipments: Vec<shipment::Model>,
+    pub returns: Vec<return_entity::Model>,
+}
+
+/// One line of an order's support timeline.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TimelineEntry {
+    pub at: DateTime<Utc>,
+    pub source: TimelineSource,
+    /// Short machine-readable kind, e.g. `StatusChanged` or `return_requested`.
+    pub kind: String,
+    pub summary: String,
+    /// Who caused the entry (`user:<id>`, `service_account:<id>`, `system`), when known.
+    pub actor: Option<String>,
+    pub details: Value,
+}
+
+/// Merges an order's records into one chronological feed. Entries with the same
+/// timestamp keep the order in which they were collected: order, events, notes, payments,
+/// shipments, returns.
+///
+/// Notes come from `order_notes`, which has every note; a `NoteAdded` event with the same
+/// text only lends the note its actor, so a note is never listed twice.
+pub fn build_timeline(order_created_at: DateTime<Utc>, records: &OrderRecords) -> Vec<TimelineEntry> {
+    let OrderRecords { events, notes, authorizations, captures, shipments, returns } = records;
+    let mut entries = vec![TimelineEntry {
+        at: order_created_at,
+        source: TimelineSource::Order,
+        kind: "order_placed".to_string(),
+        summary: "Order placed".to_string(),
+        actor: None,
+        details: Value::Null,
+    }];
+
+    let mut matched = HashSet::new();
+    let note_actors: Vec<Option<String>> = notes
+        .iter()
+        .map(|row| {
+            let recorded = events.iter().find(|recorded| {
+                !matched.contains(&recorded.sequence)
+                    && matches!(&recorded.event, OrderDomainEvent::NoteAdded { note } if *note == row.note)
+            })?;
+            matched.insert(recorded.sequence);
+            recorded.actor.clone()
+        })
+        .collect();
+
+    entries.extend(events.iter().filter(|recorded| !matched.contains(&recorded.sequence)).map(|recorded| {
+        TimelineEntry {
+            at: recorded.recorded_at,
+            source: TimelineSource::Event,
+            kind: recorded.event.event_type().to_string(),
+            summary: describe_event(&recorded.event),
+            actor: recorded.actor.clone(),
+            details: json!({ "sequence": recorded.sequence, "event": recorded.event }),
+        }
+    }));
+
+    entries.extend(notes.iter().zip(note_actors).map(|(row, actor)| TimelineEntry {
+        at: row.created_at.and_utc(),
+        source: TimelineSource::Note,
+        kind: "note_added".to_string(),
+        summary: format!("Note: {}", row.note),
+        actor,
+        details: json!({ "customer_visible": row.is_customer_visible }),
+    }));
+
+    for a in authorizations {
+        let details = json!({
+            "authorization_id": a.id,
+            "gateway": a.gateway,
+            "authorization_ref": a.authorization_ref,
+            "status": a.status,
+        });
+        entries.push(TimelineEntry {
+            at: a.created_at,
+            source: TimelineSource::Payment,
+            kind: "payment_authorized".to_string(),
+            summary: format!("{} {} authorized via {}", a.authorized_amount, a.currency, a.gateway),
+            actor: None,
+            details: details.clone(),
+        });
+        if a.status == AuthorizationStatus::Voided {
+            entries.push(TimelineEntry {
+                at: a.updated_at,
+                source: TimelineSource::Payment,
+                kind: "payment_voided".to_string(),
+                summary: format!("Authorization of {} {} voided", a.authorized_amount, a.currency),
+                actor: None,
+                details,
+            });
+        }
+    }
+    for c in captures {
+        let currency = authorizations
+            .iter()
+            .find(|a| a.id == c.authorization_id)
+            .map_or("", |a| a.currency.as_str());
+        let (kind, summary) = match c.status {
+            CaptureStatus::Pending => ("payment_capture_pending", format!("Capturing {} {}", c.amount, currency)),
+            CaptureStatus::Succeeded => ("payment_captured", format!("{} {} captured", c.amount, currency)),
+            CaptureStatus::Failed => (
+                "payment_capture_failed",
+                format!("Capture of {} {} failed: {}", c.amount, currency, c.error.as_deref().unwrap_or("unknown error")),
+            ),
+        };
+        entries.push(TimelineEntry {
+            at: c.created_at,
+            source: TimelineSource::Payment,
+            kind: kind.to_string(),
+            summary,
+            actor: Some(c.created_by.clone()),
+            details: json!({
+                "capture_id": c.id,
+                "authorization_id": c.authorization_id,
+                "shipment_reference": c.shipment_reference,
+                "final_capture": c.final_capture,
+            }),
+        });
+    }
+
+    for s in shipments {
+        let details = json!({
+            "shipment_id": s.id,
+            "carrier": s.carrier,
+            "tracking_number": s.tracking_number,
+            "status": s.status,
+        });
+        entries.push(TimelineEntry {
+            at: s.created_at.with_timezone(&Utc),
+            source: TimelineSource::Shipment,
+            kind: "shipment_created".to_string(),
+            summary: format!("Shipment created ({:?}, {})", s.carrier, s.shipping_method),
+            actor: None,
+            details: details.clone(),
+        });
+        if let Some(shipped_at) = s.shipped_at {
+            entries.push(TimelineEntry {
+                at: shipped_at.with_timezone(&Utc),
+                source: TimelineSource::Shipment,
+                kind: "shipment_shipped".to_string(),
+                summary: format!("Shipped via {:?}, tracking {}", s.carrier, s.tracking_number),
+                actor: None,
+                details,
+            });
+        }
+    }
+
+    for r in returns {
+        let actor = r.entered_by.map(|user| format!("user:{}", user));
+        let details = json!({ "return_id": r.id, "rma": r.rma, "status": r.status, "amount": r.amount });
+        entries.push(TimelineEntry {
+            at: r.requested_date,
+            source: TimelineSource::Return,
+            kind: "return_requested".to_string(),
+            summary: format!("Return {} requested", r.rma),
+            actor,
+            details: details.clone(),
+        });
+        if let Some(shipped_date) = r.shipped_date {
+            entries.push(TimelineEntry {
+                at: shipped_date,
+                source: TimelineSource::Return,
+                kind: "return_shipped".to_string(),
+                summary: format!("Return {} shipped back by customer", r.rma),
+                actor: None,
+                details,
+            });
+        }
+    }
+
+    // Stable sort keeps collection order for equal timestamps
+    entries.sort_by_key(|entry| entry.at);
+    entries
+}
+
+fn describe_event(event: &OrderDomainEvent) -> String {
+    match event {
+        OrderDomainEvent::OrderCreated { status, .. } => format!("Order created with status {}", status),
+        OrderDomainEvent::StatusChanged { from: Some(from), to } => format!("Status changed from {} to {}", from, to),
+        OrderDomainEvent::StatusChanged { from: None, to } => format!("Status set to {}", to),
+        OrderDomainEvent::ItemAdded { product_id, quantity } => format!("Added {} x {}", quantity, product_id),
+        OrderDomainEvent::ItemRemoved { product_id } => format!("Removed {}", product_id),
+        OrderDomainEvent::NoteAdded { note } => format!("Note: {}", note),
+        OrderDomainEvent::PlacedOnHold { reason } => format!("Placed on hold: {}", reason),
+        OrderDomainEvent::ReleasedFromHold => "Released from hold".to_string(),
+        OrderDomainEvent::Cancelled { reason } => format!("Cancelled: {}", reason),
+        OrderDomainEvent::Shipped { tracking_number: Some(tracking) } => format!("Marked shipped, tracking {}", tracking),
+        OrderDomainEvent::Shipped { tracking_number: None } => "Marked shipped".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use rust_decimal_macros::dec;
+    use uuid::Uuid;
+    use crate::models::{
+        payment_authorization::CaptureStrategy,
+        shipment::{ShipmentStatus, ShippingCarrier},
+    };
+
+    fn recorded(sequence: i64, event: OrderDomainEvent, at: DateTime<Utc>) -> RecordedOrderEvent {
+        RecordedOrderEvent { sequence, event, actor: Some("user:agent-7".to_string()), recorded_at: at }
+    }
+
+    #[test]
+    fn test_timeline_is_chronological_across_sources() {
+        let placed = Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        let events = vec![
+            recorded(1, OrderDomainEvent::StatusChanged { from: Some("Pending".into()), to: "Processing".into() }, placed + Duration::hours(1)),
+            recorded(2, OrderDomainEvent::NoteAdded { note: "Gift wrap".into() }, placed + Duration::hours(5)),
+        ];
+        let shipments = vec![shipment::Model {
+            id: 3,
+            order_id: 1,
+            tracking_number: "1Z999".to_string(),
+            carrier: ShippingCarrier::UPS,
+            status: ShipmentStatus::Shipped,
+            shipping_address: "1 Main St".to_string(),
+            shipping_method: "Ground".to_string(),
+            shipped_at: Some((placed + Duration::hours(4)).into()),
+            estimated_delivery: None,
+            delivered_at: None,
+            manifest_id: None,
+            created_at: (placed + Duration::hours(2)).into(),
+            updated_at: (placed + Duration::hours(4)).into(),
+        }];
+
+        let records = OrderRecords { events, shipments, ..Default::default() };
+        let timeline = build_timeline(placed, &records);
+        let kinds: Vec<_> = timeline.iter().map(|e| e.kind.as_str()).collect();
+        assert_eq!(
+            kinds,
+            ["order_placed", "StatusChanged", "shipment_created", "shipment_shipped", "NoteAdded"]
+        );
+        assert_eq!(timeline[1].summary, "Status changed from Pending to Processing");
+        assert_eq!(timeline[1].actor.as_deref(), Some("user:agent-7"));
+        assert_eq!(timeline[3].summary, "Shipped via UPS, tracking 1Z999");
+    }
+
+    #[test]
+    fn test_equal_timestamps_keep_collection_order() {
+        let at = Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        let events = vec![recorded(1, OrderDomainEvent::ReleasedFromHold, at)];
+        let timeline = build_timeline(at, &OrderRecords { events, ..Default::default() });
+        assert_eq!(timeline[0].source, TimelineSource::Order);
+        assert_eq!(timeline[1].source, TimelineSource::Event);
+    }
+
+    #[test]
+    fn test_notes_and_payments_are_merged() {
+        let placed = Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        let authorization = payment_authorization::Model {
+            id: Uuid::new_v4(),
+            order_id: Uuid::new_v4(),
+            gateway: "stripe".to_string(),
+            authorization_ref: "pi_1".to_string(),
+            currency: "USD".to_string(),
+            authorized_amount: dec!(40.00),
+            captured_amount: dec!(40.00),
+            capture_strategy: CaptureStrategy::OnShipment,
+            status: AuthorizationStatus::Captured,
+            created_at: placed + Duration::minutes(1),
+            updated_at: placed + Duration::hours(4),
+        };
+        let capture = payment_capture::Model {
+            id: Uuid::new_v4(),
+            authorization_id: authorization.id,
+            shipment_reference: Some("1Z999".to_string()),
+            amount: dec!(40.00),
+            final_capture: true,
+            status: CaptureStatus::Succeeded,
+            idempotency_key: "capture-1".to_string(),
+            gateway_ref: Some("ch_1".to_string()),
+            error: None,
+            created_by: "system".to_string(),
+            created_at: placed + Duration::hours(4),
+            updated_at: placed + Duration::hours(4),
+        };
+        let records = OrderRecords {
+            events: vec![recorded(1, OrderDomainEvent::NoteAdded { note: "Gift wrap".into() }, placed + Duration::hours(2))],
+            notes: vec![
+                OrderNoteRow {
+                    note: "Gift wrap".to_string(),
+                    is_customer_visible: false,
+                    created_at: (placed + Duration::hours(2)).naive_utc(),
+                },
+                OrderNoteRow {
+                    note: "Order placed on hold: address check".to_string(),
+                    is_customer_visible: false,
+                    created_at: (placed + Duration::hours(3)).naive_utc(),
+                },
+            ],
+            authorizations: vec![authorization],
+            captures: vec![capture],
+            ..Default::default()
+        };
+
+        let timeline = build_timeline(placed, &records);
+        let kinds: Vec<_> = timeline.iter().map(|e| e.kind.as_str()).collect();
+        assert_eq!(kinds, ["order_placed", "payment_authorized", "note_added", "note_added", "payment_captured"]);
+        assert_eq!(timeline[1].summary, "40.00 USD authorized via stripe");
+        // The note event only lends its actor; the note written without one has none
+        assert_eq!(timeline[2].actor.as_deref(), Some("user:agent-7"));
+        assert_eq!(timeline[3].actor, None);
+        assert_eq!(timeline[4].summary, "40.00 USD captured");
+        assert_eq!(timeline[4].actor.as_deref(), Some("system"));
+    }
+}
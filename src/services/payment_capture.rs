@@ -0,0 +1,533 @@
+use chrono::Utc;
+use rust_decimal::Decimal;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::{sync::Arc, time::Duration};
+use tokio::sync::broadcast;
+use tracing::{error, info, instrument, warn};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db::DbPool,
+    errors::ServiceError,
+    events::{Event, EventSender},
+    models::{
+        payment_authorization::{self, AuthorizationStatus, CaptureStrategy, Entity as PaymentAuthorization},
+        payment_capture::{self, CaptureStatus, Entity as PaymentCapture},
+    },
+    payments::{CaptureRequest, GatewayError, PaymentGateway, PaymentsConfig},
+    shutdown::Worker,
+};
+
+/// Actor recorded on captures triggered by fulfillment.
+const SYSTEM_ACTOR: &str = "system";
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewAuthorization {
+    pub order_id: Uuid,
+    #[validate(length(min = 1, max = 255))]
+    pub authorization_ref: String,
+    #[validate(length(equal = 3))]
+    pub currency: String,
+    #[validate(custom = "crate::money::validate_positive")]
+    #[serde(with = "crate::money::amount")]
+    pub amount: Decimal,
+    /// Overrides the configured strategy for this order.
+    pub capture_strategy: Option<CaptureStrategy>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ManualCapture {
+    /// Defaults to the uncaptured remainder.
+    #[serde(default, with = "crate::money::option_amount")]
+    pub amount: Option<Decimal>,
+    pub shipment_reference: Option<String>,
+    /// Release what is left after this capture. Implied when capturing the remainder.
+    #[serde(default)]
+    pub final_capture: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthorizationWithCaptures {
+    #[serde(flatten)]
+    pub authorization: payment_authorization::Model,
+    pub captures: Vec<payment_capture::Model>,
+}
+
+/// Status a capture is left in by a gateway error. Whether a capture the gateway did not
+/// answer went through is unknown, so it stays pending with its amount held until
+/// [`PaymentCaptureService::reconcile_stale`] learns the outcome; only a refusal fails it.
+pub fn status_after(error: &GatewayError) -> CaptureStatus {
+    match error {
+        GatewayError::Unavailable(_) => CaptureStatus::Pending,
+        GatewayError::Declined(_) | GatewayError::Misconfigured(_) => CaptureStatus::Failed,
+    }
+}
+
+/// What to capture against an authorization: the requested amount, or the whole remainder,
+/// and whether the capture closes the authorization.
+pub fn plan_capture(
+    authorization: &payment_authorization::Model,
+    requested: Option<Decimal>,
+    final_capture: bool,
+) -> Result<(Decimal, bool), String> {
+    if !authorization.status.is_open() {
+        return Err(format!("Authorization is {:?}", authorization.status));
+    }
+    let remaining = authorization.authorized_amount - authorization.captured_amount;
+    if remaining <= Decimal::ZERO {
+        return Err("Nothing left to capture".to_string());
+    }
+    let amount = requested.unwrap_or(remaining);
+    if amount <= Decimal::ZERO {
+        return Err("Capture amount must be positive".to_string());
+    }
+    if amount > remaining {
+        return Err(format!("Capture of {} exceeds the uncaptured {}", amount, remaining));
+    }
+    Ok((amount, final_capture || amount == remaining))
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Payment capture query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Captures authorized payments according to each authorization's strategy: at once, as
+/// shipments are marked shipped (partially for split shipments), or by hand.
+pub struct PaymentCaptureService {
+    db_pool: Arc<DbPool>,
+    events: EventSender,
+    gateway: Arc<dyn PaymentGateway>,
+    config: PaymentsConfig,
+}
+
+impl PaymentCaptureService {
+    pub fn new(db_pool: Arc<DbPool>, events: EventSender, gateway: Arc<dyn PaymentGateway>, config: PaymentsConfig) -> Self {
+        Self { db_pool, events, gateway, config }
+    }
+
+    /// Records an authorization taken at checkout. Capture-on-order authorizations are
+    /// captured before returning.
+    #[instrument(skip(self, input), fields(order_id = %input.order_id))]
+    pub async fn authorize(&self, input: NewAuthorization) -> Result<AuthorizationWithCaptures, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid authorization: {}", e)))?;
+        let db = self.db_pool.as_ref();
+        let open = PaymentAuthorization::find()
+            .filter(payment_authorization::Column::OrderId.eq(input.order_id))
+            .filter(payment_authorization::Column::Status.is_in([
+                AuthorizationStatus::Authorized,
+                AuthorizationStatus::PartiallyCaptured,
+            ]))
+            .count(db)
+            .await
+            .map_err(db_error)?;
+        if open > 0 {
+            return Err(ServiceError::ValidationError(format!(
+                "Order {} already has an open authorization",
+                input.order_id
+            )));
+        }
+        let now = Utc::now();
+        let authorization = payment_authorization::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            order_id: Set(input.order_id),
+            gateway: Set(self.gateway.name().to_string()),
+            authorization_ref: Set(input.authorization_ref),
+            currency: Set(input.currency.to_uppercase()),
+            authorized_amount: Set(input.amount),
+            captured_amount: Set(Decimal::ZERO),
+            capture_strategy: Set(input.capture_strategy.unwrap_or(self.config.capture_strategy)),
+            status: Set(AuthorizationStatus::Authorized),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(db)
+        .await
+        .map_err(db_error)?;
+        if authorization.capture_strategy == CaptureStrategy::OnOrder {
+            let key = format!("order:{}", authorization.id);
+            self.capture(authorization.id, None, true, None, key, SYSTEM_ACTOR).await?;
+        }
+        self.get(authorization.id).await
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<AuthorizationWithCaptures, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let authorization = PaymentAuthorization::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Payment authorization not found: {}", id)))?;
+        let captures = PaymentCapture::find()
+            .filter(payment_capture::Column::AuthorizationId.eq(id))
+            .order_by_asc(payment_capture::Column::CreatedAt)
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        Ok(AuthorizationWithCaptures { authorization, captures })
+    }
+
+    pub async fn for_order(&self, order_id: Uuid) -> Result<Vec<payment_authorization::Model>, ServiceError> {
+        PaymentAuthorization::find()
+            .filter(payment_authorization::Column::OrderId.eq(order_id))
+            .order_by_asc(payment_authorization::Column::CreatedAt)
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)
+    }
+
+    /// Captures by hand, whatever the authorization's strategy.
+    pub async fn capture_manually(
+        &self,
+        id: Uuid,
+        input: ManualCapture,
+        actor: &str,
+    ) -> Result<payment_capture::Model, ServiceError> {
+        let key = format!("manual:{}", Uuid::new_v4());
+        self.capture(id, input.amount, input.final_capture, input.shipment_reference, key, actor)
+            .await
+    }
+
+    /// Reserves the amount on the authorization, calls the gateway outside the database
+    /// transaction, then records the outcome. A declined capture releases its reservation and
+    /// is returned with status `failed` rather than as an error. One the gateway did not
+    /// answer, or whose outcome is never recorded, stays `pending` and is resolved by
+    /// [`PaymentCaptureService::reconcile_stale`].
+    async fn capture(
+        &self,
+        id: Uuid,
+        amount: Option<Decimal>,
+        final_capture: bool,
+        shipment_reference: Option<String>,
+        idempotency_key: String,
+        actor: &str,
+    ) -> Result<payment_capture::Model, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        if let Some(existing) = PaymentCapture::find()
+            .filter(payment_capture::Column::IdempotencyKey.eq(idempotency_key.as_str()))
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+        {
+            return Ok(existing);
+        }
+        let authorization = PaymentAuthorization::find_by_id(id)
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Payment authorization not found: {}", id)))?;
+        let (amount, final_capture) =
+            plan_capture(&authorization, amount, final_capture).map_err(ServiceError::ValidationError)?;
+        let now = Utc::now();
+        let capture = payment_capture::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            authorization_id: Set(id),
+            shipment_reference: Set(shipment_reference),
+            amount: Set(amount),
+            final_capture: Set(final_capture),
+            status: Set(CaptureStatus::Pending),
+            idempotency_key: Set(idempotency_key),
+            gateway_ref: Set(None),
+            error: Set(None),
+            created_by: Set(actor.to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(&txn)
+        .await
+        .map_err(db_error)?;
+        let mut reserved: payment_authorization::ActiveModel = authorization.clone().into();
+        reserved.captured_amount = Set(authorization.captured_amount + amount);
+        reserved.updated_at = Set(now);
+        reserved.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+
+        let outcome = self.send_capture(&authorization, &capture).await;
+        self.record_outcome(capture, outcome).await
+    }
+
+    async fn send_capture(
+        &self,
+        authorization: &payment_authorization::Model,
+        capture: &payment_capture::Model,
+    ) -> Result<String, GatewayError> {
+        self.gateway
+            .capture(CaptureRequest {
+                authorization_ref: &authorization.authorization_ref,
+                amount: capture.amount,
+                currency: &authorization.currency,
+                final_capture: capture.final_capture,
+                idempotency_key: &capture.idempotency_key,
+            })
+            .await
+    }
+
+    /// Records the gateway's answer to a pending capture: a success settles the
+    /// authorization's status, a refusal releases the reserved amount, and no answer leaves
+    /// it pending. A capture someone else already recorded is returned as is.
+    async fn record_outcome(
+        &self,
+        capture: payment_capture::Model,
+        outcome: Result<String, GatewayError>,
+    ) -> Result<payment_capture::Model, ServiceError> {
+        let (id, amount, final_capture) = (capture.authorization_id, capture.amount, capture.final_capture);
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let authorization = PaymentAuthorization::find_by_id(id)
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Payment authorization not found: {}", id)))?;
+        let capture = PaymentCapture::find_by_id(capture.id)
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Payment capture not found: {}", capture.id)))?;
+        if capture.status != CaptureStatus::Pending {
+            return Ok(capture);
+        }
+        let mut recorded: payment_capture::ActiveModel = capture.into();
+        let mut updated: payment_authorization::ActiveModel = authorization.clone().into();
+        updated.updated_at = Set(Utc::now());
+        recorded.updated_at = Set(Utc::now());
+        let succeeded = match outcome {
+            Ok(gateway_ref) => {
+                recorded.status = Set(CaptureStatus::Succeeded);
+                recorded.gateway_ref = Set(Some(gateway_ref));
+                recorded.error = Set(None);
+                let fully_captured = authorization.captured_amount >= authorization.authorized_amount;
+                updated.status = Set(if final_capture || fully_captured {
+                    AuthorizationStatus::Captured
+                } else {
+                    AuthorizationStatus::PartiallyCaptured
+                });
+                true
+            }
+            Err(e) => {
+                if status_after(&e) == CaptureStatus::Failed {
+                    warn!(authorization_id = %id, %amount, "Payment capture failed: {}", e);
+                    recorded.status = Set(CaptureStatus::Failed);
+                    updated.captured_amount = Set(authorization.captured_amount - amount);
+                } else {
+                    warn!(authorization_id = %id, %amount, "Payment capture unanswered; left pending: {}", e);
+                }
+                recorded.error = Set(Some(e.to_string()));
+                false
+            }
+        };
+        let capture = recorded.update(&txn).await.map_err(db_error)?;
+        updated.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+
+        if succeeded {
+            info!(authorization_id = %id, capture_id = %capture.id, %amount, "Payment captured");
+            let _ = self.events.send(Event::PaymentCaptured {
+                order_id: authorization.order_id,
+                authorization_id: id,
+                capture_id: capture.id,
+                amount,
+            });
+        }
+        Ok(capture)
+    }
+
+    /// Resolves captures left `pending` past `stale_capture_secs`, whose outcome was lost
+    /// between the gateway call and recording it. The capture is re-sent with its original
+    /// idempotency key, so the gateway answers with the first attempt's result instead of
+    /// capturing twice, and the amount stops being held on the authorization if it failed.
+    pub async fn reconcile_stale(&self) -> Result<usize, ServiceError> {
+        let cutoff = Utc::now() - chrono::Duration::seconds(self.config.stale_capture_secs as i64);
+        let stale = PaymentCapture::find()
+            .filter(payment_capture::Column::Status.eq(CaptureStatus::Pending))
+            .filter(payment_capture::Column::UpdatedAt.lt(cutoff))
+            .find_also_related(PaymentAuthorization)
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?;
+        let mut resolved = 0;
+        for (capture, authorization) in stale {
+            let Some(authorization) = authorization else {
+                continue;
+            };
+            let capture_id = capture.id;
+            let outcome = self.send_capture(&authorization, &capture).await;
+            if let Err(GatewayError::Unavailable(e)) = &outcome {
+                // Still no answer; leave it pending for the next sweep
+                warn!(%capture_id, "Stale capture could not be reconciled: {}", e);
+                continue;
+            }
+            let capture = self.record_outcome(capture, outcome).await?;
+            info!(%capture_id, status = ?capture.status, "Stale capture reconciled");
+            resolved += 1;
+        }
+        Ok(resolved)
+    }
+
+    /// Captures for a shipment of an order whose authorization captures on shipment.
+    /// `amount` is the shipment's value for split shipments; `None` captures the rest.
+    /// Repeated notifications for the same shipment capture once.
+    pub async fn on_shipment(
+        &self,
+        order_id: Uuid,
+        tracking_number: Option<&str>,
+        amount: Option<Decimal>,
+    ) -> Result<Option<payment_capture::Model>, ServiceError> {
+        let authorization = PaymentAuthorization::find()
+            .filter(payment_authorization::Column::OrderId.eq(order_id))
+            .filter(payment_authorization::Column::CaptureStrategy.eq(CaptureStrategy::OnShipment))
+            .filter(payment_authorization::Column::Status.is_in([
+                AuthorizationStatus::Authorized,
+                AuthorizationStatus::PartiallyCaptured,
+            ]))
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?;
+        let Some(authorization) = authorization else {
+            return Ok(None);
+        };
+        let remaining = authorization.authorized_amount - authorization.captured_amount;
+        // A shipment may be valued above what is left, e.g. after a discount; cap it
+        let amount = amount.map(|a| a.min(remaining));
+        let key = match tracking_number {
+            Some(tracking) => format!("shipment:{}:{}", authorization.id, tracking),
+            None => format!("order_shipped:{}", authorization.id),
+        };
+        let capture = self
+            .capture(
+                authorization.id,
+                amount,
+                amount.is_none(),
+                tracking_number.map(str::to_string),
+                key,
+                SYSTEM_ACTOR,
+            )
+            .await?;
+        Ok(Some(capture))
+    }
+
+    /// Releases the uncaptured part of an authorization.
+    pub async fn void(&self, id: Uuid) -> Result<payment_authorization::Model, ServiceError> {
+        let authorization = self.get(id).await?.authorization;
+        if !authorization.status.is_open() {
+            return Err(ServiceError::ValidationError(format!("Authorization is {:?}", authorization.status)));
+        }
+        self.gateway
+            .void(&authorization.authorization_ref)
+            .await
+            .map_err(|e| ServiceError::ValidationError(e.to_string()))?;
+        let mut active: payment_authorization::ActiveModel = authorization.into();
+        active.status = Set(AuthorizationStatus::Voided);
+        active.updated_at = Set(Utc::now());
+        active.update(self.db_pool.as_ref()).await.map_err(db_error)
+    }
+}
+
+/// Captures payments as orders and shipments are marked shipped.
+pub fn spawn_capture_listener(service: Arc<PaymentCaptureService>, events: EventSender, mut worker: Worker) {
+    let mut receiver = events.subscribe();
+    tokio::spawn(async move {
+        loop {
+            let received = tokio::select! {
+                biased;
+                received = receiver.recv() => received,
+                _ = worker.stopping() => break,
+            };
+            let result = match received {
+                Ok(Event::ShipmentShipped { order_id, tracking_number, amount }) => {
+                    service.on_shipment(order_id, Some(&tracking_number), amount).await
+                }
+                Ok(Event::OrderShipped(order_id)) => service.on_shipment(order_id, None, None).await,
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!(missed, "Payment capture listener lagged; affected orders need a manual capture");
+                    continue;
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            if let Err(e) = result {
+                error!("Capture on shipment failed: {}", e);
+            }
+        }
+    });
+}
+
+/// Periodically resolves captures stuck in `pending`.
+pub fn spawn_reconciler(service: Arc<PaymentCaptureService>, interval: Duration, mut worker: Worker) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = worker.stopping() => break,
+            }
+            match service.reconcile_stale().await {
+                Ok(0) => {}
+                Ok(resolved) => info!(resolved, "Stale payment captures reconciled"),
+                Err(e) => error!("Payment capture reconciliation failed: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn authorization(authorized: Decimal, captured: Decimal) -> payment_authorization::Model {
+        payment_authorization::Model {
+            id: Uuid::new_v4(),
+            order_id: Uuid::new_v4(),
+            gateway: "stripe".to_string(),
+            authorization_ref: "pi_123".to_string(),
+            currency: "USD".to_string(),
+            authorized_amount: authorized,
+            captured_amount: captured,
+            capture_strategy: CaptureStrategy::OnShipment,
+            status: if captured.is_zero() {
+                AuthorizationStatus::Authorized
+            } else {
+                AuthorizationStatus::PartiallyCaptured
+            },
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_split_shipments_capture_in_parts() {
+        let auth = authorization(dec!(100), dec!(0));
+        assert_eq!(plan_capture(&auth, Some(dec!(40)), false), Ok((dec!(40), false)));
+        let auth = authorization(dec!(100), dec!(40));
+        assert_eq!(plan_capture(&auth, Some(dec!(60)), false), Ok((dec!(60), true)));
+    }
+
+    #[test]
+    fn test_remainder_is_captured_by_default() {
+        let auth = authorization(dec!(100), dec!(25));
+        assert_eq!(plan_capture(&auth, None, false), Ok((dec!(75), true)));
+    }
+
+    #[test]
+    fn test_over_capture_and_closed_authorizations_are_rejected() {
+        assert!(plan_capture(&authorization(dec!(100), dec!(90)), Some(dec!(11)), false).is_err());
+        assert!(plan_capture(&authorization(dec!(100), dec!(0)), Some(dec!(0)), false).is_err());
+        let mut voided = authorization(dec!(100), dec!(0));
+        voided.status = AuthorizationStatus::Voided;
+        assert!(plan_capture(&voided, None, false).is_err());
+    }
+
+    #[test]
+    fn test_only_refused_captures_fail() {
+        assert_eq!(status_after(&GatewayError::Declined("expired".to_string())), CaptureStatus::Failed);
+        assert_eq!(status_after(&GatewayError::Misconfigured("no key".to_string())), CaptureStatus::Failed);
+        assert_eq!(status_after(&GatewayError::Unavailable("timeout".to_string())), CaptureStatus::Pending);
+    }
+}
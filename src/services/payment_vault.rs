@@ -0,0 +1,501 @@
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use rand::{distributions::Alphanumeric, Rng};
+use rust_decimal::Decimal;
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, info, instrument, warn};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    auth::service_accounts::hash_secret,
+    db::DbPool,
+    errors::ServiceError,
+    models::vault_token::{self, Entity as VaultToken, TokenStatus, TokenUsage},
+    payments::{GatewayError, PaymentGateway},
+};
+
+/// Prefix of vault tokens, so they are recognizable in logs and never mistaken for a
+/// processor's own payment method ids.
+pub const TOKEN_PREFIX: &str = "vt";
+
+/// Payment vault settings, loaded from the `payment_vault` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct PaymentVaultConfig {
+    /// Issue and redeem vault tokens. Uses the gateway from the `payments` section.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Lifetime of single-use tokens issued without an explicit expiry (default: 1 hour).
+    #[serde(default = "default_single_use_ttl_secs")]
+    pub single_use_ttl_secs: i64,
+
+    /// Longest lifetime a caller may ask for, in days (default: 400). Tokens never outlive
+    /// the card either.
+    #[serde(default = "default_max_ttl_days")]
+    pub max_ttl_days: i64,
+}
+
+fn default_single_use_ttl_secs() -> i64 {
+    60 * 60
+}
+
+fn default_max_ttl_days() -> i64 {
+    400
+}
+
+impl Default for PaymentVaultConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            single_use_ttl_secs: default_single_use_ttl_secs(),
+            max_ttl_days: default_max_ttl_days(),
+        }
+    }
+}
+
+/// A payment method tokenized client-side with the processor (e.g. by Stripe.js), to be
+/// wrapped in a vault token. Raw card numbers are never accepted.
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct NewVaultToken {
+    #[validate(length(min = 1, max = 255))]
+    pub payment_method_ref: String,
+    pub usage: TokenUsage,
+    /// Caps each redemption; requires `currency`.
+    #[serde(default, with = "crate::money::option_amount")]
+    pub max_amount: Option<Decimal>,
+    #[validate(length(equal = 3))]
+    pub currency: Option<String>,
+    /// Restricts the token to one checkout session, e.g. when delegating payment to an agent.
+    pub checkout_session_id: Option<Uuid>,
+    /// Defaults to `single_use_ttl_secs` for single-use tokens and to the card's expiry
+    /// for reusable ones.
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// A newly issued token. `token` is returned here only; the vault keeps its hash.
+#[derive(Debug, Clone, Serialize)]
+pub struct IssuedToken {
+    pub token: String,
+    #[serde(flatten)]
+    pub record: vault_token::Model,
+}
+
+/// One use of a token: the charge it is about to back and what is charging it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Redemption {
+    pub amount: Decimal,
+    pub currency: String,
+    pub checkout_session_id: Option<Uuid>,
+    /// E.g. `checkout:<id>` or `subscription:<id>`.
+    pub used_by: String,
+}
+
+/// Generates a new token of the form `vt_<secret>`.
+pub fn generate_token() -> String {
+    let secret: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(40)
+        .map(char::from)
+        .collect();
+    format!("{}_{}", TOKEN_PREFIX, secret)
+}
+
+/// The first instant a card with this expiry can no longer be charged.
+pub fn card_expiry(exp_month: Option<i32>, exp_year: Option<i32>) -> Option<DateTime<Utc>> {
+    let (month, year) = (u32::try_from(exp_month?).ok()?, exp_year?);
+    let (year, month) = if month >= 12 { (year + 1, 1) } else { (year, month + 1) };
+    Utc.with_ymd_and_hms(year, month, 1, 0, 0, 0).single()
+}
+
+/// The token as callers see it; active tokens past their expiry read as expired.
+pub fn effective(mut token: vault_token::Model, now: DateTime<Utc>) -> vault_token::Model {
+    if token.status == TokenStatus::Active && token.expires_at.is_some_and(|at| at <= now) {
+        token.status = TokenStatus::Expired;
+    }
+    token
+}
+
+/// Why `token` cannot back `redemption`, if it cannot.
+pub fn check_redemption(token: &vault_token::Model, redemption: &Redemption, now: DateTime<Utc>) -> Result<(), String> {
+    let status = effective(token.clone(), now).status;
+    if status != TokenStatus::Active {
+        return Err(format!("Vault token {} is {:?}", token.token_prefix, status));
+    }
+    if token.usage == TokenUsage::SingleUse && token.last_used_at.is_some() {
+        return Err(format!("Vault token {} has already been used", token.token_prefix));
+    }
+    if let Some(session) = token.checkout_session_id {
+        if redemption.checkout_session_id != Some(session) {
+            return Err(format!("Vault token {} is restricted to another checkout", token.token_prefix));
+        }
+    }
+    if let Some(currency) = &token.currency {
+        if !currency.eq_ignore_ascii_case(&redemption.currency) {
+            return Err(format!("Vault token {} is for {} payments", token.token_prefix, currency));
+        }
+    }
+    if let Some(max) = token.max_amount {
+        if redemption.amount > max {
+            return Err(format!(
+                "Amount {} exceeds the {} allowed by vault token {}",
+                redemption.amount, max, token.token_prefix
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// True when a single-use token was consumed by `used_by` itself, so a retry of that use
+/// (e.g. a checkout completed again after a conflict) may have a fresh token issued.
+pub fn redeemed_by(token: &vault_token::Model, used_by: &str) -> bool {
+    token.status == TokenStatus::Consumed && token.last_used_by.as_deref() == Some(used_by)
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Payment vault query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+fn gateway_error(e: GatewayError) -> ServiceError {
+    match e {
+        GatewayError::Declined(msg) => ServiceError::ValidationError(format!("Payment method rejected: {}", msg)),
+        e => ServiceError::InvalidOperation(e.to_string()),
+    }
+}
+
+/// Issues opaque tokens for payment methods the processor holds, so checkout, agents and
+/// subscription billing pass tokens around instead of processor ids and nothing here
+/// touches card data. Tokens are single-use or reusable, may carry an amount cap and a
+/// checkout restriction, and expire no later than the card.
+pub struct PaymentVaultService {
+    db_pool: Arc<DbPool>,
+    gateway: Arc<dyn PaymentGateway>,
+    config: PaymentVaultConfig,
+}
+
+impl PaymentVaultService {
+    pub fn new(db_pool: Arc<DbPool>, gateway: Arc<dyn PaymentGateway>, config: PaymentVaultConfig) -> Self {
+        Self { db_pool, gateway, config }
+    }
+
+    /// Verifies the payment method with the processor and issues a token for it.
+    #[instrument(skip(self, input))]
+    pub async fn tokenize(&self, input: NewVaultToken, created_by: String) -> Result<IssuedToken, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid vault token: {}", e)))?;
+        if input.max_amount.is_some() && input.currency.is_none() {
+            return Err(ServiceError::ValidationError("max_amount requires a currency".to_string()));
+        }
+        let now = Utc::now();
+        let latest = now + Duration::days(self.config.max_ttl_days);
+        if input.expires_at.is_some_and(|at| at <= now || at > latest) {
+            return Err(ServiceError::ValidationError(format!(
+                "expires_at must be in the next {} days",
+                self.config.max_ttl_days
+            )));
+        }
+
+        let details = self
+            .gateway
+            .payment_method(&input.payment_method_ref)
+            .await
+            .map_err(gateway_error)?;
+        let card_expires = card_expiry(details.exp_month, details.exp_year);
+        if card_expires.is_some_and(|at| at <= now) {
+            return Err(ServiceError::ValidationError("The card has expired".to_string()));
+        }
+        let requested = input.expires_at.or(match input.usage {
+            TokenUsage::SingleUse => Some(now + Duration::seconds(self.config.single_use_ttl_secs)),
+            TokenUsage::Reusable => None,
+        });
+        let expires_at = match (requested, card_expires) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        let token = generate_token();
+        let record = vault_token::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            token_hash: Set(hash_secret(&token)),
+            token_prefix: Set(token.chars().take(TOKEN_PREFIX.len() + 7).collect()),
+            provider: Set(self.gateway.name().to_string()),
+            payment_method_ref: Set(details.payment_method_ref),
+            customer_ref: Set(details.customer_ref),
+            brand: Set(details.brand),
+            last4: Set(details.last4),
+            exp_month: Set(details.exp_month),
+            exp_year: Set(details.exp_year),
+            usage: Set(input.usage),
+            status: Set(TokenStatus::Active),
+            max_amount: Set(input.max_amount),
+            currency: Set(input.currency.map(|c| c.to_uppercase())),
+            checkout_session_id: Set(input.checkout_session_id),
+            last_used_by: Set(None),
+            last_used_at: Set(None),
+            created_by: Set(created_by),
+            created_at: Set(now),
+            updated_at: Set(now),
+            expires_at: Set(expires_at),
+        }
+        .insert(self.db_pool.as_ref())
+        .await
+        .map_err(db_error)?;
+        info!(token_id = %record.id, usage = ?record.usage, "Payment vault token issued");
+        Ok(IssuedToken { token, record })
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<vault_token::Model, ServiceError> {
+        VaultToken::find_by_id(id)
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .map(|t| effective(t, Utc::now()))
+            .ok_or_else(|| ServiceError::NotFound(format!("Vault token not found: {}", id)))
+    }
+
+    /// Resolves a token presented by a client to its record. Unknown tokens are reported
+    /// as not found without echoing them back.
+    pub async fn lookup(&self, token: &str) -> Result<vault_token::Model, ServiceError> {
+        VaultToken::find()
+            .filter(vault_token::Column::TokenHash.eq(hash_secret(token.trim())))
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .map(|t| effective(t, Utc::now()))
+            .ok_or_else(|| ServiceError::NotFound("Vault token not found".to_string()))
+    }
+
+    /// Checks the token can back `redemption` and records the use. Single-use tokens are
+    /// consumed atomically, so two checkouts racing for one token cannot both succeed, and
+    /// are never redeemed again, whoever asks.
+    #[instrument(skip(self, redemption), fields(used_by = %redemption.used_by))]
+    pub async fn redeem(&self, id: Uuid, redemption: Redemption) -> Result<vault_token::Model, ServiceError> {
+        let token = self.get(id).await?;
+        let now = Utc::now();
+        check_redemption(&token, &redemption, now).map_err(ServiceError::ValidationError)?;
+        let status = match token.usage {
+            TokenUsage::SingleUse => TokenStatus::Consumed,
+            TokenUsage::Reusable => TokenStatus::Active,
+        };
+        let result = VaultToken::update_many()
+            .col_expr(vault_token::Column::Status, Expr::value(status))
+            .col_expr(vault_token::Column::LastUsedBy, Expr::value(Some(redemption.used_by.clone())))
+            .col_expr(vault_token::Column::LastUsedAt, Expr::value(Some(now)))
+            .col_expr(vault_token::Column::UpdatedAt, Expr::value(now))
+            .filter(vault_token::Column::Id.eq(id))
+            .filter(vault_token::Column::Status.eq(TokenStatus::Active))
+            .exec(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?;
+        if result.rows_affected == 0 {
+            return Err(ServiceError::ValidationError(format!(
+                "Vault token {} was used concurrently",
+                token.token_prefix
+            )));
+        }
+        info!(token_id = %id, amount = %redemption.amount, "Payment vault token redeemed");
+        Ok(vault_token::Model {
+            status,
+            last_used_by: Some(redemption.used_by),
+            last_used_at: Some(now),
+            updated_at: now,
+            ..token
+        })
+    }
+
+    /// Redeems a token for a use that may be a retry. Once `redemption.used_by` consumed a
+    /// single-use token, a fresh token for the same payment method, with the same
+    /// restrictions and expiry, is issued and redeemed in its place. Returns the token
+    /// redeemed, which callers record instead of the one they were given.
+    pub async fn redeem_retry(&self, id: Uuid, redemption: Redemption) -> Result<vault_token::Model, ServiceError> {
+        let token = self.get(id).await?;
+        if !redeemed_by(&token, &redemption.used_by) {
+            return self.redeem(id, redemption).await;
+        }
+        let fresh = self.reissue(&token).await?;
+        info!(token_id = %id, fresh_token_id = %fresh.id, "Payment vault token reissued for a retried use");
+        self.redeem(fresh.id, redemption).await
+    }
+
+    /// A new active token copying a consumed one. Its secret is never shown: it is only
+    /// redeemed by id.
+    async fn reissue(&self, token: &vault_token::Model) -> Result<vault_token::Model, ServiceError> {
+        let secret = generate_token();
+        let now = Utc::now();
+        vault_token::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            token_hash: Set(hash_secret(&secret)),
+            token_prefix: Set(secret.chars().take(TOKEN_PREFIX.len() + 7).collect()),
+            provider: Set(token.provider.clone()),
+            payment_method_ref: Set(token.payment_method_ref.clone()),
+            customer_ref: Set(token.customer_ref.clone()),
+            brand: Set(token.brand.clone()),
+            last4: Set(token.last4.clone()),
+            exp_month: Set(token.exp_month),
+            exp_year: Set(token.exp_year),
+            usage: Set(token.usage),
+            status: Set(TokenStatus::Active),
+            max_amount: Set(token.max_amount),
+            currency: Set(token.currency.clone()),
+            checkout_session_id: Set(token.checkout_session_id),
+            last_used_by: Set(None),
+            last_used_at: Set(None),
+            created_by: Set(token.created_by.clone()),
+            created_at: Set(now),
+            updated_at: Set(now),
+            expires_at: Set(token.expires_at),
+        }
+        .insert(self.db_pool.as_ref())
+        .await
+        .map_err(db_error)
+    }
+
+    /// Revokes a token. With `detach`, the payment method is also detached at the
+    /// processor, so nothing else can charge it either.
+    pub async fn revoke(&self, id: Uuid, detach: bool) -> Result<vault_token::Model, ServiceError> {
+        let token = self.get(id).await?;
+        if token.status != TokenStatus::Active {
+            return Err(ServiceError::InvalidOperation(format!("Vault token is {:?}", token.status)));
+        }
+        if detach {
+            self.gateway
+                .detach_payment_method(&token.payment_method_ref)
+                .await
+                .map_err(gateway_error)?;
+        }
+        let mut active: vault_token::ActiveModel = token.into();
+        active.status = Set(TokenStatus::Revoked);
+        active.updated_at = Set(Utc::now());
+        let token = active.update(self.db_pool.as_ref()).await.map_err(db_error)?;
+        warn!(token_id = %id, detach, "Payment vault token revoked");
+        Ok(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn token(now: DateTime<Utc>) -> vault_token::Model {
+        vault_token::Model {
+            id: Uuid::new_v4(),
+            token_hash: hash_secret("vt_secret"),
+            token_prefix: "vt_secr".to_string(),
+            provider: "stripe".to_string(),
+            payment_method_ref: "pm_123".to_string(),
+            customer_ref: None,
+            brand: Some("visa".to_string()),
+            last4: Some("4242".to_string()),
+            exp_month: Some(8),
+            exp_year: Some(2028),
+            usage: TokenUsage::SingleUse,
+            status: TokenStatus::Active,
+            max_amount: Some(dec!(100.00)),
+            currency: Some("USD".to_string()),
+            checkout_session_id: None,
+            last_used_by: None,
+            last_used_at: None,
+            created_by: "user:1".to_string(),
+            created_at: now,
+            updated_at: now,
+            expires_at: Some(now + Duration::hours(1)),
+        }
+    }
+
+    fn redemption(amount: Decimal) -> Redemption {
+        Redemption {
+            amount,
+            currency: "usd".to_string(),
+            checkout_session_id: None,
+            used_by: "checkout:1".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_generated_tokens_are_prefixed_and_unique() {
+        let (a, b) = (generate_token(), generate_token());
+        assert!(a.starts_with("vt_"));
+        assert_eq!(a.len(), 43);
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_card_expiry_is_the_end_of_the_expiry_month() {
+        let expected = Utc.with_ymd_and_hms(2028, 9, 1, 0, 0, 0).unwrap();
+        assert_eq!(card_expiry(Some(8), Some(2028)), Some(expected));
+        let expected = Utc.with_ymd_and_hms(2029, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!(card_expiry(Some(12), Some(2028)), Some(expected));
+        assert_eq!(card_expiry(None, Some(2028)), None);
+    }
+
+    #[test]
+    fn test_redemption_respects_cap_currency_and_expiry() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let t = token(now);
+        assert!(check_redemption(&t, &redemption(dec!(100.00)), now).is_ok());
+        assert!(check_redemption(&t, &redemption(dec!(100.01)), now).is_err());
+
+        let mut euros = redemption(dec!(10));
+        euros.currency = "EUR".to_string();
+        assert!(check_redemption(&t, &euros, now).is_err());
+
+        let later = now + Duration::hours(2);
+        assert!(check_redemption(&t, &redemption(dec!(10)), later).unwrap_err().contains("Expired"));
+    }
+
+    #[test]
+    fn test_session_bound_tokens_only_redeem_for_their_checkout() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let session = Uuid::new_v4();
+        let mut t = token(now);
+        t.checkout_session_id = Some(session);
+        assert!(check_redemption(&t, &redemption(dec!(10)), now).is_err());
+
+        let mut bound = redemption(dec!(10));
+        bound.checkout_session_id = Some(session);
+        assert!(check_redemption(&t, &bound, now).is_ok());
+
+        t.status = TokenStatus::Consumed;
+        assert!(check_redemption(&t, &bound, now).is_err());
+    }
+
+    #[test]
+    fn test_single_use_tokens_are_never_redeemed_twice() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let mut t = token(now);
+        t.last_used_by = Some("checkout:1".to_string());
+        t.last_used_at = Some(now);
+        let mut retry = redemption(dec!(10));
+        retry.used_by = "checkout:1".to_string();
+        assert!(check_redemption(&t, &retry, now).unwrap_err().contains("already been used"));
+
+        t.usage = TokenUsage::Reusable;
+        assert!(check_redemption(&t, &retry, now).is_ok());
+    }
+
+    #[test]
+    fn test_only_the_consuming_use_gets_a_fresh_token() {
+        let now = Utc.with_ymd_and_hms(2026, 10, 16, 12, 0, 0).unwrap();
+        let mut t = token(now);
+        assert!(!redeemed_by(&t, "checkout:1"));
+        t.status = TokenStatus::Consumed;
+        t.last_used_by = Some("checkout:1".to_string());
+        assert!(redeemed_by(&t, "checkout:1"));
+        assert!(!redeemed_by(&t, "checkout:2"));
+    }
+
+    #[test]
+    fn test_migration_is_safe() {
+        let plan = crate::db::migration_safety::analyze(
+            "20261016170000_payment_vault.sql",
+            include_str!("../../migrations/20261016170000_payment_vault.sql"),
+        );
+        assert_eq!(plan.violations().count(), 0);
+    }
+}
@@ -0,0 +1,640 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use tracing::{error, info, instrument, warn};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::{
+    db::{dialect, DbPool},
+    encryption::EncryptedText,
+    errors::ServiceError,
+    events::{Event, EventSender},
+    models::{
+        order::{self, DeliveryType, Entity as Order, FulfillmentType, OrderStatus},
+        pos_payment::{self, Entity as PosPayment, Tender},
+        pos_sale::{self, Entity as PosSale},
+        pos_shift::{self, Entity as PosShift, PosShiftStatus},
+    },
+    utils::pagination::PaginationParams,
+};
+
+/// Most sales a register may replay in one request.
+pub const MAX_BATCH: usize = 200;
+
+const WALK_IN_CUSTOMER: &str = "Walk-in customer";
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct OpenShift {
+    /// Generated by the register; opening the same shift again returns it unchanged.
+    pub id: Uuid,
+    #[validate(length(min = 1, max = 64))]
+    pub register_id: String,
+    pub warehouse_id: Uuid,
+    #[validate(length(min = 1, max = 100))]
+    pub cashier: String,
+    #[serde(with = "crate::money::amount")]
+    #[validate(custom = "crate::money::validate_non_negative")]
+    pub opening_float: Decimal,
+    /// Register clock time; now when omitted.
+    pub opened_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct CloseShift {
+    #[serde(with = "crate::money::amount")]
+    #[validate(custom = "crate::money::validate_non_negative")]
+    pub counted_cash: Decimal,
+    /// Register clock time; now when omitted. Offline sales rung up before it still count.
+    pub closed_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SaleLine {
+    #[validate(length(min = 1, max = 64))]
+    pub sku: String,
+    #[validate(length(max = 200))]
+    pub name: Option<String>,
+    #[validate(range(min = 1))]
+    pub quantity: i32,
+    #[serde(with = "crate::money::amount")]
+    #[validate(custom = "crate::money::validate_non_negative")]
+    pub unit_price: Decimal,
+    /// Discount on the whole line.
+    #[serde(default, with = "crate::money::amount")]
+    #[validate(custom = "crate::money::validate_non_negative")]
+    pub discount: Decimal,
+}
+
+impl SaleLine {
+    fn total(&self) -> Decimal {
+        self.unit_price * Decimal::from(self.quantity) - self.discount
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct SalePayment {
+    pub tender: Tender,
+    #[serde(with = "crate::money::amount")]
+    #[validate(custom = "crate::money::validate_positive")]
+    pub amount: Decimal,
+    /// Cash handed over, for cash tenders that gave change.
+    #[serde(default, with = "crate::money::option_amount")]
+    pub tendered: Option<Decimal>,
+    #[validate(length(max = 100))]
+    pub reference: Option<String>,
+}
+
+impl SalePayment {
+    /// Change handed back for a cash tender.
+    pub fn change(&self) -> Option<Decimal> {
+        match self.tender {
+            Tender::Cash => Some(self.tendered.unwrap_or(self.amount) - self.amount),
+            _ => None,
+        }
+    }
+}
+
+/// A sale as rung up on the register, possibly while offline.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_sale"))]
+pub struct PosSaleInput {
+    /// Generated by the register; becomes the order id.
+    pub id: Uuid,
+    pub shift_id: Uuid,
+    pub sold_at: DateTime<Utc>,
+    #[serde(default)]
+    pub offline: bool,
+    #[validate(length(equal = 3))]
+    pub currency: String,
+    #[validate(length(max = 200))]
+    pub customer_name: Option<String>,
+    #[validate(email)]
+    pub customer_email: Option<String>,
+    #[validate(length(min = 1, max = 200))]
+    #[validate]
+    pub lines: Vec<SaleLine>,
+    #[validate(length(min = 1, max = 10))]
+    #[validate]
+    pub payments: Vec<SalePayment>,
+}
+
+fn validate_sale(sale: &PosSaleInput) -> Result<(), ValidationError> {
+    if sale.lines.iter().any(|l| l.total() < Decimal::ZERO) {
+        return Err(ValidationError::new("discount_exceeds_line"));
+    }
+    if sale.payments.iter().any(|p| match p.tender {
+        Tender::Cash => p.tendered.is_some_and(|t| t < p.amount),
+        _ => p.tendered.is_some(),
+    }) {
+        return Err(ValidationError::new("invalid_tendered_amount"));
+    }
+    if sale_total(&sale.lines) != sale.payments.iter().map(|p| p.amount).sum::<Decimal>() {
+        return Err(ValidationError::new("payments_do_not_match_total"));
+    }
+    Ok(())
+}
+
+pub fn sale_total(lines: &[SaleLine]) -> Decimal {
+    lines.iter().map(SaleLine::total).sum()
+}
+
+/// Cash the sale leaves in the drawer: cash tenders net of change.
+pub fn cash_taken(payments: &[SalePayment]) -> Decimal {
+    payments.iter().filter(|p| p.tender == Tender::Cash).map(|p| p.amount).sum()
+}
+
+/// Counted cash less what the drawer should hold; negative when short.
+pub fn cash_variance(opening_float: Decimal, cash_sales: Decimal, counted: Decimal) -> Decimal {
+    counted - opening_float - cash_sales
+}
+
+/// Identifies a sale's contents, so a replay can be told apart from a different sale sent
+/// under the same id. Amounts are normalized so `5.0` and `5.00` hash alike.
+pub fn fingerprint(sale: &PosSaleInput) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(format!(
+        "{}|{}|{}|{}\n",
+        sale.shift_id,
+        sale.sold_at.timestamp_millis(),
+        sale.currency.to_uppercase(),
+        sale.customer_email.as_deref().unwrap_or_default()
+    ));
+    for line in &sale.lines {
+        hasher.update(format!(
+            "L|{}|{}|{}|{}\n",
+            line.sku,
+            line.quantity,
+            line.unit_price.normalize(),
+            line.discount.normalize()
+        ));
+    }
+    for payment in &sale.payments {
+        let tendered = payment.tendered.map(|t| t.normalize().to_string()).unwrap_or_default();
+        hasher.update(format!("P|{:?}|{}|{}\n", payment.tender, payment.amount.normalize(), tendered));
+    }
+    hex::encode(hasher.finalize())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SaleOutcome {
+    Created,
+    /// Sent before with the same contents; the existing order stands.
+    Duplicate,
+    /// Sent before with different contents. The first version is kept and the conflict
+    /// recorded on the sale for review.
+    Conflict,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SaleResult {
+    pub id: Uuid,
+    pub outcome: SaleOutcome,
+    pub order_number: Option<String>,
+    pub error: Option<String>,
+}
+
+impl SaleResult {
+    fn accepted(id: Uuid, outcome: SaleOutcome, order_number: String) -> Self {
+        Self { id, outcome, order_number: Some(order_number), error: None }
+    }
+
+    fn refused(id: Uuid, outcome: SaleOutcome, error: String) -> Self {
+        Self { id, outcome, order_number: None, error: Some(error) }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ShiftFilter {
+    pub register_id: Option<String>,
+    pub status: Option<PosShiftStatus>,
+}
+
+/// A shift with the sales and tenders received for it so far.
+#[derive(Debug, Clone, Serialize)]
+pub struct ShiftSummary {
+    #[serde(flatten)]
+    pub shift: pos_shift::Model,
+    pub conflicts: u64,
+    pub tenders: Vec<TenderTotal>,
+}
+
+#[derive(Debug, Clone, Serialize, FromQueryResult)]
+pub struct TenderTotal {
+    pub tender: String,
+    pub payments: i64,
+    #[serde(with = "crate::money::amount")]
+    pub amount: Decimal,
+}
+
+const TENDER_TOTALS_SQL: &str = r#"
+SELECT tender, COUNT(*) AS payments, SUM(amount) AS amount
+FROM pos_payments
+WHERE shift_id = $1
+GROUP BY tender
+ORDER BY tender
+"#;
+
+const INSERT_LINE_SQL: &str = r#"
+INSERT INTO order_line_items
+    (id, order_id, product_name, quantity, sale_price, original_price, seller_discount, unit,
+     product_id, brand, stock_code, size, seller_sku, sku_id, sku_image, sku_name, sku_type,
+     dropship, status, created_date)
+VALUES ($1, $2, $3, $4, $5, $6, $7, 'pcs', $8, '', '', '', $8, $8, '', $3, 'pos', FALSE, 'Delivered', $9)
+"#;
+
+fn cents(amount: Decimal) -> i32 {
+    (amount * Decimal::ONE_HUNDRED).round().to_i32().unwrap_or(0)
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("POS query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Order intake for store registers. Registers keep selling offline and replay their
+/// queued sales later; sales carry their own ids so a replay is always safe.
+pub struct PosService {
+    db_pool: Arc<DbPool>,
+    events: EventSender,
+}
+
+impl PosService {
+    pub fn new(db_pool: Arc<DbPool>, events: EventSender) -> Self {
+        Self { db_pool, events }
+    }
+
+    #[instrument(skip(self, input), fields(shift_id = %input.id))]
+    pub async fn open_shift(&self, input: OpenShift) -> Result<pos_shift::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid shift: {}", e)))?;
+        let db = self.db_pool.as_ref();
+        if let Some(existing) = PosShift::find_by_id(input.id).one(db).await.map_err(db_error)? {
+            if existing.register_id != input.register_id {
+                return Err(ServiceError::ValidationError(format!(
+                    "Shift {} belongs to register {}",
+                    input.id, existing.register_id
+                )));
+            }
+            return Ok(existing);
+        }
+        let now = Utc::now();
+        let shift = pos_shift::ActiveModel {
+            id: Set(input.id),
+            register_id: Set(input.register_id),
+            warehouse_id: Set(input.warehouse_id),
+            cashier: Set(input.cashier),
+            status: Set(PosShiftStatus::Open),
+            opening_float: Set(input.opening_float),
+            cash_sales: Set(Decimal::ZERO),
+            counted_cash: Set(None),
+            cash_variance: Set(None),
+            sales_count: Set(0),
+            opened_at: Set(input.opened_at.unwrap_or(now)),
+            closed_at: Set(None),
+            closed_by: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(db)
+        .await
+        .map_err(db_error)?;
+        info!(register_id = %shift.register_id, cashier = %shift.cashier, "POS shift opened");
+        Ok(shift)
+    }
+
+    /// Records the drawer count. Closing again replaces the count, e.g. after a recount.
+    #[instrument(skip(self, input))]
+    pub async fn close_shift(&self, id: Uuid, input: CloseShift, actor: &str) -> Result<pos_shift::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid shift close: {}", e)))?;
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let shift = PosShift::find_by_id(id)
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("POS shift {} not found", id)))?;
+        let closed_at = input.closed_at.unwrap_or_else(Utc::now);
+        if closed_at < shift.opened_at {
+            return Err(ServiceError::ValidationError("A shift cannot close before it opened".to_string()));
+        }
+        let variance = cash_variance(shift.opening_float, shift.cash_sales, input.counted_cash);
+        let mut active: pos_shift::ActiveModel = shift.into();
+        active.status = Set(PosShiftStatus::Closed);
+        active.counted_cash = Set(Some(input.counted_cash));
+        active.cash_variance = Set(Some(variance));
+        active.closed_at = Set(Some(closed_at));
+        active.closed_by = Set(Some(actor.to_string()));
+        active.updated_at = Set(Utc::now());
+        let shift = active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+        if !variance.is_zero() {
+            warn!(shift_id = %id, %variance, "POS drawer count does not match expected cash");
+        }
+        Ok(shift)
+    }
+
+    pub async fn shift(&self, id: Uuid) -> Result<ShiftSummary, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let shift = PosShift::find_by_id(id)
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("POS shift {} not found", id)))?;
+        let conflicts = PosSale::find()
+            .filter(pos_sale::Column::ShiftId.eq(id))
+            .filter(pos_sale::Column::ConflictFingerprint.is_not_null())
+            .count(db)
+            .await
+            .map_err(db_error)?;
+        let tenders = TenderTotal::find_by_statement(dialect::statement(
+            db,
+            TENDER_TOTALS_SQL,
+            [id.into()],
+        ))
+        .all(db)
+        .await
+        .map_err(db_error)?;
+        Ok(ShiftSummary { shift, conflicts, tenders })
+    }
+
+    pub async fn list_shifts(
+        &self,
+        filter: ShiftFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<pos_shift::Model>, u64), ServiceError> {
+        let mut query = PosShift::find();
+        if let Some(register_id) = filter.register_id {
+            query = query.filter(pos_shift::Column::RegisterId.eq(register_id));
+        }
+        if let Some(status) = filter.status {
+            query = query.filter(pos_shift::Column::Status.eq(status));
+        }
+        let paginator = query
+            .order_by_desc(pos_shift::Column::OpenedAt)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let items = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((items, total))
+    }
+
+    /// Sales of a shift that were replayed with different contents.
+    pub async fn conflicts(&self, shift_id: Uuid) -> Result<Vec<pos_sale::Model>, ServiceError> {
+        PosSale::find()
+            .filter(pos_sale::Column::ShiftId.eq(shift_id))
+            .filter(pos_sale::Column::ConflictFingerprint.is_not_null())
+            .order_by_asc(pos_sale::Column::SoldAt)
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)
+    }
+
+    pub async fn payments(&self, sale_id: Uuid) -> Result<Vec<pos_payment::Model>, ServiceError> {
+        PosPayment::find()
+            .filter(pos_payment::Column::SaleId.eq(sale_id))
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)
+    }
+
+    /// Takes a register's queued sales in the order they were rung up. Each sale is
+    /// committed on its own, so a failed request can simply be replayed in full.
+    #[instrument(skip(self, sales), fields(count = sales.len()))]
+    pub async fn submit_sales(&self, register_id: &str, mut sales: Vec<PosSaleInput>) -> Result<Vec<SaleResult>, ServiceError> {
+        if sales.len() > MAX_BATCH {
+            return Err(ServiceError::ValidationError(format!("At most {} sales per batch", MAX_BATCH)));
+        }
+        sales.sort_by_key(|s| s.sold_at);
+        let mut results = Vec::with_capacity(sales.len());
+        for sale in sales {
+            results.push(self.submit_sale(register_id, sale).await?);
+        }
+        Ok(results)
+    }
+
+    async fn submit_sale(&self, register_id: &str, sale: PosSaleInput) -> Result<SaleResult, ServiceError> {
+        if let Err(e) = sale.validate() {
+            return Ok(SaleResult::refused(sale.id, SaleOutcome::Rejected, format!("Invalid sale: {}", e)));
+        }
+        let fingerprint = fingerprint(&sale);
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+
+        if let Some(existing) = PosSale::find_by_id(sale.id).lock_exclusive().one(&txn).await.map_err(db_error)? {
+            let order_number = Order::find_by_id(sale.id)
+                .one(&txn)
+                .await
+                .map_err(db_error)?
+                .map(|o| o.order_number)
+                .unwrap_or_default();
+            let same = existing.fingerprint == fingerprint;
+            let replays = existing.replays + 1;
+            let mut active: pos_sale::ActiveModel = existing.into();
+            active.replays = Set(replays);
+            if !same {
+                active.conflict_fingerprint = Set(Some(fingerprint));
+                active.last_conflict_at = Set(Some(Utc::now()));
+            }
+            active.update(&txn).await.map_err(db_error)?;
+            txn.commit().await.map_err(db_error)?;
+            return Ok(if same {
+                SaleResult::accepted(sale.id, SaleOutcome::Duplicate, order_number)
+            } else {
+                warn!(sale_id = %sale.id, register_id, "POS sale replayed with different contents");
+                SaleResult {
+                    error: Some("Sale was already received with different contents; the first version is kept".to_string()),
+                    ..SaleResult::accepted(sale.id, SaleOutcome::Conflict, order_number)
+                }
+            });
+        }
+        if let Some(order) = Order::find_by_id(sale.id).one(&txn).await.map_err(db_error)? {
+            return Ok(SaleResult::refused(
+                sale.id,
+                SaleOutcome::Conflict,
+                format!("Id is already used by order {}", order.order_number),
+            ));
+        }
+
+        let Some(shift) = PosShift::find_by_id(sale.shift_id).lock_exclusive().one(&txn).await.map_err(db_error)? else {
+            return Ok(SaleResult::refused(sale.id, SaleOutcome::Rejected, format!("Shift {} is not open", sale.shift_id)));
+        };
+        if shift.register_id != register_id {
+            return Ok(SaleResult::refused(
+                sale.id,
+                SaleOutcome::Rejected,
+                format!("Shift {} belongs to register {}", shift.id, shift.register_id),
+            ));
+        }
+        if shift.closed_at.is_some_and(|closed| sale.sold_at > closed) {
+            return Ok(SaleResult::refused(
+                sale.id,
+                SaleOutcome::Rejected,
+                format!("Sale was rung up after shift {} closed", shift.id),
+            ));
+        }
+
+        let now = Utc::now();
+        let total = sale_total(&sale.lines);
+        let order = order::ActiveModel {
+            id: Set(sale.id),
+            order_number: Set(format!("POS-{}-{}", register_id, &sale.id.simple().to_string()[..8])),
+            customer_name: Set(sale.customer_name.clone().unwrap_or_else(|| WALK_IN_CUSTOMER.to_string()).into()),
+            customer_email: Set(sale.customer_email.clone().unwrap_or_default()),
+            delivery_address: Set(EncryptedText::default()),
+            notes: Set(None),
+            warehouse_id: Set(shift.warehouse_id),
+            order_status: Set(OrderStatus::Delivered),
+            fulfillment_type: Set(FulfillmentType::Standard),
+            delivery_type: Set(DeliveryType::Pickup),
+            is_cod: Set(false),
+            is_replacement_order: Set(false),
+            tracking_number: Set(None),
+            seller_note: Set(None),
+            source: Set(Some("pos".to_string())),
+            created_date: Set(sale.sold_at),
+            updated_date: Set(Some(now)),
+            delivery_date: Set(Some(sale.sold_at)),
+            cancel_order_sla_time: Set(None),
+            cancel_reason: Set(None),
+            cancellation_initiator: Set(None),
+        }
+        .insert(&txn)
+        .await
+        .map_err(db_error)?;
+
+        for line in &sale.lines {
+            let unit_discount = line.discount / Decimal::from(line.quantity);
+            txn.execute(dialect::statement(
+                &txn,
+                INSERT_LINE_SQL,
+                [
+                    Uuid::new_v4().into(),
+                    order.id.into(),
+                    line.name.clone().unwrap_or_else(|| line.sku.clone()).into(),
+                    line.quantity.into(),
+                    cents(line.unit_price - unit_discount).into(),
+                    cents(line.unit_price).into(),
+                    cents(unit_discount).into(),
+                    line.sku.clone().into(),
+                    sale.sold_at.into(),
+                ],
+            ))
+            .await
+            .map_err(db_error)?;
+        }
+        for payment in &sale.payments {
+            pos_payment::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                sale_id: Set(sale.id),
+                shift_id: Set(shift.id),
+                tender: Set(payment.tender),
+                amount: Set(payment.amount),
+                tendered: Set(payment.tendered),
+                change_given: Set(payment.change()),
+                reference: Set(payment.reference.clone().map(Into::into)),
+                created_at: Set(now),
+            }
+            .insert(&txn)
+            .await
+            .map_err(db_error)?;
+        }
+        pos_sale::ActiveModel {
+            id: Set(sale.id),
+            register_id: Set(register_id.to_string()),
+            shift_id: Set(shift.id),
+            cashier: Set(shift.cashier.clone()),
+            sold_at: Set(sale.sold_at),
+            total: Set(total),
+            currency: Set(sale.currency.to_uppercase()),
+            fingerprint: Set(fingerprint),
+            offline: Set(sale.offline),
+            replays: Set(0),
+            conflict_fingerprint: Set(None),
+            last_conflict_at: Set(None),
+            received_at: Set(now),
+        }
+        .insert(&txn)
+        .await
+        .map_err(db_error)?;
+
+        // Late offline sales of a closed shift move its expected cash, so the variance too
+        let cash_sales = shift.cash_sales + cash_taken(&sale.payments);
+        let variance = shift.counted_cash.map(|counted| cash_variance(shift.opening_float, cash_sales, counted));
+        let sales_count = shift.sales_count + 1;
+        let mut active: pos_shift::ActiveModel = shift.into();
+        active.cash_sales = Set(cash_sales);
+        active.cash_variance = Set(variance);
+        active.sales_count = Set(sales_count);
+        active.updated_at = Set(now);
+        active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+
+        let _ = self.events.send(Event::OrderCreated(order.id));
+        Ok(SaleResult::accepted(sale.id, SaleOutcome::Created, order.order_number))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use rust_decimal_macros::dec;
+
+    fn line(quantity: i32, unit_price: Decimal, discount: Decimal) -> SaleLine {
+        SaleLine { sku: "MUG".to_string(), name: None, quantity, unit_price, discount }
+    }
+
+    fn payment(tender: Tender, amount: Decimal, tendered: Option<Decimal>) -> SalePayment {
+        SalePayment { tender, amount, tendered, reference: None }
+    }
+
+    fn sale(lines: Vec<SaleLine>, payments: Vec<SalePayment>) -> PosSaleInput {
+        PosSaleInput {
+            id: Uuid::from_u128(7),
+            shift_id: Uuid::from_u128(1),
+            sold_at: Utc.with_ymd_and_hms(2026, 10, 3, 14, 30, 0).unwrap(),
+            offline: true,
+            currency: "USD".to_string(),
+            customer_name: None,
+            customer_email: None,
+            lines,
+            payments,
+        }
+    }
+
+    #[test]
+    fn test_payments_must_cover_the_total() {
+        let lines = vec![line(2, dec!(12.50), dec!(5)), line(1, dec!(3.25), Decimal::ZERO)];
+        assert_eq!(sale_total(&lines), dec!(23.25));
+        let split = vec![payment(Tender::Cash, dec!(10), Some(dec!(20))), payment(Tender::Card, dec!(13.25), None)];
+        assert!(sale(lines.clone(), split).validate().is_ok());
+        assert!(sale(lines.clone(), vec![payment(Tender::Card, dec!(20), None)]).validate().is_err());
+        assert!(sale(lines, vec![payment(Tender::Cash, dec!(23.25), Some(dec!(20)))]).validate().is_err());
+    }
+
+    #[test]
+    fn test_cash_change_and_drawer() {
+        let payments = vec![payment(Tender::Cash, dec!(17.40), Some(dec!(20))), payment(Tender::Card, dec!(5), None)];
+        assert_eq!(payments[0].change(), Some(dec!(2.60)));
+        assert_eq!(payments[1].change(), None);
+        assert_eq!(cash_taken(&payments), dec!(17.40));
+        assert_eq!(cash_variance(dec!(100), dec!(17.40), dec!(115.40)), dec!(-2.00));
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_amount_scale() {
+        let original = sale(vec![line(1, dec!(5.0), Decimal::ZERO)], vec![payment(Tender::Card, dec!(5), None)]);
+        let replay = sale(vec![line(1, dec!(5.00), Decimal::ZERO)], vec![payment(Tender::Card, dec!(5.00), None)]);
+        assert_eq!(fingerprint(&original), fingerprint(&replay));
+        let edited = sale(vec![line(2, dec!(2.50), Decimal::ZERO)], vec![payment(Tender::Card, dec!(5), None)]);
+        assert_ne!(fingerprint(&original), fingerprint(&edited));
+    }
+}
@@ -0,0 +1,549 @@
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::sea_query::LockBehavior;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::{error, info, instrument, warn};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db::{dialect, DbPool},
+    errors::ServiceError,
+    events::{Event, EventSender},
+    labels::{render_png, render_zpl, Label, LabelEntity, LabelError, LabelService, Media, Symbology},
+    models::{
+        print_job::{self, Entity as PrintJob, PrintJobStatus},
+        printer::{self, Entity as Printer, PrinterLanguage},
+    },
+    utils::pagination::PaginationParams,
+};
+
+/// How long an agent has to confirm a pulled job before it is handed out again.
+pub const CLAIM_LEASE_SECS: i64 = 120;
+/// Pulls without a confirmation before a job is failed instead of handed out again.
+pub const MAX_ATTEMPTS: i32 = 3;
+const MAX_PULL: u64 = 20;
+const SYSTEM_ACTOR: &str = "system";
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewPrinter {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(length(max = 100))]
+    pub station: Option<String>,
+    pub warehouse: Option<i32>,
+    pub language: PrinterLanguage,
+    #[serde(default)]
+    pub media: Media,
+    #[serde(default)]
+    pub auto_print_shipments: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdatePrinter {
+    #[validate(length(max = 100))]
+    pub station: Option<String>,
+    pub warehouse: Option<i32>,
+    pub media: Option<Media>,
+    pub auto_print_shipments: Option<bool>,
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewPrintJob {
+    pub entity: LabelEntity,
+    #[validate(length(min = 1, max = 128))]
+    pub id: String,
+    #[serde(default)]
+    pub format: Symbology,
+    #[serde(default = "default_copies")]
+    #[validate(range(min = 1, max = 50))]
+    pub copies: i32,
+}
+
+fn default_copies() -> i32 {
+    1
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PrintJobFilter {
+    pub printer_id: Option<Uuid>,
+    pub status: Option<PrintJobStatus>,
+}
+
+/// An agent's report on a job it pulled: `printed` or `failed`.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct JobResult {
+    pub status: PrintJobStatus,
+    #[validate(length(max = 1000))]
+    pub error: Option<String>,
+}
+
+/// Renders a label in the printer's language; returns the content type and bytes.
+pub fn render_for(
+    label: &Label,
+    language: PrinterLanguage,
+    symbology: Symbology,
+    media: Media,
+) -> Result<(&'static str, Vec<u8>), LabelError> {
+    Ok(match language {
+        PrinterLanguage::Zpl => ("application/zpl", render_zpl(label, symbology, media)?.into_bytes()),
+        PrinterLanguage::Png => ("image/png", render_png(label, symbology, media)?),
+    })
+}
+
+/// Whether an agent may take a job: it is queued, or a previous agent's claim lapsed.
+pub fn claimable(job: &print_job::Model, now: DateTime<Utc>) -> bool {
+    match job.status {
+        PrintJobStatus::Queued => true,
+        PrintJobStatus::Printing => job
+            .claimed_at
+            .map_or(true, |claimed| claimed + Duration::seconds(CLAIM_LEASE_SECS) <= now),
+        _ => false,
+    }
+}
+
+/// The printer with the fewest unfinished jobs, ties going to the first listed.
+pub fn least_loaded(printers: &[Uuid], open_jobs: &HashMap<Uuid, i64>) -> Option<Uuid> {
+    printers
+        .iter()
+        .copied()
+        .min_by_key(|id| open_jobs.get(id).copied().unwrap_or(0))
+}
+
+#[derive(Debug, FromQueryResult)]
+struct OpenJobs {
+    printer_id: Uuid,
+    jobs: i64,
+}
+
+const OPEN_JOBS_SQL: &str = r#"
+    SELECT printer_id, COUNT(*) AS jobs
+    FROM print_jobs
+    WHERE status IN ('queued', 'printing')
+    GROUP BY printer_id
+"#;
+
+fn label_error(e: LabelError) -> ServiceError {
+    match e {
+        LabelError::NotFound(what) => ServiceError::NotFound(format!("{} not found", what)),
+        LabelError::Invalid(msg) => ServiceError::ValidationError(msg),
+        LabelError::Render(msg) => ServiceError::ValidationError(format!("Label rendering failed: {}", msg)),
+        LabelError::Database(e) => db_error(e),
+    }
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Print queue query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Queues rendered labels per printer for print agents to pull. Agents poll
+/// `pull`, print, then report each job printed or failed.
+pub struct PrintService {
+    db_pool: Arc<DbPool>,
+    labels: LabelService,
+}
+
+impl PrintService {
+    pub fn new(db_pool: Arc<DbPool>) -> Self {
+        let labels = LabelService::new(db_pool.clone());
+        Self { db_pool, labels }
+    }
+
+    #[instrument(skip(self, input), fields(name = %input.name))]
+    pub async fn register_printer(&self, input: NewPrinter) -> Result<printer::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid printer: {}", e)))?;
+        let db = self.db_pool.as_ref();
+        let taken = Printer::find()
+            .filter(printer::Column::Name.eq(input.name.as_str()))
+            .count(db)
+            .await
+            .map_err(db_error)?;
+        if taken > 0 {
+            return Err(ServiceError::ValidationError(format!("Printer {} already exists", input.name)));
+        }
+        let now = Utc::now();
+        printer::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            name: Set(input.name),
+            station: Set(input.station),
+            warehouse: Set(input.warehouse),
+            language: Set(input.language),
+            media: Set(input.media.as_str().to_string()),
+            auto_print_shipments: Set(input.auto_print_shipments),
+            active: Set(true),
+            last_seen_at: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(db)
+        .await
+        .map_err(db_error)
+    }
+
+    pub async fn get_printer(&self, id: Uuid) -> Result<printer::Model, ServiceError> {
+        Printer::find_by_id(id)
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Printer {} not found", id)))
+    }
+
+    pub async fn list_printers(
+        &self,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<printer::Model>, u64), ServiceError> {
+        let paginator = Printer::find()
+            .order_by_asc(printer::Column::Name)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let items = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((items, total))
+    }
+
+    pub async fn update_printer(&self, id: Uuid, input: UpdatePrinter) -> Result<printer::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid printer: {}", e)))?;
+        let mut active: printer::ActiveModel = self.get_printer(id).await?.into();
+        if let Some(station) = input.station {
+            active.station = Set(Some(station));
+        }
+        if let Some(warehouse) = input.warehouse {
+            active.warehouse = Set(Some(warehouse));
+        }
+        if let Some(media) = input.media {
+            active.media = Set(media.as_str().to_string());
+        }
+        if let Some(auto_print) = input.auto_print_shipments {
+            active.auto_print_shipments = Set(auto_print);
+        }
+        if let Some(enabled) = input.active {
+            active.active = Set(enabled);
+        }
+        active.updated_at = Set(Utc::now());
+        active.update(self.db_pool.as_ref()).await.map_err(db_error)
+    }
+
+    /// Renders the label now, in the printer's language and media, and queues it.
+    #[instrument(skip(self, input), fields(entity = ?input.entity, id = %input.id))]
+    pub async fn enqueue(
+        &self,
+        printer_id: Uuid,
+        input: NewPrintJob,
+        actor: &str,
+    ) -> Result<print_job::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid print job: {}", e)))?;
+        let printer = self.get_printer(printer_id).await?;
+        if !printer.active {
+            return Err(ServiceError::ValidationError(format!("Printer {} is inactive", printer.name)));
+        }
+        let media = Media::parse(&printer.media).unwrap_or_default();
+        let label = self.labels.label(input.entity, &input.id).await.map_err(label_error)?;
+        let (content_type, content) =
+            render_for(&label, printer.language, input.format, media).map_err(label_error)?;
+
+        let now = Utc::now();
+        let job = print_job::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            printer_id: Set(printer.id),
+            status: Set(PrintJobStatus::Queued),
+            document_type: Set(input.entity.as_str().to_string()),
+            document_id: Set(input.id),
+            content_type: Set(content_type.to_string()),
+            content: Set(content),
+            copies: Set(input.copies),
+            attempts: Set(0),
+            error: Set(None),
+            created_by: Set(actor.to_string()),
+            claimed_at: Set(None),
+            completed_at: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(self.db_pool.as_ref())
+        .await
+        .map_err(db_error)?;
+        info!(job_id = %job.id, printer = %printer.name, "Print job queued");
+        Ok(job)
+    }
+
+    /// Queues the shipping label for a booked shipment on the least busy printer set to
+    /// print shipments. Returns `None` when no such printer is active.
+    pub async fn print_shipment_label(&self, shipment_id: i32) -> Result<Option<print_job::Model>, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let printers: Vec<Uuid> = Printer::find()
+            .filter(printer::Column::Active.eq(true))
+            .filter(printer::Column::AutoPrintShipments.eq(true))
+            .order_by_asc(printer::Column::Name)
+            .all(db)
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .map(|p| p.id)
+            .collect();
+        if printers.is_empty() {
+            warn!(shipment_id, "No printer set to print shipping labels");
+            return Ok(None);
+        }
+        let open_jobs: HashMap<Uuid, i64> =
+            OpenJobs::find_by_statement(dialect::raw(db, OPEN_JOBS_SQL))
+                .all(db)
+                .await
+                .map_err(db_error)?
+                .into_iter()
+                .map(|row| (row.printer_id, row.jobs))
+                .collect();
+        let Some(printer_id) = least_loaded(&printers, &open_jobs) else {
+            return Ok(None);
+        };
+        let job = NewPrintJob {
+            entity: LabelEntity::Shipment,
+            id: shipment_id.to_string(),
+            format: Symbology::Code128,
+            copies: 1,
+        };
+        self.enqueue(printer_id, job, SYSTEM_ACTOR).await.map(Some)
+    }
+
+    /// Hands up to `max` jobs to a printer's agent, oldest first. Jobs claimed by an agent
+    /// that never confirmed them are handed out again until `MAX_ATTEMPTS`, then failed.
+    #[instrument(skip(self))]
+    pub async fn pull(&self, printer_id: Uuid, max: u64) -> Result<Vec<print_job::Model>, ServiceError> {
+        let printer = self.get_printer(printer_id).await?;
+        let now = Utc::now();
+        let lapsed = now - Duration::seconds(CLAIM_LEASE_SECS);
+
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let candidates = PrintJob::find()
+            .filter(print_job::Column::PrinterId.eq(printer_id))
+            .filter(
+                Condition::any()
+                    .add(print_job::Column::Status.eq(PrintJobStatus::Queued))
+                    .add(
+                        Condition::all()
+                            .add(print_job::Column::Status.eq(PrintJobStatus::Printing))
+                            .add(print_job::Column::ClaimedAt.lte(lapsed)),
+                    ),
+            )
+            .order_by_asc(print_job::Column::CreatedAt)
+            .limit(max.clamp(1, MAX_PULL))
+            .lock_with_behavior(LockType::Update, LockBehavior::SkipLocked)
+            .all(&txn)
+            .await
+            .map_err(db_error)?;
+
+        let mut claimed = Vec::with_capacity(candidates.len());
+        for job in candidates.into_iter().filter(|job| claimable(job, now)) {
+            let attempts = job.attempts;
+            let mut active: print_job::ActiveModel = job.into();
+            if attempts >= MAX_ATTEMPTS {
+                active.status = Set(PrintJobStatus::Failed);
+                active.error = Set(Some(format!("Not confirmed after {} attempts", attempts)));
+                active.completed_at = Set(Some(now));
+                active.updated_at = Set(now);
+                active.update(&txn).await.map_err(db_error)?;
+                continue;
+            }
+            active.status = Set(PrintJobStatus::Printing);
+            active.attempts = Set(attempts + 1);
+            active.claimed_at = Set(Some(now));
+            active.updated_at = Set(now);
+            claimed.push(active.update(&txn).await.map_err(db_error)?);
+        }
+
+        let mut seen: printer::ActiveModel = printer.into();
+        seen.last_seen_at = Set(Some(now));
+        seen.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+        Ok(claimed)
+    }
+
+    pub async fn get_job(&self, id: Uuid) -> Result<print_job::Model, ServiceError> {
+        PrintJob::find_by_id(id)
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Print job {} not found", id)))
+    }
+
+    pub async fn list_jobs(
+        &self,
+        filter: PrintJobFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<print_job::Model>, u64), ServiceError> {
+        let mut query = PrintJob::find();
+        if let Some(printer_id) = filter.printer_id {
+            query = query.filter(print_job::Column::PrinterId.eq(printer_id));
+        }
+        if let Some(status) = filter.status {
+            query = query.filter(print_job::Column::Status.eq(status));
+        }
+        let paginator = query
+            .order_by_desc(print_job::Column::CreatedAt)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let items = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((items, total))
+    }
+
+    /// Records an agent's result for a job it pulled.
+    pub async fn report(&self, id: Uuid, result: JobResult) -> Result<print_job::Model, ServiceError> {
+        result
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid job result: {}", e)))?;
+        if !matches!(result.status, PrintJobStatus::Printed | PrintJobStatus::Failed) {
+            return Err(ServiceError::ValidationError("Status must be printed or failed".to_string()));
+        }
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let job = Self::find_locked(&txn, id).await?;
+        if job.status != PrintJobStatus::Printing {
+            return Err(ServiceError::ValidationError(format!("Print job is {:?}, not printing", job.status)));
+        }
+        let now = Utc::now();
+        let mut active: print_job::ActiveModel = job.into();
+        active.status = Set(result.status);
+        active.error = Set(result.error);
+        active.completed_at = Set(Some(now));
+        active.updated_at = Set(now);
+        let job = active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+        Ok(job)
+    }
+
+    pub async fn cancel(&self, id: Uuid) -> Result<print_job::Model, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let job = Self::find_locked(&txn, id).await?;
+        if job.status.is_finished() {
+            return Err(ServiceError::ValidationError(format!("Print job is already {:?}", job.status)));
+        }
+        let now = Utc::now();
+        let mut active: print_job::ActiveModel = job.into();
+        active.status = Set(PrintJobStatus::Cancelled);
+        active.completed_at = Set(Some(now));
+        active.updated_at = Set(now);
+        let job = active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+        Ok(job)
+    }
+
+    /// Queues a failed job again with the content it was rendered with.
+    pub async fn retry(&self, id: Uuid) -> Result<print_job::Model, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let job = Self::find_locked(&txn, id).await?;
+        if job.status != PrintJobStatus::Failed {
+            return Err(ServiceError::ValidationError("Only failed print jobs can be retried".to_string()));
+        }
+        let mut active: print_job::ActiveModel = job.into();
+        active.status = Set(PrintJobStatus::Queued);
+        active.attempts = Set(0);
+        active.error = Set(None);
+        active.claimed_at = Set(None);
+        active.completed_at = Set(None);
+        active.updated_at = Set(Utc::now());
+        let job = active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+        Ok(job)
+    }
+
+    async fn find_locked(txn: &DatabaseTransaction, id: Uuid) -> Result<print_job::Model, ServiceError> {
+        PrintJob::find_by_id(id)
+            .lock_exclusive()
+            .one(txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Print job {} not found", id)))
+    }
+}
+
+/// Queues shipping labels as shipments are booked with a carrier.
+pub fn spawn_shipment_print_listener(service: Arc<PrintService>, events: EventSender) {
+    let mut receiver = events.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(Event::ShipmentBooked { shipment_id, .. }) => {
+                    if let Err(e) = service.print_shipment_label(shipment_id).await {
+                        error!(shipment_id, "Shipping label print failed: {}", e);
+                    }
+                }
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!(missed, "Print listener lagged; some shipping labels need printing by hand");
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(status: PrintJobStatus, claimed_secs_ago: Option<i64>) -> print_job::Model {
+        let now = Utc::now();
+        print_job::Model {
+            id: Uuid::new_v4(),
+            printer_id: Uuid::new_v4(),
+            status,
+            document_type: "shipment".to_string(),
+            document_id: "42".to_string(),
+            content_type: "application/zpl".to_string(),
+            content: b"^XA^XZ".to_vec(),
+            copies: 1,
+            attempts: 1,
+            error: None,
+            created_by: SYSTEM_ACTOR.to_string(),
+            claimed_at: claimed_secs_ago.map(|secs| now - Duration::seconds(secs)),
+            completed_at: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    #[test]
+    fn test_claimable() {
+        let now = Utc::now();
+        assert!(claimable(&job(PrintJobStatus::Queued, None), now));
+        assert!(!claimable(&job(PrintJobStatus::Printing, Some(30)), now));
+        assert!(claimable(&job(PrintJobStatus::Printing, Some(CLAIM_LEASE_SECS + 1)), now));
+        assert!(!claimable(&job(PrintJobStatus::Printed, None), now));
+    }
+
+    #[test]
+    fn test_least_loaded_printer() {
+        let (a, b, c) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+        let open = HashMap::from([(a, 4), (b, 1)]);
+        assert_eq!(least_loaded(&[a, b, c], &open), Some(c));
+        assert_eq!(least_loaded(&[a, b], &open), Some(b));
+        assert_eq!(least_loaded(&[], &open), None);
+    }
+
+    #[test]
+    fn test_render_for_printer_language() {
+        let label = Label { title: "1Z999".to_string(), lines: Vec::new(), data: "1Z999".to_string() };
+        let (content_type, zpl) = render_for(&label, PrinterLanguage::Zpl, Symbology::Code128, Media::FourBySix).unwrap();
+        assert_eq!(content_type, "application/zpl");
+        assert!(zpl.starts_with(b"^XA"));
+        let (content_type, png) = render_for(&label, PrinterLanguage::Png, Symbology::Code128, Media::TwoByOne).unwrap();
+        assert_eq!(content_type, "image/png");
+        assert!(png.starts_with(b"\x89PNG"));
+    }
+
+    #[test]
+    fn test_job_content_is_not_serialized() {
+        let value = serde_json::to_value(job(PrintJobStatus::Queued, None)).unwrap();
+        assert!(value.get("content").is_none());
+        assert_eq!(value["status"], "queued");
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::Utc;
+use rust_decimal::Decimal;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, instrument};
+use validator::Validate;
+
+use crate::{
+    custom_fields::MetadataFilter,
+    db::DbPool,
+    errors::ServiceError,
+    events::{Event, EventSender},
+    models::product_listing::{self, Entity as ProductListing},
+    utils::pagination::PaginationParams,
+};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ListingFilter {
+    pub active: Option<bool>,
+}
+
+/// Listing details for a SKU, replaced as a whole on every write.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ListingInput {
+    #[validate(length(min = 1, max = 255))]
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub brand: Option<String>,
+    pub gtin: Option<String>,
+    pub item_group_id: Option<String>,
+    pub color: Option<String>,
+    pub size: Option<String>,
+    #[validate(custom = "crate::customs::validate_hs_code")]
+    pub hs_code: Option<String>,
+    #[validate(length(equal = 2))]
+    pub country_of_origin: Option<String>,
+    #[serde(with = "crate::money::amount")]
+    #[validate(custom = "crate::money::validate_non_negative")]
+    pub price: Decimal,
+    #[serde(default = "default_currency")]
+    #[validate(length(equal = 3))]
+    pub currency: String,
+    pub link: Option<String>,
+    pub image_link: Option<String>,
+    #[serde(default = "default_true")]
+    pub active: bool,
+    #[serde(default = "default_true")]
+    pub enable_checkout: bool,
+}
+
+fn default_currency() -> String {
+    "USD".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Product listing query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Writes to the sellable catalog. Every write emits `ProductListingChanged` so
+/// downstream indexes (semantic search, feeds) can refresh the SKU.
+pub struct ProductListingService {
+    db_pool: Arc<DbPool>,
+    events: EventSender,
+}
+
+impl ProductListingService {
+    pub fn new(db_pool: Arc<DbPool>, events: EventSender) -> Self {
+        Self { db_pool, events }
+    }
+
+    pub async fn get(&self, sku: &str) -> Result<product_listing::Model, ServiceError> {
+        ProductListing::find_by_id(sku.to_string())
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Product listing not found: {}", sku)))
+    }
+
+    /// Listings of the given SKUs, in no particular order; unknown SKUs are skipped.
+    pub async fn get_many(&self, skus: Vec<String>) -> Result<Vec<product_listing::Model>, ServiceError> {
+        ProductListing::find()
+            .filter(product_listing::Column::Sku.is_in(skus))
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)
+    }
+
+    /// One page of listings by SKU, and the total.
+    pub async fn list(
+        &self,
+        filter: ListingFilter,
+        metadata: &MetadataFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<product_listing::Model>, u64), ServiceError> {
+        let mut query = ProductListing::find();
+        if let Some(active) = filter.active {
+            query = query.filter(product_listing::Column::Active.eq(active));
+        }
+        if !metadata.is_empty() {
+            query = query.filter(metadata.condition("metadata"));
+        }
+        let paginator = query
+            .order_by_asc(product_listing::Column::Sku)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let items = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((items, total))
+    }
+
+    /// Creates or replaces the listing for `sku`.
+    #[instrument(skip(self, input))]
+    pub async fn upsert(&self, sku: &str, input: ListingInput) -> Result<product_listing::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid listing: {}", e)))?;
+
+        let now = Utc::now();
+        let existing = ProductListing::find_by_id(sku.to_string())
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?;
+        let listing = product_listing::ActiveModel {
+            sku: Set(sku.to_string()),
+            title: Set(input.title),
+            description: Set(input.description),
+            brand: Set(input.brand),
+            gtin: Set(input.gtin),
+            item_group_id: Set(input.item_group_id),
+            color: Set(input.color),
+            size: Set(input.size),
+            hs_code: Set(input.hs_code.map(|code| crate::customs::normalize_hs_code(&code))),
+            country_of_origin: Set(input.country_of_origin.map(|c| c.to_uppercase())),
+            price: Set(input.price),
+            currency: Set(input.currency.to_uppercase()),
+            link: Set(input.link),
+            image_link: Set(input.image_link),
+            active: Set(input.active),
+            enable_checkout: Set(input.enable_checkout),
+            metadata: Set(existing.as_ref().and_then(|l| l.metadata.clone())),
+            created_at: Set(existing.as_ref().map(|l| l.created_at).unwrap_or(now)),
+            updated_at: Set(now),
+        };
+        let saved = match existing {
+            Some(_) => listing.update(self.db_pool.as_ref()).await,
+            None => listing.insert(self.db_pool.as_ref()).await,
+        }
+        .map_err(db_error)?;
+
+        let _ = self.events.send(Event::ProductListingChanged(saved.sku.clone()));
+        Ok(saved)
+    }
+}
@@ -0,0 +1,655 @@
+use chrono::{NaiveDate, Utc};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tracing::{error, info, instrument, warn};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db::DbPool,
+    errors::ServiceError,
+    events::{Event, EventSender},
+    models::{
+        inspection_plan::{self, Entity as InspectionPlan, InspectionSource, SamplingRule},
+        asn::{self, Entity as Asn},
+        inventory_items::{self, Entity as InventoryItem},
+        purchase_order::{self, Entity as PurchaseOrder},
+        purchase_order_line::{self, Entity as PurchaseOrderLine},
+        quality_inspection::{self, Disposition, Entity as QualityInspection, InspectionStatus},
+        supplier_receipt,
+    },
+    utils::pagination::PaginationParams,
+};
+
+/// `quality_status` of inventory rows holding quarantined units. Those rows keep
+/// `available` at 0 and count the held units in `damaged_quantity`, so nothing can sell,
+/// reserve or pick them.
+pub const QUARANTINE_STATUS: &str = "quarantine";
+
+/// Quality settings, loaded from the `quality` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct QualityConfig {
+    /// `location_in_warehouse` of quarantine stock in every warehouse.
+    #[serde(default = "default_quarantine_location")]
+    pub quarantine_location: String,
+}
+
+fn default_quarantine_location() -> String {
+    "QUARANTINE".to_string()
+}
+
+impl Default for QualityConfig {
+    fn default() -> Self {
+        Self { quarantine_location: default_quarantine_location() }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewInspectionPlan {
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    pub source: InspectionSource,
+    pub sku: Option<String>,
+    pub sampling: SamplingRule,
+    #[serde(default)]
+    #[validate(range(min = 0))]
+    pub sample_value: i32,
+    #[serde(default)]
+    #[validate(range(min = 0))]
+    pub acceptance_number: i32,
+}
+
+/// A receipt or work order completion to check against the inspection plans.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewReceipt {
+    pub source: InspectionSource,
+    #[validate(length(min = 1))]
+    pub reference: String,
+    #[validate(length(min = 1))]
+    pub sku: String,
+    pub warehouse: i32,
+    pub inventory_item_id: String,
+    #[validate(range(min = 1))]
+    pub quantity: i32,
+}
+
+/// What the stored purchase order says about a PO or ASN receipt.
+#[derive(Debug, Clone, PartialEq)]
+struct PurchaseTerms {
+    purchase_order_id: Uuid,
+    supplier_id: Uuid,
+    /// Units of the receipt's SKU on the purchase order.
+    ordered_quantity: i32,
+    promised_date: NaiveDate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct InspectionResult {
+    #[validate(range(min = 0))]
+    pub defects: i32,
+    /// Defaults to pass when defects are within the plan's acceptance number, else fail.
+    pub disposition: Option<Disposition>,
+    /// Units rejected by a fail; defaults to the whole lot.
+    #[validate(range(min = 1))]
+    pub failed_quantity: Option<i32>,
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InspectionFilter {
+    pub status: Option<InspectionStatus>,
+    pub source: Option<InspectionSource>,
+    pub reference: Option<String>,
+    pub sku: Option<String>,
+}
+
+/// Units to inspect from a lot, never more than the lot and at least one unit.
+pub fn sample_size(rule: SamplingRule, value: i32, lot_quantity: i32) -> i32 {
+    let size = match rule {
+        SamplingRule::Full => lot_quantity,
+        SamplingRule::Fixed => value,
+        // Rounded up so a small lot is still sampled
+        SamplingRule::Percent => ((i64::from(lot_quantity) * i64::from(value) + 99) / 100) as i32,
+    };
+    size.clamp(1, lot_quantity.max(1))
+}
+
+/// The SKU-specific active plan for a source if there is one, else the source's general plan.
+pub fn select_plan<'a>(plans: &'a [inspection_plan::Model], source: InspectionSource, sku: &str) -> Option<&'a inspection_plan::Model> {
+    let candidates = || plans.iter().filter(|p| p.active && p.source == source);
+    candidates()
+        .find(|p| p.sku.as_deref() == Some(sku))
+        .or_else(|| candidates().find(|p| p.sku.is_none()))
+}
+
+/// The disposition of a result and how many units it sends to quarantine.
+pub fn resolve(
+    inspection: &quality_inspection::Model,
+    acceptance_number: i32,
+    result: &InspectionResult,
+) -> Result<(Disposition, i32), ServiceError> {
+    let disposition = result.disposition.unwrap_or(if result.defects <= acceptance_number {
+        Disposition::Pass
+    } else {
+        Disposition::Fail
+    });
+    let quarantined = match disposition {
+        Disposition::Pass => 0,
+        Disposition::Fail => result.failed_quantity.unwrap_or(inspection.lot_quantity),
+        Disposition::Quarantine => inspection.lot_quantity,
+    };
+    if quarantined > inspection.lot_quantity {
+        return Err(ServiceError::ValidationError(format!(
+            "Failed quantity {} exceeds the lot of {}",
+            quarantined, inspection.lot_quantity
+        )));
+    }
+    Ok((disposition, quarantined))
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Quality query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Inspection plans, inspections of receipts and completions, and quarantine moves.
+pub struct QualityService {
+    db_pool: Arc<DbPool>,
+    events: EventSender,
+    config: QualityConfig,
+}
+
+impl QualityService {
+    pub fn new(db_pool: Arc<DbPool>, events: EventSender, config: QualityConfig) -> Self {
+        Self { db_pool, events, config }
+    }
+
+    pub async fn create_plan(&self, input: NewInspectionPlan) -> Result<inspection_plan::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid inspection plan: {}", e)))?;
+        match input.sampling {
+            SamplingRule::Fixed if input.sample_value < 1 => {
+                return Err(ServiceError::ValidationError("Fixed sampling needs a sample_value of at least 1".to_string()))
+            }
+            SamplingRule::Percent if !(1..=100).contains(&input.sample_value) => {
+                return Err(ServiceError::ValidationError("Percent sampling needs a sample_value of 1 to 100".to_string()))
+            }
+            _ => {}
+        }
+        let now = Utc::now();
+        inspection_plan::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            name: Set(input.name),
+            source: Set(input.source),
+            sku: Set(input.sku),
+            sampling: Set(input.sampling),
+            sample_value: Set(input.sample_value),
+            acceptance_number: Set(input.acceptance_number),
+            active: Set(true),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(self.db_pool.as_ref())
+        .await
+        .map_err(db_error)
+    }
+
+    pub async fn list_plans(&self) -> Result<Vec<inspection_plan::Model>, ServiceError> {
+        InspectionPlan::find()
+            .order_by_asc(inspection_plan::Column::Name)
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)
+    }
+
+    /// Opens an inspection when a plan covers the receipt; `None` means the goods need no
+    /// inspection and are released as received.
+    #[instrument(skip(self, receipt), fields(reference = %receipt.reference, sku = %receipt.sku))]
+    pub async fn register_receipt(&self, receipt: NewReceipt) -> Result<Option<quality_inspection::Model>, ServiceError> {
+        receipt
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid receipt: {}", e)))?;
+        let db = self.db_pool.as_ref();
+        let terms = self.purchase_terms(&receipt).await?;
+        let plans = InspectionPlan::find()
+            .filter(inspection_plan::Column::Source.eq(receipt.source))
+            .filter(inspection_plan::Column::Active.eq(true))
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        let inspection = match select_plan(&plans, receipt.source, &receipt.sku) {
+            Some(plan) => Some(self.open_inspection(plan, &receipt, terms.as_ref()).await?),
+            None => None,
+        };
+
+        if let Some(terms) = terms {
+            supplier_receipt::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                supplier_id: Set(terms.supplier_id),
+                purchase_order_id: Set(Some(terms.purchase_order_id)),
+                reference: Set(receipt.reference),
+                sku: Set(receipt.sku),
+                ordered_quantity: Set(terms.ordered_quantity),
+                received_quantity: Set(receipt.quantity),
+                promised_date: Set(terms.promised_date),
+                inspection_id: Set(inspection.as_ref().map(|i| i.id)),
+                received_at: Set(Utc::now()),
+            }
+            .insert(db)
+            .await
+            .map_err(db_error)?;
+        }
+        Ok(inspection)
+    }
+
+    /// Supplier, ordered quantity and promised date of a PO or ASN receipt, from the stored
+    /// purchase order it arrived against. `None` for work order completions, ASNs raised
+    /// without a purchase order and references that match no purchase order; those are
+    /// left out of the supplier scorecards.
+    async fn purchase_terms(&self, receipt: &NewReceipt) -> Result<Option<PurchaseTerms>, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let order = match receipt.source {
+            InspectionSource::WorkOrderCompletion => return Ok(None),
+            InspectionSource::PoReceipt => PurchaseOrder::find()
+                .filter(purchase_order::Column::PoNumber.eq(receipt.reference.as_str()))
+                .one(db)
+                .await
+                .map_err(db_error)?,
+            InspectionSource::AsnReceipt => {
+                let asn = Asn::find()
+                    .filter(asn::Column::AsnNumber.eq(receipt.reference.as_str()))
+                    .one(db)
+                    .await
+                    .map_err(db_error)?;
+                match asn.and_then(|asn| asn.purchase_order_id) {
+                    Some(id) => PurchaseOrder::find_by_id(id).one(db).await.map_err(db_error)?,
+                    None => None,
+                }
+            }
+        };
+        let Some(order) = order else {
+            warn!(reference = %receipt.reference, "Receipt matches no purchase order; left out of supplier scorecards");
+            return Ok(None);
+        };
+        let ordered_quantity = PurchaseOrderLine::find()
+            .filter(purchase_order_line::Column::PurchaseOrderId.eq(order.id))
+            .filter(purchase_order_line::Column::Sku.eq(receipt.sku.as_str()))
+            .all(db)
+            .await
+            .map_err(db_error)?
+            .iter()
+            .map(|line| line.quantity)
+            .sum();
+        Ok(Some(PurchaseTerms {
+            purchase_order_id: order.id,
+            supplier_id: order.supplier_id,
+            ordered_quantity,
+            promised_date: order.expected_delivery_date,
+        }))
+    }
+
+    async fn open_inspection(
+        &self,
+        plan: &inspection_plan::Model,
+        receipt: &NewReceipt,
+        terms: Option<&PurchaseTerms>,
+    ) -> Result<quality_inspection::Model, ServiceError> {
+        quality_inspection::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            plan_id: Set(plan.id),
+            source: Set(receipt.source),
+            reference: Set(receipt.reference.clone()),
+            sku: Set(receipt.sku.clone()),
+            supplier_id: Set(terms.map(|t| t.supplier_id)),
+            warehouse: Set(receipt.warehouse),
+            inventory_item_id: Set(receipt.inventory_item_id.clone()),
+            lot_quantity: Set(receipt.quantity),
+            sample_size: Set(sample_size(plan.sampling, plan.sample_value, receipt.quantity)),
+            status: Set(InspectionStatus::Pending),
+            disposition: Set(None),
+            defects: Set(None),
+            quarantined_quantity: Set(None),
+            quarantine_item_id: Set(None),
+            inspector: Set(None),
+            notes: Set(None),
+            created_at: Set(Utc::now()),
+            completed_at: Set(None),
+        }
+        .insert(self.db_pool.as_ref())
+        .await
+        .map_err(db_error)
+    }
+
+    pub async fn get_inspection(&self, id: Uuid) -> Result<quality_inspection::Model, ServiceError> {
+        QualityInspection::find_by_id(id)
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Inspection not found: {}", id)))
+    }
+
+    pub async fn list_inspections(
+        &self,
+        filter: InspectionFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<quality_inspection::Model>, u64), ServiceError> {
+        let mut query = QualityInspection::find();
+        if let Some(status) = filter.status {
+            query = query.filter(quality_inspection::Column::Status.eq(status));
+        }
+        if let Some(source) = filter.source {
+            query = query.filter(quality_inspection::Column::Source.eq(source));
+        }
+        if let Some(reference) = filter.reference {
+            query = query.filter(quality_inspection::Column::Reference.eq(reference));
+        }
+        if let Some(sku) = filter.sku {
+            query = query.filter(quality_inspection::Column::Sku.eq(sku));
+        }
+        let paginator = query
+            .order_by_desc(quality_inspection::Column::CreatedAt)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let inspections = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((inspections, total))
+    }
+
+    /// Records the result of a pending inspection. Units a fail or quarantine holds are
+    /// moved out of the received inventory row into the warehouse's quarantine location in
+    /// the same transaction, and `QualityHoldCreated` is emitted once that is committed.
+    #[instrument(skip(self, result, inspector), fields(inspection_id = %id))]
+    pub async fn record_result(
+        &self,
+        id: Uuid,
+        result: InspectionResult,
+        inspector: &str,
+    ) -> Result<quality_inspection::Model, ServiceError> {
+        result
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid result: {}", e)))?;
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let inspection = QualityInspection::find_by_id(id)
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Inspection not found: {}", id)))?;
+        if inspection.status != InspectionStatus::Pending {
+            return Err(ServiceError::ValidationError("Inspection is already completed".to_string()));
+        }
+        let plan = InspectionPlan::find_by_id(inspection.plan_id)
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Inspection plan not found: {}", inspection.plan_id)))?;
+        let (disposition, quarantined) = resolve(&inspection, plan.acceptance_number, &result)?;
+
+        let quarantine_item_id = if quarantined > 0 {
+            Some(self.move_to_quarantine(&txn, &inspection, quarantined).await?)
+        } else {
+            None
+        };
+
+        let mut active: quality_inspection::ActiveModel = inspection.into();
+        active.status = Set(InspectionStatus::Completed);
+        active.disposition = Set(Some(disposition));
+        active.defects = Set(Some(result.defects));
+        active.quarantined_quantity = Set(Some(quarantined));
+        active.quarantine_item_id = Set(quarantine_item_id);
+        active.inspector = Set(Some(inspector.to_string()));
+        active.notes = Set(result.notes);
+        active.completed_at = Set(Some(Utc::now()));
+        let inspection = active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+
+        if quarantined > 0 {
+            info!(sku = %inspection.sku, quantity = quarantined, "Units moved to quarantine");
+            let _ = self.events.send(Event::QualityHoldCreated {
+                inspection_id: inspection.id,
+                sku: inspection.sku.clone(),
+                warehouse: inspection.warehouse,
+                quantity: quarantined,
+                disposition: format!("{:?}", disposition).to_lowercase(),
+            });
+        }
+        Ok(inspection)
+    }
+
+    /// Releases the units a completed inspection holds in quarantine back to the row
+    /// they were received into, e.g. after a review clears a quarantined lot.
+    #[instrument(skip(self, actor), fields(inspection_id = %id))]
+    pub async fn release_hold(&self, id: Uuid, actor: &str) -> Result<quality_inspection::Model, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let inspection = QualityInspection::find_by_id(id)
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Inspection not found: {}", id)))?;
+        let held = inspection.quarantined_quantity.unwrap_or(0);
+        let quarantine_item_id = match (&inspection.status, &inspection.quarantine_item_id) {
+            (InspectionStatus::Completed, Some(item_id)) if held > 0 => item_id.clone(),
+            _ => {
+                return Err(ServiceError::InvalidOperation(format!(
+                    "Inspection {} holds no units in quarantine",
+                    id
+                )))
+            }
+        };
+
+        let quarantine = InventoryItem::find_by_id(quarantine_item_id.clone())
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Inventory item not found: {}", quarantine_item_id)))?;
+        let remaining = quarantine.damaged_quantity.unwrap_or(0) - held;
+        if remaining < 0 {
+            return Err(ServiceError::InvalidOperation(format!(
+                "Quarantine row {} holds fewer than the {} units to release",
+                quarantine_item_id, held
+            )));
+        }
+        let received = InventoryItem::find_by_id(inspection.inventory_item_id.clone())
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Inventory item not found: {}", inspection.inventory_item_id)))?;
+        let now = Utc::now();
+        let mut active: inventory_items::ActiveModel = quarantine.into();
+        active.damaged_quantity = Set(Some(remaining));
+        active.last_movement_date = Set(Some(now));
+        active.update(&txn).await.map_err(db_error)?;
+        let available = received.available + held;
+        let mut active: inventory_items::ActiveModel = received.into();
+        active.available = Set(available);
+        active.last_movement_date = Set(Some(now));
+        active.update(&txn).await.map_err(db_error)?;
+
+        let mut active: quality_inspection::ActiveModel = inspection.into();
+        active.status = Set(InspectionStatus::Released);
+        let inspection = active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+
+        info!(sku = %inspection.sku, quantity = held, actor, "Units released from quarantine");
+        let _ = self.events.send(Event::QualityHoldReleased {
+            inspection_id: inspection.id,
+            sku: inspection.sku.clone(),
+            warehouse: inspection.warehouse,
+            quantity: held,
+        });
+        Ok(inspection)
+    }
+
+    /// Takes `quantity` off the received row and holds it on the matching quarantine row,
+    /// creating that row from the received one the first time. Returns its id.
+    async fn move_to_quarantine(
+        &self,
+        txn: &DatabaseTransaction,
+        inspection: &quality_inspection::Model,
+        quantity: i32,
+    ) -> Result<String, ServiceError> {
+        let received = InventoryItem::find_by_id(inspection.inventory_item_id.clone())
+            .lock_exclusive()
+            .one(txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Inventory item not found: {}", inspection.inventory_item_id)))?;
+        if received.available < quantity {
+            return Err(ServiceError::ValidationError(format!(
+                "Only {} units of {} are available to quarantine",
+                received.available, received.sku
+            )));
+        }
+        let now = Utc::now();
+        let quarantine_id = format!("{}-{}", received.id, self.config.quarantine_location);
+
+        let existing = InventoryItem::find_by_id(quarantine_id.clone())
+            .lock_exclusive()
+            .one(txn)
+            .await
+            .map_err(db_error)?;
+        match existing {
+            Some(row) => {
+                let held = row.damaged_quantity.unwrap_or(0) + quantity;
+                let mut active: inventory_items::ActiveModel = row.into();
+                active.available = Set(0);
+                active.damaged_quantity = Set(Some(held));
+                active.last_movement_date = Set(Some(now));
+                active.update(txn).await.map_err(db_error)?;
+            }
+            None => {
+                let mut active = received.clone().into_active_model();
+                active.reset_all();
+                active.id = Set(quarantine_id.clone());
+                active.available = Set(0);
+                active.damaged_quantity = Set(Some(quantity));
+                active.incoming = Set(0);
+                active.allocated_quantity = Set(None);
+                active.reserved_quantity = Set(None);
+                active.location_in_warehouse = Set(Some(self.config.quarantine_location.clone()));
+                active.quality_status = Set(Some(QUARANTINE_STATUS.to_string()));
+                active.last_movement_date = Set(Some(now));
+                active.insert(txn).await.map_err(db_error)?;
+            }
+        }
+
+        let available = received.available - quantity;
+        let mut active: inventory_items::ActiveModel = received.into();
+        active.available = Set(available);
+        active.last_movement_date = Set(Some(now));
+        active.update(txn).await.map_err(db_error)?;
+        Ok(quarantine_id)
+    }
+}
+
+/// Opens inspections for lots other services receive or produce, as announced by
+/// [`Event::LotReceived`].
+pub fn spawn_receipt_listener(quality: Arc<QualityService>, events: EventSender) {
+    let mut receiver = events.subscribe();
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(Event::LotReceived { source, reference, sku, warehouse, inventory_item_id, quantity }) => {
+                    let receipt = NewReceipt {
+                        source,
+                        reference: reference.clone(),
+                        sku,
+                        warehouse,
+                        inventory_item_id,
+                        quantity,
+                    };
+                    if let Err(e) = quality.register_receipt(receipt).await {
+                        error!(reference = %reference, "Could not register a received lot for inspection: {}", e);
+                    }
+                }
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    warn!(missed, "Quality receipt listener lagged; some lots need registering by hand");
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(sku: Option<&str>, source: InspectionSource) -> inspection_plan::Model {
+        let now = Utc::now();
+        inspection_plan::Model {
+            id: Uuid::new_v4(),
+            name: "Incoming".to_string(),
+            source,
+            sku: sku.map(str::to_string),
+            sampling: SamplingRule::Percent,
+            sample_value: 10,
+            acceptance_number: 1,
+            active: true,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    fn inspection(lot_quantity: i32) -> quality_inspection::Model {
+        quality_inspection::Model {
+            id: Uuid::new_v4(),
+            plan_id: Uuid::new_v4(),
+            source: InspectionSource::PoReceipt,
+            reference: "PO-1".to_string(),
+            sku: "WIDGET".to_string(),
+            supplier_id: None,
+            warehouse: 1,
+            inventory_item_id: "INV-1".to_string(),
+            lot_quantity,
+            sample_size: 5,
+            status: InspectionStatus::Pending,
+            disposition: None,
+            defects: None,
+            quarantined_quantity: None,
+            quarantine_item_id: None,
+            inspector: None,
+            notes: None,
+            created_at: Utc::now(),
+            completed_at: None,
+        }
+    }
+
+    fn result(defects: i32, disposition: Option<Disposition>, failed_quantity: Option<i32>) -> InspectionResult {
+        InspectionResult { defects, disposition, failed_quantity, notes: None }
+    }
+
+    #[test]
+    fn test_sample_size() {
+        assert_eq!(sample_size(SamplingRule::Full, 0, 40), 40);
+        assert_eq!(sample_size(SamplingRule::Fixed, 13, 40), 13);
+        assert_eq!(sample_size(SamplingRule::Fixed, 13, 8), 8);
+        assert_eq!(sample_size(SamplingRule::Percent, 10, 41), 5);
+        assert_eq!(sample_size(SamplingRule::Percent, 10, 3), 1);
+    }
+
+    #[test]
+    fn test_sku_plan_wins_over_general_plan() {
+        let plans = [plan(None, InspectionSource::PoReceipt), plan(Some("WIDGET"), InspectionSource::PoReceipt)];
+        assert_eq!(select_plan(&plans, InspectionSource::PoReceipt, "WIDGET").unwrap().id, plans[1].id);
+        assert_eq!(select_plan(&plans, InspectionSource::PoReceipt, "GADGET").unwrap().id, plans[0].id);
+        assert!(select_plan(&plans, InspectionSource::AsnReceipt, "WIDGET").is_none());
+    }
+
+    #[test]
+    fn test_disposition_follows_acceptance_number() {
+        let lot = inspection(40);
+        assert_eq!(resolve(&lot, 1, &result(1, None, None)).unwrap(), (Disposition::Pass, 0));
+        assert_eq!(resolve(&lot, 1, &result(2, None, None)).unwrap(), (Disposition::Fail, 40));
+        assert_eq!(resolve(&lot, 1, &result(2, None, Some(6))).unwrap(), (Disposition::Fail, 6));
+        assert_eq!(resolve(&lot, 1, &result(0, Some(Disposition::Quarantine), None)).unwrap(), (Disposition::Quarantine, 40));
+        assert!(resolve(&lot, 1, &result(2, None, Some(41))).is_err());
+    }
+}
@@ -0,0 +1,334 @@
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db::DbPool,
+    errors::ServiceError,
+    events::Event,
+    models::{
+        approval_step::{self, ApprovalSubject},
+        purchase_order::{self, PurchaseOrderStatus},
+        purchase_order_line,
+        requisition::{self, Entity as Requisition, RequisitionStatus},
+    },
+    money::round_currency,
+    utils::pagination::PaginationParams,
+    workflow::{ApprovalEngine, ApprovalHandler, Resolution},
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RequisitionLine {
+    #[validate(length(min = 1))]
+    pub sku: String,
+    #[validate(length(max = 500))]
+    pub description: Option<String>,
+    #[validate(range(min = 1))]
+    pub quantity: i32,
+    #[validate(custom = "crate::money::validate_non_negative")]
+    #[serde(with = "crate::money::amount")]
+    pub unit_price: Decimal,
+    /// Tax rate as a fraction, e.g. `0.0825`.
+    #[serde(default, with = "crate::money::option_amount")]
+    pub tax_rate: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ShippingAddress {
+    #[validate(length(min = 1))]
+    pub street: String,
+    #[validate(length(min = 1))]
+    pub city: String,
+    #[validate(length(min = 1))]
+    pub state: String,
+    #[validate(length(min = 1))]
+    pub postal_code: String,
+    #[validate(length(min = 2))]
+    pub country: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewRequisition {
+    pub supplier_id: Uuid,
+    #[validate(length(equal = 3))]
+    pub currency: String,
+    #[validate(length(min = 1, max = 100))]
+    #[validate]
+    pub lines: Vec<RequisitionLine>,
+    #[validate]
+    pub shipping_address: ShippingAddress,
+    pub needed_by: NaiveDate,
+    #[validate(length(max = 1000))]
+    pub justification: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RequisitionFilter {
+    pub status: Option<RequisitionStatus>,
+    pub requested_by: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RequisitionWithApprovals {
+    #[serde(flatten)]
+    pub requisition: requisition::Model,
+    pub approvals: Vec<approval_step::Model>,
+}
+
+/// Line total including tax, rounded to currency precision.
+pub fn line_total(line: &RequisitionLine) -> Decimal {
+    let net = line.unit_price * Decimal::from(line.quantity);
+    round_currency(net + net * line.tax_rate.unwrap_or(Decimal::ZERO))
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Requisition query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+async fn find<C: ConnectionTrait>(db: &C, id: Uuid) -> Result<requisition::Model, ServiceError> {
+    Requisition::find_by_id(id)
+        .one(db)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| ServiceError::NotFound(format!("Requisition not found: {}", id)))
+}
+
+async fn find_locked(txn: &DatabaseTransaction, subject_id: &str) -> Result<requisition::Model, ServiceError> {
+    let id = Uuid::parse_str(subject_id)
+        .map_err(|_| ServiceError::ValidationError(format!("Invalid requisition id: {}", subject_id)))?;
+    Requisition::find_by_id(id)
+        .lock_exclusive()
+        .one(txn)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| ServiceError::NotFound(format!("Requisition not found: {}", id)))
+}
+
+/// Purchase requisitions. Approval runs through the `requisition` workflow chain; see
+/// `RequisitionApprovals` for what submission and the outcome do.
+pub struct RequisitionService {
+    db_pool: Arc<DbPool>,
+    approvals: Arc<ApprovalEngine>,
+}
+
+impl RequisitionService {
+    pub fn new(db_pool: Arc<DbPool>, approvals: Arc<ApprovalEngine>) -> Self {
+        Self { db_pool, approvals }
+    }
+
+    #[instrument(skip(self, input))]
+    pub async fn create(&self, input: NewRequisition, requested_by: &str) -> Result<requisition::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid requisition: {}", e)))?;
+        let total: Decimal = input.lines.iter().map(line_total).sum();
+        let now = Utc::now();
+        requisition::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            requested_by: Set(requested_by.to_string()),
+            supplier_id: Set(input.supplier_id),
+            currency: Set(input.currency.to_uppercase()),
+            lines: Set(serde_json::to_value(&input.lines).expect("lines serialize")),
+            shipping_address: Set(serde_json::to_value(&input.shipping_address).expect("address serializes")),
+            total_amount: Set(total),
+            needed_by: Set(input.needed_by),
+            justification: Set(input.justification),
+            status: Set(RequisitionStatus::Draft),
+            purchase_order_id: Set(None),
+            created_at: Set(now),
+            updated_at: Set(now),
+            submitted_at: Set(None),
+        }
+        .insert(self.db_pool.as_ref())
+        .await
+        .map_err(db_error)
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<RequisitionWithApprovals, ServiceError> {
+        let requisition = find(self.db_pool.as_ref(), id).await?;
+        let approvals = self.approvals.steps(ApprovalSubject::Requisition, &id.to_string()).await?;
+        Ok(RequisitionWithApprovals { requisition, approvals })
+    }
+
+    pub async fn list(
+        &self,
+        filter: RequisitionFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<requisition::Model>, u64), ServiceError> {
+        let mut query = Requisition::find();
+        if let Some(status) = filter.status {
+            query = query.filter(requisition::Column::Status.eq(status));
+        }
+        if let Some(requested_by) = filter.requested_by {
+            query = query.filter(requisition::Column::RequestedBy.eq(requested_by));
+        }
+        let paginator = query
+            .order_by_desc(requisition::Column::CreatedAt)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let requisitions = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((requisitions, total))
+    }
+
+    /// Sends a draft into its approval chain. Fails, leaving the draft as it was, when no
+    /// configured step applies to its amount.
+    #[instrument(skip(self))]
+    pub async fn submit(&self, id: Uuid, actor: &str) -> Result<RequisitionWithApprovals, ServiceError> {
+        self.approvals
+            .request(ApprovalSubject::Requisition, &id.to_string(), actor)
+            .await?;
+        self.get(id).await
+    }
+
+    /// Withdraws a draft or pending requisition; undecided approval steps are skipped.
+    pub async fn cancel(&self, id: Uuid, actor: &str) -> Result<requisition::Model, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let requisition = find_locked(&txn, &id.to_string()).await?;
+        if requisition.requested_by != actor {
+            return Err(ServiceError::ValidationError("Only the requester can cancel a requisition".to_string()));
+        }
+        if !matches!(requisition.status, RequisitionStatus::Draft | RequisitionStatus::PendingApproval) {
+            return Err(ServiceError::ValidationError(format!(
+                "A {:?} requisition cannot be cancelled",
+                requisition.status
+            )));
+        }
+        ApprovalEngine::withdraw(&txn, ApprovalSubject::Requisition, &id.to_string()).await?;
+        let mut active: requisition::ActiveModel = requisition.into();
+        active.status = Set(RequisitionStatus::Cancelled);
+        active.updated_at = Set(Utc::now());
+        let requisition = active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+        Ok(requisition)
+    }
+}
+
+/// Workflow side of requisitions: only the requester submits a draft, the chain is routed
+/// on the total, and final approval creates the purchase order.
+pub struct RequisitionApprovals;
+
+#[async_trait]
+impl ApprovalHandler for RequisitionApprovals {
+    fn subject(&self) -> ApprovalSubject {
+        ApprovalSubject::Requisition
+    }
+
+    async fn submit(&self, txn: &DatabaseTransaction, subject_id: &str, actor: &str) -> Result<Decimal, ServiceError> {
+        let requisition = find_locked(txn, subject_id).await?;
+        if requisition.requested_by != actor {
+            return Err(ServiceError::ValidationError("Only the requester can submit a requisition".to_string()));
+        }
+        if requisition.status != RequisitionStatus::Draft {
+            return Err(ServiceError::ValidationError(format!(
+                "Requisition is {:?}, not a draft",
+                requisition.status
+            )));
+        }
+        let total = requisition.total_amount;
+        let now = Utc::now();
+        let mut active: requisition::ActiveModel = requisition.into();
+        active.status = Set(RequisitionStatus::PendingApproval);
+        active.submitted_at = Set(Some(now));
+        active.updated_at = Set(now);
+        active.update(txn).await.map_err(db_error)?;
+        Ok(total)
+    }
+
+    async fn resolve(
+        &self,
+        txn: &DatabaseTransaction,
+        subject_id: &str,
+        resolution: Resolution,
+        actor: &str,
+    ) -> Result<Option<Event>, ServiceError> {
+        let requisition = find_locked(txn, subject_id).await?;
+        let mut active: requisition::ActiveModel = requisition.clone().into();
+        active.updated_at = Set(Utc::now());
+        let event = match resolution {
+            Resolution::Rejected => {
+                active.status = Set(RequisitionStatus::Rejected);
+                None
+            }
+            Resolution::Approved => {
+                let po = convert(txn, &requisition, actor).await?;
+                info!(requisition_id = %requisition.id, purchase_order_id = %po.id, po_number = %po.po_number, "Requisition converted to purchase order");
+                active.status = Set(RequisitionStatus::Approved);
+                active.purchase_order_id = Set(Some(po.id));
+                Some(Event::RequisitionApproved { requisition_id: requisition.id, purchase_order_id: po.id })
+            }
+        };
+        active.update(txn).await.map_err(db_error)?;
+        Ok(event)
+    }
+}
+
+/// Creates the purchase order for an approved requisition.
+async fn convert<C: ConnectionTrait>(
+    db: &C,
+    requisition: &requisition::Model,
+    actor: &str,
+) -> Result<purchase_order::Model, ServiceError> {
+    let lines: Vec<RequisitionLine> = serde_json::from_value(requisition.lines.clone())
+        .map_err(|e| ServiceError::ValidationError(format!("Stored requisition lines are invalid: {}", e)))?;
+    let now = Utc::now();
+    let po = purchase_order::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        po_number: Set(format!("PO-{}", Uuid::new_v4().simple())),
+        supplier_id: Set(requisition.supplier_id),
+        requisition_id: Set(Some(requisition.id)),
+        status: Set(PurchaseOrderStatus::Open),
+        currency: Set(requisition.currency.clone()),
+        total_amount: Set(requisition.total_amount),
+        expected_delivery_date: Set(requisition.needed_by),
+        shipping_address: Set(requisition.shipping_address.clone()),
+        created_by: Set(actor.to_string()),
+        created_at: Set(now),
+        updated_at: Set(now),
+    }
+    .insert(db)
+    .await
+    .map_err(db_error)?;
+    for (index, line) in lines.iter().enumerate() {
+        purchase_order_line::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            purchase_order_id: Set(po.id),
+            line_number: Set(index as i32 + 1),
+            sku: Set(line.sku.clone()),
+            description: Set(line.description.clone()),
+            quantity: Set(line.quantity),
+            unit_price: Set(line.unit_price),
+            tax_rate: Set(line.tax_rate),
+            line_total: Set(line_total(line)),
+        }
+        .insert(db)
+        .await
+        .map_err(db_error)?;
+    }
+    Ok(po)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_line_total_includes_tax() {
+        let line = RequisitionLine {
+            sku: "PAPER".to_string(),
+            description: None,
+            quantity: 3,
+            unit_price: dec!(9.99),
+            tax_rate: Some(dec!(0.0825)),
+        };
+        assert_eq!(line_total(&line), dec!(32.44));
+    }
+}
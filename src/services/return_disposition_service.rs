@@ -0,0 +1,526 @@
+use chrono::{Days, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db::{dialect, DbPool},
+    errors::ServiceError,
+    events::{Event, EventSender},
+    models::{
+        inventory_items::{self, Entity as InventoryItem},
+        inventory_write_off::{self, WriteOffReason, WriteOffStatus},
+        return_disposition::{self, Disposition, Entity as ReturnDisposition},
+        return_entity::{self, Entity as Return, ReturnStatus},
+        vendor_return::{self, VendorReturnStatus},
+        work_order::{self, Entity as WorkOrder, WorkOrderPriority, WorkOrderStatus},
+    },
+    money::round_currency,
+    utils::pagination::PaginationParams,
+};
+
+/// Days allowed for a refurbishment work order.
+const REFURBISH_DAYS: u64 = 7;
+
+const UNSPECIFIED_REASON: &str = "unspecified";
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct DispositionLine {
+    #[validate(length(min = 1))]
+    pub sku: String,
+    #[validate(range(min = 1))]
+    pub quantity: i32,
+    /// Warehouse the units were received into.
+    pub warehouse: i32,
+    pub disposition: Disposition,
+    /// Overrides the return's reason category, e.g. when one return holds units sent back
+    /// for different reasons.
+    #[validate(length(min = 1, max = 64))]
+    pub reason: Option<String>,
+    /// Supplier to send `return_to_vendor` units to; the supplier of the latest purchase
+    /// order for the SKU when omitted.
+    pub supplier_id: Option<Uuid>,
+    #[validate(length(max = 1000))]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct DisposeRequest {
+    #[validate(length(min = 1, max = 200))]
+    #[validate]
+    pub lines: Vec<DispositionLine>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DispositionFilter {
+    pub return_id: Option<Uuid>,
+    pub sku: Option<String>,
+    pub disposition: Option<Disposition>,
+}
+
+/// Units disposed of one SKU for one reason one way.
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct DispositionRow {
+    pub sku: String,
+    pub reason: String,
+    pub disposition: Disposition,
+    pub quantity: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct DispositionCounts {
+    pub restock: i64,
+    pub refurbish: i64,
+    pub scrap: i64,
+    pub return_to_vendor: i64,
+}
+
+impl DispositionCounts {
+    fn add(&mut self, disposition: Disposition, quantity: i64) {
+        match disposition {
+            Disposition::Restock => self.restock += quantity,
+            Disposition::Refurbish => self.refurbish += quantity,
+            Disposition::Scrap => self.scrap += quantity,
+            Disposition::ReturnToVendor => self.return_to_vendor += quantity,
+        }
+    }
+
+    pub fn total(&self) -> i64 {
+        self.restock + self.refurbish + self.scrap + self.return_to_vendor
+    }
+
+    /// Share of units that went back to sellable stock, directly or after refurbishment.
+    pub fn recovery_rate(&self) -> Decimal {
+        match self.total() {
+            0 => Decimal::ZERO,
+            total => (Decimal::from(self.restock + self.refurbish) / Decimal::from(total)).round_dp(4),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReasonDispositions {
+    pub reason: String,
+    pub quantity: i64,
+    #[serde(flatten)]
+    pub dispositions: DispositionCounts,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SkuDispositions {
+    pub sku: String,
+    pub quantity: i64,
+    #[serde(flatten)]
+    pub dispositions: DispositionCounts,
+    pub recovery_rate: Decimal,
+    /// Most units first.
+    pub by_reason: Vec<ReasonDispositions>,
+}
+
+/// Groups disposition rows by SKU, then reason, SKUs with the most units first.
+pub fn dispositions_by_sku(rows: &[DispositionRow]) -> Vec<SkuDispositions> {
+    let mut skus: BTreeMap<&str, BTreeMap<&str, DispositionCounts>> = BTreeMap::new();
+    for row in rows {
+        skus.entry(row.sku.as_str())
+            .or_default()
+            .entry(row.reason.as_str())
+            .or_default()
+            .add(row.disposition, row.quantity);
+    }
+    let mut report: Vec<SkuDispositions> = skus
+        .into_iter()
+        .map(|(sku, reasons)| {
+            let mut dispositions = DispositionCounts::default();
+            let mut by_reason: Vec<ReasonDispositions> = reasons
+                .into_iter()
+                .map(|(reason, counts)| {
+                    dispositions.restock += counts.restock;
+                    dispositions.refurbish += counts.refurbish;
+                    dispositions.scrap += counts.scrap;
+                    dispositions.return_to_vendor += counts.return_to_vendor;
+                    ReasonDispositions { reason: reason.to_string(), quantity: counts.total(), dispositions: counts }
+                })
+                .collect();
+            by_reason.sort_by(|a, b| b.quantity.cmp(&a.quantity).then_with(|| a.reason.cmp(&b.reason)));
+            SkuDispositions {
+                sku: sku.to_string(),
+                quantity: dispositions.total(),
+                recovery_rate: dispositions.recovery_rate(),
+                dispositions,
+                by_reason,
+            }
+        })
+        .collect();
+    report.sort_by(|a, b| b.quantity.cmp(&a.quantity).then_with(|| a.sku.cmp(&b.sku)));
+    report
+}
+
+const DISPOSITIONS_SQL: &str = r#"
+SELECT sku, reason, disposition, SUM(quantity)::BIGINT AS quantity
+FROM return_dispositions
+WHERE created_at >= $1 AND created_at < $2 AND ($3::TEXT IS NULL OR sku = $3)
+GROUP BY sku, reason, disposition
+"#;
+
+/// Supplier and price of the most recent purchase order line for `sku`.
+#[derive(Debug, Clone, FromQueryResult)]
+struct LastPurchase {
+    supplier_id: Uuid,
+    purchase_order_id: Uuid,
+    unit_price: Decimal,
+}
+
+const LAST_PURCHASE_SQL: &str = r#"
+SELECT po.supplier_id, po.id AS purchase_order_id, pol.unit_price
+FROM purchase_order_lines pol
+JOIN purchase_orders po ON po.id = pol.purchase_order_id
+WHERE pol.sku = $1 AND ($2::UUID IS NULL OR po.supplier_id = $2)
+ORDER BY po.created_at DESC
+LIMIT 1
+"#;
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Return disposition query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Decides what happens to returned units: restocked onto the item, refurbished under a
+/// work order, scrapped as a write-off, or sent back to the supplier.
+pub struct ReturnDispositionService {
+    db_pool: Arc<DbPool>,
+    events: EventSender,
+}
+
+impl ReturnDispositionService {
+    pub fn new(db_pool: Arc<DbPool>, events: EventSender) -> Self {
+        Self { db_pool, events }
+    }
+
+    /// Disposes of units of a received return, all lines or none.
+    #[instrument(skip(self, request))]
+    pub async fn dispose(
+        &self,
+        return_id: Uuid,
+        request: DisposeRequest,
+        actor: &str,
+    ) -> Result<Vec<return_disposition::Model>, ServiceError> {
+        request
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid disposition: {}", e)))?;
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let ret = Return::find_by_id(return_id)
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Return not found: {}", return_id)))?;
+        if !matches!(ret.status, ReturnStatus::Received | ReturnStatus::Refunded) {
+            return Err(ServiceError::ValidationError(format!(
+                "Return {} is {:?}; units are disposed of once received",
+                ret.rma, ret.status
+            )));
+        }
+
+        let mut dispositions = Vec::with_capacity(request.lines.len());
+        let mut stock_changes = Vec::new();
+        for line in request.lines {
+            let reason = line
+                .reason
+                .clone()
+                .or_else(|| ret.reason_category.clone())
+                .unwrap_or_else(|| UNSPECIFIED_REASON.to_string());
+            let mut record = return_disposition::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                return_id: Set(ret.id),
+                rma: Set(ret.rma.clone()),
+                sku: Set(line.sku.clone()),
+                warehouse: Set(line.warehouse),
+                quantity: Set(line.quantity),
+                disposition: Set(line.disposition),
+                reason: Set(reason.clone()),
+                inventory_item_id: Set(None),
+                work_order_id: Set(None),
+                write_off_id: Set(None),
+                vendor_return_id: Set(None),
+                notes: Set(line.notes.clone()),
+                disposed_by: Set(actor.to_string()),
+                created_at: Set(Utc::now()),
+            };
+            match line.disposition {
+                Disposition::Restock => {
+                    let item = restock(&txn, &line).await?;
+                    stock_changes.push((item.warehouse, item.sku.clone(), item.available));
+                    record.inventory_item_id = Set(Some(item.id));
+                }
+                Disposition::Refurbish => {
+                    let work_order = refurbish_work_order(&txn, &ret, &line, actor).await?;
+                    record.work_order_id = Set(Some(work_order.id));
+                }
+                Disposition::Scrap => {
+                    let write_off = scrap(&txn, &ret, &line, actor).await?;
+                    record.write_off_id = Set(Some(write_off.id));
+                }
+                Disposition::ReturnToVendor => {
+                    let vendor_return = return_to_vendor(&txn, &ret, &line, &reason, actor).await?;
+                    record.vendor_return_id = Set(Some(vendor_return.id));
+                }
+            }
+            dispositions.push(record.insert(&txn).await.map_err(db_error)?);
+        }
+        txn.commit().await.map_err(db_error)?;
+
+        for (warehouse_id, sku, available) in stock_changes {
+            let _ = self.events.send(Event::InventoryLevelChanged { warehouse_id, sku, available });
+        }
+        info!(return_id = %return_id, lines = dispositions.len(), "Return units disposed");
+        Ok(dispositions)
+    }
+
+    pub async fn list(
+        &self,
+        filter: DispositionFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<return_disposition::Model>, u64), ServiceError> {
+        let mut query = ReturnDisposition::find();
+        if let Some(return_id) = filter.return_id {
+            query = query.filter(return_disposition::Column::ReturnId.eq(return_id));
+        }
+        if let Some(sku) = filter.sku {
+            query = query.filter(return_disposition::Column::Sku.eq(sku));
+        }
+        if let Some(disposition) = filter.disposition {
+            query = query.filter(return_disposition::Column::Disposition.eq(disposition));
+        }
+        let paginator = query
+            .order_by_desc(return_disposition::Column::CreatedAt)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let items = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((items, total))
+    }
+
+    /// Units disposed in `[from, to]` by SKU and return reason.
+    pub async fn analytics(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        sku: Option<String>,
+    ) -> Result<Vec<SkuDispositions>, ServiceError> {
+        if to < from {
+            return Err(ServiceError::ValidationError("`to` is before `from`".to_string()));
+        }
+        let start = from.and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc();
+        let end = to.succ_opt().unwrap_or(to).and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc();
+        let rows = DispositionRow::find_by_statement(dialect::statement(
+            self.db_pool.as_ref(),
+            DISPOSITIONS_SQL,
+            [start.into(), end.into(), sku.into()],
+        ))
+        .all(self.db_pool.as_ref())
+        .await
+        .map_err(db_error)?;
+        Ok(dispositions_by_sku(&rows))
+    }
+}
+
+async fn stock_item(
+    txn: &DatabaseTransaction,
+    line: &DispositionLine,
+) -> Result<inventory_items::Model, ServiceError> {
+    InventoryItem::find()
+        .filter(inventory_items::Column::Sku.eq(line.sku.as_str()))
+        .filter(inventory_items::Column::Warehouse.eq(line.warehouse))
+        .lock_exclusive()
+        .one(txn)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| {
+            ServiceError::NotFound(format!("No inventory item for {} in warehouse {}", line.sku, line.warehouse))
+        })
+}
+
+async fn restock(txn: &DatabaseTransaction, line: &DispositionLine) -> Result<inventory_items::Model, ServiceError> {
+    let item = stock_item(txn, line).await?;
+    let available = item.available + line.quantity;
+    let mut active: inventory_items::ActiveModel = item.into();
+    active.available = Set(available);
+    active.last_movement_date = Set(Some(Utc::now()));
+    active.update(txn).await.map_err(db_error)
+}
+
+async fn refurbish_work_order(
+    txn: &DatabaseTransaction,
+    ret: &return_entity::Model,
+    line: &DispositionLine,
+    actor: &str,
+) -> Result<work_order::Model, ServiceError> {
+    let last = WorkOrder::find()
+        .order_by_desc(work_order::Column::Number)
+        .one(txn)
+        .await
+        .map_err(db_error)?;
+    let now = Utc::now();
+    let mut memo = format!("Refurbish {} x {} from return {}", line.quantity, line.sku, ret.rma);
+    if let Some(notes) = &line.notes {
+        memo.push_str(": ");
+        memo.push_str(notes);
+    }
+    work_order::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        number: Set(last.map_or(1, |w| w.number + 1)),
+        site: Set(line.warehouse.to_string()),
+        work_order_type: Set("refurbish".to_string()),
+        location: Set(line.warehouse.to_string()),
+        part: Set(line.sku.clone()),
+        order_number: Set(ret.rma.clone()),
+        manufacture_order: Set(String::new()),
+        status: Set(WorkOrderStatus::Pending),
+        created_by: Set(actor.to_string()),
+        created_at: Set(now),
+        updated_at: Set(now),
+        issue_date: Set(now.date_naive()),
+        expected_completion_date: Set(now.date_naive() + Days::new(REFURBISH_DAYS)),
+        priority: Set(WorkOrderPriority::Medium),
+        memo: Set(Some(memo)),
+        bill_of_materials_number: Set(0),
+        actual_labor_hours: Set(0.0),
+        standard_labor_hours: Set(0.0),
+        capacity_utilization_id: Set(Uuid::nil()),
+        bill_of_materials_id: Set(Uuid::nil()),
+        cogs_data_id: Set(Uuid::nil()),
+        bom_revision: Set(None),
+        equipment_id: Set(None),
+        maintenance_schedule_id: Set(None),
+    }
+    .insert(txn)
+    .await
+    .map_err(db_error)
+}
+
+/// Records scrapped units as a posted damaged write-off at the item's cost. Returned units
+/// never went back on the item, so its stock is left as it is.
+async fn scrap(
+    txn: &DatabaseTransaction,
+    ret: &return_entity::Model,
+    line: &DispositionLine,
+    actor: &str,
+) -> Result<inventory_write_off::Model, ServiceError> {
+    let item = stock_item(txn, line).await?;
+    let unit_cost = item.unit_cost.or(item.average_cost).unwrap_or(Decimal::ZERO);
+    let now = Utc::now();
+    inventory_write_off::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        inventory_item_id: Set(item.id),
+        sku: Set(item.sku),
+        warehouse: Set(item.warehouse),
+        quantity: Set(line.quantity),
+        unit_cost: Set(unit_cost),
+        amount: Set(round_currency(unit_cost * Decimal::from(line.quantity))),
+        reason: Set(WriteOffReason::Damaged),
+        notes: Set(Some(format!("Scrapped from return {}", ret.rma))),
+        status: Set(WriteOffStatus::Posted),
+        requested_by: Set(actor.to_string()),
+        created_at: Set(now),
+        updated_at: Set(now),
+        posted_at: Set(Some(now)),
+    }
+    .insert(txn)
+    .await
+    .map_err(db_error)
+}
+
+async fn return_to_vendor(
+    txn: &DatabaseTransaction,
+    ret: &return_entity::Model,
+    line: &DispositionLine,
+    reason: &str,
+    actor: &str,
+) -> Result<vendor_return::Model, ServiceError> {
+    let purchase = LastPurchase::find_by_statement(dialect::statement(
+        txn,
+        LAST_PURCHASE_SQL,
+        [line.sku.clone().into(), line.supplier_id.into()],
+    ))
+    .one(txn)
+    .await
+    .map_err(db_error)?;
+    let (supplier_id, purchase_order_id, unit_cost) = match (purchase, line.supplier_id) {
+        (Some(p), _) => (p.supplier_id, Some(p.purchase_order_id), p.unit_price),
+        (None, Some(supplier_id)) => (supplier_id, None, Decimal::ZERO),
+        (None, None) => {
+            return Err(ServiceError::ValidationError(format!(
+                "No purchase order found for {}; give the supplier to return it to",
+                line.sku
+            )))
+        }
+    };
+    let now = Utc::now();
+    vendor_return::ActiveModel {
+        id: Set(Uuid::new_v4()),
+        supplier_id: Set(supplier_id),
+        purchase_order_id: Set(purchase_order_id),
+        sku: Set(line.sku.clone()),
+        quantity: Set(line.quantity),
+        unit_cost: Set(unit_cost),
+        amount: Set(round_currency(unit_cost * Decimal::from(line.quantity))),
+        rma: Set(Some(ret.rma.clone())),
+        reason: Set(reason.to_string()),
+        status: Set(VendorReturnStatus::Open),
+        created_by: Set(actor.to_string()),
+        created_at: Set(now),
+        updated_at: Set(now),
+    }
+    .insert(txn)
+    .await
+    .map_err(db_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn row(sku: &str, reason: &str, disposition: Disposition, quantity: i64) -> DispositionRow {
+        DispositionRow { sku: sku.to_string(), reason: reason.to_string(), disposition, quantity }
+    }
+
+    #[test]
+    fn test_dispositions_grouped_by_sku_and_reason() {
+        let rows = [
+            row("TEE", "defective", Disposition::Scrap, 3),
+            row("TEE", "defective", Disposition::Refurbish, 2),
+            row("TEE", "wrong_size", Disposition::Restock, 10),
+            row("MUG", "damaged", Disposition::ReturnToVendor, 4),
+        ];
+        let report = dispositions_by_sku(&rows);
+        assert_eq!(report.iter().map(|s| s.sku.as_str()).collect::<Vec<_>>(), ["TEE", "MUG"]);
+        let tee = &report[0];
+        assert_eq!((tee.quantity, tee.dispositions.scrap, tee.dispositions.restock), (15, 3, 10));
+        assert_eq!(tee.recovery_rate, dec!(0.8));
+        assert_eq!(tee.by_reason[0].reason, "wrong_size");
+        assert_eq!(tee.by_reason[1].dispositions.refurbish, 2);
+        assert_eq!(report[1].recovery_rate, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_lines_are_validated() {
+        let line = DispositionLine {
+            sku: "TEE".to_string(),
+            quantity: 0,
+            warehouse: 1,
+            disposition: Disposition::Restock,
+            reason: None,
+            supplier_id: None,
+            notes: None,
+        };
+        assert!(DisposeRequest { lines: vec![line.clone()] }.validate().is_err());
+        assert!(DisposeRequest { lines: vec![DispositionLine { quantity: 2, ..line }] }.validate().is_ok());
+        assert!(DisposeRequest { lines: vec![] }.validate().is_err());
+    }
+}
@@ -0,0 +1,560 @@
+use chrono::{Duration, Utc};
+use rust_decimal::Decimal;
+use sea_orm::sea_query::{Expr, OnConflict};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+
+use crate::{
+    errors::ServiceError,
+    models::{
+        order::{self, Entity as Order},
+        return_entity::{self, ActionNeeded, Condition, Entity as Return, ReturnStatus},
+        shipped_serial::{self, Entity as ShippedSerial},
+    },
+};
+
+/// Return fraud scoring, loaded from the `return_fraud` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ReturnFraudConfig {
+    /// Registers the `return_fraud` agent, which scores new returns and re-scores them
+    /// when a serial number is added.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Returns scoring at least this, out of 100, are flagged for inspection.
+    #[serde(default = "default_flag_threshold")]
+    pub flag_threshold: i32,
+
+    /// Days of a customer's history the rate, value and repeat signals look at.
+    #[serde(default = "default_window_days")]
+    pub window_days: i64,
+
+    /// Share of the customer's orders returned above which the rate signal fires.
+    #[serde(default = "default_max_return_rate")]
+    pub max_return_rate: f64,
+
+    /// Orders a customer needs in the window before their return rate counts.
+    #[serde(default = "default_min_orders")]
+    pub min_orders: u64,
+
+    /// Total value of the customer's returns in the window above which the value signal fires.
+    #[serde(default = "default_max_return_value")]
+    pub max_return_value: Decimal,
+
+    /// Reason categories that do not claim a fault, such as a change of mind.
+    #[serde(default = "default_discretionary_reasons")]
+    pub discretionary_reasons: Vec<String>,
+
+    /// A discretionary return requested this many days or more after the order suggests
+    /// the item was used in the meantime.
+    #[serde(default = "default_late_return_days")]
+    pub late_return_days: i64,
+
+    /// Earlier discretionary returns in the window from which a pattern is assumed.
+    #[serde(default = "default_repeat_discretionary")]
+    pub repeat_discretionary: u64,
+}
+
+fn default_flag_threshold() -> i32 {
+    50
+}
+
+fn default_window_days() -> i64 {
+    180
+}
+
+fn default_max_return_rate() -> f64 {
+    0.5
+}
+
+fn default_min_orders() -> u64 {
+    3
+}
+
+fn default_max_return_value() -> Decimal {
+    Decimal::new(100000, 2)
+}
+
+fn default_discretionary_reasons() -> Vec<String> {
+    ["changed_mind", "no_longer_needed", "size", "style"].iter().map(|r| r.to_string()).collect()
+}
+
+fn default_late_return_days() -> i64 {
+    21
+}
+
+fn default_repeat_discretionary() -> u64 {
+    2
+}
+
+impl Default for ReturnFraudConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            flag_threshold: default_flag_threshold(),
+            window_days: default_window_days(),
+            max_return_rate: default_max_return_rate(),
+            min_orders: default_min_orders(),
+            max_return_value: default_max_return_value(),
+            discretionary_reasons: default_discretionary_reasons(),
+            late_return_days: default_late_return_days(),
+            repeat_discretionary: default_repeat_discretionary(),
+        }
+    }
+}
+
+impl ReturnFraudConfig {
+    fn is_discretionary(&self, reason: Option<&str>) -> bool {
+        reason.map_or(false, |reason| self.discretionary_reasons.iter().any(|r| r.eq_ignore_ascii_case(reason)))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SignalKind {
+    /// The returned unit's serial is not one the order shipped with.
+    SerialMismatch,
+    /// The order shipped serialized units but the received return has no serial.
+    SerialMissing,
+    ReturnRate,
+    ReturnValue,
+    /// Sold as new, received used or damaged.
+    ConditionMismatch,
+    /// Wardrobing: a no-fault return late in the return window.
+    LateDiscretionaryReturn,
+    /// Wardrobing: a habit of no-fault returns.
+    RepeatDiscretionary,
+}
+
+impl SignalKind {
+    /// Points the signal adds to the score; a serial mismatch alone crosses the default threshold.
+    pub fn points(self) -> i32 {
+        match self {
+            SignalKind::SerialMismatch => 60,
+            SignalKind::SerialMissing => 20,
+            SignalKind::ReturnRate => 25,
+            SignalKind::ReturnValue => 20,
+            SignalKind::ConditionMismatch => 25,
+            SignalKind::LateDiscretionaryReturn => 15,
+            SignalKind::RepeatDiscretionary => 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FraudSignal {
+    pub kind: SignalKind,
+    pub points: i32,
+    pub detail: String,
+}
+
+/// What scoring knows about a return beyond the row itself.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FraudFacts {
+    /// Serials the order shipped with; empty when none were recorded.
+    pub shipped_serials: Vec<String>,
+    /// Another order the returned serial shipped with, if any.
+    pub serial_shipped_with: Option<Uuid>,
+    /// The customer's orders in the window.
+    pub orders: u64,
+    /// The customer's returns in the window, this one included.
+    pub returns: u64,
+    pub returned_value: Decimal,
+    /// The customer's other discretionary returns in the window.
+    pub prior_discretionary: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RiskAssessment {
+    pub score: i32,
+    pub flagged: bool,
+    /// Serial number the assessment saw, so adding one later triggers a new assessment.
+    pub serial_number: Option<String>,
+    pub signals: Vec<FraudSignal>,
+}
+
+/// Serials are compared trimmed and upper-cased.
+pub fn normalize_serial(serial: &str) -> String {
+    serial.trim().to_ascii_uppercase()
+}
+
+/// Scores a return from 0 to 100 as the sum of the signals that fire.
+pub fn assess(ret: &return_entity::Model, facts: &FraudFacts, config: &ReturnFraudConfig) -> RiskAssessment {
+    let mut signals = Vec::new();
+    let mut signal = |kind: SignalKind, detail: String| signals.push(FraudSignal { kind, points: kind.points(), detail });
+
+    let serial = ret.serial_number.as_deref().map(normalize_serial).filter(|s| !s.is_empty());
+    if !facts.shipped_serials.is_empty() {
+        match &serial {
+            Some(serial) if !facts.shipped_serials.contains(serial) => signal(
+                SignalKind::SerialMismatch,
+                match facts.serial_shipped_with {
+                    Some(other) => format!("serial {} shipped with order {}, not this one", serial, other),
+                    None => format!("serial {} was not shipped with this order", serial),
+                },
+            ),
+            None if ret.status == ReturnStatus::Received => signal(
+                SignalKind::SerialMissing,
+                format!("received without a serial; the order shipped {} serialized units", facts.shipped_serials.len()),
+            ),
+            _ => {}
+        }
+    }
+
+    if facts.orders >= config.min_orders {
+        let rate = facts.returns as f64 / facts.orders as f64;
+        if rate > config.max_return_rate {
+            signal(
+                SignalKind::ReturnRate,
+                format!("{} returns on {} orders in the last {} days", facts.returns, facts.orders, config.window_days),
+            );
+        }
+    }
+    if facts.returned_value > config.max_return_value {
+        signal(
+            SignalKind::ReturnValue,
+            format!("returns worth {} in the last {} days", facts.returned_value, config.window_days),
+        );
+    }
+
+    if ret.reported_condition == Some(Condition::New) && matches!(ret.condition, Condition::Used | Condition::Damaged) {
+        signal(SignalKind::ConditionMismatch, format!("reported new, inspected as {:?}", ret.condition).to_lowercase());
+    }
+    if config.is_discretionary(ret.reason_category.as_deref()) {
+        let age = (ret.requested_date - ret.order_date).num_days();
+        if age >= config.late_return_days {
+            signal(
+                SignalKind::LateDiscretionaryReturn,
+                format!("no-fault return requested {} days after the order", age),
+            );
+        }
+        if facts.prior_discretionary >= config.repeat_discretionary {
+            signal(
+                SignalKind::RepeatDiscretionary,
+                format!("{} earlier no-fault returns in the last {} days", facts.prior_discretionary, config.window_days),
+            );
+        }
+    }
+
+    let score = signals.iter().map(|s| s.points).sum::<i32>().clamp(0, 100);
+    RiskAssessment { score, flagged: score >= config.flag_threshold, serial_number: serial, signals }
+}
+
+/// Statuses of returns that can still be stopped by an inspection.
+const OPEN_STATUSES: [ReturnStatus; 3] = [ReturnStatus::Requested, ReturnStatus::Approved, ReturnStatus::Received];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SerialInput {
+    pub sku: String,
+    pub serial_number: String,
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Return fraud query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Scores returns for fraud and flags the risky ones for inspection.
+pub struct ReturnFraudService {
+    db: Arc<DatabaseConnection>,
+    config: ReturnFraudConfig,
+}
+
+impl ReturnFraudService {
+    pub fn new(db: Arc<DatabaseConnection>, config: ReturnFraudConfig) -> Self {
+        Self { db, config }
+    }
+
+    pub fn config(&self) -> &ReturnFraudConfig {
+        &self.config
+    }
+
+    /// Records the serials an order shipped with; serials already recorded are skipped.
+    pub async fn record_serials(&self, order_id: Uuid, serials: Vec<SerialInput>, actor: &str) -> Result<u64, ServiceError> {
+        if serials.iter().any(|s| s.sku.trim().is_empty() || s.serial_number.trim().is_empty()) {
+            return Err(ServiceError::ValidationError("Every serial needs a SKU and a serial number".to_string()));
+        }
+        Order::find_by_id(order_id)
+            .one(self.db.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Order not found: {}", order_id)))?;
+        let now = Utc::now();
+        let mut recorded = 0;
+        for input in serials {
+            let row = shipped_serial::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                order_id: Set(order_id),
+                sku: Set(input.sku.trim().to_string()),
+                serial_number: Set(normalize_serial(&input.serial_number)),
+                recorded_by: Set(actor.to_string()),
+                recorded_at: Set(now),
+            };
+            recorded += ShippedSerial::insert(row)
+                .on_conflict(
+                    OnConflict::columns([shipped_serial::Column::OrderId, shipped_serial::Column::SerialNumber])
+                        .do_nothing()
+                        .to_owned(),
+                )
+                .exec_without_returning(self.db.as_ref())
+                .await
+                .map_err(db_error)?;
+        }
+        info!(%order_id, recorded, "Shipped serials recorded");
+        Ok(recorded)
+    }
+
+    /// Open returns never assessed, or whose serial changed since they were.
+    pub async fn pending(&self, limit: u64) -> Result<Vec<return_entity::Model>, ServiceError> {
+        Return::find()
+            .filter(return_entity::Column::Status.is_in(OPEN_STATUSES))
+            .filter(
+                sea_orm::Condition::any().add(return_entity::Column::RiskScore.is_null()).add(Expr::cust(
+                    "trim(serial_number) <> '' AND upper(trim(serial_number)) IS DISTINCT FROM risk_signals ->> 'serial_number'",
+                )),
+            )
+            .order_by_asc(return_entity::Column::RequestedDate)
+            .limit(limit)
+            .all(self.db.as_ref())
+            .await
+            .map_err(db_error)
+    }
+
+    async fn facts(&self, ret: &return_entity::Model) -> Result<FraudFacts, ServiceError> {
+        let db = self.db.as_ref();
+        let shipped_serials: Vec<String> = ShippedSerial::find()
+            .select_only()
+            .column(shipped_serial::Column::SerialNumber)
+            .filter(shipped_serial::Column::OrderId.eq(ret.order_id))
+            .into_tuple()
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        let serial = ret.serial_number.as_deref().map(normalize_serial).filter(|s| !s.is_empty());
+        let serial_shipped_with = match serial {
+            Some(serial) if !shipped_serials.contains(&serial) => ShippedSerial::find()
+                .filter(shipped_serial::Column::SerialNumber.eq(serial))
+                .order_by_desc(shipped_serial::Column::RecordedAt)
+                .one(db)
+                .await
+                .map_err(db_error)?
+                .map(|s| s.order_id),
+            _ => None,
+        };
+
+        let since = Utc::now() - Duration::days(self.config.window_days);
+        let orders = Order::find()
+            .filter(order::Column::CustomerEmail.eq(ret.customer_email.as_str()))
+            .filter(order::Column::CreatedDate.gte(since))
+            .count(db)
+            .await
+            .map_err(db_error)?;
+        let history: Vec<(Uuid, Decimal, Option<String>)> = Return::find()
+            .select_only()
+            .columns([
+                return_entity::Column::Id,
+                return_entity::Column::Amount,
+                return_entity::Column::ReasonCategory,
+            ])
+            .filter(return_entity::Column::CustomerEmail.eq(ret.customer_email.as_str()))
+            .filter(return_entity::Column::RequestedDate.gte(since))
+            .filter(return_entity::Column::Status.is_not_in([ReturnStatus::Draft, ReturnStatus::Rejected]))
+            .into_tuple()
+            .all(db)
+            .await
+            .map_err(db_error)?;
+
+        let others = history.iter().filter(|(id, _, _)| *id != ret.id);
+        Ok(FraudFacts {
+            shipped_serials,
+            serial_shipped_with,
+            orders,
+            returns: others.clone().count() as u64 + 1,
+            returned_value: others.clone().map(|(_, amount, _)| *amount).sum::<Decimal>() + ret.amount,
+            prior_discretionary: others.filter(|(_, _, reason)| self.config.is_discretionary(reason.as_deref())).count() as u64,
+        })
+    }
+
+    #[instrument(skip(self, ret), fields(return_id = %ret.id))]
+    pub async fn evaluate(&self, ret: &return_entity::Model) -> Result<RiskAssessment, ServiceError> {
+        Ok(assess(ret, &self.facts(ret).await?, &self.config))
+    }
+
+    /// Stores the score on the return and, when flagged, moves an open return to
+    /// inspection. Returns whether the return was newly flagged.
+    pub async fn apply(&self, ret: &return_entity::Model, assessment: &RiskAssessment) -> Result<bool, ServiceError> {
+        let signals = serde_json::to_value(assessment).map_err(|e| ServiceError::ValidationError(e.to_string()))?;
+        Return::update_many()
+            .col_expr(return_entity::Column::RiskScore, Expr::value(assessment.score))
+            .col_expr(return_entity::Column::RiskSignals, Expr::value(signals))
+            .filter(return_entity::Column::Id.eq(ret.id))
+            .exec(self.db.as_ref())
+            .await
+            .map_err(db_error)?;
+        if !assessment.flagged {
+            return Ok(false);
+        }
+        let result = Return::update_many()
+            .col_expr(return_entity::Column::ActionNeeded, Expr::value(ActionNeeded::Inspection))
+            .filter(return_entity::Column::Id.eq(ret.id))
+            .filter(return_entity::Column::Status.is_in(OPEN_STATUSES))
+            .filter(return_entity::Column::ActionNeeded.ne(ActionNeeded::Inspection))
+            .exec(self.db.as_ref())
+            .await
+            .map_err(db_error)?;
+        if result.rows_affected > 0 {
+            info!(return_id = %ret.id, score = assessment.score, "Return flagged for inspection");
+        }
+        Ok(result.rows_affected > 0)
+    }
+
+    /// Assesses one return now, e.g. right after its serial was scanned at receipt.
+    pub async fn assess_return(&self, id: Uuid) -> Result<RiskAssessment, ServiceError> {
+        let ret = Return::find_by_id(id)
+            .one(self.db.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Return not found: {}", id)))?;
+        let assessment = self.evaluate(&ret).await?;
+        self.apply(&ret, &assessment).await?;
+        Ok(assessment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn ret(reason: &str, days_after_order: i64) -> return_entity::Model {
+        let ordered = Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        return_entity::Model {
+            id: Uuid::new_v4(),
+            created_date: ordered + Duration::days(days_after_order),
+            amount: Decimal::new(8000, 2),
+            action_needed: ActionNeeded::None,
+            condition: Condition::New,
+            customer_email: "ada@example.com".to_string(),
+            customer_id: Uuid::new_v4(),
+            description: None,
+            entered_by: None,
+            flat_rate_shipping: Decimal::ZERO,
+            order_date: ordered,
+            order_id: Uuid::new_v4(),
+            reason_category: Some(reason.to_string()),
+            reported_condition: Some(Condition::New),
+            requested_date: ordered + Duration::days(days_after_order),
+            risk_score: None,
+            risk_signals: None,
+            rma: "RMA-1".to_string(),
+            serial_number: None,
+            shipped_date: None,
+            status: ReturnStatus::Requested,
+            tax_refunded: Decimal::ZERO,
+            total_refunded: Decimal::ZERO,
+            tracking_number: None,
+        }
+    }
+
+    fn kinds(assessment: &RiskAssessment) -> Vec<SignalKind> {
+        assessment.signals.iter().map(|s| s.kind).collect()
+    }
+
+    #[test]
+    fn test_clean_return_scores_zero() {
+        let facts = FraudFacts { orders: 4, returns: 1, ..Default::default() };
+        let assessment = assess(&ret("defective", 5), &facts, &ReturnFraudConfig::default());
+        assert_eq!(assessment.score, 0);
+        assert!(!assessment.flagged);
+    }
+
+    #[test]
+    fn test_serial_mismatch_is_flagged() {
+        let other = Uuid::new_v4();
+        let facts = FraudFacts {
+            shipped_serials: vec!["SN-1".to_string()],
+            serial_shipped_with: Some(other),
+            ..Default::default()
+        };
+        let mut returned = ret("defective", 5);
+        returned.serial_number = Some(" sn-1 ".to_string());
+        assert_eq!(assess(&returned, &facts, &ReturnFraudConfig::default()).score, 0);
+
+        returned.serial_number = Some("SN-2".to_string());
+        let assessment = assess(&returned, &facts, &ReturnFraudConfig::default());
+        assert_eq!(kinds(&assessment), [SignalKind::SerialMismatch]);
+        assert!(assessment.flagged);
+        assert!(assessment.signals[0].detail.contains(&other.to_string()));
+        assert_eq!(assessment.serial_number.as_deref(), Some("SN-2"));
+
+        returned.serial_number = None;
+        assert!(assess(&returned, &facts, &ReturnFraudConfig::default()).signals.is_empty());
+        returned.status = ReturnStatus::Received;
+        assert_eq!(kinds(&assess(&returned, &facts, &ReturnFraudConfig::default())), [SignalKind::SerialMissing]);
+    }
+
+    #[test]
+    fn test_return_frequency_and_value() {
+        let config = ReturnFraudConfig::default();
+        // Too few orders for the rate to mean anything
+        let new_customer = FraudFacts { orders: 1, returns: 1, ..Default::default() };
+        assert!(assess(&ret("defective", 5), &new_customer, &config).signals.is_empty());
+
+        let serial_returner = FraudFacts {
+            orders: 4,
+            returns: 3,
+            returned_value: Decimal::new(150000, 2),
+            ..Default::default()
+        };
+        let assessment = assess(&ret("defective", 5), &serial_returner, &config);
+        assert_eq!(kinds(&assessment), [SignalKind::ReturnRate, SignalKind::ReturnValue]);
+        assert_eq!(assessment.score, 45);
+        assert!(!assessment.flagged);
+    }
+
+    #[test]
+    fn test_wardrobing_heuristics() {
+        let config = ReturnFraudConfig::default();
+        let habit = FraudFacts { prior_discretionary: 2, ..Default::default() };
+        let mut worn = ret("Changed_Mind", 28);
+        worn.condition = Condition::Used;
+        let assessment = assess(&worn, &habit, &config);
+        assert_eq!(
+            kinds(&assessment),
+            [SignalKind::ConditionMismatch, SignalKind::LateDiscretionaryReturn, SignalKind::RepeatDiscretionary]
+        );
+        assert_eq!(assessment.score, 60);
+        assert!(assessment.flagged);
+
+        // A fault claim is neither late-discretionary nor part of the habit
+        assert!(assess(&ret("defective", 28), &habit, &config).signals.is_empty());
+    }
+
+    #[test]
+    fn test_score_is_capped() {
+        let facts = FraudFacts {
+            shipped_serials: vec!["SN-1".to_string()],
+            orders: 3,
+            returns: 3,
+            returned_value: Decimal::new(500000, 2),
+            prior_discretionary: 5,
+            ..Default::default()
+        };
+        let mut returned = ret("size", 40);
+        returned.serial_number = Some("SN-9".to_string());
+        assert_eq!(assess(&returned, &facts, &ReturnFraudConfig::default()).score, 100);
+    }
+
+    #[test]
+    fn test_migration_is_safe() {
+        let plan = crate::db::migration_safety::analyze(
+            "20261016160000_return_fraud.sql",
+            include_str!("../../migrations/20261016160000_return_fraud.sql"),
+        );
+        assert_eq!(plan.violations().count(), 0);
+    }
+}
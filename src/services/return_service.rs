@@ -0,0 +1,228 @@
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, instrument};
+use uuid::Uuid;
+
+use crate::{
+    db::DbPool,
+    errors::ServiceError,
+    events::{Event, EventSender},
+    models::{
+        approval_step::ApprovalSubject,
+        return_entity::{self, ActionNeeded, Entity as Return, ReturnStatus},
+    },
+    services::return_triage::TriageDecision,
+    utils::pagination::PaginationParams,
+    workflow::{ApprovalEngine, ApprovalHandler, Resolution},
+};
+
+/// Filters accepted by `GET /returns/search`. All filters are optional and combined with AND.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ReturnSearchParams {
+    pub customer_id: Option<Uuid>,
+    pub order_id: Option<Uuid>,
+    pub status: Option<ReturnStatus>,
+    pub rma: Option<String>,
+    /// `Inspection` together with status `Requested` is the queue of returns triage left for a person.
+    pub action_needed: Option<ActionNeeded>,
+    /// Only returns whose fraud risk score is at least this, riskiest first.
+    pub min_risk_score: Option<i32>,
+}
+
+/// Return reads and updates used by the HTTP handlers. Handlers depend on this trait
+/// rather than on `ReturnService` so their tests can use `MockReturnServiceApi`.
+#[cfg_attr(test, mockall::automock)]
+#[async_trait]
+pub trait ReturnServiceApi: Send + Sync {
+    async fn get_return(&self, id: Uuid) -> Result<return_entity::Model, ServiceError>;
+
+    /// Replaces every field of the return except its id.
+    async fn update_return(&self, id: Uuid, ret: return_entity::Model) -> Result<return_entity::Model, ServiceError>;
+
+    /// Returns one page of returns, newest first, and the total number of returns.
+    async fn list_returns(&self, pagination: PaginationParams) -> Result<(Vec<return_entity::Model>, u64), ServiceError>;
+
+    async fn search_returns(
+        &self,
+        params: ReturnSearchParams,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<return_entity::Model>, u64), ServiceError>;
+}
+
+/// Database-backed return service.
+pub struct ReturnService {
+    db_pool: Arc<DbPool>,
+}
+
+impl ReturnService {
+    pub fn new(db_pool: Arc<DbPool>) -> Self {
+        Self { db_pool }
+    }
+
+    async fn page(
+        &self,
+        query: Select<Return>,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<return_entity::Model>, u64), ServiceError> {
+        let paginator = query
+            .order_by_desc(return_entity::Column::CreatedDate)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let returns = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((returns, total))
+    }
+
+    /// Applies an automated triage decision to a return that is still requested, not
+    /// flagged and not awaiting an approval chain; anything else means a person got to it
+    /// first. Approval sends `ReturnApproved` as a chain's approval would. Returns whether
+    /// the decision applied.
+    #[instrument(skip(self, events))]
+    pub async fn apply_triage(&self, id: Uuid, decision: TriageDecision, events: &EventSender) -> Result<bool, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let ret = Return::find_by_id(id)
+            .lock_exclusive()
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Return not found: {}", id)))?;
+        if ret.status != ReturnStatus::Requested
+            || ret.action_needed != ActionNeeded::None
+            || ApprovalEngine::awaiting(&txn, ApprovalSubject::Return, &id.to_string()).await?
+        {
+            return Ok(false);
+        }
+        let mut active: return_entity::ActiveModel = ret.into();
+        let event = match decision {
+            TriageDecision::AutoApprove => {
+                active.status = Set(ReturnStatus::Approved);
+                Some(Event::ReturnApproved(id))
+            }
+            TriageDecision::Review => {
+                active.action_needed = Set(ActionNeeded::Inspection);
+                None
+            }
+        };
+        active.update(&txn).await.map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+        if let Some(event) = event {
+            let _ = events.send(event);
+        }
+        Ok(true)
+    }
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Return query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+#[async_trait]
+impl ReturnServiceApi for ReturnService {
+    #[instrument(skip(self))]
+    async fn get_return(&self, id: Uuid) -> Result<return_entity::Model, ServiceError> {
+        Return::find_by_id(id)
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Return not found: {}", id)))
+    }
+
+    #[instrument(skip(self, ret))]
+    async fn update_return(&self, id: Uuid, ret: return_entity::Model) -> Result<return_entity::Model, ServiceError> {
+        self.get_return(id).await?;
+        let mut active = return_entity::Model { id, ..ret }.into_active_model().reset_all();
+        active.id = Unchanged(id);
+        active.update(self.db_pool.as_ref()).await.map_err(db_error)
+    }
+
+    async fn list_returns(&self, pagination: PaginationParams) -> Result<(Vec<return_entity::Model>, u64), ServiceError> {
+        self.page(Return::find(), pagination).await
+    }
+
+    async fn search_returns(
+        &self,
+        params: ReturnSearchParams,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<return_entity::Model>, u64), ServiceError> {
+        let mut query = Return::find();
+        if let Some(customer_id) = params.customer_id {
+            query = query.filter(return_entity::Column::CustomerId.eq(customer_id));
+        }
+        if let Some(order_id) = params.order_id {
+            query = query.filter(return_entity::Column::OrderId.eq(order_id));
+        }
+        if let Some(status) = params.status {
+            query = query.filter(return_entity::Column::Status.eq(status));
+        }
+        if let Some(rma) = params.rma {
+            query = query.filter(return_entity::Column::Rma.eq(rma));
+        }
+        if let Some(action_needed) = params.action_needed {
+            query = query.filter(return_entity::Column::ActionNeeded.eq(action_needed));
+        }
+        if let Some(min_risk_score) = params.min_risk_score {
+            query = query
+                .filter(return_entity::Column::RiskScore.gte(min_risk_score))
+                .order_by_desc(return_entity::Column::RiskScore);
+        }
+        self.page(query, pagination).await
+    }
+}
+
+/// Workflow side of returns: a requested return is routed on its amount, and the outcome
+/// approves or rejects it.
+pub struct ReturnApprovals;
+
+async fn find_requested(txn: &DatabaseTransaction, subject_id: &str) -> Result<return_entity::Model, ServiceError> {
+    let id = Uuid::parse_str(subject_id)
+        .map_err(|_| ServiceError::ValidationError(format!("Invalid return id: {}", subject_id)))?;
+    let ret = Return::find_by_id(id)
+        .lock_exclusive()
+        .one(txn)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| ServiceError::NotFound(format!("Return not found: {}", id)))?;
+    if ret.status != ReturnStatus::Requested {
+        return Err(ServiceError::ValidationError(format!("Return is {:?}, not requested", ret.status)));
+    }
+    Ok(ret)
+}
+
+#[async_trait]
+impl ApprovalHandler for ReturnApprovals {
+    fn subject(&self) -> ApprovalSubject {
+        ApprovalSubject::Return
+    }
+
+    async fn submit(&self, txn: &DatabaseTransaction, subject_id: &str, _actor: &str) -> Result<Decimal, ServiceError> {
+        Ok(find_requested(txn, subject_id).await?.amount)
+    }
+
+    async fn resolve(
+        &self,
+        txn: &DatabaseTransaction,
+        subject_id: &str,
+        resolution: Resolution,
+        _actor: &str,
+    ) -> Result<Option<Event>, ServiceError> {
+        let ret = find_requested(txn, subject_id).await?;
+        let id = ret.id;
+        let mut active: return_entity::ActiveModel = ret.into();
+        let event = match resolution {
+            Resolution::Approved => {
+                active.status = Set(ReturnStatus::Approved);
+                Event::ReturnApproved(id)
+            }
+            Resolution::Rejected => {
+                active.status = Set(ReturnStatus::Rejected);
+                Event::ReturnRejected(id)
+            }
+        };
+        active.update(txn).await.map_err(db_error)?;
+        Ok(Some(event))
+    }
+}
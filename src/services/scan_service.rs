@@ -0,0 +1,510 @@
+use chrono::Utc;
+use rust_decimal::Decimal;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, instrument};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db::DbPool,
+    errors::ServiceError,
+    events::{Event, EventSender},
+    models::{
+        asn::{self, Entity as Asn},
+        inventory_items::{self, Entity as InventoryItem},
+        order::{self, Entity as Order},
+        scan_transaction::{self, Entity as ScanTransaction},
+        shipment::{self, Entity as Shipment, ShipmentStatus},
+        work_order::{self, Entity as WorkOrder, WorkOrderStatus},
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanAction {
+    Receive,
+    Pick,
+    Pack,
+    Count,
+}
+
+impl ScanAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            ScanAction::Receive => "receive",
+            ScanAction::Pick => "pick",
+            ScanAction::Pack => "pack",
+            ScanAction::Count => "count",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScannedKind {
+    Sku,
+    Bin,
+    Asn,
+    Shipment,
+    WorkOrder,
+}
+
+/// A scanned code and what can be done with it next.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Resolved {
+    #[serde(rename = "type")]
+    pub kind: ScannedKind,
+    pub id: String,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub qty: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    pub actions: Vec<ScanAction>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ResolveQuery {
+    pub code: String,
+    /// The scanner's warehouse; stock figures cover all warehouses without it.
+    pub warehouse: Option<i32>,
+}
+
+/// A scan post. `qty` is required for receive, pick and count.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+pub struct ScanPost {
+    #[validate(length(min = 1, max = 128))]
+    pub code: String,
+    #[validate(range(min = 0, max = 100000))]
+    pub qty: Option<i32>,
+    pub warehouse: Option<i32>,
+    #[serde(rename = "ref")]
+    #[validate(length(max = 128))]
+    pub reference: Option<String>,
+    #[validate(length(max = 64))]
+    pub device: Option<String>,
+}
+
+/// What a scan post changed, kept short for small scanner screens.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScanResult {
+    pub action: ScanAction,
+    pub code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warehouse: Option<i32>,
+    /// Units on hand after the scan.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub on_hand: Option<i32>,
+    /// Counted minus expected, for counts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variance: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+}
+
+/// A scan result and whether it was replayed from an earlier post with the same key.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanOutcome {
+    pub result: ScanResult,
+    pub replayed: bool,
+}
+
+/// Next steps for stock of a SKU: receive while some is still inbound, pick while some is
+/// on hand, and count at any time.
+pub fn stock_actions(incoming: i64, available: i64) -> Vec<ScanAction> {
+    let mut actions = Vec::with_capacity(3);
+    if incoming > 0 {
+        actions.push(ScanAction::Receive);
+    }
+    if available > 0 {
+        actions.push(ScanAction::Pick);
+    }
+    actions.push(ScanAction::Count);
+    actions
+}
+
+/// Units on hand after `action` moves `qty` units, or why it cannot.
+pub fn apply_stock(action: ScanAction, available: i32, qty: i32) -> Result<i32, String> {
+    match action {
+        ScanAction::Receive if qty == 0 => Err("Nothing to receive".to_string()),
+        ScanAction::Receive => available.checked_add(qty).ok_or_else(|| "Quantity is too large".to_string()),
+        ScanAction::Pick if qty == 0 => Err("Nothing to pick".to_string()),
+        ScanAction::Pick if qty > available => Err(format!("Only {} on hand", available)),
+        ScanAction::Pick => Ok(available - qty),
+        ScanAction::Count => Ok(qty),
+        ScanAction::Pack => Err("Pack applies to shipments".to_string()),
+    }
+}
+
+/// Whether a stored scan is the same request as a retry carrying its key.
+fn same_scan(stored: &scan_transaction::Model, action: ScanAction, post: &ScanPost) -> bool {
+    stored.action == action.as_str()
+        && stored.code == post.code
+        && stored.quantity == post.qty
+        && stored.warehouse == post.warehouse
+}
+
+fn work_order_number(code: &str) -> Option<i32> {
+    code.strip_prefix("WO-").and_then(|n| n.parse().ok())
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Scan query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Endpoints for handheld RF scanners: resolve whatever was scanned, then post the movement
+/// in one small request that is safe to retry.
+pub struct ScanService {
+    db_pool: Arc<DbPool>,
+    events: EventSender,
+}
+
+impl ScanService {
+    pub fn new(db_pool: Arc<DbPool>, events: EventSender) -> Self {
+        Self { db_pool, events }
+    }
+
+    /// Tries the code as, in order, a work order label (`WO-<number>`), an ASN number, a
+    /// tracking number, a SKU or UPC, and a bin location.
+    #[instrument(skip(self))]
+    pub async fn resolve(&self, query: ResolveQuery) -> Result<Resolved, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let code = query.code.trim();
+
+        if let Some(number) = work_order_number(code) {
+            if let Some(work_order) = WorkOrder::find()
+                .filter(work_order::Column::Number.eq(number))
+                .one(db)
+                .await
+                .map_err(db_error)?
+            {
+                let open = matches!(work_order.status, WorkOrderStatus::Pending | WorkOrderStatus::InProgress);
+                return Ok(Resolved {
+                    kind: ScannedKind::WorkOrder,
+                    id: work_order.id.to_string(),
+                    title: work_order.part.clone(),
+                    qty: None,
+                    status: Some(format!("{:?}", work_order.status)),
+                    actions: if open { vec![ScanAction::Pick] } else { Vec::new() },
+                });
+            }
+        }
+
+        if let Some(asn) = Asn::find()
+            .filter(asn::Column::AsnNumber.eq(code))
+            .one(db)
+            .await
+            .map_err(db_error)?
+        {
+            return Ok(Resolved {
+                kind: ScannedKind::Asn,
+                id: asn.id.to_string(),
+                title: asn.asn_number.clone(),
+                qty: None,
+                status: Some(format!("{:?}", asn.status)),
+                actions: if asn.status.is_inbound() { vec![ScanAction::Receive] } else { Vec::new() },
+            });
+        }
+
+        if let Some(shipment) = Shipment::find()
+            .filter(shipment::Column::TrackingNumber.eq(code))
+            .one(db)
+            .await
+            .map_err(db_error)?
+        {
+            return Ok(Resolved {
+                kind: ScannedKind::Shipment,
+                id: shipment.id.to_string(),
+                title: format!("{:?} {}", shipment.carrier, shipment.tracking_number),
+                qty: None,
+                status: Some(format!("{:?}", shipment.status)),
+                actions: if shipment.status == ShipmentStatus::Processing {
+                    vec![ScanAction::Pack]
+                } else {
+                    Vec::new()
+                },
+            });
+        }
+
+        let mut items = InventoryItem::find().filter(
+            Condition::any()
+                .add(inventory_items::Column::Sku.eq(code))
+                .add(inventory_items::Column::Upc.eq(code)),
+        );
+        if let Some(warehouse) = query.warehouse {
+            items = items.filter(inventory_items::Column::Warehouse.eq(warehouse));
+        }
+        let items = items.all(db).await.map_err(db_error)?;
+        if let Some(first) = items.first() {
+            let available: i64 = items.iter().map(|i| i64::from(i.available)).sum();
+            let incoming: i64 = items.iter().map(|i| i64::from(i.incoming)).sum();
+            return Ok(Resolved {
+                kind: ScannedKind::Sku,
+                id: first.sku.clone(),
+                title: first.description.clone(),
+                qty: Some(available),
+                status: None,
+                actions: stock_actions(incoming, available),
+            });
+        }
+
+        let mut bin = InventoryItem::find().filter(inventory_items::Column::LocationInWarehouse.eq(code));
+        if let Some(warehouse) = query.warehouse {
+            bin = bin.filter(inventory_items::Column::Warehouse.eq(warehouse));
+        }
+        let skus = bin.count(db).await.map_err(db_error)?;
+        if skus > 0 {
+            return Ok(Resolved {
+                kind: ScannedKind::Bin,
+                id: code.to_string(),
+                title: format!("{} SKUs", skus),
+                qty: None,
+                status: None,
+                actions: vec![ScanAction::Count],
+            });
+        }
+
+        Err(ServiceError::NotFound(format!("Nothing matches {}", code)))
+    }
+
+    /// Posts a scan once per `idempotency_key`. A retry with the same key and payload gets
+    /// the first result back; reusing a key for a different scan is rejected.
+    #[instrument(skip(self, post), fields(code = %post.code))]
+    pub async fn post(
+        &self,
+        action: ScanAction,
+        post: ScanPost,
+        idempotency_key: &str,
+        actor: &str,
+    ) -> Result<ScanOutcome, ServiceError> {
+        post.validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid scan: {}", e)))?;
+        if idempotency_key.is_empty() || idempotency_key.len() > 128 {
+            return Err(ServiceError::ValidationError("Idempotency-Key must be 1 to 128 characters".to_string()));
+        }
+
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        if let Some(stored) = ScanTransaction::find()
+            .filter(scan_transaction::Column::IdempotencyKey.eq(idempotency_key))
+            .one(&txn)
+            .await
+            .map_err(db_error)?
+        {
+            if !same_scan(&stored, action, &post) {
+                return Err(ServiceError::ValidationError(
+                    "Idempotency-Key was already used for a different scan".to_string(),
+                ));
+            }
+            let result = serde_json::from_value(stored.response)
+                .map_err(|e| ServiceError::DatabaseError(format!("Stored scan result is unreadable: {}", e)))?;
+            return Ok(ScanOutcome { result, replayed: true });
+        }
+
+        let (result, event) = match action {
+            ScanAction::Pack => self.pack(&txn, &post).await?,
+            _ => {
+                let (result, event) = self.move_stock(&txn, action, &post).await?;
+                (result, Some(event))
+            }
+        };
+
+        scan_transaction::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            idempotency_key: Set(idempotency_key.to_string()),
+            action: Set(action.as_str().to_string()),
+            code: Set(post.code),
+            quantity: Set(post.qty),
+            warehouse: Set(post.warehouse),
+            reference: Set(post.reference),
+            device_id: Set(post.device),
+            actor: Set(actor.to_string()),
+            response: Set(serde_json::to_value(&result).expect("scan results serialize")),
+            created_at: Set(Utc::now()),
+        }
+        .insert(&txn)
+        .await
+        .map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+
+        if let Some(event) = event {
+            let _ = self.events.send(event);
+        }
+        Ok(ScanOutcome { result, replayed: false })
+    }
+
+    async fn move_stock(
+        &self,
+        txn: &DatabaseTransaction,
+        action: ScanAction,
+        post: &ScanPost,
+    ) -> Result<(ScanResult, Event), ServiceError> {
+        let qty = post
+            .qty
+            .ok_or_else(|| ServiceError::ValidationError(format!("qty is required to {}", action.as_str())))?;
+        let mut items = InventoryItem::find().filter(
+            Condition::any()
+                .add(inventory_items::Column::Sku.eq(post.code.as_str()))
+                .add(inventory_items::Column::Upc.eq(post.code.as_str())),
+        );
+        if let Some(warehouse) = post.warehouse {
+            items = items.filter(inventory_items::Column::Warehouse.eq(warehouse));
+        }
+        let mut items = items.lock_exclusive().all(txn).await.map_err(db_error)?;
+        let item = match items.len() {
+            0 => return Err(ServiceError::NotFound(format!("No stock record for {}", post.code))),
+            1 => items.remove(0),
+            _ => {
+                return Err(ServiceError::ValidationError(format!(
+                    "{} is stocked in several places; send a warehouse",
+                    post.code
+                )))
+            }
+        };
+
+        let available = apply_stock(action, item.available, qty).map_err(ServiceError::ValidationError)?;
+        let now = Utc::now();
+        let (sku, warehouse, before, incoming) = (item.sku.clone(), item.warehouse, item.available, item.incoming);
+        let mut stock: inventory_items::ActiveModel = item.into();
+        stock.available = Set(available);
+        stock.last_movement_date = Set(Some(now));
+        let mut variance = None;
+        match action {
+            ScanAction::Receive => stock.incoming = Set((incoming - qty).max(0)),
+            ScanAction::Count => {
+                variance = Some(qty - before);
+                stock.stocktake_quantity = Set(Some(qty));
+                stock.stocktake_variance = Set(Some(Decimal::from(qty - before)));
+                stock.last_stocktake_date = Set(Some(now.date_naive()));
+            }
+            _ => {}
+        }
+        stock.update(txn).await.map_err(db_error)?;
+
+        let result = ScanResult {
+            action,
+            code: sku.clone(),
+            warehouse: Some(warehouse),
+            on_hand: Some(available),
+            variance,
+            status: None,
+        };
+        let event = Event::InventoryLevelChanged { warehouse_id: warehouse, sku, available };
+        Ok((result, event))
+    }
+
+    /// Confirms a shipment is packed and sealed, which hands it to the carrier. The
+    /// shipment is announced for the order carrying its tracking number, if any.
+    async fn pack(
+        &self,
+        txn: &DatabaseTransaction,
+        post: &ScanPost,
+    ) -> Result<(ScanResult, Option<Event>), ServiceError> {
+        let shipment = Shipment::find()
+            .filter(shipment::Column::TrackingNumber.eq(post.code.as_str()))
+            .lock_exclusive()
+            .one(txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("No shipment with tracking number {}", post.code)))?;
+        if shipment.status != ShipmentStatus::Processing {
+            return Err(ServiceError::ValidationError(format!("Shipment is already {:?}", shipment.status)));
+        }
+        let now = Utc::now();
+        let mut active: shipment::ActiveModel = shipment.into();
+        active.status = Set(ShipmentStatus::Shipped);
+        active.shipped_at = Set(Some(now.into()));
+        active.updated_at = Set(now.into());
+        let shipment = active.update(txn).await.map_err(db_error)?;
+        // Shipments reference orders by tracking number; they carry no order UUID
+        let event = Order::find()
+            .filter(order::Column::TrackingNumber.eq(shipment.tracking_number.as_str()))
+            .one(txn)
+            .await
+            .map_err(db_error)?
+            .map(|order| Event::ShipmentShipped {
+                order_id: order.id,
+                tracking_number: shipment.tracking_number.clone(),
+                amount: None,
+            });
+        let result = ScanResult {
+            action: ScanAction::Pack,
+            code: shipment.tracking_number,
+            warehouse: None,
+            on_hand: None,
+            variance: None,
+            status: Some(format!("{:?}", shipment.status)),
+        };
+        Ok((result, event))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(code: &str, qty: Option<i32>) -> ScanPost {
+        ScanPost { code: code.to_string(), qty, warehouse: Some(1), reference: None, device: Some("rf-7".to_string()) }
+    }
+
+    #[test]
+    fn test_stock_actions() {
+        assert_eq!(stock_actions(5, 0), vec![ScanAction::Receive, ScanAction::Count]);
+        assert_eq!(stock_actions(0, 3), vec![ScanAction::Pick, ScanAction::Count]);
+    }
+
+    #[test]
+    fn test_apply_stock() {
+        assert_eq!(apply_stock(ScanAction::Receive, 4, 6), Ok(10));
+        assert_eq!(apply_stock(ScanAction::Pick, 4, 3), Ok(1));
+        assert!(apply_stock(ScanAction::Pick, 4, 5).is_err());
+        assert!(apply_stock(ScanAction::Pick, 4, 0).is_err());
+        assert_eq!(apply_stock(ScanAction::Count, 4, 0), Ok(0));
+    }
+
+    #[test]
+    fn test_retry_must_match_stored_scan() {
+        let stored = scan_transaction::Model {
+            id: Uuid::new_v4(),
+            idempotency_key: "rf-7:1001".to_string(),
+            action: "pick".to_string(),
+            code: "MUG".to_string(),
+            quantity: Some(2),
+            warehouse: Some(1),
+            reference: None,
+            device_id: Some("rf-7".to_string()),
+            actor: "user:1".to_string(),
+            response: serde_json::json!({}),
+            created_at: Utc::now(),
+        };
+        assert!(same_scan(&stored, ScanAction::Pick, &post("MUG", Some(2))));
+        assert!(!same_scan(&stored, ScanAction::Pick, &post("MUG", Some(3))));
+        assert!(!same_scan(&stored, ScanAction::Receive, &post("MUG", Some(2))));
+    }
+
+    #[test]
+    fn test_results_omit_empty_fields() {
+        let result = ScanResult {
+            action: ScanAction::Pick,
+            code: "MUG".to_string(),
+            warehouse: Some(1),
+            on_hand: Some(7),
+            variance: None,
+            status: None,
+        };
+        assert_eq!(
+            serde_json::to_value(&result).unwrap(),
+            serde_json::json!({ "action": "pick", "code": "MUG", "warehouse": 1, "on_hand": 7 })
+        );
+    }
+
+    #[test]
+    fn test_work_order_label_codes() {
+        assert_eq!(work_order_number("WO-1042"), Some(1042));
+        assert_eq!(work_order_number("WO-"), None);
+        assert_eq!(work_order_number("1042"), None);
+    }
+}
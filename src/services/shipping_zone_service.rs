@@ -0,0 +1,680 @@
+use chrono::Utc;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, info, instrument, warn};
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+use crate::{
+    db::DbPool,
+    errors::ServiceError,
+    geocoding::{distance_km, Address, Geocode, Geocoder},
+    models::{
+        address_geocode::{self, Entity as AddressGeocode},
+        shipping_zone::{self, Entity as ShippingZone},
+    },
+    utils::pagination::PaginationParams,
+};
+
+/// Kinds of address the zone of which is stored.
+pub const SUBJECT_TYPES: [&str; 2] = ["customer", "warehouse"];
+
+const REASSIGN_BATCH: u64 = 500;
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "check_new_zone"))]
+pub struct NewShippingZone {
+    #[validate(length(min = 1, max = 32))]
+    pub code: String,
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    #[serde(default = "default_priority")]
+    pub priority: i32,
+    #[serde(default)]
+    pub countries: Vec<String>,
+    #[serde(default)]
+    pub regions: Vec<String>,
+    #[serde(default)]
+    pub postal_prefixes: Vec<String>,
+    pub center_latitude: Option<f64>,
+    pub center_longitude: Option<f64>,
+    pub radius_km: Option<f64>,
+    #[validate(length(max = 64))]
+    pub rate_group: Option<String>,
+    #[validate(length(max = 64))]
+    pub tax_jurisdiction: Option<String>,
+    pub transit_days_min: Option<i32>,
+    pub transit_days_max: Option<i32>,
+}
+
+fn default_priority() -> i32 {
+    100
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct UpdateShippingZone {
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+    pub priority: Option<i32>,
+    pub countries: Option<Vec<String>>,
+    pub regions: Option<Vec<String>>,
+    pub postal_prefixes: Option<Vec<String>>,
+    pub center_latitude: Option<f64>,
+    pub center_longitude: Option<f64>,
+    pub radius_km: Option<f64>,
+    #[validate(length(max = 64))]
+    pub rate_group: Option<String>,
+    #[validate(length(max = 64))]
+    pub tax_jurisdiction: Option<String>,
+    pub transit_days_min: Option<i32>,
+    pub transit_days_max: Option<i32>,
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ShippingZoneFilter {
+    pub active: Option<bool>,
+    pub country: Option<String>,
+}
+
+/// Resolves a zone for an address, coordinates, or both. Coordinates are looked up when
+/// only an address is given and a geocoder is configured.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+pub struct ZoneQuery {
+    #[validate]
+    pub address: Option<Address>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ZoneResolution {
+    pub zone: Option<shipping_zone::Model>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geocode: Option<Geocode>,
+}
+
+/// What an address is matched on.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Location {
+    pub country: Option<String>,
+    pub region: Option<String>,
+    pub postal_code: Option<String>,
+    pub point: Option<(f64, f64)>,
+}
+
+impl Location {
+    /// Uses what was typed, filling gaps from the geocoder.
+    fn from_parts(address: Option<&Address>, geocode: Option<&Geocode>, point: Option<(f64, f64)>) -> Self {
+        Self {
+            country: address
+                .map(|a| a.country.clone())
+                .or_else(|| geocode.and_then(|g| g.country.clone())),
+            region: address
+                .and_then(|a| a.region.clone())
+                .or_else(|| geocode.and_then(|g| g.region.clone())),
+            postal_code: address
+                .and_then(|a| a.postal_code.clone())
+                .or_else(|| geocode.and_then(|g| g.postal_code.clone())),
+            point: point.or_else(|| geocode.map(|g| (g.latitude, g.longitude))),
+        }
+    }
+}
+
+fn check_coordinates(latitude: Option<f64>, longitude: Option<f64>, radius_km: Option<f64>) -> Result<(), ValidationError> {
+    match (latitude, longitude, radius_km) {
+        (None, None, None) => Ok(()),
+        (Some(lat), Some(lon), Some(radius)) => {
+            if !(-90.0..=90.0).contains(&lat) || !(-180.0..=180.0).contains(&lon) {
+                return Err(ValidationError::new("center_out_of_range"));
+            }
+            if radius <= 0.0 {
+                return Err(ValidationError::new("radius_must_be_positive"));
+            }
+            Ok(())
+        }
+        _ => Err(ValidationError::new("radius_zone_needs_center_and_radius")),
+    }
+}
+
+fn check_transit(min: Option<i32>, max: Option<i32>) -> Result<(), ValidationError> {
+    match (min, max) {
+        (Some(min), _) if min < 0 => Err(ValidationError::new("transit_days_negative")),
+        (Some(min), Some(max)) if max < min => Err(ValidationError::new("transit_days_max_below_min")),
+        _ => Ok(()),
+    }
+}
+
+fn check_new_zone(zone: &NewShippingZone) -> Result<(), ValidationError> {
+    if zone.countries.iter().any(|c| c.len() != 2) {
+        return Err(ValidationError::new("country_must_be_iso_alpha2"));
+    }
+    if zone.countries.is_empty()
+        && zone.regions.is_empty()
+        && zone.postal_prefixes.is_empty()
+        && zone.radius_km.is_none()
+    {
+        return Err(ValidationError::new("zone_needs_a_criterion"));
+    }
+    check_coordinates(zone.center_latitude, zone.center_longitude, zone.radius_km)?;
+    check_transit(zone.transit_days_min, zone.transit_days_max)
+}
+
+fn strings(value: &serde_json::Value) -> Vec<String> {
+    serde_json::from_value(value.clone()).unwrap_or_default()
+}
+
+fn upper(values: Vec<String>) -> Vec<String> {
+    values.into_iter().map(|v| v.trim().to_ascii_uppercase()).collect()
+}
+
+fn compact_postal(code: &str) -> String {
+    code.chars().filter(|c| !c.is_whitespace() && *c != '-').collect::<String>().to_ascii_uppercase()
+}
+
+/// Whether `location` meets every criterion `zone` sets. A radius zone never matches a
+/// location without coordinates.
+pub fn zone_matches(zone: &shipping_zone::Model, location: &Location) -> bool {
+    if !zone.active {
+        return false;
+    }
+    let countries = strings(&zone.countries);
+    if !countries.is_empty()
+        && !location
+            .country
+            .as_deref()
+            .is_some_and(|c| countries.iter().any(|z| z.eq_ignore_ascii_case(c.trim())))
+    {
+        return false;
+    }
+    let regions = strings(&zone.regions);
+    if !regions.is_empty()
+        && !location
+            .region
+            .as_deref()
+            .is_some_and(|r| regions.iter().any(|z| z.eq_ignore_ascii_case(r.trim())))
+    {
+        return false;
+    }
+    let prefixes = strings(&zone.postal_prefixes);
+    if !prefixes.is_empty() {
+        let Some(postal) = location.postal_code.as_deref().map(compact_postal) else {
+            return false;
+        };
+        if !prefixes.iter().any(|p| postal.starts_with(&compact_postal(p))) {
+            return false;
+        }
+    }
+    if let (Some(lat), Some(lon), Some(radius)) = (zone.center_latitude, zone.center_longitude, zone.radius_km) {
+        match location.point {
+            Some(point) if distance_km((lat, lon), point) <= radius => {}
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// How many criteria a zone sets; breaks priority ties in favour of narrower zones.
+fn specificity(zone: &shipping_zone::Model) -> usize {
+    [
+        !strings(&zone.countries).is_empty(),
+        !strings(&zone.regions).is_empty(),
+        !strings(&zone.postal_prefixes).is_empty(),
+        zone.radius_km.is_some(),
+    ]
+    .into_iter()
+    .filter(|set| *set)
+    .count()
+}
+
+/// The zone `location` falls in: lowest priority first, then the most specific, then by
+/// code so the answer is stable.
+pub fn pick_zone<'a>(zones: &'a [shipping_zone::Model], location: &Location) -> Option<&'a shipping_zone::Model> {
+    zones
+        .iter()
+        .filter(|zone| zone_matches(zone, location))
+        .min_by(|a, b| {
+            a.priority
+                .cmp(&b.priority)
+                .then(specificity(b).cmp(&specificity(a)))
+                .then(a.code.cmp(&b.code))
+        })
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Shipping zone query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Shipping zones, and the zone each geocoded customer and warehouse address falls in.
+pub struct ShippingZoneService {
+    db_pool: Arc<DbPool>,
+    geocoder: Option<Arc<dyn Geocoder>>,
+}
+
+impl ShippingZoneService {
+    pub fn new(db_pool: Arc<DbPool>, geocoder: Option<Arc<dyn Geocoder>>) -> Self {
+        Self { db_pool, geocoder }
+    }
+
+    #[instrument(skip(self, input), fields(code = %input.code))]
+    pub async fn create(&self, input: NewShippingZone) -> Result<shipping_zone::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid shipping zone: {}", e)))?;
+        let db = self.db_pool.as_ref();
+        let taken = ShippingZone::find()
+            .filter(shipping_zone::Column::Code.eq(input.code.as_str()))
+            .count(db)
+            .await
+            .map_err(db_error)?;
+        if taken > 0 {
+            return Err(ServiceError::ValidationError(format!("Shipping zone {} already exists", input.code)));
+        }
+        let now = Utc::now();
+        let zone = shipping_zone::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            code: Set(input.code),
+            name: Set(input.name),
+            priority: Set(input.priority),
+            countries: Set(serde_json::json!(upper(input.countries))),
+            regions: Set(serde_json::json!(input.regions)),
+            postal_prefixes: Set(serde_json::json!(upper(input.postal_prefixes))),
+            center_latitude: Set(input.center_latitude),
+            center_longitude: Set(input.center_longitude),
+            radius_km: Set(input.radius_km),
+            rate_group: Set(input.rate_group),
+            tax_jurisdiction: Set(input.tax_jurisdiction),
+            transit_days_min: Set(input.transit_days_min),
+            transit_days_max: Set(input.transit_days_max),
+            active: Set(true),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(db)
+        .await
+        .map_err(db_error)?;
+        self.reassign_all().await?;
+        Ok(zone)
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<shipping_zone::Model, ServiceError> {
+        ShippingZone::find_by_id(id)
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Shipping zone {} not found", id)))
+    }
+
+    pub async fn list(
+        &self,
+        filter: ShippingZoneFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<shipping_zone::Model>, u64), ServiceError> {
+        let mut query = ShippingZone::find();
+        if let Some(active) = filter.active {
+            query = query.filter(shipping_zone::Column::Active.eq(active));
+        }
+        if let Some(country) = filter.country {
+            query = query.filter(Expr::cust_with_values(
+                "countries @> $1::jsonb",
+                [serde_json::json!([country.to_ascii_uppercase()])],
+            ));
+        }
+        let paginator = query
+            .order_by_asc(shipping_zone::Column::Priority)
+            .order_by_asc(shipping_zone::Column::Code)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let items = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((items, total))
+    }
+
+    #[instrument(skip(self, input))]
+    pub async fn update(&self, id: Uuid, input: UpdateShippingZone) -> Result<shipping_zone::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid shipping zone: {}", e)))?;
+        let zone = self.get(id).await?;
+        let center_latitude = input.center_latitude.or(zone.center_latitude);
+        let center_longitude = input.center_longitude.or(zone.center_longitude);
+        let radius_km = input.radius_km.or(zone.radius_km);
+        check_coordinates(center_latitude, center_longitude, radius_km)
+            .and_then(|_| {
+                check_transit(
+                    input.transit_days_min.or(zone.transit_days_min),
+                    input.transit_days_max.or(zone.transit_days_max),
+                )
+            })
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid shipping zone: {}", e.code)))?;
+        if input.countries.as_ref().is_some_and(|c| c.iter().any(|c| c.len() != 2)) {
+            return Err(ServiceError::ValidationError("Countries must be ISO 3166-1 alpha-2 codes".to_string()));
+        }
+
+        let mut active: shipping_zone::ActiveModel = zone.into();
+        if let Some(name) = input.name {
+            active.name = Set(name);
+        }
+        if let Some(priority) = input.priority {
+            active.priority = Set(priority);
+        }
+        if let Some(countries) = input.countries {
+            active.countries = Set(serde_json::json!(upper(countries)));
+        }
+        if let Some(regions) = input.regions {
+            active.regions = Set(serde_json::json!(regions));
+        }
+        if let Some(prefixes) = input.postal_prefixes {
+            active.postal_prefixes = Set(serde_json::json!(upper(prefixes)));
+        }
+        active.center_latitude = Set(center_latitude);
+        active.center_longitude = Set(center_longitude);
+        active.radius_km = Set(radius_km);
+        if let Some(rate_group) = input.rate_group {
+            active.rate_group = Set(Some(rate_group));
+        }
+        if let Some(jurisdiction) = input.tax_jurisdiction {
+            active.tax_jurisdiction = Set(Some(jurisdiction));
+        }
+        if let Some(min) = input.transit_days_min {
+            active.transit_days_min = Set(Some(min));
+        }
+        if let Some(max) = input.transit_days_max {
+            active.transit_days_max = Set(Some(max));
+        }
+        if let Some(enabled) = input.active {
+            active.active = Set(enabled);
+        }
+        active.updated_at = Set(Utc::now());
+        let zone = active.update(self.db_pool.as_ref()).await.map_err(db_error)?;
+        self.reassign_all().await?;
+        Ok(zone)
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<(), ServiceError> {
+        let result = ShippingZone::delete_by_id(id)
+            .exec(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?;
+        if result.rows_affected == 0 {
+            return Err(ServiceError::NotFound(format!("Shipping zone {} not found", id)));
+        }
+        self.reassign_all().await
+    }
+
+    async fn active_zones(&self) -> Result<Vec<shipping_zone::Model>, ServiceError> {
+        ShippingZone::find()
+            .filter(shipping_zone::Column::Active.eq(true))
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)
+    }
+
+    /// Geocodes when useful and possible. A geocoder failure is logged and the address is
+    /// matched on what was typed.
+    async fn try_geocode(&self, address: &Address) -> Option<Geocode> {
+        let geocoder = self.geocoder.as_ref()?;
+        match geocoder.geocode(address).await {
+            Ok(geocode) => geocode,
+            Err(e) => {
+                warn!("Geocoding {} failed: {}", address.one_line(), e);
+                None
+            }
+        }
+    }
+
+    #[instrument(skip(self, query))]
+    pub async fn resolve(&self, query: ZoneQuery) -> Result<ZoneResolution, ServiceError> {
+        query
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid address: {}", e)))?;
+        let point = query.latitude.zip(query.longitude);
+        if query.address.is_none() && point.is_none() {
+            return Err(ServiceError::ValidationError("Send an address or latitude and longitude".to_string()));
+        }
+        let geocode = match (&query.address, point) {
+            (Some(address), None) => self.try_geocode(address).await,
+            _ => None,
+        };
+        let location = Location::from_parts(query.address.as_ref(), geocode.as_ref(), point);
+        let zones = self.active_zones().await?;
+        let zone = pick_zone(&zones, &location).cloned();
+        Ok(ZoneResolution { zone, geocode })
+    }
+
+    /// Geocodes a customer or warehouse address and stores its zone. An unchanged address
+    /// keeps its coordinates and only has its zone re-resolved.
+    #[instrument(skip(self, address))]
+    pub async fn assign(
+        &self,
+        subject_type: &str,
+        subject_id: &str,
+        address: Address,
+    ) -> Result<address_geocode::Model, ServiceError> {
+        if !SUBJECT_TYPES.contains(&subject_type) {
+            return Err(ServiceError::ValidationError(format!("Unknown address owner type: {}", subject_type)));
+        }
+        address
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid address: {}", e)))?;
+        let db = self.db_pool.as_ref();
+        let one_line = address.one_line();
+        let existing = self.find_assignment(subject_type, subject_id).await?;
+        let unchanged = existing.as_ref().filter(|row| row.address == one_line && row.latitude.is_some());
+
+        let geocode = match unchanged {
+            Some(row) => row.latitude.zip(row.longitude).map(|(latitude, longitude)| Geocode {
+                latitude,
+                longitude,
+                country: Some(row.country.clone()),
+                region: row.region.clone(),
+                postal_code: row.postal_code.clone(),
+            }),
+            None => self.try_geocode(&address).await,
+        };
+        let location = Location::from_parts(Some(&address), geocode.as_ref(), None);
+        let zones = self.active_zones().await?;
+        let zone_id = pick_zone(&zones, &location).map(|zone| zone.id);
+        let provider = match (&geocode, unchanged) {
+            (Some(_), Some(row)) => row.provider.clone(),
+            (Some(_), None) => self.geocoder.as_ref().map(|g| g.name().to_string()),
+            (None, _) => None,
+        };
+
+        let is_new = existing.is_none();
+        let mut active = match existing {
+            Some(row) => row.into(),
+            None => address_geocode::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                subject_type: Set(subject_type.to_string()),
+                subject_id: Set(subject_id.to_string()),
+                ..Default::default()
+            },
+        };
+        active.address = Set(one_line);
+        active.latitude = Set(geocode.as_ref().map(|g| g.latitude));
+        active.longitude = Set(geocode.as_ref().map(|g| g.longitude));
+        active.country = Set(location.country.unwrap_or(address.country).to_ascii_uppercase());
+        active.region = Set(location.region);
+        active.postal_code = Set(location.postal_code);
+        active.zone_id = Set(zone_id);
+        active.provider = Set(provider);
+        active.geocoded_at = Set(Utc::now());
+        if is_new {
+            active.insert(db).await.map_err(db_error)
+        } else {
+            active.update(db).await.map_err(db_error)
+        }
+    }
+
+    async fn find_assignment(
+        &self,
+        subject_type: &str,
+        subject_id: &str,
+    ) -> Result<Option<address_geocode::Model>, ServiceError> {
+        AddressGeocode::find()
+            .filter(address_geocode::Column::SubjectType.eq(subject_type))
+            .filter(address_geocode::Column::SubjectId.eq(subject_id))
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)
+    }
+
+    pub async fn assignment(
+        &self,
+        subject_type: &str,
+        subject_id: &str,
+    ) -> Result<address_geocode::Model, ServiceError> {
+        self.find_assignment(subject_type, subject_id)
+            .await?
+            .ok_or_else(|| ServiceError::NotFound(format!("No address on file for {} {}", subject_type, subject_id)))
+    }
+
+    /// Re-resolves the zone of every stored address after zones change.
+    async fn reassign_all(&self) -> Result<(), ServiceError> {
+        let db = self.db_pool.as_ref();
+        let zones = self.active_zones().await?;
+        let mut paginator = AddressGeocode::find()
+            .order_by_asc(address_geocode::Column::Id)
+            .paginate(db, REASSIGN_BATCH);
+        let mut changed = 0usize;
+        while let Some(rows) = paginator.fetch_and_next().await.map_err(db_error)? {
+            for row in rows {
+                let location = Location {
+                    country: Some(row.country.clone()),
+                    region: row.region.clone(),
+                    postal_code: row.postal_code.clone(),
+                    point: row.latitude.zip(row.longitude),
+                };
+                let zone_id = pick_zone(&zones, &location).map(|zone| zone.id);
+                if zone_id != row.zone_id {
+                    let mut active: address_geocode::ActiveModel = row.into();
+                    active.zone_id = Set(zone_id);
+                    active.update(db).await.map_err(db_error)?;
+                    changed += 1;
+                }
+            }
+        }
+        if changed > 0 {
+            info!(changed, "Addresses moved to a different shipping zone");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn zone(code: &str, priority: i32) -> shipping_zone::Model {
+        shipping_zone::Model {
+            id: Uuid::new_v4(),
+            code: code.to_string(),
+            name: code.to_string(),
+            priority,
+            countries: json!([]),
+            regions: json!([]),
+            postal_prefixes: json!([]),
+            center_latitude: None,
+            center_longitude: None,
+            radius_km: None,
+            rate_group: None,
+            tax_jurisdiction: None,
+            transit_days_min: None,
+            transit_days_max: None,
+            active: true,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn location(country: &str, region: Option<&str>, postal: Option<&str>) -> Location {
+        Location {
+            country: Some(country.to_string()),
+            region: region.map(str::to_string),
+            postal_code: postal.map(str::to_string),
+            point: None,
+        }
+    }
+
+    #[test]
+    fn test_zone_criteria_all_apply() {
+        let mut west = zone("US-WEST", 100);
+        west.countries = json!(["US"]);
+        west.regions = json!(["CA", "OR", "WA"]);
+        assert!(zone_matches(&west, &location("us", Some("ca"), None)));
+        assert!(!zone_matches(&west, &location("US", Some("NY"), None)));
+        assert!(!zone_matches(&west, &location("US", None, None)));
+    }
+
+    #[test]
+    fn test_postal_prefixes_ignore_spacing() {
+        let mut london = zone("LDN", 10);
+        london.countries = json!(["GB"]);
+        london.postal_prefixes = json!(["EC1", "SW1A"]);
+        assert!(zone_matches(&london, &location("GB", None, Some("sw1a 1aa"))));
+        assert!(!zone_matches(&london, &location("GB", None, Some("M1 1AE"))));
+        assert!(!zone_matches(&london, &location("GB", None, None)));
+    }
+
+    #[test]
+    fn test_radius_zone_needs_coordinates() {
+        let mut metro = zone("SF-METRO", 10);
+        metro.center_latitude = Some(37.7749);
+        metro.center_longitude = Some(-122.4194);
+        metro.radius_km = Some(25.0);
+        let mut oakland = location("US", None, None);
+        assert!(!zone_matches(&metro, &oakland));
+        oakland.point = Some((37.8044, -122.2712));
+        assert!(zone_matches(&metro, &oakland));
+        oakland.point = Some((34.0522, -118.2437));
+        assert!(!zone_matches(&metro, &oakland));
+    }
+
+    #[test]
+    fn test_pick_zone_by_priority_then_specificity() {
+        let mut domestic = zone("US", 100);
+        domestic.countries = json!(["US"]);
+        let mut west = zone("US-WEST", 100);
+        west.countries = json!(["US"]);
+        west.regions = json!(["CA"]);
+        let mut promo = zone("PROMO", 50);
+        promo.countries = json!(["US"]);
+        promo.active = false;
+        let zones = vec![domestic, west, promo];
+        assert_eq!(pick_zone(&zones, &location("US", Some("CA"), None)).unwrap().code, "US-WEST");
+        assert_eq!(pick_zone(&zones, &location("US", Some("TX"), None)).unwrap().code, "US");
+        assert!(pick_zone(&zones, &location("CA", None, None)).is_none());
+    }
+
+    #[test]
+    fn test_new_zone_validation() {
+        let mut input = NewShippingZone {
+            code: "EU".to_string(),
+            name: "Europe".to_string(),
+            priority: 100,
+            countries: vec!["DE".to_string(), "FR".to_string()],
+            regions: Vec::new(),
+            postal_prefixes: Vec::new(),
+            center_latitude: None,
+            center_longitude: None,
+            radius_km: None,
+            rate_group: None,
+            tax_jurisdiction: None,
+            transit_days_min: Some(2),
+            transit_days_max: Some(5),
+        };
+        assert!(input.validate().is_ok());
+        input.transit_days_max = Some(1);
+        assert!(input.validate().is_err());
+        input.transit_days_max = None;
+        input.radius_km = Some(10.0);
+        assert!(input.validate().is_err());
+        input.radius_km = None;
+        input.countries.clear();
+        assert!(input.validate().is_err());
+    }
+}
@@ -0,0 +1,556 @@
+use sea_orm::sea_query::Expr;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+use tracing::{error, instrument};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db::{dialect, DbPool},
+    errors::ServiceError,
+    geocoding::distance_km,
+    models::{
+        address_geocode::{self, Entity as AddressGeocode},
+        inventory_items,
+        order_fingerprint::{self, Entity as OrderFingerprint},
+    },
+    services::routing_rules::{self, RoutingContext, RoutingRuleService},
+};
+
+/// Rules that decide where an order ships from. Ranking rules apply in the order they
+/// are listed; `protect_store_safety_stock` is a constraint and can go anywhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SourcingRule {
+    /// Prefer locations that can fill more of what is left, so the order ships in as
+    /// few parcels as possible.
+    LowestSplitCount,
+    /// Prefer locations closer to the destination.
+    NearestLocation,
+    /// Prefer warehouses, shipping from stores only when warehouses fall short.
+    PreferWarehouses,
+    /// Never source a store's last `safety_stock` units, keeping them for walk-in shoppers.
+    ProtectStoreSafetyStock,
+}
+
+/// A stocking location that is a retail store rather than a warehouse.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StoreLocation {
+    pub warehouse: i32,
+    /// Overrides `store_safety_stock` for this store.
+    pub safety_stock: Option<i64>,
+}
+
+/// Order sourcing, loaded from the `sourcing` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SourcingConfig {
+    #[serde(default = "default_rules")]
+    pub rules: Vec<SourcingRule>,
+
+    /// Locations in `inventory_items.warehouse` that are stores; all others are warehouses.
+    #[serde(default)]
+    pub stores: Vec<StoreLocation>,
+
+    /// Units per SKU each store keeps back when safety stock is protected (default: 2).
+    #[serde(default = "default_store_safety_stock")]
+    pub store_safety_stock: i64,
+
+    /// Source from stores at all (default: true).
+    #[serde(default = "default_ship_from_store")]
+    pub ship_from_store: bool,
+
+    /// Most shipments an order may be split into; what does not fit is left unsourced.
+    #[serde(default)]
+    pub max_splits: Option<usize>,
+}
+
+fn default_rules() -> Vec<SourcingRule> {
+    vec![
+        SourcingRule::ProtectStoreSafetyStock,
+        SourcingRule::LowestSplitCount,
+        SourcingRule::NearestLocation,
+    ]
+}
+
+fn default_store_safety_stock() -> i64 {
+    2
+}
+
+fn default_ship_from_store() -> bool {
+    true
+}
+
+impl Default for SourcingConfig {
+    fn default() -> Self {
+        Self {
+            rules: default_rules(),
+            stores: Vec::new(),
+            store_safety_stock: default_store_safety_stock(),
+            ship_from_store: default_ship_from_store(),
+            max_splits: None,
+        }
+    }
+}
+
+impl SourcingConfig {
+    fn store(&self, warehouse: i32) -> Option<&StoreLocation> {
+        self.stores.iter().find(|s| s.warehouse == warehouse)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LocationKind {
+    Warehouse,
+    Store,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+pub struct SourcingLine {
+    #[validate(length(min = 1))]
+    pub sku: String,
+    #[validate(range(min = 1))]
+    pub quantity: i64,
+}
+
+/// An order to source: its lines and where it is going.
+#[derive(Debug, Clone, Deserialize, Validate)]
+pub struct SourcingRequest {
+    #[validate(length(min = 1))]
+    #[validate]
+    pub lines: Vec<SourcingLine>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Uses the customer's geocoded address when no coordinates are given.
+    pub customer_id: Option<String>,
+    /// Destination country and region for routing rules; taken from the customer's
+    /// geocoded address when omitted.
+    pub country: Option<String>,
+    pub region: Option<String>,
+    /// Sales channel for routing rules, e.g. `web` or `pos`.
+    pub channel: Option<String>,
+    /// Overrides the configured rules, e.g. to compare strategies.
+    pub rules: Option<Vec<SourcingRule>>,
+}
+
+/// A location that could ship part of the order, and what it can spare.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub warehouse: i32,
+    pub kind: LocationKind,
+    pub distance_km: Option<f64>,
+    /// Unreserved units per SKU.
+    pub stock: HashMap<String, i64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SourcedShipment {
+    pub warehouse: i32,
+    pub kind: LocationKind,
+    pub distance_km: Option<f64>,
+    pub lines: Vec<SourcingLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SourcingPlan {
+    pub shipments: Vec<SourcedShipment>,
+    /// What no location could ship, or what did not fit within `max_splits`.
+    pub unsourced: Vec<SourcingLine>,
+    pub fully_sourced: bool,
+    pub rules: Vec<SourcingRule>,
+    /// Routing rule whose overrides applied, if any.
+    pub routing_rule_id: Option<Uuid>,
+}
+
+/// Units `candidate` may ship of `sku` under `rules`.
+fn sourceable(candidate: &Candidate, sku: &str, rules: &[SourcingRule], config: &SourcingConfig) -> i64 {
+    let on_hand = candidate.stock.get(sku).copied().unwrap_or(0).max(0);
+    if candidate.kind == LocationKind::Store && rules.contains(&SourcingRule::ProtectStoreSafetyStock) {
+        let safety = config
+            .store(candidate.warehouse)
+            .and_then(|s| s.safety_stock)
+            .unwrap_or(config.store_safety_stock);
+        (on_hand - safety).max(0)
+    } else {
+        on_hand
+    }
+}
+
+/// Lines a candidate fills completely and units it can ship towards `remaining`.
+fn coverage(candidate: &Candidate, remaining: &BTreeMap<String, i64>, rules: &[SourcingRule], config: &SourcingConfig) -> (usize, i64) {
+    remaining.iter().fold((0, 0), |(lines, units), (sku, wanted)| {
+        let take = sourceable(candidate, sku, rules, config).min(*wanted);
+        (lines + usize::from(take == *wanted), units + take)
+    })
+}
+
+fn rank(
+    a: &Candidate,
+    b: &Candidate,
+    remaining: &BTreeMap<String, i64>,
+    rules: &[SourcingRule],
+    config: &SourcingConfig,
+) -> Ordering {
+    let by_rule = rules.iter().fold(Ordering::Equal, |ordering, rule| {
+        ordering.then_with(|| match rule {
+            SourcingRule::LowestSplitCount => {
+                coverage(b, remaining, rules, config).cmp(&coverage(a, remaining, rules, config))
+            }
+            SourcingRule::NearestLocation => {
+                let (da, db) = (a.distance_km.unwrap_or(f64::MAX), b.distance_km.unwrap_or(f64::MAX));
+                da.partial_cmp(&db).unwrap_or(Ordering::Equal)
+            }
+            SourcingRule::PreferWarehouses => (a.kind == LocationKind::Store).cmp(&(b.kind == LocationKind::Store)),
+            SourcingRule::ProtectStoreSafetyStock => Ordering::Equal,
+        })
+    });
+    by_rule.then(a.warehouse.cmp(&b.warehouse))
+}
+
+/// Sources `lines` from `candidates`: repeatedly picks the best-ranked location that can
+/// ship something still open and takes all it can from it. Lines may be split across
+/// locations when no single one has enough.
+pub fn plan(
+    lines: &[SourcingLine],
+    candidates: &[Candidate],
+    rules: &[SourcingRule],
+    config: &SourcingConfig,
+) -> SourcingPlan {
+    let mut remaining: BTreeMap<String, i64> = BTreeMap::new();
+    for line in lines {
+        *remaining.entry(line.sku.clone()).or_default() += line.quantity;
+    }
+    let mut open: Vec<&Candidate> = candidates
+        .iter()
+        .filter(|c| c.kind == LocationKind::Warehouse || config.ship_from_store)
+        .collect();
+    let mut shipments = Vec::new();
+
+    while !remaining.is_empty() && !config.max_splits.is_some_and(|max| shipments.len() >= max) {
+        open.retain(|c| coverage(c, &remaining, rules, config).1 > 0);
+        let Some((index, best)) = open
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| rank(a, b, &remaining, rules, config))
+            .map(|(i, c)| (i, *c))
+        else {
+            break;
+        };
+        let mut shipment_lines = Vec::new();
+        for (sku, wanted) in remaining.iter_mut() {
+            let take = sourceable(best, sku, rules, config).min(*wanted);
+            if take > 0 {
+                *wanted -= take;
+                shipment_lines.push(SourcingLine { sku: sku.clone(), quantity: take });
+            }
+        }
+        remaining.retain(|_, wanted| *wanted > 0);
+        shipments.push(SourcedShipment {
+            warehouse: best.warehouse,
+            kind: best.kind,
+            distance_km: best.distance_km,
+            lines: shipment_lines,
+        });
+        open.remove(index);
+    }
+
+    let unsourced: Vec<SourcingLine> = remaining
+        .into_iter()
+        .map(|(sku, quantity)| SourcingLine { sku, quantity })
+        .collect();
+    SourcingPlan {
+        shipments,
+        fully_sourced: unsourced.is_empty(),
+        unsourced,
+        rules: rules.to_vec(),
+        routing_rule_id: None,
+    }
+}
+
+/// A line of an order to source, with the product its units are allocated under.
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct OrderLine {
+    pub sku: String,
+    pub product_id: String,
+    pub quantity: i64,
+}
+
+/// Lines the order's own locations ship; dropship lines ship from their vendors.
+const ORDER_LINES_SQL: &str = r#"
+SELECT li.seller_sku AS sku, li.product_id, SUM(li.quantity)::BIGINT AS quantity
+FROM order_line_items li
+WHERE li.order_id = $1 AND NOT li.dropship
+GROUP BY li.seller_sku, li.product_id
+ORDER BY li.seller_sku
+"#;
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Sourcing query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+#[derive(Debug, FromQueryResult)]
+struct SkuClass {
+    abc_classification: Option<String>,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct LocationStock {
+    sku: String,
+    warehouse: i32,
+    available: Option<i64>,
+}
+
+/// Decides which warehouses and stores ship an order, from unreserved stock and the
+/// geocoded addresses of the locations and the destination. The first matching routing
+/// rule overrides the configured strategy.
+pub struct SourcingService {
+    db_pool: Arc<DbPool>,
+    routing_rules: Arc<RoutingRuleService>,
+    config: SourcingConfig,
+}
+
+impl SourcingService {
+    pub fn new(db_pool: Arc<DbPool>, routing_rules: Arc<RoutingRuleService>, config: SourcingConfig) -> Self {
+        Self { db_pool, routing_rules, config }
+    }
+
+    async fn customer_geocode(&self, customer_id: &str) -> Result<Option<address_geocode::Model>, ServiceError> {
+        AddressGeocode::find()
+            .filter(address_geocode::Column::SubjectType.eq("customer"))
+            .filter(address_geocode::Column::SubjectId.eq(customer_id))
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)
+    }
+
+    /// ABC classes of the SKUs, for routing rules on SKU class.
+    async fn sku_classes(&self, skus: Vec<String>) -> Result<Vec<String>, ServiceError> {
+        Ok(inventory_items::Entity::find()
+            .select_only()
+            .column(inventory_items::Column::AbcClassification)
+            .distinct()
+            .filter(inventory_items::Column::Sku.is_in(skus))
+            .filter(inventory_items::Column::AbcClassification.is_not_null())
+            .into_model::<SkuClass>()
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .filter_map(|c| c.abc_classification)
+            .collect())
+    }
+
+    async fn candidates(&self, skus: Vec<String>, destination: Option<(f64, f64)>) -> Result<Vec<Candidate>, ServiceError> {
+        let levels = inventory_items::Entity::find()
+            .select_only()
+            .column(inventory_items::Column::Sku)
+            .column(inventory_items::Column::Warehouse)
+            .column_as(Expr::cust("SUM(available - COALESCE(reserved_quantity, 0))"), "available")
+            .filter(inventory_items::Column::Sku.is_in(skus))
+            .group_by(inventory_items::Column::Sku)
+            .group_by(inventory_items::Column::Warehouse)
+            .into_model::<LocationStock>()
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?;
+
+        let mut stock: BTreeMap<i32, HashMap<String, i64>> = BTreeMap::new();
+        for level in levels {
+            stock.entry(level.warehouse).or_default().insert(level.sku, level.available.unwrap_or(0));
+        }
+        let locations: Vec<String> = stock.keys().map(i32::to_string).collect();
+        let points: HashMap<String, (f64, f64)> = AddressGeocode::find()
+            .filter(address_geocode::Column::SubjectType.eq("warehouse"))
+            .filter(address_geocode::Column::SubjectId.is_in(locations))
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .into_iter()
+            .filter_map(|g| Some((g.subject_id, g.latitude.zip(g.longitude)?)))
+            .collect();
+
+        Ok(stock
+            .into_iter()
+            .map(|(warehouse, stock)| Candidate {
+                warehouse,
+                kind: if self.config.store(warehouse).is_some() { LocationKind::Store } else { LocationKind::Warehouse },
+                distance_km: destination
+                    .zip(points.get(&warehouse.to_string()).copied())
+                    .map(|(to, from)| (distance_km(from, to) * 10.0).round() / 10.0),
+                stock,
+            })
+            .collect())
+    }
+
+    /// The sourcing request for a placed order: its lines, shipped to the customer's
+    /// geocoded address. Also returns the lines, for allocating what the plan sources.
+    pub async fn order_request(&self, order_id: Uuid) -> Result<(SourcingRequest, Vec<OrderLine>), ServiceError> {
+        let db = self.db_pool.as_ref();
+        let lines = OrderLine::find_by_statement(dialect::statement(db, ORDER_LINES_SQL, [order_id.into()]))
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        if lines.is_empty() {
+            return Err(ServiceError::NotFound(format!("Order {} has no lines to source", order_id)));
+        }
+        let customer_id = OrderFingerprint::find()
+            .filter(order_fingerprint::Column::OrderId.eq(order_id))
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .map(|fingerprint| fingerprint.customer_id.to_string());
+        let mut merged: BTreeMap<String, i64> = BTreeMap::new();
+        for line in &lines {
+            *merged.entry(line.sku.clone()).or_default() += line.quantity;
+        }
+        let request = SourcingRequest {
+            lines: merged.into_iter().map(|(sku, quantity)| SourcingLine { sku, quantity }).collect(),
+            latitude: None,
+            longitude: None,
+            customer_id,
+            country: None,
+            region: None,
+            channel: None,
+            rules: None,
+        };
+        Ok((request, lines))
+    }
+
+    /// Shows how an order would be sourced under the configured (or given) rules,
+    /// without reserving anything. Allocation follows the same plan.
+    #[instrument(skip(self, request))]
+    pub async fn simulate(&self, request: SourcingRequest) -> Result<SourcingPlan, ServiceError> {
+        request
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid sourcing request: {}", e)))?;
+        let geocode = match &request.customer_id {
+            Some(customer_id) => self.customer_geocode(customer_id).await?,
+            None => None,
+        };
+        // Without a destination every distance is unknown and nearest-location ranks nothing
+        let destination = request
+            .latitude
+            .zip(request.longitude)
+            .or_else(|| geocode.as_ref().and_then(|g| g.latitude.zip(g.longitude)));
+        let skus: Vec<String> = request.lines.iter().map(|l| l.sku.clone()).collect();
+
+        let context = RoutingContext {
+            country: request.country.or_else(|| geocode.as_ref().map(|g| g.country.clone())),
+            region: request.region.or_else(|| geocode.as_ref().and_then(|g| g.region.clone())),
+            channel: request.channel,
+            sku_classes: self.sku_classes(skus.clone()).await?,
+        };
+        let active_rules = self.routing_rules.active().await?;
+        let routing_rule = routing_rules::select(&active_rules, &context);
+        let config = match routing_rule {
+            Some(rule) => routing_rules::apply(rule, &self.config),
+            None => self.config.clone(),
+        };
+        let excluded = routing_rule.map(routing_rules::excluded_warehouses).unwrap_or_default();
+
+        let rules = request.rules.unwrap_or_else(|| config.rules.clone());
+        let mut candidates = self.candidates(skus, destination).await?;
+        candidates.retain(|c| !excluded.contains(&c.warehouse));
+        let mut sourced = plan(&request.lines, &candidates, &rules, &config);
+        sourced.routing_rule_id = routing_rule.map(|r| r.id);
+        Ok(sourced)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(sku: &str, quantity: i64) -> SourcingLine {
+        SourcingLine { sku: sku.to_string(), quantity }
+    }
+
+    fn candidate(warehouse: i32, kind: LocationKind, distance_km: f64, stock: &[(&str, i64)]) -> Candidate {
+        Candidate {
+            warehouse,
+            kind,
+            distance_km: Some(distance_km),
+            stock: stock.iter().map(|(sku, units)| (sku.to_string(), *units)).collect(),
+        }
+    }
+
+    fn config() -> SourcingConfig {
+        SourcingConfig {
+            stores: vec![StoreLocation { warehouse: 20, safety_stock: None }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_lowest_split_count_prefers_one_location_that_has_everything() {
+        let candidates = [
+            candidate(1, LocationKind::Warehouse, 5.0, &[("MUG", 2)]),
+            candidate(2, LocationKind::Warehouse, 900.0, &[("MUG", 2), ("CAP", 1)]),
+        ];
+        let lines = [line("MUG", 2), line("CAP", 1)];
+        let result = plan(&lines, &candidates, &default_rules(), &config());
+        assert!(result.fully_sourced);
+        assert_eq!(result.shipments.len(), 1);
+        assert_eq!(result.shipments[0].warehouse, 2);
+    }
+
+    #[test]
+    fn test_nearest_location_first_accepts_splits() {
+        let candidates = [
+            candidate(1, LocationKind::Warehouse, 5.0, &[("MUG", 2)]),
+            candidate(2, LocationKind::Warehouse, 900.0, &[("MUG", 2), ("CAP", 1)]),
+        ];
+        let rules = [SourcingRule::NearestLocation];
+        let result = plan(&[line("MUG", 2), line("CAP", 1)], &candidates, &rules, &config());
+        let warehouses: Vec<i32> = result.shipments.iter().map(|s| s.warehouse).collect();
+        assert_eq!(warehouses, vec![1, 2]);
+        assert_eq!(result.shipments[1].lines, vec![line("CAP", 1)]);
+    }
+
+    #[test]
+    fn test_store_safety_stock_is_protected() {
+        let candidates = [candidate(20, LocationKind::Store, 1.0, &[("MUG", 3)])];
+        let result = plan(&[line("MUG", 3)], &candidates, &default_rules(), &config());
+        assert_eq!(result.shipments[0].lines, vec![line("MUG", 1)]);
+        assert_eq!(result.unsourced, vec![line("MUG", 2)]);
+
+        let unprotected = [SourcingRule::NearestLocation];
+        assert!(plan(&[line("MUG", 3)], &candidates, &unprotected, &config()).fully_sourced);
+    }
+
+    #[test]
+    fn test_warehouses_before_stores_and_store_opt_out() {
+        let candidates = [
+            candidate(20, LocationKind::Store, 1.0, &[("MUG", 10)]),
+            candidate(1, LocationKind::Warehouse, 400.0, &[("MUG", 10)]),
+        ];
+        let rules = [SourcingRule::PreferWarehouses, SourcingRule::NearestLocation];
+        assert_eq!(plan(&[line("MUG", 1)], &candidates, &rules, &config()).shipments[0].warehouse, 1);
+
+        let mut no_stores = config();
+        no_stores.ship_from_store = false;
+        let rules = [SourcingRule::NearestLocation];
+        assert_eq!(plan(&[line("MUG", 1)], &candidates, &rules, &no_stores).shipments[0].warehouse, 1);
+    }
+
+    #[test]
+    fn test_max_splits_leaves_the_rest_unsourced() {
+        let candidates = [
+            candidate(1, LocationKind::Warehouse, 5.0, &[("MUG", 1)]),
+            candidate(2, LocationKind::Warehouse, 10.0, &[("MUG", 1)]),
+        ];
+        let mut limited = config();
+        limited.max_splits = Some(1);
+        let result = plan(&[line("MUG", 2)], &candidates, &default_rules(), &limited);
+        assert_eq!(result.shipments.len(), 1);
+        assert_eq!(result.unsourced, vec![line("MUG", 1)]);
+        assert!(!result.fully_sourced);
+    }
+}
@@ -0,0 +1,392 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+use tracing::error;
+use uuid::Uuid;
+
+use crate::{
+    calendar::{BusinessCalendar, Calendars},
+    db::DbPool,
+    errors::ServiceError,
+    models::{
+        purchase_order::{self, Entity as PurchaseOrder, PurchaseOrderStatus},
+        purchase_order_line::{self, Entity as PurchaseOrderLine},
+        quality_inspection::{self, Disposition, Entity as QualityInspection, InspectionStatus},
+        supplier_receipt::{self, Entity as SupplierReceipt},
+    },
+};
+
+/// Longest range a scorecard covers.
+pub const MAX_RANGE_DAYS: i64 = 366;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TrendInterval {
+    Week,
+    #[default]
+    Month,
+}
+
+impl TrendInterval {
+    /// First day of the week (Monday) or month containing `date`.
+    pub fn bucket(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            TrendInterval::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            TrendInterval::Month => date.with_day(1).expect("first of the month is valid"),
+        }
+    }
+}
+
+/// Delivery and quality performance over a set of receipts and inspections.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SupplierMetrics {
+    pub receipts: u64,
+    pub on_time: u64,
+    /// Share of receipts that arrived by the promised date; `None` with no receipts.
+    pub on_time_rate: Option<f64>,
+    /// Units on the supplier's purchase orders due in the period.
+    pub ordered_units: i64,
+    /// Units received against those orders, counting at most the ordered quantity of
+    /// each line.
+    pub filled_units: i64,
+    pub fill_rate: Option<f64>,
+    pub inspected_units: i64,
+    /// Units failed or quarantined by completed inspections.
+    pub rejected_units: i64,
+    pub rejection_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TrendPoint {
+    pub period_start: NaiveDate,
+    #[serde(flatten)]
+    pub metrics: SupplierMetrics,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Scorecard {
+    pub supplier_id: Uuid,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub interval: TrendInterval,
+    pub overall: SupplierMetrics,
+    /// One point per period with receipts or inspections, oldest first.
+    pub trend: Vec<TrendPoint>,
+}
+
+/// A SKU on a purchase order due in the scorecard range, with the units received
+/// against it so far.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DueLine {
+    pub promised_date: NaiveDate,
+    pub ordered_quantity: i64,
+    pub received_quantity: i64,
+}
+
+fn rate(part: i64, whole: i64) -> Option<f64> {
+    (whole > 0).then(|| part as f64 / whole as f64)
+}
+
+fn add_receipt(metrics: &mut SupplierMetrics, receipt: &supplier_receipt::Model, calendar: &BusinessCalendar) {
+    metrics.receipts += 1;
+    metrics.on_time += u64::from(calendar.local_date(receipt.received_at) <= receipt.promised_date);
+}
+
+fn add_line(metrics: &mut SupplierMetrics, line: &DueLine) {
+    metrics.ordered_units += line.ordered_quantity;
+    metrics.filled_units += line.received_quantity.min(line.ordered_quantity);
+}
+
+fn add_inspection(metrics: &mut SupplierMetrics, inspection: &quality_inspection::Model) {
+    metrics.inspected_units += inspection.lot_quantity as i64;
+    if matches!(inspection.disposition, Some(Disposition::Fail | Disposition::Quarantine)) {
+        metrics.rejected_units += inspection.quarantined_quantity.unwrap_or(0) as i64;
+    }
+}
+
+fn finish(mut metrics: SupplierMetrics) -> SupplierMetrics {
+    metrics.on_time_rate = rate(metrics.on_time as i64, metrics.receipts as i64);
+    metrics.fill_rate = rate(metrics.filled_units, metrics.ordered_units);
+    metrics.rejection_rate = rate(metrics.rejected_units, metrics.inspected_units);
+    metrics
+}
+
+/// Builds a scorecard from one supplier's receipts and completed inspections in the range,
+/// dated and bucketed by the local days of `calendar`, and the purchase order lines due in
+/// it, bucketed by their promised date.
+#[allow(clippy::too_many_arguments)]
+pub fn build_scorecard(
+    supplier_id: Uuid,
+    from: NaiveDate,
+    to: NaiveDate,
+    interval: TrendInterval,
+    receipts: &[supplier_receipt::Model],
+    lines: &[DueLine],
+    inspections: &[quality_inspection::Model],
+    calendar: &BusinessCalendar,
+) -> Scorecard {
+    let mut overall = SupplierMetrics::default();
+    let mut periods: BTreeMap<NaiveDate, SupplierMetrics> = BTreeMap::new();
+    for receipt in receipts {
+        let period = interval.bucket(calendar.local_date(receipt.received_at));
+        add_receipt(&mut overall, receipt, calendar);
+        add_receipt(periods.entry(period).or_default(), receipt, calendar);
+    }
+    for line in lines {
+        let period = interval.bucket(line.promised_date);
+        add_line(&mut overall, line);
+        add_line(periods.entry(period).or_default(), line);
+    }
+    for inspection in inspections {
+        let period = interval.bucket(calendar.local_date(inspection.created_at));
+        add_inspection(&mut overall, inspection);
+        add_inspection(periods.entry(period).or_default(), inspection);
+    }
+    Scorecard {
+        supplier_id,
+        from,
+        to,
+        interval,
+        overall: finish(overall),
+        trend: periods
+            .into_iter()
+            .map(|(period_start, metrics)| TrendPoint { period_start, metrics: finish(metrics) })
+            .collect(),
+    }
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Supplier scorecard query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Supplier on-time delivery, fill rate and quality rejection, from the stored purchase
+/// orders, the supplier receipts recorded against them and the inspections of those lots.
+pub struct SupplierScorecardService {
+    db_pool: Arc<DbPool>,
+    calendars: Arc<Calendars>,
+}
+
+impl SupplierScorecardService {
+    pub fn new(db_pool: Arc<DbPool>, calendars: Arc<Calendars>) -> Self {
+        Self { db_pool, calendars }
+    }
+
+    pub fn calendar(&self, warehouse: Option<&str>) -> &BusinessCalendar {
+        self.calendars.for_warehouse(warehouse)
+    }
+
+    /// Scorecard for receipts between `from` and `to`, inclusive, in the local days of
+    /// `warehouse`, and purchase orders promised for delivery between them.
+    pub async fn scorecard(
+        &self,
+        supplier_id: Uuid,
+        from: NaiveDate,
+        to: NaiveDate,
+        interval: TrendInterval,
+        warehouse: Option<&str>,
+    ) -> Result<Scorecard, ServiceError> {
+        if to < from {
+            return Err(ServiceError::ValidationError("`to` is before `from`".to_string()));
+        }
+        if (to - from).num_days() >= MAX_RANGE_DAYS {
+            return Err(ServiceError::ValidationError(format!("ranges are limited to {} days", MAX_RANGE_DAYS)));
+        }
+        let calendar = self.calendar(warehouse);
+        let start = calendar.start_of_day(from);
+        let end = calendar.end_of_day(to);
+        let db = self.db_pool.as_ref();
+        let receipts = SupplierReceipt::find()
+            .filter(supplier_receipt::Column::SupplierId.eq(supplier_id))
+            .filter(supplier_receipt::Column::ReceivedAt.gte(start))
+            .filter(supplier_receipt::Column::ReceivedAt.lt(end))
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        let inspections = QualityInspection::find()
+            .filter(quality_inspection::Column::SupplierId.eq(supplier_id))
+            .filter(quality_inspection::Column::Status.eq(InspectionStatus::Completed))
+            .filter(quality_inspection::Column::CreatedAt.gte(start))
+            .filter(quality_inspection::Column::CreatedAt.lt(end))
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        let lines = self.due_lines(supplier_id, from, to).await?;
+        Ok(build_scorecard(supplier_id, from, to, interval, &receipts, &lines, &inspections, calendar))
+    }
+
+    /// Each SKU on the supplier's purchase orders promised between `from` and `to`, with
+    /// everything received against it, including lines nothing has arrived for yet.
+    async fn due_lines(&self, supplier_id: Uuid, from: NaiveDate, to: NaiveDate) -> Result<Vec<DueLine>, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let orders = PurchaseOrder::find()
+            .filter(purchase_order::Column::SupplierId.eq(supplier_id))
+            .filter(purchase_order::Column::Status.ne(PurchaseOrderStatus::Cancelled))
+            .filter(purchase_order::Column::ExpectedDeliveryDate.between(from, to))
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        if orders.is_empty() {
+            return Ok(Vec::new());
+        }
+        let promised: HashMap<Uuid, NaiveDate> = orders.iter().map(|o| (o.id, o.expected_delivery_date)).collect();
+        let order_ids: Vec<Uuid> = promised.keys().copied().collect();
+        let mut ordered: BTreeMap<(Uuid, String), i64> = BTreeMap::new();
+        for line in PurchaseOrderLine::find()
+            .filter(purchase_order_line::Column::PurchaseOrderId.is_in(order_ids.clone()))
+            .all(db)
+            .await
+            .map_err(db_error)?
+        {
+            *ordered.entry((line.purchase_order_id, line.sku)).or_default() += line.quantity as i64;
+        }
+        let mut received: HashMap<(Uuid, String), i64> = HashMap::new();
+        for receipt in SupplierReceipt::find()
+            .filter(supplier_receipt::Column::PurchaseOrderId.is_in(order_ids))
+            .all(db)
+            .await
+            .map_err(db_error)?
+        {
+            if let Some(order_id) = receipt.purchase_order_id {
+                *received.entry((order_id, receipt.sku)).or_default() += receipt.received_quantity as i64;
+            }
+        }
+        Ok(ordered
+            .into_iter()
+            .map(|(key, ordered_quantity)| DueLine {
+                promised_date: promised[&key.0],
+                ordered_quantity,
+                received_quantity: received.get(&key).copied().unwrap_or(0),
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::inspection_plan::InspectionSource;
+    use chrono::{TimeZone, Utc};
+
+    fn date(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    fn receipt(month: u32, day: u32, promised: NaiveDate, ordered: i32, received: i32) -> supplier_receipt::Model {
+        supplier_receipt::Model {
+            id: Uuid::new_v4(),
+            supplier_id: Uuid::nil(),
+            purchase_order_id: Some(Uuid::nil()),
+            reference: "PO-1".to_string(),
+            sku: "WIDGET".to_string(),
+            ordered_quantity: ordered,
+            received_quantity: received,
+            promised_date: promised,
+            inspection_id: None,
+            received_at: Utc.with_ymd_and_hms(2024, month, day, 15, 0, 0).unwrap(),
+        }
+    }
+
+    fn line(promised: NaiveDate, ordered: i64, received: i64) -> DueLine {
+        DueLine { promised_date: promised, ordered_quantity: ordered, received_quantity: received }
+    }
+
+    fn inspection(month: u32, lot: i32, disposition: Disposition, quarantined: i32) -> quality_inspection::Model {
+        quality_inspection::Model {
+            id: Uuid::new_v4(),
+            plan_id: Uuid::new_v4(),
+            source: InspectionSource::PoReceipt,
+            reference: "PO-1".to_string(),
+            sku: "WIDGET".to_string(),
+            supplier_id: Some(Uuid::nil()),
+            warehouse: 1,
+            inventory_item_id: "1".to_string(),
+            lot_quantity: lot,
+            sample_size: 5,
+            status: InspectionStatus::Completed,
+            disposition: Some(disposition),
+            defects: Some(0),
+            quarantined_quantity: Some(quarantined),
+            quarantine_item_id: None,
+            inspector: None,
+            notes: None,
+            created_at: Utc.with_ymd_and_hms(2024, month, 10, 9, 0, 0).unwrap(),
+            completed_at: None,
+        }
+    }
+
+    #[test]
+    fn test_buckets() {
+        assert_eq!(TrendInterval::Week.bucket(date(5, 16)), date(5, 13));
+        assert_eq!(TrendInterval::Month.bucket(date(5, 16)), date(5, 1));
+    }
+
+    #[test]
+    fn test_scorecard_rates_and_trend() {
+        let receipts = [
+            receipt(4, 10, date(4, 10), 100, 100),
+            receipt(4, 20, date(4, 15), 100, 80),
+            receipt(5, 2, date(5, 5), 50, 60),
+        ];
+        // The last order has not arrived at all, which only the purchase orders show
+        let lines = [
+            line(date(4, 10), 100, 100),
+            line(date(4, 15), 100, 80),
+            line(date(5, 5), 50, 60),
+            line(date(5, 20), 50, 0),
+        ];
+        let inspections = [
+            inspection(4, 100, Disposition::Pass, 0),
+            inspection(5, 50, Disposition::Fail, 10),
+        ];
+        let calendar = BusinessCalendar::default();
+        let card = build_scorecard(
+            Uuid::nil(),
+            date(4, 1),
+            date(5, 31),
+            TrendInterval::Month,
+            &receipts,
+            &lines,
+            &inspections,
+            &calendar,
+        );
+
+        assert_eq!((card.overall.receipts, card.overall.on_time), (3, 2));
+        assert_eq!(card.overall.fill_rate, Some(230.0 / 300.0));
+        assert_eq!(card.overall.rejection_rate, Some(10.0 / 150.0));
+
+        assert_eq!(card.trend.len(), 2);
+        assert_eq!(card.trend[0].period_start, date(4, 1));
+        assert_eq!(card.trend[0].metrics.on_time_rate, Some(0.5));
+        assert_eq!(card.trend[0].metrics.rejection_rate, Some(0.0));
+        assert_eq!(card.trend[1].metrics.fill_rate, Some(0.5));
+    }
+
+    #[test]
+    fn test_empty_scorecard_has_no_rates() {
+        let calendar = BusinessCalendar::default();
+        let card = build_scorecard(Uuid::nil(), date(4, 1), date(4, 30), TrendInterval::Week, &[], &[], &[], &calendar);
+        assert_eq!(card.overall, SupplierMetrics::default());
+        assert!(card.trend.is_empty());
+    }
+
+    #[test]
+    fn test_receipts_are_dated_in_local_time() {
+        let calendar = BusinessCalendar::from_config(&crate::calendar::WarehouseCalendarConfig {
+            timezone: "Asia/Tokyo".to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        // 15:00 UTC on 30 April is already 1 May in Tokyo, past the promised date
+        let receipts = [receipt(4, 30, date(4, 30), 10, 10)];
+        let card =
+            build_scorecard(Uuid::nil(), date(4, 1), date(5, 31), TrendInterval::Month, &receipts, &[], &[], &calendar);
+        assert_eq!(card.overall.on_time, 0);
+        assert_eq!(card.trend[0].period_start, date(5, 1));
+    }
+}
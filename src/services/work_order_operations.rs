@@ -0,0 +1,516 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::{error, instrument};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db::DbPool,
+    errors::ServiceError,
+    events::{Event, EventSender},
+    models::{
+        work_order::{self, Entity as WorkOrder},
+        work_order_operation::{self, Entity as Operation, OperationStatus},
+        work_order_time_entry::{self, Entity as TimeEntry, TimeEntryKind},
+    },
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewOperation {
+    #[validate(range(min = 1))]
+    pub sequence: i32,
+    #[validate(length(min = 1, max = 255))]
+    pub name: String,
+    pub work_center: Option<String>,
+    pub scheduled_date: Option<NaiveDate>,
+    pub machine_id: Option<i32>,
+    #[validate(range(min = 0.0))]
+    pub standard_labor_hours: f64,
+    #[serde(default)]
+    #[validate(range(min = 0.0))]
+    pub standard_machine_hours: f64,
+    #[serde(with = "crate::money::amount")]
+    #[validate(custom = "crate::money::validate_non_negative")]
+    pub labor_rate: Decimal,
+    #[serde(default, with = "crate::money::amount")]
+    #[validate(custom = "crate::money::validate_non_negative")]
+    pub machine_rate: Decimal,
+}
+
+/// Machine run time captured after the fact, e.g. from a machine's cycle log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MachineTime {
+    /// Defaults to the operation's machine.
+    pub machine_id: Option<i32>,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+}
+
+/// Standard against actual hours and cost of one operation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct OperationRollup {
+    pub operation_id: Uuid,
+    pub sequence: i32,
+    pub name: String,
+    pub status: OperationStatus,
+    pub standard_labor_hours: f64,
+    pub actual_labor_hours: f64,
+    pub standard_machine_hours: f64,
+    pub actual_machine_hours: f64,
+    #[serde(with = "crate::money::amount")]
+    pub standard_cost: Decimal,
+    #[serde(with = "crate::money::amount")]
+    pub actual_cost: Decimal,
+}
+
+/// Labor and machine cost of a work order from recorded time, against its routing standard.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CostRollup {
+    pub work_order_id: Uuid,
+    pub operations: Vec<OperationRollup>,
+    pub standard_labor_hours: f64,
+    pub actual_labor_hours: f64,
+    pub standard_machine_hours: f64,
+    pub actual_machine_hours: f64,
+    #[serde(with = "crate::money::amount")]
+    pub labor_cost: Decimal,
+    #[serde(with = "crate::money::amount")]
+    pub machine_cost: Decimal,
+    #[serde(with = "crate::money::amount")]
+    pub standard_cost: Decimal,
+    /// Actual minus standard cost; positive when over standard.
+    #[serde(with = "crate::money::amount")]
+    pub variance: Decimal,
+    /// Workers still clocked in; their time is not counted yet.
+    pub open_entries: usize,
+}
+
+fn seconds(entry: &work_order_time_entry::Model) -> Option<i64> {
+    entry.ended_at.map(|ended| (ended - entry.started_at).num_seconds().max(0))
+}
+
+fn hours(seconds: i64) -> f64 {
+    seconds as f64 / 3600.0
+}
+
+/// Cost of a closed entry at its rate, to the cent; open entries cost nothing yet.
+pub fn entry_cost(entry: &work_order_time_entry::Model) -> Decimal {
+    seconds(entry)
+        .map(|s| (entry.rate * Decimal::from(s) / Decimal::from(3600)).round_dp(2))
+        .unwrap_or(Decimal::ZERO)
+}
+
+fn standard_cost(hours: f64, rate: Decimal) -> Decimal {
+    (Decimal::try_from(hours).unwrap_or(Decimal::ZERO) * rate).round_dp(2)
+}
+
+/// Rolls recorded time up per operation and for the whole work order.
+pub fn rollup(
+    work_order_id: Uuid,
+    operations: &[work_order_operation::Model],
+    entries: &[work_order_time_entry::Model],
+) -> CostRollup {
+    let mut total = CostRollup {
+        work_order_id,
+        operations: Vec::with_capacity(operations.len()),
+        standard_labor_hours: 0.0,
+        actual_labor_hours: 0.0,
+        standard_machine_hours: 0.0,
+        actual_machine_hours: 0.0,
+        labor_cost: Decimal::ZERO,
+        machine_cost: Decimal::ZERO,
+        standard_cost: Decimal::ZERO,
+        variance: Decimal::ZERO,
+        open_entries: entries.iter().filter(|e| e.ended_at.is_none()).count(),
+    };
+    for operation in operations {
+        let (mut labor_seconds, mut machine_seconds) = (0, 0);
+        let (mut labor_cost, mut machine_cost) = (Decimal::ZERO, Decimal::ZERO);
+        for entry in entries.iter().filter(|e| e.operation_id == operation.id) {
+            let elapsed = seconds(entry).unwrap_or(0);
+            match entry.kind {
+                TimeEntryKind::Labor => {
+                    labor_seconds += elapsed;
+                    labor_cost += entry_cost(entry);
+                }
+                TimeEntryKind::Machine => {
+                    machine_seconds += elapsed;
+                    machine_cost += entry_cost(entry);
+                }
+            }
+        }
+        let standard = standard_cost(operation.standard_labor_hours, operation.labor_rate)
+            + standard_cost(operation.standard_machine_hours, operation.machine_rate);
+
+        total.standard_labor_hours += operation.standard_labor_hours;
+        total.actual_labor_hours += hours(labor_seconds);
+        total.standard_machine_hours += operation.standard_machine_hours;
+        total.actual_machine_hours += hours(machine_seconds);
+        total.labor_cost += labor_cost;
+        total.machine_cost += machine_cost;
+        total.standard_cost += standard;
+        total.operations.push(OperationRollup {
+            operation_id: operation.id,
+            sequence: operation.sequence,
+            name: operation.name.clone(),
+            status: operation.status,
+            standard_labor_hours: operation.standard_labor_hours,
+            actual_labor_hours: hours(labor_seconds),
+            standard_machine_hours: operation.standard_machine_hours,
+            actual_machine_hours: hours(machine_seconds),
+            standard_cost: standard,
+            actual_cost: labor_cost + machine_cost,
+        });
+    }
+    total.variance = total.labor_cost + total.machine_cost - total.standard_cost;
+    total
+}
+
+/// Actual labor and machine cost recorded against a work order. The COGS calculation adds
+/// this to material cost.
+pub async fn conversion_cost<C: ConnectionTrait>(db: &C, work_order_id: Uuid) -> Result<Decimal, DbErr> {
+    let entries = TimeEntry::find()
+        .filter(work_order_time_entry::Column::WorkOrderId.eq(work_order_id))
+        .filter(work_order_time_entry::Column::EndedAt.is_not_null())
+        .all(db)
+        .await?;
+    Ok(entries.iter().map(entry_cost).sum())
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Work order operation query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+/// Routing steps of work orders and the labor and machine time booked against them.
+pub struct WorkOrderOperationService {
+    db_pool: Arc<DbPool>,
+    events: EventSender,
+}
+
+impl WorkOrderOperationService {
+    pub fn new(db_pool: Arc<DbPool>, events: EventSender) -> Self {
+        Self { db_pool, events }
+    }
+
+    async fn operation<C: ConnectionTrait>(
+        &self,
+        db: &C,
+        work_order_id: Uuid,
+        operation_id: Uuid,
+    ) -> Result<work_order_operation::Model, ServiceError> {
+        Operation::find_by_id(operation_id)
+            .filter(work_order_operation::Column::WorkOrderId.eq(work_order_id))
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Operation not found: {}", operation_id)))
+    }
+
+    #[instrument(skip(self, input), fields(work_order_id = %work_order_id))]
+    pub async fn add_operation(
+        &self,
+        work_order_id: Uuid,
+        input: NewOperation,
+    ) -> Result<work_order_operation::Model, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid operation: {}", e)))?;
+        let db = self.db_pool.as_ref();
+        WorkOrder::find_by_id(work_order_id)
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Work order not found: {}", work_order_id)))?;
+        let taken = Operation::find()
+            .filter(work_order_operation::Column::WorkOrderId.eq(work_order_id))
+            .filter(work_order_operation::Column::Sequence.eq(input.sequence))
+            .count(db)
+            .await
+            .map_err(db_error)?;
+        if taken > 0 {
+            return Err(ServiceError::ValidationError(format!("Sequence {} is already used", input.sequence)));
+        }
+
+        let now = Utc::now();
+        work_order_operation::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            work_order_id: Set(work_order_id),
+            sequence: Set(input.sequence),
+            name: Set(input.name),
+            work_center: Set(input.work_center),
+            scheduled_date: Set(input.scheduled_date),
+            machine_id: Set(input.machine_id),
+            standard_labor_hours: Set(input.standard_labor_hours),
+            standard_machine_hours: Set(input.standard_machine_hours),
+            labor_rate: Set(input.labor_rate),
+            machine_rate: Set(input.machine_rate),
+            status: Set(OperationStatus::Pending),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(db)
+        .await
+        .map_err(db_error)
+    }
+
+    pub async fn list_operations(&self, work_order_id: Uuid) -> Result<Vec<work_order_operation::Model>, ServiceError> {
+        Operation::find()
+            .filter(work_order_operation::Column::WorkOrderId.eq(work_order_id))
+            .order_by_asc(work_order_operation::Column::Sequence)
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)
+    }
+
+    /// Opens a labor entry for `worker`. A worker can be clocked in to one operation at a time.
+    #[instrument(skip(self), fields(work_order_id = %work_order_id, operation_id = %operation_id))]
+    pub async fn clock_in(
+        &self,
+        work_order_id: Uuid,
+        operation_id: Uuid,
+        worker: &str,
+    ) -> Result<work_order_time_entry::Model, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let operation = self.operation(&txn, work_order_id, operation_id).await?;
+        if operation.status == OperationStatus::Completed {
+            return Err(ServiceError::ValidationError(format!("Operation {} is completed", operation.name)));
+        }
+        let open = TimeEntry::find()
+            .filter(work_order_time_entry::Column::Worker.eq(worker))
+            .filter(work_order_time_entry::Column::EndedAt.is_null())
+            .one(&txn)
+            .await
+            .map_err(db_error)?;
+        if let Some(open) = open {
+            return Err(ServiceError::ValidationError(format!(
+                "{} is already clocked in to operation {}",
+                worker, open.operation_id
+            )));
+        }
+
+        let now = Utc::now();
+        if operation.status == OperationStatus::Pending {
+            let mut active: work_order_operation::ActiveModel = operation.clone().into();
+            active.status = Set(OperationStatus::InProgress);
+            active.updated_at = Set(now);
+            active.update(&txn).await.map_err(db_error)?;
+        }
+        let entry = work_order_time_entry::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            operation_id: Set(operation_id),
+            work_order_id: Set(work_order_id),
+            kind: Set(TimeEntryKind::Labor),
+            worker: Set(Some(worker.to_string())),
+            machine_id: Set(None),
+            started_at: Set(now),
+            ended_at: Set(None),
+            rate: Set(operation.labor_rate),
+            recorded_by: Set(worker.to_string()),
+            created_at: Set(now),
+        }
+        .insert(&txn)
+        .await
+        .map_err(db_error)?;
+        txn.commit().await.map_err(db_error)?;
+        Ok(entry)
+    }
+
+    /// Closes the worker's open labor entry on the operation and posts the new actuals.
+    #[instrument(skip(self), fields(work_order_id = %work_order_id, operation_id = %operation_id))]
+    pub async fn clock_out(
+        &self,
+        work_order_id: Uuid,
+        operation_id: Uuid,
+        worker: &str,
+    ) -> Result<work_order_time_entry::Model, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let open = TimeEntry::find()
+            .filter(work_order_time_entry::Column::WorkOrderId.eq(work_order_id))
+            .filter(work_order_time_entry::Column::OperationId.eq(operation_id))
+            .filter(work_order_time_entry::Column::Worker.eq(worker))
+            .filter(work_order_time_entry::Column::EndedAt.is_null())
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::ValidationError(format!("{} is not clocked in to this operation", worker)))?;
+        let mut active: work_order_time_entry::ActiveModel = open.into();
+        active.ended_at = Set(Some(Utc::now()));
+        let entry = active.update(db).await.map_err(db_error)?;
+        self.post_actuals(work_order_id).await?;
+        Ok(entry)
+    }
+
+    #[instrument(skip(self, time, recorded_by), fields(work_order_id = %work_order_id, operation_id = %operation_id))]
+    pub async fn record_machine_time(
+        &self,
+        work_order_id: Uuid,
+        operation_id: Uuid,
+        time: MachineTime,
+        recorded_by: &str,
+    ) -> Result<work_order_time_entry::Model, ServiceError> {
+        if time.ended_at <= time.started_at {
+            return Err(ServiceError::ValidationError("Machine time must end after it starts".to_string()));
+        }
+        if time.ended_at > Utc::now() {
+            return Err(ServiceError::ValidationError("Machine time cannot end in the future".to_string()));
+        }
+        let db = self.db_pool.as_ref();
+        let operation = self.operation(db, work_order_id, operation_id).await?;
+        let machine_id = time.machine_id.or(operation.machine_id).ok_or_else(|| {
+            ServiceError::ValidationError(format!("Operation {} has no machine; pass machine_id", operation.name))
+        })?;
+        let entry = work_order_time_entry::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            operation_id: Set(operation_id),
+            work_order_id: Set(work_order_id),
+            kind: Set(TimeEntryKind::Machine),
+            worker: Set(None),
+            machine_id: Set(Some(machine_id)),
+            started_at: Set(time.started_at),
+            ended_at: Set(Some(time.ended_at)),
+            rate: Set(operation.machine_rate),
+            recorded_by: Set(recorded_by.to_string()),
+            created_at: Set(Utc::now()),
+        }
+        .insert(db)
+        .await
+        .map_err(db_error)?;
+        self.post_actuals(work_order_id).await?;
+        Ok(entry)
+    }
+
+    /// Completes an operation once nobody is clocked in to it.
+    pub async fn complete_operation(
+        &self,
+        work_order_id: Uuid,
+        operation_id: Uuid,
+    ) -> Result<work_order_operation::Model, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let operation = self.operation(db, work_order_id, operation_id).await?;
+        let open = TimeEntry::find()
+            .filter(work_order_time_entry::Column::OperationId.eq(operation_id))
+            .filter(work_order_time_entry::Column::EndedAt.is_null())
+            .count(db)
+            .await
+            .map_err(db_error)?;
+        if open > 0 {
+            return Err(ServiceError::ValidationError(format!("{} workers are still clocked in", open)));
+        }
+        let mut active: work_order_operation::ActiveModel = operation.into();
+        active.status = Set(OperationStatus::Completed);
+        active.updated_at = Set(Utc::now());
+        active.update(db).await.map_err(db_error)
+    }
+
+    pub async fn rollup(&self, work_order_id: Uuid) -> Result<CostRollup, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let operations = self.list_operations(work_order_id).await?;
+        let entries = TimeEntry::find()
+            .filter(work_order_time_entry::Column::WorkOrderId.eq(work_order_id))
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        Ok(rollup(work_order_id, &operations, &entries))
+    }
+
+    /// Writes actual and standard labor hours onto the work order and announces the new
+    /// cost, so costing works from recorded time rather than estimates.
+    async fn post_actuals(&self, work_order_id: Uuid) -> Result<CostRollup, ServiceError> {
+        let rollup = self.rollup(work_order_id).await?;
+        WorkOrder::update_many()
+            .col_expr(work_order::Column::ActualLaborHours, sea_query::Expr::value(rollup.actual_labor_hours))
+            .col_expr(work_order::Column::StandardLaborHours, sea_query::Expr::value(rollup.standard_labor_hours))
+            .col_expr(work_order::Column::UpdatedAt, sea_query::Expr::value(Utc::now()))
+            .filter(work_order::Column::Id.eq(work_order_id))
+            .exec(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?;
+        let _ = self.events.send(Event::WorkOrderCostRolledUp {
+            work_order_id,
+            labor_cost: rollup.labor_cost,
+            machine_cost: rollup.machine_cost,
+        });
+        Ok(rollup)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+    use rust_decimal_macros::dec;
+
+    fn operation(sequence: i32, labor_hours: f64, machine_hours: f64) -> work_order_operation::Model {
+        let at = Utc.with_ymd_and_hms(2024, 5, 1, 8, 0, 0).unwrap();
+        work_order_operation::Model {
+            id: Uuid::new_v4(),
+            work_order_id: Uuid::nil(),
+            sequence,
+            name: format!("Step {}", sequence),
+            work_center: None,
+            scheduled_date: None,
+            machine_id: Some(1),
+            standard_labor_hours: labor_hours,
+            standard_machine_hours: machine_hours,
+            labor_rate: dec!(40),
+            machine_rate: dec!(25),
+            status: OperationStatus::InProgress,
+            created_at: at,
+            updated_at: at,
+        }
+    }
+
+    fn entry(operation: &work_order_operation::Model, kind: TimeEntryKind, minutes: Option<i64>) -> work_order_time_entry::Model {
+        let started = Utc.with_ymd_and_hms(2024, 5, 1, 9, 0, 0).unwrap();
+        work_order_time_entry::Model {
+            id: Uuid::new_v4(),
+            operation_id: operation.id,
+            work_order_id: operation.work_order_id,
+            kind,
+            worker: (kind == TimeEntryKind::Labor).then(|| "user:7".to_string()),
+            machine_id: (kind == TimeEntryKind::Machine).then_some(1),
+            started_at: started,
+            ended_at: minutes.map(|m| started + Duration::minutes(m)),
+            rate: match kind {
+                TimeEntryKind::Labor => operation.labor_rate,
+                TimeEntryKind::Machine => operation.machine_rate,
+            },
+            recorded_by: "user:7".to_string(),
+            created_at: started,
+        }
+    }
+
+    #[test]
+    fn test_entry_cost_is_prorated_to_the_second() {
+        let op = operation(10, 1.0, 0.0);
+        assert_eq!(entry_cost(&entry(&op, TimeEntryKind::Labor, Some(90))), dec!(60.00));
+        assert_eq!(entry_cost(&entry(&op, TimeEntryKind::Labor, None)), Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_rollup_compares_actual_with_standard() {
+        let cut = operation(10, 1.0, 0.5);
+        let pack = operation(20, 0.5, 0.0);
+        let entries = [
+            entry(&cut, TimeEntryKind::Labor, Some(75)),
+            entry(&cut, TimeEntryKind::Machine, Some(30)),
+            entry(&pack, TimeEntryKind::Labor, Some(30)),
+            entry(&pack, TimeEntryKind::Labor, None),
+        ];
+        let rollup = rollup(Uuid::nil(), &[cut, pack], &entries);
+
+        assert_eq!(rollup.actual_labor_hours, 1.75);
+        assert_eq!(rollup.actual_machine_hours, 0.5);
+        assert_eq!(rollup.labor_cost, dec!(70.00));
+        assert_eq!(rollup.machine_cost, dec!(12.50));
+        assert_eq!(rollup.standard_cost, dec!(72.50));
+        assert_eq!(rollup.variance, dec!(10.00));
+        assert_eq!(rollup.open_entries, 1);
+        assert_eq!(rollup.operations[0].actual_cost, dec!(62.50));
+    }
+}
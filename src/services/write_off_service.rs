@@ -0,0 +1,363 @@
+use async_trait::async_trait;
+use chrono::{NaiveDate, Utc};
+use rust_decimal::Decimal;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+use validator::Validate;
+
+use crate::{
+    db::{dialect, DbPool},
+    errors::ServiceError,
+    events::Event,
+    models::{
+        approval_step::{self, ApprovalSubject},
+        inventory_items::{self, Entity as InventoryItem},
+        inventory_write_off::{self, Entity as InventoryWriteOff, WriteOffReason, WriteOffStatus},
+    },
+    money::round_currency,
+    utils::pagination::PaginationParams,
+    workflow::{ApprovalEngine, ApprovalHandler, Resolution},
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct NewWriteOff {
+    #[validate(length(min = 1))]
+    pub inventory_item_id: String,
+    #[validate(range(min = 1))]
+    pub quantity: i32,
+    pub reason: WriteOffReason,
+    #[validate(length(max = 1000))]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WriteOffFilter {
+    pub status: Option<WriteOffStatus>,
+    pub warehouse: Option<i32>,
+    pub sku: Option<String>,
+    pub reason: Option<WriteOffReason>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WriteOffWithApprovals {
+    #[serde(flatten)]
+    pub write_off: inventory_write_off::Model,
+    pub approvals: Vec<approval_step::Model>,
+}
+
+/// Posted write-offs of one SKU in one warehouse for one reason.
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+pub struct ShrinkageRow {
+    pub warehouse: i32,
+    pub sku: String,
+    pub reason: WriteOffReason,
+    pub quantity: i64,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SkuShrinkage {
+    pub sku: String,
+    pub quantity: i64,
+    #[serde(with = "crate::money::amount")]
+    pub amount: Decimal,
+    /// Value written off per reason.
+    pub by_reason: BTreeMap<WriteOffReason, Decimal>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct WarehouseShrinkage {
+    pub warehouse: i32,
+    pub quantity: i64,
+    #[serde(with = "crate::money::amount")]
+    pub amount: Decimal,
+    /// Highest value first.
+    pub skus: Vec<SkuShrinkage>,
+}
+
+/// Checks a write-off request beyond field validation: `other` needs an explanation.
+pub fn check_reason(reason: WriteOffReason, notes: Option<&str>) -> Result<(), String> {
+    if reason == WriteOffReason::Other && notes.map_or(true, |n| n.trim().is_empty()) {
+        return Err("Write-offs for reason `other` need notes".to_string());
+    }
+    Ok(())
+}
+
+/// Groups shrinkage rows by warehouse, then SKU, highest value first.
+pub fn shrinkage_by_warehouse(rows: &[ShrinkageRow]) -> Vec<WarehouseShrinkage> {
+    let mut warehouses: BTreeMap<i32, BTreeMap<&str, SkuShrinkage>> = BTreeMap::new();
+    for row in rows {
+        let sku = warehouses
+            .entry(row.warehouse)
+            .or_default()
+            .entry(row.sku.as_str())
+            .or_insert_with(|| SkuShrinkage { sku: row.sku.clone(), ..Default::default() });
+        sku.quantity += row.quantity;
+        sku.amount += row.amount;
+        *sku.by_reason.entry(row.reason).or_default() += row.amount;
+    }
+    let mut report: Vec<WarehouseShrinkage> = warehouses
+        .into_iter()
+        .map(|(warehouse, skus)| {
+            let mut skus: Vec<SkuShrinkage> = skus.into_values().collect();
+            skus.sort_by(|a, b| b.amount.cmp(&a.amount).then_with(|| a.sku.cmp(&b.sku)));
+            WarehouseShrinkage {
+                warehouse,
+                quantity: skus.iter().map(|s| s.quantity).sum(),
+                amount: skus.iter().map(|s| s.amount).sum(),
+                skus,
+            }
+        })
+        .collect();
+    report.sort_by(|a, b| b.amount.cmp(&a.amount).then_with(|| a.warehouse.cmp(&b.warehouse)));
+    report
+}
+
+const SHRINKAGE_SQL: &str = r#"
+SELECT warehouse, sku, reason, SUM(quantity)::BIGINT AS quantity, SUM(amount) AS amount
+FROM inventory_write_offs
+WHERE status = 'posted' AND posted_at >= $1 AND posted_at < $2 AND ($3::INT IS NULL OR warehouse = $3)
+GROUP BY warehouse, sku, reason
+"#;
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Write-off query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+async fn find_pending(txn: &DatabaseTransaction, subject_id: &str) -> Result<inventory_write_off::Model, ServiceError> {
+    let id = Uuid::parse_str(subject_id)
+        .map_err(|_| ServiceError::ValidationError(format!("Invalid write-off id: {}", subject_id)))?;
+    let write_off = InventoryWriteOff::find_by_id(id)
+        .lock_exclusive()
+        .one(txn)
+        .await
+        .map_err(db_error)?
+        .ok_or_else(|| ServiceError::NotFound(format!("Write-off not found: {}", id)))?;
+    if write_off.status != WriteOffStatus::PendingApproval {
+        return Err(ServiceError::ValidationError(format!("Write-off is {:?}", write_off.status)));
+    }
+    Ok(write_off)
+}
+
+/// Shrinkage and damage write-offs. Each runs through the `write_off` approval chain,
+/// routed on the value written off, so a chain whose first step has a `min_amount` lets
+/// smaller write-offs post straight away.
+pub struct WriteOffService {
+    db_pool: Arc<DbPool>,
+    approvals: Arc<ApprovalEngine>,
+}
+
+impl WriteOffService {
+    pub fn new(db_pool: Arc<DbPool>, approvals: Arc<ApprovalEngine>) -> Self {
+        Self { db_pool, approvals }
+    }
+
+    /// Records a write-off at the item's current cost and submits it for approval.
+    #[instrument(skip(self, input), fields(item = %input.inventory_item_id))]
+    pub async fn create(&self, input: NewWriteOff, actor: &str) -> Result<WriteOffWithApprovals, ServiceError> {
+        input
+            .validate()
+            .map_err(|e| ServiceError::ValidationError(format!("Invalid write-off: {}", e)))?;
+        check_reason(input.reason, input.notes.as_deref()).map_err(ServiceError::ValidationError)?;
+        let db = self.db_pool.as_ref();
+        let item = InventoryItem::find_by_id(input.inventory_item_id.clone())
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Inventory item not found: {}", input.inventory_item_id)))?;
+        if item.available < input.quantity {
+            return Err(ServiceError::ValidationError(format!(
+                "Only {} units of {} are available to write off",
+                item.available, item.sku
+            )));
+        }
+        let unit_cost = item.unit_cost.or(item.average_cost).unwrap_or(Decimal::ZERO);
+        let now = Utc::now();
+        let write_off = inventory_write_off::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            inventory_item_id: Set(item.id),
+            sku: Set(item.sku),
+            warehouse: Set(item.warehouse),
+            quantity: Set(input.quantity),
+            unit_cost: Set(unit_cost),
+            amount: Set(round_currency(unit_cost * Decimal::from(input.quantity))),
+            reason: Set(input.reason),
+            notes: Set(input.notes),
+            status: Set(WriteOffStatus::PendingApproval),
+            requested_by: Set(actor.to_string()),
+            created_at: Set(now),
+            updated_at: Set(now),
+            posted_at: Set(None),
+        }
+        .insert(db)
+        .await
+        .map_err(db_error)?;
+        self.approvals
+            .request(ApprovalSubject::WriteOff, &write_off.id.to_string(), actor)
+            .await?;
+        self.get(write_off.id).await
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<WriteOffWithApprovals, ServiceError> {
+        let write_off = InventoryWriteOff::find_by_id(id)
+            .one(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Write-off not found: {}", id)))?;
+        let approvals = self.approvals.steps(ApprovalSubject::WriteOff, &id.to_string()).await?;
+        Ok(WriteOffWithApprovals { write_off, approvals })
+    }
+
+    pub async fn list(
+        &self,
+        filter: WriteOffFilter,
+        pagination: PaginationParams,
+    ) -> Result<(Vec<inventory_write_off::Model>, u64), ServiceError> {
+        let mut query = InventoryWriteOff::find();
+        if let Some(status) = filter.status {
+            query = query.filter(inventory_write_off::Column::Status.eq(status));
+        }
+        if let Some(warehouse) = filter.warehouse {
+            query = query.filter(inventory_write_off::Column::Warehouse.eq(warehouse));
+        }
+        if let Some(sku) = filter.sku {
+            query = query.filter(inventory_write_off::Column::Sku.eq(sku));
+        }
+        if let Some(reason) = filter.reason {
+            query = query.filter(inventory_write_off::Column::Reason.eq(reason));
+        }
+        let paginator = query
+            .order_by_desc(inventory_write_off::Column::CreatedAt)
+            .paginate(self.db_pool.as_ref(), pagination.limit());
+        let total = paginator.num_items().await.map_err(db_error)?;
+        let items = paginator.fetch_page(pagination.page_index()).await.map_err(db_error)?;
+        Ok((items, total))
+    }
+
+    /// Posted write-offs in `[from, to]` by warehouse and SKU.
+    pub async fn shrinkage_report(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        warehouse: Option<i32>,
+    ) -> Result<Vec<WarehouseShrinkage>, ServiceError> {
+        if to < from {
+            return Err(ServiceError::ValidationError("`to` is before `from`".to_string()));
+        }
+        let start = from.and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc();
+        let end = to.succ_opt().unwrap_or(to).and_hms_opt(0, 0, 0).expect("midnight is valid").and_utc();
+        let rows = ShrinkageRow::find_by_statement(dialect::statement(
+            self.db_pool.as_ref(),
+            SHRINKAGE_SQL,
+            [start.into(), end.into(), warehouse.into()],
+        ))
+        .all(self.db_pool.as_ref())
+        .await
+        .map_err(db_error)?;
+        Ok(shrinkage_by_warehouse(&rows))
+    }
+}
+
+/// Workflow side of write-offs: approval takes the stock off the item and reports the lost
+/// value, which the ledger posts against inventory.
+pub struct WriteOffApprovals;
+
+#[async_trait]
+impl ApprovalHandler for WriteOffApprovals {
+    fn subject(&self) -> ApprovalSubject {
+        ApprovalSubject::WriteOff
+    }
+
+    async fn submit(&self, txn: &DatabaseTransaction, subject_id: &str, _actor: &str) -> Result<Decimal, ServiceError> {
+        Ok(find_pending(txn, subject_id).await?.amount)
+    }
+
+    async fn resolve(
+        &self,
+        txn: &DatabaseTransaction,
+        subject_id: &str,
+        resolution: Resolution,
+        _actor: &str,
+    ) -> Result<Option<Event>, ServiceError> {
+        let write_off = find_pending(txn, subject_id).await?;
+        let now = Utc::now();
+        let mut active: inventory_write_off::ActiveModel = write_off.clone().into();
+        active.updated_at = Set(now);
+        if resolution == Resolution::Rejected {
+            active.status = Set(WriteOffStatus::Rejected);
+            active.update(txn).await.map_err(db_error)?;
+            return Ok(None);
+        }
+
+        // Stock may have moved while the write-off waited for approval
+        let item = InventoryItem::find_by_id(write_off.inventory_item_id.clone())
+            .lock_exclusive()
+            .one(txn)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Inventory item not found: {}", write_off.inventory_item_id)))?;
+        if item.available < write_off.quantity {
+            return Err(ServiceError::ValidationError(format!(
+                "Only {} units of {} are left to write off",
+                item.available, item.sku
+            )));
+        }
+        let available = item.available - write_off.quantity;
+        let mut stock: inventory_items::ActiveModel = item.into();
+        stock.available = Set(available);
+        stock.last_movement_date = Set(Some(now));
+        stock.update(txn).await.map_err(db_error)?;
+
+        active.status = Set(WriteOffStatus::Posted);
+        active.posted_at = Set(Some(now));
+        let write_off = active.update(txn).await.map_err(db_error)?;
+        info!(write_off_id = %write_off.id, sku = %write_off.sku, quantity = write_off.quantity, amount = %write_off.amount, "Inventory written off");
+        Ok((!write_off.amount.is_zero()).then(|| Event::InventoryRevalued {
+            sku: write_off.sku.clone(),
+            warehouse: write_off.warehouse,
+            amount: -write_off.amount,
+            reason: format!("Write-off ({}): {}", write_off.reason.as_str(), write_off.id),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn row(warehouse: i32, sku: &str, reason: WriteOffReason, quantity: i64, amount: Decimal) -> ShrinkageRow {
+        ShrinkageRow { warehouse, sku: sku.to_string(), reason, quantity, amount }
+    }
+
+    #[test]
+    fn test_other_reason_needs_notes() {
+        assert!(check_reason(WriteOffReason::Damaged, None).is_ok());
+        assert!(check_reason(WriteOffReason::Other, Some("  ")).is_err());
+        assert!(check_reason(WriteOffReason::Other, Some("Water leak")).is_ok());
+    }
+
+    #[test]
+    fn test_shrinkage_groups_by_warehouse_and_sku() {
+        let report = shrinkage_by_warehouse(&[
+            row(1, "MUG", WriteOffReason::Damaged, 4, dec!(20)),
+            row(1, "MUG", WriteOffReason::Theft, 1, dec!(5)),
+            row(1, "LAMP", WriteOffReason::Expired, 1, dec!(40)),
+            row(2, "MUG", WriteOffReason::Damaged, 2, dec!(10)),
+        ]);
+        assert_eq!(report.len(), 2);
+        assert_eq!(report[0].warehouse, 1);
+        assert_eq!(report[0].amount, dec!(65));
+        assert_eq!(report[0].quantity, 6);
+        assert_eq!(report[0].skus[0].sku, "LAMP");
+        let mug = &report[0].skus[1];
+        assert_eq!(mug.amount, dec!(25));
+        assert_eq!(mug.by_reason[&WriteOffReason::Theft], dec!(5));
+        assert_eq!(report[1].amount, dec!(10));
+    }
+}
@@ -0,0 +1,513 @@
+// shipment_sla/mod.rs
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration as ChronoDuration, NaiveDate, Utc};
+use lazy_static::lazy_static;
+use prometheus::IntCounter;
+use sea_orm::{ColumnTrait, DatabaseConnection, DbErr, EntityTrait, Iterable, QueryFilter, QueryOrder};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use thiserror::Error;
+use tracing::{error, info};
+
+use crate::calendar::{BusinessCalendar, Calendars};
+use crate::i18n::Locale;
+use crate::models::shipment::{self, Entity as Shipment, ShipmentStatus, ShippingCarrier};
+use crate::notifications::{create_shipment_late_notification, NotificationService};
+
+lazy_static! {
+    static ref SLA_ALERTS: IntCounter =
+        IntCounter::new("shipment_sla_alerts_total", "Overdue in-transit shipments alerted on")
+            .expect("metric can be created");
+}
+
+/// Longest reporting range accepted by `GET /api/v1/analytics/shipments/sla`.
+pub const MAX_RANGE_DAYS: i64 = 366;
+
+/// Shipment SLA settings, loaded from the `shipment_sla` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ShipmentSlaConfig {
+    /// Runs the overdue-shipment monitor. Reporting is always available.
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+
+    /// Seconds between checks for overdue in-transit shipments.
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+
+    /// Expected transit business days by shipping method, used when a shipment has no
+    /// estimated delivery date. Methods are matched case-insensitively.
+    #[serde(default = "default_transit_days")]
+    pub transit_days: HashMap<String, i64>,
+
+    /// Transit days for shipping methods missing from `transit_days`.
+    #[serde(default = "default_fallback_transit_days")]
+    pub fallback_transit_days: i64,
+
+    /// Hours past the expected delivery date a shipment may arrive and still be on time.
+    #[serde(default = "default_grace_hours")]
+    pub grace_hours: i64,
+
+    /// Users notified when an in-transit shipment becomes overdue.
+    #[serde(default)]
+    pub alert_user_ids: Vec<i32>,
+
+    /// Locale of alert messages, e.g. `de-DE`.
+    #[serde(default = "default_alert_locale")]
+    pub alert_locale: String,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    15 * 60
+}
+
+fn default_transit_days() -> HashMap<String, i64> {
+    HashMap::from([("ground".to_string(), 5), ("express".to_string(), 2)])
+}
+
+fn default_fallback_transit_days() -> i64 {
+    7
+}
+
+fn default_grace_hours() -> i64 {
+    12
+}
+
+fn default_alert_locale() -> String {
+    Locale::EnUs.tag().to_string()
+}
+
+impl Default for ShipmentSlaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_secs: default_interval_secs(),
+            transit_days: default_transit_days(),
+            fallback_transit_days: default_fallback_transit_days(),
+            grace_hours: default_grace_hours(),
+            alert_user_ids: Vec::new(),
+            alert_locale: default_alert_locale(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SlaError {
+    #[error("Invalid date range: {0}")]
+    InvalidRange(String),
+    #[error("Missing permission: shipments:read")]
+    Forbidden,
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for SlaError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            SlaError::InvalidRange(_) => (StatusCode::BAD_REQUEST, "invalid_range"),
+            SlaError::Forbidden => (StatusCode::FORBIDDEN, "forbidden"),
+            SlaError::Database(e) => {
+                error!("Shipment SLA query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "sla_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlaStatus {
+    OnTime,
+    Late,
+    /// Not delivered yet and still within the expected transit time.
+    InTransit,
+    /// Not delivered and past the expected delivery date plus grace.
+    Overdue,
+}
+
+/// Expected versus actual delivery of one shipment.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ShipmentSla {
+    pub shipment_id: i32,
+    pub order_id: i32,
+    pub carrier: ShippingCarrier,
+    pub tracking_number: String,
+    pub shipping_method: String,
+    pub shipped_at: DateTime<Utc>,
+    pub expected_delivery: DateTime<Utc>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub status: SlaStatus,
+    /// Hours past the expected delivery date, at delivery or so far; 0 when early.
+    pub delay_hours: i64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Compliance {
+    pub delivered: u64,
+    pub on_time: u64,
+    pub late: u64,
+    /// Share of delivered shipments that arrived on time; `None` with no deliveries.
+    pub compliance_rate: Option<f64>,
+    /// Mean delay of late deliveries.
+    pub average_delay_hours: Option<f64>,
+    pub in_transit: u64,
+    pub overdue: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CarrierCompliance {
+    pub carrier: ShippingCarrier,
+    #[serde(flatten)]
+    pub compliance: Compliance,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SlaReport {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub overall: Compliance,
+    pub carriers: Vec<CarrierCompliance>,
+    /// Shipments from the range that are still overdue, longest delay first.
+    pub overdue: Vec<ShipmentSla>,
+}
+
+/// The carrier's estimate when there is one, otherwise the end of the local day the
+/// configured transit time for the shipping method runs out. Transit counts business
+/// days from the ship date, which moves to the next business day past the cutoff.
+pub fn expected_delivery(
+    shipment: &shipment::Model,
+    config: &ShipmentSlaConfig,
+    calendar: &BusinessCalendar,
+) -> Option<DateTime<Utc>> {
+    if let Some(estimated) = shipment.estimated_delivery {
+        return Some(estimated.with_timezone(&Utc));
+    }
+    let shipped_at = shipment.shipped_at?.with_timezone(&Utc);
+    let days = config
+        .transit_days
+        .iter()
+        .find(|(method, _)| method.eq_ignore_ascii_case(&shipment.shipping_method))
+        .map(|(_, days)| *days)
+        .unwrap_or(config.fallback_transit_days);
+    let delivery_date = calendar.add_business_days(calendar.ship_date(shipped_at), days);
+    Some(calendar.end_of_day(delivery_date))
+}
+
+/// SLA state of a shipment at `now`; `None` for shipments that have not left or were
+/// returned without a delivery.
+pub fn evaluate(
+    shipment: &shipment::Model,
+    now: DateTime<Utc>,
+    config: &ShipmentSlaConfig,
+    calendar: &BusinessCalendar,
+) -> Option<ShipmentSla> {
+    let shipped_at = shipment.shipped_at?.with_timezone(&Utc);
+    let expected = expected_delivery(shipment, config, calendar)?;
+    let deadline = expected + ChronoDuration::hours(config.grace_hours);
+    let delivered_at = shipment.delivered_at.map(|d| d.with_timezone(&Utc));
+
+    let (status, until) = match delivered_at {
+        Some(delivered) if delivered > deadline => (SlaStatus::Late, delivered),
+        Some(delivered) => (SlaStatus::OnTime, delivered),
+        None if !matches!(shipment.status, ShipmentStatus::Shipped | ShipmentStatus::InTransit) => return None,
+        None if now > deadline => (SlaStatus::Overdue, now),
+        None => (SlaStatus::InTransit, now),
+    };
+    Some(ShipmentSla {
+        shipment_id: shipment.id,
+        order_id: shipment.order_id,
+        carrier: shipment.carrier,
+        tracking_number: shipment.tracking_number.clone(),
+        shipping_method: shipment.shipping_method.clone(),
+        shipped_at,
+        expected_delivery: expected,
+        delivered_at,
+        status,
+        delay_hours: (until - expected).num_hours().max(0),
+    })
+}
+
+pub fn summarize<'a>(rows: impl IntoIterator<Item = &'a ShipmentSla>) -> Compliance {
+    let mut summary = Compliance::default();
+    let mut delay_hours = 0;
+    for row in rows {
+        match row.status {
+            SlaStatus::OnTime => summary.on_time += 1,
+            SlaStatus::Late => {
+                summary.late += 1;
+                delay_hours += row.delay_hours;
+            }
+            SlaStatus::InTransit => summary.in_transit += 1,
+            SlaStatus::Overdue => summary.overdue += 1,
+        }
+    }
+    summary.delivered = summary.on_time + summary.late;
+    if summary.delivered > 0 {
+        summary.compliance_rate = Some(summary.on_time as f64 / summary.delivered as f64);
+    }
+    if summary.late > 0 {
+        summary.average_delay_hours = Some(delay_hours as f64 / summary.late as f64);
+    }
+    summary
+}
+
+pub fn build_report(from: NaiveDate, to: NaiveDate, rows: Vec<ShipmentSla>) -> SlaReport {
+    let carriers = ShippingCarrier::iter()
+        .filter(|carrier| rows.iter().any(|row| row.carrier == *carrier))
+        .map(|carrier| CarrierCompliance {
+            carrier,
+            compliance: summarize(rows.iter().filter(|row| row.carrier == carrier)),
+        })
+        .collect();
+    let overall = summarize(&rows);
+    let mut overdue: Vec<_> = rows.into_iter().filter(|row| row.status == SlaStatus::Overdue).collect();
+    overdue.sort_by(|a, b| b.delay_hours.cmp(&a.delay_hours));
+    SlaReport { from, to, overall, carriers, overdue }
+}
+
+/// Overdue shipments not alerted on before; `alerted` is replaced by the current
+/// overdue set so a shipment drops out once it is delivered.
+pub fn newly_overdue<'a>(alerted: &mut HashSet<i32>, overdue: &'a [ShipmentSla]) -> Vec<&'a ShipmentSla> {
+    let fresh = overdue.iter().filter(|row| !alerted.contains(&row.shipment_id)).collect();
+    *alerted = overdue.iter().map(|row| row.shipment_id).collect();
+    fresh
+}
+
+/// Carrier SLA reporting over shipments. Shipments do not record the warehouse they
+/// left from, so expected dates follow the default calendar unless a report asks for a
+/// warehouse's.
+pub struct ShipmentSlaService {
+    db: Arc<DatabaseConnection>,
+    config: ShipmentSlaConfig,
+    calendars: Arc<Calendars>,
+}
+
+impl ShipmentSlaService {
+    pub fn new(db: Arc<DatabaseConnection>, config: ShipmentSlaConfig, calendars: Arc<Calendars>) -> Self {
+        Self { db, config, calendars }
+    }
+
+    pub fn calendar(&self, warehouse: Option<&str>) -> &BusinessCalendar {
+        self.calendars.for_warehouse(warehouse)
+    }
+
+    /// Compliance per carrier for shipments shipped between `from` and `to`, inclusive,
+    /// in the local days of `warehouse`.
+    pub async fn report(&self, from: NaiveDate, to: NaiveDate, warehouse: Option<&str>) -> Result<SlaReport, SlaError> {
+        if to < from {
+            return Err(SlaError::InvalidRange("`to` is before `from`".to_string()));
+        }
+        if (to - from).num_days() >= MAX_RANGE_DAYS {
+            return Err(SlaError::InvalidRange(format!("ranges are limited to {} days", MAX_RANGE_DAYS)));
+        }
+        let calendar = self.calendar(warehouse);
+        let start = calendar.start_of_day(from);
+        let end = calendar.end_of_day(to);
+        let shipments = Shipment::find()
+            .filter(shipment::Column::ShippedAt.gte(start))
+            .filter(shipment::Column::ShippedAt.lt(end))
+            .order_by_asc(shipment::Column::ShippedAt)
+            .all(self.db.as_ref())
+            .await?;
+        let now = Utc::now();
+        let rows = shipments.iter().filter_map(|s| evaluate(s, now, &self.config, calendar)).collect();
+        Ok(build_report(from, to, rows))
+    }
+
+    /// Undelivered shipments past their expected delivery date plus grace.
+    pub async fn overdue(&self) -> Result<Vec<ShipmentSla>, DbErr> {
+        let shipments = Shipment::find()
+            .filter(shipment::Column::Status.is_in([ShipmentStatus::Shipped, ShipmentStatus::InTransit]))
+            .filter(shipment::Column::DeliveredAt.is_null())
+            .all(self.db.as_ref())
+            .await?;
+        let now = Utc::now();
+        Ok(shipments
+            .iter()
+            .filter_map(|s| evaluate(s, now, &self.config, self.calendars.default_calendar()))
+            .filter(|row| row.status == SlaStatus::Overdue)
+            .collect())
+    }
+}
+
+/// Notifies `alert_user_ids` once per shipment when it becomes overdue. The alerted
+/// set is kept in memory, so a restart alerts again on shipments still overdue.
+pub struct SlaMonitor {
+    service: Arc<ShipmentSlaService>,
+    notifications: Arc<dyn NotificationService>,
+    alerted: Mutex<HashSet<i32>>,
+}
+
+impl SlaMonitor {
+    pub fn new(service: Arc<ShipmentSlaService>, notifications: Arc<dyn NotificationService>) -> Self {
+        Self { service, notifications, alerted: Mutex::new(HashSet::new()) }
+    }
+
+    /// Sends alerts for newly overdue shipments and returns how many there were.
+    pub async fn check(&self) -> Result<usize, DbErr> {
+        let overdue = self.service.overdue().await?;
+        let fresh: Vec<ShipmentSla> = {
+            let mut alerted = self.alerted.lock().expect("alerted lock poisoned");
+            newly_overdue(&mut alerted, &overdue).into_iter().cloned().collect()
+        };
+        let config = &self.service.config;
+        let locale = Locale::parse(&config.alert_locale).unwrap_or(Locale::EnUs);
+        for row in &fresh {
+            for user_id in &config.alert_user_ids {
+                let notification = create_shipment_late_notification(*user_id, row, locale);
+                if let Err(e) = self.notifications.send_notification(notification).await {
+                    error!("Failed to send late-delivery alert for shipment {}: {}", row.shipment_id, e);
+                }
+            }
+        }
+        SLA_ALERTS.inc_by(fresh.len() as u64);
+        if !fresh.is_empty() {
+            info!(shipments = fresh.len(), "Overdue shipments alerted");
+        }
+        Ok(fresh.len())
+    }
+}
+
+pub fn spawn_monitor(monitor: Arc<SlaMonitor>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval.max(Duration::from_secs(1)));
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = monitor.check().await {
+                error!("Shipment SLA check failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 5, day, hour, 0, 0).unwrap()
+    }
+
+    fn shipment(id: i32, carrier: ShippingCarrier, estimated: Option<DateTime<Utc>>, delivered: Option<DateTime<Utc>>) -> shipment::Model {
+        shipment::Model {
+            id,
+            order_id: id,
+            tracking_number: format!("TRK{}", id),
+            carrier,
+            status: if delivered.is_some() { ShipmentStatus::Delivered } else { ShipmentStatus::InTransit },
+            shipping_address: "1 Main St".to_string(),
+            shipping_method: "Ground".to_string(),
+            shipped_at: Some(at(1, 9).into()),
+            estimated_delivery: estimated.map(Into::into),
+            delivered_at: delivered.map(Into::into),
+            manifest_id: None,
+            created_at: at(1, 8).into(),
+            updated_at: at(1, 9).into(),
+        }
+    }
+
+    #[test]
+    fn test_expected_delivery_falls_back_to_method_transit_time() {
+        let config = ShipmentSlaConfig::default();
+        let calendar = BusinessCalendar::default();
+        let estimated = shipment(1, ShippingCarrier::UPS, Some(at(3, 17)), None);
+        assert_eq!(expected_delivery(&estimated, &config, &calendar), Some(at(3, 17)));
+        // Shipped Wednesday 1 May; five business days run to the end of the 8th
+        assert_eq!(expected_delivery(&shipment(1, ShippingCarrier::UPS, None, None), &config, &calendar), Some(at(9, 0)));
+
+        let mut freight = shipment(1, ShippingCarrier::UPS, None, None);
+        freight.shipping_method = "Freight".to_string();
+        assert_eq!(expected_delivery(&freight, &config, &calendar), Some(at(11, 0)));
+    }
+
+    #[test]
+    fn test_transit_starts_the_business_day_after_a_missed_cutoff() {
+        let config = ShipmentSlaConfig::default();
+        let calendar = BusinessCalendar::default();
+        // Express handed over Friday 3 May after the 14:00 cutoff leaves on Monday the 6th
+        let mut express = shipment(1, ShippingCarrier::FedEx, None, None);
+        express.shipping_method = "Express".to_string();
+        express.shipped_at = Some(at(3, 15).into());
+        assert_eq!(expected_delivery(&express, &config, &calendar), Some(at(9, 0)));
+    }
+
+    #[test]
+    fn test_grace_period_separates_on_time_from_late() {
+        let config = ShipmentSlaConfig::default();
+        let calendar = BusinessCalendar::default();
+        let on_time = shipment(1, ShippingCarrier::UPS, Some(at(3, 9)), Some(at(3, 20)));
+        let on_time = evaluate(&on_time, at(10, 0), &config, &calendar).unwrap();
+        assert_eq!(on_time.status, SlaStatus::OnTime);
+        assert_eq!(on_time.delay_hours, 11);
+
+        let late = shipment(2, ShippingCarrier::UPS, Some(at(3, 9)), Some(at(4, 9)));
+        let late = evaluate(&late, at(10, 0), &config, &calendar).unwrap();
+        assert_eq!(late.status, SlaStatus::Late);
+        assert_eq!(late.delay_hours, 24);
+    }
+
+    #[test]
+    fn test_undelivered_shipments_become_overdue() {
+        let config = ShipmentSlaConfig::default();
+        let calendar = BusinessCalendar::default();
+        let pending = shipment(1, ShippingCarrier::FedEx, Some(at(3, 9)), None);
+        assert_eq!(evaluate(&pending, at(3, 12), &config, &calendar).unwrap().status, SlaStatus::InTransit);
+        assert_eq!(evaluate(&pending, at(4, 9), &config, &calendar).unwrap().status, SlaStatus::Overdue);
+
+        let mut returned = pending.clone();
+        returned.status = ShipmentStatus::Returned;
+        assert_eq!(evaluate(&returned, at(4, 9), &config, &calendar), None);
+    }
+
+    #[test]
+    fn test_report_groups_by_carrier() {
+        let config = ShipmentSlaConfig::default();
+        let calendar = BusinessCalendar::default();
+        let now = at(10, 0);
+        let rows = [
+            shipment(1, ShippingCarrier::UPS, Some(at(3, 9)), Some(at(3, 10))),
+            shipment(2, ShippingCarrier::UPS, Some(at(3, 9)), Some(at(5, 9))),
+            shipment(3, ShippingCarrier::DHL, Some(at(3, 9)), None),
+        ]
+        .iter()
+        .filter_map(|s| evaluate(s, now, &config, &calendar))
+        .collect();
+        let report = build_report(at(1, 0).date_naive(), at(1, 0).date_naive(), rows);
+
+        assert_eq!(report.carriers.len(), 2);
+        assert_eq!(report.carriers[0].carrier, ShippingCarrier::UPS);
+        assert_eq!(report.carriers[0].compliance.compliance_rate, Some(0.5));
+        assert_eq!(report.carriers[0].compliance.average_delay_hours, Some(48.0));
+        assert_eq!(report.overall.overdue, 1);
+        assert_eq!(report.overdue[0].shipment_id, 3);
+    }
+
+    #[test]
+    fn test_overdue_shipment_is_alerted_once() {
+        let config = ShipmentSlaConfig::default();
+        let calendar = BusinessCalendar::default();
+        let pending = shipment(1, ShippingCarrier::USPS, Some(at(3, 9)), None);
+        let overdue = vec![evaluate(&pending, at(10, 0), &config, &calendar).unwrap()];
+        let mut alerted = HashSet::new();
+        assert_eq!(newly_overdue(&mut alerted, &overdue).len(), 1);
+        assert!(newly_overdue(&mut alerted, &overdue).is_empty());
+        assert!(newly_overdue(&mut alerted, &[]).is_empty());
+        assert_eq!(newly_overdue(&mut alerted, &overdue).len(), 1);
+    }
+}
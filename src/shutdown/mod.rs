@@ -0,0 +1,206 @@
+// shutdown/mod.rs
+
+use serde::Deserialize;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{oneshot, watch};
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+/// Shutdown settings, loaded from the `shutdown` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ShutdownConfig {
+    /// Total time allowed for draining once a stop signal arrives: in-flight requests
+    /// first, then background loops and jobs with what is left (default: 30 s). Keep it below the
+    /// orchestrator's kill grace period.
+    #[serde(default = "default_drain_timeout_secs")]
+    pub drain_timeout_secs: u64,
+}
+
+fn default_drain_timeout_secs() -> u64 {
+    30
+}
+
+impl Default for ShutdownConfig {
+    fn default() -> Self {
+        Self { drain_timeout_secs: default_drain_timeout_secs() }
+    }
+}
+
+/// Resolves on Ctrl-C or, on Unix, SIGTERM as sent by orchestrators on deploys.
+pub async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Unable to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut stream) => {
+                stream.recv().await;
+            }
+            Err(e) => {
+                warn!("Unable to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => info!("Received Ctrl-C, shutting down"),
+        _ = terminate => info!("Received SIGTERM, shutting down"),
+    }
+}
+
+/// The graceful-shutdown future for the server, and a receiver that fires with the time
+/// the signal arrived.
+pub fn signal_with_notice() -> (impl Future<Output = ()>, oneshot::Receiver<Instant>) {
+    let (notify, notice) = oneshot::channel();
+    let signal = async move {
+        shutdown_signal().await;
+        let _ = notify.send(Instant::now());
+    };
+    (signal, notice)
+}
+
+/// Waits for a gracefully shutting down `server`: once the signal is noticed, in-flight
+/// requests get `timeout` to finish. Returns the end of the drain window and whether
+/// every request completed.
+pub async fn drain_server<S, E>(server: S, notice: oneshot::Receiver<Instant>, timeout: Duration) -> (Instant, bool)
+where
+    S: Future<Output = Result<(), E>>,
+    E: std::fmt::Display,
+{
+    tokio::pin!(server);
+    let signalled = tokio::select! {
+        result = &mut server => {
+            if let Err(e) = result {
+                warn!("Server stopped with an error: {}", e);
+            }
+            return (Instant::now() + timeout, true);
+        }
+        signalled = notice => signalled.unwrap_or_else(|_| Instant::now()),
+    };
+    let deadline = signalled + timeout;
+    match tokio::time::timeout_at(deadline, &mut server).await {
+        Ok(_) => {
+            info!("In-flight requests drained");
+            (deadline, true)
+        }
+        Err(_) => {
+            warn!("Requests still in flight after the drain timeout; closing them");
+            (deadline, false)
+        }
+    }
+}
+
+/// Lets background loops, such as the outbox publishers, stop at a safe point on
+/// shutdown. Each loop holds a [`Worker`] and checks it only between units of work;
+/// [`Drain::wait`] asks them all to stop and waits for them to let go.
+pub struct Drain {
+    stop: watch::Sender<bool>,
+    workers: watch::Sender<usize>,
+}
+
+impl Drain {
+    pub fn new() -> Arc<Self> {
+        let (stop, _) = watch::channel(false);
+        let (workers, _) = watch::channel(0);
+        Arc::new(Self { stop, workers })
+    }
+
+    /// Registers a loop; it counts as running until the worker is dropped, including
+    /// when its task panics.
+    pub fn worker(self: &Arc<Self>) -> Worker {
+        self.workers.send_modify(|count| *count += 1);
+        Worker { stop: self.stop.subscribe(), drain: self.clone() }
+    }
+
+    /// Asks every worker to stop and waits up to `timeout` for them to finish their
+    /// current unit of work. Returns how many were still running.
+    pub async fn wait(&self, timeout: Duration) -> usize {
+        self.stop.send_replace(true);
+        let mut workers = self.workers.subscribe();
+        let _ = tokio::time::timeout(timeout, workers.wait_for(|count| *count == 0)).await;
+        let left = *self.workers.borrow();
+        if left > 0 {
+            warn!(loops = left, "Background loops still busy at shutdown");
+        }
+        left
+    }
+}
+
+/// A background loop's handle on the [`Drain`].
+pub struct Worker {
+    stop: watch::Receiver<bool>,
+    drain: Arc<Drain>,
+}
+
+impl Worker {
+    /// Resolves once shutdown starts. Select on it only where the loop may stop.
+    pub async fn stopping(&mut self) {
+        let _ = self.stop.wait_for(|stop| *stop).await;
+    }
+
+    pub fn is_stopping(&self) -> bool {
+        *self.stop.borrow()
+    }
+}
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.drain.workers.send_modify(|count| *count -= 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_drain_waits_for_workers() {
+        let drain = Drain::new();
+        let mut worker = drain.worker();
+        let looping = tokio::spawn(async move {
+            worker.stopping().await;
+            assert!(worker.is_stopping());
+        });
+        assert_eq!(drain.wait(Duration::from_secs(1)).await, 0);
+        looping.await.unwrap();
+
+        let _stuck = drain.worker();
+        assert_eq!(drain.wait(Duration::from_millis(10)).await, 1);
+    }
+
+    #[tokio::test]
+    async fn test_panicking_worker_is_released() {
+        let drain = Drain::new();
+        let worker = drain.worker();
+        let panicked = tokio::spawn(async move {
+            let _worker = worker;
+            panic!("loop failed");
+        });
+        assert!(panicked.await.is_err());
+        assert_eq!(drain.wait(Duration::from_millis(10)).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_drain_is_bounded() {
+        let (notify, notice) = oneshot::channel();
+        notify.send(Instant::now()).unwrap();
+        let stuck = std::future::pending::<Result<(), String>>();
+        let (_, drained) = drain_server(stuck, notice, Duration::from_millis(10)).await;
+        assert!(!drained);
+
+        let (notify, notice) = oneshot::channel();
+        notify.send(Instant::now()).unwrap();
+        let (_, drained) = drain_server(async { Ok::<_, String>(()) }, notice, Duration::from_secs(1)).await;
+        assert!(drained);
+    }
+}
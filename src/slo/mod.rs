@@ -0,0 +1,548 @@
+// slo/mod.rs
+
+use axum::{
+    extract::State,
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, FromQueryResult, QueryFilter, QuerySelect,
+    Set,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+use tracing::{error, info};
+
+use crate::models::slo_bucket::{self, Entity as SloBucket};
+
+/// Latency bounds requests are counted against; latency SLO thresholds must be one of them.
+pub const LATENCY_BOUNDS_MS: [u64; 7] = [50, 100, 250, 500, 1000, 2500, 5000];
+
+/// Short windows burn rates are reported for, with their length in minutes.
+const BURN_WINDOWS: [(&str, i64); 4] = [("5m", 5), ("30m", 30), ("1h", 60), ("6h", 360)];
+
+/// Burn rate over both 1h and 5m that exhausts a 30-day budget in about two days.
+const FAST_BURN: f64 = 14.4;
+
+/// Burn rate over both 6h and 30m that exhausts a 30-day budget in five days.
+const SLOW_BURN: f64 = 6.0;
+
+/// How often old buckets are deleted.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// What an SLO measures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SloKind {
+    /// Share of requests not answered with a 5xx.
+    Availability,
+    /// Share of requests answered within `threshold_ms`, e.g. a p99 target of 500 ms is
+    /// `threshold_ms = 500` with an objective of 0.99.
+    Latency { threshold_ms: u64 },
+}
+
+/// One objective on one route group.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SloDefinition {
+    pub name: String,
+    pub route_group: String,
+    #[serde(flatten)]
+    pub kind: SloKind,
+    /// Target share of good requests, e.g. 0.999.
+    pub objective: f64,
+    /// Rolling window the error budget applies to (default: 30 days).
+    #[serde(default = "default_window_days")]
+    pub window_days: u32,
+}
+
+fn default_window_days() -> u32 {
+    30
+}
+
+/// SLO definitions and collection settings, loaded from the `slo` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SloConfig {
+    /// Records request outcomes and flushes them to `slo_buckets` (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Path prefixes of each route group; requests outside all groups aren't recorded.
+    #[serde(default = "default_route_groups")]
+    pub route_groups: BTreeMap<String, Vec<String>>,
+
+    #[serde(default = "default_objectives")]
+    pub objectives: Vec<SloDefinition>,
+
+    /// How often counts are written to the database (default: 60 s).
+    #[serde(default = "default_flush_interval_secs")]
+    pub flush_interval_secs: u64,
+}
+
+fn default_route_groups() -> BTreeMap<String, Vec<String>> {
+    let group = |prefixes: &[&str]| prefixes.iter().map(|prefix| prefix.to_string()).collect();
+    BTreeMap::from([
+        ("orders".to_string(), group(&["/orders"])),
+        ("checkout".to_string(), group(&["/api/v1/checkout"])),
+        ("inventory".to_string(), group(&["/inventory", "/api/v1/inventory"])),
+        ("products".to_string(), group(&["/api/v1/products"])),
+    ])
+}
+
+fn default_objectives() -> Vec<SloDefinition> {
+    let slo = |name: &str, route_group: &str, kind, objective| SloDefinition {
+        name: name.to_string(),
+        route_group: route_group.to_string(),
+        kind,
+        objective,
+        window_days: default_window_days(),
+    };
+    vec![
+        slo("checkout-availability", "checkout", SloKind::Availability, 0.999),
+        slo("checkout-latency", "checkout", SloKind::Latency { threshold_ms: 500 }, 0.99),
+        slo("orders-availability", "orders", SloKind::Availability, 0.999),
+        slo("orders-latency", "orders", SloKind::Latency { threshold_ms: 1000 }, 0.99),
+    ]
+}
+
+fn default_flush_interval_secs() -> u64 {
+    60
+}
+
+impl Default for SloConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            route_groups: default_route_groups(),
+            objectives: default_objectives(),
+            flush_interval_secs: default_flush_interval_secs(),
+        }
+    }
+}
+
+impl SloConfig {
+    /// Rejects objectives that cannot be evaluated.
+    pub fn check(&self) -> Result<(), SloError> {
+        let mut names = BTreeSet::new();
+        for slo in &self.objectives {
+            if !names.insert(slo.name.as_str()) {
+                return Err(SloError::Config(format!("duplicate SLO name {}", slo.name)));
+            }
+            if !self.route_groups.contains_key(&slo.route_group) {
+                return Err(SloError::Config(format!("SLO {} uses unknown route group {}", slo.name, slo.route_group)));
+            }
+            if !(slo.objective > 0.0 && slo.objective < 1.0) {
+                return Err(SloError::Config(format!("SLO {} needs an objective between 0 and 1", slo.name)));
+            }
+            if slo.window_days == 0 {
+                return Err(SloError::Config(format!("SLO {} needs a window of at least a day", slo.name)));
+            }
+            if let SloKind::Latency { threshold_ms } = slo.kind {
+                if !LATENCY_BOUNDS_MS.contains(&threshold_ms) {
+                    return Err(SloError::Config(format!(
+                        "SLO {} threshold must be one of {:?} ms",
+                        slo.name, LATENCY_BOUNDS_MS
+                    )));
+                }
+            }
+        }
+        if self.flush_interval_secs == 0 {
+            return Err(SloError::Config("flush_interval_secs must be positive".to_string()));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum SloError {
+    #[error("Invalid slo config: {0}")]
+    Config(String),
+
+    #[error("SLO not found: {0}")]
+    NotFound(String),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for SloError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            SloError::Config(_) => (StatusCode::INTERNAL_SERVER_ERROR, "slo_misconfigured"),
+            SloError::NotFound(_) => (StatusCode::NOT_FOUND, "slo_not_found"),
+            SloError::Database(e) => {
+                error!("SLO query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "slo_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// Request counts of one route group since the last flush.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub requests: u64,
+    pub errors: u64,
+    /// Requests within each of `LATENCY_BOUNDS_MS`.
+    pub within: [u64; LATENCY_BOUNDS_MS.len()],
+}
+
+impl Counts {
+    pub fn record(&mut self, status: StatusCode, latency: Duration) {
+        self.requests += 1;
+        if status.is_server_error() {
+            self.errors += 1;
+        }
+        let millis = latency.as_millis();
+        for (bound, within) in LATENCY_BOUNDS_MS.iter().zip(self.within.iter_mut()) {
+            if millis <= u128::from(*bound) {
+                *within += 1;
+            }
+        }
+    }
+}
+
+/// Summed bucket counts of one route group over a window.
+#[derive(Debug, Clone, Default, FromQueryResult)]
+pub struct WindowTotals {
+    pub route_group: String,
+    pub requests: i64,
+    pub errors: i64,
+    pub le_50ms: i64,
+    pub le_100ms: i64,
+    pub le_250ms: i64,
+    pub le_500ms: i64,
+    pub le_1000ms: i64,
+    pub le_2500ms: i64,
+    pub le_5000ms: i64,
+}
+
+impl WindowTotals {
+    fn within(&self, threshold_ms: u64) -> i64 {
+        match threshold_ms {
+            50 => self.le_50ms,
+            100 => self.le_100ms,
+            250 => self.le_250ms,
+            500 => self.le_500ms,
+            1000 => self.le_1000ms,
+            2500 => self.le_2500ms,
+            _ => self.le_5000ms,
+        }
+    }
+
+    /// Requests that count against the SLO.
+    pub fn bad(&self, kind: SloKind) -> i64 {
+        match kind {
+            SloKind::Availability => self.errors,
+            SloKind::Latency { threshold_ms } => self.requests - self.within(threshold_ms),
+        }
+    }
+}
+
+/// How fast the error budget is being spent: 1.0 spends exactly the budget over the
+/// SLO window. None without traffic.
+pub fn burn_rate(bad: i64, requests: i64, objective: f64) -> Option<f64> {
+    (requests > 0).then(|| (bad as f64 / requests as f64) / (1.0 - objective))
+}
+
+/// Multi-window burn-rate alert, as paged on by on-call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BurnAlert {
+    FastBurn,
+    SlowBurn,
+}
+
+pub fn burn_alert(burn_rates: &BTreeMap<String, Option<f64>>) -> Option<BurnAlert> {
+    let over = |window: &str, limit: f64| burn_rates.get(window).copied().flatten().is_some_and(|rate| rate > limit);
+    if over("1h", FAST_BURN) && over("5m", FAST_BURN) {
+        Some(BurnAlert::FastBurn)
+    } else if over("6h", SLOW_BURN) && over("30m", SLOW_BURN) {
+        Some(BurnAlert::SlowBurn)
+    } else {
+        None
+    }
+}
+
+/// Budget state of one SLO.
+#[derive(Debug, Clone, Serialize)]
+pub struct SloStatus {
+    #[serde(flatten)]
+    pub definition: SloDefinition,
+    /// Requests in the SLO window.
+    pub requests: i64,
+    pub bad_requests: i64,
+    /// Share of good requests in the window; None without traffic.
+    pub sli: Option<f64>,
+    /// Share of the window's error budget left; negative once it is overspent.
+    pub budget_remaining: Option<f64>,
+    /// Burn rate per short window, and over the whole SLO window as `window`.
+    pub burn_rates: BTreeMap<String, Option<f64>>,
+    pub alert: Option<BurnAlert>,
+    pub evaluated_at: DateTime<Utc>,
+}
+
+fn under(path: &str, prefix: &str) -> bool {
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+}
+
+struct Pending {
+    since: DateTime<Utc>,
+    counts: HashMap<String, Counts>,
+}
+
+/// Records request outcomes per route group, flushes them to `slo_buckets` and
+/// evaluates the configured SLOs from the buckets of all instances.
+pub struct SloTracker {
+    db: Arc<DatabaseConnection>,
+    config: SloConfig,
+    pending: Mutex<Pending>,
+}
+
+impl SloTracker {
+    pub fn new(db: Arc<DatabaseConnection>, config: SloConfig) -> Result<Self, SloError> {
+        config.check()?;
+        Ok(Self {
+            db,
+            config,
+            pending: Mutex::new(Pending { since: Utc::now(), counts: HashMap::new() }),
+        })
+    }
+
+    pub fn route_group(&self, path: &str) -> Option<&str> {
+        self.config
+            .route_groups
+            .iter()
+            .find(|(_, prefixes)| prefixes.iter().any(|prefix| under(path, prefix)))
+            .map(|(group, _)| group.as_str())
+    }
+
+    pub fn record(&self, path: &str, status: StatusCode, latency: Duration) {
+        let Some(group) = self.route_group(path) else { return };
+        let mut pending = self.pending.lock().expect("SLO counts lock poisoned");
+        pending.counts.entry(group.to_string()).or_default().record(status, latency);
+    }
+
+    /// Writes the counts collected since the last flush as one bucket per route group.
+    pub async fn flush(&self) -> Result<usize, DbErr> {
+        let (since, counts) = {
+            let mut pending = self.pending.lock().expect("SLO counts lock poisoned");
+            let counts = std::mem::take(&mut pending.counts);
+            (std::mem::replace(&mut pending.since, Utc::now()), counts)
+        };
+        if counts.is_empty() {
+            return Ok(0);
+        }
+        let rows: Vec<slo_bucket::ActiveModel> = counts
+            .into_iter()
+            .map(|(group, counts)| {
+                let within = |i: usize| Set(counts.within[i] as i64);
+                slo_bucket::ActiveModel {
+                    route_group: Set(group),
+                    bucket_start: Set(since),
+                    requests: Set(counts.requests as i64),
+                    errors: Set(counts.errors as i64),
+                    le_50ms: within(0),
+                    le_100ms: within(1),
+                    le_250ms: within(2),
+                    le_500ms: within(3),
+                    le_1000ms: within(4),
+                    le_2500ms: within(5),
+                    le_5000ms: within(6),
+                    ..Default::default()
+                }
+            })
+            .collect();
+        let written = rows.len();
+        SloBucket::insert_many(rows).exec(self.db.as_ref()).await?;
+        Ok(written)
+    }
+
+    /// Deletes buckets older than the longest SLO window.
+    pub async fn prune(&self) -> Result<u64, DbErr> {
+        let days = self.config.objectives.iter().map(|slo| slo.window_days).max().unwrap_or(default_window_days());
+        let cutoff = Utc::now() - ChronoDuration::days(i64::from(days) + 1);
+        let result = SloBucket::delete_many()
+            .filter(slo_bucket::Column::BucketStart.lt(cutoff))
+            .exec(self.db.as_ref())
+            .await?;
+        Ok(result.rows_affected)
+    }
+
+    /// Bucket totals per route group since `since`.
+    async fn totals(&self, since: DateTime<Utc>) -> Result<HashMap<String, WindowTotals>, DbErr> {
+        // Postgres sums bigints to numeric, hence the casts
+        let sum = |column: &str| Expr::cust(format!("CAST(SUM({}) AS BIGINT)", column));
+        let rows = SloBucket::find()
+            .select_only()
+            .column(slo_bucket::Column::RouteGroup)
+            .column_as(sum("requests"), "requests")
+            .column_as(sum("errors"), "errors")
+            .column_as(sum("le_50ms"), "le_50ms")
+            .column_as(sum("le_100ms"), "le_100ms")
+            .column_as(sum("le_250ms"), "le_250ms")
+            .column_as(sum("le_500ms"), "le_500ms")
+            .column_as(sum("le_1000ms"), "le_1000ms")
+            .column_as(sum("le_2500ms"), "le_2500ms")
+            .column_as(sum("le_5000ms"), "le_5000ms")
+            .filter(slo_bucket::Column::BucketStart.gte(since))
+            .group_by(slo_bucket::Column::RouteGroup)
+            .into_model::<WindowTotals>()
+            .all(self.db.as_ref())
+            .await?;
+        Ok(rows.into_iter().map(|row| (row.route_group.clone(), row)).collect())
+    }
+
+    /// Budget status of every configured SLO. Counts not yet flushed are not included.
+    pub async fn evaluate(&self) -> Result<Vec<SloStatus>, SloError> {
+        let now = Utc::now();
+        let mut windows: BTreeMap<String, HashMap<String, WindowTotals>> = BTreeMap::new();
+        for (name, minutes) in BURN_WINDOWS {
+            windows.insert(name.to_string(), self.totals(now - ChronoDuration::minutes(minutes)).await?);
+        }
+        let mut slo_windows: BTreeMap<u32, HashMap<String, WindowTotals>> = BTreeMap::new();
+        for days in self.config.objectives.iter().map(|slo| slo.window_days).collect::<BTreeSet<_>>() {
+            slo_windows.insert(days, self.totals(now - ChronoDuration::days(i64::from(days))).await?);
+        }
+
+        Ok(self
+            .config
+            .objectives
+            .iter()
+            .map(|slo| {
+                let rate = |totals: Option<&WindowTotals>| {
+                    totals.and_then(|t| burn_rate(t.bad(slo.kind), t.requests, slo.objective))
+                };
+                let full = slo_windows.get(&slo.window_days).and_then(|totals| totals.get(&slo.route_group));
+                let mut burn_rates: BTreeMap<String, Option<f64>> = windows
+                    .iter()
+                    .map(|(window, totals)| (window.clone(), rate(totals.get(&slo.route_group))))
+                    .collect();
+                let window_rate = rate(full);
+                burn_rates.insert("window".to_string(), window_rate);
+                let requests = full.map_or(0, |t| t.requests);
+                let bad_requests = full.map_or(0, |t| t.bad(slo.kind));
+                SloStatus {
+                    definition: slo.clone(),
+                    requests,
+                    bad_requests,
+                    sli: (requests > 0).then(|| 1.0 - bad_requests as f64 / requests as f64),
+                    budget_remaining: window_rate.map(|rate| 1.0 - rate),
+                    alert: burn_alert(&burn_rates),
+                    burn_rates,
+                    evaluated_at: now,
+                }
+            })
+            .collect())
+    }
+
+    pub async fn evaluate_one(&self, name: &str) -> Result<SloStatus, SloError> {
+        self.evaluate()
+            .await?
+            .into_iter()
+            .find(|status| status.definition.name == name)
+            .ok_or_else(|| SloError::NotFound(name.to_string()))
+    }
+}
+
+/// Middleware recording the status and latency of requests in a route group.
+pub async fn slo_middleware<B>(State(tracker): State<Arc<SloTracker>>, req: Request<B>, next: Next<B>) -> Response {
+    let path = req.uri().path().to_string();
+    let started = Instant::now();
+    let response = next.run(req).await;
+    tracker.record(&path, response.status(), started.elapsed());
+    response
+}
+
+/// Flushes counts every `flush_interval_secs` and prunes old buckets hourly.
+pub fn spawn_flusher(tracker: Arc<SloTracker>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(tracker.config.flush_interval_secs));
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        ticker.tick().await;
+        let mut last_prune: Option<Instant> = None;
+        loop {
+            ticker.tick().await;
+            if let Err(e) = tracker.flush().await {
+                error!("SLO bucket flush failed: {}", e);
+            }
+            if last_prune.map_or(true, |at| at.elapsed() >= PRUNE_INTERVAL) {
+                match tracker.prune().await {
+                    Ok(0) => {}
+                    Ok(deleted) => info!(buckets = deleted, "Old SLO buckets pruned"),
+                    Err(e) => error!("SLO bucket pruning failed: {}", e),
+                }
+                last_prune = Some(Instant::now());
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_are_cumulative() {
+        let mut counts = Counts::default();
+        counts.record(StatusCode::OK, Duration::from_millis(80));
+        counts.record(StatusCode::BAD_GATEWAY, Duration::from_millis(3000));
+        assert_eq!(counts.requests, 2);
+        assert_eq!(counts.errors, 1);
+        assert_eq!(counts.within, [0, 1, 1, 1, 1, 1, 2]);
+    }
+
+    #[test]
+    fn test_burn_rate_and_alerts() {
+        assert_eq!(burn_rate(0, 0, 0.999), None);
+        let rate = burn_rate(2, 1000, 0.999).unwrap();
+        assert!((rate - 2.0).abs() < 1e-9);
+
+        let rates = |fast: f64, slow: f64| {
+            BTreeMap::from([
+                ("5m".to_string(), Some(fast)),
+                ("1h".to_string(), Some(fast)),
+                ("30m".to_string(), Some(slow)),
+                ("6h".to_string(), Some(slow)),
+            ])
+        };
+        assert_eq!(burn_alert(&rates(20.0, 20.0)), Some(BurnAlert::FastBurn));
+        assert_eq!(burn_alert(&rates(1.0, 8.0)), Some(BurnAlert::SlowBurn));
+        assert_eq!(burn_alert(&rates(1.0, 1.0)), None);
+    }
+
+    #[test]
+    fn test_latency_slo_counts_slow_requests_as_bad() {
+        let totals = WindowTotals { requests: 100, errors: 3, le_500ms: 95, ..Default::default() };
+        assert_eq!(totals.bad(SloKind::Availability), 3);
+        assert_eq!(totals.bad(SloKind::Latency { threshold_ms: 500 }), 5);
+    }
+
+    #[test]
+    fn test_config_check() {
+        assert!(SloConfig::default().check().is_ok());
+        let mut config = SloConfig::default();
+        config.objectives[1].kind = SloKind::Latency { threshold_ms: 300 };
+        assert!(config.check().is_err());
+        let mut config = SloConfig::default();
+        config.objectives[0].route_group = "missing".to_string();
+        assert!(config.check().is_err());
+    }
+
+    #[test]
+    fn test_route_groups_match_whole_segments() {
+        let tracker = SloTracker::new(Arc::new(DatabaseConnection::Disconnected), SloConfig::default()).unwrap();
+        assert_eq!(tracker.route_group("/api/v1/checkout/sessions/1"), Some("checkout"));
+        assert_eq!(tracker.route_group("/orders_archive"), None);
+    }
+}
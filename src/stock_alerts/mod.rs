@@ -0,0 +1,489 @@
+// stock_alerts/mod.rs
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use lazy_static::lazy_static;
+use prometheus::IntCounter;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr, EntityTrait, FromQueryResult, QueryFilter,
+    QueryOrder, QuerySelect, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
+use thiserror::Error;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::db::dialect;
+use crate::i18n::Locale;
+use crate::models::inventory_items;
+use crate::models::stock_alert_state::{self, Entity as StockAlertState};
+use crate::models::stock_alert_subscription::{self, Entity as StockAlertSubscription};
+use crate::models::stock_alert_threshold::{self, Entity as StockAlertThreshold};
+use crate::notifications::{create_low_stock_notification, NotificationService};
+
+/// Advisory lock replicas take turns on, so each drop is alerted by one of them.
+const MONITOR_LOCK: &str = "stock_alert_monitor";
+
+lazy_static! {
+    static ref LOW_STOCK_ALERTS: IntCounter =
+        IntCounter::new("low_stock_alerts_total", "Total number of low-stock alerts sent")
+            .expect("metric can be created");
+}
+
+/// Low-stock alerting settings, loaded from the `stock_alerts` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct StockAlertsConfig {
+    /// Checks stock against thresholds and notifies subscribers (default: false).
+    /// Thresholds and subscriptions can be managed either way.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Time between checks (default: 300 s).
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+}
+
+fn default_interval_secs() -> u64 {
+    300
+}
+
+impl Default for StockAlertsConfig {
+    fn default() -> Self {
+        Self { enabled: false, interval_secs: default_interval_secs() }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum StockAlertError {
+    #[error("Not found: {0}")]
+    NotFound(Uuid),
+
+    #[error("Invalid stock alert: {0}")]
+    Invalid(String),
+
+    #[error("Missing permission: {0}")]
+    Forbidden(&'static str),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for StockAlertError {
+    fn into_response(self) -> Response {
+        let (status, code) = match &self {
+            StockAlertError::NotFound(_) => (StatusCode::NOT_FOUND, "stock_alert_not_found"),
+            StockAlertError::Invalid(_) => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_stock_alert"),
+            StockAlertError::Forbidden(_) => (StatusCode::FORBIDDEN, "forbidden"),
+            StockAlertError::Database(e) => {
+                error!("Stock alert query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "stock_alert_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SetThreshold {
+    pub sku: String,
+
+    /// Omit for the SKU's default across warehouses.
+    #[serde(default)]
+    pub warehouse: Option<i32>,
+
+    pub threshold: i32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewSubscription {
+    /// A SKU, or a prefix ending in `*`.
+    pub sku_pattern: String,
+
+    #[serde(default)]
+    pub warehouse: Option<i32>,
+
+    /// Subscribes another user; requires `inventory:write`. Defaults to the caller.
+    #[serde(default)]
+    pub user_id: Option<i32>,
+
+    #[serde(default)]
+    pub locale: Option<String>,
+}
+
+/// Whether a subscription's pattern covers `sku`: an exact SKU, or a prefix ending in `*`.
+pub fn sku_matches(pattern: &str, sku: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => sku.starts_with(prefix),
+        None => pattern == sku,
+    }
+}
+
+/// Thresholds by SKU, then warehouse (`None` for the SKU's default).
+pub type Thresholds = HashMap<String, HashMap<Option<i32>, i32>>;
+
+/// The threshold for a SKU in a warehouse: the warehouse's own, else the SKU's default.
+pub fn threshold_for(thresholds: &Thresholds, sku: &str, warehouse: i32) -> Option<i32> {
+    let by_warehouse = thresholds.get(sku)?;
+    by_warehouse.get(&Some(warehouse)).or_else(|| by_warehouse.get(&None)).copied()
+}
+
+#[derive(Debug, Clone, PartialEq, FromQueryResult)]
+struct StockRow {
+    sku: String,
+    warehouse: i32,
+    available: i32,
+    reserved_quantity: Option<i32>,
+}
+
+/// A SKU in a warehouse with sellable stock at or below its threshold.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LowStock {
+    pub sku: String,
+    pub warehouse: i32,
+    pub sellable: i32,
+    pub threshold: i32,
+}
+
+/// Locations that are low now but were not in `flagged`.
+pub fn newly_low(flagged: &HashSet<(String, i32)>, low: &[LowStock]) -> Vec<LowStock> {
+    low.iter()
+        .filter(|row| !flagged.contains(&(row.sku.clone(), row.warehouse)))
+        .cloned()
+        .collect()
+}
+
+/// Locations in `flagged` that are no longer low; they alert again when they next drop.
+pub fn recovered(flagged: &HashSet<(String, i32)>, low: &[LowStock]) -> Vec<(String, i32)> {
+    let low: HashSet<(&str, i32)> = low.iter().map(|row| (row.sku.as_str(), row.warehouse)).collect();
+    flagged
+        .iter()
+        .filter(|(sku, warehouse)| !low.contains(&(sku.as_str(), *warehouse)))
+        .cloned()
+        .collect()
+}
+
+/// Manages per-SKU reorder thresholds and who is alerted about which SKUs.
+pub struct StockAlertService {
+    db: Arc<DatabaseConnection>,
+}
+
+impl StockAlertService {
+    pub fn new(db: Arc<DatabaseConnection>) -> Self {
+        Self { db }
+    }
+
+    pub async fn thresholds(&self, sku: Option<&str>) -> Result<Vec<stock_alert_threshold::Model>, StockAlertError> {
+        let mut query = StockAlertThreshold::find();
+        if let Some(sku) = sku {
+            query = query.filter(stock_alert_threshold::Column::Sku.eq(sku));
+        }
+        Ok(query
+            .order_by_asc(stock_alert_threshold::Column::Sku)
+            .order_by_asc(stock_alert_threshold::Column::Warehouse)
+            .all(self.db.as_ref())
+            .await?)
+    }
+
+    /// Creates or replaces the threshold of a SKU in a warehouse, or its default.
+    pub async fn set_threshold(
+        &self,
+        input: SetThreshold,
+        actor: String,
+    ) -> Result<stock_alert_threshold::Model, StockAlertError> {
+        let sku = input.sku.trim().to_string();
+        if sku.is_empty() {
+            return Err(StockAlertError::Invalid("sku is required".to_string()));
+        }
+        if input.threshold < 0 {
+            return Err(StockAlertError::Invalid("threshold must be non-negative".to_string()));
+        }
+        let existing = StockAlertThreshold::find()
+            .filter(stock_alert_threshold::Column::Sku.eq(sku.clone()))
+            .filter(match input.warehouse {
+                Some(warehouse) => stock_alert_threshold::Column::Warehouse.eq(warehouse),
+                None => stock_alert_threshold::Column::Warehouse.is_null(),
+            })
+            .one(self.db.as_ref())
+            .await?;
+        let now = Utc::now();
+        let threshold = match existing {
+            Some(existing) => {
+                let mut threshold: stock_alert_threshold::ActiveModel = existing.into();
+                threshold.threshold = Set(input.threshold);
+                threshold.updated_by = Set(Some(actor));
+                threshold.updated_at = Set(now);
+                threshold.update(self.db.as_ref()).await?
+            }
+            None => {
+                stock_alert_threshold::ActiveModel {
+                    id: Set(Uuid::new_v4()),
+                    sku: Set(sku),
+                    warehouse: Set(input.warehouse),
+                    threshold: Set(input.threshold),
+                    updated_by: Set(Some(actor)),
+                    created_at: Set(now),
+                    updated_at: Set(now),
+                }
+                .insert(self.db.as_ref())
+                .await?
+            }
+        };
+        Ok(threshold)
+    }
+
+    pub async fn delete_threshold(&self, id: Uuid) -> Result<(), StockAlertError> {
+        let result = StockAlertThreshold::delete_by_id(id).exec(self.db.as_ref()).await?;
+        if result.rows_affected == 0 {
+            return Err(StockAlertError::NotFound(id));
+        }
+        Ok(())
+    }
+
+    /// Subscriptions of one user, or of everyone.
+    pub async fn subscriptions(
+        &self,
+        user_id: Option<i32>,
+    ) -> Result<Vec<stock_alert_subscription::Model>, StockAlertError> {
+        let mut query = StockAlertSubscription::find();
+        if let Some(user_id) = user_id {
+            query = query.filter(stock_alert_subscription::Column::UserId.eq(user_id));
+        }
+        Ok(query
+            .order_by_asc(stock_alert_subscription::Column::CreatedAt)
+            .all(self.db.as_ref())
+            .await?)
+    }
+
+    pub async fn subscription(&self, id: Uuid) -> Result<stock_alert_subscription::Model, StockAlertError> {
+        StockAlertSubscription::find_by_id(id)
+            .one(self.db.as_ref())
+            .await?
+            .ok_or(StockAlertError::NotFound(id))
+    }
+
+    pub async fn subscribe(
+        &self,
+        user_id: i32,
+        input: NewSubscription,
+        actor: String,
+    ) -> Result<stock_alert_subscription::Model, StockAlertError> {
+        let pattern = input.sku_pattern.trim().to_string();
+        if pattern.is_empty() || pattern == "*" {
+            return Err(StockAlertError::Invalid("sku_pattern must name a SKU or a SKU prefix".to_string()));
+        }
+        if pattern.strip_suffix('*').unwrap_or(&pattern).contains('*') {
+            return Err(StockAlertError::Invalid("`*` is only allowed at the end of sku_pattern".to_string()));
+        }
+        let locale = match input.locale {
+            Some(tag) => Locale::parse(&tag)
+                .ok_or_else(|| StockAlertError::Invalid(format!("unsupported locale: {}", tag)))?,
+            None => Locale::EnUs,
+        };
+        Ok(stock_alert_subscription::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            user_id: Set(user_id),
+            sku_pattern: Set(pattern),
+            warehouse: Set(input.warehouse),
+            locale: Set(locale.tag().to_string()),
+            created_by: Set(Some(actor)),
+            created_at: Set(Utc::now()),
+        }
+        .insert(self.db.as_ref())
+        .await?)
+    }
+
+    pub async fn unsubscribe(&self, id: Uuid) -> Result<(), StockAlertError> {
+        let result = StockAlertSubscription::delete_by_id(id).exec(self.db.as_ref()).await?;
+        if result.rows_affected == 0 {
+            return Err(StockAlertError::NotFound(id));
+        }
+        Ok(())
+    }
+
+    /// SKUs in warehouses whose sellable stock is at or below their threshold.
+    pub async fn low_stock(&self) -> Result<Vec<LowStock>, StockAlertError> {
+        let mut thresholds = Thresholds::new();
+        for row in StockAlertThreshold::find().all(self.db.as_ref()).await? {
+            thresholds.entry(row.sku).or_default().insert(row.warehouse, row.threshold);
+        }
+        if thresholds.is_empty() {
+            return Ok(Vec::new());
+        }
+        let rows = inventory_items::Entity::find()
+            .select_only()
+            .column(inventory_items::Column::Sku)
+            .column(inventory_items::Column::Warehouse)
+            .column(inventory_items::Column::Available)
+            .column(inventory_items::Column::ReservedQuantity)
+            .filter(inventory_items::Column::Sku.is_in(thresholds.keys().cloned()))
+            .into_model::<StockRow>()
+            .all(self.db.as_ref())
+            .await?;
+
+        // A SKU may be stocked in several items (lots) per warehouse
+        let mut sellable: HashMap<(String, i32), i32> = HashMap::new();
+        for row in rows {
+            *sellable.entry((row.sku, row.warehouse)).or_default() +=
+                row.available - row.reserved_quantity.unwrap_or(0);
+        }
+        let mut low: Vec<LowStock> = sellable
+            .into_iter()
+            .filter_map(|((sku, warehouse), sellable)| {
+                let threshold = threshold_for(&thresholds, &sku, warehouse)?;
+                (sellable <= threshold).then_some(LowStock { sku, warehouse, sellable, threshold })
+            })
+            .collect();
+        low.sort_by(|a, b| (&a.sku, a.warehouse).cmp(&(&b.sku, b.warehouse)));
+        Ok(low)
+    }
+}
+
+/// Notifies subscribers when SKUs run low. Each location is alerted once when it drops
+/// to its threshold and again only after it has recovered in between; alerted locations
+/// are kept in `stock_alert_states`, so restarts and replicas do not repeat them.
+pub struct StockAlertMonitor {
+    service: Arc<StockAlertService>,
+    notifications: Arc<dyn NotificationService>,
+}
+
+impl StockAlertMonitor {
+    pub fn new(service: Arc<StockAlertService>, notifications: Arc<dyn NotificationService>) -> Self {
+        Self { service, notifications }
+    }
+
+    /// Sends alerts for newly low locations and returns how many there were. Replicas
+    /// take turns on an advisory lock held until the alerts are recorded.
+    pub async fn check(&self) -> Result<usize, StockAlertError> {
+        let txn = self.service.db.begin().await?;
+        dialect::advisory_xact_lock(&txn, MONITOR_LOCK).await?;
+
+        let low = self.service.low_stock().await?;
+        let flagged: HashSet<(String, i32)> = StockAlertState::find()
+            .all(&txn)
+            .await?
+            .into_iter()
+            .map(|state| (state.sku, state.warehouse))
+            .collect();
+        let recovered = recovered(&flagged, &low);
+        if !recovered.is_empty() {
+            let condition = recovered.into_iter().fold(Condition::any(), |condition, (sku, warehouse)| {
+                condition.add(
+                    stock_alert_state::Column::Sku
+                        .eq(sku)
+                        .and(stock_alert_state::Column::Warehouse.eq(warehouse)),
+                )
+            });
+            StockAlertState::delete_many().filter(condition).exec(&txn).await?;
+        }
+        let fresh = newly_low(&flagged, &low);
+        if fresh.is_empty() {
+            txn.commit().await?;
+            return Ok(0);
+        }
+        let subscriptions = self.service.subscriptions(None).await?;
+        let mut sent = 0;
+        for row in &fresh {
+            let subscribers = subscriptions.iter().filter(|s| {
+                sku_matches(&s.sku_pattern, &row.sku) && s.warehouse.map_or(true, |w| w == row.warehouse)
+            });
+            for subscription in subscribers {
+                let locale = Locale::parse(&subscription.locale).unwrap_or(Locale::EnUs);
+                let notification = create_low_stock_notification(
+                    subscription.user_id,
+                    &row.sku,
+                    row.warehouse,
+                    row.sellable,
+                    row.threshold,
+                    locale,
+                );
+                match self.notifications.send_notification(notification).await {
+                    Ok(()) => sent += 1,
+                    Err(e) => error!(sku = %row.sku, warehouse = row.warehouse, "Failed to send low-stock alert: {}", e),
+                }
+            }
+        }
+        let now = Utc::now();
+        StockAlertState::insert_many(fresh.iter().map(|row| stock_alert_state::ActiveModel {
+            sku: Set(row.sku.clone()),
+            warehouse: Set(row.warehouse),
+            flagged_at: Set(now),
+        }))
+        .exec(&txn)
+        .await?;
+        txn.commit().await?;
+        LOW_STOCK_ALERTS.inc_by(sent);
+        info!(locations = fresh.len(), alerts = sent, "Low stock alerted");
+        Ok(fresh.len())
+    }
+}
+
+pub fn spawn_monitor(monitor: Arc<StockAlertMonitor>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval.max(Duration::from_secs(1)));
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = monitor.check().await {
+                error!("Low-stock check failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn low(sku: &str, warehouse: i32) -> LowStock {
+        LowStock { sku: sku.to_string(), warehouse, sellable: 3, threshold: 5 }
+    }
+
+    #[test]
+    fn test_sku_patterns() {
+        assert!(sku_matches("APP-100", "APP-100"));
+        assert!(!sku_matches("APP-100", "APP-1000"));
+        assert!(sku_matches("APP-*", "APP-1000"));
+        assert!(!sku_matches("APP-*", "TOY-1"));
+    }
+
+    #[test]
+    fn test_warehouse_threshold_overrides_sku_default() {
+        let mut thresholds = Thresholds::new();
+        thresholds.entry("APP-1".to_string()).or_default().insert(None, 10);
+        thresholds.entry("APP-1".to_string()).or_default().insert(Some(2), 40);
+        assert_eq!(threshold_for(&thresholds, "APP-1", 1), Some(10));
+        assert_eq!(threshold_for(&thresholds, "APP-1", 2), Some(40));
+        assert_eq!(threshold_for(&thresholds, "APP-2", 1), None);
+    }
+
+    #[test]
+    fn test_locations_alert_again_only_after_recovering() {
+        let mut flagged = HashSet::new();
+        assert_eq!(newly_low(&flagged, &[low("A", 1), low("B", 1)]).len(), 2);
+        flagged.extend([("A".to_string(), 1), ("B".to_string(), 1)]);
+        assert!(newly_low(&flagged, &[low("A", 1)]).is_empty());
+        assert_eq!(recovered(&flagged, &[low("A", 1)]), vec![("B".to_string(), 1)]);
+        // B recovered and dropped again
+        flagged.remove(&("B".to_string(), 1));
+        assert_eq!(newly_low(&flagged, &[low("A", 1), low("B", 1)]), vec![low("B", 1)]);
+    }
+
+    #[test]
+    fn test_migration_is_safe() {
+        let plan = crate::db::migration_safety::analyze(
+            "20261016230000_stock_alert_states.sql",
+            include_str!("../../migrations/20261016230000_stock_alert_states.sql"),
+        );
+        assert_eq!(plan.violations().count(), 0);
+    }
+}
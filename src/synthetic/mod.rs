@@ -0,0 +1,415 @@
+// synthetic/mod.rs
+
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec};
+use sea_orm::{ConnectionTrait, DatabaseConnection, EntityTrait, TransactionTrait};
+use serde::{Deserialize, Serialize};
+use std::{
+    future::Future,
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+use tokio::sync::broadcast;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::commands::inventory::release_inventory_command::{ReleaseInventoryCommand, ReleaseRequest};
+use crate::commands::inventory::reserve_inventory_command::{
+    ReservationRequest, ReservationStrategy, ReservationType,
+};
+use crate::commands::inventory::ReserveInventoryCommand;
+use crate::commands::orders::cancel_order_command::CancelOrderCommand;
+use crate::commands::orders::create_order_command::OrderItem;
+use crate::commands::orders::CreateOrderCommand;
+use crate::commands::Command;
+use crate::db::dialect;
+use crate::events::{Event, EventSender};
+use crate::models::order_entity::Entity as Order;
+
+lazy_static! {
+    static ref SYNTHETIC_RUNS: IntCounterVec =
+        IntCounterVec::new(
+            "synthetic_check_runs_total",
+            "Total number of synthetic order checks by outcome",
+            &["outcome"]
+        ).expect("metric can be created");
+
+    static ref SYNTHETIC_STEP_SECONDS: HistogramVec =
+        HistogramVec::new(
+            HistogramOpts::new("synthetic_check_step_seconds", "Duration of each synthetic check step")
+                .buckets(vec![0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]),
+            &["step"]
+        ).expect("metric can be created");
+}
+
+/// Synthetic transaction settings, loaded from the `synthetic` section of the config.
+/// Runs use a dedicated customer, warehouse and product so they never touch real stock,
+/// and the rows a run writes are purged once it finishes.
+#[derive(Clone, Debug, Deserialize)]
+pub struct SyntheticConfig {
+    /// Runs the check on a schedule (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Time between runs (default: 60 s).
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+
+    /// A run taking longer than this fails (default: 30 s).
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+
+    /// Consecutive failed runs before `/health/synthetic` reports failing (default: 2).
+    #[serde(default = "default_failure_threshold")]
+    pub failure_threshold: u32,
+
+    /// Customer synthetic orders are placed for. Every order it has is deleted after each
+    /// run, so it must never be a real customer.
+    #[serde(default = "default_customer_id")]
+    pub customer_id: Uuid,
+
+    /// Warehouse holding the synthetic product's stock (default: `SYNTHETIC`).
+    #[serde(default = "default_warehouse_id")]
+    pub warehouse_id: String,
+
+    #[serde(default = "default_product_id")]
+    pub product_id: Uuid,
+}
+
+fn default_interval_secs() -> u64 {
+    60
+}
+
+fn default_timeout_secs() -> u64 {
+    30
+}
+
+fn default_failure_threshold() -> u32 {
+    2
+}
+
+fn default_customer_id() -> Uuid {
+    Uuid::from_u128(0x5e7_0000_0000_0000_0000_0000_0000_0001)
+}
+
+fn default_warehouse_id() -> String {
+    "SYNTHETIC".to_string()
+}
+
+fn default_product_id() -> Uuid {
+    Uuid::from_u128(0x5e7_0000_0000_0000_0000_0000_0000_0002)
+}
+
+impl Default for SyntheticConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: default_interval_secs(),
+            timeout_secs: default_timeout_secs(),
+            failure_threshold: default_failure_threshold(),
+            customer_id: default_customer_id(),
+            warehouse_id: default_warehouse_id(),
+            product_id: default_product_id(),
+        }
+    }
+}
+
+/// Stock kept for the synthetic product; every run releases what it reserved.
+const SYNTHETIC_STOCK: i32 = 1_000_000;
+
+/// Deletes everything written for the synthetic customer's orders (`$1`), children first,
+/// so probes never show up in reports, lists or exports.
+const PURGE_SQL: &[&str] = &[
+    "DELETE FROM inventory_reservations WHERE reference_type = 'SALES_ORDER' \
+     AND reference_id IN (SELECT id FROM orders WHERE customer_id = $1)",
+    "DELETE FROM order_events WHERE order_id IN (SELECT id FROM orders WHERE customer_id = $1)",
+    "DELETE FROM order_notes WHERE order_id IN (SELECT id FROM orders WHERE customer_id = $1)",
+    "DELETE FROM order_line_items WHERE order_id IN (SELECT id FROM orders WHERE customer_id = $1)",
+    "DELETE FROM orders WHERE customer_id = $1",
+];
+
+#[derive(Error, Debug)]
+pub enum SyntheticError {
+    #[error("Step {step} failed: {message}")]
+    Step { step: &'static str, message: String },
+
+    #[error("Run timed out after {0:?}")]
+    Timeout(Duration),
+}
+
+/// Whether the order flow works end to end, as reported by `/health/synthetic`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyntheticHealth {
+    /// No run has finished yet.
+    Unknown,
+    Passing,
+    /// `failure_threshold` or more runs in a row failed.
+    Failing,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SyntheticStatus {
+    pub status: SyntheticHealth,
+    pub consecutive_failures: u32,
+    pub last_run_at: Option<DateTime<Utc>>,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub last_duration_ms: Option<u64>,
+}
+
+impl SyntheticStatus {
+    fn new() -> Self {
+        Self {
+            status: SyntheticHealth::Unknown,
+            consecutive_failures: 0,
+            last_run_at: None,
+            last_success_at: None,
+            last_error: None,
+            last_duration_ms: None,
+        }
+    }
+
+    /// Folds one run's outcome into the status.
+    pub fn record(&mut self, outcome: Result<(), String>, duration: Duration, failure_threshold: u32) {
+        let now = Utc::now();
+        self.last_run_at = Some(now);
+        self.last_duration_ms = Some(duration.as_millis() as u64);
+        match outcome {
+            Ok(()) => {
+                self.consecutive_failures = 0;
+                self.last_success_at = Some(now);
+                self.last_error = None;
+                self.status = SyntheticHealth::Passing;
+            }
+            Err(e) => {
+                self.consecutive_failures += 1;
+                self.last_error = Some(e);
+                // Fewer failures keep the previous status, so one flaky run doesn't page
+                if self.consecutive_failures >= failure_threshold.max(1) {
+                    self.status = SyntheticHealth::Failing;
+                }
+            }
+        }
+    }
+}
+
+async fn timed<T, E, F>(step: &'static str, future: F) -> Result<T, SyntheticError>
+where
+    E: std::fmt::Display,
+    F: Future<Output = Result<T, E>>,
+{
+    let started = Instant::now();
+    let result = future.await;
+    SYNTHETIC_STEP_SECONDS.with_label_values(&[step]).observe(started.elapsed().as_secs_f64());
+    result.map_err(|e| SyntheticError::Step { step, message: e.to_string() })
+}
+
+/// Places an order, reserves stock for it, releases the stock and cancels the order,
+/// through the same commands the API uses.
+pub struct SyntheticChecker {
+    db: Arc<DatabaseConnection>,
+    config: SyntheticConfig,
+    events: Arc<EventSender>,
+    // Kept so sends succeed; nothing reads it, so synthetic orders never reach
+    // webhooks, notifications or the ledger
+    _events_sink: broadcast::Receiver<Event>,
+    status: RwLock<SyntheticStatus>,
+}
+
+impl SyntheticChecker {
+    pub fn new(db: Arc<DatabaseConnection>, config: SyntheticConfig) -> Self {
+        let (sender, receiver) = broadcast::channel(16);
+        Self {
+            db,
+            config,
+            events: Arc::new(sender),
+            _events_sink: receiver,
+            status: RwLock::new(SyntheticStatus::new()),
+        }
+    }
+
+    pub fn status(&self) -> SyntheticStatus {
+        self.status.read().expect("synthetic status lock poisoned").clone()
+    }
+
+    async fn ensure_stock(&self) -> Result<(), SyntheticError> {
+        let statement = dialect::statement(
+            self.db.as_ref(),
+            "INSERT INTO inventory_levels (id, warehouse_id, product_id, quantity, allocated_quantity, version, last_updated_at) \
+             SELECT $1, $2, $3, $4, 0, 1, $5 \
+             WHERE NOT EXISTS (SELECT 1 FROM inventory_levels WHERE warehouse_id = $2 AND product_id = $3)",
+            [
+                Uuid::new_v4().into(),
+                self.config.warehouse_id.clone().into(),
+                self.config.product_id.into(),
+                SYNTHETIC_STOCK.into(),
+                Utc::now().naive_utc().into(),
+            ],
+        );
+        timed("ensure_stock", self.db.execute(statement)).await.map(|_| ())
+    }
+
+    fn release(&self, order_id: Uuid) -> ReleaseInventoryCommand {
+        ReleaseInventoryCommand {
+            reference_id: order_id,
+            reference_type: "SALES_ORDER".to_string(),
+            reason_code: "SYNTHETIC_CHECK".to_string(),
+            notes: None,
+            releases: vec![ReleaseRequest {
+                reservation_id: None,
+                product_id: Some(self.config.product_id),
+                quantity: None,
+                lot_numbers: None,
+            }],
+        }
+    }
+
+    async fn cancel(&self, order_id: Uuid) -> Result<(), SyntheticError> {
+        let order = timed("load_order", Order::find_by_id(order_id).one(self.db.as_ref()))
+            .await?
+            .ok_or(SyntheticError::Step { step: "load_order", message: format!("order {} vanished", order_id) })?;
+        let command = CancelOrderCommand {
+            order_id,
+            reason: "Synthetic check".to_string(),
+            version: order.version,
+        };
+        timed("cancel_order", command.execute(self.db.clone(), self.events.clone())).await.map(|_| ())
+    }
+
+    async fn reserve_release_cancel(&self, order_id: Uuid) -> Result<(), SyntheticError> {
+        let reserve = ReserveInventoryCommand {
+            warehouse_id: self.config.warehouse_id.clone(),
+            reference_id: order_id,
+            reference_type: "SALES_ORDER".to_string(),
+            items: vec![ReservationRequest {
+                product_id: self.config.product_id,
+                quantity: 1,
+                lot_numbers: None,
+                location_id: None,
+                substitutes: None,
+            }],
+            reservation_type: ReservationType::SalesOrder,
+            duration_days: None,
+            // Expires by itself should cleanup fail
+            ttl_seconds: Some(600),
+            priority: None,
+            notes: None,
+            reservation_strategy: ReservationStrategy::Strict,
+        };
+        let reserved = timed("reserve_inventory", reserve.execute(self.db.clone(), self.events.clone())).await?;
+        if !reserved.fully_reserved {
+            return Err(SyntheticError::Step {
+                step: "reserve_inventory",
+                message: "reservation was only partially filled".to_string(),
+            });
+        }
+        timed("release_inventory", self.release(order_id).execute(self.db.clone(), self.events.clone())).await?;
+        self.cancel(order_id).await
+    }
+
+    /// Deletes the synthetic customer's orders and what hangs off them. Sweeping by
+    /// customer also catches orders of runs that timed out part way.
+    async fn purge(&self) -> Result<(), SyntheticError> {
+        let purge = async {
+            let txn = self.db.begin().await?;
+            for sql in PURGE_SQL {
+                txn.execute(dialect::statement(&txn, sql, [self.config.customer_id.into()])).await?;
+            }
+            txn.commit().await
+        };
+        timed("purge", purge).await
+    }
+
+    async fn run(&self) -> Result<(), SyntheticError> {
+        self.ensure_stock().await?;
+        let create = CreateOrderCommand {
+            customer_id: self.config.customer_id,
+            items: vec![OrderItem { product_id: self.config.product_id, quantity: 1 }],
+            total: None,
+            allow_duplicate: true,
+            placed_by: Some("synthetic-check".to_string()),
+        };
+        let order = timed("create_order", create.execute(self.db.clone(), self.events.clone())).await?;
+        let result = self.reserve_release_cancel(order.id).await;
+        if result.is_err() {
+            // Best effort, so a broken step doesn't leave stock reserved or orders open
+            let _ = self.release(order.id).execute(self.db.clone(), self.events.clone()).await;
+            let _ = self.cancel(order.id).await;
+        }
+        result
+    }
+
+    /// Runs the loop once within the timeout and records the outcome.
+    pub async fn check(&self) -> SyntheticStatus {
+        let started = Instant::now();
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let mut outcome = match tokio::time::timeout(timeout, self.run()).await {
+            Ok(result) => result,
+            Err(_) => Err(SyntheticError::Timeout(timeout)),
+        };
+        // A run whose rows cannot be cleaned up fails, as they would leak into real data
+        if let Err(e) = self.purge().await {
+            outcome = outcome.and(Err(e));
+        }
+        let duration = started.elapsed();
+        SYNTHETIC_STEP_SECONDS.with_label_values(&["total"]).observe(duration.as_secs_f64());
+        SYNTHETIC_RUNS
+            .with_label_values(&[if outcome.is_ok() { "success" } else { "failure" }])
+            .inc();
+        if let Err(e) = &outcome {
+            warn!(duration_ms = duration.as_millis() as u64, "Synthetic order check failed: {}", e);
+        }
+
+        let mut status = self.status.write().expect("synthetic status lock poisoned");
+        let was = status.status;
+        status.record(outcome.map_err(|e| e.to_string()), duration, self.config.failure_threshold);
+        match (was, status.status) {
+            (SyntheticHealth::Failing, SyntheticHealth::Passing) => info!("Synthetic order check recovered"),
+            (previous, SyntheticHealth::Failing) if previous != SyntheticHealth::Failing => {
+                error!(failures = status.consecutive_failures, "Synthetic order check is failing")
+            }
+            _ => {}
+        }
+        status.clone()
+    }
+}
+
+pub fn spawn_scheduler(checker: Arc<SyntheticChecker>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(checker.config.interval_secs.max(1)));
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            checker.check().await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_flips_after_consecutive_failures() {
+        let mut status = SyntheticStatus::new();
+        let run = Duration::from_millis(120);
+        status.record(Err("reserve failed".to_string()), run, 2);
+        assert_eq!(status.status, SyntheticHealth::Unknown);
+        status.record(Ok(()), run, 2);
+        assert_eq!(status.status, SyntheticHealth::Passing);
+
+        status.record(Err("reserve failed".to_string()), run, 2);
+        assert_eq!(status.status, SyntheticHealth::Passing);
+        status.record(Err("reserve failed".to_string()), run, 2);
+        assert_eq!(status.status, SyntheticHealth::Failing);
+        assert_eq!(status.last_error.as_deref(), Some("reserve failed"));
+
+        status.record(Ok(()), run, 2);
+        assert_eq!(status.status, SyntheticHealth::Passing);
+        assert_eq!(status.consecutive_failures, 0);
+        assert_eq!(status.last_duration_ms, Some(120));
+    }
+}
@@ -0,0 +1,811 @@
+// tenant_export/mod.rs
+
+//! Offboarding exports: every row of a tenant's data, decrypted, as JSON lines in a
+//! bundle sealed with a key generated for that export alone. The bundle and a plaintext
+//! manifest of checksums are uploaded side by side and handed out as expiring links.
+
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Nonce,
+};
+use async_trait::async_trait;
+use aws_sdk_s3::{presigning::PresigningConfig, primitives::ByteStream};
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Duration, Utc};
+use sea_orm::{DatabaseConnection, DbErr, FromQueryResult};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::io::{AsyncWrite, AsyncWriteExt, BufWriter};
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::db::dialect;
+use crate::encryption::{self, EncryptionError};
+use crate::jobs::{JobContext, JobError, JobRunner};
+
+pub const EXPORT_JOB_KIND: &str = "tenant_export";
+
+pub const FORMAT: &str = "stateset-tenant-export/1";
+
+pub const ALGORITHM: &str = "AES-256-GCM";
+
+/// Plaintext bytes sealed per chunk of the bundle.
+pub const CHUNK_BYTES: usize = 64 * 1024;
+
+const NONCE_LEN: usize = 12;
+
+/// S3 refuses presigned links valid for longer than a week.
+const MAX_LINK_TTL_HOURS: u64 = 7 * 24;
+
+/// Tenant exports, loaded from the `tenant_export` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TenantExportConfig {
+    /// Bucket bundles are uploaded to; exports are refused without one.
+    #[serde(default)]
+    pub bucket: Option<String>,
+
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+
+    /// How long download links stay valid, capped at 168 (default: 72).
+    #[serde(default = "default_link_ttl_hours")]
+    pub link_ttl_hours: u64,
+
+    /// Column naming the tenant a row belongs to; every table with it is exported.
+    #[serde(default = "default_tenant_column")]
+    pub tenant_column: String,
+
+    /// Tables never exported, such as the tenant's wrapped data keys.
+    #[serde(default = "default_exclude_tables")]
+    pub exclude_tables: Vec<String>,
+
+    /// `table.column` pairs referencing stored files, listed in the bundle as attachments.
+    #[serde(default = "default_attachment_columns")]
+    pub attachment_columns: Vec<String>,
+
+    #[serde(default = "default_batch_size")]
+    pub batch_size: u64,
+}
+
+fn default_prefix() -> String {
+    "tenant-exports".to_string()
+}
+
+fn default_link_ttl_hours() -> u64 {
+    72
+}
+
+fn default_tenant_column() -> String {
+    "tenant_id".to_string()
+}
+
+fn default_exclude_tables() -> Vec<String> {
+    vec!["tenant_data_keys".to_string()]
+}
+
+fn default_attachment_columns() -> Vec<String> {
+    vec!["agreements.attachments".to_string(), "contacts.photo_url".to_string()]
+}
+
+fn default_batch_size() -> u64 {
+    1000
+}
+
+impl Default for TenantExportConfig {
+    fn default() -> Self {
+        Self {
+            bucket: None,
+            prefix: default_prefix(),
+            link_ttl_hours: default_link_ttl_hours(),
+            tenant_column: default_tenant_column(),
+            exclude_tables: default_exclude_tables(),
+            attachment_columns: default_attachment_columns(),
+            batch_size: default_batch_size(),
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("Tenant export is misconfigured: {0}")]
+    Misconfigured(String),
+
+    #[error("Invalid tenant id: {0:?}")]
+    InvalidTenant(String),
+
+    /// The bundle was tampered with, truncated or opened with the wrong key.
+    #[error("Export bundle is corrupt: {0}")]
+    Corrupt(String),
+
+    #[error("Export storage failed: {0}")]
+    Storage(String),
+
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error(transparent)]
+    Encryption(#[from] EncryptionError),
+
+    #[error(transparent)]
+    Job(#[from] JobError),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for ExportError {
+    fn into_response(self) -> Response {
+        match self {
+            ExportError::Job(e) => return e.into_response(),
+            ExportError::Encryption(e) => return e.into_response(),
+            _ => {}
+        }
+        let (status, code) = match &self {
+            ExportError::Misconfigured(_) => (StatusCode::SERVICE_UNAVAILABLE, "export_misconfigured"),
+            ExportError::InvalidTenant(_) => (StatusCode::BAD_REQUEST, "invalid_tenant"),
+            ExportError::Storage(_) => (StatusCode::BAD_GATEWAY, "export_storage_failed"),
+            ExportError::Corrupt(_) | ExportError::Io(_) => {
+                error!("{}", self);
+                (StatusCode::INTERNAL_SERVER_ERROR, "export_failed")
+            }
+            ExportError::Job(_) | ExportError::Encryption(_) => unreachable!("responds on its own"),
+            ExportError::Database(e) => {
+                error!("Tenant export query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "export_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+/// Tenant ids end up in object keys, so only a conservative alphabet is accepted.
+pub fn validate_tenant(tenant_id: &str) -> Result<(), ExportError> {
+    let valid = !tenant_id.is_empty()
+        && tenant_id.len() <= 128
+        && tenant_id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        && !tenant_id.starts_with('.');
+    if valid {
+        Ok(())
+    } else {
+        Err(ExportError::InvalidTenant(tenant_id.to_string()))
+    }
+}
+
+/// Decrypts every encrypted string in a row, however deeply nested.
+fn open_values(value: Value) -> Result<Value, EncryptionError> {
+    Ok(match value {
+        Value::String(stored) => Value::String(encryption::open(&stored)?),
+        Value::Array(items) => Value::Array(items.into_iter().map(open_values).collect::<Result<_, _>>()?),
+        Value::Object(fields) => Value::Object(
+            fields
+                .into_iter()
+                .map(|(name, value)| Ok((name, open_values(value)?)))
+                .collect::<Result<_, EncryptionError>>()?,
+        ),
+        other => other,
+    })
+}
+
+/// Authenticated data of a chunk: its position, and whether it ends the bundle, so
+/// chunks can be neither reordered nor dropped from the end.
+fn chunk_aad(export_id: Uuid, index: u64, last: bool) -> Vec<u8> {
+    let mut aad = export_id.as_bytes().to_vec();
+    aad.extend_from_slice(&index.to_be_bytes());
+    aad.push(last as u8);
+    aad
+}
+
+fn cipher(key: &[u8]) -> Result<Aes256Gcm, ExportError> {
+    Aes256Gcm::new_from_slice(key).map_err(|_| ExportError::Corrupt("export key is not 256 bits".to_string()))
+}
+
+/// A fresh export key.
+pub fn generate_key() -> Vec<u8> {
+    Aes256Gcm::generate_key(&mut OsRng).to_vec()
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EntityDigest {
+    pub name: String,
+    pub records: u64,
+    /// SHA-256 of the entity's record lines, in bundle order.
+    pub sha256: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttachmentDigest {
+    pub references: u64,
+    pub sha256: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleDigest {
+    /// Size and SHA-256 of the decrypted JSON lines.
+    pub bytes: u64,
+    pub sha256: String,
+    /// Size and SHA-256 of the file as downloaded.
+    pub encrypted_bytes: u64,
+    pub encrypted_sha256: String,
+}
+
+/// Checksums of a sealed bundle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BundleChecksums {
+    pub entities: Vec<EntityDigest>,
+    pub attachments: AttachmentDigest,
+    pub bundle: BundleDigest,
+}
+
+/// A running SHA-256 with the records or bytes it covers.
+#[derive(Default)]
+struct Tally {
+    count: u64,
+    digest: Sha256,
+}
+
+/// Writes bundle lines as a sequence of AES-GCM sealed chunks, each a 4-byte big-endian
+/// length, then the nonce and ciphertext, while keeping the manifest's checksums.
+pub struct BundleWriter<W> {
+    out: W,
+    cipher: Aes256Gcm,
+    export_id: Uuid,
+    buffer: Vec<u8>,
+    index: u64,
+    plaintext: Tally,
+    sealed: Tally,
+    entities: BTreeMap<String, Tally>,
+    attachments: Tally,
+}
+
+impl<W: AsyncWrite + Unpin> BundleWriter<W> {
+    pub fn new(out: W, key: &[u8], export_id: Uuid) -> Result<Self, ExportError> {
+        Ok(Self {
+            out,
+            cipher: cipher(key)?,
+            export_id,
+            buffer: Vec::with_capacity(CHUNK_BYTES * 2),
+            index: 0,
+            plaintext: Tally::default(),
+            sealed: Tally::default(),
+            entities: BTreeMap::new(),
+            attachments: Tally::default(),
+        })
+    }
+
+    async fn line(&mut self, line: &Value) -> Result<Vec<u8>, ExportError> {
+        let mut bytes = serde_json::to_vec(line).map_err(|e| ExportError::Corrupt(e.to_string()))?;
+        bytes.push(b'\n');
+        self.plaintext.digest.update(&bytes);
+        self.plaintext.count += bytes.len() as u64;
+        self.buffer.extend_from_slice(&bytes);
+        // A full chunk is held back until more follows, so the last one is always flagged
+        while self.buffer.len() > CHUNK_BYTES {
+            let rest = self.buffer.split_off(CHUNK_BYTES);
+            let chunk = std::mem::replace(&mut self.buffer, rest);
+            self.seal(&chunk, false).await?;
+        }
+        Ok(bytes)
+    }
+
+    async fn seal(&mut self, chunk: &[u8], last: bool) -> Result<(), ExportError> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let aad = chunk_aad(self.export_id, self.index, last);
+        let sealed = self
+            .cipher
+            .encrypt(&nonce, Payload { msg: chunk, aad: &aad })
+            .expect("AES-GCM encryption does not fail for in-memory buffers");
+        let mut frame = ((NONCE_LEN + sealed.len()) as u32).to_be_bytes().to_vec();
+        frame.extend_from_slice(&nonce);
+        frame.extend_from_slice(&sealed);
+        self.out.write_all(&frame).await?;
+        self.sealed.digest.update(&frame);
+        self.sealed.count += frame.len() as u64;
+        self.index += 1;
+        Ok(())
+    }
+
+    /// The first line, naming the export it belongs to.
+    pub async fn header(&mut self, tenant_id: &str, created_at: DateTime<Utc>) -> Result<(), ExportError> {
+        let line = json!({
+            "type": "header",
+            "format": FORMAT,
+            "export_id": self.export_id,
+            "tenant_id": tenant_id,
+            "created_at": created_at,
+        });
+        self.line(&line).await.map(|_| ())
+    }
+
+    pub async fn record(&mut self, entity: &str, data: Value) -> Result<(), ExportError> {
+        let bytes = self.line(&json!({ "type": "record", "entity": entity, "data": data })).await?;
+        let tally = self.entities.entry(entity.to_string()).or_default();
+        tally.count += 1;
+        tally.digest.update(&bytes);
+        Ok(())
+    }
+
+    /// A stored file the tenant's data refers to; the file itself is not copied.
+    pub async fn attachment(&mut self, entity: &str, column: &str, record_id: &str, reference: &str) -> Result<(), ExportError> {
+        let line = json!({
+            "type": "attachment",
+            "entity": entity,
+            "column": column,
+            "record_id": record_id,
+            "reference": reference,
+        });
+        let bytes = self.line(&line).await?;
+        self.attachments.count += 1;
+        self.attachments.digest.update(&bytes);
+        Ok(())
+    }
+
+    /// Seals what is left as the final chunk and flushes the output.
+    pub async fn finish(mut self) -> Result<BundleChecksums, ExportError> {
+        let rest = std::mem::take(&mut self.buffer);
+        self.seal(&rest, true).await?;
+        self.out.flush().await?;
+        Ok(BundleChecksums {
+            entities: self
+                .entities
+                .into_iter()
+                .map(|(name, tally)| EntityDigest {
+                    name,
+                    records: tally.count,
+                    sha256: hex::encode(tally.digest.finalize()),
+                })
+                .collect(),
+            attachments: AttachmentDigest {
+                references: self.attachments.count,
+                sha256: hex::encode(self.attachments.digest.finalize()),
+            },
+            bundle: BundleDigest {
+                bytes: self.plaintext.count,
+                sha256: hex::encode(self.plaintext.digest.finalize()),
+                encrypted_bytes: self.sealed.count,
+                encrypted_sha256: hex::encode(self.sealed.digest.finalize()),
+            },
+        })
+    }
+}
+
+/// Decrypts a sealed bundle, checking that no chunk was altered, reordered or dropped.
+pub fn open_bundle(key: &[u8], export_id: Uuid, sealed: &[u8]) -> Result<Vec<u8>, ExportError> {
+    let cipher = cipher(key)?;
+    let mut plaintext = Vec::with_capacity(sealed.len());
+    let mut rest = sealed;
+    let mut index = 0u64;
+    while !rest.is_empty() {
+        if rest.len() < 4 {
+            return Err(ExportError::Corrupt("truncated chunk length".to_string()));
+        }
+        let (length, body) = rest.split_at(4);
+        let length = u32::from_be_bytes(length.try_into().expect("four bytes")) as usize;
+        if length <= NONCE_LEN || body.len() < length {
+            return Err(ExportError::Corrupt(format!("chunk {} is truncated", index)));
+        }
+        let (frame, next) = body.split_at(length);
+        let (nonce, ciphertext) = frame.split_at(NONCE_LEN);
+        let last = next.is_empty();
+        let chunk = cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: &chunk_aad(export_id, index, last) })
+            .map_err(|_| ExportError::Corrupt(format!("chunk {} failed authentication", index)))?;
+        plaintext.extend_from_slice(&chunk);
+        rest = next;
+        index += 1;
+    }
+    if index == 0 {
+        return Err(ExportError::Corrupt("bundle is empty".to_string()));
+    }
+    Ok(plaintext)
+}
+
+/// Describes a bundle without revealing its contents; uploaded next to it.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: String,
+    pub export_id: Uuid,
+    pub tenant_id: String,
+    pub created_at: DateTime<Utc>,
+    pub encryption: Value,
+    pub entities: Vec<EntityDigest>,
+    pub attachments: AttachmentDigest,
+    pub bundle: BundleDigest,
+}
+
+impl Manifest {
+    pub fn new(export_id: Uuid, tenant_id: &str, created_at: DateTime<Utc>, checksums: BundleChecksums) -> Self {
+        Self {
+            format: FORMAT.to_string(),
+            export_id,
+            tenant_id: tenant_id.to_string(),
+            created_at,
+            encryption: json!({ "algorithm": ALGORITHM, "chunk_bytes": CHUNK_BYTES }),
+            entities: checksums.entities,
+            attachments: checksums.attachments,
+            bundle: checksums.bundle,
+        }
+    }
+}
+
+/// Where bundles are kept and handed out from.
+#[async_trait]
+pub trait ExportStore: Send + Sync {
+    async fn put_file(&self, key: &str, path: &Path, content_type: &str) -> Result<(), ExportError>;
+    async fn put_bytes(&self, key: &str, body: Vec<u8>, content_type: &str) -> Result<(), ExportError>;
+    /// A download link valid for `ttl`.
+    async fn link(&self, key: &str, ttl: std::time::Duration) -> Result<String, ExportError>;
+}
+
+/// Keeps bundles in S3 and hands out presigned links.
+pub struct S3ExportStore {
+    client: aws_sdk_s3::Client,
+    bucket: String,
+}
+
+impl S3ExportStore {
+    pub async fn new(bucket: String) -> Self {
+        let config = aws_config::load_from_env().await;
+        Self {
+            client: aws_sdk_s3::Client::new(&config),
+            bucket,
+        }
+    }
+}
+
+#[async_trait]
+impl ExportStore for S3ExportStore {
+    async fn put_file(&self, key: &str, path: &Path, content_type: &str) -> Result<(), ExportError> {
+        let body = ByteStream::from_path(path).await.map_err(|e| ExportError::Storage(e.to_string()))?;
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(content_type)
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| ExportError::Storage(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn put_bytes(&self, key: &str, body: Vec<u8>, content_type: &str) -> Result<(), ExportError> {
+        self.client
+            .put_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .content_type(content_type)
+            .body(body.into())
+            .send()
+            .await
+            .map_err(|e| ExportError::Storage(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn link(&self, key: &str, ttl: std::time::Duration) -> Result<String, ExportError> {
+        let presigning = PresigningConfig::expires_in(ttl).map_err(|e| ExportError::Storage(e.to_string()))?;
+        let request = self
+            .client
+            .get_object()
+            .bucket(&self.bucket)
+            .key(key)
+            .presigned(presigning)
+            .await
+            .map_err(|e| ExportError::Storage(e.to_string()))?;
+        Ok(request.uri().to_string())
+    }
+}
+
+/// Result of a finished export job.
+#[derive(Clone, Debug, Serialize)]
+pub struct ExportSummary {
+    pub export_id: Uuid,
+    pub tenant_id: String,
+    pub bundle_url: String,
+    pub manifest_url: String,
+    pub expires_at: DateTime<Utc>,
+    pub manifest: Manifest,
+}
+
+#[derive(FromQueryResult)]
+struct TableName {
+    table_name: String,
+}
+
+#[derive(FromQueryResult)]
+struct ExportRow {
+    position: String,
+    data: Value,
+}
+
+pub struct TenantExportService {
+    db: Arc<DatabaseConnection>,
+    store: Arc<dyn ExportStore>,
+    config: TenantExportConfig,
+}
+
+impl TenantExportService {
+    pub fn new(db: Arc<DatabaseConnection>, store: Arc<dyn ExportStore>, config: TenantExportConfig) -> Self {
+        Self { db, store, config }
+    }
+
+    /// Tables holding tenant rows, in name order.
+    async fn tables(&self) -> Result<Vec<String>, ExportError> {
+        let db = self.db.as_ref();
+        dialect::require_postgres(db, "Tenant export")?;
+        let rows = TableName::find_by_statement(dialect::statement(
+            db,
+            "SELECT DISTINCT c.table_name::text AS table_name FROM information_schema.columns c \
+             JOIN information_schema.tables t ON t.table_schema = c.table_schema AND t.table_name = c.table_name \
+             WHERE c.table_schema = current_schema() AND c.column_name = $1 AND t.table_type = 'BASE TABLE' \
+             ORDER BY 1",
+            [self.config.tenant_column.clone().into()],
+        ))
+        .all(db)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|row| row.table_name)
+            .filter(|table| !self.config.exclude_tables.contains(table))
+            .collect())
+    }
+
+    fn attachment_columns(&self, table: &str) -> Vec<String> {
+        self.config
+            .attachment_columns
+            .iter()
+            .filter_map(|pair| pair.split_once('.'))
+            .filter(|(t, _)| *t == table)
+            .map(|(_, column)| column.to_string())
+            .collect()
+    }
+
+    /// Copies one table's tenant rows into the bundle, in physical order so batches
+    /// resume without an index on the tenant column.
+    async fn export_table<W: AsyncWrite + Unpin>(
+        &self,
+        writer: &mut BundleWriter<W>,
+        table: &str,
+        tenant_id: &str,
+    ) -> Result<u64, ExportError> {
+        let db = self.db.as_ref();
+        let sql = format!(
+            "SELECT ctid::text AS position, to_jsonb(t) AS data FROM \"{}\" t \
+             WHERE \"{}\" = $1 AND ctid > $2::tid ORDER BY ctid LIMIT $3",
+            table.replace('"', "\"\""),
+            self.config.tenant_column.replace('"', "\"\""),
+        );
+        let attachment_columns = self.attachment_columns(table);
+        let mut position = "(0,0)".to_string();
+        let mut exported = 0;
+        loop {
+            let rows = ExportRow::find_by_statement(dialect::statement(
+                db,
+                &sql,
+                [tenant_id.into(), position.clone().into(), (self.config.batch_size.max(1) as i64).into()],
+            ))
+            .all(db)
+            .await?;
+            let Some(last) = rows.last() else { return Ok(exported) };
+            position = last.position.clone();
+            for row in rows {
+                let data = open_values(row.data)?;
+                for column in &attachment_columns {
+                    if let Some(reference) = data.get(column).and_then(Value::as_str).filter(|r| !r.is_empty()) {
+                        let record_id = match data.get("id") {
+                            Some(Value::String(id)) => id.clone(),
+                            Some(id) => id.to_string(),
+                            None => row.position.clone(),
+                        };
+                        writer.attachment(table, column, &record_id, reference).await?;
+                    }
+                }
+                writer.record(table, data).await?;
+                exported += 1;
+            }
+        }
+    }
+
+    /// Writes, seals and uploads the tenant's bundle and manifest under `key`.
+    pub async fn export(
+        &self,
+        export_id: Uuid,
+        tenant_id: &str,
+        key: &[u8],
+        context: Option<&JobContext>,
+    ) -> Result<ExportSummary, ExportError> {
+        validate_tenant(tenant_id)?;
+        let tables = self.tables().await?;
+        let created_at = Utc::now();
+        let path = std::env::temp_dir().join(format!("tenant-export-{}.jsonl.enc", export_id));
+        let sealed = async {
+            let file = tokio::fs::File::create(&path).await?;
+            let mut writer = BundleWriter::new(BufWriter::new(file), key, export_id)?;
+            writer.header(tenant_id, created_at).await?;
+            for (index, table) in tables.iter().enumerate() {
+                let records = self.export_table(&mut writer, table, tenant_id).await?;
+                if let Some(context) = context {
+                    let percent = ((index + 1) * 90 / tables.len().max(1)) as i32;
+                    context.report_progress(percent, format!("{} exported ({} records)", table, records)).await;
+                }
+            }
+            writer.finish().await
+        }
+        .await;
+        let uploaded = match sealed {
+            Ok(checksums) => {
+                let key = self.object_key(tenant_id, export_id, "bundle.jsonl.enc");
+                self.store.put_file(&key, &path, "application/octet-stream").await.map(|_| checksums)
+            }
+            Err(e) => Err(e),
+        };
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            error!(%export_id, "Removing the local export bundle failed: {}", e);
+        }
+        let manifest = Manifest::new(export_id, tenant_id, created_at, uploaded?);
+
+        let manifest_key = self.object_key(tenant_id, export_id, "manifest.json");
+        let body = serde_json::to_vec_pretty(&manifest).map_err(|e| ExportError::Corrupt(e.to_string()))?;
+        self.store.put_bytes(&manifest_key, body, "application/json").await?;
+
+        let hours = self.config.link_ttl_hours.clamp(1, MAX_LINK_TTL_HOURS);
+        let ttl = std::time::Duration::from_secs(hours * 60 * 60);
+        let summary = ExportSummary {
+            export_id,
+            tenant_id: tenant_id.to_string(),
+            bundle_url: self.store.link(&self.object_key(tenant_id, export_id, "bundle.jsonl.enc"), ttl).await?,
+            manifest_url: self.store.link(&manifest_key, ttl).await?,
+            expires_at: Utc::now() + Duration::hours(hours as i64),
+            manifest,
+        };
+        info!(
+            %export_id,
+            tenant_id,
+            entities = summary.manifest.entities.len(),
+            bytes = summary.manifest.bundle.encrypted_bytes,
+            "Tenant export uploaded"
+        );
+        Ok(summary)
+    }
+
+    fn object_key(&self, tenant_id: &str, export_id: Uuid, name: &str) -> String {
+        format!("{}/{}/{}/{}", self.config.prefix.trim_end_matches('/'), tenant_id, export_id, name)
+    }
+}
+
+/// A submitted export. The key is returned once and kept nowhere else.
+#[derive(Debug)]
+pub struct SubmittedExport {
+    pub job_id: Uuid,
+    pub export_id: Uuid,
+    pub key: String,
+}
+
+/// Exports the tenant's data as a job.
+pub async fn submit_export(
+    runner: &JobRunner,
+    service: Arc<TenantExportService>,
+    tenant_id: String,
+    created_by: Option<String>,
+) -> Result<SubmittedExport, ExportError> {
+    validate_tenant(&tenant_id)?;
+    let export_id = Uuid::new_v4();
+    let key = generate_key();
+    let encoded = STANDARD.encode(&key);
+    let job_tenant = tenant_id.clone();
+    let job_id = runner
+        .submit(EXPORT_JOB_KIND, created_by, move |context| async move {
+            let summary = service
+                .export(export_id, &job_tenant, &key, Some(&context))
+                .await
+                .map_err(|e| e.to_string())?;
+            serde_json::to_value(summary).map_err(|e| e.to_string())
+        })
+        .await?;
+    info!(tenant_id, %export_id, %job_id, "Tenant export started");
+    Ok(SubmittedExport { job_id, export_id, key: encoded })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn sealed_bundle(key: &[u8], export_id: Uuid, records: usize) -> (Vec<u8>, BundleChecksums) {
+        let mut out = Vec::new();
+        let mut writer = BundleWriter::new(&mut out, key, export_id).unwrap();
+        writer.header("acme", Utc::now()).await.unwrap();
+        for i in 0..records {
+            writer.record("orders", json!({ "id": i, "note": "x".repeat(100) })).await.unwrap();
+        }
+        writer.attachment("contacts", "photo_url", "7", "s3://photos/7.png").await.unwrap();
+        let checksums = writer.finish().await.unwrap();
+        (out, checksums)
+    }
+
+    #[tokio::test]
+    async fn test_bundle_roundtrip_matches_checksums() {
+        let key = generate_key();
+        let export_id = Uuid::new_v4();
+        // Enough records to span several chunks
+        let (sealed, checksums) = sealed_bundle(&key, export_id, 2000).await;
+        assert!(sealed.len() > CHUNK_BYTES * 2);
+
+        let plaintext = open_bundle(&key, export_id, &sealed).unwrap();
+        assert_eq!(checksums.bundle.bytes, plaintext.len() as u64);
+        assert_eq!(checksums.bundle.sha256, hex::encode(Sha256::digest(&plaintext)));
+        assert_eq!(checksums.bundle.encrypted_sha256, hex::encode(Sha256::digest(&sealed)));
+        assert_eq!(checksums.entities[0].name, "orders");
+        assert_eq!(checksums.entities[0].records, 2000);
+        assert_eq!(checksums.attachments.references, 1);
+
+        let lines: Vec<Value> = plaintext
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+        assert_eq!(lines[0]["type"], "header");
+        assert_eq!(lines[0]["export_id"], json!(export_id));
+        assert_eq!(lines.len(), 2002);
+    }
+
+    #[tokio::test]
+    async fn test_tampered_or_truncated_bundles_are_rejected() {
+        let key = generate_key();
+        let export_id = Uuid::new_v4();
+        let (sealed, _) = sealed_bundle(&key, export_id, 2000).await;
+
+        let mut tampered = sealed.clone();
+        tampered[40] ^= 1;
+        assert!(matches!(open_bundle(&key, export_id, &tampered), Err(ExportError::Corrupt(_))));
+
+        // Dropping the final chunk leaves a bundle of whole, valid chunks
+        let first_frame = 4 + u32::from_be_bytes(sealed[..4].try_into().unwrap()) as usize;
+        assert!(matches!(open_bundle(&key, export_id, &sealed[..first_frame]), Err(ExportError::Corrupt(_))));
+        assert!(matches!(open_bundle(&key, export_id, &sealed[..sealed.len() - 1]), Err(ExportError::Corrupt(_))));
+
+        assert!(matches!(open_bundle(&generate_key(), export_id, &sealed), Err(ExportError::Corrupt(_))));
+        assert!(matches!(open_bundle(&key, Uuid::new_v4(), &sealed), Err(ExportError::Corrupt(_))));
+    }
+
+    #[tokio::test]
+    async fn test_empty_bundle_still_has_a_final_chunk() {
+        let key = generate_key();
+        let export_id = Uuid::new_v4();
+        let mut out = Vec::new();
+        let checksums = BundleWriter::new(&mut out, &key, export_id).unwrap().finish().await.unwrap();
+        assert!(checksums.entities.is_empty());
+        assert_eq!(open_bundle(&key, export_id, &out).unwrap(), Vec::<u8>::new());
+        assert!(matches!(open_bundle(&key, export_id, &[]), Err(ExportError::Corrupt(_))));
+    }
+
+    #[test]
+    fn test_plaintext_values_pass_through() {
+        let row = json!({ "id": 1, "name": "Ada", "tags": ["a", { "b": "c" }], "total": 4.5, "void": null });
+        assert_eq!(open_values(row.clone()).unwrap(), row);
+    }
+
+    #[test]
+    fn test_tenant_ids_are_validated() {
+        assert!(validate_tenant("acme-corp_2.eu").is_ok());
+        for invalid in ["", "../other", "acme/orders", ".hidden", "acme corp"] {
+            assert!(matches!(validate_tenant(invalid), Err(ExportError::InvalidTenant(_))), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_config_defaults() {
+        let config: TenantExportConfig = serde_json::from_value(json!({})).unwrap();
+        assert!(config.bucket.is_none());
+        assert_eq!(config.link_ttl_hours, 72);
+        assert_eq!(config.exclude_tables, vec!["tenant_data_keys".to_string()]);
+    }
+}
@@ -0,0 +1,374 @@
+// tls/mod.rs
+
+use axum::{
+    extract::{connect_info::Connected, ConnectInfo},
+    http::Request,
+    middleware::Next,
+    response::Response,
+};
+use futures::{stream, StreamExt};
+use hyper::server::{
+    accept::{self, Accept},
+    conn::{AddrIncoming, AddrStream},
+};
+use rustls::server::{AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient};
+use rustls::{Certificate, PrivateKey, RootCertStore, ServerConfig};
+use rustls_acme::{caches::DirCache, AcmeConfig};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+use tokio_rustls::{server::TlsStream, TlsAcceptor};
+use tracing::{debug, info, warn};
+
+/// Handshakes in progress at once; accepting pauses beyond this.
+const MAX_PENDING_HANDSHAKES: usize = 256;
+
+/// TLS settings, loaded from the `tls` section of the config. For deployments without a
+/// fronting proxy; behind a load balancer that terminates TLS, leave this disabled.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TlsConfig {
+    /// Serve HTTPS instead of plain HTTP (default: false).
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// PEM certificate chain, leaf first. Either this and `key_path`, or `acme`.
+    pub cert_path: Option<String>,
+
+    /// PEM private key (PKCS#8, RSA or SEC1).
+    pub key_path: Option<String>,
+
+    /// Obtain and renew certificates from an ACME CA via TLS-ALPN-01.
+    pub acme: Option<AcmeSettings>,
+
+    /// Whether clients present certificates (default: off).
+    #[serde(default)]
+    pub client_auth: ClientAuth,
+
+    /// PEM bundle of the CAs client certificates must chain to.
+    pub client_ca_path: Option<String>,
+
+    /// Connections that haven't finished the handshake by then are dropped (default: 10 s).
+    #[serde(default = "default_handshake_timeout_secs")]
+    pub handshake_timeout_secs: u64,
+}
+
+fn default_handshake_timeout_secs() -> u64 {
+    10
+}
+
+impl Default for TlsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cert_path: None,
+            key_path: None,
+            acme: None,
+            client_auth: ClientAuth::default(),
+            client_ca_path: None,
+            handshake_timeout_secs: default_handshake_timeout_secs(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct AcmeSettings {
+    pub domains: Vec<String>,
+    /// e.g. `mailto:ops@example.com`.
+    #[serde(default)]
+    pub contact: Vec<String>,
+    /// Where the account key and certificates are kept between restarts.
+    pub cache_dir: String,
+    /// Use Let's Encrypt production instead of staging (default: false).
+    #[serde(default)]
+    pub production: bool,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientAuth {
+    #[default]
+    Off,
+    /// Verify a certificate when one is presented; other clients authenticate as usual.
+    Optional,
+    /// Refuse the handshake without a certificate from `client_ca_path`.
+    Required,
+}
+
+#[derive(Error, Debug)]
+pub enum TlsError {
+    #[error("Invalid tls config: {0}")]
+    Config(String),
+
+    #[error("Cannot read {path}: {source}")]
+    Read {
+        path: String,
+        #[source]
+        source: io::Error,
+    },
+
+    #[error("TLS setup failed: {0}")]
+    Rustls(#[from] rustls::Error),
+}
+
+fn read_pem(path: &str) -> Result<Vec<rustls_pemfile::Item>, TlsError> {
+    let read_error = |source| TlsError::Read { path: path.to_string(), source };
+    let file = File::open(path).map_err(read_error)?;
+    rustls_pemfile::read_all(&mut BufReader::new(file)).map_err(read_error)
+}
+
+fn certificates(path: &str) -> Result<Vec<Certificate>, TlsError> {
+    let certs: Vec<Certificate> = read_pem(path)?
+        .into_iter()
+        .filter_map(|item| match item {
+            rustls_pemfile::Item::X509Certificate(der) => Some(Certificate(der)),
+            _ => None,
+        })
+        .collect();
+    if certs.is_empty() {
+        return Err(TlsError::Config(format!("No certificates in {}", path)));
+    }
+    Ok(certs)
+}
+
+fn private_key(path: &str) -> Result<PrivateKey, TlsError> {
+    read_pem(path)?
+        .into_iter()
+        .find_map(|item| match item {
+            rustls_pemfile::Item::PKCS8Key(der) | rustls_pemfile::Item::RSAKey(der) | rustls_pemfile::Item::ECKey(der) => {
+                Some(PrivateKey(der))
+            }
+            _ => None,
+        })
+        .ok_or_else(|| TlsError::Config(format!("No private key in {}", path)))
+}
+
+impl TlsConfig {
+    /// Rejects configs that name no certificate source, or two.
+    pub fn check(&self) -> Result<(), TlsError> {
+        if !self.enabled {
+            return Ok(());
+        }
+        let files = self.cert_path.is_some() || self.key_path.is_some();
+        match (&self.acme, files) {
+            (Some(_), true) => return Err(TlsError::Config("Set either cert_path/key_path or acme, not both".to_string())),
+            (None, false) => return Err(TlsError::Config("Set cert_path and key_path, or acme".to_string())),
+            (None, true) if self.cert_path.is_none() || self.key_path.is_none() => {
+                return Err(TlsError::Config("cert_path and key_path go together".to_string()))
+            }
+            (Some(acme), false) if acme.domains.is_empty() => {
+                return Err(TlsError::Config("acme needs at least one domain".to_string()))
+            }
+            _ => {}
+        }
+        if self.client_auth != ClientAuth::Off && self.client_ca_path.is_none() {
+            return Err(TlsError::Config("client_auth needs client_ca_path".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Builds the rustls config. With ACME, starts the task that orders and renews the
+    /// certificate; it has to run inside the server's runtime.
+    pub fn server_config(&self, http2: bool) -> Result<Arc<ServerConfig>, TlsError> {
+        self.check()?;
+        let builder = ServerConfig::builder().with_safe_defaults();
+        let builder = match (self.client_auth, &self.client_ca_path) {
+            (ClientAuth::Off, _) | (_, None) => builder.with_no_client_auth(),
+            (mode, Some(path)) => {
+                let mut roots = RootCertStore::empty();
+                for cert in certificates(path)? {
+                    roots.add(&cert)?;
+                }
+                if mode == ClientAuth::Required {
+                    builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+                } else {
+                    builder.with_client_cert_verifier(AllowAnyAnonymousOrAuthenticatedClient::new(roots).boxed())
+                }
+            }
+        };
+
+        let mut alpn: Vec<Vec<u8>> = Vec::new();
+        if http2 {
+            alpn.push(b"h2".to_vec());
+        }
+        alpn.push(b"http/1.1".to_vec());
+        let mut config = match (&self.acme, &self.cert_path, &self.key_path) {
+            (Some(acme), _, _) => {
+                let mut state = AcmeConfig::new(acme.domains.clone())
+                    .contact(acme.contact.iter())
+                    .cache_option(Some(DirCache::new(acme.cache_dir.clone())))
+                    .directory_lets_encrypt(acme.production)
+                    .state();
+                let resolver = state.resolver();
+                tokio::spawn(async move {
+                    while let Some(event) = state.next().await {
+                        match event {
+                            Ok(event) => info!("ACME: {:?}", event),
+                            Err(e) => warn!("ACME: {:?}", e),
+                        }
+                    }
+                });
+                alpn.push(rustls_acme::acme::ACME_TLS_ALPN_NAME.to_vec());
+                builder.with_cert_resolver(resolver)
+            }
+            (None, Some(cert_path), Some(key_path)) => {
+                builder.with_single_cert(certificates(cert_path)?, private_key(key_path)?)?
+            }
+            _ => unreachable!("checked above"),
+        };
+        config.alpn_protocols = alpn;
+        Ok(Arc::new(config))
+    }
+}
+
+/// Wraps accepted TCP connections in TLS. Handshakes run concurrently so a slow client
+/// can't hold up the others; failed or timed-out handshakes and ACME challenge
+/// connections are dropped without reaching the server.
+pub fn accept(
+    mut incoming: AddrIncoming,
+    config: Arc<ServerConfig>,
+    handshake_timeout: Duration,
+) -> impl Accept<Conn = TlsStream<AddrStream>, Error = io::Error> {
+    let acceptor = TlsAcceptor::from(config);
+    let connections = stream::poll_fn(move |cx| Pin::new(&mut incoming).poll_accept(cx))
+        .filter_map(|conn| async move {
+            conn.map_err(|e| warn!("Accepting a connection failed: {}", e)).ok()
+        })
+        .map(move |conn| {
+            let acceptor = acceptor.clone();
+            let remote_addr = conn.remote_addr();
+            async move {
+                match tokio::time::timeout(handshake_timeout, acceptor.accept(conn)).await {
+                    Ok(Ok(tls)) => Some(tls),
+                    Ok(Err(e)) => {
+                        debug!("TLS handshake with {} failed: {}", remote_addr, e);
+                        None
+                    }
+                    Err(_) => {
+                        debug!("TLS handshake with {} timed out", remote_addr);
+                        None
+                    }
+                }
+            }
+        })
+        .buffer_unordered(MAX_PENDING_HANDSHAKES)
+        .filter_map(|tls| async move {
+            let tls = tls?;
+            let challenge = tls.get_ref().1.alpn_protocol() == Some(rustls_acme::acme::ACME_TLS_ALPN_NAME);
+            (!challenge).then_some(Ok(tls))
+        });
+    accept::from_stream(connections)
+}
+
+/// The verified certificate a client presented during the handshake.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientCertificate {
+    /// Subject CN; mapped to a service account's `client_id`.
+    pub common_name: Option<String>,
+    /// SHA-256 of the DER encoding, hex.
+    pub fingerprint: String,
+}
+
+impl ClientCertificate {
+    pub fn from_der(der: &[u8]) -> Self {
+        let common_name = x509_parser::parse_x509_certificate(der).ok().and_then(|(_, cert)| {
+            cert.subject()
+                .iter_common_name()
+                .next()
+                .and_then(|cn| cn.as_str().ok())
+                .map(str::to_string)
+        });
+        Self { common_name, fingerprint: hex::encode(Sha256::digest(der)) }
+    }
+}
+
+/// Per-connection details for requests served over TLS.
+#[derive(Clone, Debug)]
+pub struct TlsConnectInfo {
+    pub remote_addr: SocketAddr,
+    pub client_certificate: Option<ClientCertificate>,
+}
+
+impl Connected<&TlsStream<AddrStream>> for TlsConnectInfo {
+    fn connect_info(target: &TlsStream<AddrStream>) -> Self {
+        let (tcp, session) = target.get_ref();
+        Self {
+            remote_addr: tcp.remote_addr(),
+            client_certificate: session
+                .peer_certificates()
+                .and_then(|chain| chain.first())
+                .map(|leaf| ClientCertificate::from_der(&leaf.0)),
+        }
+    }
+}
+
+/// Exposes the TLS connection to the rest of the stack: the socket address as
+/// `ConnectInfo<SocketAddr>`, for the network ACL and IP allowlists, and the client
+/// certificate, if any, as a `ClientCertificate` extension.
+pub async fn connect_info_middleware<B>(mut req: Request<B>, next: Next<B>) -> Response {
+    if let Some(ConnectInfo(info)) = req.extensions().get::<ConnectInfo<TlsConnectInfo>>().cloned() {
+        req.extensions_mut().insert(ConnectInfo(info.remote_addr));
+        if let Some(certificate) = info.client_certificate {
+            req.extensions_mut().insert(certificate);
+        }
+    }
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_files() -> TlsConfig {
+        TlsConfig {
+            enabled: true,
+            cert_path: Some("cert.pem".to_string()),
+            key_path: Some("key.pem".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_check_needs_exactly_one_certificate_source() {
+        assert!(TlsConfig::default().check().is_ok());
+        assert!(with_files().check().is_ok());
+        assert!(TlsConfig { enabled: true, ..Default::default() }.check().is_err());
+        assert!(TlsConfig { key_path: None, ..with_files() }.check().is_err());
+
+        let acme = AcmeSettings {
+            domains: vec!["api.example.com".to_string()],
+            contact: Vec::new(),
+            cache_dir: "/var/lib/stateset/acme".to_string(),
+            production: false,
+        };
+        assert!(TlsConfig { acme: Some(acme.clone()), ..with_files() }.check().is_err());
+        let acme_only = TlsConfig { enabled: true, acme: Some(acme.clone()), ..Default::default() };
+        assert!(acme_only.check().is_ok());
+        let no_domains = TlsConfig { acme: Some(AcmeSettings { domains: Vec::new(), ..acme }), ..acme_only };
+        assert!(no_domains.check().is_err());
+    }
+
+    #[test]
+    fn test_client_auth_needs_a_ca() {
+        let required = TlsConfig { client_auth: ClientAuth::Required, ..with_files() };
+        assert!(required.check().is_err());
+        let with_ca = TlsConfig { client_ca_path: Some("clients.pem".to_string()), ..required };
+        assert!(with_ca.check().is_ok());
+    }
+
+    #[test]
+    fn test_client_certificate_identity() {
+        let mut params = rcgen::CertificateParams::new(vec!["erp.example.com".to_string()]);
+        params.distinguished_name.push(rcgen::DnType::CommonName, "sa_erp_sync");
+        let der = rcgen::Certificate::from_params(params).unwrap().serialize_der().unwrap();
+        let certificate = ClientCertificate::from_der(&der);
+        assert_eq!(certificate.common_name.as_deref(), Some("sa_erp_sync"));
+        assert_eq!(certificate.fingerprint, hex::encode(Sha256::digest(&der)));
+        assert_eq!(ClientCertificate::from_der(b"not a certificate").common_name, None);
+    }
+}
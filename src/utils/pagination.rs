@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+const DEFAULT_PER_PAGE: u64 = 20;
+const MAX_PER_PAGE: u64 = 100;
+
+/// `?page=&per_page=` query parameters. Pages are 1-based.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaginationParams {
+    #[serde(default = "default_page")]
+    pub page: u64,
+    #[serde(default = "default_per_page")]
+    pub per_page: u64,
+}
+
+fn default_page() -> u64 {
+    1
+}
+
+fn default_per_page() -> u64 {
+    DEFAULT_PER_PAGE
+}
+
+impl Default for PaginationParams {
+    fn default() -> Self {
+        Self {
+            page: default_page(),
+            per_page: default_per_page(),
+        }
+    }
+}
+
+impl PaginationParams {
+    /// Page size clamped to `1..=100`.
+    pub fn limit(&self) -> u64 {
+        self.per_page.clamp(1, MAX_PER_PAGE)
+    }
+
+    /// Zero-based page index, as expected by sea-orm paginators.
+    pub fn page_index(&self) -> u64 {
+        self.page.max(1) - 1
+    }
+}
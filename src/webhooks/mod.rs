@@ -0,0 +1,975 @@
+// webhooks/mod.rs
+
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use lazy_static::lazy_static;
+use prometheus::{IntCounter, IntCounterVec};
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait,
+    QueryFilter, QueryOrder, Set,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tokio::sync::{broadcast, mpsc};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use crate::db::dialect;
+use crate::developer_logs::{DeliveryAttempt, DeveloperLogService};
+use crate::events::{Event, EventSender};
+use crate::jobs::{JobContext, JobError, JobRunner};
+use crate::metering::sign;
+use crate::models::order::Entity as Order;
+use crate::models::webhook_event::{self, Entity as WebhookEvent};
+use crate::models::webhook_subscription::{self, Entity as WebhookSubscription};
+use crate::shutdown::Worker;
+
+pub mod transform;
+
+use transform::{Transform, TransformSpec};
+
+/// Header carrying the HMAC-SHA256 of the delivered body, hex encoded.
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+
+/// Header naming the event a delivery is for.
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+
+/// Header set to `true` on replayed deliveries.
+pub const REPLAY_HEADER: &str = "X-Webhook-Replay";
+
+/// Job kind recorded in the `jobs` table for replays.
+pub const REPLAY_JOB_KIND: &str = "webhook_replay";
+
+/// Outbox rows read per page while replaying.
+const REPLAY_PAGE_SIZE: u64 = 500;
+
+lazy_static! {
+    static ref DELIVERIES: IntCounterVec =
+        IntCounterVec::new(
+            "webhook_deliveries_total",
+            "Entity webhook deliveries by outcome",
+            &["outcome"]
+        ).expect("metric can be created");
+    static ref EVENTS_LOST: IntCounter =
+        IntCounter::new(
+            "webhook_events_lost_total",
+            "Events the webhook dispatcher missed because the event channel overflowed"
+        ).expect("metric can be created");
+}
+
+/// Entity webhook settings, loaded from the `webhooks` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct WebhooksConfig {
+    /// Delivers events to subscriptions (default: false). Subscriptions can be managed
+    /// either way.
+    #[serde(default)]
+    pub enabled: bool,
+
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+
+    /// Attempts per delivery, with exponential backoff between them (default: 3).
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+
+    /// Payload keys treated as customer PII, redacted at any depth unless a subscription
+    /// is entitled to them.
+    #[serde(default = "default_pii_keys")]
+    pub pii_keys: Vec<String>,
+
+    /// Longest time range a single replay may cover (default: 30 days).
+    #[serde(default = "default_max_replay_days")]
+    pub max_replay_days: i64,
+
+    /// Days recorded events, which carry customer PII, are kept for replays; older ones
+    /// are purged (default: 30). Events the change data capture relay has not published
+    /// yet are kept regardless.
+    #[serde(default = "default_event_retention_days")]
+    pub event_retention_days: i64,
+
+    /// Lets subscriptions deliver to loopback, private and link-local addresses
+    /// (default: false). Only for development against a local receiver.
+    #[serde(default)]
+    pub allow_private_destinations: bool,
+}
+
+fn default_timeout_ms() -> u64 {
+    10_000
+}
+
+fn default_max_attempts() -> u32 {
+    3
+}
+
+fn default_max_replay_days() -> i64 {
+    30
+}
+
+fn default_event_retention_days() -> i64 {
+    30
+}
+
+fn default_pii_keys() -> Vec<String> {
+    [
+        "customer_name",
+        "customer_email",
+        "email",
+        "phone",
+        "first_name",
+        "last_name",
+        "delivery_address",
+        "billing_address",
+        "shipping_address",
+        "address",
+        "date_of_birth",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .collect()
+}
+
+impl Default for WebhooksConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            timeout_ms: default_timeout_ms(),
+            max_attempts: default_max_attempts(),
+            pii_keys: default_pii_keys(),
+            max_replay_days: default_max_replay_days(),
+            event_retention_days: default_event_retention_days(),
+            allow_private_destinations: false,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum WebhookError {
+    #[error("Webhook subscription not found: {0}")]
+    NotFound(Uuid),
+
+    #[error("Invalid webhook subscription: {0}")]
+    Invalid(String),
+
+    #[error("Delivering PII requires the webhooks:pii permission")]
+    PiiNotPermitted,
+
+    #[error("Invalid replay: {0}")]
+    InvalidReplay(String),
+
+    #[error(transparent)]
+    Job(#[from] JobError),
+
+    #[error("Database error: {0}")]
+    Database(#[from] DbErr),
+}
+
+impl IntoResponse for WebhookError {
+    fn into_response(self) -> Response {
+        if let WebhookError::Job(e) = self {
+            return e.into_response();
+        }
+        let (status, code) = match &self {
+            WebhookError::NotFound(_) => (StatusCode::NOT_FOUND, "webhook_not_found"),
+            WebhookError::Invalid(_) => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_webhook"),
+            WebhookError::PiiNotPermitted => (StatusCode::FORBIDDEN, "pii_not_permitted"),
+            WebhookError::InvalidReplay(_) => (StatusCode::BAD_REQUEST, "invalid_replay"),
+            WebhookError::Job(_) => unreachable!("job errors respond on their own"),
+            WebhookError::Database(e) => {
+                error!("Webhook query failed: {}", e);
+                (StatusCode::INTERNAL_SERVER_ERROR, "webhook_query_failed")
+            }
+        };
+        (status, Json(json!({ "error": self.to_string(), "code": code }))).into_response()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewWebhookSubscription {
+    pub name: String,
+    pub url: String,
+
+    #[serde(default)]
+    pub event_types: Vec<String>,
+
+    /// Generated when not given; returned once on creation.
+    #[serde(default)]
+    pub secret: Option<String>,
+
+    #[serde(default)]
+    pub transform: TransformSpec,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct UpdateWebhookSubscription {
+    pub name: Option<String>,
+    pub url: Option<String>,
+    pub event_types: Option<Vec<String>>,
+    pub active: Option<bool>,
+
+    /// Replaces the whole transform.
+    pub transform: Option<TransformSpec>,
+}
+
+/// Events to re-deliver to a subscriber: those that occurred in `[from, to)`, optionally
+/// narrowed to some event types within the subscription's own.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReplayRequest {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+
+    #[serde(default)]
+    pub event_types: Vec<String>,
+}
+
+/// Checks a replay's range against the configured maximum.
+pub fn validate_replay(request: &ReplayRequest, max_days: i64) -> Result<(), WebhookError> {
+    if request.from >= request.to {
+        return Err(WebhookError::InvalidReplay("from must be before to".to_string()));
+    }
+    if request.to - request.from > chrono::Duration::days(max_days) {
+        return Err(WebhookError::InvalidReplay(format!("a replay may cover at most {} days", max_days)));
+    }
+    Ok(())
+}
+
+/// A subscription as returned by the API, with its transform grouped.
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookView {
+    pub id: Uuid,
+    pub name: String,
+    pub url: String,
+    pub event_types: Vec<String>,
+    pub transform: TransformSpec,
+    pub active: bool,
+    pub created_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+fn strings(value: &Value) -> Vec<String> {
+    serde_json::from_value(value.clone()).unwrap_or_default()
+}
+
+/// The transform stored on a subscription.
+pub fn spec_of(subscription: &webhook_subscription::Model) -> TransformSpec {
+    TransformSpec {
+        fields: subscription.fields.as_ref().map(strings),
+        exclude: strings(&subscription.exclude),
+        template: subscription.template.clone(),
+        include_pii: subscription.include_pii,
+    }
+}
+
+impl From<webhook_subscription::Model> for WebhookView {
+    fn from(subscription: webhook_subscription::Model) -> Self {
+        Self {
+            transform: spec_of(&subscription),
+            event_types: strings(&subscription.event_types),
+            id: subscription.id,
+            name: subscription.name,
+            url: subscription.url,
+            active: subscription.active,
+            created_by: subscription.created_by,
+            created_at: subscription.created_at,
+            updated_at: subscription.updated_at,
+        }
+    }
+}
+
+/// Whether `ip` is a public unicast address. Loopback, private, shared, link-local
+/// (which includes cloud metadata endpoints), multicast and unspecified addresses are not.
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            let [a, b, ..] = v4.octets();
+            !(a == 0
+                || v4.is_private()
+                || v4.is_loopback()
+                || v4.is_link_local()
+                || v4.is_broadcast()
+                || v4.is_multicast()
+                || v4.is_documentation()
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(v6) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(v4));
+            }
+            let first = v6.segments()[0];
+            !(v6.is_loopback()
+                || v6.is_unspecified()
+                || v6.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+/// The host of `url` when it is an IP address rather than a name.
+fn ip_host(url: &url::Url) -> Option<IpAddr> {
+    match url.host()? {
+        url::Host::Ipv4(ip) => Some(IpAddr::V4(ip)),
+        url::Host::Ipv6(ip) => Some(IpAddr::V6(ip)),
+        url::Host::Domain(_) => None,
+    }
+}
+
+/// Resolves delivery hosts and refuses any that resolve to a non-public address, so a
+/// name re-pointed after it was registered still cannot reach internal services.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            if let Some(blocked) = addresses.iter().find(|address| !is_public(address.ip())) {
+                return Err(format!("{} resolves to non-public address {}", name.as_str(), blocked.ip()).into());
+            }
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+fn validate_transform(spec: &TransformSpec) -> Result<(), WebhookError> {
+    Transform::compile(spec).map(|_| ()).map_err(WebhookError::Invalid)
+}
+
+/// Event name used in subscriptions and payloads, e.g. `order_created` for
+/// `Event::OrderCreated`.
+pub fn event_name(event: &Event) -> String {
+    let variant = match serde_json::to_value(event) {
+        Ok(Value::Object(map)) => map.keys().next().cloned().unwrap_or_default(),
+        Ok(Value::String(name)) => name,
+        _ => String::new(),
+    };
+    let mut name = String::with_capacity(variant.len() + 4);
+    for (i, c) in variant.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                name.push('_');
+            }
+            name.push(c.to_ascii_lowercase());
+        } else {
+            name.push(c);
+        }
+    }
+    name
+}
+
+fn event_data(event: &Event) -> Value {
+    match serde_json::to_value(event) {
+        Ok(Value::Object(map)) => map.into_iter().next().map(|(_, data)| data).unwrap_or(Value::Null),
+        _ => Value::Null,
+    }
+}
+
+/// The order an event is about, loaded as the payload's `entity`.
+fn order_id(event: &Event) -> Option<Uuid> {
+    match event {
+        Event::OrderCreated(id)
+        | Event::OrderUpdated(id)
+        | Event::OrderCancelled(id)
+        | Event::OrderCompleted(id)
+        | Event::OrderRefunded(id)
+        | Event::OrderOnHold(id)
+        | Event::OrderReleasedFromHold(id)
+        | Event::OrderShipped(id)
+        | Event::OrderItemAdded(id) => Some(*id),
+        Event::ShipmentShipped { order_id, .. } => Some(*order_id),
+        _ => None,
+    }
+}
+
+/// Whether a subscription listens for `event`; no event types means all of them.
+pub fn subscribed(event_types: &[String], event: &str) -> bool {
+    event_types.is_empty() || event_types.iter().any(|t| t == event)
+}
+
+/// Outbox event recorded when the dispatcher missed events, so readers of the outbox
+/// (the CDC relay) know it has a gap. It is never delivered.
+pub(crate) const EVENTS_LOST_EVENT: &str = "events_lost";
+
+/// What the dispatcher's queue carries: an event, or how many were missed since
+/// the last one was received.
+enum Queued {
+    Event(Event),
+    Lost { missed: u64, since: DateTime<Utc> },
+}
+
+/// Events deleted per purge statement, to keep locks short.
+const PURGE_BATCH: i64 = 1000;
+
+const PURGE_SQL: &str = r#"
+DELETE FROM webhook_events WHERE id IN (
+    SELECT e.id FROM webhook_events e
+    WHERE e.occurred_at < $1
+    ORDER BY e.occurred_at
+    LIMIT $2
+)
+"#;
+
+/// As `PURGE_SQL`, but only events the relay named `$3` has read past. Without an offset
+/// nothing has been published, so nothing is purged.
+const PURGE_RELAYED_SQL: &str = r#"
+DELETE FROM webhook_events WHERE id IN (
+    SELECT e.id FROM webhook_events e
+    WHERE e.occurred_at < $1
+      AND e.occurred_at < (SELECT o.occurred_at FROM cdc_offsets o WHERE o.name = $3)
+    ORDER BY e.occurred_at
+    LIMIT $2
+)
+"#;
+
+/// Events that occurred before this are past retention. Never less than a day.
+pub fn retention_cutoff(now: DateTime<Utc>, retention_days: i64) -> DateTime<Utc> {
+    now - chrono::Duration::days(retention_days.max(1))
+}
+
+/// Manages subscriptions and delivers events to them.
+pub struct WebhookService {
+    db: Arc<DatabaseConnection>,
+    config: WebhooksConfig,
+    client: reqwest::Client,
+    pii_keys: HashSet<String>,
+    /// The change data capture relay reads the outbox, so purges leave what it has not published.
+    cdc_relay: bool,
+    /// Records delivery attempts for the developer console.
+    delivery_log: Option<Arc<DeveloperLogService>>,
+}
+
+impl WebhookService {
+    pub fn new(db: Arc<DatabaseConnection>, config: WebhooksConfig) -> Self {
+        let pii_keys = config.pii_keys.iter().map(|k| k.to_ascii_lowercase()).collect();
+        // Redirects are not followed, so a receiver cannot bounce deliveries elsewhere
+        let mut client = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none());
+        if !config.allow_private_destinations {
+            client = client.dns_resolver(Arc::new(PublicResolver));
+        }
+        let client = client.build().expect("webhook HTTP client can be built");
+        Self { db, config, client, pii_keys, cdc_relay: false, delivery_log: None }
+    }
+
+    /// Checks a subscription URL: http or https, and unless private destinations are
+    /// allowed, a host that is or resolves only to public addresses.
+    async fn validate_url(&self, url: &str) -> Result<(), WebhookError> {
+        let parsed = url::Url::parse(url).map_err(|e| WebhookError::Invalid(format!("url: {}", e)))?;
+        if !matches!(parsed.scheme(), "https" | "http") {
+            return Err(WebhookError::Invalid("url must be http or https".to_string()));
+        }
+        if self.config.allow_private_destinations {
+            return Ok(());
+        }
+        if let Some(ip) = ip_host(&parsed) {
+            return if is_public(ip) {
+                Ok(())
+            } else {
+                Err(WebhookError::Invalid(format!("url must not point at non-public address {}", ip)))
+            };
+        }
+        let host = parsed.host_str().ok_or_else(|| WebhookError::Invalid("url has no host".to_string()))?;
+        let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host, 0))
+            .await
+            .map_err(|e| WebhookError::Invalid(format!("url host {} does not resolve: {}", host, e)))?
+            .collect();
+        match addresses.iter().find(|address| !is_public(address.ip())) {
+            Some(blocked) => Err(WebhookError::Invalid(format!(
+                "url host {} resolves to non-public address {}",
+                host,
+                blocked.ip()
+            ))),
+            None if addresses.is_empty() => Err(WebhookError::Invalid(format!("url host {} does not resolve", host))),
+            None => Ok(()),
+        }
+    }
+
+    /// Keeps outbox rows the change data capture relay has not published yet.
+    pub fn with_cdc_relay(mut self, cdc_relay: bool) -> Self {
+        self.cdc_relay = cdc_relay;
+        self
+    }
+
+    /// Records every delivery attempt, with the subscriber's response, for the developer
+    /// console.
+    pub fn with_delivery_log(mut self, delivery_log: Option<Arc<DeveloperLogService>>) -> Self {
+        self.delivery_log = delivery_log;
+        self
+    }
+
+    pub async fn list(&self) -> Result<Vec<WebhookView>, WebhookError> {
+        Ok(WebhookSubscription::find()
+            .order_by_asc(webhook_subscription::Column::CreatedAt)
+            .all(self.db.as_ref())
+            .await?
+            .into_iter()
+            .map(WebhookView::from)
+            .collect())
+    }
+
+    async fn find(&self, id: Uuid) -> Result<webhook_subscription::Model, WebhookError> {
+        WebhookSubscription::find_by_id(id).one(self.db.as_ref()).await?.ok_or(WebhookError::NotFound(id))
+    }
+
+    pub async fn get(&self, id: Uuid) -> Result<WebhookView, WebhookError> {
+        Ok(self.find(id).await?.into())
+    }
+
+    /// Creates a subscription. Returns it with its signing secret, which is not shown again.
+    pub async fn create(
+        &self,
+        input: NewWebhookSubscription,
+        created_by: Option<String>,
+    ) -> Result<(WebhookView, String), WebhookError> {
+        if input.name.trim().is_empty() {
+            return Err(WebhookError::Invalid("name is required".to_string()));
+        }
+        self.validate_url(&input.url).await?;
+        validate_transform(&input.transform)?;
+        let secret = input.secret.unwrap_or_else(|| hex::encode(Uuid::new_v4().as_bytes()));
+        let now = Utc::now();
+        let spec = input.transform;
+        let subscription = webhook_subscription::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            name: Set(input.name),
+            url: Set(input.url),
+            event_types: Set(json!(input.event_types)),
+            secret: Set(secret.clone()),
+            fields: Set(spec.fields.map(|fields| json!(fields))),
+            exclude: Set(json!(spec.exclude)),
+            template: Set(spec.template),
+            include_pii: Set(spec.include_pii),
+            active: Set(true),
+            created_by: Set(created_by),
+            created_at: Set(now),
+            updated_at: Set(now),
+        }
+        .insert(self.db.as_ref())
+        .await?;
+        info!(webhook = %subscription.id, url = %subscription.url, "Webhook subscription created");
+        Ok((subscription.into(), secret))
+    }
+
+    pub async fn update(&self, id: Uuid, input: UpdateWebhookSubscription) -> Result<WebhookView, WebhookError> {
+        let mut subscription: webhook_subscription::ActiveModel = self.find(id).await?.into();
+        if let Some(name) = input.name {
+            subscription.name = Set(name);
+        }
+        if let Some(url) = input.url {
+            self.validate_url(&url).await?;
+            subscription.url = Set(url);
+        }
+        if let Some(event_types) = input.event_types {
+            subscription.event_types = Set(json!(event_types));
+        }
+        if let Some(active) = input.active {
+            subscription.active = Set(active);
+        }
+        if let Some(spec) = input.transform {
+            validate_transform(&spec)?;
+            subscription.fields = Set(spec.fields.map(|fields| json!(fields)));
+            subscription.exclude = Set(json!(spec.exclude));
+            subscription.template = Set(spec.template);
+            subscription.include_pii = Set(spec.include_pii);
+        }
+        subscription.updated_at = Set(Utc::now());
+        Ok(subscription.update(self.db.as_ref()).await?.into())
+    }
+
+    pub async fn delete(&self, id: Uuid) -> Result<(), WebhookError> {
+        let result = WebhookSubscription::delete_by_id(id).exec(self.db.as_ref()).await?;
+        if result.rows_affected == 0 {
+            return Err(WebhookError::NotFound(id));
+        }
+        Ok(())
+    }
+
+    /// What a transform makes of a payload, for trying one out before saving it.
+    pub fn preview(&self, spec: &TransformSpec, payload: Value) -> Result<Value, WebhookError> {
+        let transform = Transform::compile(spec).map_err(WebhookError::Invalid)?;
+        Ok(transform.apply(payload, &self.pii_keys))
+    }
+
+    /// The untransformed payload for an event, with the order it concerns when there is one.
+    async fn payload(&self, event: &Event, name: &str) -> Value {
+        let entity = match order_id(event) {
+            Some(id) => match Order::find_by_id(id).one(self.db.as_ref()).await {
+                Ok(order) => order.and_then(|order| serde_json::to_value(order).ok()),
+                Err(e) => {
+                    warn!(order_id = %id, "Could not load the order for a webhook payload: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+        json!({
+            "id": Uuid::new_v4(),
+            "event": name,
+            "occurred_at": Utc::now(),
+            "data": event_data(event),
+            "entity": entity,
+        })
+    }
+
+    /// Records an event in the outbox and delivers it to every active subscription
+    /// listening for it. Every event is recorded, so a subscription that is inactive now
+    /// can have it replayed later. Transforms are applied here, per delivery, so changes
+    /// take effect on the next event.
+    pub async fn dispatch(self: &Arc<Self>, event: &Event) -> Result<usize, WebhookError> {
+        let name = event_name(event);
+        // With only the outbox wanted, nothing is delivered
+        let subscriptions: Vec<_> = if self.config.enabled {
+            WebhookSubscription::find()
+                .filter(webhook_subscription::Column::Active.eq(true))
+                .all(self.db.as_ref())
+                .await?
+                .into_iter()
+                .filter(|s| subscribed(&strings(&s.event_types), &name))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        let payload = self.payload(event, &name).await;
+        self.record(&name, &payload).await;
+        let count = subscriptions.len();
+        for subscription in subscriptions {
+            let body = match Transform::compile(&spec_of(&subscription)) {
+                Ok(transform) => transform.apply(payload.clone(), &self.pii_keys),
+                Err(e) => {
+                    DELIVERIES.with_label_values(&["invalid_transform"]).inc();
+                    warn!(webhook = %subscription.id, "Skipping delivery with an invalid transform: {}", e);
+                    continue;
+                }
+            };
+            let service = self.clone();
+            let name = name.clone();
+            tokio::spawn(async move { service.deliver(&subscription, &name, &body, false).await });
+        }
+        Ok(count)
+    }
+
+    /// Keeps the untransformed payload in the outbox for replays.
+    async fn record(&self, name: &str, payload: &Value) {
+        let id = payload["id"].as_str().and_then(|id| id.parse().ok()).unwrap_or_else(Uuid::new_v4);
+        let recorded = webhook_event::ActiveModel {
+            id: Set(id),
+            event_type: Set(name.to_string()),
+            payload: Set(payload.clone()),
+            occurred_at: Set(Utc::now()),
+        }
+        .insert(self.db.as_ref())
+        .await;
+        if let Err(e) = recorded {
+            warn!(event = name, "Could not record a webhook event for replay: {}", e);
+        }
+    }
+
+    /// Records a gap in the outbox: `missed` events sent after `since` were never recorded.
+    pub async fn record_lost(&self, missed: u64, since: DateTime<Utc>) {
+        let payload = json!({
+            "id": Uuid::new_v4(),
+            "event": EVENTS_LOST_EVENT,
+            "occurred_at": Utc::now(),
+            "data": { "missed": missed, "since": since },
+            "entity": null,
+        });
+        self.record(EVENTS_LOST_EVENT, &payload).await;
+    }
+
+    /// Deletes one batch of events older than `event_retention_days`, oldest first.
+    /// Returns how many were deleted.
+    pub async fn purge_expired(&self) -> Result<u64, WebhookError> {
+        let cutoff = retention_cutoff(Utc::now(), self.config.event_retention_days);
+        let db = self.db.as_ref();
+        let statement = if self.cdc_relay {
+            dialect::statement(db, PURGE_RELAYED_SQL, [cutoff.into(), PURGE_BATCH.into(), crate::cdc::RELAY_NAME.into()])
+        } else {
+            dialect::statement(db, PURGE_SQL, [cutoff.into(), PURGE_BATCH.into()])
+        };
+        Ok(db.execute(statement).await?.rows_affected())
+    }
+
+    /// Posts a body to a subscriber, retrying with backoff. Returns whether it was accepted.
+    async fn deliver(&self, subscription: &webhook_subscription::Model, event: &str, body: &Value, replay: bool) -> bool {
+        // Names are checked as they resolve; addresses written into the URL are checked here
+        let literal = url::Url::parse(&subscription.url).ok().as_ref().and_then(ip_host);
+        if let (false, Some(ip)) = (self.config.allow_private_destinations, literal) {
+            if !is_public(ip) {
+                DELIVERIES.with_label_values(&["blocked"]).inc();
+                warn!(webhook = %subscription.id, %ip, "Refusing to deliver to a non-public address");
+                return false;
+            }
+        }
+        let body = serde_json::to_vec(body).expect("payload serializes");
+        let signature = sign(&subscription.secret, &body);
+        let mut backoff = Duration::from_millis(500);
+        for attempt in 1..=self.config.max_attempts.max(1) {
+            let started = Instant::now();
+            let result = self
+                .client
+                .post(&subscription.url)
+                .timeout(Duration::from_millis(self.config.timeout_ms))
+                .header("Content-Type", "application/json")
+                .header(EVENT_HEADER, event)
+                .header(SIGNATURE_HEADER, &signature)
+                .header(REPLAY_HEADER, replay.to_string())
+                .body(body.clone())
+                .send()
+                .await;
+            let outcome = match result {
+                Ok(response) => {
+                    let status = response.status();
+                    Ok((status, response.bytes().await.unwrap_or_default()))
+                }
+                Err(e) => Err(e.to_string()),
+            };
+            if let Some(logs) = &self.delivery_log {
+                logs.record_delivery(DeliveryAttempt {
+                    subscription,
+                    event,
+                    attempt,
+                    replay,
+                    latency: started.elapsed(),
+                    outcome: match &outcome {
+                        Ok((status, body)) => Ok((status.as_u16(), body.as_ref())),
+                        Err(e) => Err(e.as_str()),
+                    },
+                });
+            }
+            let failure = match outcome {
+                Ok((status, _)) if status.is_success() => {
+                    DELIVERIES.with_label_values(&["delivered"]).inc();
+                    return true;
+                }
+                Ok((status, _)) => status.to_string(),
+                Err(e) => e,
+            };
+            warn!(webhook = %subscription.id, attempt, event, "Webhook delivery failed: {}", failure);
+            if attempt < self.config.max_attempts {
+                tokio::time::sleep(backoff).await;
+                backoff *= 2;
+            }
+        }
+        DELIVERIES.with_label_values(&["failed"]).inc();
+        error!(webhook = %subscription.id, event, "Giving up on webhook delivery");
+        false
+    }
+
+    /// Re-delivers outbox events in the request's range to one subscriber, oldest first,
+    /// with the subscription's current transform.
+    async fn replay(
+        &self,
+        subscription: webhook_subscription::Model,
+        request: ReplayRequest,
+        context: &JobContext,
+    ) -> Result<Value, WebhookError> {
+        let transform = Transform::compile(&spec_of(&subscription)).map_err(WebhookError::Invalid)?;
+        let subscribed_types = strings(&subscription.event_types);
+        let mut query = WebhookEvent::find()
+            .filter(webhook_event::Column::OccurredAt.gte(request.from))
+            .filter(webhook_event::Column::OccurredAt.lt(request.to));
+        if !request.event_types.is_empty() {
+            query = query.filter(webhook_event::Column::EventType.is_in(request.event_types.clone()));
+        }
+        let mut pages = query
+            .order_by_asc(webhook_event::Column::OccurredAt)
+            .order_by_asc(webhook_event::Column::Id)
+            .paginate(self.db.as_ref(), REPLAY_PAGE_SIZE);
+        let total = pages.num_items().await?;
+        let (mut seen, mut delivered, mut failed) = (0u64, 0u64, 0u64);
+        while let Some(events) = pages.fetch_and_next().await? {
+            for event in events {
+                seen += 1;
+                if event.event_type == EVENTS_LOST_EVENT || !subscribed(&subscribed_types, &event.event_type) {
+                    continue;
+                }
+                let body = transform.apply(event.payload, &self.pii_keys);
+                if self.deliver(&subscription, &event.event_type, &body, true).await {
+                    delivered += 1;
+                } else {
+                    failed += 1;
+                }
+            }
+            let percent = (seen * 100 / total.max(1)) as i32;
+            context.report_progress(percent, format!("{} of {} events replayed", seen, total)).await;
+        }
+        info!(webhook = %subscription.id, delivered, failed, "Webhook replay finished");
+        Ok(json!({ "events": seen, "delivered": delivered, "failed": failed }))
+    }
+}
+
+/// Starts a replay of past events to a subscription as a job.
+pub async fn submit_replay(
+    runner: &JobRunner,
+    service: Arc<WebhookService>,
+    id: Uuid,
+    request: ReplayRequest,
+    created_by: Option<String>,
+) -> Result<Uuid, WebhookError> {
+    validate_replay(&request, service.config.max_replay_days)?;
+    let subscription = service.find(id).await?;
+    if !subscription.active {
+        return Err(WebhookError::InvalidReplay("the subscription is not active".to_string()));
+    }
+    let job_id = runner
+        .submit(REPLAY_JOB_KIND, created_by, move |context| async move {
+            service.replay(subscription, request, &context).await.map_err(|e| e.to_string())
+        })
+        .await?;
+    info!(webhook = %id, %job_id, "Webhook replay started");
+    Ok(job_id)
+}
+
+/// Delivers events to subscriptions as they are published. On shutdown, events already
+/// published are delivered before the dispatcher stops.
+pub fn spawn_dispatcher(service: Arc<WebhookService>, events: EventSender, mut worker: Worker) {
+    let mut receiver = events.subscribe();
+    // Deliveries can be slow, so events are drained off the broadcast channel straight
+    // into an unbounded queue; the receiver only lags if the process is starved.
+    // Missed events are recorded as a gap in the outbox, in order with the rest.
+    let (queue, mut queued) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut last_received = Utc::now();
+        loop {
+            let next = match receiver.recv().await {
+                Ok(event) => {
+                    last_received = Utc::now();
+                    Queued::Event(event)
+                }
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    EVENTS_LOST.inc_by(missed);
+                    error!(missed, "Webhook dispatcher lagged; events were lost before they were recorded");
+                    Queued::Lost { missed, since: last_received }
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            };
+            if queue.send(next).is_err() {
+                break;
+            }
+        }
+    });
+    tokio::spawn(async move {
+        loop {
+            let next = tokio::select! {
+                biased;
+                next = queued.recv() => match next {
+                    Some(next) => next,
+                    None => break,
+                },
+                _ = worker.stopping() => break,
+            };
+            match next {
+                Queued::Event(event) => {
+                    if let Err(e) = service.dispatch(&event).await {
+                        error!(?event, "Webhook dispatch failed: {}", e);
+                    }
+                }
+                Queued::Lost { missed, since } => service.record_lost(missed, since).await,
+            }
+        }
+    });
+}
+
+/// Purges expired events hourly, batch after batch until none are left.
+pub fn spawn_purger(service: Arc<WebhookService>, mut worker: Worker) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(60 * 60));
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = worker.stopping() => break,
+            }
+            while !worker.is_stopping() {
+                match service.purge_expired().await {
+                    Ok(0) => break,
+                    Ok(deleted) => {
+                        info!(deleted, "Purged expired webhook events");
+                        if (deleted as i64) < PURGE_BATCH {
+                            break;
+                        }
+                    }
+                    Err(e) => {
+                        error!("Webhook event purge failed: {}", e);
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_public_addresses_are_deliverable() {
+        let blocked = [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fe80::1",
+            "fd00::1",
+            "::ffff:10.0.0.1",
+        ];
+        for blocked in blocked {
+            assert!(!is_public(blocked.parse().unwrap()), "{} should be refused", blocked);
+        }
+        for allowed in ["93.184.216.34", "2606:2800:220:1:248:1893:25c8:1946"] {
+            assert!(is_public(allowed.parse().unwrap()), "{} should be allowed", allowed);
+        }
+    }
+
+    #[test]
+    fn test_retention_cutoff() {
+        let now = Utc::now();
+        assert_eq!(retention_cutoff(now, 30), now - chrono::Duration::days(30));
+        assert_eq!(retention_cutoff(now, 0), now - chrono::Duration::days(1));
+    }
+
+    #[test]
+    fn test_event_names_and_data() {
+        let id = Uuid::nil();
+        assert_eq!(event_name(&Event::OrderCreated(id)), "order_created");
+        assert_eq!(event_data(&Event::OrderCreated(id)), json!(id));
+        let event = Event::DisputeClosed { dispute_id: id, order_id: None, won: true, amount: Default::default() };
+        assert_eq!(event_name(&event), "dispute_closed");
+        assert_eq!(event_data(&event)["won"], json!(true));
+    }
+
+    #[test]
+    fn test_subscribed() {
+        assert!(subscribed(&[], "order_created"));
+        assert!(subscribed(&["order_created".to_string()], "order_created"));
+        assert!(!subscribed(&["order_shipped".to_string()], "order_created"));
+    }
+
+    #[test]
+    fn test_replay_range_is_bounded() {
+        let to = Utc::now();
+        let request = |days: i64| ReplayRequest { from: to - chrono::Duration::days(days), to, event_types: Vec::new() };
+        assert!(validate_replay(&request(7), 30).is_ok());
+        assert!(validate_replay(&request(31), 30).is_err());
+        assert!(validate_replay(&request(-1), 30).is_err());
+    }
+
+    #[test]
+    fn test_default_pii_keys_cover_order_contact_fields() {
+        let keys = WebhooksConfig::default().pii_keys;
+        assert!(keys.iter().any(|k| k == "customer_email"));
+        assert!(keys.iter().any(|k| k == "delivery_address"));
+    }
+}
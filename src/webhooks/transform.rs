@@ -0,0 +1,394 @@
+// webhooks/transform.rs
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+
+/// Placeholder left where a PII field was removed from a payload.
+pub const REDACTED: &str = "[redacted]";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+/// A path into a payload, JSONPath or jq style: `$.entity.id`, `.items[0].sku`,
+/// `$.items[*].sku` or `$["odd key"]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+impl JsonPath {
+    pub fn parse(path: &str) -> Result<Self, String> {
+        let path = path.trim();
+        let mut rest = path.strip_prefix('$').unwrap_or(path);
+        let mut segments = Vec::new();
+        while !rest.is_empty() {
+            if let Some(after) = rest.strip_prefix('.') {
+                let end = after.find(|c| c == '.' || c == '[').unwrap_or(after.len());
+                let key = &after[..end];
+                if key.is_empty() {
+                    return Err(format!("Empty key in path '{}'", path));
+                }
+                segments.push(Segment::Key(key.to_string()));
+                rest = &after[end..];
+            } else if let Some(after) = rest.strip_prefix('[') {
+                let end = after.find(']').ok_or_else(|| format!("Unclosed '[' in path '{}'", path))?;
+                let inner = after[..end].trim();
+                segments.push(if inner == "*" {
+                    Segment::Wildcard
+                } else if let Some(key) = inner
+                    .strip_prefix('"')
+                    .and_then(|k| k.strip_suffix('"'))
+                    .or_else(|| inner.strip_prefix('\'').and_then(|k| k.strip_suffix('\'')))
+                {
+                    Segment::Key(key.to_string())
+                } else {
+                    Segment::Index(inner.parse().map_err(|_| format!("Invalid index '{}' in path '{}'", inner, path))?)
+                });
+                rest = &after[end + 1..];
+            } else {
+                return Err(format!("Expected '.' or '[' in path '{}'", path));
+            }
+        }
+        Ok(Self { segments })
+    }
+
+    fn has_wildcard(&self) -> bool {
+        self.segments.contains(&Segment::Wildcard)
+    }
+
+    /// Every value the path points at; a wildcard can match several.
+    pub fn select<'a>(&self, value: &'a Value) -> Vec<&'a Value> {
+        let mut current = vec![value];
+        for segment in &self.segments {
+            current = current
+                .into_iter()
+                .flat_map(|value| -> Vec<&'a Value> {
+                    match (segment, value) {
+                        (Segment::Key(key), Value::Object(map)) => map.get(key).into_iter().collect(),
+                        (Segment::Index(i), Value::Array(items)) => items.get(*i).into_iter().collect(),
+                        (Segment::Wildcard, Value::Array(items)) => items.iter().collect(),
+                        (Segment::Wildcard, Value::Object(map)) => map.values().collect(),
+                        _ => Vec::new(),
+                    }
+                })
+                .collect();
+        }
+        current
+    }
+
+    /// The value at the path: an array of matches for wildcard paths, `null` when nothing
+    /// matches.
+    pub fn get(&self, value: &Value) -> Value {
+        let matches = self.select(value);
+        if self.has_wildcard() {
+            Value::Array(matches.into_iter().cloned().collect())
+        } else {
+            matches.first().map_or(Value::Null, |v| (*v).clone())
+        }
+    }
+}
+
+fn copy(src: &Value, out: &mut Value, segments: &[Segment]) {
+    let Some((first, rest)) = segments.split_first() else {
+        *out = src.clone();
+        return;
+    };
+    match (first, src) {
+        (Segment::Key(key), Value::Object(map)) => {
+            if let Some(child) = map.get(key) {
+                if !out.is_object() {
+                    *out = Value::Object(Map::new());
+                }
+                let slot = out.as_object_mut().expect("object").entry(key.clone()).or_insert(Value::Null);
+                copy(child, slot, rest);
+            }
+        }
+        (Segment::Wildcard, Value::Object(map)) => {
+            for key in map.keys() {
+                let mut segments = vec![Segment::Key(key.clone())];
+                segments.extend_from_slice(rest);
+                copy(src, out, &segments);
+            }
+        }
+        // Arrays keep their length so positions still line up; unselected items are null
+        (Segment::Index(_) | Segment::Wildcard, Value::Array(items)) => {
+            if !out.is_array() {
+                *out = Value::Array(vec![Value::Null; items.len()]);
+            }
+            let slots = out.as_array_mut().expect("array");
+            for (i, item) in items.iter().enumerate() {
+                if matches!(first, Segment::Index(index) if *index != i) {
+                    continue;
+                }
+                copy(item, &mut slots[i], rest);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// A payload with only the given paths; paths that match nothing are left out.
+pub fn select_fields(payload: &Value, paths: &[JsonPath]) -> Value {
+    let mut out = Value::Object(Map::new());
+    for path in paths {
+        copy(payload, &mut out, &path.segments);
+    }
+    out
+}
+
+fn remove(value: &mut Value, segments: &[Segment]) {
+    let Some((first, rest)) = segments.split_first() else { return };
+    let last = rest.is_empty();
+    match (first, value) {
+        (Segment::Key(key), Value::Object(map)) => {
+            if last {
+                map.remove(key);
+            } else if let Some(child) = map.get_mut(key) {
+                remove(child, rest);
+            }
+        }
+        (Segment::Index(i), Value::Array(items)) => {
+            if last {
+                if *i < items.len() {
+                    items.remove(*i);
+                }
+            } else if let Some(child) = items.get_mut(*i) {
+                remove(child, rest);
+            }
+        }
+        (Segment::Wildcard, Value::Object(map)) => {
+            if last {
+                map.clear();
+            } else {
+                map.values_mut().for_each(|child| remove(child, rest));
+            }
+        }
+        (Segment::Wildcard, Value::Array(items)) => {
+            if last {
+                items.clear();
+            } else {
+                items.iter_mut().for_each(|child| remove(child, rest));
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Drops the given paths from a payload.
+pub fn remove_fields(payload: &mut Value, paths: &[JsonPath]) {
+    for path in paths {
+        remove(payload, &path.segments);
+    }
+}
+
+/// Replaces the values of keys named in `keys` (lowercase) at any depth. Returns how many
+/// were redacted.
+pub fn redact(payload: &mut Value, keys: &HashSet<String>) -> usize {
+    match payload {
+        Value::Object(map) => map
+            .iter_mut()
+            .map(|(key, value)| {
+                if keys.contains(&key.to_ascii_lowercase()) {
+                    if value.is_null() {
+                        0
+                    } else {
+                        *value = Value::String(REDACTED.to_string());
+                        1
+                    }
+                } else {
+                    redact(value, keys)
+                }
+            })
+            .sum(),
+        Value::Array(items) => items.iter_mut().map(|item| redact(item, keys)).sum(),
+        _ => 0,
+    }
+}
+
+/// The `{{ path }}` placeholders in a template string, with their byte ranges.
+fn placeholders(text: &str) -> Vec<(usize, usize, &str)> {
+    let mut found = Vec::new();
+    let mut from = 0;
+    while let Some(start) = text[from..].find("{{").map(|i| i + from) {
+        let Some(end) = text[start..].find("}}").map(|i| i + start + 2) else { break };
+        found.push((start, end, text[start + 2..end - 2].trim()));
+        from = end;
+    }
+    found
+}
+
+/// Every path a template refers to, for validating it before it is saved.
+pub fn template_paths(template: &Value) -> Vec<String> {
+    match template {
+        Value::String(text) => placeholders(text).into_iter().map(|(_, _, path)| path.to_string()).collect(),
+        Value::Array(items) => items.iter().flat_map(template_paths).collect(),
+        Value::Object(map) => map.values().flat_map(template_paths).collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn interpolate(text: &str, payload: &Value) -> Value {
+    let found = placeholders(text);
+    let resolve = |path: &str| JsonPath::parse(path).map(|p| p.get(payload)).unwrap_or(Value::Null);
+    // A string that is a single placeholder keeps the value's JSON type
+    if let [(0, end, path)] = found.as_slice() {
+        if *end == text.len() {
+            return resolve(path);
+        }
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut from = 0;
+    for (start, end, path) in found {
+        out.push_str(&text[from..start]);
+        match resolve(path) {
+            Value::Null => {}
+            Value::String(s) => out.push_str(&s),
+            other => out.push_str(&other.to_string()),
+        }
+        from = end;
+    }
+    out.push_str(&text[from..]);
+    Value::String(out)
+}
+
+/// Fills a template's `{{ path }}` placeholders from the payload.
+pub fn render(template: &Value, payload: &Value) -> Value {
+    match template {
+        Value::String(text) => interpolate(text, payload),
+        Value::Array(items) => Value::Array(items.iter().map(|item| render(item, payload)).collect()),
+        Value::Object(map) => {
+            Value::Object(map.iter().map(|(key, value)| (key.clone(), render(value, payload))).collect())
+        }
+        other => other.clone(),
+    }
+}
+
+/// How a subscription reshapes payloads, as stored on it and accepted by the API.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TransformSpec {
+    #[serde(default)]
+    pub fields: Option<Vec<String>>,
+
+    #[serde(default)]
+    pub exclude: Vec<String>,
+
+    #[serde(default)]
+    pub template: Option<Value>,
+
+    #[serde(default)]
+    pub include_pii: bool,
+}
+
+/// A parsed [`TransformSpec`], ready to apply at delivery time.
+#[derive(Debug, Clone)]
+pub struct Transform {
+    fields: Option<Vec<JsonPath>>,
+    exclude: Vec<JsonPath>,
+    template: Option<Value>,
+    include_pii: bool,
+}
+
+impl Transform {
+    pub fn compile(spec: &TransformSpec) -> Result<Self, String> {
+        let parse_all = |paths: &[String]| paths.iter().map(|p| JsonPath::parse(p)).collect::<Result<Vec<_>, _>>();
+        if let Some(template) = &spec.template {
+            for path in template_paths(template) {
+                JsonPath::parse(&path)?;
+            }
+        }
+        Ok(Self {
+            fields: spec.fields.as_deref().map(parse_all).transpose()?,
+            exclude: parse_all(&spec.exclude)?,
+            template: spec.template.clone(),
+            include_pii: spec.include_pii,
+        })
+    }
+
+    /// Redacts PII first, so neither field selection nor the template can bring it back,
+    /// then keeps `fields`, drops `exclude` and renders the template.
+    pub fn apply(&self, mut payload: Value, pii_keys: &HashSet<String>) -> Value {
+        if !self.include_pii {
+            redact(&mut payload, pii_keys);
+        }
+        if let Some(fields) = &self.fields {
+            payload = select_fields(&payload, fields);
+        }
+        remove_fields(&mut payload, &self.exclude);
+        match &self.template {
+            Some(template) => render(template, &payload),
+            None => payload,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn payload() -> Value {
+        json!({
+            "event": "order_created",
+            "entity": {
+                "id": "o-1",
+                "customer_email": "ada@example.com",
+                "items": [{ "sku": "A", "price": 5 }, { "sku": "B", "price": 7 }]
+            }
+        })
+    }
+
+    fn paths(paths: &[&str]) -> Vec<JsonPath> {
+        paths.iter().map(|p| JsonPath::parse(p).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_parse_paths() {
+        assert_eq!(JsonPath::parse("$.entity.id"), JsonPath::parse(".entity.id"));
+        assert_eq!(JsonPath::parse("$[\"entity\"]['id']"), JsonPath::parse("$.entity.id"));
+        assert!(JsonPath::parse("$.items[x]").is_err());
+        assert!(JsonPath::parse("$.items[0").is_err());
+        assert!(JsonPath::parse("entity").is_err());
+        assert_eq!(JsonPath::parse("$.entity.items[*].sku").unwrap().get(&payload()), json!(["A", "B"]));
+        assert_eq!(JsonPath::parse("$.entity.items[1].price").unwrap().get(&payload()), json!(7));
+        assert_eq!(JsonPath::parse("$.missing").unwrap().get(&payload()), Value::Null);
+    }
+
+    #[test]
+    fn test_select_and_remove_fields() {
+        let selected = select_fields(&payload(), &paths(&["$.event", "$.entity.items[*].sku"]));
+        assert_eq!(selected, json!({ "event": "order_created", "entity": { "items": [{ "sku": "A" }, { "sku": "B" }] } }));
+
+        let mut trimmed = payload();
+        remove_fields(&mut trimmed, &paths(&["$.entity.items[*].price", "$.entity.customer_email"]));
+        assert_eq!(trimmed["entity"], json!({ "id": "o-1", "items": [{ "sku": "A" }, { "sku": "B" }] }));
+    }
+
+    #[test]
+    fn test_render_keeps_types_and_interpolates() {
+        let template = json!({ "id": "{{ $.entity.id }}", "skus": "{{$.entity.items[*].sku}}", "text": "Order {{ .entity.id }} created" });
+        assert_eq!(
+            render(&template, &payload()),
+            json!({ "id": "o-1", "skus": ["A", "B"], "text": "Order o-1 created" })
+        );
+    }
+
+    #[test]
+    fn test_pii_is_redacted_before_templating() {
+        let pii: HashSet<String> = ["customer_email".to_string()].into_iter().collect();
+        let spec = TransformSpec {
+            template: Some(json!({ "email": "{{ $.entity.customer_email }}" })),
+            ..Default::default()
+        };
+        let transform = Transform::compile(&spec).unwrap();
+        assert_eq!(transform.apply(payload(), &pii), json!({ "email": REDACTED }));
+
+        let entitled = Transform::compile(&TransformSpec { include_pii: true, ..spec }).unwrap();
+        assert_eq!(entitled.apply(payload(), &pii), json!({ "email": "ada@example.com" }));
+        assert!(Transform::compile(&TransformSpec { template: Some(json!("{{ bad }}")), ..Default::default() }).is_err());
+    }
+}
@@ -0,0 +1,524 @@
+// websocket/mod.rs
+
+use axum::{
+    extract::{
+        ws::{CloseFrame, Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    http::HeaderMap,
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use futures::{SinkExt, StreamExt};
+use lazy_static::lazy_static;
+use prometheus::{IntCounterVec, IntGauge};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{borrow::Cow, collections::HashSet, fmt, str::FromStr, sync::Arc};
+use tokio::sync::{broadcast, mpsc};
+use tracing::{debug, info, warn};
+
+use crate::auth::{self, AuthConfig, AuthError, Claims};
+use crate::events::{Event, EventSender};
+
+lazy_static! {
+    static ref WS_CONNECTIONS: IntGauge =
+        IntGauge::new("websocket_connections", "Open WebSocket sessions")
+            .expect("metric can be created");
+    static ref WS_MESSAGES_DROPPED: IntCounterVec =
+        IntCounterVec::new(
+            "websocket_messages_dropped_total",
+            "Updates not delivered to WebSocket clients",
+            &["reason"]
+        ).expect("metric can be created");
+}
+
+/// Close code sent to clients that cannot keep up (RFC 6455 "try again later").
+const CLOSE_SLOW_CONSUMER: u16 = 1013;
+/// Close code sent when the token is missing or invalid (RFC 6455 "policy violation").
+const CLOSE_POLICY_VIOLATION: u16 = 1008;
+
+/// WebSocket settings, loaded from the `websocket` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct WebSocketConfig {
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Updates buffered per connection before new ones are dropped.
+    #[serde(default = "default_outbound_buffer")]
+    pub outbound_buffer: usize,
+
+    /// Consecutive dropped updates after which a slow client is disconnected.
+    #[serde(default = "default_max_dropped")]
+    pub max_consecutive_drops: usize,
+
+    #[serde(default = "default_max_subscriptions")]
+    pub max_subscriptions: usize,
+}
+
+fn default_outbound_buffer() -> usize {
+    256
+}
+
+fn default_max_dropped() -> usize {
+    1024
+}
+
+fn default_max_subscriptions() -> usize {
+    32
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            outbound_buffer: default_outbound_buffer(),
+            max_consecutive_drops: default_max_dropped(),
+            max_subscriptions: default_max_subscriptions(),
+        }
+    }
+}
+
+/// A subscription topic: `orders`, `shipments`, `inventory` or `inventory:<warehouse_id>`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Topic {
+    Orders,
+    Shipments,
+    Inventory { warehouse_id: Option<i32> },
+}
+
+impl Topic {
+    /// Permission a token must carry to subscribe. Admins may subscribe to every topic.
+    pub fn required_permission(&self) -> &'static str {
+        match self {
+            Topic::Orders => "orders:read",
+            Topic::Shipments => "shipments:read",
+            Topic::Inventory { .. } => "inventory:read",
+        }
+    }
+
+    /// Whether an update published on `published` is delivered to this subscription.
+    /// An unscoped `inventory` subscription receives every warehouse.
+    pub fn matches(&self, published: &Topic) -> bool {
+        match (self, published) {
+            (Topic::Inventory { warehouse_id: None }, Topic::Inventory { .. }) => true,
+            (Topic::Inventory { warehouse_id: Some(a) }, Topic::Inventory { warehouse_id: Some(b) }) => a == b,
+            (a, b) => a == b,
+        }
+    }
+
+    /// Maps an application event to the topic it is published on, if any.
+    pub fn for_event(event: &Event) -> Option<Topic> {
+        let name = event_name(event);
+        if let Event::InventoryLevelChanged { warehouse_id, .. } = event {
+            return Some(Topic::Inventory { warehouse_id: Some(*warehouse_id) });
+        }
+        if name.starts_with("Order") {
+            Some(Topic::Orders)
+        } else if name.starts_with("Shipment") {
+            Some(Topic::Shipments)
+        } else if name.starts_with("Inventory") {
+            Some(Topic::Inventory { warehouse_id: None })
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Topic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Topic::Orders => write!(f, "orders"),
+            Topic::Shipments => write!(f, "shipments"),
+            Topic::Inventory { warehouse_id: None } => write!(f, "inventory"),
+            Topic::Inventory { warehouse_id: Some(id) } => write!(f, "inventory:{}", id),
+        }
+    }
+}
+
+impl FromStr for Topic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(':') {
+            None if s == "orders" => Ok(Topic::Orders),
+            None if s == "shipments" => Ok(Topic::Shipments),
+            None if s == "inventory" => Ok(Topic::Inventory { warehouse_id: None }),
+            Some(("inventory", warehouse)) => warehouse
+                .parse()
+                .map(|id| Topic::Inventory { warehouse_id: Some(id) })
+                .map_err(|_| format!("Invalid warehouse id in topic: {}", s)),
+            _ => Err(format!("Unknown topic: {}", s)),
+        }
+    }
+}
+
+/// Variant name of an event, e.g. `OrderShipped`.
+fn event_name(event: &Event) -> String {
+    format!("{:?}", event)
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Messages sent by clients.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    Subscribe { topics: Vec<String> },
+    Unsubscribe { topics: Vec<String> },
+    Ping,
+}
+
+/// Messages pushed to clients.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Subscribed { topics: Vec<String> },
+    Unsubscribed { topics: Vec<String> },
+    Event { topic: String, event: String, data: Value },
+    /// Updates were skipped because the client fell behind; it should refetch state.
+    Lagged { missed: u64 },
+    Error { code: String, message: String },
+    Pong,
+}
+
+/// Per-connection state: who is connected and what they are subscribed to.
+pub struct Session {
+    claims: Claims,
+    topics: HashSet<Topic>,
+    max_subscriptions: usize,
+}
+
+impl Session {
+    pub fn new(claims: Claims, max_subscriptions: usize) -> Self {
+        Self {
+            claims,
+            topics: HashSet::new(),
+            max_subscriptions,
+        }
+    }
+
+    fn may_subscribe(&self, topic: &Topic) -> bool {
+        self.claims.is_allowed(topic.required_permission())
+    }
+
+    /// Applies a client message and returns the reply.
+    pub fn handle(&mut self, message: ClientMessage) -> ServerMessage {
+        match message {
+            ClientMessage::Ping => ServerMessage::Pong,
+            ClientMessage::Subscribe { topics } => {
+                let mut parsed = Vec::with_capacity(topics.len());
+                for raw in &topics {
+                    let topic = match raw.parse::<Topic>() {
+                        Ok(topic) => topic,
+                        Err(message) => return error("invalid_topic", message),
+                    };
+                    if !self.may_subscribe(&topic) {
+                        return error(
+                            "forbidden",
+                            format!("Subscribing to {} requires {}", topic, topic.required_permission()),
+                        );
+                    }
+                    parsed.push(topic);
+                }
+                if self.topics.iter().chain(&parsed).collect::<HashSet<_>>().len() > self.max_subscriptions {
+                    return error("too_many_subscriptions", format!("At most {} topics", self.max_subscriptions));
+                }
+                self.topics.extend(parsed);
+                ServerMessage::Subscribed { topics: self.topic_names() }
+            }
+            ClientMessage::Unsubscribe { topics } => {
+                for raw in &topics {
+                    if let Ok(topic) = raw.parse::<Topic>() {
+                        self.topics.remove(&topic);
+                    }
+                }
+                ServerMessage::Unsubscribed { topics }
+            }
+        }
+    }
+
+    /// Converts an event into a push message if this session is subscribed to it.
+    pub fn filter(&self, event: &Event) -> Option<ServerMessage> {
+        let published = Topic::for_event(event)?;
+        if !self.topics.iter().any(|t| t.matches(&published)) {
+            return None;
+        }
+        Some(ServerMessage::Event {
+            topic: published.to_string(),
+            event: event_name(event),
+            data: serde_json::to_value(event).unwrap_or(Value::Null),
+        })
+    }
+
+    fn topic_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.topics.iter().map(Topic::to_string).collect();
+        names.sort();
+        names
+    }
+}
+
+fn error(code: &str, message: impl Into<String>) -> ServerMessage {
+    ServerMessage::Error {
+        code: code.to_string(),
+        message: message.into(),
+    }
+}
+
+/// Shared state of the WebSocket endpoint.
+pub struct WebSocketHub {
+    events: Arc<EventSender>,
+    auth_config: Arc<AuthConfig>,
+    config: WebSocketConfig,
+}
+
+impl WebSocketHub {
+    pub fn new(events: Arc<EventSender>, auth_config: Arc<AuthConfig>, config: WebSocketConfig) -> Self {
+        Self {
+            events,
+            auth_config,
+            config,
+        }
+    }
+}
+
+/// Subprotocol a browser offers ahead of its token, as browsers cannot set an
+/// `Authorization` header on WebSocket requests: `new WebSocket(url, ["bearer", token])`.
+/// Tokens are never accepted in the URL, which access logs and archives record.
+const BEARER_PROTOCOL: &str = "bearer";
+
+/// The token from `Authorization: Bearer`, or the entry after `bearer` in
+/// `Sec-WebSocket-Protocol`.
+fn handshake_token(headers: &HeaderMap) -> Option<String> {
+    if let Some(token) = headers
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+    {
+        return Some(token.to_owned());
+    }
+    let protocols: Vec<&str> = headers
+        .get_all("Sec-WebSocket-Protocol")
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(','))
+        .map(str::trim)
+        .collect();
+    let position = protocols.iter().position(|p| *p == BEARER_PROTOCOL)?;
+    protocols.get(position + 1).map(|token| token.to_string())
+}
+
+/// Routes for the WebSocket endpoint. Mounted outside `auth_middleware` because the
+/// handshake authenticates itself (`Authorization` header or `Sec-WebSocket-Protocol`).
+pub fn router(hub: Arc<WebSocketHub>) -> Router {
+    Router::new()
+        .route("/api/v1/ws", get(ws_handler))
+        .with_state(hub)
+}
+
+async fn ws_handler(
+    State(hub): State<Arc<WebSocketHub>>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Result<Response, AuthError> {
+    let token = handshake_token(&headers).ok_or(AuthError::MissingAuthHeader)?;
+    let claims = auth::validate_token(&token, &hub.auth_config)?;
+
+    // Browsers close the connection unless the server selects one of the offered subprotocols
+    Ok(ws
+        .protocols([BEARER_PROTOCOL])
+        .on_upgrade(move |socket| serve(socket, hub, claims))
+        .into_response())
+}
+
+/// Runs one session. Events are fanned in from the broadcast channel and queued on a
+/// bounded per-connection buffer so a slow client never blocks publishers; updates
+/// that don't fit are dropped and the client is disconnected if it stays behind.
+async fn serve(socket: WebSocket, hub: Arc<WebSocketHub>, claims: Claims) {
+    WS_CONNECTIONS.inc();
+    info!(actor = %claims.actor(), "WebSocket session opened");
+
+    let (mut sink, mut stream) = socket.split();
+    let (outbound, mut queue) = mpsc::channel::<Message>(hub.config.outbound_buffer.max(1));
+    let mut events = hub.events.subscribe();
+    let mut session = Session::new(claims, hub.config.max_subscriptions);
+    let mut consecutive_drops = 0usize;
+
+    let writer = tokio::spawn(async move {
+        while let Some(message) = queue.recv().await {
+            if sink.send(message).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    let close_reason = loop {
+        tokio::select! {
+            incoming = stream.next() => {
+                let text = match incoming {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | None | Some(Err(_)) => break None,
+                    Some(Ok(_)) => continue,
+                };
+                let reply = match serde_json::from_str::<ClientMessage>(&text) {
+                    Ok(message) => session.handle(message),
+                    Err(e) => error("invalid_message", e.to_string()),
+                };
+                // Replies are never dropped; waiting here only slows this client down
+                if outbound.send(encode(&reply)).await.is_err() {
+                    break None;
+                }
+            }
+            received = events.recv() => {
+                let message = match received {
+                    Ok(event) => match session.filter(&event) {
+                        Some(message) => message,
+                        None => continue,
+                    },
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        WS_MESSAGES_DROPPED.with_label_values(&["lagged"]).inc_by(missed);
+                        ServerMessage::Lagged { missed }
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break None,
+                };
+                match outbound.try_send(encode(&message)) {
+                    Ok(()) => consecutive_drops = 0,
+                    Err(mpsc::error::TrySendError::Full(_)) => {
+                        WS_MESSAGES_DROPPED.with_label_values(&["buffer_full"]).inc();
+                        consecutive_drops += 1;
+                        if consecutive_drops >= hub.config.max_consecutive_drops {
+                            break Some((CLOSE_SLOW_CONSUMER, "slow consumer"));
+                        }
+                    }
+                    Err(mpsc::error::TrySendError::Closed(_)) => break None,
+                }
+            }
+        }
+    };
+
+    if let Some((code, reason)) = close_reason {
+        warn!(actor = %session.claims.actor(), code, "Closing WebSocket session: {}", reason);
+        let frame = CloseFrame { code, reason: Cow::Borrowed(reason) };
+        // The queue may be full; give the writer a bounded chance to deliver the close
+        let _ = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            outbound.send(Message::Close(Some(frame))),
+        )
+        .await;
+    }
+    drop(outbound);
+    let _ = writer.await;
+
+    WS_CONNECTIONS.dec();
+    debug!(actor = %session.claims.actor(), "WebSocket session closed");
+}
+
+fn encode(message: &ServerMessage) -> Message {
+    Message::Text(serde_json::to_string(message).expect("server messages serialize"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use uuid::Uuid;
+
+    fn claims(role: &str, permissions: &[&str]) -> Claims {
+        Claims {
+            sub: "user-1".to_string(),
+            exp: usize::MAX,
+            iss: "stateset-api".to_string(),
+            aud: "stateset-api".to_string(),
+            role: role.to_string(),
+            permissions: Some(permissions.iter().map(|p| p.to_string()).collect()),
+            actor_type: Default::default(),
+            tenant_id: None,
+        }
+    }
+
+    fn subscribe(session: &mut Session, topics: &[&str]) -> ServerMessage {
+        session.handle(ClientMessage::Subscribe {
+            topics: topics.iter().map(|t| t.to_string()).collect(),
+        })
+    }
+
+    #[test]
+    fn test_topic_parsing() {
+        assert_eq!("orders".parse::<Topic>(), Ok(Topic::Orders));
+        assert_eq!("inventory:7".parse::<Topic>(), Ok(Topic::Inventory { warehouse_id: Some(7) }));
+        assert!("inventory:main".parse::<Topic>().is_err());
+        assert!("payments".parse::<Topic>().is_err());
+        assert_eq!(Topic::Inventory { warehouse_id: Some(7) }.to_string(), "inventory:7");
+    }
+
+    #[test]
+    fn test_subscriptions_require_permission() {
+        let mut session = Session::new(claims("user", &["orders:read"]), 8);
+        assert!(matches!(subscribe(&mut session, &["orders"]), ServerMessage::Subscribed { .. }));
+        assert!(matches!(
+            subscribe(&mut session, &["shipments"]),
+            ServerMessage::Error { ref code, .. } if code == "forbidden"
+        ));
+
+        let mut admin = Session::new(claims("admin", &[]), 8);
+        assert!(matches!(subscribe(&mut admin, &["shipments", "inventory:2"]), ServerMessage::Subscribed { .. }));
+    }
+
+    #[test]
+    fn test_subscription_limit() {
+        let mut session = Session::new(claims("admin", &[]), 2);
+        assert!(matches!(subscribe(&mut session, &["orders", "shipments"]), ServerMessage::Subscribed { .. }));
+        assert!(matches!(
+            subscribe(&mut session, &["inventory"]),
+            ServerMessage::Error { ref code, .. } if code == "too_many_subscriptions"
+        ));
+        // Re-subscribing to an existing topic does not count twice
+        assert!(matches!(subscribe(&mut session, &["orders"]), ServerMessage::Subscribed { .. }));
+    }
+
+    #[test]
+    fn test_events_are_filtered_by_topic_and_warehouse() {
+        let mut session = Session::new(claims("admin", &[]), 8);
+        subscribe(&mut session, &["orders", "inventory:3"]);
+
+        let order_id = Uuid::new_v4();
+        match session.filter(&Event::OrderShipped(order_id)) {
+            Some(ServerMessage::Event { topic, event, .. }) => {
+                assert_eq!(topic, "orders");
+                assert_eq!(event, "OrderShipped");
+            }
+            other => panic!("expected an order event, got {:?}", other),
+        }
+        assert!(session.filter(&Event::ShipmentCreated(Uuid::new_v4())).is_none());
+
+        let level = |warehouse_id| Event::InventoryLevelChanged {
+            warehouse_id,
+            sku: "SKU-1".to_string(),
+            available: 4,
+        };
+        assert!(session.filter(&level(3)).is_some());
+        assert!(session.filter(&level(4)).is_none());
+        // Warehouse-less adjustments only reach unscoped inventory subscribers
+        let adjusted = Event::InventoryAdjusted { product_id: Uuid::new_v4(), adjustment: -1 };
+        assert!(session.filter(&adjusted).is_none());
+        subscribe(&mut session, &["inventory"]);
+        assert!(session.filter(&adjusted).is_some());
+        assert!(session.filter(&level(4)).is_some());
+    }
+
+    #[test]
+    fn test_handshake_token_comes_from_headers_only() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(handshake_token(&headers), None);
+        headers.insert("Sec-WebSocket-Protocol", "bearer, eyJhbGciOiJIUzI1NiJ9.e30.sig".parse().unwrap());
+        assert_eq!(handshake_token(&headers).as_deref(), Some("eyJhbGciOiJIUzI1NiJ9.e30.sig"));
+        headers.insert("Authorization", "Bearer from-header".parse().unwrap());
+        assert_eq!(handshake_token(&headers).as_deref(), Some("from-header"));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("Sec-WebSocket-Protocol", "bearer".parse().unwrap());
+        assert_eq!(handshake_token(&headers), None);
+    }
+}
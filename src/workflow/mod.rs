@@ -0,0 +1,582 @@
+// workflow/mod.rs
+
+//! Reusable approval chains. A domain registers an `ApprovalHandler` for its subject
+//! (requisitions, returns, ...); the engine routes a submitted record through the chain
+//! configured for that subject, tracks each step's approver, deadline, delegation and
+//! escalation, and hands the final outcome back to the handler. Every approver works from
+//! one inbox, `GET /api/v1/approvals/pending`.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration as ChronoDuration, Utc};
+use lazy_static::lazy_static;
+use prometheus::IntCounter;
+use rust_decimal::Decimal;
+use sea_orm::*;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
+use tracing::{error, info, instrument};
+use uuid::Uuid;
+
+use crate::{
+    auth::Claims,
+    db::DbPool,
+    errors::ServiceError,
+    events::{Event, EventSender},
+    models::approval_step::{self, ApprovalStatus, ApprovalSubject, ApproverKind, Entity as ApprovalStep},
+};
+
+lazy_static! {
+    static ref APPROVAL_ESCALATIONS: IntCounter =
+        IntCounter::new("approval_escalations_total", "Approval steps escalated past their deadline")
+            .expect("metric can be created");
+}
+
+/// Approval settings, loaded from the `workflow` section of the config.
+#[derive(Clone, Debug, Deserialize)]
+pub struct WorkflowConfig {
+    /// Approval chain per subject, e.g. `[[workflow.chains.requisition]]`. Submitting a record
+    /// whose subject has no chain, or whose amount no step applies to, fails; give the first
+    /// step `min_amount = 0` to route every record.
+    #[serde(default)]
+    pub chains: HashMap<ApprovalSubject, Vec<ChainStep>>,
+    /// Hours a pending step may stay undecided before it escalates.
+    #[serde(default = "default_escalation_hours")]
+    pub escalation_hours: i64,
+    /// Who overdue steps are reassigned to. Without one, overdue steps are only flagged.
+    #[serde(default)]
+    pub escalate_to: Option<Approver>,
+    #[serde(default = "default_escalation_interval_secs")]
+    pub escalation_interval_secs: u64,
+}
+
+fn default_escalation_hours() -> i64 {
+    48
+}
+
+fn default_escalation_interval_secs() -> u64 {
+    300
+}
+
+impl Default for WorkflowConfig {
+    fn default() -> Self {
+        Self {
+            chains: HashMap::new(),
+            escalation_hours: default_escalation_hours(),
+            escalate_to: None,
+            escalation_interval_secs: default_escalation_interval_secs(),
+        }
+    }
+}
+
+/// One configured step: applies when the subject's amount is at least `min_amount`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChainStep {
+    #[serde(default, with = "crate::money::amount")]
+    pub min_amount: Decimal,
+    pub approver: Approver,
+}
+
+/// Who decides a step: `{ role = "finance" }` or `{ actor = "user:42" }`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Approver {
+    Role(String),
+    Actor(String),
+}
+
+impl Approver {
+    pub fn of(step: &approval_step::Model) -> Self {
+        match step.approver_kind {
+            ApproverKind::Role => Approver::Role(step.approver.clone()),
+            ApproverKind::Actor => Approver::Actor(step.approver.clone()),
+        }
+    }
+
+    pub fn matches(&self, claims: &Claims) -> bool {
+        match self {
+            Approver::Role(role) => claims.role == *role,
+            Approver::Actor(actor) => claims.actor() == *actor,
+        }
+    }
+
+    fn columns(&self) -> (ApproverKind, String) {
+        match self {
+            Approver::Role(role) => (ApproverKind::Role, role.clone()),
+            Approver::Actor(actor) => (ApproverKind::Actor, actor.clone()),
+        }
+    }
+}
+
+impl fmt::Display for Approver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Approver::Role(role) => write!(f, "role:{}", role),
+            Approver::Actor(actor) => f.write_str(actor),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    Approve,
+    Reject,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecisionInput {
+    pub decision: Decision,
+    pub comment: Option<String>,
+}
+
+/// How an approval request ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resolution {
+    Approved,
+    Rejected,
+}
+
+/// The domain side of an approval chain.
+#[async_trait]
+pub trait ApprovalHandler: Send + Sync {
+    fn subject(&self) -> ApprovalSubject;
+
+    /// Checks `actor` may submit the record, marks it as awaiting approval, and returns the
+    /// amount the chain is routed on.
+    async fn submit(&self, txn: &DatabaseTransaction, subject_id: &str, actor: &str) -> Result<Decimal, ServiceError>;
+
+    /// Applies the outcome in the same transaction as the deciding step. The returned
+    /// event is sent once that transaction commits.
+    async fn resolve(
+        &self,
+        txn: &DatabaseTransaction,
+        subject_id: &str,
+        resolution: Resolution,
+        actor: &str,
+    ) -> Result<Option<Event>, ServiceError>;
+}
+
+/// Approvers for a subject of `amount`, in chain order. An approver who would decide two
+/// consecutive steps decides once.
+pub fn approvers_for(chain: &[ChainStep], amount: Decimal) -> Vec<Approver> {
+    let mut approvers: Vec<Approver> = Vec::new();
+    for step in chain.iter().filter(|s| amount >= s.min_amount) {
+        if approvers.last() != Some(&step.approver) {
+            approvers.push(step.approver.clone());
+        }
+    }
+    approvers
+}
+
+/// Whether a chain has steps and all of them are approved.
+pub fn chain_approved(steps: &[approval_step::Model]) -> bool {
+    !steps.is_empty() && steps.iter().all(|s| s.status == ApprovalStatus::Approved)
+}
+
+/// Whether a pending step is past its deadline and not yet escalated.
+pub fn needs_escalation(step: &approval_step::Model, now: DateTime<Utc>) -> bool {
+    step.status == ApprovalStatus::Pending && step.escalated_at.is_none() && step.due_at.is_some_and(|due| due <= now)
+}
+
+fn db_error(e: DbErr) -> ServiceError {
+    let msg = format!("Approval query failed: {}", e);
+    error!("{}", msg);
+    ServiceError::DatabaseError(msg)
+}
+
+pub struct ApprovalEngine {
+    db_pool: Arc<DbPool>,
+    events: EventSender,
+    config: WorkflowConfig,
+    handlers: HashMap<ApprovalSubject, Arc<dyn ApprovalHandler>>,
+}
+
+impl ApprovalEngine {
+    pub fn new(db_pool: Arc<DbPool>, events: EventSender, config: WorkflowConfig) -> Self {
+        Self { db_pool, events, config, handlers: HashMap::new() }
+    }
+
+    /// Registers the handler for its subject, replacing any earlier one.
+    pub fn register(&mut self, handler: Arc<dyn ApprovalHandler>) {
+        self.handlers.insert(handler.subject(), handler);
+    }
+
+    fn handler(&self, subject: ApprovalSubject) -> Result<&Arc<dyn ApprovalHandler>, ServiceError> {
+        self.handlers
+            .get(&subject)
+            .ok_or_else(|| ServiceError::ValidationError(format!("{:?} records are not approved through workflows", subject)))
+    }
+
+    fn due_at(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now + ChronoDuration::hours(self.config.escalation_hours)
+    }
+
+    async fn find_step<C: ConnectionTrait>(db: &C, id: Uuid) -> Result<approval_step::Model, ServiceError> {
+        ApprovalStep::find_by_id(id)
+            .lock_exclusive()
+            .one(db)
+            .await
+            .map_err(db_error)?
+            .ok_or_else(|| ServiceError::NotFound(format!("Approval step not found: {}", id)))
+    }
+
+    fn send(&self, event: Option<Event>) {
+        if let Some(event) = event {
+            let _ = self.events.send(event);
+        }
+    }
+
+    /// Submits a record for approval and creates its chain. Returns the steps, of which
+    /// there is at least one.
+    #[instrument(skip(self))]
+    pub async fn request(
+        &self,
+        subject: ApprovalSubject,
+        subject_id: &str,
+        actor: &str,
+    ) -> Result<Vec<approval_step::Model>, ServiceError> {
+        let handler = self.handler(subject)?;
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let amount = handler.submit(&txn, subject_id, actor).await?;
+        if Self::awaiting(&txn, subject, subject_id).await? {
+            return Err(ServiceError::ValidationError(format!("{:?} {} is already awaiting approval", subject, subject_id)));
+        }
+        let chain = self.config.chains.get(&subject).map(Vec::as_slice).unwrap_or_default();
+        let approvers = approvers_for(chain, amount);
+        if approvers.is_empty() {
+            // Nobody was configured to decide, which is not the same as approval
+            return Err(ServiceError::ValidationError(format!(
+                "No approval chain applies to {:?} {} of amount {}",
+                subject, subject_id, amount
+            )));
+        }
+        let now = Utc::now();
+        let mut steps = Vec::with_capacity(approvers.len());
+        for (index, approver) in approvers.iter().enumerate() {
+            let (approver_kind, approver) = approver.columns();
+            let step = approval_step::ActiveModel {
+                id: Set(Uuid::new_v4()),
+                subject: Set(subject),
+                subject_id: Set(subject_id.to_string()),
+                step: Set(index as i32 + 1),
+                approver_kind: Set(approver_kind),
+                approver: Set(approver),
+                delegated_from: Set(None),
+                status: Set(if index == 0 { ApprovalStatus::Pending } else { ApprovalStatus::Waiting }),
+                due_at: Set((index == 0).then(|| self.due_at(now))),
+                escalated_at: Set(None),
+                decided_by: Set(None),
+                comment: Set(None),
+                decided_at: Set(None),
+                created_at: Set(now),
+            }
+            .insert(&txn)
+            .await
+            .map_err(db_error)?;
+            steps.push(step);
+        }
+        txn.commit().await.map_err(db_error)?;
+        Ok(steps)
+    }
+
+    /// Whether a record has a chain with undecided steps.
+    pub async fn awaiting<C: ConnectionTrait>(db: &C, subject: ApprovalSubject, subject_id: &str) -> Result<bool, ServiceError> {
+        let open = ApprovalStep::find()
+            .filter(approval_step::Column::Subject.eq(subject))
+            .filter(approval_step::Column::SubjectId.eq(subject_id))
+            .filter(approval_step::Column::Status.is_in([ApprovalStatus::Waiting, ApprovalStatus::Pending]))
+            .count(db)
+            .await
+            .map_err(db_error)?;
+        Ok(open > 0)
+    }
+
+    /// Skips the undecided steps of a withdrawn request.
+    pub async fn withdraw<C: ConnectionTrait>(db: &C, subject: ApprovalSubject, subject_id: &str) -> Result<(), ServiceError> {
+        ApprovalStep::update_many()
+            .col_expr(approval_step::Column::Status, Expr::value(ApprovalStatus::Skipped))
+            .filter(approval_step::Column::Subject.eq(subject))
+            .filter(approval_step::Column::SubjectId.eq(subject_id))
+            .filter(approval_step::Column::Status.is_in([ApprovalStatus::Waiting, ApprovalStatus::Pending]))
+            .exec(db)
+            .await
+            .map_err(db_error)?;
+        Ok(())
+    }
+
+    /// All steps of one record, in order.
+    pub async fn steps(&self, subject: ApprovalSubject, subject_id: &str) -> Result<Vec<approval_step::Model>, ServiceError> {
+        ApprovalStep::find()
+            .filter(approval_step::Column::Subject.eq(subject))
+            .filter(approval_step::Column::SubjectId.eq(subject_id))
+            .order_by_asc(approval_step::Column::Step)
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)
+    }
+
+    /// Pending steps the caller may decide, by name or by role, oldest deadline first.
+    pub async fn pending_for(&self, claims: &Claims) -> Result<Vec<approval_step::Model>, ServiceError> {
+        ApprovalStep::find()
+            .filter(approval_step::Column::Status.eq(ApprovalStatus::Pending))
+            .filter(
+                Condition::any()
+                    .add(
+                        Condition::all()
+                            .add(approval_step::Column::ApproverKind.eq(ApproverKind::Actor))
+                            .add(approval_step::Column::Approver.eq(claims.actor())),
+                    )
+                    .add(
+                        Condition::all()
+                            .add(approval_step::Column::ApproverKind.eq(ApproverKind::Role))
+                            .add(approval_step::Column::Approver.eq(claims.role.clone())),
+                    ),
+            )
+            .order_by_asc(approval_step::Column::DueAt)
+            .all(self.db_pool.as_ref())
+            .await
+            .map_err(db_error)
+    }
+
+    /// Records the caller's decision on a pending step. Approving the last step, or
+    /// rejecting any step, resolves the request through the subject's handler.
+    #[instrument(skip(self, claims, input))]
+    pub async fn decide(&self, step_id: Uuid, claims: &Claims, input: DecisionInput) -> Result<approval_step::Model, ServiceError> {
+        let txn = self.db_pool.begin().await.map_err(db_error)?;
+        let step = Self::find_step(&txn, step_id).await?;
+        if step.status != ApprovalStatus::Pending {
+            return Err(ServiceError::ValidationError(format!("Approval step is {:?}", step.status)));
+        }
+        if !Approver::of(&step).matches(claims) {
+            return Err(ServiceError::ValidationError(format!(
+                "Approval step is assigned to {}",
+                Approver::of(&step)
+            )));
+        }
+        let handler = self.handler(step.subject)?;
+        let actor = claims.actor();
+        let now = Utc::now();
+        let (subject, subject_id) = (step.subject, step.subject_id.clone());
+        let mut active: approval_step::ActiveModel = step.into();
+        active.status = Set(match input.decision {
+            Decision::Approve => ApprovalStatus::Approved,
+            Decision::Reject => ApprovalStatus::Rejected,
+        });
+        active.decided_by = Set(Some(actor.clone()));
+        active.comment = Set(input.comment);
+        active.decided_at = Set(Some(now));
+        let step = active.update(&txn).await.map_err(db_error)?;
+
+        let resolution = match input.decision {
+            Decision::Reject => {
+                Self::withdraw(&txn, subject, &subject_id).await?;
+                Some(Resolution::Rejected)
+            }
+            Decision::Approve => {
+                let next = ApprovalStep::find()
+                    .filter(approval_step::Column::Subject.eq(subject))
+                    .filter(approval_step::Column::SubjectId.eq(subject_id.as_str()))
+                    .filter(approval_step::Column::Status.eq(ApprovalStatus::Waiting))
+                    .order_by_asc(approval_step::Column::Step)
+                    .one(&txn)
+                    .await
+                    .map_err(db_error)?;
+                match next {
+                    Some(next) => {
+                        let mut next: approval_step::ActiveModel = next.into();
+                        next.status = Set(ApprovalStatus::Pending);
+                        next.due_at = Set(Some(self.due_at(now)));
+                        next.update(&txn).await.map_err(db_error)?;
+                        None
+                    }
+                    // Only a chain whose every step is approved approves the record
+                    None if Self::all_approved(&txn, subject, &subject_id).await? => Some(Resolution::Approved),
+                    None => None,
+                }
+            }
+        };
+        let event = match resolution {
+            Some(resolution) => handler.resolve(&txn, &subject_id, resolution, &actor).await?,
+            None => None,
+        };
+        txn.commit().await.map_err(db_error)?;
+        self.send(event);
+        Ok(step)
+    }
+
+    async fn all_approved<C: ConnectionTrait>(db: &C, subject: ApprovalSubject, subject_id: &str) -> Result<bool, ServiceError> {
+        let steps = ApprovalStep::find()
+            .filter(approval_step::Column::Subject.eq(subject))
+            .filter(approval_step::Column::SubjectId.eq(subject_id))
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        Ok(chain_approved(&steps))
+    }
+
+    /// Hands an undecided step the caller may decide to another actor.
+    pub async fn delegate(&self, step_id: Uuid, claims: &Claims, delegate: &str) -> Result<approval_step::Model, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let step = Self::find_step(db, step_id).await?;
+        if !matches!(step.status, ApprovalStatus::Waiting | ApprovalStatus::Pending) {
+            return Err(ServiceError::ValidationError(format!("Approval step is {:?}", step.status)));
+        }
+        let from = Approver::of(&step);
+        if !from.matches(claims) {
+            return Err(ServiceError::ValidationError(format!("Approval step is assigned to {}", from)));
+        }
+        if delegate.trim().is_empty() || delegate == claims.actor() {
+            return Err(ServiceError::ValidationError("Delegate to a different approver".to_string()));
+        }
+        let mut active: approval_step::ActiveModel = step.into();
+        active.approver_kind = Set(ApproverKind::Actor);
+        active.approver = Set(delegate.to_string());
+        active.delegated_from = Set(Some(from.to_string()));
+        let step = active.update(db).await.map_err(db_error)?;
+        info!(step_id = %step_id, from = %from, to = %delegate, "Approval step delegated");
+        Ok(step)
+    }
+
+    /// Escalates pending steps past their deadline; returns how many were escalated.
+    pub async fn escalate_overdue(&self) -> Result<usize, ServiceError> {
+        let db = self.db_pool.as_ref();
+        let now = Utc::now();
+        let overdue = ApprovalStep::find()
+            .filter(approval_step::Column::Status.eq(ApprovalStatus::Pending))
+            .filter(approval_step::Column::EscalatedAt.is_null())
+            .filter(approval_step::Column::DueAt.lte(now))
+            .all(db)
+            .await
+            .map_err(db_error)?;
+        let mut escalated = 0;
+        for step in overdue.into_iter().filter(|s| needs_escalation(s, now)) {
+            let from = Approver::of(&step);
+            let mut active: approval_step::ActiveModel = step.into();
+            active.escalated_at = Set(Some(now));
+            if let Some(to) = self.config.escalate_to.as_ref().filter(|to| **to != from) {
+                let (approver_kind, approver) = to.columns();
+                active.approver_kind = Set(approver_kind);
+                active.approver = Set(approver);
+                active.delegated_from = Set(Some(from.to_string()));
+                active.due_at = Set(Some(self.due_at(now)));
+            }
+            let step = active.update(db).await.map_err(db_error)?;
+            info!(step_id = %step.id, from = %from, to = %Approver::of(&step), "Approval step escalated");
+            APPROVAL_ESCALATIONS.inc();
+            escalated += 1;
+        }
+        Ok(escalated)
+    }
+}
+
+/// Escalates overdue approval steps every `interval`.
+pub fn spawn_escalator(engine: Arc<ApprovalEngine>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval.max(Duration::from_secs(1)));
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = engine.escalate_overdue().await {
+                error!("Approval escalation failed: {}", e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::ActorType;
+    use rust_decimal_macros::dec;
+
+    fn chain() -> Vec<ChainStep> {
+        vec![
+            ChainStep { min_amount: dec!(0), approver: Approver::Role("manager".to_string()) },
+            ChainStep { min_amount: dec!(5000), approver: Approver::Role("finance".to_string()) },
+            ChainStep { min_amount: dec!(5000), approver: Approver::Role("finance".to_string()) },
+            ChainStep { min_amount: dec!(50000), approver: Approver::Actor("user:cfo".to_string()) },
+        ]
+    }
+
+    fn step(status: ApprovalStatus, due_at: Option<DateTime<Utc>>) -> approval_step::Model {
+        approval_step::Model {
+            id: Uuid::new_v4(),
+            subject: ApprovalSubject::Requisition,
+            subject_id: Uuid::new_v4().to_string(),
+            step: 1,
+            approver_kind: ApproverKind::Role,
+            approver: "manager".to_string(),
+            delegated_from: None,
+            status,
+            due_at,
+            escalated_at: None,
+            decided_by: None,
+            comment: None,
+            decided_at: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    fn claims(sub: &str, role: &str) -> Claims {
+        Claims {
+            sub: sub.to_string(),
+            exp: 0,
+            iss: String::new(),
+            aud: String::new(),
+            role: role.to_string(),
+            permissions: None,
+            actor_type: ActorType::Human,
+            tenant_id: None,
+        }
+    }
+
+    #[test]
+    fn test_routing_by_amount() {
+        assert_eq!(approvers_for(&chain(), dec!(120)), [Approver::Role("manager".to_string())]);
+        assert_eq!(approvers_for(&chain(), dec!(5000)).len(), 2);
+        assert_eq!(approvers_for(&chain(), dec!(75000)).last(), Some(&Approver::Actor("user:cfo".to_string())));
+        assert!(approvers_for(&[], dec!(75000)).is_empty());
+    }
+
+    #[test]
+    fn test_empty_or_undecided_chain_is_not_approved() {
+        assert!(!chain_approved(&[]));
+        assert!(!chain_approved(&[step(ApprovalStatus::Approved, None), step(ApprovalStatus::Pending, None)]));
+        assert!(!chain_approved(&[step(ApprovalStatus::Approved, None), step(ApprovalStatus::Skipped, None)]));
+        assert!(chain_approved(&[step(ApprovalStatus::Approved, None), step(ApprovalStatus::Approved, None)]));
+    }
+
+    #[test]
+    fn test_approver_matching() {
+        assert!(Approver::Role("finance".to_string()).matches(&claims("7", "finance")));
+        assert!(!Approver::Role("finance".to_string()).matches(&claims("7", "manager")));
+        assert!(Approver::Actor("user:7".to_string()).matches(&claims("7", "manager")));
+        assert_eq!(Approver::Role("finance".to_string()).to_string(), "role:finance");
+    }
+
+    #[test]
+    fn test_escalation_only_for_overdue_pending_steps() {
+        let now = Utc::now();
+        let past = Some(now - ChronoDuration::hours(1));
+        assert!(needs_escalation(&step(ApprovalStatus::Pending, past), now));
+        assert!(!needs_escalation(&step(ApprovalStatus::Pending, Some(now + ChronoDuration::hours(1))), now));
+        assert!(!needs_escalation(&step(ApprovalStatus::Waiting, past), now));
+
+        let mut escalated = step(ApprovalStatus::Pending, past);
+        escalated.escalated_at = Some(now);
+        assert!(!needs_escalation(&escalated, now));
+    }
+
+    #[test]
+    fn test_config_chains() {
+        let config: WorkflowConfig = serde_json::from_value(serde_json::json!({
+            "chains": { "requisition": [{ "min_amount": "1000", "approver": { "role": "finance" } }] },
+            "escalate_to": { "actor": "user:1" }
+        }))
+        .unwrap();
+        let chain = &config.chains[&ApprovalSubject::Requisition];
+        assert_eq!(chain[0].approver, Approver::Role("finance".to_string()));
+        assert_eq!(config.escalation_hours, 48);
+    }
+}
@@ -0,0 +1,129 @@
+//! Runs the Postgres constructs rewritten by `db::dialect` against every available
+//! database and checks they agree. SQLite always runs; set `TEST_DATABASE_URL` to a
+//! Postgres URL to compare against Postgres as well:
+//!
+//! ```sh
+//! TEST_DATABASE_URL=postgres://localhost/stateset_test cargo test --features sqlite --test dialect_parity
+//! ```
+#![cfg(feature = "sqlite")]
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use sea_orm::{ConnectOptions, ConnectionTrait, Database, DatabaseConnection, FromQueryResult, TransactionTrait};
+use stateset_api::db::dialect::{self, Dialect};
+
+/// One connection each, so temporary tables stay visible across statements.
+async fn databases() -> Vec<DatabaseConnection> {
+    let mut urls = vec!["sqlite::memory:".to_string()];
+    if let Ok(url) = std::env::var("TEST_DATABASE_URL") {
+        if url.starts_with("postgres") {
+            urls.push(url);
+        }
+    }
+    let mut databases = Vec::new();
+    for url in urls {
+        let mut options = ConnectOptions::new(url);
+        options.max_connections(1).sqlx_logging(false);
+        databases.push(Database::connect(options).await.expect("test database is reachable"));
+    }
+    databases
+}
+
+async fn run(db: &DatabaseConnection, sql: &str) {
+    db.execute(dialect::raw(db, sql)).await.unwrap_or_else(|e| panic!("{:?}: {}", Dialect::of(db), e));
+}
+
+#[derive(Debug, PartialEq, FromQueryResult)]
+struct Usage {
+    tenant_id: String,
+    quantity: i64,
+}
+
+#[tokio::test]
+async fn test_upsert_with_greatest_and_repeated_placeholders() {
+    let mut results = Vec::new();
+    for db in databases().await {
+        run(&db, "CREATE TEMPORARY TABLE parity_usage (tenant_id TEXT NOT NULL, period TEXT NOT NULL, quantity BIGINT NOT NULL, peak BIGINT NOT NULL, PRIMARY KEY (tenant_id, period))").await;
+        for (tenant, quantity) in [("acme", 5i64), ("acme", 3), ("globex", 7), ("acme", 9)] {
+            db.execute(dialect::statement(
+                &db,
+                "INSERT INTO parity_usage (tenant_id, period, quantity, peak) VALUES ($1, $2, $3, $3) \
+                 ON CONFLICT (tenant_id, period) DO UPDATE SET quantity = parity_usage.quantity + EXCLUDED.quantity, \
+                 peak = GREATEST(parity_usage.peak, EXCLUDED.peak)",
+                [tenant.into(), "2024-05".into(), quantity.into()],
+            ))
+            .await
+            .unwrap();
+        }
+        let rows = Usage::find_by_statement(dialect::statement(
+            &db,
+            "SELECT tenant_id, (quantity * 1000 + peak)::BIGINT AS quantity FROM parity_usage \
+             WHERE ($1::TEXT IS NULL OR tenant_id = $1) ORDER BY tenant_id",
+            [Option::<String>::None.into()],
+        ))
+        .all(&db)
+        .await
+        .unwrap();
+        results.push(rows);
+    }
+    assert_eq!(
+        results[0],
+        vec![
+            Usage { tenant_id: "acme".to_string(), quantity: 17_009 },
+            Usage { tenant_id: "globex".to_string(), quantity: 7_007 },
+        ]
+    );
+    assert!(results.windows(2).all(|pair| pair[0] == pair[1]));
+}
+
+#[derive(Debug, PartialEq, FromQueryResult)]
+struct Monthly {
+    month: NaiveDate,
+    orders: i64,
+}
+
+#[tokio::test]
+async fn test_date_trunc_groups_by_month() {
+    let mut results = Vec::new();
+    for db in databases().await {
+        run(&db, "CREATE TEMPORARY TABLE parity_orders (id INTEGER PRIMARY KEY, created_date TIMESTAMPTZ NOT NULL)").await;
+        let dates = [(2024, 5, 17), (2024, 5, 31), (2024, 6, 1)];
+        for (id, (y, m, d)) in dates.into_iter().enumerate() {
+            db.execute(dialect::statement(
+                &db,
+                "INSERT INTO parity_orders (id, created_date) VALUES ($1, $2)",
+                [(id as i32).into(), Utc.with_ymd_and_hms(y, m, d, 10, 30, 0).unwrap().into()],
+            ))
+            .await
+            .unwrap();
+        }
+        let rows = Monthly::find_by_statement(dialect::raw(
+            &db,
+            "SELECT date_trunc('month', created_date)::date AS month, COUNT(*) AS orders \
+             FROM parity_orders GROUP BY 1 ORDER BY 1",
+        ))
+        .all(&db)
+        .await
+        .unwrap();
+        results.push(rows);
+    }
+    assert_eq!(
+        results[0],
+        vec![
+            Monthly { month: NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(), orders: 2 },
+            Monthly { month: NaiveDate::from_ymd_opt(2024, 6, 1).unwrap(), orders: 1 },
+        ]
+    );
+    assert!(results.windows(2).all(|pair| pair[0] == pair[1]));
+}
+
+#[tokio::test]
+async fn test_locks_and_database_size() {
+    for db in databases().await {
+        let txn = db.begin().await.unwrap();
+        dialect::advisory_xact_lock(&txn, "parity").await.unwrap();
+        txn.commit().await.unwrap();
+        assert!(dialect::database_size(&db).await.unwrap() > 0);
+        let postgres_only = dialect::require_postgres(&db, "Retention policies");
+        assert_eq!(postgres_only.is_ok(), Dialect::of(&db) == Dialect::Postgres);
+    }
+}
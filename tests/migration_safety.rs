@@ -0,0 +1,20 @@
+//! Fails the build when a migration in `migrations/` blocks traffic or breaks the live
+//! release without an explicit `-- safety: allow <rule>`. `stateset-cli migration-plan`
+//! prints the full analysis.
+
+use stateset_api::db::migration_safety;
+
+#[test]
+fn test_migrations_follow_expand_contract() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("migrations");
+    let plans = migration_safety::analyze_dir(&dir).expect("migrations are readable");
+    let violations: Vec<String> = plans
+        .iter()
+        .flat_map(|plan| {
+            plan.violations().map(move |finding| {
+                format!("{} #{}: {} ({})", plan.name, finding.statement, finding.message, finding.rule)
+            })
+        })
+        .collect();
+    assert!(violations.is_empty(), "unsafe migration statements:\n{}", violations.join("\n"));
+}